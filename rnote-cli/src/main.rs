@@ -0,0 +1,281 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use clap::{ArgEnum, Parser};
+use rnote_engine::render::Renderer;
+use rnote_engine::scripting::ScriptOptions;
+use rnote_engine::sheet::bitmapexport::{BitmapExportFormat, BitmapExportPrefs, BitmapExportScope};
+use rnote_engine::sheet::replayexport::{FfmpegEncoder, ReplayExportPrefs};
+use rnote_engine::sheet::Sheet;
+use rnote_engine::strokesstate::replay::Replay;
+
+/// Converts .rnote files to other formats, without a GTK session
+#[derive(Debug, Parser)]
+#[clap(name = "rnote-cli", version)]
+struct Cli {
+    /// The .rnote files to convert
+    #[clap(required = true)]
+    inputs: Vec<PathBuf>,
+    /// The format the files are converted to
+    #[clap(short, long, arg_enum)]
+    format: ExportFormat,
+    /// The directory the converted files are written to. Defaults to the directory of each input file
+    #[clap(short, long)]
+    output_dir: Option<PathBuf>,
+    /// The pages to export, counted from 1, e.g. `3`, `2-5` or `2-`. Only for PDF, PNG and JPEG.
+    /// Without it PDFs contain the pages with content and PNG / JPEG all pages
+    #[clap(short, long, parse(try_from_str = parse_page_range))]
+    pages: Option<Range<u32>>,
+    /// The resolution of PNG and JPEG images, and of the replay videos
+    #[clap(long, default_value_t = BitmapExportPrefs::DPI_DEFAULT)]
+    dpi: f64,
+    /// The quality of JPEG images, from 1 to 100
+    #[clap(long, default_value_t = BitmapExportPrefs::JPEG_QUALITY_DEFAULT)]
+    jpeg_quality: u8,
+    /// Leaves out the background of PNG and JPEG images and of the replays. JPEG images and videos are filled white instead
+    #[clap(long)]
+    transparent: bool,
+    /// A Rhai script which is run on each file before it is converted, e.g. to number the pages
+    #[clap(long)]
+    script: Option<PathBuf>,
+    /// The factor the replay of the session recording is faster than the recording. Only for the replay formats
+    #[clap(long, default_value_t = Replay::SPEED_DEFAULT)]
+    replay_speed: f64,
+    /// Also extracts the files attached to each file into the output directory
+    #[clap(long)]
+    extract_attachments: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+enum ExportFormat {
+    Pdf,
+    Svg,
+    Png,
+    Jpeg,
+    Xopp,
+    /// The session recording as animated SVG
+    ReplaySvg,
+    /// The session recording as video, encoded with ffmpeg
+    ReplayMp4,
+    /// The session recording as video, encoded with ffmpeg
+    ReplayWebm,
+}
+
+impl ExportFormat {
+    fn file_ext(&self) -> &'static str {
+        match self {
+            Self::Pdf => "pdf",
+            Self::Svg => "svg",
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Xopp => "xopp",
+            Self::ReplaySvg => "svg",
+            Self::ReplayMp4 => "mp4",
+            Self::ReplayWebm => "webm",
+        }
+    }
+}
+
+/// Parses a page range counted from 1 into a range counted from 0
+fn parse_page_range(s: &str) -> Result<Range<u32>, String> {
+    let parse_page = |page: &str| -> Result<u32, String> {
+        match page.trim().parse::<u32>() {
+            Ok(page) if page > 0 => Ok(page),
+            _ => Err(format!(
+                "`{}` is not a page number, pages are counted from 1",
+                page
+            )),
+        }
+    };
+
+    match s.split_once('-') {
+        Some((first, "")) => Ok((parse_page(first)? - 1)..u32::MAX),
+        Some((first, last)) => {
+            let first = parse_page(first)?;
+            let last = parse_page(last)?;
+            if last < first {
+                return Err(format!(
+                    "the last page {} is before the first page {}",
+                    last, first
+                ));
+            }
+
+            Ok((first - 1)..last)
+        }
+        None => {
+            let page = parse_page(s)?;
+            Ok((page - 1)..page)
+        }
+    }
+}
+
+fn main() {
+    pretty_env_logger::init();
+    let cli = Cli::parse();
+
+    let script = match cli.script.as_ref().map(std::fs::read_to_string).transpose() {
+        Ok(script) => script,
+        Err(e) => {
+            eprintln!("reading the script failed, {}", e);
+            std::process::exit(1);
+        }
+    };
+    // The renderer is only needed for the images embedded in xopp files
+    let renderer = Arc::new(RwLock::new(Renderer::default()));
+    let mut n_failed = 0;
+
+    for input in cli.inputs.iter() {
+        if let Err(e) = convert_file(&cli, input, script.as_deref(), Arc::clone(&renderer)) {
+            eprintln!("converting `{}` failed, {:?}", input.display(), e);
+            n_failed += 1;
+        }
+    }
+
+    if n_failed > 0 {
+        eprintln!(
+            "{} of {} files failed to convert",
+            n_failed,
+            cli.inputs.len()
+        );
+        std::process::exit(1);
+    }
+}
+
+fn convert_file(
+    cli: &Cli,
+    input: &Path,
+    script: Option<&str>,
+    renderer: Arc<RwLock<Renderer>>,
+) -> Result<(), anyhow::Error> {
+    let bytes = std::fs::read(input).context("reading the input file failed")?;
+    let mut sheet = Sheet::default();
+    sheet
+        .open_sheet_from_rnote_bytes(glib::Bytes::from_owned(bytes))
+        .context("opening the sheet failed")?;
+
+    if let Some(script) = script {
+        let output = sheet.run_script(script, ScriptOptions::default())?;
+        for line in output.log {
+            println!("{}: {}", input.display(), line);
+        }
+
+        // The sheet grows to fit the inserted strokes
+        if sheet.page_layout.is_some() {
+            sheet.resize_sheet_mode_pages();
+        } else {
+            sheet.resize_sheet_mode_endless_vertical();
+        }
+    }
+
+    let stem = input
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| String::from("sheet"));
+    let output_dir = match &cli.output_dir {
+        Some(output_dir) => output_dir.clone(),
+        None => input.parent().map(Path::to_path_buf).unwrap_or_default(),
+    };
+    let output_name = format!("{}.{}", stem, cli.format.file_ext());
+    let replay_prefs = ReplayExportPrefs {
+        speed: cli.replay_speed.clamp(Replay::SPEED_MIN, Replay::SPEED_MAX),
+        dpi: cli
+            .dpi
+            .clamp(ReplayExportPrefs::DPI_MIN, ReplayExportPrefs::DPI_MAX),
+        with_background: !cli.transparent,
+        ..ReplayExportPrefs::default()
+    };
+
+    match cli.format {
+        ExportFormat::Pdf => {
+            let pdf_data = futures::executor::block_on(
+                sheet.export_sheet_as_pdf_bytes(stem.clone(), cli.pages.clone()),
+            )
+            .context("exporting the sheet as pdf failed")?;
+
+            write_output(input, &output_dir.join(output_name), &pdf_data)?;
+        }
+        ExportFormat::Svg => {
+            let svg_data = sheet.export_sheet_as_svg_string()?;
+
+            write_output(input, &output_dir.join(output_name), svg_data.as_bytes())?;
+        }
+        ExportFormat::Xopp => {
+            let xopp_data = sheet.export_sheet_as_xopp_bytes(&output_name, renderer)?;
+
+            write_output(input, &output_dir.join(output_name), &xopp_data)?;
+        }
+        ExportFormat::ReplaySvg => {
+            let svg_data = sheet.export_replay_as_animated_svg_string(&replay_prefs)?;
+            // Not to overwrite the svg of the sheet
+            let output_name = format!("{}_replay.svg", stem);
+
+            write_output(input, &output_dir.join(output_name), svg_data.as_bytes())?;
+        }
+        ExportFormat::ReplayMp4 | ExportFormat::ReplayWebm => {
+            let output = output_dir.join(output_name);
+            let encoder = FfmpegEncoder::new(output.clone());
+
+            futures::executor::block_on(
+                sheet.export_replay_as_video(replay_prefs, Box::new(encoder))?,
+            )
+            .context("exporting the replay as video was cancelled")?
+            .context("exporting the replay as video failed")?;
+            println!("{} -> {}", input.display(), output.display());
+        }
+        ExportFormat::Png | ExportFormat::Jpeg => {
+            let page_range = cli.pages.clone().unwrap_or(0..u32::MAX);
+            let first_page = page_range.start;
+            let prefs = BitmapExportPrefs {
+                format: if cli.format == ExportFormat::Jpeg {
+                    BitmapExportFormat::Jpeg
+                } else {
+                    BitmapExportFormat::Png
+                },
+                dpi: cli
+                    .dpi
+                    .clamp(BitmapExportPrefs::DPI_MIN, BitmapExportPrefs::DPI_MAX),
+                jpeg_quality: cli.jpeg_quality,
+                with_background: !cli.transparent,
+                scope: BitmapExportScope::Pages(page_range),
+            };
+
+            let images_data =
+                futures::executor::block_on(sheet.export_sheet_as_bitmap_bytes(prefs)?)
+                    .context("exporting the sheet as bitmap images failed")?;
+
+            // Every page becomes its own file, numbered like the pages
+            for (i, image_data) in images_data.into_iter().enumerate() {
+                let output_name = format!(
+                    "{}_{}.{}",
+                    stem,
+                    first_page as usize + i + 1,
+                    cli.format.file_ext()
+                );
+
+                write_output(input, &output_dir.join(output_name), &image_data)?;
+            }
+        }
+    }
+
+    if cli.extract_attachments {
+        let paths = sheet
+            .extract_attachments(&output_dir)
+            .context("extracting the attachments failed")?;
+
+        for path in paths {
+            println!("{} -> {}", input.display(), path.display());
+        }
+    }
+
+    Ok(())
+}
+
+fn write_output(input: &Path, output: &Path, data: &[u8]) -> Result<(), anyhow::Error> {
+    std::fs::write(output, data)
+        .with_context(|| format!("writing `{}` failed", output.display()))?;
+    println!("{} -> {}", input.display(), output.display());
+
+    Ok(())
+}
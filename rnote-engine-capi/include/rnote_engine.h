@@ -0,0 +1,133 @@
+#ifndef RNOTE_ENGINE_H
+#define RNOTE_ENGINE_H
+
+/* Generated with cbindgen from rnote-engine-capi, do not edit it manually */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The result of a call
+typedef enum RnoteStatus {
+  RNOTE_STATUS_OK = 0,
+  // A pointer was null, or an argument is out of its range
+  RNOTE_STATUS_INVALID_ARGUMENT,
+  // The operation failed, see `rnote_last_error()`
+  RNOTE_STATUS_FAILED,
+} RnoteStatus;
+
+// A document with its strokes. Created with `rnote_sheet_new()` and freed with `rnote_sheet_free()`
+typedef struct RnoteSheet RnoteSheet;
+
+// Bytes owned by the library, e.g. a saved or exported document. Free them with `rnote_buffer_free()`
+typedef struct RnoteBuffer {
+  uint8_t *data;
+  size_t len;
+} RnoteBuffer;
+
+// A point of a stroke, in the coordinate space of the document
+typedef struct RnotePoint {
+  double x;
+  double y;
+  // The pressure between 0.0 and 1.0
+  double pressure;
+} RnotePoint;
+
+// A color with components between 0.0 and 1.0
+typedef struct RnoteColor {
+  double r;
+  double g;
+  double b;
+  double a;
+} RnoteColor;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// The message of the last error on the calling thread, or null if no call failed yet.
+// The string is valid until the next call on the thread
+const char *rnote_last_error(void);
+
+// A new empty sheet. Returns null if it failed
+RnoteSheet *rnote_sheet_new(void);
+
+// Frees the sheet. Null is ignored
+//
+// # Safety
+// The pointer must be null or point to a sheet created with `rnote_sheet_new()`, which is not used afterwards
+void rnote_sheet_free(RnoteSheet *sheet);
+
+// Opens the document in the Rnote file format, replacing the content of the sheet
+//
+// # Safety
+// `sheet` must point to a sheet created with `rnote_sheet_new()` and `data` to `len` bytes
+RnoteStatus rnote_sheet_open(RnoteSheet *sheet, const uint8_t *data, size_t len);
+
+// Opens the Xournal++ document, replacing the content of the sheet
+//
+// # Safety
+// `sheet` must point to a sheet created with `rnote_sheet_new()` and `data` to `len` bytes
+RnoteStatus rnote_sheet_open_xopp(RnoteSheet *sheet, const uint8_t *data, size_t len);
+
+// Saves the sheet in the Rnote file format into `out`
+//
+// # Safety
+// `sheet` must point to a sheet created with `rnote_sheet_new()` and `out` to a writable buffer
+RnoteStatus rnote_sheet_save(RnoteSheet *sheet, RnoteBuffer *out);
+
+// The number of strokes of the sheet, without the trashed ones. Zero if the sheet is null
+//
+// # Safety
+// `sheet` must be null or point to a sheet created with `rnote_sheet_new()`
+size_t rnote_sheet_n_strokes(const RnoteSheet *sheet);
+
+// Inserts a brush stroke through the points with the width and color. The sheet grows to fit it
+//
+// # Safety
+// `sheet` must point to a sheet created with `rnote_sheet_new()` and `points` to `n_points` points
+RnoteStatus rnote_sheet_insert_brushstroke(RnoteSheet *sheet,
+                                           const RnotePoint *points,
+                                           size_t n_points,
+                                           double width,
+                                           RnoteColor color);
+
+// Exports the sheet as svg into `out`
+//
+// # Safety
+// `sheet` must point to a sheet created with `rnote_sheet_new()` and `out` to a writable buffer
+RnoteStatus rnote_sheet_export_svg(RnoteSheet *sheet, RnoteBuffer *out);
+
+// Exports the pages of the sheet with content as pdf with the title into `out`
+//
+// # Safety
+// `sheet` must point to a sheet created with `rnote_sheet_new()`, `title` to a nul terminated UTF-8 string and `out` to a writable buffer
+RnoteStatus rnote_sheet_export_pdf(RnoteSheet *sheet, const char *title, RnoteBuffer *out);
+
+// Exports the sheet as Xournal++ document into `out`. The file name is stored in the document
+//
+// # Safety
+// `sheet` must point to a sheet created with `rnote_sheet_new()`, `filename` to a nul terminated UTF-8 string and `out` to a writable buffer
+RnoteStatus rnote_sheet_export_xopp(RnoteSheet *sheet, const char *filename, RnoteBuffer *out);
+
+// Exports the page with the index (counted from 0) as png image with the resolution into `out`
+//
+// # Safety
+// `sheet` must point to a sheet created with `rnote_sheet_new()` and `out` to a writable buffer
+RnoteStatus rnote_sheet_export_page_png(RnoteSheet *sheet,
+                                        uint32_t page,
+                                        double dpi,
+                                        RnoteBuffer *out);
+
+// Frees the bytes of the buffer. Buffers with null data are ignored
+//
+// # Safety
+// The buffer must have been written by this library, and is not used afterwards
+void rnote_buffer_free(RnoteBuffer buffer);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* RNOTE_ENGINE_H */
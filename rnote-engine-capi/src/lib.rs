@@ -0,0 +1,491 @@
+//! The C api of the engine, so other front-ends can read, write and export Rnote documents with it.
+//! The header `include/rnote_engine.h` is generated from this file with cbindgen, see `cbindgen.toml`. Regenerate it when the api changes.
+//!
+//! Every call which can fail returns a `RnoteStatus`. The message of the last error on the calling thread is returned by `rnote_last_error()`.
+//! Panics don't unwind into the calling code, they are returned as `RNOTE_STATUS_FAILED`.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use rnote_engine::compose::color::Color;
+use rnote_engine::pens::brush::{Brush, BrushStyle};
+use rnote_engine::render::Renderer;
+use rnote_engine::sheet::bitmapexport::{BitmapExportFormat, BitmapExportPrefs, BitmapExportScope};
+use rnote_engine::sheet::Sheet;
+use rnote_engine::strokes::brushstroke::BrushStroke;
+use rnote_engine::strokes::element::Element;
+use rnote_engine::strokes::inputdata::InputData;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+
+extern crate nalgebra as na;
+
+/// The result of a call
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RnoteStatus {
+    Ok = 0,
+    /// A pointer was null, or an argument is out of its range
+    InvalidArgument,
+    /// The operation failed, see `rnote_last_error()`
+    Failed,
+}
+
+/// A document with its strokes. Created with `rnote_sheet_new()` and freed with `rnote_sheet_free()`
+pub struct RnoteSheet {
+    sheet: Sheet,
+    renderer: Arc<RwLock<Renderer>>,
+}
+
+/// Bytes owned by the library, e.g. a saved or exported document. Free them with `rnote_buffer_free()`
+#[repr(C)]
+#[derive(Debug)]
+pub struct RnoteBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl RnoteBuffer {
+    fn from_vec(bytes: Vec<u8>) -> Self {
+        let bytes = bytes.into_boxed_slice();
+        let len = bytes.len();
+
+        Self {
+            data: Box::into_raw(bytes) as *mut u8,
+            len,
+        }
+    }
+}
+
+/// A point of a stroke, in the coordinate space of the document
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RnotePoint {
+    pub x: f64,
+    pub y: f64,
+    /// The pressure between 0.0 and 1.0
+    pub pressure: f64,
+}
+
+/// A color with components between 0.0 and 1.0
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct RnoteColor {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    // Messages with interior nul bytes are cut off at the first one
+    let message = CString::new(message).unwrap_or_else(|e| {
+        let nul_position = e.nul_position();
+        let mut bytes = e.into_vec();
+        bytes.truncate(nul_position);
+
+        CString::new(bytes).unwrap_or_default()
+    });
+
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+fn invalid_argument(message: &str) -> RnoteStatus {
+    set_last_error(message.to_string());
+    RnoteStatus::InvalidArgument
+}
+
+/// Runs the call, and stores its error or panic as last error
+fn ffi_call<F>(name: &str, call: F) -> RnoteStatus
+where
+    F: FnOnce() -> Result<(), anyhow::Error>,
+{
+    match panic::catch_unwind(AssertUnwindSafe(call)) {
+        Ok(Ok(())) => RnoteStatus::Ok,
+        Ok(Err(e)) => {
+            set_last_error(format!("{}() failed, {:?}", name, e));
+            RnoteStatus::Failed
+        }
+        Err(_) => {
+            set_last_error(format!("{}() panicked", name));
+            RnoteStatus::Failed
+        }
+    }
+}
+
+/// The sheet behind the pointer. None if it is null
+///
+/// # Safety
+/// The pointer must be null or point to a sheet created with `rnote_sheet_new()`, which is not used elsewhere during the call
+unsafe fn sheet_mut<'a>(sheet: *mut RnoteSheet) -> Option<&'a mut RnoteSheet> {
+    sheet.as_mut()
+}
+
+/// The bytes behind the pointer. Empty if the length is zero, None if the pointer is null otherwise
+///
+/// # Safety
+/// The pointer must point to at least `len` bytes
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if data.is_null() {
+        None
+    } else {
+        Some(std::slice::from_raw_parts(data, len))
+    }
+}
+
+/// The string behind the pointer. None if it is null or not valid UTF-8
+///
+/// # Safety
+/// The pointer must be null or point to a nul terminated string
+unsafe fn string<'a>(string: *const c_char) -> Option<&'a str> {
+    if string.is_null() {
+        return None;
+    }
+
+    CStr::from_ptr(string).to_str().ok()
+}
+
+/// Writes the bytes into the buffer
+///
+/// # Safety
+/// The pointer must point to a writable buffer
+unsafe fn write_buffer(out: *mut RnoteBuffer, bytes: Vec<u8>) {
+    out.write(RnoteBuffer::from_vec(bytes));
+}
+
+/// Resizes the sheet to fit its strokes, so they are included in the exports
+fn resize_to_fit_strokes(sheet: &mut Sheet) {
+    if sheet.page_layout.is_some() {
+        sheet.resize_sheet_mode_pages();
+    } else {
+        sheet.resize_sheet_mode_endless_vertical();
+    }
+}
+
+/// The message of the last error on the calling thread, or null if no call failed yet.
+/// The string is valid until the next call on the thread
+#[no_mangle]
+pub extern "C" fn rnote_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// A new empty sheet. Returns null if it failed
+#[no_mangle]
+pub extern "C" fn rnote_sheet_new() -> *mut RnoteSheet {
+    let mut sheet = None;
+
+    ffi_call("rnote_sheet_new", || {
+        sheet = Some(Box::new(RnoteSheet {
+            sheet: Sheet::default(),
+            // The renderer is only needed for the images embedded in xopp files
+            renderer: Arc::new(RwLock::new(Renderer::default())),
+        }));
+        Ok(())
+    });
+
+    sheet.map_or(std::ptr::null_mut(), Box::into_raw)
+}
+
+/// Frees the sheet. Null is ignored
+///
+/// # Safety
+/// The pointer must be null or point to a sheet created with `rnote_sheet_new()`, which is not used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn rnote_sheet_free(sheet: *mut RnoteSheet) {
+    if !sheet.is_null() {
+        drop(Box::from_raw(sheet));
+    }
+}
+
+/// Opens the document in the Rnote file format, replacing the content of the sheet
+///
+/// # Safety
+/// `sheet` must point to a sheet created with `rnote_sheet_new()` and `data` to `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn rnote_sheet_open(
+    sheet: *mut RnoteSheet,
+    data: *const u8,
+    len: usize,
+) -> RnoteStatus {
+    let (sheet, bytes) = match (sheet_mut(sheet), bytes(data, len)) {
+        (Some(sheet), Some(bytes)) => (sheet, bytes),
+        _ => return invalid_argument("rnote_sheet_open() needs a sheet and data"),
+    };
+
+    ffi_call("rnote_sheet_open", || {
+        sheet
+            .sheet
+            .open_sheet_from_rnote_bytes(glib::Bytes::from(bytes))
+    })
+}
+
+/// Opens the Xournal++ document, replacing the content of the sheet
+///
+/// # Safety
+/// `sheet` must point to a sheet created with `rnote_sheet_new()` and `data` to `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn rnote_sheet_open_xopp(
+    sheet: *mut RnoteSheet,
+    data: *const u8,
+    len: usize,
+) -> RnoteStatus {
+    let (sheet, bytes) = match (sheet_mut(sheet), bytes(data, len)) {
+        (Some(sheet), Some(bytes)) => (sheet, bytes),
+        _ => return invalid_argument("rnote_sheet_open_xopp() needs a sheet and data"),
+    };
+
+    ffi_call("rnote_sheet_open_xopp", || {
+        sheet.sheet.open_from_xopp_bytes(glib::Bytes::from(bytes))
+    })
+}
+
+/// Saves the sheet in the Rnote file format into `out`
+///
+/// # Safety
+/// `sheet` must point to a sheet created with `rnote_sheet_new()` and `out` to a writable buffer
+#[no_mangle]
+pub unsafe extern "C" fn rnote_sheet_save(
+    sheet: *mut RnoteSheet,
+    out: *mut RnoteBuffer,
+) -> RnoteStatus {
+    let sheet = match sheet_mut(sheet) {
+        Some(sheet) if !out.is_null() => sheet,
+        _ => return invalid_argument("rnote_sheet_save() needs a sheet and an output buffer"),
+    };
+
+    ffi_call("rnote_sheet_save", || {
+        write_buffer(out, sheet.sheet.save_sheet_as_rnote_bytes()?.to_vec());
+        Ok(())
+    })
+}
+
+/// The number of strokes of the sheet, without the trashed ones. Zero if the sheet is null
+///
+/// # Safety
+/// `sheet` must be null or point to a sheet created with `rnote_sheet_new()`
+#[no_mangle]
+pub unsafe extern "C" fn rnote_sheet_n_strokes(sheet: *const RnoteSheet) -> usize {
+    sheet
+        .as_ref()
+        .map_or(0, |sheet| sheet.sheet.statistics().stroke_counts.total())
+}
+
+/// Inserts a brush stroke through the points with the width and color. The sheet grows to fit it
+///
+/// # Safety
+/// `sheet` must point to a sheet created with `rnote_sheet_new()` and `points` to `n_points` points
+#[no_mangle]
+pub unsafe extern "C" fn rnote_sheet_insert_brushstroke(
+    sheet: *mut RnoteSheet,
+    points: *const RnotePoint,
+    n_points: usize,
+    width: f64,
+    color: RnoteColor,
+) -> RnoteStatus {
+    let sheet = match sheet_mut(sheet) {
+        Some(sheet) if !points.is_null() && n_points > 0 => sheet,
+        _ => {
+            return invalid_argument(
+                "rnote_sheet_insert_brushstroke() needs a sheet and at least one point",
+            )
+        }
+    };
+    if !(width.is_finite() && width > 0.0) {
+        return invalid_argument("rnote_sheet_insert_brushstroke() needs a positive width");
+    }
+    let points = std::slice::from_raw_parts(points, n_points);
+    // Also rejects NaN
+    if !points
+        .iter()
+        .all(|point| (0.0..=1.0).contains(&point.pressure))
+    {
+        return invalid_argument(
+            "rnote_sheet_insert_brushstroke() needs pressures between 0.0 and 1.0",
+        );
+    }
+
+    ffi_call("rnote_sheet_insert_brushstroke", || {
+        let mut brush = Brush {
+            style: BrushStyle::Solid,
+            ..Brush::default()
+        };
+        brush.smooth_options.width = width;
+        brush.smooth_options.stroke_color = Some(Color::new(color.r, color.g, color.b, color.a));
+
+        let elements = points.iter().map(|point| {
+            Element::new(InputData::new(
+                na::vector![point.x, point.y],
+                point.pressure,
+            ))
+        });
+        let brushstroke = BrushStroke::new_w_elements(elements, &brush)
+            .context("creating the brush stroke failed")?;
+
+        sheet
+            .sheet
+            .strokes_state
+            .insert_stroke(StrokeStyle::BrushStroke(brushstroke));
+        resize_to_fit_strokes(&mut sheet.sheet);
+
+        Ok(())
+    })
+}
+
+/// Exports the sheet as svg into `out`
+///
+/// # Safety
+/// `sheet` must point to a sheet created with `rnote_sheet_new()` and `out` to a writable buffer
+#[no_mangle]
+pub unsafe extern "C" fn rnote_sheet_export_svg(
+    sheet: *mut RnoteSheet,
+    out: *mut RnoteBuffer,
+) -> RnoteStatus {
+    let sheet = match sheet_mut(sheet) {
+        Some(sheet) if !out.is_null() => sheet,
+        _ => {
+            return invalid_argument("rnote_sheet_export_svg() needs a sheet and an output buffer")
+        }
+    };
+
+    ffi_call("rnote_sheet_export_svg", || {
+        let svg_data = sheet.sheet.export_sheet_as_svg_string()?;
+
+        write_buffer(out, svg_data.into_bytes());
+        Ok(())
+    })
+}
+
+/// Exports the pages of the sheet with content as pdf with the title into `out`
+///
+/// # Safety
+/// `sheet` must point to a sheet created with `rnote_sheet_new()`, `title` to a nul terminated UTF-8 string and `out` to a writable buffer
+#[no_mangle]
+pub unsafe extern "C" fn rnote_sheet_export_pdf(
+    sheet: *mut RnoteSheet,
+    title: *const c_char,
+    out: *mut RnoteBuffer,
+) -> RnoteStatus {
+    let (sheet, title) = match (sheet_mut(sheet), string(title)) {
+        (Some(sheet), Some(title)) if !out.is_null() => (sheet, title),
+        _ => {
+            return invalid_argument(
+                "rnote_sheet_export_pdf() needs a sheet, a UTF-8 title and an output buffer",
+            )
+        }
+    };
+
+    ffi_call("rnote_sheet_export_pdf", || {
+        let pdf_data = futures::executor::block_on(
+            sheet
+                .sheet
+                .export_sheet_as_pdf_bytes(title.to_string(), None),
+        )
+        .context("exporting the sheet as pdf failed")?;
+
+        write_buffer(out, pdf_data);
+        Ok(())
+    })
+}
+
+/// Exports the sheet as Xournal++ document into `out`. The file name is stored in the document
+///
+/// # Safety
+/// `sheet` must point to a sheet created with `rnote_sheet_new()`, `filename` to a nul terminated UTF-8 string and `out` to a writable buffer
+#[no_mangle]
+pub unsafe extern "C" fn rnote_sheet_export_xopp(
+    sheet: *mut RnoteSheet,
+    filename: *const c_char,
+    out: *mut RnoteBuffer,
+) -> RnoteStatus {
+    let (sheet, filename) =
+        match (sheet_mut(sheet), string(filename)) {
+            (Some(sheet), Some(filename)) if !out.is_null() => (sheet, filename),
+            _ => return invalid_argument(
+                "rnote_sheet_export_xopp() needs a sheet, a UTF-8 file name and an output buffer",
+            ),
+        };
+
+    ffi_call("rnote_sheet_export_xopp", || {
+        let xopp_data = sheet
+            .sheet
+            .export_sheet_as_xopp_bytes(filename, Arc::clone(&sheet.renderer))?;
+
+        write_buffer(out, xopp_data);
+        Ok(())
+    })
+}
+
+/// Exports the page with the index (counted from 0) as png image with the resolution into `out`
+///
+/// # Safety
+/// `sheet` must point to a sheet created with `rnote_sheet_new()` and `out` to a writable buffer
+#[no_mangle]
+pub unsafe extern "C" fn rnote_sheet_export_page_png(
+    sheet: *mut RnoteSheet,
+    page: u32,
+    dpi: f64,
+    out: *mut RnoteBuffer,
+) -> RnoteStatus {
+    let sheet = match sheet_mut(sheet) {
+        Some(sheet) if !out.is_null() => sheet,
+        _ => {
+            return invalid_argument(
+                "rnote_sheet_export_page_png() needs a sheet and an output buffer",
+            )
+        }
+    };
+    if !(BitmapExportPrefs::DPI_MIN..=BitmapExportPrefs::DPI_MAX).contains(&dpi) {
+        return invalid_argument("rnote_sheet_export_page_png() got a dpi out of its range");
+    }
+    if page as usize >= sheet.sheet.pages().len() {
+        return invalid_argument("rnote_sheet_export_page_png() got a page which doesn't exist");
+    }
+
+    ffi_call("rnote_sheet_export_page_png", || {
+        let prefs = BitmapExportPrefs {
+            format: BitmapExportFormat::Png,
+            dpi,
+            jpeg_quality: BitmapExportPrefs::JPEG_QUALITY_DEFAULT,
+            with_background: true,
+            scope: BitmapExportScope::Pages(page..page + 1),
+        };
+
+        let image_data =
+            futures::executor::block_on(sheet.sheet.export_sheet_as_bitmap_bytes(prefs)?)
+                .context("exporting the page as png failed")?
+                .into_iter()
+                .next()
+                .context("the export has no image of the page")?;
+
+        write_buffer(out, image_data);
+        Ok(())
+    })
+}
+
+/// Frees the bytes of the buffer. Buffers with null data are ignored
+///
+/// # Safety
+/// The buffer must have been written by this library, and is not used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn rnote_buffer_free(buffer: RnoteBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
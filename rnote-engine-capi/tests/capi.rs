@@ -0,0 +1,151 @@
+use std::ffi::{CStr, CString};
+
+use rnote_engine_capi::*;
+
+const BLACK: RnoteColor = RnoteColor {
+    r: 0.0,
+    g: 0.0,
+    b: 0.0,
+    a: 1.0,
+};
+
+fn points() -> Vec<RnotePoint> {
+    (0..10)
+        .map(|i| RnotePoint {
+            x: 20.0 + f64::from(i) * 10.0,
+            y: 30.0,
+            pressure: 0.5,
+        })
+        .collect()
+}
+
+fn empty_buffer() -> RnoteBuffer {
+    RnoteBuffer {
+        data: std::ptr::null_mut(),
+        len: 0,
+    }
+}
+
+unsafe fn buffer_bytes(buffer: &RnoteBuffer) -> Vec<u8> {
+    std::slice::from_raw_parts(buffer.data, buffer.len).to_vec()
+}
+
+unsafe fn last_error() -> String {
+    CStr::from_ptr(rnote_last_error())
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[test]
+fn insert_save_and_open() {
+    unsafe {
+        let sheet = rnote_sheet_new();
+        assert!(!sheet.is_null());
+        let points = points();
+
+        assert_eq!(
+            rnote_sheet_insert_brushstroke(sheet, points.as_ptr(), points.len(), 2.0, BLACK),
+            RnoteStatus::Ok
+        );
+        assert_eq!(rnote_sheet_n_strokes(sheet), 1);
+
+        let mut saved = empty_buffer();
+        assert_eq!(rnote_sheet_save(sheet, &mut saved), RnoteStatus::Ok);
+        assert!(saved.len > 0);
+
+        let opened = rnote_sheet_new();
+        assert_eq!(
+            rnote_sheet_open(opened, saved.data, saved.len),
+            RnoteStatus::Ok
+        );
+        assert_eq!(rnote_sheet_n_strokes(opened), 1);
+
+        rnote_buffer_free(saved);
+        rnote_sheet_free(opened);
+        rnote_sheet_free(sheet);
+    }
+}
+
+#[test]
+fn exports() {
+    unsafe {
+        let sheet = rnote_sheet_new();
+        let points = points();
+        rnote_sheet_insert_brushstroke(sheet, points.as_ptr(), points.len(), 2.0, BLACK);
+
+        let mut svg = empty_buffer();
+        assert_eq!(rnote_sheet_export_svg(sheet, &mut svg), RnoteStatus::Ok);
+        assert!(String::from_utf8(buffer_bytes(&svg))
+            .unwrap()
+            .contains("<svg"));
+        rnote_buffer_free(svg);
+
+        let title = CString::new("document").unwrap();
+        let mut pdf = empty_buffer();
+        assert_eq!(
+            rnote_sheet_export_pdf(sheet, title.as_ptr(), &mut pdf),
+            RnoteStatus::Ok
+        );
+        assert!(buffer_bytes(&pdf).starts_with(b"%PDF"));
+        rnote_buffer_free(pdf);
+
+        let mut png = empty_buffer();
+        assert_eq!(
+            rnote_sheet_export_page_png(sheet, 0, 96.0, &mut png),
+            RnoteStatus::Ok
+        );
+        assert!(buffer_bytes(&png).starts_with(b"\x89PNG"));
+        rnote_buffer_free(png);
+
+        rnote_sheet_free(sheet);
+    }
+}
+
+#[test]
+fn invalid_arguments_are_reported() {
+    unsafe {
+        let sheet = rnote_sheet_new();
+        let mut buffer = empty_buffer();
+
+        assert_eq!(
+            rnote_sheet_insert_brushstroke(sheet, std::ptr::null(), 0, 2.0, BLACK),
+            RnoteStatus::InvalidArgument
+        );
+        assert!(last_error().contains("rnote_sheet_insert_brushstroke()"));
+
+        for pressure in [f64::NAN, -0.1, 1.5] {
+            let points = [RnotePoint {
+                x: 20.0,
+                y: 30.0,
+                pressure,
+            }];
+            assert_eq!(
+                rnote_sheet_insert_brushstroke(sheet, points.as_ptr(), points.len(), 2.0, BLACK),
+                RnoteStatus::InvalidArgument
+            );
+        }
+        assert!(last_error().contains("pressures"));
+        assert_eq!(rnote_sheet_n_strokes(sheet), 0);
+
+        assert_eq!(
+            rnote_sheet_export_page_png(sheet, 100, 96.0, &mut buffer),
+            RnoteStatus::InvalidArgument
+        );
+        assert_eq!(
+            rnote_sheet_save(std::ptr::null_mut(), &mut buffer),
+            RnoteStatus::InvalidArgument
+        );
+        assert!(buffer.data.is_null());
+
+        // Garbage fails to open, but leaves the sheet usable
+        let garbage = b"not a document";
+        assert_eq!(
+            rnote_sheet_open(sheet, garbage.as_ptr(), garbage.len()),
+            RnoteStatus::Failed
+        );
+        assert!(last_error().contains("rnote_sheet_open()"));
+        assert_eq!(rnote_sheet_n_strokes(sheet), 0);
+
+        rnote_sheet_free(sheet);
+    }
+}
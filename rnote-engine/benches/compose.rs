@@ -0,0 +1,51 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use nalgebra as na;
+use rnote_engine::drawbehaviour::DrawBehaviour;
+use rnote_engine::pens::brush::{Brush, BrushStyle};
+use rnote_engine::strokes::brushstroke::BrushStroke;
+use rnote_engine::strokes::element::Element;
+use rnote_engine::strokes::inputdata::InputData;
+
+/// A wavy stroke with the amount of elements
+fn brushstroke(style: BrushStyle, n_elements: usize) -> BrushStroke {
+    let brush = Brush {
+        style,
+        ..Brush::default()
+    };
+    let elements = (0..n_elements).map(|i| {
+        let x = i as f64 * 2.0;
+
+        Element::new(InputData::new(
+            na::vector![x, 100.0 + (x * 0.05).sin() * 50.0],
+            0.5 + (x * 0.1).sin() * 0.3,
+        ))
+    });
+
+    BrushStroke::new_w_elements(elements, &brush).unwrap()
+}
+
+fn compose(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compose");
+
+    for style in [BrushStyle::Solid, BrushStyle::Textured] {
+        for n_elements in [100, 1000] {
+            let stroke = brushstroke(style, n_elements);
+
+            group.bench_with_input(
+                BenchmarkId::new(format!("{:?} svgs", style), n_elements),
+                &stroke,
+                |b, stroke| b.iter(|| stroke.gen_svgs(black_box(na::Vector2::zeros()))),
+            );
+            group.bench_with_input(
+                BenchmarkId::new(format!("{:?} tessellation", style), n_elements),
+                &stroke,
+                |b, stroke| b.iter(|| stroke.gen_tessellation()),
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, compose);
+criterion_main!(benches);
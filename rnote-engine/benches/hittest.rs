@@ -0,0 +1,58 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use nalgebra as na;
+use parry2d_f64::bounding_volume::AABB;
+use rnote_engine::pens::brush::Brush;
+use rnote_engine::pens::eraser::Eraser;
+use rnote_engine::strokes::brushstroke::BrushStroke;
+use rnote_engine::strokes::element::Element;
+use rnote_engine::strokes::inputdata::InputData;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+use rnote_engine::strokesstate::StrokesState;
+
+/// The strokes are short lines on a grid, with this spacing
+const SPACING: f64 = 100.0;
+
+/// A strokes state with the amount of strokes in a square grid
+fn strokes_state(n_strokes: usize) -> StrokesState {
+    let mut strokes_state = StrokesState::default();
+    let columns = (n_strokes as f64).sqrt().ceil() as usize;
+
+    for i in 0..n_strokes {
+        let pos = na::vector![(i % columns) as f64, (i / columns) as f64] * SPACING;
+        let elements = (0..10)
+            .map(|j| Element::new(InputData::new(pos + na::vector![j as f64 * 5.0, 0.0], 0.5)));
+
+        strokes_state.insert_stroke(StrokeStyle::BrushStroke(
+            BrushStroke::new_w_elements(elements, &Brush::default()).unwrap(),
+        ));
+    }
+
+    strokes_state
+}
+
+fn hittest(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hittest");
+    let mut strokes_state = strokes_state(10_000);
+
+    let viewport = AABB::new(na::point![1000.0, 1000.0], na::point![2920.0, 2080.0]);
+    group.bench_function("viewport", |b| {
+        b.iter(|| strokes_state.keys_intersecting_bounds(black_box(viewport)))
+    });
+
+    let point = AABB::from_half_extents(na::point![5020.0, 5000.0], na::vector![5.0, 5.0]);
+    group.bench_function("point", |b| {
+        b.iter(|| strokes_state.keys_intersecting_bounds(black_box(point)))
+    });
+
+    // Next to the strokes, so they are checked but not trashed
+    let mut eraser = Eraser::default();
+    eraser.current_input = Some(InputData::new(na::vector![5020.0, 5030.0], 0.5));
+    group.bench_function("eraser", |b| {
+        b.iter(|| strokes_state.trash_colliding_strokes(&eraser, Some(viewport)))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, hittest);
+criterion_main!(benches);
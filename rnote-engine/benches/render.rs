@@ -0,0 +1,50 @@
+use std::sync::{Arc, RwLock};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use nalgebra as na;
+use rnote_engine::drawbehaviour::DrawBehaviour;
+use rnote_engine::pens::brush::{Brush, BrushStyle};
+use rnote_engine::render::Renderer;
+use rnote_engine::strokes::brushstroke::BrushStroke;
+use rnote_engine::strokes::element::Element;
+use rnote_engine::strokes::inputdata::InputData;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+
+fn stroke(style: BrushStyle) -> StrokeStyle {
+    let brush = Brush {
+        style,
+        ..Brush::default()
+    };
+    let elements = (0..500).map(|i| {
+        let x = i as f64 * 2.0;
+
+        Element::new(InputData::new(
+            na::vector![x, 100.0 + (x * 0.05).sin() * 50.0],
+            0.5,
+        ))
+    });
+
+    StrokeStyle::BrushStroke(BrushStroke::new_w_elements(elements, &brush).unwrap())
+}
+
+fn render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+    let renderer = Arc::new(RwLock::new(Renderer::default()));
+
+    for style in [BrushStyle::Solid, BrushStyle::Textured] {
+        let stroke = stroke(style);
+
+        for zoom in [0.5, 1.0, 2.0] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{:?} images", style), zoom),
+                &zoom,
+                |b, &zoom| b.iter(|| stroke.gen_images(zoom, Arc::clone(&renderer)).unwrap()),
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
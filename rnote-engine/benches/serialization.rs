@@ -0,0 +1,53 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use nalgebra as na;
+use rnote_engine::pens::brush::Brush;
+use rnote_engine::sheet::migration::FILE_FORMAT_VERSION;
+use rnote_engine::sheet::{container, Sheet};
+use rnote_engine::strokes::brushstroke::BrushStroke;
+use rnote_engine::strokes::element::Element;
+use rnote_engine::strokes::inputdata::InputData;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+
+/// A sheet with the amount of strokes, each with 100 elements
+fn sheet(n_strokes: usize) -> Sheet {
+    let mut sheet = Sheet::default();
+
+    for i in 0..n_strokes {
+        let elements = (0..100).map(|j| {
+            Element::new(InputData::new(
+                na::vector![j as f64 * 3.0, i as f64 * 10.0],
+                0.5,
+            ))
+        });
+
+        sheet.strokes_state.insert_stroke(StrokeStyle::BrushStroke(
+            BrushStroke::new_w_elements(elements, &Brush::default()).unwrap(),
+        ));
+    }
+
+    sheet
+}
+
+fn serialization(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serialization");
+    let mut sheet = sheet(1000);
+
+    // Serializes all strokes, without the cache of the previously saved chunks
+    group.bench_function("save", |b| {
+        b.iter(|| container::save(&sheet, FILE_FORMAT_VERSION).unwrap())
+    });
+
+    let bytes = sheet.save_sheet_as_rnote_bytes().unwrap();
+    group.bench_function("open", |b| {
+        b.iter(|| {
+            let mut sheet = Sheet::default();
+            sheet.open_sheet_from_rnote_bytes(bytes.clone()).unwrap();
+            sheet
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, serialization);
+criterion_main!(benches);
@@ -0,0 +1,218 @@
+//! Draws the sheet onto a 2D canvas, e.g. an HTML canvas or OffscreenCanvas in a browser.
+//! The canvas is abstracted by the `CanvasContext` trait, which mirrors the methods of `CanvasRenderingContext2D` that are needed,
+//! so a web frontend only needs to forward them. Tessellated strokes are drawn as paths, all others as svg images.
+
+use crate::compose;
+use crate::compose::tessellation::{Primitive, Tessellation};
+use crate::drawbehaviour::DrawBehaviour;
+use crate::render::{self, TessellatedStrokes};
+use crate::sheet::Sheet;
+use crate::strokes::brushstroke::BrushStrokeStyle;
+use crate::strokes::strokestyle::StrokeStyle;
+use crate::strokesstate::StrokesState;
+
+use p2d::bounding_volume::{BoundingVolume, AABB};
+
+/// A 2D canvas context. The methods have the semantics of the `CanvasRenderingContext2D` methods with the same names
+pub trait CanvasContext {
+    fn save(&mut self);
+    fn restore(&mut self);
+    fn translate(&mut self, x: f64, y: f64);
+    fn rotate(&mut self, angle: f64);
+    fn scale(&mut self, x: f64, y: f64);
+    fn set_global_alpha(&mut self, alpha: f64);
+    /// Sets the `globalCompositeOperation`, e.g "source-over" or "multiply"
+    fn set_global_composite_operation(&mut self, operation: &str);
+    /// Sets the fill style to the css color
+    fn set_fill_style(&mut self, color: &str);
+    fn begin_path(&mut self);
+    fn close_path(&mut self);
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64);
+    fn arc(&mut self, x: f64, y: f64, radius: f64, start_angle: f64, end_angle: f64);
+    #[allow(clippy::too_many_arguments)]
+    fn ellipse(
+        &mut self,
+        x: f64,
+        y: f64,
+        radius_x: f64,
+        radius_y: f64,
+        rotation: f64,
+        start_angle: f64,
+        end_angle: f64,
+    );
+    fn fill(&mut self);
+    /// Draws the svg document into the bounds, in the current coordinate space. In a browser by loading it into an image first
+    fn draw_svg(&mut self, svg_data: &str, bounds: AABB);
+}
+
+/// Draws the backgrounds and the strokes of the sheet in the viewport onto the context.
+/// The origin of the canvas is the upper left corner of the viewport, and one unit of the sheet are `zoom` pixels of the canvas
+pub fn draw_sheet(
+    sheet: &Sheet,
+    viewport: AABB,
+    zoom: f64,
+    tessellated_strokes: &TessellatedStrokes,
+    context: &mut impl CanvasContext,
+) -> Result<(), anyhow::Error> {
+    context.save();
+    context.scale(zoom, zoom);
+    context.translate(-viewport.mins[0], -viewport.mins[1]);
+
+    for svg in sheet.gen_background_svgs()? {
+        draw_svg(&svg, viewport, context);
+    }
+    draw_strokes(
+        &sheet.strokes_state,
+        viewport,
+        zoom,
+        tessellated_strokes,
+        context,
+    )?;
+
+    context.restore();
+    Ok(())
+}
+
+/// Draws the strokes in the viewport onto the context, in the coordinate space of the sheet. The selected strokes are drawn on top
+pub fn draw_strokes(
+    strokes_state: &StrokesState,
+    viewport: AABB,
+    zoom: f64,
+    tessellated_strokes: &TessellatedStrokes,
+    context: &mut impl CanvasContext,
+) -> Result<(), anyhow::Error> {
+    let mut keys = strokes_state.keys_intersecting_bounds(viewport);
+    keys.extend(
+        strokes_state
+            .selection_keys_as_rendered()
+            .into_iter()
+            .filter(|&key| {
+                strokes_state
+                    .get_stroke_ref(key)
+                    .map_or(false, |stroke| stroke.bounds().intersects(&viewport))
+            }),
+    );
+
+    for key in keys {
+        if let Some(stroke) = strokes_state.get_stroke_ref(key) {
+            draw_stroke(stroke, viewport, zoom, tessellated_strokes, context)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Draws the stroke onto the context, in the coordinate space of the sheet
+pub fn draw_stroke(
+    stroke: &StrokeStyle,
+    viewport: AABB,
+    zoom: f64,
+    tessellated_strokes: &TessellatedStrokes,
+    context: &mut impl CanvasContext,
+) -> Result<(), anyhow::Error> {
+    let composite_operation = match stroke {
+        StrokeStyle::BrushStroke(brushstroke) => match &brushstroke.style {
+            BrushStrokeStyle::Highlighter { options } => options.blend_mode.to_css_blend_mode(),
+            _ => "normal",
+        },
+        _ => "normal",
+    };
+
+    context.save();
+    // The css blend mode "normal" is "source-over" for the canvas
+    if composite_operation != "normal" {
+        context.set_global_composite_operation(composite_operation);
+    }
+
+    // Translucent primitives would get darker where they overlap, because the canvas can't composite them with the opacity of the stroke.
+    // These strokes are drawn as svg, where the opacity is applied to the stroke as a whole
+    match stroke
+        .gen_tessellation(tessellated_strokes, zoom)
+        .filter(|tessellation| tessellation.opacity >= 1.0)
+    {
+        Some(tessellation) => draw_tessellation(&tessellation, context),
+        None => {
+            for svg in stroke.gen_svgs(na::vector![0.0, 0.0])? {
+                draw_svg(&svg, viewport, context);
+            }
+        }
+    }
+
+    context.restore();
+    Ok(())
+}
+
+/// Draws the svg, if it is in the viewport
+fn draw_svg(svg: &render::Svg, viewport: AABB, context: &mut impl CanvasContext) {
+    if !svg.bounds.intersects(&viewport) || render::assert_bounds(svg.bounds).is_err() {
+        return;
+    }
+
+    let svg_data = compose::wrap_svg_root(&svg.svg_data, Some(svg.bounds), Some(svg.bounds), false);
+    context.draw_svg(&svg_data, svg.bounds);
+}
+
+fn draw_tessellation(tessellation: &Tessellation, context: &mut impl CanvasContext) {
+    for (primitive, color) in tessellation.primitives.iter() {
+        context.set_fill_style(&color.to_css_color());
+        context.begin_path();
+
+        match *primitive {
+            Primitive::Ellipse {
+                center,
+                radii,
+                rotation_angle,
+            } => {
+                context.ellipse(
+                    center[0],
+                    center[1],
+                    radii[0],
+                    radii[1],
+                    rotation_angle,
+                    0.0,
+                    std::f64::consts::TAU,
+                );
+            }
+            Primitive::Rectangle {
+                center,
+                half_extents,
+                rotation_angle,
+            } => {
+                context.save();
+                context.translate(center[0], center[1]);
+                context.rotate(rotation_angle);
+                context.rect(
+                    -half_extents[0],
+                    -half_extents[1],
+                    2.0 * half_extents[0],
+                    2.0 * half_extents[1],
+                );
+                context.restore();
+            }
+            Primitive::Capsule { start, end, width } => {
+                // Two half circles around the ends, joined by the sides
+                let vec = end - start;
+                let angle = vec[1].atan2(vec[0]);
+                let radius = width * 0.5;
+
+                context.arc(
+                    end[0],
+                    end[1],
+                    radius,
+                    angle - std::f64::consts::FRAC_PI_2,
+                    angle + std::f64::consts::FRAC_PI_2,
+                );
+                context.arc(
+                    start[0],
+                    start[1],
+                    radius,
+                    angle + std::f64::consts::FRAC_PI_2,
+                    angle + 3.0 * std::f64::consts::FRAC_PI_2,
+                );
+                context.close_path();
+            }
+        }
+
+        context.fill();
+    }
+}
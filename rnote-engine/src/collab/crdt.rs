@@ -0,0 +1,161 @@
+//! The strokes of a collaboration session as a CRDT ( conflict-free replicated data type ).
+//! Every stroke is identified by the stamp of the operation which added it, and holds a last-writer-wins register:
+//! an update replaces the stroke when its stamp is larger than the stamp of the current state.
+//! Removals are final and win over concurrent updates. The operations commute and are idempotent,
+//! so every participant ends up with the same strokes regardless of the order in which they received them.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::strokes::strokestyle::StrokeStyle;
+
+/// Identifies a participant of the session
+pub type ReplicaId = u64;
+
+/// A Lamport timestamp. Ordered by the counter, ties between participants are broken by their replica id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename = "stamp")]
+pub struct Stamp {
+    #[serde(rename = "counter")]
+    pub counter: u64,
+    #[serde(rename = "replica")]
+    pub replica: ReplicaId,
+}
+
+/// A stroke is identified by the stamp of the operation which added it
+pub type StrokeId = Stamp;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "crdt_op")]
+pub enum CrdtOp {
+    #[serde(rename = "add")]
+    Add {
+        #[serde(rename = "id")]
+        id: StrokeId,
+        #[serde(rename = "stroke")]
+        stroke: StrokeStyle,
+    },
+    /// Replaces the stroke, e.g. after it was transformed or its options were changed
+    #[serde(rename = "update")]
+    Update {
+        #[serde(rename = "id")]
+        id: StrokeId,
+        #[serde(rename = "stamp")]
+        stamp: Stamp,
+        #[serde(rename = "stroke")]
+        stroke: StrokeStyle,
+    },
+    #[serde(rename = "remove")]
+    Remove {
+        #[serde(rename = "id")]
+        id: StrokeId,
+    },
+}
+
+impl CrdtOp {
+    pub fn id(&self) -> StrokeId {
+        match self {
+            Self::Add { id, .. } | Self::Update { id, .. } | Self::Remove { id } => *id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Register {
+    /// The stamp of the current state of the stroke
+    stamp: Stamp,
+    removed: bool,
+}
+
+/// The state of the CRDT. Only the stamps are stored, the strokes themselves are kept in the strokes state
+#[derive(Debug, Clone, Default)]
+pub struct StrokesCrdt {
+    registers: HashMap<StrokeId, Register>,
+    /// The largest counter seen so far
+    clock: u64,
+}
+
+impl StrokesCrdt {
+    /// A new stamp, larger than every stamp seen so far
+    pub fn next_stamp(&mut self, replica: ReplicaId) -> Stamp {
+        self.clock += 1;
+
+        Stamp {
+            counter: self.clock,
+            replica,
+        }
+    }
+
+    /// Applies the operation. Returns whether it changes the strokes, false when it is outdated or was already applied
+    pub fn apply(&mut self, op: &CrdtOp) -> bool {
+        match *op {
+            CrdtOp::Add { id, .. } => self.put(id, id),
+            CrdtOp::Update { id, stamp, .. } => self.put(id, stamp),
+            CrdtOp::Remove { id } => {
+                self.observe(id);
+
+                let register = self.registers.entry(id).or_insert(Register {
+                    stamp: id,
+                    // Removals which arrive before the addition are kept as well, so the late addition is ignored
+                    removed: true,
+                });
+                if register.removed {
+                    return false;
+                }
+                register.removed = true;
+
+                true
+            }
+        }
+    }
+
+    /// Whether the stroke is present, i.e. it was added and is not removed
+    pub fn contains(&self, id: StrokeId) -> bool {
+        self.registers
+            .get(&id)
+            .map_or(false, |register| !register.removed)
+    }
+
+    /// The stamp of the current state of the stroke
+    pub fn stamp(&self, id: StrokeId) -> Option<Stamp> {
+        self.registers.get(&id).map(|register| register.stamp)
+    }
+
+    /// The ids of the removed strokes
+    pub fn removed_ids(&self) -> Vec<StrokeId> {
+        self.registers
+            .iter()
+            .filter(|(_, register)| register.removed)
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    /// Puts the state with the stamp into the register of the stroke, if it is newer
+    fn put(&mut self, id: StrokeId, stamp: Stamp) -> bool {
+        self.observe(id);
+        self.observe(stamp);
+
+        match self.registers.get_mut(&id) {
+            Some(register) if register.removed || register.stamp >= stamp => false,
+            Some(register) => {
+                register.stamp = stamp;
+                true
+            }
+            None => {
+                self.registers.insert(
+                    id,
+                    Register {
+                        stamp,
+                        removed: false,
+                    },
+                );
+                true
+            }
+        }
+    }
+
+    fn observe(&mut self, stamp: Stamp) {
+        self.clock = self.clock.max(stamp.counter);
+    }
+}
@@ -0,0 +1,452 @@
+//! Real-time collaboration, so that several participants annotate the same sheet simultaneously.
+//! Every participant has a `CollabSession`, which shares the strokes of its strokes state through a CRDT, see `crdt`.
+//! The session turns the local changes of the strokes into operations, and applies the operations of the other participants.
+//! It only produces and consumes messages, see `protocol`. They are sent over WebSocket with the `collab` feature, see `websocket`.
+//!
+//! The strokes are shared, but not their layers, groups and selection. Removed strokes can't be restored by the other participants,
+//! undoing their removal shares them again as new strokes.
+
+pub mod crdt;
+pub mod protocol;
+#[cfg(feature = "collab")]
+pub mod websocket;
+
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "ui")]
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "ui")]
+use anyhow::Context;
+#[cfg(feature = "ui")]
+use gtk4::Snapshot;
+#[cfg(feature = "ui")]
+use p2d::bounding_volume::{BoundingVolume, AABB};
+
+use self::crdt::{CrdtOp, ReplicaId, StrokeId, StrokesCrdt};
+use self::protocol::{CollabMessage, PROTOCOL_VERSION};
+#[cfg(feature = "ui")]
+use crate::compose;
+use crate::compose::color::Color;
+#[cfg(feature = "ui")]
+use crate::render::{self, Renderer};
+use crate::strokes::strokestyle::StrokeStyle;
+use crate::strokesstate::{StrokeKey, StrokesState};
+use crate::surfaceflags::SurfaceFlags;
+
+/// The strokes which changed, tracked while a collaboration session is joined. See `StrokesState::take_collab_changes()`
+#[derive(Debug, Clone, Default)]
+pub struct ChangedKeys {
+    tracking: bool,
+    all: bool,
+    keys: HashSet<StrokeKey>,
+}
+
+impl ChangedKeys {
+    pub fn new(tracking: bool) -> Self {
+        Self {
+            tracking,
+            ..Self::default()
+        }
+    }
+
+    pub fn tracking(&self) -> bool {
+        self.tracking
+    }
+
+    pub fn mark(&mut self, key: StrokeKey) {
+        if self.tracking {
+            self.keys.insert(key);
+        }
+    }
+
+    /// Marks every stroke as changed, e.g. when the strokes were replaced
+    pub fn mark_all(&mut self) {
+        if self.tracking {
+            self.all = true;
+        }
+    }
+
+    pub fn all(&self) -> bool {
+        self.all
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = StrokeKey> + '_ {
+        self.keys.iter().copied()
+    }
+}
+
+/// The cursor of another participant
+#[derive(Debug, Clone)]
+pub struct RemoteCursor {
+    pub name: String,
+    pub color: Color,
+    /// In sheet coordinates. None while it is not on the sheet
+    pub pos: Option<na::Vector2<f64>>,
+}
+
+#[derive(Debug)]
+pub struct CollabSession {
+    replica: ReplicaId,
+    name: String,
+    color: Color,
+    crdt: StrokesCrdt,
+    keys: HashMap<StrokeId, StrokeKey>,
+    ids: HashMap<StrokeKey, StrokeId>,
+    remote_cursors: HashMap<ReplicaId, RemoteCursor>,
+    /// The messages which need to be sent, in order
+    outgoing: Vec<CollabMessage>,
+}
+
+impl CollabSession {
+    /// The radius of the cursors of the other participants, in surface coordinates so they look the same at every zoom
+    pub const CURSOR_RADIUS: f64 = 6.0;
+    pub const CURSOR_OUTLINE_WIDTH: f64 = 2.0;
+    pub const CURSOR_OUTLINE_COLOR: Color = Color::WHITE;
+
+    /// Joins a session with the name and color shown at the cursor. The current strokes are shared with the other participants
+    pub fn join(name: String, color: Color, strokes_state: &mut StrokesState) -> Self {
+        let mut session = Self {
+            replica: rand::random::<ReplicaId>(),
+            name,
+            color,
+            crdt: StrokesCrdt::default(),
+            keys: HashMap::new(),
+            ids: HashMap::new(),
+            remote_cursors: HashMap::new(),
+            outgoing: vec![],
+        };
+
+        session.outgoing.push(CollabMessage::Hello {
+            version: PROTOCOL_VERSION,
+            replica: session.replica,
+            name: session.name.clone(),
+            color: session.color,
+        });
+
+        strokes_state.set_track_collab_changes(true);
+        // The current strokes are shared as if they were just inserted
+        let ops = strokes_state
+            .keys_unordered()
+            .into_iter()
+            .filter_map(|key| session.share_stroke(key, strokes_state))
+            .collect::<Vec<CrdtOp>>();
+        session.push_ops(ops);
+
+        session
+    }
+
+    /// Leaves the session. The strokes stay as they are, but are no longer shared
+    pub fn leave(&mut self, strokes_state: &mut StrokesState) {
+        self.sync_local_changes(strokes_state);
+        strokes_state.set_track_collab_changes(false);
+
+        self.outgoing.push(CollabMessage::Bye {
+            replica: self.replica,
+        });
+    }
+
+    pub fn replica(&self) -> ReplicaId {
+        self.replica
+    }
+
+    /// The cursors of the other participants
+    pub fn remote_cursors(&self) -> &HashMap<ReplicaId, RemoteCursor> {
+        &self.remote_cursors
+    }
+
+    /// Takes the messages which need to be sent to the other participants, in order
+    pub fn take_outgoing(&mut self) -> Vec<CollabMessage> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    /// Turns the changes of the strokes since the last call into operations for the other participants.
+    /// To be called after changes were made, e.g. when the pen was lifted
+    pub fn sync_local_changes(&mut self, strokes_state: &mut StrokesState) {
+        let changes = strokes_state.take_collab_changes();
+        let mut ops = vec![];
+
+        if changes.all() {
+            // The strokes were replaced, e.g. by opening a document. The new keys are unrelated to the previous ones
+            for (_, id) in self.ids.drain() {
+                self.keys.remove(&id);
+                ops.push(CrdtOp::Remove { id });
+            }
+            ops.extend(
+                strokes_state
+                    .keys_unordered()
+                    .into_iter()
+                    .filter_map(|key| self.share_stroke(key, strokes_state)),
+            );
+        } else {
+            for key in changes.keys() {
+                match (
+                    self.ids.get(&key).copied(),
+                    shared_stroke(strokes_state, key),
+                ) {
+                    (Some(id), Some(stroke)) => ops.push(CrdtOp::Update {
+                        id,
+                        stamp: self.crdt.next_stamp(self.replica),
+                        stroke: stroke.clone(),
+                    }),
+                    (Some(id), None) => {
+                        self.ids.remove(&key);
+                        self.keys.remove(&id);
+                        ops.push(CrdtOp::Remove { id });
+                    }
+                    (None, Some(_)) => ops.extend(self.share_stroke(key, strokes_state)),
+                    (None, None) => {}
+                }
+            }
+        }
+
+        self.push_ops(ops);
+    }
+
+    /// The local cursor moved, in sheet coordinates. None when it left the sheet. Only the latest position is sent
+    pub fn move_cursor(&mut self, pos: Option<na::Vector2<f64>>) {
+        if let Some(CollabMessage::Cursor { .. }) = self.outgoing.last() {
+            self.outgoing.pop();
+        }
+
+        self.outgoing.push(CollabMessage::Cursor {
+            replica: self.replica,
+            pos,
+        });
+    }
+
+    /// Handles a message of another participant. The changes of its operations are not recorded into the undo history
+    pub fn receive(
+        &mut self,
+        message: CollabMessage,
+        strokes_state: &mut StrokesState,
+    ) -> SurfaceFlags {
+        let mut surface_flags = SurfaceFlags::default();
+        if message.replica() == self.replica {
+            return surface_flags;
+        }
+
+        match message {
+            CollabMessage::Hello {
+                version,
+                replica,
+                name,
+                color,
+            } => {
+                if version != PROTOCOL_VERSION {
+                    log::warn!(
+                        "the participant `{}` of the collaboration session uses protocol version {}, but version {} is supported. It is ignored",
+                        name,
+                        version,
+                        PROTOCOL_VERSION
+                    );
+                    return surface_flags;
+                }
+                self.remote_cursors.insert(
+                    replica,
+                    RemoteCursor {
+                        name,
+                        color,
+                        pos: None,
+                    },
+                );
+
+                self.outgoing.push(CollabMessage::Welcome {
+                    replica: self.replica,
+                    name: self.name.clone(),
+                    color: self.color,
+                });
+                // The participant which joined needs the current state
+                self.sync_local_changes(strokes_state);
+                let ops = self.state_ops(strokes_state);
+                self.push_ops(ops);
+            }
+            CollabMessage::Welcome {
+                replica,
+                name,
+                color,
+            } => {
+                self.remote_cursors.entry(replica).or_insert(RemoteCursor {
+                    name,
+                    color,
+                    pos: None,
+                });
+            }
+            CollabMessage::Ops { ops, .. } => {
+                // The local changes are synced first, so they aren't mixed up with the changes of the operations
+                self.sync_local_changes(strokes_state);
+
+                let mut changed = false;
+                strokes_state.without_history(|strokes_state| {
+                    for op in ops {
+                        changed |= self.apply_remote(op, strokes_state);
+                    }
+                });
+                // The changes of the operations don't need to be sent back
+                strokes_state.take_collab_changes();
+
+                if changed {
+                    surface_flags.redraw = true;
+                    surface_flags.resize = true;
+                    surface_flags.sheet_changed = true;
+                }
+            }
+            CollabMessage::Cursor { replica, pos } => {
+                if let Some(cursor) = self.remote_cursors.get_mut(&replica) {
+                    cursor.pos = pos;
+                    surface_flags.redraw = true;
+                }
+            }
+            CollabMessage::Bye { replica } => {
+                if self.remote_cursors.remove(&replica).is_some() {
+                    surface_flags.redraw = true;
+                }
+            }
+        }
+
+        surface_flags
+    }
+
+    /// Draws the cursors of the other participants which are on the sheet
+    #[cfg(feature = "ui")]
+    pub fn draw_remote_cursors(
+        &self,
+        zoom: f64,
+        snapshot: &Snapshot,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<(), anyhow::Error> {
+        let radius = Self::CURSOR_RADIUS / zoom;
+        let outline_width = Self::CURSOR_OUTLINE_WIDTH / zoom;
+
+        let mut bounds = AABB::new_invalid();
+        let mut group = svg::node::element::Group::new();
+        let mut drawn = false;
+
+        for cursor in self.remote_cursors.values() {
+            let pos = match cursor.pos {
+                Some(pos) => pos,
+                None => continue,
+            };
+
+            group = group.add(
+                svg::node::element::Circle::new()
+                    .set("cx", pos[0])
+                    .set("cy", pos[1])
+                    .set("r", radius)
+                    .set("stroke", Self::CURSOR_OUTLINE_COLOR.to_css_color())
+                    .set("stroke-width", outline_width)
+                    .set("fill", cursor.color.to_css_color()),
+            );
+            bounds.take_point(na::Point2::from(pos));
+            drawn = true;
+        }
+
+        if !drawn {
+            return Ok(());
+        }
+        let bounds = bounds.loosened(radius + outline_width);
+
+        let svg_data = compose::svg_node_to_string(&group)?;
+        let svg = render::Svg { svg_data, bounds };
+
+        let images = renderer
+            .read()
+            .unwrap()
+            .gen_images(zoom, vec![svg], bounds)?;
+        if let Some(rendernode) = render::images_to_rendernode(&images, zoom)
+            .context("images_to_rendernode() failed in collabsession .draw_remote_cursors()")?
+        {
+            snapshot.append_node(&rendernode);
+        }
+
+        Ok(())
+    }
+
+    /// Shares the stroke as a new one. None if it is not shared, e.g. because it is trashed
+    fn share_stroke(&mut self, key: StrokeKey, strokes_state: &StrokesState) -> Option<CrdtOp> {
+        let stroke = shared_stroke(strokes_state, key)?.clone();
+        let id = self.crdt.next_stamp(self.replica);
+        self.keys.insert(id, key);
+        self.ids.insert(key, id);
+
+        Some(CrdtOp::Add { id, stroke })
+    }
+
+    /// The operations which recreate the current state of the strokes
+    fn state_ops(&self, strokes_state: &StrokesState) -> Vec<CrdtOp> {
+        let mut ops = self
+            .crdt
+            .removed_ids()
+            .into_iter()
+            .map(|id| CrdtOp::Remove { id })
+            .collect::<Vec<CrdtOp>>();
+
+        for (&key, &id) in self.ids.iter() {
+            if let (Some(stroke), Some(stamp)) =
+                (shared_stroke(strokes_state, key), self.crdt.stamp(id))
+            {
+                ops.push(CrdtOp::Update {
+                    id,
+                    stamp,
+                    stroke: stroke.clone(),
+                });
+            }
+        }
+
+        ops
+    }
+
+    /// Applies the local operations to the CRDT and queues them to be sent
+    fn push_ops(&mut self, ops: Vec<CrdtOp>) {
+        if ops.is_empty() {
+            return;
+        }
+        for op in ops.iter() {
+            self.crdt.apply(op);
+        }
+
+        self.outgoing.push(CollabMessage::Ops {
+            replica: self.replica,
+            ops,
+        });
+    }
+
+    /// Applies the operation of another participant. Returns whether it changed the strokes
+    fn apply_remote(&mut self, op: CrdtOp, strokes_state: &mut StrokesState) -> bool {
+        if !self.crdt.apply(&op) {
+            return false;
+        }
+
+        match op {
+            CrdtOp::Add { id, stroke } | CrdtOp::Update { id, stroke, .. } => {
+                let key = match self.keys.get(&id) {
+                    Some(&key) => {
+                        strokes_state.replace_stroke(key, stroke);
+                        key
+                    }
+                    None => {
+                        let key = strokes_state.insert_stroke(stroke);
+                        self.keys.insert(id, key);
+                        self.ids.insert(key, id);
+                        key
+                    }
+                };
+                strokes_state.update_geometry_for_stroke(key);
+            }
+            CrdtOp::Remove { id } => {
+                if let Some(key) = self.keys.remove(&id) {
+                    self.ids.remove(&key);
+                    strokes_state.remove_stroke(key);
+                }
+            }
+        }
+
+        true
+    }
+}
+
+/// The stroke if it is shared with the other participants, which trashed strokes are not
+fn shared_stroke(strokes_state: &StrokesState, key: StrokeKey) -> Option<&StrokeStyle> {
+    strokes_state
+        .get_stroke_ref(key)
+        .filter(|_| strokes_state.trashed(key) != Some(true))
+}
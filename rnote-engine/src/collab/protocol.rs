@@ -0,0 +1,83 @@
+//! The messages the participants of a collaboration session exchange. They are sent as JSON text,
+//! through a relay which forwards the messages of every participant to all others, see `websocket`.
+//!
+//! A participant which joins announces itself with `Hello`, and every other participant answers with `Welcome` and the state of the strokes as operations.
+//! Afterwards the participants only send the operations of their own changes, and the positions of their cursors.
+
+use serde::{Deserialize, Serialize};
+
+use super::crdt::{CrdtOp, ReplicaId};
+use crate::compose::color::Color;
+
+/// The version of the protocol. Participants with another version are ignored
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "collab_message")]
+pub enum CollabMessage {
+    /// A participant joined the session
+    #[serde(rename = "hello")]
+    Hello {
+        #[serde(rename = "version")]
+        version: u32,
+        #[serde(rename = "replica")]
+        replica: ReplicaId,
+        /// The name and color shown at the cursor of the participant
+        #[serde(rename = "name")]
+        name: String,
+        #[serde(rename = "color")]
+        color: Color,
+    },
+    /// Answers `Hello`, so the participant which joined knows the participants which are already present
+    #[serde(rename = "welcome")]
+    Welcome {
+        #[serde(rename = "replica")]
+        replica: ReplicaId,
+        #[serde(rename = "name")]
+        name: String,
+        #[serde(rename = "color")]
+        color: Color,
+    },
+    #[serde(rename = "ops")]
+    Ops {
+        #[serde(rename = "replica")]
+        replica: ReplicaId,
+        #[serde(rename = "ops")]
+        ops: Vec<CrdtOp>,
+    },
+    /// The cursor of the participant moved, in sheet coordinates. None when it left the sheet
+    #[serde(rename = "cursor")]
+    Cursor {
+        #[serde(rename = "replica")]
+        replica: ReplicaId,
+        #[serde(rename = "pos")]
+        pos: Option<na::Vector2<f64>>,
+    },
+    /// A participant left the session. Sent by the participant, or by the relay when the connection was lost
+    #[serde(rename = "bye")]
+    Bye {
+        #[serde(rename = "replica")]
+        replica: ReplicaId,
+    },
+}
+
+impl CollabMessage {
+    /// The participant which sent the message
+    pub fn replica(&self) -> ReplicaId {
+        match self {
+            Self::Hello { replica, .. }
+            | Self::Welcome { replica, .. }
+            | Self::Ops { replica, .. }
+            | Self::Cursor { replica, .. }
+            | Self::Bye { replica } => *replica,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, anyhow::Error> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, anyhow::Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
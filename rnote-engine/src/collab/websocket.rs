@@ -0,0 +1,236 @@
+//! The transport of the collaboration sessions over WebSocket. Every participant connects to a relay with `CollabConnection::connect()`,
+//! which forwards the messages of every participant to all others. The relay is run with `run_relay()`, e.g. by the participant which hosts the session.
+//! Only unencrypted connections are supported, so sessions outside of a local network need to be tunneled.
+
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::channel::mpsc;
+use tungstenite::client::IntoClientRequest;
+use tungstenite::{Message, WebSocket};
+
+use super::crdt::ReplicaId;
+use super::protocol::CollabMessage;
+
+/// How long reading from a socket blocks before the pending messages are written to it
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// The connection of a participant to the relay. The connection is closed when it is dropped
+#[derive(Debug)]
+pub struct CollabConnection {
+    outgoing_tx: mpsc::UnboundedSender<CollabMessage>,
+    /// To be taken out into a loop which processes the receiver stream. The received messages should be handled with `CollabSession::receive()`.
+    /// The stream ends when the connection was closed
+    pub incoming_rx: Option<mpsc::UnboundedReceiver<CollabMessage>>,
+}
+
+impl CollabConnection {
+    /// Connects to the relay at the url, e.g. `ws://192.168.1.10:9300`
+    pub fn connect(url: &str) -> Result<Self, anyhow::Error> {
+        let request = url.into_client_request()?;
+        let host = request
+            .uri()
+            .host()
+            .ok_or_else(|| anyhow::anyhow!("connect() failed, the url `{}` has no host", url))?
+            .to_string();
+        let port = request.uri().port_u16().unwrap_or(80);
+
+        let stream = TcpStream::connect((host.as_str(), port))?;
+        let (socket, _response) = tungstenite::client(request, stream).map_err(|e| {
+            anyhow::anyhow!(
+                "connect() failed, the handshake with `{}` failed with Err {}",
+                url,
+                e
+            )
+        })?;
+        socket.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+
+        let (outgoing_tx, outgoing_rx) = mpsc::unbounded::<CollabMessage>();
+        let (incoming_tx, incoming_rx) = mpsc::unbounded::<CollabMessage>();
+
+        std::thread::spawn(move || {
+            if let Err(e) = run_connection(socket, outgoing_rx, incoming_tx) {
+                log::error!("the collaboration connection failed with Err {}", e);
+            }
+        });
+
+        Ok(Self {
+            outgoing_tx,
+            incoming_rx: Some(incoming_rx),
+        })
+    }
+
+    /// Sends the message to the other participants. Fails when the connection was closed
+    pub fn send(&self, message: CollabMessage) -> Result<(), anyhow::Error> {
+        self.outgoing_tx.unbounded_send(message).map_err(|e| {
+            anyhow::anyhow!(
+                "send() failed, the collaboration connection is closed. Err {}",
+                e
+            )
+        })
+    }
+}
+
+fn run_connection(
+    mut socket: WebSocket<TcpStream>,
+    mut outgoing_rx: mpsc::UnboundedReceiver<CollabMessage>,
+    incoming_tx: mpsc::UnboundedSender<CollabMessage>,
+) -> Result<(), anyhow::Error> {
+    loop {
+        match read_text(&mut socket)? {
+            Read::Text(text) => match CollabMessage::from_json(&text) {
+                Ok(message) => {
+                    if incoming_tx.unbounded_send(message).is_err() {
+                        // Nobody receives the messages anymore
+                        break;
+                    }
+                }
+                Err(e) => log::warn!(
+                    "received an invalid message in the collaboration connection, Err {}",
+                    e
+                ),
+            },
+            Read::Pending => {}
+            Read::Closed => return Ok(()),
+        }
+
+        loop {
+            match outgoing_rx.try_next() {
+                Ok(Some(message)) => socket.write_message(Message::Text(message.to_json()?))?,
+                // The connection was dropped
+                Ok(None) => {
+                    socket.close(None)?;
+                    socket.write_pending().ok();
+                    return Ok(());
+                }
+                // No pending messages
+                Err(_) => break,
+            }
+        }
+    }
+
+    socket.close(None)?;
+    Ok(())
+}
+
+/// Runs the relay with the listener, e.g. bound to `0.0.0.0:9300`. Blocks until accepting a connection fails.
+/// When a participant disconnects without leaving the session, the relay announces it to the others
+pub fn run_relay(listener: TcpListener) -> Result<(), anyhow::Error> {
+    let peers = Arc::new(Mutex::new(Vec::<Peer>::new()));
+    let mut id_counter: u64 = 0;
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        id_counter += 1;
+        let id = id_counter;
+        let peers = Arc::clone(&peers);
+
+        std::thread::spawn(move || {
+            if let Err(e) = serve_peer(id, stream, &peers) {
+                log::warn!(
+                    "the connection of the relay to a participant failed with Err {}",
+                    e
+                );
+            }
+            remove_peer(id, &peers);
+        });
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+struct Peer {
+    id: u64,
+    tx: mpsc::UnboundedSender<String>,
+    /// The participant of the connection, known once it said hello
+    replica: Option<ReplicaId>,
+}
+
+fn serve_peer(id: u64, stream: TcpStream, peers: &Mutex<Vec<Peer>>) -> Result<(), anyhow::Error> {
+    // Registered before the handshake, so the messages of others are not lost once the participant is connected
+    let (tx, mut rx) = mpsc::unbounded::<String>();
+    peers.lock().unwrap().push(Peer {
+        id,
+        tx,
+        replica: None,
+    });
+
+    let mut socket = tungstenite::accept(stream)
+        .map_err(|e| anyhow::anyhow!("serve_peer() failed, the handshake failed with Err {}", e))?;
+    socket.get_ref().set_read_timeout(Some(POLL_INTERVAL))?;
+
+    loop {
+        match read_text(&mut socket)? {
+            Read::Text(text) => {
+                let mut peers = peers.lock().unwrap();
+                if let Ok(CollabMessage::Hello { replica, .. }) = CollabMessage::from_json(&text) {
+                    if let Some(peer) = peers.iter_mut().find(|peer| peer.id == id) {
+                        peer.replica = Some(replica);
+                    }
+                }
+                broadcast(id, &text, &peers);
+            }
+            Read::Pending => {}
+            Read::Closed => return Ok(()),
+        }
+
+        while let Ok(Some(text)) = rx.try_next() {
+            socket.write_message(Message::Text(text))?;
+        }
+    }
+}
+
+/// Forwards the text from the peer to all others
+fn broadcast(from: u64, text: &str, peers: &[Peer]) {
+    for peer in peers.iter().filter(|peer| peer.id != from) {
+        // Peers which disconnected are removed by their own thread
+        peer.tx.unbounded_send(text.to_string()).ok();
+    }
+}
+
+fn remove_peer(id: u64, peers: &Mutex<Vec<Peer>>) {
+    let mut peers = peers.lock().unwrap();
+    let replica = peers
+        .iter()
+        .position(|peer| peer.id == id)
+        .and_then(|index| peers.remove(index).replica);
+
+    if let Some(replica) = replica {
+        match (CollabMessage::Bye { replica }).to_json() {
+            Ok(text) => broadcast(id, &text, &peers),
+            Err(e) => log::error!(
+                "serializing the bye message failed in remove_peer() with Err {}",
+                e
+            ),
+        }
+    }
+}
+
+enum Read {
+    Text(String),
+    /// Nothing was received within the poll interval
+    Pending,
+    Closed,
+}
+
+fn read_text(socket: &mut WebSocket<TcpStream>) -> Result<Read, anyhow::Error> {
+    match socket.read_message() {
+        Ok(Message::Text(text)) => Ok(Read::Text(text)),
+        Ok(Message::Close(_)) => Ok(Read::Closed),
+        Ok(_) => Ok(Read::Pending),
+        Err(tungstenite::Error::Io(e))
+            if matches!(
+                e.kind(),
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+            ) =>
+        {
+            Ok(Read::Pending)
+        }
+        Err(tungstenite::Error::ConnectionClosed) | Err(tungstenite::Error::AlreadyClosed) => {
+            Ok(Read::Closed)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
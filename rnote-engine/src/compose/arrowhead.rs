@@ -0,0 +1,331 @@
+use super::color::Color;
+use super::curves;
+use super::geometry::Vector2Helpers;
+use super::shapes;
+
+use serde::{Deserialize, Serialize};
+use svg::node::element::{self, path, Element};
+
+/// The style of an arrowhead at the end of a line or curve
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
+#[repr(u32)]
+#[cfg_attr(feature = "ui", enum_type(name = "ArrowheadStyle"))]
+#[serde(rename = "arrowhead_style")]
+pub enum ArrowheadStyle {
+    #[cfg_attr(feature = "ui", enum_value(name = "None", nick = "none"))]
+    #[serde(rename = "none")]
+    None = 0,
+    /// Two strokes opening from the tip
+    #[cfg_attr(feature = "ui", enum_value(name = "Open", nick = "open"))]
+    #[serde(rename = "open")]
+    Open,
+    /// A filled triangle
+    #[cfg_attr(feature = "ui", enum_value(name = "Filled", nick = "filled"))]
+    #[serde(rename = "filled")]
+    Filled,
+    /// A filled circle around the end
+    #[cfg_attr(feature = "ui", enum_value(name = "Circle", nick = "circle"))]
+    #[serde(rename = "circle")]
+    Circle,
+    /// A bar across the end
+    #[cfg_attr(feature = "ui", enum_value(name = "Bar", nick = "bar"))]
+    #[serde(rename = "bar")]
+    Bar,
+}
+
+impl Default for ArrowheadStyle {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl ArrowheadStyle {
+    /// The distance the line should end before the tip, so that it does not poke out of the arrowhead
+    pub fn line_inset(&self, size: f64) -> f64 {
+        match self {
+            Self::Filled => size * 0.5,
+            _ => 0.0,
+        }
+    }
+}
+
+/// The Options of the arrowheads at the start and end of lines and curves
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "arrowhead_options")]
+pub struct ArrowheadOptions {
+    /// The arrowhead at the start
+    #[serde(rename = "start")]
+    pub start: ArrowheadStyle,
+    /// The arrowhead at the end
+    #[serde(rename = "end")]
+    pub end: ArrowheadStyle,
+    /// The size of the arrowheads, relative to the stroke width
+    #[serde(rename = "size_factor")]
+    pub size_factor: f64,
+}
+
+impl Default for ArrowheadOptions {
+    fn default() -> Self {
+        Self {
+            start: ArrowheadStyle::default(),
+            end: ArrowheadStyle::default(),
+            size_factor: Self::SIZE_FACTOR_DEFAULT,
+        }
+    }
+}
+
+impl ArrowheadOptions {
+    /// Size factor default
+    pub const SIZE_FACTOR_DEFAULT: f64 = 4.0;
+    /// The min size of the arrowheads, so they are visible for thin strokes
+    pub const SIZE_MIN: f64 = 4.0;
+
+    /// The size of the arrowheads for the stroke width
+    pub fn size(&self, stroke_width: f64) -> f64 {
+        (self.size_factor * stroke_width).max(Self::SIZE_MIN)
+    }
+
+    /// If there are any arrowheads
+    pub fn has_arrowheads(&self) -> bool {
+        self.start != ArrowheadStyle::None || self.end != ArrowheadStyle::None
+    }
+
+    /// The line shortened at the ends so it does not poke out of the arrowheads
+    pub fn inset_line(&self, line: curves::Line, stroke_width: f64) -> curves::Line {
+        let size = self.size(stroke_width);
+        let direction = line.end - line.start;
+        let length = direction.magnitude();
+        if length <= 0.0 {
+            return line;
+        }
+        let direction = direction / length;
+
+        let start_inset = self.start.line_inset(size);
+        let end_inset = self.end.line_inset(size);
+        // Don't let the insets flip the line
+        let scale = if start_inset + end_inset > length {
+            length / (start_inset + end_inset)
+        } else {
+            1.0
+        };
+
+        curves::Line {
+            start: line.start + direction * start_inset * scale,
+            end: line.end - direction * end_inset * scale,
+        }
+    }
+
+    /// The arc shortened at the ends so it does not poke out of the arrowheads
+    pub fn inset_arc(&self, arc: shapes::Arc, stroke_width: f64) -> shapes::Arc {
+        let size = self.size(stroke_width);
+        // The radius in global coordinates, with the mean scale of the transform
+        let radius = arc.radius
+            * arc
+                .transform
+                .transform
+                .matrix()
+                .fixed_slice::<2, 2>(0, 0)
+                .determinant()
+                .abs()
+                .sqrt();
+        let sweep = arc.sweep_angle.abs();
+        if radius <= 0.0 || sweep <= 0.0 {
+            return arc;
+        }
+
+        let start_inset = self.start.line_inset(size) / radius;
+        let end_inset = self.end.line_inset(size) / radius;
+        // Don't let the insets flip the arc
+        let scale = if start_inset + end_inset > sweep {
+            sweep / (start_inset + end_inset)
+        } else {
+            1.0
+        };
+
+        shapes::Arc {
+            start_angle: arc.start_angle + arc.sweep_angle.signum() * start_inset * scale,
+            sweep_angle: arc.sweep_angle.signum() * (sweep - (start_inset + end_inset) * scale),
+            ..arc
+        }
+    }
+
+    /// The polyline with its first and last segment shortened so it does not poke out of the arrowheads
+    pub fn inset_polyline(
+        &self,
+        polyline: &shapes::Polyline,
+        stroke_width: f64,
+    ) -> shapes::Polyline {
+        let mut polyline = polyline.clone();
+        let n_vertices = polyline.vertices.len();
+        if polyline.closed || n_vertices < 2 {
+            return polyline;
+        }
+
+        if n_vertices == 2 {
+            let line = self.inset_line(
+                curves::Line {
+                    start: polyline.vertices[0],
+                    end: polyline.vertices[1],
+                },
+                stroke_width,
+            );
+            polyline.vertices = vec![line.start, line.end];
+        } else {
+            // Only one end of the first and last segment is inset, so these are inset as lines with no arrowhead on the other end
+            let start_options = Self {
+                end: ArrowheadStyle::None,
+                ..*self
+            };
+            let end_options = Self {
+                start: ArrowheadStyle::None,
+                ..*self
+            };
+
+            polyline.vertices[0] = start_options
+                .inset_line(
+                    curves::Line {
+                        start: polyline.vertices[0],
+                        end: polyline.vertices[1],
+                    },
+                    stroke_width,
+                )
+                .start;
+            polyline.vertices[n_vertices - 1] = end_options
+                .inset_line(
+                    curves::Line {
+                        start: polyline.vertices[n_vertices - 2],
+                        end: polyline.vertices[n_vertices - 1],
+                    },
+                    stroke_width,
+                )
+                .end;
+        }
+
+        polyline
+    }
+}
+
+/// Composes the arrowheads for both ends of a line
+pub fn compose_line_arrowheads(
+    line: curves::Line,
+    options: &ArrowheadOptions,
+    stroke_width: f64,
+    stroke_color: Option<Color>,
+) -> Vec<Element> {
+    let direction = line.end - line.start;
+
+    compose_path_arrowheads(
+        (line.start, direction),
+        (line.end, direction),
+        options,
+        stroke_width,
+        stroke_color,
+    )
+}
+
+/// Composes the arrowheads for both ends of a path, given the start and end with the tangents in the direction of the path there
+pub fn compose_path_arrowheads(
+    start: (na::Vector2<f64>, na::Vector2<f64>),
+    end: (na::Vector2<f64>, na::Vector2<f64>),
+    options: &ArrowheadOptions,
+    stroke_width: f64,
+    stroke_color: Option<Color>,
+) -> Vec<Element> {
+    [
+        compose_arrowhead(
+            start.0,
+            -start.1,
+            options.start,
+            options.size(stroke_width),
+            stroke_width,
+            stroke_color,
+        ),
+        compose_arrowhead(
+            end.0,
+            end.1,
+            options.end,
+            options.size(stroke_width),
+            stroke_width,
+            stroke_color,
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Composes an arrowhead with the tip at the given position, pointing in the tangent direction of the end.
+/// Returns None for no arrowhead style or if the direction is undefined
+pub fn compose_arrowhead(
+    tip: na::Vector2<f64>,
+    tangent: na::Vector2<f64>,
+    style: ArrowheadStyle,
+    size: f64,
+    stroke_width: f64,
+    stroke_color: Option<Color>,
+) -> Option<Element> {
+    if style == ArrowheadStyle::None || tangent.magnitude() <= 0.0 {
+        return None;
+    }
+
+    let direction = tangent.unit_tang();
+    let normal = direction.unit_norm();
+    let color = stroke_color.map_or(String::from("none"), |color| color.to_css_color());
+
+    let back_left = tip - direction * size + normal * size * 0.5;
+    let back_right = tip - direction * size - normal * size * 0.5;
+
+    let element = match style {
+        ArrowheadStyle::None => return None,
+        ArrowheadStyle::Open => element::Path::new()
+            .set("stroke", color)
+            .set("stroke-width", stroke_width)
+            .set("stroke-linejoin", "round")
+            .set("stroke-linecap", "round")
+            .set("fill", "none")
+            .set(
+                "d",
+                path::Data::new()
+                    .move_to((back_left[0], back_left[1]))
+                    .line_to((tip[0], tip[1]))
+                    .line_to((back_right[0], back_right[1])),
+            )
+            .into(),
+        ArrowheadStyle::Filled => element::Path::new()
+            .set("stroke", "none")
+            .set("fill", color)
+            .set(
+                "d",
+                path::Data::new()
+                    .move_to((tip[0], tip[1]))
+                    .line_to((back_left[0], back_left[1]))
+                    .line_to((back_right[0], back_right[1]))
+                    .close(),
+            )
+            .into(),
+        ArrowheadStyle::Circle => element::Circle::new()
+            .set("cx", tip[0])
+            .set("cy", tip[1])
+            .set("r", size * 0.5)
+            .set("stroke", "none")
+            .set("fill", color)
+            .into(),
+        ArrowheadStyle::Bar => {
+            let bar_start = tip + normal * size * 0.5;
+            let bar_end = tip - normal * size * 0.5;
+
+            element::Line::new()
+                .set("x1", bar_start[0])
+                .set("y1", bar_start[1])
+                .set("x2", bar_end[0])
+                .set("y2", bar_end[1])
+                .set("stroke", color)
+                .set("stroke-width", stroke_width)
+                .set("stroke-linecap", "butt")
+                .into()
+        }
+    };
+
+    Some(element)
+}
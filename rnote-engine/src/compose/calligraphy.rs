@@ -0,0 +1,267 @@
+use super::color::Color;
+use super::curves;
+use super::geometry::Vector2Helpers;
+
+use serde::{Deserialize, Serialize};
+use svg::node::element::{self, path, Element};
+
+/// The Options of how a calligraphy stroke should look
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "calligraphy_options")]
+pub struct CalligraphyOptions {
+    /// An optional seed to generate reproducable strokes
+    #[serde(rename = "seed")]
+    pub seed: Option<u64>,
+    /// The width of the nib
+    #[serde(rename = "width")]
+    pub width: f64,
+    /// The color of the stroke
+    #[serde(rename = "stroke_color")]
+    pub stroke_color: Option<Color>,
+    /// The angle of the nib, in radians
+    #[serde(rename = "nib_angle")]
+    pub nib_angle: f64,
+    /// The min width of the stroke relative to the nib width, when the stroke direction is parallel to the nib
+    #[serde(rename = "thin_factor")]
+    pub thin_factor: f64,
+}
+
+impl Default for CalligraphyOptions {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            width: Self::WIDTH_DEFAULT,
+            stroke_color: Some(Self::COLOR_DEFAULT),
+            nib_angle: Self::NIB_ANGLE_DEFAULT,
+            thin_factor: Self::THIN_FACTOR_DEFAULT,
+        }
+    }
+}
+
+impl CalligraphyOptions {
+    /// The default width
+    pub const WIDTH_DEFAULT: f64 = 8.0;
+    /// The default color
+    pub const COLOR_DEFAULT: Color = Color {
+        r: 0.0,
+        g: 0.0,
+        b: 0.0,
+        a: 1.0,
+    };
+    /// Nib angle default
+    pub const NIB_ANGLE_DEFAULT: f64 = std::f64::consts::FRAC_PI_4;
+    /// Thin factor default
+    pub const THIN_FACTOR_DEFAULT: f64 = 0.15;
+    /// The number of splits when approximating a bezier curve for its outline
+    pub const CUBBEZ_N_SPLITS: i32 = 8;
+
+    /// The stroke width for the given stroke direction. It is the width of the nib projected onto the normal of the direction
+    pub fn width_for_direction(&self, direction: na::Vector2<f64>) -> f64 {
+        let direction = if direction.magnitude() > 0.0 {
+            direction.normalize()
+        } else {
+            return self.width;
+        };
+        let nib = na::vector![self.nib_angle.cos(), self.nib_angle.sin()];
+
+        let projected = (direction[0] * nib[1] - direction[1] * nib[0]).abs();
+
+        self.width * projected.max(self.thin_factor.clamp(0.0, 1.0))
+    }
+}
+
+/// Composes a calligraphy line
+pub fn compose_line(line: curves::Line, options: &CalligraphyOptions) -> Element {
+    let direction = line.end - line.start;
+
+    compose_outline(&[(line.start, direction), (line.end, direction)], options)
+}
+
+/// Composes a calligraphy cubic bezier curve
+pub fn compose_cubbez(cubbez: curves::CubicBezier, options: &CalligraphyOptions) -> Element {
+    let chord = cubbez.end - cubbez.start;
+
+    let samples = curves::sample_cubbez_w_tangents(cubbez, CalligraphyOptions::CUBBEZ_N_SPLITS)
+        .into_iter()
+        .map(|(pos, tangent)| {
+            // The tangent vanishes when control points coincide with the ends, falling back to the chord
+            if tangent.magnitude() > 0.0 {
+                (pos, tangent)
+            } else {
+                (pos, chord)
+            }
+        })
+        .collect::<Vec<(na::Vector2<f64>, na::Vector2<f64>)>>();
+
+    compose_outline(&samples, options)
+}
+
+/// Composes the filled outline polygon around the samples of the path, each given as position and direction
+fn compose_outline(
+    samples: &[(na::Vector2<f64>, na::Vector2<f64>)],
+    options: &CalligraphyOptions,
+) -> Element {
+    let offsets = samples
+        .iter()
+        .map(|&(pos, direction)| {
+            let offset = direction.unit_norm() * options.width_for_direction(direction) * 0.5;
+
+            (pos + offset, pos - offset)
+        })
+        .collect::<Vec<(na::Vector2<f64>, na::Vector2<f64>)>>();
+
+    let mut commands = Vec::with_capacity(offsets.len() * 2 + 1);
+
+    // One side forward, the other side backwards
+    for (i, &(left, _)) in offsets.iter().enumerate() {
+        if i == 0 {
+            commands.push(path::Command::Move(
+                path::Position::Absolute,
+                path::Parameters::from((left[0], left[1])),
+            ));
+        } else {
+            commands.push(path::Command::Line(
+                path::Position::Absolute,
+                path::Parameters::from((left[0], left[1])),
+            ));
+        }
+    }
+    for &(_, right) in offsets.iter().rev() {
+        commands.push(path::Command::Line(
+            path::Position::Absolute,
+            path::Parameters::from((right[0], right[1])),
+        ));
+    }
+    commands.push(path::Command::Close);
+
+    let fill = options
+        .stroke_color
+        .map_or(String::from("none"), |color| color.to_css_color());
+
+    element::Path::new()
+        .set("stroke", "none")
+        .set("fill", fill)
+        .set("d", path::Data::from(commands))
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The points of the outline of the composed path
+    fn outline_points(element: Element) -> Vec<na::Vector2<f64>> {
+        let svg = element.to_string();
+        let start = svg.find(" d=\"").unwrap() + 4;
+        let len = svg[start..].find('"').unwrap();
+
+        let numbers = svg[start..start + len]
+            .split(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+            .filter(|number| !number.is_empty())
+            .map(|number| number.parse::<f64>().unwrap())
+            .collect::<Vec<f64>>();
+        numbers
+            .chunks_exact(2)
+            .map(|pos| na::vector![pos[0], pos[1]])
+            .collect()
+    }
+
+    fn assert_near(first: f64, second: f64) {
+        assert!((first - second).abs() < 1e-9, "{} != {}", first, second);
+    }
+
+    #[test]
+    fn width_depends_on_the_direction_relative_to_the_nib() {
+        let options = CalligraphyOptions {
+            nib_angle: 0.0,
+            ..CalligraphyOptions::default()
+        };
+        let width = options.width;
+
+        // Across the nib the stroke is as wide as the nib, along it as thin as the thin factor allows
+        assert_near(options.width_for_direction(na::vector![0.0, 3.0]), width);
+        assert_near(
+            options.width_for_direction(na::vector![-2.0, 0.0]),
+            width * options.thin_factor,
+        );
+        assert_near(
+            options.width_for_direction(na::vector![1.0, 1.0]),
+            width * std::f64::consts::FRAC_1_SQRT_2,
+        );
+        // Without a direction the width is the nib width
+        assert_near(options.width_for_direction(na::Vector2::zeros()), width);
+
+        let rotated = CalligraphyOptions {
+            nib_angle: std::f64::consts::FRAC_PI_2,
+            ..options
+        };
+        assert_near(rotated.width_for_direction(na::vector![1.0, 0.0]), width);
+    }
+
+    #[test]
+    fn line_outline_is_offset_by_the_half_width() {
+        let line = curves::Line {
+            start: na::vector![0.0, 10.0],
+            end: na::vector![40.0, 10.0],
+        };
+
+        for (nib_angle, half_width) in [
+            (std::f64::consts::FRAC_PI_2, 4.0),
+            (0.0, 4.0 * CalligraphyOptions::THIN_FACTOR_DEFAULT),
+        ] {
+            let options = CalligraphyOptions {
+                nib_angle,
+                ..CalligraphyOptions::default()
+            };
+            let points = outline_points(compose_line(line, &options));
+
+            // One side forward, the other backwards
+            assert_eq!(points.len(), 4);
+            for (point, x) in points.iter().zip([0.0, 40.0, 40.0, 0.0]) {
+                assert_near(point[0], x);
+                assert_near((point[1] - 10.0).abs(), half_width);
+            }
+            assert_near(points[0][1], points[1][1]);
+            assert_near(points[2][1], points[3][1]);
+            assert_near(points[0][1] - 10.0, -(points[3][1] - 10.0));
+        }
+    }
+
+    #[test]
+    fn cubbez_outline_follows_the_samples() {
+        let cubbez = curves::CubicBezier {
+            start: na::vector![0.0, 0.0],
+            cp1: na::vector![10.0, 20.0],
+            cp2: na::vector![30.0, 20.0],
+            end: na::vector![40.0, 0.0],
+        };
+        let options = CalligraphyOptions::default();
+        let points = outline_points(compose_cubbez(cubbez, &options));
+        let samples = curves::sample_cubbez_w_tangents(cubbez, CalligraphyOptions::CUBBEZ_N_SPLITS);
+
+        assert_eq!(points.len(), samples.len() * 2);
+        for (i, (pos, tangent)) in samples.iter().enumerate() {
+            let left = points[i];
+            let right = points[points.len() - 1 - i];
+
+            assert_near(((left + right) * 0.5 - pos).magnitude(), 0.0);
+            assert_near(
+                (left - right).magnitude(),
+                options.width_for_direction(*tangent),
+            );
+        }
+    }
+
+    #[test]
+    fn calligraphy_options_serde_defaults() {
+        let options = serde_json::from_str::<CalligraphyOptions>(r#"{"seed": 42}"#).unwrap();
+
+        assert_eq!(options.width, CalligraphyOptions::WIDTH_DEFAULT);
+        assert_eq!(options.nib_angle, CalligraphyOptions::NIB_ANGLE_DEFAULT);
+        assert_eq!(options.thin_factor, CalligraphyOptions::THIN_FACTOR_DEFAULT);
+        assert_eq!(
+            options.stroke_color.unwrap(),
+            CalligraphyOptions::COLOR_DEFAULT
+        );
+    }
+}
@@ -1,9 +1,10 @@
-use rnote_fileformats::xoppformat;
+use rnote_fileformats::{isfformat, xoppformat};
 
+#[cfg(feature = "ui")]
 use gtk4::gdk;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename = "color")]
 pub struct Color {
     #[serde(rename = "r")]
@@ -95,6 +96,7 @@ impl Color {
         )
     }
 
+    #[cfg(feature = "ui")]
     pub fn to_gdk(&self) -> gdk::RGBA {
         gdk::RGBA::new(self.r as f32, self.g as f32, self.b as f32, self.a as f32)
     }
@@ -124,6 +126,7 @@ impl From<Color> for (f64, f64, f64, f64) {
     }
 }
 
+#[cfg(feature = "ui")]
 impl From<gdk::RGBA> for Color {
     fn from(gdk_color: gdk::RGBA) -> Self {
         Self {
@@ -159,6 +162,18 @@ impl From<xoppformat::XoppColor> for Color {
     }
 }
 
+/// From IsfColor into Color
+impl From<isfformat::IsfColor> for Color {
+    fn from(isf_color: isfformat::IsfColor) -> Self {
+        Self {
+            r: f64::from(isf_color.red) / 255.0,
+            g: f64::from(isf_color.green) / 255.0,
+            b: f64::from(isf_color.blue) / 255.0,
+            a: f64::from(isf_color.alpha) / 255.0,
+        }
+    }
+}
+
 // From Color into xoppcolor
 impl From<Color> for xoppformat::XoppColor {
     fn from(color: Color) -> Self {
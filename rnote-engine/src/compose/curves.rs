@@ -94,7 +94,6 @@ fn quadbez_coeff_c(p0: na::Vector2<f64>) -> na::Vector2<f64> {
 }
 
 // calculating the bezier curve for t: between 0.0 and 1.0
-#[allow(dead_code)]
 fn quadbez_calc(
     p0: na::Vector2<f64>,
     p1: na::Vector2<f64>,
@@ -153,6 +152,19 @@ fn cubbez_calc(
         .transpose()
 }
 
+// calculating the derivative of the cubic bezier curve for t: between 0.0 and 1.0
+fn cubbez_derive_calc(
+    p0: na::Vector2<f64>,
+    p1: na::Vector2<f64>,
+    p2: na::Vector2<f64>,
+    p3: na::Vector2<f64>,
+    t: f64,
+) -> na::Vector2<f64> {
+    3.0 * (1.0 - t).powi(2) * (p1 - p0)
+        + 6.0 * (1.0 - t) * t * (p2 - p1)
+        + 3.0 * t.powi(2) * (p3 - p2)
+}
+
 /// Returns (t1, t2) with t1, t2 between 0.0 and 1.0
 fn quadbez_solve_critical_points(
     a: na::Vector2<f64>,
@@ -345,6 +357,23 @@ pub fn approx_cubbez_with_quadbez(cubbez: CubicBezier) -> QuadBezier {
     QuadBezier { start, cp, end }
 }
 
+/// Approximating a quadratic bezier with lines, given the number of splits
+pub fn approx_quadbez_with_lines(quadbez: QuadBezier, n_splits: i32) -> Vec<Line> {
+    let mut lines = Vec::new();
+
+    for i in 0..n_splits {
+        let start_t = f64::from(i) / f64::from(n_splits);
+        let end_t = f64::from(i + 1) / f64::from(n_splits);
+
+        lines.push(Line {
+            start: quadbez_calc(quadbez.start, quadbez.cp, quadbez.end, start_t),
+            end: quadbez_calc(quadbez.start, quadbez.cp, quadbez.end, end_t),
+        })
+    }
+
+    lines
+}
+
 /// Approximating a cubic bezier with lines, given the number of splits
 pub fn approx_cubbez_with_lines(cubbez: CubicBezier, n_splits: i32) -> Vec<Line> {
     let mut lines = Vec::new();
@@ -362,6 +391,23 @@ pub fn approx_cubbez_with_lines(cubbez: CubicBezier, n_splits: i32) -> Vec<Line>
     lines
 }
 
+/// Samples the positions and the (not normalized) tangents of a cubic bezier, given the number of splits. Returns n_splits + 1 samples
+pub fn sample_cubbez_w_tangents(
+    cubbez: CubicBezier,
+    n_splits: i32,
+) -> Vec<(na::Vector2<f64>, na::Vector2<f64>)> {
+    (0..=n_splits)
+        .map(|i| {
+            let t = f64::from(i) / f64::from(n_splits);
+
+            (
+                cubbez_calc(cubbez.start, cubbez.cp1, cubbez.cp2, cubbez.end, t),
+                cubbez_derive_calc(cubbez.start, cubbez.cp1, cubbez.cp2, cubbez.end, t),
+            )
+        })
+        .collect()
+}
+
 /// Approximating a cubic bezier with lines, splitted based on critical points and the angle condition
 pub fn approx_offsetted_cubbez_with_lines_w_subdivision(
     cubbez: CubicBezier,
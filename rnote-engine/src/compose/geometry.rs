@@ -1,4 +1,5 @@
 use geo::line_string;
+#[cfg(feature = "ui")]
 use gtk4::graphene;
 use p2d::bounding_volume::AABB;
 
@@ -97,9 +98,12 @@ where
     /// It is also guaranteed that bounding boxes are aligned to the origin, meaning (0.0,0.0) is the corner of four boxes.
     /// The boxes on the edges most likely extend beyond the given aabb.
     fn split_extended_origin_aligned(self, splitted_size: na::Vector2<f64>) -> Vec<Self>;
+    /// Extends the AABB to the grid of the given size, which is aligned to the origin. Its vertices are then multiples of the size
+    fn extend_origin_aligned(&self, grid_size: na::Vector2<f64>) -> Self;
     /// Converts a AABB to a geo::Polygon
     fn to_geo_polygon(&self) -> geo::Polygon<f64>;
     /// Converts a AABB to a graphene::Rect
+    #[cfg(feature = "ui")]
     fn to_graphene_rect(&self) -> graphene::Rect;
 }
 
@@ -294,6 +298,19 @@ impl AABBHelpers for AABB {
         splitted_aabbs
     }
 
+    fn extend_origin_aligned(&self, grid_size: na::Vector2<f64>) -> Self {
+        AABB::new(
+            na::point![
+                (self.mins[0] / grid_size[0]).floor() * grid_size[0],
+                (self.mins[1] / grid_size[1]).floor() * grid_size[1]
+            ],
+            na::point![
+                (self.maxs[0] / grid_size[0]).ceil() * grid_size[0],
+                (self.maxs[1] / grid_size[1]).ceil() * grid_size[1]
+            ],
+        )
+    }
+
     fn to_geo_polygon(&self) -> geo::Polygon<f64> {
         let line_string = line_string![
             (x: self.mins[0], y: self.mins[1]),
@@ -305,6 +322,7 @@ impl AABBHelpers for AABB {
         geo::Polygon::new(line_string, vec![])
     }
 
+    #[cfg(feature = "ui")]
     fn to_graphene_rect(&self) -> graphene::Rect {
         graphene::Rect::new(
             self.mins[0] as f32,
@@ -362,3 +380,94 @@ pub fn scale_inner_bounds_to_new_outer_bounds(
         ],
     )
 }
+
+/// Clips the line from start to end to the bounds. Returns the start and end of the clipped line as parameters between 0.0 and 1.0 along the line,
+/// or None if the line is outside of the bounds
+pub fn clip_line_to_bounds(
+    start: na::Vector2<f64>,
+    end: na::Vector2<f64>,
+    bounds: AABB,
+) -> Option<(f64, f64)> {
+    let delta = end - start;
+    let (mut t_start, mut t_end) = (0.0_f64, 1.0_f64);
+
+    // Liang-Barsky, clipping against each of the four edges
+    for (p, q) in [
+        (-delta[0], start[0] - bounds.mins[0]),
+        (delta[0], bounds.maxs[0] - start[0]),
+        (-delta[1], start[1] - bounds.mins[1]),
+        (delta[1], bounds.maxs[1] - start[1]),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t_start = t_start.max(t);
+            } else {
+                t_end = t_end.min(t);
+            }
+        }
+    }
+
+    if t_start > t_end {
+        None
+    } else {
+        Some((t_start, t_end))
+    }
+}
+
+/// Clips the line from start to end to the circle. Returns the start and end of the clipped line as parameters between 0.0 and 1.0 along the line,
+/// or None if the line is outside of the circle
+pub fn clip_line_to_circle(
+    start: na::Vector2<f64>,
+    end: na::Vector2<f64>,
+    center: na::Vector2<f64>,
+    radius: f64,
+) -> Option<(f64, f64)> {
+    let delta = end - start;
+    let diff = start - center;
+
+    // Solving |start + t * delta - center| = radius for t
+    let a = delta.dot(&delta);
+    let b = 2.0 * diff.dot(&delta);
+    let c = diff.dot(&diff) - radius * radius;
+
+    if a == 0.0 {
+        return if c <= 0.0 { Some((0.0, 1.0)) } else { None };
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let discriminant_sqrt = discriminant.sqrt();
+
+    let t_start = ((-b - discriminant_sqrt) / (2.0 * a)).max(0.0);
+    let t_end = ((-b + discriminant_sqrt) / (2.0 * a)).min(1.0);
+
+    if t_start > t_end {
+        None
+    } else {
+        Some((t_start, t_end))
+    }
+}
+
+/// The distance of the point to the line segment from start to end
+pub fn dist_to_segment(
+    point: na::Vector2<f64>,
+    start: na::Vector2<f64>,
+    end: na::Vector2<f64>,
+) -> f64 {
+    let delta = end - start;
+    let length_squared = delta.magnitude_squared();
+    if length_squared == 0.0 {
+        return (point - start).magnitude();
+    }
+
+    let t = ((point - start).dot(&delta) / length_squared).clamp(0.0, 1.0);
+
+    (point - (start + delta * t)).magnitude()
+}
@@ -0,0 +1,339 @@
+use super::{color::Color, curves, textured};
+use crate::compose;
+
+use rand_distr::{Distribution, Uniform};
+use serde::{Deserialize, Serialize};
+use svg::node::element::{self, Element};
+
+/// The Options of how a hatched shape should look
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "hatched_options")]
+pub struct HatchedOptions {
+    /// An optional seed to generate reproducable strokes
+    #[serde(rename = "seed")]
+    pub seed: Option<u64>,
+    /// The width
+    #[serde(rename = "width")]
+    pub width: f64,
+    /// The color of the stroke
+    #[serde(rename = "stroke_color")]
+    pub stroke_color: Option<Color>,
+    /// Amount of hatch lines per 10 units of stroke length
+    #[serde(rename = "density")]
+    pub density: f64,
+    /// The width of a single hatch line
+    #[serde(rename = "line_width")]
+    pub line_width: f64,
+    /// The angle of the hatch lines relative to the stroke direction, in radians
+    #[serde(rename = "angle")]
+    pub angle: f64,
+    /// The max random deviation of the hatch line angles, in radians
+    #[serde(rename = "angle_jitter")]
+    pub angle_jitter: f64,
+}
+
+impl Default for HatchedOptions {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            width: Self::WIDTH_DEFAULT,
+            stroke_color: Some(Self::COLOR_DEFAULT),
+            density: Self::DENSITY_DEFAULT,
+            line_width: Self::LINE_WIDTH_DEFAULT,
+            angle: Self::ANGLE_DEFAULT,
+            angle_jitter: Self::ANGLE_JITTER_DEFAULT,
+        }
+    }
+}
+
+impl HatchedOptions {
+    /// The default width
+    pub const WIDTH_DEFAULT: f64 = 6.0;
+    /// The default color
+    pub const COLOR_DEFAULT: Color = Color {
+        r: 0.0,
+        g: 0.0,
+        b: 0.0,
+        a: 0.8,
+    };
+    /// Density default
+    pub const DENSITY_DEFAULT: f64 = 15.0;
+    /// Line width default
+    pub const LINE_WIDTH_DEFAULT: f64 = 0.5;
+    /// Angle default
+    pub const ANGLE_DEFAULT: f64 = std::f64::consts::FRAC_PI_4;
+    /// Angle jitter default
+    pub const ANGLE_JITTER_DEFAULT: f64 = 0.1;
+}
+
+/// Composes a hatched line. The hatch lines are evenly spaced along the line, with their position, length and angle randomized
+pub fn compose_line(line: curves::Line, width: f64, options: &HatchedOptions) -> Element {
+    compose_hatches(&gen_hatches_line(line, width, options), options)
+}
+
+/// Generates the hatch lines of a hatched line, see `compose_line()`
+pub fn gen_hatches_line(
+    line: curves::Line,
+    width: f64,
+    options: &HatchedOptions,
+) -> Vec<curves::Line> {
+    gen_hatches_lines_arclength(&[line], width, options)
+}
+
+/// Composes a hatched quadratic bezier curve. The hatch lines are evenly spaced along the arc length of the curve
+pub fn compose_quadbez(
+    quadbez: curves::QuadBezier,
+    width: f64,
+    options: &HatchedOptions,
+) -> Element {
+    compose_hatches(&gen_hatches_quadbez(quadbez, width, options), options)
+}
+
+/// Generates the hatch lines of a hatched quadratic bezier curve, see `compose_quadbez()`
+pub fn gen_hatches_quadbez(
+    quadbez: curves::QuadBezier,
+    width: f64,
+    options: &HatchedOptions,
+) -> Vec<curves::Line> {
+    let control_polygon_len =
+        (quadbez.cp - quadbez.start).magnitude() + (quadbez.end - quadbez.cp).magnitude();
+
+    let lines =
+        curves::approx_quadbez_with_lines(quadbez, textured::n_splits_for_len(control_polygon_len));
+
+    gen_hatches_lines_arclength(&lines, width, options)
+}
+
+/// Composes a hatched cubic bezier curve. The hatch lines are evenly spaced along the arc length of the curve
+pub fn compose_cubbez(
+    cubbez: curves::CubicBezier,
+    width: f64,
+    options: &HatchedOptions,
+) -> Element {
+    compose_hatches(&gen_hatches_cubbez(cubbez, width, options), options)
+}
+
+/// Generates the hatch lines of a hatched cubic bezier curve, see `compose_cubbez()`
+pub fn gen_hatches_cubbez(
+    cubbez: curves::CubicBezier,
+    width: f64,
+    options: &HatchedOptions,
+) -> Vec<curves::Line> {
+    let control_polygon_len = (cubbez.cp1 - cubbez.start).magnitude()
+        + (cubbez.cp2 - cubbez.cp1).magnitude()
+        + (cubbez.end - cubbez.cp2).magnitude();
+
+    let lines =
+        curves::approx_cubbez_with_lines(cubbez, textured::n_splits_for_len(control_polygon_len));
+
+    gen_hatches_lines_arclength(&lines, width, options)
+}
+
+/// Generates hatch lines along the consecutive lines approximating a curve, evenly spaced by their arc length
+fn gen_hatches_lines_arclength(
+    lines: &[curves::Line],
+    width: f64,
+    options: &HatchedOptions,
+) -> Vec<curves::Line> {
+    let mut rng = compose::new_rng_default_pcg64(options.seed);
+
+    // The accumulated arc length at the end of each line
+    let mut arclengths = Vec::with_capacity(lines.len());
+    let mut total_len = 0.0;
+    for line in lines.iter() {
+        total_len += (line.end - line.start).magnitude();
+        arclengths.push(total_len);
+    }
+
+    if total_len <= 0.0 || width <= 0.0 {
+        return vec![];
+    }
+
+    let n_hatches = ((total_len * 0.1 * options.density).round() as usize).max(1);
+    let spacing = total_len / n_hatches as f64;
+
+    // Ranges for randomization
+    let angle_jitter = options.angle_jitter.abs();
+    let distr_pos = Uniform::from(-0.5 * spacing..0.5 * spacing);
+    let distr_offset = Uniform::from(-0.1 * width..0.1 * width);
+    let distr_len = Uniform::from(0.75 * width..1.25 * width);
+    let distr_angle = Uniform::new_inclusive(-angle_jitter, angle_jitter);
+
+    (0..n_hatches)
+        .map(|i| {
+            let arclength = (i as f64 + 0.5) * spacing + distr_pos.sample(&mut rng);
+
+            // Find the line containing the arc length
+            let line_index = arclengths
+                .partition_point(|&len| len < arclength)
+                .min(lines.len() - 1);
+            let line = lines[line_index];
+            let line_vec = line.end - line.start;
+            let line_len = line_vec.magnitude();
+            let line_start_len = arclengths[line_index] - line_len;
+
+            let (t, tangent) = if line_len > 0.0 {
+                ((arclength - line_start_len) / line_len, line_vec / line_len)
+            } else {
+                (0.0, na::Vector2::x())
+            };
+            let normal = na::vector![-tangent[1], tangent[0]];
+
+            let center = line.start + line_vec * t + normal * distr_offset.sample(&mut rng);
+
+            let stroke_angle = na::Rotation2::rotation_between(&na::Vector2::x(), &tangent).angle();
+            let hatch_angle = stroke_angle + options.angle + distr_angle.sample(&mut rng);
+            let hatch_half_vec = na::vector![hatch_angle.cos(), hatch_angle.sin()]
+                * distr_len.sample(&mut rng)
+                * 0.5;
+
+            curves::Line {
+                start: center - hatch_half_vec,
+                end: center + hatch_half_vec,
+            }
+        })
+        .collect()
+}
+
+/// Composes the hatch lines
+fn compose_hatches(hatches: &[curves::Line], options: &HatchedOptions) -> Element {
+    let color = options
+        .stroke_color
+        .map_or(String::from("none"), |color| color.to_css_color());
+
+    hatches
+        .iter()
+        .fold(element::Group::new(), |group, hatch| {
+            group.add(
+                element::Line::new()
+                    .set("x1", hatch.start[0])
+                    .set("y1", hatch.start[1])
+                    .set("x2", hatch.end[0])
+                    .set("y2", hatch.end[1])
+                    .set("stroke", color.as_str())
+                    .set("stroke-width", options.line_width)
+                    .set("stroke-linecap", "round"),
+            )
+        })
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(seed: u64) -> HatchedOptions {
+        HatchedOptions {
+            seed: Some(seed),
+            ..HatchedOptions::default()
+        }
+    }
+
+    fn horizontal_line(len: f64) -> curves::Line {
+        curves::Line {
+            start: na::vector![10.0, 20.0],
+            end: na::vector![10.0 + len, 20.0],
+        }
+    }
+
+    #[test]
+    fn hatches_follow_the_density() {
+        let width = HatchedOptions::WIDTH_DEFAULT;
+        let options = options(0);
+        let hatches = gen_hatches_line(horizontal_line(100.0), width, &options);
+
+        assert_eq!(hatches.len(), 150);
+        for hatch in hatches.iter() {
+            let center = (hatch.start + hatch.end) * 0.5;
+            let len = (hatch.end - hatch.start).magnitude();
+
+            assert!((center[1] - 20.0).abs() <= 0.1 * width + 1e-9);
+            assert!((0.75 * width..=1.25 * width).contains(&len));
+        }
+    }
+
+    #[test]
+    fn hatches_are_angled_to_the_stroke_direction() {
+        let options = HatchedOptions {
+            angle_jitter: 0.0,
+            ..options(0)
+        };
+        // A vertical stroke, so the hatches are rotated by PI / 2 + the hatch angle
+        let line = curves::Line {
+            start: na::vector![0.0, 0.0],
+            end: na::vector![0.0, 50.0],
+        };
+
+        for hatch in gen_hatches_line(line, 6.0, &options) {
+            let vec = hatch.end - hatch.start;
+            let angle = vec[1].atan2(vec[0]);
+
+            assert!(
+                (angle - (std::f64::consts::FRAC_PI_2 + HatchedOptions::ANGLE_DEFAULT)).abs()
+                    < 1e-9
+            );
+        }
+    }
+
+    #[test]
+    fn hatches_are_spread_along_the_arc_length() {
+        let radius = 50.0;
+        let kappa = 4.0 / 3.0 * (std::f64::consts::SQRT_2 - 1.0);
+        let quarter_circle = curves::CubicBezier {
+            start: na::vector![radius, 0.0],
+            cp1: na::vector![radius, kappa * radius],
+            cp2: na::vector![kappa * radius, radius],
+            end: na::vector![0.0, radius],
+        };
+        let hatches = gen_hatches_cubbez(quarter_circle, 6.0, &options(0));
+
+        let expected = std::f64::consts::FRAC_PI_2 * radius * 0.1 * HatchedOptions::DENSITY_DEFAULT;
+        assert!((hatches.len() as f64 - expected).abs() <= 2.0);
+        for hatch in hatches.iter() {
+            let center = (hatch.start + hatch.end) * 0.5;
+            // Offset by at most a tenth of the width from the curve, plus the deviation of the approximated circle
+            assert!((center.magnitude() - radius).abs() <= 0.6 + 0.1);
+        }
+    }
+
+    #[test]
+    fn hatches_are_reproducible_with_the_seed() {
+        let line = horizontal_line(40.0);
+
+        let first = gen_hatches_line(line, 6.0, &options(7));
+        let second = gen_hatches_line(line, 6.0, &options(7));
+        let other = gen_hatches_line(line, 6.0, &options(8));
+
+        assert_eq!(first.len(), second.len());
+        assert!(first
+            .iter()
+            .zip(second.iter())
+            .all(|(first, second)| first.start == second.start && first.end == second.end));
+        assert!(first
+            .iter()
+            .zip(other.iter())
+            .any(|(first, other)| first.start != other.start));
+    }
+
+    #[test]
+    fn empty_lines_have_no_hatches() {
+        assert!(gen_hatches_line(horizontal_line(0.0), 6.0, &options(0)).is_empty());
+        assert!(gen_hatches_line(horizontal_line(10.0), 0.0, &options(0)).is_empty());
+    }
+
+    #[test]
+    fn missing_fields_deserialize_to_the_defaults() {
+        let options = serde_json::from_str::<HatchedOptions>("{}").unwrap();
+
+        assert_eq!(options.seed, None);
+        assert_eq!(options.width, HatchedOptions::WIDTH_DEFAULT);
+        assert_eq!(
+            options.stroke_color.map(|color| color.to_css_color()),
+            Some(HatchedOptions::COLOR_DEFAULT.to_css_color())
+        );
+        assert_eq!(options.density, HatchedOptions::DENSITY_DEFAULT);
+        assert_eq!(options.line_width, HatchedOptions::LINE_WIDTH_DEFAULT);
+        assert_eq!(options.angle, HatchedOptions::ANGLE_DEFAULT);
+        assert_eq!(options.angle_jitter, HatchedOptions::ANGLE_JITTER_DEFAULT);
+    }
+}
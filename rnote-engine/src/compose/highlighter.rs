@@ -0,0 +1,242 @@
+use super::color::Color;
+use super::curves;
+
+#[cfg(feature = "ui")]
+use gtk4::gsk;
+use serde::{Deserialize, Serialize};
+use svg::node::element::{self, path, Element};
+
+/// The blend mode of a highlighter stroke with the content below it
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
+#[repr(u32)]
+#[cfg_attr(feature = "ui", enum_type(name = "HighlighterBlendMode"))]
+#[serde(rename = "highlighter_blend_mode")]
+pub enum HighlighterBlendMode {
+    #[cfg_attr(feature = "ui", enum_value(name = "Normal", nick = "normal"))]
+    #[serde(rename = "normal")]
+    Normal = 0,
+    #[cfg_attr(feature = "ui", enum_value(name = "Multiply", nick = "multiply"))]
+    #[serde(rename = "multiply")]
+    Multiply,
+    #[cfg_attr(feature = "ui", enum_value(name = "Darken", nick = "darken"))]
+    #[serde(rename = "darken")]
+    Darken,
+}
+
+impl Default for HighlighterBlendMode {
+    fn default() -> Self {
+        Self::Multiply
+    }
+}
+
+impl HighlighterBlendMode {
+    /// The value for the css `mix-blend-mode` property
+    pub fn to_css_blend_mode(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Multiply => "multiply",
+            Self::Darken => "darken",
+        }
+    }
+
+    #[cfg(feature = "ui")]
+    pub fn to_gsk(self) -> gsk::BlendMode {
+        match self {
+            Self::Normal => gsk::BlendMode::Default,
+            Self::Multiply => gsk::BlendMode::Multiply,
+            Self::Darken => gsk::BlendMode::Darken,
+        }
+    }
+}
+
+/// The Options of how a highlighter stroke should look
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "highlighter_options")]
+pub struct HighlighterOptions {
+    /// An optional seed to generate reproducable strokes
+    #[serde(rename = "seed")]
+    pub seed: Option<u64>,
+    /// The width. Highlighter strokes have a constant width and ignore pressure
+    #[serde(rename = "width")]
+    pub width: f64,
+    /// The color of the stroke
+    #[serde(rename = "stroke_color")]
+    pub stroke_color: Option<Color>,
+    /// The blend mode with the content below
+    #[serde(rename = "blend_mode")]
+    pub blend_mode: HighlighterBlendMode,
+}
+
+impl Default for HighlighterOptions {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            width: Self::WIDTH_DEFAULT,
+            stroke_color: Some(Self::COLOR_DEFAULT),
+            blend_mode: HighlighterBlendMode::default(),
+        }
+    }
+}
+
+impl HighlighterOptions {
+    /// The default width
+    pub const WIDTH_DEFAULT: f64 = 16.0;
+    /// The default color
+    pub const COLOR_DEFAULT: Color = Color {
+        r: 1.0,
+        g: 0.9,
+        b: 0.0,
+        a: 0.5,
+    };
+}
+
+/// Composes a highlighter line
+pub fn compose_line(line: curves::Line, options: &HighlighterOptions) -> Element {
+    let commands = vec![
+        path::Command::Move(
+            path::Position::Absolute,
+            path::Parameters::from((line.start[0], line.start[1])),
+        ),
+        path::Command::Line(
+            path::Position::Absolute,
+            path::Parameters::from((line.end[0], line.end[1])),
+        ),
+    ];
+
+    compose_path(commands, options)
+}
+
+/// Composes a highlighter cubic bezier curve
+pub fn compose_cubbez(cubbez: curves::CubicBezier, options: &HighlighterOptions) -> Element {
+    let commands = vec![
+        path::Command::Move(
+            path::Position::Absolute,
+            path::Parameters::from((cubbez.start[0], cubbez.start[1])),
+        ),
+        path::Command::CubicCurve(
+            path::Position::Absolute,
+            path::Parameters::from((
+                (cubbez.cp1[0], cubbez.cp1[1]),
+                (cubbez.cp2[0], cubbez.cp2[1]),
+                (cubbez.end[0], cubbez.end[1]),
+            )),
+        ),
+    ];
+
+    compose_path(commands, options)
+}
+
+/// Flat caps, so that the segments of a stroke only touch and don't overlap into darker spots at their joints
+fn compose_path(commands: Vec<path::Command>, options: &HighlighterOptions) -> Element {
+    let color = options
+        .stroke_color
+        .map_or(String::from("none"), |color| color.to_css_color());
+
+    element::Path::new()
+        .set("stroke", color)
+        .set("stroke-width", options.width)
+        .set("stroke-linejoin", "round")
+        .set("stroke-linecap", "butt")
+        .set("fill", "none")
+        .set(
+            "style",
+            format!(
+                "mix-blend-mode: {};",
+                options.blend_mode.to_css_blend_mode()
+            ),
+        )
+        .set("d", path::Data::from(commands))
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line() -> curves::Line {
+        curves::Line {
+            start: na::vector![0.0, 0.0],
+            end: na::vector![40.0, 10.0],
+        }
+    }
+
+    #[test]
+    fn highlighter_paths_blend_with_the_content_below() {
+        for (blend_mode, css) in [
+            (HighlighterBlendMode::Normal, "mix-blend-mode: normal;"),
+            (HighlighterBlendMode::Multiply, "mix-blend-mode: multiply;"),
+            (HighlighterBlendMode::Darken, "mix-blend-mode: darken;"),
+        ] {
+            let options = HighlighterOptions {
+                blend_mode,
+                ..HighlighterOptions::default()
+            };
+            let svg = compose_line(line(), &options).to_string();
+
+            assert!(svg.contains(css), "{}", svg);
+        }
+    }
+
+    #[test]
+    fn highlighter_paths_have_flat_caps_and_a_constant_width() {
+        let options = HighlighterOptions::default();
+        let cubbez = curves::CubicBezier {
+            start: na::vector![0.0, 0.0],
+            cp1: na::vector![10.0, 20.0],
+            cp2: na::vector![30.0, 20.0],
+            end: na::vector![40.0, 0.0],
+        };
+
+        for svg in [
+            compose_line(line(), &options).to_string(),
+            compose_cubbez(cubbez, &options).to_string(),
+        ] {
+            assert!(svg.contains(r#"stroke-linecap="butt""#), "{}", svg);
+            assert!(svg.contains(r#"stroke-width="16""#), "{}", svg);
+            assert!(svg.contains(r#"fill="none""#), "{}", svg);
+        }
+        assert!(compose_cubbez(cubbez, &options).to_string().contains('C'));
+
+        let without_color = HighlighterOptions {
+            stroke_color: None,
+            ..options
+        };
+        assert!(compose_line(line(), &without_color)
+            .to_string()
+            .contains(r#"stroke="none""#));
+    }
+
+    #[test]
+    fn highlighter_options_serde_defaults() {
+        let options = serde_json::from_str::<HighlighterOptions>(r#"{"seed": 42}"#).unwrap();
+
+        assert_eq!(options.width, HighlighterOptions::WIDTH_DEFAULT);
+        assert_eq!(options.blend_mode, HighlighterBlendMode::Multiply);
+        assert_eq!(
+            options.stroke_color.unwrap(),
+            HighlighterOptions::COLOR_DEFAULT
+        );
+
+        let options =
+            serde_json::from_str::<HighlighterOptions>(r#"{"blend_mode": "darken"}"#).unwrap();
+        assert_eq!(options.blend_mode, HighlighterBlendMode::Darken);
+    }
+
+    #[cfg(feature = "ui")]
+    #[test]
+    fn blend_modes_map_to_gsk() {
+        assert_eq!(
+            HighlighterBlendMode::Normal.to_gsk(),
+            gsk::BlendMode::Default
+        );
+        assert_eq!(
+            HighlighterBlendMode::Multiply.to_gsk(),
+            gsk::BlendMode::Multiply
+        );
+        assert_eq!(
+            HighlighterBlendMode::Darken.to_gsk(),
+            gsk::BlendMode::Darken
+        );
+    }
+}
@@ -0,0 +1,1521 @@
+use crate::compose::color::Color;
+#[cfg(not(feature = "cairo"))]
+use crate::compose::svgtext;
+
+#[cfg(feature = "cairo")]
+use pango::prelude::*;
+#[cfg(not(feature = "cairo"))]
+use svg::node::element;
+
+/// The class of a math atom, which determines the spacing around it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtomClass {
+    /// Ordinary symbols like letters and digits
+    Ord,
+    /// Operators like function names
+    Op,
+    /// Binary operators like `+`
+    Bin,
+    /// Relations like `=`
+    Rel,
+    /// Punctuation like `,`
+    Punct,
+    /// Opening delimiters like `(`
+    Open,
+    /// Closing delimiters like `)`
+    Close,
+}
+
+/// A node of a parsed math expression
+#[derive(Debug, Clone)]
+pub enum MathNode {
+    /// Nodes laid out next to each other
+    Row(Vec<MathNode>),
+    /// A symbol
+    Atom {
+        text: String,
+        class: AtomClass,
+        italic: bool,
+    },
+    /// A large operator like a sum or an integral. The scripts of operators with limits are placed above and below them
+    LargeOp {
+        text: String,
+        limits: bool,
+        /// Symbols are enlarged, names like `lim` are not
+        symbol: bool,
+    },
+    /// Upright text
+    Text {
+        text: String,
+        bold: bool,
+    },
+    Frac {
+        numerator: Box<MathNode>,
+        denominator: Box<MathNode>,
+    },
+    Sqrt {
+        radicand: Box<MathNode>,
+        index: Option<Box<MathNode>>,
+    },
+    Scripts {
+        base: Box<MathNode>,
+        sup: Option<Box<MathNode>>,
+        sub: Option<Box<MathNode>>,
+    },
+    /// The body with delimiters sized to its height. A missing delimiter is written as `.`
+    Delimited {
+        left: Option<String>,
+        body: Box<MathNode>,
+        right: Option<String>,
+    },
+    Accent {
+        base: Box<MathNode>,
+        accent: char,
+    },
+    Overline(Box<MathNode>),
+    Underline(Box<MathNode>),
+    /// Horizontal space, in em
+    Space(f64),
+}
+
+impl MathNode {
+    /// The class for the spacing around the node
+    fn class(&self) -> AtomClass {
+        match self {
+            Self::Atom { class, .. } => *class,
+            Self::LargeOp { .. } => AtomClass::Op,
+            Self::Scripts { base, .. } | Self::Accent { base, .. } => base.class(),
+            _ => AtomClass::Ord,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A command, without the leading backslash
+    Command(String),
+    Char(char),
+    Whitespace,
+    BeginGroup,
+    EndGroup,
+    Sup,
+    Sub,
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let mut name = String::new();
+                while let Some(&next) = chars.peek() {
+                    if !next.is_ascii_alphabetic() {
+                        break;
+                    }
+                    name.push(next);
+                    chars.next();
+                }
+                // Commands of a single non letter, like `\,` or `\{`
+                if name.is_empty() {
+                    if let Some(next) = chars.next() {
+                        name.push(next);
+                    }
+                }
+                tokens.push(Token::Command(name));
+            }
+            '{' => tokens.push(Token::BeginGroup),
+            '}' => tokens.push(Token::EndGroup),
+            '^' => tokens.push(Token::Sup),
+            '_' => tokens.push(Token::Sub),
+            c if c.is_whitespace() => tokens.push(Token::Whitespace),
+            c => tokens.push(Token::Char(c)),
+        }
+    }
+
+    tokens
+}
+
+/// Where a row of nodes ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowEnd {
+    End,
+    EndGroup,
+    /// The `]` of an optional argument
+    Bracket,
+    /// The `\right` of a `\left`
+    Right,
+}
+
+/// The max depth of nested groups, arguments and `\left`, beyond which parsing fails.
+/// Parsing and layout recurse into the nesting, so unbounded nesting would overflow the stack
+pub const MAX_NESTING_DEPTH: usize = 64;
+
+/// Parses a math expression in a subset of the LaTeX syntax.
+/// Supported are groups, super- and subscripts, `\frac`, `\sqrt`, `\left` and `\right`, accents, `\text` and the common symbols
+pub fn parse(source: &str) -> Result<MathNode, anyhow::Error> {
+    let mut parser = Parser {
+        tokens: tokenize(source),
+        pos: 0,
+        depth: 0,
+    };
+
+    Ok(MathNode::Row(parser.parse_row(RowEnd::End)?))
+}
+
+#[derive(Debug)]
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// The current nesting depth of rows and arguments
+    depth: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek() == Some(&Token::Whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Runs the parse function one nesting level deeper, failing if the max depth is exceeded
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, anyhow::Error>,
+    ) -> Result<T, anyhow::Error> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(anyhow::anyhow!(
+                "nested deeper than {} levels",
+                MAX_NESTING_DEPTH
+            ));
+        }
+
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_row(&mut self, end: RowEnd) -> Result<Vec<MathNode>, anyhow::Error> {
+        self.nested(|parser| parser.parse_row_nodes(end))
+    }
+
+    fn parse_row_nodes(&mut self, end: RowEnd) -> Result<Vec<MathNode>, anyhow::Error> {
+        let mut nodes = vec![];
+
+        loop {
+            let token = match self.next() {
+                Some(token) => token,
+                None if end == RowEnd::End => return Ok(nodes),
+                None if end == RowEnd::Right => {
+                    return Err(anyhow::anyhow!("missing `\\right` for `\\left`"))
+                }
+                None if end == RowEnd::Bracket => return Err(anyhow::anyhow!("missing `]`")),
+                None => return Err(anyhow::anyhow!("missing `}}`")),
+            };
+
+            match token {
+                Token::Whitespace => {}
+                Token::BeginGroup => nodes.push(MathNode::Row(self.parse_row(RowEnd::EndGroup)?)),
+                Token::EndGroup if end == RowEnd::EndGroup => return Ok(nodes),
+                Token::EndGroup => return Err(anyhow::anyhow!("unexpected `}}`")),
+                Token::Char(']') if end == RowEnd::Bracket => return Ok(nodes),
+                Token::Command(name) if name == "right" => {
+                    if end == RowEnd::Right {
+                        return Ok(nodes);
+                    } else {
+                        return Err(anyhow::anyhow!("`\\right` without `\\left`"));
+                    }
+                }
+                Token::Sup => {
+                    let sup = self.parse_argument()?;
+                    attach_script(&mut nodes, Some(sup), None)?;
+                }
+                Token::Sub => {
+                    let sub = self.parse_argument()?;
+                    attach_script(&mut nodes, None, Some(sub))?;
+                }
+                Token::Char('\'') => {
+                    let prime = MathNode::Atom {
+                        text: String::from("′"),
+                        class: AtomClass::Ord,
+                        italic: false,
+                    };
+                    attach_script(&mut nodes, Some(prime), None)?;
+                }
+                Token::Command(name) => nodes.push(self.parse_command(&name)?),
+                Token::Char(c) => nodes.push(atom_for_char(c)),
+            }
+        }
+    }
+
+    /// Parses the argument of a command or script, which is a group or a single token
+    fn parse_argument(&mut self) -> Result<MathNode, anyhow::Error> {
+        self.nested(|parser| parser.parse_argument_node())
+    }
+
+    fn parse_argument_node(&mut self) -> Result<MathNode, anyhow::Error> {
+        self.skip_whitespace();
+
+        match self.next() {
+            Some(Token::BeginGroup) => Ok(MathNode::Row(self.parse_row(RowEnd::EndGroup)?)),
+            Some(Token::Command(name)) if name != "right" => self.parse_command(&name),
+            Some(Token::Char(c)) => Ok(atom_for_char(c)),
+            _ => Err(anyhow::anyhow!("missing argument")),
+        }
+    }
+
+    /// Parses the argument of text commands, keeping the whitespace
+    fn parse_text_argument(&mut self) -> Result<String, anyhow::Error> {
+        self.skip_whitespace();
+        if self.next() != Some(Token::BeginGroup) {
+            return Err(anyhow::anyhow!("missing argument for text"));
+        }
+
+        let mut text = String::new();
+        let mut depth = 0;
+        loop {
+            match self.next() {
+                Some(Token::Char(c)) => text.push(c),
+                Some(Token::Whitespace) => text.push(' '),
+                Some(Token::Command(name)) => {
+                    // Escaped chars are kept, other commands are written as they are
+                    if name.chars().all(|c| c.is_ascii_alphabetic()) {
+                        text.push('\\');
+                    }
+                    text.push_str(&name);
+                }
+                Some(Token::Sup) => text.push('^'),
+                Some(Token::Sub) => text.push('_'),
+                Some(Token::BeginGroup) => depth += 1,
+                Some(Token::EndGroup) if depth == 0 => return Ok(text),
+                Some(Token::EndGroup) => depth -= 1,
+                None => return Err(anyhow::anyhow!("missing `}}`")),
+            }
+        }
+    }
+
+    fn parse_delimiter(&mut self) -> Result<Option<String>, anyhow::Error> {
+        self.skip_whitespace();
+
+        let delimiter = match self.next() {
+            Some(Token::Char('.')) => return Ok(None),
+            Some(Token::Char(c)) if "()[]|/".contains(c) => String::from(c),
+            Some(Token::Command(name)) => match name.as_str() {
+                "{" | "lbrace" => String::from("{"),
+                "}" | "rbrace" => String::from("}"),
+                "|" | "Vert" => String::from("‖"),
+                "vert" => String::from("|"),
+                "langle" => String::from("⟨"),
+                "rangle" => String::from("⟩"),
+                "lfloor" => String::from("⌊"),
+                "rfloor" => String::from("⌋"),
+                "lceil" => String::from("⌈"),
+                "rceil" => String::from("⌉"),
+                _ => return Err(anyhow::anyhow!("invalid delimiter `\\{}`", name)),
+            },
+            _ => return Err(anyhow::anyhow!("missing delimiter")),
+        };
+
+        Ok(Some(delimiter))
+    }
+
+    fn parse_command(&mut self, name: &str) -> Result<MathNode, anyhow::Error> {
+        let node = match name {
+            "frac" | "dfrac" | "tfrac" => MathNode::Frac {
+                numerator: Box::new(self.parse_argument()?),
+                denominator: Box::new(self.parse_argument()?),
+            },
+            "sqrt" => {
+                self.skip_whitespace();
+                let index = if self.peek() == Some(&Token::Char('[')) {
+                    self.pos += 1;
+                    Some(Box::new(MathNode::Row(self.parse_row(RowEnd::Bracket)?)))
+                } else {
+                    None
+                };
+
+                MathNode::Sqrt {
+                    radicand: Box::new(self.parse_argument()?),
+                    index,
+                }
+            }
+            "text" | "textrm" | "mathrm" | "operatorname" => MathNode::Text {
+                text: self.parse_text_argument()?,
+                bold: false,
+            },
+            "textbf" | "mathbf" => MathNode::Text {
+                text: self.parse_text_argument()?,
+                bold: true,
+            },
+            "left" => {
+                let left = self.parse_delimiter()?;
+                let body = MathNode::Row(self.parse_row(RowEnd::Right)?);
+                let right = self.parse_delimiter()?;
+
+                MathNode::Delimited {
+                    left,
+                    body: Box::new(body),
+                    right,
+                }
+            }
+            "hat" | "widehat" => self.parse_accent('ˆ')?,
+            "bar" => self.parse_accent('¯')?,
+            "tilde" | "widetilde" => self.parse_accent('˜')?,
+            "dot" => self.parse_accent('˙')?,
+            "ddot" => self.parse_accent('¨')?,
+            "vec" => self.parse_accent('→')?,
+            "overline" => MathNode::Overline(Box::new(self.parse_argument()?)),
+            "underline" => MathNode::Underline(Box::new(self.parse_argument()?)),
+            "," => MathNode::Space(3.0 / 18.0),
+            ":" | ">" => MathNode::Space(4.0 / 18.0),
+            ";" => MathNode::Space(5.0 / 18.0),
+            "!" => MathNode::Space(-3.0 / 18.0),
+            " " => MathNode::Space(0.25),
+            "quad" => MathNode::Space(1.0),
+            "qquad" => MathNode::Space(2.0),
+            "\\" => return Err(anyhow::anyhow!("line breaks are not supported")),
+            _ => {
+                if let Some(node) = large_op_for_command(name) {
+                    node
+                } else if let Some(node) = symbol_for_command(name) {
+                    node
+                } else {
+                    return Err(anyhow::anyhow!("unknown command `\\{}`", name));
+                }
+            }
+        };
+
+        Ok(node)
+    }
+
+    fn parse_accent(&mut self, accent: char) -> Result<MathNode, anyhow::Error> {
+        Ok(MathNode::Accent {
+            base: Box::new(self.parse_argument()?),
+            accent,
+        })
+    }
+}
+
+/// Attaches the scripts to the last node, or to an empty node if there is none
+fn attach_script(
+    nodes: &mut Vec<MathNode>,
+    new_sup: Option<MathNode>,
+    new_sub: Option<MathNode>,
+) -> Result<(), anyhow::Error> {
+    let (base, mut sup, mut sub) = match nodes.pop() {
+        Some(MathNode::Scripts { base, sup, sub }) => (base, sup, sub),
+        Some(node) => (Box::new(node), None, None),
+        None => (Box::new(MathNode::Row(vec![])), None, None),
+    };
+
+    if let Some(new_sup) = new_sup {
+        if sup.is_some() {
+            return Err(anyhow::anyhow!("double superscript"));
+        }
+        sup = Some(Box::new(new_sup));
+    }
+    if let Some(new_sub) = new_sub {
+        if sub.is_some() {
+            return Err(anyhow::anyhow!("double subscript"));
+        }
+        sub = Some(Box::new(new_sub));
+    }
+
+    nodes.push(MathNode::Scripts { base, sup, sub });
+    Ok(())
+}
+
+fn atom_for_char(c: char) -> MathNode {
+    let (text, class, italic) = match c {
+        c if c.is_ascii_alphabetic() => (String::from(c), AtomClass::Ord, true),
+        '+' => (String::from("+"), AtomClass::Bin, false),
+        // The minus sign instead of the hyphen
+        '-' => (String::from("−"), AtomClass::Bin, false),
+        '*' => (String::from("∗"), AtomClass::Bin, false),
+        '=' | '<' | '>' | ':' => (String::from(c), AtomClass::Rel, false),
+        ',' | ';' => (String::from(c), AtomClass::Punct, false),
+        '(' | '[' => (String::from(c), AtomClass::Open, false),
+        ')' | ']' => (String::from(c), AtomClass::Close, false),
+        c => (String::from(c), AtomClass::Ord, false),
+    };
+
+    MathNode::Atom {
+        text,
+        class,
+        italic,
+    }
+}
+
+fn large_op_for_command(name: &str) -> Option<MathNode> {
+    let (text, limits, symbol) = match name {
+        "sum" => ("∑", true, true),
+        "prod" => ("∏", true, true),
+        "coprod" => ("∐", true, true),
+        "bigcup" => ("⋃", true, true),
+        "bigcap" => ("⋂", true, true),
+        "int" => ("∫", false, true),
+        "iint" => ("∬", false, true),
+        "iiint" => ("∭", false, true),
+        "oint" => ("∮", false, true),
+        "lim" | "max" | "min" | "sup" | "inf" | "det" | "gcd" | "argmax" | "argmin" => {
+            (name, true, false)
+        }
+        _ => return None,
+    };
+
+    Some(MathNode::LargeOp {
+        text: String::from(text),
+        limits,
+        symbol,
+    })
+}
+
+fn symbol_for_command(name: &str) -> Option<MathNode> {
+    let (text, class, italic) = match name {
+        // Greek letters
+        "alpha" => ("α", AtomClass::Ord, true),
+        "beta" => ("β", AtomClass::Ord, true),
+        "gamma" => ("γ", AtomClass::Ord, true),
+        "delta" => ("δ", AtomClass::Ord, true),
+        "epsilon" => ("ϵ", AtomClass::Ord, true),
+        "varepsilon" => ("ε", AtomClass::Ord, true),
+        "zeta" => ("ζ", AtomClass::Ord, true),
+        "eta" => ("η", AtomClass::Ord, true),
+        "theta" => ("θ", AtomClass::Ord, true),
+        "vartheta" => ("ϑ", AtomClass::Ord, true),
+        "iota" => ("ι", AtomClass::Ord, true),
+        "kappa" => ("κ", AtomClass::Ord, true),
+        "lambda" => ("λ", AtomClass::Ord, true),
+        "mu" => ("μ", AtomClass::Ord, true),
+        "nu" => ("ν", AtomClass::Ord, true),
+        "xi" => ("ξ", AtomClass::Ord, true),
+        "pi" => ("π", AtomClass::Ord, true),
+        "varpi" => ("ϖ", AtomClass::Ord, true),
+        "rho" => ("ρ", AtomClass::Ord, true),
+        "varrho" => ("ϱ", AtomClass::Ord, true),
+        "sigma" => ("σ", AtomClass::Ord, true),
+        "varsigma" => ("ς", AtomClass::Ord, true),
+        "tau" => ("τ", AtomClass::Ord, true),
+        "upsilon" => ("υ", AtomClass::Ord, true),
+        "phi" => ("ϕ", AtomClass::Ord, true),
+        "varphi" => ("φ", AtomClass::Ord, true),
+        "chi" => ("χ", AtomClass::Ord, true),
+        "psi" => ("ψ", AtomClass::Ord, true),
+        "omega" => ("ω", AtomClass::Ord, true),
+        "Gamma" => ("Γ", AtomClass::Ord, false),
+        "Delta" => ("Δ", AtomClass::Ord, false),
+        "Theta" => ("Θ", AtomClass::Ord, false),
+        "Lambda" => ("Λ", AtomClass::Ord, false),
+        "Xi" => ("Ξ", AtomClass::Ord, false),
+        "Pi" => ("Π", AtomClass::Ord, false),
+        "Sigma" => ("Σ", AtomClass::Ord, false),
+        "Upsilon" => ("Υ", AtomClass::Ord, false),
+        "Phi" => ("Φ", AtomClass::Ord, false),
+        "Psi" => ("Ψ", AtomClass::Ord, false),
+        "Omega" => ("Ω", AtomClass::Ord, false),
+        // Binary operators
+        "pm" => ("±", AtomClass::Bin, false),
+        "mp" => ("∓", AtomClass::Bin, false),
+        "times" => ("×", AtomClass::Bin, false),
+        "div" => ("÷", AtomClass::Bin, false),
+        "cdot" => ("⋅", AtomClass::Bin, false),
+        "ast" => ("∗", AtomClass::Bin, false),
+        "circ" => ("∘", AtomClass::Bin, false),
+        "cup" => ("∪", AtomClass::Bin, false),
+        "cap" => ("∩", AtomClass::Bin, false),
+        "setminus" => ("∖", AtomClass::Bin, false),
+        "wedge" | "land" => ("∧", AtomClass::Bin, false),
+        "vee" | "lor" => ("∨", AtomClass::Bin, false),
+        "oplus" => ("⊕", AtomClass::Bin, false),
+        "otimes" => ("⊗", AtomClass::Bin, false),
+        // Relations
+        "leq" | "le" => ("≤", AtomClass::Rel, false),
+        "geq" | "ge" => ("≥", AtomClass::Rel, false),
+        "neq" | "ne" => ("≠", AtomClass::Rel, false),
+        "approx" => ("≈", AtomClass::Rel, false),
+        "equiv" => ("≡", AtomClass::Rel, false),
+        "sim" => ("∼", AtomClass::Rel, false),
+        "simeq" => ("≃", AtomClass::Rel, false),
+        "cong" => ("≅", AtomClass::Rel, false),
+        "propto" => ("∝", AtomClass::Rel, false),
+        "ll" => ("≪", AtomClass::Rel, false),
+        "gg" => ("≫", AtomClass::Rel, false),
+        "in" => ("∈", AtomClass::Rel, false),
+        "notin" => ("∉", AtomClass::Rel, false),
+        "ni" => ("∋", AtomClass::Rel, false),
+        "subset" => ("⊂", AtomClass::Rel, false),
+        "supset" => ("⊃", AtomClass::Rel, false),
+        "subseteq" => ("⊆", AtomClass::Rel, false),
+        "supseteq" => ("⊇", AtomClass::Rel, false),
+        "perp" => ("⊥", AtomClass::Rel, false),
+        "parallel" => ("∥", AtomClass::Rel, false),
+        "mid" => ("∣", AtomClass::Rel, false),
+        "to" | "rightarrow" => ("→", AtomClass::Rel, false),
+        "leftarrow" | "gets" => ("←", AtomClass::Rel, false),
+        "leftrightarrow" => ("↔", AtomClass::Rel, false),
+        "Rightarrow" | "implies" => ("⇒", AtomClass::Rel, false),
+        "Leftarrow" => ("⇐", AtomClass::Rel, false),
+        "Leftrightarrow" | "iff" => ("⇔", AtomClass::Rel, false),
+        "mapsto" => ("↦", AtomClass::Rel, false),
+        // Ordinary symbols
+        "infty" => ("∞", AtomClass::Ord, false),
+        "partial" => ("∂", AtomClass::Ord, false),
+        "nabla" => ("∇", AtomClass::Ord, false),
+        "forall" => ("∀", AtomClass::Ord, false),
+        "exists" => ("∃", AtomClass::Ord, false),
+        "neg" | "lnot" => ("¬", AtomClass::Ord, false),
+        "emptyset" | "varnothing" => ("∅", AtomClass::Ord, false),
+        "angle" => ("∠", AtomClass::Ord, false),
+        "hbar" => ("ℏ", AtomClass::Ord, false),
+        "ell" => ("ℓ", AtomClass::Ord, false),
+        "Re" => ("ℜ", AtomClass::Ord, false),
+        "Im" => ("ℑ", AtomClass::Ord, false),
+        "aleph" => ("ℵ", AtomClass::Ord, false),
+        "prime" => ("′", AtomClass::Ord, false),
+        "degree" => ("°", AtomClass::Ord, false),
+        "ldots" | "dots" => ("…", AtomClass::Ord, false),
+        "cdots" => ("⋯", AtomClass::Ord, false),
+        "vdots" => ("⋮", AtomClass::Ord, false),
+        "ddots" => ("⋱", AtomClass::Ord, false),
+        "|" => ("‖", AtomClass::Ord, false),
+        "%" => ("%", AtomClass::Ord, false),
+        "&" => ("&", AtomClass::Ord, false),
+        "#" => ("#", AtomClass::Ord, false),
+        "$" => ("$", AtomClass::Ord, false),
+        "_" => ("_", AtomClass::Ord, false),
+        // Delimiters
+        "{" | "lbrace" => ("{", AtomClass::Open, false),
+        "}" | "rbrace" => ("}", AtomClass::Close, false),
+        "langle" => ("⟨", AtomClass::Open, false),
+        "rangle" => ("⟩", AtomClass::Close, false),
+        "lfloor" => ("⌊", AtomClass::Open, false),
+        "rfloor" => ("⌋", AtomClass::Close, false),
+        "lceil" => ("⌈", AtomClass::Open, false),
+        "rceil" => ("⌉", AtomClass::Close, false),
+        // Functions
+        "sin" | "cos" | "tan" | "cot" | "sec" | "csc" | "arcsin" | "arccos" | "arctan" | "sinh"
+        | "cosh" | "tanh" | "log" | "ln" | "lg" | "exp" | "deg" | "dim" | "ker" | "arg" | "hom"
+        | "Pr" => (name, AtomClass::Op, false),
+        _ => return None,
+    };
+
+    Some(MathNode::Atom {
+        text: String::from(text),
+        class,
+        italic,
+    })
+}
+
+/// The height of the math axis, on which fraction bars and operators are centered, in em
+const AXIS_HEIGHT: f64 = 0.25;
+/// The thickness of fraction bars and the radical sign, in em
+const RULE_THICKNESS: f64 = 0.05;
+/// The scale of super- and subscripts
+const SCRIPT_SCALE: f64 = 0.7;
+/// The scale of radical indices
+const INDEX_SCALE: f64 = 0.5;
+/// The min scale of nested scripts, relative to the font size of the expression
+const SCALE_MIN: f64 = 0.5;
+/// The scale of large operator symbols
+const LARGE_OP_SCALE: f64 = 1.4;
+
+/// The style of the nodes being laid out
+#[derive(Debug, Clone, Copy)]
+struct LayoutStyle {
+    font_size: f64,
+    font_size_min: f64,
+    /// Fractions in display style keep the font size, nested fractions are shrunk
+    display: bool,
+}
+
+impl LayoutStyle {
+    fn shrunk(self, scale: f64) -> Self {
+        Self {
+            font_size: (self.font_size * scale).max(self.font_size_min),
+            font_size_min: self.font_size_min,
+            display: false,
+        }
+    }
+}
+
+/// The font of glyphs
+#[derive(Debug, Clone)]
+struct GlyphsFont {
+    family: String,
+    size: f64,
+    italic: bool,
+    bold: bool,
+}
+
+impl GlyphsFont {
+    #[cfg(feature = "cairo")]
+    fn font_description(&self) -> pango::FontDescription {
+        let mut font_description = pango::FontDescription::new();
+        font_description.set_family(&self.family);
+        font_description.set_absolute_size(self.size * f64::from(pango::SCALE));
+        if self.italic {
+            font_description.set_style(pango::Style::Italic);
+        }
+        if self.bold {
+            font_description.set_weight(pango::Weight::Bold);
+        }
+
+        font_description
+    }
+}
+
+#[derive(Debug, Clone)]
+enum MathItem {
+    /// Text, drawn with the upper left corner of its layout at the position
+    Glyphs {
+        text: String,
+        font: GlyphsFont,
+        pos: na::Vector2<f64>,
+    },
+    /// A filled rectangle
+    Rule {
+        pos: na::Vector2<f64>,
+        size: na::Vector2<f64>,
+    },
+    /// A stroked polyline
+    Polyline {
+        points: Vec<na::Vector2<f64>>,
+        width: f64,
+    },
+}
+
+impl MathItem {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        match self {
+            Self::Glyphs { pos, .. } | Self::Rule { pos, .. } => *pos += offset,
+            Self::Polyline { points, .. } => {
+                points.iter_mut().for_each(|point| *point += offset);
+            }
+        }
+    }
+}
+
+/// A laid out math expression. Its origin is the start of its baseline, with the y axis pointing down
+#[derive(Debug, Clone, Default)]
+pub struct MathBox {
+    pub width: f64,
+    /// The height above the baseline
+    pub ascent: f64,
+    /// The depth below the baseline
+    pub descent: f64,
+    items: Vec<MathItem>,
+}
+
+impl MathBox {
+    fn empty(width: f64) -> Self {
+        Self {
+            width,
+            ..Self::default()
+        }
+    }
+
+    /// Moves the items of the other box into this box, with its origin at the offset
+    fn append(&mut self, other: MathBox, offset: na::Vector2<f64>) {
+        self.items.extend(other.items.into_iter().map(|mut item| {
+            item.translate(offset);
+            item
+        }));
+    }
+
+    /// Moves the content of the box down by the offset
+    fn shift_down(&mut self, offset: f64) {
+        self.items
+            .iter_mut()
+            .for_each(|item| item.translate(na::vector![0.0, offset]));
+        self.ascent -= offset;
+        self.descent += offset;
+    }
+
+    /// Draws the box with its origin at the position
+    #[cfg(feature = "cairo")]
+    pub fn draw_to_cairo(
+        &self,
+        cx: &cairo::Context,
+        pos: na::Vector2<f64>,
+        color: Color,
+    ) -> Result<(), anyhow::Error> {
+        cx.set_source_rgba(color.r, color.g, color.b, color.a);
+
+        for item in self.items.iter() {
+            match item {
+                MathItem::Glyphs {
+                    text,
+                    font,
+                    pos: item_pos,
+                } => {
+                    let layout = pangocairo::create_layout(cx)
+                        .ok_or_else(|| anyhow::anyhow!("pangocairo::create_layout() failed"))?;
+                    layout.set_font_description(Some(&font.font_description()));
+                    layout.set_text(text);
+
+                    cx.move_to(pos[0] + item_pos[0], pos[1] + item_pos[1]);
+                    pangocairo::show_layout(cx, &layout);
+                }
+                MathItem::Rule {
+                    pos: item_pos,
+                    size,
+                } => {
+                    cx.rectangle(pos[0] + item_pos[0], pos[1] + item_pos[1], size[0], size[1]);
+                    cx.fill()
+                        .map_err(|e| anyhow::anyhow!("cairo fill() failed with Err {}", e))?;
+                }
+                MathItem::Polyline { points, width } => {
+                    if let Some(first) = points.first() {
+                        cx.move_to(pos[0] + first[0], pos[1] + first[1]);
+                    }
+                    for point in points.iter().skip(1) {
+                        cx.line_to(pos[0] + point[0], pos[1] + point[1]);
+                    }
+                    cx.set_line_width(*width);
+                    cx.set_line_join(cairo::LineJoin::Miter);
+                    cx.stroke()
+                        .map_err(|e| anyhow::anyhow!("cairo stroke() failed with Err {}", e))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Composes the box as svg, with its origin at the position. The glyphs are svg text elements, drawn with the fonts of the svg renderer
+    #[cfg(not(feature = "cairo"))]
+    pub fn compose_svg(&self, pos: na::Vector2<f64>, color: Color) -> element::Group {
+        self.items
+            .iter()
+            .fold(element::Group::new(), |group, item| match item {
+                MathItem::Glyphs {
+                    text,
+                    font,
+                    pos: item_pos,
+                } => group.add(svgtext::compose_line(
+                    text,
+                    pos + item_pos + na::vector![0.0, svgtext::BASELINE * font.size],
+                    &svgtext::TextFont {
+                        family: font.family.clone(),
+                        size: font.size,
+                        bold: font.bold,
+                        italic: font.italic,
+                        ..svgtext::TextFont::default()
+                    },
+                    color,
+                )),
+                MathItem::Rule {
+                    pos: item_pos,
+                    size,
+                } => group.add(
+                    element::Rectangle::new()
+                        .set("x", pos[0] + item_pos[0])
+                        .set("y", pos[1] + item_pos[1])
+                        .set("width", size[0])
+                        .set("height", size[1])
+                        .set("fill", color.to_css_color()),
+                ),
+                MathItem::Polyline { points, width } => group.add(
+                    element::Polyline::new()
+                        .set(
+                            "points",
+                            points
+                                .iter()
+                                .map(|point| {
+                                    format!("{:.3},{:.3}", pos[0] + point[0], pos[1] + point[1])
+                                })
+                                .collect::<Vec<String>>()
+                                .join(" "),
+                        )
+                        .set("fill", "none")
+                        .set("stroke", color.to_css_color())
+                        .set("stroke-width", *width)
+                        .set("stroke-linejoin", "miter"),
+                ),
+            })
+    }
+}
+
+/// Lays out parsed math expressions, measuring the glyphs with pango.
+/// Without the `cairo` feature the glyphs are measured with the approximated font metrics of `svgtext`
+#[derive(Debug)]
+pub struct MathLayouter {
+    #[cfg(feature = "cairo")]
+    context: pango::Context,
+    font_family: String,
+}
+
+impl MathLayouter {
+    #[cfg(feature = "cairo")]
+    pub fn new(font_family: &str) -> Result<Self, anyhow::Error> {
+        let fontmap = pangocairo::FontMap::default()
+            .ok_or_else(|| anyhow::anyhow!("getting the default pangocairo::FontMap failed"))?;
+        let context = fontmap
+            .create_context()
+            .ok_or_else(|| anyhow::anyhow!("create_context() for the font map failed"))?;
+
+        Ok(Self {
+            context,
+            font_family: String::from(font_family),
+        })
+    }
+
+    #[cfg(not(feature = "cairo"))]
+    pub fn new(font_family: &str) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            font_family: String::from(font_family),
+        })
+    }
+
+    /// Lays out the expression with the font size
+    pub fn layout(&self, node: &MathNode, font_size: f64) -> MathBox {
+        self.layout_node(
+            node,
+            LayoutStyle {
+                font_size,
+                font_size_min: font_size * SCALE_MIN,
+                display: true,
+            },
+        )
+    }
+
+    fn layout_node(&self, node: &MathNode, style: LayoutStyle) -> MathBox {
+        let s = style.font_size;
+
+        match node {
+            MathNode::Row(nodes) => self.layout_row(nodes, style),
+            MathNode::Atom { text, italic, .. } => self.glyphs(text, s, *italic, false),
+            MathNode::LargeOp { text, symbol, .. } => {
+                if *symbol {
+                    let mut op_box = self.glyphs(text, s * LARGE_OP_SCALE, false, false);
+                    // Symbols are centered on the axis
+                    let center = (op_box.ascent - op_box.descent) * 0.5;
+                    op_box.shift_down(center - AXIS_HEIGHT * s);
+                    op_box
+                } else {
+                    self.glyphs(text, s, false, false)
+                }
+            }
+            MathNode::Text { text, bold } => self.glyphs(text, s, false, *bold),
+            MathNode::Frac {
+                numerator,
+                denominator,
+            } => self.layout_frac(numerator, denominator, style),
+            MathNode::Sqrt { radicand, index } => {
+                self.layout_sqrt(radicand, index.as_deref(), style)
+            }
+            MathNode::Scripts { base, sup, sub } => {
+                self.layout_scripts(base, sup.as_deref(), sub.as_deref(), style)
+            }
+            MathNode::Delimited { left, body, right } => {
+                self.layout_delimited(left.as_deref(), body, right.as_deref(), style)
+            }
+            MathNode::Accent { base, accent } => self.layout_accent(base, *accent, style),
+            MathNode::Overline(base) => {
+                let base = self.layout_node(base, style);
+                let thickness = RULE_THICKNESS * s;
+                let gap = 0.1 * s;
+
+                let mut overline_box = MathBox {
+                    width: base.width,
+                    ascent: base.ascent + gap + thickness * 2.0,
+                    descent: base.descent,
+                    items: vec![MathItem::Rule {
+                        pos: na::vector![0.0, -(base.ascent + gap + thickness)],
+                        size: na::vector![base.width, thickness],
+                    }],
+                };
+                overline_box.append(base, na::vector![0.0, 0.0]);
+                overline_box
+            }
+            MathNode::Underline(base) => {
+                let base = self.layout_node(base, style);
+                let thickness = RULE_THICKNESS * s;
+                let gap = 0.1 * s;
+
+                let mut underline_box = MathBox {
+                    width: base.width,
+                    ascent: base.ascent,
+                    descent: base.descent + gap + thickness * 2.0,
+                    items: vec![MathItem::Rule {
+                        pos: na::vector![0.0, base.descent + gap],
+                        size: na::vector![base.width, thickness],
+                    }],
+                };
+                underline_box.append(base, na::vector![0.0, 0.0]);
+                underline_box
+            }
+            MathNode::Space(em) => MathBox::empty(em * s),
+        }
+    }
+
+    /// Measures the text. The ascent and descent are the extents of the ink, so they can be negative for glyphs like accents
+    #[cfg(feature = "cairo")]
+    fn glyphs(&self, text: &str, font_size: f64, italic: bool, bold: bool) -> MathBox {
+        let font = GlyphsFont {
+            family: self.font_family.clone(),
+            size: font_size,
+            italic,
+            bold,
+        };
+
+        let layout = pango::Layout::new(&self.context);
+        layout.set_font_description(Some(&font.font_description()));
+        layout.set_text(text);
+
+        let (ink_rect, logical_rect) = layout.extents();
+        let scale = f64::from(pango::SCALE);
+        let baseline = f64::from(layout.baseline()) / scale;
+
+        MathBox {
+            width: f64::from(logical_rect.width()) / scale,
+            ascent: baseline - f64::from(ink_rect.y()) / scale,
+            descent: f64::from(ink_rect.y() + ink_rect.height()) / scale - baseline,
+            items: vec![MathItem::Glyphs {
+                text: String::from(text),
+                font,
+                pos: na::vector![0.0, -baseline],
+            }],
+        }
+    }
+
+    /// Measures the text with the approximated font metrics, so the ascent and descent are the ones of the line
+    #[cfg(not(feature = "cairo"))]
+    fn glyphs(&self, text: &str, font_size: f64, italic: bool, bold: bool) -> MathBox {
+        MathBox {
+            width: svgtext::text_width(text, font_size, bold),
+            ascent: svgtext::ASCENT * font_size,
+            descent: svgtext::DESCENT * font_size,
+            items: vec![MathItem::Glyphs {
+                text: String::from(text),
+                font: GlyphsFont {
+                    family: self.font_family.clone(),
+                    size: font_size,
+                    italic,
+                    bold,
+                },
+                pos: na::vector![0.0, -svgtext::BASELINE * font_size],
+            }],
+        }
+    }
+
+    fn layout_row(&self, nodes: &[MathNode], style: LayoutStyle) -> MathBox {
+        let s = style.font_size;
+
+        // Binary operators without operands on both sides become ordinary, like the unary minus
+        let mut classes = nodes.iter().map(|node| node.class()).collect::<Vec<_>>();
+        for i in 0..classes.len() {
+            if classes[i] == AtomClass::Bin {
+                let prev_binds = i == 0
+                    || matches!(
+                        classes[i - 1],
+                        AtomClass::Bin
+                            | AtomClass::Op
+                            | AtomClass::Rel
+                            | AtomClass::Open
+                            | AtomClass::Punct
+                    );
+                let next_binds = i + 1 == classes.len()
+                    || matches!(
+                        classes[i + 1],
+                        AtomClass::Rel | AtomClass::Close | AtomClass::Punct
+                    );
+
+                if prev_binds || next_binds {
+                    classes[i] = AtomClass::Ord;
+                }
+            }
+        }
+
+        let mut row_box = MathBox::default();
+        let mut x = 0.0;
+
+        for (i, node) in nodes.iter().enumerate() {
+            if i > 0 && !matches!(nodes[i - 1], MathNode::Space(_)) {
+                x += space_between(classes[i - 1], classes[i]) * s;
+            }
+
+            let node_box = self.layout_node(node, style);
+            row_box.ascent = row_box.ascent.max(node_box.ascent);
+            row_box.descent = row_box.descent.max(node_box.descent);
+            let width = node_box.width;
+            row_box.append(node_box, na::vector![x, 0.0]);
+
+            x += width;
+        }
+        row_box.width = x;
+
+        row_box
+    }
+
+    fn layout_frac(
+        &self,
+        numerator: &MathNode,
+        denominator: &MathNode,
+        style: LayoutStyle,
+    ) -> MathBox {
+        let s = style.font_size;
+        let inner_style = if style.display {
+            LayoutStyle {
+                display: false,
+                ..style
+            }
+        } else {
+            style.shrunk(SCRIPT_SCALE)
+        };
+        let numerator = self.layout_node(numerator, inner_style);
+        let denominator = self.layout_node(denominator, inner_style);
+
+        let axis = AXIS_HEIGHT * s;
+        let thickness = RULE_THICKNESS * s;
+        let gap = 0.15 * s;
+        let padding = 0.1 * s;
+        let width = numerator.width.max(denominator.width) + padding * 2.0;
+
+        let numerator_y = -(axis + thickness * 0.5 + gap + numerator.descent);
+        let denominator_y = -axis + thickness * 0.5 + gap + denominator.ascent;
+
+        let mut frac_box = MathBox {
+            width,
+            ascent: -numerator_y + numerator.ascent,
+            descent: denominator_y + denominator.descent,
+            items: vec![MathItem::Rule {
+                pos: na::vector![0.0, -axis - thickness * 0.5],
+                size: na::vector![width, thickness],
+            }],
+        };
+        let numerator_x = (width - numerator.width) * 0.5;
+        let denominator_x = (width - denominator.width) * 0.5;
+        frac_box.append(numerator, na::vector![numerator_x, numerator_y]);
+        frac_box.append(denominator, na::vector![denominator_x, denominator_y]);
+
+        frac_box
+    }
+
+    fn layout_sqrt(
+        &self,
+        radicand: &MathNode,
+        index: Option<&MathNode>,
+        style: LayoutStyle,
+    ) -> MathBox {
+        let s = style.font_size;
+        let radicand = self.layout_node(
+            radicand,
+            LayoutStyle {
+                display: false,
+                ..style
+            },
+        );
+        let index = index.map(|index| self.layout_node(index, style.shrunk(INDEX_SCALE)));
+
+        let thickness = RULE_THICKNESS * s;
+        let gap = 0.12 * s;
+        let sign_width = 0.55 * s;
+        let top = -(radicand.ascent.max(0.7 * s) + gap + thickness * 0.5);
+        let bottom = radicand.descent.max(0.05 * s);
+        let height = bottom - top;
+
+        // The index is placed above the hook of the sign, which moves right if the index is wide
+        let sign_x = index
+            .as_ref()
+            .map_or(0.0, |index| (index.width - 0.25 * s).max(0.0));
+        let hook_y = bottom - height * 0.45;
+        let bar_end_x = sign_x + sign_width + radicand.width + 0.1 * s;
+
+        let mut sqrt_box = MathBox {
+            width: bar_end_x + 0.05 * s,
+            ascent: -top + thickness,
+            descent: bottom + thickness,
+            items: vec![MathItem::Polyline {
+                points: vec![
+                    na::vector![sign_x, hook_y],
+                    na::vector![sign_x + 0.12 * s, hook_y - 0.06 * s],
+                    na::vector![sign_x + 0.3 * s, bottom],
+                    na::vector![sign_x + sign_width, top],
+                    na::vector![bar_end_x, top],
+                ],
+                width: thickness,
+            }],
+        };
+
+        if let Some(index) = index {
+            let index_y = hook_y - 0.1 * s - index.descent.max(0.0);
+            sqrt_box.ascent = sqrt_box.ascent.max(-index_y + index.ascent);
+            sqrt_box.append(index, na::vector![0.0, index_y]);
+        }
+        sqrt_box.append(radicand, na::vector![sign_x + sign_width + 0.05 * s, 0.0]);
+
+        sqrt_box
+    }
+
+    fn layout_scripts(
+        &self,
+        base: &MathNode,
+        sup: Option<&MathNode>,
+        sub: Option<&MathNode>,
+        style: LayoutStyle,
+    ) -> MathBox {
+        let s = style.font_size;
+        let base_limits = matches!(base, MathNode::LargeOp { limits: true, .. });
+        let base = self.layout_node(base, style);
+        let script_style = style.shrunk(SCRIPT_SCALE);
+        let sup = sup.map(|sup| self.layout_node(sup, script_style));
+        let sub = sub.map(|sub| self.layout_node(sub, script_style));
+
+        if base_limits {
+            return self.layout_limits(base, sup, sub, style);
+        }
+
+        let sup_shift = sup.as_ref().map_or(0.0, |sup| {
+            (base.ascent - 0.3 * s)
+                .max(0.35 * s)
+                .max(sup.descent + 0.1 * s)
+        });
+        let mut sub_shift = sub.as_ref().map_or(0.0, |sub| {
+            (base.descent + 0.05 * s)
+                .max(0.15 * s)
+                .max(sub.ascent - 0.35 * s)
+        });
+        if let (Some(sup), Some(sub)) = (sup.as_ref(), sub.as_ref()) {
+            let gap = (sup_shift - sup.descent) - (sub.ascent - sub_shift);
+            if gap < 0.1 * s {
+                sub_shift += 0.1 * s - gap;
+            }
+        }
+
+        let scripts_width = sup
+            .as_ref()
+            .map_or(0.0, |sup| sup.width)
+            .max(sub.as_ref().map_or(0.0, |sub| sub.width));
+
+        let mut scripts_box = MathBox {
+            width: base.width + scripts_width + 0.05 * s,
+            ascent: base.ascent,
+            descent: base.descent,
+            items: vec![],
+        };
+        let base_width = base.width;
+        scripts_box.append(base, na::vector![0.0, 0.0]);
+
+        if let Some(sup) = sup {
+            scripts_box.ascent = scripts_box.ascent.max(sup_shift + sup.ascent);
+            scripts_box.append(sup, na::vector![base_width, -sup_shift]);
+        }
+        if let Some(sub) = sub {
+            scripts_box.descent = scripts_box.descent.max(sub_shift + sub.descent);
+            scripts_box.append(sub, na::vector![base_width, sub_shift]);
+        }
+
+        scripts_box
+    }
+
+    /// Places the scripts centered above and below the operator
+    fn layout_limits(
+        &self,
+        base: MathBox,
+        sup: Option<MathBox>,
+        sub: Option<MathBox>,
+        style: LayoutStyle,
+    ) -> MathBox {
+        let s = style.font_size;
+        let gap = 0.15 * s;
+        let width = base
+            .width
+            .max(sup.as_ref().map_or(0.0, |sup| sup.width))
+            .max(sub.as_ref().map_or(0.0, |sub| sub.width));
+
+        let mut limits_box = MathBox {
+            width,
+            ascent: base.ascent,
+            descent: base.descent,
+            items: vec![],
+        };
+
+        if let Some(sup) = sup {
+            let sup_y = -(base.ascent + gap + sup.descent);
+            limits_box.ascent = -sup_y + sup.ascent;
+            let sup_x = (width - sup.width) * 0.5;
+            limits_box.append(sup, na::vector![sup_x, sup_y]);
+        }
+        if let Some(sub) = sub {
+            let sub_y = base.descent + gap + sub.ascent;
+            limits_box.descent = sub_y + sub.descent;
+            let sub_x = (width - sub.width) * 0.5;
+            limits_box.append(sub, na::vector![sub_x, sub_y]);
+        }
+        let base_x = (width - base.width) * 0.5;
+        limits_box.append(base, na::vector![base_x, 0.0]);
+
+        limits_box
+    }
+
+    fn layout_delimited(
+        &self,
+        left: Option<&str>,
+        body: &MathNode,
+        right: Option<&str>,
+        style: LayoutStyle,
+    ) -> MathBox {
+        let s = style.font_size;
+        let body = self.layout_node(body, style);
+        let axis = AXIS_HEIGHT * s;
+        // The delimiters extend symmetrically around the axis
+        let target_height = (body.ascent - axis).max(body.descent + axis) * 2.0;
+
+        let delimiter_box = |delimiter: Option<&str>| -> MathBox {
+            let delimiter = match delimiter {
+                Some(delimiter) => delimiter,
+                None => return MathBox::empty(0.1 * s),
+            };
+            let unscaled = self.glyphs(delimiter, s, false, false);
+            let unscaled_height = unscaled.ascent + unscaled.descent;
+            let scale = if unscaled_height > 0.0 {
+                (target_height * 1.05 / unscaled_height).max(1.0)
+            } else {
+                1.0
+            };
+
+            let mut delimiter_box = self.glyphs(delimiter, s * scale, false, false);
+            let center = (delimiter_box.ascent - delimiter_box.descent) * 0.5;
+            delimiter_box.shift_down(center - axis);
+            delimiter_box
+        };
+        let left = delimiter_box(left);
+        let right = delimiter_box(right);
+
+        let mut delimited_box = MathBox {
+            width: left.width + body.width + right.width,
+            ascent: left.ascent.max(body.ascent).max(right.ascent),
+            descent: left.descent.max(body.descent).max(right.descent),
+            items: vec![],
+        };
+        let (left_width, body_width) = (left.width, body.width);
+        delimited_box.append(left, na::vector![0.0, 0.0]);
+        delimited_box.append(body, na::vector![left_width, 0.0]);
+        delimited_box.append(right, na::vector![left_width + body_width, 0.0]);
+
+        delimited_box
+    }
+
+    fn layout_accent(&self, base: &MathNode, accent: char, style: LayoutStyle) -> MathBox {
+        let s = style.font_size;
+        let base = self.layout_node(base, style);
+        let accent = self.glyphs(&String::from(accent), s, false, false);
+        let gap = 0.05 * s;
+
+        // The bottom of the accent ink is placed just above the base
+        let accent_y = -(base.ascent + gap) - accent.descent;
+        let width = base.width.max(accent.width);
+
+        let mut accent_box = MathBox {
+            width,
+            ascent: base.ascent + gap + accent.ascent + accent.descent,
+            descent: base.descent,
+            items: vec![],
+        };
+        let (accent_x, base_x) = ((width - accent.width) * 0.5, (width - base.width) * 0.5);
+        accent_box.append(accent, na::vector![accent_x, accent_y]);
+        accent_box.append(base, na::vector![base_x, 0.0]);
+
+        accent_box
+    }
+}
+
+/// The space between atoms of the classes, in em
+fn space_between(left: AtomClass, right: AtomClass) -> f64 {
+    const THIN: f64 = 3.0 / 18.0;
+    const MEDIUM: f64 = 4.0 / 18.0;
+    const THICK: f64 = 5.0 / 18.0;
+
+    match (left, right) {
+        (AtomClass::Open, _) | (_, AtomClass::Close) | (_, AtomClass::Punct) => 0.0,
+        (AtomClass::Rel, AtomClass::Rel) => 0.0,
+        (AtomClass::Bin, _) | (_, AtomClass::Bin) => MEDIUM,
+        (AtomClass::Rel, _) | (_, AtomClass::Rel) => THICK,
+        (AtomClass::Punct, _) => THIN,
+        (AtomClass::Op, AtomClass::Ord) | (AtomClass::Ord, AtomClass::Op) => THIN,
+        (AtomClass::Op, AtomClass::Op) => THIN,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT_SIZE: f64 = 20.0;
+
+    fn parse_error(source: &str) -> String {
+        parse(source).unwrap_err().to_string()
+    }
+
+    fn layout(source: &str) -> MathBox {
+        MathLayouter::new("Serif")
+            .unwrap()
+            .layout(&parse(source).unwrap(), FONT_SIZE)
+    }
+
+    #[test]
+    fn parses_atoms_with_their_classes() {
+        let nodes = match parse("a + 1 = \\alpha").unwrap() {
+            MathNode::Row(nodes) => nodes,
+            node => panic!("expected a row, got {:?}", node),
+        };
+
+        let atoms = nodes
+            .iter()
+            .map(|node| match node {
+                MathNode::Atom {
+                    text,
+                    class,
+                    italic,
+                } => (text.as_str(), *class, *italic),
+                node => panic!("expected an atom, got {:?}", node),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            atoms,
+            vec![
+                ("a", AtomClass::Ord, true),
+                ("+", AtomClass::Bin, false),
+                ("1", AtomClass::Ord, false),
+                ("=", AtomClass::Rel, false),
+                ("α", AtomClass::Ord, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_fracs_scripts_sqrts_and_delimiters() {
+        let nodes = match parse("\\frac{1}{x^2_i} \\sqrt[3]{y} \\left( z \\right.").unwrap() {
+            MathNode::Row(nodes) => nodes,
+            node => panic!("expected a row, got {:?}", node),
+        };
+        assert_eq!(nodes.len(), 3);
+
+        match &nodes[0] {
+            MathNode::Frac {
+                numerator,
+                denominator,
+            } => {
+                assert!(matches!(numerator.as_ref(), MathNode::Row(nodes) if nodes.len() == 1));
+                match denominator.as_ref() {
+                    MathNode::Row(nodes) => assert!(matches!(
+                        nodes.as_slice(),
+                        [MathNode::Scripts {
+                            sup: Some(_),
+                            sub: Some(_),
+                            ..
+                        }]
+                    )),
+                    node => panic!("expected a row, got {:?}", node),
+                }
+            }
+            node => panic!("expected a frac, got {:?}", node),
+        }
+        assert!(matches!(
+            &nodes[1],
+            MathNode::Sqrt {
+                index: Some(index),
+                ..
+            } if matches!(index.as_ref(), MathNode::Row(nodes) if nodes.len() == 1)
+        ));
+        assert!(matches!(
+            &nodes[2],
+            MathNode::Delimited {
+                left: Some(left),
+                right: None,
+                ..
+            } if left == "("
+        ));
+    }
+
+    #[test]
+    fn reports_malformed_sources() {
+        assert_eq!(parse_error("{a"), "missing `}`");
+        assert_eq!(parse_error("a}"), "unexpected `}`");
+        assert_eq!(parse_error("\\left( a"), "missing `\\right` for `\\left`");
+        assert_eq!(parse_error("a \\right)"), "`\\right` without `\\left`");
+        assert_eq!(parse_error("\\sqrt[3"), "missing `]`");
+        assert_eq!(parse_error("\\sqrt[3]"), "missing argument");
+        assert_eq!(parse_error("x^"), "missing argument");
+        assert_eq!(parse_error("x^a^b"), "double superscript");
+        assert_eq!(parse_error("x_a_b"), "double subscript");
+        assert_eq!(parse_error("\\unknown"), "unknown command `\\unknown`");
+    }
+
+    #[test]
+    fn rejects_deep_nesting() {
+        let groups = format!("{}x{}", "{".repeat(10_000), "}".repeat(10_000));
+        let scripts = format!("{}x{}", "x^{".repeat(10_000), "}".repeat(10_000));
+        let accents = format!("{}x", "\\hat".repeat(10_000));
+        let delimiters = format!("{}x{}", "\\left(".repeat(10_000), "\\right)".repeat(10_000));
+
+        for source in [groups, scripts, accents, delimiters] {
+            assert!(parse_error(&source).starts_with("nested deeper than"));
+        }
+
+        let depth = MAX_NESTING_DEPTH / 2;
+        assert!(parse(&format!("{}x{}", "{".repeat(depth), "}".repeat(depth))).is_ok());
+    }
+
+    #[test]
+    fn frac_is_centered_on_the_axis() {
+        let x = layout("x");
+        let frac = layout("\\frac{x}{x}");
+
+        // Display style keeps the font size of numerator and denominator
+        assert!(frac.width > x.width);
+        assert!(frac.ascent > AXIS_HEIGHT * FONT_SIZE + x.ascent + x.descent);
+        assert!(frac.descent > x.ascent - AXIS_HEIGHT * FONT_SIZE);
+        assert!(frac.ascent > frac.descent);
+
+        // Nested fractions are shrunk, so the inner fraction is lower than the outer one
+        let nested = layout("\\frac{\\frac{x}{x}}{x}");
+        let inner_height = nested.ascent - frac.ascent + x.ascent + x.descent;
+        assert!(inner_height < frac.ascent + frac.descent);
+    }
+
+    #[test]
+    fn sqrt_encloses_the_radicand() {
+        let x = layout("x");
+        let sqrt = layout("\\sqrt{x}");
+        let sqrt_w_index = layout("\\sqrt[3]{x}");
+
+        assert!(sqrt.width > x.width);
+        assert!(sqrt.ascent > x.ascent);
+        assert!(sqrt.descent >= x.descent);
+        assert!(sqrt_w_index.width >= sqrt.width);
+        assert!(sqrt_w_index.ascent >= sqrt.ascent);
+    }
+
+    #[test]
+    fn scripts_are_raised_and_lowered() {
+        let x = layout("x");
+        let sup = layout("x^2");
+        let sub = layout("x_2");
+        let both = layout("x^2_2");
+
+        assert!(sup.width > x.width && sub.width > x.width);
+        assert!(sup.ascent > x.ascent);
+        assert_eq!(sup.descent, x.descent);
+        assert!(sub.descent > x.descent);
+        assert_eq!(sub.ascent, x.ascent);
+        // Both scripts share the horizontal space after the base
+        assert_eq!(both.width, sup.width);
+        assert_eq!(both.ascent, sup.ascent);
+        assert!(both.descent >= sub.descent);
+    }
+}
@@ -3,12 +3,22 @@ use rand::Rng;
 use rand::SeedableRng;
 use svg::node::{self, element};
 
+pub mod arrowhead;
+pub mod calligraphy;
 pub mod color;
 pub mod curves;
 pub mod geometry;
+pub mod hatched;
+pub mod highlighter;
+pub mod math;
+pub mod palette;
 pub mod rough;
 pub mod shapes;
 pub mod smooth;
+pub mod spray;
+#[cfg(not(feature = "cairo"))]
+pub mod svgtext;
+pub mod tessellation;
 pub mod textured;
 pub mod transformable;
 
@@ -0,0 +1,241 @@
+use serde::{Deserialize, Serialize};
+
+use super::color::Color;
+
+/// The header every GIMP palette file begins with
+const GPL_HEADER: &str = "GIMP Palette";
+
+/// A color of a palette
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "palette_color")]
+pub struct PaletteColor {
+    #[serde(rename = "color")]
+    pub color: Color,
+    /// The name of the color, can be empty
+    #[serde(default, rename = "name")]
+    pub name: String,
+}
+
+impl PaletteColor {
+    pub fn new(color: Color, name: String) -> Self {
+        Self { color, name }
+    }
+}
+
+/// A named palette of colors
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "palette")]
+pub struct Palette {
+    #[serde(rename = "name")]
+    pub name: String,
+    /// The number of colors in a row when displaying the palette. 0 when it is unspecified
+    #[serde(rename = "columns")]
+    pub columns: u32,
+    #[serde(rename = "colors")]
+    pub colors: Vec<PaletteColor>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            name: String::from("Palette"),
+            columns: 0,
+            colors: vec![],
+        }
+    }
+}
+
+impl Palette {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the palette has the color. Colors are compared with 8 bits per channel, the precision they are picked with
+    pub fn contains_color(&self, color: Color) -> bool {
+        self.colors
+            .iter()
+            .any(|palette_color| palette_color.color.to_u32() == color.to_u32())
+    }
+
+    /// Appends the color, if the palette does not have it already. Returns true if it was appended
+    pub fn push_color(&mut self, color: Color, name: String) -> bool {
+        if self.contains_color(color) {
+            return false;
+        }
+
+        self.colors.push(PaletteColor::new(color, name));
+        true
+    }
+
+    /// Removes the color at the index
+    pub fn remove_color(&mut self, index: usize) -> Option<PaletteColor> {
+        if index < self.colors.len() {
+            Some(self.colors.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Parses a GIMP palette (`.gpl`). The format has no alpha channel, so all colors are opaque
+    pub fn from_gpl(gpl: &str) -> Result<Self, anyhow::Error> {
+        let mut lines = gpl.lines();
+
+        if lines.next().map(|line| line.trim()) != Some(GPL_HEADER) {
+            return Err(anyhow::anyhow!(
+                "the palette does not begin with the header `{}`",
+                GPL_HEADER
+            ));
+        }
+
+        let mut palette = Self::default();
+
+        for (i, line) in lines.enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix("Name:") {
+                palette.name = name.trim().to_string();
+                continue;
+            }
+            if let Some(columns) = line.strip_prefix("Columns:") {
+                palette.columns = columns.trim().parse::<u32>().unwrap_or(0);
+                continue;
+            }
+
+            // The color is three values between 0 and 255, followed by an optional name
+            let mut parts = line.split_whitespace();
+            let mut channel = || -> Result<f64, anyhow::Error> {
+                let value = parts
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("line {} has less than three values", i + 2))?
+                    .parse::<u8>()
+                    .map_err(|e| anyhow::anyhow!("line {} has an invalid value, {}", i + 2, e))?;
+                Ok(f64::from(value) / 255.0)
+            };
+            let color = Color::new(channel()?, channel()?, channel()?, 1.0);
+            let name = parts.collect::<Vec<&str>>().join(" ");
+
+            palette.colors.push(PaletteColor::new(color, name));
+        }
+
+        Ok(palette)
+    }
+
+    /// Exports the palette as GIMP palette (`.gpl`). The alpha channel of the colors is dropped
+    pub fn to_gpl(&self) -> String {
+        let mut gpl = format!("{}\nName: {}\n", GPL_HEADER, self.name);
+        if self.columns > 0 {
+            gpl += &format!("Columns: {}\n", self.columns);
+        }
+        gpl += "#\n";
+
+        for palette_color in self.colors.iter() {
+            let rgba = palette_color.color.to_u32();
+            gpl += &format!(
+                "{:3} {:3} {:3}\t{}\n",
+                (rgba >> 24) & 0xff,
+                (rgba >> 16) & 0xff,
+                (rgba >> 8) & 0xff,
+                palette_color.name
+            );
+        }
+
+        gpl
+    }
+}
+
+/// The named palettes of the app. The names are unique
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "palettes")]
+pub struct Palettes {
+    #[serde(rename = "palettes")]
+    palettes: Vec<Palette>,
+}
+
+impl Palettes {
+    /// The palettes in the order they were added
+    pub fn palettes(&self) -> &[Palette] {
+        &self.palettes
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.palettes
+            .iter()
+            .map(|palette| palette.name.clone())
+            .collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Palette> {
+        self.palettes.iter().find(|palette| palette.name == name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Palette> {
+        self.palettes
+            .iter_mut()
+            .find(|palette| palette.name == name)
+    }
+
+    /// Inserts the palette. A palette with the same name is replaced and returned
+    pub fn insert(&mut self, palette: Palette) -> Option<Palette> {
+        match self.palettes.iter().position(|p| p.name == palette.name) {
+            Some(i) => Some(std::mem::replace(&mut self.palettes[i], palette)),
+            None => {
+                self.palettes.push(palette);
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Palette> {
+        let i = self
+            .palettes
+            .iter()
+            .position(|palette| palette.name == name)?;
+        Some(self.palettes.remove(i))
+    }
+
+    /// Renames the palette. Errors if another palette has the new name already
+    pub fn rename(&mut self, name: &str, new_name: String) -> Result<(), anyhow::Error> {
+        if name != new_name && self.get(&new_name).is_some() {
+            return Err(anyhow::anyhow!(
+                "a palette with the name `{}` exists already",
+                new_name
+            ));
+        }
+
+        let palette = self
+            .get_mut(name)
+            .ok_or_else(|| anyhow::anyhow!("there is no palette with the name `{}`", name))?;
+        palette.name = new_name;
+
+        Ok(())
+    }
+
+    /// Imports a GIMP palette. Its name is suffixed with a number if a palette with the name exists already.
+    /// Returns the name it was imported with
+    pub fn import_gpl(&mut self, gpl: &str) -> Result<String, anyhow::Error> {
+        let mut palette = Palette::from_gpl(gpl)?;
+
+        let base_name = palette.name.clone();
+        let mut n = 1;
+        while self.get(&palette.name).is_some() {
+            n += 1;
+            palette.name = format!("{} {}", base_name, n);
+        }
+
+        let name = palette.name.clone();
+        self.palettes.push(palette);
+
+        Ok(name)
+    }
+
+    /// Exports the palette as GIMP palette
+    pub fn export_gpl(&self, name: &str) -> Option<String> {
+        self.get(name).map(|palette| palette.to_gpl())
+    }
+}
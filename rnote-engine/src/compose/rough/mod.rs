@@ -3,8 +3,9 @@ mod roughshapes;
 
 use svg::node::element::{self, path};
 
-use roughoptions::RoughOptions;
+use roughoptions::{FillStyle, RoughOptions};
 
+use super::transformable::Transform;
 use super::{curves, shapes};
 use crate::compose;
 
@@ -130,14 +131,29 @@ pub fn rectangle(rectangle: shapes::Rectangle, options: &RoughOptions) -> elemen
         .add(rect)
 }
 
-/// Generating a fill polygon
+/// Generating a fill polygon, in the fill style of the options
 pub fn fill_polygon(coords: Vec<na::Vector2<f64>>, options: &RoughOptions) -> element::Path {
+    // Nothing to generate without a fill
+    if options.fill_color.is_none() {
+        return options.apply_to_fill_polygon_solid(element::Path::new());
+    }
+
     let mut rng = compose::new_rng_default_pcg64(options.seed);
 
-    let mut commands = Vec::new();
-    commands.append(&mut roughshapes::fill_polygon(coords, options, &mut rng));
+    match options.fill_style {
+        FillStyle::Solid => {
+            let commands = roughshapes::fill_polygon(coords, options, &mut rng);
 
-    options.apply_to_fill_polygon_solid(element::Path::new().set("d", path::Data::from(commands)))
+            options.apply_to_fill_polygon_solid(
+                element::Path::new().set("d", path::Data::from(commands)),
+            )
+        }
+        _ => {
+            let commands = roughshapes::fill_pattern(&coords, options, &mut rng);
+
+            options.apply_to_fill_pattern(element::Path::new().set("d", path::Data::from(commands)))
+        }
+    }
 }
 
 /// Generating a ellipse
@@ -164,3 +180,109 @@ pub fn ellipse(ellipse: shapes::Ellipse, options: &RoughOptions) -> element::Gro
         .add(fill_polygon)
         .add(ellipse)
 }
+
+/// Generating a closed polygon from vertices in the local coordinates of the transform
+pub fn polygon(
+    vertices: &[na::Vector2<f64>],
+    transform: &Transform,
+    options: &RoughOptions,
+) -> element::Group {
+    let mut rng = compose::new_rng_default_pcg64(options.seed);
+
+    let mut commands = Vec::new();
+    // Applying the transform at the end
+    for (start, end) in vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .take(vertices.len())
+    {
+        if !options.disable_multistroke {
+            commands.append(&mut roughshapes::doubleline(
+                *start, *end, options, &mut rng,
+            ));
+        } else {
+            commands.append(&mut roughshapes::line(
+                *start, *end, true, false, options, &mut rng,
+            ));
+        }
+    }
+
+    let polygon = options.apply_to_rect(element::Path::new().set("d", path::Data::from(commands)));
+
+    let fill_polygon = fill_polygon(vertices.to_vec(), options);
+
+    let transform_string = transform.to_svg_transform_attr_str();
+
+    element::Group::new()
+        .set("transform", transform_string)
+        .add(fill_polygon)
+        .add(polygon)
+}
+
+/// Generating a circular arc
+pub fn arc(arc: shapes::Arc, options: &RoughOptions) -> element::Group {
+    let mut rng = compose::new_rng_default_pcg64(options.seed);
+
+    let commands = arc
+        .local_cubbezs()
+        .into_iter()
+        .flat_map(|cubbez| {
+            roughshapes::cubic_bezier(
+                cubbez.start,
+                cubbez.cp1,
+                cubbez.cp2,
+                cubbez.end,
+                options,
+                &mut rng,
+            )
+        })
+        .collect::<Vec<path::Command>>();
+
+    let arc_path = options.apply_to_line(element::Path::new().set("d", path::Data::from(commands)));
+
+    let transform_string = arc.transform.to_svg_transform_attr_str();
+
+    element::Group::new()
+        .set("transform", transform_string)
+        .add(arc_path)
+}
+
+/// Generating a polyline
+pub fn polyline(polyline: &shapes::Polyline, options: &RoughOptions) -> element::Group {
+    let mut rng = compose::new_rng_default_pcg64(options.seed);
+
+    let n_lines = if polyline.closed {
+        polyline.vertices.len()
+    } else {
+        polyline.vertices.len().saturating_sub(1)
+    };
+
+    let mut commands = Vec::new();
+    for (start, end) in polyline
+        .vertices
+        .iter()
+        .zip(polyline.vertices.iter().cycle().skip(1))
+        .take(n_lines)
+    {
+        if !options.disable_multistroke {
+            commands.append(&mut roughshapes::doubleline(
+                *start, *end, options, &mut rng,
+            ));
+        } else {
+            commands.append(&mut roughshapes::line(
+                *start, *end, true, false, options, &mut rng,
+            ));
+        }
+    }
+
+    let polyline_path =
+        options.apply_to_line(element::Path::new().set("d", path::Data::from(commands)));
+
+    let mut group = element::Group::new();
+    // Only closed polylines are filled
+    if polyline.closed {
+        group = group.add(fill_polygon(polyline.vertices.clone(), options));
+    }
+
+    group.add(polyline_path)
+}
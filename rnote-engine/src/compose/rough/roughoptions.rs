@@ -1,4 +1,5 @@
 use crate::compose::color::Color;
+use crate::compose::smooth::FillRule;
 use serde::{Deserialize, Serialize};
 use svg::node::element;
 
@@ -28,6 +29,9 @@ pub struct RoughOptions {
     /// an optional fill color. When set to None no fill is produced.
     #[serde(rename = "fill_color")]
     pub fill_color: Option<Color>,
+    /// the fill rule for self intersecting shapes, when the fill style is solid
+    #[serde(rename = "fill_rule")]
+    pub fill_rule: FillRule,
     /// the fill style
     #[serde(rename = "fill_style")]
     pub fill_style: FillStyle,
@@ -98,10 +102,11 @@ impl Default for RoughOptions {
             stroke_color: Some(Color::BLACK),
             stroke_width: Self::STROKE_WIDTH_DEFAULT,
             fill_color: None,
-            fill_style: FillStyle::Hachure,
-            fill_weight: 0.5,
-            hachure_angle: -41.0,
-            hachure_gap: 4.0,
+            fill_rule: FillRule::default(),
+            fill_style: FillStyle::default(),
+            fill_weight: Self::FILL_WEIGHT_DEFAULT,
+            hachure_angle: Self::HACHURE_ANGLE_DEFAULT,
+            hachure_gap: Self::HACHURE_GAP_DEFAULT,
             curve_stepcount: Self::CURVESTEPCOUNT_DEFAULT,
             curve_fitting: 0.95,
             curve_tightness: 0.0,
@@ -145,6 +150,24 @@ impl RoughOptions {
     pub const CURVESTEPCOUNT_MAX: f64 = 1000.0;
     /// Curve stepcount default
     pub const CURVESTEPCOUNT_DEFAULT: f64 = 9.0;
+    /// Fill weight min
+    pub const FILL_WEIGHT_MIN: f64 = 0.1;
+    /// Fill weight max
+    pub const FILL_WEIGHT_MAX: f64 = 100.0;
+    /// Fill weight default
+    pub const FILL_WEIGHT_DEFAULT: f64 = 0.5;
+    /// Hachure angle min
+    pub const HACHURE_ANGLE_MIN: f64 = -180.0;
+    /// Hachure angle max
+    pub const HACHURE_ANGLE_MAX: f64 = 180.0;
+    /// Hachure angle default
+    pub const HACHURE_ANGLE_DEFAULT: f64 = -41.0;
+    /// Hachure gap min. Limits the amount of generated fill lines
+    pub const HACHURE_GAP_MIN: f64 = 1.0;
+    /// Hachure gap max
+    pub const HACHURE_GAP_MAX: f64 = 100.0;
+    /// Hachure gap default
+    pub const HACHURE_GAP_DEFAULT: f64 = 4.0;
 
     pub(super) fn apply_to_line(&self, mut path: element::Path) -> element::Path {
         path = if let Some(stroke) = self.stroke_color {
@@ -180,6 +203,33 @@ impl RoughOptions {
         } else {
             path.set("fill", "none")
         };
+        path = path.set("fill-rule", self.fill_rule.as_svg_attr_str());
+
+        path
+    }
+
+    /// Fill styles other than solid are made up of lines in the fill color
+    pub(super) fn apply_to_fill_pattern(&self, mut path: element::Path) -> element::Path {
+        path = if let Some(fill) = self.fill_color {
+            path.set("stroke", fill.to_css_color())
+        } else {
+            path.set("stroke", "none")
+        };
+        path = path.set("stroke-width", self.fill_weight);
+        path = path.set("stroke-linecap", "round");
+        path = path.set("fill", "none");
+
+        path = path.set(
+            "stroke-dasharray",
+            self.fill_line_dash
+                .iter()
+                .map(|&no| {
+                    format! {"{}", no}
+                })
+                .collect::<Vec<String>>()
+                .join(" "),
+        );
+        path = path.set("stroke-dashoffset", self.fill_line_dash_offset);
 
         path
     }
@@ -295,31 +345,92 @@ impl RoughOptions {
     pub fn set_preserve_vertices(&mut self, preserve_vertices: bool) {
         self.preserve_vertices = !preserve_vertices;
     }
+
+    /// Returns the fill style
+    pub fn fill_style(&self) -> FillStyle {
+        self.fill_style
+    }
+
+    /// Sets the fill style
+    pub fn set_fill_style(&mut self, fill_style: FillStyle) {
+        self.fill_style = fill_style;
+    }
+
+    /// Returns the fill weight
+    pub fn fill_weight(&self) -> f64 {
+        self.fill_weight
+    }
+
+    /// Sets the fill weight
+    pub fn set_fill_weight(&mut self, fill_weight: f64) {
+        self.fill_weight = fill_weight.clamp(Self::FILL_WEIGHT_MIN, Self::FILL_WEIGHT_MAX);
+    }
+
+    /// Returns the hachure angle in degrees
+    pub fn hachure_angle(&self) -> f64 {
+        self.hachure_angle
+    }
+
+    /// Sets the hachure angle in degrees
+    pub fn set_hachure_angle(&mut self, hachure_angle: f64) {
+        self.hachure_angle = hachure_angle.clamp(Self::HACHURE_ANGLE_MIN, Self::HACHURE_ANGLE_MAX);
+    }
+
+    /// Returns the hachure gap
+    pub fn hachure_gap(&self) -> f64 {
+        self.hachure_gap
+    }
+
+    /// Sets the hachure gap
+    pub fn set_hachure_gap(&mut self, hachure_gap: f64) {
+        self.hachure_gap = hachure_gap.clamp(Self::HACHURE_GAP_MIN, Self::HACHURE_GAP_MAX);
+    }
+
+    /// Returns multistroke for the fill
+    pub fn multistroke_fill(&self) -> bool {
+        !self.disable_multistroke_fill
+    }
+
+    /// Sets the multistroke for the fill
+    pub fn set_multistroke_fill(&mut self, multistroke_fill: bool) {
+        self.disable_multistroke_fill = !multistroke_fill;
+    }
 }
 
 /// available Fill styles
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
+#[repr(u32)]
+#[cfg_attr(feature = "ui", enum_type(name = "RoughFillStyle"))]
 pub enum FillStyle {
     /// Solid
-    Solid,
+    #[cfg_attr(feature = "ui", enum_value(name = "Solid", nick = "solid"))]
+    Solid = 0,
     /// Hachure
+    #[cfg_attr(feature = "ui", enum_value(name = "Hachure", nick = "hachure"))]
     Hachure,
     /// Zigzag
+    #[cfg_attr(feature = "ui", enum_value(name = "Zigzag", nick = "zigzag"))]
     Zigzag,
     /// Zigzagline
+    #[cfg_attr(feature = "ui", enum_value(name = "ZigzagLine", nick = "zigzag-line"))]
     ZigzagLine,
     /// Crosshatch
+    #[cfg_attr(feature = "ui", enum_value(name = "Crosshatch", nick = "crosshatch"))]
     Crosshatch,
     /// Dots
+    #[cfg_attr(feature = "ui", enum_value(name = "Dots", nick = "dots"))]
     Dots,
     /// Sunburst
+    #[cfg_attr(feature = "ui", enum_value(name = "Sunburst", nick = "sunburst"))]
     Sunburst,
     /// Dashed
+    #[cfg_attr(feature = "ui", enum_value(name = "Dashed", nick = "dashed"))]
     Dashed,
 }
 
 impl Default for FillStyle {
     fn default() -> Self {
-        Self::Hachure
+        Self::Solid
     }
 }
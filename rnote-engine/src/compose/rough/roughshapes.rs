@@ -1,4 +1,4 @@
-use super::roughoptions::RoughOptions;
+use super::roughoptions::{FillStyle, RoughOptions};
 use rand::Rng;
 use svg::node::element::path;
 
@@ -510,3 +510,288 @@ where
 
     (all_points, core_points)
 }
+
+/// The lines filling the polygon in the gap, at the angle (in radians). Self intersecting polygons are filled with the even-odd rule
+fn hachure_lines(
+    points: &[na::Vector2<f64>],
+    angle: f64,
+    gap: f64,
+) -> Vec<(na::Vector2<f64>, na::Vector2<f64>)> {
+    if points.len() < 3 || gap <= 0.0 {
+        return vec![];
+    }
+
+    // Rotating the polygon so that the lines are horizontal
+    let rotation = na::Rotation2::new(-angle);
+    let inverse = rotation.inverse();
+    let rotated = points
+        .iter()
+        .map(|point| rotation * point)
+        .collect::<Vec<na::Vector2<f64>>>();
+
+    let (y_min, y_max) = rotated
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(y_min, y_max), point| {
+            (y_min.min(point[1]), y_max.max(point[1]))
+        });
+
+    let mut lines = Vec::new();
+    let mut y = y_min + gap * 0.5;
+    while y < y_max {
+        let mut intersections = rotated
+            .iter()
+            .zip(rotated.iter().cycle().skip(1))
+            .filter_map(|(start, end)| {
+                if (start[1] <= y && end[1] > y) || (end[1] <= y && start[1] > y) {
+                    Some(start[0] + (y - start[1]) / (end[1] - start[1]) * (end[0] - start[0]))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<f64>>();
+        intersections.sort_by(|first, second| {
+            first
+                .partial_cmp(second)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        for pair in intersections.chunks_exact(2) {
+            lines.push((
+                inverse * na::vector![pair[0], y],
+                inverse * na::vector![pair[1], y],
+            ));
+        }
+
+        y += gap;
+    }
+
+    lines
+}
+
+/// A single line of the fill pattern
+fn pattern_line<R>(
+    start: na::Vector2<f64>,
+    end: na::Vector2<f64>,
+    options: &RoughOptions,
+    rng: &mut R,
+) -> Vec<path::Command>
+where
+    R: Rng + ?Sized,
+{
+    if options.disable_multistroke_fill {
+        line(start, end, true, false, options, rng)
+    } else {
+        doubleline(start, end, options, rng)
+    }
+}
+
+/// Fills the polygon with the pattern of the fill style. The pattern is made up of lines which should be stroked with the fill color.
+/// Returns no commands for the solid fill style, which is generated with fill_polygon()
+pub(super) fn fill_pattern<R>(
+    points: &[na::Vector2<f64>],
+    options: &RoughOptions,
+    rng: &mut R,
+) -> Vec<path::Command>
+where
+    R: Rng + ?Sized,
+{
+    let angle = options.hachure_angle.to_radians();
+    let gap = options
+        .hachure_gap
+        .clamp(RoughOptions::HACHURE_GAP_MIN, RoughOptions::HACHURE_GAP_MAX);
+
+    let mut commands = Vec::new();
+
+    match options.fill_style {
+        FillStyle::Solid => {}
+        FillStyle::Hachure => {
+            for (start, end) in hachure_lines(points, angle, gap) {
+                commands.append(&mut pattern_line(start, end, options, rng));
+            }
+        }
+        FillStyle::Crosshatch => {
+            for (start, end) in hachure_lines(points, angle, gap)
+                .into_iter()
+                .chain(hachure_lines(
+                    points,
+                    angle + std::f64::consts::FRAC_PI_2,
+                    gap,
+                ))
+            {
+                commands.append(&mut pattern_line(start, end, options, rng));
+            }
+        }
+        FillStyle::Zigzag => {
+            // Connecting the hachure lines back and forth
+            let mut prev_end: Option<na::Vector2<f64>> = None;
+            for (i, (start, end)) in hachure_lines(points, angle, gap).into_iter().enumerate() {
+                let (start, end) = if i % 2 == 0 {
+                    (start, end)
+                } else {
+                    (end, start)
+                };
+
+                if let Some(prev_end) = prev_end {
+                    commands.append(&mut pattern_line(prev_end, start, options, rng));
+                }
+                commands.append(&mut pattern_line(start, end, options, rng));
+
+                prev_end = Some(end);
+            }
+        }
+        FillStyle::ZigzagLine => {
+            let zigzag_offset = options.zigzag_offset.max(RoughOptions::HACHURE_GAP_MIN);
+
+            for (start, end) in hachure_lines(points, angle, gap) {
+                let n_zigzags = ((end - start).magnitude() / (2.0 * zigzag_offset))
+                    .round()
+                    .max(1.0);
+                let step = (end - start) / n_zigzags;
+                let normal = na::vector![-step[1], step[0]].normalize() * zigzag_offset * 0.5;
+
+                for i in 0..n_zigzags as usize {
+                    let zigzag_start = start + step * i as f64;
+                    let zigzag_peak = zigzag_start + step * 0.5 + normal;
+
+                    commands.append(&mut pattern_line(zigzag_start, zigzag_peak, options, rng));
+                    commands.append(&mut pattern_line(
+                        zigzag_peak,
+                        zigzag_start + step,
+                        options,
+                        rng,
+                    ));
+                }
+            }
+        }
+        FillStyle::Dots => {
+            // Small circles spaced by the gap along the hachure lines. Stroked with the fill weight they look like filled dots
+            let radius = options.fill_weight * 0.25;
+
+            for (start, end) in hachure_lines(points, angle, gap) {
+                let length = (end - start).magnitude();
+                let direction = (end - start) / length;
+
+                let mut dist = gap * 0.5;
+                while dist < length {
+                    let center = start
+                        + direction * dist
+                        + offset_opt(gap * 0.1, options, rng, None) * direction;
+
+                    commands.push(path::Command::Move(
+                        path::Position::Absolute,
+                        path::Parameters::from((center[0] + radius, center[1])),
+                    ));
+                    commands.push(path::Command::EllipticalArc(
+                        path::Position::Absolute,
+                        path::Parameters::from((
+                            radius,
+                            radius,
+                            0.0,
+                            0.0,
+                            1.0,
+                            center[0] - radius,
+                            center[1],
+                        )),
+                    ));
+                    commands.push(path::Command::EllipticalArc(
+                        path::Position::Absolute,
+                        path::Parameters::from((
+                            radius,
+                            radius,
+                            0.0,
+                            0.0,
+                            1.0,
+                            center[0] + radius,
+                            center[1],
+                        )),
+                    ));
+
+                    dist += gap;
+                }
+            }
+        }
+        FillStyle::Dashed => {
+            let dash_length = options.dash_offset.max(RoughOptions::HACHURE_GAP_MIN);
+            let dash_gap = options.dash_gap.max(0.0);
+
+            for (start, end) in hachure_lines(points, angle, gap) {
+                let length = (end - start).magnitude();
+                let direction = (end - start) / length;
+
+                let mut dist = 0.0;
+                while dist < length {
+                    let dash_end = (dist + dash_length).min(length);
+
+                    commands.append(&mut pattern_line(
+                        start + direction * dist,
+                        start + direction * dash_end,
+                        options,
+                        rng,
+                    ));
+
+                    dist = dash_end + dash_gap;
+                }
+            }
+        }
+        FillStyle::Sunburst => {
+            // Rays from the center to the nearest intersection with the polygon outline
+            let center = points.iter().sum::<na::Vector2<f64>>() / points.len() as f64;
+            let (radius_max, perimeter) = points.iter().zip(points.iter().cycle().skip(1)).fold(
+                (0.0_f64, 0.0),
+                |(radius_max, perimeter), (start, end)| {
+                    (
+                        radius_max.max((start - center).magnitude()),
+                        perimeter + (end - start).magnitude(),
+                    )
+                },
+            );
+            let n_rays = (perimeter / gap).round().max(4.0) as usize;
+
+            for i in 0..n_rays {
+                let ray_angle = angle + std::f64::consts::TAU * i as f64 / n_rays as f64;
+                let ray_direction = na::vector![ray_angle.cos(), ray_angle.sin()];
+
+                let ray_length = points
+                    .iter()
+                    .zip(points.iter().cycle().skip(1))
+                    .filter_map(|(start, end)| {
+                        ray_segment_intersection(center, ray_direction, *start, *end)
+                    })
+                    .fold(radius_max, f64::min);
+
+                commands.append(&mut pattern_line(
+                    center,
+                    center + ray_direction * ray_length,
+                    options,
+                    rng,
+                ));
+            }
+        }
+    }
+
+    commands
+}
+
+/// The distance along the ray to its intersection with the segment, if they intersect
+fn ray_segment_intersection(
+    ray_origin: na::Vector2<f64>,
+    ray_direction: na::Vector2<f64>,
+    start: na::Vector2<f64>,
+    end: na::Vector2<f64>,
+) -> Option<f64> {
+    let segment = end - start;
+    let denominator = ray_direction.perp(&segment);
+    if denominator.abs() < f64::EPSILON {
+        return None;
+    }
+
+    let diff = start - ray_origin;
+    let ray_dist = diff.perp(&segment) / denominator;
+    let segment_t = diff.perp(&ray_direction) / denominator;
+
+    if ray_dist >= 0.0 && (0.0..=1.0).contains(&segment_t) {
+        Some(ray_dist)
+    } else {
+        None
+    }
+}
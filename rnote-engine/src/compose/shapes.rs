@@ -1,6 +1,7 @@
 use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 
+use super::curves;
 use super::transformable::{Transform, Transformable};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -94,3 +95,370 @@ impl Ellipse {
         AABB::from_half_extents(center, half_extents)
     }
 }
+
+/// The bounds of the points in local coordinates, transformed with the transform
+fn transformed_points_aabb(points: &[na::Vector2<f64>], transform: &Transform) -> AABB {
+    let mut aabb = AABB::new_invalid();
+
+    for point in points {
+        aabb.take_point(transform.transform_point(na::Point2::from(*point)));
+    }
+
+    aabb
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "regular_polygon")]
+pub struct RegularPolygon {
+    /// The distance from the center to the vertices
+    #[serde(rename = "radius")]
+    pub radius: f64,
+    /// The number of sides
+    #[serde(rename = "n_sides")]
+    pub n_sides: u32,
+    /// The transform. The center of the polygon is the origin, the first vertex is on the positive x axis
+    #[serde(rename = "transform")]
+    pub transform: Transform,
+}
+
+impl Default for RegularPolygon {
+    fn default() -> Self {
+        Self {
+            radius: 0.0,
+            n_sides: Self::N_SIDES_DEFAULT,
+            transform: Transform::default(),
+        }
+    }
+}
+
+impl Transformable for RegularPolygon {
+    fn translate(&mut self, offset: nalgebra::Vector2<f64>) {
+        self.transform.append_translation_mut(offset);
+    }
+
+    fn rotate(&mut self, angle: f64, center: nalgebra::Point2<f64>) {
+        self.transform.append_rotation_wrt_point_mut(angle, center)
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.transform.append_scale_mut(scale);
+    }
+}
+
+impl RegularPolygon {
+    /// The default number of sides
+    pub const N_SIDES_DEFAULT: u32 = 5;
+    /// The min number of sides
+    pub const N_SIDES_MIN: u32 = 3;
+    /// The max number of sides
+    pub const N_SIDES_MAX: u32 = 100;
+
+    /// The vertices in local coordinates
+    pub fn local_vertices(&self) -> Vec<na::Vector2<f64>> {
+        let n_sides = self.n_sides.clamp(Self::N_SIDES_MIN, Self::N_SIDES_MAX);
+
+        (0..n_sides)
+            .map(|i| {
+                let angle = std::f64::consts::TAU * f64::from(i) / f64::from(n_sides);
+                na::vector![angle.cos(), angle.sin()] * self.radius
+            })
+            .collect()
+    }
+
+    pub fn global_aabb(&self) -> AABB {
+        transformed_points_aabb(&self.local_vertices(), &self.transform)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "star")]
+pub struct Star {
+    /// The distance from the center to the outer vertices
+    #[serde(rename = "outer_radius")]
+    pub outer_radius: f64,
+    /// The distance from the center to the inner vertices, relative to the outer radius
+    #[serde(rename = "inner_radius_ratio")]
+    pub inner_radius_ratio: f64,
+    /// The number of points
+    #[serde(rename = "n_points")]
+    pub n_points: u32,
+    /// The transform. The center of the star is the origin, the first point is on the positive x axis
+    #[serde(rename = "transform")]
+    pub transform: Transform,
+}
+
+impl Default for Star {
+    fn default() -> Self {
+        Self {
+            outer_radius: 0.0,
+            inner_radius_ratio: Self::INNER_RADIUS_RATIO_DEFAULT,
+            n_points: Self::N_POINTS_DEFAULT,
+            transform: Transform::default(),
+        }
+    }
+}
+
+impl Transformable for Star {
+    fn translate(&mut self, offset: nalgebra::Vector2<f64>) {
+        self.transform.append_translation_mut(offset);
+    }
+
+    fn rotate(&mut self, angle: f64, center: nalgebra::Point2<f64>) {
+        self.transform.append_rotation_wrt_point_mut(angle, center)
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.transform.append_scale_mut(scale);
+    }
+}
+
+impl Star {
+    /// The default number of points
+    pub const N_POINTS_DEFAULT: u32 = 5;
+    /// The min number of points
+    pub const N_POINTS_MIN: u32 = 3;
+    /// The max number of points
+    pub const N_POINTS_MAX: u32 = 100;
+    /// The default inner radius ratio
+    pub const INNER_RADIUS_RATIO_DEFAULT: f64 = 0.5;
+
+    /// The vertices in local coordinates, alternating between the outer and the inner ones
+    pub fn local_vertices(&self) -> Vec<na::Vector2<f64>> {
+        let n_vertices = self.n_points.clamp(Self::N_POINTS_MIN, Self::N_POINTS_MAX) * 2;
+        let inner_radius = self.outer_radius * self.inner_radius_ratio.clamp(0.0, 1.0);
+
+        (0..n_vertices)
+            .map(|i| {
+                let angle = std::f64::consts::TAU * f64::from(i) / f64::from(n_vertices);
+                let radius = if i % 2 == 0 {
+                    self.outer_radius
+                } else {
+                    inner_radius
+                };
+
+                na::vector![angle.cos(), angle.sin()] * radius
+            })
+            .collect()
+    }
+
+    pub fn global_aabb(&self) -> AABB {
+        transformed_points_aabb(&self.local_vertices(), &self.transform)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "arc")]
+pub struct Arc {
+    /// The radius
+    #[serde(rename = "radius")]
+    pub radius: f64,
+    /// The start angle in radians, starting at the positive x axis
+    #[serde(rename = "start_angle")]
+    pub start_angle: f64,
+    /// The swept angle in radians. Its sign is the direction of the sweep
+    #[serde(rename = "sweep_angle")]
+    pub sweep_angle: f64,
+    /// The transform. The center of the arc is the origin
+    #[serde(rename = "transform")]
+    pub transform: Transform,
+}
+
+impl Default for Arc {
+    fn default() -> Self {
+        Self {
+            radius: 0.0,
+            start_angle: 0.0,
+            sweep_angle: 0.0,
+            transform: Transform::default(),
+        }
+    }
+}
+
+impl Transformable for Arc {
+    fn translate(&mut self, offset: nalgebra::Vector2<f64>) {
+        self.transform.append_translation_mut(offset);
+    }
+
+    fn rotate(&mut self, angle: f64, center: nalgebra::Point2<f64>) {
+        self.transform.append_rotation_wrt_point_mut(angle, center)
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.transform.append_scale_mut(scale);
+    }
+}
+
+impl Arc {
+    /// The number of samples for the bounds
+    pub const BOUNDS_N_SAMPLES: u32 = 64;
+
+    /// The point on the arc at the angle, in local coordinates
+    pub fn local_point_at(&self, angle: f64) -> na::Vector2<f64> {
+        na::vector![angle.cos(), angle.sin()] * self.radius
+    }
+
+    pub fn start(&self) -> na::Vector2<f64> {
+        self.local_point_at(self.start_angle)
+    }
+
+    pub fn end(&self) -> na::Vector2<f64> {
+        self.local_point_at(self.start_angle + self.sweep_angle)
+    }
+
+    /// Approximates the arc with cubic bezier curves in local coordinates, each spanning at most a quarter circle
+    pub fn local_cubbezs(&self) -> Vec<curves::CubicBezier> {
+        let sweep_angle = self
+            .sweep_angle
+            .clamp(-std::f64::consts::TAU, std::f64::consts::TAU);
+        let n_segments = (sweep_angle.abs() / std::f64::consts::FRAC_PI_2)
+            .ceil()
+            .max(1.0) as u32;
+        let segment_angle = sweep_angle / f64::from(n_segments);
+        // The distance of the control points to keep them on the tangent, relative to the radius
+        let k = 4.0 / 3.0 * (segment_angle / 4.0).tan();
+
+        (0..n_segments)
+            .map(|i| {
+                let start_angle = self.start_angle + segment_angle * f64::from(i);
+                let end_angle = start_angle + segment_angle;
+
+                let start = self.local_point_at(start_angle);
+                let end = self.local_point_at(end_angle);
+                let start_tangent = na::vector![-start_angle.sin(), start_angle.cos()];
+                let end_tangent = na::vector![-end_angle.sin(), end_angle.cos()];
+
+                curves::CubicBezier {
+                    start,
+                    cp1: start + start_tangent * k * self.radius,
+                    cp2: end - end_tangent * k * self.radius,
+                    end,
+                }
+            })
+            .collect()
+    }
+
+    pub fn global_aabb(&self) -> AABB {
+        let points = (0..=Self::BOUNDS_N_SAMPLES)
+            .map(|i| {
+                self.local_point_at(
+                    self.start_angle
+                        + self.sweep_angle * f64::from(i) / f64::from(Self::BOUNDS_N_SAMPLES),
+                )
+            })
+            .collect::<Vec<na::Vector2<f64>>>();
+
+        transformed_points_aabb(&points, &self.transform)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "parallelogram")]
+pub struct Parallelogram {
+    /// The half extents of the bounding rectangle
+    #[serde(rename = "half_extents")]
+    pub half_extents: na::Vector2<f64>,
+    /// How far the top edge is shifted to the right and the bottom edge to the left, relative to the width. Between -1.0 and 1.0
+    #[serde(rename = "skew")]
+    pub skew: f64,
+    /// The transform. The center of the bounding rectangle is the origin
+    #[serde(rename = "transform")]
+    pub transform: Transform,
+}
+
+impl Default for Parallelogram {
+    fn default() -> Self {
+        Self {
+            half_extents: na::Vector2::zeros(),
+            skew: Self::SKEW_DEFAULT,
+            transform: Transform::default(),
+        }
+    }
+}
+
+impl Transformable for Parallelogram {
+    fn translate(&mut self, offset: nalgebra::Vector2<f64>) {
+        self.transform.append_translation_mut(offset);
+    }
+
+    fn rotate(&mut self, angle: f64, center: nalgebra::Point2<f64>) {
+        self.transform.append_rotation_wrt_point_mut(angle, center)
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.transform.append_scale_mut(scale);
+    }
+}
+
+impl Parallelogram {
+    /// The default skew
+    pub const SKEW_DEFAULT: f64 = 0.25;
+
+    /// The vertices in local coordinates, clockwise starting with the top left one. They stay inside the bounding rectangle
+    pub fn local_vertices(&self) -> Vec<na::Vector2<f64>> {
+        let (hx, hy) = (self.half_extents[0], self.half_extents[1]);
+        let skew = self.skew.clamp(-1.0, 1.0);
+        let shift = skew.abs() * hx * 2.0;
+
+        if skew >= 0.0 {
+            vec![
+                na::vector![-hx + shift, -hy],
+                na::vector![hx, -hy],
+                na::vector![hx - shift, hy],
+                na::vector![-hx, hy],
+            ]
+        } else {
+            vec![
+                na::vector![-hx, -hy],
+                na::vector![hx - shift, -hy],
+                na::vector![hx, hy],
+                na::vector![-hx + shift, hy],
+            ]
+        }
+    }
+
+    pub fn global_aabb(&self) -> AABB {
+        transformed_points_aabb(&self.local_vertices(), &self.transform)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "polyline")]
+pub struct Polyline {
+    /// The vertices
+    #[serde(rename = "vertices")]
+    pub vertices: Vec<na::Vector2<f64>>,
+    /// Whether the last vertex is connected to the first, making it a polygon
+    #[serde(rename = "closed")]
+    pub closed: bool,
+}
+
+impl Transformable for Polyline {
+    fn translate(&mut self, offset: nalgebra::Vector2<f64>) {
+        for vertex in self.vertices.iter_mut() {
+            *vertex += offset;
+        }
+    }
+
+    fn rotate(&mut self, angle: f64, center: nalgebra::Point2<f64>) {
+        let mut isometry = na::Isometry2::identity();
+        isometry.append_rotation_wrt_point_mut(&na::UnitComplex::new(angle), &center);
+
+        for vertex in self.vertices.iter_mut() {
+            *vertex = (isometry * na::Point2::from(*vertex)).coords;
+        }
+    }
+
+    fn scale(&mut self, scale: nalgebra::Vector2<f64>) {
+        let center = self.global_aabb().center().coords;
+
+        for vertex in self.vertices.iter_mut() {
+            *vertex = center + (*vertex - center).component_mul(&scale);
+        }
+    }
+}
+
+impl Polyline {
+    pub fn global_aabb(&self) -> AABB {
+        transformed_points_aabb(&self.vertices, &Transform::default())
+    }
+}
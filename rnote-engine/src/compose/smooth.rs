@@ -1,9 +1,97 @@
 use super::geometry::Vector2Helpers;
+use super::transformable::Transform;
 use super::{color::Color, curves, shapes};
 
 use serde::{Deserialize, Serialize};
 use svg::node::element::{self, path};
 
+/// The source of the stroke width along the path
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
+#[repr(u32)]
+#[cfg_attr(feature = "ui", enum_type(name = "SmoothWidthSource"))]
+#[serde(rename = "smooth_width_source")]
+pub enum SmoothWidthSource {
+    /// The width scales with the pressure
+    #[cfg_attr(feature = "ui", enum_value(name = "Pressure", nick = "pressure"))]
+    #[serde(rename = "pressure")]
+    Pressure = 0,
+    /// The width decreases with the speed of the stroke
+    #[cfg_attr(feature = "ui", enum_value(name = "Velocity", nick = "velocity"))]
+    #[serde(rename = "velocity")]
+    Velocity,
+    /// The width is constant
+    #[cfg_attr(feature = "ui", enum_value(name = "Constant", nick = "constant"))]
+    #[serde(rename = "constant")]
+    Constant,
+}
+
+impl Default for SmoothWidthSource {
+    fn default() -> Self {
+        Self::Pressure
+    }
+}
+
+/// The line style of the stroke outline
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
+#[repr(u32)]
+#[cfg_attr(feature = "ui", enum_type(name = "LineStyle"))]
+#[serde(rename = "line_style")]
+pub enum LineStyle {
+    #[cfg_attr(feature = "ui", enum_value(name = "Solid", nick = "solid"))]
+    #[serde(rename = "solid")]
+    Solid = 0,
+    #[cfg_attr(feature = "ui", enum_value(name = "Dashed", nick = "dashed"))]
+    #[serde(rename = "dashed")]
+    Dashed,
+    #[cfg_attr(feature = "ui", enum_value(name = "Dotted", nick = "dotted"))]
+    #[serde(rename = "dotted")]
+    Dotted,
+    #[cfg_attr(feature = "ui", enum_value(name = "DashDot", nick = "dash-dot"))]
+    #[serde(rename = "dash_dot")]
+    DashDot,
+}
+
+impl Default for LineStyle {
+    fn default() -> Self {
+        Self::Solid
+    }
+}
+
+/// The rule which decides which regions of self intersecting paths are filled
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
+#[repr(u32)]
+#[cfg_attr(feature = "ui", enum_type(name = "FillRule"))]
+#[serde(rename = "fill_rule")]
+pub enum FillRule {
+    /// Every region enclosed by the path is filled
+    #[cfg_attr(feature = "ui", enum_value(name = "NonZero", nick = "nonzero"))]
+    #[serde(rename = "nonzero")]
+    NonZero = 0,
+    /// Regions enclosed an even number of times ( e.g. the center of a star ) are not filled
+    #[cfg_attr(feature = "ui", enum_value(name = "EvenOdd", nick = "evenodd"))]
+    #[serde(rename = "evenodd")]
+    EvenOdd,
+}
+
+impl Default for FillRule {
+    fn default() -> Self {
+        Self::NonZero
+    }
+}
+
+impl FillRule {
+    /// The value of the svg `fill-rule` attribute
+    pub fn as_svg_attr_str(&self) -> &'static str {
+        match self {
+            Self::NonZero => "nonzero",
+            Self::EvenOdd => "evenodd",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(default, rename = "smoothoptions")]
 pub struct SmoothOptions {
@@ -16,6 +104,27 @@ pub struct SmoothOptions {
     pub stroke_color: Option<Color>,
     #[serde(rename = "fill_color")]
     pub fill_color: Option<Color>,
+    /// The fill rule for self intersecting shapes
+    #[serde(rename = "fill_rule")]
+    pub fill_rule: FillRule,
+    /// Whether closed shapes are filled with the dots of textured strokes in the fill color, instead of a solid fill
+    #[serde(rename = "fill_textured")]
+    pub fill_textured: bool,
+    /// How much the width grows with the stylus tilt. At full tilt the width is `(1.0 + tilt_width)` times the width
+    #[serde(rename = "tilt_width")]
+    pub tilt_width: f64,
+    /// The source of the width along the path
+    #[serde(rename = "width_source")]
+    pub width_source: SmoothWidthSource,
+    /// The line style. Only applies to stroked outlines ( marker strokes and shapes ), not to filled ones
+    #[serde(rename = "line_style")]
+    pub line_style: LineStyle,
+    /// The length of the dashes, relative to the width
+    #[serde(rename = "dash_length")]
+    pub dash_length: f64,
+    /// The length of the gaps between dashes and dots, relative to the width
+    #[serde(rename = "dash_gap")]
+    pub dash_gap: f64,
 }
 
 impl Default for SmoothOptions {
@@ -25,6 +134,13 @@ impl Default for SmoothOptions {
             width: Self::WIDTH_DEFAULT,
             stroke_color: Some(Self::COLOR_DEFAULT),
             fill_color: None,
+            fill_rule: FillRule::default(),
+            fill_textured: false,
+            tilt_width: Self::TILT_WIDTH_DEFAULT,
+            width_source: SmoothWidthSource::default(),
+            line_style: LineStyle::default(),
+            dash_length: Self::DASH_LENGTH_DEFAULT,
+            dash_gap: Self::DASH_GAP_DEFAULT,
         }
     }
 }
@@ -43,6 +159,58 @@ impl SmoothOptions {
         b: 0.0,
         a: 1.0,
     };
+    /// Tilt width default
+    pub const TILT_WIDTH_DEFAULT: f64 = 1.0;
+    /// The speed (in units per second) at which the velocity width source halves the width
+    pub const VELOCITY_REFERENCE: f64 = 500.0;
+    /// The min width factor of the velocity width source, so that very fast strokes don't vanish
+    pub const VELOCITY_WIDTH_FACTOR_MIN: f64 = 0.2;
+    /// Dash length default
+    pub const DASH_LENGTH_DEFAULT: f64 = 3.0;
+    /// Dash gap default
+    pub const DASH_GAP_DEFAULT: f64 = 2.0;
+
+    /// The width scale factor for the width source. The speed is in units per second and is None if it can't be determined,
+    /// in which case the velocity width source falls back to the pressure
+    pub fn width_source_factor(&self, pressure: f64, speed: Option<f64>) -> f64 {
+        match (self.width_source, speed) {
+            (SmoothWidthSource::Pressure, _) | (SmoothWidthSource::Velocity, None) => pressure,
+            (SmoothWidthSource::Velocity, Some(speed)) => (1.0
+                / (1.0 + speed.max(0.0) / Self::VELOCITY_REFERENCE))
+                .max(Self::VELOCITY_WIDTH_FACTOR_MIN),
+            (SmoothWidthSource::Constant, _) => 1.0,
+        }
+    }
+
+    /// The width scale factor for the given stylus tilt
+    pub fn tilt_factor(&self, tilt: na::Vector2<f64>) -> f64 {
+        1.0 + self.tilt_width.max(0.0) * tilt.magnitude().min(1.0)
+    }
+
+    /// The max width, when the stylus is fully tilted
+    pub fn width_max_w_tilt(&self) -> f64 {
+        self.width * self.tilt_factor(na::Vector2::x())
+    }
+
+    /// The value of the svg `stroke-dasharray` attribute for the line style, None for solid lines.
+    /// Round line caps extend dashes by half the width on both ends, so the gaps are widened by the width to compensate.
+    /// Dots are zero-length dashes with round line caps, and square with butt line caps
+    pub fn stroke_dasharray(&self, round_caps: bool) -> Option<String> {
+        let width = self.width;
+        let dash = self.dash_length.max(0.0) * width;
+        let (dot, gap) = if round_caps {
+            (0.0, self.dash_gap.max(0.0) * width + width)
+        } else {
+            (width, self.dash_gap.max(0.0) * width)
+        };
+
+        match self.line_style {
+            LineStyle::Solid => None,
+            LineStyle::Dashed => Some(format!("{} {}", dash, gap)),
+            LineStyle::Dotted => Some(format!("{} {}", dot, gap)),
+            LineStyle::DashDot => Some(format!("{} {} {} {}", dash, gap, dot, gap)),
+        }
+    }
 }
 
 pub fn compose_line(
@@ -560,6 +728,12 @@ pub fn compose_rectangle(
         .set("stroke", color)
         .set("stroke-width", options.width)
         .set("fill", fill)
+        .set(
+            "stroke-dasharray",
+            options
+                .stroke_dasharray(false)
+                .unwrap_or_else(|| String::from("none")),
+        )
         .into()
 }
 
@@ -586,5 +760,304 @@ pub fn compose_ellipse(ellipse: shapes::Ellipse, options: &SmoothOptions) -> ele
         .set("stroke", color)
         .set("stroke-width", options.width)
         .set("fill", fill)
+        .set(
+            "stroke-dasharray",
+            options
+                .stroke_dasharray(false)
+                .unwrap_or_else(|| String::from("none")),
+        )
+        .into()
+}
+
+/// Composes a closed polygon from vertices in the local coordinates of the transform
+pub fn compose_polygon(
+    vertices: &[na::Vector2<f64>],
+    transform: &Transform,
+    options: &SmoothOptions,
+) -> element::Element {
+    let color = if let Some(color) = options.stroke_color {
+        color.to_css_color()
+    } else {
+        String::from("none")
+    };
+    let fill = if let Some(fill) = options.fill_color {
+        fill.to_css_color()
+    } else {
+        String::from("none")
+    };
+
+    let mut commands = Vec::with_capacity(vertices.len() + 1);
+    for (i, vertex) in vertices.iter().enumerate() {
+        if i == 0 {
+            commands.push(path::Command::Move(
+                path::Position::Absolute,
+                path::Parameters::from((vertex[0], vertex[1])),
+            ));
+        } else {
+            commands.push(path::Command::Line(
+                path::Position::Absolute,
+                path::Parameters::from((vertex[0], vertex[1])),
+            ));
+        }
+    }
+    commands.push(path::Command::Close);
+
+    let transform_string = transform.to_svg_transform_attr_str();
+
+    svg::node::element::Path::new()
+        .set("transform", transform_string)
+        .set("d", path::Data::from(commands))
+        .set("stroke", color)
+        .set("stroke-width", options.width)
+        .set("stroke-linejoin", "round")
+        .set("fill", fill)
+        .set("fill-rule", options.fill_rule.as_svg_attr_str())
+        .set(
+            "stroke-dasharray",
+            options
+                .stroke_dasharray(false)
+                .unwrap_or_else(|| String::from("none")),
+        )
+        .into()
+}
+
+pub fn compose_arc(arc: shapes::Arc, options: &SmoothOptions) -> element::Element {
+    let color = if let Some(color) = options.stroke_color {
+        color.to_css_color()
+    } else {
+        String::from("none")
+    };
+    let fill = if let Some(fill) = options.fill_color {
+        fill.to_css_color()
+    } else {
+        String::from("none")
+    };
+
+    let commands = arc
+        .local_cubbezs()
+        .into_iter()
+        .enumerate()
+        .flat_map(|(i, cubbez)| compose_cubbez(cubbez, i == 0, options))
+        .collect::<Vec<path::Command>>();
+
+    let transform_string = arc.transform.to_svg_transform_attr_str();
+
+    svg::node::element::Path::new()
+        .set("transform", transform_string)
+        .set("d", path::Data::from(commands))
+        .set("stroke", color)
+        .set("stroke-width", options.width)
+        .set("fill", fill)
+        .set(
+            "stroke-dasharray",
+            options
+                .stroke_dasharray(false)
+                .unwrap_or_else(|| String::from("none")),
+        )
         .into()
 }
+
+pub fn compose_polyline(polyline: &shapes::Polyline, options: &SmoothOptions) -> element::Element {
+    let color = if let Some(color) = options.stroke_color {
+        color.to_css_color()
+    } else {
+        String::from("none")
+    };
+    // Only closed polylines are filled
+    let fill = match options.fill_color {
+        Some(fill) if polyline.closed => fill.to_css_color(),
+        _ => String::from("none"),
+    };
+
+    let mut commands = Vec::with_capacity(polyline.vertices.len() + 1);
+    for (i, vertex) in polyline.vertices.iter().enumerate() {
+        if i == 0 {
+            commands.push(path::Command::Move(
+                path::Position::Absolute,
+                path::Parameters::from((vertex[0], vertex[1])),
+            ));
+        } else {
+            commands.push(path::Command::Line(
+                path::Position::Absolute,
+                path::Parameters::from((vertex[0], vertex[1])),
+            ));
+        }
+    }
+    if polyline.closed {
+        commands.push(path::Command::Close);
+    }
+
+    svg::node::element::Path::new()
+        .set("d", path::Data::from(commands))
+        .set("stroke", color)
+        .set("stroke-width", options.width)
+        .set("stroke-linejoin", "round")
+        .set("stroke-linecap", "round")
+        .set("fill", fill)
+        .set("fill-rule", options.fill_rule.as_svg_attr_str())
+        .set(
+            "stroke-dasharray",
+            options
+                .stroke_dasharray(true)
+                .unwrap_or_else(|| String::from("none")),
+        )
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn width_grows_with_the_tilt() {
+        let options = SmoothOptions {
+            width: 2.0,
+            tilt_width: 1.5,
+            ..SmoothOptions::default()
+        };
+
+        assert_eq!(options.tilt_factor(na::Vector2::zeros()), 1.0);
+        assert_eq!(options.tilt_factor(na::vector![0.0, -0.5]), 1.75);
+        // The magnitude of diagonal tilts is capped at full tilt
+        assert_eq!(options.tilt_factor(na::vector![1.0, 1.0]), 2.5);
+        assert_eq!(options.width_max_w_tilt(), 5.0);
+
+        let without_tilt_width = SmoothOptions {
+            tilt_width: -1.0,
+            ..options
+        };
+        assert_eq!(without_tilt_width.tilt_factor(na::vector![1.0, 0.0]), 1.0);
+    }
+
+    #[test]
+    fn tilt_width_serde_default() {
+        let options = serde_json::from_str::<SmoothOptions>(r#"{"width": 2.0}"#).unwrap();
+        assert_eq!(options.tilt_width, SmoothOptions::TILT_WIDTH_DEFAULT);
+    }
+
+    #[test]
+    fn width_source_factors() {
+        let options = |width_source| SmoothOptions {
+            width_source,
+            ..SmoothOptions::default()
+        };
+        let pressure = options(SmoothWidthSource::Pressure);
+        let velocity = options(SmoothWidthSource::Velocity);
+        let constant = options(SmoothWidthSource::Constant);
+
+        assert_eq!(pressure.width_source_factor(0.3, Some(1000.0)), 0.3);
+        assert_eq!(constant.width_source_factor(0.3, Some(1000.0)), 1.0);
+
+        assert_eq!(velocity.width_source_factor(0.3, Some(0.0)), 1.0);
+        assert_eq!(
+            velocity.width_source_factor(0.3, Some(SmoothOptions::VELOCITY_REFERENCE)),
+            0.5
+        );
+        // Very fast strokes keep the min width, strokes without timestamps fall back to the pressure
+        assert_eq!(
+            velocity.width_source_factor(0.3, Some(1.0e6)),
+            SmoothOptions::VELOCITY_WIDTH_FACTOR_MIN
+        );
+        assert_eq!(velocity.width_source_factor(0.3, None), 0.3);
+
+        let slower = velocity.width_source_factor(0.3, Some(100.0));
+        let faster = velocity.width_source_factor(0.3, Some(200.0));
+        assert!(faster < slower);
+    }
+
+    #[test]
+    fn width_source_serde() {
+        let options = serde_json::from_str::<SmoothOptions>(r#"{"width": 2.0}"#).unwrap();
+        assert_eq!(options.width_source, SmoothWidthSource::Pressure);
+
+        let options =
+            serde_json::from_str::<SmoothOptions>(r#"{"width_source": "velocity"}"#).unwrap();
+        assert_eq!(options.width_source, SmoothWidthSource::Velocity);
+    }
+
+    #[test]
+    fn dash_arrays_scale_with_the_width() {
+        let options = |line_style| SmoothOptions {
+            width: 2.0,
+            line_style,
+            ..SmoothOptions::default()
+        };
+
+        assert_eq!(options(LineStyle::Solid).stroke_dasharray(true), None);
+        assert_eq!(options(LineStyle::Solid).stroke_dasharray(false), None);
+
+        // Round caps widen the gaps by the width, and draw dots as zero-length dashes
+        assert_eq!(
+            options(LineStyle::Dashed).stroke_dasharray(true).as_deref(),
+            Some("6 6")
+        );
+        assert_eq!(
+            options(LineStyle::Dotted).stroke_dasharray(true).as_deref(),
+            Some("0 6")
+        );
+        assert_eq!(
+            options(LineStyle::DashDot)
+                .stroke_dasharray(true)
+                .as_deref(),
+            Some("6 6 0 6")
+        );
+
+        assert_eq!(
+            options(LineStyle::Dashed)
+                .stroke_dasharray(false)
+                .as_deref(),
+            Some("6 4")
+        );
+        assert_eq!(
+            options(LineStyle::Dotted)
+                .stroke_dasharray(false)
+                .as_deref(),
+            Some("2 4")
+        );
+        assert_eq!(
+            options(LineStyle::DashDot)
+                .stroke_dasharray(false)
+                .as_deref(),
+            Some("6 4 2 4")
+        );
+
+        let negative = SmoothOptions {
+            dash_length: -1.0,
+            dash_gap: -1.0,
+            ..options(LineStyle::Dashed)
+        };
+        assert_eq!(negative.stroke_dasharray(false).as_deref(), Some("0 0"));
+    }
+
+    #[test]
+    fn shapes_are_composed_with_the_dash_array() {
+        let dashed = SmoothOptions {
+            line_style: LineStyle::Dashed,
+            ..SmoothOptions::default()
+        };
+        let rectangle = shapes::Rectangle::default();
+        let ellipse = shapes::Ellipse::default();
+
+        assert!(compose_rectangle(rectangle.clone(), &dashed)
+            .to_string()
+            .contains(r#"stroke-dasharray="3 2""#));
+        assert!(compose_ellipse(ellipse, &dashed)
+            .to_string()
+            .contains(r#"stroke-dasharray="3 2""#));
+        assert!(compose_rectangle(rectangle, &SmoothOptions::default())
+            .to_string()
+            .contains(r#"stroke-dasharray="none""#));
+    }
+
+    #[test]
+    fn line_style_serde_defaults() {
+        let options = serde_json::from_str::<SmoothOptions>(r#"{"width": 2.0}"#).unwrap();
+        assert_eq!(options.line_style, LineStyle::Solid);
+        assert_eq!(options.dash_length, SmoothOptions::DASH_LENGTH_DEFAULT);
+        assert_eq!(options.dash_gap, SmoothOptions::DASH_GAP_DEFAULT);
+
+        let options =
+            serde_json::from_str::<SmoothOptions>(r#"{"line_style": "dash_dot"}"#).unwrap();
+        assert_eq!(options.line_style, LineStyle::DashDot);
+    }
+}
@@ -0,0 +1,200 @@
+use super::color::Color;
+use super::textured::{TexturedDotsDistribution, TexturedDotsShape};
+use crate::compose;
+
+use rand_distr::{Distribution, Uniform};
+use serde::{Deserialize, Serialize};
+use svg::node::element::{self, Element};
+
+/// The Options of how a spray should look
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "spray_options")]
+pub struct SprayOptions {
+    /// An optional seed to generate reproducable strokes
+    #[serde(rename = "seed")]
+    pub seed: Option<u64>,
+    /// The width (the diameter of the spray)
+    #[serde(rename = "width")]
+    pub width: f64,
+    /// The color of the stroke
+    #[serde(rename = "stroke_color")]
+    pub stroke_color: Option<Color>,
+    /// Amount of dots per second
+    #[serde(rename = "density")]
+    pub density: f64,
+    /// the radius of the dots
+    #[serde(rename = "dots_radius")]
+    pub dots_radius: f64,
+    /// the distribution type for the radial falloff around the center
+    #[serde(rename = "distribution")]
+    pub distribution: TexturedDotsDistribution,
+}
+
+impl Default for SprayOptions {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            width: Self::WIDTH_DEFAULT,
+            stroke_color: Some(Self::COLOR_DEFAULT),
+            density: Self::DENSITY_DEFAULT,
+            dots_radius: Self::DOTS_RADIUS_DEFAULT,
+            distribution: TexturedDotsDistribution::default(),
+        }
+    }
+}
+
+impl SprayOptions {
+    /// The default width
+    pub const WIDTH_DEFAULT: f64 = 30.0;
+    /// The default color
+    pub const COLOR_DEFAULT: Color = Color {
+        r: 0.0,
+        g: 0.0,
+        b: 0.0,
+        a: 1.0,
+    };
+    /// Density default
+    pub const DENSITY_DEFAULT: f64 = 500.0;
+    /// Dots radius default
+    pub const DOTS_RADIUS_DEFAULT: f64 = 0.6;
+    /// The max duration of a single burst in seconds, so that long pauses between inputs don't flood the spray with dots
+    pub const BURST_DURATION_MAX: f64 = 1.0;
+}
+
+/// Composes a spray burst around the center, for the given duration in seconds
+pub fn compose_burst(center: na::Vector2<f64>, duration: f64, options: &SprayOptions) -> Element {
+    let mut rng = compose::new_rng_default_pcg64(options.seed);
+
+    let mut group = element::Group::new();
+
+    let radius = options.width * 0.5;
+    let duration = duration.clamp(0.0, SprayOptions::BURST_DURATION_MAX);
+    let n_dots = (duration * options.density).round() as usize;
+
+    if radius <= 0.0 || options.dots_radius <= 0.0 {
+        return group.into();
+    }
+
+    // Ranges for randomization
+    let range_radius = -radius..radius;
+    let distr_angle = Uniform::from(0.0..std::f64::consts::TAU);
+    let distr_dots_r = Uniform::from(options.dots_radius * 0.8..options.dots_radius * 1.25);
+
+    let fill = options
+        .stroke_color
+        .map_or(String::from(""), |color| color.to_css_color());
+
+    for _ in 0..n_dots {
+        // The distribution is symmetrical around the center, so the absolute value is the radial falloff
+        let r = options
+            .distribution
+            .sample_for_range_symmetrical_clipped(&mut rng, range_radius.clone())
+            .abs();
+        let angle = distr_angle.sample(&mut rng);
+        let pos = center + na::vector![angle.cos(), angle.sin()] * r;
+
+        let dots_r = distr_dots_r.sample(&mut rng);
+
+        group = group.add(TexturedDotsShape::Circle.compose_dot(
+            pos,
+            na::vector![dots_r, dots_r],
+            0.0,
+            &fill,
+        ));
+    }
+
+    group.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The value of the attribute of the svg element
+    fn attribute(element: &str, name: &str) -> f64 {
+        let start = element.find(&format!(" {}=\"", name)).unwrap() + name.len() + 3;
+        let len = element[start..].find('"').unwrap();
+        element[start..start + len].parse().unwrap()
+    }
+
+    /// The centers and radii of the dots of the burst
+    fn burst_dots(
+        center: na::Vector2<f64>,
+        duration: f64,
+        options: &SprayOptions,
+    ) -> Vec<(na::Vector2<f64>, f64)> {
+        compose_burst(center, duration, options)
+            .to_string()
+            .split("<circle")
+            .skip(1)
+            .map(|dot| {
+                (
+                    na::vector![attribute(dot, "cx"), attribute(dot, "cy")],
+                    attribute(dot, "r"),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn burst_dots_scale_with_the_duration() {
+        let options = SprayOptions {
+            seed: Some(3),
+            ..SprayOptions::default()
+        };
+        let center = na::vector![50.0, -20.0];
+
+        assert_eq!(burst_dots(center, 0.1, &options).len(), 50);
+        assert_eq!(burst_dots(center, 0.5, &options).len(), 250);
+        // Long pauses are clamped to the max burst duration
+        assert_eq!(
+            burst_dots(center, 10.0, &options).len(),
+            (SprayOptions::BURST_DURATION_MAX * options.density) as usize
+        );
+        assert!(burst_dots(center, -1.0, &options).is_empty());
+    }
+
+    #[test]
+    fn burst_dots_are_inside_the_spray() {
+        for seed in 0..10 {
+            let options = SprayOptions {
+                seed: Some(seed),
+                ..SprayOptions::default()
+            };
+            let center = na::vector![50.0, -20.0];
+            let dots = burst_dots(center, 0.2, &options);
+
+            for (pos, r) in dots {
+                assert!((pos - center).magnitude() <= options.width * 0.5 + 1e-9);
+                assert!((options.dots_radius * 0.8..options.dots_radius * 1.25).contains(&r));
+            }
+        }
+    }
+
+    #[test]
+    fn empty_sprays_have_no_dots() {
+        for options in [
+            SprayOptions {
+                width: 0.0,
+                ..SprayOptions::default()
+            },
+            SprayOptions {
+                dots_radius: 0.0,
+                ..SprayOptions::default()
+            },
+        ] {
+            assert!(burst_dots(na::Vector2::zeros(), 0.5, &options).is_empty());
+        }
+    }
+
+    #[test]
+    fn spray_options_serde_defaults() {
+        let options = serde_json::from_str::<SprayOptions>(r#"{"seed": 42}"#).unwrap();
+
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.width, SprayOptions::WIDTH_DEFAULT);
+        assert_eq!(options.density, SprayOptions::DENSITY_DEFAULT);
+        assert_eq!(options.dots_radius, SprayOptions::DOTS_RADIUS_DEFAULT);
+        assert_eq!(options.distribution, TexturedDotsDistribution::default());
+    }
+}
@@ -0,0 +1,211 @@
+//! Lays out and draws texts without pango, when the engine is built without the `cairo` feature (e.g. for wasm32).
+//! The texts are drawn as svg text elements, which are rendered with the fonts of the svg renderer (e.g. the browser),
+//! so the font metrics here only approximate the metrics of the fonts.
+
+use std::ops::Range;
+
+use svg::node::{self, element};
+
+use super::color::Color;
+
+/// The height of a line, in em
+pub const LINE_HEIGHT: f64 = 1.2;
+/// The height above the baseline, in em
+pub const ASCENT: f64 = 0.8;
+/// The depth below the baseline, in em
+pub const DESCENT: f64 = 0.2;
+/// The offset of the baseline from the top of a line, in em. The glyphs are centered in the line
+pub const BASELINE: f64 = (LINE_HEIGHT - ASCENT - DESCENT) * 0.5 + ASCENT;
+/// The advance of bold chars, relative to regular chars
+const BOLD_ADVANCE_SCALE: f64 = 1.1;
+
+/// The approximated advance of the char, in em. Averaged over common sans-serif fonts
+pub fn char_advance(c: char, bold: bool) -> f64 {
+    let advance = if c.is_whitespace() {
+        0.28
+    } else if c.is_ascii_punctuation() {
+        0.33
+    } else if c.is_ascii_uppercase() {
+        0.66
+    } else if c.is_ascii() {
+        0.52
+    } else if c >= '\u{2E80}' {
+        // CJK and other full width chars
+        1.0
+    } else {
+        0.6
+    };
+
+    if bold {
+        advance * BOLD_ADVANCE_SCALE
+    } else {
+        advance
+    }
+}
+
+/// The approximated width of the text, which should be a single line
+pub fn text_width(text: &str, font_size: f64, bold: bool) -> f64 {
+    text.chars().map(|c| char_advance(c, bold)).sum::<f64>() * font_size
+}
+
+/// The byte ranges of the lines of the text. Paragraphs are wrapped at whitespace to the max width,
+/// words which are wider than the max width are broken between their chars
+pub fn wrap_lines(text: &str, font_size: f64, bold: bool, max_width: f64) -> Vec<Range<usize>> {
+    let mut lines = vec![];
+    let mut paragraph_start = 0;
+
+    for paragraph in text.split('\n') {
+        let mut line_start = paragraph_start;
+        let mut line_width = 0.0;
+        // The byte index after the last whitespace in the line and the width of the line up to it
+        let mut line_break: Option<(usize, f64)> = None;
+
+        for (index, c) in paragraph.char_indices() {
+            let index = paragraph_start + index;
+            let advance = char_advance(c, bold) * font_size;
+
+            if !c.is_whitespace() && index > line_start && line_width + advance > max_width {
+                match line_break.take() {
+                    Some((break_index, break_width)) => {
+                        lines.push(line_start..break_index);
+                        line_start = break_index;
+                        line_width -= break_width;
+                    }
+                    None => {
+                        lines.push(line_start..index);
+                        line_start = index;
+                        line_width = 0.0;
+                    }
+                }
+            }
+
+            line_width += advance;
+            if c.is_whitespace() {
+                line_break = Some((index + c.len_utf8(), line_width));
+            }
+        }
+
+        let paragraph_end = paragraph_start + paragraph.len();
+        lines.push(line_start..paragraph_end);
+        // Skips the newline
+        paragraph_start = paragraph_end + 1;
+    }
+
+    lines
+}
+
+/// The font of a svg text
+#[derive(Debug, Clone)]
+pub struct TextFont {
+    pub family: String,
+    pub size: f64,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+}
+
+impl Default for TextFont {
+    fn default() -> Self {
+        Self {
+            family: String::from("Sans"),
+            size: 12.0,
+            bold: false,
+            italic: false,
+            underline: false,
+            strikethrough: false,
+        }
+    }
+}
+
+/// A svg text element of the line, with the start of its baseline at the position
+pub fn compose_line(
+    line: &str,
+    pos: na::Vector2<f64>,
+    font: &TextFont,
+    color: Color,
+) -> element::Text {
+    let mut decorations = vec![];
+    if font.underline {
+        decorations.push("underline");
+    }
+    if font.strikethrough {
+        decorations.push("line-through");
+    }
+    let decoration = if decorations.is_empty() {
+        String::from("none")
+    } else {
+        decorations.join(" ")
+    };
+
+    element::Text::new()
+        .set("x", pos[0])
+        .set("y", pos[1])
+        .set("font-family", font.family.as_str())
+        .set("font-size", font.size)
+        .set("font-weight", if font.bold { "bold" } else { "normal" })
+        .set("font-style", if font.italic { "italic" } else { "normal" })
+        .set("text-decoration", decoration)
+        .set("fill", color.to_css_color())
+        .set("xml:space", "preserve")
+        .add(node::Text::new(escape(line)))
+}
+
+/// Escapes the chars which are markup in xml
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn width_grows_with_the_chars_and_the_weight() {
+        assert_eq!(text_width("", 10.0, false), 0.0);
+        assert!(text_width("Hello", 10.0, false) > text_width("Hell", 10.0, false));
+        assert!(text_width("Hello", 10.0, true) > text_width("Hello", 10.0, false));
+        assert!((text_width("ab", 20.0, false) - 2.0 * text_width("ab", 10.0, false)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn paragraphs_are_wrapped_at_whitespace() {
+        let text = "one two three\n\nfour";
+        let width = text_width("one two ", 10.0, false);
+        let lines = wrap_lines(text, 10.0, false, width)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect::<Vec<&str>>();
+
+        assert_eq!(lines, vec!["one two ", "three", "", "four"]);
+    }
+
+    #[test]
+    fn long_words_are_broken_between_chars() {
+        let text = "abcdef";
+        let lines = wrap_lines(text, 10.0, false, text_width("abc", 10.0, false) + 1.0)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect::<Vec<&str>>();
+
+        assert_eq!(lines, vec!["abc", "def"]);
+    }
+
+    #[test]
+    fn lines_are_escaped_and_decorated() {
+        let font = TextFont {
+            underline: true,
+            strikethrough: true,
+            ..TextFont::default()
+        };
+        let text =
+            compose_line("a < b & c", na::vector![1.0, 2.0], &font, Color::BLACK).to_string();
+
+        assert!(text.contains("a &lt; b &amp; c"));
+        assert!(text.contains(r#"text-decoration="underline line-through""#));
+        assert!(text.contains(r#"x="1""#));
+        assert!(text.contains(r#"y="2""#));
+    }
+}
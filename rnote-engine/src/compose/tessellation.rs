@@ -0,0 +1,200 @@
+//! Tessellates shapes into primitives which GSK draws on its own. These are rendered by the GPU, instead of going through the svg renderer backend and uploading the images.
+
+use super::color::Color;
+
+#[cfg(feature = "ui")]
+use gtk4::{graphene, gsk, prelude::*, Snapshot};
+use p2d::bounding_volume::{BoundingVolume, AABB};
+
+/// A primitive shape which GSK can draw on its own
+#[derive(Debug, Clone, Copy)]
+pub enum Primitive {
+    /// An ellipse with the radii, rotated by the angle (in radians) around its center
+    Ellipse {
+        center: na::Vector2<f64>,
+        radii: na::Vector2<f64>,
+        rotation_angle: f64,
+    },
+    /// A rectangle with the half extents, rotated by the angle (in radians) around its center
+    Rectangle {
+        center: na::Vector2<f64>,
+        half_extents: na::Vector2<f64>,
+        rotation_angle: f64,
+    },
+    /// A line with round caps
+    Capsule {
+        start: na::Vector2<f64>,
+        end: na::Vector2<f64>,
+        width: f64,
+    },
+}
+
+impl Primitive {
+    /// The bounds, which might be larger than the primitive when it is rotated
+    pub fn bounds(&self) -> AABB {
+        match *self {
+            Self::Ellipse { center, radii, .. } => AABB::from_half_extents(
+                na::Point2::from(center),
+                na::Vector2::from_element(radii.max()),
+            ),
+            Self::Rectangle {
+                center,
+                half_extents,
+                ..
+            } => AABB::from_half_extents(
+                na::Point2::from(center),
+                na::Vector2::from_element(half_extents.magnitude()),
+            ),
+            Self::Capsule { start, end, width } => AABB::new(
+                na::Point2::from(start.inf(&end)),
+                na::Point2::from(start.sup(&end)),
+            )
+            .loosened(width * 0.5),
+        }
+    }
+
+    pub fn translate(&mut self, offset: na::Vector2<f64>) {
+        match self {
+            Self::Ellipse { center, .. } | Self::Rectangle { center, .. } => *center += offset,
+            Self::Capsule { start, end, .. } => {
+                *start += offset;
+                *end += offset;
+            }
+        }
+    }
+
+    /// Appends the primitive to the snapshot, in the coordinate space of the snapshot
+    #[cfg(feature = "ui")]
+    fn append_to_snapshot(&self, snapshot: &Snapshot, color: Color) {
+        // (center, half extents, corner radii, rotation angle) of the rounded rectangle the primitive is drawn as
+        let (center, half_extents, corner_radii, rotation_angle) = match *self {
+            Self::Ellipse {
+                center,
+                radii,
+                rotation_angle,
+            } => (center, radii, radii, rotation_angle),
+            Self::Rectangle {
+                center,
+                half_extents,
+                rotation_angle,
+            } => (center, half_extents, na::Vector2::zeros(), rotation_angle),
+            Self::Capsule { start, end, width } => {
+                let vec = end - start;
+                let radius = width * 0.5;
+
+                (
+                    (start + end) * 0.5,
+                    na::vector![vec.magnitude() * 0.5 + radius, radius],
+                    na::Vector2::from_element(radius),
+                    vec[1].atan2(vec[0]),
+                )
+            }
+        };
+
+        let rect = graphene::Rect::new(
+            -half_extents[0] as f32,
+            -half_extents[1] as f32,
+            2.0 * half_extents[0] as f32,
+            2.0 * half_extents[1] as f32,
+        );
+        let corner = graphene::Size::new(corner_radii[0] as f32, corner_radii[1] as f32);
+
+        snapshot.save();
+        snapshot.translate(&graphene::Point::new(center[0] as f32, center[1] as f32));
+        // Axis aligned primitives stay on the fast paths of the GSK renderers
+        if rotation_angle != 0.0 {
+            snapshot.rotate(rotation_angle.to_degrees() as f32);
+        }
+
+        if corner_radii[0] > 0.0 && corner_radii[1] > 0.0 {
+            snapshot
+                .push_rounded_clip(&gsk::RoundedRect::new(rect, corner, corner, corner, corner));
+            snapshot.append_color(&color.to_gdk(), &rect);
+            snapshot.pop();
+        } else {
+            snapshot.append_color(&color.to_gdk(), &rect);
+        }
+
+        snapshot.restore();
+    }
+}
+
+/// A shape tessellated into primitives
+#[derive(Debug, Clone)]
+pub struct Tessellation {
+    /// The primitives with their colors
+    pub primitives: Vec<(Primitive, Color)>,
+    /// The opacity the primitives are composited with.
+    /// Translucent strokes have opaque primitives composited with the opacity of the stroke, so the overlapping primitives don't get darker
+    pub opacity: f64,
+}
+
+impl Default for Tessellation {
+    fn default() -> Self {
+        Self {
+            primitives: vec![],
+            opacity: 1.0,
+        }
+    }
+}
+
+impl Tessellation {
+    pub fn new(opacity: f64) -> Self {
+        Self {
+            primitives: vec![],
+            opacity,
+        }
+    }
+
+    pub fn push(&mut self, primitive: Primitive, color: Color) {
+        self.primitives.push((primitive, color));
+    }
+
+    /// Appends the primitives of the other tessellation, which should have the same opacity
+    pub fn append(&mut self, mut other: Self) {
+        self.primitives.append(&mut other.primitives);
+    }
+
+    pub fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.primitives
+            .iter_mut()
+            .for_each(|(primitive, _)| primitive.translate(offset));
+    }
+
+    /// The bounds of all primitives. None if there are none
+    pub fn bounds(&self) -> Option<AABB> {
+        self.primitives
+            .iter()
+            .map(|(primitive, _)| primitive.bounds())
+            .reduce(|bounds, next| bounds.merged(&next))
+    }
+
+    /// Generates the rendernode, in the coordinate space of the surface like the rendernodes of the images. None if there are no primitives
+    #[cfg(feature = "ui")]
+    pub fn to_rendernode(&self, zoom: f64) -> Option<gsk::RenderNode> {
+        let rendernode = self.to_primitives_rendernode(zoom)?;
+
+        if self.opacity < 1.0 {
+            Some(gsk::OpacityNode::new(&rendernode, self.opacity as f32).upcast())
+        } else {
+            Some(rendernode)
+        }
+    }
+
+    /// Generates the rendernode of the primitives without the opacity, to be composited with the primitives of other tessellations first
+    #[cfg(feature = "ui")]
+    pub fn to_primitives_rendernode(&self, zoom: f64) -> Option<gsk::RenderNode> {
+        if self.primitives.is_empty() {
+            return None;
+        }
+
+        let snapshot = Snapshot::new();
+        snapshot.scale(zoom as f32, zoom as f32);
+
+        for (primitive, color) in self.primitives.iter() {
+            primitive.append_to_snapshot(&snapshot, *color);
+        }
+
+        snapshot.to_node()
+    }
+}
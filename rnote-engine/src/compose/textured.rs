@@ -1,26 +1,37 @@
 use std::ops::Range;
 
-use super::{color::Color, curves};
+use super::smooth::{SmoothOptions, SmoothWidthSource};
+use super::{color::Color, curves, shapes};
 use crate::compose;
+use crate::utils;
 
-use gtk4::glib;
+use geo::prelude::*;
+use p2d::bounding_volume::AABB;
 use rand_distr::{Distribution, Uniform};
 use serde::{Deserialize, Serialize};
 use svg::node::element::{self, Element};
 
 /// The distribution for the spread of dots across the width of the textured stroke
-#[derive(Debug, Eq, PartialEq, Clone, Copy, glib::Enum, Serialize, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
 #[repr(u32)]
-#[enum_type(name = "TexturedDotsDistribution")]
+#[cfg_attr(feature = "ui", enum_type(name = "TexturedDotsDistribution"))]
 pub enum TexturedDotsDistribution {
-    #[enum_value(name = "Uniform", nick = "uniform")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Uniform", nick = "uniform"))]
     Uniform = 0,
-    #[enum_value(name = "Normal", nick = "normal")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Normal", nick = "normal"))]
     Normal,
-    #[enum_value(name = "Exponential", nick = "exponential")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Exponential", nick = "exponential"))]
     Exponential,
-    #[enum_value(name = "ReverseExponential", nick = "reverse-exponential")]
+    #[cfg_attr(
+        feature = "ui",
+        enum_value(name = "ReverseExponential", nick = "reverse-exponential")
+    )]
     ReverseExponential,
+    #[cfg_attr(feature = "ui", enum_value(name = "Clumped", nick = "clumped"))]
+    Clumped,
+    #[cfg_attr(feature = "ui", enum_value(name = "BlueNoise", nick = "blue-noise"))]
+    BlueNoise,
 }
 
 impl Default for TexturedDotsDistribution {
@@ -30,15 +41,26 @@ impl Default for TexturedDotsDistribution {
 }
 
 impl TexturedDotsDistribution {
+    /// The average amount of dots per cluster for the clumped distribution
+    const CLUMPED_DOTS_PER_CLUSTER: f64 = 8.0;
+    /// The standard deviation of the dots around their cluster center, relative to the smaller half extent of the sampled area
+    const CLUMPED_CLUSTER_STD_DEV_FACTOR: f64 = 0.3;
+    /// The min spacing between the dots for the blue noise distribution, relative to the average spacing of the dots
+    const BLUE_NOISE_SPACING_FACTOR: f64 = 0.75;
+    /// The amount of attempts per dot to find a position with enough spacing for the blue noise distribution
+    const BLUE_NOISE_ATTEMPTS_PER_DOT: usize = 30;
+
     /// Samples a value for the given range, symmetrical to the mid of the range. For distributions that are open ended, samples are clipped to the range
-    fn sample_for_range_symmetrical_clipped<G: rand::Rng + ?Sized>(
+    pub(super) fn sample_for_range_symmetrical_clipped<G: rand::Rng + ?Sized>(
         &self,
         rng: &mut G,
         range: Range<f64>,
     ) -> f64 {
         let sample = match self {
-            Self::Uniform => rand_distr::Uniform::from(range.clone()).sample(rng),
-            Self::Normal => {
+            // In one dimension there is no spacing to maintain, so blue noise falls back to the uniform distribution
+            Self::Uniform | Self::BlueNoise => rand_distr::Uniform::from(range.clone()).sample(rng),
+            // In one dimension the clumped distribution falls back to the normal distribution
+            Self::Normal | Self::Clumped => {
                 // setting the mean to the mid of the range
                 let mean = (range.end + range.start) / 2.0;
                 // the standard deviation
@@ -80,11 +102,260 @@ impl TexturedDotsDistribution {
             sample
         }
     }
+
+    /// Samples the cluster centers for the area spanned by the given ranges. Only the clumped distribution has clusters, for all others no samples are drawn from the rng.
+    fn sample_cluster_centers<G: rand::Rng + ?Sized>(
+        &self,
+        rng: &mut G,
+        n_dots: usize,
+        range_x: Range<f64>,
+        range_y: Range<f64>,
+    ) -> Vec<na::Vector2<f64>> {
+        match self {
+            Self::Clumped => {
+                let distr_x = Uniform::from(range_x);
+                let distr_y = Uniform::from(range_y);
+                let n_clusters =
+                    ((n_dots as f64 / Self::CLUMPED_DOTS_PER_CLUSTER).ceil() as usize).max(1);
+
+                (0..n_clusters)
+                    .map(|_| na::vector![distr_x.sample(rng), distr_y.sample(rng)])
+                    .collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Samples up to n_dots positions in the area spanned by the given ranges, with a min spacing between them (Poisson disk sampling with dart throwing).
+    /// Might return less positions when no more free space is found.
+    fn sample_blue_noise_positions<G: rand::Rng + ?Sized>(
+        rng: &mut G,
+        n_dots: usize,
+        range_x: Range<f64>,
+        range_y: Range<f64>,
+    ) -> Vec<na::Vector2<f64>> {
+        let extents = na::vector![range_x.end - range_x.start, range_y.end - range_y.start];
+        if n_dots == 0 || extents[0] <= 0.0 || extents[1] <= 0.0 {
+            return vec![];
+        }
+
+        let min_spacing =
+            (extents[0] * extents[1] / n_dots as f64).sqrt() * Self::BLUE_NOISE_SPACING_FACTOR;
+        // A grid with cells small enough to hold at most one position
+        let cell_size = min_spacing / std::f64::consts::SQRT_2;
+        let grid_dims = na::vector![
+            (extents[0] / cell_size).ceil().max(1.0) as usize,
+            (extents[1] / cell_size).ceil().max(1.0) as usize
+        ];
+        let mut grid: Vec<Option<usize>> = vec![None; grid_dims[0] * grid_dims[1]];
+        let cell_for_pos = |pos: na::Vector2<f64>| {
+            (
+                (((pos[0] - range_x.start) / cell_size) as usize).min(grid_dims[0] - 1),
+                (((pos[1] - range_y.start) / cell_size) as usize).min(grid_dims[1] - 1),
+            )
+        };
+
+        let distr_x = Uniform::from(range_x.clone());
+        let distr_y = Uniform::from(range_y.clone());
+        let mut positions: Vec<na::Vector2<f64>> = Vec::with_capacity(n_dots);
+
+        for _ in 0..n_dots * Self::BLUE_NOISE_ATTEMPTS_PER_DOT {
+            if positions.len() >= n_dots {
+                break;
+            }
+
+            let candidate = na::vector![distr_x.sample(rng), distr_y.sample(rng)];
+            let (cell_x, cell_y) = cell_for_pos(candidate);
+
+            // Positions closer than the min spacing can only be in the neighbouring two cells in each direction
+            let too_close = (cell_x.saturating_sub(2)..(cell_x + 3).min(grid_dims[0])).any(|x| {
+                (cell_y.saturating_sub(2)..(cell_y + 3).min(grid_dims[1])).any(|y| {
+                    grid[y * grid_dims[0] + x].map_or(false, |i: usize| {
+                        (positions[i] - candidate).magnitude() < min_spacing
+                    })
+                })
+            });
+
+            if !too_close {
+                grid[cell_y * grid_dims[0] + cell_x] = Some(positions.len());
+                positions.push(candidate);
+            }
+        }
+
+        positions
+    }
+
+    /// Samples a position in the area spanned by the given ranges. The x-axis is along the stroke, the y-axis across its width.
+    /// The cluster centers are expected to be generated with `sample_cluster_centers()`.
+    fn sample_position_clipped<G: rand::Rng + ?Sized>(
+        &self,
+        rng: &mut G,
+        range_x: Range<f64>,
+        range_y: Range<f64>,
+        cluster_centers: &[na::Vector2<f64>],
+    ) -> na::Vector2<f64> {
+        match self {
+            Self::Clumped if !cluster_centers.is_empty() => {
+                let std_dev = ((range_x.end - range_x.start) / 2.0)
+                    .min((range_y.end - range_y.start) / 2.0)
+                    * Self::CLUMPED_CLUSTER_STD_DEV_FACTOR;
+                let distr_offset = rand_distr::Normal::new(0.0, std_dev.max(f64::EPSILON)).unwrap();
+
+                let center = cluster_centers
+                    [rand_distr::Uniform::from(0..cluster_centers.len()).sample(rng)];
+                let mut pos =
+                    center + na::vector![distr_offset.sample(rng), distr_offset.sample(rng)];
+
+                // Uniform fallback if sample is out of range
+                if !range_x.contains(&pos[0]) {
+                    pos[0] = rand_distr::Uniform::from(range_x).sample(rng);
+                }
+                if !range_y.contains(&pos[1]) {
+                    pos[1] = rand_distr::Uniform::from(range_y).sample(rng);
+                }
+
+                pos
+            }
+            _ => {
+                let x_pos = rand_distr::Uniform::from(range_x).sample(rng);
+                let y_pos = self.sample_for_range_symmetrical_clipped(rng, range_y);
+
+                na::vector![x_pos, y_pos]
+            }
+        }
+    }
+}
+
+/// The shape of the dots of the textured stroke. The shapes are sized by the radii of the dots, and rotated along the stroke direction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "textured_dots_shape")]
+pub enum TexturedDotsShape {
+    /// An ellipse with the radii
+    #[serde(rename = "ellipse")]
+    Ellipse,
+    /// A circle with the x radius
+    #[serde(rename = "circle")]
+    Circle,
+    /// A square with the x radius as half side length
+    #[serde(rename = "square")]
+    Square,
+    /// A triangle inside the box spanned by the radii, pointing in the stroke direction
+    #[serde(rename = "triangle")]
+    Triangle,
+    /// A short dash with the x radius as half length and the y radius as half thickness
+    #[serde(rename = "dash")]
+    Dash,
+    /// A custom glyph, given as SVG path data in the coordinate space `-1.0..1.0` for both axes. It is scaled by the radii
+    #[serde(rename = "custom")]
+    Custom {
+        #[serde(rename = "path_data")]
+        path_data: String,
+    },
+}
+
+impl Default for TexturedDotsShape {
+    fn default() -> Self {
+        Self::Ellipse
+    }
+}
+
+impl TexturedDotsShape {
+    /// Composes a single dot at the position, with the radii and the rotation angle (in radians)
+    pub(super) fn compose_dot(
+        &self,
+        pos: na::Vector2<f64>,
+        radii: na::Vector2<f64>,
+        rotation_angle: f64,
+        color: &str,
+    ) -> Element {
+        let rotate_transform = format!(
+            "rotate({},{},{})",
+            rotation_angle.to_degrees(),
+            pos[0],
+            pos[1]
+        );
+
+        match self {
+            Self::Ellipse => element::Ellipse::new()
+                .set("transform", rotate_transform)
+                .set("cx", pos[0])
+                .set("cy", pos[1])
+                .set("rx", radii[0])
+                .set("ry", radii[1])
+                .set("fill", color)
+                .into(),
+            Self::Circle => element::Circle::new()
+                .set("cx", pos[0])
+                .set("cy", pos[1])
+                .set("r", radii[0])
+                .set("fill", color)
+                .into(),
+            Self::Square => element::Rectangle::new()
+                .set("transform", rotate_transform)
+                .set("x", pos[0] - radii[0])
+                .set("y", pos[1] - radii[0])
+                .set("width", 2.0 * radii[0])
+                .set("height", 2.0 * radii[0])
+                .set("fill", color)
+                .into(),
+            Self::Triangle => element::Polygon::new()
+                .set("transform", rotate_transform)
+                .set(
+                    "points",
+                    format!(
+                        "{},{} {},{} {},{}",
+                        pos[0] + radii[0],
+                        pos[1],
+                        pos[0] - radii[0],
+                        pos[1] - radii[1],
+                        pos[0] - radii[0],
+                        pos[1] + radii[1]
+                    ),
+                )
+                .set("fill", color)
+                .into(),
+            Self::Dash => element::Line::new()
+                .set("transform", rotate_transform)
+                .set("x1", pos[0] - radii[0])
+                .set("y1", pos[1])
+                .set("x2", pos[0] + radii[0])
+                .set("y2", pos[1])
+                .set("stroke", color)
+                .set("stroke-width", 2.0 * radii[1])
+                .set("stroke-linecap", "round")
+                .into(),
+            Self::Custom { path_data } => element::Path::new()
+                .set(
+                    "transform",
+                    format!(
+                        "translate({},{}) rotate({}) scale({},{})",
+                        pos[0],
+                        pos[1],
+                        rotation_angle.to_degrees(),
+                        radii[0],
+                        radii[1]
+                    ),
+                )
+                .set("d", path_data.as_str())
+                .set("fill", color)
+                .into(),
+        }
+    }
+}
+
+/// A single dot of a textured shape
+#[derive(Debug, Clone, Copy)]
+pub struct TexturedDot {
+    pub pos: na::Vector2<f64>,
+    pub radii: na::Vector2<f64>,
+    /// The rotation angle in radians
+    pub rotation_angle: f64,
+    pub color: Option<Color>,
 }
 
 /// The Options of how a textured shape should look
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "textured_options")]
 pub struct TexturedOptions {
     /// An optional seed to generate reproducable strokes
@@ -105,6 +376,24 @@ pub struct TexturedOptions {
     /// the distribution type
     #[serde(rename = "distribution")]
     pub distribution: TexturedDotsDistribution,
+    /// the shape of the dots
+    #[serde(rename = "dots_shape")]
+    pub dots_shape: TexturedDotsShape,
+    /// the opacity jitter of the dots. Every dot samples its alpha between `(1.0 - alpha_jitter)` and `1.0` times the alpha of the stroke color
+    #[serde(rename = "alpha_jitter")]
+    pub alpha_jitter: f64,
+    /// whether the density of the dots scales with the pressure. Off by default, so that the strokes of documents saved before it existed keep their dots
+    #[serde(rename = "pressure_density")]
+    pub pressure_density: bool,
+    /// whether the radii of the dots scale with the pressure
+    #[serde(rename = "pressure_radii")]
+    pub pressure_radii: bool,
+    /// How much the dots elongate in the direction of the stylus tilt. At full tilt the x radius is `(1.0 + tilt_elongation)` times the radius
+    #[serde(rename = "tilt_elongation")]
+    pub tilt_elongation: f64,
+    /// whether every segment advances the seed of the previous segment, instead of deriving its own. Set for the strokes of files saved before file format version 16, so that they keep their dots
+    #[serde(rename = "legacy_seeding")]
+    pub legacy_seeding: bool,
 }
 
 impl Default for TexturedOptions {
@@ -116,6 +405,12 @@ impl Default for TexturedOptions {
             stroke_color: Some(Self::COLOR_DEFAULT),
             radii: Self::RADII_DEFAULT,
             distribution: TexturedDotsDistribution::default(),
+            dots_shape: TexturedDotsShape::default(),
+            alpha_jitter: Self::ALPHA_JITTER_DEFAULT,
+            pressure_density: false,
+            pressure_radii: false,
+            tilt_elongation: Self::TILT_ELONGATION_DEFAULT,
+            legacy_seeding: false,
         }
     }
 }
@@ -134,9 +429,80 @@ impl TexturedOptions {
     pub const DENSITY_DEFAULT: f64 = 5.0;
     /// Radii default
     pub const RADII_DEFAULT: na::Vector2<f64> = na::vector![2.0, 0.3];
+    /// Alpha jitter min
+    pub const ALPHA_JITTER_MIN: f64 = 0.0;
+    /// Alpha jitter max
+    pub const ALPHA_JITTER_MAX: f64 = 1.0;
+    /// Alpha jitter default
+    pub const ALPHA_JITTER_DEFAULT: f64 = 0.0;
+    /// The pressure at which the density and radii are not scaled. Matches the pressure of input devices without pressure support
+    pub const PRESSURE_REFERENCE: f64 = 0.5;
+    /// Tilt elongation default
+    pub const TILT_ELONGATION_DEFAULT: f64 = 1.0;
+
+    /// The seed of the segment at the given index
+    pub fn segment_seed(&self, segment_index: usize) -> Option<u64> {
+        self.seed.map(|seed| {
+            if self.legacy_seeding {
+                (0..=segment_index).fold(seed, |seed, _| utils::seed_advance(seed))
+            } else {
+                utils::seed_for_segment(seed, segment_index)
+            }
+        })
+    }
+
+    /// The scale factor for the given pressure
+    fn pressure_factor(pressure: f64) -> f64 {
+        pressure.max(0.0) / Self::PRESSURE_REFERENCE
+    }
+
+    /// The options of a solid stroke approximating the texture, for rendering it when zoomed far out where the dots can't be told apart.
+    /// The color is as translucent as the dots cover the area of the stroke on average, with their overlaps and mean alpha jitter
+    pub fn simplified_smooth_options(&self) -> SmoothOptions {
+        let dot_area = std::f64::consts::PI * self.radii[0] * self.radii[1];
+        // The density is the amount of dots per 10x10 area
+        let dots_per_area = self.density.max(0.0) / 100.0;
+        // The fraction of the area covered by at least one of the randomly placed dots
+        let coverage = 1.0 - (-dots_per_area * dot_area).exp();
+        let alpha_factor = coverage * (1.0 - self.alpha_jitter.clamp(0.0, 1.0) * 0.5);
+
+        SmoothOptions {
+            seed: self.seed,
+            width: self.width,
+            stroke_color: self.stroke_color.map(|color| Color {
+                a: color.a * alpha_factor,
+                ..color
+            }),
+            // The dots spread over the width scaled by the pressure
+            width_source: SmoothWidthSource::Pressure,
+            tilt_width: 0.0,
+            ..SmoothOptions::default()
+        }
+    }
+}
+
+/// Composes a textured line. The pressures are the pressures at the start and end of the line, the tilt is the mean stylus tilt along the line
+pub fn compose_line(
+    line: curves::Line,
+    width: f64,
+    pressures: (f64, f64),
+    tilt: na::Vector2<f64>,
+    options: &TexturedOptions,
+) -> Element {
+    compose_dots(
+        &gen_dots_line(line, width, pressures, tilt, options),
+        options,
+    )
 }
 
-pub fn compose_line(line: curves::Line, width: f64, options: &TexturedOptions) -> Element {
+/// Generates the dots of a textured line, see `compose_line()`
+pub fn gen_dots_line(
+    line: curves::Line,
+    width: f64,
+    pressures: (f64, f64),
+    tilt: na::Vector2<f64>,
+    options: &TexturedOptions,
+) -> Vec<TexturedDot> {
     let mut rng = compose::new_rng_default_pcg64(options.seed);
 
     let rect = line.line_w_width_to_rect(width);
@@ -145,57 +511,906 @@ pub fn compose_line(line: curves::Line, width: f64, options: &TexturedOptions) -
     // Ranges for randomization
     let range_x = -rect.cuboid.half_extents[0]..rect.cuboid.half_extents[0];
     let range_y = -rect.cuboid.half_extents[1]..rect.cuboid.half_extents[1];
+
+    let angle =
+        na::Rotation2::rotation_between(&na::Vector2::x(), &(line.end - line.start)).angle();
+
+    gen_dots(
+        &mut rng,
+        area,
+        range_x,
+        range_y,
+        pressures,
+        tilt,
+        options,
+        |local_pos| {
+            Some((
+                (rect.transform.transform * na::Point2::from(local_pos)).coords,
+                angle,
+            ))
+        },
+    )
+}
+
+/// Composes a textured quadratic bezier curve. The dots are distributed along the arc length of the curve. The pressures are the pressures at the start and end of the curve, the tilt is the mean stylus tilt along the curve
+pub fn compose_quadbez(
+    quadbez: curves::QuadBezier,
+    width: f64,
+    pressures: (f64, f64),
+    tilt: na::Vector2<f64>,
+    options: &TexturedOptions,
+) -> Element {
+    compose_dots(
+        &gen_dots_quadbez(quadbez, width, pressures, tilt, options),
+        options,
+    )
+}
+
+/// Generates the dots of a textured quadratic bezier curve, see `compose_quadbez()`
+pub fn gen_dots_quadbez(
+    quadbez: curves::QuadBezier,
+    width: f64,
+    pressures: (f64, f64),
+    tilt: na::Vector2<f64>,
+    options: &TexturedOptions,
+) -> Vec<TexturedDot> {
+    let control_polygon_len =
+        (quadbez.cp - quadbez.start).magnitude() + (quadbez.end - quadbez.cp).magnitude();
+
+    let lines = curves::approx_quadbez_with_lines(quadbez, n_splits_for_len(control_polygon_len));
+
+    gen_dots_lines_arclength(&lines, width, pressures, tilt, options)
+}
+
+/// Composes a textured cubic bezier curve. The dots are distributed along the arc length of the curve. The pressures are the pressures at the start and end of the curve, the tilt is the mean stylus tilt along the curve
+pub fn compose_cubbez(
+    cubbez: curves::CubicBezier,
+    width: f64,
+    pressures: (f64, f64),
+    tilt: na::Vector2<f64>,
+    options: &TexturedOptions,
+) -> Element {
+    compose_dots(
+        &gen_dots_cubbez(cubbez, width, pressures, tilt, options),
+        options,
+    )
+}
+
+/// Generates the dots of a textured cubic bezier curve, see `compose_cubbez()`
+pub fn gen_dots_cubbez(
+    cubbez: curves::CubicBezier,
+    width: f64,
+    pressures: (f64, f64),
+    tilt: na::Vector2<f64>,
+    options: &TexturedOptions,
+) -> Vec<TexturedDot> {
+    let control_polygon_len = (cubbez.cp1 - cubbez.start).magnitude()
+        + (cubbez.cp2 - cubbez.cp1).magnitude()
+        + (cubbez.end - cubbez.cp2).magnitude();
+
+    let lines = curves::approx_cubbez_with_lines(cubbez, n_splits_for_len(control_polygon_len));
+
+    gen_dots_lines_arclength(&lines, width, pressures, tilt, options)
+}
+
+/// The approximate length of the line segments when approximating curves for the arc length parameterization
+const ARCLENGTH_APPROX_SEGMENT_LEN: f64 = 2.0;
+/// The max amount of line segments when approximating curves for the arc length parameterization
+const ARCLENGTH_APPROX_MAX_SPLITS: i32 = 256;
+
+/// the amount of splits for approximating a curve with the given (estimated) length
+pub(super) fn n_splits_for_len(len: f64) -> i32 {
+    ((len / ARCLENGTH_APPROX_SEGMENT_LEN).ceil() as i32).clamp(1, ARCLENGTH_APPROX_MAX_SPLITS)
+}
+
+/// Generates dots along the consecutive lines approximating a curve. The local x coordinate is the arc length, the y coordinate the offset along the normal
+fn gen_dots_lines_arclength(
+    lines: &[curves::Line],
+    width: f64,
+    pressures: (f64, f64),
+    tilt: na::Vector2<f64>,
+    options: &TexturedOptions,
+) -> Vec<TexturedDot> {
+    let mut rng = compose::new_rng_default_pcg64(options.seed);
+
+    // The accumulated arc length at the end of each line
+    let mut arclengths = Vec::with_capacity(lines.len());
+    let mut total_len = 0.0;
+    for line in lines.iter() {
+        total_len += (line.end - line.start).magnitude();
+        arclengths.push(total_len);
+    }
+
+    if total_len <= 0.0 || width <= 0.0 {
+        return vec![];
+    }
+
+    // Ranges for randomization
+    let range_x = 0.0..total_len;
+    let range_y = -width / 2.0..width / 2.0;
+
+    let area = total_len * width;
+
+    gen_dots(
+        &mut rng,
+        area,
+        range_x,
+        range_y,
+        pressures,
+        tilt,
+        options,
+        |local_pos| {
+            // Find the line containing the arc length
+            let i = arclengths
+                .partition_point(|&len| len < local_pos[0])
+                .min(lines.len() - 1);
+            let line = lines[i];
+            let line_vec = line.end - line.start;
+            let line_len = line_vec.magnitude();
+            let line_start_len = arclengths[i] - line_len;
+
+            let t = if line_len > 0.0 {
+                (local_pos[0] - line_start_len) / line_len
+            } else {
+                0.0
+            };
+            let tangent = if line_len > 0.0 {
+                line_vec / line_len
+            } else {
+                na::Vector2::x()
+            };
+            let normal = na::vector![-tangent[1], tangent[0]];
+
+            Some((
+                line.start + line_vec * t + normal * local_pos[1],
+                na::Rotation2::rotation_between(&na::Vector2::x(), &tangent).angle(),
+            ))
+        },
+    )
+}
+
+/// Composes dots filling the closed polygon given by its vertices. The distribution is applied along the y-axis of the polygon bounds
+pub fn compose_fill_polygon(polygon: &[na::Vector2<f64>], options: &TexturedOptions) -> Element {
+    compose_dots(&gen_dots_fill_polygon(polygon, options), options)
+}
+
+/// Generates the dots filling a closed polygon, see `compose_fill_polygon()`
+pub fn gen_dots_fill_polygon(
+    polygon: &[na::Vector2<f64>],
+    options: &TexturedOptions,
+) -> Vec<TexturedDot> {
+    let mut rng = compose::new_rng_default_pcg64(options.seed);
+
+    if polygon.len() < 3 {
+        return vec![];
+    }
+
+    let mut coords = polygon
+        .iter()
+        .map(|vertex| geo::Coordinate {
+            x: vertex[0],
+            y: vertex[1],
+        })
+        .collect::<Vec<geo::Coordinate<f64>>>();
+    coords.push(coords[0]);
+    let geo_polygon = geo::Polygon::new(coords.into(), vec![]);
+
+    let mut bounds = AABB::new_invalid();
+    polygon
+        .iter()
+        .for_each(|vertex| bounds.take_point(na::Point2::from(*vertex)));
+
+    let extents = bounds.extents();
+    if extents[0] <= 0.0 || extents[1] <= 0.0 {
+        return vec![];
+    }
+
+    // Ranges for randomization
+    let range_x = bounds.mins[0]..bounds.maxs[0];
+    let range_y = bounds.mins[1]..bounds.maxs[1];
+    let area = extents[0] * extents[1];
+
+    gen_dots(
+        &mut rng,
+        area,
+        range_x,
+        range_y,
+        (
+            TexturedOptions::PRESSURE_REFERENCE,
+            TexturedOptions::PRESSURE_REFERENCE,
+        ),
+        na::Vector2::zeros(),
+        options,
+        |local_pos| {
+            if geo_polygon.contains(&geo::Point::new(local_pos[0], local_pos[1])) {
+                Some((local_pos, 0.0))
+            } else {
+                None
+            }
+        },
+    )
+}
+
+/// Composes dots filling the ellipse. The distribution is applied along the y-axis of the untransformed ellipse
+pub fn compose_fill_ellipse(ellipse: shapes::Ellipse, options: &TexturedOptions) -> Element {
+    compose_dots(&gen_dots_fill_ellipse(ellipse, options), options)
+}
+
+/// Generates the dots filling an ellipse, see `compose_fill_ellipse()`
+pub fn gen_dots_fill_ellipse(
+    ellipse: shapes::Ellipse,
+    options: &TexturedOptions,
+) -> Vec<TexturedDot> {
+    let mut rng = compose::new_rng_default_pcg64(options.seed);
+
+    if ellipse.radii[0] <= 0.0 || ellipse.radii[1] <= 0.0 {
+        return vec![];
+    }
+
+    // Ranges for randomization
+    let range_x = -ellipse.radii[0]..ellipse.radii[0];
+    let range_y = -ellipse.radii[1]..ellipse.radii[1];
+    // The area of the transformed bounding box
+    let area = 4.0
+        * ellipse.radii[0]
+        * ellipse.radii[1]
+        * ellipse
+            .transform
+            .transform
+            .matrix()
+            .fixed_slice::<2, 2>(0, 0)
+            .determinant()
+            .abs();
+    let angle = na::Rotation2::rotation_between(
+        &na::Vector2::x(),
+        &(ellipse.transform.transform * na::Vector2::x()),
+    )
+    .angle();
+
+    gen_dots(
+        &mut rng,
+        area,
+        range_x,
+        range_y,
+        (
+            TexturedOptions::PRESSURE_REFERENCE,
+            TexturedOptions::PRESSURE_REFERENCE,
+        ),
+        na::Vector2::zeros(),
+        options,
+        |local_pos| {
+            if (local_pos[0] / ellipse.radii[0]).powi(2) + (local_pos[1] / ellipse.radii[1]).powi(2)
+                <= 1.0
+            {
+                Some((
+                    (ellipse.transform.transform * na::Point2::from(local_pos)).coords,
+                    angle,
+                ))
+            } else {
+                None
+            }
+        },
+    )
+}
+
+/// Generates the dots. local_to_global maps a sampled local position to the global position and the angle of the stroke direction at that position.
+/// Dots for which it returns None are skipped.
+/// The pressures are linearly interpolated along the x-axis of the local space. The dots are elongated and turned in the direction of the tilt
+fn gen_dots<G, F>(
+    rng: &mut G,
+    area: f64,
+    range_x: Range<f64>,
+    range_y: Range<f64>,
+    pressures: (f64, f64),
+    tilt: na::Vector2<f64>,
+    options: &TexturedOptions,
+    local_to_global: F,
+) -> Vec<TexturedDot>
+where
+    G: rand::Rng + ?Sized,
+    F: Fn(na::Vector2<f64>) -> Option<(na::Vector2<f64>, f64)>,
+{
     let range_dots_rot = -std::f64::consts::FRAC_PI_8..std::f64::consts::FRAC_PI_8;
     let range_dots_rx = options.radii[0] * 0.8..options.radii[0] * 1.25;
     let range_dots_ry = options.radii[1] * 0.8..options.radii[1] * 1.25;
 
-    let distr_x = Uniform::from(range_x);
     let distr_dots_rot = Uniform::from(range_dots_rot);
     let distr_dots_rx = Uniform::from(range_dots_rx);
     let distr_dots_ry = Uniform::from(range_dots_ry);
 
-    let n_dots = (area * 0.1 * options.density).round() as i32;
-    let vec = line.end - line.start;
+    let alpha_jitter = options.alpha_jitter.clamp(
+        TexturedOptions::ALPHA_JITTER_MIN,
+        TexturedOptions::ALPHA_JITTER_MAX,
+    );
+    let distr_dots_alpha = Uniform::new_inclusive(1.0 - alpha_jitter, 1.0);
 
-    let mut group = element::Group::new();
+    let start_factor = TexturedOptions::pressure_factor(pressures.0);
+    let end_factor = TexturedOptions::pressure_factor(pressures.1);
+    let pressure_factor_at = |x: f64| {
+        let len = range_x.end - range_x.start;
+        let t = if len > 0.0 {
+            ((x - range_x.start) / len).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        start_factor + (end_factor - start_factor) * t
+    };
+    let max_factor = start_factor.max(end_factor);
 
-    for _ in 0..n_dots {
-        let x_pos = distr_x.sample(&mut rng);
-        let y_pos = options
+    let tilt_magnitude = tilt.magnitude().min(1.0);
+    let tilt_elongation = 1.0 + options.tilt_elongation.max(0.0) * tilt_magnitude;
+    let tilt_angle = tilt[1].atan2(tilt[0]);
+
+    // For a varying density, the dots are generated for the max density and then rejected depending on the density at their position
+    let n_dots = if options.pressure_density {
+        (area * 0.1 * options.density * max_factor).round() as usize
+    } else {
+        (area * 0.1 * options.density).round() as usize
+    };
+    let varying_density = options.pressure_density && start_factor != end_factor;
+
+    let cluster_centers =
+        options
             .distribution
-            .sample_for_range_symmetrical_clipped(&mut rng, range_y.clone());
+            .sample_cluster_centers(rng, n_dots, range_x.clone(), range_y.clone());
+
+    let blue_noise_positions = if options.distribution == TexturedDotsDistribution::BlueNoise {
+        Some(TexturedDotsDistribution::sample_blue_noise_positions(
+            rng,
+            n_dots,
+            range_x.clone(),
+            range_y.clone(),
+        ))
+    } else {
+        None
+    };
+
+    let mut dots = Vec::with_capacity(n_dots);
+
+    for i in 0..n_dots {
+        let local_pos = if let Some(blue_noise_positions) = &blue_noise_positions {
+            if let Some(&pos) = blue_noise_positions.get(i) {
+                pos
+            } else {
+                break;
+            }
+        } else {
+            options.distribution.sample_position_clipped(
+                rng,
+                range_x.clone(),
+                range_y.clone(),
+                &cluster_centers,
+            )
+        };
+        let factor = pressure_factor_at(local_pos[0]);
+
+        if varying_density && Uniform::from(0.0..max_factor).sample(rng) > factor {
+            continue;
+        }
+
+        let (pos, angle) = if let Some(global) = local_to_global(local_pos) {
+            global
+        } else {
+            continue;
+        };
+
+        // Turn the dots from the stroke direction towards the tilt direction, weighted by the tilt magnitude
+        let angle = if tilt_magnitude > 0.0 {
+            let direction = na::vector![angle.cos(), angle.sin()] * (1.0 - tilt_magnitude)
+                + na::vector![tilt_angle.cos(), tilt_angle.sin()] * tilt_magnitude;
+            direction[1].atan2(direction[0])
+        } else {
+            angle
+        };
+        let rotation_angle = angle + distr_dots_rot.sample(rng);
+        let mut radii = na::vector![distr_dots_rx.sample(rng), distr_dots_ry.sample(rng)];
+        if options.pressure_radii {
+            radii *= factor;
+        }
+        radii[0] *= tilt_elongation;
+
+        let color = options.stroke_color.map(|mut color| {
+            // Only sample when jitter is enabled, so that the patterns of strokes without jitter stay the same
+            if alpha_jitter > 0.0 {
+                color.a *= distr_dots_alpha.sample(rng);
+            }
+            color
+        });
+
+        dots.push(TexturedDot {
+            pos,
+            radii,
+            rotation_angle,
+            color,
+        });
+    }
+
+    dots
+}
+
+/// Composes the dots with the dots shape of the options
+fn compose_dots(dots: &[TexturedDot], options: &TexturedOptions) -> Element {
+    dots.iter()
+        .fold(element::Group::new(), |group, dot| {
+            let fill = dot
+                .color
+                .map_or(String::from(""), |color| color.to_css_color());
+
+            group.add(
+                options
+                    .dots_shape
+                    .compose_dot(dot.pos, dot.radii, dot.rotation_angle, &fill),
+            )
+        })
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compose::transformable::Transform;
+
+    #[test]
+    fn blue_noise_keeps_the_min_spacing() {
+        let ranges = [
+            (0.0..100.0, -5.0..5.0),
+            (-20.0..20.0, -20.0..20.0),
+            (0.0..3.0, 0.0..300.0),
+        ];
+
+        for seed in 0..20 {
+            let mut rng = compose::new_rng_default_pcg64(Some(seed));
+
+            for (range_x, range_y) in ranges.iter().cloned() {
+                for n_dots in [1, 10, 100, 500] {
+                    let positions = TexturedDotsDistribution::sample_blue_noise_positions(
+                        &mut rng,
+                        n_dots,
+                        range_x.clone(),
+                        range_y.clone(),
+                    );
+                    let min_spacing = ((range_x.end - range_x.start)
+                        * (range_y.end - range_y.start)
+                        / n_dots as f64)
+                        .sqrt()
+                        * TexturedDotsDistribution::BLUE_NOISE_SPACING_FACTOR;
+
+                    assert!(!positions.is_empty() && positions.len() <= n_dots);
+                    for (i, first) in positions.iter().enumerate() {
+                        assert!(range_x.contains(&first[0]) && range_y.contains(&first[1]));
+
+                        for second in positions.iter().skip(i + 1) {
+                            assert!((second - first).magnitude() >= min_spacing);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blue_noise_of_empty_areas_is_empty() {
+        let mut rng = compose::new_rng_default_pcg64(Some(0));
+
+        assert!(TexturedDotsDistribution::sample_blue_noise_positions(
+            &mut rng,
+            0,
+            0.0..1.0,
+            0.0..1.0
+        )
+        .is_empty());
+        assert!(TexturedDotsDistribution::sample_blue_noise_positions(
+            &mut rng,
+            10,
+            0.0..0.0,
+            0.0..1.0
+        )
+        .is_empty());
+    }
+
+    /// The cubic bezier approximating the quarter circle around the center from the angle 0.0 to PI / 2.0
+    fn quarter_circle(center: na::Vector2<f64>, radius: f64) -> curves::CubicBezier {
+        // The distance of the control points for the best approximation of a circle with cubic beziers
+        let kappa = 4.0 / 3.0 * (std::f64::consts::SQRT_2 - 1.0);
+
+        curves::CubicBezier {
+            start: center + na::vector![radius, 0.0],
+            cp1: center + na::vector![radius, kappa * radius],
+            cp2: center + na::vector![kappa * radius, radius],
+            end: center + na::vector![0.0, radius],
+        }
+    }
+
+    #[test]
+    fn arc_length_matches_the_circle() {
+        for radius in [5.0, 50.0, 500.0] {
+            let cubbez = quarter_circle(na::vector![10.0, -20.0], radius);
+            let n_splits = n_splits_for_len(std::f64::consts::FRAC_PI_2 * radius);
+            let lines = curves::approx_cubbez_with_lines(cubbez, n_splits);
+            let len = lines
+                .iter()
+                .map(|line| (line.end - line.start).magnitude())
+                .sum::<f64>();
+
+            // The chords are shorter than their arcs by about a 24th of the squared angle they span,
+            // and the bezier deviates from the circle by less than 0.03 %
+            let split_angle = std::f64::consts::FRAC_PI_2 / f64::from(n_splits);
+            let tolerance = split_angle.powi(2) / 24.0 + 3e-4;
+            assert!((len / (std::f64::consts::FRAC_PI_2 * radius) - 1.0).abs() < tolerance);
+        }
+    }
+
+    #[test]
+    fn dots_are_spread_evenly_along_the_circle() {
+        let center = na::vector![10.0, -20.0];
+        let width = 1.0;
+
+        for radius in [5.0, 50.0, 500.0] {
+            // The dots are spread along the chords, which are inside the arc by up to their sagitta
+            let split_angle = std::f64::consts::FRAC_PI_2
+                / f64::from(n_splits_for_len(std::f64::consts::FRAC_PI_2 * radius));
+            let sagitta = radius * (1.0 - (split_angle / 2.0).cos());
+
+            for seed in 0..10 {
+                let options = TexturedOptions {
+                    seed: Some(seed),
+                    density: 500.0,
+                    distribution: TexturedDotsDistribution::Uniform,
+                    ..TexturedOptions::default()
+                };
+                let dots = gen_dots_cubbez(
+                    quarter_circle(center, radius),
+                    width,
+                    (
+                        TexturedOptions::PRESSURE_REFERENCE,
+                        TexturedOptions::PRESSURE_REFERENCE,
+                    ),
+                    na::Vector2::zeros(),
+                    &options,
+                );
+                assert!(dots.len() > 100);
+
+                for dot in dots.iter() {
+                    let offset = dot.pos - center;
+                    assert!(
+                        (offset.magnitude() - radius).abs()
+                            <= width / 2.0 + sagitta + radius * 3e-4
+                    );
+                }
+
+                // Dots uniformly distributed along the arc length are uniformly distributed over the angle
+                let first_half = dots
+                    .iter()
+                    .filter(|dot| {
+                        let offset = dot.pos - center;
+                        offset[1].atan2(offset[0]) < std::f64::consts::FRAC_PI_4
+                    })
+                    .count();
+                assert!((first_half as f64 / dots.len() as f64 - 0.5).abs() < 0.1);
+            }
+        }
+    }
+
+    /// An L-shaped polygon, which is the 40x40 square at the origin without its quarter in the upper right
+    fn l_shape() -> Vec<na::Vector2<f64>> {
+        vec![
+            na::vector![0.0, 0.0],
+            na::vector![20.0, 0.0],
+            na::vector![20.0, 20.0],
+            na::vector![40.0, 20.0],
+            na::vector![40.0, 40.0],
+            na::vector![0.0, 40.0],
+        ]
+    }
+
+    fn fill_options(seed: u64) -> TexturedOptions {
+        TexturedOptions {
+            seed: Some(seed),
+            density: 50.0,
+            ..TexturedOptions::default()
+        }
+    }
+
+    #[test]
+    fn polygon_fill_dots_are_inside_the_polygon() {
+        for seed in 0..10 {
+            let dots = gen_dots_fill_polygon(&l_shape(), &fill_options(seed));
+            assert!(dots.len() > 50);
+
+            for dot in dots.iter() {
+                assert!((0.0..=40.0).contains(&dot.pos[0]) && (0.0..=40.0).contains(&dot.pos[1]));
+                assert!(dot.pos[0] <= 20.0 || dot.pos[1] >= 20.0);
+            }
+        }
+    }
+
+    #[test]
+    fn ellipse_fill_dots_are_inside_the_ellipse() {
+        let ellipse = shapes::Ellipse {
+            radii: na::vector![20.0, 10.0],
+            transform: Transform::new_w_isometry(na::Isometry2::new(na::vector![50.0, 30.0], 0.5)),
+        };
+        let inverse = ellipse.transform.transform.inverse();
+
+        for seed in 0..10 {
+            let dots = gen_dots_fill_ellipse(ellipse.clone(), &fill_options(seed));
+            assert!(dots.len() > 50);
+
+            for dot in dots.iter() {
+                let local = (inverse * na::Point2::from(dot.pos)).coords;
+                assert!(
+                    (local[0] / ellipse.radii[0]).powi(2) + (local[1] / ellipse.radii[1]).powi(2)
+                        <= 1.0 + 1e-9
+                );
+            }
+        }
+    }
 
-        let pos = rect.transform.transform * na::point![x_pos, y_pos];
+    #[test]
+    fn fills_are_reproducible_with_the_seed() {
+        let positions = |dots: Vec<TexturedDot>| dots.iter().map(|dot| dot.pos).collect::<Vec<_>>();
 
-        let rotation_angle = na::Rotation2::rotation_between(&na::Vector2::x(), &vec).angle()
-            + distr_dots_rot.sample(&mut rng);
-        let radii = na::vector![
-            distr_dots_rx.sample(&mut rng),
-            distr_dots_ry.sample(&mut rng)
+        assert_eq!(
+            positions(gen_dots_fill_polygon(&l_shape(), &fill_options(3))),
+            positions(gen_dots_fill_polygon(&l_shape(), &fill_options(3)))
+        );
+        assert_ne!(
+            positions(gen_dots_fill_polygon(&l_shape(), &fill_options(3))),
+            positions(gen_dots_fill_polygon(&l_shape(), &fill_options(4)))
+        );
+
+        let ellipse = shapes::Ellipse {
+            radii: na::vector![20.0, 10.0],
+            ..shapes::Ellipse::default()
+        };
+        assert_eq!(
+            positions(gen_dots_fill_ellipse(ellipse.clone(), &fill_options(3))),
+            positions(gen_dots_fill_ellipse(ellipse, &fill_options(3)))
+        );
+    }
+
+    #[test]
+    fn degenerate_fills_are_empty() {
+        assert!(gen_dots_fill_polygon(&l_shape()[..2], &fill_options(0)).is_empty());
+        assert!(gen_dots_fill_polygon(
+            &[
+                na::vector![0.0, 0.0],
+                na::vector![10.0, 0.0],
+                na::vector![20.0, 0.0]
+            ],
+            &fill_options(0)
+        )
+        .is_empty());
+        assert!(gen_dots_fill_ellipse(
+            shapes::Ellipse {
+                radii: na::vector![0.0, 10.0],
+                ..shapes::Ellipse::default()
+            },
+            &fill_options(0)
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn options_without_pressure_density_keep_the_density() {
+        // Textured options saved before the pressure density existed
+        let options = serde_json::from_str::<TexturedOptions>(
+            r#"{"seed": 42, "width": 6.0, "density": 5.0}"#,
+        )
+        .unwrap();
+        assert!(!options.pressure_density);
+
+        let line = curves::Line {
+            start: na::vector![0.0, 0.0],
+            end: na::vector![100.0, 0.0],
+        };
+        let dots_for_pressure = |pressure: f64| {
+            gen_dots_line(
+                line,
+                options.width,
+                (pressure, pressure),
+                na::Vector2::zeros(),
+                &options,
+            )
+            .iter()
+            .map(|dot| dot.pos)
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            dots_for_pressure(0.1),
+            dots_for_pressure(TexturedOptions::PRESSURE_REFERENCE)
+        );
+        assert_eq!(dots_for_pressure(1.0), dots_for_pressure(0.1));
+    }
+
+    #[test]
+    fn dots_are_composed_with_their_shape() {
+        let pos = na::vector![10.0, 20.0];
+        let radii = na::vector![2.0, 1.0];
+        let shapes = [
+            (TexturedDotsShape::Ellipse, "<ellipse", r#"rx="2""#),
+            (TexturedDotsShape::Circle, "<circle", r#"r="2""#),
+            (TexturedDotsShape::Square, "<rect", r#"width="4""#),
+            (TexturedDotsShape::Triangle, "<polygon", "12,20 8,19 8,21"),
+            (TexturedDotsShape::Dash, "<line", r#"stroke-width="2""#),
+            (
+                TexturedDotsShape::Custom {
+                    path_data: String::from("M -1 -1 L 1 0 L -1 1 Z"),
+                },
+                "<path",
+                "translate(10,20) rotate(0) scale(2,1)",
+            ),
         ];
 
-        let fill = options
-            .stroke_color
-            .map_or(String::from(""), |color| color.to_css_color());
-
-        let ellipse = element::Ellipse::new()
-            .set(
-                "transform",
-                format!(
-                    "rotate({},{},{})",
-                    rotation_angle.to_degrees(),
-                    pos[0],
-                    pos[1]
+        for (shape, tag, attribute) in shapes {
+            let svg = shape.compose_dot(pos, radii, 0.0, "#ff0000").to_string();
+
+            assert!(svg.starts_with(tag), "{}", svg);
+            assert!(svg.contains(attribute), "{}", svg);
+            assert!(svg.contains("#ff0000"), "{}", svg);
+        }
+    }
+
+    #[test]
+    fn textured_lines_compose_every_dot_with_the_dots_shape() {
+        let line = curves::Line {
+            start: na::vector![0.0, 0.0],
+            end: na::vector![50.0, 0.0],
+        };
+        let options = TexturedOptions {
+            seed: Some(1),
+            width: 6.0,
+            dots_shape: TexturedDotsShape::Square,
+            ..TexturedOptions::default()
+        };
+        let pressures = (
+            TexturedOptions::PRESSURE_REFERENCE,
+            TexturedOptions::PRESSURE_REFERENCE,
+        );
+
+        let n_dots = gen_dots_line(
+            line,
+            options.width,
+            pressures,
+            na::Vector2::zeros(),
+            &options,
+        )
+        .len();
+        let svg = compose_line(
+            line,
+            options.width,
+            pressures,
+            na::Vector2::zeros(),
+            &options,
+        )
+        .to_string();
+
+        assert!(n_dots > 0);
+        assert_eq!(svg.matches("<rect").count(), n_dots);
+        assert!(!svg.contains("<ellipse"));
+    }
+
+    #[test]
+    fn dots_shape_serde() {
+        // Options saved before the dots shape existed have ellipses
+        let options = serde_json::from_str::<TexturedOptions>(r#"{"seed": 42}"#).unwrap();
+        assert_eq!(options.dots_shape, TexturedDotsShape::Ellipse);
+
+        for shape in [
+            TexturedDotsShape::Square,
+            TexturedDotsShape::Custom {
+                path_data: String::from("M 0 0 L 1 1"),
+            },
+        ] {
+            let json = serde_json::to_string(&shape).unwrap();
+            assert_eq!(
+                serde_json::from_str::<TexturedDotsShape>(&json).unwrap(),
+                shape
+            );
+        }
+        assert_eq!(
+            serde_json::from_str::<TexturedDotsShape>(r#""dash""#).unwrap(),
+            TexturedDotsShape::Dash
+        );
+    }
+
+    #[test]
+    fn dot_alphas_are_jittered_within_the_range() {
+        let line = curves::Line {
+            start: na::vector![0.0, 0.0],
+            end: na::vector![100.0, 0.0],
+        };
+        let stroke_color = Color {
+            a: 0.8,
+            ..Color::BLACK
+        };
+        let alphas = |alpha_jitter: f64| {
+            let options = TexturedOptions {
+                seed: Some(7),
+                width: 6.0,
+                stroke_color: Some(stroke_color),
+                alpha_jitter,
+                ..TexturedOptions::default()
+            };
+            gen_dots_line(
+                line,
+                options.width,
+                (
+                    TexturedOptions::PRESSURE_REFERENCE,
+                    TexturedOptions::PRESSURE_REFERENCE,
                 ),
+                na::Vector2::zeros(),
+                &options,
             )
-            .set("cx", pos[0])
-            .set("cy", pos[1])
-            .set("rx", radii[0])
-            .set("ry", radii[1])
-            .set("fill", fill);
+            .iter()
+            .map(|dot| dot.color.unwrap().a)
+            .collect::<Vec<f64>>()
+        };
 
-        group = group.add(ellipse);
+        assert!(alphas(0.0).iter().all(|&alpha| alpha == stroke_color.a));
+
+        let jittered = alphas(0.6);
+        assert!(jittered.len() > 10);
+        assert!(jittered
+            .iter()
+            .all(|&alpha| (0.4 * stroke_color.a..=stroke_color.a).contains(&alpha)));
+        assert!(jittered.iter().any(|&alpha| alpha < 0.9 * stroke_color.a));
+
+        // Jitter above the max is clamped, so the alphas never become negative
+        assert!(alphas(3.0)
+            .iter()
+            .all(|&alpha| (0.0..=stroke_color.a).contains(&alpha)));
+    }
+
+    #[test]
+    fn alpha_jitter_serde_default() {
+        let options = serde_json::from_str::<TexturedOptions>(r#"{"seed": 42}"#).unwrap();
+        assert_eq!(options.alpha_jitter, TexturedOptions::ALPHA_JITTER_DEFAULT);
+        assert_eq!(options.alpha_jitter, 0.0);
+
+        let options =
+            serde_json::from_str::<TexturedOptions>(r#"{"seed": 42, "alpha_jitter": 0.5}"#)
+                .unwrap();
+        assert_eq!(options.alpha_jitter, 0.5);
     }
 
-    group.into()
+    #[test]
+    fn dots_turn_and_elongate_towards_the_tilt() {
+        let line = curves::Line {
+            start: na::vector![0.0, 0.0],
+            end: na::vector![100.0, 0.0],
+        };
+        let options = TexturedOptions {
+            seed: Some(5),
+            width: 6.0,
+            ..TexturedOptions::default()
+        };
+        let dots = |tilt: na::Vector2<f64>| {
+            gen_dots_line(
+                line,
+                options.width,
+                (
+                    TexturedOptions::PRESSURE_REFERENCE,
+                    TexturedOptions::PRESSURE_REFERENCE,
+                ),
+                tilt,
+                &options,
+            )
+        };
+        let max_jitter = std::f64::consts::FRAC_PI_8 + 1e-9;
+
+        for dot in dots(na::Vector2::zeros()) {
+            assert!(dot.rotation_angle.abs() <= max_jitter);
+            assert!(dot.radii[0] <= options.radii[0] * 1.25);
+        }
+
+        // Fully tilted across the line, the dots are turned across as well and twice as long
+        let tilted = dots(na::vector![0.0, 1.0]);
+        assert!(!tilted.is_empty());
+        for dot in tilted {
+            assert!((dot.rotation_angle - std::f64::consts::FRAC_PI_2).abs() <= max_jitter);
+            assert!(dot.radii[0] >= options.radii[0] * 0.8 * 2.0);
+        }
+    }
 }
@@ -77,4 +77,18 @@ impl Transform {
             matrix[(1, 2)],
         )
     }
+
+    #[cfg(feature = "cairo")]
+    pub fn to_cairo_matrix(&self) -> cairo::Matrix {
+        let matrix = self.transform;
+
+        cairo::Matrix::new(
+            matrix[(0, 0)],
+            matrix[(1, 0)],
+            matrix[(0, 1)],
+            matrix[(1, 1)],
+            matrix[(0, 2)],
+            matrix[(1, 2)],
+        )
+    }
 }
@@ -44,4 +44,12 @@ pub trait DrawBehaviour {
             .unwrap()
             .gen_images(zoom, svgs, self.bounds())
     }
+    /// draws the stroke onto the cairo context, in the coordinate space of the sheet.
+    /// Used for vector surfaces like pdf pages, where the default draws the generated svgs as paths
+    #[cfg(feature = "cairo")]
+    fn draw_to_cairo_vector(&self, cx: &cairo::Context) -> Result<(), anyhow::Error> {
+        let svgs = self.gen_svgs(na::vector![0.0, 0.0])?;
+
+        render::draw_svgs_to_cairo_context(1.0, &svgs, self.bounds(), cx)
+    }
 }
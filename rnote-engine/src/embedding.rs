@@ -0,0 +1,403 @@
+//! The api for embedding the engine into other applications, independent of the widgets of the app.
+//! An `Engine` owns the sheet and the pens. Other parts of the application send it input events and commands through an `EngineHandle`,
+//! and it emits updates of what changed through the updates receiver. The sheet holds render nodes which can't be sent between threads,
+//! so the engine stays on the thread that created it. It processes the pending events when `Engine::process_pending()` is called there,
+//! while the handle can be cloned and used from anywhere.
+
+use std::sync::{Arc, RwLock};
+
+use crate::canvas::{self, CanvasContext};
+use crate::pens::{PenEvent, PenStyle, Pens};
+use crate::render::Renderer;
+use crate::sheet::Sheet;
+use crate::strokesstate::StateTask;
+use crate::surfaceflags::SurfaceFlags;
+
+use futures::channel::mpsc;
+#[cfg(feature = "ui")]
+use gtk4::{graphene, Snapshot};
+use p2d::bounding_volume::AABB;
+
+/// How the sheet grows with its content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpandMode {
+    /// The sheet is only resized explicitly, see `Sheet::resize_sheet_mode_fixed_size()`
+    FixedSize,
+    EndlessVertical,
+    Infinite,
+    /// The sheet fits the pages of its page layout
+    Pages,
+}
+
+impl Default for ExpandMode {
+    fn default() -> Self {
+        Self::EndlessVertical
+    }
+}
+
+/// The events and commands the engine handles
+#[derive(Debug, Clone)]
+pub enum EngineEvent {
+    /// Input of the pointer, stylus or keyboard, and the changes of the pen
+    Pen(PenEvent),
+    /// The part of the sheet which is shown, and its zoom
+    SetViewport {
+        viewport: AABB,
+        zoom: f64,
+    },
+    SetExpandMode(ExpandMode),
+    Undo,
+    Redo,
+    /// Opens the document in the Rnote file format, replacing the current one
+    OpenRnoteBytes(Vec<u8>),
+    /// Stops the engine. The updates receiver gets `EngineUpdate::Quit` and no further events are processed
+    Quit,
+}
+
+/// What changed in the engine
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EngineUpdate {
+    /// The view needs to be drawn again, see `Engine::draw()` and `Engine::draw_to_canvas()`
+    Redraw,
+    /// The sheet has new bounds
+    SheetResized(AABB),
+    /// The document was changed and has unsaved changes
+    DocumentChanged,
+    SelectionChanged,
+    /// The pen changed its style or options
+    PenChanged(PenStyle),
+    /// The pens show something which changes over time, so `Engine::update_animations()` needs to be called periodically until it returns false
+    Animate,
+    Quit,
+}
+
+/// Sends events to the engine. Sending fails when the engine was dropped or stopped
+#[derive(Debug, Clone)]
+pub struct EngineHandle {
+    events_tx: mpsc::UnboundedSender<EngineEvent>,
+}
+
+impl EngineHandle {
+    pub fn send(&self, event: EngineEvent) -> Result<(), anyhow::Error> {
+        self.events_tx
+            .unbounded_send(event)
+            .map_err(|e| anyhow::anyhow!("sending the event to the engine failed, {}", e))
+    }
+}
+
+#[derive(Debug)]
+pub struct Engine {
+    pub sheet: Sheet,
+    pub pens: Pens,
+    pub renderer: Arc<RwLock<Renderer>>,
+    expand_mode: ExpandMode,
+    viewport: AABB,
+    zoom: f64,
+    quit: bool,
+    events_rx: mpsc::UnboundedReceiver<EngineEvent>,
+    tasks_rx: mpsc::UnboundedReceiver<StateTask>,
+    updates_tx: mpsc::UnboundedSender<EngineUpdate>,
+}
+
+impl Engine {
+    /// A new engine with an empty sheet, the handle to send it events and the receiver of its updates
+    pub fn new() -> (Self, EngineHandle, mpsc::UnboundedReceiver<EngineUpdate>) {
+        let (events_tx, events_rx) = mpsc::unbounded::<EngineEvent>();
+        let (updates_tx, updates_rx) = mpsc::unbounded::<EngineUpdate>();
+
+        let mut sheet = Sheet::default();
+        // The tasks of the render jobs are received by the engine instead of the app
+        let tasks_rx = sheet
+            .strokes_state
+            .tasks_rx
+            .take()
+            .expect("the tasks receiver of a new strokes state is not taken");
+        let viewport = sheet.bounds();
+
+        let engine = Self {
+            sheet,
+            pens: Pens::default(),
+            renderer: Arc::new(RwLock::new(Renderer::default())),
+            expand_mode: ExpandMode::default(),
+            viewport,
+            zoom: 1.0,
+            quit: false,
+            events_rx,
+            tasks_rx,
+            updates_tx,
+        };
+
+        (engine, EngineHandle { events_tx }, updates_rx)
+    }
+
+    /// The viewport, in the coordinate space of the sheet
+    pub fn viewport(&self) -> AABB {
+        self.viewport
+    }
+
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    pub fn expand_mode(&self) -> ExpandMode {
+        self.expand_mode
+    }
+
+    /// Processes the events which were sent through the handles and the finished render jobs, without blocking.
+    /// Returns false when the engine was stopped
+    pub fn process_pending(&mut self) -> bool {
+        while !self.quit {
+            match self.tasks_rx.try_next() {
+                Ok(Some(task)) => {
+                    let surface_flags = self.sheet.strokes_state.process_received_task(
+                        task,
+                        self.zoom,
+                        Arc::clone(&self.renderer),
+                    );
+                    self.handle_surface_flags(surface_flags);
+                }
+                // Closed or empty
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        while !self.quit {
+            match self.events_rx.try_next() {
+                Ok(Some(event)) => self.handle_event(event),
+                Ok(None) => {
+                    // All handles were dropped
+                    self.quit = true;
+                }
+                Err(_) => break,
+            }
+        }
+
+        !self.quit
+    }
+
+    /// Handles the event directly, instead of sending it through a handle
+    pub fn handle_event(&mut self, event: EngineEvent) {
+        match event {
+            EngineEvent::Pen(pen_event) => {
+                let surface_flags = self.pens.handle_event(
+                    pen_event,
+                    &mut self.sheet,
+                    Some(self.viewport),
+                    self.zoom,
+                    Arc::clone(&self.renderer),
+                );
+                self.handle_surface_flags(surface_flags);
+            }
+            EngineEvent::SetViewport { viewport, zoom } => {
+                self.viewport = viewport;
+                self.zoom = zoom;
+
+                if self.expand_mode == ExpandMode::Infinite
+                    && self.sheet.expand_sheet_mode_infinite_for_viewport(viewport)
+                {
+                    self.sheet_resized();
+                }
+                self.regenerate_content(false);
+            }
+            EngineEvent::SetExpandMode(expand_mode) => {
+                self.expand_mode = expand_mode;
+
+                match expand_mode {
+                    ExpandMode::Pages => self.sheet.enable_page_layout(),
+                    _ => self.sheet.disable_page_layout(),
+                }
+                self.resize_sheet(false);
+            }
+            EngineEvent::Undo => {
+                if self.sheet.strokes_state.undo() {
+                    self.history_navigated();
+                }
+            }
+            EngineEvent::Redo => {
+                if self.sheet.strokes_state.redo() {
+                    self.history_navigated();
+                }
+            }
+            EngineEvent::OpenRnoteBytes(bytes) => {
+                match self.sheet.open_sheet_from_rnote_bytes(bytes) {
+                    Ok(()) => {
+                        // The page layout of the document decides whether it is in the pages mode
+                        if self.sheet.page_layout.is_some() {
+                            self.expand_mode = ExpandMode::Pages;
+                        } else if self.expand_mode == ExpandMode::Pages {
+                            self.expand_mode = ExpandMode::default();
+                        }
+                        self.send_update(EngineUpdate::SelectionChanged);
+                        self.resize_sheet(false);
+                        self.regenerate_content(true);
+                    }
+                    Err(e) => {
+                        log::error!(
+                            "open_sheet_from_rnote_bytes() failed in Engine handle_event() with Err {}",
+                            e
+                        );
+                    }
+                }
+            }
+            EngineEvent::Quit => {
+                self.quit = true;
+                self.send_update(EngineUpdate::Quit);
+            }
+        }
+    }
+
+    /// Advances what changes over time, e.g. fades out the laser trails. Returns whether it needs to be called again
+    pub fn update_animations(&mut self, now: web_time::Instant) -> bool {
+        let animate = self.pens.update_animations(now);
+        self.send_update(EngineUpdate::Redraw);
+
+        animate
+    }
+
+    /// Draws the sheet, the strokes and the pens in the viewport to the snapshot. Its origin is the upper left corner of the viewport
+    #[cfg(feature = "ui")]
+    pub fn draw(&self, snapshot: &Snapshot) -> Result<(), anyhow::Error> {
+        snapshot.save();
+        snapshot.translate(&graphene::Point::new(
+            (-self.viewport.mins[0] * self.zoom) as f32,
+            (-self.viewport.mins[1] * self.zoom) as f32,
+        ));
+
+        self.sheet.draw(self.zoom, snapshot, true);
+        self.sheet
+            .strokes_state
+            .draw_strokes_tiled(snapshot, self.viewport, self.zoom, None);
+        self.sheet.strokes_state.draw_selection(self.zoom, snapshot);
+        let pens_drawn = self.pens.draw(
+            snapshot,
+            &self.sheet,
+            Some(self.viewport),
+            self.zoom,
+            Arc::clone(&self.renderer),
+        );
+
+        snapshot.restore();
+        pens_drawn
+    }
+
+    /// Draws the sheet and the strokes in the viewport to the 2D canvas context, see `canvas::draw_sheet()`
+    pub fn draw_to_canvas(&self, context: &mut impl CanvasContext) -> Result<(), anyhow::Error> {
+        let tessellated_strokes = self.renderer.read().unwrap().tessellated_strokes;
+
+        canvas::draw_sheet(
+            &self.sheet,
+            self.viewport,
+            self.zoom,
+            &tessellated_strokes,
+            context,
+        )
+    }
+
+    fn send_update(&self, update: EngineUpdate) {
+        // The receiver might be dropped when the app is not interested in the updates
+        let _ = self.updates_tx.unbounded_send(update);
+    }
+
+    fn handle_surface_flags(&mut self, surface_flags: SurfaceFlags) {
+        if surface_flags.quit {
+            self.handle_event(EngineEvent::Quit);
+            return;
+        }
+        if surface_flags.resize {
+            self.resize_sheet(false);
+        }
+        if surface_flags.resize_to_fit_strokes {
+            self.resize_sheet(true);
+        }
+        if let Some(pen_style) = surface_flags.change_to_pen {
+            let surface_flags = self.pens.handle_event(
+                PenEvent::ChangeStyle(pen_style),
+                &mut self.sheet,
+                Some(self.viewport),
+                self.zoom,
+                Arc::clone(&self.renderer),
+            );
+            self.handle_surface_flags(surface_flags);
+            self.send_update(EngineUpdate::PenChanged(pen_style));
+        }
+        if surface_flags.pen_changed {
+            self.send_update(EngineUpdate::PenChanged(self.pens.style_w_override()));
+        }
+        if surface_flags.sheet_changed {
+            self.send_update(EngineUpdate::DocumentChanged);
+        }
+        if surface_flags.selection_changed {
+            self.send_update(EngineUpdate::SelectionChanged);
+        }
+        if surface_flags.animate {
+            self.send_update(EngineUpdate::Animate);
+        }
+        if surface_flags.redraw || surface_flags.resize {
+            self.send_update(EngineUpdate::Redraw);
+        }
+    }
+
+    /// Resizes the sheet to the strokes according to the expand mode. In the fixed size mode only when explicitly fitting the strokes
+    fn resize_sheet(&mut self, fit_strokes: bool) {
+        match self.expand_mode {
+            ExpandMode::FixedSize if fit_strokes => self.sheet.resize_sheet_mode_fixed_size(),
+            ExpandMode::FixedSize => return,
+            ExpandMode::EndlessVertical => self.sheet.resize_sheet_mode_endless_vertical(),
+            ExpandMode::Infinite => {
+                self.sheet.resize_sheet_mode_infinite_to_fit_strokes();
+                self.sheet
+                    .expand_sheet_mode_infinite_for_viewport(self.viewport);
+            }
+            ExpandMode::Pages => self.sheet.resize_sheet_mode_pages(),
+        }
+
+        self.sheet_resized();
+    }
+
+    /// Updates everything after an undo or redo
+    fn history_navigated(&mut self) {
+        self.send_update(EngineUpdate::DocumentChanged);
+        self.send_update(EngineUpdate::SelectionChanged);
+        self.resize_sheet(false);
+        self.regenerate_content(false);
+    }
+
+    fn sheet_resized(&mut self) {
+        #[cfg(feature = "ui")]
+        if let Err(e) = self
+            .sheet
+            .update_background_rendernodes(self.zoom, Some(self.viewport))
+        {
+            log::error!(
+                "update_background_rendernodes() failed in Engine sheet_resized() with Err {}",
+                e
+            );
+        }
+
+        self.send_update(EngineUpdate::SheetResized(self.sheet.bounds()));
+        self.send_update(EngineUpdate::Redraw);
+    }
+
+    fn regenerate_content(&mut self, force_regenerate: bool) {
+        self.sheet
+            .strokes_state
+            .regenerate_rendering_current_view_threaded(
+                Some(self.viewport),
+                force_regenerate,
+                Arc::clone(&self.renderer),
+                self.zoom,
+            );
+        if let Err(e) = self.sheet.regenerate_backgrounds(
+            self.zoom,
+            Some(self.viewport),
+            Arc::clone(&self.renderer),
+        ) {
+            log::error!(
+                "regenerate_backgrounds() failed in Engine regenerate_content() with Err {}",
+                e
+            );
+        }
+
+        self.send_update(EngineUpdate::Redraw);
+    }
+}
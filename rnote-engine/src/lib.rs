@@ -1,16 +1,25 @@
 #![warn(missing_debug_implementations)]
 //#![warn(missing_docs)]
 
+pub mod canvas;
+pub mod collab;
 pub mod compose;
 pub mod drawbehaviour;
+pub mod embedding;
 pub mod pens;
+pub mod perfcounters;
+pub mod plugins;
+pub mod recognition;
 pub mod render;
+#[cfg(feature = "scripting")]
+pub mod scripting;
 pub mod sheet;
 pub mod strokes;
 pub mod strokesstate;
 pub mod surfaceflags;
 pub mod utils;
 
+#[cfg(feature = "ui")]
 extern crate gstreamer as gst;
 extern crate nalgebra as na;
 extern crate parry2d_f64 as p2d;
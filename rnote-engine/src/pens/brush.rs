@@ -1,37 +1,70 @@
 use std::collections::VecDeque;
 
+use crate::compose::calligraphy::CalligraphyOptions;
+use crate::compose::color::Color;
+use crate::compose::hatched::HatchedOptions;
+use crate::compose::highlighter::HighlighterOptions;
 use crate::compose::smooth::SmoothOptions;
+use crate::compose::spray::SprayOptions;
 use crate::compose::textured::TexturedOptions;
+#[cfg(feature = "ui")]
+use crate::drawbehaviour::DrawBehaviour;
+use crate::recognition::shapes::{self as shape_recognition, ShapeRecognitionOptions};
+#[cfg(feature = "ui")]
+use crate::render;
 use crate::render::Renderer;
 use crate::sheet::Sheet;
 use crate::strokes::brushstroke::BrushStroke;
 use crate::strokes::element::Element;
 use crate::strokes::inputdata::InputData;
+use crate::strokes::shapestroke::ShapeStroke;
 use crate::strokes::strokestyle::StrokeStyle;
 use crate::strokesstate::StrokeKey;
 use crate::utils;
 
-use gtk4::glib;
+#[cfg(feature = "ui")]
+use anyhow::Context;
+#[cfg(feature = "ui")]
+use gtk4::Snapshot;
 use p2d::bounding_volume::{BoundingVolume, AABB};
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, RwLock};
 
+use super::compass::Compass;
 use super::penbehaviour::PenBehaviour;
+use super::prediction::{PredictionOptions, Predictor};
+use super::pressurecurve::PressureCurve;
+use super::simplification::SimplificationOptions;
+use super::smoothing::{Smoother, SmoothingOptions};
+use super::stabilizer::{Stabilizer, StabilizerOptions};
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, glib::Enum)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
 #[repr(u32)]
-#[enum_type(name = "BrushStyle")]
+#[cfg_attr(feature = "ui", enum_type(name = "BrushStyle"))]
 #[serde(rename = "brushstyle")]
 pub enum BrushStyle {
-    #[enum_value(name = "Marker", nick = "marker")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Marker", nick = "marker"))]
     #[serde(rename = "marker")]
     Marker,
-    #[enum_value(name = "Solid", nick = "solid")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Solid", nick = "solid"))]
     #[serde(rename = "solid")]
     Solid,
-    #[enum_value(name = "Textured", nick = "textured")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Textured", nick = "textured"))]
     #[serde(rename = "textured")]
     Textured,
+    #[cfg_attr(feature = "ui", enum_value(name = "Spray", nick = "spray"))]
+    #[serde(rename = "spray")]
+    Spray,
+    #[cfg_attr(feature = "ui", enum_value(name = "Highlighter", nick = "highlighter"))]
+    #[serde(rename = "highlighter")]
+    Highlighter,
+    #[cfg_attr(feature = "ui", enum_value(name = "Calligraphy", nick = "calligraphy"))]
+    #[serde(rename = "calligraphy")]
+    Calligraphy,
+    #[cfg_attr(feature = "ui", enum_value(name = "Hatched", nick = "hatched"))]
+    #[serde(rename = "hatched")]
+    Hatched,
 }
 
 impl Default for BrushStyle {
@@ -49,9 +82,39 @@ pub struct Brush {
     pub smooth_options: SmoothOptions,
     #[serde(rename = "textured_options")]
     pub textured_options: TexturedOptions,
+    #[serde(rename = "spray_options")]
+    pub spray_options: SprayOptions,
+    #[serde(rename = "highlighter_options")]
+    pub highlighter_options: HighlighterOptions,
+    #[serde(rename = "calligraphy_options")]
+    pub calligraphy_options: CalligraphyOptions,
+    #[serde(rename = "hatched_options")]
+    pub hatched_options: HatchedOptions,
+    #[serde(rename = "smoothing_options")]
+    pub smoothing_options: SmoothingOptions,
+    #[serde(rename = "stabilizer_options")]
+    pub stabilizer_options: StabilizerOptions,
+    #[serde(rename = "prediction_options")]
+    pub prediction_options: PredictionOptions,
+    /// How the strokes are simplified when they are finished
+    #[serde(rename = "simplification_options")]
+    pub simplification_options: SimplificationOptions,
+    #[serde(rename = "pressure_curve")]
+    pub pressure_curve: PressureCurve,
+    #[serde(rename = "shape_recognition_options")]
+    pub shape_recognition_options: ShapeRecognitionOptions,
+    /// Draws arcs around a pinned center instead of freehand strokes while enabled
+    #[serde(rename = "compass")]
+    pub compass: Compass,
 
     #[serde(skip)]
     pub current_stroke: Option<StrokeKey>,
+    #[serde(skip)]
+    pub smoother: Smoother,
+    #[serde(skip)]
+    pub stabilizer: Stabilizer,
+    #[serde(skip)]
+    pub predictor: Predictor,
 }
 
 impl Default for Brush {
@@ -60,7 +123,21 @@ impl Default for Brush {
             style: BrushStyle::default(),
             smooth_options: SmoothOptions::default(),
             textured_options: TexturedOptions::default(),
+            spray_options: SprayOptions::default(),
+            highlighter_options: HighlighterOptions::default(),
+            calligraphy_options: CalligraphyOptions::default(),
+            hatched_options: HatchedOptions::default(),
+            smoothing_options: SmoothingOptions::default(),
+            stabilizer_options: StabilizerOptions::default(),
+            prediction_options: PredictionOptions::default(),
+            simplification_options: SimplificationOptions::default(),
+            pressure_curve: PressureCurve::default(),
+            shape_recognition_options: ShapeRecognitionOptions::default(),
+            compass: Compass::default(),
             current_stroke: None,
+            smoother: Smoother::default(),
+            stabilizer: Stabilizer::default(),
+            predictor: Predictor::default(),
         }
     }
 }
@@ -71,19 +148,35 @@ impl PenBehaviour for Brush {
         mut data_entries: VecDeque<InputData>,
         sheet: &mut Sheet,
         _viewport: Option<AABB>,
-        _zoom: f64,
+        zoom: f64,
         _renderer: Arc<RwLock<Renderer>>,
     ) {
         self.current_stroke = None;
+
+        if self.compass.enabled() {
+            if let Some(inputdata) = data_entries.back() {
+                let options = self.shape_smooth_options();
+                self.compass.begin(inputdata.pos(), options, sheet, zoom);
+            }
+            return;
+        }
+
+        self.smoother = Smoother::new(self.smoothing_options);
+        self.stabilizer.reset();
+        self.predictor.reset();
         let filter_bounds = sheet.bounds().loosened(utils::INPUT_OVERSHOOT);
 
         utils::filter_mapped_inputdata(filter_bounds, &mut data_entries);
 
-        let elements_iter = data_entries
+        let elements = data_entries
             .into_iter()
-            .map(|inputdata| Element::new(inputdata));
+            .filter_map(|inputdata| self.process_inputdata(inputdata))
+            .collect::<Vec<Element>>();
+        for &element in elements.iter() {
+            self.predictor.update(element, &self.prediction_options);
+        }
 
-        let brushstroke = BrushStroke::new_w_elements(elements_iter, &self);
+        let brushstroke = BrushStroke::new_w_elements(elements.into_iter(), &self);
 
         if let Some(brushstroke) = brushstroke {
             let brushstroke = StrokeStyle::BrushStroke(brushstroke);
@@ -101,6 +194,13 @@ impl PenBehaviour for Brush {
         zoom: f64,
         renderer: Arc<RwLock<Renderer>>,
     ) {
+        if self.compass.enabled() {
+            if let Some(inputdata) = data_entries.back() {
+                self.compass.motion(inputdata.pos(), sheet, zoom, renderer);
+            }
+            return;
+        }
+
         let current_stroke_key = self.current_stroke;
         if let Some(current_stroke_key) = current_stroke_key {
             let filter_bounds = sheet.bounds().loosened(utils::INPUT_OVERSHOOT);
@@ -108,26 +208,59 @@ impl PenBehaviour for Brush {
             utils::filter_mapped_inputdata(filter_bounds, &mut data_entries);
 
             for inputdata in data_entries {
-                sheet.strokes_state.add_to_brushstroke(
-                    current_stroke_key,
-                    Element::new(inputdata),
-                    renderer.clone(),
-                    zoom,
-                );
+                if let Some(element) = self.process_inputdata(inputdata) {
+                    sheet.strokes_state.add_to_brushstroke(
+                        current_stroke_key,
+                        element,
+                        renderer.clone(),
+                        zoom,
+                    );
+                    self.predictor.update(element, &self.prediction_options);
+                }
             }
         }
     }
 
     fn end(
         &mut self,
-        _data_entries: VecDeque<InputData>,
+        data_entries: VecDeque<InputData>,
         sheet: &mut Sheet,
         _viewport: Option<AABB>,
         zoom: f64,
         renderer: Arc<RwLock<Renderer>>,
     ) {
+        if self.compass.enabled() {
+            if let Some(inputdata) = data_entries.back() {
+                self.compass
+                    .motion(inputdata.pos(), sheet, zoom, renderer.clone());
+            }
+            self.compass.end(sheet, zoom, renderer);
+            return;
+        }
+
         let current_stroke_key = self.current_stroke.take();
+        let last_element = self.smoother.finish();
+        self.stabilizer.reset();
+        self.predictor.reset();
         if let Some(current_stroke_key) = current_stroke_key {
+            // Let the stroke end at the last input
+            if let Some(last_element) = last_element {
+                sheet.strokes_state.add_to_brushstroke(
+                    current_stroke_key,
+                    last_element,
+                    renderer.clone(),
+                    zoom,
+                );
+            }
+
+            if self.shape_recognition_options.enabled {
+                self.replace_w_recognized_shape(current_stroke_key, sheet);
+            }
+
+            sheet
+                .strokes_state
+                .simplify_brushstroke(current_stroke_key, &self.simplification_options);
+
             sheet
                 .strokes_state
                 .update_geometry_for_stroke(current_stroke_key);
@@ -137,4 +270,140 @@ impl PenBehaviour for Brush {
                 .regenerate_rendering_for_stroke_threaded(current_stroke_key, renderer, zoom);
         }
     }
+
+    #[cfg(feature = "ui")]
+    fn draw(
+        &self,
+        snapshot: &Snapshot,
+        sheet: &Sheet,
+        _viewport: Option<AABB>,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<(), anyhow::Error> {
+        if self.stabilizer_options.enabled {
+            self.stabilizer
+                .draw(zoom, snapshot, Arc::clone(&renderer))?;
+        }
+
+        self.draw_prediction(snapshot, sheet, zoom, renderer)?;
+
+        Ok(())
+    }
+}
+
+impl Brush {
+    /// Sets the stroke color of the current style
+    pub fn set_stroke_color(&mut self, color: Color) {
+        match self.style {
+            BrushStyle::Marker | BrushStyle::Solid => {
+                self.smooth_options.stroke_color = Some(color)
+            }
+            BrushStyle::Textured => self.textured_options.stroke_color = Some(color),
+            BrushStyle::Spray => self.spray_options.stroke_color = Some(color),
+            BrushStyle::Highlighter => self.highlighter_options.stroke_color = Some(color),
+            BrushStyle::Calligraphy => self.calligraphy_options.stroke_color = Some(color),
+            BrushStyle::Hatched => self.hatched_options.stroke_color = Some(color),
+        }
+    }
+
+    /// Draws the predicted continuation of the current stroke provisionally, until the real input arrives
+    #[cfg(feature = "ui")]
+    fn draw_prediction(
+        &self,
+        snapshot: &Snapshot,
+        sheet: &Sheet,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<(), anyhow::Error> {
+        let continuation = match self.current_stroke.and_then(|key| {
+            match sheet.strokes_state.get_stroke_ref(key) {
+                Some(StrokeStyle::BrushStroke(brushstroke)) => {
+                    brushstroke.gen_predicted_continuation(self.predictor.predicted())
+                }
+                _ => None,
+            }
+        }) {
+            Some(continuation) => StrokeStyle::BrushStroke(continuation),
+            None => return Ok(()),
+        };
+
+        let images = continuation.gen_images(zoom, renderer)?;
+        if let Some(rendernode) = render::images_to_rendernode(&images, zoom)
+            .context("images_to_rendernode() failed in brush draw_prediction()")?
+        {
+            snapshot.append_node(&rendernode);
+        }
+
+        Ok(())
+    }
+
+    /// Processes new input through the pressure curve, the stabilizer and the smoothing. Returns None if no element should be added for it
+    fn process_inputdata(&mut self, mut inputdata: InputData) -> Option<Element> {
+        inputdata.set_pressure(self.pressure_curve.apply(inputdata.pressure()));
+
+        let inputdata = self
+            .stabilizer
+            .stabilize(inputdata, &self.stabilizer_options)?;
+
+        self.smoother.smooth(Element::new(inputdata))
+    }
+
+    /// Replaces the finished stroke with the shape recognized from it, if the shape is recognized with enough confidence
+    fn replace_w_recognized_shape(&self, key: StrokeKey, sheet: &mut Sheet) {
+        let points = match sheet.strokes_state.clone_strokes_for_keys(&[key]).pop() {
+            Some(StrokeStyle::BrushStroke(brushstroke)) => brushstroke
+                .elements
+                .iter()
+                .map(|element| element.inputdata.pos())
+                .collect::<Vec<na::Vector2<f64>>>(),
+            _ => return,
+        };
+
+        if let Some(recognized) = shape_recognition::recognize(&points) {
+            if recognized.confidence >= self.shape_recognition_options.confidence_threshold {
+                let shapestroke = ShapeStroke::new_w_shape(
+                    recognized.shape,
+                    self.shape_smooth_options(),
+                    recognized.arrowheads,
+                );
+
+                sheet
+                    .strokes_state
+                    .replace_stroke(key, StrokeStyle::ShapeStroke(shapestroke));
+            }
+        }
+    }
+
+    /// The smooth options for shapes, with the width and color of the current brush style
+    fn shape_smooth_options(&self) -> SmoothOptions {
+        let (width, stroke_color) = match self.style {
+            BrushStyle::Marker | BrushStyle::Solid => {
+                (self.smooth_options.width, self.smooth_options.stroke_color)
+            }
+            BrushStyle::Textured => (
+                self.textured_options.width,
+                self.textured_options.stroke_color,
+            ),
+            BrushStyle::Spray => (self.spray_options.width, self.spray_options.stroke_color),
+            BrushStyle::Highlighter => (
+                self.highlighter_options.width,
+                self.highlighter_options.stroke_color,
+            ),
+            BrushStyle::Calligraphy => (
+                self.calligraphy_options.width,
+                self.calligraphy_options.stroke_color,
+            ),
+            BrushStyle::Hatched => (
+                self.hatched_options.width,
+                self.hatched_options.stroke_color,
+            ),
+        };
+
+        SmoothOptions {
+            width,
+            stroke_color,
+            fill_color: None,
+            ..self.smooth_options
+        }
+    }
 }
@@ -0,0 +1,316 @@
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "ui")]
+use crate::compose;
+use crate::compose::arrowhead::ArrowheadOptions;
+use crate::compose::color::Color;
+use crate::compose::shapes;
+use crate::compose::smooth::SmoothOptions;
+use crate::compose::transformable::Transform;
+#[cfg(feature = "ui")]
+use crate::render;
+use crate::render::Renderer;
+#[cfg(feature = "ui")]
+use crate::sheet::format::MeasureUnit;
+use crate::sheet::Sheet;
+use crate::strokes::shapestroke::{Shape, ShapeStroke};
+use crate::strokes::strokestyle::StrokeStyle;
+use crate::strokesstate::StrokeKey;
+
+#[cfg(feature = "ui")]
+use anyhow::Context;
+#[cfg(feature = "ui")]
+use gtk4::Snapshot;
+#[cfg(feature = "ui")]
+use p2d::bounding_volume::{BoundingVolume, AABB};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone)]
+enum CompassState {
+    Idle,
+    /// The center follows the pen
+    MovingCenter,
+    /// An arc is drawn around the center
+    Drawing {
+        key: StrokeKey,
+        arc: shapes::Arc,
+        options: SmoothOptions,
+    },
+}
+
+impl Default for CompassState {
+    fn default() -> Self {
+        Self::Idle
+    }
+}
+
+/// Lets the brush draw arcs around a pinned center, at the radius the stroke starts at.
+/// The first press pins the center, pressing onto the center moves it again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "compass")]
+pub struct Compass {
+    /// Shows the radius while an arc is drawn
+    #[serde(rename = "show_radius")]
+    pub show_radius: bool,
+    /// Whether the brush draws arcs. Not kept between sessions
+    #[serde(skip)]
+    enabled: bool,
+    #[serde(skip)]
+    center: Option<na::Vector2<f64>>,
+    #[serde(skip)]
+    state: CompassState,
+}
+
+impl Default for Compass {
+    fn default() -> Self {
+        Self {
+            show_radius: true,
+            enabled: false,
+            center: None,
+            state: CompassState::default(),
+        }
+    }
+}
+
+impl Compass {
+    /// The radius of the center handle, in surface coordinates
+    pub const CENTER_RADIUS: f64 = 8.0;
+    /// The min angle the arc needs to sweep to be kept
+    pub const SWEEP_ANGLE_MIN: f64 = 0.01;
+    pub const OUTLINE_WIDTH: f64 = 1.5;
+    /// The font size of the radius label, in surface coordinates
+    pub const LABEL_FONT_SIZE: f64 = 12.0;
+    pub const OUTLINE_COLOR: Color = Color {
+        r: 0.1,
+        g: 0.45,
+        b: 0.9,
+        a: 0.8,
+    };
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enables or disables drawing arcs. The center is unpinned either way, so the next press pins it anew
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        self.center = None;
+        self.state = CompassState::Idle;
+    }
+
+    pub fn center(&self) -> Option<na::Vector2<f64>> {
+        self.center
+    }
+
+    /// The radius of the arc which is currently drawn
+    pub fn current_radius(&self) -> Option<f64> {
+        match &self.state {
+            CompassState::Drawing { arc, .. } => Some(arc.radius),
+            _ => None,
+        }
+    }
+
+    /// Pins the center when it is not pinned yet or the position is on it, else begins a new arc through the position
+    pub fn begin(
+        &mut self,
+        pos: na::Vector2<f64>,
+        options: SmoothOptions,
+        sheet: &mut Sheet,
+        zoom: f64,
+    ) {
+        let center = match self.center {
+            Some(center) if (pos - center).magnitude() > Self::CENTER_RADIUS / zoom => center,
+            _ => {
+                self.center = Some(pos);
+                self.state = CompassState::MovingCenter;
+                return;
+            }
+        };
+
+        let diff = pos - center;
+        let arc = shapes::Arc {
+            radius: diff.magnitude(),
+            start_angle: diff[1].atan2(diff[0]),
+            sweep_angle: 0.0,
+            transform: Transform::new_w_isometry(na::Isometry2::new(center, 0.0)),
+        };
+        let key = sheet
+            .strokes_state
+            .insert_stroke(Self::arc_stroke(&arc, options));
+
+        self.state = CompassState::Drawing { key, arc, options };
+    }
+
+    /// Moves the center, or sweeps the arc to the angle of the position. The radius stays the same
+    pub fn motion(
+        &mut self,
+        pos: na::Vector2<f64>,
+        sheet: &mut Sheet,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) {
+        let center = match self.center {
+            Some(center) => center,
+            None => return,
+        };
+
+        match &mut self.state {
+            CompassState::Idle => {}
+            CompassState::MovingCenter => {
+                self.center = Some(pos);
+            }
+            CompassState::Drawing { key, arc, options } => {
+                let diff = pos - center;
+                if diff.magnitude() < ShapeStroke::ARC_START_DIST_MIN {
+                    return;
+                }
+
+                // Unwrapped, so the arc can sweep past a half circle
+                let delta = (diff[1].atan2(diff[0]) - (arc.start_angle + arc.sweep_angle)
+                    + std::f64::consts::PI)
+                    .rem_euclid(std::f64::consts::TAU)
+                    - std::f64::consts::PI;
+                arc.sweep_angle =
+                    (arc.sweep_angle + delta).clamp(-std::f64::consts::TAU, std::f64::consts::TAU);
+
+                sheet
+                    .strokes_state
+                    .replace_stroke(*key, Self::arc_stroke(arc, *options));
+                sheet
+                    .strokes_state
+                    .regenerate_rendering_for_stroke(*key, renderer, zoom);
+            }
+        }
+    }
+
+    /// Finishes the arc. Arcs which barely sweep, e.g. from a tap, are removed
+    pub fn end(&mut self, sheet: &mut Sheet, zoom: f64, renderer: Arc<RwLock<Renderer>>) {
+        if let CompassState::Drawing { key, arc, .. } = std::mem::take(&mut self.state) {
+            if arc.sweep_angle.abs() < Self::SWEEP_ANGLE_MIN {
+                sheet.strokes_state.remove_stroke(key);
+            } else {
+                sheet.strokes_state.update_geometry_for_stroke(key);
+                sheet
+                    .strokes_state
+                    .regenerate_rendering_for_stroke_threaded(key, renderer, zoom);
+            }
+        }
+    }
+
+    fn arc_stroke(arc: &shapes::Arc, options: SmoothOptions) -> StrokeStyle {
+        StrokeStyle::ShapeStroke(ShapeStroke::new_w_shape(
+            Shape::Arc(arc.clone()),
+            options,
+            ArrowheadOptions::default(),
+        ))
+    }
+
+    /// Draws the pinned center, and the radius of the arc which is drawn in millimeters
+    #[cfg(feature = "ui")]
+    pub fn draw(
+        &self,
+        snapshot: &Snapshot,
+        sheet: &Sheet,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<(), anyhow::Error> {
+        let center = match self.center {
+            Some(center) if self.enabled => center,
+            _ => return Ok(()),
+        };
+        let r = Self::CENTER_RADIUS / zoom;
+        let outline_width = Self::OUTLINE_WIDTH / zoom;
+        let color = Self::OUTLINE_COLOR.to_css_color();
+
+        let mut bounds = AABB::from_half_extents(
+            na::Point2::from(center),
+            na::Vector2::from_element(r + outline_width),
+        );
+        let mut group = svg::node::element::Group::new()
+            .add(
+                svg::node::element::Circle::new()
+                    .set("cx", center[0])
+                    .set("cy", center[1])
+                    .set("r", r)
+                    .set("stroke", color.clone())
+                    .set("stroke-width", outline_width)
+                    .set("fill", "none"),
+            )
+            .add(
+                svg::node::element::Path::new()
+                    .set(
+                        "d",
+                        format!(
+                            "M {} {} H {} M {} {} V {}",
+                            center[0] - r,
+                            center[1],
+                            center[0] + r,
+                            center[0],
+                            center[1] - r,
+                            center[1] + r
+                        ),
+                    )
+                    .set("stroke", color.clone())
+                    .set("stroke-width", outline_width),
+            );
+
+        if let (CompassState::Drawing { arc, .. }, true) = (&self.state, self.show_radius) {
+            let end = center + arc.end();
+            let font_size = Self::LABEL_FONT_SIZE / zoom;
+            let label_pos = (center + end) / 2.0 + na::vector![font_size, -font_size] * 0.5;
+            let radius = MeasureUnit::convert_measurement(
+                arc.radius,
+                MeasureUnit::Px,
+                sheet.format.dpi,
+                MeasureUnit::Mm,
+                sheet.format.dpi,
+            );
+
+            group = group
+                .add(
+                    svg::node::element::Line::new()
+                        .set("x1", center[0])
+                        .set("y1", center[1])
+                        .set("x2", end[0])
+                        .set("y2", end[1])
+                        .set("stroke", color.clone())
+                        .set("stroke-width", outline_width)
+                        .set(
+                            "stroke-dasharray",
+                            format!("{} {}", 4.0 * outline_width, 2.0 * outline_width),
+                        ),
+                )
+                .add(
+                    svg::node::element::Text::new()
+                        .set("x", label_pos[0])
+                        .set("y", label_pos[1])
+                        .set("font-size", font_size)
+                        .set("fill", color)
+                        .add(svg::node::Text::new(format!("{:.1} mm", radius))),
+                );
+
+            bounds.take_point(na::Point2::from(end));
+            // Generous space for the label
+            bounds.take_point(na::Point2::from(
+                label_pos + na::vector![10.0 * font_size, -font_size * 1.5],
+            ));
+            bounds.take_point(na::Point2::from(label_pos + na::vector![0.0, font_size]));
+            bounds = bounds.loosened(outline_width);
+        }
+
+        let svg_data = compose::svg_node_to_string(&group)?;
+        let svg = render::Svg { svg_data, bounds };
+
+        let images = renderer
+            .read()
+            .unwrap()
+            .gen_images(zoom, vec![svg], bounds)?;
+        if let Some(rendernode) = render::images_to_rendernode(&images, zoom)
+            .context("images_to_rendernode() failed in compass .draw()")?
+        {
+            snapshot.append_node(&rendernode);
+        }
+
+        Ok(())
+    }
+}
@@ -2,31 +2,197 @@ use std::collections::VecDeque;
 use std::sync::{Arc, RwLock};
 
 use crate::compose::color::Color;
-use crate::compose::geometry::AABBHelpers;
+use crate::compose::geometry::{self, AABBHelpers};
 use crate::render::Renderer;
 use crate::sheet::Sheet;
 use crate::strokes::inputdata::InputData;
 
+#[cfg(feature = "ui")]
 use gtk4::{graphene, gsk, Snapshot};
-use p2d::bounding_volume::AABB;
+use p2d::bounding_volume::{BoundingVolume, AABB};
 use serde::{Deserialize, Serialize};
 
 use super::penbehaviour::PenBehaviour;
 
+/// The style of the eraser
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
+#[repr(u32)]
+#[cfg_attr(feature = "ui", enum_type(name = "EraserStyle"))]
+#[serde(rename = "eraser_style")]
+pub enum EraserStyle {
+    /// Trashes every stroke touched by the eraser
+    #[cfg_attr(
+        feature = "ui",
+        enum_value(name = "TrashCollidingStrokes", nick = "trash-colliding-strokes")
+    )]
+    #[serde(rename = "trash_colliding_strokes")]
+    TrashCollidingStrokes = 0,
+    /// Splits the strokes touched by the eraser, removing only the erased portions
+    #[cfg_attr(
+        feature = "ui",
+        enum_value(name = "SplitCollidingStrokes", nick = "split-colliding-strokes")
+    )]
+    #[serde(rename = "split_colliding_strokes")]
+    SplitCollidingStrokes,
+}
+
+impl Default for EraserStyle {
+    fn default() -> Self {
+        Self::TrashCollidingStrokes
+    }
+}
+
+/// The shape of the eraser
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
+#[repr(u32)]
+#[cfg_attr(feature = "ui", enum_type(name = "EraserShape"))]
+#[serde(rename = "eraser_shape")]
+pub enum EraserShape {
+    /// A square with the width as side length
+    #[cfg_attr(feature = "ui", enum_value(name = "Square", nick = "square"))]
+    #[serde(rename = "square")]
+    Square = 0,
+    /// A circle with the width as diameter
+    #[cfg_attr(feature = "ui", enum_value(name = "Circle", nick = "circle"))]
+    #[serde(rename = "circle")]
+    Circle,
+    /// A band with the width along the motion path since the last input, so that fast motions don't skip strokes
+    #[cfg_attr(feature = "ui", enum_value(name = "MotionPath", nick = "motion-path"))]
+    #[serde(rename = "motion_path")]
+    MotionPath,
+}
+
+impl Default for EraserShape {
+    fn default() -> Self {
+        Self::Square
+    }
+}
+
+/// The area which is erased by the eraser
+#[derive(Debug, Clone, Copy)]
+pub enum EraserFootprint {
+    Rectangle(AABB),
+    /// The area within the radius around the line from start to end
+    Capsule {
+        start: na::Vector2<f64>,
+        end: na::Vector2<f64>,
+        radius: f64,
+    },
+}
+
+impl EraserFootprint {
+    pub fn bounds(&self) -> AABB {
+        match self {
+            Self::Rectangle(bounds) => *bounds,
+            Self::Capsule { start, end, radius } => {
+                AABB::new_positive(na::Point2::from(*start), na::Point2::from(*end))
+                    .loosened(*radius)
+            }
+        }
+    }
+
+    /// The footprint enlarged by the amount in every direction
+    pub fn loosened(&self, amount: f64) -> Self {
+        match *self {
+            Self::Rectangle(bounds) => Self::Rectangle(bounds.loosened(amount)),
+            Self::Capsule { start, end, radius } => Self::Capsule {
+                start,
+                end,
+                radius: radius + amount,
+            },
+        }
+    }
+
+    pub fn intersects_aabb(&self, aabb: &AABB) -> bool {
+        match self {
+            Self::Rectangle(bounds) => bounds.intersects(aabb),
+            Self::Capsule { start, end, radius } => {
+                let capsule = p2d::shape::Capsule::new(
+                    na::Point2::from(*start),
+                    na::Point2::from(*end),
+                    *radius,
+                );
+                let cuboid = p2d::shape::Cuboid::new(aabb.half_extents());
+
+                p2d::query::intersection_test(
+                    &na::Isometry2::identity(),
+                    &capsule,
+                    &na::Isometry2::translation(aabb.center()[0], aabb.center()[1]),
+                    &cuboid,
+                )
+                .unwrap_or_else(|_| self.bounds().intersects(aabb))
+            }
+        }
+    }
+
+    pub fn contains_point(&self, point: na::Vector2<f64>) -> bool {
+        self.clip_line(point, point).is_some()
+    }
+
+    /// Clips the line from start to end to the footprint. Returns the start and end of the clipped line as parameters between 0.0 and 1.0 along the line,
+    /// or None if the line is outside of the footprint
+    pub fn clip_line(
+        &self,
+        line_start: na::Vector2<f64>,
+        line_end: na::Vector2<f64>,
+    ) -> Option<(f64, f64)> {
+        match *self {
+            Self::Rectangle(bounds) => geometry::clip_line_to_bounds(line_start, line_end, bounds),
+            Self::Capsule { start, end, radius } => {
+                let direction = end - start;
+                let length = direction.magnitude();
+
+                let mut clipped = vec![
+                    geometry::clip_line_to_circle(line_start, line_end, start, radius),
+                    geometry::clip_line_to_circle(line_start, line_end, end, radius),
+                ];
+                if length > 0.0 {
+                    // The rectangle between the caps, in its local coordinates
+                    let rotation = na::Rotation2::rotation_between(&direction, &na::Vector2::x());
+
+                    clipped.push(geometry::clip_line_to_bounds(
+                        rotation * (line_start - start),
+                        rotation * (line_end - start),
+                        AABB::new(na::point![0.0, -radius], na::point![length, radius]),
+                    ));
+                }
+
+                // The capsule is convex, so the clipped parts are overlapping
+                clipped.into_iter().flatten().reduce(
+                    |(first_start, first_end), (second_start, second_end)| {
+                        (first_start.min(second_start), first_end.max(second_end))
+                    },
+                )
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default, rename = "eraser")]
 pub struct Eraser {
     #[serde(rename = "width")]
     pub width: f64,
+    #[serde(rename = "style")]
+    pub style: EraserStyle,
+    #[serde(rename = "shape")]
+    pub shape: EraserShape,
     #[serde(skip)]
     pub current_input: Option<InputData>,
+    #[serde(skip)]
+    pub prev_input: Option<InputData>,
 }
 
 impl Default for Eraser {
     fn default() -> Self {
         Self {
             width: Self::WIDTH_DEFAULT,
+            style: EraserStyle::default(),
+            shape: EraserShape::default(),
             current_input: None,
+            prev_input: None,
         }
     }
 }
@@ -41,6 +207,7 @@ impl PenBehaviour for Eraser {
         _renderer: Arc<RwLock<Renderer>>,
     ) {
         self.current_input = data_entries.pop_back();
+        self.prev_input = None;
     }
 
     fn motion(
@@ -48,12 +215,24 @@ impl PenBehaviour for Eraser {
         mut data_entries: VecDeque<InputData>,
         sheet: &mut Sheet,
         viewport: Option<AABB>,
-        _zoom: f64,
-        _renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
     ) {
+        self.prev_input = self.current_input;
         self.current_input = data_entries.pop_back();
 
-        sheet.strokes_state.trash_colliding_strokes(&self, viewport);
+        match self.style {
+            EraserStyle::TrashCollidingStrokes => {
+                sheet.strokes_state.trash_colliding_strokes(self, viewport);
+            }
+            EraserStyle::SplitCollidingStrokes => {
+                let new_keys = sheet.strokes_state.split_colliding_strokes(self, viewport);
+
+                sheet
+                    .strokes_state
+                    .regenerate_rendering_for_strokes_threaded(&new_keys, renderer, zoom);
+            }
+        }
     }
 
     fn end(
@@ -65,8 +244,10 @@ impl PenBehaviour for Eraser {
         _renderer: Arc<RwLock<Renderer>>,
     ) {
         self.current_input = None;
+        self.prev_input = None;
     }
 
+    #[cfg(feature = "ui")]
     fn draw(
         &self,
         snapshot: &Snapshot,
@@ -75,26 +256,60 @@ impl PenBehaviour for Eraser {
         zoom: f64,
         _renderer: Arc<RwLock<Renderer>>,
     ) -> Result<(), anyhow::Error> {
-        if let Some(bounds) = self.gen_bounds(zoom) {
+        if let Some(footprint) = self.footprint() {
+            let fill_color = Self::FILL_COLOR_DEFAULT.to_gdk();
             let border_color = Self::OUTLINE_COLOR_DEFAULT.to_gdk();
             let border_width = 2.0;
 
-            snapshot.append_color(
-                &Self::FILL_COLOR_DEFAULT.to_gdk(),
-                &bounds.to_graphene_rect(),
-            );
-
-            snapshot.append_border(
-                &gsk::RoundedRect::new(
-                    bounds.to_graphene_rect(),
-                    graphene::Size::zero(),
-                    graphene::Size::zero(),
-                    graphene::Size::zero(),
-                    graphene::Size::zero(),
-                ),
-                &[border_width, border_width, border_width, border_width],
-                &[border_color, border_color, border_color, border_color],
-            );
+            match footprint {
+                EraserFootprint::Rectangle(bounds) => {
+                    let bounds = bounds.scale(na::Vector2::from_element(zoom));
+
+                    snapshot.append_color(&fill_color, &bounds.to_graphene_rect());
+
+                    snapshot.append_border(
+                        &gsk::RoundedRect::new(
+                            bounds.to_graphene_rect(),
+                            graphene::Size::zero(),
+                            graphene::Size::zero(),
+                            graphene::Size::zero(),
+                            graphene::Size::zero(),
+                        ),
+                        &[border_width, border_width, border_width, border_width],
+                        &[border_color, border_color, border_color, border_color],
+                    );
+                }
+                EraserFootprint::Capsule { start, end, radius } => {
+                    let direction = (end - start) * zoom;
+                    let length = direction.magnitude() as f32;
+                    let radius = (radius * zoom) as f32;
+
+                    // Drawing the capsule as rounded rect in its local coordinates
+                    snapshot.save();
+                    snapshot.translate(&graphene::Point::new(
+                        (start[0] * zoom) as f32,
+                        (start[1] * zoom) as f32,
+                    ));
+                    snapshot.rotate(direction[1].atan2(direction[0]).to_degrees() as f32);
+
+                    let rect =
+                        graphene::Rect::new(-radius, -radius, length + 2.0 * radius, 2.0 * radius);
+                    let corner = graphene::Size::new(radius, radius);
+                    let rounded_rect = gsk::RoundedRect::new(rect, corner, corner, corner, corner);
+
+                    snapshot.push_rounded_clip(&rounded_rect);
+                    snapshot.append_color(&fill_color, &rect);
+                    snapshot.pop();
+
+                    snapshot.append_border(
+                        &rounded_rect,
+                        &[border_width, border_width, border_width, border_width],
+                        &[border_color, border_color, border_color, border_color],
+                    );
+
+                    snapshot.restore();
+                }
+            }
         }
         Ok(())
     }
@@ -120,25 +335,35 @@ impl Eraser {
     pub fn new(width: f64) -> Self {
         Self {
             width,
+            style: EraserStyle::default(),
+            shape: EraserShape::default(),
             current_input: None,
+            prev_input: None,
         }
     }
 
-    pub fn gen_bounds(&self, zoom: f64) -> Option<AABB> {
-        self.current_input.map_or_else(
-            || None,
-            |current_input| {
-                Some(AABB::new(
-                    na::point![
-                        ((current_input.pos()[0]) - self.width / 2.0) * zoom,
-                        ((current_input.pos()[1]) - self.width / 2.0) * zoom
-                    ],
-                    na::point![
-                        ((current_input.pos()[0]) + self.width / 2.0) * zoom,
-                        ((current_input.pos()[1]) + self.width / 2.0) * zoom
-                    ],
-                ))
+    /// The area which is erased at the current input
+    pub fn footprint(&self) -> Option<EraserFootprint> {
+        let current_pos = self.current_input?.pos();
+        let half_width = self.width * 0.5;
+
+        Some(match self.shape {
+            EraserShape::Square => EraserFootprint::Rectangle(AABB::new(
+                na::Point2::from(current_pos - na::Vector2::from_element(half_width)),
+                na::Point2::from(current_pos + na::Vector2::from_element(half_width)),
+            )),
+            EraserShape::Circle => EraserFootprint::Capsule {
+                start: current_pos,
+                end: current_pos,
+                radius: half_width,
+            },
+            EraserShape::MotionPath => EraserFootprint::Capsule {
+                start: self
+                    .prev_input
+                    .map_or(current_pos, |prev_input| prev_input.pos()),
+                end: current_pos,
+                radius: half_width,
             },
-        )
+        })
     }
 }
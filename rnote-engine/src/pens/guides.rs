@@ -0,0 +1,390 @@
+#[cfg(feature = "ui")]
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "ui")]
+use crate::compose;
+use crate::compose::color::Color;
+#[cfg(feature = "ui")]
+use crate::render::{self, Renderer};
+
+#[cfg(feature = "ui")]
+use anyhow::Context;
+#[cfg(feature = "ui")]
+use gtk4::Snapshot;
+use p2d::bounding_volume::{BoundingVolume, AABB};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ui")]
+use svg::node::element;
+
+/// The kind of a drafting guide
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
+#[serde(rename = "guide_kind")]
+#[cfg_attr(feature = "ui", enum_type(name = "GuideKind"))]
+pub enum GuideKind {
+    /// A straight ruler with two long edges
+    #[serde(rename = "ruler")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Ruler", nick = "ruler"))]
+    Ruler,
+    /// A right isosceles triangle
+    #[serde(rename = "set_square")]
+    #[cfg_attr(feature = "ui", enum_value(name = "SetSquare", nick = "set-square"))]
+    SetSquare,
+}
+
+/// A drafting guide on the canvas. Brush strokes which start close to one of its edges are drawn straight along it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Guide {
+    pub kind: GuideKind,
+    /// The center of the guide, in sheet coordinates
+    pub center: na::Vector2<f64>,
+    /// The rotation of the guide around its center, in radians
+    pub angle: f64,
+}
+
+impl Guide {
+    pub const RULER_LENGTH: f64 = 600.0;
+    pub const RULER_WIDTH: f64 = 60.0;
+    /// The length of the legs of the set square
+    pub const SET_SQUARE_LEG: f64 = 300.0;
+    /// The distance between the ticks on the ruler
+    pub const TICK_SPACING: f64 = 10.0;
+    /// The radius of the handle which rotates the guide
+    pub const ROTATION_HANDLE_RADIUS: f64 = 14.0;
+    pub const FILL_COLOR: Color = Color {
+        r: 0.55,
+        g: 0.65,
+        b: 0.75,
+        a: 0.25,
+    };
+    pub const OUTLINE_COLOR: Color = Color {
+        r: 0.2,
+        g: 0.3,
+        b: 0.4,
+        a: 0.8,
+    };
+    pub const OUTLINE_WIDTH: f64 = 1.5;
+
+    pub fn new(kind: GuideKind, center: na::Vector2<f64>) -> Self {
+        Self {
+            kind,
+            center,
+            angle: 0.0,
+        }
+    }
+
+    /// The vertices of the outline, in local coordinates around the center
+    fn local_vertices(&self) -> Vec<na::Vector2<f64>> {
+        match self.kind {
+            GuideKind::Ruler => {
+                let half_extents = na::vector![Self::RULER_LENGTH, Self::RULER_WIDTH] * 0.5;
+
+                vec![
+                    na::vector![-half_extents[0], -half_extents[1]],
+                    na::vector![half_extents[0], -half_extents[1]],
+                    na::vector![half_extents[0], half_extents[1]],
+                    na::vector![-half_extents[0], half_extents[1]],
+                ]
+            }
+            GuideKind::SetSquare => {
+                // Centered at the centroid
+                let centroid = na::Vector2::from_element(Self::SET_SQUARE_LEG / 3.0);
+
+                vec![
+                    na::vector![0.0, 0.0] - centroid,
+                    na::vector![Self::SET_SQUARE_LEG, 0.0] - centroid,
+                    na::vector![0.0, Self::SET_SQUARE_LEG] - centroid,
+                ]
+            }
+        }
+    }
+
+    fn to_global(&self, local: na::Vector2<f64>) -> na::Vector2<f64> {
+        self.center + na::Rotation2::new(self.angle) * local
+    }
+
+    fn to_local(&self, global: na::Vector2<f64>) -> na::Vector2<f64> {
+        na::Rotation2::new(-self.angle) * (global - self.center)
+    }
+
+    /// The vertices of the outline
+    pub fn vertices(&self) -> Vec<na::Vector2<f64>> {
+        self.local_vertices()
+            .into_iter()
+            .map(|vertex| self.to_global(vertex))
+            .collect()
+    }
+
+    /// The edges strokes are drawn along, as start and end. The short edges of the ruler are not among them
+    pub fn edges(&self) -> Vec<(na::Vector2<f64>, na::Vector2<f64>)> {
+        let vertices = self.vertices();
+
+        match self.kind {
+            GuideKind::Ruler => vec![(vertices[0], vertices[1]), (vertices[3], vertices[2])],
+            GuideKind::SetSquare => (0..vertices.len())
+                .map(|i| (vertices[i], vertices[(i + 1) % vertices.len()]))
+                .collect(),
+        }
+    }
+
+    /// The center of the handle which rotates the guide
+    pub fn rotation_handle(&self) -> na::Vector2<f64> {
+        let local = match self.kind {
+            GuideKind::Ruler => na::vector![(Self::RULER_LENGTH - Self::RULER_WIDTH) * 0.5, 0.0],
+            GuideKind::SetSquare => {
+                na::Vector2::from_element(Self::SET_SQUARE_LEG / 8.0 - Self::SET_SQUARE_LEG / 3.0)
+            }
+        };
+
+        self.to_global(local)
+    }
+
+    /// Whether the position is on the guide
+    pub fn contains(&self, pos: na::Vector2<f64>) -> bool {
+        let local = self.to_local(pos);
+
+        match self.kind {
+            GuideKind::Ruler => {
+                local[0].abs() <= Self::RULER_LENGTH * 0.5
+                    && local[1].abs() <= Self::RULER_WIDTH * 0.5
+            }
+            GuideKind::SetSquare => {
+                let local = local + na::Vector2::from_element(Self::SET_SQUARE_LEG / 3.0);
+                local[0] >= 0.0 && local[1] >= 0.0 && local[0] + local[1] <= Self::SET_SQUARE_LEG
+            }
+        }
+    }
+
+    pub fn bounds(&self) -> AABB {
+        let vertices = self
+            .vertices()
+            .into_iter()
+            .map(na::Point2::from)
+            .collect::<Vec<na::Point2<f64>>>();
+
+        AABB::from_points(&vertices).loosened(Self::OUTLINE_WIDTH)
+    }
+
+    #[cfg(feature = "ui")]
+    fn gen_svg(&self) -> Result<render::Svg, anyhow::Error> {
+        let vertices = self.vertices();
+        let points = vertices
+            .iter()
+            .map(|vertex| format!("{},{}", vertex[0], vertex[1]))
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        let mut group = element::Group::new().add(
+            element::Polygon::new()
+                .set("points", points)
+                .set("fill", Self::FILL_COLOR.to_css_color())
+                .set("stroke", Self::OUTLINE_COLOR.to_css_color())
+                .set("stroke-width", Self::OUTLINE_WIDTH),
+        );
+
+        // Ticks along the edges, longer every five ticks
+        for (start, end) in self.edges() {
+            let length = (end - start).magnitude();
+            let direction = (end - start) / length;
+            // Towards the inside of the guide
+            let normal = na::vector![-direction[1], direction[0]];
+            let normal = if self.contains(start + (end - start) * 0.5 + normal) {
+                normal
+            } else {
+                -normal
+            };
+
+            for i in 0..=((length / Self::TICK_SPACING).floor() as u32) {
+                let tick_start = start + direction * (f64::from(i) * Self::TICK_SPACING);
+                let tick_length = if i % 5 == 0 { 12.0 } else { 6.0 };
+                let tick_end = tick_start + normal * tick_length;
+
+                group = group.add(
+                    element::Line::new()
+                        .set("x1", tick_start[0])
+                        .set("y1", tick_start[1])
+                        .set("x2", tick_end[0])
+                        .set("y2", tick_end[1])
+                        .set("stroke", Self::OUTLINE_COLOR.to_css_color())
+                        .set("stroke-width", 1.0),
+                );
+            }
+        }
+
+        let handle = self.rotation_handle();
+        group = group.add(
+            element::Circle::new()
+                .set("cx", handle[0])
+                .set("cy", handle[1])
+                .set("r", Self::ROTATION_HANDLE_RADIUS)
+                .set("fill", "none")
+                .set("stroke", Self::OUTLINE_COLOR.to_css_color())
+                .set("stroke-width", Self::OUTLINE_WIDTH),
+        );
+
+        let svg_data = compose::svg_node_to_string(&group).map_err(|e| {
+            anyhow::anyhow!("node_to_string() failed in gen_svg() for guide, {}", e)
+        })?;
+
+        Ok(render::Svg {
+            bounds: self.bounds(),
+            svg_data,
+        })
+    }
+}
+
+/// Dragging the guide
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GuideDrag {
+    Move {
+        last_pos: na::Vector2<f64>,
+    },
+    Rotate {
+        start_angle: f64,
+        start_pointer_angle: f64,
+    },
+}
+
+/// The drafting guides. At most one guide is shown at a time
+#[derive(Debug, Clone, Default)]
+pub struct Guides {
+    /// The shown guide
+    pub guide: Option<Guide>,
+    /// The edge the current stroke is constrained to
+    constraint: Option<(na::Vector2<f64>, na::Vector2<f64>)>,
+    drag: Option<GuideDrag>,
+}
+
+impl Guides {
+    /// The distance from an edge within which strokes get constrained to it, in surface coordinates
+    pub const CONSTRAINT_DISTANCE: f64 = 24.0;
+
+    /// Shows a guide of the kind at the position, or hides it with None
+    pub fn show(&mut self, kind: Option<GuideKind>, center: na::Vector2<f64>) {
+        self.guide = kind.map(|kind| Guide::new(kind, center));
+        self.constraint = None;
+        self.drag = None;
+    }
+
+    pub fn dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Starts dragging the guide if the position is on it. Dragging the rotation handle rotates the guide, elsewhere it moves it.
+    /// Returns true if a drag started
+    pub fn begin_drag(&mut self, pos: na::Vector2<f64>) -> bool {
+        let guide = match self.guide {
+            Some(guide) => guide,
+            None => return false,
+        };
+
+        self.drag = if (pos - guide.rotation_handle()).magnitude() <= Guide::ROTATION_HANDLE_RADIUS
+        {
+            let vec = pos - guide.center;
+            Some(GuideDrag::Rotate {
+                start_angle: guide.angle,
+                start_pointer_angle: vec[1].atan2(vec[0]),
+            })
+        } else if guide.contains(pos) {
+            Some(GuideDrag::Move { last_pos: pos })
+        } else {
+            None
+        };
+
+        self.drag.is_some()
+    }
+
+    pub fn drag_to(&mut self, pos: na::Vector2<f64>) {
+        if let (Some(guide), Some(drag)) = (self.guide.as_mut(), self.drag.as_mut()) {
+            match drag {
+                GuideDrag::Move { last_pos } => {
+                    guide.center += pos - *last_pos;
+                    *last_pos = pos;
+                }
+                GuideDrag::Rotate {
+                    start_angle,
+                    start_pointer_angle,
+                } => {
+                    let vec = pos - guide.center;
+                    guide.angle = *start_angle + vec[1].atan2(vec[0]) - *start_pointer_angle;
+                }
+            }
+        }
+    }
+
+    pub fn end_drag(&mut self) {
+        self.drag = None;
+    }
+
+    /// Constrains the stroke that starts at the position to the nearest edge of the guide, if it is close enough to it.
+    /// Returns true if the stroke is constrained
+    pub fn begin_constraint(&mut self, pos: na::Vector2<f64>, zoom: f64) -> bool {
+        let constraint_distance = Self::CONSTRAINT_DISTANCE / zoom;
+
+        self.constraint = self.guide.and_then(|guide| {
+            guide
+                .edges()
+                .into_iter()
+                .map(|edge| (edge, (pos - closest_point_on_edge(pos, edge)).magnitude()))
+                .filter(|(_, distance)| *distance <= constraint_distance)
+                .min_by(|(_, first), (_, second)| {
+                    first
+                        .partial_cmp(second)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .map(|(edge, _)| edge)
+        });
+
+        self.constraint.is_some()
+    }
+
+    /// The position projected onto the line through the edge the current stroke is constrained to. Unchanged without a constraint
+    pub fn constrain(&self, pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        match self.constraint {
+            Some((start, end)) => {
+                let direction = (end - start).normalize();
+                start + direction * (pos - start).dot(&direction)
+            }
+            None => pos,
+        }
+    }
+
+    pub fn end_constraint(&mut self) {
+        self.constraint = None;
+    }
+
+    #[cfg(feature = "ui")]
+    pub fn draw(
+        &self,
+        snapshot: &Snapshot,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<(), anyhow::Error> {
+        if let Some(guide) = self.guide {
+            let svg = guide.gen_svg()?;
+            let bounds = svg.bounds;
+            let images = renderer
+                .read()
+                .unwrap()
+                .gen_images(zoom, vec![svg], bounds)?;
+
+            if let Some(rendernode) = render::images_to_rendernode(&images, zoom)
+                .context("images_to_rendernode() failed in guides.draw()")?
+            {
+                snapshot.append_node(&rendernode);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn closest_point_on_edge(
+    pos: na::Vector2<f64>,
+    (start, end): (na::Vector2<f64>, na::Vector2<f64>),
+) -> na::Vector2<f64> {
+    let edge = end - start;
+    let t = ((pos - start).dot(&edge) / edge.magnitude_squared()).clamp(0.0, 1.0);
+
+    start + edge * t
+}
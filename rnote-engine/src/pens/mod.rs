@@ -2,61 +2,91 @@ pub mod penbehaviour;
 pub mod shortcuts;
 
 pub mod brush;
+pub mod compass;
 pub mod eraser;
+pub mod guides;
+pub mod prediction;
+pub mod presets;
+pub mod pressurecurve;
 pub mod selector;
 pub mod shaper;
+pub mod simplification;
+pub mod smoothing;
+pub mod snapping;
+pub mod stabilizer;
 pub mod tools;
+pub mod typewriter;
 
 use std::collections::VecDeque;
 use std::sync::{Arc, RwLock};
 
+use crate::plugins::{self, PluginPen};
 use crate::render::Renderer;
 use crate::sheet::Sheet;
 use crate::strokes::inputdata::InputData;
+use crate::strokesstate::StrokeKey;
 use crate::surfaceflags::SurfaceFlags;
 
+use self::guides::Guides;
 use self::penbehaviour::PenBehaviour;
+use self::presets::{PenPreset, PenPresets};
 use self::shortcuts::{ShortcutAction, ShortcutKey, Shortcuts};
+use self::snapping::Snapping;
 use self::tools::Tools;
+use self::typewriter::Typewriter;
 use self::{brush::Brush, eraser::Eraser, selector::Selector, shaper::Shaper};
-use gtk4::{glib, glib::prelude::*, Snapshot};
+#[cfg(feature = "ui")]
+use gtk4::Snapshot;
 use num_derive::FromPrimitive;
 use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
+use web_time::Instant;
 
 #[derive(
-    Eq,
-    PartialEq,
-    Clone,
-    Copy,
-    Debug,
-    glib::Enum,
-    Serialize,
-    Deserialize,
-    PartialOrd,
-    Ord,
-    Hash,
-    FromPrimitive,
+    Eq, PartialEq, Clone, Copy, Debug, Serialize, Deserialize, PartialOrd, Ord, Hash, FromPrimitive,
 )]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
 #[repr(u32)]
-#[enum_type(name = "PenStyle")]
+#[cfg_attr(feature = "ui", enum_type(name = "PenStyle"))]
 #[serde(rename = "pen_style")]
 pub enum PenStyle {
-    #[enum_value(name = "BrushStyle", nick = "brush_style")]
+    #[cfg_attr(feature = "ui", enum_value(name = "BrushStyle", nick = "brush_style"))]
     #[serde(rename = "brush_style")]
     BrushStyle,
-    #[enum_value(name = "ShaperStyle", nick = "shaper_style")]
+    #[cfg_attr(
+        feature = "ui",
+        enum_value(name = "ShaperStyle", nick = "shaper_style")
+    )]
     #[serde(rename = "shaper_style")]
     ShaperStyle,
-    #[enum_value(name = "EraserStyle", nick = "eraser_style")]
+    #[cfg_attr(
+        feature = "ui",
+        enum_value(name = "EraserStyle", nick = "eraser_style")
+    )]
     #[serde(rename = "eraser_style")]
     EraserStyle,
-    #[enum_value(name = "SelectorStyle", nick = "selector_style")]
+    #[cfg_attr(
+        feature = "ui",
+        enum_value(name = "SelectorStyle", nick = "selector_style")
+    )]
     #[serde(rename = "selector_style")]
     SelectorStyle,
-    #[enum_value(name = "ToolsStyle", nick = "tools_style")]
+    #[cfg_attr(feature = "ui", enum_value(name = "ToolsStyle", nick = "tools_style"))]
     #[serde(rename = "tools_style")]
     ToolsStyle,
+    #[cfg_attr(
+        feature = "ui",
+        enum_value(name = "TypewriterStyle", nick = "typewriter_style")
+    )]
+    #[serde(rename = "typewriter_style")]
+    TypewriterStyle,
+    /// The pen of a plugin which was selected with `Pens::set_plugin_pen()`
+    #[cfg_attr(
+        feature = "ui",
+        enum_value(name = "PluginStyle", nick = "plugin_style")
+    )]
+    #[serde(rename = "plugin_style")]
+    PluginStyle,
 }
 
 impl Default for PenStyle {
@@ -77,12 +107,17 @@ impl TryFrom<u32> for PenStyle {
 }
 
 impl PenStyle {
+    /// The nick, which is also the nick of the registered enum value with the `ui` feature
     pub fn nick(self) -> String {
-        glib::EnumValue::from_value(&self.to_value())
-            .unwrap()
-            .1
-            .nick()
-            .to_string()
+        match self {
+            PenStyle::BrushStyle => String::from("brush_style"),
+            PenStyle::ShaperStyle => String::from("shaper_style"),
+            PenStyle::EraserStyle => String::from("eraser_style"),
+            PenStyle::SelectorStyle => String::from("selector_style"),
+            PenStyle::ToolsStyle => String::from("tools_style"),
+            PenStyle::TypewriterStyle => String::from("typewriter_style"),
+            PenStyle::PluginStyle => String::from("plugin_style"),
+        }
     }
 
     pub fn display_name(self) -> String {
@@ -92,6 +127,8 @@ impl PenStyle {
             PenStyle::EraserStyle => String::from("Eraser style"),
             PenStyle::SelectorStyle => String::from("Selector style"),
             PenStyle::ToolsStyle => String::from("Tools style"),
+            PenStyle::TypewriterStyle => String::from("Typewriter style"),
+            PenStyle::PluginStyle => String::from("Plugin style"),
         }
     }
     pub fn icon_name(self) -> String {
@@ -101,6 +138,8 @@ impl PenStyle {
             Self::EraserStyle => String::from("pen-eraser-symbolic"),
             Self::SelectorStyle => String::from("pen-selector-symbolic"),
             Self::ToolsStyle => String::from("pen-tools-symbolic"),
+            Self::TypewriterStyle => String::from("pen-typewriter-symbolic"),
+            Self::PluginStyle => String::from("application-x-addon-symbolic"),
         }
     }
 }
@@ -117,6 +156,21 @@ impl Default for PenState {
     }
 }
 
+/// The keyboard keys the pens react to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyboardKey {
+    /// A key producing a printable char
+    Unicode(char),
+    Return,
+    Escape,
+    BackSpace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+}
+
 #[derive(Debug, Clone)]
 pub enum PenEvent {
     DownEvent {
@@ -134,6 +188,10 @@ pub enum PenEvent {
     ChangeStyle(PenStyle),
     ChangeStyleOverride(Option<PenStyle>),
     PressedShortcutkey(ShortcutKey),
+    /// Restores the options of the preset with the given name and changes to its pen style
+    ApplyPreset(String),
+    /// A key was pressed on the canvas
+    KeyPressed(KeyboardKey),
 }
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
@@ -154,6 +212,19 @@ pub struct Pens {
     pub selector: Selector,
     #[serde(rename = "tools")]
     pub tools: Tools,
+    #[serde(rename = "typewriter")]
+    pub typewriter: Typewriter,
+    #[serde(rename = "presets")]
+    pub presets: PenPresets,
+    /// The snapping while building shapes and dragging the selection
+    #[serde(rename = "snapping")]
+    pub snapping: Snapping,
+    /// The drafting guides, which are not kept between sessions
+    #[serde(skip)]
+    pub guides: Guides,
+    /// The pen of a plugin used with `PenStyle::PluginStyle`. Plugins are registered at startup, so it is not kept between sessions
+    #[serde(skip)]
+    plugin_pen: Option<Box<dyn PluginPen>>,
 
     #[serde(skip)]
     pen_shown: bool,
@@ -161,6 +232,8 @@ pub struct Pens {
     state: PenState,
     #[serde(skip)]
     style_override: Option<PenStyle>,
+    #[serde(skip)]
+    presenting: bool,
 }
 
 impl Pens {
@@ -184,6 +257,33 @@ impl Pens {
         self.style_override.unwrap_or(self.style)
     }
 
+    pub fn presenting(&self) -> bool {
+        self.presenting
+    }
+
+    /// While presenting the pens don't edit the sheet, the input only draws laser trails
+    pub fn set_presenting(&mut self, presenting: bool) {
+        self.presenting = presenting;
+    }
+
+    /// The pen of a plugin which was selected with `set_plugin_pen()`
+    pub fn plugin_pen(&self) -> Option<&dyn PluginPen> {
+        self.plugin_pen.as_deref()
+    }
+
+    /// Selects a new instance of the registered plugin pen as the pen for `PenStyle::PluginStyle`. The pen must not be down
+    pub fn set_plugin_pen(&mut self, name: &str) -> Result<(), anyhow::Error> {
+        let plugin_pen = plugins::new_pen(name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "set_plugin_pen() failed, no pen is registered with name `{}`",
+                name
+            )
+        })?;
+
+        self.plugin_pen = Some(plugin_pen);
+        Ok(())
+    }
+
     pub fn register_new_shortcut(&mut self, key: ShortcutKey, action: ShortcutAction) {
         self.shortcuts.insert(key, action);
     }
@@ -192,6 +292,49 @@ impl Pens {
         self.shortcuts.remove(&key)
     }
 
+    /// Saves the current options of the pen with the given style as preset. Overwrites the preset with the same name
+    pub fn save_preset(&mut self, name: String, style: PenStyle) -> Result<(), anyhow::Error> {
+        let preset = match style {
+            PenStyle::BrushStyle => PenPreset::Brush(self.brush.clone()),
+            PenStyle::ShaperStyle => PenPreset::Shaper(self.shaper.clone()),
+            PenStyle::EraserStyle => PenPreset::Eraser(self.eraser.clone()),
+            PenStyle::SelectorStyle
+            | PenStyle::ToolsStyle
+            | PenStyle::TypewriterStyle
+            | PenStyle::PluginStyle => {
+                return Err(anyhow::anyhow!(
+                    "save_preset() failed, presets are not supported for pen style {:?}",
+                    style
+                ))
+            }
+        };
+
+        self.presets.insert(name, preset);
+
+        Ok(())
+    }
+
+    /// Restores the options of the preset. The pen must not be down
+    fn restore_preset(&mut self, preset: PenPreset) {
+        self.style = preset.pen_style();
+
+        match preset {
+            PenPreset::Brush(mut brush) => {
+                brush.current_stroke = None;
+                self.brush = brush;
+            }
+            PenPreset::Shaper(mut shaper) => {
+                shaper.current_stroke = None;
+                self.shaper = shaper;
+            }
+            PenPreset::Eraser(mut eraser) => {
+                eraser.current_input = None;
+                eraser.prev_input = None;
+                self.eraser = eraser;
+            }
+        }
+    }
+
     pub fn list_current_shortcuts(&self) -> Vec<(ShortcutKey, ShortcutAction)> {
         self.shortcuts
             .iter()
@@ -208,7 +351,7 @@ impl Pens {
         zoom: f64,
         renderer: Arc<RwLock<Renderer>>,
     ) -> SurfaceFlags {
-/*         log::debug!(
+        /*         log::debug!(
             "handle_event() with state: {:?}, event: {:?}, style: {:?}, style_override: {:?}",
             self.state,
             event,
@@ -216,12 +359,18 @@ impl Pens {
             self.style_override
         ); */
 
+        if self.presenting {
+            return self.handle_event_presenting(event);
+        }
+
         let mut surface_flags = SurfaceFlags::default();
+        let was_down = self.state == PenState::Down;
+
         match (self.state, event) {
             (
                 PenState::Up,
                 PenEvent::DownEvent {
-                    data_entries,
+                    mut data_entries,
                     shortcut_key,
                 },
             ) => {
@@ -229,7 +378,23 @@ impl Pens {
                     self.handle_shortcut_key(shortcut_key, &mut surface_flags);
                 }
 
-                self.pen_begin(data_entries, sheet, viewport, zoom, renderer);
+                // Everything the pen changes until it is up again is undone at once
+                sheet.strokes_state.begin_history_group();
+
+                // Pressing onto the guide drags it instead of using the pen
+                let drags_guide = data_entries
+                    .back()
+                    .map_or(false, |inputdata| self.guides.begin_drag(inputdata.pos()));
+                if !drags_guide {
+                    self.snap_shaper_input(&mut data_entries, false, sheet, zoom);
+                    self.constrain_brush_input(&mut data_entries, true, zoom);
+                    self.pen_begin(data_entries, sheet, viewport, zoom, renderer);
+                }
+
+                // The typewriter takes on the style of a tapped text, which needs to be shown
+                if self.style_w_override() == PenStyle::TypewriterStyle {
+                    surface_flags.pen_changed = true;
+                }
 
                 self.state = PenState::Down;
                 self.pen_shown = true;
@@ -256,7 +421,22 @@ impl Pens {
                     data_entries,
                     shortcut_key: _,
                 },
+            ) if self.guides.dragging() => {
+                if let Some(inputdata) = data_entries.back() {
+                    self.guides.drag_to(inputdata.pos());
+                }
+
+                surface_flags.redraw = true;
+            }
+            (
+                PenState::Down,
+                PenEvent::MotionEvent {
+                    mut data_entries,
+                    shortcut_key: _,
+                },
             ) => {
+                self.snap_shaper_input(&mut data_entries, true, sheet, zoom);
+                self.constrain_brush_input(&mut data_entries, false, zoom);
                 self.pen_motion(data_entries, sheet, viewport, zoom, renderer);
 
                 surface_flags.redraw = true;
@@ -274,12 +454,43 @@ impl Pens {
                     data_entries,
                     shortcut_key: _,
                 },
+            ) if self.guides.dragging() => {
+                if let Some(inputdata) = data_entries.back() {
+                    self.guides.drag_to(inputdata.pos());
+                }
+                self.guides.end_drag();
+
+                self.state = PenState::Up;
+                self.pen_shown = false;
+
+                surface_flags.redraw = true;
+            }
+            (
+                PenState::Down,
+                PenEvent::UpEvent {
+                    mut data_entries,
+                    shortcut_key: _,
+                },
             ) => {
-                // We deselect the selection here, before updating it when the current style is the selector
-                let all_strokes = sheet.strokes_state.keys_sorted_chrono();
-                sheet.strokes_state.set_selected_keys(&all_strokes, false);
+                self.snap_shaper_input(&mut data_entries, true, sheet, zoom);
+                self.constrain_brush_input(&mut data_entries, false, zoom);
+
+                // We deselect the selection here, before updating it when the current style is the selector.
+                // When stamping the selection is kept, so it can be stamped repeatedly
+                if !self.selector.stamping() {
+                    let all_strokes = sheet.strokes_state.keys_sorted_chrono();
+                    sheet.strokes_state.set_selected_keys(&all_strokes, false);
+                }
 
                 self.pen_end(data_entries, sheet, viewport, zoom, renderer);
+                self.snapping.clear_indicator();
+                self.guides.end_constraint();
+
+                // The brush takes on the color picked by the eyedropper, which needs to be shown
+                if let Some(picked_color) = self.tools.eyedropper_tool.picked_color.take() {
+                    self.brush.set_stroke_color(picked_color);
+                    surface_flags.pen_changed = true;
+                }
 
                 self.state = PenState::Up;
                 self.pen_shown = false;
@@ -297,7 +508,9 @@ impl Pens {
             (PenState::Down, PenEvent::ChangeStyle(new_style)) => {
                 if self.style != new_style {
                     // before changing the style, the current stroke is finished
-                    self.pen_end(VecDeque::new(), sheet, viewport, zoom, renderer);
+                    self.pen_end(VecDeque::new(), sheet, viewport, zoom, renderer.clone());
+                    self.shaper.finish_polyline(sheet, zoom, renderer);
+                    self.typewriter.finish_editing(sheet);
 
                     self.state = PenState::Up;
                     self.pen_shown = false;
@@ -315,6 +528,14 @@ impl Pens {
                     self.style = new_style;
                     //self.style_override = None;
 
+                    if self.shaper.finish_polyline(sheet, zoom, renderer) {
+                        surface_flags.resize = true;
+                        surface_flags.sheet_changed = true;
+                    }
+                    if self.typewriter.finish_editing(sheet) {
+                        surface_flags.sheet_changed = true;
+                    }
+
                     surface_flags.pen_changed = true;
                     surface_flags.redraw = true;
                 }
@@ -351,11 +572,56 @@ impl Pens {
             (PenState::Up, PenEvent::PressedShortcutkey(shortcut_key)) => {
                 self.handle_shortcut_key(shortcut_key, &mut surface_flags);
             }
+            (_, PenEvent::KeyPressed(key)) => {
+                if self.pen_key_pressed(key, sheet, viewport, zoom, renderer) {
+                    surface_flags.redraw = true;
+                    surface_flags.resize = true;
+                    surface_flags.sheet_changed = true;
+                }
+            }
+            (PenState::Down, PenEvent::ApplyPreset(name)) => {
+                if let Some(preset) = self.presets.get(&name).cloned() {
+                    // before restoring the preset, the current stroke is finished
+                    self.pen_end(VecDeque::new(), sheet, viewport, zoom, renderer);
+
+                    self.state = PenState::Up;
+                    self.pen_shown = false;
+                    self.restore_preset(preset);
+
+                    surface_flags.redraw = true;
+                    surface_flags.resize = true;
+                    surface_flags.pen_changed = true;
+                    surface_flags.sheet_changed = true;
+                    surface_flags.selection_changed = true;
+                } else {
+                    log::warn!("applying preset `{}` failed, preset not found", name);
+                }
+            }
+            (PenState::Up, PenEvent::ApplyPreset(name)) => {
+                if let Some(preset) = self.presets.get(&name).cloned() {
+                    self.restore_preset(preset);
+
+                    surface_flags.pen_changed = true;
+                    surface_flags.redraw = true;
+                } else {
+                    log::warn!("applying preset `{}` failed, preset not found", name);
+                }
+            }
+        }
+
+        if was_down && self.state == PenState::Up {
+            sheet.strokes_state.end_history_group();
+        }
+
+        // The trails of the laser fade out
+        if self.tools.laser_tool.has_trails() {
+            surface_flags.animate = true;
         }
 
         surface_flags
     }
 
+    #[cfg(feature = "ui")]
     pub fn draw(
         &self,
         snapshot: &Snapshot,
@@ -364,7 +630,29 @@ impl Pens {
         zoom: f64,
         renderer: Arc<RwLock<Renderer>>,
     ) -> Result<(), anyhow::Error> {
-        if self.pen_shown {
+        self.guides.draw(snapshot, zoom, renderer.clone())?;
+        // The snap indicator is also shown while the selection is dragged
+        self.snapping.draw_indicator(snapshot, zoom);
+        // The pinned center of the compass is shown while the pen is up as well
+        if self.style_w_override() == PenStyle::BrushStyle {
+            self.brush
+                .compass
+                .draw(snapshot, sheet, zoom, renderer.clone())?;
+        }
+        if self.style_w_override() == PenStyle::ToolsStyle
+            && matches!(self.tools.style, tools::ToolStyle::Measure)
+        {
+            self.tools
+                .measure_tool
+                .draw(zoom, sheet.format.dpi, snapshot, renderer.clone())?;
+        }
+        // The laser trails keep fading out when the pen is changed
+        self.tools
+            .laser_tool
+            .draw(zoom, Instant::now(), snapshot, renderer.clone())?;
+
+        // The typewriter shows the edited text while the pen is up
+        if self.pen_shown || self.style_w_override() == PenStyle::TypewriterStyle {
             match self.style_w_override() {
                 PenStyle::BrushStyle => self.brush.draw(snapshot, sheet, viewport, zoom, renderer),
                 PenStyle::ShaperStyle => {
@@ -377,11 +665,85 @@ impl Pens {
                     .selector
                     .draw(snapshot, sheet, viewport, zoom, renderer),
                 PenStyle::ToolsStyle => self.tools.draw(snapshot, sheet, viewport, zoom, renderer),
+                PenStyle::TypewriterStyle => self
+                    .typewriter
+                    .draw(snapshot, sheet, viewport, zoom, renderer),
+                PenStyle::PluginStyle => match &self.plugin_pen {
+                    Some(plugin_pen) => plugin_pen.draw(snapshot, sheet, viewport, zoom, renderer),
+                    None => Ok(()),
+                },
             }
         } else {
             Ok(())
         }
     }
+
+    /// Draws laser trails with the input instead of using the pens
+    fn handle_event_presenting(&mut self, event: PenEvent) -> SurfaceFlags {
+        let mut surface_flags = SurfaceFlags::default();
+
+        match event {
+            PenEvent::DownEvent { data_entries, .. } => {
+                if let Some(inputdata) = data_entries.back() {
+                    self.tools.laser_tool.begin(inputdata.pos());
+                }
+                surface_flags.redraw = true;
+            }
+            PenEvent::MotionEvent { data_entries, .. } => {
+                for inputdata in data_entries.iter() {
+                    self.tools.laser_tool.motion(inputdata.pos());
+                }
+                surface_flags.redraw = true;
+            }
+            PenEvent::UpEvent { data_entries, .. } => {
+                for inputdata in data_entries.iter() {
+                    self.tools.laser_tool.motion(inputdata.pos());
+                }
+                self.tools.laser_tool.end(Instant::now());
+                surface_flags.redraw = true;
+            }
+            _ => {}
+        }
+
+        surface_flags.animate = self.tools.laser_tool.has_trails();
+        surface_flags
+    }
+
+    /// Advances what changes over time, e.g. fades out the laser trails. Returns whether it needs to be called again
+    pub fn update_animations(&mut self, now: Instant) -> bool {
+        self.tools.laser_tool.update(now)
+    }
+
+    fn pen_key_pressed(
+        &mut self,
+        key: KeyboardKey,
+        sheet: &mut Sheet,
+        viewport: Option<AABB>,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> bool {
+        match self.style_w_override() {
+            PenStyle::BrushStyle => self.brush.key_pressed(key, sheet, viewport, zoom, renderer),
+            PenStyle::ShaperStyle => self
+                .shaper
+                .key_pressed(key, sheet, viewport, zoom, renderer),
+            PenStyle::EraserStyle => self
+                .eraser
+                .key_pressed(key, sheet, viewport, zoom, renderer),
+            PenStyle::SelectorStyle => self
+                .selector
+                .key_pressed(key, sheet, viewport, zoom, renderer),
+            PenStyle::ToolsStyle => self.tools.key_pressed(key, sheet, viewport, zoom, renderer),
+            PenStyle::TypewriterStyle => self
+                .typewriter
+                .key_pressed(key, sheet, viewport, zoom, renderer),
+            PenStyle::PluginStyle => match &mut self.plugin_pen {
+                Some(plugin_pen) => plugin_pen.key_pressed(key, sheet, viewport, zoom, renderer),
+                None => false,
+            },
+        }
+    }
+
     fn pen_begin(
         &mut self,
         data_entries: VecDeque<InputData>,
@@ -411,6 +773,15 @@ impl Pens {
                 self.tools
                     .begin(data_entries, sheet, viewport, zoom, renderer);
             }
+            PenStyle::TypewriterStyle => {
+                self.typewriter
+                    .begin(data_entries, sheet, viewport, zoom, renderer);
+            }
+            PenStyle::PluginStyle => {
+                if let Some(plugin_pen) = &mut self.plugin_pen {
+                    plugin_pen.begin(data_entries, sheet, viewport, zoom, renderer);
+                }
+            }
         }
     }
 
@@ -443,6 +814,15 @@ impl Pens {
                 self.tools
                     .motion(data_entries, sheet, viewport, zoom, renderer);
             }
+            PenStyle::TypewriterStyle => {
+                self.typewriter
+                    .motion(data_entries, sheet, viewport, zoom, renderer);
+            }
+            PenStyle::PluginStyle => {
+                if let Some(plugin_pen) = &mut self.plugin_pen {
+                    plugin_pen.motion(data_entries, sheet, viewport, zoom, renderer);
+                }
+            }
         }
     }
 
@@ -475,6 +855,73 @@ impl Pens {
                 self.tools
                     .end(data_entries, sheet, viewport, zoom, renderer);
             }
+            PenStyle::TypewriterStyle => {
+                self.typewriter
+                    .end(data_entries, sheet, viewport, zoom, renderer);
+            }
+            PenStyle::PluginStyle => {
+                if let Some(plugin_pen) = &mut self.plugin_pen {
+                    plugin_pen.end(data_entries, sheet, viewport, zoom, renderer);
+                }
+            }
+        }
+    }
+
+    /// Snaps the positions of the input when the shaper is the current pen
+    fn snap_shaper_input(
+        &mut self,
+        data_entries: &mut VecDeque<InputData>,
+        pen_down: bool,
+        sheet: &Sheet,
+        zoom: f64,
+    ) {
+        if self.style_w_override() != PenStyle::ShaperStyle || !self.snapping.enabled() {
+            return;
+        }
+
+        let anchor = self.shaper.snap_anchor(pen_down);
+        // The shape which is built does not snap to itself
+        let excluded_keys = self
+            .shaper
+            .current_stroke
+            .into_iter()
+            .collect::<Vec<StrokeKey>>();
+
+        for inputdata in data_entries.iter_mut() {
+            inputdata.set_pos(self.snapping.snap_pos(
+                inputdata.pos(),
+                anchor,
+                sheet,
+                &excluded_keys,
+                zoom,
+            ));
+        }
+    }
+
+    /// Constrains the positions of the input to the edge of the guide when the brush is the current pen.
+    /// When the stroke begins, the edge is picked which it starts close to
+    fn constrain_brush_input(
+        &mut self,
+        data_entries: &mut VecDeque<InputData>,
+        begin: bool,
+        zoom: f64,
+    ) {
+        // Arcs drawn with the compass keep their radius instead
+        if self.style_w_override() != PenStyle::BrushStyle || self.brush.compass.enabled() {
+            return;
+        }
+
+        if begin {
+            let constrained = data_entries.front().map_or(false, |inputdata| {
+                self.guides.begin_constraint(inputdata.pos(), zoom)
+            });
+            if !constrained {
+                return;
+            }
+        }
+
+        for inputdata in data_entries.iter_mut() {
+            inputdata.set_pos(self.guides.constrain(inputdata.pos()));
         }
     }
 
@@ -1,6 +1,7 @@
 use std::collections::VecDeque;
 use std::sync::{Arc, RwLock};
 
+#[cfg(feature = "ui")]
 use gtk4::Snapshot;
 use p2d::bounding_volume::AABB;
 
@@ -8,6 +9,8 @@ use crate::render::Renderer;
 use crate::sheet::Sheet;
 use crate::strokes::inputdata::InputData;
 
+use super::KeyboardKey;
+
 pub trait PenBehaviour {
     fn begin(
         &mut self,
@@ -33,6 +36,18 @@ pub trait PenBehaviour {
         zoom: f64,
         renderer: Arc<RwLock<Renderer>>,
     );
+    /// Handles a pressed keyboard key. Returns true if it changed the sheet
+    fn key_pressed(
+        &mut self,
+        _key: KeyboardKey,
+        _sheet: &mut Sheet,
+        _viewport: Option<AABB>,
+        _zoom: f64,
+        _renderer: Arc<RwLock<Renderer>>,
+    ) -> bool {
+        false
+    }
+    #[cfg(feature = "ui")]
     fn draw(
         &self,
         _snapshot: &Snapshot,
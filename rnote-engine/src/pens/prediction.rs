@@ -0,0 +1,179 @@
+use std::collections::VecDeque;
+
+use crate::strokes::element::Element;
+
+use serde::{Deserialize, Serialize};
+
+/// The Options of the input prediction
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "prediction_options")]
+pub struct PredictionOptions {
+    /// Whether the prediction is enabled
+    #[serde(rename = "enabled")]
+    pub enabled: bool,
+    /// How far ahead the input is predicted, in seconds. Should be about the latency between the stylus and the screen
+    #[serde(rename = "lookahead")]
+    pub lookahead: f64,
+    /// The amount of predicted points, spread evenly over the lookahead
+    #[serde(rename = "n_points")]
+    pub n_points: usize,
+}
+
+impl Default for PredictionOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            lookahead: Self::LOOKAHEAD_DEFAULT,
+            n_points: Self::N_POINTS_DEFAULT,
+        }
+    }
+}
+
+impl PredictionOptions {
+    /// Lookahead default
+    pub const LOOKAHEAD_DEFAULT: f64 = 0.03;
+    /// The min lookahead
+    pub const LOOKAHEAD_MIN: f64 = 0.0;
+    /// The max lookahead
+    pub const LOOKAHEAD_MAX: f64 = 0.1;
+    /// Amount of points default
+    pub const N_POINTS_DEFAULT: usize = 2;
+    /// The min amount of points
+    pub const N_POINTS_MIN: usize = 1;
+    /// The max amount of points
+    pub const N_POINTS_MAX: usize = 2;
+}
+
+/// The input prediction. It extrapolates the next points from the velocity and acceleration of the latest elements,
+/// which are drawn provisionally ahead of the stroke to hide the latency until the real input arrives.
+/// The predicted points are never added to the stroke, every new element replaces them with a new prediction
+#[derive(Debug, Clone, Default)]
+pub struct Predictor {
+    /// The latest elements of the stroke, oldest first
+    recent: VecDeque<Element>,
+    predicted: Vec<Element>,
+}
+
+impl Predictor {
+    /// Elements older than this (in seconds) compared to the latest element are not used for the prediction
+    pub const HISTORY_DURATION: f64 = 0.05;
+    /// The max amount of kept elements
+    const HISTORY_LEN: usize = 8;
+    /// Time spans shorter than this (in seconds) are considered simultaneous. Elements processed in the same batch have nearly the same timestamp
+    const MIN_DURATION: f64 = 0.001;
+
+    /// The predicted points, which should be drawn ahead of the stroke
+    pub fn predicted(&self) -> &[Element] {
+        &self.predicted
+    }
+
+    /// Resets the state. Must be called when a stroke begins and ends
+    pub fn reset(&mut self) {
+        self.recent.clear();
+        self.predicted.clear();
+    }
+
+    /// Records the element which was added to the stroke and predicts the next points from it, replacing the previous prediction
+    pub fn update(&mut self, element: Element, options: &PredictionOptions) {
+        self.recent.push_back(element);
+        while self.recent.len() > Self::HISTORY_LEN {
+            self.recent.pop_front();
+        }
+        while self.recent.len() > 2
+            && Self::duration(&self.recent[0], &element)
+                .map_or(true, |duration| duration > Self::HISTORY_DURATION)
+        {
+            self.recent.pop_front();
+        }
+
+        self.predicted.clear();
+        if !options.enabled {
+            return;
+        }
+
+        let (velocity, acceleration) = match self.motion() {
+            Some(motion) => motion,
+            None => return,
+        };
+
+        let lookahead = options.lookahead.clamp(
+            PredictionOptions::LOOKAHEAD_MIN,
+            PredictionOptions::LOOKAHEAD_MAX,
+        );
+        let n_points = options.n_points.clamp(
+            PredictionOptions::N_POINTS_MIN,
+            PredictionOptions::N_POINTS_MAX,
+        );
+        if lookahead <= 0.0 {
+            return;
+        }
+
+        // Limits the change of the velocity over the lookahead, so that jitter and sudden stops don't let the prediction overshoot
+        let max_acceleration = velocity.magnitude() / lookahead;
+        let acceleration = if acceleration.magnitude() > max_acceleration {
+            acceleration * (max_acceleration / acceleration.magnitude())
+        } else {
+            acceleration
+        };
+
+        let pos = element.inputdata.pos();
+        self.predicted = (1..=n_points)
+            .map(|i| {
+                let t = lookahead * i as f64 / n_points as f64;
+
+                let mut predicted = element;
+                predicted
+                    .inputdata
+                    .set_pos(pos + velocity * t + acceleration * (0.5 * t * t));
+                predicted.timestamp = element.timestamp.map(|timestamp| {
+                    timestamp + chrono::Duration::microseconds((t * 1_000_000.0) as i64)
+                });
+
+                predicted
+            })
+            .collect();
+    }
+
+    /// The duration between the elements in seconds. None if they have no timestamps
+    fn duration(first: &Element, second: &Element) -> Option<f64> {
+        Some(
+            (second.timestamp? - first.timestamp?)
+                .to_std()
+                .ok()?
+                .as_secs_f64(),
+        )
+    }
+
+    /// The velocity and the acceleration of the latest elements, in units per second (squared). None if they don't span enough time
+    fn motion(&self) -> Option<(na::Vector2<f64>, na::Vector2<f64>)> {
+        let first = self.recent.front()?;
+        let last = self.recent.back()?;
+        let duration = Self::duration(first, last)?;
+        if duration < Self::MIN_DURATION {
+            return None;
+        }
+
+        // The velocities over the first and the second half of the elements give the latest velocity and the acceleration.
+        // When the halves span too little time, the average velocity is used without acceleration
+        let mid = &self.recent[self.recent.len() / 2];
+        match (Self::duration(first, mid), Self::duration(mid, last)) {
+            (Some(first_duration), Some(second_duration))
+                if first_duration >= Self::MIN_DURATION
+                    && second_duration >= Self::MIN_DURATION =>
+            {
+                let first_velocity = (mid.inputdata.pos() - first.inputdata.pos()) / first_duration;
+                let second_velocity =
+                    (last.inputdata.pos() - mid.inputdata.pos()) / second_duration;
+
+                Some((
+                    second_velocity,
+                    (second_velocity - first_velocity) / (duration * 0.5),
+                ))
+            }
+            _ => Some((
+                (last.inputdata.pos() - first.inputdata.pos()) / duration,
+                na::Vector2::zeros(),
+            )),
+        }
+    }
+}
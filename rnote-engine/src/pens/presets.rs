@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::brush::Brush;
+use super::eraser::Eraser;
+use super::shaper::Shaper;
+use super::PenStyle;
+
+/// A snapshot of the options of a pen. The brush snapshot includes the options of all brush styles ( marker, solid, textured, .. )
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "pen_preset")]
+pub enum PenPreset {
+    #[serde(rename = "brush")]
+    Brush(Brush),
+    #[serde(rename = "shaper")]
+    Shaper(Shaper),
+    #[serde(rename = "eraser")]
+    Eraser(Eraser),
+}
+
+impl PenPreset {
+    /// The pen style the preset belongs to
+    pub fn pen_style(&self) -> PenStyle {
+        match self {
+            PenPreset::Brush(_) => PenStyle::BrushStyle,
+            PenPreset::Shaper(_) => PenStyle::ShaperStyle,
+            PenPreset::Eraser(_) => PenStyle::EraserStyle,
+        }
+    }
+}
+
+/// The stored pen presets, by their user given names
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "pen_presets")]
+pub struct PenPresets {
+    #[serde(rename = "presets")]
+    presets: BTreeMap<String, PenPreset>,
+}
+
+impl PenPresets {
+    /// Inserts a preset, replacing and returning the preset with the same name if there is one
+    pub fn insert(&mut self, name: String, preset: PenPreset) -> Option<PenPreset> {
+        self.presets.insert(name, preset)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<PenPreset> {
+        self.presets.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PenPreset> {
+        self.presets.get(name)
+    }
+
+    /// The names of the presets and their pen style, sorted by name
+    pub fn list_presets(&self) -> Vec<(String, PenStyle)> {
+        self.presets
+            .iter()
+            .map(|(name, preset)| (name.clone(), preset.pen_style()))
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.presets.clear();
+    }
+
+    /// Imports presets from bytes, replacing the presets with the same names
+    pub fn import_from_json_bytes(&mut self, bytes: &[u8]) -> Result<(), anyhow::Error> {
+        let imported = serde_json::from_slice::<PenPresets>(bytes)?;
+
+        self.presets.extend(imported.presets);
+
+        Ok(())
+    }
+
+    pub fn export_as_json_bytes(&self) -> Result<Vec<u8>, anyhow::Error> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+}
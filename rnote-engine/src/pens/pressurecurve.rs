@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+
+/// A curve remapping the pressure of the input, to adjust for the different pressure responses of styluses.
+/// Maps pressures between 0.0 and 1.0 to pressures between 0.0 and 1.0
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "pressure_curve")]
+pub enum PressureCurve {
+    /// The pressure is used as is
+    #[serde(rename = "linear")]
+    Linear,
+    /// A cubic bezier curve from (0.0, 0.0) to (1.0, 1.0), with the two control points in between. Like CSS `cubic-bezier()`
+    #[serde(rename = "cubic_bezier")]
+    CubicBezier {
+        #[serde(rename = "cp1")]
+        cp1: na::Vector2<f64>,
+        #[serde(rename = "cp2")]
+        cp2: na::Vector2<f64>,
+    },
+    /// A lookup table of output pressures, evenly spaced over the input pressures and linearly interpolated in between
+    #[serde(rename = "lut")]
+    Lut {
+        #[serde(rename = "values")]
+        values: Vec<f64>,
+    },
+}
+
+impl Default for PressureCurve {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+impl PressureCurve {
+    /// The iterations when solving the bezier curve for the input pressure
+    pub const BEZIER_SOLVE_ITERATIONS: usize = 24;
+
+    /// A curve that needs less pressure to reach wide strokes
+    pub fn soft() -> Self {
+        Self::CubicBezier {
+            cp1: na::vector![0.0, 0.5],
+            cp2: na::vector![0.5, 1.0],
+        }
+    }
+
+    /// A curve that needs more pressure to reach wide strokes
+    pub fn hard() -> Self {
+        Self::CubicBezier {
+            cp1: na::vector![0.5, 0.0],
+            cp2: na::vector![1.0, 0.5],
+        }
+    }
+
+    /// Remaps the pressure
+    pub fn apply(&self, pressure: f64) -> f64 {
+        let pressure = pressure.clamp(0.0, 1.0);
+
+        let mapped = match self {
+            Self::Linear => pressure,
+            Self::CubicBezier { cp1, cp2 } => Self::solve_cubbez(*cp1, *cp2, pressure),
+            Self::Lut { values } => Self::interpolate_lut(values, pressure),
+        };
+
+        mapped.clamp(0.0, 1.0)
+    }
+
+    /// Finds the y coordinate of the curve for the x coordinate with bisection. The x coordinates of the control points are clamped between 0.0 and 1.0,
+    /// so x is monotonically increasing over the curve
+    fn solve_cubbez(cp1: na::Vector2<f64>, cp2: na::Vector2<f64>, x: f64) -> f64 {
+        fn cubbez_coord(p1: f64, p2: f64, t: f64) -> f64 {
+            let mt = 1.0 - t;
+            3.0 * mt * mt * t * p1 + 3.0 * mt * t * t * p2 + t * t * t
+        }
+
+        let (cp1_x, cp2_x) = (cp1[0].clamp(0.0, 1.0), cp2[0].clamp(0.0, 1.0));
+
+        let (mut t_min, mut t_max) = (0.0, 1.0);
+        for _ in 0..Self::BEZIER_SOLVE_ITERATIONS {
+            let t = (t_min + t_max) * 0.5;
+            if cubbez_coord(cp1_x, cp2_x, t) < x {
+                t_min = t;
+            } else {
+                t_max = t;
+            }
+        }
+
+        cubbez_coord(cp1[1], cp2[1], (t_min + t_max) * 0.5)
+    }
+
+    fn interpolate_lut(values: &[f64], x: f64) -> f64 {
+        match values.len() {
+            0 => x,
+            1 => values[0],
+            len => {
+                let pos = x * (len - 1) as f64;
+                let i = (pos.floor() as usize).min(len - 2);
+
+                values[i] + (values[i + 1] - values[i]) * (pos - i as f64)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The input pressures from 0.0 to 1.0 in steps of a 20th
+    fn pressures() -> impl Iterator<Item = f64> {
+        (0..=20).map(|i| f64::from(i) / 20.0)
+    }
+
+    #[test]
+    fn linear_curve_keeps_the_pressure() {
+        for pressure in pressures() {
+            assert_eq!(PressureCurve::Linear.apply(pressure), pressure);
+        }
+        assert_eq!(PressureCurve::Linear.apply(-0.5), 0.0);
+        assert_eq!(PressureCurve::Linear.apply(1.5), 1.0);
+    }
+
+    #[test]
+    fn bezier_curves_are_solved_for_the_pressure() {
+        // The control points on the diagonal make the bezier linear
+        let diagonal = PressureCurve::CubicBezier {
+            cp1: na::vector![1.0 / 3.0, 1.0 / 3.0],
+            cp2: na::vector![2.0 / 3.0, 2.0 / 3.0],
+        };
+        for pressure in pressures() {
+            assert!((diagonal.apply(pressure) - pressure).abs() < 1e-6);
+        }
+
+        let (soft, hard) = (PressureCurve::soft(), PressureCurve::hard());
+        for curve in [&soft, &hard] {
+            assert!(curve.apply(0.0).abs() < 1e-6);
+            assert!((curve.apply(1.0) - 1.0).abs() < 1e-6);
+
+            let mapped = pressures()
+                .map(|pressure| curve.apply(pressure))
+                .collect::<Vec<f64>>();
+            assert!(mapped.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+        for pressure in pressures().skip(1).take(19) {
+            assert!(soft.apply(pressure) > pressure);
+            assert!(hard.apply(pressure) < pressure);
+        }
+    }
+
+    #[test]
+    fn lut_is_interpolated() {
+        let lut = PressureCurve::Lut {
+            values: vec![0.0, 0.8, 1.0],
+        };
+        assert_eq!(lut.apply(0.0), 0.0);
+        assert!((lut.apply(0.25) - 0.4).abs() < 1e-9);
+        assert_eq!(lut.apply(0.5), 0.8);
+        assert!((lut.apply(0.75) - 0.9).abs() < 1e-9);
+        assert_eq!(lut.apply(1.0), 1.0);
+
+        assert_eq!(PressureCurve::Lut { values: vec![] }.apply(0.3), 0.3);
+        assert_eq!(PressureCurve::Lut { values: vec![0.6] }.apply(0.3), 0.6);
+        // The output is clamped as well
+        assert_eq!(
+            PressureCurve::Lut {
+                values: vec![-1.0, 2.0]
+            }
+            .apply(0.0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn pressure_curve_serde() {
+        assert_eq!(
+            serde_json::from_str::<PressureCurve>(r#""linear""#).unwrap(),
+            PressureCurve::default()
+        );
+
+        for curve in [
+            PressureCurve::soft(),
+            PressureCurve::Lut {
+                values: vec![0.0, 0.5, 1.0],
+            },
+        ] {
+            let json = serde_json::to_string(&curve).unwrap();
+            assert_eq!(serde_json::from_str::<PressureCurve>(&json).unwrap(), curve);
+        }
+
+        // Brushes saved before the pressure curve existed keep the pressure
+        let brush = serde_json::from_str::<crate::pens::brush::Brush>("{}").unwrap();
+        assert_eq!(brush.pressure_curve, PressureCurve::Linear);
+    }
+}
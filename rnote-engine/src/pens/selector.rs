@@ -1,29 +1,45 @@
 use std::collections::VecDeque;
 use std::sync::{Arc, RwLock};
 
-use crate::compose::{self, color::Color};
-use crate::render::{self, Renderer};
+#[cfg(feature = "ui")]
+use crate::compose;
+use crate::compose::color::Color;
+#[cfg(feature = "ui")]
+use crate::render;
+use crate::render::Renderer;
 use crate::sheet::Sheet;
 use crate::strokes::inputdata::InputData;
+use crate::strokesstate::selection_comp::SelectionComponent;
 
+#[cfg(feature = "ui")]
 use anyhow::Context;
-use gtk4::{glib, Snapshot};
+#[cfg(feature = "ui")]
+use gtk4::Snapshot;
 use p2d::bounding_volume::{BoundingVolume, AABB};
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "ui")]
 use svg::node::element;
 
 use super::penbehaviour::PenBehaviour;
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, glib::Enum)]
+/// The selection mode of the selector
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
 #[serde(rename = "selector_style")]
-#[enum_type(name = "SelectorStyle")]
+#[cfg_attr(feature = "ui", enum_type(name = "SelectorStyle"))]
 pub enum SelectorStyle {
+    /// A freehand lasso. The path is closed to a polygon, strokes with all their hitboxes inside the polygon get selected
     #[serde(rename = "polygon")]
-    #[enum_value(name = "Polygon", nick = "polygon")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Polygon", nick = "polygon"))]
     Polygon,
+    /// A rectangle spanned from the first to the last position
     #[serde(rename = "rectangle")]
-    #[enum_value(name = "Rectangle", nick = "rectangle")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Rectangle", nick = "rectangle"))]
     Rectangle,
+    /// Tapping selects the topmost stroke at the position
+    #[serde(rename = "single")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Single", nick = "single"))]
+    Single,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -33,6 +49,18 @@ pub struct Selector {
     pub style: SelectorStyle,
     #[serde(skip)]
     pub path: Vec<InputData>,
+    /// Whether tapping toggles the stroke in and out of the current selection instead of replacing it. Set while shift is held down
+    #[serde(skip)]
+    pub toggle_selection: bool,
+    /// The offset of the duplicates when duplicating the selection
+    #[serde(rename = "duplication_offset")]
+    pub duplication_offset: na::Vector2<f64>,
+    /// The stamp mode. While there is a selection, each tap stamps a clone of it centered at the tapped position
+    #[serde(rename = "stamp")]
+    pub stamp: bool,
+    /// Whether the current input stamped the selection
+    #[serde(skip)]
+    stamping: bool,
 }
 
 impl Default for Selector {
@@ -40,6 +68,13 @@ impl Default for Selector {
         Self {
             style: SelectorStyle::Polygon,
             path: vec![],
+            toggle_selection: false,
+            duplication_offset: na::vector![
+                SelectionComponent::SELECTION_DUPLICATION_OFFSET_X,
+                SelectionComponent::SELECTION_DUPLICATION_OFFSET_Y
+            ],
+            stamp: false,
+            stamping: false,
         }
     }
 }
@@ -48,16 +83,26 @@ impl PenBehaviour for Selector {
     fn begin(
         &mut self,
         mut data_entries: VecDeque<InputData>,
-        _sheet: &mut Sheet,
+        sheet: &mut Sheet,
         _viewport: Option<AABB>,
-        _zoom: f64,
-        _renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
     ) {
         //log::debug!("selector begin");
 
         self.path.clear();
 
         if let Some(inputdata) = data_entries.pop_back() {
+            if self.stamp && sheet.strokes_state.selection_len() > 0 {
+                let stamped = sheet.strokes_state.stamp_selection(inputdata.pos());
+                sheet
+                    .strokes_state
+                    .regenerate_rendering_for_strokes(&stamped, renderer, zoom);
+
+                self.stamping = true;
+                return;
+            }
+
             self.path.push(inputdata);
         }
     }
@@ -72,6 +117,10 @@ impl PenBehaviour for Selector {
     ) {
         //log::debug!("selector motion. data_entries: {:?}, self.path.len(): {}", data_entries, self.path.len());
 
+        if self.stamping {
+            return;
+        }
+
         if let Some(inputdata) = data_entries.pop_back() {
             let style = self.style;
 
@@ -87,6 +136,9 @@ impl PenBehaviour for Selector {
                         self.path.insert(1, inputdata);
                     }
                 }
+                SelectorStyle::Single => {
+                    // Selecting at the tapped position
+                }
             }
         }
     }
@@ -101,6 +153,11 @@ impl PenBehaviour for Selector {
     ) {
         //log::debug!("selector end");
 
+        if self.stamping {
+            self.stamping = false;
+            return;
+        }
+
         sheet
             .strokes_state
             .update_selection_for_selector(&self, viewport);
@@ -113,6 +170,7 @@ impl PenBehaviour for Selector {
         self.path.clear();
     }
 
+    #[cfg(feature = "ui")]
     fn draw(
         &self,
         snapshot: &Snapshot,
@@ -152,6 +210,10 @@ impl PenBehaviour for Selector {
                             data.line_to((first.pos()[0] + offset[0], last.pos()[1] + offset[1]));
                     }
                 }
+                SelectorStyle::Single => {
+                    // Nothing to draw for a tap
+                    return Ok(());
+                }
             }
             data = data.close();
 
@@ -198,6 +260,15 @@ impl Selector {
         b: 0.7,
         a: 0.15,
     };
+    /// The distance a tap can be away from a stroke to still select it
+    pub const TAP_TOLERANCE: f64 = 4.0;
+    /// The min / max offset of the duplicates
+    pub const DUPLICATION_OFFSET_MAX: f64 = 1000.0;
+
+    /// Whether the current input stamped the selection. The selection is kept while stamping
+    pub fn stamping(&self) -> bool {
+        self.stamping
+    }
 
     pub fn gen_bounds(&self) -> Option<AABB> {
         // Making sure bounds are always outside of coord + width
@@ -1,14 +1,15 @@
 use crate::render::Renderer;
 use crate::strokes::inputdata::InputData;
 use crate::utils;
-use gtk4::glib;
 use p2d::bounding_volume::{BoundingVolume, AABB};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 use std::sync::{Arc, RwLock};
 
+use crate::compose::arrowhead::ArrowheadOptions;
 use crate::compose::rough::roughoptions::RoughOptions;
+use crate::compose::shapes;
 use crate::compose::smooth::SmoothOptions;
 use crate::sheet::Sheet;
 use crate::strokes::element::Element;
@@ -17,20 +18,43 @@ use crate::strokes::strokestyle::StrokeStyle;
 use crate::strokesstate::StrokeKey;
 
 use super::penbehaviour::PenBehaviour;
+use super::KeyboardKey;
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, glib::Enum)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
 #[serde(rename = "shaperstyle")]
-#[enum_type(name = "ShaperStyle")]
+#[cfg_attr(feature = "ui", enum_type(name = "ShaperStyle"))]
 pub enum ShaperStyle {
     #[serde(rename = "line")]
-    #[enum_value(name = "Line", nick = "line")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Line", nick = "line"))]
     Line,
     #[serde(rename = "rectangle")]
-    #[enum_value(name = "Rectangle", nick = "rectangle")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Rectangle", nick = "rectangle"))]
     Rectangle,
     #[serde(rename = "ellipse")]
-    #[enum_value(name = "Ellipse", nick = "ellipse")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Ellipse", nick = "ellipse"))]
     Ellipse,
+    #[serde(rename = "regular_polygon")]
+    #[cfg_attr(
+        feature = "ui",
+        enum_value(name = "RegularPolygon", nick = "regular-polygon")
+    )]
+    RegularPolygon,
+    #[serde(rename = "star")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Star", nick = "star"))]
+    Star,
+    #[serde(rename = "arc")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Arc", nick = "arc"))]
+    Arc,
+    #[serde(rename = "parallelogram")]
+    #[cfg_attr(
+        feature = "ui",
+        enum_value(name = "Parallelogram", nick = "parallelogram")
+    )]
+    Parallelogram,
+    #[serde(rename = "polyline")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Polyline", nick = "polyline"))]
+    Polyline,
 }
 
 impl Default for ShaperStyle {
@@ -39,14 +63,15 @@ impl Default for ShaperStyle {
     }
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, glib::Enum)]
-#[enum_type(name = "ShaperDrawStyle")]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
+#[cfg_attr(feature = "ui", enum_type(name = "ShaperDrawStyle"))]
 #[serde(rename = "shaper_drawstyle")]
 pub enum ShaperDrawStyle {
-    #[enum_value(name = "Smooth", nick = "smooth")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Smooth", nick = "smooth"))]
     #[serde(rename = "smooth")]
     Smooth,
-    #[enum_value(name = "Rough", nick = "rough")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Rough", nick = "rough"))]
     #[serde(rename = "rough")]
     Rough,
 }
@@ -62,6 +87,26 @@ impl ShaperDrawStyle {
     pub const ROUGH_MARGIN: f64 = 20.0;
 }
 
+/// The state of a polyline that is built vertex by vertex, each pen down placing a new one
+#[derive(Debug, Clone)]
+pub struct PolylineBuilder {
+    /// The stroke of the polyline
+    pub stroke_key: StrokeKey,
+    /// The placed vertices. The last one follows the pen while it is down
+    pub vertices: Vec<na::Vector2<f64>>,
+    /// Whether the polyline was closed into a polygon
+    pub closed: bool,
+    /// The position and time of the latest pen down, to detect double taps
+    pub last_down: Option<(na::Vector2<f64>, chrono::DateTime<chrono::Utc>)>,
+}
+
+impl PolylineBuilder {
+    /// The max distance between two taps to be a double tap, or between a tap and the first vertex to close the polyline
+    pub const TAP_DIST_MAX: f64 = 8.0;
+    /// The max duration between two taps to be a double tap, in milliseconds
+    pub const DOUBLE_TAP_DURATION_MAX: i64 = 400;
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "shaper")]
 pub struct Shaper {
@@ -73,6 +118,21 @@ pub struct Shaper {
     pub smooth_options: SmoothOptions,
     #[serde(rename = "rough_options")]
     pub rough_options: RoughOptions,
+    /// The arrowheads of lines, arcs and open polylines
+    #[serde(rename = "arrowhead_options")]
+    pub arrowhead_options: ArrowheadOptions,
+    /// The number of sides of regular polygons
+    #[serde(rename = "polygon_n_sides")]
+    pub polygon_n_sides: u32,
+    /// The number of points of stars
+    #[serde(rename = "star_n_points")]
+    pub star_n_points: u32,
+    /// The inner radius of stars, relative to the outer radius
+    #[serde(rename = "star_inner_radius_ratio")]
+    pub star_inner_radius_ratio: f64,
+    /// The skew of parallelograms, relative to their width
+    #[serde(rename = "parallelogram_skew")]
+    pub parallelogram_skew: f64,
 
     #[serde(skip)]
     pub current_stroke: Option<StrokeKey>,
@@ -80,6 +140,8 @@ pub struct Shaper {
     pub rect_start: na::Vector2<f64>,
     #[serde(skip)]
     pub rect_current: na::Vector2<f64>,
+    #[serde(skip)]
+    pub polyline_builder: Option<PolylineBuilder>,
 }
 
 impl Default for Shaper {
@@ -89,9 +151,15 @@ impl Default for Shaper {
             drawstyle: ShaperDrawStyle::default(),
             smooth_options: SmoothOptions::default(),
             rough_options: RoughOptions::default(),
+            arrowhead_options: ArrowheadOptions::default(),
+            polygon_n_sides: shapes::RegularPolygon::N_SIDES_DEFAULT,
+            star_n_points: shapes::Star::N_POINTS_DEFAULT,
+            star_inner_radius_ratio: shapes::Star::INNER_RADIUS_RATIO_DEFAULT,
+            parallelogram_skew: shapes::Parallelogram::SKEW_DEFAULT,
             current_stroke: None,
             rect_start: na::vector![0.0, 0.0],
             rect_current: na::vector![0.0, 0.0],
+            polyline_builder: None,
         }
     }
 }
@@ -102,8 +170,8 @@ impl PenBehaviour for Shaper {
         mut data_entries: VecDeque<InputData>,
         sheet: &mut Sheet,
         _viewport: Option<AABB>,
-        _zoom: f64,
-        _renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
     ) {
         self.current_stroke = None;
 
@@ -111,9 +179,18 @@ impl PenBehaviour for Shaper {
 
         utils::filter_mapped_inputdata(filter_bounds, &mut data_entries);
 
+        if !matches!(self.style, ShaperStyle::Polyline) {
+            self.finish_polyline(sheet, zoom, renderer.clone());
+        }
+
         if let Some(inputdata) = data_entries.pop_back() {
             let element = Element::new(inputdata);
 
+            if matches!(self.style, ShaperStyle::Polyline) {
+                self.polyline_begin(element, sheet, zoom, renderer);
+                return;
+            }
+
             let shapestroke = StrokeStyle::ShapeStroke(ShapeStroke::new(element, self));
             self.rect_start = element.inputdata.pos();
             self.rect_current = element.inputdata.pos();
@@ -174,4 +251,150 @@ impl PenBehaviour for Shaper {
             }
         }
     }
+
+    fn key_pressed(
+        &mut self,
+        key: KeyboardKey,
+        sheet: &mut Sheet,
+        _viewport: Option<AABB>,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> bool {
+        match key {
+            KeyboardKey::Return => self.finish_polyline(sheet, zoom, renderer),
+            // Discards the polyline that is currently built
+            KeyboardKey::Escape => {
+                if let Some(builder) = self.polyline_builder.take() {
+                    sheet.strokes_state.remove_stroke(builder.stroke_key);
+                    self.current_stroke = None;
+
+                    true
+                } else {
+                    false
+                }
+            }
+            KeyboardKey::Unicode(_)
+            | KeyboardKey::BackSpace
+            | KeyboardKey::Delete
+            | KeyboardKey::Left
+            | KeyboardKey::Right
+            | KeyboardKey::Home
+            | KeyboardKey::End => false,
+        }
+    }
+}
+
+impl Shaper {
+    /// Places a new vertex of the polyline, or closes / finishes it on a tap onto the first vertex / a double tap
+    fn polyline_begin(
+        &mut self,
+        element: Element,
+        sheet: &mut Sheet,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) {
+        let pos = element.inputdata.pos();
+        let now = chrono::Utc::now();
+
+        let builder = if let Some(builder) = self.polyline_builder.as_mut() {
+            builder
+        } else {
+            let shapestroke = StrokeStyle::ShapeStroke(ShapeStroke::new(element, self));
+            let stroke_key = sheet.strokes_state.insert_stroke(shapestroke);
+
+            self.polyline_builder = Some(PolylineBuilder {
+                stroke_key,
+                vertices: vec![pos],
+                closed: false,
+                last_down: Some((pos, now)),
+            });
+            self.current_stroke = Some(stroke_key);
+            return;
+        };
+
+        let tapped_first_vertex = builder.vertices.len() >= 3
+            && builder.vertices.first().map_or(false, |first| {
+                (pos - first).magnitude() <= PolylineBuilder::TAP_DIST_MAX
+            });
+        let double_tapped = builder.last_down.map_or(false, |(last_pos, last_time)| {
+            (pos - last_pos).magnitude() <= PolylineBuilder::TAP_DIST_MAX
+                && (now - last_time).num_milliseconds() <= PolylineBuilder::DOUBLE_TAP_DURATION_MAX
+        });
+
+        if tapped_first_vertex {
+            builder.closed = true;
+            self.finish_polyline(sheet, zoom, renderer);
+        } else if double_tapped {
+            self.finish_polyline(sheet, zoom, renderer);
+        } else {
+            builder.vertices.push(pos);
+            builder.last_down = Some((pos, now));
+
+            let stroke_key = builder.stroke_key;
+            self.current_stroke = Some(stroke_key);
+            sheet
+                .strokes_state
+                .add_to_shapestroke(stroke_key, self, element, renderer, zoom);
+        }
+    }
+
+    /// The position the direction is snapped from with angle snap. While the pen is down it is the start of the shape or the previous vertex of the polyline,
+    /// while it is up the last vertex of the polyline
+    pub fn snap_anchor(&self, pen_down: bool) -> Option<na::Vector2<f64>> {
+        if let Some(builder) = &self.polyline_builder {
+            if pen_down {
+                builder
+                    .vertices
+                    .len()
+                    .checked_sub(2)
+                    .map(|i| builder.vertices[i])
+            } else {
+                builder.vertices.last().copied()
+            }
+        } else if pen_down && self.current_stroke.is_some() {
+            Some(self.rect_start)
+        } else {
+            None
+        }
+    }
+
+    /// Finishes the polyline that is currently built, if there is one. Returns true if there was one
+    pub fn finish_polyline(
+        &mut self,
+        sheet: &mut Sheet,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> bool {
+        let builder = if let Some(builder) = self.polyline_builder.as_ref() {
+            builder
+        } else {
+            return false;
+        };
+        let stroke_key = builder.stroke_key;
+
+        if builder.vertices.len() < 2 {
+            // A single vertex is not a polyline
+            sheet.strokes_state.remove_stroke(stroke_key);
+        } else {
+            // The element moves the last vertex onto itself and applies the closing
+            let last_vertex = builder.vertices[builder.vertices.len() - 1];
+            sheet.strokes_state.add_to_shapestroke(
+                stroke_key,
+                self,
+                Element::new(InputData::new(last_vertex, InputData::PRESSURE_DEFAULT)),
+                renderer.clone(),
+                zoom,
+            );
+
+            sheet.strokes_state.update_geometry_for_stroke(stroke_key);
+            sheet
+                .strokes_state
+                .regenerate_rendering_for_stroke_threaded(stroke_key, renderer, zoom);
+        }
+
+        self.polyline_builder = None;
+        self.current_stroke = None;
+
+        true
+    }
 }
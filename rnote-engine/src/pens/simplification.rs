@@ -0,0 +1,112 @@
+use crate::strokes::element::Element;
+
+use serde::{Deserialize, Serialize};
+
+/// The Options of how finished strokes are simplified
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "simplification_options")]
+pub struct SimplificationOptions {
+    /// Whether finished strokes are simplified. Disabled, strokes keep every input
+    #[serde(rename = "enabled")]
+    pub enabled: bool,
+    /// The max distance of removed elements to the simplified stroke
+    #[serde(rename = "tolerance")]
+    pub tolerance: f64,
+}
+
+impl Default for SimplificationOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            tolerance: Self::TOLERANCE_DEFAULT,
+        }
+    }
+}
+
+impl SimplificationOptions {
+    /// Tolerance default
+    pub const TOLERANCE_DEFAULT: f64 = 0.3;
+    /// The min tolerance
+    pub const TOLERANCE_MIN: f64 = 0.0;
+    /// The max tolerance
+    pub const TOLERANCE_MAX: f64 = 5.0;
+    /// The max difference of the pressure of removed elements to the pressure interpolated between the kept elements around them
+    pub const PRESSURE_TOLERANCE: f64 = 0.02;
+}
+
+/// Simplifies the elements with the Ramer-Douglas-Peucker algorithm. Elements are only removed when they are within the tolerance
+/// of the line between the kept elements around them, and their pressure is within `PRESSURE_TOLERANCE` of the pressure interpolated along the line.
+/// The first and the last element are always kept
+pub fn simplify_elements(elements: &[Element], options: &SimplificationOptions) -> Vec<Element> {
+    if elements.len() <= 2 {
+        return elements.to_vec();
+    }
+
+    let tolerance = options.tolerance.clamp(
+        SimplificationOptions::TOLERANCE_MIN,
+        SimplificationOptions::TOLERANCE_MAX,
+    );
+    let mut keep = vec![false; elements.len()];
+    keep[0] = true;
+    keep[elements.len() - 1] = true;
+
+    // The ranges that are yet to be simplified, with their kept first and last element
+    let mut ranges = vec![(0, elements.len() - 1)];
+    while let Some((first, last)) = ranges.pop() {
+        let (start, end) = (&elements[first], &elements[last]);
+
+        let farthest = (first + 1..last)
+            .map(|i| (i, deviation(&elements[i], start, end, tolerance)))
+            .fold(
+                None,
+                |farthest: Option<(usize, f64)>, (i, deviation)| match farthest {
+                    Some((_, farthest_deviation)) if farthest_deviation >= deviation => farthest,
+                    _ => Some((i, deviation)),
+                },
+            );
+
+        if let Some((i, deviation)) = farthest {
+            if deviation > 1.0 {
+                keep[i] = true;
+                ranges.push((first, i));
+                ranges.push((i, last));
+            }
+        }
+    }
+
+    elements
+        .iter()
+        .zip(keep)
+        .filter_map(|(element, keep)| if keep { Some(*element) } else { None })
+        .collect()
+}
+
+/// The deviation of the element from the line between the start and the end, relative to the tolerances.
+/// Above 1.0 the element deviates too much in its position or its pressure to be removed
+fn deviation(element: &Element, start: &Element, end: &Element, tolerance: f64) -> f64 {
+    let pos = element.inputdata.pos();
+    let start_pos = start.inputdata.pos();
+    let line = end.inputdata.pos() - start_pos;
+
+    // The parameter of the point on the line closest to the element, for interpolating the pressure
+    let t = if line.magnitude_squared() > 0.0 {
+        ((pos - start_pos).dot(&line) / line.magnitude_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let distance = (pos - (start_pos + line * t)).magnitude();
+    let pressure_diff = (element.inputdata.pressure()
+        - (start.inputdata.pressure()
+            + (end.inputdata.pressure() - start.inputdata.pressure()) * t))
+        .abs();
+
+    let distance_deviation = if tolerance > 0.0 {
+        distance / tolerance
+    } else if distance > 0.0 {
+        f64::INFINITY
+    } else {
+        0.0
+    };
+
+    distance_deviation.max(pressure_diff / SimplificationOptions::PRESSURE_TOLERANCE)
+}
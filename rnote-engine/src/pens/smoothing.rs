@@ -0,0 +1,355 @@
+use std::collections::VecDeque;
+
+use crate::strokes::element::Element;
+
+use serde::{Deserialize, Serialize};
+
+/// The smoothing algorithm, applied to the input before the curves are fitted through it
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
+#[repr(u32)]
+#[cfg_attr(feature = "ui", enum_type(name = "SmoothingAlgorithm"))]
+#[serde(rename = "smoothing_algorithm")]
+pub enum SmoothingAlgorithm {
+    /// The input is used as is
+    #[cfg_attr(feature = "ui", enum_value(name = "None", nick = "none"))]
+    #[serde(rename = "none")]
+    None = 0,
+    /// The positions are averaged over a window of the latest inputs
+    #[cfg_attr(
+        feature = "ui",
+        enum_value(name = "MovingAverage", nick = "moving-average")
+    )]
+    #[serde(rename = "moving_average")]
+    MovingAverage,
+    /// The 1€ filter, an adaptive low pass filter which smoothes more at low speeds and lags less at high speeds
+    #[cfg_attr(
+        feature = "ui",
+        enum_value(name = "OneEuroFilter", nick = "one-euro-filter")
+    )]
+    #[serde(rename = "one_euro_filter")]
+    OneEuroFilter,
+    /// Inputs closer than a min distance to the previous one are dropped, so that the curves are fitted through fewer, more distant inputs
+    #[cfg_attr(
+        feature = "ui",
+        enum_value(name = "MinDistance", nick = "min-distance")
+    )]
+    #[serde(rename = "min_distance", alias = "catmull_rom")]
+    MinDistance,
+}
+
+impl Default for SmoothingAlgorithm {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+/// The Options of how the input should be smoothed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "smoothing_options")]
+pub struct SmoothingOptions {
+    /// The smoothing algorithm
+    #[serde(rename = "algorithm")]
+    pub algorithm: SmoothingAlgorithm,
+    /// The amount of inputs averaged by the moving average
+    #[serde(rename = "moving_average_window")]
+    pub moving_average_window: usize,
+    /// The min cutoff frequency of the 1€ filter in Hz. Lower values smooth more at low speeds
+    #[serde(rename = "one_euro_min_cutoff")]
+    pub one_euro_min_cutoff: f64,
+    /// The speed coefficient of the 1€ filter. Higher values lag less at high speeds
+    #[serde(rename = "one_euro_beta")]
+    pub one_euro_beta: f64,
+    /// The cutoff frequency for the speed of the 1€ filter in Hz
+    #[serde(rename = "one_euro_d_cutoff")]
+    pub one_euro_d_cutoff: f64,
+    /// The min distance between the inputs which are kept
+    #[serde(rename = "min_distance", alias = "catmull_rom_min_distance")]
+    pub min_distance: f64,
+}
+
+impl Default for SmoothingOptions {
+    fn default() -> Self {
+        Self {
+            algorithm: SmoothingAlgorithm::default(),
+            moving_average_window: Self::MOVING_AVERAGE_WINDOW_DEFAULT,
+            one_euro_min_cutoff: Self::ONE_EURO_MIN_CUTOFF_DEFAULT,
+            one_euro_beta: Self::ONE_EURO_BETA_DEFAULT,
+            one_euro_d_cutoff: Self::ONE_EURO_D_CUTOFF_DEFAULT,
+            min_distance: Self::MIN_DISTANCE_DEFAULT,
+        }
+    }
+}
+
+impl SmoothingOptions {
+    /// Moving average window default
+    pub const MOVING_AVERAGE_WINDOW_DEFAULT: usize = 4;
+    /// 1€ filter min cutoff default
+    pub const ONE_EURO_MIN_CUTOFF_DEFAULT: f64 = 1.0;
+    /// 1€ filter beta default
+    pub const ONE_EURO_BETA_DEFAULT: f64 = 0.007;
+    /// 1€ filter speed cutoff default
+    pub const ONE_EURO_D_CUTOFF_DEFAULT: f64 = 1.0;
+    /// Min distance default
+    pub const MIN_DISTANCE_DEFAULT: f64 = 2.0;
+    /// The assumed duration between two inputs in seconds, if they have no timestamps
+    pub const INPUT_DURATION_FALLBACK: f64 = 1.0 / 60.0;
+}
+
+/// The state of the smoothing for the stroke currently drawn
+#[derive(Debug, Clone, Default)]
+pub struct Smoother {
+    options: SmoothingOptions,
+    /// The latest inputs, for the moving average
+    window: VecDeque<Element>,
+    /// The previous filtered element and the filtered speed, for the 1€ filter
+    one_euro_prev: Option<(Element, na::Vector2<f64>)>,
+    /// The latest input and the latest emitted element
+    last_input: Option<Element>,
+    last_smoothed: Option<Element>,
+}
+
+impl Smoother {
+    pub fn new(options: SmoothingOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Smoothes the next element. Returns None if it is dropped by the algorithm
+    pub fn smooth(&mut self, element: Element) -> Option<Element> {
+        let smoothed = match self.options.algorithm {
+            SmoothingAlgorithm::None => Some(element),
+            SmoothingAlgorithm::MovingAverage => Some(self.smooth_moving_average(element)),
+            SmoothingAlgorithm::OneEuroFilter => Some(self.smooth_one_euro(element)),
+            SmoothingAlgorithm::MinDistance => self.smooth_min_distance(element),
+        };
+
+        self.last_input = Some(element);
+        if smoothed.is_some() {
+            self.last_smoothed = smoothed;
+        }
+        smoothed
+    }
+
+    /// Finishes the smoothing at the end of the stroke. Returns the last input, if the smoothed elements lag behind it or it was dropped,
+    /// so that the stroke ends at the last input
+    pub fn finish(&mut self) -> Option<Element> {
+        let last_smoothed = self.last_smoothed;
+        let last = self.last_input.filter(|last_input| {
+            last_smoothed.map_or(true, |last_smoothed| {
+                last_smoothed.inputdata.pos() != last_input.inputdata.pos()
+            })
+        });
+        *self = Self::new(self.options);
+
+        last
+    }
+
+    fn smooth_moving_average(&mut self, element: Element) -> Element {
+        self.window.push_back(element);
+        while self.window.len() > self.options.moving_average_window.max(1) {
+            self.window.pop_front();
+        }
+
+        let mean_pos = self
+            .window
+            .iter()
+            .fold(na::Vector2::zeros(), |acc, element| {
+                acc + element.inputdata.pos()
+            })
+            / self.window.len() as f64;
+
+        let mut smoothed = element;
+        smoothed.inputdata.set_pos(mean_pos);
+        smoothed
+    }
+
+    fn smooth_one_euro(&mut self, element: Element) -> Element {
+        // The smoothing factor for the cutoff frequency and the time step
+        fn alpha(cutoff: f64, dt: f64) -> f64 {
+            let tau = 1.0 / (2.0 * std::f64::consts::PI * cutoff.max(f64::EPSILON));
+            1.0 / (1.0 + tau / dt)
+        }
+
+        let (prev, prev_speed) = if let Some(prev) = self.one_euro_prev {
+            prev
+        } else {
+            self.one_euro_prev = Some((element, na::Vector2::zeros()));
+            return element;
+        };
+
+        let dt = match (prev.timestamp, element.timestamp) {
+            (Some(start), Some(end)) => (end - start)
+                .to_std()
+                .map(|duration| duration.as_secs_f64())
+                .unwrap_or(0.0),
+            _ => SmoothingOptions::INPUT_DURATION_FALLBACK,
+        };
+        let dt = if dt > 0.0 {
+            dt
+        } else {
+            SmoothingOptions::INPUT_DURATION_FALLBACK
+        };
+
+        let pos = element.inputdata.pos();
+        let prev_pos = prev.inputdata.pos();
+
+        let speed = (pos - prev_pos) / dt;
+        let speed = prev_speed.lerp(&speed, alpha(self.options.one_euro_d_cutoff, dt));
+
+        let cutoff =
+            self.options.one_euro_min_cutoff + self.options.one_euro_beta * speed.magnitude();
+        let filtered_pos = prev_pos.lerp(&pos, alpha(cutoff, dt));
+
+        let mut smoothed = element;
+        smoothed.inputdata.set_pos(filtered_pos);
+
+        self.one_euro_prev = Some((smoothed, speed));
+        smoothed
+    }
+
+    fn smooth_min_distance(&mut self, element: Element) -> Option<Element> {
+        match self.last_smoothed {
+            Some(last)
+                if (element.inputdata.pos() - last.inputdata.pos()).magnitude()
+                    < self.options.min_distance =>
+            {
+                None
+            }
+            _ => Some(element),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strokes::inputdata::InputData;
+
+    /// An element without timestamp, so the fallback duration is assumed between the elements
+    fn element(x: f64, y: f64) -> Element {
+        Element {
+            inputdata: InputData::new(na::vector![x, y], InputData::PRESSURE_DEFAULT),
+            timestamp: None,
+        }
+    }
+
+    fn smoother(algorithm: SmoothingAlgorithm) -> Smoother {
+        Smoother::new(SmoothingOptions {
+            algorithm,
+            ..SmoothingOptions::default()
+        })
+    }
+
+    #[test]
+    fn moving_average_is_the_mean_of_the_window() {
+        let mut smoother = Smoother::new(SmoothingOptions {
+            algorithm: SmoothingAlgorithm::MovingAverage,
+            moving_average_window: 3,
+            ..SmoothingOptions::default()
+        });
+
+        let smoothed = [0.0, 3.0, 6.0, 9.0, 12.0]
+            .into_iter()
+            .map(|x| {
+                smoother
+                    .smooth(element(x, 2.0 * x))
+                    .unwrap()
+                    .inputdata
+                    .pos()
+            })
+            .collect::<Vec<_>>();
+
+        // The window fills up with the first inputs
+        assert_eq!(smoothed[0], na::vector![0.0, 0.0]);
+        assert_eq!(smoothed[1], na::vector![1.5, 3.0]);
+        assert_eq!(smoothed[2], na::vector![3.0, 6.0]);
+        assert_eq!(smoothed[3], na::vector![6.0, 12.0]);
+        assert_eq!(smoothed[4], na::vector![9.0, 18.0]);
+
+        // The stroke lags behind the last input, which is appended when finished
+        assert_eq!(
+            smoother.finish().unwrap().inputdata.pos(),
+            na::vector![12.0, 24.0]
+        );
+    }
+
+    #[test]
+    fn one_euro_filter_converges_on_a_stationary_input() {
+        let mut smoother = smoother(SmoothingAlgorithm::OneEuroFilter);
+
+        assert_eq!(
+            smoother.smooth(element(0.0, 0.0)).unwrap().inputdata.pos(),
+            na::vector![0.0, 0.0]
+        );
+
+        let mut prev_dist = f64::INFINITY;
+        for _ in 0..200 {
+            let pos = smoother.smooth(element(10.0, 5.0)).unwrap().inputdata.pos();
+            let dist = (pos - na::vector![10.0, 5.0]).magnitude();
+
+            // Approaches the input without overshooting it
+            assert!(dist < prev_dist || dist == 0.0);
+            assert!(pos[0] <= 10.0 && pos[1] <= 5.0);
+            prev_dist = dist;
+        }
+        assert!(prev_dist < 1e-3);
+
+        assert_eq!(
+            smoother.finish().unwrap().inputdata.pos(),
+            na::vector![10.0, 5.0]
+        );
+    }
+
+    #[test]
+    fn min_distance_drops_close_inputs() {
+        let mut smoother = Smoother::new(SmoothingOptions {
+            algorithm: SmoothingAlgorithm::MinDistance,
+            min_distance: 2.0,
+            ..SmoothingOptions::default()
+        });
+
+        let kept = [0.0, 1.0, 1.5, 2.5, 3.0, 5.0, 5.5]
+            .into_iter()
+            .filter_map(|x| smoother.smooth(element(x, 0.0)))
+            .map(|element| element.inputdata.pos()[0])
+            .collect::<Vec<_>>();
+
+        // The distance is measured to the last kept input, not to the dropped ones
+        assert_eq!(kept, vec![0.0, 2.5, 5.0]);
+        // The last input was dropped, but the stroke should still end at it
+        assert_eq!(smoother.finish().unwrap().inputdata.pos()[0], 5.5);
+    }
+
+    #[test]
+    fn finish_skips_the_last_input_if_already_emitted() {
+        let mut min_distance = smoother(SmoothingAlgorithm::MinDistance);
+        min_distance.smooth(element(0.0, 0.0));
+        min_distance.smooth(element(10.0, 0.0));
+        assert!(min_distance.finish().is_none());
+
+        let mut unsmoothed = smoother(SmoothingAlgorithm::None);
+        unsmoothed.smooth(element(0.0, 0.0));
+        assert!(unsmoothed.finish().is_none());
+
+        // Finishing resets the state for the next stroke
+        assert!(unsmoothed.finish().is_none());
+    }
+
+    #[test]
+    fn catmull_rom_options_of_older_files_are_loaded_as_min_distance() {
+        let options = serde_json::from_str::<SmoothingOptions>(
+            r#"{"algorithm": "catmull_rom", "catmull_rom_min_distance": 3.0}"#,
+        )
+        .unwrap();
+
+        assert_eq!(options.algorithm, SmoothingAlgorithm::MinDistance);
+        assert_eq!(options.min_distance, 3.0);
+        assert_eq!(
+            options.moving_average_window,
+            SmoothingOptions::MOVING_AVERAGE_WINDOW_DEFAULT
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use std::cmp::Ordering;
+
+use crate::compose::color::Color;
+use crate::compose::geometry::AABBHelpers;
+use crate::sheet::Sheet;
+use crate::strokesstate::StrokeKey;
+
+#[cfg(feature = "ui")]
+use gtk4::{graphene, gsk, Snapshot};
+use p2d::bounding_volume::AABB;
+use serde::{Deserialize, Serialize};
+
+/// What a position snapped to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapTarget {
+    /// A point of the background grid
+    Grid,
+    /// An endpoint or midpoint of a stroke
+    Stroke,
+    /// A direction in angle snap increments from the anchor
+    Angle,
+}
+
+/// Indicates the position which was snapped to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapIndicator {
+    pub pos: na::Vector2<f64>,
+    pub target: SnapTarget,
+}
+
+/// Snapping of positions while building shapes and dragging the selection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "snapping")]
+pub struct Snapping {
+    /// Snaps to the points of the background grid, spaced by the pattern size of the background
+    #[serde(rename = "snap_to_grid")]
+    pub snap_to_grid: bool,
+    /// Snaps to the endpoints and midpoints of the strokes
+    #[serde(rename = "snap_to_strokes")]
+    pub snap_to_strokes: bool,
+    /// The distance within which positions snap to strokes and the grid, in surface coordinates
+    #[serde(rename = "snap_distance")]
+    pub snap_distance: f64,
+    /// The increment of the angle snap, in degrees
+    #[serde(rename = "angle_step")]
+    pub angle_step: f64,
+    /// Snaps the direction from the anchor to the position in angle step increments. Set while the modifier is held down
+    #[serde(skip)]
+    pub angle_snap: bool,
+    /// The latest snap
+    #[serde(skip)]
+    pub indicator: Option<SnapIndicator>,
+}
+
+impl Default for Snapping {
+    fn default() -> Self {
+        Self {
+            snap_to_grid: false,
+            snap_to_strokes: false,
+            snap_distance: Self::SNAP_DISTANCE_DEFAULT,
+            angle_step: Self::ANGLE_STEP_DEFAULT,
+            angle_snap: false,
+            indicator: None,
+        }
+    }
+}
+
+impl Snapping {
+    pub const SNAP_DISTANCE_DEFAULT: f64 = 10.0;
+    pub const ANGLE_STEP_DEFAULT: f64 = 15.0;
+    /// The radius of the indicator, in surface coordinates
+    pub const INDICATOR_RADIUS: f64 = 5.0;
+    pub const INDICATOR_COLOR: Color = Color {
+        r: 0.1,
+        g: 0.45,
+        b: 0.9,
+        a: 0.8,
+    };
+
+    /// Whether any snapping is enabled
+    pub fn enabled(&self) -> bool {
+        self.snap_to_grid || self.snap_to_strokes || self.angle_snap
+    }
+
+    /// Snaps the position. With angle snap and an anchor the direction from the anchor is snapped, keeping the distance.
+    /// Else the position snaps to the nearest stroke point, or the nearest grid point, within the snap distance.
+    /// The strokes of the excluded keys, e.g. the stroke being built, are not snapped to. Updates the indicator
+    pub fn snap_pos(
+        &mut self,
+        pos: na::Vector2<f64>,
+        anchor: Option<na::Vector2<f64>>,
+        sheet: &Sheet,
+        excluded_keys: &[StrokeKey],
+        zoom: f64,
+    ) -> na::Vector2<f64> {
+        let snapped = if let Some(anchor) = anchor.filter(|_| self.angle_snap) {
+            Some(SnapIndicator {
+                pos: self.snap_angle(pos, anchor),
+                target: SnapTarget::Angle,
+            })
+        } else {
+            self.snap_to_points(pos, sheet, excluded_keys, zoom)
+        };
+
+        self.indicator = snapped;
+        snapped.map_or(pos, |snapped| snapped.pos)
+    }
+
+    /// Snaps the offset the bounds are moved by, so that the nearest of their corners, edge midpoints and center snaps.
+    /// Angle snap does not apply here. Updates the indicator
+    pub fn snap_offset(
+        &mut self,
+        bounds: AABB,
+        offset: na::Vector2<f64>,
+        sheet: &Sheet,
+        excluded_keys: &[StrokeKey],
+        zoom: f64,
+    ) -> na::Vector2<f64> {
+        let moved_bounds = bounds.translate(offset);
+        let (mins, center, maxs) = (
+            moved_bounds.mins.coords,
+            moved_bounds.center().coords,
+            moved_bounds.maxs.coords,
+        );
+
+        let snapped = [mins[0], center[0], maxs[0]]
+            .iter()
+            .flat_map(|&x| [mins[1], center[1], maxs[1]].map(|y| na::vector![x, y]))
+            .filter_map(|point| {
+                let snapped = self.snap_to_points(point, sheet, excluded_keys, zoom)?;
+                Some((snapped, snapped.pos - point))
+            })
+            .min_by(|(_, first), (_, second)| {
+                first
+                    .magnitude()
+                    .partial_cmp(&second.magnitude())
+                    .unwrap_or(Ordering::Equal)
+            });
+
+        self.indicator = snapped.map(|(snapped, _)| snapped);
+        snapped.map_or(offset, |(_, correction)| offset + correction)
+    }
+
+    pub fn clear_indicator(&mut self) {
+        self.indicator = None;
+    }
+
+    /// Draws a ring around the snapped position
+    #[cfg(feature = "ui")]
+    pub fn draw_indicator(&self, snapshot: &Snapshot, zoom: f64) {
+        if let Some(indicator) = self.indicator {
+            let radius = Self::INDICATOR_RADIUS as f32;
+            let center = indicator.pos * zoom;
+            let color = Self::INDICATOR_COLOR.to_gdk();
+            let border_width = 2.0;
+
+            let rect = graphene::Rect::new(
+                center[0] as f32 - radius,
+                center[1] as f32 - radius,
+                2.0 * radius,
+                2.0 * radius,
+            );
+            let corner = graphene::Size::new(radius, radius);
+
+            snapshot.append_border(
+                &gsk::RoundedRect::new(rect, corner, corner, corner, corner),
+                &[border_width, border_width, border_width, border_width],
+                &[color, color, color, color],
+            );
+        }
+    }
+
+    /// The position rotated around the anchor onto the nearest direction in angle step increments
+    fn snap_angle(&self, pos: na::Vector2<f64>, anchor: na::Vector2<f64>) -> na::Vector2<f64> {
+        let vec = pos - anchor;
+        let step = self.angle_step.to_radians();
+        if step <= 0.0 {
+            return pos;
+        }
+
+        let angle = (vec[1].atan2(vec[0]) / step).round() * step;
+        anchor + na::vector![angle.cos(), angle.sin()] * vec.magnitude()
+    }
+
+    /// The nearest stroke point within the snap distance, or else the nearest grid point within it
+    fn snap_to_points(
+        &self,
+        pos: na::Vector2<f64>,
+        sheet: &Sheet,
+        excluded_keys: &[StrokeKey],
+        zoom: f64,
+    ) -> Option<SnapIndicator> {
+        let snap_distance = self.snap_distance / zoom;
+
+        if self.snap_to_strokes {
+            let search_bounds = AABB::from_half_extents(
+                na::Point2::from(pos),
+                na::Vector2::from_element(snap_distance),
+            );
+
+            let nearest = sheet
+                .strokes_state
+                .keys_intersecting_bounds(search_bounds)
+                .into_iter()
+                .filter(|key| !excluded_keys.contains(key))
+                .filter_map(|key| sheet.strokes_state.get_stroke_ref(key))
+                .flat_map(|stroke| stroke.snap_points())
+                .filter(|point| (point - pos).magnitude() <= snap_distance)
+                .min_by(|first, second| {
+                    (first - pos)
+                        .magnitude()
+                        .partial_cmp(&(second - pos).magnitude())
+                        .unwrap_or(Ordering::Equal)
+                });
+
+            if let Some(point) = nearest {
+                return Some(SnapIndicator {
+                    pos: point,
+                    target: SnapTarget::Stroke,
+                });
+            }
+        }
+
+        if self.snap_to_grid {
+            let grid_size = sheet.background_at_pos(pos).pattern_size;
+
+            if grid_size[0] > 0.0 && grid_size[1] > 0.0 {
+                let point = na::vector![
+                    (pos[0] / grid_size[0]).round() * grid_size[0],
+                    (pos[1] / grid_size[1]).round() * grid_size[1]
+                ];
+
+                if (point - pos).magnitude() <= snap_distance {
+                    return Some(SnapIndicator {
+                        pos: point,
+                        target: SnapTarget::Grid,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,323 @@
+#[cfg(feature = "ui")]
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "ui")]
+use crate::compose;
+use crate::compose::color::Color;
+#[cfg(feature = "ui")]
+use crate::render::{self, Renderer};
+use crate::strokes::inputdata::InputData;
+
+#[cfg(feature = "ui")]
+use anyhow::Context;
+#[cfg(feature = "ui")]
+use gtk4::Snapshot;
+#[cfg(feature = "ui")]
+use p2d::bounding_volume::{BoundingVolume, AABB};
+use serde::{Deserialize, Serialize};
+
+/// The Options of the stabilizer
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "stabilizer_options")]
+pub struct StabilizerOptions {
+    /// Whether the stabilizer is enabled
+    #[serde(rename = "enabled")]
+    pub enabled: bool,
+    /// The length of the rope the drawn point is pulled along by the cursor
+    #[serde(rename = "rope_length")]
+    pub rope_length: f64,
+}
+
+impl Default for StabilizerOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rope_length: Self::ROPE_LENGTH_DEFAULT,
+        }
+    }
+}
+
+impl StabilizerOptions {
+    /// Rope length default
+    pub const ROPE_LENGTH_DEFAULT: f64 = 20.0;
+    /// The min rope length
+    pub const ROPE_LENGTH_MIN: f64 = 0.0;
+    /// The max rope length
+    pub const ROPE_LENGTH_MAX: f64 = 500.0;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum StabilizerState {
+    /// Not drawing
+    Idle,
+    /// Drawing, the drawn point is pulled behind the cursor
+    Pulling {
+        drawn_pos: na::Vector2<f64>,
+        cursor_pos: na::Vector2<f64>,
+    },
+}
+
+impl Default for StabilizerState {
+    fn default() -> Self {
+        Self::Idle
+    }
+}
+
+/// The stabilizer ( or "lazy brush" ). The drawn point only follows the cursor when it is further away than the rope length,
+/// so that small jitters of the cursor are not drawn
+#[derive(Debug, Clone, Default)]
+pub struct Stabilizer {
+    state: StabilizerState,
+}
+
+impl Stabilizer {
+    pub const ROPE_COLOR: Color = Color {
+        r: 0.5,
+        g: 0.7,
+        b: 0.7,
+        a: 1.0,
+    };
+    pub const ROPE_WIDTH: f64 = 1.0;
+    pub const ROPE_END_RADIUS: f64 = 2.0;
+
+    pub fn state(&self) -> StabilizerState {
+        self.state
+    }
+
+    /// Resets the state. Must be called when a stroke ends
+    pub fn reset(&mut self) {
+        self.state = StabilizerState::Idle;
+    }
+
+    /// Stabilizes the input. Returns the input moved to the drawn point, or None if the drawn point did not move
+    pub fn stabilize(
+        &mut self,
+        mut inputdata: InputData,
+        options: &StabilizerOptions,
+    ) -> Option<InputData> {
+        if !options.enabled {
+            return Some(inputdata);
+        }
+
+        let cursor_pos = inputdata.pos();
+
+        match self.state {
+            StabilizerState::Idle => {
+                self.state = StabilizerState::Pulling {
+                    drawn_pos: cursor_pos,
+                    cursor_pos,
+                };
+
+                Some(inputdata)
+            }
+            StabilizerState::Pulling { drawn_pos, .. } => {
+                let rope_length = options.rope_length.clamp(
+                    StabilizerOptions::ROPE_LENGTH_MIN,
+                    StabilizerOptions::ROPE_LENGTH_MAX,
+                );
+                let delta = cursor_pos - drawn_pos;
+                let dist = delta.magnitude();
+
+                if dist > rope_length {
+                    let drawn_pos = drawn_pos + delta * ((dist - rope_length) / dist);
+                    self.state = StabilizerState::Pulling {
+                        drawn_pos,
+                        cursor_pos,
+                    };
+
+                    inputdata.set_pos(drawn_pos);
+                    Some(inputdata)
+                } else {
+                    self.state = StabilizerState::Pulling {
+                        drawn_pos,
+                        cursor_pos,
+                    };
+
+                    None
+                }
+            }
+        }
+    }
+
+    /// Draws the rope between the drawn point and the cursor
+    #[cfg(feature = "ui")]
+    pub fn draw(
+        &self,
+        zoom: f64,
+        snapshot: &Snapshot,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<(), anyhow::Error> {
+        let (drawn_pos, cursor_pos) = match self.state {
+            StabilizerState::Idle => return Ok(()),
+            StabilizerState::Pulling {
+                drawn_pos,
+                cursor_pos,
+            } => (drawn_pos, cursor_pos),
+        };
+
+        let mut bounds = AABB::new_invalid();
+        bounds.take_point(na::Point2::from(drawn_pos));
+        bounds.take_point(na::Point2::from(cursor_pos));
+        bounds.loosen(Self::ROPE_END_RADIUS + Self::ROPE_WIDTH);
+
+        let rope = svg::node::element::Line::new()
+            .set("x1", drawn_pos[0])
+            .set("y1", drawn_pos[1])
+            .set("x2", cursor_pos[0])
+            .set("y2", cursor_pos[1])
+            .set("stroke", Self::ROPE_COLOR.to_css_color())
+            .set("stroke-width", Self::ROPE_WIDTH)
+            .set("stroke-dasharray", "4 2");
+
+        let cursor_end = svg::node::element::Circle::new()
+            .set("cx", cursor_pos[0])
+            .set("cy", cursor_pos[1])
+            .set("r", Self::ROPE_END_RADIUS)
+            .set("stroke", Self::ROPE_COLOR.to_css_color())
+            .set("stroke-width", Self::ROPE_WIDTH)
+            .set("fill", "none");
+
+        let group = svg::node::element::Group::new().add(rope).add(cursor_end);
+
+        let svg_data = compose::svg_node_to_string(&group)?;
+        let svg = render::Svg { svg_data, bounds };
+
+        let images = renderer
+            .read()
+            .unwrap()
+            .gen_images(zoom, vec![svg], bounds)?;
+        if let Some(rendernode) = render::images_to_rendernode(&images, zoom)
+            .context("images_to_rendernode() failed in stabilizer .draw()")?
+        {
+            snapshot.append_node(&rendernode);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled(rope_length: f64) -> StabilizerOptions {
+        StabilizerOptions {
+            enabled: true,
+            rope_length,
+        }
+    }
+
+    fn input(x: f64, y: f64) -> InputData {
+        InputData::new(na::vector![x, y], 0.7)
+    }
+
+    fn assert_near(first: na::Vector2<f64>, second: na::Vector2<f64>) {
+        assert!(
+            (first - second).magnitude() < 1e-9,
+            "{} != {}",
+            first,
+            second
+        );
+    }
+
+    #[test]
+    fn disabled_stabilizer_passes_the_input_through() {
+        let mut stabilizer = Stabilizer::default();
+        let options = StabilizerOptions::default();
+        assert!(!options.enabled);
+
+        for pos in [input(0.0, 0.0), input(1.0, 0.0), input(100.0, 0.0)] {
+            assert_near(
+                stabilizer.stabilize(pos, &options).unwrap().pos(),
+                pos.pos(),
+            );
+        }
+        assert!(matches!(stabilizer.state(), StabilizerState::Idle));
+    }
+
+    #[test]
+    fn drawn_point_is_pulled_along_the_rope() {
+        let mut stabilizer = Stabilizer::default();
+        let options = enabled(10.0);
+
+        // The first input starts the stroke where it is
+        assert_near(
+            stabilizer
+                .stabilize(input(0.0, 0.0), &options)
+                .unwrap()
+                .pos(),
+            na::vector![0.0, 0.0],
+        );
+
+        // Inside of the rope the drawn point stays
+        assert!(stabilizer.stabilize(input(6.0, 8.0), &options).is_none());
+        match stabilizer.state() {
+            StabilizerState::Pulling {
+                drawn_pos,
+                cursor_pos,
+            } => {
+                assert_near(drawn_pos, na::vector![0.0, 0.0]);
+                assert_near(cursor_pos, na::vector![6.0, 8.0]);
+            }
+            StabilizerState::Idle => panic!("the stabilizer is not pulling"),
+        }
+
+        // Beyond the rope the drawn point follows at the rope length behind the cursor, keeping the pressure
+        let pulled = stabilizer.stabilize(input(12.0, 16.0), &options).unwrap();
+        assert_near(pulled.pos(), na::vector![6.0, 8.0]);
+        assert_eq!(pulled.pressure(), 0.7);
+
+        let pulled = stabilizer.stabilize(input(6.0, 30.0), &options).unwrap();
+        assert_near(pulled.pos(), na::vector![6.0, 20.0]);
+
+        stabilizer.reset();
+        assert!(matches!(stabilizer.state(), StabilizerState::Idle));
+        assert_near(
+            stabilizer
+                .stabilize(input(50.0, 50.0), &options)
+                .unwrap()
+                .pos(),
+            na::vector![50.0, 50.0],
+        );
+    }
+
+    #[test]
+    fn rope_length_is_clamped() {
+        // Without a rope the drawn point is the cursor
+        let mut stabilizer = Stabilizer::default();
+        let options = enabled(-5.0);
+        stabilizer.stabilize(input(0.0, 0.0), &options);
+        assert_near(
+            stabilizer
+                .stabilize(input(3.0, 4.0), &options)
+                .unwrap()
+                .pos(),
+            na::vector![3.0, 4.0],
+        );
+
+        let mut stabilizer = Stabilizer::default();
+        let options = enabled(StabilizerOptions::ROPE_LENGTH_MAX * 2.0);
+        stabilizer.stabilize(input(0.0, 0.0), &options);
+        assert_near(
+            stabilizer
+                .stabilize(
+                    input(StabilizerOptions::ROPE_LENGTH_MAX + 10.0, 0.0),
+                    &options,
+                )
+                .unwrap()
+                .pos(),
+            na::vector![10.0, 0.0],
+        );
+    }
+
+    #[test]
+    fn stabilizer_options_serde_defaults() {
+        let options = serde_json::from_str::<StabilizerOptions>("{}").unwrap();
+        assert!(!options.enabled);
+        assert_eq!(options.rope_length, StabilizerOptions::ROPE_LENGTH_DEFAULT);
+
+        let options = serde_json::from_str::<StabilizerOptions>(r#"{"enabled": true}"#).unwrap();
+        assert!(options.enabled);
+        assert_eq!(options.rope_length, StabilizerOptions::ROPE_LENGTH_DEFAULT);
+    }
+}
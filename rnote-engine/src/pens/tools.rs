@@ -1,31 +1,54 @@
 use std::collections::VecDeque;
 use std::sync::{Arc, RwLock};
 
+#[cfg(feature = "ui")]
 use crate::compose;
 use crate::compose::color::Color;
+#[cfg(feature = "ui")]
 use crate::compose::geometry::AABBHelpers;
-use crate::render::{self, Renderer};
+#[cfg(feature = "ui")]
+use crate::render;
+use crate::render::Renderer;
+use crate::sheet::format::MeasureUnit;
 use crate::sheet::Sheet;
 use crate::strokes::inputdata::InputData;
 use crate::strokesstate::StrokeKey;
 
+#[cfg(feature = "ui")]
 use anyhow::Context;
-use gtk4::{glib, Snapshot};
+#[cfg(feature = "ui")]
+use gtk4::Snapshot;
+#[cfg(feature = "ui")]
+use p2d::bounding_volume::BoundingVolume;
 use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
+use web_time::Instant;
 
 use super::penbehaviour::PenBehaviour;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, glib::Enum)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
 #[serde(rename = "tool_style")]
-#[enum_type(name = "ToolStyle")]
+#[cfg_attr(feature = "ui", enum_type(name = "ToolStyle"))]
 pub enum ToolStyle {
     #[serde(rename = "expandsheet")]
-    #[enum_value(name = "Expandsheet", nick = "expandsheet")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Expandsheet", nick = "expandsheet"))]
     ExpandSheet,
     #[serde(rename = "dragproximity")]
-    #[enum_value(name = "Dragproximity", nick = "dragproximity")]
+    #[cfg_attr(
+        feature = "ui",
+        enum_value(name = "Dragproximity", nick = "dragproximity")
+    )]
     DragProximity,
+    #[serde(rename = "eyedropper")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Eyedropper", nick = "eyedropper"))]
+    Eyedropper,
+    #[serde(rename = "measure")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Measure", nick = "measure"))]
+    Measure,
+    #[serde(rename = "laser")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Laser", nick = "laser"))]
+    Laser,
 }
 
 impl Default for ToolStyle {
@@ -78,6 +101,7 @@ impl ExpandSheetTool {
     };
     pub const OFFSET_LINE_STROKE_WIDTH: f64 = 2.0;
 
+    #[cfg(feature = "ui")]
     pub fn draw(
         &self,
         sheet_bounds: AABB,
@@ -180,6 +204,7 @@ impl DragProximityTool {
     };
     pub const RADIUS_DEFAULT: f64 = 60.0;
 
+    #[cfg(feature = "ui")]
     pub fn draw(
         &self,
         _sheet_bounds: AABB,
@@ -231,6 +256,498 @@ impl DragProximityTool {
     }
 }
 
+/// Picks the color of the stroke or the background under the pen. When lifted the brush takes on the picked color
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename = "eyedropper_tool")]
+pub struct EyedropperTool {
+    #[serde(skip)]
+    pub pos: Option<na::Vector2<f64>>,
+    #[serde(skip)]
+    pub picked_color: Option<Color>,
+}
+
+impl EyedropperTool {
+    /// The tolerance for hitting strokes
+    pub const TOLERANCE: f64 = 2.0;
+    pub const OUTLINE_COLOR: Color = Color {
+        r: 0.5,
+        g: 0.7,
+        b: 0.7,
+        a: 1.0,
+    };
+    pub const OUTLINE_WIDTH: f64 = 2.0;
+    /// The radius of the preview of the picked color, in surface coordinates
+    pub const PREVIEW_RADIUS: f64 = 16.0;
+    /// The offset of the preview to the top right of the position, so that it is not covered by the stylus
+    pub const PREVIEW_OFFSET: f64 = 24.0;
+
+    fn pick(&mut self, pos: na::Vector2<f64>, sheet: &Sheet, zoom: f64) {
+        self.pos = Some(pos);
+        self.picked_color = sheet.color_at_pos(pos, Self::TOLERANCE / zoom);
+    }
+
+    #[cfg(feature = "ui")]
+    pub fn draw(
+        &self,
+        zoom: f64,
+        snapshot: &Snapshot,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<(), anyhow::Error> {
+        let (pos, picked_color) = match (self.pos, self.picked_color) {
+            (Some(pos), Some(picked_color)) => (pos, picked_color),
+            _ => return Ok(()),
+        };
+        let r = Self::PREVIEW_RADIUS / zoom;
+        let center = pos + na::vector![1.0, -1.0] * (Self::PREVIEW_OFFSET / zoom);
+        let bounds = AABB::from_half_extents(
+            na::Point2::from(center),
+            na::Vector2::from_element(r + Self::OUTLINE_WIDTH / zoom),
+        );
+
+        let preview_circle = svg::node::element::Circle::new()
+            .set("cx", center[0])
+            .set("cy", center[1])
+            .set("r", r)
+            .set("stroke", Self::OUTLINE_COLOR.to_css_color())
+            .set("stroke-width", Self::OUTLINE_WIDTH / zoom)
+            .set("fill", picked_color.to_css_color());
+
+        let svg_data = compose::svg_node_to_string(&preview_circle)?;
+        let svg = render::Svg { svg_data, bounds };
+
+        let images = renderer
+            .read()
+            .unwrap()
+            .gen_images(zoom, vec![svg], bounds)?;
+        if let Some(rendernode) = render::images_to_rendernode(&images, zoom)
+            .context("images_to_rendernode() failed in eyedroppertool .draw()")?
+        {
+            snapshot.append_node(&rendernode);
+        }
+
+        Ok(())
+    }
+}
+
+/// The measurement of the measure tool, in its unit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Measurement {
+    /// The length of all segments
+    pub length: f64,
+    /// The angle of the last segment in degrees, counter-clockwise from the positive x axis
+    pub angle: f64,
+    /// The enclosed area, when the polygon is closed
+    pub area: Option<f64>,
+}
+
+/// Measures dragged lines. Pressing at the end of the last segment continues a polygon from there,
+/// releasing onto its first vertex closes it to measure the area as well.
+/// The measurement is shown until the next one begins
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename = "measure_tool")]
+pub struct MeasureTool {
+    #[serde(rename = "unit")]
+    pub unit: MeasureUnit,
+    #[serde(skip)]
+    vertices: Vec<na::Vector2<f64>>,
+    #[serde(skip)]
+    closed: bool,
+}
+
+impl MeasureTool {
+    /// The distance within which presses continue the polygon and releases close it, in surface coordinates
+    pub const SNAP_DIST: f64 = 8.0;
+    pub const OUTLINE_COLOR: Color = Color {
+        r: 0.9,
+        g: 0.3,
+        b: 0.1,
+        a: 1.0,
+    };
+    pub const OUTLINE_WIDTH: f64 = 1.5;
+    pub const FILL_COLOR: Color = Color {
+        r: 0.9,
+        g: 0.3,
+        b: 0.1,
+        a: 0.1,
+    };
+    /// The radius of the vertices, in surface coordinates
+    pub const VERTEX_RADIUS: f64 = 3.0;
+    /// The font size of the label, in surface coordinates
+    pub const LABEL_FONT_SIZE: f64 = 12.0;
+
+    pub fn vertices(&self) -> &[na::Vector2<f64>] {
+        &self.vertices
+    }
+
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.closed = false;
+    }
+
+    fn begin(&mut self, pos: na::Vector2<f64>, zoom: f64) {
+        let continues = !self.closed
+            && self.vertices.last().map_or(false, |last| {
+                (pos - last).magnitude() <= Self::SNAP_DIST / zoom
+            });
+
+        if continues {
+            self.vertices.push(pos);
+        } else {
+            self.vertices = vec![pos, pos];
+            self.closed = false;
+        }
+    }
+
+    fn motion(&mut self, pos: na::Vector2<f64>) {
+        if let Some(last) = self.vertices.last_mut() {
+            *last = pos;
+        }
+    }
+
+    fn end(&mut self, zoom: f64) {
+        let snap_dist = Self::SNAP_DIST / zoom;
+        let n_vertices = self.vertices.len();
+        if n_vertices < 2 {
+            return;
+        }
+        let (last, before_last) = (self.vertices[n_vertices - 1], self.vertices[n_vertices - 2]);
+
+        // A tap adds no segment
+        if (last - before_last).magnitude() <= snap_dist {
+            self.vertices.pop();
+            if self.vertices.len() < 2 {
+                self.clear();
+            }
+        } else if n_vertices >= 4 && (last - self.vertices[0]).magnitude() <= snap_dist {
+            self.vertices.pop();
+            self.closed = true;
+        }
+    }
+
+    /// Measures the segments, converted from sheet coordinates to the unit with the dpi of the sheet format.
+    /// None when nothing is measured
+    pub fn measurement(&self, dpi: f64) -> Option<Measurement> {
+        if self.vertices.len() < 2 {
+            return None;
+        }
+        let convert = |value: f64| {
+            MeasureUnit::convert_measurement(value, MeasureUnit::Px, dpi, self.unit, dpi)
+        };
+
+        let mut segments = self
+            .vertices
+            .windows(2)
+            .map(|vertices| (vertices[0], vertices[1]))
+            .collect::<Vec<(na::Vector2<f64>, na::Vector2<f64>)>>();
+        if self.closed {
+            segments.push((self.vertices[self.vertices.len() - 1], self.vertices[0]));
+        }
+
+        let length = segments
+            .iter()
+            .map(|(start, end)| (end - start).magnitude())
+            .sum::<f64>();
+        let (start, end) = segments[segments.len() - 1];
+        let diff = end - start;
+        // The y axis of the sheet points down
+        let angle = (-diff[1]).atan2(diff[0]).to_degrees();
+
+        let area = if self.closed {
+            // The shoelace formula
+            let area_px = segments
+                .iter()
+                .map(|(start, end)| start[0] * end[1] - end[0] * start[1])
+                .sum::<f64>()
+                .abs()
+                * 0.5;
+
+            Some(area_px * convert(1.0).powi(2))
+        } else {
+            None
+        };
+
+        Some(Measurement {
+            length: convert(length),
+            angle,
+            area,
+        })
+    }
+
+    #[cfg(feature = "ui")]
+    pub fn draw(
+        &self,
+        zoom: f64,
+        dpi: f64,
+        snapshot: &Snapshot,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<(), anyhow::Error> {
+        let measurement = match self.measurement(dpi) {
+            Some(measurement) => measurement,
+            None => return Ok(()),
+        };
+        let outline_width = Self::OUTLINE_WIDTH / zoom;
+        let vertex_radius = Self::VERTEX_RADIUS / zoom;
+        let font_size = Self::LABEL_FONT_SIZE / zoom;
+        let color = Self::OUTLINE_COLOR.to_css_color();
+
+        let mut bounds = AABB::new_invalid();
+        let mut group = svg::node::element::Group::new();
+
+        let mut path_data = svg::node::element::path::Data::new()
+            .move_to((self.vertices[0][0], self.vertices[0][1]));
+        for vertex in self.vertices.iter().skip(1) {
+            path_data = path_data.line_to((vertex[0], vertex[1]));
+        }
+        if self.closed {
+            path_data = path_data.close();
+        }
+        group = group.add(
+            svg::node::element::Path::new()
+                .set("d", path_data)
+                .set("stroke", color.clone())
+                .set("stroke-width", outline_width)
+                .set(
+                    "fill",
+                    if self.closed {
+                        Self::FILL_COLOR.to_css_color()
+                    } else {
+                        String::from("none")
+                    },
+                ),
+        );
+
+        for vertex in self.vertices.iter() {
+            group = group.add(
+                svg::node::element::Circle::new()
+                    .set("cx", vertex[0])
+                    .set("cy", vertex[1])
+                    .set("r", vertex_radius)
+                    .set("fill", color.clone()),
+            );
+            bounds.take_point(na::Point2::from(*vertex));
+        }
+
+        let unit = self.unit.nick();
+        let mut lines = vec![
+            format!("{:.2} {}", measurement.length, unit),
+            format!("{:.1}°", measurement.angle),
+        ];
+        if let Some(area) = measurement.area {
+            lines.push(format!("{:.2} {}²", area, unit));
+        }
+
+        // The label is placed to the bottom right of the last vertex
+        let label_pos = self.vertices[self.vertices.len() - 1] + na::vector![1.0, 1.0] * font_size;
+        for (i, line) in lines.iter().enumerate() {
+            group = group.add(
+                svg::node::element::Text::new()
+                    .set("x", label_pos[0])
+                    .set("y", label_pos[1] + font_size * 1.2 * i as f64)
+                    .set("font-size", font_size)
+                    .set("fill", color.clone())
+                    .add(svg::node::Text::new(line.clone())),
+            );
+        }
+        // Generous space for the label
+        bounds.take_point(na::Point2::from(
+            label_pos + na::vector![10.0 * font_size, font_size * 1.2 * lines.len() as f64],
+        ));
+        bounds.take_point(na::Point2::from(label_pos - na::vector![0.0, font_size]));
+        let bounds = bounds.loosened(vertex_radius + outline_width);
+
+        let svg_data = compose::svg_node_to_string(&group)?;
+        let svg = render::Svg { svg_data, bounds };
+
+        let images = renderer
+            .read()
+            .unwrap()
+            .gen_images(zoom, vec![svg], bounds)?;
+        if let Some(rendernode) = render::images_to_rendernode(&images, zoom)
+            .context("images_to_rendernode() failed in measuretool .draw()")?
+        {
+            snapshot.append_node(&rendernode);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct LaserTrail {
+    points: Vec<na::Vector2<f64>>,
+    /// When the pen was lifted
+    ended: Option<Instant>,
+}
+
+impl LaserTrail {
+    /// The opacity, from fully opaque while the trail is drawn to zero once the timeout passed since the pen was lifted
+    fn opacity(&self, timeout: f64, now: Instant) -> f64 {
+        match self.ended {
+            Some(ended) => {
+                let elapsed = now.saturating_duration_since(ended).as_secs_f64();
+                (1.0 - elapsed / timeout.max(LaserTool::TIMEOUT_MIN)).clamp(0.0, 1.0)
+            }
+            None => 1.0,
+        }
+    }
+}
+
+/// Draws bright trails for presenting, which fade out and vanish after the timeout.
+/// They are never inserted into the sheet, so they are not part of the history or the saved file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, rename = "laser_tool")]
+pub struct LaserTool {
+    #[serde(rename = "color")]
+    pub color: Color,
+    #[serde(rename = "width")]
+    pub width: f64,
+    /// The time in seconds a trail fades out over after the pen is lifted
+    #[serde(rename = "timeout")]
+    pub timeout: f64,
+    #[serde(skip)]
+    trails: Vec<LaserTrail>,
+}
+
+impl Default for LaserTool {
+    fn default() -> Self {
+        Self {
+            color: Self::COLOR_DEFAULT,
+            width: Self::WIDTH_DEFAULT,
+            timeout: Self::TIMEOUT_DEFAULT,
+            trails: vec![],
+        }
+    }
+}
+
+impl LaserTool {
+    pub const COLOR_DEFAULT: Color = Color {
+        r: 1.0,
+        g: 0.1,
+        b: 0.2,
+        a: 1.0,
+    };
+    /// The width of the trails, in surface coordinates so they look the same at every zoom
+    pub const WIDTH_DEFAULT: f64 = 4.0;
+    pub const WIDTH_MIN: f64 = 1.0;
+    pub const WIDTH_MAX: f64 = 20.0;
+    pub const TIMEOUT_DEFAULT: f64 = 1.5;
+    pub const TIMEOUT_MIN: f64 = 0.1;
+    pub const TIMEOUT_MAX: f64 = 30.0;
+    /// The width of the glow around the trails, relative to their width
+    pub const GLOW_WIDTH_FACTOR: f64 = 3.0;
+    pub const GLOW_OPACITY: f64 = 0.3;
+
+    /// Whether there are trails which are drawn or fading out
+    pub fn has_trails(&self) -> bool {
+        !self.trails.is_empty()
+    }
+
+    pub fn begin(&mut self, pos: na::Vector2<f64>) {
+        self.trails.push(LaserTrail {
+            points: vec![pos],
+            ended: None,
+        });
+    }
+
+    pub fn motion(&mut self, pos: na::Vector2<f64>) {
+        if let Some(trail) = self.trails.last_mut().filter(|trail| trail.ended.is_none()) {
+            trail.points.push(pos);
+        }
+    }
+
+    pub fn end(&mut self, now: Instant) {
+        if let Some(trail) = self.trails.last_mut().filter(|trail| trail.ended.is_none()) {
+            trail.ended = Some(now);
+        }
+    }
+
+    /// Removes the trails which faded out. Returns whether some are still shown
+    pub fn update(&mut self, now: Instant) -> bool {
+        let timeout = self.timeout;
+        self.trails
+            .retain(|trail| trail.opacity(timeout, now) > 0.0);
+
+        self.has_trails()
+    }
+
+    #[cfg(feature = "ui")]
+    pub fn draw(
+        &self,
+        zoom: f64,
+        now: Instant,
+        snapshot: &Snapshot,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<(), anyhow::Error> {
+        let width = self.width / zoom;
+        let glow_width = width * Self::GLOW_WIDTH_FACTOR;
+        let color = self.color.to_css_color();
+
+        let mut bounds = AABB::new_invalid();
+        let mut group = svg::node::element::Group::new();
+        let mut drawn = false;
+
+        for trail in self.trails.iter() {
+            let opacity = trail.opacity(self.timeout, now);
+            let (first, rest) = match trail.points.split_first() {
+                Some(points) if opacity > 0.0 => points,
+                _ => continue,
+            };
+
+            let mut path_data = svg::node::element::path::Data::new().move_to((first[0], first[1]));
+            if rest.is_empty() {
+                // A tap is drawn as dot
+                path_data = path_data.line_to((first[0], first[1]));
+            }
+            for point in rest {
+                path_data = path_data.line_to((point[0], point[1]));
+            }
+            for point in trail.points.iter() {
+                bounds.take_point(na::Point2::from(*point));
+            }
+            drawn = true;
+
+            for (stroke_width, stroke_opacity) in
+                [(glow_width, opacity * Self::GLOW_OPACITY), (width, opacity)]
+            {
+                group = group.add(
+                    svg::node::element::Path::new()
+                        .set("d", path_data.clone())
+                        .set("stroke", color.clone())
+                        .set("stroke-opacity", stroke_opacity)
+                        .set("stroke-width", stroke_width)
+                        .set("stroke-linecap", "round")
+                        .set("stroke-linejoin", "round")
+                        .set("fill", "none"),
+                );
+            }
+        }
+
+        if !drawn {
+            return Ok(());
+        }
+        let bounds = bounds.loosened(glow_width);
+
+        let svg_data = compose::svg_node_to_string(&group)?;
+        let svg = render::Svg { svg_data, bounds };
+
+        let images = renderer
+            .read()
+            .unwrap()
+            .gen_images(zoom, vec![svg], bounds)?;
+        if let Some(rendernode) = render::images_to_rendernode(&images, zoom)
+            .context("images_to_rendernode() failed in lasertool .draw()")?
+        {
+            snapshot.append_node(&rendernode);
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[serde(default, rename = "tools")]
 pub struct Tools {
@@ -240,6 +757,12 @@ pub struct Tools {
     pub expand_sheet_tool: ExpandSheetTool,
     #[serde(skip)]
     pub drag_proximity_tool: DragProximityTool,
+    #[serde(skip)]
+    pub eyedropper_tool: EyedropperTool,
+    #[serde(rename = "measure_tool")]
+    pub measure_tool: MeasureTool,
+    #[serde(rename = "laser_tool")]
+    pub laser_tool: LaserTool,
 }
 
 impl PenBehaviour for Tools {
@@ -248,7 +771,7 @@ impl PenBehaviour for Tools {
         mut data_entries: VecDeque<InputData>,
         sheet: &mut Sheet,
         _viewport: Option<AABB>,
-        _zoom: f64,
+        zoom: f64,
         _renderer: Arc<RwLock<Renderer>>,
     ) {
         if let Some(inputdata) = data_entries.pop_back() {
@@ -268,6 +791,15 @@ impl PenBehaviour for Tools {
                     self.drag_proximity_tool.pos = inputdata.pos();
                     self.drag_proximity_tool.offset = na::Vector2::zeros();
                 }
+                ToolStyle::Eyedropper => {
+                    self.eyedropper_tool.pick(inputdata.pos(), sheet, zoom);
+                }
+                ToolStyle::Measure => {
+                    self.measure_tool.begin(inputdata.pos(), zoom);
+                }
+                ToolStyle::Laser => {
+                    self.laser_tool.begin(inputdata.pos());
+                }
             }
         }
     }
@@ -314,16 +846,27 @@ impl PenBehaviour for Tools {
                         self.drag_proximity_tool.offset = na::Vector2::zeros();
                     }
                 }
+                ToolStyle::Eyedropper => {
+                    self.eyedropper_tool.pick(inputdata.pos(), sheet, zoom);
+                }
+                ToolStyle::Measure => {
+                    self.measure_tool.motion(inputdata.pos());
+                }
+                ToolStyle::Laser => {
+                    for inputdata in data_entries.iter().chain(std::iter::once(&inputdata)) {
+                        self.laser_tool.motion(inputdata.pos());
+                    }
+                }
             }
         }
     }
 
     fn end(
         &mut self,
-        _data_entries: VecDeque<InputData>,
+        data_entries: VecDeque<InputData>,
         _sheet: &mut Sheet,
         _viewport: Option<AABB>,
-        _zoom: f64,
+        zoom: f64,
         _renderer: Arc<RwLock<Renderer>>,
     ) {
         let current_style = self.style;
@@ -337,9 +880,28 @@ impl PenBehaviour for Tools {
                 self.drag_proximity_tool.pos = na::Vector2::zeros();
                 self.drag_proximity_tool.offset = na::Vector2::zeros();
             }
+            // The picked color is kept, to be taken on by the brush
+            ToolStyle::Eyedropper => {
+                self.eyedropper_tool.pos = None;
+            }
+            // The measurement is kept to be read, until the next one begins
+            ToolStyle::Measure => {
+                if let Some(inputdata) = data_entries.back() {
+                    self.measure_tool.motion(inputdata.pos());
+                }
+                self.measure_tool.end(zoom);
+            }
+            // The trail fades out from here on
+            ToolStyle::Laser => {
+                for inputdata in data_entries.iter() {
+                    self.laser_tool.motion(inputdata.pos());
+                }
+                self.laser_tool.end(Instant::now());
+            }
         }
     }
 
+    #[cfg(feature = "ui")]
     fn draw(
         &self,
         snapshot: &Snapshot,
@@ -359,6 +921,11 @@ impl PenBehaviour for Tools {
                 self.drag_proximity_tool
                     .draw(sheet_bounds, zoom, snapshot, renderer)?;
             }
+            ToolStyle::Eyedropper => {
+                self.eyedropper_tool.draw(zoom, snapshot, renderer)?;
+            }
+            // Drawn by the pens, as the measurement and the fading trails are shown while the pen is up as well
+            ToolStyle::Measure | ToolStyle::Laser => {}
         }
 
         Ok(())
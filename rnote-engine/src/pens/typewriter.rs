@@ -0,0 +1,335 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "ui")]
+use crate::compose;
+use crate::compose::color::Color;
+#[cfg(feature = "ui")]
+use crate::render;
+use crate::render::Renderer;
+use crate::sheet::Sheet;
+use crate::strokes::inputdata::InputData;
+use crate::strokes::strokestyle::StrokeStyle;
+use crate::strokes::textstroke::{TextStroke, TextStyle};
+use crate::strokesstate::StrokeKey;
+
+#[cfg(feature = "ui")]
+use anyhow::Context;
+#[cfg(feature = "ui")]
+use gtk4::Snapshot;
+#[cfg(feature = "ui")]
+use p2d::bounding_volume::BoundingVolume;
+use p2d::bounding_volume::AABB;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ui")]
+use svg::node::element;
+
+use super::penbehaviour::PenBehaviour;
+use super::KeyboardKey;
+
+#[derive(Debug, Clone, Copy)]
+pub enum TypewriterState {
+    Idle,
+    /// Editing the text with the key. The cursor is the byte index into the text
+    Editing {
+        key: StrokeKey,
+        cursor: usize,
+    },
+}
+
+impl Default for TypewriterState {
+    fn default() -> Self {
+        Self::Idle
+    }
+}
+
+/// The typewriter places text boxes on the sheet. Tapping an existing text continues editing it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "typewriter")]
+pub struct Typewriter {
+    /// The style of new texts
+    #[serde(rename = "text_style")]
+    pub text_style: TextStyle,
+    /// The width new texts are wrapped at
+    #[serde(rename = "max_width")]
+    pub max_width: f64,
+    #[serde(skip)]
+    state: TypewriterState,
+}
+
+impl Default for Typewriter {
+    fn default() -> Self {
+        Self {
+            text_style: TextStyle::default(),
+            max_width: TextStroke::MAX_WIDTH_DEFAULT,
+            state: TypewriterState::default(),
+        }
+    }
+}
+
+impl PenBehaviour for Typewriter {
+    fn begin(
+        &mut self,
+        data_entries: VecDeque<InputData>,
+        sheet: &mut Sheet,
+        _viewport: Option<AABB>,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) {
+        let pos = if let Some(inputdata) = data_entries.back() {
+            inputdata.pos()
+        } else {
+            return;
+        };
+
+        if let Some(key) = sheet.strokes_state.text_at_pos(pos, Self::TAP_TOLERANCE) {
+            if self.editing_key() != Some(key) {
+                self.finish_editing(sheet);
+            }
+
+            let cursor = if let Some(textstroke) = sheet.strokes_state.get_textstroke(key) {
+                // Further style changes start from the style of the edited text
+                self.text_style = textstroke.style.clone();
+                textstroke.cursor_index_at_pos(pos)
+            } else {
+                0
+            };
+
+            self.state = TypewriterState::Editing { key, cursor };
+        } else {
+            self.finish_editing(sheet);
+
+            let textstroke =
+                TextStroke::new(String::new(), pos, self.max_width, self.text_style.clone());
+            let key = sheet
+                .strokes_state
+                .insert_stroke(StrokeStyle::TextStroke(textstroke));
+            sheet
+                .strokes_state
+                .regenerate_rendering_for_stroke(key, renderer, zoom);
+
+            self.state = TypewriterState::Editing { key, cursor: 0 };
+        }
+    }
+
+    fn motion(
+        &mut self,
+        _data_entries: VecDeque<InputData>,
+        _sheet: &mut Sheet,
+        _viewport: Option<AABB>,
+        _zoom: f64,
+        _renderer: Arc<RwLock<Renderer>>,
+    ) {
+    }
+
+    fn end(
+        &mut self,
+        _data_entries: VecDeque<InputData>,
+        _sheet: &mut Sheet,
+        _viewport: Option<AABB>,
+        _zoom: f64,
+        _renderer: Arc<RwLock<Renderer>>,
+    ) {
+    }
+
+    fn key_pressed(
+        &mut self,
+        keyboard_key: KeyboardKey,
+        sheet: &mut Sheet,
+        _viewport: Option<AABB>,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> bool {
+        let (key, cursor) = match self.state {
+            TypewriterState::Editing { key, cursor } => (key, cursor),
+            TypewriterState::Idle => return false,
+        };
+
+        if keyboard_key == KeyboardKey::Escape {
+            self.finish_editing(sheet);
+            return true;
+        }
+
+        let textstroke = if let Some(textstroke) = sheet.strokes_state.get_textstroke_mut(key) {
+            textstroke
+        } else {
+            // The text was removed in the meantime
+            self.state = TypewriterState::Idle;
+            return false;
+        };
+
+        let new_cursor = match keyboard_key {
+            KeyboardKey::Unicode(c) => textstroke.insert_text(cursor, c.encode_utf8(&mut [0; 4])),
+            KeyboardKey::Return => textstroke.insert_text(cursor, "\n"),
+            KeyboardKey::BackSpace => textstroke.remove_prev_char(cursor),
+            KeyboardKey::Delete => {
+                textstroke.remove_next_char(cursor);
+                cursor
+            }
+            KeyboardKey::Left => textstroke.prev_cursor_index(cursor),
+            KeyboardKey::Right => textstroke.next_cursor_index(cursor),
+            KeyboardKey::Home => textstroke.line_start_index(cursor),
+            KeyboardKey::End => textstroke.line_end_index(cursor),
+            KeyboardKey::Escape => cursor,
+        };
+        self.state = TypewriterState::Editing {
+            key,
+            cursor: new_cursor,
+        };
+
+        sheet.strokes_state.update_geometry_for_stroke(key);
+        sheet
+            .strokes_state
+            .regenerate_rendering_for_stroke(key, renderer, zoom);
+
+        true
+    }
+
+    #[cfg(feature = "ui")]
+    fn draw(
+        &self,
+        snapshot: &Snapshot,
+        sheet: &Sheet,
+        _viewport: Option<AABB>,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<(), anyhow::Error> {
+        let (key, cursor) = match self.state {
+            TypewriterState::Editing { key, cursor } => (key, cursor),
+            TypewriterState::Idle => return Ok(()),
+        };
+        let textstroke = if let Some(textstroke) = sheet.strokes_state.get_textstroke(key) {
+            textstroke
+        } else {
+            return Ok(());
+        };
+
+        let size = textstroke.local_size()?;
+        let corners = [
+            na::point![0.0, 0.0],
+            na::point![size[0], 0.0],
+            na::point![size[0], size[1]],
+            na::point![0.0, size[1]],
+        ]
+        .map(|corner| textstroke.transform.transform_point(corner));
+
+        let mut outline_data = element::path::Data::new().move_to((corners[0][0], corners[0][1]));
+        for corner in corners.iter().skip(1) {
+            outline_data = outline_data.line_to((corner[0], corner[1]));
+        }
+        outline_data = outline_data.close();
+
+        let outline = element::Path::new()
+            .set("d", outline_data)
+            .set("stroke", Self::OUTLINE_COLOR.to_css_color())
+            .set("stroke-width", Self::OUTLINE_WIDTH / zoom)
+            .set("stroke-dasharray", format!("{} {}", 4.0 / zoom, 6.0 / zoom))
+            .set("fill", "none");
+
+        let (cursor_start, cursor_end) = textstroke.cursor_line(cursor)?;
+        let cursor_line = element::Line::new()
+            .set("x1", cursor_start[0])
+            .set("y1", cursor_start[1])
+            .set("x2", cursor_end[0])
+            .set("y2", cursor_end[1])
+            .set("stroke", Self::CURSOR_COLOR.to_css_color())
+            .set("stroke-width", Self::CURSOR_WIDTH / zoom);
+
+        let group = element::Group::new().add(outline).add(cursor_line);
+        let svg_data = compose::svg_node_to_string(&group).map_err(|e| {
+            anyhow::anyhow!("node_to_string() failed in draw() for typewriter, {}", e)
+        })?;
+
+        let bounds = textstroke
+            .bounds
+            .loosened(Self::CURSOR_WIDTH.max(Self::OUTLINE_WIDTH) / zoom);
+        let svg = render::Svg { bounds, svg_data };
+        let images = renderer
+            .read()
+            .unwrap()
+            .gen_images(zoom, vec![svg], bounds)?;
+        if let Some(rendernode) = render::images_to_rendernode(&images, zoom)
+            .context("images_to_rendernode() failed in typewriter.draw()")?
+        {
+            snapshot.append_node(&rendernode);
+        }
+
+        Ok(())
+    }
+}
+
+impl Typewriter {
+    /// The distance a tap can be away from a text to still edit it
+    pub const TAP_TOLERANCE: f64 = 4.0;
+    pub const OUTLINE_WIDTH: f64 = 1.5;
+    pub const OUTLINE_COLOR: Color = Color {
+        r: 0.5,
+        g: 0.5,
+        b: 0.5,
+        a: 0.7,
+    };
+    pub const CURSOR_WIDTH: f64 = 2.0;
+    pub const CURSOR_COLOR: Color = Color {
+        r: 0.2,
+        g: 0.4,
+        b: 0.9,
+        a: 1.0,
+    };
+
+    /// The key of the text currently being edited
+    pub fn editing_key(&self) -> Option<StrokeKey> {
+        match self.state {
+            TypewriterState::Editing { key, .. } => Some(key),
+            TypewriterState::Idle => None,
+        }
+    }
+
+    /// Applies the current text style to the text being edited. Returns true if there is a text being edited
+    pub fn apply_text_style_to_editing(
+        &mut self,
+        sheet: &mut Sheet,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> bool {
+        let key = if let Some(key) = self.editing_key() {
+            key
+        } else {
+            return false;
+        };
+
+        if let Some(textstroke) = sheet.strokes_state.get_textstroke_mut(key) {
+            textstroke.set_style(self.text_style.clone());
+
+            sheet.strokes_state.update_geometry_for_stroke(key);
+            sheet
+                .strokes_state
+                .regenerate_rendering_for_stroke(key, renderer, zoom);
+
+            true
+        } else {
+            self.state = TypewriterState::Idle;
+            false
+        }
+    }
+
+    /// Finishes editing the current text. Empty texts are removed. Returns true if a text was removed
+    pub fn finish_editing(&mut self, sheet: &mut Sheet) -> bool {
+        let key = if let Some(key) = self.editing_key() {
+            key
+        } else {
+            return false;
+        };
+        self.state = TypewriterState::Idle;
+
+        if sheet
+            .strokes_state
+            .get_textstroke(key)
+            .map_or(false, |textstroke| textstroke.text.is_empty())
+        {
+            sheet.strokes_state.remove_stroke(key);
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Counters of the rendering performance, to make regressions measurable and to display them in a debug overlay.
+//! The strokes are composed on the threadpool as well, so the counters are atomics which are shared with the jobs.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use web_time::Instant;
+
+/// The counters. Read them with `stats()`
+#[derive(Debug, Default)]
+pub struct PerfCounters {
+    frames: AtomicU64,
+    /// The strokes drawn in the last frame
+    frame_strokes_drawn: AtomicU64,
+    /// The tiles generated in the last frame
+    frame_tiles_generated: AtomicU64,
+    /// The time drawing the strokes took in the last frame, in microseconds
+    frame_draw_time: AtomicU64,
+    /// The strokes which were composed completely
+    strokes_composed: AtomicU64,
+    /// The time composing the strokes took in total, in microseconds
+    compose_time: AtomicU64,
+}
+
+/// The state of the counters at some point
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PerfStats {
+    /// The drawn frames
+    pub frames: u64,
+    /// The strokes drawn in the last frame
+    pub frame_strokes_drawn: u64,
+    /// The tiles generated in the last frame, see `strokesstate::tilecache`
+    pub frame_tiles_generated: u64,
+    /// The time drawing the strokes took in the last frame
+    pub frame_draw_time: Duration,
+    /// The strokes which were composed completely (rendered to images or tessellated)
+    pub strokes_composed: u64,
+    /// The time composing the strokes took in total
+    pub compose_time: Duration,
+}
+
+impl PerfStats {
+    /// The mean time composing a stroke took. Zero if none were composed
+    pub fn mean_compose_time(&self) -> Duration {
+        if self.strokes_composed == 0 {
+            return Duration::ZERO;
+        }
+
+        Duration::from_secs_f64(self.compose_time.as_secs_f64() / self.strokes_composed as f64)
+    }
+}
+
+impl std::fmt::Display for PerfStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "frames: {}", self.frames)?;
+        writeln!(
+            f,
+            "last frame: {} strokes drawn, {} tiles generated in {:.2} ms",
+            self.frame_strokes_drawn,
+            self.frame_tiles_generated,
+            self.frame_draw_time.as_secs_f64() * 1000.0
+        )?;
+        write!(
+            f,
+            "strokes composed: {}, {:.2} ms per stroke",
+            self.strokes_composed,
+            self.mean_compose_time().as_secs_f64() * 1000.0
+        )
+    }
+}
+
+impl PerfCounters {
+    /// Records a drawn frame
+    pub fn record_frame(&self, strokes_drawn: usize, tiles_generated: usize, draw_time: Duration) {
+        self.frames.fetch_add(1, Ordering::Relaxed);
+        self.frame_strokes_drawn
+            .store(strokes_drawn as u64, Ordering::Relaxed);
+        self.frame_tiles_generated
+            .store(tiles_generated as u64, Ordering::Relaxed);
+        self.frame_draw_time
+            .store(draw_time.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Records a stroke which was composed, since the start instant
+    pub fn record_compose(&self, start: Instant) {
+        self.strokes_composed.fetch_add(1, Ordering::Relaxed);
+        self.compose_time
+            .fetch_add(start.elapsed().as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> PerfStats {
+        PerfStats {
+            frames: self.frames.load(Ordering::Relaxed),
+            frame_strokes_drawn: self.frame_strokes_drawn.load(Ordering::Relaxed),
+            frame_tiles_generated: self.frame_tiles_generated.load(Ordering::Relaxed),
+            frame_draw_time: Duration::from_micros(self.frame_draw_time.load(Ordering::Relaxed)),
+            strokes_composed: self.strokes_composed.load(Ordering::Relaxed),
+            compose_time: Duration::from_micros(self.compose_time.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Resets all counters, e.g. before measuring something
+    pub fn reset(&self) {
+        for counter in [
+            &self.frames,
+            &self.frame_strokes_drawn,
+            &self.frame_tiles_generated,
+            &self.frame_draw_time,
+            &self.strokes_composed,
+            &self.compose_time,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Plugins add pens and stroke styles to the engine from other crates, without forking it.
+//! A pen implements `PluginPen`, a stroke style `Composer`. They are registered once at startup with `register_pen()` and `register_composer()`.
+//!
+//! The strokes of composers are `StrokeStyle::ExtensionStroke`s, which are stored in documents tagged with the type name of their composer.
+//! Documents with strokes of composers which are not registered still open: these strokes are kept and saved as they were loaded, but are not drawn.
+//! Plugins are linked into the app, loading them dynamically would need a stable ABI which Rust doesn't have.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+
+use crate::compose::transformable::Transformable;
+use crate::drawbehaviour::DrawBehaviour;
+use crate::pens::penbehaviour::PenBehaviour;
+
+/// A stroke style added by a plugin. The strokes are inserted as `StrokeStyle::ExtensionStroke(ExtensionStroke::new(composer))`
+pub trait Composer: DrawBehaviour + Transformable + std::fmt::Debug + Send + Sync {
+    /// The name the strokes are tagged with in documents. Must be unique, e.g. prefixed with the name of the crate
+    fn type_name(&self) -> &'static str;
+    /// The data of the stroke as it is stored in documents. It is read back with the deserializer registered for the type name
+    fn serialize_data(&self) -> Result<serde_json::Value, anyhow::Error>;
+    fn clone_composer(&self) -> Box<dyn Composer>;
+}
+
+impl Clone for Box<dyn Composer> {
+    fn clone(&self) -> Self {
+        self.clone_composer()
+    }
+}
+
+/// A pen added by a plugin. It is used with `PenStyle::PluginStyle`, after it was selected with `Pens::set_plugin_pen()`
+pub trait PluginPen: PenBehaviour + std::fmt::Debug {
+    /// The name the pen is registered with
+    fn name(&self) -> &'static str;
+    fn display_name(&self) -> String;
+    fn icon_name(&self) -> String;
+    fn clone_pen(&self) -> Box<dyn PluginPen>;
+}
+
+impl Clone for Box<dyn PluginPen> {
+    fn clone(&self) -> Self {
+        self.clone_pen()
+    }
+}
+
+/// Reads a stroke of a composer from its data, see `Composer::serialize_data()`
+pub type ComposerDeserializer = fn(serde_json::Value) -> Result<Box<dyn Composer>, anyhow::Error>;
+/// Creates a new instance of a plugin pen
+pub type PenConstructor = fn() -> Box<dyn PluginPen>;
+
+/// The deserializer for composers which implement `Deserialize`, e.g. `register_composer("mycrate.mystroke", deserialize_composer::<MyStroke>)`
+pub fn deserialize_composer<T>(data: serde_json::Value) -> Result<Box<dyn Composer>, anyhow::Error>
+where
+    T: Composer + DeserializeOwned + 'static,
+{
+    Ok(Box::new(serde_json::from_value::<T>(data)?))
+}
+
+#[derive(Default)]
+struct Registry {
+    composers: HashMap<&'static str, ComposerDeserializer>,
+    /// In the order they were registered
+    pens: Vec<(&'static str, PenConstructor)>,
+}
+
+static REGISTRY: Lazy<RwLock<Registry>> = Lazy::new(|| RwLock::new(Registry::default()));
+
+/// Registers the composer with the type name of its strokes. Documents opened before are not updated.
+/// Fails if a composer with the type name is already registered
+pub fn register_composer(
+    type_name: &'static str,
+    deserializer: ComposerDeserializer,
+) -> Result<(), anyhow::Error> {
+    let mut registry = REGISTRY.write().unwrap();
+    if registry.composers.contains_key(type_name) {
+        return Err(anyhow::anyhow!(
+            "register_composer() failed, a composer with type name `{}` is already registered",
+            type_name
+        ));
+    }
+
+    registry.composers.insert(type_name, deserializer);
+    Ok(())
+}
+
+/// Registers the pen with its name. Fails if a pen with the name is already registered
+pub fn register_pen(name: &'static str, constructor: PenConstructor) -> Result<(), anyhow::Error> {
+    let mut registry = REGISTRY.write().unwrap();
+    if registry
+        .pens
+        .iter()
+        .any(|&(registered_name, _)| registered_name == name)
+    {
+        return Err(anyhow::anyhow!(
+            "register_pen() failed, a pen with name `{}` is already registered",
+            name
+        ));
+    }
+
+    registry.pens.push((name, constructor));
+    Ok(())
+}
+
+/// The names of the registered pens, in the order they were registered
+pub fn registered_pens() -> Vec<&'static str> {
+    REGISTRY
+        .read()
+        .unwrap()
+        .pens
+        .iter()
+        .map(|&(name, _)| name)
+        .collect()
+}
+
+/// A new instance of the registered pen. None if no pen is registered with the name
+pub fn new_pen(name: &str) -> Option<Box<dyn PluginPen>> {
+    let constructor = REGISTRY
+        .read()
+        .unwrap()
+        .pens
+        .iter()
+        .find(|&&(registered_name, _)| registered_name == name)
+        .map(|&(_, constructor)| constructor)?;
+
+    Some(constructor())
+}
+
+/// The deserializer of the composer registered with the type name
+pub(crate) fn composer_deserializer(type_name: &str) -> Option<ComposerDeserializer> {
+    REGISTRY.read().unwrap().composers.get(type_name).copied()
+}
@@ -0,0 +1,794 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use futures::channel::oneshot;
+use p2d::bounding_volume::{BoundingVolume, AABB};
+
+use crate::utils;
+
+/// The ink the handwriting is recognized from. Each entry holds the positions of one stroke
+pub type Ink = Vec<Vec<na::Vector2<f64>>>;
+
+/// The text recognized from ink
+#[derive(Debug, Clone)]
+pub struct RecognizedText {
+    /// The text, with lines separated by `\n`
+    pub text: String,
+    /// How certain the backend is about the text, between 0.0 and 1.0
+    pub confidence: f64,
+}
+
+/// A backend recognizing handwritten text. Backends must be thread safe, because recognizing runs on a separate thread
+pub trait HandwritingBackend: std::fmt::Debug + Send + Sync {
+    /// The name of the backend, shown to the user
+    fn name(&self) -> &str;
+
+    /// Recognizes the text from the ink
+    fn recognize(&self, ink: &Ink) -> Result<RecognizedText, anyhow::Error>;
+}
+
+/// Recognizes the text on a new thread to avoid blocking.
+/// Returns the receiver to be awaited on for the recognized text
+pub fn recognize_async(
+    backend: Arc<dyn HandwritingBackend>,
+    ink: Ink,
+) -> oneshot::Receiver<Result<RecognizedText, anyhow::Error>> {
+    let (oneshot_sender, oneshot_receiver) =
+        oneshot::channel::<Result<RecognizedText, anyhow::Error>>();
+
+    utils::spawn(move || {
+        let result = backend.recognize(&ink);
+
+        if oneshot_sender.send(result).is_err() {
+            log::error!(
+                "oneshot_sender.send() failed in recognize_async(), the receiver was dropped"
+            );
+        }
+    });
+
+    oneshot_receiver
+}
+
+/// The amount of points the ink of a character is resampled to
+const RESAMPLE_N_POINTS: usize = 32;
+/// The mean distance of the points to the template, relative to the character size, at which the confidence drops to 0.0
+const CLOUD_DISTANCE_MAX: f64 = 0.5;
+/// Strokes closer than this ratio of the largest stroke size vertically belong to the same line
+const LINE_MARGIN_RATIO: f64 = 0.4;
+/// Strokes closer than this ratio of the line height horizontally belong to the same character
+const CHAR_GAP_RATIO: f64 = 0.05;
+/// Characters further apart than this ratio of the line height are separated by a space
+const WORD_GAP_RATIO: f64 = 0.4;
+/// Characters smaller than this ratio of the line height are recognized as dots
+const DOT_SIZE_RATIO: f64 = 0.15;
+
+/// The positions of a stroke, together with their bounds
+type BoundedStroke = (Vec<na::Vector2<f64>>, AABB);
+
+/// A character template, as the strokes in a box with the height 1.0
+#[derive(Debug, Clone)]
+struct Template {
+    character: char,
+    cloud: Vec<na::Vector2<f64>>,
+}
+
+/// The offline backend. It matches the point cloud of every character against built in templates ( the $P recognizer ),
+/// so it recognizes block capitals, digits and a few symbols independent of the stroke order and direction.
+#[derive(Debug, Clone)]
+pub struct PointCloudBackend {
+    templates: Vec<Template>,
+}
+
+impl Default for PointCloudBackend {
+    fn default() -> Self {
+        Self {
+            templates: builtin_templates()
+                .into_iter()
+                .filter_map(|(character, strokes)| {
+                    Some(Template {
+                        character,
+                        cloud: normalize_cloud(&strokes)?,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+impl HandwritingBackend for PointCloudBackend {
+    fn name(&self) -> &str {
+        "Point cloud"
+    }
+
+    fn recognize(&self, ink: &Ink) -> Result<RecognizedText, anyhow::Error> {
+        let strokes = ink
+            .iter()
+            .filter_map(|points| Some((points.clone(), points_bounds(points)?)))
+            .collect::<Vec<BoundedStroke>>();
+        if strokes.is_empty() {
+            return Err(anyhow::anyhow!(
+                "recognize() failed for the point cloud backend, the ink is empty"
+            ));
+        }
+
+        let mut lines = Vec::<String>::new();
+        let mut confidence_sum = 0.0;
+        let mut n_characters: u32 = 0;
+
+        for line in segment_lines(strokes) {
+            let line_bounds = line
+                .iter()
+                .skip(1)
+                .fold(line[0].1, |acc, (_, bounds)| acc.merged(bounds));
+            let line_height = line_bounds.extents()[1].max(1.0);
+
+            let mut text = String::new();
+            let mut prev_maxs_x: Option<f64> = None;
+
+            for character in segment_characters(line, line_height) {
+                let character_bounds = character
+                    .iter()
+                    .skip(1)
+                    .fold(character[0].1, |acc, (_, bounds)| acc.merged(bounds));
+
+                if let Some(prev_maxs_x) = prev_maxs_x {
+                    if character_bounds.mins[0] - prev_maxs_x > WORD_GAP_RATIO * line_height {
+                        text.push(' ');
+                    }
+                }
+                prev_maxs_x = Some(character_bounds.maxs[0]);
+
+                if character_bounds.extents().max() < DOT_SIZE_RATIO * line_height {
+                    text.push('.');
+                    confidence_sum += 1.0;
+                    n_characters += 1;
+                    continue;
+                }
+
+                let points = character
+                    .into_iter()
+                    .map(|(points, _)| points)
+                    .collect::<Vec<Vec<na::Vector2<f64>>>>();
+
+                if let Some((recognized, confidence)) = self.recognize_character(&points) {
+                    text.push(recognized);
+                    confidence_sum += confidence;
+                    n_characters += 1;
+                }
+            }
+
+            lines.push(text);
+        }
+
+        Ok(RecognizedText {
+            text: lines.join("\n"),
+            confidence: if n_characters > 0 {
+                confidence_sum / f64::from(n_characters)
+            } else {
+                0.0
+            },
+        })
+    }
+}
+
+impl PointCloudBackend {
+    /// Returns the character of the best matching template, and the confidence
+    fn recognize_character(&self, strokes: &[Vec<na::Vector2<f64>>]) -> Option<(char, f64)> {
+        let cloud = normalize_cloud(strokes)?;
+
+        self.templates
+            .iter()
+            .map(|template| {
+                let distance = greedy_cloud_match(&cloud, &template.cloud);
+                (
+                    template.character,
+                    (1.0 - distance / CLOUD_DISTANCE_MAX).clamp(0.0, 1.0),
+                )
+            })
+            .max_by(|first, second| {
+                first
+                    .1
+                    .partial_cmp(&second.1)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+    }
+}
+
+fn points_bounds(points: &[na::Vector2<f64>]) -> Option<AABB> {
+    let first = points.first()?;
+
+    Some(points.iter().skip(1).fold(
+        AABB::new(na::Point2::from(*first), na::Point2::from(*first)),
+        |mut acc, point| {
+            acc.take_point(na::Point2::from(*point));
+            acc
+        },
+    ))
+}
+
+/// Splits the strokes into lines, sorted from top to bottom. Tall strokes are assigned first, so that small strokes like dots join their line
+fn segment_lines(mut strokes: Vec<BoundedStroke>) -> Vec<Vec<BoundedStroke>> {
+    strokes.sort_by(|first, second| {
+        second.1.extents()[1]
+            .partial_cmp(&first.1.extents()[1])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    // Flat strokes close to a line still join it, e.g. the strokes of a `=`
+    let margin = LINE_MARGIN_RATIO
+        * strokes
+            .iter()
+            .map(|(_, bounds)| bounds.extents().max())
+            .fold(0.0, f64::max);
+    let mut lines: Vec<(AABB, Vec<BoundedStroke>)> = vec![];
+
+    for stroke in strokes {
+        let center_y = stroke.1.center()[1];
+
+        // Strokes touching a stroke of the line join it as well, e.g. the stem of a `Y`
+        if let Some(line) = lines.iter_mut().find(|(bounds, line_strokes)| {
+            (bounds.mins[1] - margin <= center_y && center_y <= bounds.maxs[1] + margin)
+                || line_strokes
+                    .iter()
+                    .any(|(_, line_stroke_bounds)| line_stroke_bounds.intersects(&stroke.1))
+        }) {
+            line.0.merge(&stroke.1);
+            line.1.push(stroke);
+        } else {
+            lines.push((stroke.1, vec![stroke]));
+        }
+    }
+
+    lines.sort_by(|first, second| {
+        first.0.mins[1]
+            .partial_cmp(&second.0.mins[1])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    lines.into_iter().map(|(_, strokes)| strokes).collect()
+}
+
+/// Splits the strokes of a line into characters, sorted from left to right. Strokes overlapping horizontally belong to the same character
+fn segment_characters(
+    mut strokes: Vec<BoundedStroke>,
+    line_height: f64,
+) -> Vec<Vec<BoundedStroke>> {
+    strokes.sort_by(|first, second| {
+        first.1.mins[0]
+            .partial_cmp(&second.1.mins[0])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut characters: Vec<(f64, Vec<BoundedStroke>)> = vec![];
+
+    for stroke in strokes {
+        match characters.last_mut() {
+            Some((maxs_x, character))
+                if stroke.1.mins[0] <= *maxs_x + CHAR_GAP_RATIO * line_height =>
+            {
+                *maxs_x = maxs_x.max(stroke.1.maxs[0]);
+                character.push(stroke);
+            }
+            _ => characters.push((stroke.1.maxs[0], vec![stroke])),
+        }
+    }
+
+    characters
+        .into_iter()
+        .map(|(_, character)| character)
+        .collect()
+}
+
+/// Resamples the strokes to evenly spaced points, scales them to the size 1.0 and centers them around the origin.
+/// Returns None if the strokes have no extent
+fn normalize_cloud(strokes: &[Vec<na::Vector2<f64>>]) -> Option<Vec<na::Vector2<f64>>> {
+    let length = strokes
+        .iter()
+        .map(|points| path_length(points))
+        .sum::<f64>();
+    if length <= 0.0 {
+        return None;
+    }
+    let spacing = length / (RESAMPLE_N_POINTS - 1) as f64;
+
+    let mut cloud = Vec::with_capacity(RESAMPLE_N_POINTS);
+    let mut dist_acc = 0.0;
+
+    for points in strokes.iter().filter(|points| !points.is_empty()) {
+        // The first point is kept, the others are evenly spaced along the paths of all strokes
+        if cloud.is_empty() {
+            cloud.push(points[0]);
+        }
+        let mut prev = points[0];
+
+        for &point in points.iter().skip(1) {
+            let mut dist = (point - prev).magnitude();
+
+            while dist_acc + dist >= spacing && dist > 0.0 {
+                let new_point = prev + (point - prev) * ((spacing - dist_acc) / dist);
+                cloud.push(new_point);
+
+                prev = new_point;
+                dist = (point - prev).magnitude();
+                dist_acc = 0.0;
+            }
+
+            dist_acc += dist;
+            prev = point;
+        }
+    }
+    // Rounding errors may cause the last point to be missing
+    cloud.truncate(RESAMPLE_N_POINTS);
+    while cloud.len() < RESAMPLE_N_POINTS {
+        cloud.push(*strokes.iter().rev().find_map(|points| points.last())?);
+    }
+
+    let bounds = points_bounds(&cloud)?;
+    let scale = bounds.extents().max();
+    if scale <= 0.0 {
+        return None;
+    }
+    let centroid = cloud.iter().sum::<na::Vector2<f64>>() / cloud.len() as f64;
+
+    Some(
+        cloud
+            .into_iter()
+            .map(|point| (point - centroid) / scale)
+            .collect(),
+    )
+}
+
+fn path_length(points: &[na::Vector2<f64>]) -> f64 {
+    points
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).magnitude())
+        .sum()
+}
+
+/// The min weighted mean distance between the clouds, trying a few start points in both directions
+fn greedy_cloud_match(cloud: &[na::Vector2<f64>], template: &[na::Vector2<f64>]) -> f64 {
+    let n = cloud.len().min(template.len());
+    let step = ((n as f64).sqrt().floor() as usize).max(1);
+
+    (0..n)
+        .step_by(step)
+        .map(|start| {
+            cloud_distance(cloud, template, start).min(cloud_distance(template, cloud, start))
+        })
+        .fold(f64::MAX, f64::min)
+}
+
+/// Matches every point of the first cloud with the closest unmatched point of the second. Earlier matches are weighted more
+fn cloud_distance(first: &[na::Vector2<f64>], second: &[na::Vector2<f64>], start: usize) -> f64 {
+    let n = first.len().min(second.len());
+    let mut matched = vec![false; n];
+    let mut distance_sum = 0.0;
+    let mut weight_sum = 0.0;
+
+    for offset in 0..n {
+        let point = first[(start + offset) % n];
+
+        if let Some((closest_index, closest_dist)) = second[..n]
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !matched[*index])
+            .map(|(index, other)| (index, (other - point).magnitude()))
+            .min_by(|first, second| {
+                first
+                    .1
+                    .partial_cmp(&second.1)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+        {
+            matched[closest_index] = true;
+
+            let weight = 1.0 - offset as f64 / n as f64;
+            distance_sum += weight * closest_dist;
+            weight_sum += weight;
+        }
+    }
+
+    if weight_sum > 0.0 {
+        distance_sum / weight_sum
+    } else {
+        f64::MAX
+    }
+}
+
+/// The points of a template stroke
+type TemplateStroke = Vec<(f64, f64)>;
+
+/// Points along an elliptic arc, with the angles in radians ( clockwise, starting at the right )
+fn arc(center: (f64, f64), radii: (f64, f64), angle_start: f64, angle_end: f64) -> TemplateStroke {
+    const N_SEGMENTS: usize = 12;
+
+    (0..=N_SEGMENTS)
+        .map(|i| {
+            let angle = angle_start + (angle_end - angle_start) * i as f64 / N_SEGMENTS as f64;
+            (
+                center.0 + radii.0 * angle.cos(),
+                center.1 + radii.1 * angle.sin(),
+            )
+        })
+        .collect()
+}
+
+/// Joins the parts to a single stroke
+fn joined(parts: &[TemplateStroke]) -> TemplateStroke {
+    parts.concat()
+}
+
+/// The built in templates. The characters are drawn in a box with the height 1.0, with the y axis pointing down
+fn builtin_templates() -> Vec<(char, Ink)> {
+    let templates: Vec<(char, Vec<TemplateStroke>)> = vec![
+        // Digits
+        ('0', vec![arc((0.3, 0.5), (0.3, 0.5), -PI / 2.0, 1.5 * PI)]),
+        ('1', vec![vec![(0.1, 0.2), (0.3, 0.0), (0.3, 1.0)]]),
+        (
+            '2',
+            vec![joined(&[
+                arc((0.3, 0.3), (0.3, 0.3), PI, 2.25 * PI),
+                vec![(0.0, 1.0), (0.6, 1.0)],
+            ])],
+        ),
+        (
+            '3',
+            vec![joined(&[
+                arc((0.3, 0.25), (0.3, 0.25), 1.25 * PI, 2.5 * PI),
+                arc((0.3, 0.75), (0.3, 0.25), -PI / 2.0, 0.75 * PI),
+            ])],
+        ),
+        (
+            '4',
+            vec![
+                vec![(0.45, 0.0), (0.0, 0.7), (0.6, 0.7)],
+                vec![(0.45, 0.0), (0.45, 1.0)],
+            ],
+        ),
+        (
+            '5',
+            vec![joined(&[
+                vec![(0.55, 0.0), (0.1, 0.0), (0.05, 0.45)],
+                arc((0.3, 0.7), (0.3, 0.3), -0.75 * PI, 0.75 * PI),
+            ])],
+        ),
+        (
+            '6',
+            vec![joined(&[
+                vec![(0.5, 0.0), (0.25, 0.15), (0.05, 0.45)],
+                arc((0.3, 0.7), (0.3, 0.3), PI, 3.0 * PI),
+            ])],
+        ),
+        ('7', vec![vec![(0.0, 0.0), (0.6, 0.0), (0.2, 1.0)]]),
+        (
+            '8',
+            vec![joined(&[
+                arc((0.3, 0.25), (0.25, 0.25), PI / 2.0, 2.5 * PI),
+                arc((0.3, 0.72), (0.3, 0.28), -PI / 2.0, 1.5 * PI),
+            ])],
+        ),
+        (
+            '9',
+            vec![joined(&[
+                arc((0.3, 0.3), (0.3, 0.3), 0.0, 2.0 * PI),
+                vec![(0.6, 0.3), (0.5, 1.0)],
+            ])],
+        ),
+        // Letters
+        (
+            'A',
+            vec![
+                vec![(0.0, 1.0), (0.3, 0.0), (0.6, 1.0)],
+                vec![(0.12, 0.6), (0.48, 0.6)],
+            ],
+        ),
+        (
+            'B',
+            vec![
+                vec![(0.0, 0.0), (0.0, 1.0)],
+                joined(&[
+                    arc((0.0, 0.25), (0.45, 0.25), -PI / 2.0, PI / 2.0),
+                    arc((0.0, 0.75), (0.55, 0.25), -PI / 2.0, PI / 2.0),
+                ]),
+            ],
+        ),
+        (
+            'C',
+            vec![arc((0.35, 0.5), (0.35, 0.5), -PI / 4.0, -1.75 * PI)],
+        ),
+        (
+            'D',
+            vec![
+                vec![(0.0, 0.0), (0.0, 1.0)],
+                arc((0.0, 0.5), (0.6, 0.5), -PI / 2.0, PI / 2.0),
+            ],
+        ),
+        (
+            'E',
+            vec![
+                vec![(0.6, 0.0), (0.0, 0.0), (0.0, 1.0), (0.6, 1.0)],
+                vec![(0.0, 0.5), (0.45, 0.5)],
+            ],
+        ),
+        (
+            'F',
+            vec![
+                vec![(0.6, 0.0), (0.0, 0.0), (0.0, 1.0)],
+                vec![(0.0, 0.5), (0.45, 0.5)],
+            ],
+        ),
+        (
+            'G',
+            vec![joined(&[
+                arc((0.35, 0.5), (0.35, 0.5), -PI / 4.0, -1.75 * PI),
+                vec![(0.7, 0.55), (0.4, 0.55)],
+            ])],
+        ),
+        (
+            'H',
+            vec![
+                vec![(0.0, 0.0), (0.0, 1.0)],
+                vec![(0.6, 0.0), (0.6, 1.0)],
+                vec![(0.0, 0.5), (0.6, 0.5)],
+            ],
+        ),
+        (
+            'I',
+            vec![
+                vec![(0.3, 0.0), (0.3, 1.0)],
+                vec![(0.1, 0.0), (0.5, 0.0)],
+                vec![(0.1, 1.0), (0.5, 1.0)],
+            ],
+        ),
+        (
+            'J',
+            vec![joined(&[
+                vec![(0.6, 0.0), (0.6, 0.7)],
+                arc((0.3, 0.7), (0.3, 0.3), 0.0, PI),
+            ])],
+        ),
+        (
+            'K',
+            vec![
+                vec![(0.0, 0.0), (0.0, 1.0)],
+                vec![(0.6, 0.0), (0.0, 0.55), (0.6, 1.0)],
+            ],
+        ),
+        ('L', vec![vec![(0.0, 0.0), (0.0, 1.0), (0.6, 1.0)]]),
+        (
+            'M',
+            vec![vec![
+                (0.0, 1.0),
+                (0.0, 0.0),
+                (0.35, 0.6),
+                (0.7, 0.0),
+                (0.7, 1.0),
+            ]],
+        ),
+        (
+            'N',
+            vec![vec![(0.0, 1.0), (0.0, 0.0), (0.6, 1.0), (0.6, 0.0)]],
+        ),
+        ('O', vec![arc((0.4, 0.5), (0.4, 0.5), -PI / 2.0, 1.5 * PI)]),
+        (
+            'P',
+            vec![joined(&[
+                vec![(0.0, 1.0), (0.0, 0.0)],
+                arc((0.0, 0.25), (0.55, 0.25), -PI / 2.0, PI / 2.0),
+            ])],
+        ),
+        (
+            'Q',
+            vec![
+                arc((0.4, 0.5), (0.4, 0.5), -PI / 2.0, 1.5 * PI),
+                vec![(0.45, 0.7), (0.8, 1.0)],
+            ],
+        ),
+        (
+            'R',
+            vec![
+                joined(&[
+                    vec![(0.0, 1.0), (0.0, 0.0)],
+                    arc((0.0, 0.25), (0.55, 0.25), -PI / 2.0, PI / 2.0),
+                ]),
+                vec![(0.15, 0.5), (0.6, 1.0)],
+            ],
+        ),
+        (
+            'S',
+            vec![joined(&[
+                arc((0.3, 0.25), (0.3, 0.25), -PI / 4.0, -1.5 * PI),
+                arc((0.3, 0.75), (0.3, 0.25), -PI / 2.0, 0.75 * PI),
+            ])],
+        ),
+        (
+            'T',
+            vec![vec![(0.0, 0.0), (0.6, 0.0)], vec![(0.3, 0.0), (0.3, 1.0)]],
+        ),
+        (
+            'U',
+            vec![joined(&[
+                vec![(0.0, 0.0), (0.0, 0.65)],
+                arc((0.3, 0.65), (0.3, 0.35), PI, 0.0),
+                vec![(0.6, 0.65), (0.6, 0.0)],
+            ])],
+        ),
+        ('V', vec![vec![(0.0, 0.0), (0.3, 1.0), (0.6, 0.0)]]),
+        (
+            'W',
+            vec![vec![
+                (0.0, 0.0),
+                (0.2, 1.0),
+                (0.4, 0.3),
+                (0.6, 1.0),
+                (0.8, 0.0),
+            ]],
+        ),
+        (
+            'X',
+            vec![vec![(0.0, 0.0), (0.6, 1.0)], vec![(0.6, 0.0), (0.0, 1.0)]],
+        ),
+        (
+            'Y',
+            vec![
+                vec![(0.0, 0.0), (0.3, 0.5), (0.6, 0.0)],
+                vec![(0.3, 0.5), (0.3, 1.0)],
+            ],
+        ),
+        (
+            'Z',
+            vec![vec![(0.0, 0.0), (0.6, 0.0), (0.0, 1.0), (0.6, 1.0)]],
+        ),
+        // Symbols
+        (
+            '+',
+            vec![vec![(0.0, 0.5), (0.6, 0.5)], vec![(0.3, 0.2), (0.3, 0.8)]],
+        ),
+        ('-', vec![vec![(0.0, 0.5), (0.6, 0.5)]]),
+        (
+            '=',
+            vec![vec![(0.0, 0.4), (0.6, 0.4)], vec![(0.0, 0.6), (0.6, 0.6)]],
+        ),
+        ('/', vec![vec![(0.6, 0.0), (0.0, 1.0)]]),
+    ];
+
+    templates
+        .into_iter()
+        .map(|(character, strokes)| {
+            (
+                character,
+                strokes
+                    .into_iter()
+                    .map(|points| points.into_iter().map(|(x, y)| na::vector![x, y]).collect())
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The ink of the template of the character, scaled to the height and moved to the offset
+    fn drawn(character: char, offset: na::Vector2<f64>, height: f64) -> Ink {
+        builtin_templates()
+            .into_iter()
+            .find(|(template_character, _)| *template_character == character)
+            .map(|(_, strokes)| {
+                strokes
+                    .into_iter()
+                    .map(|points| {
+                        points
+                            .into_iter()
+                            .map(|point| offset + point * height)
+                            .collect()
+                    })
+                    .collect()
+            })
+            .unwrap()
+    }
+
+    /// The ink of the characters, drawn next to each other with small gaps. Spaces leave a wide gap
+    fn written(text: &str, offset: na::Vector2<f64>, height: f64) -> Ink {
+        let mut ink = Ink::new();
+        let mut x = offset[0];
+
+        for character in text.chars() {
+            if character == ' ' {
+                x += 0.6 * height;
+                continue;
+            }
+            let strokes = drawn(character, na::vector![x, offset[1]], height);
+            x = strokes
+                .iter()
+                .flatten()
+                .map(|point| point[0])
+                .fold(x, f64::max)
+                + 0.2 * height;
+
+            ink.extend(strokes);
+        }
+
+        ink
+    }
+
+    #[test]
+    fn templates_are_recognized() {
+        let backend = PointCloudBackend::default();
+
+        for (character, _) in builtin_templates() {
+            let recognized = backend
+                .recognize(&drawn(character, na::vector![100.0, 100.0], 40.0))
+                .unwrap();
+
+            assert_eq!(recognized.text, character.to_string());
+            assert!(recognized.confidence > 0.9);
+        }
+    }
+
+    #[test]
+    fn stroke_direction_is_ignored() {
+        let backend = PointCloudBackend::default();
+        let reversed = drawn('7', na::vector![0.0, 0.0], 40.0)
+            .into_iter()
+            .map(|points| points.into_iter().rev().collect())
+            .collect::<Ink>();
+
+        assert_eq!(backend.recognize(&reversed).unwrap().text, "7");
+    }
+
+    #[test]
+    fn words_are_separated_by_spaces() {
+        let backend = PointCloudBackend::default();
+
+        assert_eq!(
+            backend
+                .recognize(&written("AB 12", na::vector![0.0, 0.0], 40.0))
+                .unwrap()
+                .text,
+            "AB 12"
+        );
+    }
+
+    #[test]
+    fn lines_are_separated_by_newlines() {
+        let backend = PointCloudBackend::default();
+        // Written out of order, the lines are still sorted from top to bottom
+        let mut ink = written("ZX", na::vector![0.0, 100.0], 40.0);
+        ink.extend(written("A1", na::vector![0.0, 0.0], 40.0));
+
+        assert_eq!(backend.recognize(&ink).unwrap().text, "A1\nZX");
+    }
+
+    #[test]
+    fn small_strokes_are_dots() {
+        let backend = PointCloudBackend::default();
+        let mut ink = written("A", na::vector![0.0, 0.0], 40.0);
+        ink.push(vec![na::vector![40.0, 38.0], na::vector![41.0, 39.0]]);
+
+        assert_eq!(backend.recognize(&ink).unwrap().text, "A.");
+    }
+
+    #[test]
+    fn empty_ink_is_an_error() {
+        let backend = PointCloudBackend::default();
+
+        assert!(backend.recognize(&vec![]).is_err());
+        assert!(backend.recognize(&vec![vec![]]).is_err());
+    }
+
+    #[test]
+    fn recognize_async_sends_the_text() {
+        let backend: Arc<dyn HandwritingBackend> = Arc::new(PointCloudBackend::default());
+
+        let recognized = futures::executor::block_on(recognize_async(
+            backend,
+            written("42", na::vector![0.0, 0.0], 40.0),
+        ))
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(recognized.text, "42");
+    }
+}
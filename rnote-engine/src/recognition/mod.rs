@@ -0,0 +1,2 @@
+pub mod handwriting;
+pub mod shapes;
@@ -0,0 +1,566 @@
+use crate::compose::arrowhead::{ArrowheadOptions, ArrowheadStyle};
+use crate::compose::transformable::Transform;
+use crate::compose::{curves, shapes};
+use crate::strokes::shapestroke::Shape;
+
+use serde::{Deserialize, Serialize};
+
+/// The Options of the shape recognition
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "shape_recognition_options")]
+pub struct ShapeRecognitionOptions {
+    /// Whether finished strokes are replaced by the recognized shapes
+    #[serde(rename = "enabled")]
+    pub enabled: bool,
+    /// The min confidence ( between 0.0 and 1.0 ) a shape must be recognized with to replace the stroke
+    #[serde(rename = "confidence_threshold")]
+    pub confidence_threshold: f64,
+}
+
+impl Default for ShapeRecognitionOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            confidence_threshold: Self::CONFIDENCE_THRESHOLD_DEFAULT,
+        }
+    }
+}
+
+impl ShapeRecognitionOptions {
+    /// Confidence threshold default
+    pub const CONFIDENCE_THRESHOLD_DEFAULT: f64 = 0.8;
+    /// The min confidence threshold
+    pub const CONFIDENCE_THRESHOLD_MIN: f64 = 0.0;
+    /// The max confidence threshold
+    pub const CONFIDENCE_THRESHOLD_MAX: f64 = 1.0;
+}
+
+/// A shape recognized from the points of a stroke
+#[derive(Debug, Clone)]
+pub struct RecognizedShape {
+    pub shape: Shape,
+    /// The arrowheads, if the shape is an arrow
+    pub arrowheads: ArrowheadOptions,
+    /// How well the shape fits the points, between 0.0 and 1.0
+    pub confidence: f64,
+}
+
+/// The amount of points the stroke is resampled to
+const RESAMPLE_N_POINTS: usize = 64;
+/// The window of the straws ( the distance between the points `i - window` and `i + window` ) for the corner detection
+const STRAW_WINDOW: usize = 3;
+/// Points with straws shorter than this factor times the median straw are corner candidates
+const STRAW_THRESHOLD_FACTOR: f64 = 0.95;
+/// Corners where the stroke turns less than this angle ( in radians ) are merged into the straight segment
+const CORNER_ANGLE_MIN: f64 = 0.6;
+/// The stroke is closed if the distance between its ends is smaller than this ratio of its length
+const CLOSED_DIST_RATIO: f64 = 0.2;
+/// The mean distance of the points to the fitted shape, relative to the shape size, at which the confidence drops to 0.0
+const FIT_TOLERANCE: f64 = 0.1;
+/// Strokes shorter than this are not recognized
+const LENGTH_MIN: f64 = 10.0;
+/// The corners of the arrowhead must be closer to the tip than this ratio of the shaft length
+const ARROWHEAD_SHAFT_RATIO_MAX: f64 = 0.5;
+
+/// Recognizes lines, arrows, triangles, rectangles and ellipses from the points of a stroke.
+/// Returns the shape which fits the points best, or None if the stroke is too short to be recognized
+pub fn recognize(points: &[na::Vector2<f64>]) -> Option<RecognizedShape> {
+    let length = path_length(points);
+    if points.len() < 2 || length < LENGTH_MIN {
+        return None;
+    }
+
+    let resampled = resample(points, RESAMPLE_N_POINTS);
+    let closed = (points[points.len() - 1] - points[0]).magnitude() < CLOSED_DIST_RATIO * length;
+    let corners = detect_corners(&resampled, closed);
+
+    let candidates = if closed {
+        vec![
+            recognize_ellipse(&resampled),
+            recognize_polygon(&resampled, &corners),
+        ]
+    } else {
+        vec![
+            recognize_line(&resampled),
+            recognize_arrow(&resampled, &corners),
+        ]
+    };
+
+    candidates.into_iter().flatten().max_by(|first, second| {
+        first
+            .confidence
+            .partial_cmp(&second.confidence)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    })
+}
+
+fn path_length(points: &[na::Vector2<f64>]) -> f64 {
+    points
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).magnitude())
+        .sum()
+}
+
+/// Resamples the points to n points evenly spaced along the path
+fn resample(points: &[na::Vector2<f64>], n_points: usize) -> Vec<na::Vector2<f64>> {
+    let spacing = path_length(points) / (n_points - 1) as f64;
+    let mut resampled = vec![points[0]];
+    let mut prev = points[0];
+    let mut dist_acc = 0.0;
+
+    for &point in points.iter().skip(1) {
+        let mut dist = (point - prev).magnitude();
+
+        while dist_acc + dist >= spacing && dist > 0.0 {
+            let new_point = prev + (point - prev) * ((spacing - dist_acc) / dist);
+            resampled.push(new_point);
+
+            prev = new_point;
+            dist = (point - prev).magnitude();
+            dist_acc = 0.0;
+        }
+
+        dist_acc += dist;
+        prev = point;
+    }
+
+    // Floating point errors may leave the last point out
+    if resampled.len() < n_points {
+        resampled.push(points[points.len() - 1]);
+    }
+    resampled.truncate(n_points);
+
+    resampled
+}
+
+/// Detects the corners of the resampled points with the "ShortStraw" algorithm, and merges corners which are nearly straight.
+/// Returns the indices of the corners, including the first and last point. For closed strokes the first and last point are only kept if they are corners
+fn detect_corners(resampled: &[na::Vector2<f64>], closed: bool) -> Vec<usize> {
+    let len = resampled.len();
+    let mut corners = vec![0];
+
+    if len > 2 * STRAW_WINDOW {
+        let straws = (STRAW_WINDOW..len - STRAW_WINDOW)
+            .map(|i| (resampled[i + STRAW_WINDOW] - resampled[i - STRAW_WINDOW]).magnitude())
+            .collect::<Vec<f64>>();
+
+        let mut sorted_straws = straws.clone();
+        sorted_straws.sort_by(|first, second| {
+            first
+                .partial_cmp(second)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let threshold = sorted_straws[sorted_straws.len() / 2] * STRAW_THRESHOLD_FACTOR;
+
+        // The corners are the local minimas of the runs of straws below the threshold
+        let mut i = 0;
+        while i < straws.len() {
+            if straws[i] < threshold {
+                let mut local_min = i;
+                while i < straws.len() && straws[i] < threshold {
+                    if straws[i] < straws[local_min] {
+                        local_min = i;
+                    }
+                    i += 1;
+                }
+                corners.push(local_min + STRAW_WINDOW);
+            }
+            i += 1;
+        }
+    }
+    corners.push(len - 1);
+
+    if closed {
+        // The ends meet, so they are the same corner
+        corners.pop();
+    }
+
+    // Corners closer together than the straw window are duplicates of the same corner
+    let segment_length_min = (resampled[1] - resampled[0]).magnitude() * STRAW_WINDOW as f64;
+
+    // Merge the nearly straight corners, the ends of open strokes are always kept
+    loop {
+        let n_corners = corners.len();
+        if n_corners < 3 {
+            break;
+        }
+
+        let straightest = (0..n_corners)
+            .filter(|&i| closed || (i != 0 && i != n_corners - 1))
+            .map(|i| {
+                let prev = resampled[corners[(i + n_corners - 1) % n_corners]];
+                let current = resampled[corners[i]];
+                let next = resampled[corners[(i + 1) % n_corners]];
+
+                if (current - prev).magnitude() < segment_length_min
+                    || (next - current).magnitude() < segment_length_min
+                {
+                    (i, 0.0)
+                } else {
+                    (i, turning_angle(current - prev, next - current))
+                }
+            })
+            .min_by(|first, second| {
+                first
+                    .1
+                    .partial_cmp(&second.1)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+
+        match straightest {
+            Some((i, angle)) if angle < CORNER_ANGLE_MIN => {
+                corners.remove(i);
+            }
+            _ => break,
+        }
+    }
+
+    corners
+}
+
+/// The angle the direction turns from the first to the second vector, between 0.0 and PI
+fn turning_angle(first: na::Vector2<f64>, second: na::Vector2<f64>) -> f64 {
+    if first.magnitude() <= 0.0 || second.magnitude() <= 0.0 {
+        return 0.0;
+    }
+
+    first.angle(&second)
+}
+
+fn dist_to_segment(point: na::Vector2<f64>, start: na::Vector2<f64>, end: na::Vector2<f64>) -> f64 {
+    let segment = end - start;
+    let length_squared = segment.magnitude_squared();
+    if length_squared <= 0.0 {
+        return (point - start).magnitude();
+    }
+
+    let t = ((point - start).dot(&segment) / length_squared).clamp(0.0, 1.0);
+
+    (point - (start + segment * t)).magnitude()
+}
+
+/// The mean distance of the points to the closest edge of the polygon
+fn mean_dist_to_polygon(points: &[na::Vector2<f64>], vertices: &[na::Vector2<f64>]) -> f64 {
+    let n_vertices = vertices.len();
+
+    points
+        .iter()
+        .map(|&point| {
+            (0..n_vertices)
+                .map(|i| dist_to_segment(point, vertices[i], vertices[(i + 1) % n_vertices]))
+                .fold(f64::MAX, f64::min)
+        })
+        .sum::<f64>()
+        / points.len() as f64
+}
+
+/// Maps the mean distance of the points to the fitted shape to a confidence, relative to the size of the shape
+fn fit_confidence(mean_dist: f64, size: f64) -> f64 {
+    if size <= 0.0 {
+        return 0.0;
+    }
+
+    (1.0 - mean_dist / (size * FIT_TOLERANCE)).clamp(0.0, 1.0)
+}
+
+fn recognize_line(resampled: &[na::Vector2<f64>]) -> Option<RecognizedShape> {
+    let line = curves::Line {
+        start: resampled[0],
+        end: resampled[resampled.len() - 1],
+    };
+    let length = (line.end - line.start).magnitude();
+
+    let mean_dist = resampled
+        .iter()
+        .map(|&point| dist_to_segment(point, line.start, line.end))
+        .sum::<f64>()
+        / resampled.len() as f64;
+
+    Some(RecognizedShape {
+        shape: Shape::Line(line),
+        arrowheads: ArrowheadOptions::default(),
+        confidence: fit_confidence(mean_dist, length),
+    })
+}
+
+/// Recognizes arrows drawn as the shaft followed by the head, where the head corners are all around the tip
+fn recognize_arrow(resampled: &[na::Vector2<f64>], corners: &[usize]) -> Option<RecognizedShape> {
+    if corners.len() < 4 {
+        return None;
+    }
+
+    let start = resampled[corners[0]];
+    let tip = resampled[corners[1]];
+    let shaft = tip - start;
+    let shaft_length = shaft.magnitude();
+    if shaft_length <= 0.0 {
+        return None;
+    }
+
+    let head_corners = corners[2..]
+        .iter()
+        .map(|&i| resampled[i])
+        .collect::<Vec<na::Vector2<f64>>>();
+    if head_corners
+        .iter()
+        .any(|&corner| (corner - tip).magnitude() > shaft_length * ARROWHEAD_SHAFT_RATIO_MAX)
+    {
+        return None;
+    }
+
+    // The head must have corners on both sides of the shaft
+    let sides = head_corners
+        .iter()
+        .map(|&corner| shaft.perp(&(corner - tip)))
+        .collect::<Vec<f64>>();
+    if !(sides.iter().any(|&side| side > 0.0) && sides.iter().any(|&side| side < 0.0)) {
+        return None;
+    }
+
+    let shaft_points = &resampled[corners[0]..=corners[1]];
+    let mean_dist = shaft_points
+        .iter()
+        .map(|&point| dist_to_segment(point, start, tip))
+        .sum::<f64>()
+        / shaft_points.len() as f64;
+
+    Some(RecognizedShape {
+        shape: Shape::Line(curves::Line { start, end: tip }),
+        arrowheads: ArrowheadOptions {
+            end: ArrowheadStyle::Open,
+            ..ArrowheadOptions::default()
+        },
+        confidence: fit_confidence(mean_dist, shaft_length),
+    })
+}
+
+/// Recognizes triangles and rectangles from closed strokes
+fn recognize_polygon(resampled: &[na::Vector2<f64>], corners: &[usize]) -> Option<RecognizedShape> {
+    let vertices = corners
+        .iter()
+        .map(|&i| resampled[i])
+        .collect::<Vec<na::Vector2<f64>>>();
+
+    match vertices.len() {
+        3 => {
+            let size = (0..3)
+                .map(|i| (vertices[(i + 1) % 3] - vertices[i]).magnitude())
+                .fold(0.0, f64::max);
+            let confidence = fit_confidence(mean_dist_to_polygon(resampled, &vertices), size);
+
+            Some(RecognizedShape {
+                shape: Shape::Polyline(shapes::Polyline {
+                    vertices,
+                    closed: true,
+                }),
+                arrowheads: ArrowheadOptions::default(),
+                confidence,
+            })
+        }
+        4 => {
+            // The rectangle is aligned to the average direction of the opposite edges
+            let first_edge = (vertices[1] - vertices[0]) - (vertices[3] - vertices[2]);
+            let angle = first_edge[1].atan2(first_edge[0]);
+            let center = vertices.iter().sum::<na::Vector2<f64>>() / 4.0;
+            let half_extents = na::vector![
+                ((vertices[1] - vertices[0]).magnitude() + (vertices[3] - vertices[2]).magnitude())
+                    * 0.25,
+                ((vertices[2] - vertices[1]).magnitude() + (vertices[0] - vertices[3]).magnitude())
+                    * 0.25
+            ];
+
+            let isometry = na::Isometry2::new(center, angle);
+            let rect_vertices = [
+                na::vector![-half_extents[0], -half_extents[1]],
+                na::vector![half_extents[0], -half_extents[1]],
+                na::vector![half_extents[0], half_extents[1]],
+                na::vector![-half_extents[0], half_extents[1]],
+            ]
+            .into_iter()
+            .map(|vertex| (isometry * na::Point2::from(vertex)).coords)
+            .collect::<Vec<na::Vector2<f64>>>();
+
+            let confidence = fit_confidence(
+                mean_dist_to_polygon(resampled, &rect_vertices),
+                half_extents.max() * 2.0,
+            );
+
+            Some(RecognizedShape {
+                shape: Shape::Rectangle(shapes::Rectangle {
+                    cuboid: p2d::shape::Cuboid::new(half_extents),
+                    transform: Transform::new_w_isometry(isometry),
+                }),
+                arrowheads: ArrowheadOptions::default(),
+                confidence,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Fits an ellipse aligned to the principal axes of the points
+fn recognize_ellipse(resampled: &[na::Vector2<f64>]) -> Option<RecognizedShape> {
+    let n_points = resampled.len() as f64;
+    let center = resampled.iter().sum::<na::Vector2<f64>>() / n_points;
+
+    let covariance = resampled
+        .iter()
+        .fold(na::Matrix2::<f64>::zeros(), |acc, &point| {
+            let diff = point - center;
+            acc + diff * diff.transpose()
+        })
+        / n_points;
+    let angle = 0.5 * (2.0 * covariance[(0, 1)]).atan2(covariance[(0, 0)] - covariance[(1, 1)]);
+
+    let isometry = na::Isometry2::new(center, angle);
+    let local_points = resampled
+        .iter()
+        .map(|&point| {
+            isometry
+                .inverse_transform_point(&na::Point2::from(point))
+                .coords
+        })
+        .collect::<Vec<na::Vector2<f64>>>();
+
+    // For points evenly spread around an ellipse, the mean squared coordinate along an axis is half its squared radius
+    let radii = (local_points
+        .iter()
+        .fold(na::Vector2::<f64>::zeros(), |acc, point| {
+            acc + point.component_mul(point)
+        })
+        * (2.0 / n_points))
+        .map(f64::sqrt);
+    if radii[0] <= 0.0 || radii[1] <= 0.0 {
+        return None;
+    }
+
+    // The radial error approximates the distance to the ellipse, relative to the radius
+    let mean_dist = local_points
+        .iter()
+        .map(|point| (point.component_div(&radii).magnitude() - 1.0).abs())
+        .sum::<f64>()
+        / n_points
+        * radii.mean();
+
+    Some(RecognizedShape {
+        shape: Shape::Ellipse(shapes::Ellipse {
+            radii,
+            transform: Transform::new_w_isometry(isometry),
+        }),
+        arrowheads: ArrowheadOptions::default(),
+        confidence: fit_confidence(mean_dist, radii.max() * 2.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The points along the path through the vertices, `n_points` per edge
+    fn points_along(vertices: &[na::Vector2<f64>], n_points: usize) -> Vec<na::Vector2<f64>> {
+        let mut points = vertices
+            .windows(2)
+            .flat_map(|edge| {
+                (0..n_points).map(move |i| edge[0].lerp(&edge[1], i as f64 / n_points as f64))
+            })
+            .collect::<Vec<na::Vector2<f64>>>();
+        points.extend(vertices.last());
+
+        points
+    }
+
+    #[test]
+    fn line_is_recognized() {
+        // Slightly wobbly, like drawn by hand
+        let points = (0..=40)
+            .map(|i| na::vector![f64::from(i) * 5.0, f64::from(i) * 2.5 + f64::from(i % 2)])
+            .collect::<Vec<na::Vector2<f64>>>();
+
+        let recognized = recognize(&points).unwrap();
+        assert!(recognized.confidence > ShapeRecognitionOptions::CONFIDENCE_THRESHOLD_DEFAULT);
+        match recognized.shape {
+            Shape::Line(line) => {
+                assert!((line.start - points[0]).magnitude() < 5.0);
+                assert!((line.end - points[40]).magnitude() < 5.0);
+            }
+            shape => panic!("recognized {:?} instead of a line", shape),
+        }
+    }
+
+    #[test]
+    fn rectangle_is_recognized() {
+        let points = points_along(
+            &[
+                na::vector![0.0, 0.0],
+                na::vector![200.0, 0.0],
+                na::vector![200.0, 100.0],
+                na::vector![0.0, 100.0],
+                na::vector![0.0, 5.0],
+            ],
+            20,
+        );
+
+        let recognized = recognize(&points).unwrap();
+        assert!(recognized.confidence > ShapeRecognitionOptions::CONFIDENCE_THRESHOLD_DEFAULT);
+        match recognized.shape {
+            Shape::Rectangle(rectangle) => {
+                let mut half_extents = rectangle.cuboid.half_extents;
+                // The edge the rectangle is aligned to might be either of them
+                if half_extents[0] < half_extents[1] {
+                    half_extents = half_extents.yx();
+                }
+                assert!((half_extents - na::vector![100.0, 50.0]).magnitude() < 10.0);
+            }
+            shape => panic!("recognized {:?} instead of a rectangle", shape),
+        }
+    }
+
+    #[test]
+    fn ellipse_is_recognized() {
+        let center = na::vector![100.0, 100.0];
+        let points = (0..=100)
+            .map(|i| {
+                let angle = f64::from(i) / 100.0 * std::f64::consts::TAU;
+                center + na::vector![80.0 * angle.cos(), 40.0 * angle.sin()]
+            })
+            .collect::<Vec<na::Vector2<f64>>>();
+
+        let recognized = recognize(&points).unwrap();
+        assert!(recognized.confidence > ShapeRecognitionOptions::CONFIDENCE_THRESHOLD_DEFAULT);
+        match recognized.shape {
+            Shape::Ellipse(ellipse) => {
+                let mut radii = ellipse.radii;
+                if radii[0] < radii[1] {
+                    radii = radii.yx();
+                }
+                // The radii are estimated from the points resampled along the stroke, which are denser around the flat sides
+                let expected = na::vector![80.0, 40.0];
+                assert!((radii - expected).component_div(&expected).amax() < 0.1);
+                assert!(
+                    (ellipse
+                        .transform
+                        .transform_point(na::point![0.0, 0.0])
+                        .coords
+                        - center)
+                        .magnitude()
+                        < 5.0
+                );
+            }
+            shape => panic!("recognized {:?} instead of an ellipse", shape),
+        }
+    }
+
+    #[test]
+    fn scribbles_are_rejected() {
+        let zigzag = (0..=20)
+            .map(|i| na::vector![f64::from(i) * 10.0, f64::from(i % 2) * 60.0])
+            .collect::<Vec<na::Vector2<f64>>>();
+        assert!(recognize(&zigzag).map_or(true, |recognized| {
+            recognized.confidence < ShapeRecognitionOptions::CONFIDENCE_THRESHOLD_DEFAULT
+        }));
+
+        // Too short to be recognized at all
+        assert!(recognize(&[na::vector![0.0, 0.0], na::vector![2.0, 2.0]]).is_none());
+        assert!(recognize(&[na::vector![0.0, 0.0]]).is_none());
+    }
+}
@@ -1,28 +1,77 @@
+#[cfg(feature = "ui")]
 use std::ops::Deref;
 
 use anyhow::Context;
-use gtk4::{gdk, gio, glib, gsk, prelude::*, Native, Snapshot, Widget};
+#[cfg(feature = "ui")]
+use glib::translate::ToGlibPtr;
+#[cfg(feature = "ui")]
+use gtk4::{gdk, gio, glib, graphene, gsk, prelude::*, Native, Snapshot, Widget};
 use p2d::bounding_volume::AABB;
 
 use crate::compose;
+use crate::compose::color::Color;
 use crate::compose::geometry::AABBHelpers;
 
-#[derive(Debug, Clone, Copy, glib::Enum)]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
 #[repr(u32)]
-#[enum_type(name = "RendererBackend")]
+#[cfg_attr(feature = "ui", enum_type(name = "RendererBackend"))]
 pub enum RendererBackend {
+    /// Only available with the `ui` feature
+    #[cfg(feature = "ui")]
     #[enum_value(name = "Librsvg", nick = "librsvg")]
     Librsvg,
-    #[enum_value(name = "Resvg", nick = "resvg")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Resvg", nick = "resvg"))]
     Resvg,
 }
 
+/// The strokes which are tessellated and drawn with rendernodes by the GPU, instead of being rendered to images through the renderer backend.
+/// Much faster for strokes made of many small shapes, like textured strokes. Strokes which can't be tessellated are rendered to images nonetheless
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TessellatedStrokes {
+    pub marker: bool,
+    pub solid: bool,
+    pub textured: bool,
+}
+
+impl Default for TessellatedStrokes {
+    fn default() -> Self {
+        Self {
+            marker: false,
+            solid: false,
+            textured: true,
+        }
+    }
+}
+
+/// The level of detail the strokes are rendered with. Zoomed far out, strokes made of many small shapes (like the dots of textured strokes)
+/// are rendered as simpler shapes approximating them, which look the same at that size but are much faster to render
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetailLevel {
+    Full,
+    Simplified,
+}
+
+impl DetailLevel {
+    /// Below this zoom the strokes are rendered simplified
+    pub const SIMPLIFIED_ZOOM_THRESHOLD: f64 = 0.4;
+
+    pub fn for_zoom(zoom: f64) -> Self {
+        if zoom < Self::SIMPLIFIED_ZOOM_THRESHOLD {
+            Self::Simplified
+        } else {
+            Self::Full
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum ImageMemoryFormat {
     R8g8b8a8Premultiplied,
     B8g8r8a8Premultiplied,
 }
 
+#[cfg(feature = "ui")]
 impl TryFrom<gdk::MemoryFormat> for ImageMemoryFormat {
     type Error = anyhow::Error;
     fn try_from(gdk_memory_format: gdk::MemoryFormat) -> Result<Self, Self::Error> {
@@ -38,6 +87,7 @@ impl TryFrom<gdk::MemoryFormat> for ImageMemoryFormat {
 }
 
 /// From impl ImageMemoryFormat into gdk::MemoryFormat
+#[cfg(feature = "ui")]
 impl From<ImageMemoryFormat> for gdk::MemoryFormat {
     fn from(format: ImageMemoryFormat) -> Self {
         match format {
@@ -86,6 +136,39 @@ impl Image {
             }
         }
     }
+
+    /// The color of the pixel at the position in the coordinate space of the sheet. None if the position is outside of the image
+    pub fn color_at(&self, pos: na::Vector2<f64>) -> Option<Color> {
+        if !self.bounds.contains_local_point(&na::Point2::from(pos))
+            || self.pixel_width == 0
+            || self.pixel_height == 0
+        {
+            return None;
+        }
+
+        let rel_pos = (pos - self.bounds.mins.coords).component_div(&self.bounds.extents());
+        let x = ((rel_pos[0] * f64::from(self.pixel_width)) as u32).min(self.pixel_width - 1);
+        let y = ((rel_pos[1] * f64::from(self.pixel_height)) as u32).min(self.pixel_height - 1);
+        let i = ((y * self.pixel_width + x) * 4) as usize;
+        let pixel = self.data.get(i..i + 4)?;
+
+        let (r, g, b, a) = match self.memory_format {
+            ImageMemoryFormat::R8g8b8a8Premultiplied => (pixel[0], pixel[1], pixel[2], pixel[3]),
+            ImageMemoryFormat::B8g8r8a8Premultiplied => (pixel[2], pixel[1], pixel[0], pixel[3]),
+        };
+        if a == 0 {
+            return Some(Color::TRANSPARENT);
+        }
+
+        // The channels are premultiplied with the alpha
+        let a = f64::from(a) / 255.0;
+        Some(Color::new(
+            (f64::from(r) / 255.0 / a).min(1.0),
+            (f64::from(g) / 255.0 / a).min(1.0),
+            (f64::from(b) / 255.0 / a).min(1.0),
+            a,
+        ))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -103,6 +186,7 @@ pub struct Renderer {
     pub max_tile_size: na::Vector2<f64>,
     // the maximum size for svgs are joined together for rendering
     pub max_join_size: na::Vector2<f64>,
+    pub tessellated_strokes: TessellatedStrokes,
 }
 
 impl Default for Renderer {
@@ -120,11 +204,15 @@ impl Default for Renderer {
         };
 
         Self {
+            #[cfg(feature = "ui")]
             backend: RendererBackend::Librsvg,
+            #[cfg(not(feature = "ui"))]
+            backend: RendererBackend::Resvg,
             usvg_options,
             usvg_xml_options,
             max_tile_size: na::vector![1024.0, 1024.0],
             max_join_size: na::vector![1024.0, 1024.0],
+            tessellated_strokes: TessellatedStrokes::default(),
         }
     }
 }
@@ -143,11 +231,13 @@ impl Renderer {
         }
 
         match self.backend {
+            #[cfg(feature = "ui")]
             RendererBackend::Librsvg => self.gen_images_librsvg(zoom, svgs, bounds),
             RendererBackend::Resvg => self.gen_images_resvg(zoom, svgs, bounds),
         }
     }
 
+    #[cfg(feature = "ui")]
     fn gen_images_librsvg(
         &self,
         zoom: f64,
@@ -382,6 +472,7 @@ pub fn image_into_encoded_bytes(
     Ok(bytes_buf)
 }
 
+#[cfg(feature = "ui")]
 pub fn image_to_memtexture(image: &Image) -> Result<gdk::MemoryTexture, anyhow::Error> {
     assert_image(image)?;
 
@@ -396,6 +487,7 @@ pub fn image_to_memtexture(image: &Image) -> Result<gdk::MemoryTexture, anyhow::
     ))
 }
 
+#[cfg(feature = "ui")]
 pub fn image_to_rendernode(image: &Image, zoom: f64) -> Result<gsk::RenderNode, anyhow::Error> {
     assert_image(image)?;
 
@@ -409,6 +501,7 @@ pub fn image_to_rendernode(image: &Image, zoom: f64) -> Result<gsk::RenderNode,
 }
 
 /// images to rendernode. Returns Ok(None) when no images in slice
+#[cfg(feature = "ui")]
 pub fn images_to_rendernode(
     images: &[Image],
     zoom: f64,
@@ -423,6 +516,7 @@ pub fn images_to_rendernode(
     Ok(snapshot.to_node())
 }
 
+#[cfg(feature = "ui")]
 pub fn append_images_to_rendernode(
     rendernode: Option<&gsk::RenderNode>,
     images: &[Image],
@@ -446,6 +540,143 @@ pub fn append_images_to_rendernode(
     Ok(snapshot.to_node())
 }
 
+/// A rendernode composed incrementally from appended nodes, like the renderings of the last elements of a stroke while it is drawn.
+/// The appended nodes are collapsed into containers of `CHUNK_SIZE` nodes, and these again into larger containers,
+/// so composing the rendernode after appending a node takes the same time regardless of how many nodes were appended before
+#[cfg(feature = "ui")]
+#[derive(Debug, Clone)]
+pub struct IncrementalNode {
+    /// The levels of nodes, from the most recently appended nodes up to the containers of the oldest. Every level holds less than `CHUNK_SIZE` nodes
+    levels: Vec<Vec<gsk::RenderNode>>,
+    /// The opacity the nodes are composited with, so overlapping nodes don't get darker
+    opacity: f64,
+    /// The zoom the nodes are rendered at
+    zoom: f64,
+    /// The last composed rendernode
+    composed: Option<gsk::RenderNode>,
+}
+
+#[cfg(feature = "ui")]
+impl IncrementalNode {
+    /// The number of nodes collapsed into a container
+    pub const CHUNK_SIZE: usize = 32;
+
+    pub fn new(opacity: f64, zoom: f64) -> Self {
+        Self {
+            levels: vec![],
+            opacity,
+            zoom,
+            composed: None,
+        }
+    }
+
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    /// The number of nodes the rendernode is composed of, which stays small
+    pub fn n_composed_nodes(&self) -> usize {
+        self.levels.iter().map(|level| level.len()).sum()
+    }
+
+    /// Whether the rendernode is the one composed last, meaning it was not replaced by another rendering in the meantime
+    pub fn is_composed(&self, rendernode: Option<&gsk::RenderNode>) -> bool {
+        match (self.composed.as_ref(), rendernode) {
+            (Some(composed), Some(rendernode)) => {
+                let composed_ptr: *mut gsk::ffi::GskRenderNode = composed.to_glib_none().0;
+                let ptr: *mut gsk::ffi::GskRenderNode = rendernode.to_glib_none().0;
+
+                composed_ptr == ptr
+            }
+            _ => false,
+        }
+    }
+
+    pub fn append(&mut self, node: gsk::RenderNode) {
+        let mut node = node;
+        let mut level = 0;
+
+        loop {
+            if self.levels.len() <= level {
+                self.levels.push(vec![]);
+            }
+            self.levels[level].push(node);
+
+            if self.levels[level].len() < Self::CHUNK_SIZE {
+                break;
+            }
+
+            // Collapses the full level into a single container on the next level
+            let snapshot = Snapshot::new();
+            for node in self.levels[level].drain(..) {
+                snapshot.append_node(&node);
+            }
+            node = match snapshot.to_node() {
+                Some(node) => node,
+                None => break,
+            };
+            level += 1;
+        }
+    }
+
+    /// Composes the appended nodes into the rendernode. Returns None when there are none
+    pub fn compose(&mut self) -> Option<gsk::RenderNode> {
+        let snapshot = Snapshot::new();
+
+        let translucent = self.opacity < 1.0;
+        if translucent {
+            snapshot.push_opacity(self.opacity);
+        }
+
+        // The higher levels hold the older nodes
+        for node in self.levels.iter().rev().flatten() {
+            snapshot.append_node(node);
+        }
+
+        if translucent {
+            snapshot.pop();
+        }
+
+        self.composed = snapshot.to_node();
+        self.composed.clone()
+    }
+}
+
+/// The color matrix and offset to display the sheet with inverted ink, e.g. for dark themes. Black ink becomes white and white paper black,
+/// while the hues are kept. It is only applied when drawing to the screen, the strokes and exports keep their colors
+#[cfg(feature = "ui")]
+pub fn invert_ink_color_matrix() -> (graphene::Matrix, graphene::Vec4) {
+    let (matrix, offset) = invert_ink_color_matrix_values();
+
+    (
+        graphene::Matrix::from_float(matrix),
+        graphene::Vec4::new(offset[0], offset[1], offset[2], offset[3]),
+    )
+}
+
+/// The values of the inverted ink color matrix in row major order, and the offset
+#[cfg(any(feature = "ui", test))]
+fn invert_ink_color_matrix_values() -> ([f32; 16], [f32; 4]) {
+    // Luminance preserving hue rotation by 180 degrees, as in the css `hue-rotate()` filter
+    const HUE_ROTATE_180: [[f32; 3]; 3] = [
+        [-0.574, 1.430, 0.144],
+        [0.426, 0.430, 0.144],
+        [0.426, 1.430, -0.856],
+    ];
+
+    // Inverting and rotating the hue gives `1 - hue_rotate * color`. gsk multiplies with the transposed matrix
+    let mut matrix = [0.0; 16];
+    for (row, values) in HUE_ROTATE_180.iter().enumerate() {
+        for (col, value) in values.iter().enumerate() {
+            matrix[col * 4 + row] = -value;
+        }
+    }
+    matrix[15] = 1.0;
+
+    (matrix, [1.0, 1.0, 1.0, 0.0])
+}
+
+#[cfg(feature = "ui")]
 pub fn rendernode_to_texture(
     active_widget: &Widget,
     node: &gsk::RenderNode,
@@ -468,6 +699,7 @@ pub fn rendernode_to_texture(
     Ok(None)
 }
 
+#[cfg(feature = "ui")]
 pub fn draw_svgs_to_cairo_context(
     zoom: f64,
     svgs: &[Svg],
@@ -514,6 +746,184 @@ pub fn draw_svgs_to_cairo_context(
     Ok(())
 }
 
+/// The resolution of the images the svgs are rendered to without the `ui` feature, relative to the device units of the surface.
+/// The device units of vector surfaces like PDFs are points, which would be too coarse
+#[cfg(all(feature = "cairo", not(feature = "ui")))]
+const SVG_IMAGE_OVERSAMPLING: f64 = 2.0;
+
+/// Without the `ui` feature the svgs are rendered with resvg into images at the resolution of the surface, which are then painted onto the context
+#[cfg(all(feature = "cairo", not(feature = "ui")))]
+pub fn draw_svgs_to_cairo_context(
+    zoom: f64,
+    svgs: &[Svg],
+    mut bounds: AABB,
+    cx: &cairo::Context,
+) -> Result<(), anyhow::Error> {
+    bounds.ensure_valid();
+    assert_bounds(bounds)?;
+
+    // The pixels of the images per unit of the sheet
+    let (device_scale_x, device_scale_y) = cx.user_to_device_distance(zoom, zoom)?;
+    let image_zoom = device_scale_x.abs().max(device_scale_y.abs()) * SVG_IMAGE_OVERSAMPLING;
+
+    for svg in svgs {
+        let svg_data = compose::wrap_svg_root(
+            svg.svg_data.as_str(),
+            Some(svg.bounds),
+            Some(svg.bounds),
+            false,
+        );
+        let svg_tree =
+            usvg::Tree::from_data(svg_data.as_bytes(), &usvg::Options::default().to_ref())?;
+
+        let width_scaled = (svg.bounds.extents()[0] * image_zoom).ceil() as u32;
+        let height_scaled = (svg.bounds.extents()[1] * image_zoom).ceil() as u32;
+        if width_scaled == 0 || height_scaled == 0 {
+            continue;
+        }
+
+        let mut pixmap = tiny_skia::Pixmap::new(width_scaled, height_scaled).ok_or_else(|| {
+            anyhow::anyhow!("tiny_skia::Pixmap::new() failed in draw_svgs_to_cairo_context()")
+        })?;
+        resvg::render(
+            &svg_tree,
+            usvg::FitTo::Original,
+            tiny_skia::Transform::from_scale(image_zoom as f32, image_zoom as f32),
+            pixmap.as_mut(),
+        )
+        .ok_or_else(|| anyhow::anyhow!("resvg::render failed in draw_svgs_to_cairo_context()"))?;
+
+        // tiny_skia pixmaps are RGBA, cairo image surfaces BGRA
+        let mut data = pixmap.take();
+        for pixel in data.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        let surface = cairo::ImageSurface::create_for_data(
+            data,
+            cairo::Format::ARgb32,
+            width_scaled as i32,
+            height_scaled as i32,
+            (width_scaled * 4) as i32,
+        )?;
+
+        cx.save()?;
+        cx.scale(zoom, zoom);
+        cx.translate(svg.bounds.mins[0], svg.bounds.mins[1]);
+        cx.scale(1.0 / image_zoom, 1.0 / image_zoom);
+        cx.set_source_surface(&surface, 0.0, 0.0)?;
+        cx.paint()?;
+        cx.restore()?;
+    }
+
+    Ok(())
+}
+
+/// Draws onto a cairo svg surface of the size, which converts text glyphs to paths.
+/// Returns the svg data without xml header, with the ids prefixed, so that glyphs don't collide with the glyphs of other svgs
+#[cfg(feature = "cairo")]
+pub fn cairo_drawing_to_svg_data<F>(
+    size: na::Vector2<f64>,
+    draw: F,
+) -> Result<String, anyhow::Error>
+where
+    F: FnOnce(&cairo::Context) -> Result<(), anyhow::Error>,
+{
+    let svg_stream: Vec<u8> = vec![];
+    let surface = cairo::SvgSurface::for_stream(size[0], size[1], svg_stream).map_err(|e| {
+        anyhow::anyhow!(
+            "create SvgSurface with dimensions ({}, {}) failed in cairo_drawing_to_svg_data() with Err {}",
+            size[0],
+            size[1],
+            e
+        )
+    })?;
+    {
+        let cx = cairo::Context::new(&surface)
+            .context("new cairo::Context failed in cairo_drawing_to_svg_data()")?;
+        draw(&cx)?;
+    }
+    let svg_data = surface
+        .finish_output_stream()
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "finish_output_stream() failed in cairo_drawing_to_svg_data() with Err {}",
+                e
+            )
+        })?
+        .downcast::<Vec<u8>>()
+        .map_err(|_e| anyhow::anyhow!("downcast() failed in cairo_drawing_to_svg_data()"))?;
+    let svg_data = String::from_utf8(*svg_data)?;
+
+    let rtree = usvg::Tree::from_str(&svg_data, &usvg::Options::default().to_ref())?;
+    let xml_options = usvg::XmlOptions {
+        id_prefix: Some(compose::random_id_prefix()),
+        ..usvg::XmlOptions::default()
+    };
+
+    Ok(compose::remove_xml_header(&rtree.to_string(&xml_options)))
+}
+
+/// Draws onto a cairo image surface covering the bounds, without the svg roundtrip.
+/// The context is scaled by the zoom and translated, so that drawing happens in the coordinate space of the sheet
+#[cfg(feature = "cairo")]
+pub fn cairo_drawing_to_image<F>(bounds: AABB, zoom: f64, draw: F) -> Result<Image, anyhow::Error>
+where
+    F: FnOnce(&cairo::Context) -> Result<(), anyhow::Error>,
+{
+    let mut bounds = bounds;
+    bounds.ensure_valid();
+    assert_bounds(bounds)?;
+    let bounds = bounds.ceil();
+
+    let width_scaled = (bounds.extents()[0] * zoom).round() as u32;
+    let height_scaled = (bounds.extents()[1] * zoom).round() as u32;
+
+    let mut surface = cairo::ImageSurface::create(
+        cairo::Format::ARgb32,
+        width_scaled as i32,
+        height_scaled as i32,
+    )
+    .map_err(|e| {
+        anyhow::anyhow!(
+            "create ImageSurface with dimensions ({}, {}) failed in cairo_drawing_to_image() with Err {}",
+            width_scaled,
+            height_scaled,
+            e
+        )
+    })?;
+
+    // Context in new scope, else accessing the surface data fails with a borrow error
+    {
+        let cx = cairo::Context::new(&surface)
+            .context("new cairo::Context failed in cairo_drawing_to_image()")?;
+        cx.scale(zoom, zoom);
+        cx.translate(-bounds.mins[0], -bounds.mins[1]);
+
+        draw(&cx)?;
+    }
+    // Surface needs to be flushed before accessing its data
+    surface.flush();
+
+    let data = surface
+        .data()
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "accessing imagesurface data failed in cairo_drawing_to_image() with Err {}",
+                e
+            )
+        })?
+        .to_vec();
+
+    Ok(Image {
+        data,
+        bounds,
+        pixel_width: width_scaled,
+        pixel_height: height_scaled,
+        memory_format: ImageMemoryFormat::B8g8r8a8Premultiplied,
+    })
+}
+
+#[cfg(feature = "ui")]
 fn _gen_caironode_librsvg(zoom: f64, svg: &Svg) -> Result<gsk::CairoNode, anyhow::Error> {
     if svg.bounds.extents()[0] < 0.0 || svg.bounds.extents()[1] < 0.0 {
         return Err(anyhow::anyhow!(
@@ -558,3 +968,101 @@ pub fn assert_image(image: &Image) -> Result<(), anyhow::Error> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Remaps the rgba color like gsk applies the color matrix, with the color as row vector
+    fn invert_ink(color: [f32; 4]) -> [f32; 4] {
+        let (matrix, offset) = invert_ink_color_matrix_values();
+
+        let mut inverted = offset;
+        for (col, value) in inverted.iter_mut().enumerate() {
+            for (row, component) in color.iter().enumerate() {
+                *value += component * matrix[row * 4 + col];
+            }
+        }
+        inverted
+    }
+
+    fn luminance(color: [f32; 4]) -> f32 {
+        0.213 * color[0] + 0.715 * color[1] + 0.072 * color[2]
+    }
+
+    fn assert_near(first: [f32; 4], second: [f32; 4]) {
+        assert!(
+            first
+                .iter()
+                .zip(second.iter())
+                .all(|(first, second)| (first - second).abs() < 1e-3),
+            "{:?} != {:?}",
+            first,
+            second
+        );
+    }
+
+    #[test]
+    fn inverted_ink_swaps_black_and_white() {
+        assert_near(invert_ink([0.0, 0.0, 0.0, 1.0]), [1.0, 1.0, 1.0, 1.0]);
+        assert_near(invert_ink([1.0, 1.0, 1.0, 1.0]), [0.0, 0.0, 0.0, 1.0]);
+        assert_near(invert_ink([0.5, 0.5, 0.5, 1.0]), [0.5, 0.5, 0.5, 1.0]);
+        // The alpha is kept
+        assert_near(invert_ink([0.0, 0.0, 0.0, 0.3]), [1.0, 1.0, 1.0, 0.3]);
+    }
+
+    #[test]
+    fn inverted_ink_keeps_the_hues() {
+        for color in [
+            [1.0, 0.0, 0.0, 1.0],
+            [0.0, 0.6, 0.0, 1.0],
+            [0.0, 0.0, 0.8, 1.0],
+            [0.2, 0.1, 0.5, 1.0],
+        ] {
+            let inverted = invert_ink(color);
+
+            // The dominant channel stays the same
+            let dominant = |color: [f32; 4]| {
+                (0..3)
+                    .max_by(|&first, &second| color[first].partial_cmp(&color[second]).unwrap())
+                    .unwrap()
+            };
+            assert_eq!(dominant(inverted), dominant(color));
+            // Dark colors become light and the other way round
+            assert!((luminance(inverted) - (1.0 - luminance(color))).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn image_color_at_unpremultiplies_the_pixel() {
+        // Two pixels side by side, red with half alpha and transparent
+        let image = Image {
+            data: vec![0, 0, 128, 128, 0, 0, 0, 0],
+            bounds: AABB::new(na::point![10.0, 10.0], na::point![30.0, 20.0]),
+            pixel_width: 2,
+            pixel_height: 1,
+            memory_format: ImageMemoryFormat::B8g8r8a8Premultiplied,
+        };
+
+        let color = image.color_at(na::vector![15.0, 15.0]).unwrap();
+        assert_eq!((color.r, color.g, color.b), (1.0, 0.0, 0.0));
+        assert!((color.a - 128.0 / 255.0).abs() < 1e-9);
+        assert_eq!(
+            image.color_at(na::vector![25.0, 15.0]),
+            Some(Color::TRANSPARENT)
+        );
+        // The right and bottom edges still hit the last pixels
+        assert_eq!(
+            image.color_at(na::vector![30.0, 20.0]),
+            Some(Color::TRANSPARENT)
+        );
+        assert_eq!(image.color_at(na::vector![5.0, 15.0]), None);
+
+        let image = Image {
+            memory_format: ImageMemoryFormat::R8g8b8a8Premultiplied,
+            ..image
+        };
+        let color = image.color_at(na::vector![15.0, 15.0]).unwrap();
+        assert_eq!((color.r, color.g, color.b), (0.0, 0.0, 1.0));
+    }
+}
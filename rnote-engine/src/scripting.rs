@@ -0,0 +1,538 @@
+//! Scripts automating edits of the document, e.g. numbering the pages or drawing the grid of a calendar. They are written in [Rhai](https://rhai.rs).
+//! Scripts are sandboxed: they can't access files or load modules, and are stopped when they exceed the limits of the options.
+//! The changes of a script are applied when it has run successfully, and are undone at once.
+//!
+//! Scripts have this api:
+//! - `strokes()`: the strokes of the document as rendered, `selection()`: the selected strokes
+//! - `pages()`: the bounds of the pages, as maps with `x`, `y`, `width` and `height`
+//! - `rgb(r, g, b)` and `rgba(r, g, b, a)`: a color with components between 0.0 and 1.0
+//! - `add_line(x1, y1, x2, y2)`, `add_rectangle(x, y, width, height)`, `add_ellipse(x, y, radius_x, radius_y)` and `add_text(text, x, y)`: insert a stroke and return it
+//! - on strokes: the properties `kind` and `bounds`, and the methods `recolor(color)`, `set_width(width)`, `translate(x, y)` and `trash()`
+//! - `print()` and `debug()`: write into the log of the output
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::compose::arrowhead::ArrowheadOptions;
+use crate::compose::color::Color;
+use crate::compose::curves;
+use crate::compose::shapes;
+use crate::compose::smooth::SmoothOptions;
+use crate::compose::transformable::{Transform, Transformable};
+use crate::drawbehaviour::DrawBehaviour;
+use crate::sheet::Sheet;
+use crate::strokes::shapestroke::{Shape, ShapeStroke};
+use crate::strokes::strokestyle::{StrokeOptionsModification, StrokeStyle};
+use crate::strokes::textstroke::{TextStroke, TextStyle};
+use crate::strokesstate::StrokeKey;
+
+use p2d::bounding_volume::AABB;
+use rhai::{Dynamic, Engine, EvalAltResult, Map};
+
+/// The options scripts are run with
+#[derive(Debug, Clone)]
+pub struct ScriptOptions {
+    /// The options of the shapes inserted by the script
+    pub shape_options: SmoothOptions,
+    /// The style of the texts inserted by the script
+    pub text_style: TextStyle,
+    /// The operations after which the script is stopped, so endless loops can't block the app
+    pub max_operations: u64,
+}
+
+impl Default for ScriptOptions {
+    fn default() -> Self {
+        Self {
+            shape_options: SmoothOptions::default(),
+            text_style: TextStyle::default(),
+            max_operations: Self::MAX_OPERATIONS_DEFAULT,
+        }
+    }
+}
+
+impl ScriptOptions {
+    /// The default max operations
+    pub const MAX_OPERATIONS_DEFAULT: u64 = 1_000_000;
+    /// The min max operations
+    pub const MAX_OPERATIONS_MIN: u64 = 1_000;
+    /// The max max operations
+    pub const MAX_OPERATIONS_MAX: u64 = 100_000_000;
+
+    /// The max nesting of function calls
+    const MAX_CALL_LEVELS: usize = 64;
+    /// The max length of strings, arrays and maps, and the max number of strokes a script inserts
+    const MAX_SIZE: usize = 100_000;
+}
+
+/// The output of a script which ran successfully
+#[derive(Debug, Clone, Default)]
+pub struct ScriptOutput {
+    /// The inserted, modified and trashed strokes
+    pub changed_keys: Vec<StrokeKey>,
+    /// The lines printed by the script
+    pub log: Vec<String>,
+}
+
+/// A stroke in scripts, as index into the strokes of the script state
+#[derive(Debug, Clone, Copy)]
+struct ScriptStroke(usize);
+
+/// A stroke as seen by the script
+#[derive(Debug, Clone)]
+struct ScriptStrokeEntry {
+    /// None for strokes inserted by the script
+    key: Option<StrokeKey>,
+    kind: &'static str,
+    /// The bounds when the script started, moved with the translations of the script
+    bounds: AABB,
+}
+
+/// The changes of the script, applied to the sheet in order when it has run successfully
+#[derive(Debug, Clone)]
+enum ScriptCommand {
+    Insert(ScriptStroke, Box<StrokeStyle>),
+    Recolor(ScriptStroke, Color),
+    SetWidth(ScriptStroke, f64),
+    Translate(ScriptStroke, na::Vector2<f64>),
+    Trash(ScriptStroke),
+}
+
+#[derive(Debug)]
+struct ScriptState {
+    strokes: Vec<ScriptStrokeEntry>,
+    n_selected: usize,
+    pages: Vec<AABB>,
+    options: ScriptOptions,
+    commands: Vec<ScriptCommand>,
+    log: Vec<String>,
+}
+
+impl ScriptState {
+    fn new(sheet: &Sheet, options: ScriptOptions) -> Self {
+        let strokes_state = &sheet.strokes_state;
+        let entries = |keys: Vec<StrokeKey>| {
+            keys.into_iter()
+                .filter_map(|key| {
+                    let stroke = strokes_state.get_stroke_ref(key)?;
+
+                    Some(ScriptStrokeEntry {
+                        key: Some(key),
+                        kind: stroke_kind(stroke),
+                        bounds: stroke.bounds(),
+                    })
+                })
+                .collect::<Vec<ScriptStrokeEntry>>()
+        };
+        let mut strokes = entries(strokes_state.keys_as_rendered());
+        let selection = entries(strokes_state.selection_keys_as_rendered());
+        let n_selected = selection.len();
+        // The selection is drawn on top, and is the range at the end
+        strokes.extend(selection);
+
+        Self {
+            strokes,
+            n_selected,
+            pages: sheet.pages(),
+            options,
+            commands: vec![],
+            log: vec![],
+        }
+    }
+
+    fn insert(&mut self, stroke: StrokeStyle) -> Result<ScriptStroke, Box<EvalAltResult>> {
+        if self.commands.len() >= ScriptOptions::MAX_SIZE {
+            return Err("the script exceeded the max number of changes".into());
+        }
+        let script_stroke = ScriptStroke(self.strokes.len());
+
+        self.strokes.push(ScriptStrokeEntry {
+            key: None,
+            kind: stroke_kind(&stroke),
+            bounds: stroke.bounds(),
+        });
+        self.commands
+            .push(ScriptCommand::Insert(script_stroke, Box::new(stroke)));
+
+        Ok(script_stroke)
+    }
+
+    fn entry(&self, stroke: ScriptStroke) -> Result<&ScriptStrokeEntry, Box<EvalAltResult>> {
+        self.strokes
+            .get(stroke.0)
+            .ok_or_else(|| "the stroke doesn't exist".into())
+    }
+}
+
+type SharedState = Rc<RefCell<ScriptState>>;
+
+impl Sheet {
+    /// Runs the script on the sheet. When it fails, the sheet is left as it is.
+    /// The changes are undone as one history entry. The output holds the keys of the changed strokes, to regenerate their rendering
+    pub fn run_script(
+        &mut self,
+        script: &str,
+        options: ScriptOptions,
+    ) -> Result<ScriptOutput, anyhow::Error> {
+        let state = Rc::new(RefCell::new(ScriptState::new(self, options)));
+
+        script_engine(&state)
+            .run(script)
+            .map_err(|e| anyhow::anyhow!("running the script failed, {}", e))?;
+
+        // The engine, which holds the other references, is dropped after running
+        let state = Rc::try_unwrap(state)
+            .map_err(|_| anyhow::anyhow!("the state of the script is still referenced"))?
+            .into_inner();
+
+        Ok(ScriptOutput {
+            changed_keys: self.apply_script_commands(state.strokes, state.commands),
+            log: state.log,
+        })
+    }
+
+    fn apply_script_commands(
+        &mut self,
+        strokes: Vec<ScriptStrokeEntry>,
+        commands: Vec<ScriptCommand>,
+    ) -> Vec<StrokeKey> {
+        let mut keys = strokes
+            .into_iter()
+            .map(|entry| entry.key)
+            .collect::<Vec<Option<StrokeKey>>>();
+        let mut changed_keys = vec![];
+
+        // The changes of the script are undone at once
+        self.strokes_state.begin_history_group();
+        for command in commands {
+            let changed_key = match command {
+                ScriptCommand::Insert(stroke, new_stroke) => {
+                    let key = self.strokes_state.insert_stroke(*new_stroke);
+                    keys[stroke.0] = Some(key);
+                    Some(key)
+                }
+                ScriptCommand::Recolor(stroke, color) => keys[stroke.0].inspect(|&key| {
+                    self.strokes_state.modify_stroke_options(
+                        &[key],
+                        &StrokeOptionsModification {
+                            stroke_color: Some(color),
+                            ..StrokeOptionsModification::default()
+                        },
+                    );
+                }),
+                ScriptCommand::SetWidth(stroke, width) => keys[stroke.0].inspect(|&key| {
+                    self.strokes_state.modify_stroke_options(
+                        &[key],
+                        &StrokeOptionsModification {
+                            width: Some(width),
+                            ..StrokeOptionsModification::default()
+                        },
+                    );
+                }),
+                ScriptCommand::Translate(stroke, offset) => keys[stroke.0].and_then(|key| {
+                    let mut translated = self.strokes_state.get_stroke_ref(key)?.clone();
+                    translated.translate(offset);
+
+                    self.strokes_state.replace_stroke(key, translated)?;
+                    Some(key)
+                }),
+                ScriptCommand::Trash(stroke) => keys[stroke.0].inspect(|&key| {
+                    self.strokes_state.set_trashed(key, true);
+                }),
+            };
+
+            if let Some(key) = changed_key {
+                if !changed_keys.contains(&key) {
+                    changed_keys.push(key);
+                }
+            }
+        }
+        self.strokes_state.end_history_group();
+
+        changed_keys
+    }
+}
+
+fn stroke_kind(stroke: &StrokeStyle) -> &'static str {
+    match stroke {
+        StrokeStyle::BrushStroke(_) => "brushstroke",
+        StrokeStyle::ShapeStroke(_) => "shapestroke",
+        StrokeStyle::VectorImage(_) => "vectorimage",
+        StrokeStyle::BitmapImage(_) => "bitmapimage",
+        StrokeStyle::TextStroke(_) => "textstroke",
+        StrokeStyle::MathStroke(_) => "mathstroke",
+        StrokeStyle::CheckboxStroke(_) => "checkboxstroke",
+        StrokeStyle::ExtensionStroke(_) => "extensionstroke",
+    }
+}
+
+/// Integers are accepted for floats, so scripts can write `10` instead of `10.0`
+fn number(value: Dynamic) -> Result<f64, Box<EvalAltResult>> {
+    value
+        .as_float()
+        .or_else(|_| value.as_int().map(|value| value as f64))
+        .map_err(|type_name| format!("expected a number, got {}", type_name).into())
+}
+
+/// Numbers which end up in the geometry or the options of strokes must be finite, NaN or infinite values would break their bounds and rendering
+fn finite_number(value: Dynamic) -> Result<f64, Box<EvalAltResult>> {
+    let value = number(value)?;
+    if !value.is_finite() {
+        return Err(format!("expected a finite number, got {}", value).into());
+    }
+
+    Ok(value)
+}
+
+fn bounds_map(bounds: AABB) -> Map {
+    let mut map = Map::new();
+    map.insert("x".into(), Dynamic::from(bounds.mins[0]));
+    map.insert("y".into(), Dynamic::from(bounds.mins[1]));
+    map.insert("width".into(), Dynamic::from(bounds.extents()[0]));
+    map.insert("height".into(), Dynamic::from(bounds.extents()[1]));
+
+    map
+}
+
+fn shapestroke(shape: Shape, options: &ScriptOptions) -> StrokeStyle {
+    StrokeStyle::ShapeStroke(ShapeStroke::new_w_shape(
+        shape,
+        options.shape_options.clone(),
+        ArrowheadOptions::default(),
+    ))
+}
+
+/// The engine with the api for scripts and the limits of the sandbox
+fn script_engine(state: &SharedState) -> Engine {
+    let mut engine = Engine::new();
+    let max_operations = state.borrow().options.max_operations.clamp(
+        ScriptOptions::MAX_OPERATIONS_MIN,
+        ScriptOptions::MAX_OPERATIONS_MAX,
+    );
+
+    engine
+        .set_max_operations(max_operations)
+        .set_max_call_levels(ScriptOptions::MAX_CALL_LEVELS)
+        .set_max_string_size(ScriptOptions::MAX_SIZE)
+        .set_max_array_size(ScriptOptions::MAX_SIZE)
+        .set_max_map_size(ScriptOptions::MAX_SIZE)
+        .set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new())
+        .disable_symbol("eval");
+
+    let print_state = Rc::clone(state);
+    engine.on_print(move |text| print_state.borrow_mut().log.push(text.to_string()));
+    let debug_state = Rc::clone(state);
+    engine.on_debug(move |text, _, _| debug_state.borrow_mut().log.push(text.to_string()));
+
+    engine
+        .register_type_with_name::<ScriptStroke>("Stroke")
+        .register_type_with_name::<Color>("Color");
+
+    let strokes_state = Rc::clone(state);
+    engine.register_fn("strokes", move || {
+        let state = strokes_state.borrow();
+        let n_strokes = state.strokes.len();
+
+        (0..n_strokes)
+            .map(|i| Dynamic::from(ScriptStroke(i)))
+            .collect::<rhai::Array>()
+    });
+    let selection_state = Rc::clone(state);
+    engine.register_fn("selection", move || {
+        let state = selection_state.borrow();
+        let n_existing = state.strokes.len()
+            - state
+                .strokes
+                .iter()
+                .filter(|entry| entry.key.is_none())
+                .count();
+
+        // Inserted strokes are pushed after the selection
+        (n_existing - state.n_selected..n_existing)
+            .map(|i| Dynamic::from(ScriptStroke(i)))
+            .collect::<rhai::Array>()
+    });
+    let pages_state = Rc::clone(state);
+    engine.register_fn("pages", move || {
+        pages_state
+            .borrow()
+            .pages
+            .iter()
+            .map(|&bounds| Dynamic::from(bounds_map(bounds)))
+            .collect::<rhai::Array>()
+    });
+
+    engine.register_fn(
+        "rgb",
+        |r: Dynamic, g: Dynamic, b: Dynamic| -> Result<Color, Box<EvalAltResult>> {
+            Ok(Color::new(
+                finite_number(r)?,
+                finite_number(g)?,
+                finite_number(b)?,
+                1.0,
+            ))
+        },
+    );
+    engine.register_fn(
+        "rgba",
+        |r: Dynamic, g: Dynamic, b: Dynamic, a: Dynamic| -> Result<Color, Box<EvalAltResult>> {
+            Ok(Color::new(
+                finite_number(r)?,
+                finite_number(g)?,
+                finite_number(b)?,
+                finite_number(a)?,
+            ))
+        },
+    );
+
+    let line_state = Rc::clone(state);
+    engine.register_fn(
+        "add_line",
+        move |x1: Dynamic,
+              y1: Dynamic,
+              x2: Dynamic,
+              y2: Dynamic|
+              -> Result<ScriptStroke, Box<EvalAltResult>> {
+            let line = curves::Line {
+                start: na::vector![finite_number(x1)?, finite_number(y1)?],
+                end: na::vector![finite_number(x2)?, finite_number(y2)?],
+            };
+            let mut state = line_state.borrow_mut();
+            let stroke = shapestroke(Shape::Line(line), &state.options);
+
+            state.insert(stroke)
+        },
+    );
+    let rectangle_state = Rc::clone(state);
+    engine.register_fn(
+        "add_rectangle",
+        move |x: Dynamic,
+              y: Dynamic,
+              width: Dynamic,
+              height: Dynamic|
+              -> Result<ScriptStroke, Box<EvalAltResult>> {
+            let half_extents =
+                na::vector![finite_number(width)?, finite_number(height)?].abs() * 0.5;
+            let rectangle = shapes::Rectangle {
+                cuboid: p2d::shape::Cuboid::new(half_extents),
+                transform: Transform::new_w_isometry(na::Isometry2::new(
+                    na::vector![finite_number(x)?, finite_number(y)?] + half_extents,
+                    0.0,
+                )),
+            };
+            let mut state = rectangle_state.borrow_mut();
+            let stroke = shapestroke(Shape::Rectangle(rectangle), &state.options);
+
+            state.insert(stroke)
+        },
+    );
+    let ellipse_state = Rc::clone(state);
+    engine.register_fn(
+        "add_ellipse",
+        move |x: Dynamic,
+              y: Dynamic,
+              radius_x: Dynamic,
+              radius_y: Dynamic|
+              -> Result<ScriptStroke, Box<EvalAltResult>> {
+            let ellipse = shapes::Ellipse {
+                radii: na::vector![finite_number(radius_x)?, finite_number(radius_y)?].abs(),
+                transform: Transform::new_w_isometry(na::Isometry2::new(
+                    na::vector![finite_number(x)?, finite_number(y)?],
+                    0.0,
+                )),
+            };
+            let mut state = ellipse_state.borrow_mut();
+            let stroke = shapestroke(Shape::Ellipse(ellipse), &state.options);
+
+            state.insert(stroke)
+        },
+    );
+    let text_state = Rc::clone(state);
+    engine.register_fn(
+        "add_text",
+        move |text: &str, x: Dynamic, y: Dynamic| -> Result<ScriptStroke, Box<EvalAltResult>> {
+            let pos = na::vector![finite_number(x)?, finite_number(y)?];
+            let mut state = text_state.borrow_mut();
+            let textstroke = TextStroke::new(
+                text.to_string(),
+                pos,
+                TextStroke::MAX_WIDTH_DEFAULT,
+                state.options.text_style.clone(),
+            );
+
+            state.insert(StrokeStyle::TextStroke(textstroke))
+        },
+    );
+
+    let kind_state = Rc::clone(state);
+    engine.register_get(
+        "kind",
+        move |stroke: &mut ScriptStroke| -> Result<String, Box<EvalAltResult>> {
+            Ok(kind_state.borrow().entry(*stroke)?.kind.to_string())
+        },
+    );
+    let bounds_state = Rc::clone(state);
+    engine.register_get(
+        "bounds",
+        move |stroke: &mut ScriptStroke| -> Result<Map, Box<EvalAltResult>> {
+            Ok(bounds_map(bounds_state.borrow().entry(*stroke)?.bounds))
+        },
+    );
+
+    let recolor_state = Rc::clone(state);
+    engine.register_fn(
+        "recolor",
+        move |stroke: &mut ScriptStroke, color: Color| -> Result<(), Box<EvalAltResult>> {
+            let mut state = recolor_state.borrow_mut();
+            state.entry(*stroke)?;
+
+            state.commands.push(ScriptCommand::Recolor(*stroke, color));
+            Ok(())
+        },
+    );
+    let width_state = Rc::clone(state);
+    engine.register_fn(
+        "set_width",
+        move |stroke: &mut ScriptStroke, width: Dynamic| -> Result<(), Box<EvalAltResult>> {
+            let width = finite_number(width)?;
+            if width <= 0.0 {
+                return Err("the width must be positive".into());
+            }
+            let mut state = width_state.borrow_mut();
+            state.entry(*stroke)?;
+
+            state.commands.push(ScriptCommand::SetWidth(*stroke, width));
+            Ok(())
+        },
+    );
+    let translate_state = Rc::clone(state);
+    engine.register_fn(
+        "translate",
+        move |stroke: &mut ScriptStroke,
+              x: Dynamic,
+              y: Dynamic|
+              -> Result<(), Box<EvalAltResult>> {
+            let offset = na::vector![finite_number(x)?, finite_number(y)?];
+            let mut state = translate_state.borrow_mut();
+            let bounds = state.entry(*stroke)?.bounds;
+
+            state.strokes[stroke.0].bounds = AABB::new(bounds.mins + offset, bounds.maxs + offset);
+            state
+                .commands
+                .push(ScriptCommand::Translate(*stroke, offset));
+            Ok(())
+        },
+    );
+    let trash_state = Rc::clone(state);
+    engine.register_fn(
+        "trash",
+        move |stroke: &mut ScriptStroke| -> Result<(), Box<EvalAltResult>> {
+            let mut state = trash_state.borrow_mut();
+            state.entry(*stroke)?;
+
+            state.commands.push(ScriptCommand::Trash(*stroke));
+            Ok(())
+        },
+    );
+
+    engine
+}
@@ -0,0 +1,188 @@
+//! Files attached to the sheet, like the sources of the notes, spreadsheets or reference images, so the sheet carries its source material with it.
+//! In containers the files are stored as assets ( see `container` ).
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::Sheet;
+
+/// A file attached to the sheet
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "attachment")]
+pub struct Attachment {
+    /// The file name, without directories. Unique within the sheet
+    #[serde(rename = "name")]
+    pub name: String,
+    #[serde(rename = "mime_type")]
+    pub mime_type: String,
+    /// The size of the file in bytes
+    #[serde(rename = "size")]
+    pub size: u64,
+    /// When the file was attached
+    #[serde(rename = "added")]
+    pub added: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(rename = "data_base64", with = "crate::sheet::container::asset_base64")]
+    pub data_base64: String,
+}
+
+impl Attachment {
+    pub const MIME_TYPE_DEFAULT: &'static str = "application/octet-stream";
+
+    /// A new attachment. The mime type is guessed from the name and the content
+    pub fn new(name: String, bytes: &[u8]) -> Self {
+        let mime_type = Self::guess_mime_type(&name, bytes);
+
+        Self {
+            name,
+            mime_type,
+            size: bytes.len() as u64,
+            added: Some(chrono::Utc::now()),
+            data_base64: base64::encode(bytes),
+        }
+    }
+
+    /// The decoded bytes of the file
+    pub fn data(&self) -> Result<Vec<u8>, anyhow::Error> {
+        Ok(base64::decode(&self.data_base64)?)
+    }
+    #[cfg(feature = "cairo")]
+    fn guess_mime_type(name: &str, bytes: &[u8]) -> String {
+        let (content_type, _uncertain) = gio::content_type_guess(Some(name), bytes);
+        gio::content_type_get_mime_type(&content_type)
+            .map(|mime_type| mime_type.to_string())
+            .unwrap_or_else(|| String::from(Self::MIME_TYPE_DEFAULT))
+    }
+
+    /// Without gio the mime type is only guessed from the extension of the name
+    #[cfg(not(feature = "cairo"))]
+    fn guess_mime_type(name: &str, _bytes: &[u8]) -> String {
+        let extension = Path::new(name)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+
+        let mime_type = match extension.as_deref() {
+            Some("txt") => "text/plain",
+            Some("md") => "text/markdown",
+            Some("csv") => "text/csv",
+            Some("html" | "htm") => "text/html",
+            Some("json") => "application/json",
+            Some("pdf") => "application/pdf",
+            Some("zip") => "application/zip",
+            Some("png") => "image/png",
+            Some("jpg" | "jpeg") => "image/jpeg",
+            Some("gif") => "image/gif",
+            Some("webp") => "image/webp",
+            Some("svg") => "image/svg+xml",
+            Some("mp3") => "audio/mpeg",
+            Some("ogg") => "audio/ogg",
+            Some("wav") => "audio/wav",
+            Some("mp4") => "video/mp4",
+            Some("webm") => "video/webm",
+            _ => Self::MIME_TYPE_DEFAULT,
+        };
+
+        String::from(mime_type)
+    }
+}
+
+impl Sheet {
+    /// The attached files, in the order they were attached
+    pub fn attachments(&self) -> &[Attachment] {
+        &self.attachments
+    }
+
+    /// The index of the attachment with the name
+    pub fn attachment_index(&self, name: &str) -> Option<usize> {
+        self.attachments
+            .iter()
+            .position(|attachment| attachment.name == name)
+    }
+
+    /// Attaches the file and returns its index. Directories in the name are stripped,
+    /// and names which are already taken get a number appended, e.g. `report (2).pdf`
+    pub fn add_attachment(&mut self, name: &str, bytes: &[u8]) -> usize {
+        let name = self.unique_attachment_name(name);
+
+        self.attachments.push(Attachment::new(name, bytes));
+        self.attachments.len() - 1
+    }
+
+    /// Attaches the file at the path and returns its index
+    pub fn add_attachment_from_path(&mut self, path: &Path) -> Result<usize, anyhow::Error> {
+        let bytes = std::fs::read(path)?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Ok(self.add_attachment(&name, &bytes))
+    }
+
+    pub fn remove_attachment(&mut self, index: usize) -> Option<Attachment> {
+        if index < self.attachments.len() {
+            Some(self.attachments.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Extracts the attached file into the directory, replacing a file with the same name. Returns the path of the extracted file
+    pub fn extract_attachment(&self, index: usize, dir: &Path) -> Result<PathBuf, anyhow::Error> {
+        let attachment = self.attachments.get(index).ok_or_else(|| {
+            anyhow::anyhow!(
+                "extracting the attachment {} failed, the sheet has only {} attachments",
+                index,
+                self.attachments.len()
+            )
+        })?;
+        // The name is checked again, as it might come from a file that was edited by hand
+        let path = dir.join(sanitize_name(&attachment.name));
+
+        std::fs::write(&path, attachment.data()?)?;
+
+        Ok(path)
+    }
+
+    /// Extracts all attached files into the directory. Returns the paths of the extracted files
+    pub fn extract_attachments(&self, dir: &Path) -> Result<Vec<PathBuf>, anyhow::Error> {
+        (0..self.attachments.len())
+            .map(|index| self.extract_attachment(index, dir))
+            .collect()
+    }
+
+    fn unique_attachment_name(&self, name: &str) -> String {
+        let name = sanitize_name(name);
+        if self.attachment_index(&name).is_none() {
+            return name;
+        }
+
+        let (stem, ext) = match name.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() => (stem, Some(ext)),
+            _ => (name.as_str(), None),
+        };
+
+        (2..)
+            .map(|i| match ext {
+                Some(ext) => format!("{} ({}).{}", stem, i, ext),
+                None => format!("{} ({})", stem, i),
+            })
+            .find(|name| self.attachment_index(name).is_none())
+            .unwrap()
+    }
+}
+
+/// Strips the directories from the name, so extracting never writes outside of the directory
+fn sanitize_name(name: &str) -> String {
+    let name = Path::new(name)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    if name.is_empty() {
+        String::from("attachment")
+    } else {
+        name
+    }
+}
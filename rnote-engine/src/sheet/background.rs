@@ -1,33 +1,51 @@
 use std::sync::{Arc, RwLock};
 
 use anyhow::Context;
-use gtk4::{glib, gsk, Snapshot};
-use p2d::bounding_volume::{BoundingVolume, AABB};
+#[cfg(feature = "ui")]
+use gtk4::{gsk, Snapshot};
+#[cfg(feature = "ui")]
+use p2d::bounding_volume::BoundingVolume;
+use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 use svg::node::element;
 
 use crate::compose;
 use crate::compose::color::Color;
+#[cfg(feature = "ui")]
 use crate::compose::geometry::AABBHelpers;
 use crate::render::{self, Renderer};
+use rnote_fileformats::xoppformat;
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy, glib::Enum, Serialize, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
 #[repr(u32)]
-#[enum_type(name = "PatternStyle")]
+#[cfg_attr(feature = "ui", enum_type(name = "PatternStyle"))]
 #[serde(rename = "pattern_style")]
 pub enum PatternStyle {
-    #[enum_value(name = "None", nick = "none")]
+    #[cfg_attr(feature = "ui", enum_value(name = "None", nick = "none"))]
     #[serde(rename = "none")]
     None = 0,
-    #[enum_value(name = "Lines", nick = "lines")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Lines", nick = "lines"))]
     #[serde(rename = "lines")]
     Lines,
-    #[enum_value(name = "Grid", nick = "grid")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Grid", nick = "grid"))]
     #[serde(rename = "grid")]
     Grid,
-    #[enum_value(name = "Dots", nick = "dots")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Dots", nick = "dots"))]
     #[serde(rename = "dots")]
     Dots,
+    #[cfg_attr(feature = "ui", enum_value(name = "Isometric", nick = "isometric"))]
+    #[serde(rename = "isometric")]
+    Isometric,
+    #[cfg_attr(feature = "ui", enum_value(name = "Hexagons", nick = "hexagons"))]
+    #[serde(rename = "hexagons")]
+    Hexagons,
+    #[cfg_attr(feature = "ui", enum_value(name = "Music Staff", nick = "music-staff"))]
+    #[serde(rename = "music_staff")]
+    MusicStaff,
+    #[cfg_attr(feature = "ui", enum_value(name = "Custom Tile", nick = "custom-tile"))]
+    #[serde(rename = "custom_tile")]
+    CustomTile,
 }
 
 impl Default for PatternStyle {
@@ -164,6 +182,243 @@ pub fn gen_dots_pattern(
     group.into()
 }
 
+/// An isometric grid of equilateral triangles. The row spacing is the vertical distance between the slanted lines
+pub fn gen_isometric_pattern(
+    bounds: AABB,
+    row_spacing: f64,
+    color: Color,
+    line_width: f64,
+) -> svg::node::element::Element {
+    let pattern_id = compose::random_id_prefix() + "_bg_isometric_pattern";
+    let width = row_spacing * 3_f64.sqrt();
+
+    let line = |x1: f64, y1: f64, x2: f64, y2: f64| {
+        element::Line::new()
+            .set("stroke-width", line_width)
+            .set("stroke", color.to_css_color())
+            .set("x1", x1)
+            .set("y1", y1)
+            .set("x2", x2)
+            .set("y2", y2)
+    };
+
+    let pattern = element::Definitions::new().add(
+        element::Pattern::new()
+            .set("id", pattern_id.as_str())
+            .set("x", 0_f64)
+            .set("y", 0_f64)
+            .set("width", width)
+            .set("height", row_spacing)
+            .set("patternUnits", "userSpaceOnUse")
+            .set("patternContentUnits", "userSpaceOnUse")
+            .add(line(0.0, 0.0, width, row_spacing))
+            .add(line(0.0, row_spacing, width, 0.0))
+            .add(line(0.0, 0.0, 0.0, row_spacing))
+            .add(line(width * 0.5, 0.0, width * 0.5, row_spacing)),
+    );
+
+    let rect = element::Rectangle::new()
+        .set("x", bounds.mins[0])
+        .set("y", bounds.mins[1])
+        .set("width", bounds.extents()[0])
+        .set("height", bounds.extents()[1])
+        .set("fill", format!("url(#{})", pattern_id));
+
+    let group = element::Group::new().add(pattern).add(rect);
+    group.into()
+}
+
+/// A grid of hexagons with their pointy side up. The width is the distance between two opposite sides
+pub fn gen_hexagon_pattern(
+    bounds: AABB,
+    hexagon_width: f64,
+    color: Color,
+    line_width: f64,
+) -> svg::node::element::Element {
+    let pattern_id = compose::random_id_prefix() + "_bg_hexagon_pattern";
+    let w = hexagon_width;
+    // The length of a side
+    let r = hexagon_width / 3_f64.sqrt();
+
+    // One hexagon in the middle, and the sides of the neighbouring hexagons which reach into the tile
+    let data = element::path::Data::new()
+        .move_to((w * 0.5, 0.0))
+        .line_to((w * 0.5, r * 0.5))
+        .line_to((w, r))
+        .line_to((w, r * 2.0))
+        .line_to((w * 0.5, r * 2.5))
+        .line_to((0.0, r * 2.0))
+        .line_to((0.0, r))
+        .close()
+        .move_to((w * 0.5, r * 2.5))
+        .line_to((w * 0.5, r * 3.0));
+
+    let pattern = element::Definitions::new().add(
+        element::Pattern::new()
+            .set("id", pattern_id.as_str())
+            .set("x", 0_f64)
+            .set("y", 0_f64)
+            .set("width", w)
+            .set("height", r * 3.0)
+            .set("patternUnits", "userSpaceOnUse")
+            .set("patternContentUnits", "userSpaceOnUse")
+            .add(
+                element::Path::new()
+                    .set("stroke-width", line_width)
+                    .set("stroke", color.to_css_color())
+                    .set("fill", "none")
+                    .set("d", data),
+            ),
+    );
+
+    let rect = element::Rectangle::new()
+        .set("x", bounds.mins[0])
+        .set("y", bounds.mins[1])
+        .set("width", bounds.extents()[0])
+        .set("height", bounds.extents()[1])
+        .set("fill", format!("url(#{})", pattern_id));
+
+    let group = element::Group::new().add(pattern).add(rect);
+    group.into()
+}
+
+/// Staves of five lines, separated by the height of a staff
+pub fn gen_music_staff_pattern(
+    bounds: AABB,
+    line_spacing: f64,
+    color: Color,
+    line_width: f64,
+) -> svg::node::element::Element {
+    let pattern_id = compose::random_id_prefix() + "_bg_music_staff_pattern";
+
+    let mut pattern = element::Pattern::new()
+        .set("id", pattern_id.as_str())
+        .set("x", 0_f64)
+        .set("y", 0_f64)
+        .set("width", bounds.extents()[0])
+        .set("height", line_spacing * Background::MUSIC_STAFF_PERIOD)
+        .set("patternUnits", "userSpaceOnUse")
+        .set("patternContentUnits", "userSpaceOnUse");
+
+    for i in 0..5 {
+        let y = line_spacing * (2.0 + f64::from(i));
+        pattern = pattern.add(
+            element::Line::new()
+                .set("stroke-width", line_width)
+                .set("stroke", color.to_css_color())
+                .set("x1", 0_f64)
+                .set("y1", y)
+                .set("x2", bounds.extents()[0])
+                .set("y2", y),
+        );
+    }
+
+    let rect = element::Rectangle::new()
+        .set("x", bounds.mins[0])
+        .set("y", bounds.mins[1])
+        .set("width", bounds.extents()[0])
+        .set("height", bounds.extents()[1])
+        .set("fill", format!("url(#{})", pattern_id));
+
+    let group = element::Group::new()
+        .add(element::Definitions::new().add(pattern))
+        .add(rect);
+    group.into()
+}
+
+/// Repeats the svg tile, scaled to the tile size
+pub fn gen_custom_tile_pattern(
+    bounds: AABB,
+    tile_size: na::Vector2<f64>,
+    tile_svg: &str,
+) -> svg::node::element::Element {
+    let pattern_id = compose::random_id_prefix() + "_bg_custom_tile_pattern";
+
+    let pattern = element::Definitions::new().add(
+        element::Pattern::new()
+            .set("id", pattern_id.as_str())
+            .set("x", 0_f64)
+            .set("y", 0_f64)
+            .set("width", tile_size[0])
+            .set("height", tile_size[1])
+            .set("patternUnits", "userSpaceOnUse")
+            .set("patternContentUnits", "userSpaceOnUse")
+            .add(
+                element::Image::new()
+                    .set("x", 0_f64)
+                    .set("y", 0_f64)
+                    .set("width", tile_size[0])
+                    .set("height", tile_size[1])
+                    .set("preserveAspectRatio", "none")
+                    .set(
+                        "xlink:href",
+                        format!("data:image/svg+xml;base64,{}", base64::encode(tile_svg)),
+                    ),
+            ),
+    );
+
+    let rect = element::Rectangle::new()
+        .set("x", bounds.mins[0])
+        .set("y", bounds.mins[1])
+        .set("width", bounds.extents()[0])
+        .set("height", bounds.extents()[1])
+        .set("fill", format!("url(#{})", pattern_id));
+
+    let group = element::Group::new().add(pattern).add(rect);
+    group.into()
+}
+
+/// A background which replaces the sheet background between two vertical positions, across the entire width of the sheet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "background_region")]
+pub struct BackgroundRegion {
+    #[serde(rename = "y_start")]
+    pub y_start: f64,
+    #[serde(rename = "y_end")]
+    pub y_end: f64,
+    #[serde(rename = "background")]
+    pub background: Background,
+}
+
+impl Default for BackgroundRegion {
+    fn default() -> Self {
+        Self {
+            y_start: 0.0,
+            y_end: 0.0,
+            background: Background::default(),
+        }
+    }
+}
+
+impl BackgroundRegion {
+    pub fn new(y_start: f64, y_end: f64, background: Background) -> Self {
+        Self {
+            y_start: y_start.min(y_end),
+            y_end: y_start.max(y_end),
+            background,
+        }
+    }
+
+    pub fn contains_y(&self, y: f64) -> bool {
+        self.y_start <= y && y < self.y_end
+    }
+
+    /// The part of the sheet the region covers. None if it is outside of the sheet
+    pub fn bounds(&self, sheet_bounds: AABB) -> Option<AABB> {
+        let y_start = self.y_start.max(sheet_bounds.mins[1]);
+        let y_end = self.y_end.min(sheet_bounds.maxs[1]);
+
+        if y_start < y_end {
+            Some(AABB::new(
+                na::point![sheet_bounds.mins[0], y_start],
+                na::point![sheet_bounds.maxs[0], y_end],
+            ))
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "background")]
 pub struct Background {
@@ -175,8 +430,12 @@ pub struct Background {
     pub pattern_size: na::Vector2<f64>,
     #[serde(rename = "pattern_color")]
     pub pattern_color: Color,
+    /// The svg tile of the custom tile pattern
+    #[serde(rename = "custom_tile")]
+    pub custom_tile: Option<String>,
     #[serde(skip)]
     pub image: Option<render::Image>,
+    #[cfg(feature = "ui")]
     #[serde(skip)]
     rendernode: Option<gsk::RenderNode>,
 }
@@ -188,7 +447,9 @@ impl Default for Background {
             pattern: PatternStyle::default(),
             pattern_size: Self::PATTERN_SIZE_DEFAULT,
             pattern_color: Self::PATTERN_COLOR_DEFAULT,
+            custom_tile: None,
             image: None,
+            #[cfg(feature = "ui")]
             rendernode: None,
         }
     }
@@ -204,21 +465,44 @@ impl Background {
         b: 1.0,
         a: 1.0,
     };
+    /// The line spacing of lined and ruled Xournal++ backgrounds, in the Xournal++ dpi
+    pub const XOPP_LINES_SPACING: f64 = 24.0;
+    /// The spacing of graph and dotted Xournal++ backgrounds, in the Xournal++ dpi
+    pub const XOPP_GRID_SPACING: f64 = 14.17;
+    /// The height of a staff and the gap to the next one, in line spacings
+    pub const MUSIC_STAFF_PERIOD: f64 = 8.0;
+
+    /// The size after which the pattern repeats. The isometric pattern uses the pattern height as row spacing,
+    /// the hexagon pattern the pattern width as hexagon width and the music staff the pattern height as line spacing
+    pub fn pattern_period(&self) -> na::Vector2<f64> {
+        let pattern_size = self.pattern_size.map(|size| size.max(1.0));
+
+        match self.pattern {
+            PatternStyle::Isometric => na::vector![pattern_size[1] * 3_f64.sqrt(), pattern_size[1]],
+            PatternStyle::Hexagons => na::vector![pattern_size[0], pattern_size[0] * 3_f64.sqrt()],
+            PatternStyle::MusicStaff => {
+                na::vector![pattern_size[0], pattern_size[1] * Self::MUSIC_STAFF_PERIOD]
+            }
+            _ => pattern_size,
+        }
+    }
 
     pub fn tile_size(&self) -> na::Vector2<f64> {
-        // Calculate tile size as multiple of pattern_size with max size TITLE_MAX_SIZE
+        let pattern_period = self.pattern_period();
+
+        // Calculate tile size as multiple of the pattern period with max size TITLE_MAX_SIZE
         let tile_factor =
-            na::Vector2::from_element(Self::TILE_MAX_SIZE).component_div(&self.pattern_size);
+            na::Vector2::from_element(Self::TILE_MAX_SIZE).component_div(&pattern_period);
 
         let tile_width = if tile_factor[0] > 1.0 {
-            tile_factor[0].floor() * self.pattern_size[0]
+            tile_factor[0].floor() * pattern_period[0]
         } else {
-            self.pattern_size[0]
+            pattern_period[0]
         };
         let tile_height = if tile_factor[1] > 1.0 {
-            tile_factor[1].floor() * self.pattern_size[1]
+            tile_factor[1].floor() * pattern_period[1]
         } else {
-            self.pattern_size[1]
+            pattern_period[1]
         };
         let tile_size = na::vector![tile_width, tile_height];
 
@@ -266,6 +550,39 @@ impl Background {
                     2.0,
                 ));
             }
+            PatternStyle::Isometric => {
+                group = group.add(gen_isometric_pattern(
+                    bounds,
+                    self.pattern_period()[1],
+                    self.pattern_color,
+                    1.0,
+                ));
+            }
+            PatternStyle::Hexagons => {
+                group = group.add(gen_hexagon_pattern(
+                    bounds,
+                    self.pattern_period()[0],
+                    self.pattern_color,
+                    1.0,
+                ));
+            }
+            PatternStyle::MusicStaff => {
+                group = group.add(gen_music_staff_pattern(
+                    bounds,
+                    self.pattern_period()[1] / Self::MUSIC_STAFF_PERIOD,
+                    self.pattern_color,
+                    1.0,
+                ));
+            }
+            PatternStyle::CustomTile => {
+                if let Some(custom_tile) = &self.custom_tile {
+                    group = group.add(gen_custom_tile_pattern(
+                        bounds,
+                        self.pattern_period(),
+                        custom_tile,
+                    ));
+                }
+            }
         }
         let svg_data = compose::svg_node_to_string(&group)
             .map_err(|e| anyhow::anyhow!("node_to_string() failed for background, {}", e))?;
@@ -290,6 +607,7 @@ impl Background {
         )?))
     }
 
+    #[cfg_attr(not(feature = "ui"), allow(unused_variables))]
     pub fn regenerate_background(
         &mut self,
         zoom: f64,
@@ -302,10 +620,12 @@ impl Background {
 
         self.image = self.gen_image(renderer, zoom, tile_bounds)?;
 
+        #[cfg(feature = "ui")]
         self.update_rendernode(zoom, sheet_bounds, viewport)?;
         Ok(())
     }
 
+    #[cfg(feature = "ui")]
     pub fn gen_rendernode(
         &mut self,
         zoom: f64,
@@ -352,6 +672,7 @@ impl Background {
         Ok(snapshot.to_node())
     }
 
+    #[cfg(feature = "ui")]
     pub fn update_rendernode(
         &mut self,
         zoom: f64,
@@ -373,9 +694,95 @@ impl Background {
         Ok(())
     }
 
+    /// Sets the svg as tile of the custom tile pattern and switches to it. Errors if the svg can't be parsed
+    pub fn set_custom_tile(&mut self, svg: &str) -> Result<(), anyhow::Error> {
+        usvg::Tree::from_str(svg, &usvg::Options::default().to_ref())
+            .context("the custom background tile is not a valid svg")?;
+
+        self.custom_tile = Some(svg.to_string());
+        self.pattern = PatternStyle::CustomTile;
+        Ok(())
+    }
+
+    #[cfg(feature = "ui")]
+    pub fn clear_rendernode(&mut self) {
+        self.rendernode = None;
+    }
+
+    #[cfg(feature = "ui")]
     pub fn draw(&self, snapshot: &Snapshot) {
         self.rendernode.iter().for_each(|rendernode| {
             snapshot.append_node(rendernode);
         });
     }
+
+    /// Whether the background is drawn the same as the other one
+    pub fn draws_like(&self, other: &Self) -> bool {
+        self.color == other.color
+            && self.pattern == other.pattern
+            && self.pattern_size == other.pattern_size
+            && self.pattern_color == other.pattern_color
+            && self.custom_tile == other.custom_tile
+    }
+
+    /// Converts a Xournal++ background. The values are expected to be in the Xournal++ dpi.
+    /// Pixmap and pdf backgrounds are not supported and result in a plain background
+    pub fn from_xoppbackground(xopp_background: &xoppformat::XoppBackground) -> Self {
+        let mut background = Self::default();
+
+        match &xopp_background.bg_type {
+            xoppformat::XoppBackgroundType::Solid { color, style } => {
+                background.color = Color::from(*color);
+
+                match style {
+                    xoppformat::XoppBackgroundSolidStyle::Plain => {
+                        background.pattern = PatternStyle::None;
+                    }
+                    xoppformat::XoppBackgroundSolidStyle::Lined
+                    | xoppformat::XoppBackgroundSolidStyle::Ruled => {
+                        background.pattern = PatternStyle::Lines;
+                        background.pattern_size =
+                            na::Vector2::from_element(Self::XOPP_LINES_SPACING);
+                    }
+                    xoppformat::XoppBackgroundSolidStyle::Graph => {
+                        background.pattern = PatternStyle::Grid;
+                        background.pattern_size =
+                            na::Vector2::from_element(Self::XOPP_GRID_SPACING);
+                    }
+                    xoppformat::XoppBackgroundSolidStyle::Dotted => {
+                        background.pattern = PatternStyle::Dots;
+                        background.pattern_size =
+                            na::Vector2::from_element(Self::XOPP_GRID_SPACING);
+                    }
+                }
+            }
+            xoppformat::XoppBackgroundType::Pixmap { .. } | xoppformat::XoppBackgroundType::Pdf => {
+                background.pattern = PatternStyle::None;
+            }
+        }
+
+        background
+    }
+
+    /// Converts the background into a Xournal++ background. Xournal++ has fixed pattern sizes, so only the style and color are kept
+    pub fn to_xoppbackground(&self) -> xoppformat::XoppBackground {
+        let style = match self.pattern {
+            PatternStyle::None => xoppformat::XoppBackgroundSolidStyle::Plain,
+            PatternStyle::Lines => xoppformat::XoppBackgroundSolidStyle::Lined,
+            PatternStyle::Grid | PatternStyle::Isometric | PatternStyle::Hexagons => {
+                xoppformat::XoppBackgroundSolidStyle::Graph
+            }
+            PatternStyle::Dots => xoppformat::XoppBackgroundSolidStyle::Dotted,
+            PatternStyle::MusicStaff => xoppformat::XoppBackgroundSolidStyle::Lined,
+            PatternStyle::CustomTile => xoppformat::XoppBackgroundSolidStyle::Plain,
+        };
+
+        xoppformat::XoppBackground {
+            name: None,
+            bg_type: xoppformat::XoppBackgroundType::Solid {
+                color: self.color.into(),
+                style,
+            },
+        }
+    }
 }
@@ -0,0 +1,85 @@
+use std::ops::Range;
+
+/// The image format of bitmap exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitmapExportFormat {
+    Png,
+    Jpeg,
+}
+
+impl Default for BitmapExportFormat {
+    fn default() -> Self {
+        Self::Png
+    }
+}
+
+impl BitmapExportFormat {
+    /// The file extension, without the leading dot
+    pub fn file_ext(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+        }
+    }
+}
+
+/// What gets exported as bitmap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BitmapExportScope {
+    /// The pages in the range, counted from 0. Every page becomes one image. Pages beyond the end of the sheet are skipped
+    Pages(Range<u32>),
+    /// The current selection, as one image covering the selection bounds
+    Selection,
+}
+
+impl Default for BitmapExportScope {
+    fn default() -> Self {
+        Self::Pages(0..u32::MAX)
+    }
+}
+
+/// The preferences for exporting the sheet as bitmap images
+#[derive(Debug, Clone)]
+pub struct BitmapExportPrefs {
+    pub format: BitmapExportFormat,
+    /// The resolution of the exported images. The sheet is rendered at its format DPI scaled to this resolution
+    pub dpi: f64,
+    /// The quality of JPEG images, from 1 to 100. Ignored for PNG images
+    pub jpeg_quality: u8,
+    /// Whether the background is drawn. Without it PNG images are transparent, JPEG images are filled white
+    pub with_background: bool,
+    pub scope: BitmapExportScope,
+}
+
+impl Default for BitmapExportPrefs {
+    fn default() -> Self {
+        Self {
+            format: BitmapExportFormat::default(),
+            dpi: Self::DPI_DEFAULT,
+            jpeg_quality: Self::JPEG_QUALITY_DEFAULT,
+            with_background: true,
+            scope: BitmapExportScope::default(),
+        }
+    }
+}
+
+impl BitmapExportPrefs {
+    pub const DPI_MIN: f64 = 10.0;
+    pub const DPI_MAX: f64 = 1200.0;
+    pub const DPI_DEFAULT: f64 = 96.0;
+
+    pub const JPEG_QUALITY_MIN: u8 = 1;
+    pub const JPEG_QUALITY_MAX: u8 = 100;
+    pub const JPEG_QUALITY_DEFAULT: u8 = 85;
+
+    /// The image format the encoder expects
+    pub fn image_output_format(&self) -> image::ImageOutputFormat {
+        match self.format {
+            BitmapExportFormat::Png => image::ImageOutputFormat::Png,
+            BitmapExportFormat::Jpeg => image::ImageOutputFormat::Jpeg(
+                self.jpeg_quality
+                    .clamp(Self::JPEG_QUALITY_MIN, Self::JPEG_QUALITY_MAX),
+            ),
+        }
+    }
+}
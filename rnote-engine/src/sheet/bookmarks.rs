@@ -0,0 +1,164 @@
+//! Named positions in the sheet, to navigate long documents like chapters. A bookmark remembers the center and the zoom of the view it was added at.
+//! The bookmarks are kept in reading order: by page, then from top to bottom and from left to right.
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use super::presentation::PresentationView;
+use super::Sheet;
+
+/// A named position in the sheet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "bookmark")]
+pub struct Bookmark {
+    #[serde(rename = "name")]
+    pub name: String,
+    /// The center of the view, in the coordinate space of the sheet
+    #[serde(rename = "pos")]
+    pub pos: na::Vector2<f64>,
+    #[serde(rename = "zoom")]
+    pub zoom: f64,
+    /// When the bookmark was added
+    #[serde(rename = "added")]
+    pub added: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Default for Bookmark {
+    fn default() -> Self {
+        Self {
+            name: String::default(),
+            pos: na::Vector2::zeros(),
+            zoom: Self::ZOOM_DEFAULT,
+            added: None,
+        }
+    }
+}
+
+impl Bookmark {
+    pub const ZOOM_MIN: f64 = 0.01;
+    pub const ZOOM_MAX: f64 = 100.0;
+    pub const ZOOM_DEFAULT: f64 = 1.0;
+
+    pub fn new(name: String, pos: na::Vector2<f64>, zoom: f64) -> Self {
+        Self {
+            name,
+            pos,
+            zoom: clamp_zoom(zoom),
+            added: Some(chrono::Utc::now()),
+        }
+    }
+
+    /// The view to jump to the bookmark
+    pub fn view(&self) -> PresentationView {
+        PresentationView {
+            center: self.pos,
+            zoom: clamp_zoom(self.zoom),
+        }
+    }
+}
+
+impl Sheet {
+    /// The bookmarks, in reading order
+    pub fn bookmarks(&self) -> &[Bookmark] {
+        &self.bookmarks
+    }
+
+    pub fn bookmark_index(&self, name: &str) -> Option<usize> {
+        self.bookmarks
+            .iter()
+            .position(|bookmark| bookmark.name == name)
+    }
+
+    /// Adds a bookmark at the center of the view with the zoom, and returns its index in reading order.
+    /// Empty names are replaced by a numbered default name, e.g. `Bookmark 3`
+    pub fn add_bookmark(&mut self, name: &str, pos: na::Vector2<f64>, zoom: f64) -> usize {
+        let name = if name.trim().is_empty() {
+            self.default_bookmark_name()
+        } else {
+            name.trim().to_string()
+        };
+
+        self.bookmarks.push(Bookmark::new(name, pos, zoom));
+        self.sort_bookmarks();
+
+        self.bookmarks
+            .iter()
+            .rposition(|bookmark| bookmark.pos == pos)
+            .unwrap_or(self.bookmarks.len() - 1)
+    }
+
+    /// Renames the bookmark. Returns false if there is no bookmark with the index or the name is empty
+    pub fn rename_bookmark(&mut self, index: usize, name: &str) -> bool {
+        match self.bookmarks.get_mut(index) {
+            Some(bookmark) if !name.trim().is_empty() => {
+                bookmark.name = name.trim().to_string();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn remove_bookmark(&mut self, index: usize) -> Option<Bookmark> {
+        if index < self.bookmarks.len() {
+            Some(self.bookmarks.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// The view to jump to the bookmark
+    pub fn bookmark_view(&self, index: usize) -> Option<PresentationView> {
+        Some(self.bookmarks.get(index)?.view())
+    }
+
+    /// The index of the first bookmark after the position in reading order, e.g. the center of the current view
+    pub fn next_bookmark_index(&self, pos: na::Vector2<f64>) -> Option<usize> {
+        self.bookmarks
+            .iter()
+            .position(|bookmark| self.cmp_reading_order(bookmark.pos, pos) == Ordering::Greater)
+    }
+
+    /// The index of the last bookmark before the position in reading order
+    pub fn previous_bookmark_index(&self, pos: na::Vector2<f64>) -> Option<usize> {
+        self.bookmarks
+            .iter()
+            .rposition(|bookmark| self.cmp_reading_order(bookmark.pos, pos) == Ordering::Less)
+    }
+
+    /// Sorts the bookmarks into reading order again, e.g. after the pages were moved
+    pub fn sort_bookmarks(&mut self) {
+        let mut bookmarks = std::mem::take(&mut self.bookmarks);
+        bookmarks.sort_by(|first, second| self.cmp_reading_order(first.pos, second.pos));
+        self.bookmarks = bookmarks;
+    }
+
+    /// Compares the positions by page, then from top to bottom and from left to right. Positions outside of the pages come last
+    pub(crate) fn cmp_reading_order(
+        &self,
+        first: na::Vector2<f64>,
+        second: na::Vector2<f64>,
+    ) -> Ordering {
+        let page_index = |pos: na::Vector2<f64>| self.page_index_at(pos).unwrap_or(usize::MAX);
+
+        page_index(first)
+            .cmp(&page_index(second))
+            .then_with(|| first[1].partial_cmp(&second[1]).unwrap_or(Ordering::Equal))
+            .then_with(|| first[0].partial_cmp(&second[0]).unwrap_or(Ordering::Equal))
+    }
+
+    fn default_bookmark_name(&self) -> String {
+        (self.bookmarks.len() + 1..)
+            .map(|i| format!("Bookmark {}", i))
+            .find(|name| self.bookmark_index(name).is_none())
+            .unwrap()
+    }
+}
+
+fn clamp_zoom(zoom: f64) -> f64 {
+    if zoom.is_finite() {
+        zoom.clamp(Bookmark::ZOOM_MIN, Bookmark::ZOOM_MAX)
+    } else {
+        Bookmark::ZOOM_DEFAULT
+    }
+}
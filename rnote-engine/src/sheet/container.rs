@@ -0,0 +1,1150 @@
+//! The .rnote container. It is a zip archive with the entries:
+//! - `manifest.json`: the file format version of the container, to pick how the sheet is loaded before reading it
+//! - `sheet.cbor`: the sheet, serialized as CBOR. The strokes and embedded assets are replaced by references to their entries
+//! - `strokes/<chunk>`: the slots of the strokes, in chunks of `STROKES_CHUNK_SIZE` slots. Every chunk holds the CBOR encoded slots one after the other, as slotmap serializes them
+//! - `assets/<id>`: the embedded assets, like the data of images and imported PDFs
+//!
+//! The entries are written and read streaming, so the sheet is never held a second time in memory as json or CBOR.
+//! Writing serializes the sheet twice: the first pass writes the entries of the stroke chunks and the assets as they come up, and serializes the rest into the void.
+//! The second pass writes the sheet entry, which only references them. Reading feeds the chunk entries one after the other into the deserializer of the strokes,
+//! and decodes the assets straight out of the bytes of the container.
+//! The asset fields use `asset_base64` or `asset_text` as serde `with` modules, the strokes `chunked_strokes`. They only become references while a container is written or read,
+//! everywhere else (e.g. the clipboard and legacy .rnote files) they stay inline.
+//!
+//! Saving is incremental: the `ContainerCache` shares the bytes of the container that was last written or read for the sheet,
+//! and the entries of the chunks and assets which did not change since are copied from it as they are, without serializing and compressing them again.
+
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Cursor, Read, Write};
+use std::ops::Deref;
+use std::sync::Arc;
+use std::thread::LocalKey;
+
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde::ser::{Impossible, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use slotmap::{HopSlotMap, Key};
+
+use crate::strokes::strokestyle::StrokeStyle;
+use crate::strokesstate::StrokeKey;
+
+/// The name of the manifest entry
+pub const MANIFEST_ENTRY: &str = "manifest.json";
+/// The name of the sheet entry
+pub const SHEET_ENTRY: &str = "sheet.cbor";
+/// The directory of the stroke chunk entries
+pub const STROKES_DIR: &str = "strokes/";
+/// The directory of the asset entries
+pub const ASSETS_DIR: &str = "assets/";
+/// The number of stroke slots in a chunk. Changing a stroke rewrites its whole chunk on the next save
+pub const STROKES_CHUNK_SIZE: u32 = 256;
+
+type ContainerWriter = zip::ZipWriter<Cursor<Vec<u8>>>;
+type ContainerArchive = zip::ZipArchive<Cursor<ContainerBytes>>;
+type CborSerError = ciborium::ser::Error<io::Error>;
+
+/// The manifest of the container
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "manifest")]
+pub struct Manifest {
+    #[serde(rename = "file_format_version")]
+    pub file_format_version: u64,
+}
+
+/// A reference to a chunk entry of the strokes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "chunk_ref")]
+pub struct ChunkRef {
+    /// The index of the chunk. Its first slot is at `chunk * STROKES_CHUNK_SIZE`
+    #[serde(rename = "chunk")]
+    pub chunk: u32,
+    /// The number of slots in the chunk. Only the last chunk has less than `STROKES_CHUNK_SIZE`
+    #[serde(rename = "n_slots")]
+    pub n_slots: u32,
+    /// The number of strokes in the chunk
+    #[serde(rename = "n_strokes")]
+    pub n_strokes: u32,
+    /// The assets referenced by the strokes of the chunk
+    #[serde(rename = "assets")]
+    pub assets: Vec<u64>,
+}
+
+/// The strokes field in the sheet entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "chunked_strokes")]
+struct ChunkedStrokes {
+    #[serde(rename = "chunks")]
+    chunks: Vec<ChunkRef>,
+}
+
+/// A vacant slot as it is serialized by slotmap
+#[derive(Serialize)]
+struct VacantSlot {
+    value: Option<()>,
+    version: u32,
+}
+
+/// The bytes of a container. Clones share the bytes, so the cache keeps the container that was written or read without copying it
+#[derive(Clone)]
+pub struct ContainerBytes(Arc<dyn AsRef<[u8]> + Send + Sync>);
+
+impl ContainerBytes {
+    pub fn new<B: AsRef<[u8]> + Send + Sync + 'static>(bytes: B) -> Self {
+        Self(Arc::new(bytes))
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        (*self.0).as_ref()
+    }
+}
+
+impl Deref for ContainerBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for ContainerBytes {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl std::fmt::Debug for ContainerBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContainerBytes")
+            .field("n_bytes", &self.len())
+            .finish()
+    }
+}
+
+/// The chunks of the strokes which changed since the sheet was last written into or read from a container
+#[derive(Debug, Clone, Default)]
+pub struct ChangedChunks {
+    all: bool,
+    chunks: HashSet<u32>,
+}
+
+impl ChangedChunks {
+    /// Marks the chunk of the stroke as changed
+    pub fn mark(&mut self, key: StrokeKey) {
+        self.chunks.insert(slot_of_key(key).0 / STROKES_CHUNK_SIZE);
+    }
+
+    /// Marks every chunk as changed, e.g. when the strokes were replaced
+    pub fn mark_all(&mut self) {
+        self.all = true;
+    }
+
+    pub fn clear(&mut self) {
+        self.all = false;
+        self.chunks.clear();
+    }
+
+    pub fn contains(&self, chunk: u32) -> bool {
+        self.all || self.chunks.contains(&chunk)
+    }
+}
+
+/// The container that was last written or read for a sheet
+#[derive(Debug, Clone)]
+pub struct ContainerCache {
+    bytes: ContainerBytes,
+    chunks: HashMap<u32, ChunkRef>,
+    assets: HashSet<u64>,
+    rewritten_chunks: Vec<u32>,
+}
+
+impl ContainerCache {
+    /// The chunks which were serialized again when the container was written. The entries of the other chunks were copied
+    pub fn rewritten_chunks(&self) -> &[u32] {
+        &self.rewritten_chunks
+    }
+
+    /// The number of chunks in the container
+    pub fn n_chunks(&self) -> usize {
+        self.chunks.len()
+    }
+}
+
+/// The passes over the sheet while a container is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WritePass {
+    /// Writes the entries of the stroke chunks and the assets
+    Entries,
+    /// Writes the sheet entry, which references them
+    Sheet,
+}
+
+/// The state while a container is written
+struct WriteState {
+    pass: WritePass,
+    /// Taken while a slot of the strokes or the sheet entry is serialized into it
+    zip: Option<ContainerWriter>,
+    previous: Option<ContainerArchive>,
+    previous_chunks: HashMap<u32, ChunkRef>,
+    previous_assets: HashSet<u64>,
+    changed_chunks: ChangedChunks,
+    /// The references to the chunks of the strokes, serialized or copied, for the sheet entry
+    chunk_refs: Vec<ChunkRef>,
+    /// The chunks which were serialized
+    rewritten_chunks: Vec<u32>,
+    /// The ids of every asset referenced by the sheet
+    referenced_assets: HashSet<u64>,
+    /// The assets referenced while serializing the current chunk
+    chunk_assets: Option<Vec<u64>>,
+    /// The assets which are written once the current chunk is finished. None for the assets which are copied from the previous container
+    pending_assets: Vec<(u64, Option<Vec<u8>>)>,
+}
+
+impl WriteState {
+    fn write_pending_assets(&mut self) -> Result<(), anyhow::Error> {
+        if self.pending_assets.is_empty() {
+            return Ok(());
+        }
+        let zip = self
+            .zip
+            .as_mut()
+            .context("the container is not available to write the assets")?;
+
+        for (id, asset) in self.pending_assets.drain(..) {
+            let name = format!("{}{}", ASSETS_DIR, id);
+            match asset {
+                Some(asset) => {
+                    // Most assets are already compressed images
+                    zip.start_file(
+                        name,
+                        zip::write::FileOptions::default()
+                            .compression_method(zip::CompressionMethod::Stored),
+                    )?;
+                    zip.write_all(&asset)?;
+                }
+                None => {
+                    let previous = self
+                        .previous
+                        .as_mut()
+                        .context("a copied asset has no previous container")?;
+                    zip.raw_copy_file(previous.by_name(&name)?)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The position of the data of an entry in the container
+#[derive(Debug, Clone, Copy)]
+struct EntryData {
+    start: usize,
+    len: usize,
+    deflated: bool,
+}
+
+impl EntryData {
+    fn of(entry: &zip::read::ZipFile<'_>, n_bytes: usize) -> Result<Self, anyhow::Error> {
+        let deflated = match entry.compression() {
+            zip::CompressionMethod::Stored => false,
+            zip::CompressionMethod::Deflated => true,
+            method => {
+                return Err(anyhow::anyhow!(
+                    "the entry `{}` uses the unsupported compression {}",
+                    entry.name(),
+                    method
+                ))
+            }
+        };
+        let start = entry.data_start() as usize;
+        let len = entry.compressed_size() as usize;
+        if start.checked_add(len).map_or(true, |end| end > n_bytes) {
+            return Err(anyhow::anyhow!(
+                "the entry `{}` exceeds the container",
+                entry.name()
+            ));
+        }
+
+        Ok(Self {
+            start,
+            len,
+            deflated,
+        })
+    }
+
+    /// Reads the decompressed data of the entry, sharing the bytes of the container
+    fn reader(self, bytes: &ContainerBytes) -> Box<dyn Read> {
+        let data = SharedSlice {
+            bytes: bytes.clone(),
+            pos: self.start,
+            end: self.start + self.len,
+        };
+
+        if self.deflated {
+            Box::new(flate2::read::DeflateDecoder::new(data))
+        } else {
+            Box::new(data)
+        }
+    }
+}
+
+/// Reads a range of the container bytes
+struct SharedSlice {
+    bytes: ContainerBytes,
+    pos: usize,
+    end: usize,
+}
+
+impl Read for SharedSlice {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data = &self.bytes[self.pos..self.end];
+        let n = data.len().min(buf.len());
+        buf[..n].copy_from_slice(&data[..n]);
+        self.pos += n;
+
+        Ok(n)
+    }
+}
+
+/// The state while a container is read
+#[derive(Debug)]
+struct ReadState {
+    bytes: ContainerBytes,
+    chunk_entries: HashMap<u32, EntryData>,
+    asset_entries: HashMap<u64, EntryData>,
+    chunk_refs: HashMap<u32, ChunkRef>,
+}
+
+thread_local! {
+    static WRITE_STATE: RefCell<Option<WriteState>> = const { RefCell::new(None) };
+    static READ_STATE: RefCell<Option<ReadState>> = const { RefCell::new(None) };
+}
+
+/// Sets the state of the container which is written or read, for the serialize and deserialize impls which can't be handed it.
+/// The previous state is put back when the guard is dropped, also on panics and early returns, so a container can be written or read in the middle of another one
+struct StateGuard<T: 'static> {
+    state: &'static LocalKey<RefCell<Option<T>>>,
+    previous: Option<Option<T>>,
+}
+
+impl<T: 'static> StateGuard<T> {
+    fn set(state: &'static LocalKey<RefCell<Option<T>>>, value: T) -> Self {
+        let previous = state.with(|state| state.borrow_mut().replace(value));
+
+        Self {
+            state,
+            previous: Some(previous),
+        }
+    }
+
+    /// Takes the state back out, and puts back the previous one
+    fn finish(mut self) -> Option<T> {
+        let previous = self.previous.take().flatten();
+
+        self.state
+            .with(|state| std::mem::replace(&mut *state.borrow_mut(), previous))
+    }
+}
+
+impl<T: 'static> Drop for StateGuard<T> {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            self.state.with(|state| *state.borrow_mut() = previous);
+        }
+    }
+}
+
+/// Whether the bytes are a container, as opposed to a legacy gzipped json .rnote file
+pub fn is_container(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"PK\x03\x04")
+}
+
+/// Writes the value as sheet into a new container
+pub fn save<T: Serialize>(
+    value: &T,
+    file_format_version: u64,
+) -> Result<ContainerBytes, anyhow::Error> {
+    save_with_cache(value, file_format_version, None, &ChangedChunks::default())
+        .map(|(bytes, _cache)| bytes)
+}
+
+/// Writes the value as sheet into a new container. Chunks which are not in `changed_chunks` and the assets are copied from the cached container, if they are in it.
+/// Returns the container and the cache for the next save, which share the bytes
+pub fn save_with_cache<T: Serialize>(
+    value: &T,
+    file_format_version: u64,
+    cache: Option<&ContainerCache>,
+    changed_chunks: &ChangedChunks,
+) -> Result<(ContainerBytes, ContainerCache), anyhow::Error> {
+    let deflated =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::<u8>::new()));
+
+    zip.start_file(MANIFEST_ENTRY, deflated)?;
+    serde_json::to_writer(
+        &mut zip,
+        &Manifest {
+            file_format_version,
+        },
+    )?;
+
+    let previous = cache
+        .map(|cache| zip::ZipArchive::new(Cursor::new(cache.bytes.clone())))
+        .transpose()?;
+    let state_guard = StateGuard::set(
+        &WRITE_STATE,
+        WriteState {
+            pass: WritePass::Entries,
+            zip: Some(zip),
+            previous,
+            previous_chunks: cache.map(|cache| cache.chunks.clone()).unwrap_or_default(),
+            previous_assets: cache.map(|cache| cache.assets.clone()).unwrap_or_default(),
+            changed_chunks: changed_chunks.clone(),
+            chunk_refs: vec![],
+            rewritten_chunks: vec![],
+            referenced_assets: HashSet::new(),
+            chunk_assets: None,
+            pending_assets: vec![],
+        },
+    );
+
+    let write_res = ciborium::ser::into_writer(value, io::sink())
+        .map_err(|e| anyhow::anyhow!("writing the entries of the sheet failed with Err {:?}", e))
+        .and_then(|()| {
+            let mut zip = WRITE_STATE
+                .with(|state| {
+                    let mut state = state.borrow_mut();
+                    let state = state.as_mut()?;
+                    state.pass = WritePass::Sheet;
+                    state.zip.take()
+                })
+                .context("the container is missing after writing the entries")?;
+
+            zip.start_file(SHEET_ENTRY, deflated)?;
+            ciborium::ser::into_writer(value, &mut zip)
+                .map_err(|e| anyhow::anyhow!("serializing the sheet failed with Err {:?}", e))?;
+            Ok(zip)
+        });
+    let state = state_guard.finish();
+    let mut zip = write_res?;
+    let state = state.context("the state of the written container is missing")?;
+
+    let bytes = ContainerBytes::new(zip.finish()?.into_inner());
+    let cache = ContainerCache {
+        bytes: bytes.clone(),
+        chunks: state
+            .chunk_refs
+            .into_iter()
+            .map(|chunk_ref| (chunk_ref.chunk, chunk_ref))
+            .collect(),
+        assets: state.referenced_assets,
+        rewritten_chunks: state.rewritten_chunks,
+    };
+
+    Ok((bytes, cache))
+}
+
+/// The manifest of the container
+pub fn load_manifest(bytes: &[u8]) -> Result<Manifest, anyhow::Error> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+    let manifest_entry = archive
+        .by_name(MANIFEST_ENTRY)
+        .context("the container has no manifest")?;
+
+    Ok(serde_json::from_reader(manifest_entry)?)
+}
+
+/// Reads the sheet of the container, with `deserialize` reading the sheet entry.
+/// The deserialized references read the chunks and assets out of the container as they come up
+pub fn load<T, F>(bytes: &ContainerBytes, deserialize: F) -> Result<T, anyhow::Error>
+where
+    F: FnOnce(&mut dyn Read) -> Result<T, anyhow::Error>,
+{
+    load_with_cache(bytes, deserialize).map(|(value, _cache)| value)
+}
+
+/// Like `load`, and also returns the cache to save the sheet incrementally into the container again. The cache shares the bytes
+pub fn load_with_cache<T, F>(
+    bytes: &ContainerBytes,
+    deserialize: F,
+) -> Result<(T, ContainerCache), anyhow::Error>
+where
+    F: FnOnce(&mut dyn Read) -> Result<T, anyhow::Error>,
+{
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes.clone()))?;
+
+    let mut state = ReadState {
+        bytes: bytes.clone(),
+        chunk_entries: HashMap::new(),
+        asset_entries: HashMap::new(),
+        chunk_refs: HashMap::new(),
+    };
+    for i in 0..archive.len() {
+        let entry = archive.by_index_raw(i)?;
+        let (is_chunk, index) = if let Some(index) = entry.name().strip_prefix(STROKES_DIR) {
+            (true, index.parse::<u64>().ok())
+        } else if let Some(index) = entry.name().strip_prefix(ASSETS_DIR) {
+            (false, index.parse::<u64>().ok())
+        } else {
+            continue;
+        };
+        let index = match index {
+            Some(index) => index,
+            None => continue,
+        };
+
+        let entry_data = EntryData::of(&entry, bytes.len())?;
+        if is_chunk {
+            state.chunk_entries.insert(index as u32, entry_data);
+        } else {
+            state.asset_entries.insert(index, entry_data);
+        }
+    }
+    let assets = state
+        .asset_entries
+        .keys()
+        .copied()
+        .collect::<HashSet<u64>>();
+
+    let mut sheet_entry = archive
+        .by_name(SHEET_ENTRY)
+        .context("the container has no sheet")?;
+
+    let state_guard = StateGuard::set(&READ_STATE, state);
+    let res = deserialize(&mut sheet_entry);
+    let chunk_refs = state_guard
+        .finish()
+        .map(|state| state.chunk_refs)
+        .unwrap_or_default();
+
+    let cache = ContainerCache {
+        bytes: bytes.clone(),
+        chunks: chunk_refs,
+        assets,
+        rewritten_chunks: vec![],
+    };
+
+    Ok((res?, cache))
+}
+
+/// Deserializes CBOR from the reader
+pub fn from_cbor_reader<T: DeserializeOwned>(reader: &mut dyn Read) -> Result<T, anyhow::Error> {
+    ciborium::de::from_reader(reader)
+        .map_err(|e| anyhow::anyhow!("deserializing the sheet failed with Err {:?}", e))
+}
+
+/// The slot index and version of the key
+fn slot_of_key(key: StrokeKey) -> (u32, u32) {
+    let ffi = key.data().as_ffi();
+
+    ((ffi & 0xffff_ffff) as u32, (ffi >> 32) as u32)
+}
+
+/// The id of an asset. Equal assets get the same id, so they are stored only once and can be copied from the previous container
+fn asset_id(data: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Encodes the CBOR header of an array with the length
+fn cbor_array_header(len: u64) -> Vec<u8> {
+    const ARRAY: u8 = 4 << 5;
+
+    if len < 24 {
+        vec![ARRAY | len as u8]
+    } else if len <= u64::from(u8::MAX) {
+        vec![ARRAY | 24, len as u8]
+    } else if len <= u64::from(u16::MAX) {
+        let mut header = vec![ARRAY | 25];
+        header.extend_from_slice(&(len as u16).to_be_bytes());
+        header
+    } else if len <= u64::from(u32::MAX) {
+        let mut header = vec![ARRAY | 26];
+        header.extend_from_slice(&(len as u32).to_be_bytes());
+        header
+    } else {
+        let mut header = vec![ARRAY | 27];
+        header.extend_from_slice(&len.to_be_bytes());
+        header
+    }
+}
+
+/// A field that is either inline, or a reference to an asset entry of the container
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AssetField {
+    Inline(String),
+    Ref {
+        #[serde(rename = "asset")]
+        asset: u64,
+    },
+}
+
+#[derive(Serialize)]
+struct AssetRef {
+    #[serde(rename = "asset")]
+    asset: u64,
+}
+
+/// Adds the asset to the container that is currently written. The asset is only decoded if it is not in the previous container.
+/// None if no container is written
+fn add_asset<F>(data: &str, decode: F) -> Option<Result<u64, anyhow::Error>>
+where
+    F: FnOnce() -> Result<Vec<u8>, anyhow::Error>,
+{
+    WRITE_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let state = state.as_mut()?;
+        let id = asset_id(data);
+
+        // Written by the first pass
+        if state.pass == WritePass::Sheet {
+            return Some(Ok(id));
+        }
+        if let Some(chunk_assets) = state.chunk_assets.as_mut() {
+            chunk_assets.push(id);
+        }
+        if state.referenced_assets.insert(id) {
+            let asset = if state.previous_assets.contains(&id) {
+                None
+            } else {
+                match decode() {
+                    Ok(asset) => Some(asset),
+                    Err(e) => return Some(Err(e)),
+                }
+            };
+            state.pending_assets.push((id, asset));
+
+            // Outside of the chunks the asset entry can be written right away
+            if state.zip.is_some() && state.chunk_assets.is_none() {
+                if let Err(e) = state.write_pending_assets() {
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        Some(Ok(id))
+    })
+}
+
+/// Decodes the asset of the container that is currently read. Stored assets are decoded straight out of the bytes of the container
+fn decode_asset<T, F>(id: u64, decode: F) -> Result<T, anyhow::Error>
+where
+    F: FnOnce(&[u8]) -> Result<T, anyhow::Error>,
+{
+    let (bytes, entry) = READ_STATE
+        .with(|state| {
+            let state = state.borrow();
+            let state = state.as_ref()?;
+            Some((state.bytes.clone(), *state.asset_entries.get(&id)?))
+        })
+        .ok_or_else(|| anyhow::anyhow!("the asset {} is missing in the container", id))?;
+
+    if entry.deflated {
+        let mut data = Vec::new();
+        entry.reader(&bytes).read_to_end(&mut data)?;
+        decode(&data)
+    } else {
+        decode(&bytes[entry.start..entry.start + entry.len])
+    }
+}
+
+/// For base64 encoded data. In containers the data is stored decoded
+pub mod asset_base64 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(data: &str, serializer: S) -> Result<S::Ok, S::Error> {
+        match add_asset(data, || Ok(base64::decode(data)?)) {
+            Some(id) => AssetRef {
+                asset: id.map_err(serde::ser::Error::custom)?,
+            }
+            .serialize(serializer),
+            None => serializer.serialize_str(data),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        match AssetField::deserialize(deserializer)? {
+            AssetField::Inline(data) => Ok(data),
+            AssetField::Ref { asset } => decode_asset(asset, |data| Ok(base64::encode(data)))
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// For text data, stored as utf8 in containers
+pub mod asset_text {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(data: &str, serializer: S) -> Result<S::Ok, S::Error> {
+        match add_asset(data, || Ok(data.as_bytes().to_vec())) {
+            Some(id) => AssetRef {
+                asset: id.map_err(serde::ser::Error::custom)?,
+            }
+            .serialize(serializer),
+            None => serializer.serialize_str(data),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        match AssetField::deserialize(deserializer)? {
+            AssetField::Inline(data) => Ok(data),
+            AssetField::Ref { asset } => {
+                decode_asset(asset, |data| Ok(String::from(std::str::from_utf8(data)?)))
+                    .map_err(serde::de::Error::custom)
+            }
+        }
+    }
+}
+
+/// For the strokes. In containers they are stored in chunk entries
+pub mod chunked_strokes {
+    use super::*;
+
+    /// The field is either inline, or references the chunks of the container
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StrokesField {
+        Chunked(ChunkedStrokes),
+        Inline(HopSlotMap<StrokeKey, StrokeStyle>),
+    }
+
+    pub fn serialize<S: Serializer>(
+        strokes: &HopSlotMap<StrokeKey, StrokeStyle>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match WRITE_STATE.with(|state| state.borrow().as_ref().map(|state| state.pass)) {
+            None => strokes.serialize(serializer),
+            Some(WritePass::Entries) => {
+                let mut n_strokes = BTreeMap::<u32, u32>::new();
+                for key in strokes.keys() {
+                    *n_strokes
+                        .entry(slot_of_key(key).0 / STROKES_CHUNK_SIZE)
+                        .or_default() += 1;
+                }
+
+                // The slots are serialized by slotmap, so the vacant slots keep their versions
+                strokes
+                    .serialize(&mut ChunksWriter {
+                        n_strokes: &n_strokes,
+                        next_slot: 0,
+                        current: None,
+                    })
+                    .map_err(|e| {
+                        serde::ser::Error::custom(format!(
+                            "writing the strokes chunks failed with Err {:?}",
+                            e
+                        ))
+                    })?;
+
+                // Serialized into the void
+                ChunkedStrokes { chunks: vec![] }.serialize(serializer)
+            }
+            Some(WritePass::Sheet) => {
+                let chunks = WRITE_STATE
+                    .with(|state| {
+                        state
+                            .borrow()
+                            .as_ref()
+                            .map(|state| state.chunk_refs.clone())
+                    })
+                    .unwrap_or_default();
+
+                ChunkedStrokes { chunks }.serialize(serializer)
+            }
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HopSlotMap<StrokeKey, StrokeStyle>, D::Error> {
+        let chunked = match StrokesField::deserialize(deserializer)? {
+            StrokesField::Inline(strokes) => return Ok(strokes),
+            StrokesField::Chunked(chunked) => chunked,
+        };
+
+        let mut chunk_refs = chunked.chunks;
+        chunk_refs.sort_unstable_by_key(|chunk_ref| chunk_ref.chunk);
+
+        let bytes = READ_STATE
+            .with(|state| state.borrow().as_ref().map(|state| state.bytes.clone()))
+            .ok_or_else(|| serde::de::Error::custom("no container is read"))?;
+
+        // The chunks are read one after the other as the slots array, as slotmap serializes it
+        let n_slots = chunk_refs
+            .last()
+            .map_or(1, |chunk_ref| {
+                chunk_ref.chunk * STROKES_CHUNK_SIZE + chunk_ref.n_slots
+            })
+            .max(1);
+        let mut parts = VecDeque::with_capacity(chunk_refs.len() * 2 + 2);
+        parts.push_back(SlotsPart::Encoded(Cursor::new(cbor_array_header(
+            u64::from(n_slots),
+        ))));
+        let mut next_slot = 0;
+
+        for chunk_ref in chunk_refs {
+            let first_slot = chunk_ref.chunk * STROKES_CHUNK_SIZE;
+            if first_slot < next_slot {
+                return Err(serde::de::Error::custom(format!(
+                    "the strokes chunk {} is referenced twice",
+                    chunk_ref.chunk
+                )));
+            }
+            // Earlier saves left out the chunks without strokes
+            if first_slot > next_slot {
+                parts.push_back(SlotsPart::Encoded(Cursor::new(
+                    vacant_slots(first_slot - next_slot).map_err(serde::de::Error::custom)?,
+                )));
+            }
+
+            let entry = READ_STATE
+                .with(|state| {
+                    state.borrow_mut().as_mut().and_then(|state| {
+                        state.chunk_refs.insert(chunk_ref.chunk, chunk_ref.clone());
+                        state.chunk_entries.get(&chunk_ref.chunk).copied()
+                    })
+                })
+                .ok_or_else(|| {
+                    serde::de::Error::custom(format!(
+                        "the strokes chunk {} is missing in the container",
+                        chunk_ref.chunk
+                    ))
+                })?;
+            parts.push_back(SlotsPart::Chunk(entry));
+
+            next_slot = first_slot + chunk_ref.n_slots;
+        }
+        if next_slot == 0 {
+            // The sentinel slot
+            parts.push_back(SlotsPart::Encoded(Cursor::new(
+                vacant_slots(1).map_err(serde::de::Error::custom)?,
+            )));
+        }
+
+        ciborium::de::from_reader(SlotsReader { bytes, parts }).map_err(|e| {
+            serde::de::Error::custom(format!("deserializing the strokes failed with Err {:?}", e))
+        })
+    }
+
+    /// The encoded vacant slots
+    fn vacant_slots(n: u32) -> Result<Vec<u8>, CborSerError> {
+        let mut slots = Vec::new();
+        for _ in 0..n {
+            ciborium::ser::into_writer(
+                &VacantSlot {
+                    value: None,
+                    version: 0,
+                },
+                &mut slots,
+            )?;
+        }
+
+        Ok(slots)
+    }
+
+    /// A part of the slots array
+    enum SlotsPart {
+        Encoded(Cursor<Vec<u8>>),
+        /// A chunk entry, which is opened once it is reached
+        Chunk(EntryData),
+        Open(Box<dyn Read>),
+    }
+
+    /// Reads the slots array out of its parts
+    struct SlotsReader {
+        bytes: ContainerBytes,
+        parts: VecDeque<SlotsPart>,
+    }
+
+    impl Read for SlotsReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            while let Some(part) = self.parts.front_mut() {
+                let n = match part {
+                    SlotsPart::Encoded(encoded) => encoded.read(buf)?,
+                    SlotsPart::Chunk(entry) => {
+                        *part = SlotsPart::Open(entry.reader(&self.bytes));
+                        continue;
+                    }
+                    SlotsPart::Open(reader) => reader.read(buf)?,
+                };
+                if n > 0 || buf.is_empty() {
+                    return Ok(n);
+                }
+                self.parts.pop_front();
+            }
+
+            Ok(0)
+        }
+    }
+
+    /// Writes the slots of the strokes into the chunk entries of the container, or copies the unchanged chunks from the previous container.
+    /// Serializes the slots array of slotmap, every other value is rejected
+    struct ChunksWriter<'a> {
+        /// The number of strokes by chunk
+        n_strokes: &'a BTreeMap<u32, u32>,
+        next_slot: u32,
+        /// The chunk which is serialized. None while the slots of a copied chunk are skipped
+        current: Option<ChunkRef>,
+    }
+
+    impl<'a> ChunksWriter<'a> {
+        fn start_chunk(&mut self, chunk: u32) -> Result<(), CborSerError> {
+            self.current = with_write_state(|state| {
+                let name = format!("{}{}", STROKES_DIR, chunk);
+                let reused = if state.changed_chunks.contains(chunk) {
+                    None
+                } else {
+                    state.previous_chunks.get(&chunk).cloned()
+                };
+                let zip = state
+                    .zip
+                    .as_mut()
+                    .context("the container is not available to write the chunk")?;
+
+                match reused {
+                    Some(chunk_ref) => {
+                        let previous = state
+                            .previous
+                            .as_mut()
+                            .context("a copied chunk has no previous container")?;
+                        zip.raw_copy_file(previous.by_name(&name)?)?;
+
+                        for &id in chunk_ref.assets.iter() {
+                            if state.referenced_assets.insert(id) {
+                                state.pending_assets.push((id, None));
+                            }
+                        }
+                        state.write_pending_assets()?;
+                        state.chunk_refs.push(chunk_ref);
+
+                        Ok(None)
+                    }
+                    None => {
+                        zip.start_file(
+                            name,
+                            zip::write::FileOptions::default()
+                                .compression_method(zip::CompressionMethod::Deflated),
+                        )?;
+                        state.chunk_assets = Some(vec![]);
+
+                        Ok(Some(ChunkRef {
+                            chunk,
+                            n_slots: 0,
+                            n_strokes: self.n_strokes.get(&chunk).copied().unwrap_or(0),
+                            assets: vec![],
+                        }))
+                    }
+                }
+            })?;
+
+            Ok(())
+        }
+
+        fn finish_chunk(&mut self) -> Result<(), CborSerError> {
+            let mut chunk_ref = match self.current.take() {
+                Some(chunk_ref) => chunk_ref,
+                None => return Ok(()),
+            };
+
+            with_write_state(|state| {
+                let mut assets = state.chunk_assets.take().unwrap_or_default();
+                assets.sort_unstable();
+                assets.dedup();
+                chunk_ref.assets = assets;
+
+                state.write_pending_assets()?;
+                state.rewritten_chunks.push(chunk_ref.chunk);
+                state.chunk_refs.push(chunk_ref);
+
+                Ok(())
+            })
+        }
+
+        fn write_slot<T: ?Sized + Serialize>(&mut self, slot: &T) -> Result<(), CborSerError> {
+            // Serialized outside of the borrow of the state, because the assets of the stroke are added to it
+            let mut zip = with_write_state(|state| {
+                state
+                    .zip
+                    .take()
+                    .context("the container is not available to write the slot")
+            })?;
+            let res = ciborium::ser::into_writer(slot, &mut zip);
+            with_write_state(|state| {
+                state.zip = Some(zip);
+                Ok(())
+            })?;
+
+            res
+        }
+    }
+
+    /// Runs the function on the state of the container that is currently written
+    fn with_write_state<T, F>(f: F) -> Result<T, CborSerError>
+    where
+        F: FnOnce(&mut WriteState) -> Result<T, anyhow::Error>,
+    {
+        WRITE_STATE
+            .with(|state| {
+                let mut state = state.borrow_mut();
+                let state = state
+                    .as_mut()
+                    .ok_or_else(|| anyhow::anyhow!("no container is written"))?;
+
+                f(state)
+            })
+            .map_err(|e| ciborium::ser::Error::Value(format!("{:?}", e)))
+    }
+
+    impl<'a, 'b> SerializeSeq for &'b mut ChunksWriter<'a> {
+        type Ok = ();
+        type Error = CborSerError;
+
+        fn serialize_element<T: ?Sized + Serialize>(
+            &mut self,
+            slot: &T,
+        ) -> Result<(), Self::Error> {
+            let index = self.next_slot;
+            self.next_slot += 1;
+
+            if index % STROKES_CHUNK_SIZE == 0 {
+                self.finish_chunk()?;
+                self.start_chunk(index / STROKES_CHUNK_SIZE)?;
+            }
+            if let Some(current) = self.current.as_mut() {
+                current.n_slots += 1;
+                self.write_slot(slot)?;
+            }
+
+            Ok(())
+        }
+
+        fn end(self) -> Result<(), Self::Error> {
+            self.finish_chunk()
+        }
+    }
+
+    macro_rules! reject {
+        ($($method:ident($($arg:ty),*)),* $(,)?) => {
+            $(
+                fn $method(self, $(_: $arg),*) -> Result<(), Self::Error> {
+                    Err(unexpected_value())
+                }
+            )*
+        };
+    }
+
+    fn unexpected_value() -> CborSerError {
+        ciborium::ser::Error::Value(String::from(
+            "the strokes are not serialized as a slots array",
+        ))
+    }
+
+    impl<'a, 'b> Serializer for &'b mut ChunksWriter<'a> {
+        type Ok = ();
+        type Error = CborSerError;
+        type SerializeSeq = Self;
+        type SerializeTuple = Impossible<(), CborSerError>;
+        type SerializeTupleStruct = Impossible<(), CborSerError>;
+        type SerializeTupleVariant = Impossible<(), CborSerError>;
+        type SerializeMap = Impossible<(), CborSerError>;
+        type SerializeStruct = Impossible<(), CborSerError>;
+        type SerializeStructVariant = Impossible<(), CborSerError>;
+
+        fn serialize_seq(self, _len: Option<usize>) -> Result<Self, Self::Error> {
+            Ok(self)
+        }
+
+        reject!(
+            serialize_bool(bool),
+            serialize_i8(i8),
+            serialize_i16(i16),
+            serialize_i32(i32),
+            serialize_i64(i64),
+            serialize_u8(u8),
+            serialize_u16(u16),
+            serialize_u32(u32),
+            serialize_u64(u64),
+            serialize_f32(f32),
+            serialize_f64(f64),
+            serialize_char(char),
+            serialize_str(&str),
+            serialize_bytes(&[u8]),
+            serialize_none(),
+            serialize_unit(),
+            serialize_unit_struct(&'static str),
+            serialize_unit_variant(&'static str, u32, &'static str),
+        );
+
+        fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<(), Self::Error> {
+            Err(unexpected_value())
+        }
+
+        fn serialize_newtype_struct<T: ?Sized + Serialize>(
+            self,
+            _name: &'static str,
+            _value: &T,
+        ) -> Result<(), Self::Error> {
+            Err(unexpected_value())
+        }
+
+        fn serialize_newtype_variant<T: ?Sized + Serialize>(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _value: &T,
+        ) -> Result<(), Self::Error> {
+            Err(unexpected_value())
+        }
+
+        fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+            Err(unexpected_value())
+        }
+
+        fn serialize_tuple_struct(
+            self,
+            _name: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+            Err(unexpected_value())
+        }
+
+        fn serialize_tuple_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+            Err(unexpected_value())
+        }
+
+        fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+            Err(unexpected_value())
+        }
+
+        fn serialize_struct(
+            self,
+            _name: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeStruct, Self::Error> {
+            Err(unexpected_value())
+        }
+
+        fn serialize_struct_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeStructVariant, Self::Error> {
+            Err(unexpected_value())
+        }
+    }
+}
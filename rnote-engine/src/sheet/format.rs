@@ -1,37 +1,41 @@
-use gtk4::{glib, graphene, gsk, Snapshot};
+#[cfg(feature = "ui")]
+use gtk4::{graphene, gsk, Snapshot};
+#[cfg(feature = "ui")]
 use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 
 use crate::compose::color::Color;
+#[cfg(feature = "ui")]
 use crate::compose::geometry::AABBHelpers;
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy, glib::Enum, Serialize, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
 #[repr(u32)]
-#[enum_type(name = "PredefinedFormat")]
+#[cfg_attr(feature = "ui", enum_type(name = "PredefinedFormat"))]
 #[serde(rename = "predefined_format")]
 pub enum PredefinedFormat {
-    #[enum_value(name = "A6", nick = "a6")]
+    #[cfg_attr(feature = "ui", enum_value(name = "A6", nick = "a6"))]
     #[serde(rename = "a6")]
     A6 = 0,
-    #[enum_value(name = "A5", nick = "a5")]
+    #[cfg_attr(feature = "ui", enum_value(name = "A5", nick = "a5"))]
     #[serde(rename = "a5")]
     A5,
-    #[enum_value(name = "A4", nick = "a4")]
+    #[cfg_attr(feature = "ui", enum_value(name = "A4", nick = "a4"))]
     #[serde(rename = "a4")]
     A4,
-    #[enum_value(name = "A3", nick = "a3")]
+    #[cfg_attr(feature = "ui", enum_value(name = "A3", nick = "a3"))]
     #[serde(rename = "a3")]
     A3,
-    #[enum_value(name = "A2", nick = "a2")]
+    #[cfg_attr(feature = "ui", enum_value(name = "A2", nick = "a2"))]
     #[serde(rename = "a2")]
     A2,
-    #[enum_value(name = "US Letter", nick = "us-letter")]
+    #[cfg_attr(feature = "ui", enum_value(name = "US Letter", nick = "us-letter"))]
     #[serde(rename = "us_letter")]
     UsLetter,
-    #[enum_value(name = "US Legal", nick = "us-legal")]
+    #[cfg_attr(feature = "ui", enum_value(name = "US Legal", nick = "us-legal"))]
     #[serde(rename = "us_legal")]
     UsLegal,
-    #[enum_value(name = "Custom", nick = "custom")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Custom", nick = "custom"))]
     #[serde(rename = "custom")]
     Custom,
 }
@@ -42,20 +46,24 @@ impl Default for PredefinedFormat {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy, glib::Enum, Serialize, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
 #[repr(u32)]
-#[enum_type(name = "MeasureUnit")]
+#[cfg_attr(feature = "ui", enum_type(name = "MeasureUnit"))]
 #[serde(rename = "measure_unit")]
 pub enum MeasureUnit {
-    #[enum_value(name = "Pixel", nick = "px")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Pixel", nick = "px"))]
     #[serde(rename = "px")]
     Px = 0,
-    #[enum_value(name = "Millimeter", nick = "mm")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Millimeter", nick = "mm"))]
     #[serde(rename = "mm")]
     Mm,
-    #[enum_value(name = "Centimeter", nick = "cm")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Centimeter", nick = "cm"))]
     #[serde(rename = "cm")]
     Cm,
+    #[cfg_attr(feature = "ui", enum_value(name = "Inch", nick = "in"))]
+    #[serde(rename = "in")]
+    In,
 }
 
 impl Default for MeasureUnit {
@@ -78,25 +86,38 @@ impl MeasureUnit {
             MeasureUnit::Px => value,
             MeasureUnit::Mm => (value / Self::AMOUNT_MM_IN_INCH) * value_dpi,
             MeasureUnit::Cm => ((value * 10.0) / Self::AMOUNT_MM_IN_INCH) * value_dpi,
+            MeasureUnit::In => value * value_dpi,
         };
 
         match desired_unit {
             MeasureUnit::Px => value_in_px,
             MeasureUnit::Mm => (value_in_px / desired_dpi) * Self::AMOUNT_MM_IN_INCH,
-            MeasureUnit::Cm => (value_in_px / desired_dpi) * Self::AMOUNT_MM_IN_INCH * 10.0,
+            MeasureUnit::Cm => (value_in_px / desired_dpi) * Self::AMOUNT_MM_IN_INCH / 10.0,
+            MeasureUnit::In => value_in_px / desired_dpi,
+        }
+    }
+
+    /// The abbreviation of the unit, as shown next to measurements
+    pub fn nick(self) -> String {
+        match self {
+            MeasureUnit::Px => String::from("px"),
+            MeasureUnit::Mm => String::from("mm"),
+            MeasureUnit::Cm => String::from("cm"),
+            MeasureUnit::In => String::from("in"),
         }
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy, glib::Enum, Serialize, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
 #[repr(u32)]
-#[enum_type(name = "FormatOrientation")]
+#[cfg_attr(feature = "ui", enum_type(name = "FormatOrientation"))]
 #[serde(rename = "orientation")]
 pub enum Orientation {
-    #[enum_value(name = "Portrait", nick = "portrait")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Portrait", nick = "portrait"))]
     #[serde(rename = "portrait")]
     Portrait = 0,
-    #[enum_value(name = "Landscape", nick = "landscape")]
+    #[cfg_attr(feature = "ui", enum_value(name = "Landscape", nick = "landscape"))]
     #[serde(rename = "landscape")]
     Landscape,
 }
@@ -151,10 +172,8 @@ impl Format {
         a: 1.0,
     };
 
+    #[cfg(feature = "ui")]
     pub fn draw(&self, sheet_bounds: AABB, snapshot: &Snapshot, zoom: f64) {
-        let border_radius = graphene::Size::new(0.0, 0.0);
-        let border_width = 1_f32;
-
         snapshot.push_clip(
             &sheet_bounds
                 .scale(na::Vector2::from_element(zoom))
@@ -165,27 +184,36 @@ impl Format {
             sheet_bounds.split_extended_origin_aligned(na::vector![self.width, self.height]);
 
         for page_bounds in pages_bounds {
-            let rounded_rect = gsk::RoundedRect::new(
-                page_bounds
-                    .scale(na::Vector2::from_element(zoom))
-                    .to_graphene_rect(),
-                border_radius.clone(),
-                border_radius.clone(),
-                border_radius.clone(),
-                border_radius.clone(),
-            );
-            snapshot.append_border(
-                &rounded_rect,
-                &[border_width, border_width, border_width, border_width],
-                &[
-                    Self::FORMAT_BORDER_COLOR.to_gdk(),
-                    Self::FORMAT_BORDER_COLOR.to_gdk(),
-                    Self::FORMAT_BORDER_COLOR.to_gdk(),
-                    Self::FORMAT_BORDER_COLOR.to_gdk(),
-                ],
-            );
+            Self::draw_page_border(page_bounds, snapshot, zoom);
         }
 
         snapshot.pop();
     }
+
+    /// Draws the border of a single page
+    #[cfg(feature = "ui")]
+    pub fn draw_page_border(page_bounds: AABB, snapshot: &Snapshot, zoom: f64) {
+        let border_radius = graphene::Size::new(0.0, 0.0);
+        let border_width = 1_f32;
+
+        let rounded_rect = gsk::RoundedRect::new(
+            page_bounds
+                .scale(na::Vector2::from_element(zoom))
+                .to_graphene_rect(),
+            border_radius.clone(),
+            border_radius.clone(),
+            border_radius.clone(),
+            border_radius,
+        );
+        snapshot.append_border(
+            &rounded_rect,
+            &[border_width, border_width, border_width, border_width],
+            &[
+                Self::FORMAT_BORDER_COLOR.to_gdk(),
+                Self::FORMAT_BORDER_COLOR.to_gdk(),
+                Self::FORMAT_BORDER_COLOR.to_gdk(),
+                Self::FORMAT_BORDER_COLOR.to_gdk(),
+            ],
+        );
+    }
 }
@@ -0,0 +1,246 @@
+use anyhow::Context;
+use serde_json::json;
+
+/// The version of the .rnote file format that is written when saving.
+/// Increase it and append a migration to `MIGRATIONS` whenever fields of the serialized structs are added or changed, so older app versions refuse the files instead of dropping the fields
+pub const FILE_FORMAT_VERSION: u64 = 16;
+
+/// The first version that derives the seeds of textured stroke segments independently, see `migrate_v15_to_v16()`
+pub const SEGMENT_SEEDS_VERSION: u64 = 16;
+
+/// A migration upgrades the json of a sheet from one file format version to the next
+type Migration = fn(serde_json::Value) -> Result<serde_json::Value, anyhow::Error>;
+
+/// The migration at index `n` upgrades files from version `n` to version `n + 1`
+const MIGRATIONS: [Migration; FILE_FORMAT_VERSION as usize] = [
+    migrate_v0_to_v1,
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+    migrate_v4_to_v5,
+    migrate_v5_to_v6,
+    migrate_v6_to_v7,
+    migrate_v7_to_v8,
+    migrate_v8_to_v9,
+    migrate_v9_to_v10,
+    migrate_v10_to_v11,
+    migrate_v11_to_v12,
+    migrate_v12_to_v13,
+    migrate_v13_to_v14,
+    migrate_v14_to_v15,
+    migrate_v15_to_v16,
+];
+
+/// The file format version of the json of a sheet. Files saved before the version was introduced are version 0
+pub fn file_format_version(sheet: &serde_json::Value) -> u64 {
+    sheet
+        .get("file_format_version")
+        .and_then(|version| version.as_u64())
+        .unwrap_or(0)
+}
+
+/// Errors for files from newer versions, because fields unknown to this version would be dropped silently
+pub fn check_supported(file_format_version: u64) -> Result<(), anyhow::Error> {
+    if file_format_version > FILE_FORMAT_VERSION {
+        return Err(anyhow::anyhow!(
+            "the file has the file format version {}, which is newer than the supported version {}. Update the app to open it",
+            file_format_version,
+            FILE_FORMAT_VERSION
+        ));
+    }
+
+    Ok(())
+}
+
+/// Upgrades the json of a sheet to the current file format version by applying the migrations one after the other.
+/// Files from newer versions are refused
+pub fn migrate_sheet_json(
+    mut sheet: serde_json::Value,
+) -> Result<serde_json::Value, anyhow::Error> {
+    if !sheet.is_object() {
+        return Err(anyhow::anyhow!("the sheet is not a json object"));
+    }
+    let version = file_format_version(&sheet);
+    check_supported(version)?;
+
+    for (from_version, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        sheet = migration(sheet).with_context(|| {
+            format!(
+                "migrating the sheet from file format version {} to {} failed",
+                from_version,
+                from_version + 1
+            )
+        })?;
+        sheet["file_format_version"] = json!(from_version + 1);
+    }
+
+    Ok(sheet)
+}
+
+/// The number of strokes in the json of a sheet, to verify that none got lost when loading.
+/// In containers the strokes can be references to their chunks, which record their number of strokes
+pub fn count_strokes(sheet: &serde_json::Value) -> usize {
+    let strokes = &sheet["strokes_state"]["strokes"];
+
+    if let Some(chunks) = strokes.get("chunks").and_then(|chunks| chunks.as_array()) {
+        return chunks
+            .iter()
+            .filter_map(|chunk| chunk.get("n_strokes").and_then(|n| n.as_u64()))
+            .sum::<u64>() as usize;
+    }
+
+    strokes
+        .as_array()
+        .map(|slots| {
+            slots
+                .iter()
+                .filter(|slot| slot.get("value").map_or(false, |value| !value.is_null()))
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+/// Files saved before the file format version existed. Only the ones from app versions `0.3.x` need changes:
+/// marker strokes became brush strokes, and the smooth and rough styles were renamed
+/// The layers, groups and links of the strokes were added before version 1; files without them load with the defaults
+fn migrate_v0_to_v1(mut sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    let is_v0_3 = sheet["version"]
+        .as_str()
+        .map_or(false, |version| version.starts_with("0.3."));
+    if !is_v0_3 {
+        return Ok(sheet);
+    }
+
+    let strokes = match sheet
+        .pointer_mut("/strokes_state/strokes")
+        .and_then(|strokes| strokes.as_array_mut())
+    {
+        Some(strokes) => strokes,
+        None => return Ok(sheet),
+    };
+
+    for stroke in strokes.iter_mut() {
+        let val = match stroke.get_mut("value") {
+            Some(val) if !val.is_null() => val,
+            _ => continue,
+        };
+        let val_obj = val
+            .as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("stroke is not an object"))?;
+
+        if let Some(mut bstroke) = val_obj.remove("markerstroke") {
+            let brushstroke = bstroke
+                .as_object_mut()
+                .ok_or_else(|| anyhow::anyhow!("markerstroke is not an object"))?;
+            let options = brushstroke
+                .remove("marker")
+                .ok_or_else(|| anyhow::anyhow!("markerstroke has no marker options"))?;
+            brushstroke.insert(
+                "style".to_string(),
+                json!({
+                    "marker": {
+                        "options": options
+                    }
+                }),
+            );
+
+            val_obj.insert(String::from("brushstroke"), bstroke);
+        } else if let Some(style) = val.pointer_mut("/brushstroke/style") {
+            if let Some(style) = style.as_object_mut() {
+                if let Some(solid_settings) = style.remove("smooth") {
+                    style.insert("solid".to_string(), solid_settings);
+                }
+            }
+        }
+
+        if let Some(drawstyle) = val.pointer_mut("/shapestroke/drawstyle") {
+            let drawstyle = drawstyle
+                .as_object_mut()
+                .ok_or_else(|| anyhow::anyhow!("shapestroke drawstyle is not an object"))?;
+
+            if let Some(smooth) = drawstyle.remove("Smooth") {
+                drawstyle.insert(String::from("smooth"), smooth);
+            } else if let Some(rough) = drawstyle.remove("Rough") {
+                drawstyle.insert(String::from("rough"), rough);
+            }
+        }
+    }
+
+    Ok(sheet)
+}
+
+/// Version 2 moved the sheet into the container, see `container`. The json of the sheet itself is unchanged
+fn migrate_v1_to_v2(sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(sheet)
+}
+
+/// Version 3 moved the strokes in containers into chunk entries, see `container::chunked_strokes`. The json of the sheet itself is unchanged
+fn migrate_v2_to_v3(sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(sheet)
+}
+
+/// Version 4 added the palette of the document. Older files have none
+fn migrate_v3_to_v4(sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(sheet)
+}
+
+/// Version 5 added the page layout of sheets made up of discrete pages. Older files have none, they keep the endless sheet
+fn migrate_v4_to_v5(sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(sheet)
+}
+
+/// Version 6 added the isometric, hexagon, music staff and custom tile background patterns, and the svg tile of the custom tile pattern. Older files have none of them
+fn migrate_v5_to_v6(sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(sheet)
+}
+
+/// Version 7 added the background regions replacing the background in parts of the sheet. Older files have none
+fn migrate_v6_to_v7(sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(sheet)
+}
+
+/// Version 8 added the extension strokes of composers from plugins. Older files have none
+fn migrate_v7_to_v8(sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(sheet)
+}
+
+/// Version 9 added the recording of the session to the strokes state. Older files have none
+fn migrate_v8_to_v9(sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(sheet)
+}
+
+/// Version 10 added the audio notes to the strokes state, and when the strokes were created to their chrono components. Older files have neither
+fn migrate_v9_to_v10(sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(sheet)
+}
+
+/// Version 11 added the files attached to the sheet. Older files have none
+fn migrate_v10_to_v11(sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(sheet)
+}
+
+/// Version 12 added the bookmarks of the sheet. Older files have none
+fn migrate_v11_to_v12(sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(sheet)
+}
+
+/// Version 13 added the heading level to the style of texts. Texts of older files are body text
+fn migrate_v12_to_v13(sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(sheet)
+}
+
+/// Version 14 added the tags of the strokes. Strokes of older files have none
+fn migrate_v13_to_v14(sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(sheet)
+}
+
+/// Version 15 added the checkbox strokes. Older files have none
+fn migrate_v14_to_v15(sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(sheet)
+}
+
+/// Version 16 derives the seed of every segment of textured strokes from its index, and added the legacy seeding flag to the textured options.
+/// The textured strokes of older files get flagged when the sheet is loaded, so they keep the dots they were saved with
+fn migrate_v15_to_v16(sheet: serde_json::Value) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(sheet)
+}
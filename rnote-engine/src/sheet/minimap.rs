@@ -0,0 +1,482 @@
+//! A scaled-down rendering of the whole sheet with the viewport drawn on top, to navigate large sheets quickly.
+//! Like the tile cache of the strokes ( see `strokesstate::tilecache` ), the minimap is split into a grid of tiles which are cached
+//! with the signature of their content. When the sheet changes, only the tiles whose signature changed are rendered again on the threadpool.
+//! The zoom of the minimap is a power of two, so growing the sheet only renders every tile again when the sheet doubles its size.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use futures::channel::mpsc;
+use futures::StreamExt;
+#[cfg(feature = "ui")]
+use gtk4::{graphene, gsk, Snapshot};
+use p2d::bounding_volume::AABB;
+
+use super::Sheet;
+use crate::compose::color::Color;
+use crate::compose::geometry::AABBHelpers;
+use crate::render;
+use crate::utils;
+
+/// The index of a tile of the minimap. The tile with index (0, 0) begins at the origin of the sheet
+pub type TileIndex = na::Vector2<i64>;
+
+/// A tile of the minimap
+#[derive(Debug, Clone)]
+pub struct MinimapTile {
+    pub index: TileIndex,
+    /// The signature of the content the tile was rendered from
+    pub signature: u64,
+    /// The rendered tile, with the tile bounds clipped to the sheet in sheet coordinates
+    pub image: render::Image,
+    /// The image as texture, in the coordinates of the sheet scaled by the zoom of the minimap
+    #[cfg(feature = "ui")]
+    rendernode: Option<gsk::RenderNode>,
+}
+
+/// A tile which finished rendering on the threadpool. Render nodes can't be sent between threads, so they are created when the tile is received
+#[derive(Debug)]
+struct RenderedTile {
+    index: TileIndex,
+    signature: u64,
+    image: render::Image,
+}
+
+/// Renders and caches the minimap of a sheet
+#[derive(Debug)]
+pub struct Minimap {
+    size: u32,
+    with_background: bool,
+    zoom: f64,
+    sheet_bounds: AABB,
+    /// The rendered tiles. They are kept until the tile is rendered again, so they might be outdated
+    tiles: HashMap<TileIndex, MinimapTile>,
+    /// The signatures of the tiles which are being rendered
+    pending: HashMap<TileIndex, u64>,
+    tx: mpsc::UnboundedSender<Result<RenderedTile, TileIndex>>,
+    rx: mpsc::UnboundedReceiver<Result<RenderedTile, TileIndex>>,
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Self::new(Self::SIZE_DEFAULT)
+    }
+}
+
+impl Minimap {
+    pub const SIZE_MIN: u32 = 32;
+    pub const SIZE_MAX: u32 = 2048;
+    pub const SIZE_DEFAULT: u32 = 256;
+    /// The size of the tiles, in pixels of the minimap
+    pub const TILE_SIZE: f64 = 128.0;
+    /// The width of the border of the viewport rectangle, in pixels of the minimap
+    pub const VIEWPORT_BORDER_WIDTH: f64 = 2.0;
+    pub const VIEWPORT_COLOR_DEFAULT: Color = Color {
+        r: 0.2,
+        g: 0.5,
+        b: 0.9,
+        a: 1.0,
+    };
+
+    /// A new minimap, which fits into a square with the size in pixels
+    pub fn new(size: u32) -> Self {
+        let (tx, rx) = mpsc::unbounded();
+
+        Self {
+            size: size.clamp(Self::SIZE_MIN, Self::SIZE_MAX),
+            with_background: true,
+            zoom: 0.0,
+            sheet_bounds: AABB::new_zero(),
+            tiles: HashMap::new(),
+            pending: HashMap::new(),
+            tx,
+            rx,
+        }
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Changes the size of the minimap in pixels. The tiles are rendered again on the next update when the zoom changes
+    pub fn set_size(&mut self, size: u32) {
+        self.size = size.clamp(Self::SIZE_MIN, Self::SIZE_MAX);
+    }
+
+    pub fn with_background(&self) -> bool {
+        self.with_background
+    }
+
+    pub fn set_with_background(&mut self, with_background: bool) {
+        if with_background != self.with_background {
+            self.with_background = with_background;
+            self.invalidate_all();
+        }
+    }
+
+    /// The zoom of the minimap of the last update. Zero before the first update
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    /// The bounds of the sheet of the last update
+    pub fn sheet_bounds(&self) -> AABB {
+        self.sheet_bounds
+    }
+
+    /// The size of the minimap in pixels, which is at most `size()` in both directions
+    pub fn extents(&self) -> na::Vector2<f64> {
+        self.sheet_bounds.extents() * self.zoom
+    }
+
+    /// The rendered tiles, which might be outdated until they are rendered again
+    pub fn tiles(&self) -> impl Iterator<Item = &MinimapTile> {
+        self.tiles.values()
+    }
+
+    pub fn tile(&self, index: TileIndex) -> Option<&MinimapTile> {
+        self.tiles.get(&index)
+    }
+
+    /// The number of tiles which are being rendered
+    pub fn n_pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Discards the tiles, so the whole minimap is rendered again on the next update
+    pub fn invalidate_all(&mut self) {
+        self.tiles.clear();
+        self.pending.clear();
+    }
+
+    /// The zoom for a minimap of the size, rounded down to a power of two so the zoom only changes when the sheet doubles or halves its size
+    pub fn zoom_for_bounds(size: u32, sheet_bounds: AABB) -> f64 {
+        let max_extent = sheet_bounds.extents().max().max(1.0);
+
+        2.0_f64.powf((f64::from(size) / max_extent).log2().floor())
+    }
+
+    /// The bounds of the tile with the index at the zoom, in the coordinate space of the sheet
+    pub fn tile_bounds(index: TileIndex, zoom: f64) -> AABB {
+        let size = Self::TILE_SIZE / zoom;
+        let mins = na::point![index[0] as f64 * size, index[1] as f64 * size];
+
+        AABB::new(mins, mins + na::Vector2::from_element(size))
+    }
+
+    /// The indices of the first and the last tile (inclusive) which the bounds intersect at the zoom
+    pub fn tile_index_range(bounds: AABB, zoom: f64) -> (TileIndex, TileIndex) {
+        let size = Self::TILE_SIZE / zoom;
+
+        (
+            na::vector![
+                (bounds.mins[0] / size).floor() as i64,
+                (bounds.mins[1] / size).floor() as i64
+            ],
+            na::vector![
+                (bounds.maxs[0] / size).floor() as i64,
+                (bounds.maxs[1] / size).floor() as i64
+            ],
+        )
+    }
+
+    /// Takes the finished tiles, and starts rendering the tiles which are missing or outdated on the threadpool.
+    /// Expected to be called periodically, e.g. when the sheet changed. Returns whether tiles were updated
+    pub fn update(&mut self, sheet: &Sheet) -> bool {
+        let mut updated = self.receive_finished();
+
+        let sheet_bounds = sheet.bounds();
+        let zoom = Self::zoom_for_bounds(self.size, sheet_bounds);
+        if zoom != self.zoom {
+            self.zoom = zoom;
+            self.invalidate_all();
+            updated = true;
+        }
+        if sheet_bounds != self.sheet_bounds {
+            self.sheet_bounds = sheet_bounds;
+            updated = true;
+        }
+
+        // Discards the tiles which are outside of the sheet
+        let (first, last) = Self::tile_index_range(sheet_bounds, zoom);
+        let in_range = |index: &TileIndex| {
+            index[0] >= first[0]
+                && index[1] >= first[1]
+                && index[0] <= last[0]
+                && index[1] <= last[1]
+        };
+        let n_tiles = self.tiles.len();
+        self.tiles.retain(|index, _| in_range(index));
+        self.pending.retain(|index, _| in_range(index));
+        updated |= n_tiles != self.tiles.len();
+
+        let background_signature = sheet.background_signature();
+        let mut outdated = vec![];
+        for x in first[0]..=last[0] {
+            for y in first[1]..=last[1] {
+                let index = na::vector![x, y];
+                let bounds = match clip_bounds(Self::tile_bounds(index, zoom), sheet_bounds) {
+                    Some(bounds) => bounds,
+                    None => continue,
+                };
+                let signature = tile_signature(sheet, bounds, background_signature);
+                let current = self
+                    .tiles
+                    .get(&index)
+                    .map_or(false, |tile| tile.signature == signature);
+
+                if !current && self.pending.get(&index) != Some(&signature) {
+                    outdated.push((index, bounds, signature));
+                }
+            }
+        }
+        if outdated.is_empty() {
+            return updated;
+        }
+
+        let background_svgs = if self.with_background {
+            match sheet.gen_background_svgs() {
+                Ok(background_svgs) => Some(background_svgs),
+                Err(e) => {
+                    log::error!(
+                        "gen_background_svgs() failed in Minimap update() with Err {}",
+                        e
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        for (index, bounds, signature) in outdated {
+            let layers = sheet
+                .strokes_state
+                .clone_strokes_for_bounds_by_layer(bounds);
+            let background_svgs = background_svgs.clone();
+            let tx = self.tx.clone();
+            self.pending.insert(index, signature);
+
+            // Rendered on the threadpool to not block the UI
+            utils::spawn(move || {
+                let image = render::cairo_drawing_to_image(bounds, zoom, |cx| {
+                    if let Some(background_svgs) = &background_svgs {
+                        render::draw_svgs_to_cairo_context(1.0, background_svgs, sheet_bounds, cx)?;
+                    }
+
+                    super::draw_layers_to_cairo(cx, &layers)
+                });
+
+                let result = match image {
+                    Ok(image) => Ok(RenderedTile {
+                        index,
+                        signature,
+                        image,
+                    }),
+                    Err(e) => {
+                        log::error!(
+                            "rendering the minimap tile {:?} failed with Err {}",
+                            index,
+                            e
+                        );
+                        Err(index)
+                    }
+                };
+                if let Err(e) = tx.unbounded_send(result) {
+                    log::error!("sending the minimap tile {:?} failed with Err {}", index, e);
+                }
+            });
+        }
+
+        updated
+    }
+
+    /// The viewport in the coordinates of the minimap
+    pub fn viewport_rect(&self, viewport: AABB) -> AABB {
+        AABB::new(
+            ((viewport.mins.coords - self.sheet_bounds.mins.coords) * self.zoom).into(),
+            ((viewport.maxs.coords - self.sheet_bounds.mins.coords) * self.zoom).into(),
+        )
+    }
+
+    /// The position in the coordinates of the minimap converted to the coordinate space of the sheet
+    pub fn minimap_to_sheet_coords(&self, pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        if self.zoom > 0.0 {
+            self.sheet_bounds.mins.coords + pos / self.zoom
+        } else {
+            self.sheet_bounds.mins.coords
+        }
+    }
+
+    /// The viewport moved to be centered at the position in the coordinates of the minimap, e.g. when the minimap is clicked.
+    /// It is kept inside the sheet where it fits
+    pub fn viewport_centered_at(&self, pos: na::Vector2<f64>, viewport: AABB) -> AABB {
+        let extents = viewport.extents();
+        let center = self.minimap_to_sheet_coords(pos);
+        let mut mins = center - extents * 0.5;
+
+        for i in 0..2 {
+            if extents[i] < self.sheet_bounds.extents()[i] {
+                mins[i] = mins[i]
+                    .max(self.sheet_bounds.mins[i])
+                    .min(self.sheet_bounds.maxs[i] - extents[i]);
+            } else {
+                mins[i] = self.sheet_bounds.mins[i];
+            }
+        }
+
+        AABB::new(mins.into(), (mins + extents).into())
+    }
+
+    /// Draws the tiles and the viewport rectangle to the snapshot. Its origin is the upper left corner of the minimap
+    #[cfg(feature = "ui")]
+    pub fn draw(&self, snapshot: &Snapshot, viewport: Option<AABB>, viewport_color: Color) {
+        let extents = self.extents();
+        snapshot.push_clip(&graphene::Rect::new(
+            0.0,
+            0.0,
+            extents[0] as f32,
+            extents[1] as f32,
+        ));
+        snapshot.save();
+        snapshot.translate(&graphene::Point::new(
+            (-self.sheet_bounds.mins[0] * self.zoom) as f32,
+            (-self.sheet_bounds.mins[1] * self.zoom) as f32,
+        ));
+
+        for tile in self.tiles.values() {
+            if let Some(rendernode) = &tile.rendernode {
+                snapshot.append_node(rendernode);
+            }
+        }
+
+        snapshot.restore();
+
+        if let Some(viewport) = viewport {
+            let viewport_rect = self.viewport_rect(viewport);
+            let border_width = Self::VIEWPORT_BORDER_WIDTH as f32;
+
+            let rounded_rect = gsk::RoundedRect::new(
+                viewport_rect.to_graphene_rect(),
+                graphene::Size::zero(),
+                graphene::Size::zero(),
+                graphene::Size::zero(),
+                graphene::Size::zero(),
+            );
+
+            snapshot.append_border(
+                &rounded_rect,
+                &[border_width, border_width, border_width, border_width],
+                &[
+                    viewport_color.to_gdk(),
+                    viewport_color.to_gdk(),
+                    viewport_color.to_gdk(),
+                    viewport_color.to_gdk(),
+                ],
+            );
+        }
+
+        snapshot.pop();
+    }
+
+    /// Takes the tiles which finished rendering. Tiles of outdated renderings are dropped
+    fn receive_finished(&mut self) -> bool {
+        let mut updated = false;
+
+        while let Ok(Some(result)) = self.rx.try_next() {
+            updated |= self.receive(result);
+        }
+
+        updated
+    }
+
+    /// Blocks until the tiles which are being rendered are finished, and takes them. Returns whether tiles were updated
+    pub fn wait_for_pending(&mut self) -> bool {
+        let mut updated = false;
+
+        while !self.pending.is_empty() {
+            match futures::executor::block_on(self.rx.next()) {
+                Some(result) => updated |= self.receive(result),
+                None => break,
+            }
+        }
+
+        updated
+    }
+
+    /// Takes the rendered tile. Returns whether the tiles were updated
+    fn receive(&mut self, result: Result<RenderedTile, TileIndex>) -> bool {
+        let index = match &result {
+            Ok(tile) => tile.index,
+            Err(index) => *index,
+        };
+        let signature = match self.pending.get(&index) {
+            Some(&signature) => signature,
+            None => return false,
+        };
+
+        match result {
+            Ok(tile) if tile.signature == signature => {
+                self.pending.remove(&index);
+
+                #[cfg(feature = "ui")]
+                let rendernode = match render::image_to_rendernode(&tile.image, self.zoom) {
+                    Ok(rendernode) => Some(rendernode),
+                    Err(e) => {
+                        log::error!(
+                            "image_to_rendernode() failed in Minimap receive() with Err {}",
+                            e
+                        );
+                        None
+                    }
+                };
+                self.tiles.insert(
+                    index,
+                    MinimapTile {
+                        index,
+                        signature,
+                        image: tile.image,
+                        #[cfg(feature = "ui")]
+                        rendernode,
+                    },
+                );
+                true
+            }
+            // A newer rendering of the tile is pending
+            Ok(_) => false,
+            Err(_) => {
+                self.pending.remove(&index);
+                false
+            }
+        }
+    }
+}
+
+/// The signature of the content of the tile. It changes when a stroke in the bounds changes, is added or removed, or when the backgrounds change
+fn tile_signature(sheet: &Sheet, bounds: AABB, background_signature: u64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    background_signature.hash(&mut hasher);
+    for coord in bounds.mins.iter().chain(bounds.maxs.iter()) {
+        coord.to_bits().hash(&mut hasher);
+    }
+
+    sheet
+        .strokes_state
+        .signature_for_bounds(bounds)
+        .hash(&mut hasher);
+
+    hasher.finish()
+}
+
+/// The part of the bounds inside the sheet. None if they don't overlap
+fn clip_bounds(bounds: AABB, sheet_bounds: AABB) -> Option<AABB> {
+    let mins = bounds.mins.sup(&sheet_bounds.mins);
+    let maxs = bounds.maxs.inf(&sheet_bounds.maxs);
+
+    if mins[0] < maxs[0] && mins[1] < maxs[1] {
+        Some(AABB::new(mins, maxs))
+    } else {
+        None
+    }
+}
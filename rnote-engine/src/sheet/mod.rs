@@ -1,33 +1,71 @@
+pub mod attachments;
 pub mod background;
+pub mod bitmapexport;
+pub mod bookmarks;
+pub mod container;
 pub mod format;
-
+pub mod migration;
+#[cfg(feature = "cairo")]
+pub mod minimap;
+pub mod outline;
+pub mod pagelayout;
+pub mod presentation;
+pub mod recovery;
+pub mod replayexport;
+pub mod statistics;
+pub mod template;
+pub mod thumbnails;
+pub mod todos;
+
+#[cfg(feature = "cairo")]
+use std::ops::Range;
 use std::sync::{Arc, RwLock};
 
 use crate::compose;
+use crate::compose::color::Color;
 use crate::compose::geometry::AABBHelpers;
+use crate::compose::palette::Palette;
 use crate::compose::transformable::Transformable;
+use crate::drawbehaviour::DrawBehaviour;
 use crate::render::{self, Renderer};
 use crate::strokes::strokestyle::StrokeStyle;
-use crate::strokesstate::StrokesState;
+use crate::strokesstate::layer_comp::Layer;
+use crate::strokesstate::{StrokeKey, StrokesState};
 use crate::utils;
 use anyhow::Context;
+#[cfg(feature = "cairo")]
 use futures::channel::oneshot;
 use rnote_fileformats::xoppformat;
 use rnote_fileformats::FileFormatLoader;
 use rnote_fileformats::FileFormatSaver;
 
-use self::{background::Background, format::Format};
-
-use gtk4::{glib, Snapshot};
+use self::attachments::Attachment;
+#[cfg(feature = "cairo")]
+use self::bitmapexport::{BitmapExportFormat, BitmapExportPrefs, BitmapExportScope};
+use self::bookmarks::Bookmark;
+use self::container::{ContainerBytes, ContainerCache};
+#[cfg(feature = "cairo")]
+use self::outline::OutlinePrefs;
+use self::pagelayout::{Page, PageLayout};
+use self::statistics::DocumentStatistics;
+use self::{
+    background::{Background, BackgroundRegion},
+    format::Format,
+};
+
+#[cfg(feature = "ui")]
+use gtk4::Snapshot;
 use p2d::bounding_volume::{BoundingVolume, AABB};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(default, rename = "sheet")]
 pub struct Sheet {
     #[serde(rename = "version")]
     pub version: String,
+    /// The version of the file format, see `migration::FILE_FORMAT_VERSION`
+    #[serde(rename = "file_format_version")]
+    pub file_format_version: u64,
     #[serde(rename = "x")]
     pub x: f64,
     #[serde(rename = "y")]
@@ -42,12 +80,31 @@ pub struct Sheet {
     pub format: Format,
     #[serde(rename = "background")]
     pub background: Background,
+    /// Backgrounds which replace the background in parts of the sheet. Later regions are on top of earlier ones
+    #[serde(rename = "background_regions")]
+    pub background_regions: Vec<BackgroundRegion>,
+    /// The palette of the document, offered next to the palettes of the app
+    #[serde(rename = "palette")]
+    pub palette: Option<Palette>,
+    /// The discrete pages the sheet is made up of in the page layout mode. None in the other modes
+    #[serde(rename = "page_layout")]
+    pub page_layout: Option<PageLayout>,
+    /// The attached files, see `attachments`
+    #[serde(rename = "attachments")]
+    pub attachments: Vec<Attachment>,
+    /// The bookmarks, in reading order, see `bookmarks`
+    #[serde(rename = "bookmarks")]
+    pub bookmarks: Vec<Bookmark>,
+    /// The container the sheet was last saved into or opened from, to save incrementally
+    #[serde(skip)]
+    pub container_cache: Option<ContainerCache>,
 }
 
 impl Default for Sheet {
     fn default() -> Self {
         Self {
             version: String::from("0.1"),
+            file_format_version: migration::FILE_FORMAT_VERSION,
             x: 0.0,
             y: 0.0,
             width: Format::default().width,
@@ -55,11 +112,20 @@ impl Default for Sheet {
             strokes_state: StrokesState::default(),
             format: Format::default(),
             background: Background::default(),
+            background_regions: vec![],
+            palette: None,
+            page_layout: None,
+            attachments: vec![],
+            bookmarks: vec![],
+            container_cache: None,
         }
     }
 }
 
 impl Sheet {
+    /// The number of chunks the sheet is padded with around the strokes and the viewport in infinite mode
+    pub const INFINITE_PADDING_CHUNKS: f64 = 2.0;
+
     pub fn bounds(&self) -> AABB {
         AABB::new(
             na::point![self.x, self.y],
@@ -67,6 +133,20 @@ impl Sheet {
         )
     }
 
+    /// The color of the topmost stroke at the position, or the background color when no stroke is hit.
+    /// None if the position is outside of the sheet
+    pub fn color_at_pos(&self, pos: na::Vector2<f64>, tolerance: f64) -> Option<Color> {
+        if !self.bounds().contains_local_point(&na::Point2::from(pos)) {
+            return None;
+        }
+
+        Some(
+            self.strokes_state
+                .color_at_pos(pos, tolerance)
+                .unwrap_or_else(|| self.background_at_pos(pos).color),
+        )
+    }
+
     /// Generates bounds which contain all pages with content, and are extended to fit the format size.
     pub fn bounds_w_content_extended(&self) -> Option<AABB> {
         let bounds = self.pages_bounds_containing_content();
@@ -85,16 +165,35 @@ impl Sheet {
         )
     }
 
-    // Generates bounds for each page for the sheet size, extended to fit the sheet format. May contain many empty pages (in infinite mode)
-    pub fn pages_bounds(&self) -> Vec<AABB> {
-        let sheet_bounds = self.bounds();
+    /// The bounds of the pages of the sheet, row by row from its origin and clipped to its bounds. These are just as many as `calc_n_pages()`.
+    /// With a page layout these are the bounds of its pages
+    pub fn pages(&self) -> Vec<AABB> {
+        if let Some(page_layout) = &self.page_layout {
+            return page_layout.pages_bounds();
+        }
 
-        if self.format.height > 0.0 && self.format.width > 0.0 {
-            sheet_bounds
-                .split_extended_origin_aligned(na::vector![self.format.width, self.format.height])
-        } else {
-            vec![]
+        // Avoid div by 0
+        if self.format.height <= 0.0 || self.format.width <= 0.0 {
+            return vec![];
         }
+
+        let sheet_bounds = self.bounds();
+        let page_size = na::vector![self.format.width, self.format.height];
+        let n_columns = (self.width / self.format.width).round() as u32;
+        let n_rows = (self.height / self.format.height).round() as u32;
+
+        (0..n_rows)
+            .flat_map(|row| {
+                (0..n_columns).map(move |column| {
+                    let mins = sheet_bounds.mins
+                        + na::vector![
+                            f64::from(column) * page_size[0],
+                            f64::from(row) * page_size[1]
+                        ];
+                    AABB::new(mins, (mins + page_size).inf(&sheet_bounds.maxs))
+                })
+            })
+            .collect()
     }
 
     // Generates bounds for each page which is containing content, extended to fit the sheet format
@@ -103,7 +202,17 @@ impl Sheet {
         let keys = self.strokes_state.keys_as_rendered();
         let strokes_bounds = &self.strokes_state.strokes_bounds(&keys);
 
-        if self.format.height > 0.0 && self.format.width > 0.0 {
+        if let Some(page_layout) = &self.page_layout {
+            page_layout
+                .pages_bounds()
+                .into_iter()
+                .filter(|current_page_bounds| {
+                    strokes_bounds
+                        .iter()
+                        .any(|stroke_bounds| stroke_bounds.intersects(current_page_bounds))
+                })
+                .collect::<Vec<AABB>>()
+        } else if self.format.height > 0.0 && self.format.width > 0.0 {
             sheet_bounds
                 .split_extended_origin_aligned(na::vector![self.format.width, self.format.height])
                 .into_iter()
@@ -119,6 +228,10 @@ impl Sheet {
     }
 
     pub fn calc_n_pages(&self) -> u32 {
+        if let Some(page_layout) = &self.page_layout {
+            return page_layout.len() as u32;
+        }
+
         // Avoid div by 0
         if self.format.height > 0.0 && self.format.width > 0.0 {
             (self.width / self.format.width).round() as u32
@@ -128,12 +241,170 @@ impl Sheet {
         }
     }
 
-    /// Generates all containing svgs for the sheet without root or xml header for the entire size
-    pub fn gen_svgs(&self) -> Result<Vec<render::Svg>, anyhow::Error> {
+    /// The index of the page in `pages()` the position belongs to. None if it is not on a page
+    pub fn page_index_at(&self, pos: na::Vector2<f64>) -> Option<usize> {
+        if let Some(page_layout) = &self.page_layout {
+            return page_layout.page_index_at(pos);
+        }
+
+        self.pages()
+            .iter()
+            .position(|page_bounds| page_bounds.contains_local_point(&na::Point2::from(pos)))
+    }
+
+    /// The statistics of the document, e.g. for a dialog of the document properties
+    pub fn statistics(&self) -> DocumentStatistics {
+        DocumentStatistics::new(self)
+    }
+
+    /// The background at the position, which is the topmost background region containing it or the sheet background
+    pub fn background_at_pos(&self, pos: na::Vector2<f64>) -> &Background {
+        self.background_regions
+            .iter()
+            .rev()
+            .find(|region| region.contains_y(pos[1]))
+            .map(|region| &region.background)
+            .unwrap_or(&self.background)
+    }
+
+    /// Adds a background region on top of the others, replacing a region with the same range. Returns its index
+    pub fn add_background_region(&mut self, region: BackgroundRegion) -> usize {
+        self.background_regions.retain(|r| {
+            (r.y_start - region.y_start).abs() > f64::EPSILON
+                || (r.y_end - region.y_end).abs() > f64::EPSILON
+        });
+        self.background_regions.push(region);
+        self.background_regions.len() - 1
+    }
+
+    pub fn remove_background_region(&mut self, index: usize) -> Option<BackgroundRegion> {
+        if index < self.background_regions.len() {
+            Some(self.background_regions.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// The background of the page, for which a background region covering the page is created if there is none yet.
+    /// It starts as a copy of the sheet background. None if there is no page at the index
+    pub fn page_background_mut(&mut self, page_index: usize) -> Option<&mut Background> {
+        let page_bounds = *self.pages().get(page_index)?;
+        let (y_start, y_end) = (page_bounds.mins[1], page_bounds.maxs[1]);
+
+        let index = match self.background_regions.iter().position(|region| {
+            (region.y_start - y_start).abs() <= f64::EPSILON
+                && (region.y_end - y_end).abs() <= f64::EPSILON
+        }) {
+            Some(index) => index,
+            None => self.add_background_region(BackgroundRegion::new(
+                y_start,
+                y_end,
+                self.background.clone(),
+            )),
+        };
+
+        Some(&mut self.background_regions[index].background)
+    }
+
+    /// Removes the background regions covering the page, so it has the sheet background again. Returns true if any were removed
+    pub fn reset_page_background(&mut self, page_index: usize) -> bool {
+        let page_bounds = match self.pages().get(page_index) {
+            Some(page_bounds) => *page_bounds,
+            None => return false,
+        };
+        let n_regions = self.background_regions.len();
+
+        self.background_regions.retain(|region| {
+            (region.y_start - page_bounds.mins[1]).abs() > f64::EPSILON
+                || (region.y_end - page_bounds.maxs[1]).abs() > f64::EPSILON
+        });
+        self.background_regions.len() != n_regions
+    }
+
+    /// Generates the svgs of the sheet background and the background regions on top of it
+    pub fn gen_background_svgs(&self) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let sheet_bounds = self.bounds();
+
+        // Background bounds are still sheet bounds, for alignment
+        let mut svgs = vec![self.background.gen_svg(sheet_bounds.loosened(1.0))?];
+
+        for region in self.background_regions.iter() {
+            if let Some(region_bounds) = region.bounds(sheet_bounds) {
+                svgs.push(region.background.gen_svg(region_bounds)?);
+            }
+        }
+
+        Ok(svgs)
+    }
+
+    /// Regenerates the background images and rendernodes of the sheet background and the background regions
+    pub fn regenerate_backgrounds(
+        &mut self,
+        zoom: f64,
+        viewport: Option<AABB>,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<(), anyhow::Error> {
+        let sheet_bounds = self.bounds();
+
+        self.background.regenerate_background(
+            zoom,
+            sheet_bounds,
+            viewport,
+            Arc::clone(&renderer),
+        )?;
+
+        for region in self.background_regions.iter_mut() {
+            if let Some(region_bounds) = region.bounds(sheet_bounds) {
+                region.background.regenerate_background(
+                    zoom,
+                    region_bounds,
+                    viewport,
+                    Arc::clone(&renderer),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Updates the background rendernodes of the sheet background and the background regions, for example when the sheet size changed
+    #[cfg(feature = "ui")]
+    pub fn update_background_rendernodes(
+        &mut self,
+        zoom: f64,
+        viewport: Option<AABB>,
+    ) -> Result<(), anyhow::Error> {
         let sheet_bounds = self.bounds();
-        let mut svgs = vec![];
 
-        svgs.push(self.background.gen_svg(sheet_bounds.loosened(1.0))?);
+        self.background
+            .update_rendernode(zoom, sheet_bounds, viewport)?;
+
+        for region in self.background_regions.iter_mut() {
+            match region.bounds(sheet_bounds) {
+                Some(region_bounds) => {
+                    region
+                        .background
+                        .update_rendernode(zoom, region_bounds, viewport)?;
+                }
+                None => region.background.clear_rendernode(),
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "ui")]
+    fn draw_backgrounds(&self, snapshot: &Snapshot) {
+        self.background.draw(snapshot);
+
+        for region in self.background_regions.iter() {
+            region.background.draw(snapshot);
+        }
+    }
+
+    /// Generates all containing svgs for the sheet without root or xml header for the entire size
+    pub fn gen_svgs(&self) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let mut svgs = self.gen_background_svgs()?;
 
         svgs.append(&mut self.strokes_state.gen_svgs_all_strokes());
 
@@ -142,11 +413,7 @@ impl Sheet {
 
     /// Generates all containing svgs for the sheet without root or xml header for the given viewport
     pub fn gen_svgs_for_viewport(&self, viewport: AABB) -> Result<Vec<render::Svg>, anyhow::Error> {
-        let sheet_bounds = self.bounds();
-        let mut svgs = vec![];
-
-        // Background bounds are still sheet bounds, for alignment
-        svgs.push(self.background.gen_svg(sheet_bounds.loosened(1.0))?);
+        let mut svgs = self.gen_background_svgs()?;
 
         svgs.append(&mut self.strokes_state.gen_svgs_for_bounds(viewport));
 
@@ -179,41 +446,284 @@ impl Sheet {
         self.height = new_height;
     }
 
+    /// The size of the chunks the sheet is made up of in infinite mode, which is the format size.
+    /// The chunks are aligned to the origin, so the sheet grows and shrinks by whole chunks into every direction, including negative coordinates
+    pub fn infinite_chunk_size(&self) -> na::Vector2<f64> {
+        na::vector![self.format.width.max(1.0), self.format.height.max(1.0)]
+    }
+
+    /// The chunks which make up the sheet in infinite mode
+    pub fn infinite_chunks(&self) -> Vec<AABB> {
+        let chunk_size = self.infinite_chunk_size();
+        let bounds = self.bounds().extend_origin_aligned(chunk_size);
+
+        let n_columns = (bounds.extents()[0] / chunk_size[0]).round() as u32;
+        let n_rows = (bounds.extents()[1] / chunk_size[1]).round() as u32;
+
+        (0..n_rows)
+            .flat_map(|row| {
+                (0..n_columns).map(move |column| {
+                    let mins = bounds.mins
+                        + na::vector![
+                            f64::from(column) * chunk_size[0],
+                            f64::from(row) * chunk_size[1]
+                        ];
+                    AABB::new(mins, mins + chunk_size)
+                })
+            })
+            .collect()
+    }
+
+    /// Resizes the sheet to the chunks around the strokes, which can shrink it
     pub fn resize_sheet_mode_infinite_to_fit_strokes(&mut self) {
-        let padding_horizontal = self.format.width * 2.0;
-        let padding_vertical = self.format.height * 2.0;
+        let chunk_size = self.infinite_chunk_size();
 
         let mut keys = self.strokes_state.keys_as_rendered();
         keys.append(&mut self.strokes_state.selection_keys_as_rendered());
 
-        let new_bounds = if let Some(new_bounds) = self.strokes_state.gen_bounds(&keys) {
-            new_bounds.expand(na::vector![padding_horizontal, padding_vertical])
-        } else {
-            // If sheet is empty, resize to one page with the format size
-            AABB::new(
-                na::point![0.0, 0.0],
-                na::point![self.format.width, self.format.height],
-            )
-            .expand(na::vector![padding_horizontal, padding_vertical])
+        // If sheet is empty, resize around the origin chunk
+        let content_bounds = self
+            .strokes_state
+            .gen_bounds(&keys)
+            .unwrap_or_else(|| AABB::new(na::point![0.0, 0.0], na::Point2::from(chunk_size)));
+
+        self.set_bounds(
+            content_bounds
+                .extend_origin_aligned(chunk_size)
+                .expand(chunk_size * Self::INFINITE_PADDING_CHUNKS),
+        );
+    }
+
+    /// Grows the sheet by the chunks the viewport is getting close to. Returns true if the sheet grew
+    pub fn expand_sheet_mode_infinite_for_viewport(&mut self, viewport: AABB) -> bool {
+        let chunk_size = self.infinite_chunk_size();
+
+        let new_bounds = self.bounds().merged(
+            &viewport
+                .extend_origin_aligned(chunk_size)
+                .expand(chunk_size * Self::INFINITE_PADDING_CHUNKS),
+        );
+        if new_bounds == self.bounds() {
+            return false;
+        }
+
+        self.set_bounds(new_bounds);
+        true
+    }
+
+    /// Enables the page layout mode. A sheet without a page layout gets one with a page of the format size
+    pub fn enable_page_layout(&mut self) {
+        if self.page_layout.is_none() {
+            self.page_layout = Some(PageLayout::new(Page::from_format(&self.format)));
+        }
+    }
+
+    /// Disables the page layout mode. The strokes are kept where they are
+    pub fn disable_page_layout(&mut self) {
+        self.page_layout = None;
+    }
+
+    /// Resizes the sheet to the pages of the page layout. Pages like the last one are appended until all strokes are on pages
+    pub fn resize_sheet_mode_pages(&mut self) {
+        self.enable_page_layout();
+        let content_height = self.strokes_state.calc_height();
+
+        let bounds = self.page_layout.as_mut().and_then(|page_layout| {
+            while let (Some(bounds), Some(last_page)) = (
+                page_layout.bounds(),
+                page_layout.get(page_layout.len().saturating_sub(1)),
+            ) {
+                if bounds.maxs[1] >= content_height {
+                    break;
+                }
+                page_layout.insert_page(page_layout.len(), last_page);
+            }
+
+            page_layout.bounds()
+        });
+
+        if let Some(bounds) = bounds {
+            self.set_bounds(bounds);
+        }
+    }
+
+    /// Inserts the page into the page layout at the index. The strokes on the following pages move down with them.
+    /// Returns false if the sheet has no page layout
+    pub fn insert_page(&mut self, index: usize, page: Page, zoom: f64) -> bool {
+        let mut page_layout = match self.page_layout.clone() {
+            Some(page_layout) => page_layout,
+            None => return false,
         };
-        self.x = new_bounds.mins[0];
-        self.y = new_bounds.mins[1];
-        self.width = new_bounds.extents()[0];
-        self.height = new_bounds.extents()[1];
+        let index = index.min(page_layout.len());
+
+        let new_indices = (0..page_layout.len())
+            .map(|i| Some(if i < index { i } else { i + 1 }))
+            .collect::<Vec<Option<usize>>>();
+        page_layout.insert_page(index, page);
+
+        self.apply_page_layout(page_layout, &new_indices, zoom);
+        true
     }
 
-    pub fn expand_sheet_mode_infinite_for_viewport(&mut self, viewport: AABB) {
-        let padding_horizontal = self.format.width * 2.0;
-        let padding_vertical = self.format.height * 2.0;
+    /// Removes the page at the index from the page layout, together with the strokes on it. The strokes on the following pages move up with them.
+    /// The last page of a layout can't be removed
+    pub fn remove_page(&mut self, index: usize, zoom: f64) -> Option<Page> {
+        let mut page_layout = self.page_layout.clone()?;
+        if page_layout.len() <= 1 {
+            return None;
+        }
 
-        let new_bounds = self
-            .bounds()
-            .merged(&viewport.expand(na::vector![padding_horizontal, padding_vertical]));
+        let new_indices = (0..page_layout.len())
+            .map(|i| match i.cmp(&index) {
+                std::cmp::Ordering::Less => Some(i),
+                std::cmp::Ordering::Equal => None,
+                std::cmp::Ordering::Greater => Some(i - 1),
+            })
+            .collect::<Vec<Option<usize>>>();
+        let removed = page_layout.remove_page(index)?;
 
-        self.x = new_bounds.mins[0];
-        self.y = new_bounds.mins[1];
-        self.width = new_bounds.extents()[0];
-        self.height = new_bounds.extents()[1];
+        self.apply_page_layout(page_layout, &new_indices, zoom);
+        Some(removed)
+    }
+
+    /// Moves the page at the index `from` to the index `to`, together with the strokes on it. Returns false if it could not be moved
+    pub fn move_page(&mut self, from: usize, to: usize, zoom: f64) -> bool {
+        let mut page_layout = match self.page_layout.clone() {
+            Some(page_layout) => page_layout,
+            None => return false,
+        };
+        if !page_layout.move_page(from, to) {
+            return false;
+        }
+
+        let mut order = (0..page_layout.len()).collect::<Vec<usize>>();
+        let moved = order.remove(from);
+        order.insert(to, moved);
+
+        let mut new_indices = vec![None; order.len()];
+        for (new_index, &prev_index) in order.iter().enumerate() {
+            new_indices[prev_index] = Some(new_index);
+        }
+
+        self.apply_page_layout(page_layout, &new_indices, zoom);
+        true
+    }
+
+    /// Replaces the page at the index, for example to change its size or orientation. The strokes on the following pages follow them
+    pub fn set_page(&mut self, index: usize, page: Page, zoom: f64) -> bool {
+        let mut page_layout = match self.page_layout.clone() {
+            Some(page_layout) => page_layout,
+            None => return false,
+        };
+        if !page_layout.set_page(index, page) {
+            return false;
+        }
+
+        let new_indices = (0..page_layout.len())
+            .map(Some)
+            .collect::<Vec<Option<usize>>>();
+
+        self.apply_page_layout(page_layout, &new_indices, zoom);
+        true
+    }
+
+    /// Replaces the page layout, moving the strokes of every page to where the page is in the new layout.
+    /// `new_indices` maps the index of every page of the current layout to its index in the new one, the strokes of pages mapped to None are trashed.
+    /// The strokes are changed in one history step, the page layout itself is not part of the history
+    fn apply_page_layout(
+        &mut self,
+        new_page_layout: PageLayout,
+        new_indices: &[Option<usize>],
+        zoom: f64,
+    ) {
+        let prev_page_layout = match self.page_layout.take() {
+            Some(page_layout) => page_layout,
+            None => return,
+        };
+        let prev_pages_bounds = prev_page_layout.pages_bounds();
+        let new_pages_bounds = new_page_layout.pages_bounds();
+
+        let keys = self
+            .strokes_state
+            .keys_unordered()
+            .into_iter()
+            .filter(|&key| self.strokes_state.trashed(key) == Some(false))
+            .collect::<Vec<StrokeKey>>();
+
+        self.strokes_state.begin_history_group();
+        for key in keys {
+            let center = match self.strokes_state.get_stroke_ref(key) {
+                Some(stroke) => stroke.bounds().center().coords,
+                None => continue,
+            };
+            let prev_index = match prev_page_layout.page_index_at(center) {
+                Some(prev_index) => prev_index,
+                None => continue,
+            };
+
+            match new_indices.get(prev_index).copied().flatten() {
+                Some(new_index) => {
+                    let offset =
+                        new_pages_bounds[new_index].mins - prev_pages_bounds[prev_index].mins;
+                    if offset != na::Vector2::zeros() {
+                        self.strokes_state.translate_strokes(&[key], offset, zoom);
+                    }
+                }
+                None => self.strokes_state.set_trashed(key, true),
+            }
+        }
+        self.strokes_state.end_history_group();
+
+        // The backgrounds of pages follow them as well
+        self.background_regions = std::mem::take(&mut self.background_regions)
+            .into_iter()
+            .filter_map(|mut region| {
+                let prev_index = prev_pages_bounds.iter().position(|page_bounds| {
+                    (region.y_start - page_bounds.mins[1]).abs() <= f64::EPSILON
+                        && (region.y_end - page_bounds.maxs[1]).abs() <= f64::EPSILON
+                });
+
+                match prev_index.map(|prev_index| new_indices.get(prev_index).copied().flatten()) {
+                    Some(Some(new_index)) => {
+                        region.y_start = new_pages_bounds[new_index].mins[1];
+                        region.y_end = new_pages_bounds[new_index].maxs[1];
+                        Some(region)
+                    }
+                    // The page was removed
+                    Some(None) => None,
+                    None => Some(region),
+                }
+            })
+            .collect();
+
+        // And the bookmarks, which are removed with their page
+        self.bookmarks = std::mem::take(&mut self.bookmarks)
+            .into_iter()
+            .filter_map(|mut bookmark| {
+                let prev_index = match prev_page_layout.page_index_at(bookmark.pos) {
+                    Some(prev_index) => prev_index,
+                    None => return Some(bookmark),
+                };
+
+                let new_index = new_indices.get(prev_index).copied().flatten()?;
+                bookmark.pos +=
+                    new_pages_bounds[new_index].mins - prev_pages_bounds[prev_index].mins;
+                Some(bookmark)
+            })
+            .collect();
+
+        if let Some(bounds) = new_page_layout.bounds() {
+            self.set_bounds(bounds);
+        }
+        self.page_layout = Some(new_page_layout);
+        self.sort_bookmarks();
+    }
+
+    fn set_bounds(&mut self, bounds: AABB) {
+        self.x = bounds.mins[0];
+        self.y = bounds.mins[1];
+        self.width = bounds.extents()[0];
+        self.height = bounds.extents()[1];
     }
 
     // a new sheet should always be imported with this method, as to not replace the threadpool, channel handlers, ..
@@ -225,8 +735,15 @@ impl Sheet {
         self.strokes_state.import_strokes_state(sheet.strokes_state);
         self.format = sheet.format;
         self.background = sheet.background;
+        self.background_regions = sheet.background_regions;
+        self.palette = sheet.palette;
+        self.page_layout = sheet.page_layout;
+        self.attachments = sheet.attachments;
+        self.bookmarks = sheet.bookmarks;
+        self.container_cache = sheet.container_cache;
     }
 
+    #[cfg(feature = "ui")]
     pub fn draw(&self, zoom: f64, snapshot: &Snapshot, with_borders: bool) {
         snapshot.push_clip(
             &self
@@ -235,76 +752,105 @@ impl Sheet {
                 .to_graphene_rect(),
         );
 
-        self.background.draw(snapshot);
+        if let Some(page_layout) = &self.page_layout {
+            // Only the pages have a background, the gaps between them stay empty
+            for page_bounds in page_layout.pages_bounds() {
+                snapshot.push_clip(
+                    &page_bounds
+                        .scale(na::Vector2::from_element(zoom))
+                        .to_graphene_rect(),
+                );
+                self.draw_backgrounds(snapshot);
+                snapshot.pop();
 
-        if with_borders {
-            self.format.draw(self.bounds(), snapshot, zoom);
+                if with_borders {
+                    Format::draw_page_border(page_bounds, snapshot, zoom);
+                }
+            }
+        } else {
+            self.draw_backgrounds(snapshot);
+
+            if with_borders {
+                self.format.draw(self.bounds(), snapshot, zoom);
+            }
         }
 
         snapshot.pop();
     }
 
-    pub fn open_sheet_from_rnote_bytes(&mut self, bytes: glib::Bytes) -> Result<(), anyhow::Error> {
-        let decompressed_bytes = utils::decompress_from_gzip(&bytes)?;
-        let mut sheet: serde_json::Value =
-            serde_json::from_str(&String::from_utf8(decompressed_bytes)?)?;
+    /// Opens a sheet from the bytes of a .rnote file. Files of older file format versions are migrated to the current version
+    pub fn open_sheet_from_rnote_bytes<B: AsRef<[u8]> + Send + Sync + 'static>(
+        &mut self,
+        bytes: B,
+    ) -> Result<(), anyhow::Error> {
+        let sheet = if container::is_container(bytes.as_ref()) {
+            // The cache of the container shares the bytes
+            let bytes = ContainerBytes::new(bytes);
+            let file_format_version = container::load_manifest(&bytes)?.file_format_version;
+            migration::check_supported(file_format_version)?;
+
+            if file_format_version == migration::FILE_FORMAT_VERSION {
+                let (mut sheet, cache) =
+                    container::load_with_cache(&bytes, container::from_cbor_reader::<Self>)?;
+                sheet.container_cache = Some(cache);
+                sheet
+            } else {
+                // Older containers are migrated through json
+                container::load(&bytes, |reader| {
+                    let mut sheet = container::from_cbor_reader::<serde_json::Value>(reader)?;
+                    if let Some(sheet) = sheet.as_object_mut() {
+                        sheet.insert(
+                            String::from("file_format_version"),
+                            serde_json::json!(file_format_version),
+                        );
+                    }
+
+                    Self::from_older_json(sheet)
+                })?
+            }
+        } else {
+            // Legacy gzipped json files
+            let decompressed_bytes = utils::decompress_from_gzip(bytes.as_ref())?;
+            let sheet = serde_json::from_slice::<serde_json::Value>(&decompressed_bytes)?;
+            drop(decompressed_bytes);
 
-        sheet = Self::update_sheet_syntax(sheet).unwrap();
+            Self::from_older_json(sheet)?
+        };
 
-        self.import_sheet(serde_json::from_value(sheet)?);
+        self.import_sheet(sheet);
+        // The opened container is the base for the next incremental save
+        self.strokes_state.clear_changed_chunks();
 
         Ok(())
     }
 
-    fn update_sheet_syntax(mut sheet: serde_json::Value) -> Option<serde_json::Value> {
-        if sheet["version"].as_str()?.starts_with("0.3.") {
-            for stroke in sheet["strokes_state"]["strokes"].as_array_mut()? {
-                let val = stroke.get_mut("value")?;
-                if val.is_null() {
-                    continue;
-                }
-
-                if let Some(bstroke) = val.as_object_mut()?.remove("markerstroke") {
-                    val.as_object_mut()?.insert(String::from("brushstroke"), bstroke);
-
-                    let brushstroke = val["brushstroke"].as_object_mut()?;
-                    let options = brushstroke.remove("marker")?;
-                    brushstroke.insert(
-                        "style".to_string(),
-                        json!({
-                            "marker": {
-                                "options": options
-                            }
-                        }),
-                    );
-                } else if let Some(style) = val.pointer_mut("/brushstroke/style") {
-                    let style = style.as_object_mut()?;
-
-                    if let Some(solid_settings) = style.remove("smooth") {
-                        style.insert("solid".to_string(), solid_settings);
-                    }
-                }
-
-                if val.get("shapestroke").is_some() {
-                    let drawstyle = val["shapestroke"]["drawstyle"].as_object_mut()?;
+    /// Migrates the json of a file of an older file format version and deserializes it, verifying that no strokes got lost
+    fn from_older_json(sheet: serde_json::Value) -> Result<Self, anyhow::Error> {
+        let file_format_version = migration::file_format_version(&sheet);
+        let sheet = migration::migrate_sheet_json(sheet)?;
+        let n_strokes = migration::count_strokes(&sheet);
+        let mut sheet: Self = serde_json::from_value(sheet)?;
 
-                    if let Some(smooth) = drawstyle.remove("Smooth") {
-                        drawstyle.insert(String::from("smooth"), smooth);
-                    } else if let Some(smooth) = drawstyle.remove("Rough") {
-                        drawstyle.insert(String::from("rough"), smooth);
-                    }
-                }
-            }
+        if sheet.strokes_state.strokes_len() != n_strokes {
+            return Err(anyhow::anyhow!(
+                "only {} of {} strokes could be loaded from the file",
+                sheet.strokes_state.strokes_len(),
+                n_strokes
+            ));
+        }
+        // The strokes can be references to container chunks in the json, so they are marked after deserializing
+        if file_format_version < migration::SEGMENT_SEEDS_VERSION {
+            sheet.strokes_state.keep_legacy_textured_seeding();
         }
 
-        Some(sheet)
+        Ok(sheet)
     }
 
-    pub fn open_from_xopp_bytes(&mut self, bytes: glib::Bytes) -> Result<(), anyhow::Error> {
+    pub fn open_from_xopp_bytes(&mut self, bytes: impl AsRef<[u8]>) -> Result<(), anyhow::Error> {
         // We set the sheet dpi to the hardcoded xournal++ dpi, so no need to convert values or coordinates anywhere
         self.format.dpi = xoppformat::XoppFile::DPI;
 
-        let xopp_file = xoppformat::XoppFile::load_from_bytes(&bytes)?;
+        let xopp_file = xoppformat::XoppFile::load_from_bytes(bytes.as_ref())?;
 
         // Extract the largest width of all sheets, add together all heights
         let (sheet_width, sheet_height) = xopp_file
@@ -320,7 +866,6 @@ impl Sheet {
 
         let mut sheet = Self::default();
         let mut format = Format::default();
-        let mut background = Background::default();
 
         sheet.x = 0.0;
         sheet.y = 0.0;
@@ -330,16 +875,13 @@ impl Sheet {
         format.width = sheet_width;
         format.height = sheet_height / f64::from(no_pages);
 
-        if let Some(first_page) = xopp_file.xopp_root.pages.get(0) {
-            if let xoppformat::XoppBackgroundType::Solid {
-                color: _color,
-                style: _style,
-            } = &first_page.background.bg_type
-            {
-                // Background styles would not align with Rnotes background patterns, so everything is plain
-                background.pattern = background::PatternStyle::None;
-            }
-        }
+        // The background of the first page is the sheet background, the other pages get background regions where their background differs
+        let background = xopp_file
+            .xopp_root
+            .pages
+            .get(0)
+            .map(|first_page| Background::from_xoppbackground(&first_page.background))
+            .unwrap_or_default();
 
         // Offsetting as rnote has one global coordinate space
         let mut offset = na::Vector2::<f64>::zeros();
@@ -361,6 +903,21 @@ impl Sheet {
                     }
                 }
 
+                // import texts
+                for new_xopptext in layers.texts.into_iter() {
+                    match StrokeStyle::from_xopptext(new_xopptext, offset) {
+                        Ok(new_text) => {
+                            sheet.strokes_state.insert_stroke(new_text);
+                        }
+                        Err(e) => {
+                            log::error!(
+                                "from_xopptext() failed in open_from_xopp_bytes() with Err {}",
+                                e
+                            );
+                        }
+                    }
+                }
+
                 // import images
                 for new_xoppimage in layers.images.into_iter() {
                     match StrokeStyle::from_xoppimage(new_xoppimage, offset) {
@@ -377,6 +934,15 @@ impl Sheet {
                 }
             }
 
+            let page_background = Background::from_xoppbackground(&page.background);
+            if !page_background.draws_like(&background) {
+                sheet.add_background_region(BackgroundRegion::new(
+                    offset[1],
+                    offset[1] + page.height,
+                    page_background,
+                ));
+            }
+
             // Only add to y offset, results in vertical pages
             offset[1] += page.height;
         }
@@ -389,12 +955,61 @@ impl Sheet {
         Ok(())
     }
 
-    pub fn save_sheet_as_rnote_bytes(&self, filename: &str) -> Result<Vec<u8>, anyhow::Error> {
-        let json_output = serde_json::to_string(self)?;
+    /// Saves the sheet into a .rnote container. Only the chunks of the strokes which changed since the sheet was last saved or opened are serialized again,
+    /// the others are copied from the cached container
+    pub fn save_sheet_as_rnote_bytes(&mut self) -> Result<ContainerBytes, anyhow::Error> {
+        let (bytes, cache) = container::save_with_cache(
+            &*self,
+            migration::FILE_FORMAT_VERSION,
+            self.container_cache.as_ref(),
+            self.strokes_state.changed_chunks(),
+        )?;
+        log::debug!(
+            "saved sheet, serialized {} of {} strokes chunks again",
+            cache.rewritten_chunks().len(),
+            cache.n_chunks()
+        );
+
+        self.container_cache = Some(cache);
+        self.strokes_state.clear_changed_chunks();
+
+        Ok(bytes)
+    }
+
+    /// Saves the sheet as template into a new .rnote container. It holds the strokes which are not trashed, the backgrounds, the format, the page layout and the palette.
+    /// The strokes are all placed on one layer
+    pub fn save_as_template_bytes(&self) -> Result<ContainerBytes, anyhow::Error> {
+        let mut template = Self::default();
+        template.set_bounds(self.bounds());
+        template.format = self.format;
+        template.background = self.background.clone();
+        template.background_regions = self.background_regions.clone();
+        template.palette = self.palette.clone();
+        template.page_layout = self.page_layout.clone();
+
+        for stroke in self
+            .strokes_state
+            .clone_strokes_for_keys(&self.strokes_state.keys_as_rendered())
+        {
+            template.strokes_state.insert_stroke(stroke);
+        }
+
+        container::save(&template, migration::FILE_FORMAT_VERSION)
+    }
+
+    /// Replaces the content of the sheet with a new sheet from the bytes of a template.
+    /// Unlike an opened file, the new sheet is not saved incrementally on top of the template and can't be undone back to it
+    pub fn new_from_template_bytes<B: AsRef<[u8]> + Send + Sync + 'static>(
+        &mut self,
+        bytes: B,
+    ) -> Result<(), anyhow::Error> {
+        self.open_sheet_from_rnote_bytes(bytes)
+            .context("opening the template failed")?;
 
-        let compressed_bytes = utils::compress_to_gzip(json_output.as_bytes(), filename)?;
+        self.container_cache = None;
+        self.strokes_state.clear_history();
 
-        Ok(compressed_bytes)
+        Ok(())
     }
 
     pub fn export_sheet_as_svg_string(&self) -> Result<String, anyhow::Error> {
@@ -426,20 +1041,15 @@ impl Sheet {
     ) -> Result<Vec<u8>, anyhow::Error> {
         let current_dpi = self.format.dpi;
 
-        // Only one background for all pages
-        let background = xoppformat::XoppBackground {
-            name: None,
-            bg_type: xoppformat::XoppBackgroundType::Solid {
-                color: self.background.color.into(),
-                style: xoppformat::XoppBackgroundSolidStyle::Plain,
-            },
-        };
-
         // xopp spec needs at least one page in vec, but its fine since pages_bounds() always produces at least one
         let pages = self
             .pages_bounds_containing_content()
             .iter()
             .map(|&page_bounds| {
+                // The background of a page is the background in its center
+                let background = self
+                    .background_at_pos(page_bounds.center().coords)
+                    .to_xoppbackground();
                 let page_keys = self.strokes_state.keys_intersecting_bounds(page_bounds);
 
                 let strokes = self.strokes_state.clone_strokes_for_keys(&page_keys);
@@ -506,7 +1116,7 @@ impl Sheet {
                 xoppformat::XoppPage {
                     width: page_dimensions[0],
                     height: page_dimensions[1],
-                    background: background.clone(),
+                    background,
                     layers: vec![layer],
                 }
             })
@@ -527,27 +1137,62 @@ impl Sheet {
         Ok(xoppfile_bytes)
     }
 
+    /// Exports every page of the sheet format containing content as a pdf page. The strokes are drawn as vector paths and texts stay text.
+    /// With a page range, the pages in the range (counted from 0) are exported instead. Each pdf page has the size of its page, so the pages of a page layout keep their sizes.
+    /// The headings on the exported pages become the bookmarks of the pdf ( see `outline` ).
     /// Returns the receiver to be awaited on for the bytes
-    pub fn export_sheet_as_pdf_bytes(&self, title: String) -> oneshot::Receiver<Vec<u8>> {
+    #[cfg(feature = "cairo")]
+    pub fn export_sheet_as_pdf_bytes(
+        &self,
+        title: String,
+        page_range: Option<Range<u32>>,
+    ) -> oneshot::Receiver<Vec<u8>> {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<Vec<u8>>();
 
-        let pages = self
-            .pages_bounds_containing_content()
+        let pages_bounds = match page_range {
+            Some(page_range) => self
+                .pages()
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| page_range.contains(&(*i as u32)))
+                .map(|(_, page_bounds)| page_bounds)
+                .collect::<Vec<AABB>>(),
+            None => self.pages_bounds_containing_content(),
+        };
+
+        let pages = pages_bounds
             .into_iter()
-            .filter_map(|page_bounds| {
-                Some((page_bounds, self.gen_svgs_for_viewport(page_bounds).ok()?))
+            .map(|page_bounds| {
+                (
+                    page_bounds,
+                    self.strokes_state
+                        .clone_strokes_for_bounds_by_layer(page_bounds),
+                )
             })
-            .collect::<Vec<(AABB, Vec<render::Svg>)>>();
+            .collect::<Vec<(AABB, Vec<(f64, Vec<StrokeStyle>)>)>>();
 
         let sheet_bounds = self.bounds();
+        let background_svgs = self.gen_background_svgs();
         let format_size = na::vector![f64::from(self.format.width), f64::from(self.format.height)];
+        let outline_entries = self.outline(OutlinePrefs::default());
 
         // Fill the pdf surface on a new thread to avoid blocking
-        rayon::spawn(move || {
+        utils::spawn(move || {
             if let Err(e) = || -> Result<(), anyhow::Error> {
-                let surface =
-                    cairo::PdfSurface::for_stream(format_size[0], format_size[1], Vec::<u8>::new())
-                        .context("pdfsurface creation failed")?;
+                let pages_bounds = pages
+                    .iter()
+                    .map(|(page_bounds, _)| *page_bounds)
+                    .collect::<Vec<AABB>>();
+                let first_page_size = pages
+                    .first()
+                    .map(|(page_bounds, _)| page_bounds.extents())
+                    .unwrap_or(format_size);
+                let surface = cairo::PdfSurface::for_stream(
+                    first_page_size[0],
+                    first_page_size[1],
+                    Vec::<u8>::new(),
+                )
+                .context("pdfsurface creation failed")?;
 
                 surface
                     .set_metadata(cairo::PdfMetadata::Title, title.as_str())
@@ -564,18 +1209,40 @@ impl Sheet {
                     let cairo_cx =
                         cairo::Context::new(&surface).context("cario cx new() failed")?;
 
-                    for (page_bounds, page_svgs) in pages.into_iter() {
+                    let background_svgs = background_svgs?;
+
+                    for (page_bounds, page_layers) in pages.into_iter() {
+                        // The size is applied to the following pages, it has to be set before anything is drawn on the page
+                        surface
+                            .set_size(page_bounds.extents()[0], page_bounds.extents()[1])
+                            .context("set pdf surface page size failed")?;
+
+                        cairo_cx.save()?;
                         cairo_cx.translate(-page_bounds.mins[0], -page_bounds.mins[1]);
+                        cairo_cx.rectangle(
+                            page_bounds.mins[0],
+                            page_bounds.mins[1],
+                            page_bounds.extents()[0],
+                            page_bounds.extents()[1],
+                        );
+                        cairo_cx.clip();
+
                         render::draw_svgs_to_cairo_context(
                             1.0,
-                            &page_svgs,
+                            &background_svgs,
                             sheet_bounds,
                             &cairo_cx,
                         )?;
+
+                        draw_layers_to_cairo(&cairo_cx, &page_layers)?;
+
+                        cairo_cx.restore()?;
                         cairo_cx.show_page().context("show page failed")?;
-                        cairo_cx.translate(page_bounds.mins[0], page_bounds.mins[1]);
                     }
                 }
+                outline::add_outline_to_pdf(&surface, &outline_entries, &pages_bounds)
+                    .context("adding the outline to the pdf failed")?;
+
                 let data = *surface
                     .finish_output_stream()
                     .map_err(|e| {
@@ -606,4 +1273,289 @@ impl Sheet {
 
         oneshot_receiver
     }
+
+    /// Exports the sheet onto the pages of the imported PDFs, keeping their text and vector content.
+    /// The strokes, except images, are drawn on top of the pages where they were placed on import. Pages that weren't imported are kept unchanged.
+    #[cfg(feature = "ui")]
+    pub fn export_sheet_as_annotated_pdf_bytes(
+        &self,
+        title: String,
+    ) -> Result<oneshot::Receiver<Vec<u8>>, anyhow::Error> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<Vec<u8>>();
+
+        let imported_pdfs = self.strokes_state.imported_pdfs().to_vec();
+        if imported_pdfs.is_empty() {
+            return Err(anyhow::anyhow!(
+                "export_sheet_as_annotated_pdf_bytes() failed, the sheet has no imported PDF"
+            ));
+        }
+
+        let pdfs = imported_pdfs
+            .into_iter()
+            .map(|imported_pdf| {
+                let data = base64::decode(&imported_pdf.data_base64)?;
+                let annotations = imported_pdf
+                    .pages
+                    .iter()
+                    .map(|page| {
+                        (
+                            page.page_index,
+                            page.bounds,
+                            self.strokes_state
+                                .gen_svgs_annotations_for_bounds(page.bounds),
+                        )
+                    })
+                    .collect::<Vec<(i32, AABB, Vec<render::Svg>)>>();
+
+                Ok((data, annotations))
+            })
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+        // Fill the pdf surface on a new thread to avoid blocking
+        utils::spawn(move || {
+            if let Err(e) = || -> Result<(), anyhow::Error> {
+                let surface = cairo::PdfSurface::for_stream(
+                    Format::default().width,
+                    Format::default().height,
+                    Vec::<u8>::new(),
+                )
+                .context("pdfsurface creation failed")?;
+
+                surface
+                    .set_metadata(cairo::PdfMetadata::Title, title.as_str())
+                    .context("set pdf surface title metadata failed")?;
+                surface
+                    .set_metadata(
+                        cairo::PdfMetadata::CreateDate,
+                        utils::now_formatted_string().as_str(),
+                    )
+                    .context("set pdf surface date metadata failed")?;
+
+                // New scope to avoid errors when flushing
+                {
+                    let cairo_cx =
+                        cairo::Context::new(&surface).context("cario cx new() failed")?;
+
+                    for (data, annotations) in pdfs.into_iter() {
+                        let doc = poppler::Document::from_data(&data, None)?;
+
+                        for page_index in 0..doc.n_pages() {
+                            let page = if let Some(page) = doc.page(page_index) {
+                                page
+                            } else {
+                                continue;
+                            };
+                            let page_size = page.size();
+
+                            // The size has to be set before anything is drawn on the page
+                            surface
+                                .set_size(page_size.0, page_size.1)
+                                .context("set pdf surface page size failed")?;
+
+                            // Rendering for printing keeps the text, so the exported PDF stays searchable
+                            page.render_for_printing(&cairo_cx);
+
+                            if let Some((_, page_bounds, page_svgs)) = annotations
+                                .iter()
+                                .find(|(index, _, _)| *index == page_index)
+                            {
+                                if !page_svgs.is_empty() {
+                                    let scale = page_size.0 / page_bounds.extents()[0];
+
+                                    cairo_cx.save()?;
+                                    cairo_cx.scale(scale, scale);
+                                    cairo_cx.translate(-page_bounds.mins[0], -page_bounds.mins[1]);
+                                    cairo_cx.rectangle(
+                                        page_bounds.mins[0],
+                                        page_bounds.mins[1],
+                                        page_bounds.extents()[0],
+                                        page_bounds.extents()[1],
+                                    );
+                                    cairo_cx.clip();
+
+                                    render::draw_svgs_to_cairo_context(
+                                        1.0,
+                                        page_svgs,
+                                        *page_bounds,
+                                        &cairo_cx,
+                                    )?;
+                                    cairo_cx.restore()?;
+                                }
+                            }
+
+                            cairo_cx.show_page().context("show page failed")?;
+                        }
+                    }
+                }
+                let data = *surface
+                    .finish_output_stream()
+                    .map_err(|e| {
+                        anyhow::anyhow!(
+                            "finish_outputstream() failed in export_sheet_as_annotated_pdf_bytes with Err {:?}",
+                            e
+                        )
+                    })?
+                    .downcast::<Vec<u8>>()
+                    .map_err(|e| {
+                        anyhow::anyhow!(
+                            "downcast() finished output stream failed in export_sheet_as_annotated_pdf_bytes with Err {:?}",
+                            e
+                        )
+                    })?;
+
+                oneshot_sender.send(data).map_err(|e| {
+                    anyhow::anyhow!(
+                        "oneshot_sender.send() failed in export_sheet_as_annotated_pdf_bytes with Err {:?}",
+                        e
+                    )
+                })?;
+                Ok(())
+            }() {
+                log::error!(
+                    "export_sheet_as_annotated_pdf_bytes() failed with Err, {}",
+                    e
+                );
+            }
+        });
+
+        Ok(oneshot_receiver)
+    }
+
+    /// Exports the sheet as bitmap images with the preferences. Every exported page becomes one image, the selection is exported as a single image.
+    /// The images are encoded in the order of the pages
+    #[cfg(feature = "cairo")]
+    pub fn export_sheet_as_bitmap_bytes(
+        &self,
+        prefs: BitmapExportPrefs,
+    ) -> Result<oneshot::Receiver<Vec<Vec<u8>>>, anyhow::Error> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<Vec<Vec<u8>>>();
+
+        let parts = match &prefs.scope {
+            BitmapExportScope::Pages(page_range) => self
+                .pages()
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| page_range.contains(&(*i as u32)))
+                .map(|(_, page_bounds)| {
+                    (
+                        page_bounds,
+                        self.strokes_state
+                            .clone_strokes_for_bounds_by_layer(page_bounds),
+                    )
+                })
+                .collect::<Vec<(AABB, Vec<(f64, Vec<StrokeStyle>)>)>>(),
+            BitmapExportScope::Selection => {
+                let selection_bounds = self
+                    .strokes_state
+                    .gen_selection_bounds()
+                    .ok_or_else(|| anyhow::anyhow!("there is no selection to export"))?;
+
+                vec![(
+                    selection_bounds,
+                    self.strokes_state.clone_selection_by_layer(),
+                )]
+            }
+        };
+        if parts.is_empty() {
+            return Err(anyhow::anyhow!(
+                "there are no pages in the page range to export"
+            ));
+        }
+
+        let sheet_bounds = self.bounds();
+        let background_svgs = if prefs.with_background {
+            Some(self.gen_background_svgs()?)
+        } else {
+            None
+        };
+        let zoom = prefs.dpi / self.format.dpi;
+
+        // Render the images on a new thread to avoid blocking
+        utils::spawn(move || {
+            if let Err(e) = || -> Result<(), anyhow::Error> {
+                let data = parts
+                    .into_iter()
+                    .map(|(bounds, layers)| {
+                        let image = render::cairo_drawing_to_image(bounds, zoom, |cx| {
+                            // JPEG has no alpha channel, so transparent areas would turn black
+                            if background_svgs.is_none() && prefs.format == BitmapExportFormat::Jpeg
+                            {
+                                cx.set_source_rgba(1.0, 1.0, 1.0, 1.0);
+                                cx.paint()?;
+                            }
+                            if let Some(background_svgs) = &background_svgs {
+                                render::draw_svgs_to_cairo_context(
+                                    1.0,
+                                    background_svgs,
+                                    sheet_bounds,
+                                    cx,
+                                )?;
+                            }
+
+                            draw_layers_to_cairo(cx, &layers)
+                        })?;
+
+                        match prefs.format {
+                            BitmapExportFormat::Png => {
+                                render::image_into_encoded_bytes(image, prefs.image_output_format())
+                            }
+                            BitmapExportFormat::Jpeg => {
+                                let mut bytes_buf: Vec<u8> = vec![];
+
+                                image::DynamicImage::ImageRgb8(
+                                    image::DynamicImage::ImageRgba8(image.to_imgbuf()?).into_rgb8(),
+                                )
+                                .write_to(&mut bytes_buf, prefs.image_output_format())
+                                .context("write_to() jpeg image failed")?;
+
+                                Ok(bytes_buf)
+                            }
+                        }
+                    })
+                    .collect::<Result<Vec<Vec<u8>>, anyhow::Error>>()?;
+
+                oneshot_sender.send(data).map_err(|e| {
+                    anyhow::anyhow!(
+                        "oneshot_sender.send() failed in export_sheet_as_bitmap_bytes with Err {:?}",
+                        e
+                    )
+                })?;
+                Ok(())
+            }() {
+                log::error!("export_sheet_as_bitmap_bytes() failed with Err, {}", e);
+            }
+        });
+
+        Ok(oneshot_receiver)
+    }
+}
+
+/// Draws the strokes of the layers from the bottom to the top layer. Translucent layers are composited as a group, like on the canvas
+#[cfg(feature = "cairo")]
+fn draw_layers_to_cairo(
+    cx: &cairo::Context,
+    layers: &[(f64, Vec<StrokeStyle>)],
+) -> Result<(), anyhow::Error> {
+    for (layer_opacity, strokes) in layers {
+        let translucent = *layer_opacity < Layer::OPACITY_MAX;
+        if translucent {
+            cx.push_group();
+        }
+
+        for stroke in strokes.iter() {
+            if let Err(e) = stroke.draw_to_cairo_vector(cx) {
+                log::error!(
+                    "draw_to_cairo_vector() failed for stroke in draw_layers_to_cairo() with Err {}",
+                    e
+                );
+            }
+        }
+
+        if translucent {
+            cx.pop_group_to_source()?;
+            cx.paint_with_alpha(*layer_opacity)?;
+        }
+    }
+
+    Ok(())
 }
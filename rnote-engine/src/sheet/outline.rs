@@ -0,0 +1,244 @@
+//! The outline of the sheet, built from its headings, e.g. for a table of contents or the bookmarks of exported PDFs.
+//! Texts are headings when they are marked with a heading level ( see `TextStyle::heading_level` ),
+//! or, with the font size heuristic, when their font size is notably larger than the one of the body text.
+
+use std::collections::HashMap;
+
+#[cfg(feature = "cairo")]
+use p2d::bounding_volume::AABB;
+use serde::{Deserialize, Serialize};
+
+use super::Sheet;
+use crate::strokes::textstroke::{TextStroke, TextStyle};
+use crate::strokesstate::StrokeKey;
+
+/// A heading of the outline, with its subheadings
+#[derive(Debug, Clone)]
+pub struct OutlineEntry {
+    /// The first line of the text
+    pub title: String,
+    /// The heading level, beginning with 1 for the top level
+    pub level: u32,
+    /// The key of the text
+    pub key: StrokeKey,
+    /// The upper left corner of the text, in the coordinate space of the sheet
+    pub pos: na::Vector2<f64>,
+    /// The index of the page containing the heading
+    pub page_index: Option<usize>,
+    pub children: Vec<OutlineEntry>,
+}
+
+/// How the outline is built
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "outline_prefs")]
+pub struct OutlinePrefs {
+    /// Texts which are not marked as headings are headings as well, when their font size is large enough
+    #[serde(rename = "from_font_size")]
+    pub from_font_size: bool,
+    /// How much larger than the font size of the body text the font size of texts must be to be headings
+    #[serde(rename = "font_size_ratio")]
+    pub font_size_ratio: f64,
+}
+
+impl Default for OutlinePrefs {
+    fn default() -> Self {
+        Self {
+            from_font_size: true,
+            font_size_ratio: Self::FONT_SIZE_RATIO_DEFAULT,
+        }
+    }
+}
+
+impl OutlinePrefs {
+    pub const FONT_SIZE_RATIO_MIN: f64 = 1.05;
+    pub const FONT_SIZE_RATIO_MAX: f64 = 10.0;
+    pub const FONT_SIZE_RATIO_DEFAULT: f64 = 1.3;
+}
+
+/// A heading before the tree is built
+struct Heading<'a> {
+    key: StrokeKey,
+    textstroke: &'a TextStroke,
+    title: String,
+    /// The font size on the sheet, rounded to half points, so sizes which differ only slightly are the same level
+    size_key: i64,
+}
+
+impl Sheet {
+    /// The outline of the headings of the sheet, in reading order.
+    /// Only texts which are not trashed and on visible layers are headings
+    pub fn outline(&self, prefs: OutlinePrefs) -> Vec<OutlineEntry> {
+        let headings = self
+            .strokes_state
+            .keys_unordered()
+            .into_iter()
+            .filter(|&key| {
+                self.strokes_state.trashed(key) == Some(false)
+                    && self.strokes_state.layer_visible(key)
+            })
+            .filter_map(|key| {
+                let textstroke = self.strokes_state.get_textstroke(key)?;
+                let title = textstroke
+                    .text
+                    .lines()
+                    .map(|line| line.trim())
+                    .find(|line| !line.is_empty())?
+                    .to_string();
+
+                Some(Heading {
+                    key,
+                    textstroke,
+                    title,
+                    size_key: (textstroke.font_size_on_sheet() * 2.0).round() as i64,
+                })
+            })
+            .collect::<Vec<Heading>>();
+
+        let heuristic_levels = if prefs.from_font_size {
+            heuristic_levels(&headings, prefs.font_size_ratio)
+        } else {
+            HashMap::new()
+        };
+
+        let mut entries = headings
+            .into_iter()
+            .filter_map(|heading| {
+                let level = match heading.textstroke.style.heading_level {
+                    Some(level) => level,
+                    None => *heuristic_levels.get(&heading.size_key)?,
+                }
+                .clamp(TextStyle::HEADING_LEVEL_MIN, TextStyle::HEADING_LEVEL_MAX);
+                let pos = heading.textstroke.pos();
+
+                Some(OutlineEntry {
+                    title: heading.title,
+                    level,
+                    key: heading.key,
+                    pos,
+                    page_index: self.page_index_at(pos),
+                    children: vec![],
+                })
+            })
+            .collect::<Vec<OutlineEntry>>();
+        entries.sort_by(|first, second| self.cmp_reading_order(first.pos, second.pos));
+
+        build_tree(entries)
+    }
+}
+
+/// The heading levels by the rounded font size, for the texts whose font size is larger by the ratio than the font size of most of the text.
+/// The largest font size is the top level
+fn heuristic_levels(headings: &[Heading], font_size_ratio: f64) -> HashMap<i64, u32> {
+    let font_size_ratio = font_size_ratio.clamp(
+        OutlinePrefs::FONT_SIZE_RATIO_MIN,
+        OutlinePrefs::FONT_SIZE_RATIO_MAX,
+    );
+
+    // The body text is the font size most of the characters not marked as headings have
+    let mut n_chars = HashMap::<i64, usize>::new();
+    for heading in headings
+        .iter()
+        .filter(|heading| heading.textstroke.style.heading_level.is_none())
+    {
+        *n_chars.entry(heading.size_key).or_default() += heading.textstroke.text.chars().count();
+    }
+    let body_size_key = match n_chars
+        .iter()
+        .max_by_key(|(&size_key, &n_chars)| (n_chars, -size_key))
+    {
+        Some((&body_size_key, _)) => body_size_key,
+        None => return HashMap::new(),
+    };
+
+    let mut size_keys = n_chars
+        .into_iter()
+        .map(|(size_key, _)| size_key)
+        .filter(|&size_key| size_key as f64 >= body_size_key as f64 * font_size_ratio)
+        .collect::<Vec<i64>>();
+    size_keys.sort_unstable_by(|first, second| second.cmp(first));
+
+    size_keys
+        .into_iter()
+        .enumerate()
+        .map(|(i, size_key)| (size_key, TextStyle::HEADING_LEVEL_MIN + i as u32))
+        .collect()
+}
+
+/// Nests the entries in reading order under the preceding entries with a higher level
+fn build_tree(entries: Vec<OutlineEntry>) -> Vec<OutlineEntry> {
+    let mut roots = vec![];
+    // The chain of the last entry and its ancestors, which the following entries might be nested under
+    let mut open = Vec::<OutlineEntry>::new();
+
+    for entry in entries {
+        while open
+            .last()
+            .map_or(false, |parent| parent.level >= entry.level)
+        {
+            close_last(&mut open, &mut roots);
+        }
+        open.push(entry);
+    }
+    while !open.is_empty() {
+        close_last(&mut open, &mut roots);
+    }
+
+    roots
+}
+
+fn close_last(open: &mut Vec<OutlineEntry>, roots: &mut Vec<OutlineEntry>) {
+    if let Some(entry) = open.pop() {
+        match open.last_mut() {
+            Some(parent) => parent.children.push(entry),
+            None => roots.push(entry),
+        }
+    }
+}
+
+/// The id of the root of the outline of a pdf
+#[cfg(feature = "cairo")]
+const PDF_OUTLINE_ROOT: i32 = 0;
+
+/// Adds the entries to the outline of the pdf, as links to the exported pages with the bounds.
+/// Headings on pages which are not exported are left out, and their subheadings move up a level
+#[cfg(feature = "cairo")]
+pub(crate) fn add_outline_to_pdf(
+    surface: &cairo::PdfSurface,
+    entries: &[OutlineEntry],
+    pages_bounds: &[AABB],
+) -> Result<(), anyhow::Error> {
+    add_outline_entries_to_pdf(surface, PDF_OUTLINE_ROOT, entries, pages_bounds)
+}
+
+#[cfg(feature = "cairo")]
+fn add_outline_entries_to_pdf(
+    surface: &cairo::PdfSurface,
+    parent_id: i32,
+    entries: &[OutlineEntry],
+    pages_bounds: &[AABB],
+) -> Result<(), anyhow::Error> {
+    for entry in entries {
+        let page = pages_bounds
+            .iter()
+            .position(|page_bounds| page_bounds.contains_local_point(&na::Point2::from(entry.pos)));
+
+        let id = match page {
+            Some(page) => {
+                // In the coordinate space of the pdf page, which begins at the upper left corner of the page
+                let pos = entry.pos - pages_bounds[page].mins.coords;
+
+                surface.add_outline(
+                    parent_id,
+                    &entry.title,
+                    &format!("page={} pos=[{} {}]", page + 1, pos[0], pos[1]),
+                    cairo::PdfOutline::empty(),
+                )?
+            }
+            None => parent_id,
+        };
+
+        add_outline_entries_to_pdf(surface, id, &entry.children, pages_bounds)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use p2d::bounding_volume::AABB;
+use serde::{Deserialize, Serialize};
+
+use super::format::{Format, Orientation};
+
+/// A page of the page layout
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "page")]
+pub struct Page {
+    #[serde(rename = "width")]
+    pub width: f64,
+    #[serde(rename = "height")]
+    pub height: f64,
+}
+
+impl Default for Page {
+    fn default() -> Self {
+        Self::from_format(&Format::default())
+    }
+}
+
+impl Page {
+    pub fn new(width: f64, height: f64) -> Self {
+        Self {
+            width: width.clamp(Format::WIDTH_MIN, Format::WIDTH_MAX),
+            height: height.clamp(Format::HEIGHT_MIN, Format::HEIGHT_MAX),
+        }
+    }
+
+    /// A page with the size of the format
+    pub fn from_format(format: &Format) -> Self {
+        Self::new(format.width, format.height)
+    }
+
+    pub fn size(&self) -> na::Vector2<f64> {
+        na::vector![self.width, self.height]
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        if self.width > self.height {
+            Orientation::Landscape
+        } else {
+            Orientation::Portrait
+        }
+    }
+
+    /// The page with its width and height swapped, when it does not already have the orientation
+    pub fn with_orientation(self, orientation: Orientation) -> Self {
+        if self.orientation() == orientation {
+            self
+        } else {
+            Self::new(self.height, self.width)
+        }
+    }
+}
+
+/// The layout of a sheet made up of discrete pages, which can have different sizes.
+/// The pages are placed below each other, aligned to the left at the origin and separated by a gap
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "page_layout")]
+pub struct PageLayout {
+    #[serde(rename = "pages")]
+    pages: Vec<Page>,
+}
+
+impl PageLayout {
+    /// The gap between the pages
+    pub const PAGE_GAP: f64 = 32.0;
+
+    /// A layout with a single page
+    pub fn new(page: Page) -> Self {
+        Self { pages: vec![page] }
+    }
+
+    pub fn pages(&self) -> &[Page] {
+        &self.pages
+    }
+
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<Page> {
+        self.pages.get(index).copied()
+    }
+
+    /// Inserts the page at the index. Indices past the end append the page
+    pub fn insert_page(&mut self, index: usize, page: Page) {
+        let index = index.min(self.pages.len());
+        self.pages.insert(index, page);
+    }
+
+    pub fn remove_page(&mut self, index: usize) -> Option<Page> {
+        if index < self.pages.len() {
+            Some(self.pages.remove(index))
+        } else {
+            None
+        }
+    }
+
+    /// Moves the page at the index `from` to the index `to`. Returns false if one of the indices is out of range
+    pub fn move_page(&mut self, from: usize, to: usize) -> bool {
+        if from >= self.pages.len() || to >= self.pages.len() {
+            return false;
+        }
+
+        let page = self.pages.remove(from);
+        self.pages.insert(to, page);
+        true
+    }
+
+    /// Replaces the page at the index, for example to change its size or orientation
+    pub fn set_page(&mut self, index: usize, page: Page) -> bool {
+        if let Some(old) = self.pages.get_mut(index) {
+            *old = page;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The bounds of every page, in the order of the pages
+    pub fn pages_bounds(&self) -> Vec<AABB> {
+        let mut y = 0.0;
+
+        self.pages
+            .iter()
+            .map(|page| {
+                let bounds = AABB::new(na::point![0.0, y], na::point![page.width, y + page.height]);
+                y += page.height + Self::PAGE_GAP;
+                bounds
+            })
+            .collect()
+    }
+
+    pub fn page_bounds(&self, index: usize) -> Option<AABB> {
+        self.pages_bounds().get(index).copied()
+    }
+
+    /// The bounds containing all pages
+    pub fn bounds(&self) -> Option<AABB> {
+        let pages_bounds = self.pages_bounds();
+        let last = pages_bounds.last()?;
+        let max_width = self.pages.iter().map(|page| page.width).fold(0.0, f64::max);
+
+        Some(AABB::new(
+            na::point![0.0, 0.0],
+            na::point![max_width, last.maxs[1]],
+        ))
+    }
+
+    /// The index of the page the position belongs to. A page extends down to the next page, so positions in the gaps belong to the page above.
+    /// Positions above the first or below the last page belong to them. None if the layout is empty
+    pub fn page_index_at(&self, pos: na::Vector2<f64>) -> Option<usize> {
+        let pages_bounds = self.pages_bounds();
+        if pages_bounds.is_empty() {
+            return None;
+        }
+
+        Some(
+            pages_bounds
+                .iter()
+                .skip(1)
+                .position(|bounds| pos[1] < bounds.mins[1])
+                .unwrap_or(pages_bounds.len() - 1),
+        )
+    }
+}
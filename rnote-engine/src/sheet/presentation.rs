@@ -0,0 +1,153 @@
+//! Presents the pages of a sheet one at a time, like slides. The view fits exactly one page into the viewport,
+//! and moving to another page transitions smoothly to it. While presenting, the pens don't edit the sheet (see `Pens::set_presenting()`).
+
+use std::time::Duration;
+
+use p2d::bounding_volume::AABB;
+use web_time::Instant;
+
+use super::Sheet;
+
+/// What the viewport shows, in the coordinate space of the sheet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PresentationView {
+    /// The position in the center of the viewport
+    pub center: na::Vector2<f64>,
+    pub zoom: f64,
+}
+
+impl PresentationView {
+    /// The view which fits the page exactly into the viewport with the size, in surface coordinates
+    pub fn fit_page(page_bounds: AABB, viewport_size: na::Vector2<f64>) -> Self {
+        let page_size = page_bounds.extents();
+        let zoom = (viewport_size[0] / page_size[0]).min(viewport_size[1] / page_size[1]);
+
+        Self {
+            center: page_bounds.center().coords,
+            zoom: if zoom.is_finite() && zoom > 0.0 {
+                zoom
+            } else {
+                1.0
+            },
+        }
+    }
+
+    /// Interpolates to the other view. The zoom is interpolated geometrically, so zooming in and out feels the same
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        Self {
+            center: self.center.lerp(&other.center, t),
+            zoom: self.zoom * (other.zoom / self.zoom).powf(t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Transition {
+    from: PresentationView,
+    started: Instant,
+}
+
+#[derive(Debug, Clone)]
+pub struct Presentation {
+    page_index: usize,
+    transition: Option<Transition>,
+}
+
+impl Presentation {
+    pub const TRANSITION_DURATION: Duration = Duration::from_millis(300);
+
+    /// A new presentation, beginning at the page with the index
+    pub fn new(page_index: usize) -> Self {
+        Self {
+            page_index,
+            transition: None,
+        }
+    }
+
+    /// A new presentation beginning at the page containing the position, e.g. the page currently in view
+    pub fn new_at_pos(sheet: &Sheet, pos: na::Vector2<f64>) -> Self {
+        Self::new(sheet.page_index_at(pos).unwrap_or(0))
+    }
+
+    pub fn page_index(&self) -> usize {
+        self.page_index
+    }
+
+    /// The view at the time. Transitions smoothly to the current page after it has changed.
+    /// None if the sheet has no pages
+    pub fn view(
+        &self,
+        sheet: &Sheet,
+        viewport_size: na::Vector2<f64>,
+        now: Instant,
+    ) -> Option<PresentationView> {
+        let pages = sheet.pages();
+        let page_bounds = pages.get(self.page_index).or_else(|| pages.last())?;
+        let target = PresentationView::fit_page(*page_bounds, viewport_size);
+
+        match self.transition {
+            Some(transition) => {
+                let t = (now
+                    .saturating_duration_since(transition.started)
+                    .as_secs_f64()
+                    / Self::TRANSITION_DURATION.as_secs_f64())
+                .clamp(0.0, 1.0);
+                // Eases in and out
+                let t = t * t * (3.0 - 2.0 * t);
+
+                Some(transition.from.interpolate(&target, t))
+            }
+            None => Some(target),
+        }
+    }
+
+    /// Whether the view is transitioning to the page at the time
+    pub fn transitioning(&self, now: Instant) -> bool {
+        self.transition.map_or(false, |transition| {
+            now.saturating_duration_since(transition.started) < Self::TRANSITION_DURATION
+        })
+    }
+
+    /// Moves to the page with the index, clamped to the pages of the sheet. Returns false if the page did not change
+    pub fn go_to_page(
+        &mut self,
+        page_index: usize,
+        sheet: &Sheet,
+        viewport_size: na::Vector2<f64>,
+        now: Instant,
+    ) -> bool {
+        let n_pages = sheet.pages().len();
+        let page_index = page_index.min(n_pages.saturating_sub(1));
+        if page_index == self.page_index {
+            return false;
+        }
+
+        // Transitions from where the view is right now, which might be in the middle of another transition
+        let from = self.view(sheet, viewport_size, now);
+        self.page_index = page_index;
+        self.transition = from.map(|from| Transition { from, started: now });
+
+        true
+    }
+
+    pub fn next_page(
+        &mut self,
+        sheet: &Sheet,
+        viewport_size: na::Vector2<f64>,
+        now: Instant,
+    ) -> bool {
+        self.go_to_page(self.page_index + 1, sheet, viewport_size, now)
+    }
+
+    pub fn previous_page(
+        &mut self,
+        sheet: &Sheet,
+        viewport_size: na::Vector2<f64>,
+        now: Instant,
+    ) -> bool {
+        match self.page_index.checked_sub(1) {
+            Some(page_index) => self.go_to_page(page_index, sheet, viewport_size, now),
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,355 @@
+//! Crash recovery. While there are unsaved changes, the app writes a snapshot of the sheet into the recovery directory every few minutes,
+//! and in between appends the strokes which changed since to a journal. On the next launch the snapshot is restored and the journal replayed on top of it.
+//!
+//! The recovery directory holds the files:
+//! - `snapshot.rnote`: the sheet as .rnote file. It is written to a temporary file first and then renamed, so a crash while writing keeps the previous snapshot
+//! - `recovery.json`: the metadata of the snapshot
+//! - `journal.jsonl`: one json line for every flush of the journal, with the strokes which were inserted, modified or removed since the last flush.
+//!     A line which was cut off by a crash is ignored
+//!
+//! The journal finds the changed strokes by comparing their revisions with the journaled ones. Every recorded change of a stroke bumps its revision,
+//! including moving, rotating and resizing, so these are journaled as well. Strokes whose keys disappeared since the last flush are journaled as removed.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use super::Sheet;
+use crate::strokes::strokestyle::StrokeStyle;
+use crate::strokesstate::StrokeKey;
+
+/// The name of the snapshot file
+pub const SNAPSHOT_FILE: &str = "snapshot.rnote";
+/// The name of the metadata file
+pub const METADATA_FILE: &str = "recovery.json";
+/// The name of the journal file
+pub const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// The metadata of a recovery snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "recovery_metadata")]
+pub struct RecoveryMetadata {
+    /// Identifies the snapshot, so that journal records from an older snapshot are not replayed on a newer one
+    #[serde(rename = "snapshot_id")]
+    pub snapshot_id: u64,
+    /// When the snapshot was written
+    #[serde(rename = "snapshot_time")]
+    pub snapshot_time: chrono::DateTime<chrono::Utc>,
+    /// The file the sheet was opened from or last saved to. None if it was never saved
+    #[serde(rename = "file_path")]
+    pub file_path: Option<PathBuf>,
+}
+
+/// A stroke in the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "journal_stroke")]
+struct JournalStroke {
+    /// The key of the stroke in the session which wrote the journal
+    #[serde(rename = "key")]
+    key: StrokeKey,
+    #[serde(rename = "trashed")]
+    trashed: bool,
+    #[serde(rename = "stroke")]
+    stroke: StrokeStyle,
+}
+
+/// One flush of the journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "journal_record")]
+struct JournalRecord {
+    #[serde(rename = "snapshot_id")]
+    snapshot_id: u64,
+    /// The strokes which were inserted or modified, in chronological order
+    #[serde(rename = "strokes")]
+    strokes: Vec<JournalStroke>,
+    /// The keys of the strokes which were removed
+    #[serde(rename = "removed")]
+    removed: Vec<StrokeKey>,
+}
+
+/// Writes the recovery snapshot and journal for the sheet of a session
+#[derive(Debug)]
+pub struct Recovery {
+    dir: PathBuf,
+    /// The metadata of the snapshot this session wrote last
+    snapshot: Option<RecoveryMetadata>,
+    /// The chrono counter of the sheet when the journal was last flushed
+    journaled_chrono: u32,
+    /// The revisions of the strokes in the snapshot and the journal, to find the changed and removed strokes
+    journaled_revisions: HashMap<StrokeKey, u64>,
+}
+
+impl Recovery {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            snapshot: None,
+            journaled_chrono: 0,
+            journaled_revisions: HashMap::new(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The metadata of the snapshot this session wrote last. None if there is none, or it was discarded
+    pub fn snapshot(&self) -> Option<&RecoveryMetadata> {
+        self.snapshot.as_ref()
+    }
+
+    /// How long ago this session wrote its last snapshot
+    pub fn snapshot_age(&self) -> Option<std::time::Duration> {
+        self.snapshot.as_ref().map(|snapshot| {
+            (chrono::Utc::now() - snapshot.snapshot_time)
+                .to_std()
+                .unwrap_or_default()
+        })
+    }
+
+    /// Detects a snapshot which is left in the recovery directory, e.g. from a session that crashed.
+    /// Should be called on launch before anything is written
+    pub fn detect(&self) -> Option<RecoveryMetadata> {
+        if !self.dir.join(SNAPSHOT_FILE).exists() {
+            return None;
+        }
+
+        match fs::read(self.dir.join(METADATA_FILE))
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| Ok(serde_json::from_slice::<RecoveryMetadata>(&bytes)?))
+        {
+            Ok(metadata) => Some(metadata),
+            Err(e) => {
+                log::error!(
+                    "reading the recovery metadata in detect() failed with Err {}",
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Writes a new snapshot of the sheet and starts a new journal
+    pub fn write_snapshot(
+        &mut self,
+        sheet: &mut Sheet,
+        file_path: Option<PathBuf>,
+    ) -> Result<(), anyhow::Error> {
+        fs::create_dir_all(&self.dir).context("creating the recovery directory failed")?;
+
+        let metadata = RecoveryMetadata {
+            snapshot_id: rand::random::<u64>(),
+            snapshot_time: chrono::Utc::now(),
+            file_path,
+        };
+        let bytes = sheet.save_sheet_as_rnote_bytes()?;
+
+        let snapshot_tmp = self.dir.join(format!("{}.tmp", SNAPSHOT_FILE));
+        write_synced(&snapshot_tmp, &bytes)?;
+        fs::rename(&snapshot_tmp, self.dir.join(SNAPSHOT_FILE))?;
+
+        let metadata_tmp = self.dir.join(format!("{}.tmp", METADATA_FILE));
+        write_synced(&metadata_tmp, &serde_json::to_vec(&metadata)?)?;
+        fs::rename(&metadata_tmp, self.dir.join(METADATA_FILE))?;
+
+        // Records of the previous snapshot are skipped on replay, so a crash before the truncation is harmless
+        write_synced(&self.dir.join(JOURNAL_FILE), &[])?;
+
+        self.journaled_chrono = sheet.strokes_state.chrono_counter();
+        self.journaled_revisions = stroke_revisions(sheet);
+        self.snapshot = Some(metadata);
+
+        Ok(())
+    }
+
+    /// Appends the strokes which changed since the last flush to the journal.
+    /// Writes a snapshot instead if there is none yet, or the sheet was cleared since
+    pub fn flush_journal(
+        &mut self,
+        sheet: &mut Sheet,
+        file_path: Option<PathBuf>,
+    ) -> Result<(), anyhow::Error> {
+        let snapshot_id = match &self.snapshot {
+            Some(snapshot) if sheet.strokes_state.chrono_counter() >= self.journaled_chrono => {
+                snapshot.snapshot_id
+            }
+            _ => return self.write_snapshot(sheet, file_path),
+        };
+
+        let revisions = stroke_revisions(sheet);
+        let removed = self
+            .journaled_revisions
+            .keys()
+            .filter(|key| !revisions.contains_key(key))
+            .copied()
+            .collect::<Vec<StrokeKey>>();
+
+        // Comparing the revisions instead of the chrono, because strokes can keep an older chrono, e.g. the segments of a split stroke
+        let mut changed = revisions
+            .iter()
+            .filter(|(key, revision)| self.journaled_revisions.get(key) != Some(revision))
+            .map(|(&key, _)| key)
+            .collect::<Vec<StrokeKey>>();
+        sheet.strokes_state.sort_keys_chrono(&mut changed);
+
+        let strokes = changed
+            .into_iter()
+            .filter_map(|key| {
+                Some(JournalStroke {
+                    key,
+                    trashed: sheet.strokes_state.trashed(key)?,
+                    stroke: sheet.strokes_state.get_stroke_ref(key)?.clone(),
+                })
+            })
+            .collect::<Vec<JournalStroke>>();
+
+        if !strokes.is_empty() || !removed.is_empty() {
+            let mut line = serde_json::to_vec(&JournalRecord {
+                snapshot_id,
+                strokes,
+                removed,
+            })?;
+            line.push(b'\n');
+
+            let mut journal = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.dir.join(JOURNAL_FILE))?;
+            journal.write_all(&line)?;
+            journal.sync_data()?;
+        }
+
+        self.journaled_chrono = sheet.strokes_state.chrono_counter();
+        self.journaled_revisions = revisions;
+
+        Ok(())
+    }
+
+    /// Restores the snapshot in the recovery directory into the sheet, and replays the journal on top of it.
+    /// Strokes which were inserted after the snapshot are inserted on the current layer
+    pub fn restore(&self, sheet: &mut Sheet) -> Result<RecoveryMetadata, anyhow::Error> {
+        let metadata = self
+            .detect()
+            .ok_or_else(|| anyhow::anyhow!("there is no recovery snapshot"))?;
+
+        let bytes = fs::read(self.dir.join(SNAPSHOT_FILE))
+            .context("reading the recovery snapshot failed")?;
+        sheet
+            .open_sheet_from_rnote_bytes(bytes)
+            .context("opening the recovery snapshot failed")?;
+
+        let journal = match fs::File::open(self.dir.join(JOURNAL_FILE)) {
+            Ok(journal) => journal,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(metadata),
+            Err(e) => return Err(e.into()),
+        };
+
+        let snapshot_keys = sheet
+            .strokes_state
+            .keys_unordered()
+            .into_iter()
+            .collect::<HashSet<StrokeKey>>();
+        // The keys change for strokes which are inserted again
+        let mut inserted_keys = HashMap::<StrokeKey, StrokeKey>::new();
+
+        for line in BufReader::new(journal).lines() {
+            let record = match serde_json::from_str::<JournalRecord>(&line?) {
+                Ok(record) => record,
+                Err(e) => {
+                    log::warn!(
+                        "skipping journal record in restore() that failed to parse with Err {}",
+                        e
+                    );
+                    continue;
+                }
+            };
+            if record.snapshot_id != metadata.snapshot_id {
+                continue;
+            }
+
+            for key in record.removed {
+                let existing_key = match inserted_keys.remove(&key) {
+                    Some(key) => Some(key),
+                    None if snapshot_keys.contains(&key) => Some(key),
+                    None => None,
+                };
+
+                if let Some(key) = existing_key {
+                    sheet.strokes_state.remove_stroke(key);
+                }
+            }
+
+            for journal_stroke in record.strokes {
+                let existing_key = match inserted_keys.get(&journal_stroke.key) {
+                    Some(&key) => Some(key),
+                    None if snapshot_keys.contains(&journal_stroke.key) => Some(journal_stroke.key),
+                    None => None,
+                };
+
+                let key = match existing_key {
+                    Some(key)
+                        if sheet
+                            .strokes_state
+                            .replace_stroke(key, journal_stroke.stroke.clone())
+                            .is_some() =>
+                    {
+                        key
+                    }
+                    _ => {
+                        let key = sheet.strokes_state.insert_stroke(journal_stroke.stroke);
+                        inserted_keys.insert(journal_stroke.key, key);
+                        key
+                    }
+                };
+
+                sheet.strokes_state.set_trashed(key, journal_stroke.trashed);
+            }
+        }
+
+        sheet.strokes_state.update_geometry_all_strokes();
+        // The replayed journal is not undoable
+        sheet.strokes_state.clear_history();
+
+        Ok(metadata)
+    }
+
+    /// Removes the snapshot and the journal, e.g. after the sheet was saved
+    pub fn discard(&mut self) -> Result<(), anyhow::Error> {
+        self.snapshot = None;
+        self.journaled_chrono = 0;
+        self.journaled_revisions.clear();
+
+        for file in [SNAPSHOT_FILE, METADATA_FILE, JOURNAL_FILE] {
+            match fs::remove_file(self.dir.join(file)) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The current revisions of the strokes of the sheet
+fn stroke_revisions(sheet: &Sheet) -> HashMap<StrokeKey, u64> {
+    sheet
+        .strokes_state
+        .keys_unordered()
+        .into_iter()
+        .map(|key| (key, sheet.strokes_state.stroke_revision(key)))
+        .collect()
+}
+
+/// Writes the file and syncs it to disk before returning
+fn write_synced(path: &Path, data: &[u8]) -> Result<(), anyhow::Error> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+
+    Ok(())
+}
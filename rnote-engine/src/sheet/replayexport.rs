@@ -0,0 +1,407 @@
+//! Exports the session recording as an animation of the sheet being drawn, see `strokesstate::recording`.
+//! Animated SVGs show and hide the versions of the strokes with SMIL animations, so they only animate in viewers which support them, e.g. browsers.
+//! Videos are rendered frame by frame like the replay ( see `strokesstate::replay` ), and handed to a `VideoEncoder`,
+//! e.g. `FfmpegEncoder` which pipes them into the ffmpeg executable.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+
+use anyhow::Context;
+#[cfg(feature = "cairo")]
+use futures::channel::oneshot;
+use p2d::bounding_volume::{BoundingVolume, AABB};
+
+use super::Sheet;
+use crate::compose;
+use crate::drawbehaviour::DrawBehaviour;
+#[cfg(feature = "cairo")]
+use crate::render;
+use crate::strokes::strokestyle::StrokeStyle;
+use crate::strokesstate::recording::{RecordedAction, RecordedEvent};
+use crate::strokesstate::replay::{self, Replay};
+use crate::strokesstate::StrokeKey;
+#[cfg(feature = "cairo")]
+use crate::strokesstate::StrokesState;
+#[cfg(feature = "cairo")]
+use crate::utils;
+
+/// The preferences for exporting the session recording as animation
+#[derive(Debug, Clone)]
+pub struct ReplayExportPrefs {
+    /// The factor the animation is faster than the recording
+    pub speed: f64,
+    /// The frames per second of videos
+    pub fps: f64,
+    /// The resolution of videos. The sheet is rendered at its format DPI scaled to this resolution
+    pub dpi: f64,
+    /// Whether the background is drawn. Without it animated SVGs are transparent, videos are filled white
+    pub with_background: bool,
+    /// How long the finished drawing is shown at the end of videos, in seconds
+    pub hold_end: f64,
+    /// The number of steps brush strokes are drawn in, in animated SVGs. Every step adds a copy of the partially drawn stroke
+    pub svg_steps: usize,
+}
+
+impl Default for ReplayExportPrefs {
+    fn default() -> Self {
+        Self {
+            speed: Replay::SPEED_DEFAULT,
+            fps: Self::FPS_DEFAULT,
+            dpi: Self::DPI_DEFAULT,
+            with_background: true,
+            hold_end: Self::HOLD_END_DEFAULT,
+            svg_steps: Self::SVG_STEPS_DEFAULT,
+        }
+    }
+}
+
+impl ReplayExportPrefs {
+    pub const FPS_MIN: f64 = 1.0;
+    pub const FPS_MAX: f64 = 60.0;
+    pub const FPS_DEFAULT: f64 = 30.0;
+
+    pub const DPI_MIN: f64 = 10.0;
+    pub const DPI_MAX: f64 = 600.0;
+    pub const DPI_DEFAULT: f64 = 96.0;
+
+    pub const HOLD_END_MIN: f64 = 0.0;
+    pub const HOLD_END_MAX: f64 = 30.0;
+    pub const HOLD_END_DEFAULT: f64 = 2.0;
+
+    pub const SVG_STEPS_MIN: usize = 1;
+    pub const SVG_STEPS_MAX: usize = 32;
+    pub const SVG_STEPS_DEFAULT: usize = 8;
+
+    /// The margin around the recorded strokes
+    pub const MARGIN: f64 = 20.0;
+
+    fn speed(&self) -> f64 {
+        self.speed.clamp(Replay::SPEED_MIN, Replay::SPEED_MAX)
+    }
+}
+
+/// Encodes the frames of a video
+pub trait VideoEncoder: Send {
+    /// Called before the first frame, with the size of the frames in pixels
+    fn begin(&mut self, width: u32, height: u32, fps: f64) -> Result<(), anyhow::Error>;
+    /// Encodes the next frame, with the size passed to `begin()`
+    fn push_frame(&mut self, frame: &image::RgbaImage) -> Result<(), anyhow::Error>;
+    /// Called after the last frame, to finish the video
+    fn finish(&mut self) -> Result<(), anyhow::Error>;
+}
+
+/// Encodes the video with the ffmpeg executable, which needs to be installed. The container and codec are chosen by ffmpeg from the file extension of the output,
+/// e.g. `.mp4` or `.webm`
+#[derive(Debug)]
+pub struct FfmpegEncoder {
+    /// The ffmpeg executable, looked up in the path if it is only a name
+    pub ffmpeg: PathBuf,
+    pub output: PathBuf,
+    child: Option<Child>,
+}
+
+impl FfmpegEncoder {
+    pub fn new(output: PathBuf) -> Self {
+        Self {
+            ffmpeg: PathBuf::from("ffmpeg"),
+            output,
+            child: None,
+        }
+    }
+}
+
+impl VideoEncoder for FfmpegEncoder {
+    fn begin(&mut self, width: u32, height: u32, fps: f64) -> Result<(), anyhow::Error> {
+        let child = Command::new(&self.ffmpeg)
+            .args(["-y", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgba"])
+            .args(["-s", &format!("{}x{}", width, height)])
+            .args(["-r", &format!("{}", fps)])
+            .args(["-i", "-"])
+            // Most codecs need even sizes for the chroma subsampling
+            .args([
+                "-vf",
+                "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+                "-pix_fmt",
+                "yuv420p",
+            ])
+            .arg(&self.output)
+            .stdin(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("running `{}` failed", self.ffmpeg.display()))?;
+
+        self.child = Some(child);
+        Ok(())
+    }
+
+    fn push_frame(&mut self, frame: &image::RgbaImage) -> Result<(), anyhow::Error> {
+        let stdin = self
+            .child
+            .as_mut()
+            .and_then(|child| child.stdin.as_mut())
+            .ok_or_else(|| anyhow::anyhow!("push_frame() failed, ffmpeg is not running"))?;
+
+        stdin
+            .write_all(frame.as_raw())
+            .context("writing the frame to ffmpeg failed")
+    }
+
+    fn finish(&mut self) -> Result<(), anyhow::Error> {
+        let mut child = self
+            .child
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("finish() failed, ffmpeg is not running"))?;
+        // Closing stdin ends the input
+        drop(child.stdin.take());
+
+        let status = child.wait().context("waiting for ffmpeg failed")?;
+        if !status.success() {
+            return Err(anyhow::anyhow!("ffmpeg failed with {}", status));
+        }
+
+        Ok(())
+    }
+}
+
+/// A version of a recorded stroke, shown from the beginning until the end. None if it is shown until the end of the recording
+#[derive(Debug, Clone)]
+struct StrokeVersion {
+    stroke: StrokeStyle,
+    begin: f64,
+    end: Option<f64>,
+}
+
+/// The versions of the recorded strokes, in the order the strokes were first inserted. Brush strokes which were drawn over time
+/// get partial versions for the steps they are drawn in
+fn stroke_versions(events: &[RecordedEvent], steps: usize) -> Vec<Vec<StrokeVersion>> {
+    let mut order = Vec::<StrokeKey>::new();
+    let mut versions = HashMap::<StrokeKey, Vec<StrokeVersion>>::new();
+
+    for event in events.iter() {
+        let key = event.action.key();
+        let begin = match &event.action {
+            RecordedAction::Insert { duration, .. } => event.t - duration,
+            RecordedAction::Change { .. } | RecordedAction::Remove { .. } => event.t,
+        };
+        if let Some(last) = versions
+            .get_mut(&key)
+            .and_then(|versions| versions.last_mut())
+        {
+            if last.end.is_none() {
+                last.end = Some(begin.max(last.begin));
+            }
+        }
+
+        let stroke = match &event.action {
+            RecordedAction::Insert {
+                stroke, duration, ..
+            } => {
+                if !versions.contains_key(&key) {
+                    order.push(key);
+                }
+                let key_versions = versions.entry(key).or_default();
+
+                if let StrokeStyle::BrushStroke(brushstroke) = stroke {
+                    let steps = steps.min(brushstroke.elements.len());
+                    if *duration > 0.0 && steps > 1 {
+                        key_versions.extend((0..steps).map(|i| {
+                            let progress = i as f64 / steps as f64;
+                            StrokeVersion {
+                                stroke: StrokeStyle::BrushStroke(replay::partial_brushstroke(
+                                    brushstroke,
+                                    progress,
+                                )),
+                                begin: begin + duration * progress,
+                                end: Some(begin + duration * (i + 1) as f64 / steps as f64),
+                            }
+                        }));
+                    }
+                }
+
+                stroke
+            }
+            RecordedAction::Change { stroke, .. } => stroke,
+            RecordedAction::Remove { .. } => continue,
+        };
+
+        versions.entry(key).or_default().push(StrokeVersion {
+            stroke: stroke.clone(),
+            begin: event.t,
+            end: None,
+        });
+    }
+
+    order
+        .into_iter()
+        .filter_map(|key| versions.remove(&key))
+        .collect()
+}
+
+/// The bounds of all recorded strokes
+fn recording_bounds(events: &[RecordedEvent]) -> Option<AABB> {
+    events
+        .iter()
+        .filter_map(|event| match &event.action {
+            RecordedAction::Insert { stroke, .. } | RecordedAction::Change { stroke, .. } => {
+                Some(stroke.bounds())
+            }
+            RecordedAction::Remove { .. } => None,
+        })
+        .reduce(|acc, bounds| acc.merged(&bounds))
+        .map(|bounds| bounds.loosened(ReplayExportPrefs::MARGIN))
+}
+
+/// Shows the svg data from the beginning until the end, in seconds of the animation
+fn animated_svg_group(svg_data: &str, begin: f64, end: Option<f64>) -> String {
+    // Shown from the start without being hidden again, so viewers without animations show it as well
+    if begin <= 0.0 && end.is_none() {
+        return format!("<g>\n{}\n</g>", svg_data);
+    }
+
+    let dur = end.map_or(String::new(), |end| {
+        format!(" dur=\"{:.3}s\"", (end - begin).max(0.001))
+    });
+    format!(
+        "<g visibility=\"hidden\">\n<set attributeName=\"visibility\" to=\"visible\" begin=\"{:.3}s\"{}/>\n{}\n</g>",
+        begin.max(0.0),
+        dur,
+        svg_data
+    )
+}
+
+impl Sheet {
+    /// Exports the session recording as SVG, which animates the strokes being drawn
+    pub fn export_replay_as_animated_svg_string(
+        &self,
+        prefs: &ReplayExportPrefs,
+    ) -> Result<String, anyhow::Error> {
+        let events = self.strokes_state.session_recording().events();
+        let bounds = recording_bounds(events).ok_or_else(|| {
+            anyhow::anyhow!("export_replay_as_animated_svg_string() failed, nothing is recorded")
+        })?;
+        let speed = prefs.speed();
+        let steps = prefs.svg_steps.clamp(
+            ReplayExportPrefs::SVG_STEPS_MIN,
+            ReplayExportPrefs::SVG_STEPS_MAX,
+        );
+
+        let mut groups = vec![];
+        if prefs.with_background {
+            groups.extend(
+                self.gen_background_svgs()?
+                    .into_iter()
+                    .map(|svg| svg.svg_data),
+            );
+        }
+        for key_versions in stroke_versions(events, steps) {
+            for version in key_versions {
+                let svg_data = version
+                    .stroke
+                    .gen_svgs(na::vector![0.0, 0.0])?
+                    .into_iter()
+                    .map(|svg| svg.svg_data)
+                    .collect::<Vec<String>>()
+                    .join("\n");
+
+                groups.push(animated_svg_group(
+                    &svg_data,
+                    version.begin / speed,
+                    version.end.map(|end| end / speed),
+                ));
+            }
+        }
+
+        Ok(compose::wrap_svg_root(
+            groups.join("\n").as_str(),
+            Some(bounds),
+            Some(bounds),
+            true,
+        ))
+    }
+
+    /// Exports the session recording as video, which shows the strokes being drawn. The frames are rendered and encoded on a new thread,
+    /// the receiver gets the result when the video is finished
+    #[cfg(feature = "cairo")]
+    pub fn export_replay_as_video(
+        &self,
+        prefs: ReplayExportPrefs,
+        mut encoder: Box<dyn VideoEncoder>,
+    ) -> Result<oneshot::Receiver<Result<(), anyhow::Error>>, anyhow::Error> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<Result<(), anyhow::Error>>();
+
+        let recording = self.strokes_state.session_recording();
+        let bounds = recording_bounds(recording.events()).ok_or_else(|| {
+            anyhow::anyhow!("export_replay_as_video() failed, nothing is recorded")
+        })?;
+        let mut replay = Replay::new(recording);
+
+        let sheet_bounds = self.bounds();
+        let background_svgs = if prefs.with_background {
+            Some(self.gen_background_svgs()?)
+        } else {
+            None
+        };
+        let zoom = prefs
+            .dpi
+            .clamp(ReplayExportPrefs::DPI_MIN, ReplayExportPrefs::DPI_MAX)
+            / self.format.dpi;
+        let fps = prefs
+            .fps
+            .clamp(ReplayExportPrefs::FPS_MIN, ReplayExportPrefs::FPS_MAX);
+        let speed = prefs.speed();
+        let hold_end = prefs.hold_end.clamp(
+            ReplayExportPrefs::HOLD_END_MIN,
+            ReplayExportPrefs::HOLD_END_MAX,
+        );
+
+        // Render the frames on a new thread to avoid blocking
+        utils::spawn(move || {
+            let result = || -> Result<(), anyhow::Error> {
+                let mut replayed = StrokesState::default();
+                let n_frames = ((replay.duration() / speed + hold_end) * fps).ceil() as usize + 1;
+
+                for frame in 0..n_frames {
+                    replay.seek(frame as f64 / fps * speed, &mut replayed);
+
+                    let image = render::cairo_drawing_to_image(bounds, zoom, |cx| {
+                        // Videos have no alpha channel
+                        cx.set_source_rgba(1.0, 1.0, 1.0, 1.0);
+                        cx.paint()?;
+                        if let Some(background_svgs) = &background_svgs {
+                            render::draw_svgs_to_cairo_context(
+                                1.0,
+                                background_svgs,
+                                sheet_bounds,
+                                cx,
+                            )?;
+                        }
+
+                        for key in replayed.keys_sorted_chrono() {
+                            if let Some(stroke) = replayed.get_stroke_ref(key) {
+                                stroke.draw_to_cairo_vector(cx)?;
+                            }
+                        }
+                        Ok(())
+                    })?
+                    .to_imgbuf()?;
+
+                    if frame == 0 {
+                        encoder.begin(image.width(), image.height(), fps)?;
+                    }
+                    encoder.push_frame(&image)?;
+                }
+
+                encoder.finish()
+            }();
+
+            if let Err(e) = &result {
+                log::error!("export_replay_as_video() failed with Err, {}", e);
+            }
+            oneshot_sender.send(result).ok();
+        });
+
+        Ok(oneshot_receiver)
+    }
+}
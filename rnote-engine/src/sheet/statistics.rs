@@ -0,0 +1,247 @@
+//! Statistics of the document, for a dialog of the document properties. They help to find out what makes a file large,
+//! e.g. embedded images or many long strokes.
+
+use std::collections::HashSet;
+
+use super::Sheet;
+use crate::drawbehaviour::DrawBehaviour;
+use crate::strokes::strokestyle::StrokeStyle;
+use crate::strokesstate::StrokeKey;
+
+use p2d::bounding_volume::{BoundingVolume, AABB};
+
+/// The number of strokes of each type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StrokeCounts {
+    pub brushstrokes: usize,
+    pub shapestrokes: usize,
+    pub vectorimages: usize,
+    pub bitmapimages: usize,
+    pub textstrokes: usize,
+    pub mathstrokes: usize,
+    pub checkboxstrokes: usize,
+    pub extensionstrokes: usize,
+}
+
+impl StrokeCounts {
+    pub fn total(&self) -> usize {
+        self.brushstrokes
+            + self.shapestrokes
+            + self.vectorimages
+            + self.bitmapimages
+            + self.textstrokes
+            + self.mathstrokes
+            + self.checkboxstrokes
+            + self.extensionstrokes
+    }
+
+    fn count(&mut self, stroke: &StrokeStyle) {
+        match stroke {
+            StrokeStyle::BrushStroke(_) => self.brushstrokes += 1,
+            StrokeStyle::ShapeStroke(_) => self.shapestrokes += 1,
+            StrokeStyle::VectorImage(_) => self.vectorimages += 1,
+            StrokeStyle::BitmapImage(_) => self.bitmapimages += 1,
+            StrokeStyle::TextStroke(_) => self.textstrokes += 1,
+            StrokeStyle::MathStroke(_) => self.mathstrokes += 1,
+            StrokeStyle::CheckboxStroke(_) => self.checkboxstrokes += 1,
+            StrokeStyle::ExtensionStroke(_) => self.extensionstrokes += 1,
+        }
+    }
+}
+
+/// The statistics of a page of the sheet, in the order of `Sheet::pages()`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageStatistics {
+    /// The bounds of the page
+    pub bounds: AABB,
+    /// The bounds of the strokes intersecting the page, clipped to the page. None if the page is empty
+    pub content_bounds: Option<AABB>,
+    /// The number of strokes intersecting the page. Strokes intersecting multiple pages are counted on each of them
+    pub n_strokes: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetKind {
+    VectorImage,
+    BitmapImage,
+    /// A PDF which was imported, and is kept to export annotated documents
+    Pdf,
+}
+
+/// An asset embedded in the document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssetStatistics {
+    pub kind: AssetKind,
+    /// The key of the image stroke. None for PDFs
+    pub key: Option<StrokeKey>,
+    /// The size of the asset in bytes, as it is stored in the file
+    pub size: usize,
+    /// Whether this is a copy of an asset listed before it. Copies are stored only once in the file
+    pub duplicate: bool,
+}
+
+/// The statistics of the document. Trashed strokes are left out, except for `n_trashed_strokes`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocumentStatistics {
+    pub stroke_counts: StrokeCounts,
+    /// The trashed strokes, which are removed when the history containing them is dropped
+    pub n_trashed_strokes: usize,
+    /// The total length of the paths of the brush strokes and the outlines of the shapes, in the coordinate space of the sheet
+    pub ink_length: f64,
+    /// The total number of elements of the brush strokes
+    pub n_elements: usize,
+    pub pages: Vec<PageStatistics>,
+    /// The embedded assets, the largest first
+    pub assets: Vec<AssetStatistics>,
+}
+
+impl DocumentStatistics {
+    pub fn new(sheet: &Sheet) -> Self {
+        let strokes_state = &sheet.strokes_state;
+        let mut statistics = Self {
+            pages: sheet
+                .pages()
+                .into_iter()
+                .map(|bounds| PageStatistics {
+                    bounds,
+                    content_bounds: None,
+                    n_strokes: 0,
+                })
+                .collect(),
+            ..Self::default()
+        };
+        // The data of the assets seen so far, to find the duplicates
+        let mut seen_assets = HashSet::new();
+
+        for key in strokes_state.keys_unordered() {
+            let stroke = match strokes_state.get_stroke_ref(key) {
+                Some(stroke) => stroke,
+                None => continue,
+            };
+            if strokes_state.trashed(key).unwrap_or(false) {
+                statistics.n_trashed_strokes += 1;
+                continue;
+            }
+
+            statistics.stroke_counts.count(stroke);
+
+            match stroke {
+                StrokeStyle::BrushStroke(brushstroke) => {
+                    statistics.n_elements += brushstroke.elements.len();
+                    statistics.ink_length += path_length(
+                        brushstroke
+                            .elements
+                            .iter()
+                            .map(|element| element.inputdata.pos()),
+                        false,
+                    );
+                }
+                StrokeStyle::ShapeStroke(shapestroke) => {
+                    statistics.ink_length += path_length(
+                        shapestroke.shape.outline_points().into_iter(),
+                        shapestroke.shape.is_closed(),
+                    );
+                }
+                StrokeStyle::VectorImage(vectorimage) => {
+                    statistics.assets.push(AssetStatistics {
+                        kind: AssetKind::VectorImage,
+                        key: Some(key),
+                        size: vectorimage.svg_data.len(),
+                        duplicate: !seen_assets.insert(vectorimage.svg_data.as_str()),
+                    });
+                }
+                StrokeStyle::BitmapImage(bitmapimage) => {
+                    statistics.assets.push(AssetStatistics {
+                        kind: AssetKind::BitmapImage,
+                        key: Some(key),
+                        size: base64_decoded_len(&bitmapimage.data_base64),
+                        duplicate: !seen_assets.insert(bitmapimage.data_base64.as_str()),
+                    });
+                }
+                StrokeStyle::TextStroke(_)
+                | StrokeStyle::MathStroke(_)
+                | StrokeStyle::CheckboxStroke(_)
+                | StrokeStyle::ExtensionStroke(_) => {}
+            }
+
+            let stroke_bounds = stroke.bounds();
+            for page in statistics.pages.iter_mut() {
+                if !page.bounds.intersects(&stroke_bounds) {
+                    continue;
+                }
+
+                let clipped = AABB::new(
+                    page.bounds.mins.sup(&stroke_bounds.mins),
+                    page.bounds.maxs.inf(&stroke_bounds.maxs),
+                );
+                page.n_strokes += 1;
+                page.content_bounds = Some(
+                    page.content_bounds
+                        .map_or(clipped, |content_bounds| content_bounds.merged(&clipped)),
+                );
+            }
+        }
+
+        for imported_pdf in strokes_state.imported_pdfs() {
+            statistics.assets.push(AssetStatistics {
+                kind: AssetKind::Pdf,
+                key: None,
+                size: base64_decoded_len(&imported_pdf.data_base64),
+                duplicate: !seen_assets.insert(imported_pdf.data_base64.as_str()),
+            });
+        }
+
+        // Stable, so the first of equal assets stays the one which is not a duplicate
+        statistics
+            .assets
+            .sort_by(|first, second| second.size.cmp(&first.size));
+
+        statistics
+    }
+
+    /// The size of the assets in bytes, with each asset counted once as it is stored in the file
+    pub fn assets_size(&self) -> usize {
+        self.assets
+            .iter()
+            .filter(|asset| !asset.duplicate)
+            .map(|asset| asset.size)
+            .sum()
+    }
+
+    /// The number of pages which contain strokes
+    pub fn n_pages_w_content(&self) -> usize {
+        self.pages
+            .iter()
+            .filter(|page| page.content_bounds.is_some())
+            .count()
+    }
+}
+
+/// The length of the path through the points, back to the first point if it is closed
+fn path_length(points: impl Iterator<Item = na::Vector2<f64>>, closed: bool) -> f64 {
+    let mut length = 0.0;
+    let mut first = None;
+    let mut prev: Option<na::Vector2<f64>> = None;
+
+    for point in points {
+        if let Some(prev) = prev {
+            length += (point - prev).magnitude();
+        } else {
+            first = Some(point);
+        }
+        prev = Some(point);
+    }
+
+    if let (true, Some(first), Some(last)) = (closed, first, prev) {
+        length += (first - last).magnitude();
+    }
+
+    length
+}
+
+/// The length of the data encoded in the base64 string
+fn base64_decoded_len(data: &str) -> usize {
+    let padding = data.bytes().rev().take_while(|&b| b == b'=').count();
+
+    (data.len() / 4 * 3).saturating_sub(padding)
+}
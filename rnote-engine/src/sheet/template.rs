@@ -0,0 +1,104 @@
+//! Templates for new sheets. A template is a .rnote file in the templates directory, holding the strokes, backgrounds, format and page layout of a sheet.
+//! The file stem is the name of the template.
+//!
+//! New sheets are instantiated from a template through `Sheet::new_from_template_bytes()`, which keeps nothing of the template file besides its content,
+//! so saving the new sheet never overwrites the template.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use super::Sheet;
+
+/// The file extension of templates
+pub const TEMPLATE_FILE_EXT: &str = "rnote";
+
+/// A template in the templates directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateInfo {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// The templates directory
+#[derive(Debug, Clone)]
+pub struct Templates {
+    dir: PathBuf,
+}
+
+impl Templates {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The available templates, sorted by their name. Empty if the templates directory does not exist yet
+    pub fn list(&self) -> Result<Vec<TemplateInfo>, anyhow::Error> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut templates = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if !path.is_file()
+                    || path.extension().and_then(|ext| ext.to_str()) != Some(TEMPLATE_FILE_EXT)
+                {
+                    return None;
+                }
+
+                Some(TemplateInfo {
+                    name: path.file_stem()?.to_str()?.to_string(),
+                    path,
+                })
+            })
+            .collect::<Vec<TemplateInfo>>();
+        templates.sort_by(|first, second| first.name.cmp(&second.name));
+
+        Ok(templates)
+    }
+
+    /// The path of the template with the name. Errors if the name can't be a file name
+    pub fn template_path(&self, name: &str) -> Result<PathBuf, anyhow::Error> {
+        let name = name.trim();
+        if name.is_empty()
+            || name.starts_with('.')
+            || name.contains(|c: char| std::path::is_separator(c) || c.is_control())
+        {
+            return Err(anyhow::anyhow!("`{}` is not a valid template name", name));
+        }
+
+        Ok(self.dir.join(format!("{}.{}", name, TEMPLATE_FILE_EXT)))
+    }
+
+    /// Saves the sheet as template with the name, replacing a template with the same name
+    pub fn save(&self, name: &str, sheet: &Sheet) -> Result<TemplateInfo, anyhow::Error> {
+        let path = self.template_path(name)?;
+        let bytes = sheet.save_as_template_bytes()?;
+
+        fs::create_dir_all(&self.dir).context("creating the templates directory failed")?;
+        fs::write(&path, &bytes).context("writing the template failed")?;
+
+        Ok(TemplateInfo {
+            name: name.trim().to_string(),
+            path,
+        })
+    }
+
+    pub fn remove(&self, name: &str) -> Result<(), anyhow::Error> {
+        fs::remove_file(self.template_path(name)?).context("removing the template failed")
+    }
+
+    /// Replaces the content of the sheet with a new sheet from the template with the name
+    pub fn instantiate(&self, name: &str, sheet: &mut Sheet) -> Result<(), anyhow::Error> {
+        let bytes = fs::read(self.template_path(name)?).context("reading the template failed")?;
+
+        sheet.new_from_template_bytes(bytes)
+    }
+}
@@ -0,0 +1,350 @@
+//! Small renderings of the pages, e.g. for a page overview panel.
+//! The thumbnails are cached per page together with the signature of the page they were rendered from ( see `Sheet::page_signature()` ),
+//! and rendered again on the threadpool when the signature changed, e.g. because a stroke on the page was edited.
+//! The signature is built from the revisions of the strokes, which change with every change of a stroke ( see `StrokeRevisions` ).
+
+use std::collections::hash_map::DefaultHasher;
+#[cfg(feature = "cairo")]
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+#[cfg(feature = "cairo")]
+use futures::channel::mpsc;
+#[cfg(feature = "cairo")]
+use futures::StreamExt;
+use p2d::bounding_volume::AABB;
+use slotmap::SecondaryMap;
+
+use super::Sheet;
+#[cfg(feature = "cairo")]
+use crate::render;
+use crate::strokesstate::StrokeKey;
+#[cfg(feature = "cairo")]
+use crate::utils;
+
+/// The revisions of the strokes. Every change of a stroke gives it a new revision
+#[derive(Debug, Clone, Default)]
+pub struct StrokeRevisions {
+    counter: u64,
+    /// The revision of strokes which did not change since they were replaced or cleared
+    base: u64,
+    revisions: SecondaryMap<StrokeKey, u64>,
+}
+
+impl StrokeRevisions {
+    pub fn mark(&mut self, key: StrokeKey) {
+        self.counter += 1;
+        self.revisions.insert(key, self.counter);
+    }
+
+    /// Gives every stroke a new revision, e.g. when the strokes were replaced
+    pub fn mark_all(&mut self) {
+        self.counter += 1;
+        self.base = self.counter;
+        self.revisions.clear();
+    }
+
+    pub fn revision(&self, key: StrokeKey) -> u64 {
+        self.revisions.get(key).copied().unwrap_or(self.base)
+    }
+}
+
+/// The thumbnail of a page
+#[cfg(feature = "cairo")]
+#[derive(Debug, Clone)]
+pub struct PageThumbnail {
+    pub page_index: usize,
+    /// The signature of the page the thumbnail was rendered from
+    pub signature: u64,
+    /// The rendered page, with the page bounds in sheet coordinates
+    pub image: render::Image,
+}
+
+/// Renders and caches the thumbnails of the pages of a sheet
+#[cfg(feature = "cairo")]
+#[derive(Debug)]
+pub struct PageThumbnails {
+    width: u32,
+    with_background: bool,
+    /// The thumbnails by page index. They are kept until the page is rendered again, so they might be outdated
+    thumbnails: Vec<Option<PageThumbnail>>,
+    /// The signatures of the pages which are being rendered
+    pending: HashMap<usize, u64>,
+    tx: mpsc::UnboundedSender<Result<PageThumbnail, usize>>,
+    rx: mpsc::UnboundedReceiver<Result<PageThumbnail, usize>>,
+}
+
+#[cfg(feature = "cairo")]
+impl Default for PageThumbnails {
+    fn default() -> Self {
+        Self::new(Self::WIDTH_DEFAULT)
+    }
+}
+
+#[cfg(feature = "cairo")]
+impl PageThumbnails {
+    pub const WIDTH_MIN: u32 = 16;
+    pub const WIDTH_MAX: u32 = 1024;
+    pub const WIDTH_DEFAULT: u32 = 160;
+
+    /// New thumbnails, rendered with the width in pixels
+    pub fn new(width: u32) -> Self {
+        let (tx, rx) = mpsc::unbounded();
+
+        Self {
+            width: width.clamp(Self::WIDTH_MIN, Self::WIDTH_MAX),
+            with_background: true,
+            thumbnails: vec![],
+            pending: HashMap::new(),
+            tx,
+            rx,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Changes the width of the thumbnails in pixels. All pages are rendered again on the next update
+    pub fn set_width(&mut self, width: u32) {
+        let width = width.clamp(Self::WIDTH_MIN, Self::WIDTH_MAX);
+        if width != self.width {
+            self.width = width;
+            self.invalidate_all();
+        }
+    }
+
+    pub fn with_background(&self) -> bool {
+        self.with_background
+    }
+
+    pub fn set_with_background(&mut self, with_background: bool) {
+        if with_background != self.with_background {
+            self.with_background = with_background;
+            self.invalidate_all();
+        }
+    }
+
+    /// The thumbnail of the page, which might be outdated until the page is rendered again
+    pub fn thumbnail(&self, page_index: usize) -> Option<&PageThumbnail> {
+        self.thumbnails.get(page_index)?.as_ref()
+    }
+
+    /// The thumbnails by page index. None for pages which are not rendered yet
+    pub fn thumbnails(&self) -> &[Option<PageThumbnail>] {
+        &self.thumbnails
+    }
+
+    /// The number of pages which are being rendered
+    pub fn n_pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Discards the thumbnails, so every page is rendered again on the next update
+    pub fn invalidate_all(&mut self) {
+        self.thumbnails.clear();
+        self.pending.clear();
+    }
+
+    /// Moves the thumbnail along with its page, so it is shown at the new position until the pages are rendered again
+    pub fn move_thumbnail(&mut self, from: usize, to: usize) {
+        if from >= self.thumbnails.len() || to >= self.thumbnails.len() {
+            return;
+        }
+
+        let moved = self.thumbnails.remove(from);
+        self.thumbnails.insert(to, moved);
+        for (page_index, thumbnail) in self.thumbnails.iter_mut().enumerate() {
+            if let Some(thumbnail) = thumbnail {
+                thumbnail.page_index = page_index;
+            }
+        }
+        self.pending.clear();
+    }
+
+    /// Takes the finished thumbnails, and starts rendering the pages whose thumbnail is missing or outdated on the threadpool.
+    /// Expected to be called periodically, e.g. when the sheet changed. Returns whether thumbnails were updated
+    pub fn update(&mut self, sheet: &Sheet) -> bool {
+        let pages = sheet.pages();
+        let updated = self.receive_finished(pages.len());
+        self.thumbnails.resize(pages.len(), None);
+
+        let background_signature = sheet.background_signature();
+        let outdated = pages
+            .iter()
+            .enumerate()
+            .filter_map(|(page_index, &page_bounds)| {
+                let signature = sheet.page_signature_w_bounds(page_bounds, background_signature);
+                let current = self.thumbnails[page_index]
+                    .as_ref()
+                    .map_or(false, |thumbnail| thumbnail.signature == signature);
+
+                if current || self.pending.get(&page_index) == Some(&signature) {
+                    None
+                } else {
+                    Some((page_index, page_bounds, signature))
+                }
+            })
+            .collect::<Vec<(usize, AABB, u64)>>();
+        if outdated.is_empty() {
+            return updated;
+        }
+
+        let background_svgs = if self.with_background {
+            match sheet.gen_background_svgs() {
+                Ok(background_svgs) => Some(background_svgs),
+                Err(e) => {
+                    log::error!(
+                        "gen_background_svgs() failed in PageThumbnails update() with Err {}",
+                        e
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let sheet_bounds = sheet.bounds();
+
+        for (page_index, page_bounds, signature) in outdated {
+            let layers = sheet
+                .strokes_state
+                .clone_strokes_for_bounds_by_layer(page_bounds);
+            let background_svgs = background_svgs.clone();
+            let zoom = f64::from(self.width) / page_bounds.extents()[0].max(1.0);
+            let tx = self.tx.clone();
+            self.pending.insert(page_index, signature);
+
+            // Rendered on the threadpool to not block the UI
+            utils::spawn(move || {
+                let image = render::cairo_drawing_to_image(page_bounds, zoom, |cx| {
+                    if let Some(background_svgs) = &background_svgs {
+                        render::draw_svgs_to_cairo_context(1.0, background_svgs, sheet_bounds, cx)?;
+                    }
+
+                    super::draw_layers_to_cairo(cx, &layers)
+                });
+
+                let result = match image {
+                    Ok(image) => Ok(PageThumbnail {
+                        page_index,
+                        signature,
+                        image,
+                    }),
+                    Err(e) => {
+                        log::error!(
+                            "rendering the thumbnail of page {} failed with Err {}",
+                            page_index,
+                            e
+                        );
+                        Err(page_index)
+                    }
+                };
+                if let Err(e) = tx.unbounded_send(result) {
+                    log::error!(
+                        "sending the thumbnail of page {} failed with Err {}",
+                        page_index,
+                        e
+                    );
+                }
+            });
+        }
+
+        updated
+    }
+
+    /// Takes the thumbnails which finished rendering. Thumbnails of outdated renderings are dropped
+    fn receive_finished(&mut self, n_pages: usize) -> bool {
+        let mut updated = false;
+
+        while let Ok(Some(result)) = self.rx.try_next() {
+            updated |= self.receive(result, n_pages);
+        }
+
+        updated
+    }
+
+    /// Blocks until the pages which are being rendered are finished, and takes their thumbnails. Returns whether thumbnails were updated
+    pub fn wait_for_pending(&mut self) -> bool {
+        let n_pages = self.thumbnails.len();
+        let mut updated = false;
+
+        while !self.pending.is_empty() {
+            match futures::executor::block_on(self.rx.next()) {
+                Some(result) => updated |= self.receive(result, n_pages),
+                None => break,
+            }
+        }
+
+        updated
+    }
+
+    /// Takes the rendered thumbnail, if its page is one of the pages. Returns whether the thumbnails were updated
+    fn receive(&mut self, result: Result<PageThumbnail, usize>, n_pages: usize) -> bool {
+        let page_index = match &result {
+            Ok(thumbnail) => thumbnail.page_index,
+            Err(page_index) => *page_index,
+        };
+        let signature = match self.pending.get(&page_index) {
+            Some(&signature) => signature,
+            None => return false,
+        };
+
+        match result {
+            Ok(thumbnail) if thumbnail.signature == signature => {
+                self.pending.remove(&page_index);
+                if page_index < n_pages {
+                    self.thumbnails
+                        .resize(self.thumbnails.len().max(n_pages), None);
+                    self.thumbnails[page_index] = Some(thumbnail);
+                    true
+                } else {
+                    false
+                }
+            }
+            // A newer rendering of the page is pending
+            Ok(_) => false,
+            Err(_) => {
+                self.pending.remove(&page_index);
+                false
+            }
+        }
+    }
+}
+
+impl Sheet {
+    /// The signature of the content of the page. It changes when a stroke on the page changes, is added or removed, or when the backgrounds change
+    pub fn page_signature(&self, page_index: usize) -> Option<u64> {
+        let page_bounds = *self.pages().get(page_index)?;
+
+        Some(self.page_signature_w_bounds(page_bounds, self.background_signature()))
+    }
+
+    fn page_signature_w_bounds(&self, page_bounds: AABB, background_signature: u64) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        background_signature.hash(&mut hasher);
+        for coord in page_bounds.mins.iter().chain(page_bounds.maxs.iter()) {
+            coord.to_bits().hash(&mut hasher);
+        }
+
+        self.strokes_state
+            .signature_for_bounds(page_bounds)
+            .hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    pub(crate) fn background_signature(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        match serde_json::to_string(&(&self.background, &self.background_regions)) {
+            Ok(backgrounds) => backgrounds.hash(&mut hasher),
+            Err(e) => log::error!(
+                "serializing the backgrounds failed in background_signature() with Err {}",
+                e
+            ),
+        }
+
+        hasher.finish()
+    }
+}
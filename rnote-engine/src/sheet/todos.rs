@@ -0,0 +1,57 @@
+//! The todos of the sheet, from its checkboxes ( see `CheckboxStroke` ), e.g. for a list of the open todos to jump to.
+
+use super::Sheet;
+use crate::strokesstate::StrokeKey;
+
+/// A checkbox of the sheet
+#[derive(Debug, Clone)]
+pub struct Todo {
+    /// The first line of the label of the checkbox
+    pub title: String,
+    pub checked: bool,
+    /// The key of the checkbox
+    pub key: StrokeKey,
+    /// The upper left corner of the box, in the coordinate space of the sheet
+    pub pos: na::Vector2<f64>,
+    /// The index of the page containing the checkbox
+    pub page_index: Option<usize>,
+}
+
+impl Sheet {
+    /// The todos of the sheet, checked or not, in reading order.
+    /// Only checkboxes which are not trashed and on visible layers are todos
+    pub fn todos(&self) -> Vec<Todo> {
+        let mut todos = self
+            .strokes_state
+            .keys_unordered()
+            .into_iter()
+            .filter(|&key| {
+                self.strokes_state.trashed(key) == Some(false)
+                    && self.strokes_state.layer_visible(key)
+            })
+            .filter_map(|key| {
+                let checkboxstroke = self.strokes_state.get_checkboxstroke(key)?;
+                let pos = checkboxstroke.pos();
+
+                Some(Todo {
+                    title: checkboxstroke.title().to_string(),
+                    checked: checkboxstroke.checked,
+                    key,
+                    pos,
+                    page_index: self.page_index_at(pos),
+                })
+            })
+            .collect::<Vec<Todo>>();
+        todos.sort_by(|first, second| self.cmp_reading_order(first.pos, second.pos));
+
+        todos
+    }
+
+    /// The todos which are not checked yet, in reading order
+    pub fn open_todos(&self) -> Vec<Todo> {
+        self.todos()
+            .into_iter()
+            .filter(|todo| !todo.checked)
+            .collect()
+    }
+}
@@ -7,9 +7,10 @@ use crate::compose::shapes;
 use crate::compose::transformable::{Transform, Transformable};
 use crate::drawbehaviour::DrawBehaviour;
 use crate::render::{self, Renderer};
+#[cfg(feature = "ui")]
+use crate::strokes::pdfimport::PdfPagePlacement;
 
 use anyhow::Context;
-use gtk4::cairo;
 use image::{io::Reader, GenericImageView};
 use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
@@ -22,6 +23,8 @@ pub enum BitmapImageFormat {
     Png,
     #[serde(rename = "jpeg")]
     Jpeg,
+    #[serde(rename = "webp")]
+    Webp,
 }
 
 impl BitmapImageFormat {
@@ -29,19 +32,25 @@ impl BitmapImageFormat {
         match self {
             BitmapImageFormat::Png => String::from("image/png"),
             BitmapImageFormat::Jpeg => String::from("image/jpeg"),
+            BitmapImageFormat::Webp => String::from("image/webp"),
         }
     }
 }
 
+/// A bitmap image. The original image bytes are kept, so cropping can always be undone
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "bitmapimage")]
 pub struct BitmapImage {
-    #[serde(rename = "data_base64")]
+    #[serde(rename = "data_base64", with = "crate::sheet::container::asset_base64")]
     pub data_base64: String,
     #[serde(rename = "format")]
     pub format: BitmapImageFormat,
     #[serde(rename = "intrinsic_size")]
     pub intrinsic_size: na::Vector2<f64>,
+    /// The drawn region of the image, in the pixel coordinates of the image. None when the image is not cropped
+    #[serde(rename = "crop")]
+    pub crop: Option<AABB>,
+    /// The rectangle the drawn region is mapped to
     #[serde(rename = "rectangle")]
     pub rectangle: shapes::Rectangle,
     #[serde(rename = "bounds")]
@@ -54,6 +63,7 @@ impl Default for BitmapImage {
             data_base64: String::default(),
             format: BitmapImageFormat::Png,
             intrinsic_size: na::vector![0.0, 0.0],
+            crop: None,
             rectangle: shapes::Rectangle::default(),
             bounds: AABB::new_zero(),
         }
@@ -78,8 +88,18 @@ impl DrawBehaviour for BitmapImage {
         rectangle.transform.append_translation_mut(offset);
 
         let transform_string = rectangle.transform.to_svg_transform_attr_str();
+        let crop_bounds = self.crop_bounds();
 
-        let svg_root = element::Image::new()
+        let image = element::Image::new()
+            .set("x", 0.0)
+            .set("y", 0.0)
+            .set("width", self.intrinsic_size[0])
+            .set("height", self.intrinsic_size[1])
+            .set("preserveAspectRatio", "none")
+            .set("href", self.gen_href_data()?);
+
+        // The nested svg maps the cropped region onto the rectangle and clips the rest of the image
+        let svg_root = element::SVG::new()
             .set("x", -self.rectangle.cuboid.half_extents[0])
             .set("y", -self.rectangle.cuboid.half_extents[1])
             .set("width", 2.0 * self.rectangle.cuboid.half_extents[0])
@@ -88,21 +108,20 @@ impl DrawBehaviour for BitmapImage {
                 "viewBox",
                 format!(
                     "{:.3} {:.3} {:.3} {:.3}",
-                    0.0, 0.0, self.intrinsic_size[0], self.intrinsic_size[1]
+                    crop_bounds.mins[0],
+                    crop_bounds.mins[1],
+                    crop_bounds.extents()[0],
+                    crop_bounds.extents()[1]
                 ),
             )
             .set("preserveAspectRatio", "none")
+            .add(image);
+
+        let group = element::Group::new()
             .set("transform", transform_string)
-            .set(
-                "href",
-                format!(
-                    "data:{mime_type};base64,{data_base64}",
-                    mime_type = &self.format.as_mime_type(),
-                    data_base64 = &self.data_base64
-                ),
-            );
+            .add(svg_root);
 
-        let svg_data = compose::svg_node_to_string(&svg_root)?;
+        let svg_data = compose::svg_node_to_string(&group)?;
         let svg = render::Svg {
             bounds: self.bounds.translate(offset),
             svg_data,
@@ -132,6 +151,8 @@ impl Transformable for BitmapImage {
 impl BitmapImage {
     pub const OFFSET_X_DEFAULT: f64 = 32.0;
     pub const OFFSET_Y_DEFAULT: f64 = 32.0;
+    /// The min size of the cropped region, in pixels
+    pub const CROP_SIZE_MIN: f64 = 1.0;
 
     pub fn import_from_image_bytes<P>(
         to_be_read: P,
@@ -146,6 +167,7 @@ impl BitmapImage {
         let format = match reader.format() {
             Some(image::ImageFormat::Png) => BitmapImageFormat::Png,
             Some(image::ImageFormat::Jpeg) => BitmapImageFormat::Jpeg,
+            Some(image::ImageFormat::WebP) => BitmapImageFormat::Webp,
             _ => {
                 return Err(anyhow::Error::msg("unsupported format."));
             }
@@ -167,6 +189,7 @@ impl BitmapImage {
             data_base64,
             format,
             intrinsic_size,
+            crop: None,
             rectangle,
             bounds: AABB::new_zero(),
         };
@@ -175,78 +198,57 @@ impl BitmapImage {
         Ok(bitmapimage)
     }
 
-    pub fn import_from_pdf_bytes(
-        to_be_read: &[u8],
-        pos: na::Vector2<f64>,
-        page_width: Option<i32>,
-    ) -> Result<Vec<Self>, anyhow::Error> {
-        let doc = poppler::Document::from_data(to_be_read, None)?;
-
-        let mut images = Vec::new();
-
-        for i in 0..doc.n_pages() {
-            if let Some(page) = doc.page(i) {
-                let intrinsic_size = page.size();
-
-                let (width, height, zoom) = if let Some(page_width) = page_width {
-                    let zoom = f64::from(page_width) / intrinsic_size.0;
-
-                    (page_width, (intrinsic_size.1 * zoom).round() as i32, zoom)
-                } else {
-                    (
-                        intrinsic_size.0.round() as i32,
-                        intrinsic_size.1.round() as i32,
-                        1.0,
-                    )
-                };
-
-                let x = pos[0];
-                let y = pos[1]
-                    + f64::from(i) * (f64::from(height) + f64::from(Self::OFFSET_Y_DEFAULT) / 2.0);
-
-                let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height)
-                    .map_err(|e| {
-                        anyhow::anyhow!(
-                            "create ImageSurface with dimensions ({}, {}) failed, {}",
-                            width,
-                            height,
-                            e
-                        )
-                    })?;
-
-                {
-                    let cx = cairo::Context::new(&surface).context("new cairo::Context failed")?;
-                    cx.scale(zoom, zoom);
-
-                    // Set margin to white
-                    cx.set_source_rgba(1.0, 1.0, 1.0, 1.0);
-                    cx.paint()?;
-
-                    page.render(&cx);
-
-                    cx.scale(1.0 / zoom, 1.0 / zoom);
-
-                    // Draw outline around page
-                    cx.set_source_rgba(0.7, 0.5, 0.5, 1.0);
-                    let line_width = 1.0;
-                    cx.set_line_width(line_width);
-                    cx.rectangle(
-                        line_width / 2.0,
-                        line_width / 2.0,
-                        f64::from(width) - line_width,
-                        f64::from(height) - line_width,
-                    );
-                    cx.stroke()?;
-                }
-
-                let mut png_data: Vec<u8> = Vec::new();
-                surface.write_to_png(&mut png_data)?;
-
-                images.push(Self::import_from_image_bytes(&png_data, na::vector![x, y])?);
-            }
+    /// Renders the page of a PDF as bitmap image, with the placement from `PdfImportOptions::place_pages()`
+    #[cfg(feature = "ui")]
+    pub fn import_from_pdf_page(
+        page: &poppler::Page,
+        placement: &PdfPagePlacement,
+    ) -> Result<Self, anyhow::Error> {
+        let intrinsic_size = page.size();
+
+        let width = placement.size[0].round() as i32;
+        let height = placement.size[1].round() as i32;
+        let zoom = f64::from(width) / intrinsic_size.0;
+
+        let surface =
+            cairo::ImageSurface::create(cairo::Format::ARgb32, width, height).map_err(|e| {
+                anyhow::anyhow!(
+                    "create ImageSurface with dimensions ({}, {}) failed, {}",
+                    width,
+                    height,
+                    e
+                )
+            })?;
+
+        {
+            let cx = cairo::Context::new(&surface).context("new cairo::Context failed")?;
+            cx.scale(zoom, zoom);
+
+            // Set margin to white
+            cx.set_source_rgba(1.0, 1.0, 1.0, 1.0);
+            cx.paint()?;
+
+            page.render(&cx);
+
+            cx.scale(1.0 / zoom, 1.0 / zoom);
+
+            // Draw outline around page
+            cx.set_source_rgba(0.7, 0.5, 0.5, 1.0);
+            let line_width = 1.0;
+            cx.set_line_width(line_width);
+            cx.rectangle(
+                line_width / 2.0,
+                line_width / 2.0,
+                f64::from(width) - line_width,
+                f64::from(height) - line_width,
+            );
+            cx.stroke()?;
         }
 
-        Ok(images)
+        let mut png_data: Vec<u8> = Vec::new();
+        surface.write_to_png(&mut png_data)?;
+
+        Self::import_from_image_bytes(&png_data, placement.pos)
     }
 
     pub fn update_geometry(&mut self) {
@@ -255,6 +257,78 @@ impl BitmapImage {
         }
     }
 
+    /// The drawn region of the image, in the pixel coordinates of the image
+    pub fn crop_bounds(&self) -> AABB {
+        self.crop.unwrap_or_else(|| {
+            AABB::new(na::point![0.0, 0.0], na::Point2::from(self.intrinsic_size))
+        })
+    }
+
+    /// Crops the image to the region, in the pixel coordinates of the image. None removes the crop.
+    /// The rectangle shrinks or grows with the drawn region, so the visible part of the image stays where it is
+    pub fn set_crop(&mut self, crop: Option<AABB>) {
+        let new_crop = match crop {
+            Some(crop) => {
+                let mins = na::point![
+                    crop.mins[0].clamp(0.0, self.intrinsic_size[0] - Self::CROP_SIZE_MIN),
+                    crop.mins[1].clamp(0.0, self.intrinsic_size[1] - Self::CROP_SIZE_MIN)
+                ];
+                let maxs = na::point![
+                    crop.maxs[0].clamp(mins[0] + Self::CROP_SIZE_MIN, self.intrinsic_size[0]),
+                    crop.maxs[1].clamp(mins[1] + Self::CROP_SIZE_MIN, self.intrinsic_size[1])
+                ];
+
+                AABB::new(mins, maxs)
+            }
+            None => AABB::new(na::point![0.0, 0.0], na::Point2::from(self.intrinsic_size)),
+        };
+        let old_crop = self.crop_bounds();
+
+        // The scale from image pixels to the coordinate space of the rectangle
+        let half_extents = self.rectangle.cuboid.half_extents;
+        let scale = (half_extents * 2.0).component_div(&old_crop.extents());
+        let local_mins = (new_crop.mins - old_crop.mins).component_mul(&scale) - half_extents;
+        let local_maxs = (new_crop.maxs - old_crop.mins).component_mul(&scale) - half_extents;
+
+        self.rectangle.cuboid.half_extents = (local_maxs - local_mins) * 0.5;
+        self.rectangle.transform.transform *=
+            na::Translation2::from((local_mins + local_maxs) * 0.5);
+
+        self.crop = if new_crop.mins == na::point![0.0, 0.0]
+            && new_crop.maxs == na::Point2::from(self.intrinsic_size)
+        {
+            None
+        } else {
+            Some(new_crop)
+        };
+
+        self.update_geometry();
+    }
+
+    /// The data url for the image. Webp images are embedded as png, because not all svg renderers support them
+    fn gen_href_data(&self) -> Result<String, anyhow::Error> {
+        match self.format {
+            BitmapImageFormat::Png | BitmapImageFormat::Jpeg => Ok(format!(
+                "data:{mime_type};base64,{data_base64}",
+                mime_type = &self.format.as_mime_type(),
+                data_base64 = &self.data_base64
+            )),
+            BitmapImageFormat::Webp => {
+                let bytes = base64::decode(&self.data_base64)?;
+                let image = image::load_from_memory_with_format(&bytes, image::ImageFormat::WebP)
+                    .context("decoding webp image failed in gen_href_data()")?;
+
+                let mut png_bytes = Vec::<u8>::new();
+                image.write_to(&mut png_bytes, image::ImageOutputFormat::Png)?;
+
+                Ok(format!(
+                    "data:image/png;base64,{}",
+                    base64::encode(&png_bytes)
+                ))
+            }
+        }
+    }
+
     pub fn export_as_image_bytes(
         &self,
         zoom: f64,
@@ -1,16 +1,28 @@
-use crate::compose::geometry::AABBHelpers;
+use crate::compose::calligraphy::CalligraphyOptions;
+use crate::compose::color::Color;
+use crate::compose::geometry::{self, AABBHelpers};
+use crate::compose::hatched::HatchedOptions;
+#[cfg(feature = "ui")]
+use crate::compose::highlighter::HighlighterBlendMode;
+use crate::compose::highlighter::HighlighterOptions;
 use crate::compose::smooth::SmoothOptions;
-use crate::compose::textured::TexturedOptions;
+use crate::compose::spray::SprayOptions;
+use crate::compose::tessellation::{Primitive, Tessellation};
+use crate::compose::textured::{TexturedDotsShape, TexturedOptions};
 use crate::compose::transformable::Transformable;
-use crate::compose::{self, curves, smooth, textured};
+use crate::compose::{self, calligraphy, curves, hatched, highlighter, smooth, spray, textured};
 use crate::drawbehaviour::DrawBehaviour;
 use crate::pens::brush::Brush;
 use crate::pens::brush::BrushStyle;
+use crate::pens::eraser::EraserFootprint;
 use crate::strokes::inputdata::InputData;
 use crate::{render, utils};
 
+#[cfg(feature = "ui")]
+use gtk4::gsk;
 use p2d::bounding_volume::{BoundingVolume, AABB};
 use rand::{Rng, SeedableRng};
+#[cfg(not(target_arch = "wasm32"))]
 use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
 use svg::node::element::path;
@@ -35,6 +47,26 @@ pub enum BrushStrokeStyle {
         #[serde(rename = "options")]
         options: TexturedOptions,
     },
+    #[serde(rename = "spray")]
+    Spray {
+        #[serde(rename = "options")]
+        options: SprayOptions,
+    },
+    #[serde(rename = "highlighter")]
+    Highlighter {
+        #[serde(rename = "options")]
+        options: HighlighterOptions,
+    },
+    #[serde(rename = "calligraphy")]
+    Calligraphy {
+        #[serde(rename = "options")]
+        options: CalligraphyOptions,
+    },
+    #[serde(rename = "hatched")]
+    Hatched {
+        #[serde(rename = "options")]
+        options: HatchedOptions,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,48 +102,68 @@ impl DrawBehaviour for BrushStroke {
             let mut bounds = AABB::new_invalid();
             bounds.take_point(na::Point2::from(first.inputdata.pos()));
 
-            bounds.merge(
-                &self
-                    .elements
-                    .par_iter()
-                    .zip(self.elements.par_iter().skip(1))
-                    .zip(self.elements.par_iter().skip(2))
-                    .zip(self.elements.par_iter().skip(3))
-                    .filter_map(|(((first, second), third), forth)| {
-                        let mut bounds = AABB::new_invalid();
-
-                        let width = match &self.style {
-                            BrushStrokeStyle::Marker { options } => options.width,
-                            BrushStrokeStyle::Solid { options } => options.width,
-                            BrushStrokeStyle::Textured { options } => options.width,
-                        };
-
-                        if let Some(cubbez) = curves::gen_cubbez_w_catmull_rom(
-                            first.inputdata.pos(),
-                            second.inputdata.pos(),
-                            third.inputdata.pos(),
-                            forth.inputdata.pos(),
-                        ) {
-                            // Bounds are definitely inside the polygon of the control points. (Could be improved with the second derivative of the bezier curve)
-                            bounds.take_point(na::Point2::from(cubbez.start));
-                            bounds.take_point(na::Point2::from(cubbez.cp1));
-                            bounds.take_point(na::Point2::from(cubbez.cp2));
-                            bounds.take_point(na::Point2::from(cubbez.end));
-                        } else if let Some(line) =
-                            curves::gen_line(second.inputdata.pos(), third.inputdata.pos())
-                        {
-                            bounds.take_point(na::Point2::from(line.start));
-                            bounds.take_point(na::Point2::from(line.end));
-                        } else {
-                            return None;
-                        }
+            // The bounds of the segments between the second and the third of every four elements
+            let segment_bounds = |(((first, second), third), forth): (
+                ((&Element, &Element), &Element),
+                &Element,
+            )| {
+                let mut bounds = AABB::new_invalid();
+
+                let width = match &self.style {
+                    BrushStrokeStyle::Marker { options } => options.width,
+                    BrushStrokeStyle::Solid { options } => options.width_max_w_tilt(),
+                    BrushStrokeStyle::Textured { options } => options.width,
+                    BrushStrokeStyle::Spray { options } => options.width,
+                    BrushStrokeStyle::Highlighter { options } => options.width,
+                    BrushStrokeStyle::Calligraphy { options } => options.width,
+                    BrushStrokeStyle::Hatched { options } => options.width,
+                };
+
+                if let Some(cubbez) = curves::gen_cubbez_w_catmull_rom(
+                    first.inputdata.pos(),
+                    second.inputdata.pos(),
+                    third.inputdata.pos(),
+                    forth.inputdata.pos(),
+                ) {
+                    // Bounds are definitely inside the polygon of the control points. (Could be improved with the second derivative of the bezier curve)
+                    bounds.take_point(na::Point2::from(cubbez.start));
+                    bounds.take_point(na::Point2::from(cubbez.cp1));
+                    bounds.take_point(na::Point2::from(cubbez.cp2));
+                    bounds.take_point(na::Point2::from(cubbez.end));
+                } else if let Some(line) =
+                    curves::gen_line(second.inputdata.pos(), third.inputdata.pos())
+                {
+                    bounds.take_point(na::Point2::from(line.start));
+                    bounds.take_point(na::Point2::from(line.end));
+                } else {
+                    return None;
+                }
 
-                        bounds.loosen(width + 1.0);
+                bounds.loosen(width + 1.0);
 
-                        Some(bounds)
-                    })
-                    .reduce(AABB::new_invalid, |i, next| i.merged(&next)),
-            );
+                Some(bounds)
+            };
+
+            #[cfg(not(target_arch = "wasm32"))]
+            let segments_bounds = self
+                .elements
+                .par_iter()
+                .zip(self.elements.par_iter().skip(1))
+                .zip(self.elements.par_iter().skip(2))
+                .zip(self.elements.par_iter().skip(3))
+                .filter_map(segment_bounds)
+                .reduce(AABB::new_invalid, |i, next| i.merged(&next));
+            #[cfg(target_arch = "wasm32")]
+            let segments_bounds = self
+                .elements
+                .iter()
+                .zip(self.elements.iter().skip(1))
+                .zip(self.elements.iter().skip(2))
+                .zip(self.elements.iter().skip(3))
+                .filter_map(segment_bounds)
+                .fold(AABB::new_invalid(), |i, next| i.merged(&next));
+
+            bounds.merge(&segments_bounds);
             Some(bounds)
         } else {
             None
@@ -121,11 +173,23 @@ impl DrawBehaviour for BrushStroke {
     fn gen_svgs(&self, offset: na::Vector2<f64>) -> Result<Vec<render::Svg>, anyhow::Error> {
         let svg_root = false;
 
-        match self.style {
-            BrushStrokeStyle::Marker { options } => self.gen_svgs_marker(options, offset, svg_root),
-            BrushStrokeStyle::Solid { options } => self.gen_svgs_solid(options, offset, svg_root),
+        match &self.style {
+            BrushStrokeStyle::Marker { options } => {
+                self.gen_svgs_marker(*options, offset, svg_root)
+            }
+            BrushStrokeStyle::Solid { options } => self.gen_svgs_solid(*options, offset, svg_root),
             BrushStrokeStyle::Textured { options } => {
-                self.gen_svgs_textured(options, offset, svg_root)
+                self.gen_svgs_textured(options.clone(), offset, svg_root)
+            }
+            BrushStrokeStyle::Spray { options } => self.gen_svgs_spray(*options, offset, svg_root),
+            BrushStrokeStyle::Highlighter { options } => {
+                self.gen_svgs_highlighter(*options, offset, svg_root)
+            }
+            BrushStrokeStyle::Calligraphy { options } => {
+                self.gen_svgs_calligraphy(*options, offset, svg_root)
+            }
+            BrushStrokeStyle::Hatched { options } => {
+                self.gen_svgs_hatched(*options, offset, svg_root)
             }
         }
     }
@@ -146,6 +210,10 @@ impl Transformable for BrushStroke {
             element
                 .inputdata
                 .set_pos((isometry * na::Point2::from(element.inputdata.pos())).coords);
+            // The tilt is relative to the sheet, so it rotates with the stroke
+            element
+                .inputdata
+                .set_tilt(isometry.rotation * element.inputdata.tilt());
         });
         self.update_geometry();
     }
@@ -156,6 +224,13 @@ impl Transformable for BrushStroke {
             element
                 .inputdata
                 .set_pos(((element.inputdata.pos() - center).component_mul(&scale)) + center);
+            // Negative scales mirror the stroke, so the tilt is mirrored as well
+            element.inputdata.set_tilt(
+                element
+                    .inputdata
+                    .tilt()
+                    .component_mul(&scale.map(|s| s.signum())),
+            );
         });
         self.update_geometry();
     }
@@ -163,11 +238,109 @@ impl Transformable for BrushStroke {
 
 impl BrushStroke {
     pub const HITBOX_DEFAULT: f64 = 10.0;
+    /// The assumed duration between two elements in seconds, if they have no timestamps
+    pub const SPRAY_ELEM_DURATION_FALLBACK: f64 = 1.0 / 60.0;
+    /// The amount of lines the curve of a segment is approximated with when it is tessellated
+    const TESSELLATION_SEGMENT_SPLITS: i32 = 5;
 
     pub fn new(element: Element, brush: &Brush) -> Self {
         let seed = Some(rand_pcg::Pcg64::from_entropy().gen());
 
-        let style = match brush.style {
+        let style = Self::style_from_brush(brush, seed);
+        let elements = Vec::with_capacity(4);
+        let bounds = AABB::new(
+            na::point![element.inputdata.pos()[0], element.inputdata.pos()[1]],
+            na::point![element.inputdata.pos()[0], element.inputdata.pos()[1]],
+        );
+        let hitbox = Vec::new();
+
+        let mut brushstroke = Self {
+            elements,
+            style,
+            bounds,
+            hitboxes: hitbox,
+        };
+
+        // Pushing with push_elem() instead filling vector, because bounds are getting updated there too
+        brushstroke.push_elem(element);
+
+        brushstroke
+    }
+
+    /// The seed of the style options
+    pub fn seed(&self) -> Option<u64> {
+        match &self.style {
+            BrushStrokeStyle::Marker { options } => options.seed,
+            BrushStrokeStyle::Solid { options } => options.seed,
+            BrushStrokeStyle::Textured { options } => options.seed,
+            BrushStrokeStyle::Spray { options } => options.seed,
+            BrushStrokeStyle::Highlighter { options } => options.seed,
+            BrushStrokeStyle::Calligraphy { options } => options.seed,
+            BrushStrokeStyle::Hatched { options } => options.seed,
+        }
+    }
+
+    /// Sets the seed of the style options
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        match &mut self.style {
+            BrushStrokeStyle::Marker { options } => options.seed = seed,
+            BrushStrokeStyle::Solid { options } => options.seed = seed,
+            BrushStrokeStyle::Textured { options } => options.seed = seed,
+            BrushStrokeStyle::Spray { options } => options.seed = seed,
+            BrushStrokeStyle::Highlighter { options } => options.seed = seed,
+            BrushStrokeStyle::Calligraphy { options } => options.seed = seed,
+            BrushStrokeStyle::Hatched { options } => options.seed = seed,
+        }
+    }
+
+    /// Restyles the stroke with the current style and options of the brush. The seed is kept, so textured and sprayed strokes keep their pattern
+    pub fn set_style_from_brush(&mut self, brush: &Brush) {
+        self.style = Self::style_from_brush(brush, self.seed());
+        self.update_geometry();
+    }
+
+    /// The stroke color
+    pub fn stroke_color(&self) -> Option<Color> {
+        match &self.style {
+            BrushStrokeStyle::Marker { options } => options.stroke_color,
+            BrushStrokeStyle::Solid { options } => options.stroke_color,
+            BrushStrokeStyle::Textured { options } => options.stroke_color,
+            BrushStrokeStyle::Spray { options } => options.stroke_color,
+            BrushStrokeStyle::Highlighter { options } => options.stroke_color,
+            BrushStrokeStyle::Calligraphy { options } => options.stroke_color,
+            BrushStrokeStyle::Hatched { options } => options.stroke_color,
+        }
+    }
+
+    /// Sets the stroke color
+    pub fn set_stroke_color(&mut self, stroke_color: Option<Color>) {
+        match &mut self.style {
+            BrushStrokeStyle::Marker { options } => options.stroke_color = stroke_color,
+            BrushStrokeStyle::Solid { options } => options.stroke_color = stroke_color,
+            BrushStrokeStyle::Textured { options } => options.stroke_color = stroke_color,
+            BrushStrokeStyle::Spray { options } => options.stroke_color = stroke_color,
+            BrushStrokeStyle::Highlighter { options } => options.stroke_color = stroke_color,
+            BrushStrokeStyle::Calligraphy { options } => options.stroke_color = stroke_color,
+            BrushStrokeStyle::Hatched { options } => options.stroke_color = stroke_color,
+        }
+    }
+
+    /// Sets the width. The bounds depend on it, so the geometry is updated
+    pub fn set_width(&mut self, width: f64) {
+        match &mut self.style {
+            BrushStrokeStyle::Marker { options } => options.width = width,
+            BrushStrokeStyle::Solid { options } => options.width = width,
+            BrushStrokeStyle::Textured { options } => options.width = width,
+            BrushStrokeStyle::Spray { options } => options.width = width,
+            BrushStrokeStyle::Highlighter { options } => options.width = width,
+            BrushStrokeStyle::Calligraphy { options } => options.width = width,
+            BrushStrokeStyle::Hatched { options } => options.width = width,
+        }
+        self.update_geometry();
+    }
+
+    fn style_from_brush(brush: &Brush, seed: Option<u64>) -> BrushStrokeStyle {
+        match brush.style {
             BrushStyle::Marker => {
                 let mut options = brush.smooth_options;
                 options.seed = seed;
@@ -181,30 +354,36 @@ impl BrushStroke {
                 BrushStrokeStyle::Solid { options }
             }
             BrushStyle::Textured => {
-                let mut options = brush.textured_options;
+                let mut options = brush.textured_options.clone();
                 options.seed = seed;
 
                 BrushStrokeStyle::Textured { options }
             }
-        };
-        let elements = Vec::with_capacity(4);
-        let bounds = AABB::new(
-            na::point![element.inputdata.pos()[0], element.inputdata.pos()[1]],
-            na::point![element.inputdata.pos()[0], element.inputdata.pos()[1]],
-        );
-        let hitbox = Vec::new();
+            BrushStyle::Spray => {
+                let mut options = brush.spray_options;
+                options.seed = seed;
 
-        let mut brushstroke = Self {
-            elements,
-            style,
-            bounds,
-            hitboxes: hitbox,
-        };
+                BrushStrokeStyle::Spray { options }
+            }
+            BrushStyle::Highlighter => {
+                let mut options = brush.highlighter_options;
+                options.seed = seed;
 
-        // Pushing with push_elem() instead filling vector, because bounds are getting updated there too
-        brushstroke.push_elem(element);
+                BrushStrokeStyle::Highlighter { options }
+            }
+            BrushStyle::Calligraphy => {
+                let mut options = brush.calligraphy_options;
+                options.seed = seed;
 
-        brushstroke
+                BrushStrokeStyle::Calligraphy { options }
+            }
+            BrushStyle::Hatched => {
+                let mut options = brush.hatched_options;
+                options.seed = seed;
+
+                BrushStrokeStyle::Hatched { options }
+            }
+        }
     }
 
     pub fn new_w_elements(
@@ -246,12 +425,154 @@ impl BrushStroke {
         self.hitboxes = self.gen_hitboxes();
     }
 
+    /// Splits the stroke where it is overlapped by the eraser footprint, removing the overlapped portions.
+    /// Returns the remaining segments as new strokes ( which might be none, if the footprint covers the entire stroke ),
+    /// or None if the stroke is not overlapped by the footprint
+    pub fn split_w_eraser_footprint(&self, footprint: &EraserFootprint) -> Option<Vec<Self>> {
+        let width = match &self.style {
+            BrushStrokeStyle::Marker { options } => options.width,
+            BrushStrokeStyle::Solid { options } => options.width,
+            BrushStrokeStyle::Textured { options } => options.width,
+            BrushStrokeStyle::Spray { options } => options.width,
+            BrushStrokeStyle::Highlighter { options } => options.width,
+            BrushStrokeStyle::Calligraphy { options } => options.width,
+            BrushStrokeStyle::Hatched { options } => options.width,
+        };
+        // The visible stroke should end at the footprint, not its center line
+        let footprint = footprint.loosened(width * 0.5);
+
+        if !footprint.intersects_aabb(&self.bounds) {
+            return None;
+        }
+
+        if self.elements.len() == 1 {
+            return if footprint.contains_point(self.elements[0].inputdata.pos()) {
+                Some(vec![])
+            } else {
+                None
+            };
+        }
+
+        let mut overlapped = false;
+        let mut segments: Vec<Vec<Element>> = Vec::new();
+        let mut current: Vec<Element> = Vec::new();
+
+        for (first, second) in self.elements.iter().zip(self.elements.iter().skip(1)) {
+            match footprint.clip_line(first.inputdata.pos(), second.inputdata.pos()) {
+                None => {
+                    if current.is_empty() {
+                        current.push(*first);
+                    }
+                    current.push(*second);
+                }
+                Some((t_start, t_end)) => {
+                    overlapped = true;
+
+                    if t_start > 0.0 {
+                        if current.is_empty() {
+                            current.push(*first);
+                        }
+                        current.push(Self::interpolate_elems(first, second, t_start));
+                    }
+                    if current.len() > 1 {
+                        segments.push(std::mem::take(&mut current));
+                    } else {
+                        current.clear();
+                    }
+
+                    if t_end < 1.0 {
+                        current.push(Self::interpolate_elems(first, second, t_end));
+                        current.push(*second);
+                    }
+                }
+            }
+        }
+        if current.len() > 1 {
+            segments.push(current);
+        }
+
+        if !overlapped {
+            return None;
+        }
+
+        Some(
+            segments
+                .into_iter()
+                .map(|elements| {
+                    let mut segment = Self {
+                        elements,
+                        style: self.style.clone(),
+                        bounds: self.bounds,
+                        hitboxes: Vec::new(),
+                    };
+                    segment.update_geometry();
+
+                    segment
+                })
+                .collect(),
+        )
+    }
+
+    /// Whether the position hits the stroke path, within the tolerance outside of the stroke width
+    pub fn hittest(&self, pos: na::Vector2<f64>, tolerance: f64) -> bool {
+        let width = match &self.style {
+            BrushStrokeStyle::Marker { options } => options.width,
+            BrushStrokeStyle::Solid { options } => options.width,
+            BrushStrokeStyle::Textured { options } => options.width,
+            BrushStrokeStyle::Spray { options } => options.width,
+            BrushStrokeStyle::Highlighter { options } => options.width,
+            BrushStrokeStyle::Calligraphy { options } => options.width,
+            BrushStrokeStyle::Hatched { options } => options.width,
+        };
+        let hit_dist = width * 0.5 + tolerance;
+
+        if !self
+            .bounds
+            .loosened(tolerance)
+            .contains_local_point(&na::Point2::from(pos))
+        {
+            return false;
+        }
+
+        match self.elements.len() {
+            0 => false,
+            1 => (pos - self.elements[0].inputdata.pos()).magnitude() <= hit_dist,
+            _ => self
+                .elements
+                .iter()
+                .zip(self.elements.iter().skip(1))
+                .any(|(first, second)| {
+                    geometry::dist_to_segment(pos, first.inputdata.pos(), second.inputdata.pos())
+                        <= hit_dist
+                }),
+        }
+    }
+
+    /// The element at the parameter t between 0.0 and 1.0 between the two elements
+    fn interpolate_elems(first: &Element, second: &Element, t: f64) -> Element {
+        let mut inputdata = first.inputdata;
+        inputdata.set_pos(first.inputdata.pos().lerp(&second.inputdata.pos(), t));
+        inputdata.set_pressure(
+            first.inputdata.pressure()
+                + (second.inputdata.pressure() - first.inputdata.pressure()) * t,
+        );
+
+        Element {
+            inputdata,
+            timestamp: first.timestamp,
+        }
+    }
+
     fn update_bounds_to_last_elem(&mut self) {
         if let Some(last) = self.elements.last() {
-            let width = match self.style {
+            let width = match &self.style {
                 BrushStrokeStyle::Marker { options } => options.width,
-                BrushStrokeStyle::Solid { options } => options.width,
+                BrushStrokeStyle::Solid { options } => options.width_max_w_tilt(),
                 BrushStrokeStyle::Textured { options } => options.width,
+                BrushStrokeStyle::Spray { options } => options.width,
+                BrushStrokeStyle::Highlighter { options } => options.width,
+                BrushStrokeStyle::Calligraphy { options } => options.width,
+                BrushStrokeStyle::Hatched { options } => options.width,
             };
 
             self.bounds.merge(&AABB::new(
@@ -277,10 +598,14 @@ impl BrushStroke {
     }
 
     fn gen_hitbox_for_elems(&self, first: &Element, second: Option<&Element>) -> AABB {
-        let width = match self.style {
+        let width = match &self.style {
             BrushStrokeStyle::Marker { options } => options.width,
             BrushStrokeStyle::Solid { options } => options.width,
             BrushStrokeStyle::Textured { options } => options.width,
+            BrushStrokeStyle::Spray { options } => options.width,
+            BrushStrokeStyle::Highlighter { options } => options.width,
+            BrushStrokeStyle::Calligraphy { options } => options.width,
+            BrushStrokeStyle::Hatched { options } => options.width,
         };
 
         let first = first.inputdata.pos();
@@ -317,8 +642,9 @@ impl BrushStroke {
         offset: na::Vector2<f64>,
         svg_root: bool,
     ) -> Result<Option<render::Svg>, anyhow::Error> {
-        match self.style {
-            BrushStrokeStyle::Marker { mut options } => {
+        match &self.style {
+            BrushStrokeStyle::Marker { options } => {
+                let mut options = *options;
                 let mut seed = options.seed;
                 // Advance the seed (skip first three elements) so that stroke keeps generating the same patterns
                 for _ in 3..self.elements.len() {
@@ -326,11 +652,19 @@ impl BrushStroke {
                 }
                 options.seed = seed;
 
+                // The elements are the last segment of the stroke
+                let dash_offset = self.marker_dash_offset(self.elements.len().saturating_sub(4));
+
                 Ok(Self::gen_svg_elem_marker(
-                    &options, elements, offset, svg_root,
+                    &options,
+                    elements,
+                    offset,
+                    dash_offset,
+                    svg_root,
                 ))
             }
-            BrushStrokeStyle::Solid { mut options } => {
+            BrushStrokeStyle::Solid { options } => {
+                let mut options = *options;
                 let mut seed = options.seed;
                 // Advance the seed (skip first three elements) so that stroke keeps generating the same patterns
                 for _ in 3..self.elements.len() {
@@ -342,25 +676,77 @@ impl BrushStroke {
                     &options, elements, offset, svg_root,
                 ))
             }
-            BrushStrokeStyle::Textured { mut options } => {
-                let mut seed = options.seed;
-                // Advance the seed (skip first three elements) so that stroke keeps generating the same patterns
-                for _ in 3..self.elements.len() {
-                    seed = seed.map(|seed| utils::seed_advance(seed));
-                }
-                options.seed = seed;
+            BrushStrokeStyle::Textured { options } => {
+                let mut options = options.clone();
+                // The elements are the last segment of the stroke
+                options.seed = options.segment_seed(self.elements.len().saturating_sub(4));
 
                 Ok(Self::gen_svg_elem_textured(
                     &options, elements, offset, svg_root,
                 ))
             }
+            BrushStrokeStyle::Spray { options } => {
+                let mut options = *options;
+                // The elements are the last segment of the stroke
+                let segment_index = self.elements.len().saturating_sub(4);
+                options.seed = options
+                    .seed
+                    .map(|seed| utils::seed_for_segment(seed, segment_index));
+
+                Ok(Self::gen_svg_elem_spray(
+                    &options, elements, offset, svg_root,
+                ))
+            }
+            BrushStrokeStyle::Highlighter { options } => Ok(Self::gen_svg_elem_highlighter(
+                options, elements, offset, svg_root,
+            )),
+            BrushStrokeStyle::Calligraphy { options } => Ok(Self::gen_svg_elem_calligraphy(
+                options, elements, offset, svg_root,
+            )),
+            BrushStrokeStyle::Hatched { options } => {
+                let mut options = *options;
+                // The elements are the last segment of the stroke
+                let segment_index = self.elements.len().saturating_sub(4);
+                options.seed = options
+                    .seed
+                    .map(|seed| utils::seed_for_segment(seed, segment_index));
+
+                Ok(Self::gen_svg_elem_hatched(
+                    &options, elements, offset, svg_root,
+                ))
+            }
+        }
+    }
+
+    /// The blend mode this stroke should be drawn with onto the strokes below it, if it needs one
+    #[cfg(feature = "ui")]
+    pub fn blend_mode(&self) -> Option<gsk::BlendMode> {
+        match &self.style {
+            BrushStrokeStyle::Highlighter { options } => match options.blend_mode {
+                HighlighterBlendMode::Normal => None,
+                blend_mode => Some(blend_mode.to_gsk()),
+            },
+            _ => None,
         }
     }
 
+    /// The offset of the dash pattern for the marker segment with the given index, so that the pattern continues across the segments.
+    /// It is the length of the stroke before the segment, approximated by the distances between the elements
+    fn marker_dash_offset(&self, segment_index: usize) -> f64 {
+        self.elements
+            .iter()
+            .skip(1)
+            .zip(self.elements.iter().skip(2))
+            .take(segment_index)
+            .map(|(first, second)| (second.inputdata.pos() - first.inputdata.pos()).magnitude())
+            .sum()
+    }
+
     pub fn gen_svg_elem_marker(
         options: &SmoothOptions,
         elements: (&Element, &Element, &Element, &Element),
         offset: na::Vector2<f64>,
+        dash_offset: f64,
         svg_root: bool,
     ) -> Option<render::Svg> {
         let mut commands = Vec::new();
@@ -406,7 +792,7 @@ impl BrushStroke {
             .stroke_color
             .map_or(String::from(""), |color| color.to_css_color());
 
-        let path = svg::node::element::Path::new()
+        let mut path = svg::node::element::Path::new()
             .set("stroke", color)
             .set("stroke-width", width)
             .set("stroke-linejoin", "round")
@@ -414,6 +800,12 @@ impl BrushStroke {
             .set("fill", "none")
             .set("d", path::Data::from(commands));
 
+        if let Some(dasharray) = options.stroke_dasharray(true) {
+            path = path
+                .set("stroke-dasharray", dasharray)
+                .set("stroke-dashoffset", dash_offset);
+        }
+
         let mut svg_data = compose::svg_node_to_string(&path)
             .map_err(|e| {
                 anyhow::anyhow!(
@@ -441,14 +833,38 @@ impl BrushStroke {
             .zip(self.elements.iter().skip(1))
             .zip(self.elements.iter().skip(2))
             .zip(self.elements.iter().skip(3))
-            .filter_map(|(((first, second), third), forth)| {
-                Self::gen_svg_elem_marker(&options, (first, second, third, forth), offset, svg_root)
+            .scan(0.0, |dash_offset, (((first, second), third), forth)| {
+                let segment_dash_offset = *dash_offset;
+                *dash_offset += (third.inputdata.pos() - second.inputdata.pos()).magnitude();
+
+                Some(Self::gen_svg_elem_marker(
+                    &options,
+                    (first, second, third, forth),
+                    offset,
+                    segment_dash_offset,
+                    svg_root,
+                ))
             })
+            .flatten()
             .collect();
 
         Ok(svgs)
     }
 
+    /// The speed in units per second from the first to the second element. None if the elements have no timestamps, or they are (nearly) simultaneous
+    fn speed_between_elems(first: &Element, second: &Element) -> Option<f64> {
+        let duration = (second.timestamp? - first.timestamp?)
+            .to_std()
+            .ok()?
+            .as_secs_f64();
+
+        if duration <= f64::EPSILON {
+            return None;
+        }
+
+        Some((second.inputdata.pos() - first.inputdata.pos()).magnitude() / duration)
+    }
+
     pub fn gen_svg_elem_solid(
         options: &SmoothOptions,
         elements: (&Element, &Element, &Element, &Element),
@@ -457,9 +873,17 @@ impl BrushStroke {
     ) -> Option<render::Svg> {
         let mut commands = Vec::new();
 
-        let width = options.width;
-        let start_width = elements.1.inputdata.pressure() * options.width;
-        let end_width = elements.2.inputdata.pressure() * options.width;
+        let width = options.width_max_w_tilt();
+        let start_width = options.width_source_factor(
+            elements.1.inputdata.pressure(),
+            Self::speed_between_elems(elements.0, elements.1),
+        ) * options.width
+            * options.tilt_factor(elements.1.inputdata.tilt());
+        let end_width = options.width_source_factor(
+            elements.2.inputdata.pressure(),
+            Self::speed_between_elems(elements.1, elements.2),
+        ) * options.width
+            * options.tilt_factor(elements.2.inputdata.tilt());
 
         let mut bounds = AABB::new_invalid();
 
@@ -579,11 +1003,34 @@ impl BrushStroke {
         let start_width = elements.1.inputdata.pressure() * options.width;
         let end_width = elements.2.inputdata.pressure() * options.width;
         let mid_width = (start_width + end_width) * 0.5;
+        let pressures = (
+            elements.1.inputdata.pressure(),
+            elements.2.inputdata.pressure(),
+        );
+        let tilt = (elements.1.inputdata.tilt() + elements.2.inputdata.tilt()) * 0.5;
 
         let mut bounds = AABB::new_invalid();
 
         // Configure the textured Configuration
-        let element = if let Some(mut line) =
+        let element = if let Some(mut cubbez) = curves::gen_cubbez_w_catmull_rom(
+            elements.0.inputdata.pos(),
+            elements.1.inputdata.pos(),
+            elements.2.inputdata.pos(),
+            elements.3.inputdata.pos(),
+        ) {
+            cubbez.start += offset;
+            cubbez.cp1 += offset;
+            cubbez.cp2 += offset;
+            cubbez.end += offset;
+
+            // Bounds are definitely inside the polygon of the control points
+            bounds.take_point(na::Point2::from(cubbez.start));
+            bounds.take_point(na::Point2::from(cubbez.cp1));
+            bounds.take_point(na::Point2::from(cubbez.cp2));
+            bounds.take_point(na::Point2::from(cubbez.end));
+
+            textured::compose_cubbez(cubbez, mid_width, pressures, tilt, &options)
+        } else if let Some(mut line) =
             curves::gen_line(elements.1.inputdata.pos(), elements.2.inputdata.pos())
         {
             line.start += offset;
@@ -592,7 +1039,7 @@ impl BrushStroke {
             bounds.take_point(na::Point2::from(line.start));
             bounds.take_point(na::Point2::from(line.end));
 
-            textured::compose_line(line, mid_width, &options)
+            textured::compose_line(line, mid_width, pressures, tilt, &options)
         } else {
             return None;
         };
@@ -621,7 +1068,8 @@ impl BrushStroke {
         offset: na::Vector2<f64>,
         svg_root: bool,
     ) -> Result<Vec<render::Svg>, anyhow::Error> {
-        let mut seed = options.seed;
+        let seed = options.seed;
+        let mut legacy_seed = seed;
 
         let svgs: Vec<render::Svg> = self
             .elements
@@ -629,9 +1077,15 @@ impl BrushStroke {
             .zip(self.elements.iter().skip(1))
             .zip(self.elements.iter().skip(2))
             .zip(self.elements.iter().skip(3))
-            .filter_map(|(((first, second), third), forth)| {
-                seed = seed.map(|seed| utils::seed_advance(seed));
-                options.seed = seed;
+            .enumerate()
+            .filter_map(|(segment_index, (((first, second), third), forth))| {
+                // The same seeds as `TexturedOptions::segment_seed()`, without advancing from the first segment every time
+                options.seed = if options.legacy_seeding {
+                    legacy_seed = legacy_seed.map(utils::seed_advance);
+                    legacy_seed
+                } else {
+                    seed.map(|seed| utils::seed_for_segment(seed, segment_index))
+                };
 
                 Self::gen_svg_elem_textured(
                     &options,
@@ -644,4 +1098,583 @@ impl BrushStroke {
 
         Ok(svgs)
     }
+
+    /// A simplified version of the stroke, for rendering it zoomed far out (see `render::DetailLevel`).
+    /// Textured strokes are approximated by a solid stroke, see `TexturedOptions::simplified_smooth_options()`. None if the stroke has no simpler representation
+    pub fn simplified(&self) -> Option<Self> {
+        match &self.style {
+            BrushStrokeStyle::Textured { options } => {
+                let mut simplified = self.clone();
+                simplified.style = BrushStrokeStyle::Solid {
+                    options: options.simplified_smooth_options(),
+                };
+
+                Some(simplified)
+            }
+            _ => None,
+        }
+    }
+
+    /// A provisional stroke with the same style, which continues the last elements through the predicted elements (see `pens::prediction`).
+    /// It also covers the segment to the last element, which is only rendered once the next element is added. None if there is nothing predicted
+    pub fn gen_predicted_continuation(&self, predicted: &[Element]) -> Option<Self> {
+        let last_predicted = *predicted.last()?;
+        let elems_len = self.elements.len();
+
+        let elements = self.elements[elems_len.saturating_sub(3)..]
+            .iter()
+            .chain(predicted.iter())
+            .copied()
+            // The last predicted element is repeated, so the curve reaches it
+            .chain(std::iter::once(last_predicted))
+            .collect::<Vec<Element>>();
+
+        let mut continuation = Self {
+            elements,
+            style: self.style.clone(),
+            bounds: self.bounds,
+            hitboxes: vec![],
+        };
+        // The hitboxes are not needed, as it is only drawn
+        if let Some(bounds) = continuation.gen_bounds() {
+            continuation.set_bounds(bounds);
+        }
+
+        Some(continuation)
+    }
+
+    /// Tessellates the stroke, so that it can be drawn by the GPU. None if its style or options can't be tessellated
+    pub fn gen_tessellation(&self) -> Option<Tessellation> {
+        let mut tessellation = Tessellation::new(self.tessellation_opacity());
+
+        for (segment_index, (((first, second), third), forth)) in self
+            .elements
+            .iter()
+            .zip(self.elements.iter().skip(1))
+            .zip(self.elements.iter().skip(2))
+            .zip(self.elements.iter().skip(3))
+            .enumerate()
+        {
+            tessellation.append(
+                self.gen_tessellation_for_segment(segment_index, (first, second, third, forth))?,
+            );
+        }
+
+        Some(tessellation)
+    }
+
+    /// Tessellates the last segment of the stroke, to append it to the tessellation while the stroke is drawn
+    pub fn gen_tessellation_for_last_elems(&self) -> Option<Tessellation> {
+        let elems_len = self.elements.len();
+        if elems_len < 4 {
+            return Some(Tessellation::new(self.tessellation_opacity()));
+        }
+
+        self.gen_tessellation_for_segment(
+            elems_len - 4,
+            (
+                &self.elements[elems_len - 4],
+                &self.elements[elems_len - 3],
+                &self.elements[elems_len - 2],
+                &self.elements[elems_len - 1],
+            ),
+        )
+    }
+
+    /// Translucent marker and solid strokes are tessellated into opaque primitives, which are composited with the opacity of the color
+    fn tessellation_opacity(&self) -> f64 {
+        match &self.style {
+            BrushStrokeStyle::Marker { options } | BrushStrokeStyle::Solid { options } => {
+                options.stroke_color.map_or(1.0, |color| color.a)
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// The lines approximating the curve from the second to the third element
+    fn segment_lines(elements: (&Element, &Element, &Element, &Element)) -> Vec<curves::Line> {
+        if let Some(cubbez) = curves::gen_cubbez_w_catmull_rom(
+            elements.0.inputdata.pos(),
+            elements.1.inputdata.pos(),
+            elements.2.inputdata.pos(),
+            elements.3.inputdata.pos(),
+        ) {
+            curves::approx_cubbez_with_lines(cubbez, Self::TESSELLATION_SEGMENT_SPLITS)
+        } else if let Some(line) =
+            curves::gen_line(elements.1.inputdata.pos(), elements.2.inputdata.pos())
+        {
+            vec![line]
+        } else {
+            vec![]
+        }
+    }
+
+    fn gen_tessellation_for_segment(
+        &self,
+        segment_index: usize,
+        elements: (&Element, &Element, &Element, &Element),
+    ) -> Option<Tessellation> {
+        let mut tessellation = Tessellation::new(self.tessellation_opacity());
+
+        match &self.style {
+            BrushStrokeStyle::Marker { options } => {
+                // Dashes are only supported by the svg renderer
+                if options.stroke_dasharray(true).is_some() {
+                    return None;
+                }
+                let color = match options.stroke_color {
+                    Some(color) => Color { a: 1.0, ..color },
+                    None => return Some(tessellation),
+                };
+
+                for line in Self::segment_lines(elements) {
+                    tessellation.push(
+                        Primitive::Capsule {
+                            start: line.start,
+                            end: line.end,
+                            width: options.width,
+                        },
+                        color,
+                    );
+                }
+            }
+            BrushStrokeStyle::Solid { options } => {
+                let color = match options.stroke_color {
+                    Some(color) => Color { a: 1.0, ..color },
+                    None => return Some(tessellation),
+                };
+                let start_width = options.width_source_factor(
+                    elements.1.inputdata.pressure(),
+                    Self::speed_between_elems(elements.0, elements.1),
+                ) * options.width
+                    * options.tilt_factor(elements.1.inputdata.tilt());
+                let end_width = options.width_source_factor(
+                    elements.2.inputdata.pressure(),
+                    Self::speed_between_elems(elements.1, elements.2),
+                ) * options.width
+                    * options.tilt_factor(elements.2.inputdata.tilt());
+
+                let lines = Self::segment_lines(elements);
+                let n_lines = lines.len() as f64;
+
+                // The widths are interpolated along the lines like in gen_svg_elem_solid(). Each line has the mean width of its start and end
+                for (i, line) in lines.into_iter().enumerate() {
+                    let t = (i as f64 + 0.5) / n_lines;
+
+                    tessellation.push(
+                        Primitive::Capsule {
+                            start: line.start,
+                            end: line.end,
+                            width: start_width + (end_width - start_width) * t,
+                        },
+                        color,
+                    );
+                }
+            }
+            BrushStrokeStyle::Textured { options } => {
+                if matches!(
+                    options.dots_shape,
+                    TexturedDotsShape::Triangle | TexturedDotsShape::Custom { .. }
+                ) {
+                    return None;
+                }
+
+                let mut options = options.clone();
+                options.seed = options.segment_seed(segment_index);
+
+                // The same dots as in gen_svg_elem_textured()
+                let start_width = elements.1.inputdata.pressure() * options.width;
+                let end_width = elements.2.inputdata.pressure() * options.width;
+                let mid_width = (start_width + end_width) * 0.5;
+                let pressures = (
+                    elements.1.inputdata.pressure(),
+                    elements.2.inputdata.pressure(),
+                );
+                let tilt = (elements.1.inputdata.tilt() + elements.2.inputdata.tilt()) * 0.5;
+
+                let dots = if let Some(cubbez) = curves::gen_cubbez_w_catmull_rom(
+                    elements.0.inputdata.pos(),
+                    elements.1.inputdata.pos(),
+                    elements.2.inputdata.pos(),
+                    elements.3.inputdata.pos(),
+                ) {
+                    textured::gen_dots_cubbez(cubbez, mid_width, pressures, tilt, &options)
+                } else if let Some(line) =
+                    curves::gen_line(elements.1.inputdata.pos(), elements.2.inputdata.pos())
+                {
+                    textured::gen_dots_line(line, mid_width, pressures, tilt, &options)
+                } else {
+                    vec![]
+                };
+
+                for dot in dots {
+                    let color = match dot.color {
+                        Some(color) => color,
+                        None => continue,
+                    };
+                    let primitive = match options.dots_shape {
+                        TexturedDotsShape::Ellipse => Primitive::Ellipse {
+                            center: dot.pos,
+                            radii: dot.radii,
+                            rotation_angle: dot.rotation_angle,
+                        },
+                        TexturedDotsShape::Circle => Primitive::Ellipse {
+                            center: dot.pos,
+                            radii: na::Vector2::from_element(dot.radii[0]),
+                            rotation_angle: 0.0,
+                        },
+                        TexturedDotsShape::Square => Primitive::Rectangle {
+                            center: dot.pos,
+                            half_extents: na::Vector2::from_element(dot.radii[0]),
+                            rotation_angle: dot.rotation_angle,
+                        },
+                        TexturedDotsShape::Dash => {
+                            let direction =
+                                na::vector![dot.rotation_angle.cos(), dot.rotation_angle.sin()];
+
+                            Primitive::Capsule {
+                                start: dot.pos - direction * dot.radii[0],
+                                end: dot.pos + direction * dot.radii[0],
+                                width: 2.0 * dot.radii[1],
+                            }
+                        }
+                        TexturedDotsShape::Triangle | TexturedDotsShape::Custom { .. } => {
+                            return None
+                        }
+                    };
+
+                    tessellation.push(primitive, color);
+                }
+            }
+            BrushStrokeStyle::Spray { .. }
+            | BrushStrokeStyle::Highlighter { .. }
+            | BrushStrokeStyle::Calligraphy { .. }
+            | BrushStrokeStyle::Hatched { .. } => return None,
+        }
+
+        Some(tessellation)
+    }
+
+    pub fn gen_svg_elem_spray(
+        options: &SprayOptions,
+        elements: (&Element, &Element, &Element, &Element),
+        offset: na::Vector2<f64>,
+        svg_root: bool,
+    ) -> Option<render::Svg> {
+        // The spray is applied at the third element, for the time since the second element
+        let duration = match (elements.1.timestamp, elements.2.timestamp) {
+            (Some(start), Some(end)) => (end - start)
+                .to_std()
+                .map(|duration| duration.as_secs_f64())
+                .unwrap_or(0.0),
+            _ => Self::SPRAY_ELEM_DURATION_FALLBACK,
+        };
+        let center = elements.2.inputdata.pos() + offset;
+
+        let mut bounds = AABB::new_positive(na::Point2::from(center), na::Point2::from(center));
+        bounds.loosen(options.width * 0.5 + options.dots_radius * 1.25 + 1.0);
+
+        let element = spray::compose_burst(center, duration, options);
+
+        let mut svg_data = compose::svg_node_to_string(&element)
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "node_to_string() failed in gen_svg_elem_spray() of brushstroke with Err `{}`",
+                    e
+                )
+            })
+            .ok()?;
+
+        if svg_root {
+            svg_data = compose::wrap_svg_root(&svg_data, Some(bounds), Some(bounds), true);
+        }
+
+        Some(render::Svg { svg_data, bounds })
+    }
+
+    pub fn gen_svgs_spray(
+        &self,
+        mut options: SprayOptions,
+        offset: na::Vector2<f64>,
+        svg_root: bool,
+    ) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let seed = options.seed;
+
+        let svgs: Vec<render::Svg> = self
+            .elements
+            .iter()
+            .zip(self.elements.iter().skip(1))
+            .zip(self.elements.iter().skip(2))
+            .zip(self.elements.iter().skip(3))
+            .enumerate()
+            .filter_map(|(segment_index, (((first, second), third), forth))| {
+                options.seed = seed.map(|seed| utils::seed_for_segment(seed, segment_index));
+
+                Self::gen_svg_elem_spray(&options, (first, second, third, forth), offset, svg_root)
+            })
+            .collect();
+
+        Ok(svgs)
+    }
+
+    pub fn gen_svg_elem_highlighter(
+        options: &HighlighterOptions,
+        elements: (&Element, &Element, &Element, &Element),
+        offset: na::Vector2<f64>,
+        svg_root: bool,
+    ) -> Option<render::Svg> {
+        let width = options.width;
+
+        let mut bounds = AABB::new_invalid();
+
+        let element = if let Some(mut cubbez) = curves::gen_cubbez_w_catmull_rom(
+            elements.0.inputdata.pos(),
+            elements.1.inputdata.pos(),
+            elements.2.inputdata.pos(),
+            elements.3.inputdata.pos(),
+        ) {
+            cubbez.start += offset;
+            cubbez.cp1 += offset;
+            cubbez.cp2 += offset;
+            cubbez.end += offset;
+
+            // Bounds are definitely inside the polygon of the control points. (Could be improved with the second derivative of the bezier curve)
+            bounds.take_point(na::Point2::from(cubbez.start));
+            bounds.take_point(na::Point2::from(cubbez.cp1));
+            bounds.take_point(na::Point2::from(cubbez.cp2));
+            bounds.take_point(na::Point2::from(cubbez.end));
+
+            highlighter::compose_cubbez(cubbez, options)
+        } else if let Some(mut line) =
+            curves::gen_line(elements.1.inputdata.pos(), elements.2.inputdata.pos())
+        {
+            line.start += offset;
+            line.end += offset;
+
+            bounds.take_point(na::Point2::from(line.start));
+            bounds.take_point(na::Point2::from(line.end));
+
+            highlighter::compose_line(line, options)
+        } else {
+            return None;
+        };
+
+        bounds.loosen(width + 1.0);
+
+        let mut svg_data = compose::svg_node_to_string(&element)
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "node_to_string() failed in gen_svg_elem_highlighter() of brushstroke with Err `{}`",
+                    e
+                )
+            })
+            .ok()?;
+
+        if svg_root {
+            svg_data = compose::wrap_svg_root(&svg_data, Some(bounds), Some(bounds), true);
+        }
+
+        Some(render::Svg { svg_data, bounds })
+    }
+
+    pub fn gen_svgs_highlighter(
+        &self,
+        options: HighlighterOptions,
+        offset: na::Vector2<f64>,
+        svg_root: bool,
+    ) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let svgs: Vec<render::Svg> = self
+            .elements
+            .iter()
+            .zip(self.elements.iter().skip(1))
+            .zip(self.elements.iter().skip(2))
+            .zip(self.elements.iter().skip(3))
+            .filter_map(|(((first, second), third), forth)| {
+                Self::gen_svg_elem_highlighter(
+                    &options,
+                    (first, second, third, forth),
+                    offset,
+                    svg_root,
+                )
+            })
+            .collect();
+
+        Ok(svgs)
+    }
+
+    pub fn gen_svg_elem_calligraphy(
+        options: &CalligraphyOptions,
+        elements: (&Element, &Element, &Element, &Element),
+        offset: na::Vector2<f64>,
+        svg_root: bool,
+    ) -> Option<render::Svg> {
+        let width = options.width;
+
+        let mut bounds = AABB::new_invalid();
+
+        let element = if let Some(mut cubbez) = curves::gen_cubbez_w_catmull_rom(
+            elements.0.inputdata.pos(),
+            elements.1.inputdata.pos(),
+            elements.2.inputdata.pos(),
+            elements.3.inputdata.pos(),
+        ) {
+            cubbez.start += offset;
+            cubbez.cp1 += offset;
+            cubbez.cp2 += offset;
+            cubbez.end += offset;
+
+            // Bounds are definitely inside the polygon of the control points. (Could be improved with the second derivative of the bezier curve)
+            bounds.take_point(na::Point2::from(cubbez.start));
+            bounds.take_point(na::Point2::from(cubbez.cp1));
+            bounds.take_point(na::Point2::from(cubbez.cp2));
+            bounds.take_point(na::Point2::from(cubbez.end));
+
+            calligraphy::compose_cubbez(cubbez, options)
+        } else if let Some(mut line) =
+            curves::gen_line(elements.1.inputdata.pos(), elements.2.inputdata.pos())
+        {
+            line.start += offset;
+            line.end += offset;
+
+            bounds.take_point(na::Point2::from(line.start));
+            bounds.take_point(na::Point2::from(line.end));
+
+            calligraphy::compose_line(line, options)
+        } else {
+            return None;
+        };
+
+        bounds.loosen(width + 1.0);
+
+        let mut svg_data = compose::svg_node_to_string(&element)
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "node_to_string() failed in gen_svg_elem_calligraphy() of brushstroke with Err `{}`",
+                    e
+                )
+            })
+            .ok()?;
+
+        if svg_root {
+            svg_data = compose::wrap_svg_root(&svg_data, Some(bounds), Some(bounds), true);
+        }
+
+        Some(render::Svg { svg_data, bounds })
+    }
+
+    pub fn gen_svgs_calligraphy(
+        &self,
+        options: CalligraphyOptions,
+        offset: na::Vector2<f64>,
+        svg_root: bool,
+    ) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let svgs: Vec<render::Svg> = self
+            .elements
+            .iter()
+            .zip(self.elements.iter().skip(1))
+            .zip(self.elements.iter().skip(2))
+            .zip(self.elements.iter().skip(3))
+            .filter_map(|(((first, second), third), forth)| {
+                Self::gen_svg_elem_calligraphy(
+                    &options,
+                    (first, second, third, forth),
+                    offset,
+                    svg_root,
+                )
+            })
+            .collect();
+
+        Ok(svgs)
+    }
+
+    pub fn gen_svg_elem_hatched(
+        options: &HatchedOptions,
+        elements: (&Element, &Element, &Element, &Element),
+        offset: na::Vector2<f64>,
+        svg_root: bool,
+    ) -> Option<render::Svg> {
+        let width = options.width;
+
+        let mut bounds = AABB::new_invalid();
+
+        let element = if let Some(mut cubbez) = curves::gen_cubbez_w_catmull_rom(
+            elements.0.inputdata.pos(),
+            elements.1.inputdata.pos(),
+            elements.2.inputdata.pos(),
+            elements.3.inputdata.pos(),
+        ) {
+            cubbez.start += offset;
+            cubbez.cp1 += offset;
+            cubbez.cp2 += offset;
+            cubbez.end += offset;
+
+            // Bounds are definitely inside the polygon of the control points. (Could be improved with the second derivative of the bezier curve)
+            bounds.take_point(na::Point2::from(cubbez.start));
+            bounds.take_point(na::Point2::from(cubbez.cp1));
+            bounds.take_point(na::Point2::from(cubbez.cp2));
+            bounds.take_point(na::Point2::from(cubbez.end));
+
+            hatched::compose_cubbez(cubbez, width, options)
+        } else if let Some(mut line) =
+            curves::gen_line(elements.1.inputdata.pos(), elements.2.inputdata.pos())
+        {
+            line.start += offset;
+            line.end += offset;
+
+            bounds.take_point(na::Point2::from(line.start));
+            bounds.take_point(na::Point2::from(line.end));
+
+            hatched::compose_line(line, width, options)
+        } else {
+            return None;
+        };
+
+        // The hatch lines are up to 1.25 times the width long
+        bounds.loosen(width + options.line_width + 1.0);
+
+        let mut svg_data = compose::svg_node_to_string(&element)
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "node_to_string() failed in gen_svg_elem_hatched() of brushstroke with Err `{}`",
+                    e
+                )
+            })
+            .ok()?;
+
+        if svg_root {
+            svg_data = compose::wrap_svg_root(&svg_data, Some(bounds), Some(bounds), true);
+        }
+
+        Some(render::Svg { svg_data, bounds })
+    }
+
+    pub fn gen_svgs_hatched(
+        &self,
+        mut options: HatchedOptions,
+        offset: na::Vector2<f64>,
+        svg_root: bool,
+    ) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let seed = options.seed;
+
+        let svgs: Vec<render::Svg> = self
+            .elements
+            .iter()
+            .zip(self.elements.iter().skip(1))
+            .zip(self.elements.iter().skip(2))
+            .zip(self.elements.iter().skip(3))
+            .enumerate()
+            .filter_map(|(segment_index, (((first, second), third), forth))| {
+                options.seed = seed.map(|seed| utils::seed_for_segment(seed, segment_index));
+
+                Self::gen_svg_elem_hatched(
+                    &options,
+                    (first, second, third, forth),
+                    offset,
+                    svg_root,
+                )
+            })
+            .collect();
+
+        Ok(svgs)
+    }
 }
@@ -0,0 +1,407 @@
+use crate::compose;
+use crate::compose::color::Color;
+use crate::compose::geometry::AABBHelpers;
+use crate::compose::transformable::{Transform, Transformable};
+use crate::drawbehaviour::DrawBehaviour;
+use crate::render;
+
+#[cfg(feature = "cairo")]
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "cairo")]
+use anyhow::Context;
+use p2d::bounding_volume::AABB;
+#[cfg(feature = "cairo")]
+use pango::prelude::*;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "cairo")]
+use svg::node;
+use svg::node::element;
+
+#[cfg(not(feature = "cairo"))]
+use crate::compose::svgtext;
+
+/// A checkbox with a label, e.g. for todos. It is checked and unchecked by tapping it in the view mode, see `StrokesState::toggle_checkbox()`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "checkboxstroke")]
+pub struct CheckboxStroke {
+    /// The label next to the box
+    #[serde(rename = "text")]
+    pub text: String,
+    #[serde(rename = "checked")]
+    pub checked: bool,
+    /// The font size of the label, in the coordinate space of the checkbox
+    #[serde(rename = "font_size")]
+    pub font_size: f64,
+    #[serde(rename = "color")]
+    pub color: Color,
+    /// The transform of the checkbox. Its origin is the upper left corner of the box
+    #[serde(rename = "transform")]
+    pub transform: Transform,
+    #[serde(rename = "bounds")]
+    pub bounds: AABB,
+}
+
+impl Default for CheckboxStroke {
+    fn default() -> Self {
+        Self {
+            text: String::default(),
+            checked: false,
+            font_size: Self::FONT_SIZE_DEFAULT,
+            color: Color::BLACK,
+            transform: Transform::default(),
+            bounds: AABB::new_zero(),
+        }
+    }
+}
+
+impl DrawBehaviour for CheckboxStroke {
+    fn bounds(&self) -> AABB {
+        self.bounds
+    }
+
+    fn set_bounds(&mut self, bounds: AABB) {
+        self.bounds = bounds;
+    }
+
+    fn gen_bounds(&self) -> Option<AABB> {
+        let size = self.local_size().ok()?;
+
+        let corners = [
+            na::point![0.0, 0.0],
+            na::point![size[0], 0.0],
+            na::point![0.0, size[1]],
+            na::point![size[0], size[1]],
+        ]
+        .map(|corner| self.transform.transform_point(corner));
+
+        let mut bounds = AABB::new(corners[0], corners[0]);
+        corners.iter().skip(1).for_each(|&corner| {
+            bounds.take_point(corner);
+        });
+
+        Some(bounds)
+    }
+
+    #[cfg(feature = "cairo")]
+    fn gen_svgs(&self, offset: na::Vector2<f64>) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let size = self.local_size()?;
+        let mut transform = self.transform.clone();
+        transform.append_translation_mut(offset);
+
+        let svg_data = render::cairo_drawing_to_svg_data(size, |cx| self.draw_to_cairo(cx))
+            .context("cairo_drawing_to_svg_data() failed in checkboxstroke gen_svgs()")?;
+
+        let svg_root = element::SVG::new()
+            .set("x", 0.0)
+            .set("y", 0.0)
+            .set("width", size[0])
+            .set("height", size[1])
+            .set(
+                "viewBox",
+                format!("{:.3} {:.3} {:.3} {:.3}", 0.0, 0.0, size[0], size[1]),
+            )
+            .set("preserveAspectRatio", "none")
+            .add(node::Text::new(svg_data));
+
+        let group = element::Group::new()
+            .set("transform", transform.to_svg_transform_attr_str())
+            .add(svg_root);
+
+        Ok(vec![render::Svg {
+            svg_data: compose::svg_node_to_string(&group)?,
+            bounds: self.bounds.translate(offset),
+        }])
+    }
+
+    /// Without the `cairo` feature the label lines are svg text elements, which are drawn with the fonts of the svg renderer
+    #[cfg(not(feature = "cairo"))]
+    fn gen_svgs(&self, offset: na::Vector2<f64>) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let mut transform = self.transform.clone();
+        transform.append_translation_mut(offset);
+        let box_size = self.box_size();
+        let line_width = Self::LINE_WIDTH * self.font_size;
+        let color = self.color.to_css_color();
+
+        // Inset by half the line width, so the outline stays inside the bounds
+        let inset = line_width * 0.5;
+        let mut group = element::Group::new()
+            .set("transform", transform.to_svg_transform_attr_str())
+            .add(
+                element::Rectangle::new()
+                    .set("x", inset)
+                    .set("y", inset)
+                    .set("width", box_size - line_width)
+                    .set("height", box_size - line_width)
+                    .set("fill", "none")
+                    .set("stroke", color.clone())
+                    .set("stroke-width", line_width),
+            );
+
+        if self.checked {
+            group = group.add(
+                element::Polyline::new()
+                    .set(
+                        "points",
+                        format!(
+                            "{:.3},{:.3} {:.3},{:.3} {:.3},{:.3}",
+                            box_size * 0.22,
+                            box_size * 0.52,
+                            box_size * 0.42,
+                            box_size * 0.74,
+                            box_size * 0.8,
+                            box_size * 0.28
+                        ),
+                    )
+                    .set("fill", "none")
+                    .set("stroke", color)
+                    .set("stroke-width", line_width)
+                    .set("stroke-linecap", "round")
+                    .set("stroke-linejoin", "round"),
+            );
+        }
+
+        let font = svgtext::TextFont {
+            family: String::from(Self::FONT_FAMILY),
+            size: self.font_size,
+            strikethrough: self.checked,
+            ..svgtext::TextFont::default()
+        };
+        let label_x = box_size + Self::LABEL_SPACING * self.font_size;
+        for (line_index, line) in self.text.split('\n').enumerate() {
+            group = group.add(svgtext::compose_line(
+                line,
+                na::vector![
+                    label_x,
+                    (line_index as f64 * svgtext::LINE_HEIGHT + svgtext::BASELINE) * self.font_size
+                ],
+                &font,
+                self.color,
+            ));
+        }
+
+        Ok(vec![render::Svg {
+            svg_data: compose::svg_node_to_string(&group)?,
+            bounds: self.bounds.translate(offset),
+        }])
+    }
+
+    /// The checkbox is rendered directly with cairo and pango, without the svg roundtrip
+    #[cfg(feature = "cairo")]
+    fn gen_images(
+        &self,
+        zoom: f64,
+        _renderer: Arc<RwLock<render::Renderer>>,
+    ) -> Result<Vec<render::Image>, anyhow::Error> {
+        let image = render::cairo_drawing_to_image(self.bounds, zoom, |cx| {
+            cx.transform(self.transform.to_cairo_matrix());
+            self.draw_to_cairo(cx)
+        })
+        .context("cairo_drawing_to_image() failed in checkboxstroke gen_images()")?;
+
+        Ok(vec![image])
+    }
+
+    /// The label is drawn with pango, so it stays text on pdf surfaces
+    #[cfg(feature = "cairo")]
+    fn draw_to_cairo_vector(&self, cx: &cairo::Context) -> Result<(), anyhow::Error> {
+        cx.save()?;
+        cx.transform(self.transform.to_cairo_matrix());
+        self.draw_to_cairo(cx)?;
+        cx.restore()?;
+
+        Ok(())
+    }
+}
+
+impl Transformable for CheckboxStroke {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.transform.append_translation_mut(offset);
+        self.update_geometry();
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        self.transform.append_rotation_wrt_point_mut(angle, center);
+        self.update_geometry();
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.transform.append_scale_mut(scale);
+        self.update_geometry();
+    }
+}
+
+impl CheckboxStroke {
+    /// The font family of the label
+    pub const FONT_FAMILY: &'static str = "Sans";
+    /// The default font size
+    pub const FONT_SIZE_DEFAULT: f64 = 24.0;
+    /// The size of the side of the box, in em. About the height of a line of the label
+    pub const BOX_SIZE: f64 = 1.2;
+    /// The space between the box and the label, in em
+    pub const LABEL_SPACING: f64 = 0.4;
+    /// The width of the box outline and the check mark, in em
+    pub const LINE_WIDTH: f64 = 0.08;
+
+    /// A new unchecked checkbox with the upper left corner of its box at the position
+    pub fn new(text: String, pos: na::Vector2<f64>, font_size: f64, color: Color) -> Self {
+        let mut checkboxstroke = Self {
+            text,
+            checked: false,
+            font_size,
+            color,
+            transform: Transform::new_w_isometry(na::Isometry2::new(pos, 0.0)),
+            bounds: AABB::new_zero(),
+        };
+        checkboxstroke.update_geometry();
+
+        checkboxstroke
+    }
+
+    pub fn update_geometry(&mut self) {
+        if let Some(new_bounds) = self.gen_bounds() {
+            self.set_bounds(new_bounds);
+        }
+    }
+
+    /// Sets a new label and updates the geometry
+    pub fn set_text(&mut self, text: String) {
+        self.text = text;
+        self.update_geometry();
+    }
+
+    /// Checks the checkbox when it is unchecked and unchecks it otherwise. Returns whether it is checked now
+    pub fn toggle(&mut self) -> bool {
+        self.checked = !self.checked;
+        self.checked
+    }
+
+    /// The first line of the label, e.g. to list the todos
+    pub fn title(&self) -> &str {
+        self.text
+            .lines()
+            .map(|line| line.trim())
+            .find(|line| !line.is_empty())
+            .unwrap_or_default()
+    }
+
+    /// The position of the upper left corner of the box, in the coordinate space of the sheet
+    pub fn pos(&self) -> na::Vector2<f64> {
+        self.transform.transform_point(na::Point2::origin()).coords
+    }
+
+    /// Sets up the layout with the label. Checked labels are struck through
+    #[cfg(feature = "cairo")]
+    fn setup_layout(&self, layout: &pango::Layout) {
+        let mut font_description = pango::FontDescription::new();
+        font_description.set_family(Self::FONT_FAMILY);
+        font_description.set_absolute_size(self.font_size * f64::from(pango::SCALE));
+        layout.set_font_description(Some(&font_description));
+
+        let attributes = pango::AttrList::new();
+        if self.checked {
+            attributes.insert(pango::AttrInt::new_strikethrough(true));
+        }
+        layout.set_attributes(Some(&attributes));
+
+        layout.set_text(&self.text);
+    }
+
+    #[cfg(feature = "cairo")]
+    fn gen_layout(&self) -> Result<pango::Layout, anyhow::Error> {
+        let fontmap = pangocairo::FontMap::default()
+            .ok_or_else(|| anyhow::anyhow!("getting the default pangocairo::FontMap failed"))?;
+        let context = fontmap
+            .create_context()
+            .ok_or_else(|| anyhow::anyhow!("create_context() for the font map failed"))?;
+
+        let layout = pango::Layout::new(&context);
+        self.setup_layout(&layout);
+
+        Ok(layout)
+    }
+
+    fn box_size(&self) -> f64 {
+        Self::BOX_SIZE * self.font_size
+    }
+
+    /// The size of the box and the label, in the coordinate space of the checkbox
+    #[cfg(feature = "cairo")]
+    fn local_size(&self) -> Result<na::Vector2<f64>, anyhow::Error> {
+        let (_ink_rect, logical_rect) = self.gen_layout()?.extents();
+        let label_size = na::vector![
+            f64::from(logical_rect.width()) / f64::from(pango::SCALE),
+            f64::from(logical_rect.height()) / f64::from(pango::SCALE)
+        ];
+        let box_size = self.box_size();
+
+        Ok(na::vector![
+            box_size + Self::LABEL_SPACING * self.font_size + label_size[0],
+            box_size.max(label_size[1])
+        ])
+    }
+
+    /// The size of the box and the label, in the coordinate space of the checkbox
+    #[cfg(not(feature = "cairo"))]
+    fn local_size(&self) -> Result<na::Vector2<f64>, anyhow::Error> {
+        let lines = self.text.split('\n');
+        let n_lines = lines.clone().count();
+        let label_width = lines
+            .map(|line| svgtext::text_width(line, self.font_size, false))
+            .fold(0.0, f64::max);
+        let box_size = self.box_size();
+
+        Ok(na::vector![
+            box_size + Self::LABEL_SPACING * self.font_size + label_width,
+            box_size.max(n_lines as f64 * svgtext::LINE_HEIGHT * self.font_size)
+        ])
+    }
+
+    /// Draws the box, the check mark when checked and the label onto the cairo context, in the coordinate space of the checkbox
+    #[cfg(feature = "cairo")]
+    fn draw_to_cairo(&self, cx: &cairo::Context) -> Result<(), anyhow::Error> {
+        let box_size = self.box_size();
+        let line_width = Self::LINE_WIDTH * self.font_size;
+        cx.set_source_rgba(self.color.r, self.color.g, self.color.b, self.color.a);
+        cx.set_line_width(line_width);
+        cx.set_line_cap(cairo::LineCap::Round);
+        cx.set_line_join(cairo::LineJoin::Round);
+
+        // Inset by half the line width, so the outline stays inside the bounds
+        let inset = line_width * 0.5;
+        cx.rectangle(inset, inset, box_size - line_width, box_size - line_width);
+        cx.stroke()?;
+
+        if self.checked {
+            cx.move_to(box_size * 0.22, box_size * 0.52);
+            cx.line_to(box_size * 0.42, box_size * 0.74);
+            cx.line_to(box_size * 0.8, box_size * 0.28);
+            cx.stroke()?;
+        }
+
+        let layout = pangocairo::create_layout(cx)
+            .ok_or_else(|| anyhow::anyhow!("pangocairo::create_layout() failed"))?;
+        self.setup_layout(&layout);
+        cx.move_to(box_size + Self::LABEL_SPACING * self.font_size, 0.0);
+        pangocairo::show_layout(cx, &layout);
+
+        Ok(())
+    }
+
+    /// Whether the position hits the box or the label
+    pub fn hittest(&self, pos: na::Vector2<f64>, tolerance: f64) -> bool {
+        let size = match self.local_size() {
+            Ok(size) => size,
+            Err(_) => return false,
+        };
+        let local_pos = match self.transform.transform.try_inverse() {
+            Some(inverse) => (inverse * na::Point2::from(pos)).coords,
+            None => return false,
+        };
+
+        local_pos[0] >= -tolerance
+            && local_pos[0] <= size[0] + tolerance
+            && local_pos[1] >= -tolerance
+            && local_pos[1] <= size[1] + tolerance
+    }
+}
@@ -0,0 +1,210 @@
+use std::sync::{Arc, RwLock};
+
+use crate::compose::transformable::Transformable;
+use crate::drawbehaviour::DrawBehaviour;
+use crate::plugins::{self, Composer};
+use crate::render::{self, Renderer};
+
+use p2d::bounding_volume::{BoundingVolume, AABB};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A stroke of a composer added by a plugin, see the `plugins` module.
+/// When its composer is not registered, the stroke is kept as it was loaded. It is neither drawn nor transformed then
+#[derive(Debug, Clone)]
+pub struct ExtensionStroke {
+    type_name: String,
+    /// None when the composer of the type name is not registered
+    composer: Option<Box<dyn Composer>>,
+    /// The data as it was loaded, for strokes without registered composer
+    data: serde_json::Value,
+    bounds: AABB,
+}
+
+/// The stroke as it is stored in documents
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "extensionstroke")]
+struct ExtensionStrokeRecord {
+    #[serde(rename = "type_name")]
+    type_name: String,
+    #[serde(rename = "bounds")]
+    bounds: AABB,
+    #[serde(rename = "data")]
+    data: serde_json::Value,
+}
+
+impl Serialize for ExtensionStroke {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let data = match &self.composer {
+            Some(composer) => composer
+                .serialize_data()
+                .map_err(serde::ser::Error::custom)?,
+            None => self.data.clone(),
+        };
+
+        ExtensionStrokeRecord {
+            type_name: self.type_name.clone(),
+            bounds: self.bounds(),
+            data,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtensionStroke {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let record = ExtensionStrokeRecord::deserialize(deserializer)?;
+
+        let composer = match plugins::composer_deserializer(&record.type_name) {
+            Some(composer_deserializer) => match composer_deserializer(record.data.clone()) {
+                Ok(composer) => Some(composer),
+                Err(e) => {
+                    log::error!(
+                        "deserializing the stroke of composer `{}` failed with Err {}, it is kept as it was loaded",
+                        record.type_name,
+                        e
+                    );
+                    None
+                }
+            },
+            None => {
+                log::warn!(
+                    "no composer is registered with type name `{}`, its stroke is kept as it was loaded",
+                    record.type_name
+                );
+                None
+            }
+        };
+
+        Ok(Self {
+            type_name: record.type_name,
+            // The data is only kept when it can't be read by the composer
+            data: if composer.is_some() {
+                serde_json::Value::Null
+            } else {
+                record.data
+            },
+            composer,
+            bounds: record.bounds,
+        })
+    }
+}
+
+impl DrawBehaviour for ExtensionStroke {
+    fn bounds(&self) -> AABB {
+        self.composer
+            .as_ref()
+            .map_or(self.bounds, |composer| composer.bounds())
+    }
+
+    fn set_bounds(&mut self, bounds: AABB) {
+        match &mut self.composer {
+            Some(composer) => composer.set_bounds(bounds),
+            None => self.bounds = bounds,
+        }
+    }
+
+    fn gen_bounds(&self) -> Option<AABB> {
+        match &self.composer {
+            Some(composer) => composer.gen_bounds(),
+            None => Some(self.bounds),
+        }
+    }
+
+    fn gen_svgs(&self, offset: na::Vector2<f64>) -> Result<Vec<render::Svg>, anyhow::Error> {
+        match &self.composer {
+            Some(composer) => composer.gen_svgs(offset),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn gen_images(
+        &self,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<Vec<render::Image>, anyhow::Error> {
+        match &self.composer {
+            Some(composer) => composer.gen_images(zoom, renderer),
+            None => Ok(vec![]),
+        }
+    }
+
+    #[cfg(feature = "cairo")]
+    fn draw_to_cairo_vector(&self, cx: &cairo::Context) -> Result<(), anyhow::Error> {
+        match &self.composer {
+            Some(composer) => composer.draw_to_cairo_vector(cx),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Transformable for ExtensionStroke {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        if let Some(composer) = &mut self.composer {
+            composer.translate(offset);
+        }
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        if let Some(composer) = &mut self.composer {
+            composer.rotate(angle, center);
+        }
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        if let Some(composer) = &mut self.composer {
+            composer.scale(scale);
+        }
+    }
+}
+
+impl ExtensionStroke {
+    pub fn new(composer: Box<dyn Composer>) -> Self {
+        Self {
+            type_name: composer.type_name().to_string(),
+            bounds: composer.bounds(),
+            composer: Some(composer),
+            data: serde_json::Value::Null,
+        }
+    }
+
+    /// The type name of the composer of the stroke
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// The stroke of the composer. None when the composer is not registered
+    pub fn composer(&self) -> Option<&dyn Composer> {
+        self.composer.as_deref()
+    }
+
+    pub fn composer_mut(&mut self) -> Option<&mut Box<dyn Composer>> {
+        self.composer.as_mut()
+    }
+
+    /// The approximate memory in bytes used by the stroke. Without the memory the composer allocates
+    pub fn size(&self) -> usize {
+        match &self.composer {
+            Some(composer) => std::mem::size_of_val(composer.as_ref()),
+            None => self.data.to_string().len(),
+        }
+    }
+
+    /// Whether the position hits the bounds of the stroke within the tolerance
+    pub fn hittest(&self, pos: na::Vector2<f64>, tolerance: f64) -> bool {
+        self.bounds()
+            .loosened(tolerance)
+            .contains_local_point(&na::Point2::from(pos))
+    }
+
+    pub fn update_geometry(&mut self) {
+        if let Some(new_bounds) = self.gen_bounds() {
+            self.set_bounds(new_bounds);
+        }
+    }
+}
@@ -5,6 +5,10 @@ use serde::{Deserialize, Serialize};
 pub struct InputData {
     pos: na::Vector2<f64>,
     pressure: f64,
+    /// The tilt of the stylus in x and y direction, each between -1.0 and 1.0. (0.0, 0.0) is perpendicular to the surface
+    tilt: na::Vector2<f64>,
+    /// The barrel rotation of the stylus in radians, between 0.0 and 2 PI
+    rotation: f64,
 }
 
 impl Default for InputData {
@@ -12,12 +16,16 @@ impl Default for InputData {
         Self {
             pos: na::vector![0.0, 0.0],
             pressure: Self::PRESSURE_DEFAULT,
+            tilt: Self::TILT_DEFAULT,
+            rotation: Self::ROTATION_DEFAULT,
         }
     }
 }
 
 impl InputData {
     pub const PRESSURE_DEFAULT: f64 = 0.5;
+    pub const TILT_DEFAULT: na::Vector2<f64> = na::vector![0.0, 0.0];
+    pub const ROTATION_DEFAULT: f64 = 0.0;
 
     pub fn new(pos: na::Vector2<f64>, pressure: f64) -> Self {
         let mut inputdata = Self::default();
@@ -42,4 +50,52 @@ impl InputData {
     pub fn set_pressure(&mut self, pressure: f64) {
         self.pressure = pressure.clamp(0.0, 1.0);
     }
+
+    pub fn tilt(&self) -> na::Vector2<f64> {
+        self.tilt
+    }
+
+    pub fn set_tilt(&mut self, tilt: na::Vector2<f64>) {
+        self.tilt = na::vector![tilt[0].clamp(-1.0, 1.0), tilt[1].clamp(-1.0, 1.0)];
+    }
+
+    pub fn rotation(&self) -> f64 {
+        self.rotation
+    }
+
+    pub fn set_rotation(&mut self, rotation: f64) {
+        self.rotation = rotation.rem_euclid(std::f64::consts::TAU);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tilt_and_rotation_are_kept_in_range() {
+        let mut inputdata = InputData::new(na::vector![1.0, 2.0], 0.5);
+        assert_eq!(inputdata.tilt(), InputData::TILT_DEFAULT);
+        assert_eq!(inputdata.rotation(), InputData::ROTATION_DEFAULT);
+
+        inputdata.set_tilt(na::vector![1.5, -0.25]);
+        assert_eq!(inputdata.tilt(), na::vector![1.0, -0.25]);
+
+        inputdata.set_rotation(-std::f64::consts::FRAC_PI_2);
+        assert!((inputdata.rotation() - 1.5 * std::f64::consts::PI).abs() < 1e-9);
+        inputdata.set_rotation(std::f64::consts::TAU + 1.0);
+        assert!((inputdata.rotation() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn inputdata_without_tilt_is_upright() {
+        // Saved before the tilt and rotation existed
+        let inputdata =
+            serde_json::from_str::<InputData>(r#"{"pos": [10.0, 20.0], "pressure": 0.8}"#).unwrap();
+
+        assert_eq!(inputdata.pos(), na::vector![10.0, 20.0]);
+        assert_eq!(inputdata.pressure(), 0.8);
+        assert_eq!(inputdata.tilt(), InputData::TILT_DEFAULT);
+        assert_eq!(inputdata.rotation(), InputData::ROTATION_DEFAULT);
+    }
 }
@@ -0,0 +1,273 @@
+use crate::compose;
+use crate::compose::color::Color;
+use crate::compose::geometry::AABBHelpers;
+use crate::compose::math::{self, MathBox, MathLayouter, MathNode};
+use crate::compose::transformable::{Transform, Transformable};
+use crate::drawbehaviour::DrawBehaviour;
+use crate::render;
+
+#[cfg(feature = "cairo")]
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "cairo")]
+use anyhow::Context;
+use p2d::bounding_volume::AABB;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "cairo")]
+use svg::node;
+use svg::node::element;
+
+/// A math expression placed on the sheet, rendered from its LaTeX source. The source stays editable after placement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "mathstroke")]
+pub struct MathStroke {
+    /// The LaTeX source of the expression, without the surrounding `$`
+    #[serde(rename = "source")]
+    pub source: String,
+    /// The font size, in the coordinate space of the expression
+    #[serde(rename = "font_size")]
+    pub font_size: f64,
+    #[serde(rename = "color")]
+    pub color: Color,
+    /// The transform of the expression. Its origin is the upper left corner of the expression
+    #[serde(rename = "transform")]
+    pub transform: Transform,
+    #[serde(rename = "bounds")]
+    pub bounds: AABB,
+}
+
+impl Default for MathStroke {
+    fn default() -> Self {
+        Self {
+            source: String::default(),
+            font_size: Self::FONT_SIZE_DEFAULT,
+            color: Color::BLACK,
+            transform: Transform::default(),
+            bounds: AABB::new_zero(),
+        }
+    }
+}
+
+impl DrawBehaviour for MathStroke {
+    fn bounds(&self) -> AABB {
+        self.bounds
+    }
+
+    fn set_bounds(&mut self, bounds: AABB) {
+        self.bounds = bounds;
+    }
+
+    fn gen_bounds(&self) -> Option<AABB> {
+        let size = self.local_size(&self.gen_mathbox().ok()?);
+
+        let corners = [
+            na::point![0.0, 0.0],
+            na::point![size[0], 0.0],
+            na::point![0.0, size[1]],
+            na::point![size[0], size[1]],
+        ]
+        .map(|corner| self.transform.transform_point(corner));
+
+        let mut bounds = AABB::new(corners[0], corners[0]);
+        corners.iter().skip(1).for_each(|&corner| {
+            bounds.take_point(corner);
+        });
+
+        Some(bounds)
+    }
+
+    #[cfg(feature = "cairo")]
+    fn gen_svgs(&self, offset: na::Vector2<f64>) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let mathbox = self.gen_mathbox()?;
+        let size = self.local_size(&mathbox);
+        let mut transform = self.transform.clone();
+        transform.append_translation_mut(offset);
+
+        let svg_data =
+            render::cairo_drawing_to_svg_data(size, |cx| self.draw_to_cairo(cx, &mathbox))
+                .context("cairo_drawing_to_svg_data() failed in mathstroke gen_svgs()")?;
+
+        let svg_root = element::SVG::new()
+            .set("x", 0.0)
+            .set("y", 0.0)
+            .set("width", size[0])
+            .set("height", size[1])
+            .set(
+                "viewBox",
+                format!("{:.3} {:.3} {:.3} {:.3}", 0.0, 0.0, size[0], size[1]),
+            )
+            .set("preserveAspectRatio", "none")
+            .add(node::Text::new(svg_data));
+
+        let group = element::Group::new()
+            .set("transform", transform.to_svg_transform_attr_str())
+            .add(svg_root);
+
+        Ok(vec![render::Svg {
+            svg_data: compose::svg_node_to_string(&group)?,
+            bounds: self.bounds.translate(offset),
+        }])
+    }
+
+    /// Without the `cairo` feature the glyphs are svg text elements, which are drawn with the fonts of the svg renderer
+    #[cfg(not(feature = "cairo"))]
+    fn gen_svgs(&self, offset: na::Vector2<f64>) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let mathbox = self.gen_mathbox()?;
+        let padding = Self::PADDING * self.font_size;
+        let mut transform = self.transform.clone();
+        transform.append_translation_mut(offset);
+
+        let group = element::Group::new()
+            .set("transform", transform.to_svg_transform_attr_str())
+            .add(mathbox.compose_svg(na::vector![padding, padding + mathbox.ascent], self.color));
+
+        Ok(vec![render::Svg {
+            svg_data: compose::svg_node_to_string(&group)?,
+            bounds: self.bounds.translate(offset),
+        }])
+    }
+
+    /// The expression is rendered directly with pango, without the svg roundtrip
+    #[cfg(feature = "cairo")]
+    fn gen_images(
+        &self,
+        zoom: f64,
+        _renderer: Arc<RwLock<render::Renderer>>,
+    ) -> Result<Vec<render::Image>, anyhow::Error> {
+        let mathbox = self.gen_mathbox()?;
+
+        let image = render::cairo_drawing_to_image(self.bounds, zoom, |cx| {
+            cx.transform(self.transform.to_cairo_matrix());
+            self.draw_to_cairo(cx, &mathbox)
+        })
+        .context("cairo_drawing_to_image() failed in mathstroke gen_images()")?;
+
+        Ok(vec![image])
+    }
+
+    /// The expression is drawn with pango, so its glyphs stay text on pdf surfaces
+    #[cfg(feature = "cairo")]
+    fn draw_to_cairo_vector(&self, cx: &cairo::Context) -> Result<(), anyhow::Error> {
+        let mathbox = self.gen_mathbox()?;
+
+        cx.save()?;
+        cx.transform(self.transform.to_cairo_matrix());
+        self.draw_to_cairo(cx, &mathbox)?;
+        cx.restore()?;
+
+        Ok(())
+    }
+}
+
+impl Transformable for MathStroke {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.transform.append_translation_mut(offset);
+        self.update_geometry();
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        self.transform.append_rotation_wrt_point_mut(angle, center);
+        self.update_geometry();
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.transform.append_scale_mut(scale);
+        self.update_geometry();
+    }
+}
+
+impl MathStroke {
+    /// The font family of the expression
+    pub const FONT_FAMILY: &'static str = "Serif";
+    /// The default font size
+    pub const FONT_SIZE_DEFAULT: f64 = 32.0;
+    /// The padding around the expression, in em
+    pub const PADDING: f64 = 0.2;
+
+    /// A new expression with its upper left corner at the position.
+    /// Sources which don't parse are rendered as they are, `parse_source()` can be used to check them beforehand
+    pub fn new(source: String, pos: na::Vector2<f64>, font_size: f64, color: Color) -> Self {
+        let mut mathstroke = Self {
+            source,
+            font_size,
+            color,
+            transform: Transform::new_w_isometry(na::Isometry2::new(pos, 0.0)),
+            bounds: AABB::new_zero(),
+        };
+        mathstroke.update_geometry();
+
+        mathstroke
+    }
+
+    pub fn update_geometry(&mut self) {
+        if let Some(new_bounds) = self.gen_bounds() {
+            self.set_bounds(new_bounds);
+        }
+    }
+
+    /// Parses the source, returning the error for sources that can't be rendered
+    pub fn parse_source(source: &str) -> Result<MathNode, anyhow::Error> {
+        math::parse(source)
+    }
+
+    /// Sets a new source and updates the geometry
+    pub fn set_source(&mut self, source: String) {
+        self.source = source;
+        self.update_geometry();
+    }
+
+    /// Lays out the expression. Falls back to the plain source when it doesn't parse
+    fn gen_mathbox(&self) -> Result<MathBox, anyhow::Error> {
+        let node = Self::parse_source(&self.source).unwrap_or_else(|e| {
+            log::debug!(
+                "parsing math source failed in mathstroke gen_mathbox(), rendering it as text. Err {}",
+                e
+            );
+            MathNode::Text {
+                text: self.source.clone(),
+                bold: false,
+            }
+        });
+
+        Ok(MathLayouter::new(Self::FONT_FAMILY)?.layout(&node, self.font_size))
+    }
+
+    /// The size of the expression including its padding, in the coordinate space of the expression
+    fn local_size(&self, mathbox: &MathBox) -> na::Vector2<f64> {
+        let padding = Self::PADDING * self.font_size;
+
+        na::vector![
+            mathbox.width + padding * 2.0,
+            mathbox.ascent + mathbox.descent + padding * 2.0
+        ]
+    }
+
+    /// Draws the expression onto the cairo context, in the coordinate space of the expression
+    #[cfg(feature = "cairo")]
+    fn draw_to_cairo(&self, cx: &cairo::Context, mathbox: &MathBox) -> Result<(), anyhow::Error> {
+        let padding = Self::PADDING * self.font_size;
+
+        mathbox.draw_to_cairo(
+            cx,
+            na::vector![padding, padding + mathbox.ascent],
+            self.color,
+        )
+    }
+
+    /// Whether the position hits the expression
+    pub fn hittest(&self, pos: na::Vector2<f64>, tolerance: f64) -> bool {
+        let size = match self.gen_mathbox() {
+            Ok(mathbox) => self.local_size(&mathbox),
+            Err(_) => return false,
+        };
+        let local_pos = match self.transform.transform.try_inverse() {
+            Some(inverse) => (inverse * na::Point2::from(pos)).coords,
+            None => return false,
+        };
+
+        local_pos[0] >= -tolerance
+            && local_pos[0] <= size[0] + tolerance
+            && local_pos[1] >= -tolerance
+            && local_pos[1] <= size[1] + tolerance
+    }
+}
@@ -1,7 +1,12 @@
 pub mod bitmapimage;
 pub mod brushstroke;
+pub mod checkboxstroke;
 pub mod element;
+pub mod extensionstroke;
 pub mod inputdata;
+pub mod mathstroke;
+pub mod pdfimport;
 pub mod shapestroke;
 pub mod strokestyle;
+pub mod textstroke;
 pub mod vectorimage;
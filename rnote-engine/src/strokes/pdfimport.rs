@@ -0,0 +1,147 @@
+use std::ops::Range;
+
+use p2d::bounding_volume::AABB;
+use serde::{Deserialize, Serialize};
+
+/// The type of strokes the pages of imported PDFs become
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfImportPagesType {
+    /// Vector images, which stay sharp on all zoom levels
+    Vector,
+    /// Bitmap images, which are faster to render for complex pages
+    Bitmap,
+}
+
+impl Default for PdfImportPagesType {
+    fn default() -> Self {
+        Self::Vector
+    }
+}
+
+/// The options for importing a PDF
+#[derive(Debug, Clone)]
+pub struct PdfImportOptions {
+    /// The pages to import, counted from 0. Pages beyond the end of the document are skipped
+    pub page_range: Range<u32>,
+    /// The width of the imported pages. None keeps the size of the PDF pages
+    pub page_width: Option<f64>,
+    /// The vertical spacing between the imported pages
+    pub page_spacing: f64,
+    pub pages_type: PdfImportPagesType,
+}
+
+impl Default for PdfImportOptions {
+    fn default() -> Self {
+        Self {
+            page_range: 0..u32::MAX,
+            page_width: None,
+            page_spacing: Self::PAGE_SPACING_DEFAULT,
+            pages_type: PdfImportPagesType::default(),
+        }
+    }
+}
+
+/// Where a page of an imported PDF is placed on the sheet
+#[derive(Debug, Clone, Copy)]
+pub struct PdfPagePlacement {
+    /// The index of the page in the document
+    pub page_index: i32,
+    /// The upper left corner of the page
+    pub pos: na::Vector2<f64>,
+    pub size: na::Vector2<f64>,
+}
+
+impl PdfImportOptions {
+    /// The default spacing between the pages
+    pub const PAGE_SPACING_DEFAULT: f64 = 16.0;
+
+    /// Places the pages in the range one below the other, starting at the position
+    #[cfg(feature = "ui")]
+    pub fn place_pages(
+        &self,
+        doc: &poppler::Document,
+        pos: na::Vector2<f64>,
+    ) -> Vec<PdfPagePlacement> {
+        let n_pages = doc.n_pages().max(0) as u32;
+        let mut y = pos[1];
+
+        (self.page_range.start..self.page_range.end.min(n_pages))
+            .filter_map(|i| {
+                let page_index = i as i32;
+                let intrinsic_size = doc.page(page_index)?.size();
+
+                let size = if let Some(page_width) = self.page_width {
+                    let zoom = page_width / intrinsic_size.0;
+                    na::vector![page_width, intrinsic_size.1 * zoom]
+                } else {
+                    na::vector![intrinsic_size.0, intrinsic_size.1]
+                };
+
+                let placement = PdfPagePlacement {
+                    page_index,
+                    pos: na::vector![pos[0], y],
+                    size,
+                };
+                y += size[1] + self.page_spacing;
+
+                Some(placement)
+            })
+            .collect()
+    }
+}
+
+/// The number of pages of the PDF
+#[cfg(feature = "ui")]
+pub fn pdf_n_pages(bytes: &[u8]) -> Result<u32, anyhow::Error> {
+    let doc = poppler::Document::from_data(bytes, None)?;
+
+    Ok(doc.n_pages().max(0) as u32)
+}
+
+/// A PDF that was imported into the sheet. It is kept so that the sheet can be exported onto the original pages
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "imported_pdf")]
+pub struct ImportedPdf {
+    /// The bytes of the original PDF
+    #[serde(rename = "data_base64", with = "crate::sheet::container::asset_base64")]
+    pub data_base64: String,
+    /// The pages which were imported
+    #[serde(rename = "pages")]
+    pub pages: Vec<ImportedPdfPage>,
+}
+
+/// A page of an imported PDF and where it was placed on the sheet
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename = "imported_pdf_page")]
+pub struct ImportedPdfPage {
+    #[serde(rename = "page_index")]
+    pub page_index: i32,
+    #[serde(rename = "bounds")]
+    pub bounds: AABB,
+}
+
+impl ImportedPdf {
+    pub fn new(bytes: &[u8], placements: &[PdfPagePlacement]) -> Self {
+        Self {
+            data_base64: base64::encode(bytes),
+            pages: placements
+                .iter()
+                .map(|placement| ImportedPdfPage {
+                    page_index: placement.page_index,
+                    bounds: AABB::new(
+                        na::Point2::from(placement.pos),
+                        na::Point2::from(placement.pos + placement.size),
+                    ),
+                })
+                .collect(),
+        }
+    }
+
+    /// The bounds where the page was placed on the sheet. None if the page wasn't imported
+    pub fn page_bounds(&self, page_index: i32) -> Option<AABB> {
+        self.pages
+            .iter()
+            .find(|page| page.page_index == page_index)
+            .map(|page| page.bounds)
+    }
+}
@@ -1,7 +1,10 @@
 use crate::compose;
-use crate::compose::geometry::AABBHelpers;
+use crate::compose::arrowhead::{self, ArrowheadOptions};
+use crate::compose::color::Color;
+use crate::compose::geometry::{self, AABBHelpers};
 use crate::compose::rough::roughoptions::RoughOptions;
-use crate::compose::smooth::SmoothOptions;
+use crate::compose::smooth::{FillRule, SmoothOptions};
+use crate::compose::textured::{self, TexturedOptions};
 use crate::compose::transformable::{Transform, Transformable};
 use crate::compose::{curves, rough, shapes};
 use crate::drawbehaviour::DrawBehaviour;
@@ -24,6 +27,16 @@ pub enum Shape {
     Rectangle(shapes::Rectangle),
     #[serde(rename = "ellipse")]
     Ellipse(shapes::Ellipse),
+    #[serde(rename = "regular_polygon")]
+    RegularPolygon(shapes::RegularPolygon),
+    #[serde(rename = "star")]
+    Star(shapes::Star),
+    #[serde(rename = "arc")]
+    Arc(shapes::Arc),
+    #[serde(rename = "parallelogram")]
+    Parallelogram(shapes::Parallelogram),
+    #[serde(rename = "polyline")]
+    Polyline(shapes::Polyline),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +66,21 @@ impl Transformable for Shape {
             Self::Ellipse(ellipse) => {
                 ellipse.translate(offset);
             }
+            Self::RegularPolygon(polygon) => {
+                polygon.translate(offset);
+            }
+            Self::Star(star) => {
+                star.translate(offset);
+            }
+            Self::Arc(arc) => {
+                arc.translate(offset);
+            }
+            Self::Parallelogram(parallelogram) => {
+                parallelogram.translate(offset);
+            }
+            Self::Polyline(polyline) => {
+                polyline.translate(offset);
+            }
         }
     }
 
@@ -67,6 +95,21 @@ impl Transformable for Shape {
             Self::Ellipse(ellipse) => {
                 ellipse.rotate(angle, center);
             }
+            Self::RegularPolygon(polygon) => {
+                polygon.rotate(angle, center);
+            }
+            Self::Star(star) => {
+                star.rotate(angle, center);
+            }
+            Self::Arc(arc) => {
+                arc.rotate(angle, center);
+            }
+            Self::Parallelogram(parallelogram) => {
+                parallelogram.rotate(angle, center);
+            }
+            Self::Polyline(polyline) => {
+                polyline.rotate(angle, center);
+            }
         }
     }
 
@@ -81,6 +124,21 @@ impl Transformable for Shape {
             Self::Ellipse(ellipse) => {
                 ellipse.scale(scale);
             }
+            Self::RegularPolygon(polygon) => {
+                polygon.scale(scale);
+            }
+            Self::Star(star) => {
+                star.scale(scale);
+            }
+            Self::Arc(arc) => {
+                arc.scale(scale);
+            }
+            Self::Parallelogram(parallelogram) => {
+                parallelogram.scale(scale);
+            }
+            Self::Polyline(polyline) => {
+                polyline.scale(scale);
+            }
         }
     }
 }
@@ -91,6 +149,145 @@ impl Shape {
             Self::Line(line) => line.global_aabb(),
             Self::Rectangle(rectangle) => rectangle.global_aabb(),
             Self::Ellipse(ellipse) => ellipse.global_aabb(),
+            Self::RegularPolygon(polygon) => polygon.global_aabb(),
+            Self::Star(star) => star.global_aabb(),
+            Self::Arc(arc) => arc.global_aabb(),
+            Self::Parallelogram(parallelogram) => parallelogram.global_aabb(),
+            Self::Polyline(polyline) => polyline.global_aabb(),
+        }
+    }
+
+    /// The number of samples for the outlines of curved shapes
+    pub const OUTLINE_N_SAMPLES: u32 = 64;
+
+    /// Points along the outline in global coordinates. They are connected in order, and the last one to the first one if the shape is closed
+    pub fn outline_points(&self) -> Vec<na::Vector2<f64>> {
+        fn transform_points(
+            points: Vec<na::Vector2<f64>>,
+            transform: &Transform,
+        ) -> Vec<na::Vector2<f64>> {
+            points
+                .into_iter()
+                .map(|point| transform.transform_point(na::Point2::from(point)).coords)
+                .collect()
+        }
+
+        match self {
+            Self::Line(line) => vec![line.start, line.end],
+            Self::Rectangle(rectangle) => {
+                let half_extents = rectangle.cuboid.half_extents;
+
+                transform_points(
+                    vec![
+                        na::vector![-half_extents[0], -half_extents[1]],
+                        na::vector![half_extents[0], -half_extents[1]],
+                        na::vector![half_extents[0], half_extents[1]],
+                        na::vector![-half_extents[0], half_extents[1]],
+                    ],
+                    &rectangle.transform,
+                )
+            }
+            Self::Ellipse(ellipse) => transform_points(
+                (0..Self::OUTLINE_N_SAMPLES)
+                    .map(|i| {
+                        let angle = std::f64::consts::TAU * f64::from(i)
+                            / f64::from(Self::OUTLINE_N_SAMPLES);
+                        na::vector![angle.cos(), angle.sin()].component_mul(&ellipse.radii)
+                    })
+                    .collect(),
+                &ellipse.transform,
+            ),
+            Self::RegularPolygon(polygon) => {
+                transform_points(polygon.local_vertices(), &polygon.transform)
+            }
+            Self::Star(star) => transform_points(star.local_vertices(), &star.transform),
+            Self::Arc(arc) => transform_points(
+                (0..=Self::OUTLINE_N_SAMPLES)
+                    .map(|i| {
+                        arc.local_point_at(
+                            arc.start_angle
+                                + arc.sweep_angle * f64::from(i)
+                                    / f64::from(Self::OUTLINE_N_SAMPLES),
+                        )
+                    })
+                    .collect(),
+                &arc.transform,
+            ),
+            Self::Parallelogram(parallelogram) => {
+                transform_points(parallelogram.local_vertices(), &parallelogram.transform)
+            }
+            Self::Polyline(polyline) => polyline.vertices.clone(),
+        }
+    }
+
+    /// The start and end of open shapes, each with the tangent in the direction of the shape there. None for closed shapes
+    pub fn ends_w_tangents(&self) -> Option<[(na::Vector2<f64>, na::Vector2<f64>); 2]> {
+        match self {
+            Self::Line(line) => {
+                let direction = line.end - line.start;
+
+                Some([(line.start, direction), (line.end, direction)])
+            }
+            Self::Arc(arc) => {
+                let end_angle = arc.start_angle + arc.sweep_angle;
+                // The derivative of the point at the angle, in the direction of the sweep
+                let local_tangent =
+                    |angle: f64| na::vector![-angle.sin(), angle.cos()] * arc.sweep_angle.signum();
+
+                Some([
+                    (
+                        arc.transform
+                            .transform_point(na::Point2::from(arc.start()))
+                            .coords,
+                        arc.transform.transform * local_tangent(arc.start_angle),
+                    ),
+                    (
+                        arc.transform
+                            .transform_point(na::Point2::from(arc.end()))
+                            .coords,
+                        arc.transform.transform * local_tangent(end_angle),
+                    ),
+                ])
+            }
+            Self::Polyline(polyline) if !polyline.closed => {
+                let first = *polyline.vertices.first()?;
+                let last = *polyline.vertices.last()?;
+                // Vertices on top of the ends don't give a direction
+                let second = polyline
+                    .vertices
+                    .iter()
+                    .find(|vertex| **vertex != first)
+                    .copied()
+                    .unwrap_or(first);
+                let second_last = polyline
+                    .vertices
+                    .iter()
+                    .rev()
+                    .find(|vertex| **vertex != last)
+                    .copied()
+                    .unwrap_or(last);
+
+                Some([(first, second - first), (last, last - second_last)])
+            }
+            Self::Polyline(_)
+            | Self::Rectangle(_)
+            | Self::Ellipse(_)
+            | Self::RegularPolygon(_)
+            | Self::Star(_)
+            | Self::Parallelogram(_) => None,
+        }
+    }
+
+    /// Whether the shape encloses an area which can be filled
+    pub fn is_closed(&self) -> bool {
+        match self {
+            Self::Line(_) | Self::Arc(_) => false,
+            Self::Polyline(polyline) => polyline.closed,
+            Self::Rectangle(_)
+            | Self::Ellipse(_)
+            | Self::RegularPolygon(_)
+            | Self::Star(_)
+            | Self::Parallelogram(_) => true,
         }
     }
 }
@@ -104,6 +301,9 @@ pub struct ShapeStroke {
     pub shape: Shape,
     #[serde(rename = "drawstyle")]
     pub drawstyle: ShapeDrawStyle,
+    /// The arrowheads, if the shape is open ( a line, arc or open polyline )
+    #[serde(rename = "arrowheads")]
+    pub arrowheads: ArrowheadOptions,
     #[serde(rename = "bounds")]
     pub bounds: AABB,
 }
@@ -127,25 +327,54 @@ impl DrawBehaviour for ShapeStroke {
         match &self.drawstyle {
             ShapeDrawStyle::Smooth { options } => {
                 let width = options.width;
-                Some(
-                    self.shape
-                        .bounds()
-                        .loosened(width * 0.5 + ShaperDrawStyle::SMOOTH_MARGIN),
-                )
+                Some(self.shape.bounds().loosened(
+                    width * 0.5 + self.arrowheads_margin(width) + ShaperDrawStyle::SMOOTH_MARGIN,
+                ))
             }
             ShapeDrawStyle::Rough { options } => {
                 let width = options.stroke_width();
-                Some(
-                    self.shape
-                        .bounds()
-                        .loosened(width * 0.5 + ShaperDrawStyle::ROUGH_MARGIN),
-                )
+                Some(self.shape.bounds().loosened(
+                    width * 0.5 + self.arrowheads_margin(width) + ShaperDrawStyle::ROUGH_MARGIN,
+                ))
             }
         }
     }
 
     fn gen_svgs(&self, offset: na::Vector2<f64>) -> Result<Vec<render::Svg>, anyhow::Error> {
-        let element: svg::node::element::Element = match self.shape {
+        let element = if let Some(textured_fill) = self.compose_textured_fill(offset) {
+            // The outline is composed without its solid fill on top of the dots
+            let mut outline = self.clone();
+            if let ShapeDrawStyle::Smooth { options } = &mut outline.drawstyle {
+                options.fill_color = None;
+            }
+
+            svg::node::element::Group::new()
+                .add(textured_fill)
+                .add(outline.compose_shape(offset))
+                .into()
+        } else {
+            self.compose_shape(offset)
+        };
+
+        let svg_data = compose::svg_node_to_string(&element).map_err(|e| {
+            anyhow::anyhow!(
+                "node_to_string() failed in gen_svg_data() for a shapestroke, {}",
+                e
+            )
+        })?;
+
+        let svg = render::Svg {
+            bounds: self.bounds.translate(offset),
+            svg_data,
+        };
+        Ok(vec![svg])
+    }
+}
+
+impl ShapeStroke {
+    /// Composes the shape with its stroke and fill
+    fn compose_shape(&self, offset: na::Vector2<f64>) -> svg::node::element::Element {
+        match self.shape {
             Shape::Line(ref line) => {
                 let line = curves::Line {
                     start: line.start + offset,
@@ -154,6 +383,14 @@ impl DrawBehaviour for ShapeStroke {
 
                 match &self.drawstyle {
                     ShapeDrawStyle::Smooth { options } => {
+                        let arrowheads = arrowhead::compose_line_arrowheads(
+                            line,
+                            &self.arrowheads,
+                            options.width,
+                            options.stroke_color,
+                        );
+                        let line = self.arrowheads.inset_line(line, options.width);
+
                         let color = if let Some(color) = options.stroke_color {
                             color.to_css_color()
                         } else {
@@ -166,7 +403,7 @@ impl DrawBehaviour for ShapeStroke {
                             String::from("none")
                         };
 
-                        svg::node::element::Line::new()
+                        let line_elem = svg::node::element::Line::new()
                             .set("x1", line.start[0])
                             .set("y1", line.start[1])
                             .set("x2", line.end[0])
@@ -174,11 +411,38 @@ impl DrawBehaviour for ShapeStroke {
                             .set("stroke", color)
                             .set("stroke-width", options.width)
                             .set("fill", fill)
+                            .set(
+                                "stroke-dasharray",
+                                options
+                                    .stroke_dasharray(false)
+                                    .unwrap_or_else(|| String::from("none")),
+                            );
+
+                        arrowheads
+                            .into_iter()
+                            .fold(
+                                svg::node::element::Group::new().add(line_elem),
+                                |group, arrowhead| group.add(arrowhead),
+                            )
+                            .into()
+                    }
+                    ShapeDrawStyle::Rough { options } => {
+                        let arrowheads = arrowhead::compose_line_arrowheads(
+                            line,
+                            &self.arrowheads,
+                            options.stroke_width(),
+                            options.stroke_color,
+                        );
+                        let line = self.arrowheads.inset_line(line, options.stroke_width());
+
+                        arrowheads
+                            .into_iter()
+                            .fold(
+                                svg::node::element::Group::new().add(rough::line(line, options)),
+                                |group, arrowhead| group.add(arrowhead),
+                            )
                             .into()
                     }
-                    ShapeDrawStyle::Rough { options } => svg::node::element::Group::new()
-                        .add(rough::line(line, options))
-                        .into(),
                 }
             }
             Shape::Rectangle(ref rectangle) => {
@@ -205,20 +469,112 @@ impl DrawBehaviour for ShapeStroke {
                     ShapeDrawStyle::Rough { options } => rough::ellipse(ellipse, options).into(),
                 }
             }
-        };
+            Shape::RegularPolygon(ref polygon) => {
+                let mut transform = polygon.transform;
+                transform.append_translation_mut(offset);
 
-        let svg_data = compose::svg_node_to_string(&element).map_err(|e| {
-            anyhow::anyhow!(
-                "node_to_string() failed in gen_svg_data() for a shapestroke, {}",
-                e
-            )
-        })?;
+                self.compose_polygon(&polygon.local_vertices(), &transform)
+            }
+            Shape::Star(ref star) => {
+                let mut transform = star.transform;
+                transform.append_translation_mut(offset);
 
-        let svg = render::Svg {
-            bounds: self.bounds.translate(offset),
-            svg_data,
+                self.compose_polygon(&star.local_vertices(), &transform)
+            }
+            Shape::Parallelogram(ref parallelogram) => {
+                let mut transform = parallelogram.transform;
+                transform.append_translation_mut(offset);
+
+                self.compose_polygon(&parallelogram.local_vertices(), &transform)
+            }
+            Shape::Arc(ref arc) => {
+                let mut arc = arc.clone();
+                arc.transform.append_translation_mut(offset);
+
+                match &self.drawstyle {
+                    ShapeDrawStyle::Smooth { options } => group_w_arrowheads(
+                        compose::smooth::compose_arc(
+                            self.arrowheads.inset_arc(arc, options.width),
+                            options,
+                        ),
+                        self.compose_arrowheads(offset, options.width, options.stroke_color),
+                    ),
+                    ShapeDrawStyle::Rough { options } => group_w_arrowheads(
+                        rough::arc(
+                            self.arrowheads.inset_arc(arc, options.stroke_width()),
+                            options,
+                        )
+                        .into(),
+                        self.compose_arrowheads(
+                            offset,
+                            options.stroke_width(),
+                            options.stroke_color,
+                        ),
+                    ),
+                }
+            }
+            Shape::Polyline(ref polyline) => {
+                let mut polyline = polyline.clone();
+                polyline.translate(offset);
+
+                match &self.drawstyle {
+                    ShapeDrawStyle::Smooth { options } => group_w_arrowheads(
+                        compose::smooth::compose_polyline(
+                            &self.arrowheads.inset_polyline(&polyline, options.width),
+                            options,
+                        ),
+                        self.compose_arrowheads(offset, options.width, options.stroke_color),
+                    ),
+                    ShapeDrawStyle::Rough { options } => group_w_arrowheads(
+                        rough::polyline(
+                            &self
+                                .arrowheads
+                                .inset_polyline(&polyline, options.stroke_width()),
+                            options,
+                        )
+                        .into(),
+                        self.compose_arrowheads(
+                            offset,
+                            options.stroke_width(),
+                            options.stroke_color,
+                        ),
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Composes the textured fill of closed shapes drawn smooth, if it is enabled and there is a fill color.
+    /// The dots are the dots of textured strokes, in the fill color
+    pub fn compose_textured_fill(
+        &self,
+        offset: na::Vector2<f64>,
+    ) -> Option<svg::node::element::Element> {
+        let options = match &self.drawstyle {
+            ShapeDrawStyle::Smooth { options } if options.fill_textured => options,
+            _ => return None,
         };
-        Ok(vec![svg])
+        if !self.shape.is_closed() {
+            return None;
+        }
+        let textured_options = TexturedOptions {
+            seed: options.seed,
+            stroke_color: Some(options.fill_color?),
+            ..TexturedOptions::default()
+        };
+
+        let mut shape = self.shape.clone();
+        shape.translate(offset);
+
+        match shape {
+            Shape::Ellipse(ellipse) => {
+                Some(textured::compose_fill_ellipse(ellipse, &textured_options))
+            }
+            shape => Some(textured::compose_fill_polygon(
+                &shape.outline_points(),
+                &textured_options,
+            )),
+        }
     }
 }
 
@@ -260,26 +616,63 @@ impl ShapeStroke {
                     0.0,
                 )),
             }),
+            ShaperStyle::RegularPolygon => Shape::RegularPolygon(shapes::RegularPolygon {
+                radius: 0.0,
+                n_sides: shaper.polygon_n_sides,
+                transform: Transform::new_w_isometry(na::Isometry2::new(
+                    element.inputdata.pos(),
+                    0.0,
+                )),
+            }),
+            ShaperStyle::Star => Shape::Star(shapes::Star {
+                outer_radius: 0.0,
+                inner_radius_ratio: shaper.star_inner_radius_ratio,
+                n_points: shaper.star_n_points,
+                transform: Transform::new_w_isometry(na::Isometry2::new(
+                    element.inputdata.pos(),
+                    0.0,
+                )),
+            }),
+            ShaperStyle::Arc => Shape::Arc(shapes::Arc {
+                radius: 0.0,
+                start_angle: 0.0,
+                sweep_angle: 0.0,
+                transform: Transform::new_w_isometry(na::Isometry2::new(
+                    element.inputdata.pos(),
+                    0.0,
+                )),
+            }),
+            ShaperStyle::Parallelogram => Shape::Parallelogram(shapes::Parallelogram {
+                half_extents: na::vector![0.0, 0.0],
+                skew: shaper.parallelogram_skew,
+                transform: Transform::new_w_isometry(na::Isometry2::new(
+                    element.inputdata.pos(),
+                    0.0,
+                )),
+            }),
+            ShaperStyle::Polyline => Shape::Polyline(shapes::Polyline {
+                vertices: vec![element.inputdata.pos()],
+                closed: false,
+            }),
         };
         let bounds = shape.bounds();
-        let drawstyle = match shaper.drawstyle {
-            ShaperDrawStyle::Smooth => {
-                let mut options = shaper.smooth_options;
-                options.seed = seed;
+        let drawstyle = Self::drawstyle_from_shaper(shaper, seed);
 
-                ShapeDrawStyle::Smooth { options }
-            }
-            ShaperDrawStyle::Rough => {
-                let mut options = shaper.rough_options.clone();
-                options.seed = seed;
-
-                ShapeDrawStyle::Rough { options }
+        let arrowheads = match shaper.style {
+            ShaperStyle::Line | ShaperStyle::Arc | ShaperStyle::Polyline => {
+                shaper.arrowhead_options
             }
+            ShaperStyle::Rectangle
+            | ShaperStyle::Ellipse
+            | ShaperStyle::RegularPolygon
+            | ShaperStyle::Star
+            | ShaperStyle::Parallelogram => ArrowheadOptions::default(),
         };
 
         let mut shapestroke = Self {
             shape,
             drawstyle,
+            arrowheads,
             bounds,
             seed,
         };
@@ -291,6 +684,30 @@ impl ShapeStroke {
         shapestroke
     }
 
+    /// A new shapestroke from an already complete shape, drawn with smooth options
+    pub fn new_w_shape(
+        shape: Shape,
+        mut options: SmoothOptions,
+        arrowheads: ArrowheadOptions,
+    ) -> Self {
+        let seed = Some(rand_pcg::Pcg64::from_entropy().gen());
+        options.seed = seed;
+
+        let mut shapestroke = Self {
+            bounds: shape.bounds(),
+            shape,
+            drawstyle: ShapeDrawStyle::Smooth { options },
+            arrowheads,
+            seed,
+        };
+
+        if let Some(new_bounds) = shapestroke.gen_bounds() {
+            shapestroke.bounds = new_bounds;
+        }
+
+        shapestroke
+    }
+
     pub fn update_shape(&mut self, shaper: &mut Shaper, element: Element) {
         match self.shape {
             Shape::Line(ref mut line) => {
@@ -316,14 +733,285 @@ impl ShapeStroke {
 
                 ellipse.radii = diff.abs();
             }
+            Shape::RegularPolygon(ref mut polygon) => {
+                // The polygon is centered at the start, with a vertex at the current position
+                let diff = element.inputdata.pos() - shaper.rect_start;
+
+                polygon.radius = diff.magnitude();
+                polygon.transform = Transform::new_w_isometry(na::Isometry2::new(
+                    shaper.rect_start,
+                    diff[1].atan2(diff[0]),
+                ));
+            }
+            Shape::Star(ref mut star) => {
+                // The star is centered at the start, with a point at the current position
+                let diff = element.inputdata.pos() - shaper.rect_start;
+
+                star.outer_radius = diff.magnitude();
+                star.transform = Transform::new_w_isometry(na::Isometry2::new(
+                    shaper.rect_start,
+                    diff[1].atan2(diff[0]),
+                ));
+            }
+            Shape::Arc(ref mut arc) => {
+                // The arc is centered at the start. Its start angle is set once the current position is far enough away,
+                // after that the sweep follows the angle of the current position, unwrapped so it can sweep past a half circle
+                let diff = element.inputdata.pos() - shaper.rect_start;
+                let angle = diff[1].atan2(diff[0]);
+
+                if arc.radius <= 0.0 {
+                    if diff.magnitude() >= Self::ARC_START_DIST_MIN {
+                        arc.radius = diff.magnitude();
+                        arc.start_angle = angle;
+                        arc.sweep_angle = 0.0;
+                    }
+                } else {
+                    let delta = (angle - (arc.start_angle + arc.sweep_angle)
+                        + std::f64::consts::PI)
+                        .rem_euclid(std::f64::consts::TAU)
+                        - std::f64::consts::PI;
+
+                    arc.radius = diff.magnitude();
+                    arc.sweep_angle = (arc.sweep_angle + delta)
+                        .clamp(-std::f64::consts::TAU, std::f64::consts::TAU);
+                }
+            }
+            Shape::Parallelogram(ref mut parallelogram) => {
+                // The parallelogram spans the rectangle between the start and the current position
+                let pos = element.inputdata.pos();
+
+                parallelogram.half_extents = ((pos - shaper.rect_start) / 2.0).abs();
+                parallelogram.transform = Transform::new_w_isometry(na::Isometry2::new(
+                    (pos + shaper.rect_start) / 2.0,
+                    0.0,
+                ));
+            }
+            Shape::Polyline(ref mut polyline) => {
+                // The vertices are placed by the builder of the shaper, the last one follows the current position
+                if let Some(builder) = shaper.polyline_builder.as_mut() {
+                    if let Some(last) = builder.vertices.last_mut() {
+                        *last = element.inputdata.pos();
+                    }
+
+                    polyline.vertices = builder.vertices.clone();
+                    polyline.closed = builder.closed;
+                }
+            }
         }
 
         self.update_geometry();
     }
 
+    /// The min distance of the position from the center before the arc starts
+    pub const ARC_START_DIST_MIN: f64 = 4.0;
+
+    /// Composes a closed polygon shape from its vertices in the local coordinates of the transform
+    fn compose_polygon(
+        &self,
+        vertices: &[na::Vector2<f64>],
+        transform: &Transform,
+    ) -> svg::node::element::Element {
+        match &self.drawstyle {
+            ShapeDrawStyle::Smooth { options } => {
+                compose::smooth::compose_polygon(vertices, transform, options)
+            }
+            ShapeDrawStyle::Rough { options } => {
+                rough::polygon(vertices, transform, options).into()
+            }
+        }
+    }
+
+    /// Composes the arrowheads at the ends of open shapes
+    fn compose_arrowheads(
+        &self,
+        offset: na::Vector2<f64>,
+        stroke_width: f64,
+        stroke_color: Option<Color>,
+    ) -> Vec<svg::node::element::Element> {
+        match self.shape.ends_w_tangents() {
+            Some([start, end]) if self.arrowheads.has_arrowheads() => {
+                arrowhead::compose_path_arrowheads(
+                    (start.0 + offset, start.1),
+                    (end.0 + offset, end.1),
+                    &self.arrowheads,
+                    stroke_width,
+                    stroke_color,
+                )
+            }
+            _ => vec![],
+        }
+    }
+
+    /// The extra margin needed for the arrowheads
+    fn arrowheads_margin(&self, stroke_width: f64) -> f64 {
+        match self.shape {
+            Shape::Line(_) | Shape::Arc(_) | Shape::Polyline(_)
+                if self.arrowheads.has_arrowheads() =>
+            {
+                self.arrowheads.size(stroke_width)
+            }
+            _ => 0.0,
+        }
+    }
+
     pub fn update_geometry(&mut self) {
         if let Some(new_bounds) = self.gen_bounds() {
             self.bounds = new_bounds;
         }
     }
+
+    /// Whether the position hits the outline of the shape within the tolerance outside of the stroke width
+    pub fn hits_outline(&self, pos: na::Vector2<f64>, tolerance: f64) -> bool {
+        let width = match &self.drawstyle {
+            ShapeDrawStyle::Smooth { options } => options.width,
+            ShapeDrawStyle::Rough { options } => options.stroke_width,
+        };
+        let hit_dist = width * 0.5 + tolerance;
+
+        let points = self.shape.outline_points();
+        let n_segments = if self.shape.is_closed() {
+            points.len()
+        } else {
+            points.len().saturating_sub(1)
+        };
+
+        points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .take(n_segments)
+            .any(|(start, end)| geometry::dist_to_segment(pos, *start, *end) <= hit_dist)
+    }
+
+    /// Whether the position hits the outline of the shape within the tolerance outside of the stroke width, or its fill
+    pub fn hittest(&self, pos: na::Vector2<f64>, tolerance: f64) -> bool {
+        if self.hits_outline(pos, tolerance) {
+            return true;
+        }
+
+        let filled = self.fill_color().is_some();
+        let closed = self.shape.is_closed();
+        let points = self.shape.outline_points();
+
+        // Crossings with a ray to the right, an odd number means the position is inside the outline
+        filled
+            && closed
+            && points
+                .iter()
+                .zip(points.iter().cycle().skip(1))
+                .filter(|(start, end)| {
+                    (start[1] > pos[1]) != (end[1] > pos[1])
+                        && pos[0]
+                            < start[0]
+                                + (pos[1] - start[1]) / (end[1] - start[1]) * (end[0] - start[0])
+                })
+                .count()
+                % 2
+                == 1
+    }
+
+    /// Sets the seed of the shape and its drawstyle options
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+
+        match &mut self.drawstyle {
+            ShapeDrawStyle::Smooth { options } => options.seed = seed,
+            ShapeDrawStyle::Rough { options } => options.seed = seed,
+        }
+    }
+
+    /// Restyles the shape with the current drawstyle and options of the shaper. The seed is kept, so rough shapes keep their look
+    pub fn set_drawstyle_from_shaper(&mut self, shaper: &Shaper) {
+        self.drawstyle = Self::drawstyle_from_shaper(shaper, self.seed);
+        self.update_geometry();
+    }
+
+    /// The stroke color
+    pub fn stroke_color(&self) -> Option<Color> {
+        match &self.drawstyle {
+            ShapeDrawStyle::Smooth { options } => options.stroke_color,
+            ShapeDrawStyle::Rough { options } => options.stroke_color,
+        }
+    }
+
+    /// The fill color
+    pub fn fill_color(&self) -> Option<Color> {
+        match &self.drawstyle {
+            ShapeDrawStyle::Smooth { options } => options.fill_color,
+            ShapeDrawStyle::Rough { options } => options.fill_color,
+        }
+    }
+
+    /// Sets the stroke color
+    pub fn set_stroke_color(&mut self, stroke_color: Option<Color>) {
+        match &mut self.drawstyle {
+            ShapeDrawStyle::Smooth { options } => options.stroke_color = stroke_color,
+            ShapeDrawStyle::Rough { options } => options.stroke_color = stroke_color,
+        }
+    }
+
+    /// Sets the width. The bounds depend on it, so the geometry is updated
+    pub fn set_width(&mut self, width: f64) {
+        match &mut self.drawstyle {
+            ShapeDrawStyle::Smooth { options } => options.width = width,
+            ShapeDrawStyle::Rough { options } => options.stroke_width = width,
+        }
+        self.update_geometry();
+    }
+
+    fn drawstyle_from_shaper(shaper: &Shaper, seed: Option<u64>) -> ShapeDrawStyle {
+        match shaper.drawstyle {
+            ShaperDrawStyle::Smooth => {
+                let mut options = shaper.smooth_options;
+                options.seed = seed;
+
+                ShapeDrawStyle::Smooth { options }
+            }
+            ShaperDrawStyle::Rough => {
+                let mut options = shaper.rough_options.clone();
+                options.seed = seed;
+
+                ShapeDrawStyle::Rough { options }
+            }
+        }
+    }
+
+    /// Sets the fill color. Returns false if the shape is not closed and can't be filled
+    pub fn set_fill_color(&mut self, fill_color: Option<Color>) -> bool {
+        if !self.shape.is_closed() {
+            return false;
+        }
+
+        match &mut self.drawstyle {
+            ShapeDrawStyle::Smooth { options } => options.fill_color = fill_color,
+            ShapeDrawStyle::Rough { options } => options.fill_color = fill_color,
+        }
+        true
+    }
+
+    /// Sets the fill rule. Returns false if the shape is not closed and can't be filled
+    pub fn set_fill_rule(&mut self, fill_rule: FillRule) -> bool {
+        if !self.shape.is_closed() {
+            return false;
+        }
+
+        match &mut self.drawstyle {
+            ShapeDrawStyle::Smooth { options } => options.fill_rule = fill_rule,
+            ShapeDrawStyle::Rough { options } => options.fill_rule = fill_rule,
+        }
+        true
+    }
+}
+
+/// Groups the element with the arrowheads drawn on top of it
+fn group_w_arrowheads(
+    element: svg::node::element::Element,
+    arrowheads: Vec<svg::node::element::Element>,
+) -> svg::node::element::Element {
+    arrowheads
+        .into_iter()
+        .fold(
+            svg::node::element::Group::new().add(element),
+            |group, arrowhead| group.add(arrowhead),
+        )
+        .into()
 }
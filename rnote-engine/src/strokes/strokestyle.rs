@@ -1,22 +1,31 @@
 use super::bitmapimage::{self, BitmapImage};
 use super::brushstroke::{BrushStroke, BrushStrokeStyle};
+use super::checkboxstroke::CheckboxStroke;
+use super::extensionstroke::ExtensionStroke;
 use super::inputdata::InputData;
-use super::shapestroke::ShapeStroke;
+use super::mathstroke::MathStroke;
+use super::shapestroke::{Shape, ShapeStroke};
+use super::textstroke::{TextStroke, TextStyle};
 use super::vectorimage::VectorImage;
 use crate::compose::color::Color;
 use crate::compose::geometry::AABBHelpers;
+use crate::compose::highlighter::HighlighterOptions;
 use crate::compose::shapes;
 use crate::compose::smooth::SmoothOptions;
+use crate::compose::tessellation::Tessellation;
 use crate::compose::transformable::{Transform, Transformable};
 use crate::drawbehaviour::DrawBehaviour;
 use crate::pens::brush::{Brush, BrushStyle};
-use crate::render::{self, Renderer};
+use crate::pens::shaper::Shaper;
+use crate::render::{self, DetailLevel, Renderer, TessellatedStrokes};
 use crate::strokes::element::Element;
 use crate::utils;
 
 use std::sync::{Arc, RwLock};
 
-use p2d::bounding_volume::AABB;
+use p2d::bounding_volume::{BoundingVolume, AABB};
+use rand::{Rng, SeedableRng};
+use rnote_fileformats::isfformat;
 use rnote_fileformats::xoppformat::{self, XoppColor};
 use serde::{Deserialize, Serialize};
 
@@ -31,6 +40,15 @@ pub enum StrokeStyle {
     VectorImage(VectorImage),
     #[serde(rename = "bitmapimage")]
     BitmapImage(BitmapImage),
+    #[serde(rename = "textstroke")]
+    TextStroke(TextStroke),
+    #[serde(rename = "mathstroke")]
+    MathStroke(MathStroke),
+    #[serde(rename = "checkboxstroke")]
+    CheckboxStroke(CheckboxStroke),
+    /// A stroke of a composer added by a plugin
+    #[serde(rename = "extensionstroke")]
+    ExtensionStroke(ExtensionStroke),
 }
 
 impl Default for StrokeStyle {
@@ -39,6 +57,20 @@ impl Default for StrokeStyle {
     }
 }
 
+/// Modifications of the options of already drawn strokes. Options which are None are left as they are.
+/// The restyling is applied first, so the stroke color and width override the options of the pens
+#[derive(Debug, Clone, Default)]
+pub struct StrokeOptionsModification {
+    /// Restyles brush strokes with the style and options of the brush
+    pub brush: Option<Brush>,
+    /// Restyles shapes with the drawstyle and options of the shaper
+    pub shaper: Option<Shaper>,
+    /// The new stroke color
+    pub stroke_color: Option<Color>,
+    /// The new width
+    pub width: Option<f64>,
+}
+
 impl DrawBehaviour for StrokeStyle {
     fn bounds(&self) -> AABB {
         match self {
@@ -46,6 +78,10 @@ impl DrawBehaviour for StrokeStyle {
             Self::ShapeStroke(shapestroke) => shapestroke.bounds(),
             Self::VectorImage(vectorimage) => vectorimage.bounds(),
             Self::BitmapImage(bitmapimage) => bitmapimage.bounds(),
+            Self::TextStroke(textstroke) => textstroke.bounds(),
+            Self::MathStroke(mathstroke) => mathstroke.bounds(),
+            Self::CheckboxStroke(checkboxstroke) => checkboxstroke.bounds(),
+            Self::ExtensionStroke(extensionstroke) => extensionstroke.bounds(),
         }
     }
 
@@ -55,6 +91,10 @@ impl DrawBehaviour for StrokeStyle {
             Self::ShapeStroke(shapestroke) => shapestroke.set_bounds(bounds),
             Self::VectorImage(vectorimage) => vectorimage.set_bounds(bounds),
             Self::BitmapImage(bitmapimage) => bitmapimage.set_bounds(bounds),
+            Self::TextStroke(textstroke) => textstroke.set_bounds(bounds),
+            Self::MathStroke(mathstroke) => mathstroke.set_bounds(bounds),
+            Self::CheckboxStroke(checkboxstroke) => checkboxstroke.set_bounds(bounds),
+            Self::ExtensionStroke(extensionstroke) => extensionstroke.set_bounds(bounds),
         }
     }
 
@@ -64,6 +104,57 @@ impl DrawBehaviour for StrokeStyle {
             Self::ShapeStroke(shapestroke) => shapestroke.gen_svgs(offset),
             Self::VectorImage(vectorimage) => vectorimage.gen_svgs(offset),
             Self::BitmapImage(bitmapimage) => bitmapimage.gen_svgs(offset),
+            Self::TextStroke(textstroke) => textstroke.gen_svgs(offset),
+            Self::MathStroke(mathstroke) => mathstroke.gen_svgs(offset),
+            Self::CheckboxStroke(checkboxstroke) => checkboxstroke.gen_svgs(offset),
+            Self::ExtensionStroke(extensionstroke) => extensionstroke.gen_svgs(offset),
+        }
+    }
+
+    fn gen_images(
+        &self,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<Vec<render::Image>, anyhow::Error> {
+        match self {
+            // Texts, math expressions and checkboxes are rendered directly with pango, with the `cairo` feature
+            Self::TextStroke(textstroke) => textstroke.gen_images(zoom, renderer),
+            Self::MathStroke(mathstroke) => mathstroke.gen_images(zoom, renderer),
+            Self::CheckboxStroke(checkboxstroke) => checkboxstroke.gen_images(zoom, renderer),
+            // Composers may render their strokes themselves
+            Self::ExtensionStroke(extensionstroke) => extensionstroke.gen_images(zoom, renderer),
+            Self::BrushStroke(brushstroke)
+                if DetailLevel::for_zoom(zoom) == DetailLevel::Simplified =>
+            {
+                match brushstroke.simplified() {
+                    Some(simplified) => simplified.gen_images(zoom, renderer),
+                    None => brushstroke.gen_images(zoom, renderer),
+                }
+            }
+            _ => {
+                let svgs = self.gen_svgs(na::vector![0.0, 0.0])?;
+
+                renderer
+                    .read()
+                    .unwrap()
+                    .gen_images(zoom, svgs, self.bounds())
+            }
+        }
+    }
+
+    #[cfg(feature = "cairo")]
+    fn draw_to_cairo_vector(&self, cx: &cairo::Context) -> Result<(), anyhow::Error> {
+        match self {
+            // Keeps the text as text, instead of converting the glyphs to paths
+            Self::TextStroke(textstroke) => textstroke.draw_to_cairo_vector(cx),
+            Self::MathStroke(mathstroke) => mathstroke.draw_to_cairo_vector(cx),
+            Self::CheckboxStroke(checkboxstroke) => checkboxstroke.draw_to_cairo_vector(cx),
+            Self::ExtensionStroke(extensionstroke) => extensionstroke.draw_to_cairo_vector(cx),
+            _ => {
+                let svgs = self.gen_svgs(na::vector![0.0, 0.0])?;
+
+                render::draw_svgs_to_cairo_context(1.0, &svgs, self.bounds(), cx)
+            }
         }
     }
 }
@@ -83,6 +174,18 @@ impl Transformable for StrokeStyle {
             Self::BitmapImage(bitmapimage) => {
                 bitmapimage.translate(offset);
             }
+            Self::TextStroke(textstroke) => {
+                textstroke.translate(offset);
+            }
+            Self::MathStroke(mathstroke) => {
+                mathstroke.translate(offset);
+            }
+            Self::CheckboxStroke(checkboxstroke) => {
+                checkboxstroke.translate(offset);
+            }
+            Self::ExtensionStroke(extensionstroke) => {
+                extensionstroke.translate(offset);
+            }
         }
     }
 
@@ -100,6 +203,18 @@ impl Transformable for StrokeStyle {
             Self::BitmapImage(bitmapimage) => {
                 bitmapimage.rotate(angle, center);
             }
+            Self::TextStroke(textstroke) => {
+                textstroke.rotate(angle, center);
+            }
+            Self::MathStroke(mathstroke) => {
+                mathstroke.rotate(angle, center);
+            }
+            Self::CheckboxStroke(checkboxstroke) => {
+                checkboxstroke.rotate(angle, center);
+            }
+            Self::ExtensionStroke(extensionstroke) => {
+                extensionstroke.rotate(angle, center);
+            }
         }
     }
 
@@ -117,11 +232,281 @@ impl Transformable for StrokeStyle {
             Self::BitmapImage(bitmapimage) => {
                 bitmapimage.scale(scale);
             }
+            Self::TextStroke(textstroke) => {
+                textstroke.scale(scale);
+            }
+            Self::MathStroke(mathstroke) => {
+                mathstroke.scale(scale);
+            }
+            Self::CheckboxStroke(checkboxstroke) => {
+                checkboxstroke.scale(scale);
+            }
+            Self::ExtensionStroke(extensionstroke) => {
+                extensionstroke.scale(scale);
+            }
         }
     }
 }
 
 impl StrokeStyle {
+    /// Modifies the options of the stroke. Images and the strokes of plugins have no options and are left as they are, texts, math expressions and checkboxes only take on the stroke color.
+    /// Returns true if the stroke was modified and needs to be rerendered
+    pub fn modify_options(&mut self, modification: &StrokeOptionsModification) -> bool {
+        match self {
+            Self::BrushStroke(brushstroke) => {
+                if let Some(brush) = &modification.brush {
+                    brushstroke.set_style_from_brush(brush);
+                }
+                if let Some(stroke_color) = modification.stroke_color {
+                    brushstroke.set_stroke_color(Some(stroke_color));
+                }
+                if let Some(width) = modification.width {
+                    brushstroke.set_width(width);
+                }
+                true
+            }
+            Self::ShapeStroke(shapestroke) => {
+                if let Some(shaper) = &modification.shaper {
+                    shapestroke.set_drawstyle_from_shaper(shaper);
+                }
+                if let Some(stroke_color) = modification.stroke_color {
+                    shapestroke.set_stroke_color(Some(stroke_color));
+                }
+                if let Some(width) = modification.width {
+                    shapestroke.set_width(width);
+                }
+                true
+            }
+            Self::TextStroke(textstroke) => {
+                if let Some(stroke_color) = modification.stroke_color {
+                    textstroke.set_color(stroke_color);
+                    true
+                } else {
+                    false
+                }
+            }
+            Self::MathStroke(mathstroke) => {
+                if let Some(stroke_color) = modification.stroke_color {
+                    mathstroke.color = stroke_color;
+                    true
+                } else {
+                    false
+                }
+            }
+            Self::CheckboxStroke(checkboxstroke) => {
+                if let Some(stroke_color) = modification.stroke_color {
+                    checkboxstroke.color = stroke_color;
+                    true
+                } else {
+                    false
+                }
+            }
+            Self::VectorImage(_) | Self::BitmapImage(_) | Self::ExtensionStroke(_) => false,
+        }
+    }
+
+    /// Whether the stroke is drawn with the color, as stroke color or as fill color of shapes.
+    /// Colors are compared with 8 bits per channel, the precision they are picked with
+    pub fn uses_color(&self, color: Color) -> bool {
+        let matches =
+            |other: Option<Color>| other.map_or(false, |other| other.to_u32() == color.to_u32());
+
+        match self {
+            Self::BrushStroke(brushstroke) => matches(brushstroke.stroke_color()),
+            Self::ShapeStroke(shapestroke) => {
+                matches(shapestroke.stroke_color()) || matches(shapestroke.fill_color())
+            }
+            Self::TextStroke(textstroke) => matches(Some(textstroke.style.color)),
+            Self::MathStroke(mathstroke) => matches(Some(mathstroke.color)),
+            Self::CheckboxStroke(checkboxstroke) => matches(Some(checkboxstroke.color)),
+            Self::VectorImage(_) | Self::BitmapImage(_) | Self::ExtensionStroke(_) => false,
+        }
+    }
+
+    /// Replaces every use of the color `from` with `to`, see `uses_color()`. Images and the strokes of plugins are left as they are.
+    /// Returns true if the stroke was modified and needs to be rerendered
+    pub fn replace_color(&mut self, from: Color, to: Color) -> bool {
+        let matches =
+            |other: Option<Color>| other.map_or(false, |other| other.to_u32() == from.to_u32());
+
+        match self {
+            Self::BrushStroke(brushstroke) => {
+                if matches(brushstroke.stroke_color()) {
+                    brushstroke.set_stroke_color(Some(to));
+                    true
+                } else {
+                    false
+                }
+            }
+            Self::ShapeStroke(shapestroke) => {
+                let mut modified = false;
+                if matches(shapestroke.stroke_color()) {
+                    shapestroke.set_stroke_color(Some(to));
+                    modified = true;
+                }
+                if matches(shapestroke.fill_color()) {
+                    modified |= shapestroke.set_fill_color(Some(to));
+                }
+                modified
+            }
+            Self::TextStroke(textstroke) => {
+                if matches(Some(textstroke.style.color)) {
+                    textstroke.set_color(to);
+                    true
+                } else {
+                    false
+                }
+            }
+            Self::MathStroke(mathstroke) => {
+                if matches(Some(mathstroke.color)) {
+                    mathstroke.color = to;
+                    true
+                } else {
+                    false
+                }
+            }
+            Self::CheckboxStroke(checkboxstroke) => {
+                if matches(Some(checkboxstroke.color)) {
+                    checkboxstroke.color = to;
+                    true
+                } else {
+                    false
+                }
+            }
+            Self::VectorImage(_) | Self::BitmapImage(_) | Self::ExtensionStroke(_) => false,
+        }
+    }
+
+    /// Gives the stroke a new random seed, so that clones of textured, sprayed and rough strokes don't look identical
+    pub fn reseed(&mut self) {
+        let seed = Some(rand_pcg::Pcg64::from_entropy().gen());
+
+        match self {
+            Self::BrushStroke(brushstroke) => brushstroke.set_seed(seed),
+            Self::ShapeStroke(shapestroke) => shapestroke.set_seed(seed),
+            Self::VectorImage(_)
+            | Self::BitmapImage(_)
+            | Self::TextStroke(_)
+            | Self::MathStroke(_)
+            | Self::CheckboxStroke(_)
+            | Self::ExtensionStroke(_) => {}
+        }
+    }
+
+    /// Whether the position hits the stroke. Strokes are tested against their path within the tolerance, images against their bounds
+    /// and texts, math expressions and checkboxes against their box
+    pub fn hittest(&self, pos: na::Vector2<f64>, tolerance: f64) -> bool {
+        match self {
+            Self::BrushStroke(brushstroke) => brushstroke.hittest(pos, tolerance),
+            Self::ShapeStroke(shapestroke) => shapestroke.hittest(pos, tolerance),
+            Self::VectorImage(vectorimage) => vectorimage
+                .bounds
+                .loosened(tolerance)
+                .contains_local_point(&na::Point2::from(pos)),
+            Self::BitmapImage(bitmapimage) => bitmapimage
+                .bounds
+                .loosened(tolerance)
+                .contains_local_point(&na::Point2::from(pos)),
+            Self::TextStroke(textstroke) => textstroke.hittest(pos, tolerance),
+            Self::MathStroke(mathstroke) => mathstroke.hittest(pos, tolerance),
+            Self::CheckboxStroke(checkboxstroke) => checkboxstroke.hittest(pos, tolerance),
+            Self::ExtensionStroke(extensionstroke) => extensionstroke.hittest(pos, tolerance),
+        }
+    }
+
+    /// Whether strokes of its type are tessellated to be drawn by the GPU
+    fn tessellated(&self, tessellated_strokes: &TessellatedStrokes) -> bool {
+        match self {
+            Self::BrushStroke(brushstroke) => match brushstroke.style {
+                BrushStrokeStyle::Marker { .. } => tessellated_strokes.marker,
+                BrushStrokeStyle::Solid { .. } => tessellated_strokes.solid,
+                BrushStrokeStyle::Textured { .. } => tessellated_strokes.textured,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// The tessellation of the stroke at the zoom, when strokes of its type are tessellated to be drawn by the GPU.
+    /// Zoomed far out it is the tessellation of the simplified stroke (see `render::DetailLevel`). None if it is rendered to images instead
+    pub fn gen_tessellation(
+        &self,
+        tessellated_strokes: &TessellatedStrokes,
+        zoom: f64,
+    ) -> Option<Tessellation> {
+        match self {
+            Self::BrushStroke(brushstroke) if self.tessellated(tessellated_strokes) => {
+                if DetailLevel::for_zoom(zoom) == DetailLevel::Simplified {
+                    if let Some(simplified) = brushstroke.simplified() {
+                        return simplified.gen_tessellation();
+                    }
+                }
+
+                brushstroke.gen_tessellation()
+            }
+            _ => None,
+        }
+    }
+
+    /// The tessellation of the last segment of the stroke at full detail, see `gen_tessellation()`
+    pub fn gen_tessellation_for_last_elems(
+        &self,
+        tessellated_strokes: &TessellatedStrokes,
+    ) -> Option<Tessellation> {
+        match self {
+            Self::BrushStroke(brushstroke) if self.tessellated(tessellated_strokes) => {
+                brushstroke.gen_tessellation_for_last_elems()
+            }
+            _ => None,
+        }
+    }
+
+    /// The points other positions snap to. These are the endpoints and midpoints of brush strokes and lines, and the vertices and edge midpoints of polylines.
+    /// Other shapes have the corners, edge midpoints and the center of their shape bounds, images and texts of their bounds
+    pub fn snap_points(&self) -> Vec<na::Vector2<f64>> {
+        match self {
+            Self::BrushStroke(brushstroke) => {
+                let elements = &brushstroke.elements;
+
+                match (elements.first(), elements.last()) {
+                    (Some(first), Some(last)) => vec![
+                        first.inputdata.pos(),
+                        elements[elements.len() / 2].inputdata.pos(),
+                        last.inputdata.pos(),
+                    ],
+                    _ => vec![],
+                }
+            }
+            Self::ShapeStroke(shapestroke) => match &shapestroke.shape {
+                Shape::Line(line) => vec![line.start, (line.start + line.end) * 0.5, line.end],
+                Shape::Polyline(polyline) => {
+                    let vertices = &polyline.vertices;
+                    let n_edges = if polyline.closed {
+                        vertices.len()
+                    } else {
+                        vertices.len().saturating_sub(1)
+                    };
+
+                    vertices
+                        .iter()
+                        .copied()
+                        .chain(
+                            (0..n_edges)
+                                .map(|i| (vertices[i] + vertices[(i + 1) % vertices.len()]) * 0.5),
+                        )
+                        .collect()
+                }
+                shape => bounds_snap_points(shape.bounds()),
+            },
+            Self::VectorImage(_)
+            | Self::BitmapImage(_)
+            | Self::TextStroke(_)
+            | Self::MathStroke(_)
+            | Self::CheckboxStroke(_)
+            | Self::ExtensionStroke(_) => bounds_snap_points(self.bounds()),
+        }
+    }
+
     pub fn from_xoppstroke(
         stroke: xoppformat::XoppStroke,
         offset: na::Vector2<f64>,
@@ -136,10 +521,23 @@ impl StrokeStyle {
             smooth_options.width = width;
         }
 
-        let brush = Brush {
-            style: BrushStyle::Solid,
-            smooth_options,
-            ..Brush::default()
+        // Highlighter strokes keep their look, everything else becomes a solid stroke
+        let brush = if let xoppformat::XoppTool::Highlighter = stroke.tool {
+            let mut highlighter_options = HighlighterOptions::default();
+            highlighter_options.width = smooth_options.width;
+            highlighter_options.stroke_color = smooth_options.stroke_color;
+
+            Brush {
+                style: BrushStyle::Highlighter,
+                highlighter_options,
+                ..Brush::default()
+            }
+        } else {
+            Brush {
+                style: BrushStyle::Solid,
+                smooth_options,
+                ..Brush::default()
+            }
         };
 
         let elements = stroke.coords.into_iter().map(|mut coords| {
@@ -161,6 +559,56 @@ impl StrokeStyle {
             ))
     }
 
+    /// Converts a ISF stroke, in the coordinate space of the given dpi
+    pub fn from_isfstroke(
+        stroke: isfformat::IsfStroke,
+        offset: na::Vector2<f64>,
+        dpi: f64,
+    ) -> Result<Self, anyhow::Error> {
+        let attributes = stroke.drawing_attributes;
+        let width = utils::convert_value_dpi(attributes.width, isfformat::IsfFile::DPI, dpi);
+        let color = Some(Color::from(attributes.color));
+
+        let brush = if attributes.is_highlighter {
+            let mut highlighter_options = HighlighterOptions::default();
+            highlighter_options.width = width;
+            highlighter_options.stroke_color = color;
+
+            Brush {
+                style: BrushStyle::Highlighter,
+                highlighter_options,
+                ..Brush::default()
+            }
+        } else {
+            let mut smooth_options = SmoothOptions::default();
+            smooth_options.width = width;
+            smooth_options.stroke_color = color;
+
+            Brush {
+                style: BrushStyle::Solid,
+                smooth_options,
+                ..Brush::default()
+            }
+        };
+
+        let pressures = stroke.pressures.unwrap_or_default();
+        let elements = stroke.coords.into_iter().enumerate().map(|(i, coords)| {
+            let pos = utils::convert_coord_dpi(coords, isfformat::IsfFile::DPI, dpi) + offset;
+            let pressure = pressures
+                .get(i)
+                .copied()
+                .unwrap_or(InputData::PRESSURE_DEFAULT);
+
+            Element::new(InputData::new(pos, pressure))
+        });
+
+        BrushStroke::new_w_elements(elements, &brush)
+            .map(StrokeStyle::BrushStroke)
+            .ok_or_else(|| {
+                anyhow::Error::msg("BrushStroke new_w_elements() failed in from_isfstroke()")
+            })
+    }
+
     pub fn from_xoppimage(
         image: xoppformat::XoppImage,
         offset: na::Vector2<f64>,
@@ -191,6 +639,26 @@ impl StrokeStyle {
         Ok(StrokeStyle::BitmapImage(bitmapimage))
     }
 
+    pub fn from_xopptext(
+        text: xoppformat::XoppText,
+        offset: na::Vector2<f64>,
+    ) -> Result<Self, anyhow::Error> {
+        let style = TextStyle {
+            font_family: text.font,
+            font_size: f64::from(text.size)
+                .clamp(TextStyle::FONT_SIZE_MIN, TextStyle::FONT_SIZE_MAX),
+            color: Color::from(text.color),
+            ..TextStyle::default()
+        };
+
+        Ok(StrokeStyle::TextStroke(TextStroke::new(
+            text.text,
+            na::vector![text.x, text.y] + offset,
+            TextStroke::MAX_WIDTH_DEFAULT,
+            style,
+        )))
+    }
+
     pub fn into_xopp(
         self,
         current_dpi: f64,
@@ -203,20 +671,45 @@ impl StrokeStyle {
                     return None;
                 }
 
-                let (width, color): (f64, XoppColor) = match brushstroke.style {
-                    // Return early if color is None
-                    BrushStrokeStyle::Marker { options } => {
-                        (options.width, options.stroke_color?.into())
-                    }
-                    BrushStrokeStyle::Solid { options } => {
-                        (options.width, options.stroke_color?.into())
-                    }
-                    BrushStrokeStyle::Textured { options } => {
-                        (options.width, options.stroke_color?.into())
-                    }
-                };
-
-                let tool = xoppformat::XoppTool::Pen;
+                let (width, color, tool): (f64, XoppColor, xoppformat::XoppTool) =
+                    match brushstroke.style {
+                        // Return early if color is None
+                        BrushStrokeStyle::Marker { options } => (
+                            options.width,
+                            options.stroke_color?.into(),
+                            xoppformat::XoppTool::Pen,
+                        ),
+                        BrushStrokeStyle::Solid { options } => (
+                            options.width,
+                            options.stroke_color?.into(),
+                            xoppformat::XoppTool::Pen,
+                        ),
+                        BrushStrokeStyle::Textured { options } => (
+                            options.width,
+                            options.stroke_color?.into(),
+                            xoppformat::XoppTool::Pen,
+                        ),
+                        BrushStrokeStyle::Spray { options } => (
+                            options.width,
+                            options.stroke_color?.into(),
+                            xoppformat::XoppTool::Pen,
+                        ),
+                        BrushStrokeStyle::Highlighter { options } => (
+                            options.width,
+                            options.stroke_color?.into(),
+                            xoppformat::XoppTool::Highlighter,
+                        ),
+                        BrushStrokeStyle::Calligraphy { options } => (
+                            options.width,
+                            options.stroke_color?.into(),
+                            xoppformat::XoppTool::Pen,
+                        ),
+                        BrushStrokeStyle::Hatched { options } => (
+                            options.width,
+                            options.stroke_color?.into(),
+                            xoppformat::XoppTool::Pen,
+                        ),
+                    };
 
                 // The first width element is the absolute width of the stroke
                 let stroke_width =
@@ -224,13 +717,15 @@ impl StrokeStyle {
 
                 let mut width_vec = vec![stroke_width];
 
-                // the rest are pressures between 0.0 and 1.0
-                let mut pressures = brushstroke
-                    .elements
-                    .iter()
-                    .map(|element| stroke_width * element.inputdata.pressure())
-                    .collect::<Vec<f64>>();
-                width_vec.append(&mut pressures);
+                // the rest are pressures between 0.0 and 1.0. Highlighter strokes have a constant width
+                if !matches!(tool, xoppformat::XoppTool::Highlighter) {
+                    let mut pressures = brushstroke
+                        .elements
+                        .iter()
+                        .map(|element| stroke_width * element.inputdata.pressure())
+                        .collect::<Vec<f64>>();
+                    width_vec.append(&mut pressures);
+                }
 
                 let coords = brushstroke
                     .elements
@@ -379,6 +874,159 @@ impl StrokeStyle {
                     },
                 ))
             }
+            StrokeStyle::TextStroke(textstroke) => {
+                // Xopp texts can't be transformed, so only the position of the upper left corner is exported
+                let pos = textstroke
+                    .transform
+                    .transform_point(na::point![0.0, 0.0])
+                    .coords;
+
+                Some(xoppformat::XoppStrokeStyle::XoppText(
+                    xoppformat::XoppText {
+                        font: textstroke.style.font_family,
+                        size: utils::convert_value_dpi(
+                            textstroke.style.font_size,
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        )
+                        .round() as u32,
+                        x: utils::convert_value_dpi(pos[0], current_dpi, xoppformat::XoppFile::DPI),
+                        y: utils::convert_value_dpi(pos[1], current_dpi, xoppformat::XoppFile::DPI),
+                        color: textstroke.style.color.into(),
+                        text: textstroke.text,
+                    },
+                ))
+            }
+            StrokeStyle::CheckboxStroke(checkboxstroke) => {
+                // Xopp has no checkboxes, so they are exported as texts beginning with a ballot box
+                let pos = checkboxstroke.pos();
+                let ballot_box = if checkboxstroke.checked {
+                    '\u{2611}'
+                } else {
+                    '\u{2610}'
+                };
+
+                Some(xoppformat::XoppStrokeStyle::XoppText(
+                    xoppformat::XoppText {
+                        font: String::from(CheckboxStroke::FONT_FAMILY),
+                        size: utils::convert_value_dpi(
+                            checkboxstroke.font_size,
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        )
+                        .round() as u32,
+                        x: utils::convert_value_dpi(pos[0], current_dpi, xoppformat::XoppFile::DPI),
+                        y: utils::convert_value_dpi(pos[1], current_dpi, xoppformat::XoppFile::DPI),
+                        color: checkboxstroke.color.into(),
+                        text: format!("{} {}", ballot_box, checkboxstroke.text),
+                    },
+                ))
+            }
+            StrokeStyle::MathStroke(mathstroke) => {
+                // Xopp has no math expressions, so they are exported as images
+                let math_image = render::concat_images(
+                    mathstroke.gen_images(1.0, renderer).ok()?,
+                    mathstroke.bounds(),
+                    1.0,
+                )
+                .ok()?;
+                let image_bytes =
+                    render::image_into_encoded_bytes(math_image, image::ImageOutputFormat::Png)
+                        .map_err(|e| {
+                            log::error!(
+                                "image_to_bytes() failed in to_xopp() for mathstroke with Err {}",
+                                e
+                            )
+                        })
+                        .ok()?;
+
+                Some(xoppformat::XoppStrokeStyle::XoppImage(
+                    xoppformat::XoppImage {
+                        left: utils::convert_value_dpi(
+                            mathstroke.bounds.mins[0],
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        ),
+                        top: utils::convert_value_dpi(
+                            mathstroke.bounds.mins[1],
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        ),
+                        right: utils::convert_value_dpi(
+                            mathstroke.bounds.maxs[0],
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        ),
+                        bottom: utils::convert_value_dpi(
+                            mathstroke.bounds.maxs[1],
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        ),
+                        data: base64::encode(&image_bytes),
+                    },
+                ))
+            }
+            StrokeStyle::ExtensionStroke(extensionstroke) => {
+                // Xopp doesn't know the strokes of plugins, so they are exported as images
+                let bounds = extensionstroke.bounds();
+                let extension_image = render::concat_images(
+                    extensionstroke.gen_images(1.0, renderer).ok()?,
+                    bounds,
+                    1.0,
+                )
+                .ok()?;
+                let image_bytes = render::image_into_encoded_bytes(
+                    extension_image,
+                    image::ImageOutputFormat::Png,
+                )
+                .map_err(|e| {
+                    log::error!(
+                        "image_to_bytes() failed in to_xopp() for extensionstroke with Err {}",
+                        e
+                    )
+                })
+                .ok()?;
+
+                Some(xoppformat::XoppStrokeStyle::XoppImage(
+                    xoppformat::XoppImage {
+                        left: utils::convert_value_dpi(
+                            bounds.mins[0],
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        ),
+                        top: utils::convert_value_dpi(
+                            bounds.mins[1],
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        ),
+                        right: utils::convert_value_dpi(
+                            bounds.maxs[0],
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        ),
+                        bottom: utils::convert_value_dpi(
+                            bounds.maxs[1],
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        ),
+                        data: base64::encode(&image_bytes),
+                    },
+                ))
+            }
         }
     }
 }
+
+/// The corners, edge midpoints and the center of the bounds
+fn bounds_snap_points(bounds: AABB) -> Vec<na::Vector2<f64>> {
+    let (mins, center, maxs) = (
+        bounds.mins.coords,
+        bounds.center().coords,
+        bounds.maxs.coords,
+    );
+
+    [mins[0], center[0], maxs[0]]
+        .iter()
+        .flat_map(|&x| [mins[1], center[1], maxs[1]].map(|y| na::vector![x, y]))
+        .collect()
+}
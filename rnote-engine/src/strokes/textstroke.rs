@@ -0,0 +1,767 @@
+use crate::compose;
+use crate::compose::color::Color;
+use crate::compose::geometry::AABBHelpers;
+use crate::compose::transformable::{Transform, Transformable};
+use crate::drawbehaviour::DrawBehaviour;
+use crate::render;
+
+use std::ops::Range;
+#[cfg(feature = "cairo")]
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "cairo")]
+use anyhow::Context;
+use p2d::bounding_volume::AABB;
+#[cfg(feature = "cairo")]
+use pango::prelude::*;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "cairo")]
+use svg::node;
+use svg::node::element;
+
+#[cfg(not(feature = "cairo"))]
+use crate::compose::svgtext;
+
+/// The alignment of the text lines in the text box
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "ui", derive(glib::Enum))]
+#[repr(u32)]
+#[cfg_attr(feature = "ui", enum_type(name = "TextAlignment"))]
+#[serde(rename = "text_alignment")]
+pub enum TextAlignment {
+    #[cfg_attr(feature = "ui", enum_value(name = "Start", nick = "start"))]
+    #[serde(rename = "start")]
+    Start = 0,
+    #[cfg_attr(feature = "ui", enum_value(name = "Center", nick = "center"))]
+    #[serde(rename = "center")]
+    Center,
+    #[cfg_attr(feature = "ui", enum_value(name = "End", nick = "end"))]
+    #[serde(rename = "end")]
+    End,
+    /// Justified lines, filling the width of the text box
+    #[cfg_attr(feature = "ui", enum_value(name = "Fill", nick = "fill"))]
+    #[serde(rename = "fill")]
+    Fill,
+}
+
+impl Default for TextAlignment {
+    fn default() -> Self {
+        Self::Start
+    }
+}
+
+#[cfg(feature = "cairo")]
+impl From<TextAlignment> for pango::Alignment {
+    fn from(alignment: TextAlignment) -> Self {
+        match alignment {
+            // Justified lines are aligned to the start, the justification is set separately on the layout
+            TextAlignment::Start | TextAlignment::Fill => pango::Alignment::Left,
+            TextAlignment::Center => pango::Alignment::Center,
+            TextAlignment::End => pango::Alignment::Right,
+        }
+    }
+}
+
+/// The style of a text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "text_style")]
+pub struct TextStyle {
+    #[serde(rename = "font_family")]
+    pub font_family: String,
+    /// The font size, in the coordinate space of the text
+    #[serde(rename = "font_size")]
+    pub font_size: f64,
+    #[serde(rename = "color")]
+    pub color: Color,
+    #[serde(rename = "bold")]
+    pub bold: bool,
+    #[serde(rename = "italic")]
+    pub italic: bool,
+    #[serde(rename = "underline")]
+    pub underline: bool,
+    #[serde(rename = "alignment")]
+    pub alignment: TextAlignment,
+    /// The level of the heading the text is marked as, beginning with 1 for the top level. None for body text ( see `sheet::outline` )
+    #[serde(rename = "heading_level")]
+    pub heading_level: Option<u32>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font_family: String::from(Self::FONT_FAMILY_DEFAULT),
+            font_size: Self::FONT_SIZE_DEFAULT,
+            color: Color::BLACK,
+            bold: false,
+            italic: false,
+            underline: false,
+            alignment: TextAlignment::default(),
+            heading_level: None,
+        }
+    }
+}
+
+impl TextStyle {
+    /// The default font family
+    pub const FONT_FAMILY_DEFAULT: &'static str = "Sans";
+    /// The min font size
+    pub const FONT_SIZE_MIN: f64 = 1.0;
+    /// The max font size
+    pub const FONT_SIZE_MAX: f64 = 512.0;
+    /// The default font size
+    pub const FONT_SIZE_DEFAULT: f64 = 32.0;
+    /// The top heading level
+    pub const HEADING_LEVEL_MIN: u32 = 1;
+    /// The lowest heading level
+    pub const HEADING_LEVEL_MAX: u32 = 6;
+
+    #[cfg(feature = "cairo")]
+    pub fn font_description(&self) -> pango::FontDescription {
+        let mut font_description = pango::FontDescription::new();
+        font_description.set_family(&self.font_family);
+        font_description.set_absolute_size(self.font_size * f64::from(pango::SCALE));
+        font_description.set_weight(if self.bold {
+            pango::Weight::Bold
+        } else {
+            pango::Weight::Normal
+        });
+        font_description.set_style(if self.italic {
+            pango::Style::Italic
+        } else {
+            pango::Style::Normal
+        });
+
+        font_description
+    }
+}
+
+/// A text box placed on the sheet. The text stays editable after placement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "textstroke")]
+pub struct TextStroke {
+    #[serde(rename = "text")]
+    pub text: String,
+    #[serde(rename = "style")]
+    pub style: TextStyle,
+    /// The width the text is wrapped at, in the coordinate space of the text
+    #[serde(rename = "max_width")]
+    pub max_width: f64,
+    /// The transform of the text box. Its origin is the upper left corner of the text
+    #[serde(rename = "transform")]
+    pub transform: Transform,
+    #[serde(rename = "bounds")]
+    pub bounds: AABB,
+}
+
+impl Default for TextStroke {
+    fn default() -> Self {
+        Self {
+            text: String::default(),
+            style: TextStyle::default(),
+            max_width: Self::MAX_WIDTH_DEFAULT,
+            transform: Transform::default(),
+            bounds: AABB::new_zero(),
+        }
+    }
+}
+
+impl DrawBehaviour for TextStroke {
+    fn bounds(&self) -> AABB {
+        self.bounds
+    }
+
+    fn set_bounds(&mut self, bounds: AABB) {
+        self.bounds = bounds;
+    }
+
+    fn gen_bounds(&self) -> Option<AABB> {
+        let size = self.local_size().ok()?;
+
+        let corners = [
+            na::point![0.0, 0.0],
+            na::point![size[0], 0.0],
+            na::point![0.0, size[1]],
+            na::point![size[0], size[1]],
+        ]
+        .map(|corner| self.transform.transform_point(corner));
+
+        let mut bounds = AABB::new(corners[0], corners[0]);
+        corners.iter().skip(1).for_each(|&corner| {
+            bounds.take_point(corner);
+        });
+
+        Some(bounds)
+    }
+
+    #[cfg(feature = "cairo")]
+    fn gen_svgs(&self, offset: na::Vector2<f64>) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let size = self.local_size()?;
+        let mut transform = self.transform.clone();
+        transform.append_translation_mut(offset);
+
+        // The text is drawn with pango onto a cairo svg surface, which converts the glyphs to paths
+        let svg_data = render::cairo_drawing_to_svg_data(size, |cx| self.draw_to_cairo(cx))
+            .context("cairo_drawing_to_svg_data() failed in textstroke gen_svgs()")?;
+
+        let svg_root = element::SVG::new()
+            .set("x", 0.0)
+            .set("y", 0.0)
+            .set("width", size[0])
+            .set("height", size[1])
+            .set(
+                "viewBox",
+                format!("{:.3} {:.3} {:.3} {:.3}", 0.0, 0.0, size[0], size[1]),
+            )
+            .set("preserveAspectRatio", "none")
+            .add(node::Text::new(svg_data));
+
+        let group = element::Group::new()
+            .set("transform", transform.to_svg_transform_attr_str())
+            .add(svg_root);
+
+        Ok(vec![render::Svg {
+            svg_data: compose::svg_node_to_string(&group)?,
+            bounds: self.bounds.translate(offset),
+        }])
+    }
+
+    /// Without the `cairo` feature the lines are svg text elements, which are drawn with the fonts of the svg renderer
+    #[cfg(not(feature = "cairo"))]
+    fn gen_svgs(&self, offset: na::Vector2<f64>) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let mut transform = self.transform.clone();
+        transform.append_translation_mut(offset);
+        let font = svgtext::TextFont {
+            family: self.style.font_family.clone(),
+            size: self.style.font_size,
+            bold: self.style.bold,
+            italic: self.style.italic,
+            underline: self.style.underline,
+            strikethrough: false,
+        };
+
+        let group = self.gen_lines().into_iter().fold(
+            element::Group::new().set("transform", transform.to_svg_transform_attr_str()),
+            |group, line| {
+                group.add(svgtext::compose_line(
+                    &self.text[line.range],
+                    na::vector![line.x, line.y + svgtext::BASELINE * self.style.font_size],
+                    &font,
+                    self.style.color,
+                ))
+            },
+        );
+
+        Ok(vec![render::Svg {
+            svg_data: compose::svg_node_to_string(&group)?,
+            bounds: self.bounds.translate(offset),
+        }])
+    }
+
+    /// The text is rendered directly with pango, without the svg roundtrip
+    #[cfg(feature = "cairo")]
+    fn gen_images(
+        &self,
+        zoom: f64,
+        _renderer: Arc<RwLock<render::Renderer>>,
+    ) -> Result<Vec<render::Image>, anyhow::Error> {
+        let image = render::cairo_drawing_to_image(self.bounds, zoom, |cx| {
+            cx.transform(self.transform.to_cairo_matrix());
+            self.draw_to_cairo(cx)
+        })
+        .context("cairo_drawing_to_image() failed in textstroke gen_images()")?;
+
+        Ok(vec![image])
+    }
+
+    /// The text is drawn with pango, so it stays selectable text on pdf surfaces
+    #[cfg(feature = "cairo")]
+    fn draw_to_cairo_vector(&self, cx: &cairo::Context) -> Result<(), anyhow::Error> {
+        cx.save()?;
+        cx.transform(self.transform.to_cairo_matrix());
+        self.draw_to_cairo(cx)?;
+        cx.restore()?;
+
+        Ok(())
+    }
+}
+
+impl Transformable for TextStroke {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.transform.append_translation_mut(offset);
+        self.update_geometry();
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        self.transform.append_rotation_wrt_point_mut(angle, center);
+        self.update_geometry();
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.transform.append_scale_mut(scale);
+        self.update_geometry();
+    }
+}
+
+impl TextStroke {
+    /// The default width the text is wrapped at
+    pub const MAX_WIDTH_DEFAULT: f64 = 600.0;
+    /// The min width the text is wrapped at
+    pub const MAX_WIDTH_MIN: f64 = 20.0;
+
+    /// A new text with its upper left corner at the position
+    pub fn new(text: String, pos: na::Vector2<f64>, max_width: f64, style: TextStyle) -> Self {
+        let mut textstroke = Self {
+            text,
+            style,
+            max_width: max_width.max(Self::MAX_WIDTH_MIN),
+            transform: Transform::new_w_isometry(na::Isometry2::new(pos, 0.0)),
+            bounds: AABB::new_zero(),
+        };
+        textstroke.update_geometry();
+
+        textstroke
+    }
+
+    pub fn update_geometry(&mut self) {
+        if let Some(new_bounds) = self.gen_bounds() {
+            self.set_bounds(new_bounds);
+        }
+    }
+
+    /// Sets up the layout with the text and its style
+    #[cfg(feature = "cairo")]
+    fn setup_layout(&self, layout: &pango::Layout) {
+        layout.set_font_description(Some(&self.style.font_description()));
+        layout.set_width((self.max_width * f64::from(pango::SCALE)).round() as i32);
+        layout.set_wrap(pango::WrapMode::WordChar);
+        layout.set_alignment(self.style.alignment.into());
+        layout.set_justify(self.style.alignment == TextAlignment::Fill);
+
+        let attributes = pango::AttrList::new();
+        if self.style.underline {
+            attributes.insert(pango::AttrInt::new_underline(pango::Underline::Single));
+        }
+        layout.set_attributes(Some(&attributes));
+
+        layout.set_text(&self.text);
+    }
+
+    /// Generates the layout of the text, in the coordinate space of the text
+    #[cfg(feature = "cairo")]
+    pub fn gen_layout(&self) -> Result<pango::Layout, anyhow::Error> {
+        let fontmap = pangocairo::FontMap::default()
+            .ok_or_else(|| anyhow::anyhow!("getting the default pangocairo::FontMap failed"))?;
+        let context = fontmap
+            .create_context()
+            .ok_or_else(|| anyhow::anyhow!("create_context() for the font map failed"))?;
+
+        let layout = pango::Layout::new(&context);
+        self.setup_layout(&layout);
+
+        Ok(layout)
+    }
+
+    /// The size of the text box, in the coordinate space of the text
+    #[cfg(feature = "cairo")]
+    pub fn local_size(&self) -> Result<na::Vector2<f64>, anyhow::Error> {
+        let (_ink_rect, logical_rect) = self.gen_layout()?.extents();
+
+        Ok(na::vector![
+            self.max_width,
+            f64::from(logical_rect.height()) / f64::from(pango::SCALE)
+        ])
+    }
+
+    /// The size of the text box, in the coordinate space of the text
+    #[cfg(not(feature = "cairo"))]
+    pub fn local_size(&self) -> Result<na::Vector2<f64>, anyhow::Error> {
+        Ok(na::vector![
+            self.max_width,
+            self.gen_lines().len() as f64 * self.line_height()
+        ])
+    }
+
+    #[cfg(not(feature = "cairo"))]
+    fn line_height(&self) -> f64 {
+        svgtext::LINE_HEIGHT * self.style.font_size
+    }
+
+    /// Lays out the lines of the text with the approximated font metrics of `svgtext`, in the coordinate space of the text
+    #[cfg(not(feature = "cairo"))]
+    fn gen_lines(&self) -> Vec<TextLine> {
+        svgtext::wrap_lines(
+            &self.text,
+            self.style.font_size,
+            self.style.bold,
+            self.max_width,
+        )
+        .into_iter()
+        .enumerate()
+        .map(|(line_index, range)| {
+            let width = svgtext::text_width(
+                self.text[range.clone()].trim_end(),
+                self.style.font_size,
+                self.style.bold,
+            );
+            let x = match self.style.alignment {
+                TextAlignment::Start | TextAlignment::Fill => 0.0,
+                TextAlignment::Center => (self.max_width - width) * 0.5,
+                TextAlignment::End => self.max_width - width,
+            };
+
+            TextLine {
+                range,
+                x,
+                y: line_index as f64 * self.line_height(),
+            }
+        })
+        .collect()
+    }
+
+    /// The rect of the char at the byte index as x, y, width and height, in the coordinate space of the text. Zero wide at the end of a line
+    #[cfg(not(feature = "cairo"))]
+    fn index_to_rect(&self, lines: &[TextLine], index: usize) -> [f64; 4] {
+        // Indices at the end of a wrapped line are at the start of the next line
+        let line = lines
+            .iter()
+            .rev()
+            .find(|line| line.range.start <= index)
+            .unwrap_or(&lines[0]);
+        let index = index.min(line.range.end);
+        let width = self.text[index..line.range.end]
+            .chars()
+            .next()
+            .map_or(0.0, |c| {
+                svgtext::char_advance(c, self.style.bold) * self.style.font_size
+            });
+
+        [
+            line.x
+                + svgtext::text_width(
+                    &self.text[line.range.start..index],
+                    self.style.font_size,
+                    self.style.bold,
+                ),
+            line.y,
+            width,
+            self.line_height(),
+        ]
+    }
+
+    /// Draws the text onto the cairo context, in the coordinate space of the text
+    #[cfg(feature = "cairo")]
+    fn draw_to_cairo(&self, cx: &cairo::Context) -> Result<(), anyhow::Error> {
+        let layout = pangocairo::create_layout(cx)
+            .ok_or_else(|| anyhow::anyhow!("pangocairo::create_layout() failed"))?;
+        self.setup_layout(&layout);
+
+        cx.set_source_rgba(
+            self.style.color.r,
+            self.style.color.g,
+            self.style.color.b,
+            self.style.color.a,
+        );
+        pangocairo::show_layout(cx, &layout);
+
+        Ok(())
+    }
+
+    /// Converts a position in the coordinate space of the sheet to the coordinate space of the text
+    fn pos_to_local(&self, pos: na::Vector2<f64>) -> Option<na::Vector2<f64>> {
+        Some((self.transform.transform.try_inverse()? * na::Point2::from(pos)).coords)
+    }
+
+    /// The font size, in the coordinate space of the sheet. Differs from the font size of the style when the text was scaled
+    pub fn font_size_on_sheet(&self) -> f64 {
+        let matrix = self.transform.transform.matrix();
+        let scale = (matrix[(0, 0)] * matrix[(1, 1)] - matrix[(0, 1)] * matrix[(1, 0)])
+            .abs()
+            .sqrt();
+
+        self.style.font_size * scale
+    }
+
+    /// The position of the upper left corner of the text, in the coordinate space of the sheet
+    pub fn pos(&self) -> na::Vector2<f64> {
+        self.transform.transform_point(na::Point2::origin()).coords
+    }
+
+    /// Whether the position hits the text box
+    pub fn hittest(&self, pos: na::Vector2<f64>, tolerance: f64) -> bool {
+        match (self.pos_to_local(pos), self.local_size()) {
+            (Some(local_pos), Ok(size)) => {
+                local_pos[0] >= -tolerance
+                    && local_pos[0] <= size[0] + tolerance
+                    && local_pos[1] >= -tolerance
+                    && local_pos[1] <= size[1] + tolerance
+            }
+            _ => false,
+        }
+    }
+
+    /// The byte index of the cursor position nearest to the position
+    #[cfg(feature = "cairo")]
+    pub fn cursor_index_at_pos(&self, pos: na::Vector2<f64>) -> usize {
+        let (local_pos, layout) = match (self.pos_to_local(pos), self.gen_layout()) {
+            (Some(local_pos), Ok(layout)) => (local_pos, layout),
+            _ => return self.text.len(),
+        };
+
+        let (_inside, index, trailing) = layout.xy_to_index(
+            (local_pos[0] * f64::from(pango::SCALE)).round() as i32,
+            (local_pos[1] * f64::from(pango::SCALE)).round() as i32,
+        );
+        let mut index = (index.max(0) as usize).min(self.text.len());
+        // trailing is the number of chars the position is behind the start of the indexed grapheme
+        for _ in 0..trailing {
+            index = self.next_cursor_index(index);
+        }
+
+        index
+    }
+
+    /// The byte index of the cursor position nearest to the position
+    #[cfg(not(feature = "cairo"))]
+    pub fn cursor_index_at_pos(&self, pos: na::Vector2<f64>) -> usize {
+        let local_pos = match self.pos_to_local(pos) {
+            Some(local_pos) => local_pos,
+            None => return self.text.len(),
+        };
+        let lines = self.gen_lines();
+        let line_index = (local_pos[1] / self.line_height())
+            .floor()
+            .clamp(0.0, (lines.len() - 1) as f64) as usize;
+        let line = &lines[line_index];
+
+        let mut x = line.x;
+        for (index, c) in self.text[line.range.clone()].char_indices() {
+            let advance = svgtext::char_advance(c, self.style.bold) * self.style.font_size;
+            if local_pos[0] < x + advance * 0.5 {
+                return line.range.start + index;
+            }
+            x += advance;
+        }
+
+        line.range.end
+    }
+
+    /// The start and end of the cursor line at the byte index, in the coordinate space of the sheet
+    #[cfg(feature = "cairo")]
+    pub fn cursor_line(
+        &self,
+        index: usize,
+    ) -> Result<(na::Vector2<f64>, na::Vector2<f64>), anyhow::Error> {
+        let layout = self.gen_layout()?;
+        let rect = layout.index_to_pos(index.min(self.text.len()) as i32);
+
+        let start = na::point![
+            f64::from(rect.x()) / f64::from(pango::SCALE),
+            f64::from(rect.y()) / f64::from(pango::SCALE)
+        ];
+        let end = start + na::vector![0.0, f64::from(rect.height()) / f64::from(pango::SCALE)];
+
+        Ok((
+            self.transform.transform_point(start).coords,
+            self.transform.transform_point(end).coords,
+        ))
+    }
+
+    /// The start and end of the cursor line at the byte index, in the coordinate space of the sheet
+    #[cfg(not(feature = "cairo"))]
+    pub fn cursor_line(
+        &self,
+        index: usize,
+    ) -> Result<(na::Vector2<f64>, na::Vector2<f64>), anyhow::Error> {
+        let [x, y, _width, height] =
+            self.index_to_rect(&self.gen_lines(), index.min(self.text.len()));
+
+        Ok((
+            self.transform.transform_point(na::point![x, y]).coords,
+            self.transform
+                .transform_point(na::point![x, y + height])
+                .coords,
+        ))
+    }
+
+    /// The bounds of the text in the byte range, in the coordinate space of the sheet
+    #[cfg(feature = "cairo")]
+    pub fn range_bounds(&self, range: Range<usize>) -> Option<AABB> {
+        let layout = self.gen_layout().ok()?;
+        let range = range.start.min(self.text.len())..range.end.min(self.text.len());
+
+        let mut bounds: Option<AABB> = None;
+        for (index, _) in self.text[range.clone()].char_indices() {
+            let rect = layout.index_to_pos((range.start + index) as i32);
+            let (x, y, width, height) = (
+                f64::from(rect.x()) / f64::from(pango::SCALE),
+                f64::from(rect.y()) / f64::from(pango::SCALE),
+                f64::from(rect.width()) / f64::from(pango::SCALE),
+                f64::from(rect.height()) / f64::from(pango::SCALE),
+            );
+
+            // The width is negative for right-to-left text
+            for corner in [
+                na::point![x, y],
+                na::point![x + width, y],
+                na::point![x, y + height],
+                na::point![x + width, y + height],
+            ] {
+                let corner = self.transform.transform_point(corner);
+                match bounds.as_mut() {
+                    Some(bounds) => bounds.take_point(corner),
+                    None => bounds = Some(AABB::new(corner, corner)),
+                }
+            }
+        }
+
+        bounds
+    }
+
+    /// The bounds of the text in the byte range, in the coordinate space of the sheet
+    #[cfg(not(feature = "cairo"))]
+    pub fn range_bounds(&self, range: Range<usize>) -> Option<AABB> {
+        let lines = self.gen_lines();
+        let range = range.start.min(self.text.len())..range.end.min(self.text.len());
+
+        let mut bounds: Option<AABB> = None;
+        for (index, _) in self.text[range.clone()].char_indices() {
+            let [x, y, width, height] = self.index_to_rect(&lines, range.start + index);
+
+            for corner in [
+                na::point![x, y],
+                na::point![x + width, y],
+                na::point![x, y + height],
+                na::point![x + width, y + height],
+            ] {
+                let corner = self.transform.transform_point(corner);
+                match bounds.as_mut() {
+                    Some(bounds) => bounds.take_point(corner),
+                    None => bounds = Some(AABB::new(corner, corner)),
+                }
+            }
+        }
+
+        bounds
+    }
+
+    /// The byte ranges of the non-overlapping occurrences of the query in the text
+    pub fn find_text(&self, query: &str, case_sensitive: bool) -> Vec<Range<usize>> {
+        if query.is_empty() {
+            return vec![];
+        }
+        let chars_eq = |first: char, second: char| {
+            if case_sensitive {
+                first == second
+            } else {
+                first.to_lowercase().eq(second.to_lowercase())
+            }
+        };
+
+        let mut matches = vec![];
+        let mut next_start = 0;
+        for (start, _) in self.text.char_indices() {
+            if start < next_start {
+                continue;
+            }
+
+            let mut text_chars = self.text[start..].char_indices();
+            let mut end = start;
+            let matched = query.chars().all(|query_char| match text_chars.next() {
+                Some((index, text_char)) if chars_eq(text_char, query_char) => {
+                    end = start + index + text_char.len_utf8();
+                    true
+                }
+                _ => false,
+            });
+
+            if matched {
+                matches.push(start..end);
+                next_start = end;
+            }
+        }
+
+        matches
+    }
+
+    /// The byte index of the char boundary before the index
+    pub fn prev_cursor_index(&self, index: usize) -> usize {
+        self.text[..index.min(self.text.len())]
+            .char_indices()
+            .last()
+            .map_or(0, |(prev, _)| prev)
+    }
+
+    /// The byte index of the char boundary after the index
+    pub fn next_cursor_index(&self, index: usize) -> usize {
+        let index = index.min(self.text.len());
+
+        self.text[index..]
+            .chars()
+            .next()
+            .map_or(index, |c| index + c.len_utf8())
+    }
+
+    /// The byte index of the start of the line the index is in
+    pub fn line_start_index(&self, index: usize) -> usize {
+        self.text[..index.min(self.text.len())]
+            .rfind('\n')
+            .map_or(0, |newline| newline + 1)
+    }
+
+    /// The byte index of the end of the line the index is in
+    pub fn line_end_index(&self, index: usize) -> usize {
+        let index = index.min(self.text.len());
+
+        self.text[index..]
+            .find('\n')
+            .map_or(self.text.len(), |newline| index + newline)
+    }
+
+    /// Inserts the text at the byte index. Returns the byte index after the inserted text
+    pub fn insert_text(&mut self, index: usize, text: &str) -> usize {
+        let index = index.min(self.text.len());
+        self.text.insert_str(index, text);
+        self.update_geometry();
+
+        index + text.len()
+    }
+
+    /// Removes the char before the byte index. Returns the byte index of the removed char
+    pub fn remove_prev_char(&mut self, index: usize) -> usize {
+        let prev = self.prev_cursor_index(index);
+        self.text
+            .replace_range(prev..index.min(self.text.len()), "");
+        self.update_geometry();
+
+        prev
+    }
+
+    /// Removes the char after the byte index
+    pub fn remove_next_char(&mut self, index: usize) {
+        let index = index.min(self.text.len());
+        let next = self.next_cursor_index(index);
+        self.text.replace_range(index..next, "");
+        self.update_geometry();
+    }
+
+    pub fn set_style(&mut self, style: TextStyle) {
+        self.style = style;
+        self.update_geometry();
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.style.color = color;
+    }
+}
+
+/// A line of the text laid out without pango, see `TextStroke::gen_lines()`
+#[cfg(not(feature = "cairo"))]
+#[derive(Debug, Clone)]
+struct TextLine {
+    /// The byte range of the line in the text
+    range: Range<usize>,
+    /// The x of the start of the line, depending on the alignment
+    x: f64,
+    /// The y of the top of the line
+    y: f64,
+}
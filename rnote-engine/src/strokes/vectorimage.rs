@@ -7,17 +7,20 @@ use crate::compose::transformable::{Transform, Transformable};
 use crate::drawbehaviour::DrawBehaviour;
 use crate::render;
 use crate::render::Renderer;
+#[cfg(feature = "ui")]
+use crate::strokes::pdfimport::PdfPagePlacement;
 
 use anyhow::Context;
 use p2d::bounding_volume::AABB;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use svg::node::{self, element};
+use usvg::NodeExt;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "vectorimage")]
 pub struct VectorImage {
-    #[serde(rename = "svg_data")]
+    #[serde(rename = "svg_data", with = "crate::sheet::container::asset_text")]
     pub svg_data: String,
     #[serde(rename = "intrinsic_size")]
     pub intrinsic_size: na::Vector2<f64>,
@@ -157,96 +160,169 @@ impl VectorImage {
         Ok(vector_image)
     }
 
-    pub fn import_from_pdf_bytes(
-        to_be_read: &[u8],
+    /// Imports the svg as separate images for each of its elements, so they can be selected and transformed on their own.
+    /// Groups with opacity, clip paths, masks or filters are kept together, because their effect spans all of their children.
+    /// Falls back to a single image if the svg has no elements that could be split
+    pub fn import_from_svg_data_split(
+        svg_data: &str,
         pos: na::Vector2<f64>,
-        page_width: Option<i32>,
         renderer: Arc<RwLock<Renderer>>,
     ) -> Result<Vec<Self>, anyhow::Error> {
-        let doc = poppler::Document::from_data(to_be_read, None)?;
-
-        let mut images = Vec::new();
-
-        for i in 0..doc.n_pages() {
-            if let Some(page) = doc.page(i) {
-                let intrinsic_size = page.size();
-
-                let (width, height, _zoom) = if let Some(page_width) = page_width {
-                    let zoom = f64::from(page_width) / intrinsic_size.0;
-
-                    (f64::from(page_width), (intrinsic_size.1 * zoom), zoom)
-                } else {
-                    (intrinsic_size.0, intrinsic_size.1, 1.0)
-                };
-
-                let x = pos[0];
-                let y = pos[1] + f64::from(i) * (height + f64::from(Self::OFFSET_Y_DEFAULT) / 2.0);
-
-                let svg_stream: Vec<u8> = vec![];
-
-                let surface =
-                    cairo::SvgSurface::for_stream(intrinsic_size.0, intrinsic_size.1, svg_stream)
-                        .map_err(|e| {
-                        anyhow::anyhow!(
-                            "create SvgSurface with dimensions ({}, {}) failed in vectorimage import_from_pdf_bytes with Err {}",
-                            intrinsic_size.0,
-                            intrinsic_size.1,
-                            e
-                        )
-                    })?;
-
-                {
-                    let cx = cairo::Context::new(&surface).map_err(|e| {
-                        anyhow::anyhow!(
-                            "new cairo::Context failed in vectorimage import_from_pdf_bytes() with Err {}",
-                            e
-                        )
-                    })?;
-
-                    // Set margin to white
-                    cx.set_source_rgba(1.0, 1.0, 1.0, 1.0);
-                    cx.paint()?;
-
-                    page.render(&cx);
-
-                    // Draw outline around page
-                    cx.set_source_rgba(0.7, 0.5, 0.5, 1.0);
-
-                    let line_width = 1.0;
-                    cx.set_line_width(line_width);
-                    cx.rectangle(
-                        line_width / 2.0,
-                        line_width / 2.0,
-                        intrinsic_size.0 - line_width,
-                        intrinsic_size.1 - line_width,
-                    );
-                    cx.stroke()?;
-                }
-                let svg_data = match surface.finish_output_stream() {
-                    Ok(file_content) => match file_content.downcast::<Vec<u8>>() {
-                        Ok(file_content) => *file_content,
-                        Err(_) => {
-                            log::error!("file_content.downcast() in VectorImage::import_from_pdf_bytes() failed");
-                            continue;
-                        }
+        let rtree =
+            usvg::Tree::from_str(svg_data, &renderer.read().unwrap().usvg_options.to_ref())?;
+        let xml_options = renderer.read().unwrap().usvg_xml_options.clone();
+
+        let (size, view_box) = {
+            let svg_node = rtree.svg_node();
+            (svg_node.size, svg_node.view_box)
+        };
+        // The scale from the user space of the svg to its intrinsic size
+        let scale = na::vector![
+            size.width() / view_box.rect.width(),
+            size.height() / view_box.rect.height()
+        ];
+
+        let mut elements = vec![];
+        collect_split_elements(&rtree.root(), &mut elements);
+
+        let images = elements
+            .into_iter()
+            .filter_map(|mut element| {
+                let bbox = element.calculate_bbox()?;
+                // The bounding box doesn't include the strokes
+                let margin = max_stroke_width(&element) * 0.5;
+                let (x, y, width, height) = (
+                    bbox.x() - margin,
+                    bbox.y() - margin,
+                    bbox.width() + margin * 2.0,
+                    bbox.height() + margin * 2.0,
+                );
+
+                let element_tree = usvg::Tree::create(usvg::Svg {
+                    size: usvg::Size::new(width, height)?,
+                    view_box: usvg::ViewBox {
+                        rect: usvg::Rect::new(x, y, width, height)?,
+                        aspect: usvg::AspectRatio::default(),
                     },
-                    Err(e) => {
-                        log::error!("surface.finish_output_stream() in VectorImage::import_from_pdf_bytes() failed with Err {}", e);
-                        continue;
-                    }
-                };
-                let svg_data = String::from_utf8(svg_data)?;
-
-                images.push(Self::import_from_svg_data(
-                    svg_data.as_str(),
-                    na::vector![x, y],
-                    Some(na::vector![width, height]),
+                });
+                // Gradients, patterns and clip paths are referenced from the defs
+                for mut def in rtree.defs().children() {
+                    element_tree.defs().append(def.make_deep_copy());
+                }
+
+                // The element keeps the transforms of its parent groups
+                let parent_transform = element
+                    .parent()
+                    .map(|parent| parent.abs_transform())
+                    .unwrap_or_default();
+                let mut group = element_tree
+                    .root()
+                    .append_kind(usvg::NodeKind::Group(usvg::Group {
+                        transform: parent_transform,
+                        ..usvg::Group::default()
+                    }));
+                group.append(element.make_deep_copy());
+
+                let element_pos = pos
+                    + na::vector![x - view_box.rect.x(), y - view_box.rect.y()]
+                        .component_mul(&scale);
+                let element_size = na::vector![width, height].component_mul(&scale);
+
+                Self::import_from_svg_data(
+                    &element_tree.to_string(&xml_options),
+                    element_pos,
+                    Some(element_size),
                     Arc::clone(&renderer),
-                )?);
-            }
+                )
+                .map_err(|e| {
+                    log::error!(
+                        "import_from_svg_data() failed for element in import_from_svg_data_split() with Err {}",
+                        e
+                    )
+                })
+                .ok()
+            })
+            .collect::<Vec<Self>>();
+
+        if images.is_empty() {
+            Ok(vec![Self::import_from_svg_data(
+                svg_data, pos, None, renderer,
+            )?])
+        } else {
+            Ok(images)
         }
+    }
 
-        Ok(images)
+    /// Converts the page of a PDF to a vector image, with the placement from `PdfImportOptions::place_pages()`
+    #[cfg(feature = "ui")]
+    pub fn import_from_pdf_page(
+        page: &poppler::Page,
+        placement: &PdfPagePlacement,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<Self, anyhow::Error> {
+        let intrinsic_size = page.size();
+
+        let svg_stream: Vec<u8> = vec![];
+
+        let surface = cairo::SvgSurface::for_stream(intrinsic_size.0, intrinsic_size.1, svg_stream)
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "create SvgSurface with dimensions ({}, {}) failed in vectorimage import_from_pdf_page with Err {}",
+                    intrinsic_size.0,
+                    intrinsic_size.1,
+                    e
+                )
+            })?;
+
+        {
+            let cx = cairo::Context::new(&surface).map_err(|e| {
+                anyhow::anyhow!(
+                    "new cairo::Context failed in vectorimage import_from_pdf_page() with Err {}",
+                    e
+                )
+            })?;
+
+            // Set margin to white
+            cx.set_source_rgba(1.0, 1.0, 1.0, 1.0);
+            cx.paint()?;
+
+            page.render(&cx);
+
+            // Draw outline around page
+            cx.set_source_rgba(0.7, 0.5, 0.5, 1.0);
+
+            let line_width = 1.0;
+            cx.set_line_width(line_width);
+            cx.rectangle(
+                line_width / 2.0,
+                line_width / 2.0,
+                intrinsic_size.0 - line_width,
+                intrinsic_size.1 - line_width,
+            );
+            cx.stroke()?;
+        }
+        let svg_data = *surface
+            .finish_output_stream()
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "surface.finish_output_stream() failed in vectorimage import_from_pdf_page() with Err {}",
+                    e
+                )
+            })?
+            .downcast::<Vec<u8>>()
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "file_content.downcast() failed in vectorimage import_from_pdf_page()"
+                )
+            })?;
+        let svg_data = String::from_utf8(svg_data)?;
+
+        Self::import_from_svg_data(
+            svg_data.as_str(),
+            placement.pos,
+            Some(placement.size),
+            renderer,
+        )
     }
 
     pub fn update_geometry(&mut self) {
@@ -311,3 +387,38 @@ impl VectorImage {
         Ok(render::image_into_encoded_bytes(image_raw, format)?)
     }
 }
+
+/// Collects the elements the svg is split into. Groups without effects are split further
+fn collect_split_elements(node: &usvg::Node, elements: &mut Vec<usvg::Node>) {
+    for child in node.children() {
+        match *child.borrow() {
+            usvg::NodeKind::Group(ref group)
+                if group.opacity.value() >= 1.0
+                    && group.clip_path.is_none()
+                    && group.mask.is_none()
+                    && group.filter.is_empty() =>
+            {
+                collect_split_elements(&child, elements);
+            }
+            usvg::NodeKind::Group(_) | usvg::NodeKind::Path(_) | usvg::NodeKind::Image(_) => {
+                elements.push(child.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The max stroke width of the paths of the node and its descendants, in the user space of the svg
+fn max_stroke_width(node: &usvg::Node) -> f64 {
+    node.descendants()
+        .filter_map(|descendant| match *descendant.borrow() {
+            usvg::NodeKind::Path(ref path) => {
+                let stroke_width = path.stroke.as_ref()?.width.value();
+                let (scale_x, scale_y) = descendant.abs_transform().get_scale();
+
+                Some(stroke_width * scale_x.max(scale_y))
+            }
+            _ => None,
+        })
+        .fold(0.0, f64::max)
+}
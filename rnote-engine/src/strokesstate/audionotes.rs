@@ -0,0 +1,179 @@
+//! Audio notes, recorded while writing and stored in the sheet.
+//! The strokes are matched to the audio by the moment they were written, so playback can jump to the moment a stroke was written.
+//! Brush strokes were written at the timestamp of their first element, other strokes when they were inserted ( see `chrono_comp` ).
+
+use super::{StrokeKey, StrokesState};
+use crate::strokes::strokestyle::StrokeStyle;
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A recorded audio note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "audio_note")]
+pub struct AudioNote {
+    /// The encoded audio
+    #[serde(rename = "data_base64", with = "crate::sheet::container::asset_base64")]
+    pub data_base64: String,
+    /// The mime type of the encoded audio, e.g. `audio/ogg`
+    #[serde(rename = "mime_type")]
+    pub mime_type: String,
+    /// When the recording was started
+    #[serde(rename = "started")]
+    pub started: DateTime<Utc>,
+    /// The length of the recording in seconds
+    #[serde(rename = "duration")]
+    pub duration: f64,
+}
+
+impl Default for AudioNote {
+    fn default() -> Self {
+        Self {
+            data_base64: String::default(),
+            mime_type: String::from(Self::MIME_TYPE_DEFAULT),
+            started: Utc.timestamp(0, 0),
+            duration: 0.0,
+        }
+    }
+}
+
+impl AudioNote {
+    pub const MIME_TYPE_DEFAULT: &'static str = "audio/ogg";
+
+    pub fn new(bytes: &[u8], mime_type: String, started: DateTime<Utc>, duration: f64) -> Self {
+        Self {
+            data_base64: base64::encode(bytes),
+            mime_type,
+            started,
+            duration: duration.max(0.0),
+        }
+    }
+
+    /// The decoded bytes of the encoded audio
+    pub fn data(&self) -> Result<Vec<u8>, anyhow::Error> {
+        Ok(base64::decode(&self.data_base64)?)
+    }
+
+    /// The offset in seconds of the moment in the recording, or None if it was not recorded
+    pub fn offset_of(&self, moment: DateTime<Utc>) -> Option<f64> {
+        let offset = (moment - self.started).num_milliseconds() as f64 / 1000.0;
+
+        if (0.0..=self.duration).contains(&offset) {
+            Some(offset)
+        } else {
+            None
+        }
+    }
+}
+
+/// A position in an audio note
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioPosition {
+    /// The index into `StrokesState::audio_notes()`
+    pub note_index: usize,
+    /// The offset into the recording in seconds
+    pub offset: f64,
+}
+
+/// Systems that are related to the audio notes.
+impl StrokesState {
+    /// The distance a position can be away from a stroke to still hit it
+    pub const AUDIO_TOLERANCE: f64 = 4.0;
+    /// The time in seconds playback starts before the moment a stroke was written, to hear what lead to it
+    pub const AUDIO_LEAD_TIME: f64 = 2.0;
+
+    /// The audio notes, in the order they were added
+    pub fn audio_notes(&self) -> &[AudioNote] {
+        &self.audio_notes
+    }
+
+    /// Adds the audio note and returns its index
+    pub fn add_audio_note(&mut self, audio_note: AudioNote) -> usize {
+        self.audio_notes.push(audio_note);
+        self.audio_notes.len() - 1
+    }
+
+    pub fn remove_audio_note(&mut self, note_index: usize) -> Option<AudioNote> {
+        if note_index < self.audio_notes.len() {
+            Some(self.audio_notes.remove(note_index))
+        } else {
+            None
+        }
+    }
+
+    /// The moment the stroke was written, or None for strokes from files which did not store it
+    pub fn written_at(&self, key: StrokeKey) -> Option<DateTime<Utc>> {
+        let first_timestamp = match self.strokes.get(key)? {
+            StrokeStyle::BrushStroke(brushstroke) => brushstroke
+                .elements
+                .first()
+                .and_then(|element| element.timestamp),
+            _ => None,
+        };
+
+        first_timestamp.or_else(|| self.chrono_components.get(key)?.created)
+    }
+
+    /// The position in the audio notes where the stroke was written, moved earlier by the lead time.
+    /// For overlapping notes the latest one is taken
+    pub fn audio_position_for_stroke(&self, key: StrokeKey) -> Option<AudioPosition> {
+        let written_at = self.written_at(key)?;
+
+        self.audio_notes
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(note_index, audio_note)| {
+                audio_note
+                    .offset_of(written_at)
+                    .map(|offset| AudioPosition {
+                        note_index,
+                        offset: (offset - Self::AUDIO_LEAD_TIME).max(0.0),
+                    })
+            })
+    }
+
+    /// The audio position of the topmost stroke at the position that was written while recording, which is not trashed and on a visible layer
+    pub fn audio_position_at(&self, pos: na::Vector2<f64>) -> Option<(StrokeKey, AudioPosition)> {
+        if self.audio_notes.is_empty() {
+            return None;
+        }
+
+        let mut keys = self.keys_as_rendered();
+        keys.append(&mut self.selection_keys_as_rendered());
+
+        keys.into_iter().rev().find_map(|key| {
+            if self.trashed(key).unwrap_or(true)
+                || !self.strokes.get(key)?.hittest(pos, Self::AUDIO_TOLERANCE)
+            {
+                return None;
+            }
+
+            self.audio_position_for_stroke(key)
+                .map(|audio_position| (key, audio_position))
+        })
+    }
+
+    /// The index of the audio note: the not trashed strokes written while it was recorded, with their offset into it in seconds, in the order they were written
+    pub fn keys_written_during(&self, note_index: usize) -> Vec<(StrokeKey, f64)> {
+        let audio_note = match self.audio_notes.get(note_index) {
+            Some(audio_note) => audio_note,
+            None => return vec![],
+        };
+
+        let mut written = self
+            .strokes
+            .keys()
+            .filter(|&key| !self.trashed(key).unwrap_or(true))
+            .filter_map(|key| Some((key, audio_note.offset_of(self.written_at(key)?)?)))
+            .collect::<Vec<(StrokeKey, f64)>>();
+        written.sort_by(|first, second| {
+            first
+                .1
+                .partial_cmp(&second.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        written
+    }
+}
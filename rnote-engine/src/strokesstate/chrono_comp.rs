@@ -1,28 +1,52 @@
+#[cfg(not(target_arch = "wasm32"))]
 use rayon::iter::{ParallelBridge, ParallelIterator};
-use rayon::slice::ParallelSliceMut;
 use serde::{Deserialize, Serialize};
 
+use super::history::HistoryChange;
 use super::{StrokeKey, StrokesState};
+use crate::utils;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, Ord, PartialEq, PartialOrd)]
 #[serde(default, rename = "chrono_component")]
 pub struct ChronoComponent {
     #[serde(rename = "t")]
     pub t: u32,
+    /// When the stroke was inserted. None for strokes from files which did not store it
+    #[serde(rename = "created")]
+    pub created: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl Default for ChronoComponent {
     fn default() -> Self {
-        Self { t: 0 }
+        Self {
+            t: 0,
+            created: None,
+        }
     }
 }
 
 impl ChronoComponent {
     pub fn new(t: u32) -> Self {
-        Self { t }
+        Self {
+            t,
+            created: Some(chrono::Utc::now()),
+        }
     }
 }
 
+/// The change of the z-order of the selected strokes. Strokes are drawn in chronological order, so this changes their chronology
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrokesZOrder {
+    /// Moves the strokes one stroke up
+    Raise,
+    /// Moves the strokes one stroke down
+    Lower,
+    /// Moves the strokes above all other strokes
+    BringToFront,
+    /// Moves the strokes below all other strokes
+    SendToBack,
+}
+
 /// Systems that are related to their Chronology.
 impl StrokesState {
     pub fn set_chrono_to_last(&mut self, key: StrokeKey) {
@@ -37,13 +61,19 @@ impl StrokesState {
         }
     }
 
+    /// The chrono_component of the newest inserted or modified stroke
+    pub fn chrono_counter(&self) -> u32 {
+        self.chrono_counter
+    }
+
     pub fn last_stroke_key(&self) -> Option<StrokeKey> {
         let chrono_components = &self.chrono_components;
         let trash_components = &self.trash_components;
 
-        let mut sorted: Vec<(StrokeKey, u32)> = chrono_components
-            .iter()
-            .par_bridge()
+        let iter = chrono_components.iter();
+        #[cfg(not(target_arch = "wasm32"))]
+        let iter = iter.par_bridge();
+        let mut sorted: Vec<(StrokeKey, u32)> = iter
             .filter_map(|(key, chrono_comp)| {
                 if let (Some(trash_comp), chrono_comp) = (trash_components.get(key), chrono_comp) {
                     if !trash_comp.trashed {
@@ -65,9 +95,10 @@ impl StrokesState {
         let trash_components = &self.trash_components;
         let selection_components = &self.selection_components;
 
-        let mut sorted: Vec<(StrokeKey, u32)> = chrono_components
-            .iter()
-            .par_bridge()
+        let iter = chrono_components.iter();
+        #[cfg(not(target_arch = "wasm32"))]
+        let iter = iter.par_bridge();
+        let mut sorted: Vec<(StrokeKey, u32)> = iter
             .filter_map(|(key, chrono_comp)| {
                 if let (Some(trash_comp), Some(selection_comp)) =
                     (trash_components.get(key), selection_components.get(key))
@@ -90,9 +121,10 @@ impl StrokesState {
         let chrono_components = &self.chrono_components;
         let trash_components = &self.trash_components;
 
-        let mut sorted = chrono_components
-            .iter()
-            .par_bridge()
+        let iter = chrono_components.iter();
+        #[cfg(not(target_arch = "wasm32"))]
+        let iter = iter.par_bridge();
+        let mut sorted = iter
             .filter_map(|(key, chrono_comp)| {
                 if let (Some(trash_comp), chrono_comp) = (trash_components.get(key), chrono_comp) {
                     if trash_comp.trashed {
@@ -102,25 +134,173 @@ impl StrokesState {
                 None
             })
             .collect::<Vec<(StrokeKey, u32)>>();
-        sorted.par_sort_unstable_by(|first, second| first.1.cmp(&second.1));
+        utils::sort_unstable_by(&mut sorted, |first, second| first.1.cmp(&second.1));
 
         let last_trashed_key = sorted.last().copied();
 
         last_trashed_key.map(|(last_trashed_key, _i)| last_trashed_key)
     }
 
+    /// Changes the z-order of the selected strokes within their layers, keeping the order between the selected strokes.
+    pub fn change_selection_z_order(&mut self, z_order: StrokesZOrder) {
+        // The changes on all layers are undone at once
+        self.begin_history_group();
+        for layer in self.layer_order().to_vec() {
+            // Only moving past not trashed strokes has a visible effect
+            let keys = self
+                .keys_on_layer(layer)
+                .into_iter()
+                .filter(|&key| !self.trashed(key).unwrap_or(true))
+                .collect::<Vec<StrokeKey>>();
+            let (selected, unselected): (Vec<StrokeKey>, Vec<StrokeKey>) = keys
+                .iter()
+                .partition(|&&key| self.selected(key).unwrap_or(false));
+            if selected.is_empty() || unselected.is_empty() {
+                continue;
+            }
+
+            let reordered = match z_order {
+                StrokesZOrder::BringToFront => [unselected, selected].concat(),
+                StrokesZOrder::SendToBack => [selected, unselected].concat(),
+                StrokesZOrder::Raise => {
+                    let mut reordered = keys.clone();
+                    for i in (0..reordered.len() - 1).rev() {
+                        if selected.contains(&reordered[i]) && !selected.contains(&reordered[i + 1])
+                        {
+                            reordered.swap(i, i + 1);
+                        }
+                    }
+                    reordered
+                }
+                StrokesZOrder::Lower => {
+                    let mut reordered = keys.clone();
+                    for i in 1..reordered.len() {
+                        if selected.contains(&reordered[i]) && !selected.contains(&reordered[i - 1])
+                        {
+                            reordered.swap(i, i - 1);
+                        }
+                    }
+                    reordered
+                }
+            };
+
+            // The strokes swap their timestamps, so strokes on other layers and trashed strokes are unaffected
+            let ts = keys
+                .iter()
+                .filter_map(|&key| Some(self.chrono_components.get(key)?.t))
+                .collect::<Vec<u32>>();
+            for (key, t) in reordered.into_iter().zip(ts) {
+                if self
+                    .chrono_components
+                    .get(key)
+                    .map_or(true, |chrono_comp| chrono_comp.t == t)
+                {
+                    continue;
+                }
+                self.record_change(key, HistoryChange::ZOrder);
+                if let Some(chrono_comp) = self.chrono_components.get_mut(key) {
+                    chrono_comp.t = t;
+                }
+            }
+        }
+        self.end_history_group();
+    }
+
     /// Returns the keys in chronological order, as in first: gets drawn first, last: gets drawn last
     pub fn keys_sorted_chrono(&self) -> Vec<StrokeKey> {
         let chrono_components = &self.chrono_components;
 
-        let mut sorted = chrono_components
-            .iter()
-            .par_bridge()
+        let iter = chrono_components.iter();
+        #[cfg(not(target_arch = "wasm32"))]
+        let iter = iter.par_bridge();
+        let mut sorted = iter
             .map(|(key, chrono_comp)| (key, chrono_comp.t))
             .collect::<Vec<(StrokeKey, u32)>>();
-        sorted.par_sort_unstable_by(|first, second| first.1.cmp(&second.1));
+        utils::sort_unstable_by(&mut sorted, |first, second| first.1.cmp(&second.1));
 
         let (keys, _ts): (Vec<StrokeKey>, Vec<u32>) = sorted.into_iter().unzip();
         keys
     }
+
+    /// Sorts the keys in chronological order
+    pub fn sort_keys_chrono(&self, keys: &mut [StrokeKey]) {
+        keys.sort_by_key(|&key| {
+            self.chrono_components
+                .get(key)
+                .map(|chrono_comp| chrono_comp.t)
+        });
+    }
+
+    /// Returns the keys of the strokes which were inserted or modified after the chrono counter had the value t, in chronological order
+    pub fn keys_changed_since(&self, t: u32) -> Vec<StrokeKey> {
+        let chrono_components = &self.chrono_components;
+
+        let iter = chrono_components.iter();
+        #[cfg(not(target_arch = "wasm32"))]
+        let iter = iter.par_bridge();
+        let mut changed = iter
+            .filter_map(|(key, chrono_comp)| {
+                if chrono_comp.t > t {
+                    Some((key, chrono_comp.t))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<(StrokeKey, u32)>>();
+        utils::sort_unstable_by(&mut changed, |first, second| first.1.cmp(&second.1));
+
+        let (keys, _ts): (Vec<StrokeKey>, Vec<u32>) = changed.into_iter().unzip();
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pens::brush::Brush;
+    use crate::strokes::brushstroke::BrushStroke;
+    use crate::strokes::element::Element;
+    use crate::strokes::inputdata::InputData;
+    use crate::strokes::strokestyle::StrokeStyle;
+
+    fn insert_brushstroke(strokes_state: &mut StrokesState, x: f64) -> StrokeKey {
+        let elements =
+            (0..3).map(|i| Element::new(InputData::new(na::vector![x, f64::from(i) * 10.0], 1.0)));
+        strokes_state.insert_stroke(StrokeStyle::BrushStroke(
+            BrushStroke::new_w_elements(elements, &Brush::default()).unwrap(),
+        ))
+    }
+
+    #[test]
+    fn selecting_keeps_the_z_order() {
+        let mut strokes_state = StrokesState::default();
+        let keys = [0.0, 10.0, 20.0].map(|x| insert_brushstroke(&mut strokes_state, x));
+
+        strokes_state.set_selected_keys(&[keys[0]], true);
+        assert_eq!(strokes_state.keys_sorted_chrono(), keys);
+        strokes_state.set_selected_keys(&[keys[0]], false);
+        assert_eq!(strokes_state.keys_sorted_chrono(), keys);
+    }
+
+    #[test]
+    fn z_order_survives_deselecting() {
+        let mut strokes_state = StrokesState::default();
+        let keys = [0.0, 10.0, 20.0].map(|x| insert_brushstroke(&mut strokes_state, x));
+
+        strokes_state.set_selected_keys(&[keys[2]], true);
+        strokes_state.change_selection_z_order(StrokesZOrder::SendToBack);
+        strokes_state.set_selected_keys(&[keys[2]], false);
+        assert_eq!(
+            strokes_state.keys_sorted_chrono(),
+            [keys[2], keys[0], keys[1]]
+        );
+
+        strokes_state.set_selected_keys(&[keys[0]], true);
+        strokes_state.change_selection_z_order(StrokesZOrder::Raise);
+        strokes_state.set_selected_keys(&[keys[0]], false);
+        assert_eq!(
+            strokes_state.keys_sorted_chrono(),
+            [keys[2], keys[1], keys[0]]
+        );
+    }
 }
@@ -0,0 +1,186 @@
+use super::history::HistoryChange;
+use super::{StrokeKey, StrokesState};
+
+use serde::{Deserialize, Serialize};
+
+slotmap::new_key_type! {
+    pub struct GroupKey;
+}
+
+/// A group of strokes, which are selected, transformed and deleted as a single unit.
+/// Groups can be nested, a nested group has the group it is in as parent
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "group")]
+pub struct Group {
+    #[serde(rename = "parent")]
+    pub parent: Option<GroupKey>,
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self { parent: None }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "group_component")]
+pub struct GroupComponent {
+    /// The innermost group the stroke is in
+    #[serde(rename = "group")]
+    pub group: Option<GroupKey>,
+}
+
+impl Default for GroupComponent {
+    fn default() -> Self {
+        Self { group: None }
+    }
+}
+
+/// Systems that are related to the groups.
+impl StrokesState {
+    /// The outermost group the stroke is in
+    pub fn topmost_group(&self, key: StrokeKey) -> Option<GroupKey> {
+        let mut group = self.group_components.get(key)?.group?;
+
+        while let Some(parent) = self.groups.get(group).and_then(|group| group.parent) {
+            group = parent;
+        }
+
+        Some(group)
+    }
+
+    /// Whether the stroke is in the group, directly or through nested groups
+    pub fn in_group(&self, key: StrokeKey, group: GroupKey) -> bool {
+        let mut current = self
+            .group_components
+            .get(key)
+            .and_then(|group_comp| group_comp.group);
+
+        while let Some(current_group) = current {
+            if current_group == group {
+                return true;
+            }
+            current = self
+                .groups
+                .get(current_group)
+                .and_then(|group| group.parent);
+        }
+
+        false
+    }
+
+    /// The strokes in the group, including the strokes of nested groups
+    pub fn group_members(&self, group: GroupKey) -> Vec<StrokeKey> {
+        self.group_components
+            .keys()
+            .filter(|&key| self.in_group(key, group))
+            .collect()
+    }
+
+    /// The keys with all the other strokes in their topmost groups added
+    pub fn keys_w_group_members(&self, keys: &[StrokeKey]) -> Vec<StrokeKey> {
+        let mut groups = keys
+            .iter()
+            .filter_map(|&key| self.topmost_group(key))
+            .collect::<Vec<GroupKey>>();
+        groups.sort_unstable();
+        groups.dedup();
+
+        let mut keys_w_members = keys.to_vec();
+        for group in groups {
+            for member in self.group_members(group) {
+                if !keys_w_members.contains(&member) {
+                    keys_w_members.push(member);
+                }
+            }
+        }
+
+        keys_w_members
+    }
+
+    /// Selects all strokes in the topmost groups of the selected strokes, so groups are always selected as a whole
+    pub fn expand_selection_to_groups(&mut self) {
+        let selection_keys = self.selection_keys_as_rendered();
+        let keys_w_members = self.keys_w_group_members(&selection_keys);
+
+        for key in keys_w_members {
+            if !self.trashed(key).unwrap_or(true) {
+                self.set_selected(key, true);
+            }
+        }
+    }
+
+    /// Groups the selected strokes and groups into a new group. Returns its key, or None if there are less than two units to group
+    pub fn group_selection(&mut self) -> Option<GroupKey> {
+        let selection_keys = self.selection_keys_as_rendered();
+
+        let mut topmost_groups = selection_keys
+            .iter()
+            .filter_map(|&key| self.topmost_group(key))
+            .collect::<Vec<GroupKey>>();
+        topmost_groups.sort_unstable();
+        topmost_groups.dedup();
+        let ungrouped = selection_keys
+            .iter()
+            .copied()
+            .filter(|&key| self.topmost_group(key).is_none())
+            .collect::<Vec<StrokeKey>>();
+
+        if topmost_groups.len() + ungrouped.len() < 2 {
+            return None;
+        }
+
+        let new_group = self.groups.insert(Group::default());
+
+        for group in topmost_groups {
+            if let Some(group) = self.groups.get_mut(group) {
+                group.parent = Some(new_group);
+            }
+        }
+        for key in ungrouped {
+            self.record_change(key, HistoryChange::Modify);
+            // Inserting, because strokes from files without groups don't have a group component yet
+            self.group_components.insert(
+                key,
+                GroupComponent {
+                    group: Some(new_group),
+                },
+            );
+        }
+
+        Some(new_group)
+    }
+
+    /// Dissolves the topmost groups of the selected strokes. Nested groups are kept and become topmost groups themselves
+    pub fn ungroup_selection(&mut self) {
+        let mut topmost_groups = self
+            .selection_keys_as_rendered()
+            .iter()
+            .filter_map(|&key| self.topmost_group(key))
+            .collect::<Vec<GroupKey>>();
+        topmost_groups.sort_unstable();
+        topmost_groups.dedup();
+
+        for removed in topmost_groups {
+            for group in self.groups.values_mut() {
+                if group.parent == Some(removed) {
+                    group.parent = None;
+                }
+            }
+            let members = self
+                .group_components
+                .iter()
+                .filter(|(_, group_comp)| group_comp.group == Some(removed))
+                .map(|(key, _)| key)
+                .collect::<Vec<StrokeKey>>();
+            for key in members {
+                self.record_change(key, HistoryChange::Modify);
+                if let Some(group_comp) = self.group_components.get_mut(key) {
+                    group_comp.group = None;
+                }
+            }
+
+            self.groups.remove(removed);
+        }
+    }
+}
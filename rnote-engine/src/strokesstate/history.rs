@@ -0,0 +1,756 @@
+//! The undo history. Every entry only stores the strokes which were changed by it, with their state before and after the change.
+//! Changes of the same kind which follow each other quickly ( e.g. the steps of a spin button ) are coalesced into one entry,
+//! and changes inside a group ( e.g. a pen stroke, or dragging the selection ) always end up in one entry.
+//! The entries are nodes of an undo tree: committing changes after undoing starts a new branch, and the undone branch is kept to navigate back to it.
+//! When the entries take up more memory than the budget, the oldest ones are dropped, first from the other branches.
+//!
+//! Only the strokes and their trash, chrono, group, layer, link and tag components are recorded. Changes to the layers and groups themselves are not undone.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::{StrokeComponents, StrokeKey, StrokesState};
+use crate::strokes::element::Element;
+use crate::strokes::strokestyle::StrokeStyle;
+
+use p2d::bounding_volume::AABB;
+use slotmap::HopSlotMap;
+use web_time::Instant;
+
+/// The kind of a change to a stroke. Only changes of the same kind are coalesced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryChange {
+    Insert,
+    Remove,
+    Trash,
+    Translate,
+    Rotate,
+    Resize,
+    ModifyOptions,
+    Modify,
+    /// The strokes were moved in the z-order, which only changes their chrono components
+    ZOrder,
+}
+
+impl HistoryChange {
+    /// Whether the change modifies the stroke itself, and not only its components. Only then the stroke needs to be stored
+    fn modifies_stroke(self) -> bool {
+        !matches!(self, Self::Trash | Self::ZOrder)
+    }
+
+    /// Whether the change of a stroke which was inserted by the pending entry belongs to the insertion, like moving a clone into place.
+    /// Trashing or removing the stroke is an entry of its own, so the insertion is kept when it is undone
+    fn belongs_to_insert(self) -> bool {
+        !matches!(self, Self::Trash | Self::Remove)
+    }
+}
+
+/// The state of a stroke before or after a history entry
+#[derive(Debug, Clone)]
+struct StrokeRecord {
+    /// The stroke is only stored when it was modified by the entry
+    stroke: Option<StrokeStyle>,
+    trashed: bool,
+    components: StrokeComponents,
+}
+
+impl StrokeRecord {
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.stroke.as_ref().map_or(0, stroke_size)
+            + self
+                .components
+                .tag
+                .tags
+                .iter()
+                .map(|tag| tag.len())
+                .sum::<usize>()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StrokeDelta {
+    /// None if the stroke was inserted by the entry
+    before: Option<StrokeRecord>,
+    /// None if the stroke was removed by the entry. Recorded when the entry is committed
+    after: Option<StrokeRecord>,
+}
+
+#[derive(Debug, Clone)]
+struct HistoryEntry {
+    change: HistoryChange,
+    deltas: HashMap<StrokeKey, StrokeDelta>,
+    first_change: Instant,
+    last_change: Instant,
+    /// When the entry was committed
+    committed: Option<chrono::DateTime<chrono::Utc>>,
+    /// The approximate memory used by the entry in bytes
+    size: usize,
+}
+
+impl HistoryEntry {
+    fn new(change: HistoryChange) -> Self {
+        Self {
+            change,
+            deltas: HashMap::new(),
+            first_change: Instant::now(),
+            last_change: Instant::now(),
+            committed: None,
+            size: 0,
+        }
+    }
+
+    /// The changed strokes, and whether the stroke itself was modified by undoing or applying the entry, for the session recording
+    fn changed_strokes(&self, undo: bool) -> Vec<(StrokeKey, bool)> {
+        self.deltas
+            .iter()
+            .map(|(&key, delta)| {
+                let record = if undo { &delta.before } else { &delta.after };
+                (
+                    key,
+                    record
+                        .as_ref()
+                        .map_or(false, |record| record.stroke.is_some()),
+                )
+            })
+            .collect()
+    }
+
+    fn remap_key(&mut self, old_key: StrokeKey, new_key: StrokeKey) {
+        if let Some(delta) = self.deltas.remove(&old_key) {
+            self.deltas.insert(new_key, delta);
+        }
+    }
+}
+
+slotmap::new_key_type! {
+    pub struct HistoryNodeKey;
+}
+
+#[derive(Debug, Clone)]
+struct HistoryNode {
+    parent: Option<HistoryNodeKey>,
+    /// The children in the order they were committed. Every child is a branch of the history
+    children: Vec<HistoryNodeKey>,
+    /// The child which is redone. It is the child which was committed or navigated to last
+    active_child: Option<HistoryNodeKey>,
+    /// None for the root, which stands for the state before the oldest entry
+    entry: Option<HistoryEntry>,
+    /// Increases with every commit, to find the oldest nodes
+    seq: u64,
+}
+
+/// A node of the undo tree, to show the history
+#[derive(Debug, Clone)]
+pub struct HistoryNodeInfo {
+    pub key: HistoryNodeKey,
+    pub parent: Option<HistoryNodeKey>,
+    /// The branches in the order they were committed
+    pub children: Vec<HistoryNodeKey>,
+    /// The child which is redone
+    pub active_child: Option<HistoryNodeKey>,
+    /// The kind of the change. None for the root, which stands for the state before the oldest entry
+    pub change: Option<HistoryChange>,
+    /// The number of strokes changed by the entry
+    pub n_strokes: usize,
+    pub committed: Option<chrono::DateTime<chrono::Utc>>,
+    /// Whether the strokes are currently in the state after this node
+    pub is_current: bool,
+}
+
+/// The undo tree. Undoing moves to the parent of the current node, redoing to its active child.
+/// Committing after undoing starts a new branch, and keeps the undone ones
+#[derive(Debug)]
+pub struct History {
+    nodes: HopSlotMap<HistoryNodeKey, HistoryNode>,
+    root: HistoryNodeKey,
+    /// The node of the current state of the strokes
+    current_node: HistoryNodeKey,
+    /// The entry which records the current changes, until it is committed
+    pending: Option<HistoryEntry>,
+    /// While above zero, all changes are recorded into the pending entry, regardless of their kind and time
+    group_depth: u32,
+    /// Disabled while undoing and redoing
+    recording: bool,
+    /// The memory in bytes the entries may take up, before the oldest ones are dropped
+    memory_budget: usize,
+    /// The approximate memory in bytes used by the entries of all nodes
+    memory: usize,
+    seq_counter: u64,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        let mut nodes = HopSlotMap::with_key();
+        let root = nodes.insert(HistoryNode::root());
+
+        Self {
+            nodes,
+            root,
+            current_node: root,
+            pending: None,
+            group_depth: 0,
+            recording: true,
+            memory_budget: Self::MEMORY_BUDGET_DEFAULT,
+            memory: 0,
+            seq_counter: 0,
+        }
+    }
+}
+
+impl HistoryNode {
+    fn root() -> Self {
+        Self {
+            parent: None,
+            children: vec![],
+            active_child: None,
+            entry: None,
+            seq: 0,
+        }
+    }
+}
+
+impl History {
+    /// Changes of the same kind which are closer together are coalesced
+    pub const COALESCE_INTERVAL: Duration = Duration::from_millis(500);
+    /// The default memory budget, 256 MiB
+    pub const MEMORY_BUDGET_DEFAULT: usize = 256 * 1024 * 1024;
+
+    pub fn can_undo(&self) -> bool {
+        self.current_node != self.root || self.pending.is_some()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.nodes[self.current_node].active_child.is_some() && self.pending.is_none()
+    }
+
+    /// The number of entries which can be undone, without the pending one
+    pub fn undo_len(&self) -> usize {
+        self.path_from_root(self.current_node).len() - 1
+    }
+
+    /// The number of entries which can be redone along the active children
+    pub fn redo_len(&self) -> usize {
+        std::iter::successors(self.nodes[self.current_node].active_child, |&key| {
+            self.nodes[key].active_child
+        })
+        .count()
+    }
+
+    pub fn root(&self) -> HistoryNodeKey {
+        self.root
+    }
+
+    pub fn current_node(&self) -> HistoryNodeKey {
+        self.current_node
+    }
+
+    /// The nodes of the undo tree in the order they were committed, starting with the root
+    pub fn tree(&self) -> Vec<HistoryNodeInfo> {
+        let mut nodes = self
+            .nodes
+            .iter()
+            .map(|(key, node)| {
+                (
+                    node.seq,
+                    HistoryNodeInfo {
+                        key,
+                        parent: node.parent,
+                        children: node.children.clone(),
+                        active_child: node.active_child,
+                        change: node.entry.as_ref().map(|entry| entry.change),
+                        n_strokes: node.entry.as_ref().map_or(0, |entry| entry.deltas.len()),
+                        committed: node.entry.as_ref().and_then(|entry| entry.committed),
+                        is_current: key == self.current_node,
+                    },
+                )
+            })
+            .collect::<Vec<(u64, HistoryNodeInfo)>>();
+        nodes.sort_unstable_by_key(|(seq, _)| *seq);
+
+        nodes.into_iter().map(|(_, info)| info).collect()
+    }
+
+    /// The approximate memory in bytes used by the entries
+    pub fn memory(&self) -> usize {
+        self.memory
+    }
+
+    pub fn memory_budget(&self) -> usize {
+        self.memory_budget
+    }
+
+    pub fn set_memory_budget(&mut self, memory_budget: usize) {
+        self.memory_budget = memory_budget;
+        self.enforce_memory_budget();
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.root = self.nodes.insert(HistoryNode::root());
+        self.current_node = self.root;
+        self.pending = None;
+        self.group_depth = 0;
+        self.memory = 0;
+    }
+
+    /// Whether the pending entry must be committed before the change can be recorded
+    fn needs_commit(&self, change: HistoryChange) -> bool {
+        match &self.pending {
+            Some(pending) if self.group_depth == 0 => {
+                pending.change != change || pending.last_change.elapsed() > Self::COALESCE_INTERVAL
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the stroke was inserted by the pending entry. Its following changes belong to the entry, e.g. when a clone is moved after it was inserted
+    fn inserted_in_pending(&self, key: StrokeKey) -> bool {
+        self.pending
+            .as_ref()
+            .and_then(|pending| pending.deltas.get(&key))
+            .map_or(false, |delta| delta.before.is_none())
+    }
+
+    /// Whether the state of the stroke before the pending entry is recorded, and if it includes the stroke itself
+    fn recorded(&self, key: StrokeKey) -> Option<bool> {
+        self.pending
+            .as_ref()
+            .and_then(|pending| pending.deltas.get(&key))
+            .map(|delta| {
+                delta
+                    .before
+                    .as_ref()
+                    .map_or(true, |before| before.stroke.is_some())
+            })
+    }
+
+    fn record_before(
+        &mut self,
+        key: StrokeKey,
+        change: HistoryChange,
+        before: Option<StrokeRecord>,
+    ) {
+        let pending = self
+            .pending
+            .get_or_insert_with(|| HistoryEntry::new(change));
+        pending.last_change = Instant::now();
+
+        match pending.deltas.get_mut(&key) {
+            Some(StrokeDelta {
+                before: Some(recorded),
+                ..
+            }) => {
+                // The stroke itself is unchanged until its first modification, so it can be added to the earlier record
+                if recorded.stroke.is_none() {
+                    recorded.stroke = before.and_then(|before| before.stroke);
+                }
+            }
+            // Inserted by the entry
+            Some(_) => {}
+            None => {
+                pending.deltas.insert(
+                    key,
+                    StrokeDelta {
+                        before,
+                        after: None,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Adds the entry as new child of the current node, which becomes the current node. The other children are kept as branches
+    fn push_node(&mut self, mut entry: HistoryEntry) {
+        self.seq_counter += 1;
+        self.memory += entry.size;
+        entry.committed = Some(chrono::Utc::now());
+
+        let key = self.nodes.insert(HistoryNode {
+            parent: Some(self.current_node),
+            children: vec![],
+            active_child: None,
+            entry: Some(entry),
+            seq: self.seq_counter,
+        });
+        let parent = &mut self.nodes[self.current_node];
+        parent.children.push(key);
+        parent.active_child = Some(key);
+        self.current_node = key;
+
+        self.enforce_memory_budget();
+    }
+
+    /// The keys of the nodes from the root to the node
+    fn path_from_root(&self, key: HistoryNodeKey) -> Vec<HistoryNodeKey> {
+        let mut path = std::iter::successors(Some(key), |&key| self.nodes.get(key)?.parent)
+            .collect::<Vec<_>>();
+        path.reverse();
+        path
+    }
+
+    /// Drops the oldest nodes until the memory budget is met. First the oldest leaves of other branches are dropped,
+    /// then the oldest entries on the path to the current node. The last entry on the path is always kept
+    fn enforce_memory_budget(&mut self) {
+        while self.memory > self.memory_budget {
+            let path = self.path_from_root(self.current_node);
+
+            let oldest_leaf = self
+                .nodes
+                .iter()
+                .filter(|(key, node)| node.children.is_empty() && !path.contains(key))
+                .min_by_key(|(_, node)| node.seq)
+                .map(|(key, _)| key);
+
+            if let Some(leaf) = oldest_leaf {
+                self.remove_leaf(leaf);
+                continue;
+            }
+
+            // Only the path is left, so the root has a single child. It becomes the new root, which drops its entry
+            if path.len() <= 2 {
+                break;
+            }
+            let new_root = path[1];
+            self.nodes.remove(self.root);
+            let node = &mut self.nodes[new_root];
+            node.parent = None;
+            if let Some(entry) = node.entry.take() {
+                self.memory = self.memory.saturating_sub(entry.size);
+            }
+            self.root = new_root;
+        }
+    }
+
+    fn remove_leaf(&mut self, leaf: HistoryNodeKey) {
+        let node = match self.nodes.remove(leaf) {
+            Some(node) => node,
+            None => return,
+        };
+        if let Some(entry) = node.entry {
+            self.memory = self.memory.saturating_sub(entry.size);
+        }
+
+        if let Some(parent) = node.parent.and_then(|parent| self.nodes.get_mut(parent)) {
+            parent.children.retain(|&child| child != leaf);
+            if parent.active_child == Some(leaf) {
+                parent.active_child = parent.children.last().copied();
+            }
+        }
+    }
+
+    /// Updates the keys of a stroke which was inserted again by undo or redo
+    fn remap_key(&mut self, old_key: StrokeKey, new_key: StrokeKey) {
+        self.nodes
+            .values_mut()
+            .filter_map(|node| node.entry.as_mut())
+            .for_each(|entry| entry.remap_key(old_key, new_key));
+    }
+}
+
+/// The approximate memory in bytes used by the stroke
+fn stroke_size(stroke: &StrokeStyle) -> usize {
+    std::mem::size_of::<StrokeStyle>()
+        + match stroke {
+            StrokeStyle::BrushStroke(brushstroke) => {
+                brushstroke.elements.len() * std::mem::size_of::<Element>()
+                    + brushstroke.hitboxes.len() * std::mem::size_of::<AABB>()
+            }
+            StrokeStyle::ShapeStroke(_) => 0,
+            StrokeStyle::VectorImage(vectorimage) => vectorimage.svg_data.len(),
+            StrokeStyle::BitmapImage(bitmapimage) => bitmapimage.data_base64.len(),
+            StrokeStyle::TextStroke(textstroke) => {
+                textstroke.text.len() + textstroke.style.font_family.len()
+            }
+            StrokeStyle::MathStroke(mathstroke) => mathstroke.source.len(),
+            StrokeStyle::CheckboxStroke(checkboxstroke) => checkboxstroke.text.len(),
+            StrokeStyle::ExtensionStroke(extensionstroke) => extensionstroke.size(),
+        }
+}
+
+/// Systems that are related to the history.
+impl StrokesState {
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub fn set_history_memory_budget(&mut self, memory_budget: usize) {
+        self.history.set_memory_budget(memory_budget);
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Records the state of the stroke before the change. Needs to be called before the stroke or its recorded components are modified
+    pub(crate) fn record_change(&mut self, key: StrokeKey, change: HistoryChange) {
+        self.collab_changes.mark(key);
+        self.stroke_revisions.mark(key);
+        if !self.history.recording || !self.strokes.contains_key(key) {
+            return;
+        }
+        if !(change.belongs_to_insert() && self.history.inserted_in_pending(key))
+            && self.history.needs_commit(change)
+        {
+            self.commit_history();
+        }
+
+        let before = match self.history.recorded(key) {
+            Some(true) => None,
+            Some(false) if !change.modifies_stroke() => None,
+            _ => self.stroke_record(key, change.modifies_stroke()),
+        };
+        // Already recorded strokes only keep the entry from being committed
+        self.history.record_before(key, change, before);
+    }
+
+    /// Records the insertion of the stroke. Needs to be called right after it was inserted
+    pub(crate) fn record_insert(&mut self, key: StrokeKey) {
+        self.collab_changes.mark(key);
+        self.stroke_revisions.mark(key);
+        if !self.history.recording {
+            return;
+        }
+        if self.history.needs_commit(HistoryChange::Insert) {
+            self.commit_history();
+        }
+
+        self.history.record_before(key, HistoryChange::Insert, None);
+    }
+
+    /// Runs the changes without recording them, e.g. for changes of other participants of a collaboration session which are not undone locally
+    pub(crate) fn without_history<T>(&mut self, changes: impl FnOnce(&mut Self) -> T) -> T {
+        let recording = self.history.recording;
+        self.history.recording = false;
+        let result = changes(self);
+        self.history.recording = recording;
+
+        result
+    }
+
+    /// Starts a group of changes which are recorded into one entry, e.g. while the pen is down. Groups can be nested
+    pub fn begin_history_group(&mut self) {
+        if self.history.group_depth == 0 {
+            self.commit_history();
+        }
+        self.history.group_depth += 1;
+    }
+
+    /// Ends the group of changes and commits them when it was the outermost group
+    pub fn end_history_group(&mut self) {
+        self.history.group_depth = self.history.group_depth.saturating_sub(1);
+
+        if self.history.group_depth == 0 {
+            // The changes of the group last until it ends, e.g. a pen stroke until the pen is lifted
+            if let Some(pending) = self.history.pending.as_mut() {
+                pending.last_change = Instant::now();
+            }
+            self.commit_history();
+        }
+    }
+
+    /// Commits the pending entry as new node of the undo tree. When entries were undone before, it starts a new branch
+    pub fn commit_history(&mut self) {
+        let mut entry = if let Some(entry) = self.history.pending.take() {
+            entry
+        } else {
+            return;
+        };
+
+        let keys = entry.deltas.keys().copied().collect::<Vec<StrokeKey>>();
+        for key in keys {
+            let delta = match entry.deltas.get_mut(&key) {
+                Some(delta) => delta,
+                None => continue,
+            };
+            let with_stroke = delta
+                .before
+                .as_ref()
+                .map_or(true, |before| before.stroke.is_some());
+            delta.after = self.stroke_record(key, with_stroke);
+
+            // Strokes which were inserted and removed again are left out
+            if delta.before.is_none() && delta.after.is_none() {
+                entry.deltas.remove(&key);
+            }
+        }
+        if entry.deltas.is_empty() {
+            return;
+        }
+        let changes = entry.changed_strokes(false);
+        let (first_change, last_change) = (entry.first_change, entry.last_change);
+
+        entry.size = std::mem::size_of::<HistoryEntry>()
+            + entry
+                .deltas
+                .values()
+                .map(|delta| {
+                    std::mem::size_of::<(StrokeKey, StrokeDelta)>()
+                        + delta.before.as_ref().map_or(0, StrokeRecord::size)
+                        + delta.after.as_ref().map_or(0, StrokeRecord::size)
+                })
+                .sum::<usize>();
+
+        self.history.push_node(entry);
+        self.record_session_changes(first_change, last_change, &changes);
+    }
+
+    /// Undoes the entry of the current node, and moves to its parent. Returns false if there was nothing to undo.
+    /// The pending changes are committed first, so they are undone as well
+    pub fn undo(&mut self) -> bool {
+        self.commit_history();
+        self.undo_current_node()
+    }
+
+    /// Redoes the entry of the active child of the current node, and moves to it. Returns false if there was nothing to redo,
+    /// which is also the case when there were pending changes, because committing them starts a new branch
+    pub fn redo(&mut self) -> bool {
+        self.commit_history();
+
+        match self.history.nodes[self.history.current_node].active_child {
+            Some(child) => self.redo_node(child),
+            None => false,
+        }
+    }
+
+    /// Moves to the node of the undo tree, by undoing up to the common parent and redoing down the branch of the node.
+    /// The nodes on the way become the active children, so the branch is redone from then on. Returns false if the node does not exist.
+    /// The pending changes are committed first, and become a branch of their own
+    pub fn navigate_history(&mut self, target: HistoryNodeKey) -> bool {
+        self.commit_history();
+        if !self.history.nodes.contains_key(target) {
+            return false;
+        }
+
+        let target_path = self.history.path_from_root(target);
+        while !target_path.contains(&self.history.current_node) {
+            if !self.undo_current_node() {
+                return false;
+            }
+        }
+
+        let common = target_path
+            .iter()
+            .position(|&key| key == self.history.current_node)
+            .unwrap_or(0);
+        for &key in target_path[common + 1..].iter() {
+            if !self.redo_node(key) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn undo_current_node(&mut self) -> bool {
+        let current_node = self.history.current_node;
+        let (parent, mut entry) = match self.history.nodes.get_mut(current_node) {
+            Some(HistoryNode {
+                parent: Some(parent),
+                entry,
+                ..
+            }) => match entry.take() {
+                Some(entry) => (*parent, entry),
+                None => return false,
+            },
+            _ => return false,
+        };
+
+        self.apply_history_entry(&mut entry, true);
+
+        self.history.nodes[current_node].entry = Some(entry);
+        self.history.nodes[parent].active_child = Some(current_node);
+        self.history.current_node = parent;
+
+        true
+    }
+
+    /// Redoes the child of the current node
+    fn redo_node(&mut self, child: HistoryNodeKey) -> bool {
+        let mut entry = match self.history.nodes.get_mut(child) {
+            Some(node) if node.parent == Some(self.history.current_node) => {
+                match node.entry.take() {
+                    Some(entry) => entry,
+                    None => return false,
+                }
+            }
+            _ => return false,
+        };
+
+        self.apply_history_entry(&mut entry, false);
+
+        self.history.nodes[child].entry = Some(entry);
+        self.history.nodes[self.history.current_node].active_child = Some(child);
+        self.history.current_node = child;
+
+        true
+    }
+
+    fn stroke_record(&self, key: StrokeKey, with_stroke: bool) -> Option<StrokeRecord> {
+        let stroke = self.strokes.get(key)?;
+
+        Some(StrokeRecord {
+            stroke: if with_stroke {
+                Some(stroke.clone())
+            } else {
+                None
+            },
+            trashed: self.trashed(key).unwrap_or(false),
+            components: self.stroke_components(key)?,
+        })
+    }
+
+    /// Restores the strokes of the entry to their state before ( when undoing ) or after it
+    fn apply_history_entry(&mut self, entry: &mut HistoryEntry, undo: bool) {
+        self.history.recording = false;
+        let mut remapped_keys = Vec::new();
+
+        for (&key, delta) in entry.deltas.iter() {
+            let record = if undo { &delta.before } else { &delta.after };
+
+            let key = match (record, self.strokes.contains_key(key)) {
+                (None, true) => {
+                    self.remove_stroke(key);
+                    continue;
+                }
+                (None, false) => continue,
+                (Some(record), true) => {
+                    if let Some(stroke) = record.stroke.clone() {
+                        self.replace_stroke(key, stroke);
+                    }
+                    key
+                }
+                (Some(record), false) => {
+                    if let Some(stroke) = record.stroke.clone() {
+                        let new_key = self.insert_stroke(stroke);
+                        remapped_keys.push((key, new_key));
+                        new_key
+                    } else {
+                        log::debug!(
+                            "the stroke with key {:?} is missing in apply_history_entry(), and the record has no stroke to insert",
+                            key
+                        );
+                        continue;
+                    }
+                }
+            };
+
+            if let Some(record) = record {
+                self.set_trashed(key, record.trashed);
+                // After trashing, which moves the stroke to the front of the chronology
+                self.set_stroke_components(key, record.components.clone());
+            }
+            self.update_geometry_for_stroke(key);
+        }
+
+        for (old_key, new_key) in remapped_keys {
+            entry.remap_key(old_key, new_key);
+            self.history.remap_key(old_key, new_key);
+        }
+
+        self.history.recording = true;
+
+        let now = Instant::now();
+        self.record_session_changes(now, now, &entry.changed_strokes(undo));
+    }
+}
@@ -0,0 +1,310 @@
+use super::history::HistoryChange;
+use super::{StrokeKey, StrokesState};
+
+use serde::{Deserialize, Serialize};
+
+slotmap::new_key_type! {
+    pub struct LayerKey;
+}
+
+/// A layer of the sheet. Layers are composited in the order of the layer table, each with its own opacity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "layer")]
+pub struct Layer {
+    #[serde(rename = "name")]
+    pub name: String,
+    /// Strokes on invisible layers are neither drawn nor exported
+    #[serde(rename = "visible")]
+    pub visible: bool,
+    /// Strokes on locked layers can't be selected, erased or modified with tools
+    #[serde(rename = "locked")]
+    pub locked: bool,
+    /// The opacity the layer is composited with, between 0.0 and 1.0
+    #[serde(rename = "opacity")]
+    pub opacity: f64,
+}
+
+impl Default for Layer {
+    fn default() -> Self {
+        Self {
+            name: String::from(Self::NAME_DEFAULT),
+            visible: true,
+            locked: false,
+            opacity: Self::OPACITY_MAX,
+        }
+    }
+}
+
+impl Layer {
+    /// The default name
+    pub const NAME_DEFAULT: &'static str = "Layer";
+    /// The min opacity
+    pub const OPACITY_MIN: f64 = 0.0;
+    /// The max opacity
+    pub const OPACITY_MAX: f64 = 1.0;
+
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            ..Self::default()
+        }
+    }
+
+    /// If the strokes on the layer can be selected, erased or modified with tools
+    pub fn editable(&self) -> bool {
+        self.visible && !self.locked
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "layer_component")]
+pub struct LayerComponent {
+    /// The layer the stroke is on. None or a removed layer mean the bottom layer
+    #[serde(rename = "layer")]
+    pub layer: Option<LayerKey>,
+}
+
+impl Default for LayerComponent {
+    fn default() -> Self {
+        Self { layer: None }
+    }
+}
+
+/// Systems that are related to the layers.
+impl StrokesState {
+    /// The layer keys, ordered from the bottom to the top layer
+    pub fn layer_order(&self) -> &[LayerKey] {
+        &self.layer_order
+    }
+
+    pub fn get_layer(&self, layer: LayerKey) -> Option<&Layer> {
+        self.layers.get(layer)
+    }
+
+    /// The layer new strokes are inserted on
+    pub fn current_layer(&self) -> Option<LayerKey> {
+        self.current_layer
+    }
+
+    pub fn set_current_layer(&mut self, layer: LayerKey) {
+        if self.layers.contains_key(layer) {
+            self.current_layer = Some(layer);
+        } else {
+            log::debug!(
+                "set_current_layer() failed, layer with key {:?} does not exist",
+                layer
+            );
+        }
+    }
+
+    /// The layer the stroke is on
+    pub fn layer(&self, key: StrokeKey) -> Option<LayerKey> {
+        self.layer_components
+            .get(key)
+            .and_then(|layer_comp| layer_comp.layer)
+            .filter(|&layer| self.layers.contains_key(layer))
+            .or_else(|| self.layer_order.first().copied())
+    }
+
+    /// Puts the stroke on the layer
+    pub fn set_layer(&mut self, key: StrokeKey, layer: LayerKey) {
+        if !self.strokes.contains_key(key) || !self.layers.contains_key(layer) {
+            log::debug!(
+                "set_layer() failed for stroke with key {:?} and layer with key {:?}",
+                key,
+                layer
+            );
+            return;
+        }
+
+        self.record_change(key, HistoryChange::Modify);
+        self.layer_components
+            .insert(key, LayerComponent { layer: Some(layer) });
+    }
+
+    /// The position of the layer in the layer order, with 0 being the bottom layer
+    pub fn layer_index(&self, layer: LayerKey) -> Option<usize> {
+        self.layer_order.iter().position(|&other| other == layer)
+    }
+
+    /// If the stroke is on a visible layer
+    pub fn layer_visible(&self, key: StrokeKey) -> bool {
+        self.layer(key)
+            .and_then(|layer| self.layers.get(layer))
+            .map_or(true, |layer| layer.visible)
+    }
+
+    /// If the stroke is on a visible and unlocked layer
+    pub fn layer_editable(&self, key: StrokeKey) -> bool {
+        self.layer(key)
+            .and_then(|layer| self.layers.get(layer))
+            .map_or(true, |layer| layer.editable())
+    }
+
+    /// The keys of the strokes on the layer, in chronological order
+    pub fn keys_on_layer(&self, layer: LayerKey) -> Vec<StrokeKey> {
+        self.keys_sorted_chrono()
+            .into_iter()
+            .filter(|&key| self.layer(key) == Some(layer))
+            .collect()
+    }
+
+    /// Creates a new layer above the current layer and makes it the current layer
+    pub fn create_layer(&mut self, name: String) -> LayerKey {
+        let layer = self.layers.insert(Layer::new(name));
+
+        let index = self
+            .current_layer
+            .and_then(|current_layer| self.layer_index(current_layer))
+            .map_or(self.layer_order.len(), |index| index + 1);
+        self.layer_order.insert(index, layer);
+        self.current_layer = Some(layer);
+
+        layer
+    }
+
+    /// Deletes the layer together with its strokes. The last remaining layer can't be deleted.
+    /// Returns false if the layer was not deleted
+    pub fn delete_layer(&mut self, layer: LayerKey) -> bool {
+        let index = match self.layer_index(layer) {
+            Some(index) if self.layer_order.len() > 1 => index,
+            _ => return false,
+        };
+
+        for key in self.keys_on_layer(layer) {
+            self.remove_stroke(key);
+        }
+
+        self.layer_order.remove(index);
+        self.layers.remove(layer);
+
+        if self.current_layer == Some(layer) {
+            self.current_layer = self.layer_order.get(index.saturating_sub(1)).copied();
+        }
+
+        true
+    }
+
+    /// Moves the layer to the index in the layer order, with 0 being the bottom layer
+    pub fn move_layer(&mut self, layer: LayerKey, index: usize) {
+        if let Some(old_index) = self.layer_index(layer) {
+            self.layer_order.remove(old_index);
+            self.layer_order
+                .insert(index.min(self.layer_order.len()), layer);
+        }
+    }
+
+    /// Merges the layer into the layer below it. Returns the key of the layer below, or None if there is none.
+    /// The merged strokes keep being drawn above the strokes of the layer below, but take on its properties
+    pub fn merge_layer_down(&mut self, layer: LayerKey) -> Option<LayerKey> {
+        let index = self.layer_index(layer)?;
+        let below = *self.layer_order.get(index.checked_sub(1)?)?;
+
+        for key in self.keys_on_layer(layer) {
+            self.set_layer(key, below);
+            self.set_chrono_to_last(key);
+        }
+
+        self.layer_order.remove(index);
+        self.layers.remove(layer);
+
+        if self.current_layer == Some(layer) {
+            self.current_layer = Some(below);
+        }
+
+        Some(below)
+    }
+
+    pub fn rename_layer(&mut self, layer: LayerKey, name: String) {
+        if let Some(layer) = self.layers.get_mut(layer) {
+            layer.name = name;
+        }
+    }
+
+    /// Sets the layer visibility. Strokes on hidden layers are deselected
+    pub fn set_layer_visible(&mut self, layer: LayerKey, visible: bool) {
+        if let Some(layer_props) = self.layers.get_mut(layer) {
+            layer_props.visible = visible;
+        }
+        if !visible {
+            self.deselect_layer(layer);
+        }
+    }
+
+    /// Sets the layer lock. Strokes on locked layers are deselected
+    pub fn set_layer_locked(&mut self, layer: LayerKey, locked: bool) {
+        if let Some(layer_props) = self.layers.get_mut(layer) {
+            layer_props.locked = locked;
+        }
+        if locked {
+            self.deselect_layer(layer);
+        }
+    }
+
+    pub fn set_layer_opacity(&mut self, layer: LayerKey, opacity: f64) {
+        if let Some(layer) = self.layers.get_mut(layer) {
+            layer.opacity = opacity.clamp(Layer::OPACITY_MIN, Layer::OPACITY_MAX);
+        }
+    }
+
+    /// Moves the selected strokes onto the layer. They keep their chronological order
+    pub fn move_selection_to_layer(&mut self, layer: LayerKey) {
+        for key in self.selection_keys_as_rendered() {
+            self.set_layer(key, layer);
+        }
+    }
+
+    fn deselect_layer(&mut self, layer: LayerKey) {
+        for key in self.keys_on_layer(layer) {
+            if let Some(selection_comp) = self.selection_components.get_mut(key) {
+                selection_comp.selected = false;
+            }
+        }
+    }
+
+    /// Repairs the layer table, so that there is always at least one layer, the order contains every layer once,
+    /// the current layer exists and every stroke is tagged with an existing layer. Needed after importing a state
+    pub(super) fn ensure_valid_layers(&mut self) {
+        let mut layer_order = Vec::with_capacity(self.layer_order.len());
+        for layer in self.layer_order.drain(..) {
+            if self.layers.contains_key(layer) && !layer_order.contains(&layer) {
+                layer_order.push(layer);
+            }
+        }
+        self.layer_order = layer_order;
+
+        let unordered = self
+            .layers
+            .keys()
+            .filter(|layer| !self.layer_order.contains(layer))
+            .collect::<Vec<LayerKey>>();
+        self.layer_order.extend(unordered);
+
+        if self.layer_order.is_empty() {
+            let layer = self.layers.insert(Layer::default());
+            self.layer_order.push(layer);
+        }
+
+        if !self.current_layer.map_or(false, |current_layer| {
+            self.layers.contains_key(current_layer)
+        }) {
+            self.current_layer = self.layer_order.last().copied();
+        }
+
+        // Strokes from files without layers are put on the bottom layer
+        let untagged = self
+            .strokes
+            .keys()
+            .filter(|&key| {
+                !self
+                    .layer_components
+                    .get(key)
+                    .and_then(|layer_comp| layer_comp.layer)
+                    .map_or(false, |layer| self.layers.contains_key(layer))
+            })
+            .collect::<Vec<StrokeKey>>();
+        for key in untagged {
+            self.set_layer(key, self.layer_order[0]);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use super::history::HistoryChange;
+use super::{StrokeKey, StrokesState};
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// A link attached to a stroke
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "stroke_link")]
+pub enum StrokeLink {
+    /// An external URL, opened by the UI
+    #[serde(rename = "url")]
+    Url(String),
+    /// A page of the sheet, as index into `Sheet::pages()`
+    #[serde(rename = "page")]
+    Page(u32),
+}
+
+impl StrokeLink {
+    /// The prefix of the text form of page links. Pages are counted from 1 in the text form
+    pub const PAGE_PREFIX: &'static str = "#page=";
+}
+
+impl FromStr for StrokeLink {
+    type Err = anyhow::Error;
+
+    /// Parses `#page=<n>`, with n starting at 1, as page link and everything with an URI scheme as URL
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if let Some(page) = s.strip_prefix(Self::PAGE_PREFIX) {
+            let page = page.parse::<u32>()?;
+            if page == 0 {
+                return Err(anyhow::anyhow!(
+                    "parsing StrokeLink from `{}` failed, pages start at 1",
+                    s
+                ));
+            }
+
+            Ok(Self::Page(page - 1))
+        } else if s.split_once(':').map_or(false, |(scheme, rest)| {
+            !scheme.is_empty()
+                && !rest.is_empty()
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+        }) {
+            Ok(Self::Url(String::from(s)))
+        } else {
+            Err(anyhow::anyhow!(
+                "parsing StrokeLink from `{}` failed, neither a page link nor an URL",
+                s
+            ))
+        }
+    }
+}
+
+impl fmt::Display for StrokeLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Url(url) => write!(f, "{}", url),
+            Self::Page(page) => write!(f, "{}{}", Self::PAGE_PREFIX, page + 1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "link_component")]
+pub struct LinkComponent {
+    #[serde(rename = "link")]
+    pub link: Option<StrokeLink>,
+}
+
+impl Default for LinkComponent {
+    fn default() -> Self {
+        Self { link: None }
+    }
+}
+
+/// Systems that are related to the links attached to strokes.
+impl StrokesState {
+    /// The distance a position can be away from a stroke to still hit its link
+    pub const LINK_TOLERANCE: f64 = 4.0;
+
+    pub fn link(&self, key: StrokeKey) -> Option<&StrokeLink> {
+        self.link_components
+            .get(key)
+            .and_then(|link_comp| link_comp.link.as_ref())
+    }
+
+    /// Attaches the link to the stroke, or removes its link when None
+    pub fn set_link(&mut self, key: StrokeKey, link: Option<StrokeLink>) {
+        if !self.strokes.contains_key(key) {
+            log::debug!(
+                "set_link() failed, stroke with key {:?} does not exist",
+                key
+            );
+            return;
+        }
+
+        self.record_change(key, HistoryChange::Modify);
+        // Inserting, because strokes from files without links don't have a link component yet
+        self.link_components.insert(key, LinkComponent { link });
+    }
+
+    /// Attaches the link to all selected strokes, or removes their links when None
+    pub fn set_selection_link(&mut self, link: Option<StrokeLink>) {
+        for key in self.selection_keys_as_rendered() {
+            self.set_link(key, link.clone());
+        }
+    }
+
+    /// The link of the topmost linked stroke at the position, which is not trashed and on a visible layer
+    pub fn link_at_position(&self, pos: na::Vector2<f64>) -> Option<(StrokeKey, StrokeLink)> {
+        let mut keys = self.keys_as_rendered();
+        keys.append(&mut self.selection_keys_as_rendered());
+
+        keys.into_iter().rev().find_map(|key| {
+            let link = self.link(key)?;
+            if self.trashed(key).unwrap_or(true) {
+                return None;
+            }
+
+            if self.strokes.get(key)?.hittest(pos, Self::LINK_TOLERANCE) {
+                Some((key, link.clone()))
+            } else {
+                None
+            }
+        })
+    }
+}
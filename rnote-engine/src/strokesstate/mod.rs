@@ -1,31 +1,76 @@
+pub mod audionotes;
 pub mod chrono_comp;
+pub mod group_comp;
+pub mod history;
+pub mod layer_comp;
+pub mod link_comp;
+pub mod recording;
 pub mod render_comp;
+pub mod renderjobs;
+pub mod replay;
 pub mod selection_comp;
+pub mod spatialindex;
+pub mod tag_comp;
+pub mod textsearch;
+#[cfg(feature = "ui")]
+pub mod tilecache;
 pub mod trash_comp;
 
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::sync::{Arc, RwLock};
 
+use audionotes::AudioNote;
 use chrono_comp::ChronoComponent;
+use group_comp::{Group, GroupComponent, GroupKey};
+use history::{History, HistoryChange};
+use layer_comp::{Layer, LayerComponent, LayerKey};
+use link_comp::LinkComponent;
 use p2d::query::PointQuery;
+use recording::SessionRecording;
 use render_comp::RenderComponent;
+use renderjobs::{RenderJob, RenderJobs};
 use selection_comp::SelectionComponent;
+use spatialindex::SpatialIndex;
+use tag_comp::{TagComponent, TagFilter};
+#[cfg(feature = "ui")]
+use tilecache::TileCache;
 use trash_comp::TrashComponent;
 
+use crate::collab::ChangedKeys;
+use crate::compose::color::Color;
 use crate::compose::geometry::{self, AABBHelpers};
 use crate::compose::transformable::Transformable;
 use crate::drawbehaviour::DrawBehaviour;
 use crate::pens::shaper::Shaper;
+use crate::pens::simplification::{self, SimplificationOptions};
 use crate::pens::tools::DragProximityTool;
 use crate::pens::PenStyle;
+use crate::perfcounters::PerfCounters;
+use crate::recognition::handwriting::{Ink, RecognizedText};
 use crate::render::{self, Renderer};
+use crate::sheet::container::ChangedChunks;
+use crate::sheet::thumbnails::StrokeRevisions;
 use crate::strokes::bitmapimage::BitmapImage;
+use crate::strokes::brushstroke::{BrushStroke, BrushStrokeStyle};
+use crate::strokes::checkboxstroke::CheckboxStroke;
 use crate::strokes::element::Element;
-use crate::strokes::strokestyle::StrokeStyle;
+use crate::strokes::mathstroke::MathStroke;
+use crate::strokes::pdfimport::ImportedPdf;
+#[cfg(feature = "ui")]
+use crate::strokes::pdfimport::{PdfImportOptions, PdfImportPagesType};
+use crate::strokes::strokestyle::{StrokeOptionsModification, StrokeStyle};
+use crate::strokes::textstroke::{TextStroke, TextStyle};
 use crate::strokes::vectorimage::VectorImage;
 use crate::surfaceflags::SurfaceFlags;
+use crate::utils;
 
 use p2d::bounding_volume::{BoundingSphere, BoundingVolume, AABB};
+#[cfg(not(target_arch = "wasm32"))]
 use rayon::iter::{ParallelBridge, ParallelIterator};
+use rnote_fileformats::isfformat::IsfFile;
+use rnote_fileformats::FileFormatLoader;
 use serde::{Deserialize, Serialize};
 use slotmap::{HopSlotMap, SecondaryMap};
 
@@ -37,22 +82,35 @@ The Entities are the StrokeKey's, which represent a stroke. There are different
     * 'selection_components': Hold state wether the strokes are selected
     * 'chrono_components': Hold state about the time, chronological ordering
     * 'render_components': Hold state about the current rendering of the strokes.
+    * 'group_components': Hold the group the strokes are in. The groups themselves are stored in 'groups', and can be nested.
+    * 'layer_components': Hold the layer the strokes are on. The layer table is stored in 'layers', ordered by 'layer_order'.
+    * 'link_components': Hold the URL or page link attached to the strokes.
+    * 'tag_components': Hold the tags assigned to the strokes, which the drawn strokes can be filtered by.
 
 The systems are implemented as methods on StrokesState, loosely categorized to the different components (but often modify others as well).
 Most systems take a key or a slice of keys, and iterate with them over the different components.
 There also is a different category of methods which return filtered keys, e.g. `.keys_sorted_chrono` returns the keys in chronological ordering,
     `.stoke_keys_in_order_rendering` returns keys in the order which they should be rendered.
+Systems which modify a stroke or its trash and layer components record its previous state into the undo history first, see `history`.
 */
 
 #[derive(Debug, Clone)]
 pub enum StateTask {
+    /// The images are dropped when their job was cancelled in the meantime, see `renderjobs`
     UpdateStrokeWithImages {
         key: StrokeKey,
         images: Vec<render::Image>,
+        job: Option<RenderJob>,
     },
     AppendImagesToStroke {
         key: StrokeKey,
         images: Vec<render::Image>,
+        job: Option<RenderJob>,
+    },
+    /// The images of the last elements of a stroke which is being drawn, appended to its incremental rendering
+    AppendElemsImagesToStroke {
+        key: StrokeKey,
+        images: Vec<render::Image>,
     },
     InsertStroke {
         stroke: StrokeStyle,
@@ -60,6 +118,16 @@ pub enum StateTask {
     Quit,
 }
 
+/// The strokes in which colors are replaced, see `StrokesState::replace_color()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorReplaceScope {
+    Document,
+    Selection,
+    Layer(LayerKey),
+}
+
+/// No threads can be spawned on wasm32, there the jobs run on the current thread (see `StrokesState::spawn_job()`)
+#[cfg(not(target_arch = "wasm32"))]
 pub fn default_threadpool() -> rayon::ThreadPool {
     rayon::ThreadPoolBuilder::default()
         .build()
@@ -73,11 +141,21 @@ slotmap::new_key_type! {
     pub struct StrokeKey;
 }
 
+/// The components of a stroke which describe it, without the trash, selection and render components which describe its current state
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StrokeComponents {
+    pub(crate) chrono: ChronoComponent,
+    pub(crate) group: GroupComponent,
+    pub(crate) layer: LayerComponent,
+    pub(crate) link: LinkComponent,
+    pub(crate) tag: TagComponent,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(default, rename = "strokes_state")]
 pub struct StrokesState {
     // Components
-    #[serde(rename = "strokes")]
+    #[serde(rename = "strokes", with = "crate::sheet::container::chunked_strokes")]
     strokes: HopSlotMap<StrokeKey, StrokeStyle>,
     #[serde(rename = "trash_components")]
     trash_components: SecondaryMap<StrokeKey, TrashComponent>,
@@ -87,44 +165,133 @@ pub struct StrokesState {
     chrono_components: SecondaryMap<StrokeKey, ChronoComponent>,
     #[serde(rename = "render_components")]
     render_components: SecondaryMap<StrokeKey, RenderComponent>,
+    #[serde(rename = "group_components")]
+    group_components: SecondaryMap<StrokeKey, GroupComponent>,
+
+    #[serde(rename = "layer_components")]
+    layer_components: SecondaryMap<StrokeKey, LayerComponent>,
+    #[serde(rename = "link_components")]
+    link_components: SecondaryMap<StrokeKey, LinkComponent>,
+    #[serde(rename = "tag_components")]
+    tag_components: SecondaryMap<StrokeKey, TagComponent>,
+
+    #[serde(rename = "groups")]
+    groups: HopSlotMap<GroupKey, Group>,
+    #[serde(rename = "layers")]
+    layers: HopSlotMap<LayerKey, Layer>,
+    /// The layers from the bottom to the top
+    #[serde(rename = "layer_order")]
+    layer_order: Vec<LayerKey>,
+    #[serde(rename = "current_layer")]
+    current_layer: Option<LayerKey>,
+    /// The imported PDFs, in the order they were imported
+    #[serde(rename = "imported_pdfs")]
+    imported_pdfs: Vec<ImportedPdf>,
+    /// The recording of the session, see `recording`
+    #[serde(rename = "session_recording")]
+    session_recording: SessionRecording,
+    /// The recorded audio notes, see `audionotes`
+    #[serde(rename = "audio_notes")]
+    audio_notes: Vec<AudioNote>,
 
     // Other state
     /// value is equal chrono_component of the newest inserted or modified stroke.
     #[serde(rename = "chrono_counter")]
     chrono_counter: u32,
+    /// The chunks of the strokes which changed since the last save, to only serialize them again. See `sheet::container`
+    #[serde(skip)]
+    changed_chunks: ChangedChunks,
+    /// The strokes which changed since a collaboration session last synced them, see `collab`
+    #[serde(skip)]
+    collab_changes: ChangedKeys,
+    /// The revisions of the strokes, for the signatures of the page thumbnails. See `sheet::thumbnails`
+    #[serde(skip)]
+    stroke_revisions: StrokeRevisions,
+    /// The undo history, see `history`
+    #[serde(skip)]
+    history: History,
+    /// The rendering of the strokes cached in tiles, see `tilecache`
+    #[cfg(feature = "ui")]
+    #[serde(skip)]
+    tile_cache: RefCell<TileCache>,
+    /// The filter for the drawn strokes by their tags, see `tag_comp`. Part of the view, so it is not saved
+    #[serde(skip)]
+    tag_filter: Option<TagFilter>,
+    /// The jobs rendering the strokes on the threadpool, see `renderjobs`
+    #[serde(skip)]
+    render_jobs: RenderJobs,
+    /// The bounds of the strokes indexed for spatial queries, see `spatialindex`
+    #[serde(skip)]
+    spatial_index: RefCell<SpatialIndex>,
+    /// The counters of the rendering performance, see `perfcounters`
+    #[serde(skip)]
+    perf_counters: Arc<PerfCounters>,
 
     #[serde(skip)]
     pub tasks_tx: futures::channel::mpsc::UnboundedSender<StateTask>,
     /// To be taken out into a loop which processes the receiver stream. The received tasks should be processed with process_received_task()
     #[serde(skip)]
     pub tasks_rx: Option<futures::channel::mpsc::UnboundedReceiver<StateTask>>,
+    #[cfg(not(target_arch = "wasm32"))]
     #[serde(skip, default = "default_threadpool")]
     pub threadpool: rayon::ThreadPool,
 }
 
 impl Default for StrokesState {
     fn default() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
         let threadpool = default_threadpool();
 
         let (tasks_tx, tasks_rx) = futures::channel::mpsc::unbounded::<StateTask>();
 
+        let mut layers = HopSlotMap::with_key();
+        let layer = layers.insert(Layer::default());
+
         Self {
             strokes: HopSlotMap::with_key(),
             trash_components: SecondaryMap::new(),
             selection_components: SecondaryMap::new(),
             chrono_components: SecondaryMap::new(),
             render_components: SecondaryMap::new(),
+            group_components: SecondaryMap::new(),
+            layer_components: SecondaryMap::new(),
+            link_components: SecondaryMap::new(),
+            tag_components: SecondaryMap::new(),
+
+            groups: HopSlotMap::with_key(),
+            layers,
+            layer_order: vec![layer],
+            current_layer: Some(layer),
+            imported_pdfs: vec![],
+            session_recording: SessionRecording::default(),
+            audio_notes: Vec::new(),
 
             chrono_counter: 0,
+            changed_chunks: ChangedChunks::default(),
+            collab_changes: ChangedKeys::default(),
+            stroke_revisions: StrokeRevisions::default(),
+            history: History::default(),
+            #[cfg(feature = "ui")]
+            tile_cache: RefCell::new(TileCache::default()),
+            tag_filter: None,
+            render_jobs: RenderJobs::default(),
+            spatial_index: RefCell::new(SpatialIndex::default()),
+            perf_counters: Arc::new(PerfCounters::default()),
 
             tasks_tx,
             tasks_rx: Some(tasks_rx),
+            #[cfg(not(target_arch = "wasm32"))]
             threadpool,
         }
     }
 }
 
 impl StrokesState {
+    /// The ratio of the cap height to the font size, used to match recognized text to the size of the handwriting
+    pub const RECOGNIZED_CAP_HEIGHT_RATIO: f64 = 0.7;
+    /// The max width of recognized text relative to the width of the handwriting, so that it doesn't wrap early
+    pub const RECOGNIZED_WIDTH_FACTOR: f64 = 1.5;
+
     pub fn new() -> Self {
         Self::default()
     }
@@ -136,7 +303,76 @@ impl StrokesState {
         self.selection_components = strokes_state.selection_components;
         self.chrono_components = strokes_state.chrono_components;
         self.render_components = strokes_state.render_components;
+        self.group_components = strokes_state.group_components;
+        self.layer_components = strokes_state.layer_components;
+        self.link_components = strokes_state.link_components;
+        self.tag_components = strokes_state.tag_components;
+        self.groups = strokes_state.groups;
+        self.layers = strokes_state.layers;
+        self.layer_order = strokes_state.layer_order;
+        self.current_layer = strokes_state.current_layer;
+        self.imported_pdfs = strokes_state.imported_pdfs;
+        self.session_recording = strokes_state.session_recording;
+        self.audio_notes = strokes_state.audio_notes;
         self.chrono_counter = strokes_state.chrono_counter;
+        self.changed_chunks.mark_all();
+        self.collab_changes.mark_all();
+        self.stroke_revisions.mark_all();
+        self.spatial_index.get_mut().mark_all();
+
+        self.ensure_valid_layers();
+        self.history.clear();
+        #[cfg(feature = "ui")]
+        self.tile_cache.get_mut().clear();
+        self.render_jobs.cancel_all();
+    }
+
+    /// Runs the job on the threadpool. On wasm32 it runs on the current thread before returning,
+    /// so the tasks it sends are received after it finished
+    pub(crate) fn spawn_job<F>(&self, job: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.threadpool.spawn(job);
+        #[cfg(target_arch = "wasm32")]
+        job();
+    }
+
+    /// The jobs rendering the strokes on the threadpool
+    pub fn render_jobs(&self) -> &RenderJobs {
+        &self.render_jobs
+    }
+
+    /// The counters of the rendering performance, e.g. for displaying them in a debug overlay
+    pub fn perf_counters(&self) -> &PerfCounters {
+        &self.perf_counters
+    }
+
+    /// The number of strokes, including the trashed ones
+    pub fn strokes_len(&self) -> usize {
+        self.strokes.len()
+    }
+
+    /// The chunks of the strokes which changed since the last save
+    pub fn changed_chunks(&self) -> &ChangedChunks {
+        &self.changed_chunks
+    }
+
+    /// Called after the strokes were written into or read from a container
+    pub fn clear_changed_chunks(&mut self) {
+        self.changed_chunks.clear();
+    }
+
+    /// Starts or stops tracking which strokes change, for syncing them in a collaboration session
+    pub fn set_track_collab_changes(&mut self, track: bool) {
+        self.collab_changes = ChangedKeys::new(track);
+    }
+
+    /// Takes the strokes which changed since the last call
+    pub fn take_collab_changes(&mut self) -> ChangedKeys {
+        let tracking = self.collab_changes.tracking();
+        std::mem::replace(&mut self.collab_changes, ChangedKeys::new(tracking))
     }
 
     /// processes the received task from tasks_rx.
@@ -150,18 +386,28 @@ impl StrokesState {
         let mut surface_flags = SurfaceFlags::default();
 
         match task {
-            StateTask::UpdateStrokeWithImages { key, images } => {
+            // The images of cancelled jobs are outdated
+            StateTask::UpdateStrokeWithImages { job: Some(job), .. }
+            | StateTask::AppendImagesToStroke { job: Some(job), .. }
+                if job.cancelled() => {}
+            StateTask::UpdateStrokeWithImages { key, images, .. } => {
                 self.regenerate_rendering_with_images(key, images, zoom);
 
                 surface_flags.redraw = true;
                 surface_flags.sheet_changed = true;
             }
-            StateTask::AppendImagesToStroke { key, images } => {
+            StateTask::AppendImagesToStroke { key, images, .. } => {
                 self.append_images_to_rendering(key, images, zoom);
 
                 surface_flags.redraw = true;
                 surface_flags.sheet_changed = true;
             }
+            StateTask::AppendElemsImagesToStroke { key, images } => {
+                self.append_elems_images_to_rendering(key, images, zoom);
+
+                surface_flags.redraw = true;
+                surface_flags.sheet_changed = true;
+            }
             StateTask::InsertStroke { stroke } => match stroke {
                 StrokeStyle::BrushStroke(brushstroke) => {
                     let inserted = self.insert_stroke(StrokeStyle::BrushStroke(brushstroke));
@@ -210,6 +456,43 @@ impl StrokesState {
                     surface_flags.sheet_changed = true;
                     surface_flags.selection_changed = true;
                 }
+                StrokeStyle::TextStroke(textstroke) => {
+                    let inserted = self.insert_stroke(StrokeStyle::TextStroke(textstroke));
+
+                    self.regenerate_rendering_for_stroke_threaded(inserted, renderer, zoom);
+
+                    surface_flags.redraw = true;
+                    surface_flags.resize = true;
+                    surface_flags.sheet_changed = true;
+                }
+                StrokeStyle::MathStroke(mathstroke) => {
+                    let inserted = self.insert_stroke(StrokeStyle::MathStroke(mathstroke));
+
+                    self.regenerate_rendering_for_stroke_threaded(inserted, renderer, zoom);
+
+                    surface_flags.redraw = true;
+                    surface_flags.resize = true;
+                    surface_flags.sheet_changed = true;
+                }
+                StrokeStyle::CheckboxStroke(checkboxstroke) => {
+                    let inserted = self.insert_stroke(StrokeStyle::CheckboxStroke(checkboxstroke));
+
+                    self.regenerate_rendering_for_stroke_threaded(inserted, renderer, zoom);
+
+                    surface_flags.redraw = true;
+                    surface_flags.resize = true;
+                    surface_flags.sheet_changed = true;
+                }
+                StrokeStyle::ExtensionStroke(extensionstroke) => {
+                    let inserted =
+                        self.insert_stroke(StrokeStyle::ExtensionStroke(extensionstroke));
+
+                    self.regenerate_rendering_for_stroke_threaded(inserted, renderer, zoom);
+
+                    surface_flags.redraw = true;
+                    surface_flags.resize = true;
+                    surface_flags.sheet_changed = true;
+                }
             },
             StateTask::Quit => {
                 surface_flags.quit = true;
@@ -222,6 +505,8 @@ impl StrokesState {
     pub fn insert_stroke(&mut self, stroke: StrokeStyle) -> StrokeKey {
         let key = self.strokes.insert(stroke);
         self.chrono_counter += 1;
+        self.changed_chunks.mark(key);
+        self.spatial_index.get_mut().mark(key);
 
         let mut render_comp = RenderComponent::default();
         // set flag for rendering regeneration
@@ -231,21 +516,102 @@ impl StrokesState {
         self.selection_components
             .insert(key, SelectionComponent::default());
         self.render_components.insert(key, render_comp);
+        self.group_components.insert(key, GroupComponent::default());
+        self.layer_components.insert(
+            key,
+            LayerComponent {
+                layer: self.current_layer,
+            },
+        );
+        self.link_components.insert(key, LinkComponent::default());
+        self.tag_components.insert(key, TagComponent::default());
         self.chrono_components
             .insert(key, ChronoComponent::new(self.chrono_counter));
+        self.record_insert(key);
 
         key
     }
 
     pub fn remove_stroke(&mut self, key: StrokeKey) -> Option<StrokeStyle> {
+        self.record_change(key, HistoryChange::Remove);
         self.trash_components.remove(key);
         self.selection_components.remove(key);
         self.chrono_components.remove(key);
         self.render_components.remove(key);
+        self.group_components.remove(key);
+        self.layer_components.remove(key);
+        self.link_components.remove(key);
+        self.tag_components.remove(key);
+        self.changed_chunks.mark(key);
+        self.spatial_index.get_mut().mark(key);
 
         self.strokes.remove(key)
     }
 
+    /// Replaces the stroke for the key, keeping its components. Returns the replaced stroke
+    pub fn replace_stroke(&mut self, key: StrokeKey, stroke: StrokeStyle) -> Option<StrokeStyle> {
+        self.record_change(key, HistoryChange::Modify);
+        let replaced = std::mem::replace(self.strokes.get_mut(key)?, stroke);
+        self.changed_chunks.mark(key);
+        self.spatial_index.get_mut().mark(key);
+
+        // set flag for rendering regeneration
+        if let Some(render_comp) = self.render_components.get_mut(key) {
+            render_comp.regenerate_flag = true;
+        }
+
+        Some(replaced)
+    }
+
+    /// The components of the stroke, e.g. to copy them onto other strokes. Missing components are returned as their default
+    pub(crate) fn stroke_components(&self, key: StrokeKey) -> Option<StrokeComponents> {
+        if !self.strokes.contains_key(key) {
+            return None;
+        }
+
+        Some(StrokeComponents {
+            chrono: self.chrono_components.get(key).copied().unwrap_or_default(),
+            group: self.group_components.get(key).copied().unwrap_or_default(),
+            layer: self.layer_components.get(key).copied().unwrap_or_default(),
+            link: self.link_components.get(key).cloned().unwrap_or_default(),
+            tag: self.tag_components.get(key).cloned().unwrap_or_default(),
+        })
+    }
+
+    /// Replaces the components of the stroke. Groups and layers which were removed in the meantime are left out
+    pub(crate) fn set_stroke_components(&mut self, key: StrokeKey, components: StrokeComponents) {
+        if !self.strokes.contains_key(key) {
+            log::debug!(
+                "set_stroke_components() failed, stroke with key {:?} does not exist",
+                key
+            );
+            return;
+        }
+
+        self.record_change(key, HistoryChange::Modify);
+        self.chrono_components.insert(key, components.chrono);
+        self.group_components.insert(
+            key,
+            GroupComponent {
+                group: components
+                    .group
+                    .group
+                    .filter(|&group| self.groups.contains_key(group)),
+            },
+        );
+        self.layer_components.insert(
+            key,
+            LayerComponent {
+                layer: components
+                    .layer
+                    .layer
+                    .filter(|&layer| self.layers.contains_key(layer)),
+            },
+        );
+        self.set_link(key, components.link.link);
+        self.set_tags(key, components.tag.tags.iter().map(|tag| tag.as_str()));
+    }
+
     pub fn add_to_brushstroke(
         &mut self,
         key: StrokeKey,
@@ -253,13 +619,60 @@ impl StrokesState {
         renderer: Arc<RwLock<Renderer>>,
         zoom: f64,
     ) {
+        self.record_change(key, HistoryChange::Modify);
         if let Some(StrokeStyle::BrushStroke(ref mut brushstroke)) = self.strokes.get_mut(key) {
             brushstroke.push_elem(element);
+            self.changed_chunks.mark(key);
+            self.spatial_index.get_mut().mark(key);
         }
 
         self.append_rendering_new_elem_threaded(key, renderer, zoom);
     }
 
+    /// Marks the textured brush strokes to keep the segment seeds of files saved before file format version 16, see `TexturedOptions::legacy_seeding`
+    pub fn keep_legacy_textured_seeding(&mut self) {
+        for stroke in self.strokes.values_mut() {
+            if let StrokeStyle::BrushStroke(BrushStroke {
+                style: BrushStrokeStyle::Textured { options },
+                ..
+            }) = stroke
+            {
+                options.legacy_seeding = true;
+            }
+        }
+    }
+
+    /// Simplifies the elements of the finished brush stroke, see `simplification`. Its rendering needs to be regenerated afterwards
+    pub fn simplify_brushstroke(&mut self, key: StrokeKey, options: &SimplificationOptions) {
+        if !options.enabled {
+            return;
+        }
+
+        let simplified = match self.strokes.get(key) {
+            Some(StrokeStyle::BrushStroke(brushstroke)) => {
+                let simplified = simplification::simplify_elements(&brushstroke.elements, options);
+
+                if simplified.len() == brushstroke.elements.len() {
+                    return;
+                }
+                simplified
+            }
+            _ => return,
+        };
+
+        self.record_change(key, HistoryChange::Modify);
+        if let Some(StrokeStyle::BrushStroke(ref mut brushstroke)) = self.strokes.get_mut(key) {
+            brushstroke.elements = simplified;
+            brushstroke.update_geometry();
+            self.changed_chunks.mark(key);
+            self.spatial_index.get_mut().mark(key);
+        }
+
+        if let Some(render_comp) = self.render_components.get_mut(key) {
+            render_comp.regenerate_flag = true;
+        }
+    }
+
     pub fn add_to_shapestroke(
         &mut self,
         key: StrokeKey,
@@ -268,29 +681,277 @@ impl StrokesState {
         renderer: Arc<RwLock<Renderer>>,
         zoom: f64,
     ) {
+        self.record_change(key, HistoryChange::Modify);
         if let Some(StrokeStyle::ShapeStroke(ref mut shapestroke)) = self.strokes.get_mut(key) {
             shapestroke.update_shape(shaper, element);
+            self.changed_chunks.mark(key);
+            self.spatial_index.get_mut().mark(key);
         }
 
         self.append_rendering_new_elem_threaded(key, renderer, zoom);
     }
 
-    /// Clears every stroke and every component
+    pub fn get_textstroke(&self, key: StrokeKey) -> Option<&TextStroke> {
+        if let Some(StrokeStyle::TextStroke(textstroke)) = self.strokes.get(key) {
+            Some(textstroke)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_textstroke_mut(&mut self, key: StrokeKey) -> Option<&mut TextStroke> {
+        self.record_change(key, HistoryChange::Modify);
+        if let Some(StrokeStyle::TextStroke(textstroke)) = self.strokes.get_mut(key) {
+            self.changed_chunks.mark(key);
+            self.spatial_index.get_mut().mark(key);
+            Some(textstroke)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_bitmapimage(&self, key: StrokeKey) -> Option<&BitmapImage> {
+        if let Some(StrokeStyle::BitmapImage(bitmapimage)) = self.strokes.get(key) {
+            Some(bitmapimage)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_bitmapimage_mut(&mut self, key: StrokeKey) -> Option<&mut BitmapImage> {
+        self.record_change(key, HistoryChange::Modify);
+        if let Some(StrokeStyle::BitmapImage(bitmapimage)) = self.strokes.get_mut(key) {
+            self.changed_chunks.mark(key);
+            self.spatial_index.get_mut().mark(key);
+            Some(bitmapimage)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mathstroke(&self, key: StrokeKey) -> Option<&MathStroke> {
+        if let Some(StrokeStyle::MathStroke(mathstroke)) = self.strokes.get(key) {
+            Some(mathstroke)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mathstroke_mut(&mut self, key: StrokeKey) -> Option<&mut MathStroke> {
+        self.record_change(key, HistoryChange::Modify);
+        if let Some(StrokeStyle::MathStroke(mathstroke)) = self.strokes.get_mut(key) {
+            self.changed_chunks.mark(key);
+            self.spatial_index.get_mut().mark(key);
+            Some(mathstroke)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_checkboxstroke(&self, key: StrokeKey) -> Option<&CheckboxStroke> {
+        if let Some(StrokeStyle::CheckboxStroke(checkboxstroke)) = self.strokes.get(key) {
+            Some(checkboxstroke)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_checkboxstroke_mut(&mut self, key: StrokeKey) -> Option<&mut CheckboxStroke> {
+        self.record_change(key, HistoryChange::Modify);
+        if let Some(StrokeStyle::CheckboxStroke(checkboxstroke)) = self.strokes.get_mut(key) {
+            self.changed_chunks.mark(key);
+            self.spatial_index.get_mut().mark(key);
+            Some(checkboxstroke)
+        } else {
+            None
+        }
+    }
+
+    /// The topmost checkbox hit by the position, which is not trashed and on an editable layer
+    pub fn checkbox_at_pos(&self, pos: na::Vector2<f64>, tolerance: f64) -> Option<StrokeKey> {
+        self.keys_as_rendered().into_iter().rev().find(|&key| {
+            !self.trashed(key).unwrap_or(true)
+                && self.layer_editable(key)
+                && self
+                    .get_checkboxstroke(key)
+                    .map_or(false, |checkboxstroke| {
+                        checkboxstroke.hittest(pos, tolerance)
+                    })
+        })
+    }
+
+    /// Checks or unchecks the checkbox, and flags it for regenerating its rendering. Returns whether it is checked now, or None if it is not a checkbox
+    pub fn toggle_checkbox(&mut self, key: StrokeKey) -> Option<bool> {
+        self.get_checkboxstroke(key)?;
+        let checked = self.get_checkboxstroke_mut(key)?.toggle();
+        self.update_geometry_for_stroke(key);
+
+        Some(checked)
+    }
+
+    /// The topmost text hit by the position, which is not trashed and on an editable layer
+    pub fn text_at_pos(&self, pos: na::Vector2<f64>, tolerance: f64) -> Option<StrokeKey> {
+        self.keys_as_rendered().into_iter().rev().find(|&key| {
+            !self.trashed(key).unwrap_or(true)
+                && self.layer_editable(key)
+                && self
+                    .get_textstroke(key)
+                    .map_or(false, |textstroke| textstroke.hittest(pos, tolerance))
+        })
+    }
+
+    /// The color of the topmost stroke at the position, which is not trashed and on a visible layer. Shapes give their stroke color on the outline
+    /// and their fill color inside it, images the color of their rendered pixels. Transparent colors are skipped to the stroke below
+    pub fn color_at_pos(&self, pos: na::Vector2<f64>, tolerance: f64) -> Option<Color> {
+        let mut keys = self.keys_as_rendered();
+        keys.append(&mut self.selection_keys_as_rendered());
+
+        keys.into_iter().rev().find_map(|key| {
+            if self.trashed(key).unwrap_or(true) || !self.layer_visible(key) {
+                return None;
+            }
+            let stroke = self.strokes.get(key)?;
+            if !stroke.hittest(pos, tolerance) {
+                return None;
+            }
+
+            let color = match stroke {
+                StrokeStyle::BrushStroke(brushstroke) => brushstroke.stroke_color(),
+                StrokeStyle::ShapeStroke(shapestroke) => {
+                    if shapestroke.hits_outline(pos, tolerance) {
+                        shapestroke.stroke_color()
+                    } else {
+                        shapestroke.fill_color()
+                    }
+                }
+                StrokeStyle::TextStroke(textstroke) => Some(textstroke.style.color),
+                StrokeStyle::MathStroke(mathstroke) => Some(mathstroke.color),
+                StrokeStyle::CheckboxStroke(checkboxstroke) => Some(checkboxstroke.color),
+                StrokeStyle::VectorImage(_)
+                | StrokeStyle::BitmapImage(_)
+                | StrokeStyle::ExtensionStroke(_) => self
+                    .render_components
+                    .get(key)?
+                    .images
+                    .iter()
+                    .rev()
+                    .find_map(|image| image.color_at(pos)),
+            };
+
+            color.filter(|color| color.a > 0.0)
+        })
+    }
+
+    /// The ink of the brush strokes for the keys, which handwriting is recognized from. Other strokes are skipped
+    pub fn ink_for_keys(&self, keys: &[StrokeKey]) -> Ink {
+        keys.iter()
+            .filter_map(|&key| match self.strokes.get(key) {
+                Some(StrokeStyle::BrushStroke(brushstroke)) => Some(
+                    brushstroke
+                        .elements
+                        .iter()
+                        .map(|element| element.inputdata.pos())
+                        .collect::<Vec<na::Vector2<f64>>>(),
+                ),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Replaces the brush strokes for the keys with a text of the recognized text, placed at their position.
+    /// The font size is chosen to match the height of the handwritten lines. The brush strokes are trashed.
+    /// Returns the key of the new text, or None if the text or the strokes are empty
+    pub fn replace_w_recognized_text(
+        &mut self,
+        keys: &[StrokeKey],
+        recognized: &RecognizedText,
+        mut style: TextStyle,
+    ) -> Option<StrokeKey> {
+        let keys = keys
+            .iter()
+            .copied()
+            .filter(|&key| matches!(self.strokes.get(key), Some(StrokeStyle::BrushStroke(_))))
+            .collect::<Vec<StrokeKey>>();
+
+        if recognized.text.trim().is_empty() {
+            return None;
+        }
+        let bounds = self.gen_bounds(&keys)?;
+
+        let n_lines = recognized.text.lines().count().max(1);
+        // Handwritten block capitals are about as high as the cap height of the font
+        style.font_size =
+            (bounds.extents()[1] / n_lines as f64 / Self::RECOGNIZED_CAP_HEIGHT_RATIO)
+                .clamp(TextStyle::FONT_SIZE_MIN, TextStyle::FONT_SIZE_MAX);
+
+        let textstroke = TextStroke::new(
+            recognized.text.clone(),
+            na::vector![bounds.mins[0], bounds.mins[1]],
+            bounds.extents()[0] * Self::RECOGNIZED_WIDTH_FACTOR,
+            style,
+        );
+
+        // Replacing the handwriting is undone at once
+        self.begin_history_group();
+        for &key in keys.iter() {
+            self.set_selected(key, false);
+            self.set_trashed(key, true);
+        }
+        let text_key = self.insert_stroke(StrokeStyle::TextStroke(textstroke));
+        self.end_history_group();
+
+        Some(text_key)
+    }
+
+    /// Clears every stroke and every component. The layer table is reset to a single default layer
     pub fn clear(&mut self) {
         self.chrono_counter = 0;
+        self.changed_chunks.mark_all();
+        self.collab_changes.mark_all();
+        self.stroke_revisions.mark_all();
+        self.spatial_index.get_mut().mark_all();
 
         self.strokes.clear();
         self.trash_components.clear();
         self.selection_components.clear();
         self.chrono_components.clear();
         self.render_components.clear();
+        self.group_components.clear();
+        self.layer_components.clear();
+        self.link_components.clear();
+        self.tag_components.clear();
+        self.groups.clear();
+        self.layers.clear();
+        self.layer_order.clear();
+        self.current_layer = None;
+        self.imported_pdfs.clear();
+        self.session_recording = SessionRecording::default();
+        self.audio_notes.clear();
+
+        self.ensure_valid_layers();
+        self.history.clear();
+        #[cfg(feature = "ui")]
+        self.tile_cache.get_mut().clear();
+        self.render_jobs.cancel_all();
     }
 
     /// Returns the stroke keys in the order that they should be rendered. Does not return the selection keys!
+    /// The keys are ordered by their layer from bottom to top, and chronologically within a layer. Strokes on hidden layers are not returned.
     pub fn keys_as_rendered(&self) -> Vec<StrokeKey> {
-        let keys_sorted_chrono = self.keys_sorted_chrono();
+        self.layer_order
+            .iter()
+            .flat_map(|&layer| self.keys_as_rendered_on_layer(layer))
+            .collect::<Vec<StrokeKey>>()
+    }
+
+    /// Returns the stroke keys on the layer in the order that they should be rendered. Does not return the selection keys!
+    /// Returns no keys for hidden layers.
+    pub fn keys_as_rendered_on_layer(&self, layer: LayerKey) -> Vec<StrokeKey> {
+        if !self.layers.get(layer).map_or(false, |layer| layer.visible) {
+            return vec![];
+        }
 
-        keys_sorted_chrono
+        self.keys_on_layer(layer)
             .iter()
             .filter_map(|&key| {
                 if self.does_render(key).unwrap_or(false)
@@ -305,18 +966,55 @@ impl StrokesState {
             .collect::<Vec<StrokeKey>>()
     }
 
+    /// The keys of the rendered strokes intersecting the bounds, in the order that they should be rendered (see `keys_as_rendered()`)
     pub fn keys_intersecting_bounds(&self, bounds: AABB) -> Vec<StrokeKey> {
-        self.keys_as_rendered()
-            .iter()
-            .filter_map(|&key| {
-                let stroke = self.strokes.get(key)?;
-                if stroke.bounds().intersects(&bounds) {
-                    Some(key)
-                } else {
-                    None
-                }
+        let mut keys = self
+            .keys_unordered_intersecting_bounds(bounds)
+            .into_iter()
+            .filter(|&key| {
+                self.layer_visible(key)
+                    && self.does_render(key).unwrap_or(false)
+                    && !(self.trashed(key).unwrap_or(false))
+                    && !(self.selected(key).unwrap_or(false))
+                    && self
+                        .strokes
+                        .get(key)
+                        .map_or(false, |stroke| stroke.bounds().intersects(&bounds))
             })
-            .collect::<Vec<StrokeKey>>()
+            .collect::<Vec<StrokeKey>>();
+        self.sort_keys_as_rendered(&mut keys);
+
+        keys
+    }
+
+    /// The keys of all strokes intersecting the bounds, including the trashed ones, in no particular order.
+    /// Found through the spatial index, without iterating all strokes
+    pub fn keys_unordered_intersecting_bounds(&self, bounds: AABB) -> Vec<StrokeKey> {
+        let mut spatial_index = self.spatial_index.borrow_mut();
+        spatial_index.update(&self.strokes);
+
+        spatial_index.query(bounds)
+    }
+
+    /// Sorts the keys in the order that they should be rendered, by their layer from bottom to top and chronologically within a layer
+    pub fn sort_keys_as_rendered(&self, keys: &mut [StrokeKey]) {
+        keys.sort_by_key(|&key| {
+            (
+                self.layer(key).and_then(|layer| self.layer_index(layer)),
+                self.chrono_components
+                    .get(key)
+                    .map(|chrono_comp| chrono_comp.t),
+            )
+        });
+    }
+
+    /// The keys of all strokes, including the trashed ones, in no particular order
+    pub fn keys_unordered(&self) -> Vec<StrokeKey> {
+        self.strokes.keys().collect::<Vec<StrokeKey>>()
+    }
+
+    pub fn get_stroke_ref(&self, key: StrokeKey) -> Option<&StrokeStyle> {
+        self.strokes.get(key)
     }
 
     pub fn clone_strokes_for_keys(&self, keys: &[StrokeKey]) -> Vec<StrokeStyle> {
@@ -333,21 +1031,24 @@ impl StrokesState {
     ) {
         let tasks_tx = self.tasks_tx.clone();
 
-        self.threadpool.spawn(move || {
+        self.spawn_job(move || {
                 match String::from_utf8(bytes) {
                     Ok(svg) => {
-                        match VectorImage::import_from_svg_data(svg.as_str(), pos, None, renderer) {
-                            Ok(vectorimage) => {
-                                let vectorimage = StrokeStyle::VectorImage(vectorimage);
-
-                                tasks_tx.unbounded_send(StateTask::InsertStroke {
-                                    stroke: vectorimage
-                                }).unwrap_or_else(|e| {
-                                    log::error!("tasks_tx.send() failed in insert_vectorimage_bytes_threaded() with Err, {}", e);
-                                });
+                        // Every element of the svg becomes its own stroke
+                        match VectorImage::import_from_svg_data_split(svg.as_str(), pos, renderer) {
+                            Ok(vectorimages) => {
+                                for vectorimage in vectorimages {
+                                    let vectorimage = StrokeStyle::VectorImage(vectorimage);
+
+                                    tasks_tx.unbounded_send(StateTask::InsertStroke {
+                                        stroke: vectorimage
+                                    }).unwrap_or_else(|e| {
+                                        log::error!("tasks_tx.send() failed in insert_vectorimage_bytes_threaded() with Err, {}", e);
+                                    });
+                                }
                             }
                             Err(e) => {
-                                log::error!("VectorImage::import_from_svg_data() failed in insert_vectorimage_bytes_threaded() with Err, {}", e);
+                                log::error!("VectorImage::import_from_svg_data_split() failed in insert_vectorimage_bytes_threaded() with Err, {}", e);
                             }
                         }
                     }
@@ -358,10 +1059,52 @@ impl StrokesState {
             });
     }
 
+    /// Inserts the strokes of a ISF stream, or of the ISF streams embedded in an EMF file, with the upper left corner of the ink at the position.
+    /// The dpi is the one of the sheet. The inserted strokes get selected and their keys are returned
+    pub fn insert_ink_bytes(
+        &mut self,
+        pos: na::Vector2<f64>,
+        bytes: &[u8],
+        dpi: f64,
+    ) -> Result<Vec<StrokeKey>, anyhow::Error> {
+        let isf_file = IsfFile::load_from_bytes(bytes)?;
+
+        let ink_mins = isf_file
+            .strokes
+            .iter()
+            .flat_map(|stroke| stroke.coords.iter())
+            .fold(None, |acc: Option<na::Vector2<f64>>, &coords| {
+                Some(acc.map_or(coords, |acc| acc.inf(&coords)))
+            })
+            .ok_or_else(|| anyhow::anyhow!("insert_ink_bytes() failed, the ink has no strokes"))?;
+        let offset = pos - utils::convert_coord_dpi(ink_mins, IsfFile::DPI, dpi);
+
+        let inserted = isf_file
+            .strokes
+            .into_iter()
+            .filter_map(
+                |stroke| match StrokeStyle::from_isfstroke(stroke, offset, dpi) {
+                    Ok(stroke) => Some(self.insert_stroke(stroke)),
+                    Err(e) => {
+                        log::error!(
+                            "from_isfstroke() failed in insert_ink_bytes() with Err {}",
+                            e
+                        );
+                        None
+                    }
+                },
+            )
+            .collect::<Vec<StrokeKey>>();
+
+        self.set_selected_keys(&inserted, true);
+
+        Ok(inserted)
+    }
+
     pub fn insert_bitmapimage_bytes_threaded(&mut self, pos: na::Vector2<f64>, bytes: Vec<u8>) {
         let tasks_tx = self.tasks_tx.clone();
 
-        self.threadpool.spawn(move || {
+        self.spawn_job(move || {
                 match BitmapImage::import_from_image_bytes(&bytes, pos) {
                     Ok(bitmapimage) => {
                         let bitmapimage = StrokeStyle::BitmapImage(bitmapimage);
@@ -379,63 +1122,76 @@ impl StrokesState {
             });
     }
 
-    pub fn insert_pdf_bytes_as_vector_threaded(
+    /// Imports the pages of the PDF with the options. The pages are rendered one after another in the thread pool
+    /// and inserted as soon as they are ready, so large documents don't block the engine.
+    /// The PDF itself is kept, to be able to export the sheet onto its original pages
+    #[cfg(feature = "ui")]
+    pub fn insert_pdf_bytes_threaded(
         &mut self,
         pos: na::Vector2<f64>,
-        page_width: Option<i32>,
         bytes: Vec<u8>,
+        options: PdfImportOptions,
         renderer: Arc<RwLock<Renderer>>,
     ) {
         let tasks_tx = self.tasks_tx.clone();
 
-        self.threadpool.spawn(move || {
-                match VectorImage::import_from_pdf_bytes(&bytes, pos, page_width, renderer) {
-                    Ok(images) => {
-                        for image in images {
-                            let image = StrokeStyle::VectorImage(image);
+        let placements = match poppler::Document::from_data(&bytes, None) {
+            Ok(doc) => options.place_pages(&doc, pos),
+            Err(e) => {
+                log::error!(
+                    "poppler::Document::from_data() failed in insert_pdf_bytes_threaded() with Err, {}",
+                    e
+                );
+                return;
+            }
+        };
+        self.imported_pdfs
+            .push(ImportedPdf::new(&bytes, &placements));
 
-                            tasks_tx.unbounded_send(StateTask::InsertStroke {
-                                stroke: image
-                            }).unwrap_or_else(|e| {
-                                log::error!("tasks_tx.send() failed in insert_pdf_bytes_as_vector_threaded() with Err, {}", e);
-                            });
-                        }
-                    }
+        self.spawn_job(move || {
+                let doc = match poppler::Document::from_data(&bytes, None) {
+                    Ok(doc) => doc,
                     Err(e) => {
-                        log::error!("VectorImage::import_from_pdf_bytes() failed in insert_pdf_bytes_as_vector_threaded() with Err, {}", e);
+                        log::error!("poppler::Document::from_data() failed in insert_pdf_bytes_threaded() with Err, {}", e);
+                        return;
                     }
-                }
-            });
-    }
-
-    pub fn insert_pdf_bytes_as_bitmap_threaded(
-        &mut self,
-        pos: na::Vector2<f64>,
-        page_width: Option<i32>,
-        bytes: Vec<u8>,
-    ) {
-        let tasks_tx = self.tasks_tx.clone();
-
-        self.threadpool.spawn(move || {
-                match BitmapImage::import_from_pdf_bytes(&bytes, pos, page_width) {
-                    Ok(images) => {
-                        for image in images {
-                            let image = StrokeStyle::BitmapImage(image);
+                };
 
+                for placement in placements {
+                    let page = if let Some(page) = doc.page(placement.page_index) {
+                        page
+                    } else {
+                        continue;
+                    };
+
+                    let stroke = match options.pages_type {
+                        PdfImportPagesType::Vector => VectorImage::import_from_pdf_page(&page, &placement, Arc::clone(&renderer))
+                            .map(StrokeStyle::VectorImage),
+                        PdfImportPagesType::Bitmap => BitmapImage::import_from_pdf_page(&page, &placement)
+                            .map(StrokeStyle::BitmapImage),
+                    };
+
+                    match stroke {
+                        Ok(stroke) => {
                             tasks_tx.unbounded_send(StateTask::InsertStroke {
-                                stroke: image
+                                stroke
                             }).unwrap_or_else(|e| {
-                                log::error!("tasks_tx.send() failed in insert_pdf_bytes_as_bitmap_threaded() with Err, {}", e);
+                                log::error!("tasks_tx.send() failed in insert_pdf_bytes_threaded() with Err, {}", e);
                             });
                         }
-                    }
-                    Err(e) => {
-                        log::error!("BitmapImage::import_from_pdf_bytes() failed in insert_pdf_bytes_as_bitmap_threaded() with Err, {}", e);
+                        Err(e) => {
+                            log::error!("importing page {} failed in insert_pdf_bytes_threaded() with Err, {}", placement.page_index, e);
+                        }
                     }
                 }
             });
     }
 
+    /// The imported PDFs, in the order they were imported
+    pub fn imported_pdfs(&self) -> &[ImportedPdf] {
+        &self.imported_pdfs
+    }
+
     pub fn import_state(&mut self, strokes_state: &Self) {
         self.clear();
         self.chrono_counter = strokes_state.chrono_counter;
@@ -445,10 +1201,24 @@ impl StrokesState {
         self.selection_components = strokes_state.selection_components.clone();
         self.chrono_components = strokes_state.chrono_components.clone();
         self.render_components = strokes_state.render_components.clone();
+        self.group_components = strokes_state.group_components.clone();
+        self.layer_components = strokes_state.layer_components.clone();
+        self.link_components = strokes_state.link_components.clone();
+        self.tag_components = strokes_state.tag_components.clone();
+        self.groups = strokes_state.groups.clone();
+        self.layers = strokes_state.layers.clone();
+        self.layer_order = strokes_state.layer_order.clone();
+        self.current_layer = strokes_state.current_layer;
+        self.imported_pdfs = strokes_state.imported_pdfs.clone();
+        self.audio_notes = strokes_state.audio_notes.clone();
+
+        self.ensure_valid_layers();
     }
 
     pub fn update_geometry_for_stroke(&mut self, key: StrokeKey) {
         if let Some(stroke) = self.strokes.get_mut(key) {
+            self.changed_chunks.mark(key);
+            self.spatial_index.get_mut().mark(key);
             match stroke {
                 StrokeStyle::BrushStroke(ref mut brushstroke) => {
                     brushstroke.update_geometry();
@@ -462,6 +1232,18 @@ impl StrokesState {
                 StrokeStyle::BitmapImage(ref mut bitmapimage) => {
                     bitmapimage.update_geometry();
                 }
+                StrokeStyle::TextStroke(ref mut textstroke) => {
+                    textstroke.update_geometry();
+                }
+                StrokeStyle::MathStroke(ref mut mathstroke) => {
+                    mathstroke.update_geometry();
+                }
+                StrokeStyle::CheckboxStroke(ref mut checkboxstroke) => {
+                    checkboxstroke.update_geometry();
+                }
+                StrokeStyle::ExtensionStroke(ref mut extensionstroke) => {
+                    extensionstroke.update_geometry();
+                }
             }
 
             // set flag for rendering regeneration
@@ -567,43 +1349,126 @@ impl StrokesState {
     }
 
     pub fn gen_svgs_for_bounds(&self, bounds: AABB) -> Vec<render::Svg> {
-        let keys = self.keys_as_rendered();
+        self.layer_order
+            .iter()
+            .flat_map(|&layer| self.gen_svgs_for_layer(layer, Some(bounds), false))
+            .collect::<Vec<render::Svg>>()
+    }
 
-        keys.iter()
-            .filter_map(|&key| {
-                let stroke = self.strokes.get(key)?;
-                if !stroke.bounds().intersects(&bounds) {
-                    return None;
-                }
+    /// Clones the strokes intersecting the bounds, including the selected ones, to draw them outside of the state.
+    /// The strokes are grouped by their layer from the bottom to the top layer, each group with the layer opacity
+    pub fn clone_strokes_for_bounds_by_layer(&self, bounds: AABB) -> Vec<(f64, Vec<StrokeStyle>)> {
+        self.layer_order
+            .iter()
+            .filter_map(|&layer| {
+                let layer_opacity = self
+                    .layers
+                    .get(layer)
+                    .filter(|layer| layer.visible)?
+                    .opacity;
+
+                let strokes = self
+                    .keys_on_layer(layer)
+                    .into_iter()
+                    .filter(|&key| {
+                        self.does_render(key).unwrap_or(false)
+                            && !(self.trashed(key).unwrap_or(false))
+                    })
+                    .filter_map(|key| self.strokes.get(key))
+                    .filter(|stroke| stroke.bounds().intersects(&bounds))
+                    .cloned()
+                    .collect::<Vec<StrokeStyle>>();
+
+                Some((layer_opacity, strokes))
+            })
+            .collect()
+    }
 
-                match stroke.gen_svgs(na::vector![0.0, 0.0]) {
-                    Ok(svgs) => Some(svgs),
-                    Err(e) => {
-                        log::error!(
-                            "stroke.gen_svgs() failed in gen_svg_for_bounds() with Err {}",
-                            e
-                        );
-                        None
-                    }
-                }
+    /// The signature of the strokes drawn in the bounds, including the selection.
+    /// It changes when any of them changes, is reordered, added or removed, or when the opacity of their layer changes ( see `sheet::thumbnails` )
+    pub fn signature_for_bounds(&self, bounds: AABB) -> u64 {
+        let mut keys = self
+            .keys_unordered_intersecting_bounds(bounds)
+            .into_iter()
+            .filter(|&key| {
+                self.layer_visible(key)
+                    && self.does_render(key).unwrap_or(false)
+                    && !(self.trashed(key).unwrap_or(false))
+                    && self
+                        .strokes
+                        .get(key)
+                        .map_or(false, |stroke| stroke.bounds().intersects(&bounds))
             })
-            .flatten()
+            .collect::<Vec<StrokeKey>>();
+        self.sort_keys_as_rendered(&mut keys);
+
+        let mut hasher = DefaultHasher::new();
+        for key in keys {
+            key.hash(&mut hasher);
+            self.stroke_revisions.revision(key).hash(&mut hasher);
+            self.layer(key)
+                .and_then(|layer| self.layers.get(layer))
+                .map(|layer| layer.opacity.to_bits())
+                .hash(&mut hasher);
+        }
+
+        hasher.finish()
+    }
+
+    /// The revision of the stroke, which changes with every change of the stroke
+    pub fn stroke_revision(&self, key: StrokeKey) -> u64 {
+        self.stroke_revisions.revision(key)
+    }
+
+    /// Generates the svgs for the strokes intersecting the bounds, leaving out images. Used to draw the strokes as annotations on top of imported PDF pages
+    pub fn gen_svgs_annotations_for_bounds(&self, bounds: AABB) -> Vec<render::Svg> {
+        self.layer_order
+            .iter()
+            .flat_map(|&layer| self.gen_svgs_for_layer(layer, Some(bounds), true))
             .collect::<Vec<render::Svg>>()
     }
 
     /// Generates a Svg for all strokes as drawn onto the canvas without xml headers or svg roots. Does not include the selection.
     pub fn gen_svgs_all_strokes(&self) -> Vec<render::Svg> {
-        let keys = self.keys_as_rendered();
+        self.layer_order
+            .iter()
+            .flat_map(|&layer| self.gen_svgs_for_layer(layer, None, false))
+            .collect::<Vec<render::Svg>>()
+    }
 
-        keys.iter()
+    /// Generates the svgs for the strokes on the layer, optionally only the ones intersecting the bounds.
+    /// For translucent layers the svgs are joined into one, which is wrapped in a group with the layer opacity.
+    /// With `skip_images` vector and bitmap images are left out.
+    fn gen_svgs_for_layer(
+        &self,
+        layer: LayerKey,
+        bounds: Option<AABB>,
+        skip_images: bool,
+    ) -> Vec<render::Svg> {
+        let svgs = self
+            .keys_as_rendered_on_layer(layer)
+            .iter()
             .filter_map(|&key| {
                 let stroke = self.strokes.get(key)?;
+                if let Some(bounds) = bounds {
+                    if !stroke.bounds().intersects(&bounds) {
+                        return None;
+                    }
+                }
+                if skip_images
+                    && matches!(
+                        stroke,
+                        StrokeStyle::VectorImage(_) | StrokeStyle::BitmapImage(_)
+                    )
+                {
+                    return None;
+                }
 
                 match stroke.gen_svgs(na::vector![0.0, 0.0]) {
                     Ok(svgs) => Some(svgs),
                     Err(e) => {
                         log::error!(
-                            "stroke.gen_svgs() failed in gen_svg_all_strokes() with Err {}",
+                            "stroke.gen_svgs() failed in gen_svgs_for_layer() with Err {}",
                             e
                         );
                         None
@@ -611,10 +1476,33 @@ impl StrokesState {
                 }
             })
             .flatten()
-            .collect::<Vec<render::Svg>>()
+            .collect::<Vec<render::Svg>>();
+
+        let opacity = self
+            .layers
+            .get(layer)
+            .map_or(Layer::OPACITY_MAX, |layer| layer.opacity);
+        if svgs.is_empty() || opacity >= Layer::OPACITY_MAX {
+            return svgs;
+        }
+
+        let bounds = svgs
+            .iter()
+            .fold(AABB::new_invalid(), |acc, svg| acc.merged(&svg.bounds));
+        let svg_data = svgs
+            .into_iter()
+            .map(|svg| svg.svg_data)
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        vec![render::Svg {
+            svg_data: format!("<g opacity=\"{}\">\n{}\n</g>", opacity, svg_data),
+            bounds,
+        }]
     }
 
     /// Translate the strokes with the offset
+    #[cfg_attr(not(feature = "ui"), allow(unused_variables))]
     pub fn translate_strokes(
         &mut self,
         strokes: &[StrokeKey],
@@ -622,14 +1510,27 @@ impl StrokesState {
         zoom: f64,
     ) {
         strokes.iter().for_each(|&key| {
+            self.record_change(key, HistoryChange::Translate);
             if let Some(stroke) = self.strokes.get_mut(key) {
+                self.changed_chunks.mark(key);
+                self.spatial_index.get_mut().mark(key);
                 stroke.translate(offset);
 
                 if let Some(render_comp) = self.render_components.get_mut(key) {
+                    if let Some(tessellation) = &mut render_comp.tessellation {
+                        tessellation.translate(offset);
+                        #[cfg(feature = "ui")]
+                        {
+                            render_comp.rendernode = tessellation.to_rendernode(zoom);
+                        }
+                        return;
+                    }
+
                     for image in render_comp.images.iter_mut() {
                         image.bounds = image.bounds.translate(offset);
                     }
 
+                    #[cfg(feature = "ui")]
                     match render::images_to_rendernode(&render_comp.images, zoom) {
                         Ok(Some(rendernode)) => {
                             render_comp.rendernode = Some(rendernode);
@@ -655,7 +1556,10 @@ impl StrokesState {
         zoom: f64,
     ) {
         strokes.iter().for_each(|&key| {
+            self.record_change(key, HistoryChange::Rotate);
             if let Some(stroke) = self.strokes.get_mut(key) {
+                self.changed_chunks.mark(key);
+                self.spatial_index.get_mut().mark(key);
                 stroke.rotate(angle, center);
 
                 self.regenerate_rendering_for_stroke(key, Arc::clone(&renderer), zoom);
@@ -673,7 +1577,10 @@ impl StrokesState {
         zoom: f64,
     ) {
         strokes.iter().for_each(|&key| {
+            self.record_change(key, HistoryChange::Resize);
             if let Some(stroke) = self.strokes.get_mut(key) {
+                self.changed_chunks.mark(key);
+                self.spatial_index.get_mut().mark(key);
                 let old_stroke_bounds = stroke.bounds();
                 let new_stroke_bounds = geometry::scale_inner_bounds_to_new_outer_bounds(
                     stroke.bounds(),
@@ -694,6 +1601,74 @@ impl StrokesState {
         });
     }
 
+    /// Modifies the options ( color, width, style ) of already drawn strokes and flags them for rendering regeneration
+    pub fn modify_stroke_options(
+        &mut self,
+        strokes: &[StrokeKey],
+        modification: &StrokeOptionsModification,
+    ) {
+        strokes.iter().for_each(|&key| {
+            self.record_change(key, HistoryChange::ModifyOptions);
+            if let Some(stroke) = self.strokes.get_mut(key) {
+                if stroke.modify_options(modification) {
+                    self.update_geometry_for_stroke(key);
+                }
+            }
+        });
+    }
+
+    /// Replaces the color `from` with `to` in the strokes of the scope, as stroke color and fill color of shapes.
+    /// Trashed strokes and strokes on hidden or locked layers are left as they are.
+    /// The renderings of the modified strokes are regenerated, their keys are returned
+    pub fn replace_color(
+        &mut self,
+        from: Color,
+        to: Color,
+        scope: ColorReplaceScope,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> Vec<StrokeKey> {
+        let keys = match scope {
+            ColorReplaceScope::Document => self.keys_unordered(),
+            ColorReplaceScope::Selection => self.selection_keys_as_rendered(),
+            ColorReplaceScope::Layer(layer) => self.keys_on_layer(layer),
+        };
+        let keys = keys
+            .into_iter()
+            .filter(|&key| {
+                !self.trashed(key).unwrap_or(true)
+                    && self.layer_editable(key)
+                    && self
+                        .strokes
+                        .get(key)
+                        .map_or(false, |stroke| stroke.uses_color(from))
+            })
+            .collect::<Vec<StrokeKey>>();
+
+        // Replacing the color is undone at once
+        self.begin_history_group();
+        let modified = keys
+            .into_iter()
+            .filter(|&key| {
+                self.record_change(key, HistoryChange::ModifyOptions);
+                let replaced = self
+                    .strokes
+                    .get_mut(key)
+                    .map_or(false, |stroke| stroke.replace_color(from, to));
+                if replaced {
+                    self.update_geometry_for_stroke(key);
+                }
+
+                replaced
+            })
+            .collect::<Vec<StrokeKey>>();
+        self.end_history_group();
+
+        self.regenerate_rendering_for_strokes_threaded(&modified, renderer, zoom);
+
+        modified
+    }
+
     /// Returns all strokes below the y_pos
     pub fn keys_below_y_pos(&self, y_pos: f64) -> Vec<StrokeKey> {
         self.strokes
@@ -729,9 +1704,17 @@ impl StrokesState {
             ],
         );
 
-        self.strokes
-            .iter_mut()
-            .par_bridge()
+        let not_editable = self
+            .strokes
+            .keys()
+            .filter(|&key| !self.layer_editable(key))
+            .collect::<Vec<StrokeKey>>();
+
+        let strokes = self.strokes.iter_mut();
+        #[cfg(not(target_arch = "wasm32"))]
+        let strokes = strokes.par_bridge();
+        strokes
+            .filter(|(key, _stroke)| !not_editable.contains(key))
             .filter_map(|(key, stroke)| match stroke {
                 StrokeStyle::BrushStroke(brushstroke) => {
                     if brushstroke.bounds().intersects(&tool_bounds) {
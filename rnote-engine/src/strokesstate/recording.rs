@@ -0,0 +1,286 @@
+//! The session recording. While recording, every committed history entry is appended to the recording as timestamped actions on the strokes,
+//! so the session can be replayed stroke by stroke in the order it was drawn, see `replay`. The recording is saved with the document.
+//!
+//! Only the changes made locally are recorded, changes of other participants of a collaboration session are not.
+//! The recording stores the whole stroke for every change, so long sessions with many large strokes make the document grow considerably.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use web_time::Instant;
+
+use super::{StrokeKey, StrokesState};
+use crate::strokes::strokestyle::StrokeStyle;
+
+/// An action on a stroke. The keys are the keys of the strokes in the recorded strokes state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "recorded_action")]
+pub enum RecordedAction {
+    /// The stroke was drawn or inserted, or restored from the trash
+    #[serde(rename = "insert")]
+    Insert {
+        #[serde(rename = "key")]
+        key: StrokeKey,
+        #[serde(rename = "stroke")]
+        stroke: StrokeStyle,
+        /// How long drawing the stroke took in seconds, e.g. while the pen was down. Zero for strokes which were inserted at once
+        #[serde(rename = "duration")]
+        duration: f64,
+    },
+    /// The stroke was modified, e.g. moved or resized
+    #[serde(rename = "change")]
+    Change {
+        #[serde(rename = "key")]
+        key: StrokeKey,
+        #[serde(rename = "stroke")]
+        stroke: StrokeStyle,
+    },
+    /// The stroke was removed or trashed
+    #[serde(rename = "remove")]
+    Remove {
+        #[serde(rename = "key")]
+        key: StrokeKey,
+    },
+}
+
+impl RecordedAction {
+    pub fn key(&self) -> StrokeKey {
+        match self {
+            Self::Insert { key, .. } | Self::Change { key, .. } | Self::Remove { key } => *key,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "recorded_event")]
+pub struct RecordedEvent {
+    /// When the action was finished, in seconds since the start of the recording
+    #[serde(rename = "t")]
+    pub t: f64,
+    #[serde(rename = "action")]
+    pub action: RecordedAction,
+}
+
+/// The state while recording
+#[derive(Debug, Clone)]
+struct ActiveRecording {
+    started: Instant,
+    /// The time of the recording when it was started. Continued recordings begin at the end of the earlier ones, without the pause between them
+    offset: f64,
+    /// The strokes which are present in the recording, i.e. inserted and not removed
+    present: HashSet<StrokeKey>,
+}
+
+impl ActiveRecording {
+    fn time(&self, instant: Instant) -> f64 {
+        self.offset
+            + instant
+                .saturating_duration_since(self.started)
+                .as_secs_f64()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "session_recording")]
+pub struct SessionRecording {
+    /// The events ordered by their time
+    #[serde(rename = "events")]
+    events: Vec<RecordedEvent>,
+    /// Set while recording
+    #[serde(skip)]
+    active: Option<ActiveRecording>,
+}
+
+impl SessionRecording {
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// The length of the recording in seconds
+    pub fn duration(&self) -> f64 {
+        self.events.last().map_or(0.0, |event| event.t)
+    }
+
+    /// The last recorded stroke of every present stroke
+    fn present_strokes(&self) -> HashMap<StrokeKey, &StrokeStyle> {
+        let mut present = HashMap::new();
+
+        for event in self.events.iter() {
+            match &event.action {
+                RecordedAction::Insert { key, stroke, .. }
+                | RecordedAction::Change { key, stroke } => {
+                    present.insert(*key, stroke);
+                }
+                RecordedAction::Remove { key } => {
+                    present.remove(key);
+                }
+            }
+        }
+
+        present
+    }
+
+    fn push(&mut self, t: f64, action: RecordedAction) {
+        if let Some(active) = self.active.as_mut() {
+            match &action {
+                RecordedAction::Insert { key, .. } => {
+                    active.present.insert(*key);
+                }
+                RecordedAction::Remove { key } => {
+                    active.present.remove(key);
+                }
+                RecordedAction::Change { .. } => {}
+            }
+        }
+
+        // Keeps the events ordered, the time of entries which were committed late may be earlier than the last event
+        let t = t.max(self.duration());
+        self.events.push(RecordedEvent { t, action });
+    }
+}
+
+/// Systems that are related to the session recording.
+impl StrokesState {
+    pub fn session_recording(&self) -> &SessionRecording {
+        &self.session_recording
+    }
+
+    /// Starts recording, or continues the existing recording at its end. Pending changes are committed first, so they are not recorded.
+    /// Strokes which are not yet in the recording are recorded as inserted at the start, changes made while not recording as changed
+    pub fn start_session_recording(&mut self) {
+        if self.session_recording.is_recording() {
+            return;
+        }
+        self.commit_history();
+
+        let t = self.session_recording.duration();
+        let recorded = self
+            .session_recording
+            .present_strokes()
+            .into_iter()
+            .map(|(key, stroke)| (key, serde_json::to_value(stroke).ok()))
+            .collect::<HashMap<StrokeKey, Option<serde_json::Value>>>();
+        let keys = self
+            .keys_sorted_chrono()
+            .into_iter()
+            .filter(|&key| self.trashed(key) == Some(false))
+            .collect::<Vec<StrokeKey>>();
+        let present = keys.iter().copied().collect::<HashSet<StrokeKey>>();
+
+        let mut actions = vec![];
+        for &key in recorded.keys() {
+            if !present.contains(&key) {
+                actions.push(RecordedAction::Remove { key });
+            }
+        }
+        for &key in keys.iter() {
+            let stroke = match self.strokes.get(key) {
+                Some(stroke) => stroke,
+                None => continue,
+            };
+
+            match recorded.get(&key) {
+                None => actions.push(RecordedAction::Insert {
+                    key,
+                    stroke: stroke.clone(),
+                    duration: 0.0,
+                }),
+                Some(recorded_stroke) => {
+                    if *recorded_stroke != serde_json::to_value(stroke).ok() {
+                        actions.push(RecordedAction::Change {
+                            key,
+                            stroke: stroke.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        self.session_recording.active = Some(ActiveRecording {
+            started: Instant::now(),
+            offset: t,
+            present: recorded.keys().copied().collect(),
+        });
+        for action in actions {
+            self.session_recording.push(t, action);
+        }
+    }
+
+    /// Stops recording. Pending changes are committed first, so they are recorded
+    pub fn stop_session_recording(&mut self) {
+        self.commit_history();
+        self.session_recording.active = None;
+    }
+
+    /// Removes all events of the recording. Recording continues if it was started
+    pub fn clear_session_recording(&mut self) {
+        let recording = self.session_recording.is_recording();
+        self.session_recording = SessionRecording::default();
+
+        if recording {
+            self.start_session_recording();
+        }
+    }
+
+    /// Records the changes of the strokes, which began and ended at the instants. The flag of a key is true if the stroke itself was modified,
+    /// and not only its components. Needs to be called right after the changes were committed or applied
+    pub(crate) fn record_session_changes(
+        &mut self,
+        began: Instant,
+        ended: Instant,
+        changes: &[(StrokeKey, bool)],
+    ) {
+        let (t, mut actions) = match &self.session_recording.active {
+            Some(active) => {
+                let t = active.time(ended);
+                let duration = t - active.time(began);
+
+                let actions = changes
+                    .iter()
+                    .filter_map(|&(key, modified)| {
+                        let was_present = active.present.contains(&key);
+                        let stroke = self
+                            .strokes
+                            .get(key)
+                            .filter(|_| self.trashed(key) == Some(false));
+
+                        match (was_present, stroke) {
+                            (false, Some(stroke)) => Some(RecordedAction::Insert {
+                                key,
+                                stroke: stroke.clone(),
+                                duration,
+                            }),
+                            (true, Some(stroke)) if modified => Some(RecordedAction::Change {
+                                key,
+                                stroke: stroke.clone(),
+                            }),
+                            (true, None) => Some(RecordedAction::Remove { key }),
+                            _ => None,
+                        }
+                    })
+                    .collect::<Vec<RecordedAction>>();
+
+                (t, actions)
+            }
+            None => return,
+        };
+        // In the order the strokes are drawn in
+        actions.sort_by_key(|action| {
+            self.chrono_components
+                .get(action.key())
+                .map(|chrono_comp| chrono_comp.t)
+        });
+
+        for action in actions {
+            self.session_recording.push(t, action);
+        }
+    }
+}
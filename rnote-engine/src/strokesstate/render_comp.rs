@@ -1,15 +1,27 @@
+#[cfg(feature = "ui")]
+use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
+#[cfg(feature = "ui")]
+use super::layer_comp::{Layer, LayerKey};
+#[cfg(feature = "ui")]
+use super::tilecache::{TileCache, TileIndex, TileKey};
 use super::StateTask;
 use super::{StrokeKey, StrokeStyle, StrokesState};
+#[cfg(feature = "ui")]
 use crate::compose::color::Color;
 use crate::compose::geometry::AABBHelpers;
+use crate::compose::tessellation::Tessellation;
 use crate::drawbehaviour::DrawBehaviour;
-use crate::render::{self, Renderer};
+#[cfg(feature = "ui")]
+use crate::render::IncrementalNode;
+use crate::render::{self, DetailLevel, Renderer};
 
-use gtk4::{graphene, gsk, Snapshot};
+#[cfg(feature = "ui")]
+use gtk4::{graphene, gsk, prelude::*, Snapshot, Widget};
 use p2d::bounding_volume::{BoundingVolume, AABB};
 use serde::{Deserialize, Serialize};
+use web_time::Instant;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "render_component")]
@@ -20,8 +32,16 @@ pub struct RenderComponent {
     pub regenerate_flag: bool,
     #[serde(skip)]
     pub images: Vec<render::Image>,
+    #[cfg(feature = "ui")]
     #[serde(skip)]
     pub rendernode: Option<gsk::RenderNode>,
+    /// The tessellation the rendernode is generated from, when the stroke is drawn by the GPU instead of from the images
+    #[serde(skip)]
+    pub tessellation: Option<Tessellation>,
+    /// The incremental rendering while the stroke is being drawn, composed from the renderings of the appended elements
+    #[cfg(feature = "ui")]
+    #[serde(skip)]
+    pub incremental: Option<IncrementalNode>,
 }
 
 impl Default for RenderComponent {
@@ -30,8 +50,123 @@ impl Default for RenderComponent {
             render: true,
             regenerate_flag: true,
             images: vec![],
+            #[cfg(feature = "ui")]
             rendernode: None,
+            tessellation: None,
+            #[cfg(feature = "ui")]
+            incremental: None,
+        }
+    }
+}
+
+impl RenderComponent {
+    /// Replaces the rendering with the tessellation
+    #[cfg_attr(not(feature = "ui"), allow(unused_variables))]
+    fn set_tessellation(&mut self, tessellation: Tessellation, zoom: f64) {
+        #[cfg(feature = "ui")]
+        {
+            self.rendernode = tessellation.to_rendernode(zoom);
+        }
+        self.images = vec![];
+        self.tessellation = Some(tessellation);
+        self.regenerate_flag = false;
+    }
+
+    /// Replaces the rendering with the images. The rendering is kept when there are no images
+    #[cfg_attr(not(feature = "ui"), allow(unused_variables))]
+    fn set_images(&mut self, images: Vec<render::Image>, zoom: f64) -> Result<(), anyhow::Error> {
+        if images.is_empty() {
+            return Ok(());
+        }
+
+        #[cfg(feature = "ui")]
+        {
+            self.rendernode = render::images_to_rendernode(&images, zoom)?;
+        }
+        self.images = images;
+        self.tessellation = None;
+        self.regenerate_flag = false;
+        Ok(())
+    }
+
+    /// Appends the images to the rendering
+    #[cfg_attr(not(feature = "ui"), allow(unused_variables))]
+    fn append_images(
+        &mut self,
+        mut images: Vec<render::Image>,
+        zoom: f64,
+    ) -> Result<(), anyhow::Error> {
+        #[cfg(feature = "ui")]
+        {
+            self.rendernode =
+                render::append_images_to_rendernode(self.rendernode.as_ref(), &images, zoom)?;
+        }
+        self.images.append(&mut images);
+        self.regenerate_flag = false;
+        Ok(())
+    }
+
+    /// Appends the images of the last elements to the incremental rendering
+    #[cfg_attr(not(feature = "ui"), allow(unused_variables))]
+    fn append_elems_images(
+        &mut self,
+        mut images: Vec<render::Image>,
+        zoom: f64,
+    ) -> Result<(), anyhow::Error> {
+        #[cfg(feature = "ui")]
+        if let Some(last_elems_node) = render::images_to_rendernode(&images, zoom)? {
+            self.append_incremental(last_elems_node, zoom, 1.0, |rendernode| rendernode.cloned());
+        }
+        self.images.append(&mut images);
+        Ok(())
+    }
+
+    /// Drops the rendering, so it is regenerated when the stroke is needed again
+    fn clear_rendering(&mut self) {
+        #[cfg(feature = "ui")]
+        {
+            self.rendernode = None;
+        }
+        self.images = vec![];
+        self.tessellation = None;
+        self.regenerate_flag = true;
+    }
+
+    /// Appends the node to the incremental rendering, so only the new node is composed with the rendering of the stroke so far.
+    /// When the rendering was replaced or the zoom changed since the last composition, a new incremental rendering is begun from the base, which gets the current rendernode
+    #[cfg(feature = "ui")]
+    fn append_incremental(
+        &mut self,
+        node: gsk::RenderNode,
+        zoom: f64,
+        opacity: f64,
+        base: impl FnOnce(Option<&gsk::RenderNode>) -> Option<gsk::RenderNode>,
+    ) {
+        let outdated = self.incremental.as_ref().map_or(true, |incremental| {
+            incremental.zoom() != zoom || !incremental.is_composed(self.rendernode.as_ref())
+        });
+
+        if outdated {
+            let mut incremental = IncrementalNode::new(opacity, zoom);
+            if let Some(base) = base(self.rendernode.as_ref()) {
+                incremental.append(base);
+            }
+            self.incremental = Some(incremental);
+        }
+
+        if let Some(incremental) = self.incremental.as_mut() {
+            incremental.append(node);
+            self.rendernode = incremental.compose();
         }
+        self.regenerate_flag = false;
+    }
+
+    /// Whether the stroke is currently rendered incrementally, i.e. it is being drawn
+    #[cfg(feature = "ui")]
+    fn rendered_incrementally(&self) -> bool {
+        self.incremental.as_ref().map_or(false, |incremental| {
+            incremental.is_composed(self.rendernode.as_ref())
+        })
     }
 }
 
@@ -102,19 +237,28 @@ impl StrokesState {
         renderer: Arc<RwLock<Renderer>>,
         zoom: f64,
     ) {
+        // Rendered right away, so a job still rendering the stroke is outdated
+        self.render_jobs.cancel(key);
+
         if let (Some(stroke), Some(render_comp)) =
             (self.strokes.get(key), self.render_components.get_mut(key))
         {
-            match stroke.gen_images(zoom, renderer) {
+            let compose_start = Instant::now();
+            let tessellation =
+                stroke.gen_tessellation(&renderer.read().unwrap().tessellated_strokes, zoom);
+            if let Some(tessellation) = tessellation {
+                self.perf_counters.record_compose(compose_start);
+                render_comp.set_tessellation(tessellation, zoom);
+                return;
+            }
+
+            let images = stroke.gen_images(zoom, renderer);
+            self.perf_counters.record_compose(compose_start);
+
+            match images {
                 Ok(images) => {
-                    match render::images_to_rendernode(&images, zoom) {
-                        Ok(Some(rendernode)) => {
-                            render_comp.rendernode = Some(rendernode);
-                            render_comp.regenerate_flag = false;
-                            render_comp.images = images;
-                        }
-                        Ok(None) => {},
-                        Err(e) => log::error!("image_to_rendernode() failed in regenerate_rendering_for_stroke() with Err {}", e),
+                    if let Err(e) = render_comp.set_images(images, zoom) {
+                        log::error!("image_to_rendernode() failed in regenerate_rendering_for_stroke() with Err {}", e);
                     }
                 }
                 Err(e) => {
@@ -150,13 +294,35 @@ impl StrokesState {
         if let (Some(render_comp), Some(stroke)) =
             (self.render_components.get_mut(key), self.strokes.get(key))
         {
+            // Tessellating is fast, and the rendernodes can't be sent between threads
+            let compose_start = Instant::now();
+            let tessellation =
+                stroke.gen_tessellation(&renderer.read().unwrap().tessellated_strokes, zoom);
+            if let Some(tessellation) = tessellation {
+                self.perf_counters.record_compose(compose_start);
+                self.render_jobs.cancel(key);
+                render_comp.set_tessellation(tessellation, zoom);
+                return;
+            }
+
             let stroke = stroke.clone();
+            let job = self.render_jobs.begin(key, zoom);
+            let perf_counters = Arc::clone(&self.perf_counters);
 
             render_comp.regenerate_flag = false;
 
             // Spawn a new thread for image rendering
-            self.threadpool.spawn(move || {
-                match stroke.gen_images(zoom, renderer) {
+            self.spawn_job(move || {
+                // The job might have been cancelled while it was waiting in the queue
+                if job.cancelled() {
+                    return;
+                }
+
+                let compose_start = Instant::now();
+                let images = stroke.gen_images(zoom, renderer);
+                perf_counters.record_compose(compose_start);
+
+                match images {
                     Ok(images) => {
                         // Send each image individually, to not block too long
                         let mut images_iter = images.into_iter();
@@ -165,14 +331,20 @@ impl StrokesState {
                             tasks_tx.unbounded_send(StateTask::UpdateStrokeWithImages {
                                 key,
                                 images: vec![first_image],
+                                job: Some(job.clone()),
                             }).unwrap_or_else(|e| {
                                 log::error!("tasks_tx.send() UpdateStrokeWithImages failed in regenerate_rendering_for_stroke_threaded() for stroke with key {:?}, with Err, {}",key, e);
                             });
                             while let Some(next_image) = images_iter.next() {
+                                if job.cancelled() {
+                                    return;
+                                }
+
                                 // Append the next images
                                 tasks_tx.unbounded_send(StateTask::AppendImagesToStroke {
                                     key,
                                     images: vec![next_image],
+                                    job: Some(job.clone()),
                                 }).unwrap_or_else(|e| {
                                     log::error!("tasks_tx.send() AppendImagesToStroke failed in regenerate_rendering_for_stroke_threaded() for stroke with key {:?}, with Err, {}",key, e);
                                 });
@@ -219,9 +391,8 @@ impl StrokesState {
                 if let Some(viewport) = viewport {
                     // Loosening the bounds to avoid strokes popping up
                     if !viewport.expand(viewport.extents()).intersects(&stroke.bounds()) {
-                        render_comp.rendernode = None;
-                        render_comp.images = vec![];
-                        render_comp.regenerate_flag = true;
+                        render_comp.clear_rendering();
+                        self.render_jobs.cancel(key);
 
                         return;
                     }
@@ -229,17 +400,24 @@ impl StrokesState {
                 if !force_regenerate && !render_comp.regenerate_flag {
                     return;
                 }
+                self.render_jobs.cancel(key);
+
+                let compose_start = Instant::now();
+                let tessellation =
+                    stroke.gen_tessellation(&renderer.read().unwrap().tessellated_strokes, zoom);
+                if let Some(tessellation) = tessellation {
+                    self.perf_counters.record_compose(compose_start);
+                    render_comp.set_tessellation(tessellation, zoom);
+                    return;
+                }
 
-                match stroke.gen_images(zoom, Arc::clone(&renderer)) {
+                let images = stroke.gen_images(zoom, Arc::clone(&renderer));
+                self.perf_counters.record_compose(compose_start);
+
+                match images {
                     Ok(images) => {
-                        match render::images_to_rendernode(&images, zoom) {
-                            Ok(Some(rendernode)) => {
-                                render_comp.rendernode = Some(rendernode);
-                                render_comp.images = images;
-                                render_comp.regenerate_flag = false;
-                            }
-                            Ok(None) => {}
-                            Err(e) => log::error!("stroke.gen_images() failed in regenerate_stroke_current_view() with Err {}", e),
+                        if let Err(e) = render_comp.set_images(images, zoom) {
+                            log::error!("stroke.gen_images() failed in regenerate_stroke_current_view() with Err {}", e);
                         }
                     }
                     Err(e) => {
@@ -266,6 +444,10 @@ impl StrokesState {
         renderer: Arc<RwLock<Renderer>>,
         zoom: f64,
     ) {
+        // Jobs of a previous zoom are outdated
+        self.render_jobs.cancel_other_zooms(zoom);
+        self.render_jobs.remove_finished();
+
         let keys = self.render_components.keys().collect::<Vec<StrokeKey>>();
 
         keys.iter().for_each(|&key| {
@@ -276,9 +458,8 @@ impl StrokesState {
                 if let Some(viewport) = viewport {
                     // Loosening the bounds to avoid strokes popping up
                     if !viewport.expand(viewport.extents()).intersects(&stroke.bounds()) {
-                        render_comp.rendernode = None;
-                        render_comp.images = vec![];
-                        render_comp.regenerate_flag = true;
+                        render_comp.clear_rendering();
+                        self.render_jobs.cancel(key);
 
                         return;
                     }
@@ -298,6 +479,56 @@ impl StrokesState {
         })
     }
 
+    /// Appends the tessellation of the last elements, when the stroke is drawn by the GPU. Returns false if it is rendered to images instead
+    fn append_tessellation_new_elem(
+        &mut self,
+        key: StrokeKey,
+        renderer: &Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let tessellated_strokes = renderer.read().unwrap().tessellated_strokes;
+
+        if let (Some(stroke), Some(render_comp)) =
+            (self.strokes.get(key), self.render_components.get_mut(key))
+        {
+            match render_comp.tessellation.take() {
+                // Simplified tessellations are cheap, so they are regenerated as a whole to stay simplified
+                Some(mut tessellation) if DetailLevel::for_zoom(zoom) == DetailLevel::Full => {
+                    if let Some(last_elems_tessellation) =
+                        stroke.gen_tessellation_for_last_elems(&tessellated_strokes)
+                    {
+                        // Only the primitives of the last elements are composed with the rendering so far
+                        #[cfg(feature = "ui")]
+                        if let Some(last_elems_node) =
+                            last_elems_tessellation.to_primitives_rendernode(zoom)
+                        {
+                            render_comp.append_incremental(
+                                last_elems_node,
+                                zoom,
+                                tessellation.opacity,
+                                |_| tessellation.to_primitives_rendernode(zoom),
+                            );
+                        }
+                        tessellation.append(last_elems_tessellation);
+                        render_comp.images = vec![];
+                        render_comp.tessellation = Some(tessellation);
+                        render_comp.regenerate_flag = false;
+                        return true;
+                    }
+                }
+                _ => {
+                    if let Some(tessellation) = stroke.gen_tessellation(&tessellated_strokes, zoom)
+                    {
+                        render_comp.set_tessellation(tessellation, zoom);
+                        return true;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
     /// Append the last elements to the render_comp of the stroke. The rendering for strokes that don't support generating rendering for only the last elements are regenerated completely
     pub fn append_rendering_new_elem(
         &mut self,
@@ -305,6 +536,10 @@ impl StrokesState {
         renderer: Arc<RwLock<Renderer>>,
         zoom: f64,
     ) {
+        if self.append_tessellation_new_elem(key, &renderer, zoom) {
+            return;
+        }
+
         if let (Some(stroke), Some(render_comp)) =
             (self.strokes.get(key), self.render_components.get_mut(key))
         {
@@ -334,18 +569,9 @@ impl StrokesState {
                                 vec![last_elems_svg],
                                 bounds,
                             ) {
-                                Ok(mut images) => {
-                                    match render::append_images_to_rendernode(
-                                        render_comp.rendernode.as_ref(),
-                                        &images,
-                                        zoom,
-                                    ) {
-                                        Ok(rendernode) => {
-                                            render_comp.rendernode = rendernode;
-                                            render_comp.images.append(&mut images);
-                                            render_comp.regenerate_flag = false;
-                                        }
-                                        Err(e) => log::error!("append_images_to_rendernode() failed in append_rendering_new_elem() with Err {}", e),
+                                Ok(images) => {
+                                    if let Err(e) = render_comp.append_elems_images(images, zoom) {
+                                        log::error!("images_to_rendernode() failed in append_rendering_new_elem() with Err {}", e);
                                     }
                                 }
                                 Err(e) => {
@@ -358,28 +584,24 @@ impl StrokesState {
                 // regenerate everything for strokes that don't support generating svgs for the last added elements
                 StrokeStyle::ShapeStroke(_)
                 | StrokeStyle::VectorImage(_)
-                | StrokeStyle::BitmapImage(_) => {
-                    match stroke.gen_images(zoom, renderer) {
-                        Ok(images) => {
-                            match render::images_to_rendernode(&images, zoom) {
-                                Ok(Some(rendernode)) => {
-                                    render_comp.rendernode = Some(rendernode);
-                                    render_comp.regenerate_flag = false;
-                                    render_comp.images = images;
-                                }
-                                Ok(None) => log::error!("image_to_rendernode() failed in regenerate_rendering_for_stroke(), returned None"),
-                                Err(e) => log::error!("image_to_rendernode() failed in regenerate_rendering_for_stroke() with Err {}", e),
-                            }
+                | StrokeStyle::BitmapImage(_)
+                | StrokeStyle::TextStroke(_)
+                | StrokeStyle::MathStroke(_)
+                | StrokeStyle::CheckboxStroke(_)
+                | StrokeStyle::ExtensionStroke(_) => match stroke.gen_images(zoom, renderer) {
+                    Ok(images) => {
+                        if let Err(e) = render_comp.set_images(images, zoom) {
+                            log::error!("image_to_rendernode() failed in regenerate_rendering_for_stroke() with Err {}", e);
                         }
-                        Err(e) => {
-                            log::debug!(
+                    }
+                    Err(e) => {
+                        log::debug!(
                                 "stroke.gen_image() failed in regenerate_rendering_newest_elem() for stroke with key: {:?}, with Err {}",
                                 key,
                                 e
                             )
-                        }
                     }
-                }
+                },
             }
         } else {
             log::debug!(
@@ -396,16 +618,25 @@ impl StrokesState {
         renderer: Arc<RwLock<Renderer>>,
         zoom: f64,
     ) {
+        if self.append_tessellation_new_elem(key, &renderer, zoom) {
+            return;
+        }
+
         let tasks_tx = self.tasks_tx.clone();
 
         if let (Some(stroke), Some(render_comp)) =
             (self.strokes.get(key), self.render_components.get_mut(key))
         {
             let stroke = stroke.clone();
+            // Only the complete regenerations are jobs, the images of the last elements are appended to them
+            let job = match stroke {
+                StrokeStyle::BrushStroke(_) => None,
+                _ => Some(self.render_jobs.begin(key, zoom)),
+            };
 
             render_comp.regenerate_flag = true;
 
-            self.threadpool.spawn(move || {
+            self.spawn_job(move || {
                 match stroke {
                     StrokeStyle::BrushStroke(brushstroke) => {
                         let elems_len = brushstroke.elements.len();
@@ -433,11 +664,11 @@ impl StrokesState {
                                     bounds,
                                 ) {
                                     Ok(images) => {
-                                        tasks_tx.unbounded_send(StateTask::AppendImagesToStroke {
+                                        tasks_tx.unbounded_send(StateTask::AppendElemsImagesToStroke {
                                             key,
                                             images,
                                         }).unwrap_or_else(|e| {
-                                            log::error!("sending AppendImagesToStroke as task for markerstroke failed in regenerate_rendering_new_elem() for stroke with key {:?}, with Err, {}",key, e);
+                                            log::error!("sending AppendElemsImagesToStroke as task for markerstroke failed in regenerate_rendering_new_elem() for stroke with key {:?}, with Err, {}",key, e);
                                         });
                                     }
                                     Err(e) => {
@@ -450,12 +681,17 @@ impl StrokesState {
                     // regenerate everything for strokes that don't support generating svgs for the last added elements
                     StrokeStyle::ShapeStroke(_)
                     | StrokeStyle::VectorImage(_)
-                    | StrokeStyle::BitmapImage(_) => {
+                    | StrokeStyle::BitmapImage(_)
+                    | StrokeStyle::TextStroke(_)
+                    | StrokeStyle::MathStroke(_)
+                    | StrokeStyle::CheckboxStroke(_)
+                    | StrokeStyle::ExtensionStroke(_) => {
                         match stroke.gen_images(zoom, renderer) {
                             Ok(images) => {
                                 tasks_tx.unbounded_send(StateTask::UpdateStrokeWithImages {
                                     key,
                                     images,
+                                    job,
                                 }).unwrap_or_else(|e| {
                                     log::error!("sending task UpdateStrokeWithImages failed in regenerate_rendering_newest_elem() for stroke with key {:?}, with Err {}", key, e);
                                 });
@@ -486,17 +722,11 @@ impl StrokesState {
         zoom: f64,
     ) {
         if let Some(render_comp) = self.render_components.get_mut(key) {
-            match render::images_to_rendernode(&images, zoom) {
-                Ok(Some(rendernode)) => {
-                    render_comp.rendernode = Some(rendernode);
-                    render_comp.regenerate_flag = false;
-                    render_comp.images = images;
-                }
-                Ok(None) => {}
-                Err(e) => log::error!(
+            if let Err(e) = render_comp.set_images(images, zoom) {
+                log::error!(
                     "image_to_rendernode() failed in regenerate_rendering_with_images() with Err {}",
                     e
-                ),
+                );
             }
         } else {
             log::debug!(
@@ -509,29 +739,51 @@ impl StrokesState {
     pub fn append_images_to_rendering(
         &mut self,
         key: StrokeKey,
-        mut images: Vec<render::Image>,
+        images: Vec<render::Image>,
         zoom: f64,
     ) {
         if let Some(render_comp) = self.render_components.get_mut(key) {
-            match render::append_images_to_rendernode(render_comp.rendernode.as_ref(), &images, zoom) {
-                Ok(rendernode) => {
-                    render_comp.rendernode = rendernode;
-                    render_comp.regenerate_flag = false;
-                    render_comp.images.append(&mut images);
-                }
-                Err(e) => log::error!(
+            if let Err(e) = render_comp.append_images(images, zoom) {
+                log::error!(
                     "append_images_to_rendernode() failed in append_images_to_rendering() with Err {}",
                     e
-                ),
+                );
+            }
+        }
+    }
+
+    /// Appends the images of the last elements of a stroke which is being drawn to its incremental rendering
+    pub fn append_elems_images_to_rendering(
+        &mut self,
+        key: StrokeKey,
+        images: Vec<render::Image>,
+        zoom: f64,
+    ) {
+        if let Some(render_comp) = self.render_components.get_mut(key) {
+            if let Err(e) = render_comp.append_elems_images(images, zoom) {
+                log::error!(
+                    "images_to_rendernode() failed in append_elems_images_to_rendering() with Err {}",
+                    e
+                );
             }
         }
     }
 
     /// Updates the cached rendernodes to the current zoom. Used to display the scaled (pixelated) images until new ones are generated with one of the regenerate_*_threaded funcs
+    #[cfg(feature = "ui")]
     pub fn update_rendernodes_current_zoom(&mut self, zoom: f64) {
+        // Jobs of a previous zoom are outdated
+        self.render_jobs.cancel_other_zooms(zoom);
+
         self.render_components
             .iter_mut()
             .for_each(|(_key, render_comp)| {
+                // Tessellations are regenerated sharply right away
+                if let Some(tessellation) = &render_comp.tessellation {
+                    render_comp.rendernode = tessellation.to_rendernode(zoom);
+                    return;
+                }
+
                 match render::images_to_rendernode(&render_comp.images, zoom) {
                     Ok(Some(rendernode)) => {
                         render_comp.rendernode = Some(rendernode);
@@ -545,28 +797,211 @@ impl StrokesState {
             });
     }
 
-    /// Draws the strokes without the selection
+    /// Draws the strokes without the selection. Every layer is composited separately with its opacity
+    #[cfg(feature = "ui")]
     pub fn draw_strokes(&self, snapshot: &Snapshot, viewport: Option<AABB>) {
-        self.keys_as_rendered().iter().for_each(|&key| {
-            if let (Some(stroke), Some(render_comp)) =
-                (self.strokes.get(key), self.render_components.get(key))
-            {
-                // skip if stroke is not in viewport
-                if let Some(viewport) = viewport {
-                    if !viewport.intersects(&stroke.bounds()) {
-                        return;
+        for &layer in self.layer_order() {
+            let opacity = self
+                .get_layer(layer)
+                .map_or(Layer::OPACITY_MAX, |layer| layer.opacity);
+
+            if let Some(layer_node) = self.gen_layer_node(layer, viewport) {
+                if opacity < Layer::OPACITY_MAX {
+                    snapshot
+                        .append_node(&gsk::OpacityNode::new(&layer_node, opacity as f32).upcast());
+                } else {
+                    snapshot.append_node(&layer_node);
+                }
+            }
+        }
+    }
+
+    /// Draws the strokes without the selection like `draw_strokes()`, but through the tile cache (see `tilecache`).
+    /// Only the tiles under strokes whose rendering changed since the last draw are generated again, the others are taken from the cache
+    #[cfg(feature = "ui")]
+    pub fn draw_strokes_tiled(
+        &self,
+        snapshot: &Snapshot,
+        viewport: AABB,
+        zoom: f64,
+        active_widget: Option<&Widget>,
+    ) {
+        let draw_start = Instant::now();
+        let mut strokes_drawn = 0;
+        let mut tile_cache = self.tile_cache.borrow_mut();
+        tile_cache.begin_draw(zoom);
+        let (first, last) = TileCache::tile_index_range(viewport, zoom);
+
+        // The strokes in the viewport, found through the spatial index
+        let mut layers_keys = HashMap::<LayerKey, Vec<StrokeKey>>::new();
+        for key in self.keys_intersecting_bounds(viewport) {
+            if let Some(layer) = self.layer(key) {
+                layers_keys.entry(layer).or_default().push(key);
+            }
+        }
+
+        for &layer in self.layer_order() {
+            let opacity = self
+                .get_layer(layer)
+                .map_or(Layer::OPACITY_MAX, |layer| layer.opacity);
+
+            let mut keys = layers_keys.remove(&layer).unwrap_or_default();
+            // The strokes being drawn on top of the layer are drawn over the tiles instead of through them,
+            // so the tiles under them are not generated again on every input event
+            let n_incremental = keys
+                .iter()
+                .rev()
+                .take_while(|&&key| self.drawn_over_tiles(key))
+                .count();
+            let incremental_keys = keys.split_off(keys.len() - n_incremental);
+            strokes_drawn += keys.len() + incremental_keys.len();
+
+            // The strokes intersecting the visible tiles, in the order they are rendered
+            let mut tiles_keys = HashMap::<TileIndex, Vec<StrokeKey>>::new();
+            for key in keys {
+                let bounds = match (self.strokes.get(key), self.render_components.get(key)) {
+                    (Some(stroke), Some(render_comp)) if render_comp.rendernode.is_some() => {
+                        stroke.bounds()
                     }
+                    _ => continue,
+                };
+
+                let (stroke_first, stroke_last) = TileCache::tile_index_range(bounds, zoom);
+                for x in stroke_first[0].max(first[0])..=stroke_last[0].min(last[0]) {
+                    for y in stroke_first[1].max(first[1])..=stroke_last[1].min(last[1]) {
+                        tiles_keys.entry(na::vector![x, y]).or_default().push(key);
+                    }
+                }
+            }
+
+            if tiles_keys.is_empty() && incremental_keys.is_empty() {
+                continue;
+            }
+
+            if opacity < Layer::OPACITY_MAX {
+                snapshot.push_opacity(opacity);
+            }
+
+            for (index, keys) in tiles_keys {
+                let signature = TileCache::signature(keys.iter().filter_map(|&key| {
+                    Some((key, self.render_components.get(key)?.rendernode.as_ref()?))
+                }));
+
+                if let Some(rendernode) = tile_cache.tile_rendernode(
+                    TileKey { layer, index },
+                    signature,
+                    || self.gen_strokes_node(&keys),
+                    active_widget,
+                ) {
+                    snapshot.append_node(&rendernode);
+                }
+            }
+
+            if let Some(incremental_node) = self.gen_strokes_node(&incremental_keys) {
+                snapshot.append_node(&incremental_node);
+            }
+
+            if opacity < Layer::OPACITY_MAX {
+                snapshot.pop();
+            }
+        }
+
+        // Keeps the tiles around the viewport, so they don't need to be generated again when moving back and forth
+        tile_cache.retain(viewport.expand(viewport.extents()), self.layer_order());
+
+        self.perf_counters.record_frame(
+            strokes_drawn,
+            tile_cache.generated_tiles().len(),
+            draw_start.elapsed(),
+        );
+    }
+
+    /// Whether the stroke is rendered incrementally because it is being drawn, and can be drawn over the tiles.
+    /// Strokes with a blend mode need to be blended with the strokes below them, so they are drawn through the tiles nonetheless
+    #[cfg(feature = "ui")]
+    fn drawn_over_tiles(&self, key: StrokeKey) -> bool {
+        let blended = match self.strokes.get(key) {
+            Some(StrokeStyle::BrushStroke(brushstroke)) => brushstroke.blend_mode().is_some(),
+            _ => false,
+        };
+
+        !blended
+            && self
+                .render_components
+                .get(key)
+                .map_or(false, |render_comp| render_comp.rendered_incrementally())
+    }
+
+    /// Generates the node for the strokes on the layer, without the selection
+    #[cfg(feature = "ui")]
+    fn gen_layer_node(&self, layer: LayerKey, viewport: Option<AABB>) -> Option<gsk::RenderNode> {
+        let keys = self
+            .keys_as_rendered_on_layer(layer)
+            .into_iter()
+            .filter(|&key| {
+                // skip if stroke is not in viewport
+                match (viewport, self.strokes.get(key)) {
+                    (Some(viewport), Some(stroke)) => viewport.intersects(&stroke.bounds()),
+                    _ => true,
                 }
+            })
+            .collect::<Vec<StrokeKey>>();
+
+        self.gen_strokes_node(&keys)
+    }
+
+    /// Generates the node compositing the strokes in the order of the keys, through the tag filter (see `tag_comp`)
+    #[cfg(feature = "ui")]
+    fn gen_strokes_node(&self, keys: &[StrokeKey]) -> Option<gsk::RenderNode> {
+        // Strokes with a blend mode are blended with all strokes drawn below them, so they are accumulated in a separate snapshot
+        let mut strokes_snapshot = Snapshot::new();
 
+        keys.iter().for_each(|&key| {
+            if let (Some(stroke), Some(render_comp), Some(opacity)) = (
+                self.strokes.get(key),
+                self.render_components.get(key),
+                self.tag_filter_opacity(key),
+            ) {
                 if let Some(rendernode) = render_comp.rendernode.as_ref() {
-                    snapshot.append_node(rendernode);
+                    let dimmed_node: gsk::RenderNode;
+                    let rendernode = if opacity < 1.0 {
+                        dimmed_node = gsk::OpacityNode::new(rendernode, opacity as f32).upcast();
+                        &dimmed_node
+                    } else {
+                        rendernode
+                    };
+
+                    let blend_mode = match stroke {
+                        StrokeStyle::BrushStroke(brushstroke) => brushstroke.blend_mode(),
+                        _ => None,
+                    };
+
+                    if let Some(blend_mode) = blend_mode {
+                        // to_node() finishes the snapshot, so continue with a new one
+                        let below = strokes_snapshot.to_node();
+                        strokes_snapshot = Snapshot::new();
+
+                        if let Some(below) = below {
+                            let blend_node: gsk::RenderNode =
+                                gsk::BlendNode::new(&below, rendernode, blend_mode).upcast();
+                            strokes_snapshot.append_node(&blend_node);
+                        } else {
+                            strokes_snapshot.append_node(rendernode);
+                        }
+                    } else {
+                        strokes_snapshot.append_node(rendernode);
+                    }
                 }
             }
         });
+
+        strokes_snapshot.to_node()
     }
 
     /// Draws the selection
+    #[cfg(feature = "ui")]
     pub fn draw_selection(&self, zoom: f64, snapshot: &Snapshot) {
+        #[cfg(feature = "ui")]
         fn draw_selected_bounds(bounds: AABB, zoom: f64, snapshot: &Snapshot) {
             let bounds = graphene::Rect::new(
                 bounds.mins[0] as f32,
@@ -618,6 +1053,7 @@ impl StrokesState {
         });
     }
 
+    #[cfg(feature = "ui")]
     pub fn draw_debug(&self, zoom: f64, snapshot: &Snapshot) {
         self.keys_sorted_chrono().iter().for_each(|&key| {
             let stroke = if let Some(stroke) = self.strokes.get(key) {
@@ -692,6 +1128,38 @@ impl StrokesState {
                         snapshot,
                     );
                 }
+                StrokeStyle::TextStroke(textstroke) => {
+                    visual_debug::draw_bounds(
+                        textstroke.bounds,
+                        visual_debug::COLOR_STROKE_BOUNDS,
+                        zoom,
+                        snapshot,
+                    );
+                }
+                StrokeStyle::MathStroke(mathstroke) => {
+                    visual_debug::draw_bounds(
+                        mathstroke.bounds,
+                        visual_debug::COLOR_STROKE_BOUNDS,
+                        zoom,
+                        snapshot,
+                    );
+                }
+                StrokeStyle::CheckboxStroke(checkboxstroke) => {
+                    visual_debug::draw_bounds(
+                        checkboxstroke.bounds,
+                        visual_debug::COLOR_STROKE_BOUNDS,
+                        zoom,
+                        snapshot,
+                    );
+                }
+                StrokeStyle::ExtensionStroke(extensionstroke) => {
+                    visual_debug::draw_bounds(
+                        extensionstroke.bounds(),
+                        visual_debug::COLOR_STROKE_BOUNDS,
+                        zoom,
+                        snapshot,
+                    );
+                }
             }
             // Pop Blur and opacity for hidden strokes
             if let (Some(render_comp), Some(trash_comp)) = (
@@ -704,15 +1172,28 @@ impl StrokesState {
                 }
             }
         });
+
+        let tile_cache = self.tile_cache.borrow();
+        for key in tile_cache.generated_tiles() {
+            visual_debug::draw_bounds(
+                TileCache::tile_bounds(key.index, tile_cache.zoom()),
+                visual_debug::COLOR_TILE_GENERATED,
+                zoom,
+                snapshot,
+            );
+        }
     }
 }
 
 /// module for visual debugging
 pub mod visual_debug {
+    #[cfg(feature = "ui")]
     use gtk4::{graphene, gsk, Snapshot};
+    #[cfg(feature = "ui")]
     use p2d::bounding_volume::AABB;
 
     use crate::compose::color::Color;
+    #[cfg(feature = "ui")]
     use crate::compose::geometry::AABBHelpers;
 
     pub const COLOR_POS: Color = Color {
@@ -751,6 +1232,13 @@ pub mod visual_debug {
         b: 0.8,
         a: 1.0,
     };
+    /// Tiles of the tile cache which were generated in the current draw
+    pub const COLOR_TILE_GENERATED: Color = Color {
+        r: 0.0,
+        g: 0.8,
+        b: 0.2,
+        a: 0.6,
+    };
     pub const COLOR_SHEET_BOUNDS: Color = Color {
         r: 0.8,
         g: 0.0,
@@ -758,6 +1246,7 @@ pub mod visual_debug {
         a: 1.0,
     };
 
+    #[cfg(feature = "ui")]
     pub fn draw_bounds(bounds: AABB, color: Color, zoom: f64, snapshot: &Snapshot) {
         let bounds = graphene::Rect::new(
             bounds.mins[0] as f32,
@@ -787,6 +1276,7 @@ pub mod visual_debug {
         )
     }
 
+    #[cfg(feature = "ui")]
     pub fn draw_pos(pos: na::Vector2<f64>, color: Color, zoom: f64, snapshot: &Snapshot) {
         snapshot.append_color(
             &color.to_gdk(),
@@ -799,6 +1289,7 @@ pub mod visual_debug {
         );
     }
 
+    #[cfg(feature = "ui")]
     pub fn draw_fill(rect: AABB, color: Color, zoom: f64, snapshot: &Snapshot) {
         snapshot.append_color(
             &color.to_gdk(),
@@ -0,0 +1,93 @@
+//! The jobs rendering the images of the strokes on the threadpool. Every stroke has at most one job, a new one cancels the previous,
+//! and changing the zoom cancels the jobs rendering at another zoom. The finished images are sent back with their job through the tasks channel,
+//! so that the images of jobs which were cancelled in the meantime are dropped instead of replacing a newer rendering.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::StrokeKey;
+
+/// A job rendering a stroke at a zoom. Clones refer to the same job
+#[derive(Debug, Clone)]
+pub struct RenderJob {
+    cancelled: Arc<AtomicBool>,
+    zoom: f64,
+}
+
+impl RenderJob {
+    pub fn new(zoom: f64) -> Self {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            zoom,
+        }
+    }
+
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the job was cancelled. Checked by the job before it renders and sends its images, and when its images are received
+    pub fn cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RenderJobs {
+    jobs: HashMap<StrokeKey, RenderJob>,
+}
+
+impl RenderJobs {
+    /// Begins a new job rendering the stroke at the zoom. The previous job of the stroke is cancelled, as its rendering is outdated
+    pub fn begin(&mut self, key: StrokeKey, zoom: f64) -> RenderJob {
+        let job = RenderJob::new(zoom);
+
+        if let Some(previous) = self.jobs.insert(key, job.clone()) {
+            previous.cancel();
+        }
+
+        job
+    }
+
+    /// Cancels the job of the stroke, e.g. when it is rendered right away or its rendering is dropped
+    pub fn cancel(&mut self, key: StrokeKey) {
+        if let Some(job) = self.jobs.remove(&key) {
+            job.cancel();
+        }
+    }
+
+    /// Cancels the jobs rendering at another zoom than the current one
+    pub fn cancel_other_zooms(&mut self, zoom: f64) {
+        self.jobs.retain(|_key, job| {
+            if job.zoom != zoom {
+                job.cancel();
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    pub fn cancel_all(&mut self) {
+        self.jobs.drain().for_each(|(_key, job)| job.cancel());
+    }
+
+    /// Removes the finished jobs whose images were all received. Nothing else refers to them anymore
+    pub fn remove_finished(&mut self) {
+        self.jobs
+            .retain(|_key, job| Arc::strong_count(&job.cancelled) > 1);
+    }
+
+    /// The jobs which are still rendering, or whose images were not received yet
+    pub fn n_pending(&self) -> usize {
+        self.jobs
+            .values()
+            .filter(|job| Arc::strong_count(&job.cancelled) > 1)
+            .count()
+    }
+}
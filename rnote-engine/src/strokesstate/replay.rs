@@ -0,0 +1,188 @@
+//! Replays a session recording stroke by stroke, in the order the strokes were drawn ( see `recording` ).
+//! The strokes are played into another strokes state, e.g. of a sheet which is only shown while replaying, and the replay is not recorded in its history.
+//! Brush strokes are drawn progressively over the time it took to draw them.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::recording::{RecordedAction, RecordedEvent, SessionRecording};
+use super::{StrokeKey, StrokesState};
+use crate::strokes::brushstroke::BrushStroke;
+use crate::strokes::strokestyle::StrokeStyle;
+use crate::surfaceflags::SurfaceFlags;
+
+#[derive(Debug, Clone)]
+pub struct Replay {
+    events: Vec<RecordedEvent>,
+    /// The position in the recording in seconds
+    position: f64,
+    speed: f64,
+    /// The index of the next event which is not yet applied
+    next_event: usize,
+    /// The keys of the replayed strokes, for the keys of the recorded strokes
+    keys: HashMap<StrokeKey, StrokeKey>,
+}
+
+impl Replay {
+    pub const SPEED_MIN: f64 = 0.1;
+    pub const SPEED_MAX: f64 = 16.0;
+    pub const SPEED_DEFAULT: f64 = 1.0;
+
+    /// A new replay of the recording, at its start
+    pub fn new(recording: &SessionRecording) -> Self {
+        Self {
+            events: recording.events().to_vec(),
+            position: 0.0,
+            speed: Self::SPEED_DEFAULT,
+            next_event: 0,
+            keys: HashMap::new(),
+        }
+    }
+
+    /// The length of the replay in seconds of the recording
+    pub fn duration(&self) -> f64 {
+        self.events.last().map_or(0.0, |event| event.t)
+    }
+
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_event >= self.events.len()
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// The factor the replay is faster than the recording
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.clamp(Self::SPEED_MIN, Self::SPEED_MAX);
+    }
+
+    /// Advances the replay by the elapsed time, scaled by the speed. Expected to be called periodically, e.g. every frame, until the replay is finished
+    pub fn advance(&mut self, elapsed: Duration, strokes_state: &mut StrokesState) -> SurfaceFlags {
+        self.seek(
+            self.position + elapsed.as_secs_f64() * self.speed,
+            strokes_state,
+        )
+    }
+
+    /// Shows the next stroke at once, for stepping through the recording stroke by stroke
+    pub fn step(&mut self, strokes_state: &mut StrokesState) -> SurfaceFlags {
+        match self.events.get(self.next_event) {
+            Some(event) => self.seek(event.t, strokes_state),
+            None => SurfaceFlags::default(),
+        }
+    }
+
+    /// Moves the replay to the position in seconds. Seeking backwards starts over and removes the replayed strokes again.
+    /// Resize needed after calling this
+    pub fn seek(&mut self, position: f64, strokes_state: &mut StrokesState) -> SurfaceFlags {
+        let mut surface_flags = SurfaceFlags::default();
+        let position = position.clamp(0.0, self.duration());
+
+        strokes_state.without_history(|strokes_state| {
+            if position < self.position {
+                for (_, key) in self.keys.drain() {
+                    strokes_state.remove_stroke(key);
+                }
+                self.next_event = 0;
+            }
+            self.position = position;
+
+            while let Some(event) = self.events.get(self.next_event) {
+                if event.t > position {
+                    break;
+                }
+                Self::apply_action(&event.action, &mut self.keys, strokes_state);
+                self.next_event += 1;
+            }
+
+            self.draw_next_partially(strokes_state);
+        });
+
+        surface_flags.redraw = true;
+        surface_flags.resize = true;
+
+        surface_flags
+    }
+
+    fn apply_action(
+        action: &RecordedAction,
+        keys: &mut HashMap<StrokeKey, StrokeKey>,
+        strokes_state: &mut StrokesState,
+    ) {
+        match action {
+            RecordedAction::Insert { key, stroke, .. } | RecordedAction::Change { key, stroke } => {
+                Self::show_stroke(*key, stroke.clone(), keys, strokes_state);
+            }
+            RecordedAction::Remove { key } => {
+                if let Some(key) = keys.remove(key) {
+                    strokes_state.remove_stroke(key);
+                }
+            }
+        }
+    }
+
+    /// Shows the part of the next brush stroke which was already drawn at the current position
+    fn draw_next_partially(&mut self, strokes_state: &mut StrokesState) {
+        let (key, brushstroke, begin, duration) = match self.events.get(self.next_event) {
+            Some(RecordedEvent {
+                t,
+                action:
+                    RecordedAction::Insert {
+                        key,
+                        stroke: StrokeStyle::BrushStroke(brushstroke),
+                        duration,
+                    },
+            }) if *duration > 0.0 => (*key, brushstroke, t - duration, *duration),
+            _ => return,
+        };
+        if self.position < begin {
+            return;
+        }
+
+        let partial = partial_brushstroke(brushstroke, (self.position - begin) / duration);
+
+        Self::show_stroke(
+            key,
+            StrokeStyle::BrushStroke(partial),
+            &mut self.keys,
+            strokes_state,
+        );
+    }
+
+    fn show_stroke(
+        recorded_key: StrokeKey,
+        stroke: StrokeStyle,
+        keys: &mut HashMap<StrokeKey, StrokeKey>,
+        strokes_state: &mut StrokesState,
+    ) {
+        let key = match keys.get(&recorded_key) {
+            Some(&key) => {
+                strokes_state.replace_stroke(key, stroke);
+                key
+            }
+            None => {
+                let key = strokes_state.insert_stroke(stroke);
+                keys.insert(recorded_key, key);
+                key
+            }
+        };
+        strokes_state.update_geometry_for_stroke(key);
+    }
+}
+
+/// The part of the brush stroke which was drawn at the progress, from 0.0 to 1.0. At least the first element is drawn
+pub(crate) fn partial_brushstroke(brushstroke: &BrushStroke, progress: f64) -> BrushStroke {
+    let n_elements = ((brushstroke.elements.len() as f64 * progress).ceil() as usize)
+        .clamp(1, brushstroke.elements.len().max(1));
+
+    let mut partial = brushstroke.clone();
+    partial.elements.truncate(n_elements);
+    partial.update_geometry();
+
+    partial
+}
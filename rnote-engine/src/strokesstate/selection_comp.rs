@@ -1,13 +1,19 @@
+use super::history::HistoryChange;
 use super::{StrokeKey, StrokeStyle, StrokesState};
+use crate::compose::color::Color;
 use crate::compose::geometry::AABBHelpers;
+use crate::compose::smooth::FillRule;
+use crate::compose::transformable::Transformable;
 use crate::drawbehaviour::DrawBehaviour;
 use crate::pens::selector::{self, Selector};
 use crate::{compose, render};
 
 use geo::line_string;
 use geo::prelude::*;
+#[cfg(feature = "ui")]
 use gtk4::{gio, glib, prelude::*};
 use p2d::bounding_volume::{BoundingVolume, AABB};
+#[cfg(not(target_arch = "wasm32"))]
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -33,6 +39,68 @@ impl SelectionComponent {
     }
 }
 
+/// The alignment of the selected strokes, relative to the bounds of the selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrokesAlignment {
+    /// Aligns the left edges
+    Left,
+    /// Aligns the right edges
+    Right,
+    /// Aligns the top edges
+    Top,
+    /// Aligns the bottom edges
+    Bottom,
+    /// Aligns the centers on the same vertical line
+    CenterHorizontal,
+    /// Aligns the centers on the same horizontal line
+    CenterVertical,
+}
+
+/// The axis along which the selected strokes are distributed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrokesDistribution {
+    /// Evenly spaces the strokes from left to right
+    Horizontal,
+    /// Evenly spaces the strokes from top to bottom
+    Vertical,
+}
+
+/// The engine native clipboard content of a selection. It holds the strokes with all their options and seeds,
+/// so they are pasted with full fidelity within a document and across documents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "strokes_clipboard")]
+pub struct StrokesClipboard {
+    #[serde(rename = "strokes")]
+    pub strokes: Vec<StrokeStyle>,
+    /// The bounds of the strokes when they were copied
+    #[serde(rename = "bounds")]
+    pub bounds: AABB,
+}
+
+impl Default for StrokesClipboard {
+    fn default() -> Self {
+        Self {
+            strokes: vec![],
+            bounds: AABB::new_invalid(),
+        }
+    }
+}
+
+impl StrokesClipboard {
+    /// The private mime type of the clipboard content
+    pub const MIME_TYPE: &'static str = "application/x-rnote-strokes";
+    /// The zoom the selection is rendered with for the PNG clipboard content, so that it stays sharp when pasted into other apps
+    pub const PNG_ZOOM: f64 = 2.0;
+
+    pub fn to_json_bytes(&self) -> Result<Vec<u8>, anyhow::Error> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    pub fn from_json_bytes(bytes: &[u8]) -> Result<Self, anyhow::Error> {
+        Ok(serde_json::from_slice::<Self>(bytes)?)
+    }
+}
+
 impl StrokesState {
     /// Returns false if selecting is unsupported
     pub fn can_select(&self, key: StrokeKey) -> bool {
@@ -51,15 +119,14 @@ impl StrokesState {
         }
     }
 
-    /// Sets if the stroke is currently selected
+    /// Sets if the stroke is currently selected. Strokes on hidden or locked layers can't be selected
     pub fn set_selected(&mut self, key: StrokeKey, selected: bool) {
+        if selected && !self.layer_editable(key) {
+            return;
+        }
+
         if let Some(selection_comp) = self.selection_components.get_mut(key) {
             selection_comp.selected = selected;
-
-            if let Some(chrono_comp) = self.chrono_components.get_mut(key) {
-                self.chrono_counter += 1;
-                chrono_comp.t = self.chrono_counter;
-            }
         } else {
             log::debug!(
                 "get selection_comp in set_selected() returned None for stroke with key {:?}",
@@ -101,35 +168,278 @@ impl StrokesState {
         self.gen_bounds(&self.selection_keys_as_rendered())
     }
 
-    pub fn duplicate_selection(&mut self, zoom: f64) {
-        let offset = na::vector![
-            SelectionComponent::SELECTION_DUPLICATION_OFFSET_X,
-            SelectionComponent::SELECTION_DUPLICATION_OFFSET_Y
-        ];
-
+    /// Duplicates the selection with the offset. The duplicates become the new selection
+    pub fn duplicate_selection(&mut self, offset: na::Vector2<f64>) -> Vec<StrokeKey> {
         let old_selected = self.selection_keys_as_rendered();
         self.set_selected_keys(&old_selected, false);
 
-        let new_selected = old_selected
+        let new_selected = self.insert_reseeded_clones(&old_selected, offset);
+        self.set_selected_keys(&new_selected, true);
+
+        new_selected
+    }
+
+    /// Stamps a clone of the selection centered at the position. The selection stays as it is, so it can be stamped repeatedly.
+    /// Returns the keys of the stamped strokes
+    pub fn stamp_selection(&mut self, pos: na::Vector2<f64>) -> Vec<StrokeKey> {
+        let selection_bounds = if let Some(selection_bounds) = self.gen_selection_bounds() {
+            selection_bounds
+        } else {
+            return vec![];
+        };
+
+        let selection_keys = self.selection_keys_as_rendered();
+        let offset = pos - selection_bounds.center().coords;
+
+        self.insert_reseeded_clones(&selection_keys, offset)
+    }
+
+    /// Inserts clones of the strokes with the offset and new seeds, returning their new keys. The clones keep the links and tags of the strokes
+    fn insert_reseeded_clones(
+        &mut self,
+        keys: &[StrokeKey],
+        offset: na::Vector2<f64>,
+    ) -> Vec<StrokeKey> {
+        keys.iter()
+            .filter_map(|&key| {
+                Some((
+                    self.strokes.get(key)?.clone(),
+                    self.link(key).cloned(),
+                    self.tags(key).to_vec(),
+                ))
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|(mut stroke, link, tags)| {
+                stroke.reseed();
+                stroke.translate(offset);
+
+                let key = self.insert_stroke(stroke);
+                self.set_link(key, link);
+                self.set_tags(key, tags.iter().map(|tag| tag.as_str()));
+                self.update_geometry_for_stroke(key);
+                key
+            })
+            .collect()
+    }
+
+    /// Aligns the bounds of the selected strokes to the bounds of the selection
+    pub fn align_selection(&mut self, alignment: StrokesAlignment) {
+        let selection_bounds = if let Some(selection_bounds) = self.gen_selection_bounds() {
+            selection_bounds
+        } else {
+            return;
+        };
+
+        for key in self.selection_keys_as_rendered() {
+            self.record_change(key, HistoryChange::Translate);
+            if let Some(stroke) = self.strokes.get_mut(key) {
+                self.changed_chunks.mark(key);
+                self.spatial_index.get_mut().mark(key);
+                let bounds = stroke.bounds();
+
+                let offset = match alignment {
+                    StrokesAlignment::Left => {
+                        na::vector![selection_bounds.mins[0] - bounds.mins[0], 0.0]
+                    }
+                    StrokesAlignment::Right => {
+                        na::vector![selection_bounds.maxs[0] - bounds.maxs[0], 0.0]
+                    }
+                    StrokesAlignment::Top => {
+                        na::vector![0.0, selection_bounds.mins[1] - bounds.mins[1]]
+                    }
+                    StrokesAlignment::Bottom => {
+                        na::vector![0.0, selection_bounds.maxs[1] - bounds.maxs[1]]
+                    }
+                    StrokesAlignment::CenterHorizontal => {
+                        na::vector![selection_bounds.center()[0] - bounds.center()[0], 0.0]
+                    }
+                    StrokesAlignment::CenterVertical => {
+                        na::vector![0.0, selection_bounds.center()[1] - bounds.center()[1]]
+                    }
+                };
+
+                stroke.translate(offset);
+                self.update_geometry_for_stroke(key);
+            }
+        }
+    }
+
+    /// Distributes the selected strokes evenly, so that the gaps between their bounds are equal.
+    /// The outermost strokes stay in place. Needs at least three selected strokes
+    pub fn distribute_selection(&mut self, distribution: StrokesDistribution) {
+        let axis = match distribution {
+            StrokesDistribution::Horizontal => 0,
+            StrokesDistribution::Vertical => 1,
+        };
+
+        let mut keys_bounds = self
+            .selection_keys_as_rendered()
+            .into_iter()
+            .filter_map(|key| Some((key, self.strokes.get(key)?.bounds())))
+            .collect::<Vec<(StrokeKey, AABB)>>();
+
+        if keys_bounds.len() < 3 {
+            return;
+        }
+
+        keys_bounds.sort_by(|(_, first), (_, second)| {
+            first.mins[axis]
+                .partial_cmp(&second.mins[axis])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let start = keys_bounds[0].1.mins[axis];
+        let end = keys_bounds
             .iter()
-            .map(|&key| {
-                let new_key = self.insert_stroke(self.strokes.get(key).unwrap().clone());
-                self.set_selected(new_key, true);
-                new_key
+            .map(|(_, bounds)| bounds.maxs[axis])
+            .fold(f64::MIN, f64::max);
+        let extents_sum = keys_bounds
+            .iter()
+            .map(|(_, bounds)| bounds.extents()[axis])
+            .sum::<f64>();
+        // The gap might be negative when the strokes overlap, then they are overlapping evenly
+        let gap = (end - start - extents_sum) / (keys_bounds.len() - 1) as f64;
+
+        let mut pos = start;
+        for (key, bounds) in keys_bounds {
+            let mut offset = na::Vector2::<f64>::zeros();
+            offset[axis] = pos - bounds.mins[axis];
+
+            self.record_change(key, HistoryChange::Translate);
+            if let Some(stroke) = self.strokes.get_mut(key) {
+                stroke.translate(offset);
+                self.update_geometry_for_stroke(key);
+            }
+
+            pos += bounds.extents()[axis] + gap;
+        }
+    }
+
+    /// Copies the selection into the clipboard content. Returns None if nothing is selected
+    pub fn copy_selection(&self) -> Option<StrokesClipboard> {
+        let bounds = self.gen_selection_bounds()?;
+
+        Some(StrokesClipboard {
+            strokes: self.clone_strokes_for_keys(&self.selection_keys_as_rendered()),
+            bounds,
+        })
+    }
+
+    /// Copies the selection into the clipboard content and trashes it. Returns None if nothing is selected
+    pub fn cut_selection(&mut self) -> Option<StrokesClipboard> {
+        let clipboard = self.copy_selection()?;
+        self.trash_selection();
+
+        Some(clipboard)
+    }
+
+    /// Pastes the clipboard content as the new selection, deselecting the current one.
+    /// The strokes are moved so that the upper left corner of their bounds is at the position,
+    /// or offset from where they were copied like a duplication if there is none.
+    /// Returns the keys of the pasted strokes
+    pub fn paste_clipboard(
+        &mut self,
+        clipboard: StrokesClipboard,
+        pos: Option<na::Vector2<f64>>,
+    ) -> Vec<StrokeKey> {
+        let offset = match pos {
+            Some(pos) => pos - clipboard.bounds.mins.coords,
+            None => na::vector![
+                SelectionComponent::SELECTION_DUPLICATION_OFFSET_X,
+                SelectionComponent::SELECTION_DUPLICATION_OFFSET_Y
+            ],
+        };
+
+        let old_selected = self.selection_keys_as_rendered();
+        self.set_selected_keys(&old_selected, false);
+
+        clipboard
+            .strokes
+            .into_iter()
+            .map(|mut stroke| {
+                stroke.translate(offset);
+
+                let key = self.insert_stroke(stroke);
+                // The hitboxes are not serialized, so the geometry must be regenerated
+                self.update_geometry_for_stroke(key);
+                self.set_selected(key, true);
+                key
             })
-            .collect::<Vec<StrokeKey>>();
+            .collect()
+    }
 
-        // Offsetting the new selected stroke to make the duplication apparent to the user
-        self.translate_strokes(&new_selected, offset, zoom);
+    /// Selects the topmost stroke hit by the tap, deselecting all others. With toggle it is toggled in and out of the current selection instead
+    pub fn update_selection_for_tap(
+        &mut self,
+        pos: na::Vector2<f64>,
+        toggle: bool,
+        tolerance: f64,
+        viewport: Option<AABB>,
+    ) {
+        // Only the strokes near the position can be hit
+        let mut keys = self.keys_unordered_intersecting_bounds(AABB::from_half_extents(
+            na::Point2::from(pos),
+            na::Vector2::from_element(tolerance.max(0.0)),
+        ));
+        self.sort_keys_chrono(&mut keys);
+
+        let hit_key = keys.iter().rev().copied().find(|&key| {
+            let stroke = if let Some(stroke) = self.strokes.get(key) {
+                stroke
+            } else {
+                return false;
+            };
+            // skip if stroke is trashed
+            if self.trashed(key).unwrap_or(true) {
+                return false;
+            }
+            // skip if stroke is on a hidden or locked layer
+            if !self.layer_editable(key) {
+                return false;
+            }
+            // skip if stroke is not in viewport
+            if let Some(viewport) = viewport {
+                if !viewport.intersects(&stroke.bounds()) {
+                    return false;
+                }
+            }
+
+            stroke.hittest(pos, tolerance)
+        });
+
+        if !toggle {
+            self.selection_components
+                .values_mut()
+                .for_each(|selection_comp| {
+                    selection_comp.selected = false;
+                });
+        }
+
+        if let Some(hit_key) = hit_key {
+            let selected = !(toggle && self.selected(hit_key).unwrap_or(false));
+
+            // Groups are toggled as a whole
+            for key in self.keys_w_group_members(&[hit_key]) {
+                if self.trashed(key).unwrap_or(true) || !self.layer_editable(key) {
+                    continue;
+                }
+                if let Some(selection_comp) = self.selection_components.get_mut(key) {
+                    selection_comp.selected = selected;
+                }
+            }
+        }
     }
 
     /// Returns true if selection has changed
     pub fn update_selection_for_selector(&mut self, selector: &Selector, viewport: Option<AABB>) {
         let selector_polygon = match selector.style {
             selector::SelectorStyle::Polygon => {
-                let selector_path_points = selector
-                    .path
-                    .par_iter()
+                #[cfg(not(target_arch = "wasm32"))]
+                let path = selector.path.par_iter();
+                #[cfg(target_arch = "wasm32")]
+                let path = selector.path.iter();
+                let selector_path_points = path
                     .map(|inputdata| geo::Coordinate {
                         x: inputdata.pos()[0],
                         y: inputdata.pos()[1],
@@ -153,9 +463,50 @@ impl StrokesState {
                     return;
                 }
             }
+            selector::SelectorStyle::Single => {
+                if let Some(tap) = selector.path.first() {
+                    self.update_selection_for_tap(
+                        tap.pos(),
+                        selector.toggle_selection,
+                        Selector::TAP_TOLERANCE,
+                        viewport,
+                    );
+                }
+                return;
+            }
         };
 
-        self.keys_sorted_chrono().iter().for_each(|&key| {
+        // The selection is determined anew for the strokes inside the bounds of the selector, and the strokes which were selected until now
+        let selector_bounds = selector
+            .path
+            .iter()
+            .fold(None, |bounds: Option<AABB>, inputdata| {
+                let pos = na::Point2::from(inputdata.pos());
+                Some(bounds.map_or(AABB::new(pos, pos), |mut bounds| {
+                    bounds.take_point(pos);
+                    bounds
+                }))
+            });
+        let mut keys = selector_bounds
+            .map(|bounds| self.keys_unordered_intersecting_bounds(bounds))
+            .unwrap_or_default();
+        keys.extend(
+            self.selection_components
+                .iter()
+                .filter_map(|(key, selection_comp)| {
+                    if selection_comp.selected {
+                        Some(key)
+                    } else {
+                        None
+                    }
+                }),
+        );
+        keys.sort_unstable();
+        keys.dedup();
+        keys.retain(|&key| self.layer_editable(key));
+        self.sort_keys_chrono(&mut keys);
+
+        keys.iter().for_each(|&key| {
             let stroke = if let Some(stroke) = self.strokes.get(key) {
                 stroke
             } else {
@@ -189,46 +540,137 @@ impl StrokesState {
                                 }
                             }
                             selection_comp.selected = true;
-
-                            if let Some(chrono_comp) = self.chrono_components.get_mut(key) {
-                                self.chrono_counter += 1;
-                                chrono_comp.t = self.chrono_counter;
-                            }
                         }
                     }
                     StrokeStyle::ShapeStroke(shapestroke) => {
                         if selector_polygon.contains(&shapestroke.bounds.to_geo_polygon()) {
                             selection_comp.selected = true;
-
-                            if let Some(chrono_comp) = self.chrono_components.get_mut(key) {
-                                self.chrono_counter += 1;
-                                chrono_comp.t = self.chrono_counter;
-                            }
                         }
                     }
                     StrokeStyle::VectorImage(vectorimage) => {
                         if selector_polygon.contains(&vectorimage.bounds.to_geo_polygon()) {
                             selection_comp.selected = true;
-
-                            if let Some(chrono_comp) = self.chrono_components.get_mut(key) {
-                                self.chrono_counter += 1;
-                                chrono_comp.t = self.chrono_counter;
-                            }
                         }
                     }
                     StrokeStyle::BitmapImage(bitmapimage) => {
                         if selector_polygon.contains(&bitmapimage.bounds.to_geo_polygon()) {
                             selection_comp.selected = true;
-
-                            if let Some(chrono_comp) = self.chrono_components.get_mut(key) {
-                                self.chrono_counter += 1;
-                                chrono_comp.t = self.chrono_counter;
-                            }
+                        }
+                    }
+                    StrokeStyle::TextStroke(textstroke) => {
+                        if selector_polygon.contains(&textstroke.bounds.to_geo_polygon()) {
+                            selection_comp.selected = true;
+                        }
+                    }
+                    StrokeStyle::MathStroke(mathstroke) => {
+                        if selector_polygon.contains(&mathstroke.bounds.to_geo_polygon()) {
+                            selection_comp.selected = true;
+                        }
+                    }
+                    StrokeStyle::CheckboxStroke(checkboxstroke) => {
+                        if selector_polygon.contains(&checkboxstroke.bounds.to_geo_polygon()) {
+                            selection_comp.selected = true;
+                        }
+                    }
+                    StrokeStyle::ExtensionStroke(extensionstroke) => {
+                        if selector_polygon.contains(&extensionstroke.bounds().to_geo_polygon()) {
+                            selection_comp.selected = true;
                         }
                     }
                 }
             }
         });
+
+        self.expand_selection_to_groups();
+    }
+
+    /// Sets the fill color of the selected shapes which can be filled
+    pub fn set_fill_color_selection(&mut self, fill_color: Option<Color>) {
+        for key in self.selection_keys_as_rendered() {
+            self.record_change(key, HistoryChange::ModifyOptions);
+            if let Some(StrokeStyle::ShapeStroke(shapestroke)) = self.strokes.get_mut(key) {
+                if shapestroke.set_fill_color(fill_color) {
+                    self.update_geometry_for_stroke(key);
+                }
+            }
+        }
+    }
+
+    /// Sets the fill rule of the selected shapes which can be filled
+    pub fn set_fill_rule_selection(&mut self, fill_rule: FillRule) {
+        for key in self.selection_keys_as_rendered() {
+            self.record_change(key, HistoryChange::ModifyOptions);
+            if let Some(StrokeStyle::ShapeStroke(shapestroke)) = self.strokes.get_mut(key) {
+                if shapestroke.set_fill_rule(fill_rule) {
+                    self.update_geometry_for_stroke(key);
+                }
+            }
+        }
+    }
+
+    /// Mirrors the selection horizontally ( left to right ) around the center of the selection bounds
+    pub fn mirror_selection_horizontal(&mut self) {
+        self.mirror_selection(na::vector![-1.0, 1.0]);
+    }
+
+    /// Mirrors the selection vertically ( top to bottom ) around the center of the selection bounds
+    pub fn mirror_selection_vertical(&mut self) {
+        self.mirror_selection(na::vector![1.0, -1.0]);
+    }
+
+    /// Mirrors the selection with a scale of -1.0 on the mirrored axis.
+    /// The strokes scale around their own pivot, so they are moved afterwards to their mirrored bounds
+    fn mirror_selection(&mut self, scale: na::Vector2<f64>) {
+        let selection_center = if let Some(selection_bounds) = self.gen_selection_bounds() {
+            selection_bounds.center()
+        } else {
+            return;
+        };
+
+        for key in self.selection_keys_as_rendered() {
+            self.record_change(key, HistoryChange::Modify);
+            if let Some(stroke) = self.strokes.get_mut(key) {
+                self.changed_chunks.mark(key);
+                self.spatial_index.get_mut().mark(key);
+                let old_center = stroke.bounds().center();
+                stroke.scale(scale);
+
+                let new_center = stroke.bounds().center();
+                let mirrored_center =
+                    selection_center + (old_center - selection_center).component_mul(&scale);
+                stroke.translate(mirrored_center - new_center);
+
+                self.update_geometry_for_stroke(key);
+            }
+        }
+    }
+
+    /// Clones the selected strokes to draw them outside of the state, grouped by their layer like `clone_strokes_for_bounds_by_layer()`
+    pub fn clone_selection_by_layer(&self) -> Vec<(f64, Vec<StrokeStyle>)> {
+        self.layer_order
+            .iter()
+            .filter_map(|&layer| {
+                let layer_opacity = self
+                    .layers
+                    .get(layer)
+                    .filter(|layer| layer.visible)?
+                    .opacity;
+
+                let strokes = self
+                    .keys_on_layer(layer)
+                    .into_iter()
+                    .filter(|&key| {
+                        self.does_render(key).unwrap_or(false)
+                            && !(self.trashed(key).unwrap_or(false))
+                            && self.selected(key).unwrap_or(false)
+                    })
+                    .filter_map(|key| self.strokes.get(key))
+                    .cloned()
+                    .collect::<Vec<StrokeStyle>>();
+
+                Some((layer_opacity, strokes))
+            })
+            .collect()
     }
 
     /// the svgs of the current selection, without xml header or svg root
@@ -245,26 +687,60 @@ impl StrokesState {
             .collect::<Vec<render::Svg>>())
     }
 
-    pub fn export_selection_as_svg(&self, file: gio::File) -> Result<(), anyhow::Error> {
-        let selection_svgs = self.gen_svgs_selection()?;
+    /// Exports the selection as a svg, cropped to the bounds of the selection. None if nothing is selected
+    pub fn export_selection_as_svg_string(&self) -> Result<Option<String>, anyhow::Error> {
         let selection_bounds = if let Some(selection_bounds) = self.gen_selection_bounds() {
             selection_bounds
         } else {
-            return Ok(());
+            return Ok(None);
         };
+        let selection_svgs = self.gen_svgs_selection()?;
 
-        let mut svg_data = selection_svgs
+        let svg_data = selection_svgs
             .iter()
             .map(|svg| svg.svg_data.as_str())
             .collect::<Vec<&str>>()
             .join("\n");
 
-        svg_data = compose::wrap_svg_root(
+        Ok(Some(compose::wrap_svg_root(
             svg_data.as_str(),
             Some(selection_bounds),
             Some(selection_bounds),
             true,
-        );
+        )))
+    }
+
+    /// Exports the selection as a PNG image, cropped to the bounds of the selection and with a transparent background.
+    /// None if nothing is selected
+    #[cfg(feature = "cairo")]
+    pub fn export_selection_as_png_bytes(
+        &self,
+        zoom: f64,
+    ) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        let selection_bounds = if let Some(selection_bounds) = self.gen_selection_bounds() {
+            selection_bounds
+        } else {
+            return Ok(None);
+        };
+        let selection_svgs = self.gen_svgs_selection()?;
+
+        let image = render::cairo_drawing_to_image(selection_bounds, zoom, |cx| {
+            render::draw_svgs_to_cairo_context(1.0, &selection_svgs, selection_bounds, cx)
+        })?;
+
+        Ok(Some(render::image_into_encoded_bytes(
+            image,
+            image::ImageOutputFormat::Png,
+        )?))
+    }
+
+    #[cfg(feature = "ui")]
+    pub fn export_selection_as_svg(&self, file: gio::File) -> Result<(), anyhow::Error> {
+        let svg_data = if let Some(svg_data) = self.export_selection_as_svg_string()? {
+            svg_data
+        } else {
+            return Ok(());
+        };
 
         file.replace_async(
             None,
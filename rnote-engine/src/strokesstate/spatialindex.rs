@@ -0,0 +1,197 @@
+//! A spatial index of the bounds of the strokes, so that finding the strokes in some bounds (e.g. in the viewport, under the eraser or inside the selector)
+//! doesn't need to iterate all strokes. The sheet is split into a grid of cells, and every stroke is listed in the cells its bounds intersect.
+//! Changed strokes are only marked, and are indexed again before the next query.
+
+use std::collections::{HashMap, HashSet};
+
+use super::StrokeKey;
+use crate::drawbehaviour::DrawBehaviour;
+use crate::strokes::strokestyle::StrokeStyle;
+
+use p2d::bounding_volume::{BoundingVolume, AABB};
+use slotmap::{HopSlotMap, SecondaryMap};
+
+/// The index of a cell in the grid. The cell with index (0, 0) begins at the origin of the sheet
+type CellIndex = na::Vector2<i64>;
+
+#[derive(Debug, Clone)]
+pub struct SpatialIndex {
+    cells: HashMap<CellIndex, Vec<StrokeKey>>,
+    /// The bounds the strokes are indexed with
+    indexed: SecondaryMap<StrokeKey, AABB>,
+    /// Strokes which would be listed in too many cells. They are checked on every query instead
+    large: HashSet<StrokeKey>,
+    /// Changed strokes, which are indexed again before the next query
+    pending: HashSet<StrokeKey>,
+    /// Whether all strokes need to be indexed again, e.g. after they were replaced or deserialized
+    outdated: bool,
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        Self {
+            cells: HashMap::new(),
+            indexed: SecondaryMap::new(),
+            large: HashSet::new(),
+            pending: HashSet::new(),
+            outdated: true,
+        }
+    }
+}
+
+impl SpatialIndex {
+    /// The size of the cells, in the coordinate space of the sheet
+    pub const CELL_SIZE: f64 = 256.0;
+    /// Strokes intersecting more cells are not listed in the cells, but kept as large strokes
+    pub const LARGE_STROKE_CELLS: f64 = 64.0;
+
+    /// The indices of the first and the last cell (inclusive) the bounds intersect, and the number of cells between them.
+    /// None for bounds which are not finite
+    fn cell_range(bounds: AABB) -> Option<(CellIndex, CellIndex, f64)> {
+        if !(bounds.mins.coords.iter().all(|c| c.is_finite())
+            && bounds.maxs.coords.iter().all(|c| c.is_finite()))
+        {
+            return None;
+        }
+
+        let first = (bounds.mins.coords / Self::CELL_SIZE).map(|c| c.floor());
+        let last = (bounds.maxs.coords / Self::CELL_SIZE).map(|c| c.floor());
+        let n_cells = (last[0] - first[0] + 1.0).max(0.0) * (last[1] - first[1] + 1.0).max(0.0);
+
+        Some((first.map(|c| c as i64), last.map(|c| c as i64), n_cells))
+    }
+
+    /// The number of indexed strokes
+    pub fn len(&self) -> usize {
+        self.indexed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indexed.is_empty()
+    }
+
+    /// Marks the stroke as changed, so it is indexed again before the next query. Also for inserted and removed strokes
+    pub fn mark(&mut self, key: StrokeKey) {
+        if !self.outdated {
+            self.pending.insert(key);
+        }
+    }
+
+    /// Marks all strokes as changed, e.g. when the strokes were replaced
+    pub fn mark_all(&mut self) {
+        self.outdated = true;
+        self.pending.clear();
+    }
+
+    /// Indexes the changed strokes again
+    pub fn update(&mut self, strokes: &HopSlotMap<StrokeKey, StrokeStyle>) {
+        if self.outdated {
+            self.cells.clear();
+            self.indexed.clear();
+            self.large.clear();
+
+            for (key, stroke) in strokes.iter() {
+                self.insert(key, stroke.bounds());
+            }
+            self.outdated = false;
+
+            return;
+        }
+
+        let pending = std::mem::take(&mut self.pending);
+        for key in pending {
+            self.remove(key);
+
+            if let Some(stroke) = strokes.get(key) {
+                self.insert(key, stroke.bounds());
+            }
+        }
+    }
+
+    fn insert(&mut self, key: StrokeKey, bounds: AABB) {
+        self.indexed.insert(key, bounds);
+
+        match Self::cell_range(bounds) {
+            Some((first, last, n_cells)) if n_cells <= Self::LARGE_STROKE_CELLS => {
+                for x in first[0]..=last[0] {
+                    for y in first[1]..=last[1] {
+                        self.cells.entry(na::vector![x, y]).or_default().push(key);
+                    }
+                }
+            }
+            _ => {
+                self.large.insert(key);
+            }
+        }
+    }
+
+    fn remove(&mut self, key: StrokeKey) {
+        let bounds = match self.indexed.remove(key) {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        if self.large.remove(&key) {
+            return;
+        }
+
+        if let Some((first, last, _)) = Self::cell_range(bounds) {
+            for x in first[0]..=last[0] {
+                for y in first[1]..=last[1] {
+                    let index = na::vector![x, y];
+
+                    if let Some(cell) = self.cells.get_mut(&index) {
+                        cell.retain(|&other| other != key);
+
+                        if cell.is_empty() {
+                            self.cells.remove(&index);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The keys of the strokes whose indexed bounds intersect the bounds, in no particular order.
+    /// `update()` needs to be called first, so that the changed strokes are indexed with their current bounds
+    pub fn query(&self, bounds: AABB) -> Vec<StrokeKey> {
+        let (first, last, n_cells) = match Self::cell_range(bounds) {
+            Some(range) => range,
+            None => return vec![],
+        };
+
+        // Checking all strokes is faster when the bounds cover more cells than there are strokes, e.g. far zoomed out
+        if n_cells > self.indexed.len() as f64 {
+            return self
+                .indexed
+                .iter()
+                .filter_map(|(key, indexed_bounds)| {
+                    if indexed_bounds.intersects(&bounds) {
+                        Some(key)
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+        }
+
+        let mut found = HashSet::new();
+        for x in first[0]..=last[0] {
+            for y in first[1]..=last[1] {
+                if let Some(cell) = self.cells.get(&na::vector![x, y]) {
+                    found.extend(cell.iter().copied().filter(|&key| {
+                        self.indexed
+                            .get(key)
+                            .map_or(false, |indexed_bounds| indexed_bounds.intersects(&bounds))
+                    }));
+                }
+            }
+        }
+        found.extend(self.large.iter().copied().filter(|&key| {
+            self.indexed
+                .get(key)
+                .map_or(false, |indexed_bounds| indexed_bounds.intersects(&bounds))
+        }));
+
+        found.into_iter().collect()
+    }
+}
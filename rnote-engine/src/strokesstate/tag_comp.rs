@@ -0,0 +1,251 @@
+use super::history::HistoryChange;
+use super::{StrokeKey, StrokesState};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "tag_component")]
+pub struct TagComponent {
+    /// The normalized tags, sorted and without duplicates. See `StrokesState::normalize_tag()`
+    #[serde(rename = "tags")]
+    pub tags: Vec<String>,
+}
+
+impl Default for TagComponent {
+    fn default() -> Self {
+        Self { tags: vec![] }
+    }
+}
+
+/// How strokes which don't match the tag filter are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "tag_filter_mode")]
+pub enum TagFilterMode {
+    /// Drawn with a low opacity
+    #[serde(rename = "dim")]
+    Dim,
+    /// Not drawn at all
+    #[serde(rename = "hide")]
+    Hide,
+}
+
+impl Default for TagFilterMode {
+    fn default() -> Self {
+        Self::Dim
+    }
+}
+
+/// Filters the drawn strokes by their tags. Strokes match when they have at least one of the tags of the filter
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename = "tag_filter")]
+pub struct TagFilter {
+    #[serde(rename = "tags")]
+    pub tags: Vec<String>,
+    #[serde(rename = "mode")]
+    pub mode: TagFilterMode,
+}
+
+impl Default for TagFilter {
+    fn default() -> Self {
+        Self {
+            tags: vec![],
+            mode: TagFilterMode::default(),
+        }
+    }
+}
+
+impl TagFilter {
+    /// The opacity of the strokes which don't match the filter in the dim mode
+    pub const DIMMED_OPACITY: f64 = 0.2;
+
+    /// A filter for the tags, normalized. None if none of the tags is valid
+    pub fn new<'a>(tags: impl IntoIterator<Item = &'a str>, mode: TagFilterMode) -> Option<Self> {
+        let tags = normalize_tags(tags);
+
+        if tags.is_empty() {
+            None
+        } else {
+            Some(Self { tags, mode })
+        }
+    }
+
+    pub fn matches(&self, tags: &[String]) -> bool {
+        tags.iter().any(|tag| self.tags.contains(tag))
+    }
+}
+
+/// Systems that are related to the tags of strokes, and the tag filter.
+/// The tags are part of the document, the tag filter only affects the view. Exports, thumbnails and the selection are not filtered
+impl StrokesState {
+    /// Normalizes the tag: trimmed, without a leading `#` and lowercase. None if it is empty
+    pub fn normalize_tag(tag: &str) -> Option<String> {
+        let tag = tag.trim();
+        let tag = tag.strip_prefix('#').unwrap_or(tag).trim().to_lowercase();
+
+        if tag.is_empty() {
+            None
+        } else {
+            Some(tag)
+        }
+    }
+
+    /// The tags of the stroke, sorted
+    pub fn tags(&self, key: StrokeKey) -> &[String] {
+        self.tag_components
+            .get(key)
+            .map_or(&[], |tag_comp| tag_comp.tags.as_slice())
+    }
+
+    /// Replaces the tags of the stroke. The tags are normalized, invalid ones are skipped
+    pub fn set_tags<'a>(&mut self, key: StrokeKey, tags: impl IntoIterator<Item = &'a str>) {
+        if !self.strokes.contains_key(key) {
+            log::debug!(
+                "set_tags() failed, stroke with key {:?} does not exist",
+                key
+            );
+            return;
+        }
+
+        let tags = normalize_tags(tags);
+        if self.tags(key) == tags.as_slice() {
+            return;
+        }
+
+        self.record_change(key, HistoryChange::Modify);
+        // Inserting, because strokes from files without tags don't have a tag component yet
+        self.tag_components.insert(key, TagComponent { tags });
+        self.tags_changed();
+    }
+
+    pub fn add_tag(&mut self, key: StrokeKey, tag: &str) {
+        let mut tags = self.tags(key).to_vec();
+        tags.push(tag.to_string());
+
+        self.set_tags(key, tags.iter().map(|tag| tag.as_str()));
+    }
+
+    pub fn remove_tag(&mut self, key: StrokeKey, tag: &str) {
+        if let Some(tag) = Self::normalize_tag(tag) {
+            let tags = self
+                .tags(key)
+                .iter()
+                .filter(|&other| *other != tag)
+                .cloned()
+                .collect::<Vec<String>>();
+
+            self.set_tags(key, tags.iter().map(|tag| tag.as_str()));
+        }
+    }
+
+    /// Replaces the tags of all selected strokes
+    pub fn set_selection_tags<'a>(&mut self, tags: impl IntoIterator<Item = &'a str>) {
+        let tags = normalize_tags(tags);
+
+        for key in self.selection_keys_as_rendered() {
+            self.set_tags(key, tags.iter().map(|tag| tag.as_str()));
+        }
+    }
+
+    pub fn add_tag_to_selection(&mut self, tag: &str) {
+        for key in self.selection_keys_as_rendered() {
+            self.add_tag(key, tag);
+        }
+    }
+
+    pub fn remove_tag_from_selection(&mut self, tag: &str) {
+        for key in self.selection_keys_as_rendered() {
+            self.remove_tag(key, tag);
+        }
+    }
+
+    /// The tags all selected strokes have in common, sorted
+    pub fn selection_tags(&self) -> Vec<String> {
+        let keys = self.selection_keys_as_rendered();
+        let mut tags = match keys.first() {
+            Some(&key) => self.tags(key).to_vec(),
+            None => return vec![],
+        };
+
+        for &key in keys.iter().skip(1) {
+            let other = self.tags(key);
+            tags.retain(|tag| other.contains(tag));
+        }
+
+        tags
+    }
+
+    /// All tags of the strokes which are not trashed, sorted and without duplicates
+    pub fn all_tags(&self) -> Vec<String> {
+        let mut tags = self
+            .tag_components
+            .iter()
+            .filter(|&(key, _)| self.trashed(key) == Some(false))
+            .flat_map(|(_, tag_comp)| tag_comp.tags.iter().cloned())
+            .collect::<Vec<String>>();
+        tags.sort_unstable();
+        tags.dedup();
+
+        tags
+    }
+
+    /// The keys of the strokes with the tag, which are not trashed
+    pub fn keys_with_tag(&self, tag: &str) -> Vec<StrokeKey> {
+        let tag = match Self::normalize_tag(tag) {
+            Some(tag) => tag,
+            None => return vec![],
+        };
+
+        self.tag_components
+            .iter()
+            .filter(|&(key, tag_comp)| {
+                self.trashed(key) == Some(false) && tag_comp.tags.contains(&tag)
+            })
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    pub fn tag_filter(&self) -> Option<&TagFilter> {
+        self.tag_filter.as_ref()
+    }
+
+    /// Sets the filter for the drawn strokes, or removes it when None
+    pub fn set_tag_filter(&mut self, tag_filter: Option<TagFilter>) {
+        if self.tag_filter != tag_filter {
+            self.tag_filter = tag_filter;
+            // The cached tiles are drawn with the previous filter
+            #[cfg(feature = "ui")]
+            self.tile_cache.get_mut().clear();
+        }
+    }
+
+    /// The opacity the stroke is drawn with through the tag filter. None if it is hidden
+    pub fn tag_filter_opacity(&self, key: StrokeKey) -> Option<f64> {
+        match self.tag_filter.as_ref() {
+            Some(tag_filter) if !tag_filter.matches(self.tags(key)) => match tag_filter.mode {
+                TagFilterMode::Dim => Some(TagFilter::DIMMED_OPACITY),
+                TagFilterMode::Hide => None,
+            },
+            _ => Some(1.0),
+        }
+    }
+
+    fn tags_changed(&mut self) {
+        // Strokes might match the filter now or not anymore
+        #[cfg(feature = "ui")]
+        if self.tag_filter.is_some() {
+            self.tile_cache.get_mut().clear();
+        }
+    }
+}
+
+/// Normalizes the tags, sorted and without duplicates
+fn normalize_tags<'a>(tags: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut tags = tags
+        .into_iter()
+        .filter_map(StrokesState::normalize_tag)
+        .collect::<Vec<String>>();
+    tags.sort_unstable();
+    tags.dedup();
+
+    tags
+}
@@ -0,0 +1,57 @@
+use super::{StrokeKey, StrokesState};
+
+use std::cmp::Ordering;
+use std::ops::Range;
+
+use p2d::bounding_volume::AABB;
+
+/// A match of a text search
+#[derive(Debug, Clone)]
+pub struct TextSearchMatch {
+    /// The key of the text containing the match
+    pub key: StrokeKey,
+    /// The byte range of the match in the text
+    pub range: Range<usize>,
+    /// The bounds of the matched text, in the coordinate space of the sheet
+    pub bounds: AABB,
+}
+
+/// Systems that are related to searching the text content of the sheet.
+impl StrokesState {
+    /// Searches the texts which are not trashed and on visible layers for the query.
+    /// Handwriting is not recognized, so only typed text is found.
+    /// The matches are ordered as they appear on the sheet, from top to bottom and left to right
+    pub fn search_text(&self, query: &str, case_sensitive: bool) -> Vec<TextSearchMatch> {
+        let mut matches = self
+            .keys_as_rendered()
+            .into_iter()
+            .filter(|&key| !self.trashed(key).unwrap_or(true))
+            .filter_map(|key| Some((key, self.get_textstroke(key)?)))
+            .flat_map(|(key, textstroke)| {
+                textstroke
+                    .find_text(query, case_sensitive)
+                    .into_iter()
+                    .filter_map(move |range| {
+                        Some(TextSearchMatch {
+                            key,
+                            bounds: textstroke.range_bounds(range.clone())?,
+                            range,
+                        })
+                    })
+            })
+            .collect::<Vec<TextSearchMatch>>();
+
+        matches.sort_by(|first, second| {
+            first.bounds.mins[1]
+                .partial_cmp(&second.bounds.mins[1])
+                .unwrap_or(Ordering::Equal)
+                .then(
+                    first.bounds.mins[0]
+                        .partial_cmp(&second.bounds.mins[0])
+                        .unwrap_or(Ordering::Equal),
+                )
+        });
+
+        matches
+    }
+}
@@ -0,0 +1,193 @@
+//! Caches the rendering of the strokes in tiles. The sheet is split into a grid of tiles, and every tile in view keeps the strokes intersecting it
+//! composited into a single texture. Drawing the strokes then only draws the textures of the visible tiles, and only the tiles under strokes
+//! whose rendering changed since the last draw are generated again (see `StrokesState::draw_strokes_tiled()`).
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use super::layer_comp::LayerKey;
+use super::StrokeKey;
+use crate::compose::geometry::AABBHelpers;
+use crate::render;
+
+#[cfg(feature = "ui")]
+use glib::translate::ToGlibPtr;
+#[cfg(feature = "ui")]
+use gtk4::{gsk, prelude::*, Widget};
+use p2d::bounding_volume::AABB;
+
+/// The index of a tile in the grid. The tile with index (0, 0) begins at the origin of the sheet
+pub type TileIndex = na::Vector2<i64>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileKey {
+    pub layer: LayerKey,
+    pub index: TileIndex,
+}
+
+#[derive(Debug, Clone)]
+struct Tile {
+    /// The signature of the strokes the tile was generated from, see `TileCache::signature()`
+    signature: u64,
+    /// The composited strokes. Kept alive, so the addresses of their rendernodes in the signature can't be reused by new rendernodes
+    _content: gsk::RenderNode,
+    /// The composited strokes rendered into a texture
+    rendernode: gsk::RenderNode,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TileCache {
+    /// The zoom the tiles are generated for
+    zoom: f64,
+    tiles: HashMap<TileKey, Tile>,
+    /// The tiles which were generated in the current draw, for visual debugging
+    generated: Vec<TileKey>,
+}
+
+impl TileCache {
+    /// The size of the tiles, in surface coordinates
+    pub const TILE_SIZE: f64 = 512.0;
+
+    /// The bounds of the tile with the index at the zoom, in the coordinate space of the sheet
+    pub fn tile_bounds(index: TileIndex, zoom: f64) -> AABB {
+        let size = Self::TILE_SIZE / zoom;
+        let mins = na::point![index[0] as f64 * size, index[1] as f64 * size];
+
+        AABB::new(mins, mins + na::Vector2::from_element(size))
+    }
+
+    /// The indices of the first and the last tile (inclusive) which the bounds intersect at the zoom
+    pub fn tile_index_range(bounds: AABB, zoom: f64) -> (TileIndex, TileIndex) {
+        let size = Self::TILE_SIZE / zoom;
+
+        (
+            na::vector![
+                (bounds.mins[0] / size).floor() as i64,
+                (bounds.mins[1] / size).floor() as i64
+            ],
+            na::vector![
+                (bounds.maxs[0] / size).floor() as i64,
+                (bounds.maxs[1] / size).floor() as i64
+            ],
+        )
+    }
+
+    /// The signature of the strokes with their rendernodes, in the order they are composited.
+    /// It changes when any of the strokes gets a new rendering, is reordered, added or removed
+    #[cfg(feature = "ui")]
+    pub fn signature<'a>(
+        strokes: impl IntoIterator<Item = (StrokeKey, &'a gsk::RenderNode)>,
+    ) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        for (key, rendernode) in strokes {
+            let ptr: *mut gsk::ffi::GskRenderNode = rendernode.to_glib_none().0;
+
+            key.hash(&mut hasher);
+            (ptr as usize).hash(&mut hasher);
+        }
+
+        hasher.finish()
+    }
+
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    pub fn n_tiles(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// The tiles which were generated in the current draw
+    pub fn generated_tiles(&self) -> &[TileKey] {
+        &self.generated
+    }
+
+    /// Begins a new draw at the zoom. The tiles of another zoom are discarded, as the rendernodes of the strokes are regenerated for the new zoom anyway
+    pub fn begin_draw(&mut self, zoom: f64) {
+        if self.zoom != zoom {
+            self.tiles.clear();
+            self.zoom = zoom;
+        }
+        self.generated.clear();
+    }
+
+    /// The rendernode of the tile, in surface coordinates. Taken from the cache when the signature matches,
+    /// else the content is generated and rendered into a texture by the renderer of the widget.
+    /// Without a widget, or when rendering the texture fails, the content is drawn clipped to the tile instead
+    #[cfg(feature = "ui")]
+    pub fn tile_rendernode(
+        &mut self,
+        key: TileKey,
+        signature: u64,
+        gen_content: impl FnOnce() -> Option<gsk::RenderNode>,
+        active_widget: Option<&Widget>,
+    ) -> Option<gsk::RenderNode> {
+        if let Some(tile) = self.tiles.get(&key) {
+            if tile.signature == signature {
+                return Some(tile.rendernode.clone());
+            }
+        }
+
+        self.generated.push(key);
+
+        let content = match gen_content() {
+            Some(content) => content,
+            None => {
+                self.tiles.remove(&key);
+                return None;
+            }
+        };
+
+        let surface_bounds =
+            Self::tile_bounds(key.index, self.zoom).scale(na::Vector2::from_element(self.zoom));
+        let texture = active_widget.and_then(|widget| {
+            match render::rendernode_to_texture(widget, &content, Some(surface_bounds)) {
+                Ok(texture) => texture,
+                Err(e) => {
+                    log::error!(
+                        "rendernode_to_texture() in tile_rendernode() failed with Err {}",
+                        e
+                    );
+                    None
+                }
+            }
+        });
+        let rendernode: gsk::RenderNode = match texture {
+            Some(texture) => {
+                gsk::TextureNode::new(&texture, &surface_bounds.to_graphene_rect()).upcast()
+            }
+            None => gsk::ClipNode::new(&content, &surface_bounds.to_graphene_rect()).upcast(),
+        };
+
+        self.tiles.insert(
+            key,
+            Tile {
+                signature,
+                _content: content,
+                rendernode: rendernode.clone(),
+            },
+        );
+
+        Some(rendernode)
+    }
+
+    /// Discards the tiles outside of the bounds (in sheet coordinates) and of removed layers, so the cache doesn't grow while moving around the sheet
+    pub fn retain(&mut self, bounds: AABB, layers: &[LayerKey]) {
+        let (first, last) = Self::tile_index_range(bounds, self.zoom);
+
+        self.tiles.retain(|key, _| {
+            layers.contains(&key.layer)
+                && key.index[0] >= first[0]
+                && key.index[1] >= first[1]
+                && key.index[0] <= last[0]
+                && key.index[1] <= last[1]
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.tiles.clear();
+        self.generated.clear();
+    }
+}
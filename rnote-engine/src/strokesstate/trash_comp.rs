@@ -2,6 +2,7 @@ use crate::drawbehaviour::DrawBehaviour;
 use crate::pens::eraser::Eraser;
 use crate::strokes::strokestyle::StrokeStyle;
 
+use super::history::HistoryChange;
 use super::{StrokeKey, StrokesState};
 
 use p2d::bounding_volume::{BoundingVolume, AABB};
@@ -39,6 +40,7 @@ impl StrokesState {
     }
 
     pub fn set_trashed(&mut self, key: StrokeKey, trash: bool) {
+        self.record_change(key, HistoryChange::Trash);
         if let Some(trash_comp) = self.trash_components.get_mut(key) {
             trash_comp.trashed = trash;
 
@@ -54,44 +56,13 @@ impl StrokesState {
         }
     }
 
-    /// Resize needed after calling this
-    pub fn undo_last_stroke(&mut self) -> Option<StrokeKey> {
-        let last_stroke_key = self.last_stroke_key();
-        if let Some(last_stroke_key) = last_stroke_key {
-            self.set_trashed(last_stroke_key, true);
-
-            Some(last_stroke_key)
-        } else {
-            None
-        }
-    }
-
-    /// Resize needed after calling this
-    pub fn redo_last_stroke(&mut self) -> Option<StrokeKey> {
-        let last_trashed_key = self.last_trashed_key();
-        if let Some(last_trashed_key) = last_trashed_key {
-            self.set_trashed(last_trashed_key, false);
-
-            Some(last_trashed_key)
-        } else {
-            None
-        }
-    }
-
     pub fn trash_selection(&mut self) {
         self.selection_keys_as_rendered().iter().for_each(|&key| {
             if let Some(selection_comp) = self.selection_components.get_mut(key) {
                 if selection_comp.selected {
                     selection_comp.selected = false;
 
-                    if let Some(trash_comp) = self.trash_components.get_mut(key) {
-                        trash_comp.trashed = true;
-
-                        if let Some(chrono_comp) = self.chrono_components.get_mut(key) {
-                            self.chrono_counter += 1;
-                            chrono_comp.t = self.chrono_counter;
-                        }
-                    }
+                    self.set_trashed(key, true);
                 }
             }
         });
@@ -99,19 +70,20 @@ impl StrokesState {
 
     /// trash strokes that collide with the eraser
     pub fn trash_colliding_strokes(&mut self, eraser: &Eraser, viewport: Option<AABB>) {
-        if let Some(eraser_current_input) = eraser.current_input {
-            let eraser_bounds = AABB::new(
-                na::Point2::from(
-                    eraser_current_input.pos()
-                        - na::vector![eraser.width / 2.0, eraser.width / 2.0],
-                ),
-                na::Point2::from(
-                    eraser_current_input.pos()
-                        + na::vector![eraser.width / 2.0, eraser.width / 2.0],
-                ),
-            );
+        if let Some(eraser_footprint) = eraser.footprint() {
+            let mut colliding = Vec::new();
 
-            self.strokes.iter_mut().for_each(|(key, stroke)| {
+            // Only the strokes near the eraser can collide with it
+            let keys = self.keys_unordered_intersecting_bounds(eraser_footprint.bounds());
+            keys.into_iter().for_each(|key| {
+                let stroke = match self.strokes.get(key) {
+                    Some(stroke) => stroke,
+                    None => return,
+                };
+                // skip if stroke is already trashed, or on a hidden or locked layer
+                if self.trashed(key).unwrap_or(true) || !self.layer_editable(key) {
+                    return;
+                }
                 if let Some(viewport) = viewport {
                     if !viewport.intersects(&stroke.bounds()) {
                         return;
@@ -120,19 +92,10 @@ impl StrokesState {
                 match stroke {
                     StrokeStyle::BrushStroke(brushstroke) => {
                         // First check markerstroke bounds, then conditionally check hitbox
-                        if eraser_bounds.intersects(&brushstroke.bounds) {
+                        if eraser_footprint.intersects_aabb(&brushstroke.bounds) {
                             for hitbox_elem in brushstroke.hitboxes.iter() {
-                                if eraser_bounds.intersects(hitbox_elem) {
-                                    if let Some(trash_comp) = self.trash_components.get_mut(key) {
-                                        trash_comp.trashed = true;
-
-                                        if let Some(chrono_comp) =
-                                            self.chrono_components.get_mut(key)
-                                        {
-                                            self.chrono_counter += 1;
-                                            chrono_comp.t = self.chrono_counter;
-                                        }
-                                    }
+                                if eraser_footprint.intersects_aabb(hitbox_elem) {
+                                    colliding.push(key);
 
                                     return;
                                 }
@@ -140,15 +103,8 @@ impl StrokesState {
                         }
                     }
                     StrokeStyle::ShapeStroke(shapestroke) => {
-                        if eraser_bounds.intersects(&shapestroke.bounds) {
-                            if let Some(trash_comp) = self.trash_components.get_mut(key) {
-                                trash_comp.trashed = true;
-
-                                if let Some(chrono_comp) = self.chrono_components.get_mut(key) {
-                                    self.chrono_counter += 1;
-                                    chrono_comp.t = self.chrono_counter;
-                                }
-                            }
+                        if eraser_footprint.intersects_aabb(&shapestroke.bounds) {
+                            colliding.push(key);
                         }
                     }
                     StrokeStyle::VectorImage(_vectorimage) => {
@@ -157,8 +113,227 @@ impl StrokesState {
                     StrokeStyle::BitmapImage(_bitmapimage) => {
                         // Ignore BitmapImage when trashing with the Eraser
                     }
+                    StrokeStyle::TextStroke(_textstroke) => {
+                        // Ignore TextStroke when trashing with the Eraser
+                    }
+                    StrokeStyle::MathStroke(_mathstroke) => {
+                        // Ignore MathStroke when trashing with the Eraser
+                    }
+                    StrokeStyle::CheckboxStroke(_checkboxstroke) => {
+                        // Ignore CheckboxStroke when trashing with the Eraser
+                    }
+                    StrokeStyle::ExtensionStroke(_extensionstroke) => {
+                        // Ignore ExtensionStroke when trashing with the Eraser
+                    }
                 }
             });
+
+            for key in colliding {
+                self.set_trashed(key, true);
+            }
         }
     }
+
+    /// Split brush strokes that collide with the eraser, removing the erased portions. The split strokes are trashed and replaced by their remaining segments.
+    /// Shape strokes are trashed entirely, images and texts are ignored.
+    /// Returns the keys of the inserted segments, which need their rendering regenerated
+    pub fn split_colliding_strokes(
+        &mut self,
+        eraser: &Eraser,
+        viewport: Option<AABB>,
+    ) -> Vec<StrokeKey> {
+        let eraser_footprint = if let Some(eraser_footprint) = eraser.footprint() {
+            eraser_footprint
+        } else {
+            return vec![];
+        };
+
+        let mut split_strokes = Vec::new();
+
+        // Only the strokes near the eraser can collide with it
+        let keys = self.keys_unordered_intersecting_bounds(eraser_footprint.bounds());
+        keys.into_iter().for_each(|key| {
+            let stroke = match self.strokes.get(key) {
+                Some(stroke) => stroke,
+                None => return,
+            };
+            if self.trashed(key).unwrap_or(true) || !self.layer_editable(key) {
+                return;
+            }
+            if let Some(viewport) = viewport {
+                if !viewport.intersects(&stroke.bounds()) {
+                    return;
+                }
+            }
+            match stroke {
+                StrokeStyle::BrushStroke(brushstroke) => {
+                    if let Some(segments) = brushstroke.split_w_eraser_footprint(&eraser_footprint)
+                    {
+                        split_strokes.push((key, segments));
+                    }
+                }
+                StrokeStyle::ShapeStroke(shapestroke) => {
+                    if eraser_footprint.intersects_aabb(&shapestroke.bounds) {
+                        split_strokes.push((key, vec![]));
+                    }
+                }
+                StrokeStyle::VectorImage(_vectorimage) => {
+                    // Ignore VectorImage when splitting with the Eraser
+                }
+                StrokeStyle::BitmapImage(_bitmapimage) => {
+                    // Ignore BitmapImage when splitting with the Eraser
+                }
+                StrokeStyle::TextStroke(_textstroke) => {
+                    // Ignore TextStroke when splitting with the Eraser
+                }
+                StrokeStyle::MathStroke(_mathstroke) => {
+                    // Ignore MathStroke when splitting with the Eraser
+                }
+                StrokeStyle::CheckboxStroke(_checkboxstroke) => {
+                    // Ignore CheckboxStroke when splitting with the Eraser
+                }
+                StrokeStyle::ExtensionStroke(_extensionstroke) => {
+                    // Ignore ExtensionStroke when splitting with the Eraser
+                }
+            }
+        });
+
+        if split_strokes.is_empty() {
+            return vec![];
+        }
+
+        // Trashing the split strokes and inserting their segments is undone at once
+        self.begin_history_group();
+        let segment_keys = split_strokes
+            .into_iter()
+            .flat_map(|(key, segments)| {
+                // Captured before trashing, which moves the stroke to the front of the chronology
+                let components = self.stroke_components(key);
+                self.set_trashed(key, true);
+
+                segments
+                    .into_iter()
+                    .map(|segment| {
+                        let segment_key = self.insert_stroke(StrokeStyle::BrushStroke(segment));
+                        // The segments take the place of the split stroke, in its chronology, group and layer, with its link and tags
+                        if let Some(components) = components.clone() {
+                            self.set_stroke_components(segment_key, components);
+                        }
+                        segment_key
+                    })
+                    .collect::<Vec<StrokeKey>>()
+            })
+            .collect();
+        self.end_history_group();
+
+        segment_keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pens::brush::Brush;
+    use crate::pens::eraser::EraserStyle;
+    use crate::strokes::brushstroke::BrushStroke;
+    use crate::strokes::element::Element;
+    use crate::strokes::inputdata::InputData;
+
+    /// Inserts a horizontal brush stroke from x 0.0 to 200.0, with the elements 10.0 apart
+    fn insert_brushstroke(strokes_state: &mut StrokesState) -> StrokeKey {
+        let elements = (0..=20)
+            .map(|i| Element::new(InputData::new(na::vector![f64::from(i) * 10.0, 100.0], 1.0)));
+        let key = strokes_state.insert_stroke(StrokeStyle::BrushStroke(
+            BrushStroke::new_w_elements(elements, &Brush::default()).unwrap(),
+        ));
+        strokes_state.commit_history();
+
+        key
+    }
+
+    /// A splitting eraser, 30.0 wide at the position
+    fn splitting_eraser(pos: na::Vector2<f64>) -> Eraser {
+        let mut eraser = Eraser::new(30.0);
+        eraser.style = EraserStyle::SplitCollidingStrokes;
+        eraser.current_input = Some(InputData::new(pos, 1.0));
+
+        eraser
+    }
+
+    /// The x coordinates of the elements of the segments, ordered from left to right
+    fn segments_xs(strokes_state: &StrokesState, keys: &[StrokeKey]) -> Vec<Vec<f64>> {
+        let mut segments_xs = keys
+            .iter()
+            .map(|&key| match strokes_state.get_stroke_ref(key) {
+                Some(StrokeStyle::BrushStroke(brushstroke)) => brushstroke
+                    .elements
+                    .iter()
+                    .map(|element| element.inputdata.pos()[0])
+                    .collect::<Vec<f64>>(),
+                _ => panic!("the segment is not a brush stroke"),
+            })
+            .collect::<Vec<Vec<f64>>>();
+        segments_xs.sort_by(|first, second| first[0].total_cmp(&second[0]));
+
+        segments_xs
+    }
+
+    #[test]
+    fn erasing_the_middle_splits_the_stroke_in_two() {
+        let mut strokes_state = StrokesState::default();
+        let key = insert_brushstroke(&mut strokes_state);
+
+        let segments = strokes_state
+            .split_colliding_strokes(&splitting_eraser(na::vector![100.0, 100.0]), None);
+        assert_eq!(segments.len(), 2);
+        assert_eq!(strokes_state.trashed(key), Some(true));
+
+        let segments_xs = segments_xs(&strokes_state, &segments);
+        assert_eq!(segments_xs[0].first(), Some(&0.0));
+        assert!(segments_xs[0].iter().all(|&x| x <= 85.0));
+        assert!(segments_xs[1].iter().all(|&x| x >= 115.0));
+        assert_eq!(segments_xs[1].last(), Some(&200.0));
+    }
+
+    #[test]
+    fn erasing_an_end_shortens_the_stroke() {
+        let mut strokes_state = StrokesState::default();
+        insert_brushstroke(&mut strokes_state);
+
+        let segments = strokes_state
+            .split_colliding_strokes(&splitting_eraser(na::vector![200.0, 100.0]), None);
+        assert_eq!(segments.len(), 1);
+
+        let segments_xs = segments_xs(&strokes_state, &segments);
+        assert_eq!(segments_xs[0].first(), Some(&0.0));
+        assert!(segments_xs[0].iter().all(|&x| x <= 185.0));
+
+        // Erasing away from the stroke splits nothing
+        assert!(strokes_state
+            .split_colliding_strokes(&splitting_eraser(na::vector![100.0, 300.0]), None)
+            .is_empty());
+    }
+
+    #[test]
+    fn splitting_is_undone() {
+        let mut strokes_state = StrokesState::default();
+        let key = insert_brushstroke(&mut strokes_state);
+
+        let segments = strokes_state
+            .split_colliding_strokes(&splitting_eraser(na::vector![100.0, 100.0]), None);
+        let split_xs = segments_xs(&strokes_state, &segments);
+        assert_eq!(strokes_state.keys_as_rendered().len(), 2);
+
+        assert!(strokes_state.undo());
+        assert_eq!(strokes_state.keys_as_rendered(), vec![key]);
+        assert_eq!(strokes_state.trashed(key), Some(false));
+
+        assert!(strokes_state.redo());
+        assert_eq!(strokes_state.trashed(key), Some(true));
+        // The segments are inserted again, with new keys
+        assert_eq!(
+            segments_xs(&strokes_state, &strokes_state.keys_as_rendered()),
+            split_xs
+        );
+    }
 }
@@ -3,22 +3,17 @@ use std::io::prelude::*;
 
 use flate2::read::MultiGzDecoder;
 use flate2::{Compression, GzBuilder};
-use gtk4::glib;
 use p2d::bounding_volume::AABB;
 use rand::{Rng, SeedableRng};
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::slice::ParallelSliceMut;
 
 use crate::strokes::inputdata::InputData;
 
 pub const INPUT_OVERSHOOT: f64 = 30.0;
 
 pub fn now_formatted_string() -> String {
-    match glib::DateTime::now_local() {
-        Ok(datetime) => match datetime.format("%F_%H-%M-%S") {
-            Ok(s) => s.to_string(),
-            Err(_) => String::from("1970-01-01_12-00-00"),
-        },
-        Err(_) => String::from("1970-01-01_12-00-00"),
-    }
+    chrono::Local::now().format("%F_%H-%M-%S").to_string()
 }
 
 /// returns a new seed by generating a random value seeded from the old seed
@@ -27,6 +22,35 @@ pub fn seed_advance(seed: u64) -> u64 {
     rng.gen()
 }
 
+/// returns the seed for the segment at the given index. Every segment seed is independent of the other segments, so segments can be regenerated on their own
+pub fn seed_for_segment(seed: u64, segment_index: usize) -> u64 {
+    // Multiplying with the golden ratio spreads consecutive indices across all bits
+    seed_advance(seed ^ (segment_index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// Runs the job on the global threadpool. No threads can be spawned on wasm32, there it runs on the current thread before returning
+pub fn spawn<F>(job: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    rayon::spawn(job);
+    #[cfg(target_arch = "wasm32")]
+    job();
+}
+
+/// Sorts the slice with the comparator, in parallel where threads are available
+pub fn sort_unstable_by<T, F>(slice: &mut [T], compare: F)
+where
+    T: Send,
+    F: Fn(&T, &T) -> std::cmp::Ordering + Sync,
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    slice.par_sort_unstable_by(compare);
+    #[cfg(target_arch = "wasm32")]
+    slice.sort_unstable_by(compare);
+}
+
 pub fn convert_value_dpi(value: f64, current_dpi: f64, target_dpi: f64) -> f64 {
     (value / current_dpi) * target_dpi
 }
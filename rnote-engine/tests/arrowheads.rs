@@ -0,0 +1,168 @@
+use nalgebra as na;
+use rnote_engine::compose::arrowhead::{ArrowheadOptions, ArrowheadStyle};
+use rnote_engine::compose::curves;
+use rnote_engine::compose::shapes;
+use rnote_engine::compose::smooth::SmoothOptions;
+use rnote_engine::compose::transformable::Transform;
+use rnote_engine::drawbehaviour::DrawBehaviour;
+use rnote_engine::strokes::shapestroke::{Shape, ShapeStroke};
+
+fn assert_near(first: na::Vector2<f64>, second: na::Vector2<f64>) {
+    assert!(
+        (first - second).magnitude() < 1e-9,
+        "{} != {}",
+        first,
+        second
+    );
+}
+
+/// Asserts the position of the end and the direction of its tangent
+fn assert_end(
+    end: (na::Vector2<f64>, na::Vector2<f64>),
+    pos: na::Vector2<f64>,
+    direction: na::Vector2<f64>,
+) {
+    assert_near(end.0, pos);
+    assert_near(end.1.normalize(), direction);
+}
+
+fn arc(sweep_angle: f64, transform: Transform) -> shapes::Arc {
+    shapes::Arc {
+        radius: 10.0,
+        start_angle: 0.0,
+        sweep_angle,
+        transform,
+    }
+}
+
+fn end_arrowhead() -> ArrowheadOptions {
+    ArrowheadOptions {
+        end: ArrowheadStyle::Filled,
+        ..ArrowheadOptions::default()
+    }
+}
+
+#[test]
+fn line_arrowheads_point_along_the_line() {
+    let [start, end] = Shape::Line(curves::Line {
+        start: na::vector![0.0, 0.0],
+        end: na::vector![0.0, 20.0],
+    })
+    .ends_w_tangents()
+    .unwrap();
+
+    assert_end(start, na::vector![0.0, 0.0], na::vector![0.0, 1.0]);
+    assert_end(end, na::vector![0.0, 20.0], na::vector![0.0, 1.0]);
+}
+
+#[test]
+fn arc_arrowheads_follow_the_sweep() {
+    let center = na::vector![5.0, 5.0];
+    let transform = Transform::new_w_isometry(na::Isometry2::new(center, 0.0));
+
+    // Counter clockwise in the math sense, the end arrowhead points back towards negative x
+    let [start, end] = Shape::Arc(arc(std::f64::consts::FRAC_PI_2, transform))
+        .ends_w_tangents()
+        .unwrap();
+    assert_end(
+        start,
+        center + na::vector![10.0, 0.0],
+        na::vector![0.0, 1.0],
+    );
+    assert_end(end, center + na::vector![0.0, 10.0], na::vector![-1.0, 0.0]);
+
+    // The same quarter swept the other way
+    let [start, end] = Shape::Arc(arc(-std::f64::consts::FRAC_PI_2, transform))
+        .ends_w_tangents()
+        .unwrap();
+    assert_end(
+        start,
+        center + na::vector![10.0, 0.0],
+        na::vector![0.0, -1.0],
+    );
+    assert_end(
+        end,
+        center + na::vector![0.0, -10.0],
+        na::vector![-1.0, 0.0],
+    );
+}
+
+#[test]
+fn arc_arrowheads_are_rotated_with_the_arc() {
+    let transform = Transform::new_w_isometry(na::Isometry2::new(
+        na::Vector2::zeros(),
+        std::f64::consts::FRAC_PI_2,
+    ));
+
+    let [start, end] = Shape::Arc(arc(std::f64::consts::FRAC_PI_2, transform))
+        .ends_w_tangents()
+        .unwrap();
+    assert_end(start, na::vector![0.0, 10.0], na::vector![-1.0, 0.0]);
+    assert_end(end, na::vector![-10.0, 0.0], na::vector![0.0, -1.0]);
+}
+
+#[test]
+fn polyline_arrowheads_follow_the_end_segments() {
+    let mut polyline = shapes::Polyline {
+        vertices: vec![
+            na::vector![0.0, 0.0],
+            na::vector![10.0, 0.0],
+            na::vector![10.0, 10.0],
+            // The vertex being placed can be on top of the last one
+            na::vector![10.0, 10.0],
+        ],
+        closed: false,
+    };
+
+    let [start, end] = Shape::Polyline(polyline.clone()).ends_w_tangents().unwrap();
+    assert_end(start, na::vector![0.0, 0.0], na::vector![1.0, 0.0]);
+    assert_end(end, na::vector![10.0, 10.0], na::vector![0.0, 1.0]);
+
+    // Closed polylines have no ends
+    polyline.closed = true;
+    assert!(Shape::Polyline(polyline).ends_w_tangents().is_none());
+    assert!(Shape::Rectangle(shapes::Rectangle::default())
+        .ends_w_tangents()
+        .is_none());
+}
+
+#[test]
+fn curves_end_before_filled_arrowheads() {
+    let options = end_arrowhead();
+    let stroke_width = 1.0;
+    let inset = options.size(stroke_width) * 0.5;
+
+    let inset_arc = options.inset_arc(
+        arc(std::f64::consts::FRAC_PI_2, Transform::default()),
+        stroke_width,
+    );
+    assert_eq!(inset_arc.start_angle, 0.0);
+    assert!((inset_arc.sweep_angle - (std::f64::consts::FRAC_PI_2 - inset / 10.0)).abs() < 1e-9);
+
+    let inset_polyline = options.inset_polyline(
+        &shapes::Polyline {
+            vertices: vec![
+                na::vector![0.0, 0.0],
+                na::vector![10.0, 0.0],
+                na::vector![10.0, 10.0],
+            ],
+            closed: false,
+        },
+        stroke_width,
+    );
+    assert_near(inset_polyline.vertices[0], na::vector![0.0, 0.0]);
+    assert_near(inset_polyline.vertices[1], na::vector![10.0, 0.0]);
+    assert_near(inset_polyline.vertices[2], na::vector![10.0, 10.0 - inset]);
+}
+
+#[test]
+fn arc_bounds_include_the_arrowheads() {
+    let shape = Shape::Arc(arc(std::f64::consts::FRAC_PI_2, Transform::default()));
+    let options = SmoothOptions::default();
+
+    let without = ShapeStroke::new_w_shape(shape.clone(), options, ArrowheadOptions::default());
+    let with = ShapeStroke::new_w_shape(shape, options, end_arrowhead());
+
+    assert!(with.bounds().maxs[0] > without.bounds().maxs[0]);
+    assert!(with.bounds().mins[0] < without.bounds().mins[0]);
+}
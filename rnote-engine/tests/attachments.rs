@@ -0,0 +1,78 @@
+use rnote_engine::sheet::Sheet;
+
+fn reopen(sheet: &mut Sheet) -> Sheet {
+    let bytes = sheet.save_sheet_as_rnote_bytes().unwrap();
+
+    let mut reopened = Sheet::default();
+    reopened
+        .open_sheet_from_rnote_bytes(bytes)
+        .unwrap();
+    reopened
+}
+
+#[test]
+fn attachments_are_saved_with_the_sheet() {
+    let mut sheet = Sheet::default();
+    let csv = b"a,b\n1,2\n".to_vec();
+    sheet.add_attachment("table.csv", &csv);
+    sheet.add_attachment("source.bin", &[0, 159, 146, 150]);
+
+    let reopened = reopen(&mut sheet);
+    let attachments = reopened.attachments();
+    assert_eq!(attachments.len(), 2);
+    assert_eq!(attachments[0].name, "table.csv");
+    assert_eq!(attachments[0].size, csv.len() as u64);
+    assert_eq!(attachments[0].data().unwrap(), csv);
+    assert_eq!(attachments[1].data().unwrap(), vec![0, 159, 146, 150]);
+    assert_eq!(reopened.attachment_index("source.bin"), Some(1));
+}
+
+#[test]
+fn attachment_names_are_unique_and_without_directories() {
+    let mut sheet = Sheet::default();
+    let first = sheet.add_attachment("report.pdf", b"first");
+    let second = sheet.add_attachment("report.pdf", b"second");
+    let third = sheet.add_attachment("../../report.pdf", b"third");
+    let unnamed = sheet.add_attachment("..", b"unnamed");
+
+    let names = [first, second, third, unnamed]
+        .iter()
+        .map(|&index| sheet.attachments()[index].name.as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        names,
+        vec![
+            "report.pdf",
+            "report (2).pdf",
+            "report (3).pdf",
+            "attachment"
+        ]
+    );
+
+    assert!(sheet.remove_attachment(second).is_some());
+    // The freed name is taken again
+    sheet.add_attachment("report.pdf", b"again");
+    assert!(sheet.attachment_index("report (2).pdf").is_some());
+}
+
+#[test]
+fn attachments_are_extracted_into_the_directory() {
+    let dir = std::env::temp_dir().join(format!("rnote-attachments-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut sheet = Sheet::default();
+    sheet.add_attachment("notes.txt", b"some notes");
+    sheet.add_attachment("image.png", b"not really a png");
+
+    let paths = sheet.extract_attachments(&dir).unwrap();
+    assert_eq!(paths, vec![dir.join("notes.txt"), dir.join("image.png")]);
+    assert_eq!(std::fs::read(&paths[0]).unwrap(), b"some notes");
+
+    // Attaching from a path keeps the file name
+    let mut other = Sheet::default();
+    let index = other.add_attachment_from_path(&paths[0]).unwrap();
+    assert_eq!(other.attachments()[index].name, "notes.txt");
+    assert!(other.extract_attachment(1, &dir).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use nalgebra as na;
+use rnote_engine::sheet::Sheet;
+use rnote_engine::strokes::element::Element;
+use rnote_engine::strokes::inputdata::InputData;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+use rnote_engine::strokesstate::audionotes::AudioNote;
+use rnote_engine::strokesstate::StrokesState;
+
+/// A horizontal brush stroke, written at the moment
+fn written_brushstroke(start: na::Vector2<f64>, written_at: chrono::DateTime<Utc>) -> StrokeStyle {
+    common::brushstroke_w_elements((0..5).map(|i| Element {
+        inputdata: InputData::new(start + na::vector![i as f64 * 10.0, 0.0], 1.0),
+        timestamp: Some(written_at + Duration::milliseconds(i * 100)),
+    }))
+}
+
+fn audio_note(started: chrono::DateTime<Utc>, duration: f64) -> AudioNote {
+    AudioNote::new(
+        &[1, 2, 3, 4],
+        String::from(AudioNote::MIME_TYPE_DEFAULT),
+        started,
+        duration,
+    )
+}
+
+fn assert_approx(first: f64, second: f64) {
+    assert!((first - second).abs() < 1e-3, "{} != {}", first, second);
+}
+
+#[test]
+fn strokes_map_to_the_moment_they_were_written() {
+    let started = Utc::now();
+    let mut strokes_state = StrokesState::default();
+    strokes_state.add_audio_note(audio_note(started, 60.0));
+
+    let early = strokes_state.insert_stroke(written_brushstroke(na::vector![0.0, 0.0], started));
+    let later = strokes_state.insert_stroke(written_brushstroke(
+        na::vector![0.0, 50.0],
+        started + Duration::seconds(30),
+    ));
+    let before = strokes_state.insert_stroke(written_brushstroke(
+        na::vector![0.0, 100.0],
+        started - Duration::seconds(5),
+    ));
+
+    assert_eq!(
+        strokes_state.written_at(later),
+        Some(started + Duration::seconds(30))
+    );
+    let position = strokes_state.audio_position_for_stroke(later).unwrap();
+    assert_eq!(position.note_index, 0);
+    assert_approx(position.offset, 30.0 - StrokesState::AUDIO_LEAD_TIME);
+    // Playback does not start before the recording
+    assert_approx(
+        strokes_state
+            .audio_position_for_stroke(early)
+            .unwrap()
+            .offset,
+        0.0,
+    );
+    assert!(strokes_state.audio_position_for_stroke(before).is_none());
+
+    // Tapping the stroke
+    let (key, position) = strokes_state
+        .audio_position_at(na::vector![20.0, 50.0])
+        .unwrap();
+    assert_eq!(key, later);
+    assert_approx(position.offset, 30.0 - StrokesState::AUDIO_LEAD_TIME);
+    assert!(strokes_state
+        .audio_position_at(na::vector![20.0, 100.0])
+        .is_none());
+
+    // The index of the note, in the order the strokes were written
+    let written = strokes_state.keys_written_during(0);
+    assert_eq!(
+        written.iter().map(|(key, _)| *key).collect::<Vec<_>>(),
+        vec![early, later]
+    );
+}
+
+#[test]
+fn inserted_strokes_have_a_timestamp() {
+    let mut strokes_state = StrokesState::default();
+    strokes_state.add_audio_note(audio_note(Utc::now() - Duration::seconds(10), 20.0));
+
+    // Strokes without timestamped elements are written when they are inserted
+    let mut stroke = written_brushstroke(na::vector![0.0, 0.0], Utc::now());
+    if let StrokeStyle::BrushStroke(brushstroke) = &mut stroke {
+        brushstroke
+            .elements
+            .iter_mut()
+            .for_each(|element| element.timestamp = None);
+    }
+    let key = strokes_state.insert_stroke(stroke);
+
+    let position = strokes_state.audio_position_for_stroke(key).unwrap();
+    assert!(position.offset > 10.0 - StrokesState::AUDIO_LEAD_TIME - 1.0);
+    assert!(position.offset <= 10.0);
+}
+
+#[test]
+fn audio_notes_are_saved_with_the_sheet() {
+    let started = Utc::now();
+    let mut sheet = Sheet::default();
+    sheet
+        .strokes_state
+        .insert_stroke(written_brushstroke(na::vector![0.0, 0.0], started));
+    sheet.strokes_state.add_audio_note(audio_note(started, 5.0));
+    let bytes = sheet.save_sheet_as_rnote_bytes().unwrap();
+
+    let mut reopened = Sheet::default();
+    reopened
+        .open_sheet_from_rnote_bytes(bytes)
+        .unwrap();
+    let audio_notes = reopened.strokes_state.audio_notes();
+    assert_eq!(audio_notes.len(), 1);
+    assert_eq!(audio_notes[0].data().unwrap(), vec![1, 2, 3, 4]);
+    assert_eq!(audio_notes[0].started, started);
+    assert_eq!(reopened.strokes_state.keys_written_during(0).len(), 1);
+
+    assert!(reopened.strokes_state.remove_audio_note(0).is_some());
+    assert!(reopened.strokes_state.remove_audio_note(0).is_none());
+}
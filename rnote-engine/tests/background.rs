@@ -0,0 +1,189 @@
+use std::sync::{Arc, RwLock};
+
+use nalgebra as na;
+use parry2d_f64::bounding_volume::AABB;
+use rnote_engine::render::Renderer;
+use rnote_engine::sheet::background::{Background, PatternStyle};
+use rnote_engine::sheet::Sheet;
+use rnote_fileformats::xoppformat;
+use rnote_fileformats::{FileFormatLoader, FileFormatSaver};
+
+const TILE_SVG: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"><circle cx="5" cy="5" r="2"/></svg>"#;
+
+#[test]
+fn tiles_are_multiples_of_the_pattern_period() {
+    let mut background = Background::default();
+
+    for pattern in [
+        PatternStyle::Isometric,
+        PatternStyle::Hexagons,
+        PatternStyle::MusicStaff,
+    ] {
+        background.pattern = pattern;
+        let period = background.pattern_period();
+        let tile_size = background.tile_size();
+
+        for i in 0..2 {
+            let n = tile_size[i] / period[i];
+            assert!((n - n.round()).abs() < 1e-9, "{:?}", pattern);
+            assert!(n.round() >= 1.0);
+        }
+    }
+
+    background.pattern = PatternStyle::MusicStaff;
+    assert_eq!(
+        background.pattern_period()[1],
+        background.pattern_size[1] * Background::MUSIC_STAFF_PERIOD
+    );
+}
+
+#[test]
+fn custom_tile_is_embedded() {
+    let mut background = Background::default();
+    assert!(background.set_custom_tile("no svg").is_err());
+    assert_eq!(background.custom_tile, None);
+
+    background.set_custom_tile(TILE_SVG).unwrap();
+    assert_eq!(background.pattern, PatternStyle::CustomTile);
+
+    let svg = background
+        .gen_svg(AABB::new(na::point![0.0, 0.0], na::point![100.0, 100.0]))
+        .unwrap();
+    assert!(svg.svg_data.contains("data:image/svg+xml;base64,"));
+}
+
+#[test]
+fn page_backgrounds_override_the_sheet_background() {
+    let mut sheet = Sheet::default();
+    sheet.enable_page_layout();
+    let page = sheet.page_layout.as_ref().unwrap().get(0).unwrap();
+    assert!(sheet.insert_page(1, page, 1.0));
+    let pages = sheet.pages();
+
+    sheet.page_background_mut(1).unwrap().pattern = PatternStyle::MusicStaff;
+    // Modifying it again reuses the region of the page
+    sheet.page_background_mut(1).unwrap().pattern_size[1] = 8.0;
+    assert_eq!(sheet.background_regions.len(), 1);
+
+    let first_page_center = pages[0].center().coords;
+    let second_page_center = pages[1].center().coords;
+    assert_eq!(
+        sheet.background_at_pos(first_page_center).pattern,
+        sheet.background.pattern
+    );
+    assert_eq!(
+        sheet.background_at_pos(second_page_center).pattern,
+        PatternStyle::MusicStaff
+    );
+    assert_eq!(sheet.page_index_at(second_page_center), Some(1));
+
+    assert!(sheet.reset_page_background(1));
+    assert!(!sheet.reset_page_background(1));
+    assert_eq!(
+        sheet.background_at_pos(second_page_center).pattern,
+        sheet.background.pattern
+    );
+}
+
+/// The .xopp files of the rnote-fileformats tests
+const XOPP_FIXTURES: &[&[u8]] = &[
+    include_bytes!("../../rnote-fileformats/tests/simple.xopp"),
+    include_bytes!("../../rnote-fileformats/tests/image.xopp"),
+    include_bytes!("../../rnote-fileformats/tests/pdf_annotation.xopp"),
+    include_bytes!("../../rnote-fileformats/tests/xoppnative.xopp"),
+];
+
+fn json(value: &impl serde::Serialize) -> serde_json::Value {
+    serde_json::to_value(value).unwrap()
+}
+
+#[test]
+fn xopp_backgrounds_round_trip() {
+    let mut n_solid = 0;
+
+    for bytes in XOPP_FIXTURES {
+        let xopp_file = xoppformat::XoppFile::load_from_bytes(bytes).unwrap();
+
+        for page in xopp_file.xopp_root.pages.iter() {
+            let converted = Background::from_xoppbackground(&page.background).to_xoppbackground();
+
+            let expected = match &page.background.bg_type {
+                xoppformat::XoppBackgroundType::Solid { color, style } => {
+                    n_solid += 1;
+                    let style = match style {
+                        // Rnote draws both as lines
+                        xoppformat::XoppBackgroundSolidStyle::Ruled => {
+                            xoppformat::XoppBackgroundSolidStyle::Lined
+                        }
+                        style => style.clone(),
+                    };
+                    xoppformat::XoppBackgroundType::Solid {
+                        color: *color,
+                        style,
+                    }
+                }
+                // Pixmap and pdf backgrounds are imported as plain backgrounds
+                xoppformat::XoppBackgroundType::Pixmap { .. }
+                | xoppformat::XoppBackgroundType::Pdf => xoppformat::XoppBackgroundType::Solid {
+                    color: Background::default().color.into(),
+                    style: xoppformat::XoppBackgroundSolidStyle::Plain,
+                },
+            };
+            assert_eq!(json(&converted.bg_type), json(&expected));
+        }
+    }
+    assert!(n_solid > 0);
+}
+
+#[test]
+fn xopp_page_backgrounds_are_background_regions() {
+    let mut xopp_file = xoppformat::XoppFile::load_from_bytes(XOPP_FIXTURES[3]).unwrap();
+    assert_eq!(xopp_file.xopp_root.pages.len(), 2);
+    xopp_file.xopp_root.pages[1].background.bg_type = xoppformat::XoppBackgroundType::Solid {
+        color: xoppformat::XoppColor {
+            red: 0xff,
+            green: 0xff,
+            blue: 0xc0,
+            alpha: 0xff,
+        },
+        style: xoppformat::XoppBackgroundSolidStyle::Graph,
+    };
+    let first_page_height = xopp_file.xopp_root.pages[0].height;
+    let xopp_backgrounds = xopp_file
+        .xopp_root
+        .pages
+        .iter()
+        .map(|page| json(&page.background.bg_type))
+        .collect::<Vec<_>>();
+
+    let mut sheet = Sheet::default();
+    sheet
+        .open_from_xopp_bytes(xopp_file.save_as_bytes("backgrounds.xopp").unwrap())
+        .unwrap();
+
+    assert_eq!(sheet.background.pattern, PatternStyle::Lines);
+    assert_eq!(sheet.background_regions.len(), 1);
+    assert_eq!(sheet.background_regions[0].y_start, first_page_height);
+    assert_eq!(
+        sheet
+            .background_at_pos(na::vector![10.0, first_page_height + 10.0])
+            .pattern,
+        PatternStyle::Grid
+    );
+
+    // Exporting again keeps the background of every page
+    let exported = sheet
+        .export_sheet_as_xopp_bytes(
+            "backgrounds.xopp",
+            Arc::new(RwLock::new(Renderer::default())),
+        )
+        .unwrap();
+    let exported_backgrounds = xoppformat::XoppFile::load_from_bytes(&exported)
+        .unwrap()
+        .xopp_root
+        .pages
+        .iter()
+        .map(|page| json(&page.background.bg_type))
+        .collect::<Vec<_>>();
+    assert_eq!(exported_backgrounds, xopp_backgrounds);
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use nalgebra as na;
+use rnote_engine::sheet::bookmarks::Bookmark;
+use rnote_engine::sheet::Sheet;
+
+fn names(sheet: &Sheet) -> Vec<&str> {
+    sheet
+        .bookmarks()
+        .iter()
+        .map(|bookmark| bookmark.name.as_str())
+        .collect()
+}
+
+#[test]
+fn bookmarks_are_saved_with_the_sheet() {
+    let mut sheet = Sheet::default();
+    sheet.add_bookmark("Introduction", na::vector![100.0, 100.0], 1.5);
+    sheet.add_bookmark("", na::vector![100.0, 800.0], 1000.0);
+    assert_eq!(names(&sheet), vec!["Introduction", "Bookmark 2"]);
+
+    assert!(sheet.rename_bookmark(1, " Proofs "));
+    assert!(!sheet.rename_bookmark(1, "  "));
+    assert!(!sheet.rename_bookmark(2, "Appendix"));
+
+    let bytes = sheet.save_sheet_as_rnote_bytes().unwrap();
+    let mut reopened = Sheet::default();
+    reopened
+        .open_sheet_from_rnote_bytes(bytes)
+        .unwrap();
+    assert_eq!(names(&reopened), vec!["Introduction", "Proofs"]);
+
+    let view = reopened.bookmark_view(0).unwrap();
+    assert_eq!(view.center, na::vector![100.0, 100.0]);
+    assert_eq!(view.zoom, 1.5);
+    // The zoom is clamped
+    assert_eq!(reopened.bookmark_view(1).unwrap().zoom, Bookmark::ZOOM_MAX);
+    assert!(reopened.bookmark_view(2).is_none());
+
+    assert_eq!(reopened.remove_bookmark(0).unwrap().name, "Introduction");
+    assert_eq!(reopened.bookmark_index("Proofs"), Some(0));
+}
+
+#[test]
+fn bookmarks_are_navigated_in_reading_order() {
+    let mut sheet = common::sheet_with_pages(2);
+    let first_page = sheet.pages()[0];
+    let second_page = sheet.pages()[1];
+
+    let conclusion = sheet.add_bookmark("Conclusion", second_page.center().coords, 1.0);
+    assert_eq!(conclusion, 0);
+    let proofs = sheet.add_bookmark("Proofs", first_page.center().coords, 1.0);
+    assert_eq!(proofs, 0);
+    let introduction = sheet.add_bookmark(
+        "Introduction",
+        first_page.mins.coords + na::vector![100.0, 100.0],
+        1.0,
+    );
+    assert_eq!(introduction, 0);
+    assert_eq!(names(&sheet), vec!["Introduction", "Proofs", "Conclusion"]);
+
+    let pos = first_page.mins.coords;
+    assert_eq!(sheet.next_bookmark_index(pos), Some(0));
+    assert_eq!(sheet.previous_bookmark_index(pos), None);
+
+    // From a bookmark to the following one
+    let pos = sheet.bookmark_view(1).unwrap().center;
+    assert_eq!(sheet.next_bookmark_index(pos), Some(2));
+    assert_eq!(sheet.previous_bookmark_index(pos), Some(0));
+
+    let pos = second_page.maxs.coords - na::vector![1.0, 1.0];
+    assert_eq!(sheet.next_bookmark_index(pos), None);
+    assert_eq!(sheet.previous_bookmark_index(pos), Some(2));
+}
+
+#[test]
+fn bookmarks_follow_their_pages() {
+    let mut sheet = common::sheet_with_pages(3);
+    let pages = sheet.pages();
+    let offset = na::vector![50.0, 60.0];
+    for (i, page_bounds) in pages.iter().enumerate() {
+        sheet.add_bookmark(
+            &format!("Page {}", i + 1),
+            page_bounds.mins.coords + offset,
+            1.0,
+        );
+    }
+
+    assert!(sheet.move_page(0, 2, 1.0));
+    assert_eq!(names(&sheet), vec!["Page 2", "Page 3", "Page 1"]);
+    let pages = sheet.pages();
+    assert_eq!(
+        sheet.bookmarks()[2].pos,
+        pages[2].mins.coords + offset
+    );
+
+    // Removing the page removes its bookmarks
+    assert!(sheet.remove_page(0, 1.0).is_some());
+    assert_eq!(names(&sheet), vec!["Page 3", "Page 1"]);
+    assert_eq!(
+        sheet.bookmarks()[0].pos,
+        sheet.pages()[0].mins.coords + offset
+    );
+}
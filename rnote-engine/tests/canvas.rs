@@ -0,0 +1,185 @@
+mod common;
+
+use nalgebra as na;
+use parry2d_f64::bounding_volume::AABB;
+use rnote_engine::canvas::{self, CanvasContext};
+use rnote_engine::compose::color::Color;
+use rnote_engine::pens::brush::{Brush, BrushStyle};
+use rnote_engine::render::TessellatedStrokes;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+use rnote_engine::strokesstate::StrokesState;
+
+/// Records the calls which are relevant for the tests
+#[derive(Debug, Default)]
+struct RecordingContext {
+    depth: i32,
+    fills: usize,
+    svgs: Vec<AABB>,
+    composite_operations: Vec<String>,
+}
+
+impl CanvasContext for RecordingContext {
+    fn save(&mut self) {
+        self.depth += 1;
+    }
+    fn restore(&mut self) {
+        self.depth -= 1;
+    }
+    fn translate(&mut self, _x: f64, _y: f64) {}
+    fn rotate(&mut self, _angle: f64) {}
+    fn scale(&mut self, _x: f64, _y: f64) {}
+    fn set_global_alpha(&mut self, _alpha: f64) {}
+    fn set_global_composite_operation(&mut self, operation: &str) {
+        self.composite_operations.push(operation.to_string());
+    }
+    fn set_fill_style(&mut self, _color: &str) {}
+    fn begin_path(&mut self) {}
+    fn close_path(&mut self) {}
+    fn rect(&mut self, _x: f64, _y: f64, _width: f64, _height: f64) {}
+    fn arc(&mut self, _x: f64, _y: f64, _radius: f64, _start_angle: f64, _end_angle: f64) {}
+    fn ellipse(
+        &mut self,
+        _x: f64,
+        _y: f64,
+        _radius_x: f64,
+        _radius_y: f64,
+        _rotation: f64,
+        _start_angle: f64,
+        _end_angle: f64,
+    ) {
+    }
+    fn fill(&mut self) {
+        self.fills += 1;
+    }
+    fn draw_svg(&mut self, svg_data: &str, bounds: AABB) {
+        assert!(svg_data.starts_with("<svg"));
+        self.svgs.push(bounds);
+    }
+}
+
+fn brushstroke(brush: &Brush, y: f64) -> StrokeStyle {
+    StrokeStyle::BrushStroke(common::brushstroke_w_brush(
+        common::horizontal_elements(na::vector![100.0, y], 10),
+        brush,
+    ))
+}
+
+fn viewport() -> AABB {
+    AABB::new(na::point![0.0, 0.0], na::point![500.0, 500.0])
+}
+
+#[test]
+fn tessellated_strokes_are_drawn_as_paths() {
+    let mut strokes_state = StrokesState::default();
+    let mut brush = Brush::default();
+    brush.style = BrushStyle::Textured;
+    strokes_state.insert_stroke(brushstroke(&brush, 100.0));
+
+    let mut context = RecordingContext::default();
+    canvas::draw_strokes(
+        &strokes_state,
+        viewport(),
+        1.0,
+        &TessellatedStrokes::default(),
+        &mut context,
+    )
+    .unwrap();
+
+    assert!(context.fills > 0);
+    assert!(context.svgs.is_empty());
+    assert_eq!(context.depth, 0);
+}
+
+#[test]
+fn other_strokes_are_drawn_as_svgs() {
+    let mut strokes_state = StrokesState::default();
+    let mut brush = Brush::default();
+    brush.style = BrushStyle::Solid;
+    strokes_state.insert_stroke(brushstroke(&brush, 100.0));
+    // Outside of the viewport
+    strokes_state.insert_stroke(brushstroke(&brush, 1000.0));
+
+    let mut context = RecordingContext::default();
+    canvas::draw_strokes(
+        &strokes_state,
+        viewport(),
+        1.0,
+        &TessellatedStrokes::default(),
+        &mut context,
+    )
+    .unwrap();
+
+    assert_eq!(context.fills, 0);
+    assert!(!context.svgs.is_empty());
+    assert!(context
+        .svgs
+        .iter()
+        .all(|bounds| bounds.maxs[1] < viewport().maxs[1]));
+    assert!(context.composite_operations.is_empty());
+    assert_eq!(context.depth, 0);
+}
+
+#[test]
+fn translucent_tessellations_are_drawn_as_svgs() {
+    let tessellated_strokes = TessellatedStrokes {
+        solid: true,
+        ..TessellatedStrokes::default()
+    };
+    let mut brush = Brush::default();
+    brush.style = BrushStyle::Solid;
+
+    let mut strokes_state = StrokesState::default();
+    strokes_state.insert_stroke(brushstroke(&brush, 100.0));
+    let mut context = RecordingContext::default();
+    canvas::draw_strokes(
+        &strokes_state,
+        viewport(),
+        1.0,
+        &tessellated_strokes,
+        &mut context,
+    )
+    .unwrap();
+    assert!(context.fills > 0);
+    assert!(context.svgs.is_empty());
+
+    // The primitives can't be composited with the opacity of the stroke on the canvas
+    brush.smooth_options.stroke_color = Some(Color {
+        r: 0.0,
+        g: 0.0,
+        b: 0.0,
+        a: 0.5,
+    });
+    let mut strokes_state = StrokesState::default();
+    strokes_state.insert_stroke(brushstroke(&brush, 100.0));
+    let mut context = RecordingContext::default();
+    canvas::draw_strokes(
+        &strokes_state,
+        viewport(),
+        1.0,
+        &tessellated_strokes,
+        &mut context,
+    )
+    .unwrap();
+    assert_eq!(context.fills, 0);
+    assert!(!context.svgs.is_empty());
+}
+
+#[test]
+fn highlighters_are_blended() {
+    let mut strokes_state = StrokesState::default();
+    let mut brush = Brush::default();
+    brush.style = BrushStyle::Highlighter;
+    strokes_state.insert_stroke(brushstroke(&brush, 100.0));
+
+    let mut context = RecordingContext::default();
+    canvas::draw_strokes(
+        &strokes_state,
+        viewport(),
+        1.0,
+        &TessellatedStrokes::default(),
+        &mut context,
+    )
+    .unwrap();
+
+    assert_eq!(context.composite_operations, vec![String::from("multiply")]);
+}
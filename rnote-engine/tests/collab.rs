@@ -0,0 +1,238 @@
+mod common;
+
+use nalgebra as na;
+use rnote_engine::collab::crdt::{CrdtOp, Stamp, StrokesCrdt};
+use rnote_engine::collab::protocol::CollabMessage;
+use rnote_engine::collab::CollabSession;
+use rnote_engine::compose::color::Color;
+use rnote_engine::drawbehaviour::DrawBehaviour;
+use rnote_engine::strokesstate::{StrokeKey, StrokesState};
+
+/// A participant of the session
+struct Participant {
+    session: CollabSession,
+    strokes_state: StrokesState,
+}
+
+impl Participant {
+    fn join(name: &str, mut strokes_state: StrokesState) -> Self {
+        let session = CollabSession::join(String::from(name), Color::BLUE, &mut strokes_state);
+
+        Self {
+            session,
+            strokes_state,
+        }
+    }
+
+    fn min_x_of_strokes(&self) -> Vec<f64> {
+        let mut min_x = self
+            .strokes_state
+            .keys_unordered()
+            .into_iter()
+            .filter(|&key| self.strokes_state.trashed(key) == Some(false))
+            .map(|key| {
+                self.strokes_state
+                    .get_stroke_ref(key)
+                    .unwrap()
+                    .bounds()
+                    .mins[0]
+            })
+            .collect::<Vec<f64>>();
+        min_x.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        min_x
+    }
+}
+
+/// Delivers the messages between the participants, like the relay does, until none are left
+fn exchange(participants: &mut [&mut Participant]) {
+    loop {
+        let mut delivered = false;
+
+        for from in 0..participants.len() {
+            let sender = &mut *participants[from];
+            sender.session.sync_local_changes(&mut sender.strokes_state);
+            let messages = sender.session.take_outgoing();
+
+            for message in messages {
+                // Through json, like over the connection
+                let json = message.to_json().unwrap();
+
+                for (to, participant) in participants.iter_mut().enumerate() {
+                    if to != from {
+                        participant.session.receive(
+                            CollabMessage::from_json(&json).unwrap(),
+                            &mut participant.strokes_state,
+                        );
+                    }
+                }
+                delivered = true;
+            }
+        }
+
+        if !delivered {
+            break;
+        }
+    }
+}
+
+fn only_key(strokes_state: &StrokesState) -> StrokeKey {
+    let keys = strokes_state.keys_unordered();
+    assert_eq!(keys.len(), 1);
+    keys[0]
+}
+
+#[test]
+fn operations_converge_in_any_order() {
+    let id = Stamp {
+        counter: 1,
+        replica: 1,
+    };
+    let ops = vec![
+        CrdtOp::Add {
+            id,
+            stroke: common::brushstroke_through(&[na::vector![0.0, 0.0], na::vector![40.0, 0.0]]),
+        },
+        CrdtOp::Update {
+            id,
+            stamp: Stamp {
+                counter: 2,
+                replica: 2,
+            },
+            stroke: common::brushstroke_through(&[na::vector![10.0, 0.0], na::vector![50.0, 0.0]]),
+        },
+        CrdtOp::Update {
+            id,
+            stamp: Stamp {
+                counter: 2,
+                replica: 3,
+            },
+            stroke: common::brushstroke_through(&[na::vector![20.0, 0.0], na::vector![60.0, 0.0]]),
+        },
+    ];
+
+    let mut in_order = StrokesCrdt::default();
+    assert!(ops.iter().all(|op| in_order.apply(op)));
+    let mut reversed = StrokesCrdt::default();
+    for op in ops.iter().rev() {
+        reversed.apply(op);
+    }
+    // Applying again changes nothing
+    assert!(!ops.iter().any(|op| reversed.apply(op)));
+
+    // Concurrent updates are ordered by the replica
+    assert_eq!(in_order.stamp(id), reversed.stamp(id));
+    assert_eq!(in_order.stamp(id).unwrap().replica, 3);
+
+    // Removals are final, even when they arrive before the addition
+    let mut removed_first = StrokesCrdt::default();
+    assert!(!removed_first.apply(&CrdtOp::Remove { id }));
+    assert!(!ops.iter().any(|op| removed_first.apply(op)));
+    assert!(!removed_first.contains(id));
+    assert!(in_order.apply(&CrdtOp::Remove { id }));
+    assert!(!in_order.contains(id));
+
+    // Later local stamps are larger than the observed ones
+    assert!(in_order.next_stamp(1) > in_order.stamp(id).unwrap());
+}
+
+#[test]
+fn participants_share_their_changes() {
+    let mut host_strokes_state = StrokesState::default();
+    host_strokes_state.insert_stroke(common::brushstroke_through(&[
+        na::vector![0.0, 0.0],
+        na::vector![40.0, 0.0],
+    ]));
+    let mut host = Participant::join("host", host_strokes_state);
+    let mut guest = Participant::join("guest", StrokesState::default());
+
+    exchange(&mut [&mut host, &mut guest]);
+    assert_eq!(guest.min_x_of_strokes(), host.min_x_of_strokes());
+    assert_eq!(guest.session.remote_cursors().len(), 1);
+    assert_eq!(host.session.remote_cursors().len(), 1);
+    // Changes of others are not undone locally
+    assert!(!guest.strokes_state.history().can_undo());
+
+    // Changes and insertions of the guest are shared with the host
+    let key = only_key(&guest.strokes_state);
+    guest
+        .strokes_state
+        .translate_strokes(&[key], na::vector![100.0, 0.0], 1.0);
+    guest
+        .strokes_state
+        .insert_stroke(common::brushstroke_through(&[
+            na::vector![500.0, 0.0],
+            na::vector![540.0, 0.0],
+        ]));
+    exchange(&mut [&mut host, &mut guest]);
+    assert_eq!(host.min_x_of_strokes().len(), 2);
+    assert_eq!(host.min_x_of_strokes(), guest.min_x_of_strokes());
+
+    // Trashed strokes are removed for the others
+    let keys = host.strokes_state.keys_unordered();
+    for &key in keys.iter() {
+        host.strokes_state.set_trashed(key, true);
+    }
+    exchange(&mut [&mut host, &mut guest]);
+    assert!(guest.strokes_state.keys_unordered().is_empty());
+}
+
+#[test]
+fn concurrent_changes_converge() {
+    let mut host_strokes_state = StrokesState::default();
+    host_strokes_state.insert_stroke(common::brushstroke_through(&[
+        na::vector![0.0, 0.0],
+        na::vector![40.0, 0.0],
+    ]));
+    let mut host = Participant::join("host", host_strokes_state);
+    let mut guest = Participant::join("guest", StrokesState::default());
+    exchange(&mut [&mut host, &mut guest]);
+
+    // Both move the same stroke before receiving the change of the other
+    let host_key = only_key(&host.strokes_state);
+    let guest_key = only_key(&guest.strokes_state);
+    host.strokes_state
+        .translate_strokes(&[host_key], na::vector![10.0, 0.0], 1.0);
+    guest
+        .strokes_state
+        .translate_strokes(&[guest_key], na::vector![20.0, 0.0], 1.0);
+    host.session.sync_local_changes(&mut host.strokes_state);
+    guest.session.sync_local_changes(&mut guest.strokes_state);
+    exchange(&mut [&mut host, &mut guest]);
+
+    assert_eq!(host.min_x_of_strokes(), guest.min_x_of_strokes());
+
+    // One removes the stroke while the other moves it, the removal wins
+    host.strokes_state.remove_stroke(host_key);
+    guest
+        .strokes_state
+        .translate_strokes(&[guest_key], na::vector![20.0, 0.0], 1.0);
+    host.session.sync_local_changes(&mut host.strokes_state);
+    guest.session.sync_local_changes(&mut guest.strokes_state);
+    exchange(&mut [&mut host, &mut guest]);
+
+    assert!(host.strokes_state.keys_unordered().is_empty());
+    assert!(guest.strokes_state.keys_unordered().is_empty());
+}
+
+#[test]
+fn remote_cursors_follow_the_participants() {
+    let mut host = Participant::join("host", StrokesState::default());
+    let mut guest = Participant::join("guest", StrokesState::default());
+    exchange(&mut [&mut host, &mut guest]);
+
+    guest.session.move_cursor(Some(na::vector![10.0, 10.0]));
+    guest.session.move_cursor(Some(na::vector![20.0, 30.0]));
+    // Only the latest position is sent
+    assert_eq!(guest.session.take_outgoing().len(), 1);
+
+    guest.session.move_cursor(Some(na::vector![20.0, 30.0]));
+    exchange(&mut [&mut host, &mut guest]);
+    let cursor = &host.session.remote_cursors()[&guest.session.replica()];
+    assert_eq!(cursor.name, "guest");
+    assert_eq!(cursor.pos, Some(na::vector![20.0, 30.0]));
+
+    guest.session.leave(&mut guest.strokes_state);
+    exchange(&mut [&mut host, &mut guest]);
+    assert!(host.session.remote_cursors().is_empty());
+}
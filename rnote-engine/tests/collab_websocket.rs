@@ -0,0 +1,35 @@
+#![cfg(feature = "collab")]
+
+use std::net::TcpListener;
+
+use futures::StreamExt;
+use rnote_engine::collab::protocol::CollabMessage;
+use rnote_engine::collab::websocket::{self, CollabConnection};
+use rnote_engine::collab::CollabSession;
+use rnote_engine::compose::color::Color;
+use rnote_engine::strokesstate::StrokesState;
+
+#[test]
+fn relay_forwards_the_messages() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || websocket::run_relay(listener));
+
+    let host_connection = CollabConnection::connect(&url).unwrap();
+    let mut guest_connection = CollabConnection::connect(&url).unwrap();
+    let mut guest_rx = guest_connection.incoming_rx.take().unwrap();
+
+    let mut host_strokes_state = StrokesState::default();
+    let mut host = CollabSession::join(String::from("host"), Color::RED, &mut host_strokes_state);
+    for message in host.take_outgoing() {
+        host_connection.send(message).unwrap();
+    }
+
+    let hello = futures::executor::block_on(guest_rx.next()).unwrap();
+    assert!(matches!(hello, CollabMessage::Hello { replica, .. } if replica == host.replica()));
+
+    // The host is announced to have left when its connection is lost
+    drop(host_connection);
+    let bye = futures::executor::block_on(guest_rx.next()).unwrap();
+    assert!(matches!(bye, CollabMessage::Bye { replica } if replica == host.replica()));
+}
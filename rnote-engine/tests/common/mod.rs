@@ -0,0 +1,88 @@
+//! Helpers shared by the integration tests. Every test crate only uses some of them
+#![allow(dead_code)]
+
+use nalgebra as na;
+use rnote_engine::drawbehaviour::DrawBehaviour;
+use rnote_engine::pens::brush::Brush;
+use rnote_engine::sheet::Sheet;
+use rnote_engine::strokes::brushstroke::BrushStroke;
+use rnote_engine::strokes::element::Element;
+use rnote_engine::strokes::inputdata::InputData;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+
+/// A brush stroke with the brush
+pub fn brushstroke_w_brush(
+    elements: impl IntoIterator<Item = Element>,
+    brush: &Brush,
+) -> BrushStroke {
+    BrushStroke::new_w_elements(elements.into_iter(), brush).unwrap()
+}
+
+/// A brush stroke with the default brush
+pub fn brushstroke_w_elements(elements: impl IntoIterator<Item = Element>) -> StrokeStyle {
+    StrokeStyle::BrushStroke(brushstroke_w_brush(elements, &Brush::default()))
+}
+
+/// Horizontal elements, 10.0 apart
+pub fn horizontal_elements(
+    start: na::Vector2<f64>,
+    n_elements: usize,
+) -> impl Iterator<Item = Element> {
+    (0..n_elements).map(move |i| {
+        Element::new(InputData::new(
+            start + na::vector![i as f64 * 10.0, 0.0],
+            1.0,
+        ))
+    })
+}
+
+/// Elements at the positions, with a medium pressure
+pub fn elements_through(positions: &[na::Vector2<f64>]) -> Vec<Element> {
+    positions
+        .iter()
+        .map(|&pos| Element::new(InputData::new(pos, 0.5)))
+        .collect()
+}
+
+/// A horizontal brush stroke with the default brush, with the elements 10.0 apart
+pub fn brushstroke(start: na::Vector2<f64>, n_elements: usize) -> StrokeStyle {
+    brushstroke_w_elements(horizontal_elements(start, n_elements))
+}
+
+/// A brush stroke with the default brush through the positions
+pub fn brushstroke_through(positions: &[na::Vector2<f64>]) -> StrokeStyle {
+    brushstroke_w_elements(elements_through(positions))
+}
+
+/// A sheet with the page layout enabled and the number of pages
+pub fn sheet_with_pages(n_pages: usize) -> Sheet {
+    let mut sheet = Sheet::default();
+    sheet.enable_page_layout();
+    let page = sheet.page_layout.as_ref().unwrap().get(0).unwrap();
+    for i in 1..n_pages {
+        assert!(sheet.insert_page(i, page, 1.0));
+    }
+    sheet
+}
+
+/// The max distance of the coordinates in the paths of the brush stroke svgs from the horizontal line at y
+pub fn max_path_distance_from_y(brushstroke: &BrushStroke, y: f64) -> f64 {
+    let mut max_distance = 0.0_f64;
+
+    for svg in brushstroke.gen_svgs(na::Vector2::zeros()).unwrap() {
+        for path_data in svg.svg_data.split(" d=\"").skip(1) {
+            let path_data = &path_data[..path_data.find('"').unwrap()];
+            let numbers = path_data
+                .split(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+                .filter(|number| !number.is_empty())
+                .map(|number| number.parse::<f64>().unwrap())
+                .collect::<Vec<f64>>();
+
+            for pos in numbers.chunks_exact(2) {
+                max_distance = max_distance.max((pos[1] - y).abs());
+            }
+        }
+    }
+
+    max_distance
+}
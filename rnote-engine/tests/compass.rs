@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+
+use nalgebra as na;
+use rnote_engine::pens::brush::Brush;
+use rnote_engine::pens::penbehaviour::PenBehaviour;
+use rnote_engine::render::Renderer;
+use rnote_engine::sheet::Sheet;
+use rnote_engine::strokes::inputdata::InputData;
+use rnote_engine::strokes::shapestroke::Shape;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+
+fn input(pos: na::Vector2<f64>) -> VecDeque<InputData> {
+    VecDeque::from(vec![InputData::new(pos, 1.0)])
+}
+
+fn stroke(brush: &mut Brush, sheet: &mut Sheet, positions: &[na::Vector2<f64>]) {
+    let renderer = Arc::new(RwLock::new(Renderer::default()));
+    let (first, rest) = positions.split_first().unwrap();
+
+    brush.begin(input(*first), sheet, None, 1.0, renderer.clone());
+    for pos in rest {
+        brush.motion(input(*pos), sheet, None, 1.0, renderer.clone());
+    }
+    brush.end(VecDeque::new(), sheet, None, 1.0, renderer);
+}
+
+#[test]
+fn compass_draws_arcs_around_the_pinned_center() {
+    let mut sheet = Sheet::default();
+    let mut brush = Brush::default();
+    brush.compass.set_enabled(true);
+    let center = na::vector![200.0, 200.0];
+
+    // The first press pins the center
+    stroke(&mut brush, &mut sheet, &[center]);
+    assert_eq!(brush.compass.center(), Some(center));
+    assert!(sheet.strokes_state.keys_as_rendered().is_empty());
+
+    // A quarter circle, the radius stays the one the stroke starts at
+    stroke(
+        &mut brush,
+        &mut sheet,
+        &[
+            center + na::vector![50.0, 0.0],
+            center + na::vector![60.0, 60.0],
+            center + na::vector![0.0, 40.0],
+        ],
+    );
+
+    let keys = sheet.strokes_state.keys_as_rendered();
+    assert_eq!(keys.len(), 1);
+    match sheet.strokes_state.get_stroke_ref(keys[0]) {
+        Some(StrokeStyle::ShapeStroke(shapestroke)) => match &shapestroke.shape {
+            Shape::Arc(arc) => {
+                assert!((arc.radius - 50.0).abs() < 1e-9);
+                assert!(arc.start_angle.abs() < 1e-9);
+                assert!((arc.sweep_angle - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+            }
+            shape => panic!("expected an arc, got {:?}", shape),
+        },
+        stroke => panic!("expected a shapestroke, got {:?}", stroke),
+    }
+}
+
+#[test]
+fn compass_taps_leave_no_strokes() {
+    let mut sheet = Sheet::default();
+    let mut brush = Brush::default();
+    brush.compass.set_enabled(true);
+    let center = na::vector![200.0, 200.0];
+    stroke(&mut brush, &mut sheet, &[center]);
+
+    stroke(&mut brush, &mut sheet, &[center + na::vector![30.0, 30.0]]);
+    assert!(sheet.strokes_state.keys_as_rendered().is_empty());
+
+    // Pressing onto the center moves it
+    stroke(
+        &mut brush,
+        &mut sheet,
+        &[center, center + na::vector![20.0, 0.0]],
+    );
+    assert_eq!(
+        brush.compass.center(),
+        Some(center + na::vector![20.0, 0.0])
+    );
+    assert!(sheet.strokes_state.keys_as_rendered().is_empty());
+
+    brush.compass.set_enabled(false);
+    assert_eq!(brush.compass.center(), None);
+}
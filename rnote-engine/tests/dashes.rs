@@ -0,0 +1,73 @@
+mod common;
+
+use nalgebra as na;
+use rnote_engine::compose::smooth::{LineStyle, SmoothOptions};
+use rnote_engine::drawbehaviour::DrawBehaviour;
+use rnote_engine::pens::brush::{Brush, BrushStyle};
+use rnote_engine::strokes::brushstroke::BrushStroke;
+
+fn dashed_marker(positions: &[na::Vector2<f64>]) -> BrushStroke {
+    let brush = Brush {
+        style: BrushStyle::Marker,
+        smooth_options: SmoothOptions {
+            line_style: LineStyle::Dashed,
+            ..SmoothOptions::default()
+        },
+        ..Brush::default()
+    };
+    common::brushstroke_w_brush(common::elements_through(positions), &brush)
+}
+
+/// The value of the dash offset attribute of the svg
+fn dash_offset(svg_data: &str) -> f64 {
+    let start = svg_data.find("stroke-dashoffset=\"").unwrap() + "stroke-dashoffset=\"".len();
+    let len = svg_data[start..].find('"').unwrap();
+    svg_data[start..start + len].parse().unwrap()
+}
+
+#[test]
+fn marker_dashes_continue_across_the_segments() {
+    let positions = [
+        na::vector![0.0, 0.0],
+        na::vector![10.0, 0.0],
+        na::vector![20.0, 0.0],
+        na::vector![20.0, 5.0],
+        na::vector![20.0, 25.0],
+        na::vector![50.0, 25.0],
+    ];
+    let brushstroke = dashed_marker(&positions);
+    let svgs = brushstroke.gen_svgs(na::Vector2::zeros()).unwrap();
+
+    // Every segment starts where the length of the stroke before it ended
+    let offsets = svgs
+        .iter()
+        .map(|svg| dash_offset(&svg.svg_data))
+        .collect::<Vec<f64>>();
+    assert_eq!(offsets, vec![0.0, 10.0, 15.0]);
+
+    // The last segment generated on its own while drawing has the same offset
+    let elements = &brushstroke.elements[brushstroke.elements.len() - 4..];
+    let appended = brushstroke
+        .gen_svg_for_elems(
+            (&elements[0], &elements[1], &elements[2], &elements[3]),
+            na::Vector2::zeros(),
+            false,
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(dash_offset(&appended.svg_data), 15.0);
+}
+
+#[test]
+fn solid_markers_have_no_dashes() {
+    let brush = Brush {
+        style: BrushStyle::Marker,
+        ..Brush::default()
+    };
+    let brushstroke =
+        common::brushstroke_w_brush(common::horizontal_elements(na::Vector2::zeros(), 6), &brush);
+
+    for svg in brushstroke.gen_svgs(na::Vector2::zeros()).unwrap() {
+        assert!(!svg.svg_data.contains("stroke-dasharray"));
+    }
+}
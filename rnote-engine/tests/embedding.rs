@@ -0,0 +1,105 @@
+use std::collections::VecDeque;
+
+use nalgebra as na;
+use rnote_engine::embedding::{Engine, EngineEvent, EngineUpdate, ExpandMode};
+use rnote_engine::pens::PenEvent;
+use rnote_engine::strokes::inputdata::InputData;
+
+fn input(pos: na::Vector2<f64>) -> VecDeque<InputData> {
+    VecDeque::from(vec![InputData::new(pos, 0.5)])
+}
+
+fn received_updates(
+    updates_rx: &mut futures::channel::mpsc::UnboundedReceiver<EngineUpdate>,
+) -> Vec<EngineUpdate> {
+    let mut updates = vec![];
+    while let Ok(Some(update)) = updates_rx.try_next() {
+        updates.push(update);
+    }
+
+    updates
+}
+
+#[test]
+fn strokes_are_drawn_through_the_handle() {
+    let (mut engine, handle, mut updates_rx) = Engine::new();
+
+    handle
+        .send(EngineEvent::Pen(PenEvent::DownEvent {
+            data_entries: input(na::vector![100.0, 100.0]),
+            shortcut_key: None,
+        }))
+        .unwrap();
+    for i in 1..10 {
+        handle
+            .send(EngineEvent::Pen(PenEvent::MotionEvent {
+                data_entries: input(na::vector![100.0 + 10.0 * f64::from(i), 100.0]),
+                shortcut_key: None,
+            }))
+            .unwrap();
+    }
+    handle
+        .send(EngineEvent::Pen(PenEvent::UpEvent {
+            data_entries: VecDeque::new(),
+            shortcut_key: None,
+        }))
+        .unwrap();
+
+    // Nothing happens until the engine processes the events
+    assert!(engine.sheet.strokes_state.keys_as_rendered().is_empty());
+    assert!(engine.process_pending());
+    assert_eq!(engine.sheet.strokes_state.keys_as_rendered().len(), 1);
+
+    let updates = received_updates(&mut updates_rx);
+    assert!(updates.contains(&EngineUpdate::DocumentChanged));
+    assert!(updates.contains(&EngineUpdate::Redraw));
+
+    handle.send(EngineEvent::Undo).unwrap();
+    assert!(engine.process_pending());
+    assert!(engine.sheet.strokes_state.keys_as_rendered().is_empty());
+    assert!(received_updates(&mut updates_rx).contains(&EngineUpdate::DocumentChanged));
+}
+
+#[test]
+fn viewport_and_expand_mode() {
+    let (mut engine, handle, mut updates_rx) = Engine::new();
+    let viewport = parry2d_f64::bounding_volume::AABB::new(
+        na::point![-5000.0, -5000.0],
+        na::point![-4000.0, -4000.0],
+    );
+
+    handle
+        .send(EngineEvent::SetExpandMode(ExpandMode::Infinite))
+        .unwrap();
+    handle
+        .send(EngineEvent::SetViewport {
+            viewport,
+            zoom: 2.0,
+        })
+        .unwrap();
+    assert!(engine.process_pending());
+
+    assert_eq!(engine.expand_mode(), ExpandMode::Infinite);
+    assert_eq!(engine.viewport(), viewport);
+    assert_eq!(engine.zoom(), 2.0);
+    // The infinite sheet expanded to the viewport
+    assert!(engine.sheet.bounds().mins[0] <= viewport.mins[0]);
+    assert!(received_updates(&mut updates_rx)
+        .iter()
+        .any(|update| matches!(update, EngineUpdate::SheetResized(_))));
+}
+
+#[test]
+fn quits() {
+    let (mut engine, handle, mut updates_rx) = Engine::new();
+
+    handle.send(EngineEvent::Quit).unwrap();
+    handle.send(EngineEvent::Undo).unwrap();
+    assert!(!engine.process_pending());
+    assert_eq!(received_updates(&mut updates_rx), vec![EngineUpdate::Quit]);
+
+    // Dropping all handles stops the engine as well
+    let (mut engine, handle, _updates_rx) = Engine::new();
+    drop(handle);
+    assert!(!engine.process_pending());
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use nalgebra as na;
+use rnote_engine::compose::arrowhead::ArrowheadOptions;
+use rnote_engine::compose::color::Color;
+use rnote_engine::compose::shapes;
+use rnote_engine::compose::smooth::SmoothOptions;
+use rnote_engine::compose::transformable::Transform;
+use rnote_engine::pens::brush::Brush;
+use rnote_engine::pens::tools::{ToolStyle, Tools};
+use rnote_engine::sheet::Sheet;
+use rnote_engine::strokes::shapestroke::{Shape, ShapeStroke};
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+
+const RED: Color = Color {
+    r: 1.0,
+    g: 0.0,
+    b: 0.0,
+    a: 1.0,
+};
+const GREEN: Color = Color {
+    r: 0.0,
+    g: 1.0,
+    b: 0.0,
+    a: 1.0,
+};
+const BLUE: Color = Color {
+    r: 0.0,
+    g: 0.0,
+    b: 1.0,
+    a: 1.0,
+};
+
+/// A horizontal brush stroke with the color from x = 10.0 to x = 60.0
+fn colored_brushstroke(y: f64, color: Color) -> StrokeStyle {
+    let mut brush = Brush::default();
+    brush.set_stroke_color(color);
+
+    StrokeStyle::BrushStroke(common::brushstroke_w_brush(
+        common::horizontal_elements(na::vector![10.0, y], 6),
+        &brush,
+    ))
+}
+
+/// A square with the half extents around the center
+fn square(center: na::Vector2<f64>, half_extents: f64, fill_color: Option<Color>) -> StrokeStyle {
+    let options = SmoothOptions {
+        stroke_color: Some(GREEN),
+        fill_color,
+        ..SmoothOptions::default()
+    };
+    let rectangle = shapes::Rectangle {
+        cuboid: parry2d_f64::shape::Cuboid::new(na::Vector2::from_element(half_extents)),
+        transform: Transform::new_w_isometry(na::Isometry2::new(center, 0.0)),
+    };
+
+    StrokeStyle::ShapeStroke(ShapeStroke::new_w_shape(
+        Shape::Rectangle(rectangle),
+        options,
+        ArrowheadOptions::default(),
+    ))
+}
+
+#[test]
+fn color_at_pos_is_the_color_of_the_topmost_stroke() {
+    let mut sheet = Sheet::default();
+    let background_color = sheet.background.color;
+    let tolerance = 1.0;
+
+    let below = sheet
+        .strokes_state
+        .insert_stroke(colored_brushstroke(50.0, RED));
+    assert_eq!(
+        sheet.color_at_pos(na::vector![30.0, 50.0], tolerance),
+        Some(RED)
+    );
+    // Within the tolerance outside of the stroke width
+    assert_eq!(
+        sheet.color_at_pos(na::vector![30.0, 51.2], tolerance),
+        Some(RED)
+    );
+    assert_eq!(
+        sheet.color_at_pos(na::vector![30.0, 300.0], tolerance),
+        Some(background_color)
+    );
+    assert_eq!(
+        sheet.color_at_pos(na::vector![-10.0, -10.0], tolerance),
+        None
+    );
+
+    let above = sheet
+        .strokes_state
+        .insert_stroke(colored_brushstroke(50.0, BLUE));
+    assert_eq!(
+        sheet.color_at_pos(na::vector![30.0, 50.0], tolerance),
+        Some(BLUE)
+    );
+
+    // Trashed strokes and hidden layers are skipped
+    sheet.strokes_state.set_trashed(above, true);
+    assert_eq!(
+        sheet.color_at_pos(na::vector![30.0, 50.0], tolerance),
+        Some(RED)
+    );
+
+    let layer = sheet.strokes_state.layer(below).unwrap();
+    sheet.strokes_state.set_layer_visible(layer, false);
+    assert_eq!(
+        sheet.color_at_pos(na::vector![30.0, 50.0], tolerance),
+        Some(background_color)
+    );
+}
+
+#[test]
+fn shapes_give_the_stroke_color_on_the_outline_and_the_fill_color_inside() {
+    let mut sheet = Sheet::default();
+    let center = na::vector![200.0, 200.0];
+    let yellow = Color::new(1.0, 1.0, 0.0, 1.0);
+
+    sheet
+        .strokes_state
+        .insert_stroke(square(center, 50.0, Some(yellow)));
+    assert_eq!(
+        sheet.color_at_pos(center + na::vector![50.0, 0.0], 1.0),
+        Some(GREEN)
+    );
+    assert_eq!(sheet.color_at_pos(center, 1.0), Some(yellow));
+}
+
+#[test]
+fn transparent_and_missing_fills_show_the_stroke_below() {
+    let center = na::vector![35.0, 50.0];
+
+    for fill_color in [None, Some(Color::TRANSPARENT)] {
+        let mut sheet = Sheet::default();
+        sheet
+            .strokes_state
+            .insert_stroke(colored_brushstroke(50.0, RED));
+        sheet
+            .strokes_state
+            .insert_stroke(square(center, 40.0, fill_color));
+
+        assert_eq!(sheet.color_at_pos(center, 1.0), Some(RED));
+        assert_eq!(
+            sheet.color_at_pos(center + na::vector![0.0, 40.0], 1.0),
+            Some(GREEN)
+        );
+    }
+}
+
+#[test]
+fn eyedropper_serde() {
+    assert!(matches!(
+        serde_json::from_str::<ToolStyle>(r#""eyedropper""#).unwrap(),
+        ToolStyle::Eyedropper
+    ));
+    assert_eq!(
+        serde_json::to_string(&ToolStyle::Eyedropper).unwrap(),
+        r#""eyedropper""#
+    );
+
+    // The picked color is not saved
+    let mut tools = Tools::default();
+    tools.eyedropper_tool.picked_color = Some(RED);
+    let tools: Tools = serde_json::from_str(&serde_json::to_string(&tools).unwrap()).unwrap();
+    assert!(tools.eyedropper_tool.picked_color.is_none());
+    assert!(tools.eyedropper_tool.pos.is_none());
+}
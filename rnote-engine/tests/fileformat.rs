@@ -0,0 +1,305 @@
+use std::io::Cursor;
+
+use rnote_engine::sheet::migration::{self, FILE_FORMAT_VERSION};
+use rnote_engine::sheet::{container, Sheet};
+use rnote_engine::strokes::brushstroke::{BrushStroke, BrushStrokeStyle};
+use rnote_engine::strokes::pdfimport::ImportedPdf;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+use rnote_engine::strokesstate::StrokeKey;
+use rnote_engine::utils;
+use slotmap::Key;
+
+/// The fixtures of every file format version, at the index of their version
+const FIXTURES: &[&str] = &[
+    include_str!("fixtures/sheet_v0.json"),
+    include_str!("fixtures/sheet_v1.json"),
+    include_str!("fixtures/sheet_v2.json"),
+    include_str!("fixtures/sheet_v3.json"),
+    include_str!("fixtures/sheet_v4.json"),
+    include_str!("fixtures/sheet_v5.json"),
+    include_str!("fixtures/sheet_v6.json"),
+    include_str!("fixtures/sheet_v7.json"),
+    include_str!("fixtures/sheet_v8.json"),
+    include_str!("fixtures/sheet_v9.json"),
+    include_str!("fixtures/sheet_v10.json"),
+    include_str!("fixtures/sheet_v11.json"),
+    include_str!("fixtures/sheet_v12.json"),
+    include_str!("fixtures/sheet_v13.json"),
+    include_str!("fixtures/sheet_v14.json"),
+    include_str!("fixtures/sheet_v15.json"),
+    include_str!("fixtures/sheet_v16.json"),
+];
+
+/// The first version that is saved into the container instead of gzipped json
+const CONTAINER_VERSION: u64 = 2;
+
+/// Packs the json of the sheet like the version was saved
+fn rnote_bytes(json: &serde_json::Value) -> Vec<u8> {
+    let version = migration::file_format_version(json);
+
+    if version >= CONTAINER_VERSION {
+        container::save(json, version).unwrap().to_vec()
+    } else {
+        utils::compress_to_gzip(json.to_string().as_bytes(), "fixture.rnote").unwrap()
+    }
+}
+
+fn open_rnote_bytes(bytes: impl AsRef<[u8]> + Send + 'static) -> Result<Sheet, anyhow::Error> {
+    let mut sheet = Sheet::default();
+    sheet.open_sheet_from_rnote_bytes(bytes)?;
+
+    Ok(sheet)
+}
+
+#[test]
+fn fixture_for_every_version() {
+    assert_eq!(FIXTURES.len() as u64, FILE_FORMAT_VERSION + 1);
+
+    for (version, fixture) in FIXTURES.iter().enumerate() {
+        let json: serde_json::Value = serde_json::from_str(fixture).unwrap();
+        assert_eq!(migration::file_format_version(&json), version as u64);
+    }
+}
+
+#[test]
+fn open_every_version_without_losing_strokes() {
+    for fixture in FIXTURES {
+        let json: serde_json::Value = serde_json::from_str(fixture).unwrap();
+        let n_strokes = migration::count_strokes(&json);
+
+        let sheet = open_rnote_bytes(rnote_bytes(&json)).unwrap();
+        assert_eq!(sheet.strokes_state.strokes_len(), n_strokes);
+        assert_eq!(sheet.file_format_version, FILE_FORMAT_VERSION);
+    }
+}
+
+#[test]
+fn migrate_v0_marker_and_smooth_styles() {
+    let json: serde_json::Value = serde_json::from_str(FIXTURES[0]).unwrap();
+    let migrated = migration::migrate_sheet_json(json).unwrap();
+
+    assert_eq!(
+        migration::file_format_version(&migrated),
+        FILE_FORMAT_VERSION
+    );
+    assert!(migrated
+        .pointer("/strokes_state/strokes/1/value/brushstroke/style/marker/options")
+        .is_some());
+    assert!(migrated
+        .pointer("/strokes_state/strokes/2/value/brushstroke/style/solid/options")
+        .is_some());
+}
+
+#[test]
+fn refuse_newer_version() {
+    let mut json: serde_json::Value =
+        serde_json::from_str(FIXTURES[FILE_FORMAT_VERSION as usize]).unwrap();
+    json["file_format_version"] = serde_json::json!(FILE_FORMAT_VERSION + 1);
+
+    assert!(open_rnote_bytes(rnote_bytes(&json)).is_err());
+}
+
+#[test]
+fn save_and_reopen() {
+    let json: serde_json::Value =
+        serde_json::from_str(FIXTURES[FILE_FORMAT_VERSION as usize]).unwrap();
+    let mut sheet = open_rnote_bytes(rnote_bytes(&json)).unwrap();
+    let saved_bytes = sheet.save_sheet_as_rnote_bytes().unwrap();
+
+    assert!(container::is_container(&saved_bytes));
+    assert_eq!(
+        container::load_manifest(&saved_bytes)
+            .unwrap()
+            .file_format_version,
+        FILE_FORMAT_VERSION
+    );
+
+    let reopened = open_rnote_bytes(saved_bytes).unwrap();
+    assert_eq!(
+        reopened.strokes_state.strokes_len(),
+        sheet.strokes_state.strokes_len()
+    );
+}
+
+/// Panics when it is serialized, like a broken serialize impl
+struct PanickingValue;
+
+impl serde::Serialize for PanickingValue {
+    fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        panic!("serializing the value panicked");
+    }
+}
+
+#[test]
+fn panics_while_saving_leave_no_container_behind() {
+    let json: serde_json::Value =
+        serde_json::from_str(FIXTURES[FILE_FORMAT_VERSION as usize]).unwrap();
+    let sheet = open_rnote_bytes(rnote_bytes(&json)).unwrap();
+    assert!(sheet.strokes_state.strokes_len() > 0);
+
+    assert!(
+        std::panic::catch_unwind(|| container::save(&PanickingValue, FILE_FORMAT_VERSION)).is_err()
+    );
+
+    // The strokes are serialized inline again, instead of into the container of the panicked save
+    let reserialized: Sheet =
+        serde_json::from_value(serde_json::to_value(&sheet).unwrap()).unwrap();
+    assert_eq!(
+        reserialized.strokes_state.strokes_len(),
+        sheet.strokes_state.strokes_len()
+    );
+}
+
+#[test]
+fn assets_are_separate_entries() {
+    let pdf_data = b"%PDF-1.4 not a real pdf".to_vec();
+    let imported_pdf = ImportedPdf {
+        data_base64: base64::encode(&pdf_data),
+        pages: vec![],
+    };
+
+    let bytes = container::save(&imported_pdf, FILE_FORMAT_VERSION).unwrap();
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes.as_slice())).unwrap();
+    let asset_name = archive
+        .file_names()
+        .find(|name| name.starts_with(container::ASSETS_DIR))
+        .map(String::from)
+        .unwrap();
+    let mut asset = Vec::new();
+    std::io::Read::read_to_end(&mut archive.by_name(&asset_name).unwrap(), &mut asset).unwrap();
+    assert_eq!(asset, pdf_data);
+
+    let loaded: ImportedPdf =
+        container::load(&bytes, container::from_cbor_reader::<ImportedPdf>).unwrap();
+    assert_eq!(loaded.data_base64, imported_pdf.data_base64);
+
+    // Outside of containers the data stays inline
+    let json = serde_json::to_value(&imported_pdf).unwrap();
+    assert_eq!(json["data_base64"], imported_pdf.data_base64.as_str());
+}
+
+/// The index of the slot of the key in the slotmap
+fn slot_index(key: StrokeKey) -> u32 {
+    (key.data().as_ffi() & 0xffff_ffff) as u32
+}
+
+/// The keys of the strokes, sorted to compare them
+fn sorted_keys(sheet: &Sheet) -> Vec<StrokeKey> {
+    let mut keys = sheet.strokes_state.keys_unordered();
+    keys.sort_unstable();
+    keys
+}
+
+#[test]
+fn incremental_save_rewrites_changed_chunks() {
+    let n_chunks = 3;
+    let mut json: serde_json::Value =
+        serde_json::from_str(FIXTURES[FILE_FORMAT_VERSION as usize]).unwrap();
+    let stroke_slot = json["strokes_state"]["strokes"][1].clone();
+    let mut slots = vec![serde_json::json!({ "value": null, "version": 0 })];
+    slots.extend(
+        std::iter::repeat(stroke_slot)
+            .take((n_chunks * container::STROKES_CHUNK_SIZE) as usize - 1),
+    );
+    json["strokes_state"]["strokes"] = serde_json::Value::Array(slots);
+
+    let mut sheet = open_rnote_bytes(rnote_bytes(&json)).unwrap();
+    let first_bytes = sheet.save_sheet_as_rnote_bytes().unwrap();
+    let cache = sheet.container_cache.as_ref().unwrap();
+    assert_eq!(cache.n_chunks(), n_chunks as usize);
+    assert_eq!(cache.rewritten_chunks().len(), n_chunks as usize);
+
+    // Nothing changed, everything is copied
+    let unchanged_bytes = sheet.save_sheet_as_rnote_bytes().unwrap();
+    let cache = sheet.container_cache.as_ref().unwrap();
+    assert!(cache.rewritten_chunks().is_empty());
+    assert_eq!(
+        open_rnote_bytes(unchanged_bytes)
+            .unwrap()
+            .strokes_state
+            .strokes_len(),
+        sheet.strokes_state.strokes_len()
+    );
+
+    // Changing a stroke in the second chunk only rewrites that chunk
+    let key = sheet
+        .strokes_state
+        .keys_unordered()
+        .into_iter()
+        .find(|&key| slot_index(key) == container::STROKES_CHUNK_SIZE + 1)
+        .unwrap();
+    let stroke = sheet.strokes_state.get_stroke_ref(key).unwrap().clone();
+    sheet.strokes_state.replace_stroke(key, stroke);
+    let changed_bytes = sheet.save_sheet_as_rnote_bytes().unwrap();
+    assert_eq!(
+        sheet.container_cache.as_ref().unwrap().rewritten_chunks(),
+        &[1]
+    );
+
+    let mut first_archive = zip::ZipArchive::new(Cursor::new(first_bytes.as_slice())).unwrap();
+    let mut changed_archive = zip::ZipArchive::new(Cursor::new(changed_bytes.as_slice())).unwrap();
+    for chunk in 0..n_chunks {
+        let name = format!("{}{}", container::STROKES_DIR, chunk);
+        let first_entry = first_archive.by_name(&name).unwrap();
+        let (first_crc32, first_size) = (first_entry.crc32(), first_entry.compressed_size());
+        drop(first_entry);
+        let changed_entry = changed_archive.by_name(&name).unwrap();
+
+        assert_eq!(changed_entry.crc32(), first_crc32);
+        assert_eq!(changed_entry.compressed_size(), first_size);
+    }
+
+    let reopened = open_rnote_bytes(changed_bytes).unwrap();
+    assert_eq!(sorted_keys(&reopened), sorted_keys(&sheet));
+}
+
+#[test]
+fn removed_strokes_keep_their_slot_versions() {
+    let json: serde_json::Value =
+        serde_json::from_str(FIXTURES[FILE_FORMAT_VERSION as usize]).unwrap();
+    let mut sheet = open_rnote_bytes(rnote_bytes(&json)).unwrap();
+    let removed_key = sheet.strokes_state.keys_unordered()[0];
+    let stroke = sheet.strokes_state.remove_stroke(removed_key).unwrap();
+
+    let mut reopened = open_rnote_bytes(sheet.save_sheet_as_rnote_bytes().unwrap()).unwrap();
+    let inserted_key = reopened.strokes_state.insert_stroke(stroke);
+
+    // A key of a removed stroke must not refer to a new stroke
+    assert_ne!(inserted_key, removed_key);
+}
+
+/// Whether the textured brush strokes of the sheet keep the legacy seeding
+fn legacy_seedings(sheet: &Sheet) -> Vec<bool> {
+    sheet
+        .strokes_state
+        .keys_unordered()
+        .into_iter()
+        .filter_map(|key| match sheet.strokes_state.get_stroke_ref(key) {
+            Some(StrokeStyle::BrushStroke(BrushStroke {
+                style: BrushStrokeStyle::Textured { options },
+                ..
+            })) => Some(options.legacy_seeding),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn textured_strokes_of_older_files_keep_their_seeding() {
+    let json: serde_json::Value =
+        serde_json::from_str(FIXTURES[FILE_FORMAT_VERSION as usize]).unwrap();
+    let sheet = open_rnote_bytes(rnote_bytes(&json)).unwrap();
+    assert_eq!(legacy_seedings(&sheet), vec![false]);
+
+    // Saved by the previous version, with the strokes in the chunks of the container
+    let older_bytes = container::save(&sheet, migration::SEGMENT_SEEDS_VERSION - 1)
+        .unwrap()
+        .to_vec();
+    let mut reopened = open_rnote_bytes(older_bytes).unwrap();
+    assert_eq!(legacy_seedings(&reopened), vec![true]);
+
+    // Saving again keeps the flag
+    let resaved = open_rnote_bytes(reopened.save_sheet_as_rnote_bytes().unwrap()).unwrap();
+    assert_eq!(legacy_seedings(&resaved), vec![true]);
+}
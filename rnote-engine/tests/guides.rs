@@ -0,0 +1,69 @@
+use nalgebra as na;
+use rnote_engine::pens::guides::{Guide, GuideKind, Guides};
+
+fn assert_near(first: na::Vector2<f64>, second: na::Vector2<f64>) {
+    assert!(
+        (first - second).magnitude() < 1e-9,
+        "{} != {}",
+        first,
+        second
+    );
+}
+
+#[test]
+fn strokes_along_the_ruler_are_straight() {
+    let mut guides = Guides::default();
+    guides.show(Some(GuideKind::Ruler), na::vector![0.0, 0.0]);
+    let edge_y = -Guide::RULER_WIDTH * 0.5;
+
+    // Too far away from the edges
+    assert!(!guides.begin_constraint(na::vector![0.0, edge_y - 100.0], 1.0));
+    assert_near(
+        guides.constrain(na::vector![10.0, -100.0]),
+        na::vector![10.0, -100.0],
+    );
+
+    assert!(guides.begin_constraint(na::vector![0.0, edge_y - 10.0], 1.0));
+    assert_near(
+        guides.constrain(na::vector![120.0, edge_y - 15.0]),
+        na::vector![120.0, edge_y],
+    );
+    // Beyond the ends of the edge the stroke continues along its line
+    assert_near(
+        guides.constrain(na::vector![1000.0, edge_y + 3.0]),
+        na::vector![1000.0, edge_y],
+    );
+
+    guides.end_constraint();
+    assert_near(
+        guides.constrain(na::vector![5.0, 5.0]),
+        na::vector![5.0, 5.0],
+    );
+}
+
+#[test]
+fn guides_are_moved_and_rotated_by_dragging() {
+    let mut guides = Guides::default();
+    assert!(!guides.begin_drag(na::vector![0.0, 0.0]));
+
+    guides.show(Some(GuideKind::SetSquare), na::vector![0.0, 0.0]);
+    assert!(guides.begin_drag(na::vector![0.0, 0.0]));
+    guides.drag_to(na::vector![50.0, 20.0]);
+    guides.end_drag();
+    assert_near(guides.guide.unwrap().center, na::vector![50.0, 20.0]);
+
+    let handle = guides.guide.unwrap().rotation_handle();
+    assert!(guides.begin_drag(handle));
+    // Rotating the handle by a quarter turn around the center
+    let center = guides.guide.unwrap().center;
+    let handle_vec = handle - center;
+    guides.drag_to(center + na::vector![-handle_vec[1], handle_vec[0]]);
+    guides.end_drag();
+    assert!((guides.guide.unwrap().angle - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+    assert!(!guides.dragging());
+
+    // The edges rotated along
+    let guide = guides.guide.unwrap();
+    let (start, end) = guide.edges()[0];
+    assert!(((end - start).normalize() - na::vector![0.0, 1.0]).magnitude() < 1e-9);
+}
@@ -0,0 +1,237 @@
+mod common;
+
+use std::sync::{Arc, RwLock};
+
+use nalgebra as na;
+use rnote_engine::compose::color::Color;
+use rnote_engine::drawbehaviour::DrawBehaviour;
+use rnote_engine::render::Renderer;
+use rnote_engine::strokes::strokestyle::{StrokeOptionsModification, StrokeStyle};
+use rnote_engine::strokesstate::chrono_comp::StrokesZOrder;
+use rnote_engine::strokesstate::{ColorReplaceScope, StrokeKey, StrokesState};
+
+fn insert_committed(strokes_state: &mut StrokesState) -> StrokeKey {
+    let key = strokes_state.insert_stroke(common::brushstroke(na::vector![10.0, 10.0], 1));
+    strokes_state.commit_history();
+    key
+}
+
+#[test]
+fn undo_and_redo_insert() {
+    let mut strokes_state = StrokesState::default();
+    insert_committed(&mut strokes_state);
+
+    assert!(strokes_state.undo());
+    assert_eq!(strokes_state.strokes_len(), 0);
+    assert!(!strokes_state.undo());
+
+    assert!(strokes_state.redo());
+    assert_eq!(strokes_state.strokes_len(), 1);
+    assert!(!strokes_state.redo());
+}
+
+#[test]
+fn rapid_changes_are_coalesced() {
+    let mut strokes_state = StrokesState::default();
+    let key = insert_committed(&mut strokes_state);
+    let bounds = strokes_state.get_stroke_ref(key).unwrap().bounds();
+
+    // Like the steps of the width spin button
+    for i in 1..=10 {
+        let modification = StrokeOptionsModification {
+            width: Some(2.0 + f64::from(i)),
+            ..StrokeOptionsModification::default()
+        };
+        strokes_state.modify_stroke_options(&[key], &modification);
+    }
+    strokes_state.commit_history();
+    assert_eq!(strokes_state.history().undo_len(), 2);
+
+    assert!(strokes_state.undo());
+    assert_eq!(strokes_state.get_stroke_ref(key).unwrap().bounds(), bounds);
+}
+
+#[test]
+fn group_is_undone_at_once() {
+    let mut strokes_state = StrokesState::default();
+    let key = insert_committed(&mut strokes_state);
+
+    strokes_state.begin_history_group();
+    strokes_state.set_trashed(key, true);
+    strokes_state.insert_stroke(common::brushstroke(na::vector![20.0, 20.0], 1));
+    strokes_state.end_history_group();
+    assert_eq!(strokes_state.history().undo_len(), 2);
+
+    assert!(strokes_state.undo());
+    assert_eq!(strokes_state.strokes_len(), 1);
+    assert_eq!(strokes_state.trashed(key), Some(false));
+}
+
+#[test]
+fn removed_stroke_is_inserted_again() {
+    let mut strokes_state = StrokesState::default();
+    let key = insert_committed(&mut strokes_state);
+
+    strokes_state.remove_stroke(key);
+    strokes_state.commit_history();
+    assert_eq!(strokes_state.strokes_len(), 0);
+
+    // The key of the inserted stroke changes, which the following entries need to follow
+    assert!(strokes_state.undo());
+    assert_eq!(strokes_state.strokes_len(), 1);
+    assert!(strokes_state.undo());
+    assert_eq!(strokes_state.strokes_len(), 0);
+    assert!(strokes_state.redo());
+    assert!(strokes_state.redo());
+    assert_eq!(strokes_state.strokes_len(), 0);
+}
+
+#[test]
+fn removed_stroke_keeps_its_components() {
+    let mut strokes_state = StrokesState::default();
+    let first_key = insert_committed(&mut strokes_state);
+    let second_key = insert_committed(&mut strokes_state);
+    strokes_state.set_tags(first_key, ["todo"]);
+    strokes_state.commit_history();
+
+    strokes_state.remove_stroke(first_key);
+    strokes_state.commit_history();
+    assert!(strokes_state.undo());
+
+    // Inserted again with a new key, but in its place in the chronology
+    let keys = strokes_state.keys_sorted_chrono();
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[1], second_key);
+    assert_eq!(strokes_state.tags(keys[0]), ["todo"]);
+
+    assert!(strokes_state.undo());
+    assert!(strokes_state.tags(keys[0]).is_empty());
+}
+
+#[test]
+fn z_order_change_is_undone() {
+    let mut strokes_state = StrokesState::default();
+    let first_key = insert_committed(&mut strokes_state);
+    let second_key = insert_committed(&mut strokes_state);
+
+    strokes_state.set_selected_keys(&[first_key], true);
+    strokes_state.change_selection_z_order(StrokesZOrder::BringToFront);
+    assert_eq!(strokes_state.keys_sorted_chrono(), [second_key, first_key]);
+
+    assert!(strokes_state.undo());
+    assert_eq!(strokes_state.keys_sorted_chrono(), [first_key, second_key]);
+    assert!(strokes_state.redo());
+    assert_eq!(strokes_state.keys_sorted_chrono(), [second_key, first_key]);
+}
+
+#[test]
+fn clear_ends_the_groups() {
+    let mut strokes_state = StrokesState::default();
+    strokes_state.begin_history_group();
+    strokes_state.clear_history();
+
+    let key = strokes_state.insert_stroke(common::brushstroke(na::vector![10.0, 10.0], 1));
+    // Changes of another kind are a new entry again
+    strokes_state.set_trashed(key, true);
+    assert_eq!(strokes_state.history().undo_len(), 1);
+}
+
+#[test]
+fn nothing_to_redo_after_new_change() {
+    let mut strokes_state = StrokesState::default();
+    insert_committed(&mut strokes_state);
+    assert!(strokes_state.undo());
+
+    insert_committed(&mut strokes_state);
+    assert!(!strokes_state.redo());
+    assert_eq!(strokes_state.strokes_len(), 1);
+}
+
+#[test]
+fn memory_budget_drops_oldest_entries() {
+    let mut strokes_state = StrokesState::default();
+    for _ in 0..10 {
+        insert_committed(&mut strokes_state);
+    }
+    assert_eq!(strokes_state.history().undo_len(), 10);
+
+    let entry_memory = strokes_state.history().memory() / 10;
+    strokes_state.set_history_memory_budget(entry_memory * 3);
+    assert!(strokes_state.history().undo_len() <= 3);
+    assert!(strokes_state.history().memory() <= strokes_state.history().memory_budget());
+
+    // The newest entry is always kept
+    strokes_state.set_history_memory_budget(0);
+    assert_eq!(strokes_state.history().undo_len(), 1);
+}
+
+#[test]
+fn undone_branch_is_kept() {
+    let mut strokes_state = StrokesState::default();
+    let first_key = insert_committed(&mut strokes_state);
+    let first_node = strokes_state.history().current_node();
+    assert!(strokes_state.undo());
+
+    let second_key = insert_committed(&mut strokes_state);
+    let second_node = strokes_state.history().current_node();
+    assert!(strokes_state.get_stroke_ref(first_key).is_none());
+
+    let tree = strokes_state.history().tree();
+    assert_eq!(tree.len(), 3);
+    assert_eq!(tree[0].key, strokes_state.history().root());
+    assert_eq!(tree[0].children, vec![first_node, second_node]);
+    assert!(tree[2].is_current);
+
+    // Navigating to the first branch undoes the second one
+    assert!(strokes_state.navigate_history(first_node));
+    assert_eq!(strokes_state.strokes_len(), 1);
+    assert!(strokes_state.get_stroke_ref(second_key).is_none());
+    assert_eq!(strokes_state.history().current_node(), first_node);
+
+    // Redo follows the branch which was navigated to last
+    assert!(strokes_state.undo());
+    assert!(strokes_state.redo());
+    assert_eq!(strokes_state.history().current_node(), first_node);
+}
+
+#[test]
+fn replaced_color_is_undone_at_once() {
+    let mut strokes_state = StrokesState::default();
+    let first_key = insert_committed(&mut strokes_state);
+    let second_key = insert_committed(&mut strokes_state);
+    let from = strokes_state
+        .get_stroke_ref(first_key)
+        .and_then(|stroke| match stroke {
+            StrokeStyle::BrushStroke(brushstroke) => brushstroke.stroke_color(),
+            _ => None,
+        })
+        .unwrap();
+    let to = Color::RED;
+
+    let modified = strokes_state.replace_color(
+        from,
+        to,
+        ColorReplaceScope::Document,
+        Arc::new(RwLock::new(Renderer::default())),
+        1.0,
+    );
+    assert_eq!(modified.len(), 2);
+    assert!(strokes_state
+        .get_stroke_ref(second_key)
+        .unwrap()
+        .uses_color(to));
+    assert!(!strokes_state
+        .get_stroke_ref(second_key)
+        .unwrap()
+        .uses_color(from));
+
+    assert!(strokes_state.undo());
+    assert!(strokes_state
+        .get_stroke_ref(first_key)
+        .unwrap()
+        .uses_color(from));
+    assert!(strokes_state
+        .get_stroke_ref(second_key)
+        .unwrap()
+        .uses_color(from));
+}
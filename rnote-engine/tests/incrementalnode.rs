@@ -0,0 +1,40 @@
+#![cfg(feature = "ui")]
+
+use gtk4::{gdk, graphene, gsk, prelude::*};
+use rnote_engine::render::IncrementalNode;
+
+fn color_node(i: usize) -> gsk::RenderNode {
+    gsk::ColorNode::new(
+        &gdk::RGBA::new(1.0, 0.0, 0.0, 1.0),
+        &graphene::Rect::new(i as f32, 0.0, 1.0, 1.0),
+    )
+    .upcast()
+}
+
+#[test]
+fn appended_nodes_are_collapsed() {
+    let mut incremental = IncrementalNode::new(1.0, 1.0);
+    assert!(incremental.compose().is_none());
+
+    for i in 0..10_000 {
+        incremental.append(color_node(i));
+    }
+
+    // Ten thousand nodes are composed from a few containers
+    assert!(incremental.n_composed_nodes() < IncrementalNode::CHUNK_SIZE * 3);
+    let rendernode = incremental.compose().unwrap();
+    let bounds = rendernode.bounds();
+    assert_eq!(bounds.x(), 0.0);
+    assert_eq!(bounds.width(), 10_000.0);
+}
+
+#[test]
+fn replaced_renderings_are_detected() {
+    let mut incremental = IncrementalNode::new(0.5, 1.0);
+    incremental.append(color_node(0));
+
+    let rendernode = incremental.compose();
+    assert!(incremental.is_composed(rendernode.as_ref()));
+    assert!(!incremental.is_composed(Some(&color_node(0))));
+    assert!(!incremental.is_composed(None));
+}
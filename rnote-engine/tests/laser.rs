@@ -0,0 +1,51 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use nalgebra as na;
+use rnote_engine::pens::penbehaviour::PenBehaviour;
+use rnote_engine::pens::tools::{ToolStyle, Tools};
+use rnote_engine::render::Renderer;
+use rnote_engine::sheet::Sheet;
+use rnote_engine::strokes::inputdata::InputData;
+
+fn input(pos: na::Vector2<f64>) -> VecDeque<InputData> {
+    VecDeque::from(vec![InputData::new(pos, 1.0)])
+}
+
+#[test]
+fn laser_trails_fade_out_without_entering_the_sheet() {
+    let renderer = Arc::new(RwLock::new(Renderer::default()));
+    let mut sheet = Sheet::default();
+    let mut tools = Tools::default();
+    tools.style = ToolStyle::Laser;
+    tools.laser_tool.timeout = 1.0;
+
+    tools.begin(
+        input(na::vector![10.0, 10.0]),
+        &mut sheet,
+        None,
+        1.0,
+        renderer.clone(),
+    );
+    tools.motion(
+        input(na::vector![50.0, 20.0]),
+        &mut sheet,
+        None,
+        1.0,
+        renderer.clone(),
+    );
+    // Trails which are still drawn don't fade
+    assert!(tools
+        .laser_tool
+        .update(Instant::now() + Duration::from_secs(10)));
+
+    tools.end(VecDeque::new(), &mut sheet, None, 1.0, renderer);
+    let ended = Instant::now();
+    assert!(tools.laser_tool.update(ended));
+    assert!(tools.laser_tool.update(ended + Duration::from_millis(500)));
+    assert!(!tools.laser_tool.update(ended + Duration::from_secs(2)));
+    assert!(!tools.laser_tool.has_trails());
+
+    assert!(sheet.strokes_state.keys_sorted_chrono().is_empty());
+}
@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+
+use nalgebra as na;
+use rnote_engine::pens::penbehaviour::PenBehaviour;
+use rnote_engine::pens::tools::{ToolStyle, Tools};
+use rnote_engine::render::Renderer;
+use rnote_engine::sheet::format::MeasureUnit;
+use rnote_engine::sheet::Sheet;
+use rnote_engine::strokes::inputdata::InputData;
+
+fn input(pos: na::Vector2<f64>) -> VecDeque<InputData> {
+    VecDeque::from(vec![InputData::new(pos, 1.0)])
+}
+
+fn drag(tools: &mut Tools, sheet: &mut Sheet, from: na::Vector2<f64>, to: na::Vector2<f64>) {
+    let renderer = Arc::new(RwLock::new(Renderer::default()));
+
+    tools.begin(input(from), sheet, None, 1.0, renderer.clone());
+    tools.motion(input(to), sheet, None, 1.0, renderer.clone());
+    tools.end(VecDeque::new(), sheet, None, 1.0, renderer);
+}
+
+#[test]
+fn measures_distances_and_angles_in_the_unit() {
+    let mut sheet = Sheet::default();
+    let mut tools = Tools::default();
+    tools.style = ToolStyle::Measure;
+    tools.measure_tool.unit = MeasureUnit::In;
+
+    drag(
+        &mut tools,
+        &mut sheet,
+        na::vector![100.0, 100.0],
+        na::vector![196.0, 4.0],
+    );
+
+    let measurement = tools.measure_tool.measurement(96.0).unwrap();
+    assert!((measurement.length - 2.0_f64.sqrt()).abs() < 1e-9);
+    // The y axis of the sheet points down
+    assert!((measurement.angle - 45.0).abs() < 1e-9);
+    assert_eq!(measurement.area, None);
+
+    tools.measure_tool.unit = MeasureUnit::Cm;
+    let measurement = tools.measure_tool.measurement(96.0).unwrap();
+    assert!((measurement.length - 2.0_f64.sqrt() * 2.54).abs() < 1e-9);
+}
+
+#[test]
+fn closed_polygons_measure_the_area() {
+    let mut sheet = Sheet::default();
+    let mut tools = Tools::default();
+    tools.style = ToolStyle::Measure;
+    tools.measure_tool.unit = MeasureUnit::Px;
+
+    let corners = [
+        na::vector![0.0, 0.0],
+        na::vector![100.0, 0.0],
+        na::vector![100.0, 50.0],
+        na::vector![0.0, 50.0],
+        na::vector![0.0, 0.0],
+    ];
+    for segment in corners.windows(2) {
+        drag(&mut tools, &mut sheet, segment[0], segment[1]);
+    }
+
+    assert!(tools.measure_tool.closed());
+    assert_eq!(tools.measure_tool.vertices().len(), 4);
+    let measurement = tools.measure_tool.measurement(96.0).unwrap();
+    assert!((measurement.length - 300.0).abs() < 1e-9);
+    assert!((measurement.area.unwrap() - 5000.0).abs() < 1e-9);
+
+    // A drag away from the polygon begins a new measurement
+    drag(
+        &mut tools,
+        &mut sheet,
+        na::vector![300.0, 300.0],
+        na::vector![310.0, 300.0],
+    );
+    assert!(!tools.measure_tool.closed());
+    assert_eq!(tools.measure_tool.vertices().len(), 2);
+}
+
+#[test]
+fn converts_between_units() {
+    let mm = MeasureUnit::convert_measurement(1.0, MeasureUnit::In, 96.0, MeasureUnit::Mm, 96.0);
+    assert!((mm - 25.4).abs() < 1e-9);
+    let cm = MeasureUnit::convert_measurement(96.0, MeasureUnit::Px, 96.0, MeasureUnit::Cm, 96.0);
+    assert!((cm - 2.54).abs() < 1e-9);
+}
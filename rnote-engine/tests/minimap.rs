@@ -0,0 +1,113 @@
+#![cfg(feature = "cairo")]
+
+mod common;
+
+use nalgebra as na;
+use parry2d_f64::bounding_volume::AABB;
+use rnote_engine::sheet::minimap::Minimap;
+use rnote_engine::sheet::Sheet;
+
+/// Updates the minimap and waits until the tiles are rendered
+fn update_until_finished(minimap: &mut Minimap, sheet: &Sheet) {
+    minimap.update(sheet);
+    minimap.wait_for_pending();
+    assert_eq!(minimap.n_pending(), 0);
+}
+
+#[test]
+fn minimap_covers_the_whole_sheet() {
+    let sheet = Sheet::default();
+    let mut minimap = Minimap::new(1024);
+    update_until_finished(&mut minimap, &sheet);
+
+    // The zoom is a power of two and the minimap fits into its size
+    assert_eq!(minimap.zoom().log2().fract(), 0.0);
+    assert!(minimap.extents().max() <= 1024.0);
+    assert!(minimap.extents().max() > 512.0);
+
+    let (first, last) = Minimap::tile_index_range(sheet.bounds(), minimap.zoom());
+    let n_tiles = ((last[0] - first[0] + 1) * (last[1] - first[1] + 1)) as usize;
+    assert!(n_tiles > 1);
+    assert_eq!(minimap.tiles().count(), n_tiles);
+    for tile in minimap.tiles() {
+        assert!(f64::from(tile.image.pixel_width) <= Minimap::TILE_SIZE);
+        assert!(f64::from(tile.image.pixel_height) <= Minimap::TILE_SIZE);
+    }
+    // The tiles at the edges are clipped to the sheet
+    assert!(minimap
+        .tiles()
+        .any(|tile| f64::from(tile.image.pixel_width) < Minimap::TILE_SIZE));
+
+    // Nothing changed, so nothing is rendered again
+    assert!(!minimap.update(&sheet));
+    assert_eq!(minimap.n_pending(), 0);
+}
+
+#[test]
+fn edits_only_render_the_tiles_they_touch() {
+    let mut sheet = Sheet::default();
+    let mut minimap = Minimap::new(1024);
+    let tile_bounds = Minimap::tile_bounds(
+        na::vector![0, 0],
+        Minimap::zoom_for_bounds(1024, sheet.bounds()),
+    );
+    let key = sheet.strokes_state.insert_stroke(common::brushstroke(
+        tile_bounds.center().coords - na::vector![20.0, 0.0],
+        5,
+    ));
+    update_until_finished(&mut minimap, &sheet);
+    let signature = minimap.tile(na::vector![0, 0]).unwrap().signature;
+
+    sheet
+        .strokes_state
+        .translate_strokes(&[key], na::vector![10.0, 0.0], 1.0);
+    minimap.update(&sheet);
+    assert_eq!(minimap.n_pending(), 1);
+    // The outdated tile is kept until the new one is rendered
+    assert_eq!(
+        minimap.tile(na::vector![0, 0]).unwrap().signature,
+        signature
+    );
+
+    update_until_finished(&mut minimap, &sheet);
+    let new_signature = minimap.tile(na::vector![0, 0]).unwrap().signature;
+    assert_ne!(new_signature, signature);
+
+    // Growing the sheet a bit keeps the zoom, so the tiles away from the edges are kept
+    let zoom = minimap.zoom();
+    sheet.height += 10.0;
+    update_until_finished(&mut minimap, &sheet);
+    assert_eq!(minimap.zoom(), zoom);
+    assert_eq!(
+        minimap.tile(na::vector![0, 0]).unwrap().signature,
+        new_signature
+    );
+}
+
+#[test]
+fn minimap_positions_map_to_the_sheet() {
+    let sheet = Sheet::default();
+    let mut minimap = Minimap::new(1024);
+    minimap.update(&sheet);
+    let zoom = minimap.zoom();
+    let sheet_bounds = sheet.bounds();
+
+    let viewport = AABB::new(na::point![100.0, 200.0], na::point![300.0, 500.0]);
+    let viewport_rect = minimap.viewport_rect(viewport);
+    assert_eq!(viewport_rect.mins, na::point![100.0 * zoom, 200.0 * zoom]);
+    assert_eq!(
+        minimap.minimap_to_sheet_coords(viewport_rect.maxs.coords),
+        na::vector![300.0, 500.0]
+    );
+
+    // Clicking the minimap centers the viewport at the position
+    let centered = minimap.viewport_centered_at(na::vector![300.0, 400.0] * zoom, viewport);
+    assert_eq!(centered.center(), na::point![300.0, 400.0]);
+    assert_eq!(centered.extents(), viewport.extents());
+
+    // but keeps it inside the sheet
+    let at_corner = minimap.viewport_centered_at(na::vector![0.0, 0.0], viewport);
+    assert_eq!(at_corner.mins, sheet_bounds.mins);
+    let at_end = minimap.viewport_centered_at(minimap.extents(), viewport);
+    assert!((at_end.maxs - sheet_bounds.maxs).norm() < 1e-9);
+}
@@ -0,0 +1,144 @@
+use nalgebra as na;
+use rnote_engine::sheet::outline::{OutlineEntry, OutlinePrefs};
+use rnote_engine::sheet::Sheet;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+use rnote_engine::strokes::textstroke::{TextStroke, TextStyle};
+use rnote_engine::strokesstate::StrokeKey;
+
+fn insert_text(
+    sheet: &mut Sheet,
+    text: &str,
+    y: f64,
+    font_size: f64,
+    heading_level: Option<u32>,
+) -> StrokeKey {
+    let style = TextStyle {
+        font_size,
+        heading_level,
+        ..TextStyle::default()
+    };
+
+    sheet
+        .strokes_state
+        .insert_stroke(StrokeStyle::TextStroke(TextStroke::new(
+            String::from(text),
+            na::vector![50.0, y],
+            TextStroke::MAX_WIDTH_DEFAULT,
+            style,
+        )))
+}
+
+/// The titles of the entries and their levels, depth first
+fn flatten(entries: &[OutlineEntry]) -> Vec<(String, u32, usize)> {
+    entries
+        .iter()
+        .flat_map(|entry| {
+            std::iter::once((entry.title.clone(), entry.level, entry.children.len()))
+                .chain(flatten(&entry.children))
+        })
+        .collect()
+}
+
+fn marked_only() -> OutlinePrefs {
+    OutlinePrefs {
+        from_font_size: false,
+        ..OutlinePrefs::default()
+    }
+}
+
+#[test]
+fn marked_headings_are_nested_by_level() {
+    let mut sheet = Sheet::default();
+    // Inserted out of order, the outline is in reading order
+    insert_text(&mut sheet, "Chapter 2", 400.0, 32.0, Some(1));
+    let chapter = insert_text(
+        &mut sheet,
+        "\n  Chapter 1 \nThe first chapter",
+        100.0,
+        32.0,
+        Some(1),
+    );
+    insert_text(&mut sheet, "Section 1.1", 200.0, 32.0, Some(2));
+    insert_text(&mut sheet, "Some body text", 250.0, 32.0, None);
+    insert_text(&mut sheet, "Section 1.2", 300.0, 32.0, Some(2));
+    insert_text(&mut sheet, "Detail", 500.0, 32.0, Some(3));
+    insert_text(&mut sheet, "   ", 600.0, 32.0, Some(1));
+
+    let outline = sheet.outline(marked_only());
+    assert_eq!(outline.len(), 2);
+    assert_eq!(outline[0].key, chapter);
+    assert_eq!(outline[0].pos, na::vector![50.0, 100.0]);
+    assert_eq!(outline[0].page_index, Some(0));
+    assert_eq!(
+        flatten(&outline),
+        vec![
+            (String::from("Chapter 1"), 1, 2),
+            (String::from("Section 1.1"), 2, 0),
+            (String::from("Section 1.2"), 2, 0),
+            // Levels may be skipped
+            (String::from("Chapter 2"), 1, 1),
+            (String::from("Detail"), 3, 0),
+        ]
+    );
+}
+
+#[test]
+fn large_texts_are_headings_with_the_font_size_heuristic() {
+    let mut sheet = Sheet::default();
+    insert_text(&mut sheet, "Title", 100.0, 48.0, None);
+    insert_text(&mut sheet, "Subtitle", 200.0, 32.0, None);
+    insert_text(
+        &mut sheet,
+        "A long paragraph of the body text",
+        300.0,
+        16.0,
+        None,
+    );
+    insert_text(
+        &mut sheet,
+        "Another paragraph of the body text",
+        400.0,
+        16.0,
+        None,
+    );
+    insert_text(&mut sheet, "Slightly larger text", 500.0, 18.0, None);
+    // Marked headings keep their level
+    insert_text(&mut sheet, "Marked", 600.0, 16.0, Some(2));
+
+    assert_eq!(
+        flatten(&sheet.outline(OutlinePrefs::default())),
+        vec![
+            (String::from("Title"), 1, 2),
+            (String::from("Subtitle"), 2, 0),
+            (String::from("Marked"), 2, 0),
+        ]
+    );
+    assert_eq!(
+        flatten(&sheet.outline(marked_only())),
+        vec![(String::from("Marked"), 2, 0)]
+    );
+
+    // Without body text, texts of the same size are not headings
+    let mut sheet = Sheet::default();
+    insert_text(&mut sheet, "First", 100.0, 32.0, None);
+    insert_text(&mut sheet, "Second", 200.0, 32.0, None);
+    assert!(sheet.outline(OutlinePrefs::default()).is_empty());
+}
+
+#[test]
+#[cfg(feature = "cairo")]
+fn headings_become_the_bookmarks_of_exported_pdfs() {
+    let mut sheet = Sheet::default();
+    let trashed = insert_text(&mut sheet, "Trashed", 100.0, 32.0, Some(1));
+    insert_text(&mut sheet, "Chapter", 200.0, 32.0, Some(1));
+    sheet.strokes_state.set_trashed(trashed, true);
+
+    let outline = sheet.outline(marked_only());
+    assert_eq!(outline.len(), 1);
+    assert_eq!(outline[0].title, "Chapter");
+
+    let bytes =
+        futures::executor::block_on(sheet.export_sheet_as_pdf_bytes(String::from("Outline"), None))
+            .unwrap();
+    assert!(bytes.windows(9).any(|window| window == b"/Outlines"));
+}
@@ -0,0 +1,67 @@
+use rnote_engine::compose::color::Color;
+use rnote_engine::compose::palette::{Palette, Palettes};
+
+const GPL: &str = "GIMP Palette
+Name: Pastels
+Columns: 2
+# A comment
+255 179 186\tPastel Red
+186 255 201 Pastel Green
+ 0   0   0
+";
+
+#[test]
+fn parse_gpl() {
+    let palette = Palette::from_gpl(GPL).unwrap();
+
+    assert_eq!(palette.name, "Pastels");
+    assert_eq!(palette.columns, 2);
+    assert_eq!(palette.colors.len(), 3);
+    assert_eq!(palette.colors[0].name, "Pastel Red");
+    assert_eq!(palette.colors[0].color.to_u32(), 0xffb3_baff);
+    assert!(palette.colors[2].name.is_empty());
+    assert!(palette.contains_color(Color::BLACK));
+
+    assert!(Palette::from_gpl("255 0 0 Red").is_err());
+    assert!(Palette::from_gpl("GIMP Palette\n255 0 Red").is_err());
+}
+
+#[test]
+fn gpl_roundtrip() {
+    let palette = Palette::from_gpl(GPL).unwrap();
+    let reparsed = Palette::from_gpl(&palette.to_gpl()).unwrap();
+
+    assert_eq!(reparsed.name, palette.name);
+    assert_eq!(reparsed.columns, palette.columns);
+    assert_eq!(
+        reparsed
+            .colors
+            .iter()
+            .map(|palette_color| (palette_color.color.to_u32(), palette_color.name.clone()))
+            .collect::<Vec<(u32, String)>>(),
+        palette
+            .colors
+            .iter()
+            .map(|palette_color| (palette_color.color.to_u32(), palette_color.name.clone()))
+            .collect::<Vec<(u32, String)>>()
+    );
+}
+
+#[test]
+fn imported_names_are_unique() {
+    let mut palettes = Palettes::default();
+
+    assert_eq!(palettes.import_gpl(GPL).unwrap(), "Pastels");
+    assert_eq!(palettes.import_gpl(GPL).unwrap(), "Pastels 2");
+    assert!(palettes
+        .rename("Pastels 2", String::from("Pastels"))
+        .is_err());
+
+    palettes
+        .rename("Pastels 2", String::from("Sketch"))
+        .unwrap();
+    assert_eq!(palettes.names(), vec!["Pastels", "Sketch"]);
+    assert!(palettes.export_gpl("Sketch").is_some());
+    assert!(palettes.remove("Pastels").is_some());
+    assert_eq!(palettes.palettes().len(), 1);
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use nalgebra as na;
+use rnote_engine::perfcounters::{PerfCounters, PerfStats};
+use rnote_engine::render::Renderer;
+use rnote_engine::strokesstate::StrokesState;
+
+#[test]
+fn counters_are_recorded_and_reset() {
+    let perf_counters = PerfCounters::default();
+    assert_eq!(perf_counters.stats(), PerfStats::default());
+    assert_eq!(perf_counters.stats().mean_compose_time(), Duration::ZERO);
+
+    perf_counters.record_frame(10, 2, Duration::from_millis(4));
+    perf_counters.record_frame(12, 0, Duration::from_millis(3));
+    perf_counters.record_compose(Instant::now());
+
+    let stats = perf_counters.stats();
+    assert_eq!(stats.frames, 2);
+    // Only the last frame is kept
+    assert_eq!(stats.frame_strokes_drawn, 12);
+    assert_eq!(stats.frame_tiles_generated, 0);
+    assert_eq!(stats.frame_draw_time, Duration::from_millis(3));
+    assert_eq!(stats.strokes_composed, 1);
+    assert!(stats.to_string().contains("12 strokes drawn"));
+
+    perf_counters.reset();
+    assert_eq!(perf_counters.stats(), PerfStats::default());
+}
+
+#[test]
+fn composed_strokes_are_counted() {
+    let mut strokes_state = StrokesState::default();
+    let renderer = Arc::new(RwLock::new(Renderer::default()));
+    let key = strokes_state.insert_stroke(common::brushstroke(na::vector![0.0, 10.0], 10));
+
+    strokes_state.regenerate_rendering_for_stroke(key, Arc::clone(&renderer), 1.0);
+    strokes_state.regenerate_rendering_for_stroke(key, renderer, 2.0);
+    assert_eq!(strokes_state.perf_counters().stats().strokes_composed, 2);
+}
@@ -0,0 +1,277 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+
+use nalgebra as na;
+use parry2d_f64::bounding_volume::AABB;
+use rnote_engine::compose::transformable::Transformable;
+use rnote_engine::drawbehaviour::DrawBehaviour;
+use rnote_engine::pens::penbehaviour::PenBehaviour;
+use rnote_engine::pens::{PenEvent, PenStyle, Pens};
+use rnote_engine::plugins::{self, Composer, PluginPen};
+use rnote_engine::render::{self, Renderer};
+use rnote_engine::sheet::Sheet;
+use rnote_engine::strokes::extensionstroke::ExtensionStroke;
+use rnote_engine::strokes::inputdata::InputData;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+use serde::{Deserialize, Serialize};
+
+const MARKER_TYPE_NAME: &str = "tests.marker";
+
+/// A filled circle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Marker {
+    pos: na::Vector2<f64>,
+    radius: f64,
+}
+
+impl DrawBehaviour for Marker {
+    fn bounds(&self) -> AABB {
+        AABB::from_half_extents(
+            na::Point2::from(self.pos),
+            na::vector![self.radius, self.radius],
+        )
+    }
+
+    fn set_bounds(&mut self, bounds: AABB) {
+        self.pos = bounds.center().coords;
+        self.radius = bounds.half_extents().min();
+    }
+
+    fn gen_bounds(&self) -> Option<AABB> {
+        Some(self.bounds())
+    }
+
+    fn gen_svgs(&self, offset: na::Vector2<f64>) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let center = self.pos + offset;
+
+        Ok(vec![render::Svg {
+            svg_data: format!(
+                r#"<circle cx="{}" cy="{}" r="{}" fill="black"/>"#,
+                center[0], center[1], self.radius
+            ),
+            bounds: self.bounds(),
+        }])
+    }
+}
+
+impl Transformable for Marker {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.pos += offset;
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        self.pos = (na::Rotation2::new(angle) * (self.pos - center.coords)) + center.coords;
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.radius *= scale.min();
+    }
+}
+
+impl Composer for Marker {
+    fn type_name(&self) -> &'static str {
+        MARKER_TYPE_NAME
+    }
+
+    fn serialize_data(&self) -> Result<serde_json::Value, anyhow::Error> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn clone_composer(&self) -> Box<dyn Composer> {
+        Box::new(self.clone())
+    }
+}
+
+/// Inserts a marker where the pen is lifted
+#[derive(Debug, Clone)]
+struct MarkerPen {}
+
+impl PenBehaviour for MarkerPen {
+    fn begin(
+        &mut self,
+        _data_entries: VecDeque<InputData>,
+        _sheet: &mut Sheet,
+        _viewport: Option<AABB>,
+        _zoom: f64,
+        _renderer: Arc<RwLock<Renderer>>,
+    ) {
+    }
+
+    fn motion(
+        &mut self,
+        _data_entries: VecDeque<InputData>,
+        _sheet: &mut Sheet,
+        _viewport: Option<AABB>,
+        _zoom: f64,
+        _renderer: Arc<RwLock<Renderer>>,
+    ) {
+    }
+
+    fn end(
+        &mut self,
+        data_entries: VecDeque<InputData>,
+        sheet: &mut Sheet,
+        _viewport: Option<AABB>,
+        _zoom: f64,
+        _renderer: Arc<RwLock<Renderer>>,
+    ) {
+        if let Some(inputdata) = data_entries.back() {
+            sheet
+                .strokes_state
+                .insert_stroke(StrokeStyle::ExtensionStroke(ExtensionStroke::new(
+                    Box::new(Marker {
+                        pos: inputdata.pos(),
+                        radius: 5.0,
+                    }),
+                )));
+        }
+    }
+}
+
+impl PluginPen for MarkerPen {
+    fn name(&self) -> &'static str {
+        "tests.marker_pen"
+    }
+
+    fn display_name(&self) -> String {
+        String::from("Marker")
+    }
+
+    fn icon_name(&self) -> String {
+        String::from("application-x-addon-symbolic")
+    }
+
+    fn clone_pen(&self) -> Box<dyn PluginPen> {
+        Box::new(self.clone())
+    }
+}
+
+/// The tests share the registry, so the composer is registered once
+fn register_marker() {
+    static REGISTER: std::sync::Once = std::sync::Once::new();
+    REGISTER.call_once(|| {
+        plugins::register_composer(MARKER_TYPE_NAME, plugins::deserialize_composer::<Marker>)
+            .unwrap();
+        plugins::register_pen("tests.marker_pen", || Box::new(MarkerPen {})).unwrap();
+    });
+}
+
+fn pen_input(pens: &mut Pens, sheet: &mut Sheet, renderer: Arc<RwLock<Renderer>>) {
+    pens.handle_event(
+        PenEvent::DownEvent {
+            data_entries: VecDeque::from(vec![InputData::new(na::vector![10.0, 10.0], 1.0)]),
+            shortcut_key: None,
+        },
+        sheet,
+        None,
+        1.0,
+        renderer.clone(),
+    );
+    pens.handle_event(
+        PenEvent::UpEvent {
+            data_entries: VecDeque::from(vec![InputData::new(na::vector![20.0, 20.0], 1.0)]),
+            shortcut_key: None,
+        },
+        sheet,
+        None,
+        1.0,
+        renderer,
+    );
+}
+
+fn marker_stroke(pos: na::Vector2<f64>) -> StrokeStyle {
+    StrokeStyle::ExtensionStroke(ExtensionStroke::new(Box::new(Marker { pos, radius: 5.0 })))
+}
+
+#[test]
+fn strokes_of_registered_composers_are_saved_and_opened() {
+    register_marker();
+    let mut sheet = Sheet::default();
+    let key = sheet
+        .strokes_state
+        .insert_stroke(marker_stroke(na::vector![50.0, 50.0]));
+    sheet.strokes_state.update_geometry_for_stroke(key);
+
+    let stroke = sheet.strokes_state.get_stroke_ref(key).unwrap();
+    assert!(stroke.hittest(na::vector![52.0, 50.0], 0.0));
+    assert!(!stroke.gen_svgs(na::vector![0.0, 0.0]).unwrap().is_empty());
+
+    let bytes = sheet.save_sheet_as_rnote_bytes().unwrap();
+    let mut opened = Sheet::default();
+    opened
+        .open_sheet_from_rnote_bytes(bytes)
+        .unwrap();
+
+    let keys = opened.strokes_state.keys_sorted_chrono();
+    assert_eq!(keys.len(), 1);
+    match opened.strokes_state.get_stroke_ref(keys[0]).unwrap() {
+        StrokeStyle::ExtensionStroke(extensionstroke) => {
+            assert_eq!(extensionstroke.type_name(), MARKER_TYPE_NAME);
+            assert!(extensionstroke.composer().is_some());
+            assert_eq!(
+                extensionstroke.bounds(),
+                AABB::new(na::point![45.0, 45.0], na::point![55.0, 55.0])
+            );
+        }
+        stroke => panic!("opened {:?} instead of an extension stroke", stroke),
+    }
+}
+
+#[test]
+fn strokes_of_unregistered_composers_are_kept() {
+    register_marker();
+    let mut json = serde_json::to_value(marker_stroke(na::vector![50.0, 50.0])).unwrap();
+    json["extensionstroke"]["type_name"] = serde_json::json!("tests.unregistered");
+
+    let mut stroke: StrokeStyle = serde_json::from_value(json.clone()).unwrap();
+    match &stroke {
+        StrokeStyle::ExtensionStroke(extensionstroke) => {
+            assert!(extensionstroke.composer().is_none())
+        }
+        stroke => panic!("deserialized {:?} instead of an extension stroke", stroke),
+    }
+    // Neither drawn nor transformed
+    assert!(stroke.gen_svgs(na::vector![0.0, 0.0]).unwrap().is_empty());
+    stroke.translate(na::vector![10.0, 0.0]);
+
+    assert_eq!(serde_json::to_value(&stroke).unwrap(), json);
+}
+
+#[test]
+fn registering_twice_fails() {
+    plugins::register_composer("tests.twice", plugins::deserialize_composer::<Marker>).unwrap();
+    assert!(
+        plugins::register_composer("tests.twice", plugins::deserialize_composer::<Marker>).is_err()
+    );
+
+    plugins::register_pen("tests.twice_pen", || Box::new(MarkerPen {})).unwrap();
+    assert!(plugins::register_pen("tests.twice_pen", || Box::new(MarkerPen {})).is_err());
+}
+
+#[test]
+fn plugin_pens_receive_the_input() {
+    register_marker();
+    let renderer = Arc::new(RwLock::new(Renderer::default()));
+    let mut sheet = Sheet::default();
+    let mut pens = Pens::default();
+
+    assert!(plugins::registered_pens().contains(&"tests.marker_pen"));
+    assert!(pens.set_plugin_pen("tests.unregistered_pen").is_err());
+
+    pens.handle_event(
+        PenEvent::ChangeStyle(PenStyle::PluginStyle),
+        &mut sheet,
+        None,
+        1.0,
+        renderer.clone(),
+    );
+    // Without a selected plugin pen the input is ignored
+    pen_input(&mut pens, &mut sheet, renderer.clone());
+    assert!(sheet.strokes_state.keys_sorted_chrono().is_empty());
+
+    pens.set_plugin_pen("tests.marker_pen").unwrap();
+    assert_eq!(pens.plugin_pen().unwrap().name(), "tests.marker_pen");
+
+    pen_input(&mut pens, &mut sheet, renderer);
+    assert_eq!(sheet.statistics().stroke_counts.extensionstrokes, 1);
+}
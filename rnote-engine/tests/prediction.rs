@@ -0,0 +1,97 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use nalgebra as na;
+use rnote_engine::pens::brush::Brush;
+use rnote_engine::pens::prediction::{PredictionOptions, Predictor};
+use rnote_engine::strokes::element::Element;
+use rnote_engine::strokes::inputdata::InputData;
+
+/// An element at the position, `millis` milliseconds into the stroke
+fn element(pos: na::Vector2<f64>, millis: i64) -> Element {
+    Element {
+        inputdata: InputData::new(pos, 0.5),
+        timestamp: Some(Utc.timestamp_millis(millis)),
+    }
+}
+
+#[test]
+fn steady_motion_is_extrapolated() {
+    let options = PredictionOptions {
+        lookahead: 0.03,
+        n_points: 2,
+        ..PredictionOptions::default()
+    };
+    let mut predictor = Predictor::default();
+
+    // Moving right with 1000 units per second, one element every 10 ms
+    for i in 0..5 {
+        predictor.update(element(na::vector![10.0 * i as f64, 0.0], 10 * i), &options);
+    }
+
+    let predicted = predictor.predicted();
+    assert_eq!(predicted.len(), 2);
+    assert!((predicted[0].inputdata.pos() - na::vector![55.0, 0.0]).magnitude() < 1e-6);
+    assert!((predicted[1].inputdata.pos() - na::vector![70.0, 0.0]).magnitude() < 1e-6);
+
+    // The prediction is replaced when the real input arrives
+    predictor.update(element(na::vector![50.0, 0.0], 50), &options);
+    assert!((predictor.predicted()[1].inputdata.pos() - na::vector![80.0, 0.0]).magnitude() < 1e-6);
+
+    predictor.reset();
+    assert!(predictor.predicted().is_empty());
+}
+
+#[test]
+fn sudden_stops_dont_overshoot() {
+    let options = PredictionOptions::default();
+    let mut predictor = Predictor::default();
+
+    for (i, x) in [0.0, 10.0, 20.0, 30.0, 30.5, 30.6].iter().enumerate() {
+        predictor.update(element(na::vector![*x, 0.0], 10 * i as i64), &options);
+    }
+
+    // The deceleration can at most stop the prediction, not reverse it
+    for predicted in predictor.predicted() {
+        assert!(predicted.inputdata.pos()[0] >= 30.6 - 1e-6);
+    }
+}
+
+#[test]
+fn simultaneous_and_disabled_input_is_not_predicted() {
+    let mut predictor = Predictor::default();
+
+    // Processed in the same batch
+    predictor.update(
+        element(na::vector![0.0, 0.0], 0),
+        &PredictionOptions::default(),
+    );
+    predictor.update(
+        element(na::vector![10.0, 0.0], 0),
+        &PredictionOptions::default(),
+    );
+    assert!(predictor.predicted().is_empty());
+
+    let disabled = PredictionOptions {
+        enabled: false,
+        ..PredictionOptions::default()
+    };
+    predictor.update(element(na::vector![20.0, 0.0], 10), &disabled);
+    assert!(predictor.predicted().is_empty());
+}
+
+#[test]
+fn predicted_continuation_extends_the_stroke() {
+    let elements = (0..10).map(|i| element(na::vector![10.0 * i as f64, 0.0], 10 * i));
+    let brushstroke = common::brushstroke_w_brush(elements, &Brush::default());
+    let predicted = [
+        element(na::vector![100.0, 0.0], 100),
+        element(na::vector![110.0, 0.0], 110),
+    ];
+
+    let continuation = brushstroke.gen_predicted_continuation(&predicted).unwrap();
+    // The last three elements, the predicted ones and the repeated last predicted one
+    assert_eq!(continuation.elements.len(), 6);
+    assert!(continuation.bounds.maxs[0] > brushstroke.bounds.maxs[0]);
+    assert!(brushstroke.gen_predicted_continuation(&[]).is_none());
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use std::time::Instant;
+
+use nalgebra as na;
+use parry2d_f64::bounding_volume::AABB;
+use rnote_engine::sheet::presentation::{Presentation, PresentationView};
+use rnote_engine::sheet::Sheet;
+
+#[test]
+fn view_fits_exactly_one_page() {
+    let page_bounds = AABB::new(na::point![0.0, 100.0], na::point![200.0, 400.0]);
+
+    let view = PresentationView::fit_page(page_bounds, na::vector![1000.0, 600.0]);
+    assert_eq!(view.center, na::vector![100.0, 250.0]);
+    // The height of the page is the limit
+    assert!((view.zoom - 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn navigation_is_clamped_to_the_pages() {
+    let sheet = common::sheet_with_pages(3);
+    let viewport_size = na::vector![800.0, 600.0];
+    let now = Instant::now();
+    let mut presentation = Presentation::new(0);
+
+    assert!(!presentation.previous_page(&sheet, viewport_size, now));
+    assert!(presentation.next_page(&sheet, viewport_size, now));
+    assert!(presentation.next_page(&sheet, viewport_size, now));
+    assert_eq!(presentation.page_index(), 2);
+    assert!(!presentation.next_page(&sheet, viewport_size, now));
+    assert_eq!(presentation.page_index(), 2);
+
+    assert!(!presentation.go_to_page(10, &sheet, viewport_size, now));
+    assert!(presentation.go_to_page(0, &sheet, viewport_size, now));
+    assert_eq!(presentation.page_index(), 0);
+}
+
+#[test]
+fn page_changes_transition_smoothly() {
+    let sheet = common::sheet_with_pages(2);
+    let pages = sheet.pages();
+    let viewport_size = na::vector![800.0, 600.0];
+    let start = Instant::now();
+    let mut presentation = Presentation::new(0);
+
+    let first = presentation.view(&sheet, viewport_size, start).unwrap();
+    assert_eq!(first, PresentationView::fit_page(pages[0], viewport_size));
+    assert!(!presentation.transitioning(start));
+
+    assert!(presentation.next_page(&sheet, viewport_size, start));
+    let second = PresentationView::fit_page(pages[1], viewport_size);
+    assert!(presentation.transitioning(start));
+
+    // Halfway through the view is between the pages
+    let halfway = presentation
+        .view(
+            &sheet,
+            viewport_size,
+            start + Presentation::TRANSITION_DURATION / 2,
+        )
+        .unwrap();
+    let midpoint = (first.center + second.center) / 2.0;
+    assert!((halfway.center - midpoint).magnitude() < 1e-6);
+
+    let end = start + Presentation::TRANSITION_DURATION;
+    assert!(!presentation.transitioning(end));
+    assert_eq!(presentation.view(&sheet, viewport_size, end), Some(second));
+}
+
+#[test]
+fn endless_sheet_is_presented_page_by_page() {
+    let mut sheet = Sheet::default();
+    let page_height = sheet.format.height;
+    sheet.strokes_state.insert_stroke(common::brushstroke(
+        na::vector![100.0, page_height * 1.5],
+        5,
+    ));
+    sheet.resize_sheet_mode_endless_vertical();
+    let viewport_size = na::vector![800.0, 600.0];
+    let now = Instant::now();
+    let mut presentation = Presentation::new(0);
+
+    // The stroke is on the second page, and the padding below it extends into a third one
+    let n_pages = sheet.calc_n_pages() as usize;
+    assert_eq!(n_pages, 3);
+    while presentation.next_page(&sheet, viewport_size, now) {}
+    assert_eq!(presentation.page_index(), n_pages - 1);
+
+    // The last page ends with the sheet
+    let end = now + Presentation::TRANSITION_DURATION;
+    let last_page = AABB::new(
+        na::point![0.0, page_height * 2.0],
+        na::Point2::from(na::vector![sheet.width, sheet.height]),
+    );
+    assert_eq!(
+        presentation.view(&sheet, viewport_size, end),
+        Some(PresentationView::fit_page(last_page, viewport_size))
+    );
+}
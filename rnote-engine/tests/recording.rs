@@ -0,0 +1,174 @@
+mod common;
+
+use std::time::Duration;
+
+use nalgebra as na;
+use rnote_engine::sheet::Sheet;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+use rnote_engine::strokesstate::recording::RecordedAction;
+use rnote_engine::strokesstate::replay::Replay;
+use rnote_engine::strokesstate::StrokesState;
+
+/// Draws the stroke like the pen does, over the duration
+fn draw(strokes_state: &mut StrokesState, stroke: StrokeStyle, duration: Duration) {
+    strokes_state.begin_history_group();
+    strokes_state.insert_stroke(stroke);
+    std::thread::sleep(duration);
+    strokes_state.end_history_group();
+}
+
+fn n_elements(strokes_state: &StrokesState) -> Vec<usize> {
+    strokes_state
+        .keys_sorted_chrono()
+        .into_iter()
+        .filter_map(|key| match strokes_state.get_stroke_ref(key) {
+            Some(StrokeStyle::BrushStroke(brushstroke)) => Some(brushstroke.elements.len()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn recording_records_the_actions_in_order() {
+    let mut strokes_state = StrokesState::default();
+    let present = strokes_state.insert_stroke(common::brushstroke(na::vector![0.0, 0.0], 2));
+    strokes_state.commit_history();
+
+    strokes_state.start_session_recording();
+    assert!(strokes_state.session_recording().is_recording());
+    draw(
+        &mut strokes_state,
+        common::brushstroke(na::vector![0.0, 50.0], 2),
+        Duration::from_millis(20),
+    );
+    strokes_state.translate_strokes(&[present], na::vector![10.0, 0.0], 1.0);
+    strokes_state.commit_history();
+    strokes_state.set_trashed(present, true);
+    strokes_state.commit_history();
+    // Undone changes are recorded as well
+    strokes_state.undo();
+    strokes_state.stop_session_recording();
+
+    // Not recorded anymore
+    strokes_state.insert_stroke(common::brushstroke(na::vector![0.0, 100.0], 2));
+    strokes_state.commit_history();
+
+    let events = strokes_state.session_recording().events();
+    let actions = events
+        .iter()
+        .map(|event| match event.action {
+            RecordedAction::Insert { .. } => "insert",
+            RecordedAction::Change { .. } => "change",
+            RecordedAction::Remove { .. } => "remove",
+        })
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        actions,
+        vec!["insert", "insert", "change", "remove", "insert"]
+    );
+
+    // The present stroke is recorded at the start
+    assert_eq!(events[0].t, 0.0);
+    assert!(events.windows(2).all(|events| events[0].t <= events[1].t));
+    match events[1].action {
+        RecordedAction::Insert { duration, .. } => assert!(duration >= 0.02),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn continued_recordings_record_the_changes_in_between() {
+    let mut strokes_state = StrokesState::default();
+    strokes_state.start_session_recording();
+    let key = strokes_state.insert_stroke(common::brushstroke(na::vector![0.0, 0.0], 2));
+    strokes_state.stop_session_recording();
+    assert_eq!(strokes_state.session_recording().events().len(), 1);
+
+    // Changed while not recording
+    strokes_state.translate_strokes(&[key], na::vector![10.0, 0.0], 1.0);
+    strokes_state.commit_history();
+
+    strokes_state.start_session_recording();
+    let actions = strokes_state
+        .session_recording()
+        .events()
+        .iter()
+        .map(|event| &event.action)
+        .collect::<Vec<&RecordedAction>>();
+    assert!(matches!(
+        actions[..],
+        [RecordedAction::Insert { .. }, RecordedAction::Change { .. }]
+    ));
+}
+
+#[test]
+fn recording_is_saved_with_the_sheet() {
+    let mut sheet = Sheet::default();
+    sheet.strokes_state.start_session_recording();
+    draw(
+        &mut sheet.strokes_state,
+        common::brushstroke(na::vector![0.0, 0.0], 2),
+        Duration::from_millis(0),
+    );
+    let bytes = sheet.save_sheet_as_rnote_bytes().unwrap();
+
+    let mut reopened = Sheet::default();
+    reopened
+        .open_sheet_from_rnote_bytes(bytes)
+        .unwrap();
+    let recording = reopened.strokes_state.session_recording();
+    assert_eq!(recording.events().len(), 1);
+    assert!(!recording.is_recording());
+}
+
+#[test]
+fn replay_draws_the_strokes_progressively() {
+    let mut strokes_state = StrokesState::default();
+    strokes_state.start_session_recording();
+    draw(
+        &mut strokes_state,
+        common::brushstroke(na::vector![0.0, 0.0], 10),
+        Duration::from_millis(50),
+    );
+    draw(
+        &mut strokes_state,
+        common::brushstroke(na::vector![0.0, 50.0], 10),
+        Duration::from_millis(50),
+    );
+    strokes_state.stop_session_recording();
+
+    let mut replay = Replay::new(strokes_state.session_recording());
+    let mut replayed = StrokesState::default();
+    let second = &strokes_state.session_recording().events()[1];
+    let second_begin = match second.action {
+        RecordedAction::Insert { duration, .. } => second.t - duration,
+        _ => unreachable!(),
+    };
+
+    // Halfway through the second stroke
+    replay.seek(
+        second_begin + (replay.duration() - second_begin) / 2.0,
+        &mut replayed,
+    );
+    let partial = n_elements(&replayed);
+    assert_eq!(partial.len(), 2);
+    assert_eq!(partial[0], 10);
+    assert!(partial[1] > 0 && partial[1] < 10);
+    // The replay is not undone
+    assert!(!replayed.history().can_undo());
+
+    replay.step(&mut replayed);
+    assert!(replay.is_finished());
+    assert_eq!(n_elements(&replayed), vec![10, 10]);
+
+    // Seeking backwards starts over
+    replay.seek(0.0, &mut replayed);
+    assert!(n_elements(&replayed).is_empty());
+
+    // The speed scales the elapsed time
+    replay.set_speed(f64::MAX);
+    assert_eq!(replay.speed(), Replay::SPEED_MAX);
+    replay.advance(Duration::from_secs(1), &mut replayed);
+    assert!(replay.is_finished());
+    assert_eq!(n_elements(&replayed), vec![10, 10]);
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use nalgebra as na;
+use rnote_engine::drawbehaviour::DrawBehaviour;
+use rnote_engine::sheet::recovery::{Recovery, JOURNAL_FILE};
+use rnote_engine::sheet::Sheet;
+
+fn recovery_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rnote-recovery-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// The upper left corners of the strokes, in hundredths, and whether they are trashed. Sorted, so that sheets can be compared regardless of their keys
+fn strokes(sheet: &Sheet) -> Vec<(i64, i64, bool)> {
+    let mut strokes = sheet
+        .strokes_state
+        .keys_unordered()
+        .into_iter()
+        .map(|key| {
+            let mins = sheet
+                .strokes_state
+                .get_stroke_ref(key)
+                .unwrap()
+                .bounds()
+                .mins;
+            (
+                (mins[0] * 100.0).round() as i64,
+                (mins[1] * 100.0).round() as i64,
+                sheet.strokes_state.trashed(key).unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    strokes.sort_unstable();
+    strokes
+}
+
+fn restore(recovery: &Recovery) -> Sheet {
+    let mut sheet = Sheet::default();
+    Recovery::new(recovery.dir().to_path_buf())
+        .restore(&mut sheet)
+        .unwrap();
+    sheet
+}
+
+#[test]
+fn snapshot_and_journal_are_restored() {
+    let mut recovery = Recovery::new(recovery_dir("restore"));
+    let mut sheet = Sheet::default();
+    let moved = sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke(na::vector![10.0, 10.0], 3));
+    let removed = sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke(na::vector![10.0, 100.0], 3));
+    let trashed = sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke(na::vector![10.0, 200.0], 3));
+
+    recovery.write_snapshot(&mut sheet, None).unwrap();
+    assert!(recovery.snapshot().is_some());
+
+    sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke(na::vector![10.0, 300.0], 3));
+    recovery.flush_journal(&mut sheet, None).unwrap();
+
+    // Moving a stroke doesn't touch its chrono, but it is journaled through its revision
+    sheet
+        .strokes_state
+        .translate_strokes(&[moved], na::vector![50.0, 25.0], 1.0);
+    sheet.strokes_state.remove_stroke(removed);
+    sheet.strokes_state.set_trashed(trashed, true);
+    recovery.flush_journal(&mut sheet, None).unwrap();
+
+    let restored = restore(&recovery);
+    assert_eq!(restored.strokes_state.strokes_len(), 3);
+    assert_eq!(strokes(&restored), strokes(&sheet));
+
+    recovery.discard().unwrap();
+    assert!(recovery.detect().is_none());
+}
+
+#[test]
+fn removed_and_reinserted_strokes_are_replayed() {
+    let mut recovery = Recovery::new(recovery_dir("reinsert"));
+    let mut sheet = Sheet::default();
+    sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke(na::vector![10.0, 10.0], 3));
+    recovery.write_snapshot(&mut sheet, None).unwrap();
+
+    // The keys of strokes inserted after the snapshot can be different when replayed, so later records find them through their journaled keys
+    let first = sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke(na::vector![10.0, 100.0], 3));
+    recovery.flush_journal(&mut sheet, None).unwrap();
+    sheet
+        .strokes_state
+        .translate_strokes(&[first], na::vector![0.0, 50.0], 1.0);
+    recovery.flush_journal(&mut sheet, None).unwrap();
+    sheet.strokes_state.remove_stroke(first);
+    recovery.flush_journal(&mut sheet, None).unwrap();
+
+    // Reuses the slot of the removed stroke
+    let second = sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke(na::vector![10.0, 300.0], 3));
+    assert_ne!(first, second);
+    recovery.flush_journal(&mut sheet, None).unwrap();
+    sheet.strokes_state.set_trashed(second, true);
+    recovery.flush_journal(&mut sheet, None).unwrap();
+
+    let restored = restore(&recovery);
+    assert_eq!(restored.strokes_state.strokes_len(), 2);
+    assert_eq!(strokes(&restored), strokes(&sheet));
+}
+
+#[test]
+fn truncated_journal_line_is_skipped() {
+    let mut recovery = Recovery::new(recovery_dir("truncated"));
+    let mut sheet = Sheet::default();
+    recovery.write_snapshot(&mut sheet, None).unwrap();
+
+    sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke(na::vector![10.0, 10.0], 3));
+    recovery.flush_journal(&mut sheet, None).unwrap();
+
+    // A crash while appending cuts off the last line
+    let journal_path = recovery.dir().join(JOURNAL_FILE);
+    let journal = std::fs::read_to_string(&journal_path).unwrap();
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(journal_path)
+        .unwrap();
+    file.write_all(&journal.as_bytes()[..journal.len() / 2])
+        .unwrap();
+
+    let restored = restore(&recovery);
+    assert_eq!(strokes(&restored), strokes(&sheet));
+}
+
+#[test]
+fn journal_records_of_older_snapshots_are_ignored() {
+    let mut recovery = Recovery::new(recovery_dir("stale"));
+    let mut sheet = Sheet::default();
+    recovery.write_snapshot(&mut sheet, None).unwrap();
+    let stale_id = recovery.snapshot().unwrap().snapshot_id;
+
+    let key = sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke(na::vector![10.0, 10.0], 3));
+    recovery.flush_journal(&mut sheet, None).unwrap();
+    let journal_path = recovery.dir().join(JOURNAL_FILE);
+    let stale_journal = std::fs::read(&journal_path).unwrap();
+    assert!(!stale_journal.is_empty());
+
+    sheet.strokes_state.remove_stroke(key);
+    recovery.write_snapshot(&mut sheet, None).unwrap();
+    assert_ne!(recovery.snapshot().unwrap().snapshot_id, stale_id);
+
+    // The records of the previous snapshot were left in the journal, e.g. by a crash before it was truncated
+    std::fs::write(journal_path, stale_journal).unwrap();
+
+    let restored = restore(&recovery);
+    assert_eq!(restored.strokes_state.strokes_len(), 0);
+}
@@ -0,0 +1,31 @@
+use rnote_engine::strokesstate::renderjobs::RenderJobs;
+use rnote_engine::strokesstate::StrokeKey;
+use slotmap::SlotMap;
+
+#[test]
+fn new_jobs_cancel_the_outdated_ones() {
+    let mut keys = SlotMap::<StrokeKey, ()>::with_key();
+    let (first, second) = (keys.insert(()), keys.insert(()));
+    let mut render_jobs = RenderJobs::default();
+
+    let first_job = render_jobs.begin(first, 1.0);
+    let second_job = render_jobs.begin(second, 1.0);
+    assert_eq!(render_jobs.n_pending(), 2);
+
+    // Only the previous job of the same stroke is cancelled
+    let first_job_again = render_jobs.begin(first, 1.0);
+    assert!(first_job.cancelled());
+    assert!(!second_job.cancelled());
+
+    // Changing the zoom cancels the jobs of the previous zoom
+    let second_job_zoomed = render_jobs.begin(second, 2.0);
+    render_jobs.cancel_other_zooms(2.0);
+    assert!(first_job_again.cancelled());
+    assert!(!second_job_zoomed.cancelled());
+    assert_eq!(render_jobs.n_pending(), 1);
+
+    // Finished once nothing refers to the job anymore
+    drop(second_job_zoomed);
+    render_jobs.remove_finished();
+    assert_eq!(render_jobs.n_pending(), 0);
+}
@@ -0,0 +1,131 @@
+mod common;
+
+#[cfg(feature = "cairo")]
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use nalgebra as na;
+use rnote_engine::sheet::replayexport::ReplayExportPrefs;
+#[cfg(feature = "cairo")]
+use rnote_engine::sheet::replayexport::VideoEncoder;
+use rnote_engine::sheet::Sheet;
+
+/// A sheet with a recording of two strokes, drawn over some time
+fn recorded_sheet() -> Sheet {
+    let mut sheet = Sheet::default();
+    sheet.strokes_state.start_session_recording();
+
+    for y in [50.0, 100.0] {
+        sheet.strokes_state.begin_history_group();
+        sheet
+            .strokes_state
+            .insert_stroke(common::brushstroke(na::vector![50.0, y], 10));
+        std::thread::sleep(Duration::from_millis(30));
+        sheet.strokes_state.end_history_group();
+    }
+    sheet.strokes_state.stop_session_recording();
+
+    sheet
+}
+
+#[cfg(feature = "cairo")]
+#[derive(Debug, Default)]
+struct Frames {
+    size: Option<(u32, u32)>,
+    frames: Vec<image::RgbaImage>,
+    finished: bool,
+}
+
+/// Collects the frames instead of encoding them
+#[cfg(feature = "cairo")]
+struct CollectingEncoder(Arc<Mutex<Frames>>);
+
+#[cfg(feature = "cairo")]
+impl VideoEncoder for CollectingEncoder {
+    fn begin(&mut self, width: u32, height: u32, _fps: f64) -> Result<(), anyhow::Error> {
+        self.0.lock().unwrap().size = Some((width, height));
+        Ok(())
+    }
+
+    fn push_frame(&mut self, frame: &image::RgbaImage) -> Result<(), anyhow::Error> {
+        self.0.lock().unwrap().frames.push(frame.clone());
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), anyhow::Error> {
+        self.0.lock().unwrap().finished = true;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "cairo")]
+fn is_blank(frame: &image::RgbaImage) -> bool {
+    frame.pixels().all(|pixel| pixel.0 == [255, 255, 255, 255])
+}
+
+#[test]
+fn animated_svg_shows_the_strokes_progressively() {
+    let sheet = recorded_sheet();
+    let prefs = ReplayExportPrefs {
+        with_background: false,
+        svg_steps: 4,
+        ..ReplayExportPrefs::default()
+    };
+
+    let svg = sheet.export_replay_as_animated_svg_string(&prefs).unwrap();
+    // Four partial versions and the finished stroke for each stroke
+    assert_eq!(svg.matches("<set ").count(), 10);
+    assert_eq!(svg.matches(" dur=\"").count(), 8);
+
+    // A faster animation begins the strokes earlier
+    let faster = sheet
+        .export_replay_as_animated_svg_string(&ReplayExportPrefs {
+            speed: 2.0,
+            ..prefs
+        })
+        .unwrap();
+    assert_ne!(faster, svg);
+
+    // Nothing to export without a recording
+    assert!(Sheet::default()
+        .export_replay_as_animated_svg_string(&ReplayExportPrefs::default())
+        .is_err());
+}
+
+#[test]
+#[cfg(feature = "cairo")]
+fn video_frames_show_the_drawing() {
+    let sheet = recorded_sheet();
+    let prefs = ReplayExportPrefs {
+        fps: 60.0,
+        hold_end: 0.5,
+        with_background: false,
+        ..ReplayExportPrefs::default()
+    };
+    let duration = sheet.strokes_state.session_recording().duration();
+
+    let frames = Arc::new(Mutex::new(Frames::default()));
+    let encoder = CollectingEncoder(Arc::clone(&frames));
+    futures::executor::block_on(
+        sheet
+            .export_replay_as_video(prefs, Box::new(encoder))
+            .unwrap(),
+    )
+    .unwrap()
+    .unwrap();
+
+    let frames = frames.lock().unwrap();
+    assert!(frames.finished);
+    assert_eq!(
+        frames.frames.len(),
+        ((duration + 0.5) * 60.0).ceil() as usize + 1
+    );
+    let (width, height) = frames.size.unwrap();
+    assert!(frames
+        .frames
+        .iter()
+        .all(|frame| frame.width() == width && frame.height() == height));
+
+    assert!(is_blank(&frames.frames[0]));
+    assert!(!is_blank(frames.frames.last().unwrap()));
+}
@@ -0,0 +1,155 @@
+#![cfg(feature = "scripting")]
+
+mod common;
+
+use nalgebra as na;
+use rnote_engine::compose::color::Color;
+use rnote_engine::drawbehaviour::DrawBehaviour;
+use rnote_engine::scripting::ScriptOptions;
+use rnote_engine::sheet::Sheet;
+
+#[test]
+fn inserts_strokes_on_every_page() {
+    let mut sheet = Sheet::default();
+    sheet.height = sheet.format.height * 3.0;
+
+    let output = sheet
+        .run_script(
+            r#"
+                let number = 1;
+                for page in pages() {
+                    add_text(`${number}`, page.x + 20, page.y + 20);
+                    add_line(page.x, page.y + page.height - 40, page.x + page.width, page.y + page.height - 40);
+                    number += 1;
+                }
+                print(`numbered ${number - 1} pages`);
+            "#,
+            ScriptOptions::default(),
+        )
+        .unwrap();
+
+    assert_eq!(output.changed_keys.len(), 6);
+    assert_eq!(output.log, vec![String::from("numbered 3 pages")]);
+    assert_eq!(sheet.statistics().stroke_counts.textstrokes, 3);
+    assert_eq!(sheet.statistics().stroke_counts.shapestrokes, 3);
+}
+
+#[test]
+fn modifies_existing_strokes() {
+    let mut sheet = Sheet::default();
+    let key = sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke_through(&[
+            na::vector![10.0, 10.0],
+            na::vector![50.0, 10.0],
+        ]));
+    let bounds = sheet.strokes_state.get_stroke_ref(key).unwrap().bounds();
+
+    let output = sheet
+        .run_script(
+            r#"
+                for stroke in strokes() {
+                    if stroke.kind == "brushstroke" {
+                        stroke.recolor(rgb(1, 0, 0));
+                        stroke.translate(100, 0);
+                    }
+                }
+            "#,
+            ScriptOptions::default(),
+        )
+        .unwrap();
+
+    let stroke = sheet.strokes_state.get_stroke_ref(key).unwrap();
+    assert_eq!(output.changed_keys, vec![key]);
+    assert!(stroke.uses_color(Color::RED));
+    assert!((stroke.bounds().mins[0] - (bounds.mins[0] + 100.0)).abs() < 1e-6);
+
+    // The changes of the script are undone at once
+    sheet.strokes_state.undo();
+    let stroke = sheet.strokes_state.get_stroke_ref(key).unwrap();
+    assert!(!stroke.uses_color(Color::RED));
+    assert!((stroke.bounds().mins[0] - bounds.mins[0]).abs() < 1e-6);
+}
+
+#[test]
+fn failing_scripts_leave_the_sheet_unchanged() {
+    let mut sheet = Sheet::default();
+    sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke_through(&[
+            na::vector![10.0, 10.0],
+            na::vector![50.0, 10.0],
+        ]));
+
+    assert!(sheet
+        .run_script(
+            r#"
+                for stroke in strokes() {
+                    stroke.trash();
+                }
+                throw "stop";
+            "#,
+            ScriptOptions::default(),
+        )
+        .is_err());
+    assert_eq!(sheet.statistics().stroke_counts.brushstrokes, 1);
+
+    // Endless loops are stopped
+    let options = ScriptOptions {
+        max_operations: ScriptOptions::MAX_OPERATIONS_MIN,
+        ..ScriptOptions::default()
+    };
+    assert!(sheet
+        .run_script("add_rectangle(0, 0, 10, 10); loop { }", options)
+        .is_err());
+    assert_eq!(sheet.statistics().stroke_counts.shapestrokes, 0);
+}
+
+#[test]
+fn non_finite_numbers_are_rejected() {
+    let mut sheet = Sheet::default();
+    let key = sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke_through(&[
+            na::vector![10.0, 10.0],
+            na::vector![50.0, 10.0],
+        ]));
+    let bounds = sheet.strokes_state.get_stroke_ref(key).unwrap().bounds();
+
+    for script in [
+        r#"add_line(0, 0, parse_float("NaN"), 10);"#,
+        r#"add_rectangle(0, 0, parse_float("inf"), 10);"#,
+        r#"add_ellipse(parse_float("-inf"), 0, 10, 10);"#,
+        r#"add_text("text", 0, parse_float("NaN"));"#,
+        r#"for stroke in strokes() { stroke.translate(parse_float("NaN"), 0); }"#,
+    ] {
+        // The rectangle inserted before is discarded as well
+        assert!(sheet
+            .run_script(
+                &format!("add_rectangle(0, 0, 10, 10); {}", script),
+                ScriptOptions::default()
+            )
+            .is_err());
+    }
+
+    assert_eq!(sheet.statistics().stroke_counts.shapestrokes, 0);
+    assert_eq!(sheet.statistics().stroke_counts.textstrokes, 0);
+    let stroke_bounds = sheet.strokes_state.get_stroke_ref(key).unwrap().bounds();
+    assert_eq!(stroke_bounds.mins, bounds.mins);
+    assert_eq!(stroke_bounds.maxs, bounds.maxs);
+}
+
+#[test]
+fn scripts_cant_import_modules() {
+    let mut sheet = Sheet::default();
+
+    assert!(sheet
+        .run_script(
+            r#"import "/etc/passwd" as passwd;"#,
+            ScriptOptions::default()
+        )
+        .is_err());
+    assert!(sheet
+        .run_script(r#"eval("print(1)");"#, ScriptOptions::default())
+        .is_err());
+}
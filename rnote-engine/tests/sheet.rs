@@ -0,0 +1,152 @@
+mod common;
+
+use nalgebra as na;
+use parry2d_f64::bounding_volume::AABB;
+use rnote_engine::sheet::format::Orientation;
+use rnote_engine::sheet::pagelayout::{Page, PageLayout};
+use rnote_engine::sheet::Sheet;
+
+fn is_aligned(sheet: &Sheet) -> bool {
+    let chunk_size = sheet.infinite_chunk_size();
+    let bounds = sheet.bounds();
+
+    [bounds.mins, bounds.maxs].iter().all(|vertex| {
+        (vertex[0] / chunk_size[0]).fract() == 0.0 && (vertex[1] / chunk_size[1]).fract() == 0.0
+    })
+}
+
+#[test]
+fn expands_into_negative_coordinates() {
+    let mut sheet = Sheet::default();
+    sheet.resize_sheet_mode_infinite_to_fit_strokes();
+    let chunk_size = sheet.infinite_chunk_size();
+    assert!(is_aligned(&sheet));
+
+    let viewport = AABB::new(
+        na::point![-chunk_size[0] * 10.5, -chunk_size[1] * 7.2],
+        na::point![-chunk_size[0] * 9.8, -chunk_size[1] * 6.6],
+    );
+    assert!(sheet.expand_sheet_mode_infinite_for_viewport(viewport));
+    assert!(is_aligned(&sheet));
+    assert_eq!(
+        sheet.bounds().mins,
+        na::point![
+            -chunk_size[0] * (11.0 + Sheet::INFINITE_PADDING_CHUNKS),
+            -chunk_size[1] * (8.0 + Sheet::INFINITE_PADDING_CHUNKS)
+        ]
+    );
+
+    // Moving the viewport within the chunk does not grow the sheet
+    let viewport = AABB::new(
+        na::point![-chunk_size[0] * 10.4, -chunk_size[1] * 7.1],
+        na::point![-chunk_size[0] * 9.9, -chunk_size[1] * 6.7],
+    );
+    assert!(!sheet.expand_sheet_mode_infinite_for_viewport(viewport));
+}
+
+#[test]
+fn shrinks_by_whole_chunks() {
+    let mut sheet = Sheet::default();
+    let chunk_size = sheet.infinite_chunk_size();
+
+    let viewport = AABB::new(
+        na::point![chunk_size[0] * 20.3, chunk_size[1] * -4.5],
+        na::point![chunk_size[0] * 21.1, chunk_size[1] * -3.9],
+    );
+    sheet.expand_sheet_mode_infinite_for_viewport(viewport);
+
+    // Without strokes the sheet shrinks to the chunks around the origin
+    sheet.resize_sheet_mode_infinite_to_fit_strokes();
+    assert!(is_aligned(&sheet));
+    assert_eq!(
+        sheet.infinite_chunks().len(),
+        ((1.0 + 2.0 * Sheet::INFINITE_PADDING_CHUNKS).powi(2)) as usize
+    );
+    assert!(sheet.infinite_chunks().contains(&AABB::new(
+        na::point![0.0, 0.0],
+        na::Point2::from(chunk_size)
+    )));
+}
+
+#[test]
+fn pages_are_placed_below_each_other() {
+    let mut page_layout = PageLayout::new(Page::new(100.0, 200.0));
+    page_layout.insert_page(
+        1,
+        Page::new(100.0, 200.0).with_orientation(Orientation::Landscape),
+    );
+    page_layout.insert_page(0, Page::new(50.0, 50.0));
+
+    let pages_bounds = page_layout.pages_bounds();
+    assert_eq!(pages_bounds.len(), 3);
+    assert_eq!(
+        pages_bounds[1].mins,
+        na::point![0.0, 50.0 + PageLayout::PAGE_GAP]
+    );
+    assert_eq!(pages_bounds[2].extents(), na::vector![200.0, 100.0]);
+    assert_eq!(
+        page_layout.bounds().unwrap().maxs,
+        na::point![200.0, 350.0 + 2.0 * PageLayout::PAGE_GAP]
+    );
+
+    // Positions in the gap belong to the page above
+    assert_eq!(page_layout.page_index_at(na::vector![0.0, -10.0]), Some(0));
+    assert_eq!(page_layout.page_index_at(na::vector![0.0, 60.0]), Some(0));
+    assert_eq!(page_layout.page_index_at(na::vector![0.0, 100.0]), Some(1));
+    assert_eq!(page_layout.page_index_at(na::vector![0.0, 1000.0]), Some(2));
+
+    assert!(page_layout.move_page(2, 0));
+    assert_eq!(
+        page_layout.get(0).unwrap().orientation(),
+        Orientation::Landscape
+    );
+    assert!(!page_layout.move_page(0, 3));
+}
+
+#[test]
+fn removed_page_takes_its_strokes() {
+    let mut sheet = Sheet::default();
+    sheet.enable_page_layout();
+    let page = sheet.page_layout.as_ref().unwrap().get(0).unwrap();
+    assert!(sheet.insert_page(1, page, 1.0));
+    assert_eq!(sheet.calc_n_pages(), 2);
+    assert_eq!(
+        sheet.bounds(),
+        sheet.page_layout.as_ref().unwrap().bounds().unwrap()
+    );
+
+    let key = sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke(na::vector![10.0, 10.0], 1));
+    sheet.strokes_state.commit_history();
+
+    assert!(sheet.remove_page(0, 1.0).is_some());
+    assert_eq!(sheet.strokes_state.trashed(key), Some(true));
+    assert_eq!(sheet.calc_n_pages(), 1);
+
+    // The last page is kept
+    assert!(sheet.remove_page(0, 1.0).is_none());
+}
+
+#[test]
+fn pages_are_clipped_to_the_sheet() {
+    let mut sheet = Sheet::default();
+    sheet.height = sheet.format.height * 2.6;
+
+    let pages = sheet.pages();
+    assert_eq!(pages.len(), sheet.calc_n_pages() as usize);
+    assert_eq!(pages.len(), 3);
+    assert_eq!(pages[1].mins, na::point![0.0, sheet.format.height]);
+    assert_eq!(pages[2].maxs, sheet.bounds().maxs);
+
+    // The rows and columns of the infinite mode, in negative coordinates as well
+    sheet.x = -sheet.format.width;
+    sheet.y = -sheet.format.height;
+    sheet.width = sheet.format.width * 2.0;
+    sheet.height = sheet.format.height * 2.0;
+    let pages = sheet.pages();
+    assert_eq!(pages.len(), sheet.calc_n_pages() as usize);
+    assert_eq!(pages.len(), 4);
+    assert_eq!(pages[0].mins, sheet.bounds().mins);
+    assert_eq!(pages[3].maxs, sheet.bounds().maxs);
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use nalgebra as na;
+use rnote_engine::pens::simplification::{self, SimplificationOptions};
+use rnote_engine::strokes::element::Element;
+use rnote_engine::strokes::inputdata::InputData;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+use rnote_engine::strokesstate::StrokesState;
+
+fn elements(points: impl Iterator<Item = (na::Vector2<f64>, f64)>) -> Vec<Element> {
+    points
+        .map(|(pos, pressure)| Element::new(InputData::new(pos, pressure)))
+        .collect()
+}
+
+#[test]
+fn straight_lines_are_reduced_to_their_ends() {
+    let options = SimplificationOptions::default();
+    let line = elements((0..100).map(|i| (na::vector![f64::from(i), 2.0 * f64::from(i)], 0.5)));
+
+    let simplified = simplification::simplify_elements(&line, &options);
+    assert_eq!(simplified.len(), 2);
+    assert_eq!(simplified[0].inputdata.pos(), line[0].inputdata.pos());
+    assert_eq!(simplified[1].inputdata.pos(), line[99].inputdata.pos());
+
+    // A linear change of the pressure is interpolated as well
+    let tapered =
+        elements((0..100).map(|i| (na::vector![f64::from(i), 0.0], 0.2 + f64::from(i) * 0.005)));
+    assert_eq!(
+        simplification::simplify_elements(&tapered, &options).len(),
+        2
+    );
+}
+
+#[test]
+fn corners_and_pressure_changes_are_kept() {
+    let options = SimplificationOptions::default();
+
+    // A corner at the 50th element
+    let corner = elements((0..100).map(|i| {
+        let i = f64::from(i);
+        (na::vector![i.min(50.0), (i - 50.0).max(0.0)], 0.5)
+    }));
+    let simplified = simplification::simplify_elements(&corner, &options);
+    assert_eq!(simplified.len(), 3);
+    assert_eq!(simplified[1].inputdata.pos(), na::vector![50.0, 0.0]);
+
+    // A pressure peak on a straight line
+    let peak = elements((0..100).map(|i| {
+        let pressure = if i == 30 { 0.9 } else { 0.5 };
+        (na::vector![f64::from(i), 0.0], pressure)
+    }));
+    let simplified = simplification::simplify_elements(&peak, &options);
+    assert!(simplified
+        .iter()
+        .any(|element| (element.inputdata.pressure() - 0.9).abs() < 1e-9));
+
+    // Nothing is removed with a tolerance of zero from a wavy line
+    let wavy = elements((0..100).map(|i| (na::vector![f64::from(i), f64::from(i % 2)], 0.5)));
+    let exact = SimplificationOptions {
+        tolerance: 0.0,
+        ..SimplificationOptions::default()
+    };
+    assert_eq!(simplification::simplify_elements(&wavy, &exact).len(), 100);
+}
+
+#[test]
+fn simplified_strokes_can_be_undone() {
+    let mut strokes_state = StrokesState::default();
+    let line = elements((0..50).map(|i| (na::vector![f64::from(i), 0.0], 0.5)));
+    let key = strokes_state.insert_stroke(common::brushstroke_w_elements(line));
+    strokes_state.commit_history();
+
+    let n_elements = |strokes_state: &StrokesState| match strokes_state.get_stroke_ref(key) {
+        Some(StrokeStyle::BrushStroke(brushstroke)) => brushstroke.elements.len(),
+        _ => 0,
+    };
+
+    // Disabled, the stroke keeps every element
+    let disabled = SimplificationOptions {
+        enabled: false,
+        ..SimplificationOptions::default()
+    };
+    strokes_state.simplify_brushstroke(key, &disabled);
+    assert_eq!(n_elements(&strokes_state), 50);
+
+    strokes_state.simplify_brushstroke(key, &SimplificationOptions::default());
+    strokes_state.commit_history();
+    assert_eq!(n_elements(&strokes_state), 2);
+
+    assert!(strokes_state.undo());
+    assert_eq!(n_elements(&strokes_state), 50);
+}
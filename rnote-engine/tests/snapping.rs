@@ -0,0 +1,87 @@
+mod common;
+
+use nalgebra as na;
+use parry2d_f64::bounding_volume::AABB;
+use rnote_engine::pens::snapping::{SnapTarget, Snapping};
+use rnote_engine::sheet::Sheet;
+
+fn assert_near(first: na::Vector2<f64>, second: na::Vector2<f64>) {
+    assert!(
+        (first - second).magnitude() < 1e-9,
+        "{} != {}",
+        first,
+        second
+    );
+}
+
+#[test]
+fn snaps_to_grid_and_strokes() {
+    let mut sheet = Sheet::default();
+    sheet.background.pattern_size = na::vector![20.0, 20.0];
+    let mut snapping = Snapping {
+        snap_to_grid: true,
+        ..Snapping::default()
+    };
+
+    assert_near(
+        snapping.snap_pos(na::vector![38.0, 43.0], None, &sheet, &[], 1.0),
+        na::vector![40.0, 40.0],
+    );
+    assert_eq!(snapping.indicator.unwrap().target, SnapTarget::Grid);
+
+    // Too far away from the nearest grid point
+    assert_near(
+        snapping.snap_pos(na::vector![30.0, 30.0], None, &sheet, &[], 1.0),
+        na::vector![30.0, 30.0],
+    );
+    assert_eq!(snapping.indicator, None);
+
+    let key = sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke(na::vector![44.0, 41.0], 1));
+    snapping.snap_to_strokes = true;
+
+    // Stroke points take precedence over the grid
+    assert_near(
+        snapping.snap_pos(na::vector![42.0, 42.0], None, &sheet, &[], 1.0),
+        na::vector![44.0, 41.0],
+    );
+    assert_eq!(snapping.indicator.unwrap().target, SnapTarget::Stroke);
+    assert_near(
+        snapping.snap_pos(na::vector![42.0, 42.0], None, &sheet, &[key], 1.0),
+        na::vector![40.0, 40.0],
+    );
+}
+
+#[test]
+fn snaps_angles_from_the_anchor() {
+    let sheet = Sheet::default();
+    let mut snapping = Snapping {
+        angle_snap: true,
+        ..Snapping::default()
+    };
+    let anchor = na::vector![10.0, 10.0];
+
+    let snapped = snapping.snap_pos(na::vector![20.0, 11.0], Some(anchor), &sheet, &[], 1.0);
+    assert!((snapped[1] - anchor[1]).abs() < 1e-9);
+    assert!(((snapped - anchor).magnitude() - 101.0_f64.sqrt()).abs() < 1e-9);
+
+    let snapped = snapping.snap_pos(na::vector![20.0, 19.0], Some(anchor), &sheet, &[], 1.0);
+    assert!(((snapped - anchor).angle(&na::Vector2::x()).to_degrees() - 45.0).abs() < 1e-9);
+}
+
+#[test]
+fn snaps_moved_bounds() {
+    let mut sheet = Sheet::default();
+    sheet.background.pattern_size = na::vector![20.0, 20.0];
+    let mut snapping = Snapping {
+        snap_to_grid: true,
+        ..Snapping::default()
+    };
+
+    let bounds = AABB::new(na::point![1.0, 1.0], na::point![11.0, 11.0]);
+    let offset = snapping.snap_offset(bounds, na::vector![17.0, 17.0], &sheet, &[], 1.0);
+
+    // The corner nearest to a grid point, the top left one, snaps onto it
+    assert_near(offset, na::vector![19.0, 19.0]);
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use nalgebra as na;
+use parry2d_f64::bounding_volume::AABB;
+use rnote_engine::strokesstate::spatialindex::SpatialIndex;
+use rnote_engine::strokesstate::StrokesState;
+
+fn bounds_around(pos: na::Vector2<f64>) -> AABB {
+    AABB::from_half_extents(na::Point2::from(pos), na::Vector2::from_element(20.0))
+}
+
+#[test]
+fn queries_find_the_strokes_in_the_bounds() {
+    let mut strokes_state = StrokesState::default();
+    let near = strokes_state.insert_stroke(common::brushstroke(na::vector![10.0, 10.0], 1));
+    let far = strokes_state.insert_stroke(common::brushstroke(na::vector![5000.0, 5000.0], 1));
+
+    assert_eq!(
+        strokes_state.keys_intersecting_bounds(bounds_around(na::vector![10.0, 10.0])),
+        vec![near]
+    );
+    assert_eq!(
+        strokes_state.keys_intersecting_bounds(bounds_around(na::vector![5000.0, 5000.0])),
+        vec![far]
+    );
+    // Bounds covering many cells, which are checked against all strokes instead
+    assert_eq!(
+        strokes_state.keys_intersecting_bounds(AABB::new(
+            na::point![-100_000.0, -100_000.0],
+            na::point![100_000.0, 100_000.0]
+        )),
+        vec![near, far]
+    );
+}
+
+#[test]
+fn changed_strokes_are_indexed_again() {
+    let mut strokes_state = StrokesState::default();
+    let key = strokes_state.insert_stroke(common::brushstroke(na::vector![10.0, 10.0], 1));
+    assert_eq!(
+        strokes_state.keys_intersecting_bounds(bounds_around(na::vector![10.0, 10.0])),
+        vec![key]
+    );
+
+    strokes_state.translate_strokes(&[key], na::vector![1000.0, 0.0], 1.0);
+    assert!(strokes_state
+        .keys_intersecting_bounds(bounds_around(na::vector![10.0, 10.0]))
+        .is_empty());
+    assert_eq!(
+        strokes_state.keys_intersecting_bounds(bounds_around(na::vector![1010.0, 10.0])),
+        vec![key]
+    );
+
+    strokes_state.remove_stroke(key);
+    assert!(strokes_state
+        .keys_intersecting_bounds(bounds_around(na::vector![1010.0, 10.0]))
+        .is_empty());
+}
+
+#[test]
+fn an_empty_index_finds_nothing() {
+    let spatial_index = SpatialIndex::default();
+
+    assert!(spatial_index.is_empty());
+    assert!(spatial_index
+        .query(bounds_around(na::vector![0.0, 0.0]))
+        .is_empty());
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use nalgebra as na;
+use rnote_engine::compose::arrowhead::ArrowheadOptions;
+use rnote_engine::compose::curves;
+use rnote_engine::compose::smooth::SmoothOptions;
+use rnote_engine::sheet::statistics::AssetKind;
+use rnote_engine::sheet::Sheet;
+use rnote_engine::strokes::bitmapimage::BitmapImage;
+use rnote_engine::strokes::shapestroke::{Shape, ShapeStroke};
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+use rnote_engine::strokes::vectorimage::VectorImage;
+
+/// A sheet with two pages below each other
+fn sheet_w_two_pages() -> Sheet {
+    let mut sheet = Sheet::default();
+    sheet.height = sheet.format.height * 2.0;
+
+    sheet
+}
+
+#[test]
+fn counts_strokes_and_ink_length() {
+    let mut sheet = sheet_w_two_pages();
+    sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke_through(&[
+            na::vector![10.0, 10.0],
+            na::vector![40.0, 10.0],
+            na::vector![40.0, 50.0],
+        ]));
+    sheet
+        .strokes_state
+        .insert_stroke(StrokeStyle::ShapeStroke(ShapeStroke::new_w_shape(
+            Shape::Line(curves::Line {
+                start: na::vector![10.0, 100.0],
+                end: na::vector![110.0, 100.0],
+            }),
+            SmoothOptions::default(),
+            ArrowheadOptions::default(),
+        )));
+    let trashed = sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke_through(&[
+            na::vector![0.0, 0.0],
+            na::vector![1000.0, 0.0],
+        ]));
+    sheet.strokes_state.set_trashed(trashed, true);
+
+    let statistics = sheet.statistics();
+    assert_eq!(statistics.stroke_counts.brushstrokes, 1);
+    assert_eq!(statistics.stroke_counts.shapestrokes, 1);
+    assert_eq!(statistics.stroke_counts.total(), 2);
+    assert_eq!(statistics.n_trashed_strokes, 1);
+    assert_eq!(statistics.n_elements, 3);
+    assert!((statistics.ink_length - 170.0).abs() < 1e-9);
+}
+
+#[test]
+fn page_content_bounds() {
+    let mut sheet = sheet_w_two_pages();
+    let page_height = sheet.format.height;
+    // Crosses from the first into the second page. The bounds of brush strokes span the curves between the elements, so it needs more than two
+    sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke_through(
+            &(-2..=2)
+                .map(|i| na::vector![100.0, page_height + f64::from(i) * 50.0])
+                .collect::<Vec<na::Vector2<f64>>>(),
+        ));
+
+    let statistics = sheet.statistics();
+    assert_eq!(statistics.pages.len(), 2);
+    assert_eq!(statistics.n_pages_w_content(), 2);
+
+    for page in statistics.pages.iter() {
+        assert_eq!(page.n_strokes, 1);
+
+        let content_bounds = page.content_bounds.unwrap();
+        assert!(content_bounds.mins[1] >= page.bounds.mins[1]);
+        assert!(content_bounds.maxs[1] <= page.bounds.maxs[1]);
+    }
+
+    sheet.strokes_state.clear();
+    let statistics = sheet.statistics();
+    assert_eq!(statistics.n_pages_w_content(), 0);
+    assert!(statistics.pages.iter().all(|page| page.n_strokes == 0));
+}
+
+#[test]
+fn asset_sizes() {
+    let mut sheet = sheet_w_two_pages();
+    let bitmapimage = BitmapImage {
+        // 7 bytes
+        data_base64: String::from("AAECAwQFBg=="),
+        ..BitmapImage::default()
+    };
+    sheet
+        .strokes_state
+        .insert_stroke(StrokeStyle::BitmapImage(bitmapimage.clone()));
+    sheet
+        .strokes_state
+        .insert_stroke(StrokeStyle::BitmapImage(bitmapimage));
+    sheet
+        .strokes_state
+        .insert_stroke(StrokeStyle::VectorImage(VectorImage {
+            svg_data: String::from("<svg xmlns=\"http://www.w3.org/2000/svg\"/>"),
+            ..VectorImage::default()
+        }));
+
+    let statistics = sheet.statistics();
+    assert_eq!(statistics.assets.len(), 3);
+    assert_eq!(statistics.assets[0].kind, AssetKind::VectorImage);
+    assert_eq!(statistics.assets[0].size, 41);
+
+    let bitmaps = &statistics.assets[1..];
+    assert!(bitmaps
+        .iter()
+        .all(|asset| asset.kind == AssetKind::BitmapImage && asset.size == 7));
+    assert_eq!(bitmaps.iter().filter(|asset| asset.duplicate).count(), 1);
+    // The duplicate is stored only once
+    assert_eq!(statistics.assets_size(), 48);
+}
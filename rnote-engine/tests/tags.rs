@@ -0,0 +1,99 @@
+use nalgebra as na;
+use rnote_engine::sheet::Sheet;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+use rnote_engine::strokes::textstroke::{TextStroke, TextStyle};
+use rnote_engine::strokesstate::tag_comp::{TagFilter, TagFilterMode};
+use rnote_engine::strokesstate::{StrokeKey, StrokesState};
+
+fn insert_text(strokes_state: &mut StrokesState, y: f64) -> StrokeKey {
+    strokes_state.insert_stroke(StrokeStyle::TextStroke(TextStroke::new(
+        String::from("Text"),
+        na::vector![50.0, y],
+        TextStroke::MAX_WIDTH_DEFAULT,
+        TextStyle::default(),
+    )))
+}
+
+#[test]
+fn tags_are_normalized() {
+    let mut strokes_state = StrokesState::default();
+    let first = insert_text(&mut strokes_state, 100.0);
+    let second = insert_text(&mut strokes_state, 200.0);
+
+    strokes_state.set_tags(first, [" Todo", "#important", "todo", "#", "  "]);
+    assert_eq!(strokes_state.tags(first), ["important", "todo"]);
+    assert!(strokes_state.tags(second).is_empty());
+
+    strokes_state.set_selected_keys(&[first, second], true);
+    strokes_state.add_tag_to_selection("#Todo");
+    assert_eq!(strokes_state.tags(second), ["todo"]);
+    assert_eq!(strokes_state.selection_tags(), ["todo"]);
+
+    strokes_state.remove_tag_from_selection("TODO");
+    assert_eq!(strokes_state.tags(first), ["important"]);
+    assert!(strokes_state.tags(second).is_empty());
+    assert!(strokes_state.selection_tags().is_empty());
+
+    // Duplicates keep the tags
+    let duplicates = strokes_state.duplicate_selection(na::vector![10.0, 10.0]);
+    assert_eq!(duplicates.len(), 2);
+    assert_eq!(strokes_state.keys_with_tag("important").len(), 2);
+
+    strokes_state.set_trashed(first, true);
+    assert_eq!(strokes_state.keys_with_tag("#Important").len(), 1);
+}
+
+#[test]
+fn tags_are_saved_with_the_sheet() {
+    let mut sheet = Sheet::default();
+    let first = insert_text(&mut sheet.strokes_state, 100.0);
+    let second = insert_text(&mut sheet.strokes_state, 200.0);
+    sheet.strokes_state.set_tags(first, ["todo", "math"]);
+    sheet.strokes_state.add_tag(second, "Physics");
+    sheet
+        .strokes_state
+        .set_tag_filter(TagFilter::new(["todo"], TagFilterMode::Hide));
+
+    let bytes = sheet.save_sheet_as_rnote_bytes().unwrap();
+    let mut reopened = Sheet::default();
+    reopened
+        .open_sheet_from_rnote_bytes(bytes)
+        .unwrap();
+
+    assert_eq!(
+        reopened.strokes_state.all_tags(),
+        vec!["math", "physics", "todo"]
+    );
+    assert_eq!(reopened.strokes_state.keys_with_tag("todo").len(), 1);
+    // The filter is part of the view
+    assert!(reopened.strokes_state.tag_filter().is_none());
+}
+
+#[test]
+fn strokes_not_matching_the_filter_are_dimmed_or_hidden() {
+    let mut strokes_state = StrokesState::default();
+    let tagged = insert_text(&mut strokes_state, 100.0);
+    let untagged = insert_text(&mut strokes_state, 200.0);
+    strokes_state.set_tags(tagged, ["todo", "math"]);
+
+    assert!(TagFilter::new(["", "#"], TagFilterMode::Dim).is_none());
+    assert_eq!(strokes_state.tag_filter_opacity(untagged), Some(1.0));
+
+    strokes_state.set_tag_filter(TagFilter::new(["#Todo", "physics"], TagFilterMode::Dim));
+    assert_eq!(
+        strokes_state.tag_filter().unwrap().tags,
+        vec!["physics", "todo"]
+    );
+    assert_eq!(strokes_state.tag_filter_opacity(tagged), Some(1.0));
+    assert_eq!(
+        strokes_state.tag_filter_opacity(untagged),
+        Some(TagFilter::DIMMED_OPACITY)
+    );
+
+    strokes_state.set_tag_filter(TagFilter::new(["physics"], TagFilterMode::Hide));
+    assert_eq!(strokes_state.tag_filter_opacity(tagged), None);
+    assert_eq!(strokes_state.tag_filter_opacity(untagged), None);
+
+    strokes_state.set_tag_filter(None);
+    assert_eq!(strokes_state.tag_filter_opacity(untagged), Some(1.0));
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use std::path::PathBuf;
+
+use nalgebra as na;
+use rnote_engine::sheet::background::PatternStyle;
+use rnote_engine::sheet::template::Templates;
+use rnote_engine::sheet::Sheet;
+
+fn templates_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rnote-templates-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn new_sheet_from_template() {
+    let templates = Templates::new(templates_dir("instantiate"));
+    assert!(templates.list().unwrap().is_empty());
+
+    let mut sheet = Sheet::default();
+    sheet.background.pattern = PatternStyle::Grid;
+    sheet.format.width = 500.0;
+    sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke(na::vector![10.0, 10.0], 1));
+    let trashed_key = sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke(na::vector![20.0, 20.0], 1));
+    sheet.strokes_state.set_trashed(trashed_key, true);
+
+    templates.save("Cornell notes", &sheet).unwrap();
+    templates.save("Planner", &Sheet::default()).unwrap();
+
+    let names = templates
+        .list()
+        .unwrap()
+        .into_iter()
+        .map(|template| template.name)
+        .collect::<Vec<String>>();
+    assert_eq!(names, vec!["Cornell notes", "Planner"]);
+
+    let mut new_sheet = Sheet::default();
+    new_sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke(na::vector![30.0, 30.0], 1));
+    templates
+        .instantiate("Cornell notes", &mut new_sheet)
+        .unwrap();
+
+    // Trashed strokes are not part of the template
+    assert_eq!(new_sheet.strokes_state.strokes_len(), 1);
+    assert_eq!(new_sheet.background.pattern, PatternStyle::Grid);
+    assert_eq!(new_sheet.format.width, 500.0);
+
+    templates.remove("Planner").unwrap();
+    assert_eq!(templates.list().unwrap().len(), 1);
+
+    let _ = std::fs::remove_dir_all(templates.dir());
+}
+
+#[test]
+fn invalid_template_names() {
+    let templates = Templates::new(templates_dir("names"));
+
+    for name in ["", "  ", ".hidden", "a/b"] {
+        assert!(templates.template_path(name).is_err(), "{:?}", name);
+    }
+    assert!(templates
+        .template_path(" Weekly planner ")
+        .unwrap()
+        .ends_with("Weekly planner.rnote"));
+}
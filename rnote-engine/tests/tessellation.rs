@@ -0,0 +1,107 @@
+mod common;
+
+use nalgebra as na;
+use rnote_engine::compose::color::Color;
+use rnote_engine::compose::tessellation::Primitive;
+use rnote_engine::compose::textured::TexturedDotsShape;
+use rnote_engine::drawbehaviour::DrawBehaviour;
+use rnote_engine::pens::brush::{Brush, BrushStyle};
+use rnote_engine::render::{DetailLevel, TessellatedStrokes};
+use rnote_engine::strokes::brushstroke::BrushStroke;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+
+fn brushstroke(brush: &Brush) -> BrushStroke {
+    let positions = (0..10)
+        .map(|i| na::vector![100.0 + 10.0 * f64::from(i), 100.0 + 3.0 * f64::from(i)])
+        .collect::<Vec<na::Vector2<f64>>>();
+
+    common::brushstroke_w_brush(common::elements_through(&positions), brush)
+}
+
+#[test]
+fn textured_strokes_are_tessellated_into_their_dots() {
+    let mut brush = Brush::default();
+    brush.style = BrushStyle::Textured;
+    brush.textured_options.width = 8.0;
+    let stroke = brushstroke(&brush);
+
+    let tessellation = stroke.gen_tessellation().unwrap();
+    assert!(!tessellation.primitives.is_empty());
+    assert!(tessellation
+        .primitives
+        .iter()
+        .all(|(primitive, _)| matches!(primitive, Primitive::Ellipse { .. })));
+    // Dots only spill over the stroke by their radii
+    let bounds = tessellation.bounds().unwrap();
+    let stroke_bounds = stroke.bounds();
+    assert!(bounds.mins[0] > stroke_bounds.mins[0] - 5.0);
+    assert!(bounds.maxs[0] < stroke_bounds.maxs[0] + 5.0);
+
+    // It is the same for every generation, like the svgs
+    assert_eq!(
+        stroke.gen_tessellation().unwrap().primitives.len(),
+        tessellation.primitives.len()
+    );
+
+    brush.textured_options.dots_shape = TexturedDotsShape::Triangle;
+    assert!(brushstroke(&brush).gen_tessellation().is_none());
+}
+
+#[test]
+fn translucent_strokes_are_tessellated_into_opaque_primitives() {
+    let mut brush = Brush::default();
+    brush.style = BrushStyle::Marker;
+    brush.smooth_options.stroke_color = Some(Color {
+        r: 1.0,
+        g: 0.0,
+        b: 0.0,
+        a: 0.5,
+    });
+    let stroke = StrokeStyle::BrushStroke(brushstroke(&brush));
+
+    // Marker strokes are rendered to images by default
+    let mut tessellated_strokes = TessellatedStrokes::default();
+    assert!(stroke.gen_tessellation(&tessellated_strokes, 1.0).is_none());
+    tessellated_strokes.marker = true;
+
+    let tessellation = stroke.gen_tessellation(&tessellated_strokes, 1.0).unwrap();
+    assert!((tessellation.opacity - 0.5).abs() < 1e-9);
+    assert!(tessellation.primitives.iter().all(|(primitive, color)| {
+        matches!(primitive, Primitive::Capsule { .. }) && color.a == 1.0
+    }));
+}
+
+#[test]
+fn textured_strokes_are_simplified_when_zoomed_far_out() {
+    let mut brush = Brush::default();
+    brush.style = BrushStyle::Textured;
+    brush.textured_options.width = 8.0;
+    let stroke = StrokeStyle::BrushStroke(brushstroke(&brush));
+    let tessellated_strokes = TessellatedStrokes::default();
+
+    let zoom = DetailLevel::SIMPLIFIED_ZOOM_THRESHOLD * 0.5;
+    assert_eq!(DetailLevel::for_zoom(zoom), DetailLevel::Simplified);
+    let tessellation = stroke.gen_tessellation(&tessellated_strokes, zoom).unwrap();
+    assert!(!tessellation.primitives.is_empty());
+    assert!(tessellation
+        .primitives
+        .iter()
+        .all(|(primitive, _)| matches!(primitive, Primitive::Capsule { .. })));
+    // The dots don't cover the whole stroke
+    assert!(tessellation.opacity > 0.0 && tessellation.opacity < 1.0);
+
+    let tessellation = stroke.gen_tessellation(&tessellated_strokes, 1.0).unwrap();
+    assert!(tessellation
+        .primitives
+        .iter()
+        .all(|(primitive, _)| matches!(primitive, Primitive::Ellipse { .. })));
+
+    // Without dots there is nothing to approximate
+    brush.textured_options.density = 0.0;
+    let color = brush
+        .textured_options
+        .simplified_smooth_options()
+        .stroke_color
+        .unwrap();
+    assert_eq!(color.a, 0.0);
+}
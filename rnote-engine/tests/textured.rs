@@ -0,0 +1,93 @@
+mod common;
+
+use nalgebra as na;
+use rnote_engine::compose::textured::TexturedOptions;
+use rnote_engine::drawbehaviour::DrawBehaviour;
+use rnote_engine::pens::brush::{Brush, BrushStyle};
+use rnote_engine::strokes::brushstroke::{BrushStroke, BrushStrokeStyle};
+use rnote_engine::utils;
+
+fn textured_brushstroke(n_elements: usize) -> BrushStroke {
+    let brush = Brush {
+        style: BrushStyle::Textured,
+        ..Brush::default()
+    };
+    common::brushstroke_w_brush(
+        common::horizontal_elements(na::vector![0.0, 0.0], n_elements),
+        &brush,
+    )
+}
+
+fn set_legacy_seeding(brushstroke: &mut BrushStroke, legacy_seeding: bool) {
+    match &mut brushstroke.style {
+        BrushStrokeStyle::Textured { options } => options.legacy_seeding = legacy_seeding,
+        _ => panic!("not a textured brush stroke"),
+    }
+}
+
+fn svg_data(brushstroke: &BrushStroke) -> Vec<String> {
+    brushstroke
+        .gen_svgs(na::Vector2::zeros())
+        .unwrap()
+        .into_iter()
+        .map(|svg| svg.svg_data)
+        .collect()
+}
+
+/// The svg of the last segment, like it is appended while drawing
+fn appended_svg_data(brushstroke: &BrushStroke) -> String {
+    let elements = &brushstroke.elements[brushstroke.elements.len() - 4..];
+    brushstroke
+        .gen_svg_for_elems(
+            (&elements[0], &elements[1], &elements[2], &elements[3]),
+            na::Vector2::zeros(),
+            false,
+        )
+        .unwrap()
+        .unwrap()
+        .svg_data
+}
+
+#[test]
+fn segment_seeds_are_stable_when_elements_are_appended() {
+    for legacy_seeding in [false, true] {
+        let mut brushstroke = textured_brushstroke(6);
+        set_legacy_seeding(&mut brushstroke, legacy_seeding);
+        let svgs = svg_data(&brushstroke);
+        assert_eq!(svgs.len(), 3);
+
+        brushstroke.push_elem(
+            common::horizontal_elements(na::vector![60.0, 0.0], 1)
+                .next()
+                .unwrap(),
+        );
+        let appended_svgs = svg_data(&brushstroke);
+
+        assert_eq!(appended_svgs.len(), 4);
+        assert_eq!(appended_svgs[..3], svgs[..]);
+        // The appended segment is the same when only it is generated while drawing
+        assert_eq!(appended_svgs[3], appended_svg_data(&brushstroke));
+    }
+}
+
+#[test]
+fn legacy_seeding_advances_the_seed_for_every_segment() {
+    let options = TexturedOptions {
+        seed: Some(42),
+        ..TexturedOptions::default()
+    };
+    let legacy_options = TexturedOptions {
+        legacy_seeding: true,
+        ..options.clone()
+    };
+
+    let mut seed = 42;
+    for segment_index in 0..5 {
+        seed = utils::seed_advance(seed);
+        assert_eq!(legacy_options.segment_seed(segment_index), Some(seed));
+        assert_eq!(
+            options.segment_seed(segment_index),
+            Some(utils::seed_for_segment(42, segment_index))
+        );
+    }
+}
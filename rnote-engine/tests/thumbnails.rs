@@ -0,0 +1,106 @@
+#![cfg(feature = "cairo")]
+
+mod common;
+
+use nalgebra as na;
+use rnote_engine::sheet::thumbnails::PageThumbnails;
+use rnote_engine::sheet::Sheet;
+
+/// Updates the thumbnails and waits until the pages are rendered
+fn update_until_finished(thumbnails: &mut PageThumbnails, sheet: &Sheet) {
+    thumbnails.update(sheet);
+    thumbnails.wait_for_pending();
+    assert_eq!(thumbnails.n_pending(), 0);
+}
+
+#[test]
+fn thumbnails_are_rendered_for_every_page() {
+    let sheet = common::sheet_with_pages(3);
+    let mut thumbnails = PageThumbnails::new(100);
+
+    update_until_finished(&mut thumbnails, &sheet);
+    assert_eq!(thumbnails.thumbnails().len(), 3);
+    for (page_index, thumbnail) in thumbnails.thumbnails().iter().enumerate() {
+        let thumbnail = thumbnail.as_ref().unwrap();
+        assert_eq!(thumbnail.page_index, page_index);
+        assert_eq!(thumbnail.image.pixel_width, 100);
+        assert_eq!(Some(thumbnail.signature), sheet.page_signature(page_index));
+    }
+
+    // Nothing changed, so nothing is rendered again
+    assert!(!thumbnails.update(&sheet));
+    assert_eq!(thumbnails.n_pending(), 0);
+}
+
+#[test]
+fn edits_only_invalidate_the_pages_they_touch() {
+    let mut sheet = common::sheet_with_pages(3);
+    let second_page = sheet.pages()[1];
+    let key = sheet.strokes_state.insert_stroke(common::brushstroke(
+        second_page.center().coords - na::vector![20.0, 0.0],
+        5,
+    ));
+    let mut thumbnails = PageThumbnails::default();
+    update_until_finished(&mut thumbnails, &sheet);
+
+    let signatures = (0..3)
+        .map(|page_index| sheet.page_signature(page_index).unwrap())
+        .collect::<Vec<u64>>();
+
+    sheet
+        .strokes_state
+        .translate_strokes(&[key], na::vector![10.0, 0.0], 1.0);
+    assert_eq!(sheet.page_signature(0), Some(signatures[0]));
+    assert_ne!(sheet.page_signature(1), Some(signatures[1]));
+    assert_eq!(sheet.page_signature(2), Some(signatures[2]));
+
+    thumbnails.update(&sheet);
+    assert_eq!(thumbnails.n_pending(), 1);
+    // The outdated thumbnail is kept until the new one is rendered
+    assert_eq!(thumbnails.thumbnail(1).unwrap().signature, signatures[1]);
+    update_until_finished(&mut thumbnails, &sheet);
+    assert_eq!(
+        Some(thumbnails.thumbnail(1).unwrap().signature),
+        sheet.page_signature(1)
+    );
+
+    // Trashing changes the page as well
+    sheet.strokes_state.set_trashed(key, true);
+    assert_ne!(
+        sheet.page_signature(1),
+        Some(thumbnails.thumbnail(1).unwrap().signature)
+    );
+}
+
+#[test]
+fn moving_pages_moves_the_thumbnails() {
+    let mut sheet = common::sheet_with_pages(3);
+    let first_page = sheet.pages()[0];
+    sheet
+        .strokes_state
+        .insert_stroke(common::brushstroke(first_page.center().coords, 5));
+    let mut thumbnails = PageThumbnails::default();
+    update_until_finished(&mut thumbnails, &sheet);
+    let stroke_page_signature = thumbnails.thumbnail(0).unwrap().signature;
+
+    assert!(sheet.move_page(0, 2, 1.0));
+    thumbnails.move_thumbnail(0, 2);
+    assert_eq!(
+        thumbnails.thumbnail(2).unwrap().signature,
+        stroke_page_signature
+    );
+    assert_eq!(thumbnails.thumbnail(2).unwrap().page_index, 2);
+
+    // The pages are rendered again at their new positions
+    update_until_finished(&mut thumbnails, &sheet);
+    for page_index in 0..3 {
+        assert_eq!(
+            Some(thumbnails.thumbnail(page_index).unwrap().signature),
+            sheet.page_signature(page_index)
+        );
+    }
+
+    thumbnails.set_width(PageThumbnails::WIDTH_MAX * 2);
+    assert_eq!(thumbnails.width(), PageThumbnails::WIDTH_MAX);
+    assert!(thumbnails.thumbnail(0).is_none());
+}
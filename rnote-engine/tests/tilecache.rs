@@ -0,0 +1,76 @@
+#![cfg(feature = "ui")]
+
+use gtk4::{gdk, graphene, gsk, prelude::*};
+use nalgebra as na;
+use parry2d_f64::bounding_volume::AABB;
+use rnote_engine::strokesstate::layer_comp::LayerKey;
+use rnote_engine::strokesstate::tilecache::{TileCache, TileKey};
+use rnote_engine::strokesstate::StrokeKey;
+
+fn color_node() -> gsk::RenderNode {
+    gsk::ColorNode::new(
+        &gdk::RGBA::new(1.0, 0.0, 0.0, 1.0),
+        &graphene::Rect::new(0.0, 0.0, 10.0, 10.0),
+    )
+    .upcast()
+}
+
+#[test]
+fn tiles_cover_the_bounds() {
+    let zoom = 2.0;
+    let size = TileCache::TILE_SIZE / zoom;
+
+    let (first, last) = TileCache::tile_index_range(
+        AABB::new(na::point![-1.0, 10.0], na::point![size + 1.0, 20.0]),
+        zoom,
+    );
+    assert_eq!(first, na::vector![-1, 0]);
+    assert_eq!(last, na::vector![1, 0]);
+
+    let bounds = TileCache::tile_bounds(na::vector![1, -1], zoom);
+    assert_eq!(bounds.mins, na::point![size, -size]);
+    assert_eq!(bounds.maxs, na::point![2.0 * size, 0.0]);
+}
+
+#[test]
+fn tiles_are_only_generated_again_when_their_strokes_change() {
+    let mut tile_cache = TileCache::default();
+    let key = TileKey {
+        layer: LayerKey::default(),
+        index: na::vector![0, 0],
+    };
+    let stroke = StrokeKey::default();
+    let rendernode = color_node();
+    let signature = TileCache::signature([(stroke, &rendernode)]);
+
+    tile_cache.begin_draw(1.0);
+    assert!(tile_cache
+        .tile_rendernode(key, signature, || Some(rendernode.clone()), None)
+        .is_some());
+    assert_eq!(tile_cache.generated_tiles(), &[key]);
+
+    // Unchanged strokes are taken from the cache
+    tile_cache.begin_draw(1.0);
+    assert!(tile_cache
+        .tile_rendernode(key, signature, || panic!("generated again"), None)
+        .is_some());
+    assert!(tile_cache.generated_tiles().is_empty());
+
+    // A new rendering of the stroke changes the signature
+    let new_rendernode = color_node();
+    let new_signature = TileCache::signature([(stroke, &new_rendernode)]);
+    assert_ne!(new_signature, signature);
+    tile_cache.begin_draw(1.0);
+    tile_cache.tile_rendernode(key, new_signature, || Some(new_rendernode.clone()), None);
+    assert_eq!(tile_cache.generated_tiles(), &[key]);
+
+    // Changing the zoom discards the tiles, and tiles outside of the bounds are discarded
+    tile_cache.begin_draw(2.0);
+    assert_eq!(tile_cache.n_tiles(), 0);
+    tile_cache.tile_rendernode(key, new_signature, || Some(new_rendernode.clone()), None);
+    tile_cache.retain(
+        AABB::new(na::point![1000.0, 1000.0], na::point![2000.0, 2000.0]),
+        &[key.layer],
+    );
+    assert_eq!(tile_cache.n_tiles(), 0);
+}
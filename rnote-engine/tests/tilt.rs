@@ -0,0 +1,58 @@
+mod common;
+
+use nalgebra as na;
+use parry2d_f64::bounding_volume::BoundingVolume;
+use rnote_engine::compose::transformable::Transformable;
+use rnote_engine::drawbehaviour::DrawBehaviour;
+use rnote_engine::pens::brush::Brush;
+use rnote_engine::strokes::brushstroke::BrushStroke;
+use rnote_engine::strokes::element::Element;
+
+/// Horizontal elements along y = 50.0 with the stylus tilt
+fn tilted_elements(tilt: na::Vector2<f64>) -> Vec<Element> {
+    common::horizontal_elements(na::vector![0.0, 50.0], 8)
+        .map(|mut element| {
+            element.inputdata.set_tilt(tilt);
+            element
+        })
+        .collect()
+}
+
+fn assert_near(first: na::Vector2<f64>, second: na::Vector2<f64>) {
+    assert!(
+        (first - second).magnitude() < 1e-9,
+        "{} != {}",
+        first,
+        second
+    );
+}
+
+#[test]
+fn solid_strokes_widen_with_the_tilt() {
+    let brush = Brush::default();
+    let upright = common::brushstroke_w_brush(tilted_elements(na::Vector2::zeros()), &brush);
+    let tilted = common::brushstroke_w_brush(tilted_elements(na::vector![1.0, 0.0]), &brush);
+
+    let upright_distance = common::max_path_distance_from_y(&upright, 50.0);
+    assert!(upright_distance > 0.0);
+    // At full tilt the default tilt width doubles the width
+    assert!(common::max_path_distance_from_y(&tilted, 50.0) > upright_distance * 1.5);
+    // The bounds are large enough for the widest tilt
+    assert!(upright.bounds().contains(&tilted.bounds()));
+}
+
+#[test]
+fn tilt_rotates_and_mirrors_with_the_stroke() {
+    let mut brushstroke =
+        common::brushstroke_w_brush(tilted_elements(na::vector![0.5, 0.0]), &Brush::default());
+
+    brushstroke.rotate(std::f64::consts::FRAC_PI_2, na::point![0.0, 0.0]);
+    for element in brushstroke.elements.iter() {
+        assert_near(element.inputdata.tilt(), na::vector![0.0, 0.5]);
+    }
+
+    brushstroke.scale(na::vector![1.0, -2.0]);
+    for element in brushstroke.elements.iter() {
+        assert_near(element.inputdata.tilt(), na::vector![0.0, -0.5]);
+    }
+}
@@ -0,0 +1,120 @@
+use nalgebra as na;
+use rnote_engine::compose::color::Color;
+use rnote_engine::sheet::Sheet;
+use rnote_engine::strokes::checkboxstroke::CheckboxStroke;
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+use rnote_engine::strokes::textstroke::{TextStroke, TextStyle};
+use rnote_engine::strokesstate::StrokeKey;
+
+fn insert_checkbox(sheet: &mut Sheet, text: &str, pos: na::Vector2<f64>) -> StrokeKey {
+    sheet
+        .strokes_state
+        .insert_stroke(StrokeStyle::CheckboxStroke(CheckboxStroke::new(
+            String::from(text),
+            pos,
+            CheckboxStroke::FONT_SIZE_DEFAULT,
+            Color::BLACK,
+        )))
+}
+
+fn titles(todos: &[rnote_engine::sheet::todos::Todo]) -> Vec<&str> {
+    todos.iter().map(|todo| todo.title.as_str()).collect()
+}
+
+#[test]
+fn checkboxes_are_toggled_at_their_position() {
+    let mut sheet = Sheet::default();
+    let key = insert_checkbox(&mut sheet, "Buy milk", na::vector![100.0, 100.0]);
+    let text = sheet
+        .strokes_state
+        .insert_stroke(StrokeStyle::TextStroke(TextStroke::new(
+            String::from("Text"),
+            na::vector![100.0, 400.0],
+            TextStroke::MAX_WIDTH_DEFAULT,
+            TextStyle::default(),
+        )));
+
+    // The box and the label are hit
+    let box_center = na::vector![100.0, 100.0]
+        + na::Vector2::repeat(CheckboxStroke::BOX_SIZE * CheckboxStroke::FONT_SIZE_DEFAULT * 0.5);
+    assert_eq!(
+        sheet.strokes_state.checkbox_at_pos(box_center, 0.0),
+        Some(key)
+    );
+    let label_pos = box_center + na::vector![CheckboxStroke::FONT_SIZE_DEFAULT * 2.0, 0.0];
+    assert_eq!(
+        sheet.strokes_state.checkbox_at_pos(label_pos, 0.0),
+        Some(key)
+    );
+    assert!(sheet
+        .strokes_state
+        .checkbox_at_pos(na::vector![100.0, 410.0], 0.0)
+        .is_none());
+
+    let bounds = sheet.strokes_state.get_checkboxstroke(key).unwrap().bounds;
+    assert_eq!(sheet.strokes_state.toggle_checkbox(key), Some(true));
+    assert!(sheet.strokes_state.get_checkboxstroke(key).unwrap().checked);
+    // Striking through the label keeps the size
+    assert_eq!(
+        sheet.strokes_state.get_checkboxstroke(key).unwrap().bounds,
+        bounds
+    );
+    assert_eq!(sheet.strokes_state.toggle_checkbox(key), Some(false));
+    assert_eq!(sheet.strokes_state.toggle_checkbox(text), None);
+
+    // Toggling is undone like other modifications
+    sheet.strokes_state.commit_history();
+    sheet.strokes_state.toggle_checkbox(key);
+    assert!(sheet.strokes_state.undo());
+    assert!(!sheet.strokes_state.get_checkboxstroke(key).unwrap().checked);
+}
+
+#[test]
+fn checked_state_is_saved_with_the_sheet() {
+    let mut sheet = Sheet::default();
+    let key = insert_checkbox(&mut sheet, "Buy milk", na::vector![100.0, 100.0]);
+    insert_checkbox(&mut sheet, "Call mom", na::vector![100.0, 200.0]);
+    sheet.strokes_state.toggle_checkbox(key);
+
+    let bytes = sheet.save_sheet_as_rnote_bytes().unwrap();
+    let mut reopened = Sheet::default();
+    reopened
+        .open_sheet_from_rnote_bytes(bytes)
+        .unwrap();
+
+    let todos = reopened.todos();
+    assert_eq!(
+        todos
+            .iter()
+            .map(|todo| (todo.title.as_str(), todo.checked))
+            .collect::<Vec<_>>(),
+        vec![("Buy milk", true), ("Call mom", false)]
+    );
+    assert_eq!(reopened.statistics().stroke_counts.checkboxstrokes, 2);
+}
+
+#[test]
+fn open_todos_are_listed_in_reading_order() {
+    let mut sheet = Sheet::default();
+    insert_checkbox(&mut sheet, "Third", na::vector![300.0, 300.0]);
+    insert_checkbox(
+        &mut sheet,
+        "\n  First \nwith details",
+        na::vector![100.0, 100.0],
+    );
+    let done = insert_checkbox(&mut sheet, "Done", na::vector![100.0, 150.0]);
+    insert_checkbox(&mut sheet, "Second", na::vector![500.0, 200.0]);
+    let trashed = insert_checkbox(&mut sheet, "Trashed", na::vector![100.0, 250.0]);
+
+    sheet.strokes_state.toggle_checkbox(done);
+    sheet.strokes_state.set_trashed(trashed, true);
+
+    assert_eq!(
+        titles(&sheet.todos()),
+        vec!["First", "Done", "Second", "Third"]
+    );
+    let open_todos = sheet.open_todos();
+    assert_eq!(titles(&open_todos), vec!["First", "Second", "Third"]);
+    assert_eq!(open_todos[0].pos, na::vector![100.0, 100.0]);
+    assert_eq!(open_todos[0].page_index, Some(0));
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use nalgebra as na;
+use rnote_engine::compose::smooth::{SmoothOptions, SmoothWidthSource};
+use rnote_engine::pens::brush::Brush;
+use rnote_engine::strokes::brushstroke::BrushStroke;
+
+/// A horizontal solid stroke along y = 50.0 with the velocity width source, with the elements the duration in milliseconds apart
+fn velocity_brushstroke(elem_duration_ms: Option<i64>) -> BrushStroke {
+    let brush = Brush {
+        smooth_options: SmoothOptions {
+            width: 4.0,
+            width_source: SmoothWidthSource::Velocity,
+            ..SmoothOptions::default()
+        },
+        ..Brush::default()
+    };
+    let start = chrono::Utc::now();
+    let elements = common::horizontal_elements(na::vector![0.0, 50.0], 8)
+        .enumerate()
+        .map(|(i, mut element)| {
+            element.timestamp = elem_duration_ms
+                .map(|duration| start + chrono::Duration::milliseconds(duration * i as i64));
+            element
+        });
+
+    common::brushstroke_w_brush(elements, &brush)
+}
+
+#[test]
+fn fast_strokes_are_thinner() {
+    // 100 units per second and 10000 units per second
+    let slow = common::max_path_distance_from_y(&velocity_brushstroke(Some(100)), 50.0);
+    let fast = common::max_path_distance_from_y(&velocity_brushstroke(Some(1)), 50.0);
+
+    assert!(fast > 0.0);
+    assert!(fast < slow * 0.5);
+}
+
+#[test]
+fn strokes_without_timestamps_fall_back_to_the_pressure() {
+    let without_timestamps = common::max_path_distance_from_y(&velocity_brushstroke(None), 50.0);
+    let slow = common::max_path_distance_from_y(&velocity_brushstroke(Some(100)), 50.0);
+
+    // The pressure of the elements is 1.0, so the width is not reduced
+    assert!(without_timestamps > slow);
+}
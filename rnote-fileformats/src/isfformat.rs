@@ -0,0 +1,1029 @@
+use serde::{Deserialize, Serialize};
+
+use super::FileFormatLoader;
+
+/// Represents ink in the Ink Serialized Format (ISF), as used by Windows Ink and exported by OneNote.
+/// The spec can be found here: <https://docs.microsoft.com/en-us/uwp/specifications/ink-serialized-format>
+///
+/// Loads either a raw ISF stream, or all ISF streams which are embedded in the comment records of an EMF file.
+/// The coordinates are in HIMETRIC units, meaning a vector of (1,0) has a length of 0.01 mm.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct IsfFile {
+    /// The strokes
+    pub strokes: Vec<IsfStroke>,
+}
+
+impl FileFormatLoader for IsfFile {
+    fn load_from_bytes(bytes: &[u8]) -> Result<Self, anyhow::Error> {
+        if emf_is_emf(bytes) {
+            let strokes = emf_comments(bytes)?
+                .into_iter()
+                .filter_map(isf_find_stream)
+                .map(|stream| IsfDecoder::new(stream).decode())
+                .collect::<Result<Vec<Vec<IsfStroke>>, anyhow::Error>>()?
+                .into_iter()
+                .flatten()
+                .collect::<Vec<IsfStroke>>();
+
+            if strokes.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "loading IsfFile from EMF failed, the file contains no ink"
+                ));
+            }
+
+            Ok(Self { strokes })
+        } else {
+            Ok(Self {
+                strokes: IsfDecoder::new(bytes).decode()?,
+            })
+        }
+    }
+}
+
+impl IsfFile {
+    /// The DPI of the HIMETRIC coordinates
+    pub const DPI: f64 = 2540.0;
+}
+
+/// A ISF stroke
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct IsfStroke {
+    /// The coordinates, with the transform of the stroke applied
+    pub coords: Vec<na::Vector2<f64>>,
+    /// The pressures, normalized between 0.0 and 1.0. None if the stroke has no pressure
+    pub pressures: Option<Vec<f64>>,
+    /// The drawing attributes
+    pub drawing_attributes: IsfDrawingAttributes,
+}
+
+/// The ISF drawing attributes of a stroke
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct IsfDrawingAttributes {
+    /// The color
+    pub color: IsfColor,
+    /// The width of the pen tip, in HIMETRIC units
+    pub width: f64,
+    /// The height of the pen tip, in HIMETRIC units
+    pub height: f64,
+    /// Whether the stroke was drawn with a highlighter
+    pub is_highlighter: bool,
+}
+
+impl Default for IsfDrawingAttributes {
+    fn default() -> Self {
+        Self {
+            color: IsfColor::default(),
+            width: Self::WIDTH_DEFAULT,
+            height: Self::WIDTH_DEFAULT,
+            is_highlighter: false,
+        }
+    }
+}
+
+impl IsfDrawingAttributes {
+    /// The default pen width, in HIMETRIC units
+    pub const WIDTH_DEFAULT: f64 = 53.0;
+    /// The raster operation of highlighter strokes (`R2_MASKPEN`)
+    const RASTER_OPERATION_HIGHLIGHTER: u64 = 9;
+}
+
+/// A ISF color
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct IsfColor {
+    /// red, ranging [0, 255]
+    pub red: u8,
+    /// green, ranging [0, 255]
+    pub green: u8,
+    /// blue, ranging [0, 255]
+    pub blue: u8,
+    /// alpha, ranging [0, 255]
+    pub alpha: u8,
+}
+
+impl Default for IsfColor {
+    fn default() -> Self {
+        Self {
+            red: 0,
+            green: 0,
+            blue: 0,
+            alpha: 0xff,
+        }
+    }
+}
+
+/// The ISF tags
+mod tag {
+    pub const INK_SPACE_RECT: u64 = 0;
+    pub const DRAW_ATTRS_TABLE: u64 = 2;
+    pub const DRAW_ATTRS_BLOCK: u64 = 3;
+    pub const STROKE_DESC_TABLE: u64 = 4;
+    pub const STROKE_DESC_BLOCK: u64 = 5;
+    pub const BUTTONS: u64 = 6;
+    pub const NO_X: u64 = 7;
+    pub const NO_Y: u64 = 8;
+    pub const DIDX: u64 = 9;
+    pub const STROKE: u64 = 10;
+    pub const STROKE_PROPERTY_LIST: u64 = 11;
+    pub const SIDX: u64 = 13;
+    pub const TRANSFORM_TABLE: u64 = 15;
+    pub const TRANSFORM: u64 = 16;
+    pub const TRANSFORM_ISOTROPIC_SCALE: u64 = 17;
+    pub const TRANSFORM_ANISOTROPIC_SCALE: u64 = 18;
+    pub const TRANSFORM_ROTATE: u64 = 19;
+    pub const TRANSFORM_TRANSLATE: u64 = 20;
+    pub const TRANSFORM_SCALE_AND_TRANSLATE: u64 = 21;
+    pub const TRANSFORM_QUAD: u64 = 22;
+    pub const TIDX: u64 = 23;
+    pub const METRIC_TABLE: u64 = 24;
+    pub const METRIC_BLOCK: u64 = 25;
+    pub const MIDX: u64 = 26;
+
+    // The known properties
+    pub const X: u64 = 50;
+    pub const Y: u64 = 51;
+    pub const NORMAL_PRESSURE: u64 = 56;
+    pub const COLOR_REF: u64 = 68;
+    pub const PEN_WIDTH: u64 = 69;
+    pub const PEN_HEIGHT: u64 = 70;
+    pub const PEN_TIP: u64 = 71;
+    pub const DRAWING_FLAGS: u64 = 72;
+    pub const TRANSPARENCY: u64 = 80;
+    pub const CURVE_FITTING_ERROR: u64 = 81;
+    pub const RASTER_OPERATION: u64 = 87;
+}
+
+/// The default bit amounts of the huffman codecs, indexed by the lower bits of the compression byte
+const HUFFMAN_BIT_AMOUNTS: [&[u8]; 8] = [
+    &[0, 1, 2, 4, 6, 8, 12, 16, 24, 32],
+    &[0, 1, 1, 2, 4, 8, 12, 16, 24, 32],
+    &[0, 1, 1, 1, 2, 4, 8, 14, 22, 32],
+    &[0, 2, 2, 3, 5, 8, 12, 16, 24, 32],
+    &[0, 3, 4, 5, 8, 12, 16, 24, 32],
+    &[0, 4, 6, 8, 12, 16, 24, 32],
+    &[0, 6, 8, 12, 16, 24, 32],
+    &[0, 7, 8, 12, 16, 24, 32],
+];
+
+/// The pressure range of strokes without a metric for it
+const PRESSURE_MAX_DEFAULT: f64 = 1023.0;
+
+/// A 2D affine transform, mapping x' = x * m11 + y * m21 + dx and y' = x * m12 + y * m22 + dy
+#[derive(Debug, Clone, Copy)]
+struct IsfTransform {
+    m11: f64,
+    m12: f64,
+    m21: f64,
+    m22: f64,
+    dx: f64,
+    dy: f64,
+}
+
+impl Default for IsfTransform {
+    fn default() -> Self {
+        Self {
+            m11: 1.0,
+            m12: 0.0,
+            m21: 0.0,
+            m22: 1.0,
+            dx: 0.0,
+            dy: 0.0,
+        }
+    }
+}
+
+impl IsfTransform {
+    fn apply(&self, x: f64, y: f64) -> na::Vector2<f64> {
+        na::vector![
+            x * self.m11 + y * self.m21 + self.dx,
+            x * self.m12 + y * self.m22 + self.dy
+        ]
+    }
+}
+
+/// Describes which packet properties the strokes contain, in the order they are stored
+#[derive(Debug, Clone)]
+struct IsfStrokeDescriptor {
+    packet_properties: Vec<u64>,
+}
+
+impl Default for IsfStrokeDescriptor {
+    fn default() -> Self {
+        Self {
+            packet_properties: vec![tag::X, tag::Y],
+        }
+    }
+}
+
+/// Reads the ISF primitives from a byte slice
+#[derive(Debug, Clone, Copy)]
+struct IsfReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> IsfReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn read_u8(&mut self) -> Result<u8, anyhow::Error> {
+        let byte = *self
+            .bytes
+            .get(self.pos)
+            .ok_or_else(|| anyhow::anyhow!("unexpected end of ISF stream at {}", self.pos))?;
+        self.pos += 1;
+
+        Ok(byte)
+    }
+
+    /// Multibyte encoded unsigned integer, 7 bits per byte starting with the least significant ones
+    fn read_mbuint(&mut self) -> Result<u64, anyhow::Error> {
+        let mut value = 0_u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+            value |= u64::from(byte & 0x7f) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(anyhow::anyhow!(
+            "multibyte integer in ISF stream at {} is too long",
+            self.pos
+        ))
+    }
+
+    /// Multibyte encoded signed integer, with the sign in the least significant bit
+    fn read_mbsint(&mut self) -> Result<i64, anyhow::Error> {
+        let value = self.read_mbuint()?;
+        let magnitude = (value >> 1) as i64;
+
+        Ok(if value & 1 == 1 {
+            -magnitude
+        } else {
+            magnitude
+        })
+    }
+
+    fn read_f32(&mut self) -> Result<f64, anyhow::Error> {
+        let bytes = self.read_bytes(4)?;
+
+        Ok(f64::from(f32::from_le_bytes([
+            bytes[0], bytes[1], bytes[2], bytes[3],
+        ])))
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], anyhow::Error> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "unexpected end of ISF stream, {} bytes requested at {}",
+                    len,
+                    self.pos
+                )
+            })?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+
+        Ok(bytes)
+    }
+
+    /// Reads a size prefixed block
+    fn read_block(&mut self) -> Result<IsfReader<'a>, anyhow::Error> {
+        let size = self.read_mbuint()? as usize;
+
+        Ok(IsfReader::new(self.read_bytes(size)?))
+    }
+
+    fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.pos.min(self.bytes.len())..]
+    }
+}
+
+/// Reads single bits, starting with the most significant bit of each byte
+#[derive(Debug)]
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    bit_pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, bit_pos: 0 }
+    }
+
+    fn read_bit(&mut self) -> Result<bool, anyhow::Error> {
+        let byte = self
+            .bytes
+            .get(self.bit_pos / 8)
+            .ok_or_else(|| anyhow::anyhow!("unexpected end of compressed ISF packet data"))?;
+        let bit = (byte >> (7 - self.bit_pos % 8)) & 1 == 1;
+        self.bit_pos += 1;
+
+        Ok(bit)
+    }
+
+    fn read_bits(&mut self, n_bits: u8) -> Result<u64, anyhow::Error> {
+        let mut value = 0_u64;
+        for _ in 0..n_bits {
+            value = (value << 1) | u64::from(self.read_bit()?);
+        }
+
+        Ok(value)
+    }
+
+    /// The number of bits that are left to be read
+    fn bits_remaining(&self) -> usize {
+        self.bytes
+            .len()
+            .saturating_mul(8)
+            .saturating_sub(self.bit_pos)
+    }
+
+    /// The number of bytes that were started to be read
+    fn bytes_consumed(&self) -> usize {
+        (self.bit_pos + 7) / 8
+    }
+}
+
+/// Reverts the delta-delta transform, which is applied to packet values before compression
+#[derive(Debug, Default)]
+struct DeltaDelta {
+    prev: i64,
+    prev_prev: i64,
+}
+
+impl DeltaDelta {
+    fn inverse(&mut self, value: i64) -> Result<i64, anyhow::Error> {
+        let result = self
+            .prev
+            .checked_mul(2)
+            .and_then(|prev| prev.checked_sub(self.prev_prev))
+            .and_then(|prediction| prediction.checked_add(value))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "ISF packet value overflows when reverting the delta-delta transform"
+                )
+            })?;
+        self.prev_prev = self.prev;
+        self.prev = result;
+
+        Ok(result)
+    }
+}
+
+/// Decompresses `count` packet values. Returns the values and the number of bytes that were read
+fn decompress_packet_values(
+    bytes: &[u8],
+    count: usize,
+) -> Result<(Vec<i64>, usize), anyhow::Error> {
+    let compression = *bytes
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("ISF packet data is missing its compression byte"))?;
+    let mut bit_reader = BitReader::new(&bytes[1..]);
+    // Every value takes at least one bit, so larger counts can only come from malformed streams
+    if count > bit_reader.bits_remaining() {
+        return Err(anyhow::anyhow!(
+            "ISF packet data with {} bits can't hold {} values",
+            bit_reader.bits_remaining(),
+            count
+        ));
+    }
+    let mut values = Vec::with_capacity(count);
+
+    match compression & 0xc0 {
+        // Huffman coding, always with the delta-delta transform
+        0x80 => {
+            let bit_amounts = HUFFMAN_BIT_AMOUNTS
+                .get(usize::from(compression & 0x1f))
+                .ok_or_else(|| {
+                    anyhow::anyhow!("unknown ISF huffman codec index {}", compression & 0x1f)
+                })?;
+            let mut mins = vec![0_u64; bit_amounts.len()];
+            let mut lower_bound = 1_u64;
+            for (i, &bits) in bit_amounts.iter().enumerate().skip(1) {
+                mins[i] = lower_bound;
+                lower_bound += 1 << (bits - 1);
+            }
+
+            let mut delta_delta = DeltaDelta::default();
+            for _ in 0..count {
+                let mut index = 0;
+                while bit_reader.read_bit()? {
+                    index += 1;
+                }
+
+                let value = if index == 0 {
+                    0
+                } else if index < bit_amounts.len() {
+                    let data = bit_reader.read_bits(bit_amounts[index])?;
+                    let magnitude = ((data >> 1) + mins[index]) as i64;
+                    if data & 1 == 1 {
+                        -magnitude
+                    } else {
+                        magnitude
+                    }
+                } else {
+                    // Escape code, the value follows as 64 bits
+                    bit_reader.read_bits(64)? as i64
+                };
+
+                values.push(delta_delta.inverse(value)?);
+            }
+        }
+        // Bit packing with a fixed width, optionally with the delta-delta transform
+        0x00 => {
+            let width = compression & 0x1f;
+            let mut delta_delta = (compression & 0x20 != 0).then(DeltaDelta::default);
+
+            for _ in 0..count {
+                let mut value = if width == 0 {
+                    0
+                } else {
+                    let data = bit_reader.read_bits(width)?;
+                    // Sign extension
+                    if width < 64 && data & (1 << (width - 1)) != 0 {
+                        (data | (u64::MAX << width)) as i64
+                    } else {
+                        data as i64
+                    }
+                };
+
+                if let Some(delta_delta) = delta_delta.as_mut() {
+                    value = delta_delta.inverse(value)?;
+                }
+                values.push(value);
+            }
+        }
+        _ => {
+            return Err(anyhow::anyhow!(
+                "unsupported ISF packet compression {:#x}",
+                compression
+            ));
+        }
+    }
+
+    Ok((values, 1 + bit_reader.bytes_consumed()))
+}
+
+/// Decodes the strokes of a ISF stream
+#[derive(Debug)]
+struct IsfDecoder<'a> {
+    bytes: &'a [u8],
+    drawing_attributes: Vec<IsfDrawingAttributes>,
+    stroke_descriptors: Vec<IsfStrokeDescriptor>,
+    transforms: Vec<IsfTransform>,
+    /// The max pressure of each metric block
+    pressure_maxs: Vec<Option<f64>>,
+    didx: usize,
+    sidx: usize,
+    tidx: usize,
+    midx: usize,
+}
+
+impl<'a> IsfDecoder<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            drawing_attributes: vec![],
+            stroke_descriptors: vec![],
+            transforms: vec![],
+            pressure_maxs: vec![],
+            didx: 0,
+            sidx: 0,
+            tidx: 0,
+            midx: 0,
+        }
+    }
+
+    fn decode(mut self) -> Result<Vec<IsfStroke>, anyhow::Error> {
+        let mut reader = IsfReader::new(self.bytes);
+
+        let version = reader.read_mbuint()?;
+        if version != 0 {
+            return Err(anyhow::anyhow!("unsupported ISF version {}", version));
+        }
+        let mut reader = reader.read_block()?;
+        let mut strokes = vec![];
+
+        while !reader.is_empty() {
+            match reader.read_mbuint()? {
+                tag::INK_SPACE_RECT => {
+                    for _ in 0..4 {
+                        reader.read_mbsint()?;
+                    }
+                }
+                tag::DRAW_ATTRS_TABLE => {
+                    let mut table = reader.read_block()?;
+                    while !table.is_empty() {
+                        let attributes = Self::decode_drawing_attributes(table.read_block()?)?;
+                        self.drawing_attributes.push(attributes);
+                    }
+                }
+                tag::DRAW_ATTRS_BLOCK => {
+                    let attributes = Self::decode_drawing_attributes(reader.read_block()?)?;
+                    self.drawing_attributes.push(attributes);
+                }
+                tag::STROKE_DESC_TABLE => {
+                    let mut table = reader.read_block()?;
+                    while !table.is_empty() {
+                        let descriptor = Self::decode_stroke_descriptor(table.read_block()?)?;
+                        self.stroke_descriptors.push(descriptor);
+                    }
+                }
+                tag::STROKE_DESC_BLOCK => {
+                    let descriptor = Self::decode_stroke_descriptor(reader.read_block()?)?;
+                    self.stroke_descriptors.push(descriptor);
+                }
+                tag::TRANSFORM_TABLE => {
+                    let mut table = reader.read_block()?;
+                    while !table.is_empty() {
+                        let transform_tag = table.read_mbuint()?;
+                        let transform = Self::decode_transform(transform_tag, &mut table)?;
+                        self.transforms.push(transform);
+                    }
+                }
+                transform_tag @ tag::TRANSFORM..=tag::TRANSFORM_QUAD => {
+                    let transform = Self::decode_transform(transform_tag, &mut reader)?;
+                    self.transforms.push(transform);
+                }
+                tag::METRIC_TABLE => {
+                    let mut table = reader.read_block()?;
+                    while !table.is_empty() {
+                        let pressure_max = Self::decode_metric_block(table.read_block()?)?;
+                        self.pressure_maxs.push(pressure_max);
+                    }
+                }
+                tag::METRIC_BLOCK => {
+                    let pressure_max = Self::decode_metric_block(reader.read_block()?)?;
+                    self.pressure_maxs.push(pressure_max);
+                }
+                tag::DIDX => self.didx = reader.read_mbuint()? as usize,
+                tag::SIDX => self.sidx = reader.read_mbuint()? as usize,
+                tag::TIDX => self.tidx = reader.read_mbuint()? as usize,
+                tag::MIDX => self.midx = reader.read_mbuint()? as usize,
+                tag::STROKE => {
+                    let stroke = self.decode_stroke(reader.read_block()?)?;
+                    strokes.push(stroke);
+                }
+                // Everything else is size prefixed and not needed for the strokes.
+                // This includes the guid table and the custom tags, which start at 100 and index into it
+                _ => {
+                    reader.read_block()?;
+                }
+            }
+        }
+
+        Ok(strokes)
+    }
+
+    fn decode_drawing_attributes(
+        mut block: IsfReader,
+    ) -> Result<IsfDrawingAttributes, anyhow::Error> {
+        let mut attributes = IsfDrawingAttributes::default();
+        let mut transparency = 0_u64;
+
+        while !block.is_empty() {
+            match block.read_mbuint()? {
+                tag::COLOR_REF => {
+                    // COLORREF, stored as 0x00BBGGRR
+                    let color_ref = block.read_mbuint()?;
+                    attributes.color.red = (color_ref & 0xff) as u8;
+                    attributes.color.green = ((color_ref >> 8) & 0xff) as u8;
+                    attributes.color.blue = ((color_ref >> 16) & 0xff) as u8;
+                }
+                tag::PEN_WIDTH => attributes.width = block.read_mbuint()? as f64,
+                tag::PEN_HEIGHT => attributes.height = block.read_mbuint()? as f64,
+                tag::TRANSPARENCY => transparency = block.read_mbuint()?.min(255),
+                tag::RASTER_OPERATION => {
+                    attributes.is_highlighter =
+                        block.read_mbuint()? == IsfDrawingAttributes::RASTER_OPERATION_HIGHLIGHTER;
+                }
+                tag::PEN_TIP | tag::DRAWING_FLAGS | tag::CURVE_FITTING_ERROR => {
+                    block.read_mbuint()?;
+                }
+                // The mantissa and custom attributes are size prefixed
+                _ => {
+                    block.read_block()?;
+                }
+            }
+        }
+        attributes.color.alpha = 255 - transparency as u8;
+
+        Ok(attributes)
+    }
+
+    fn decode_stroke_descriptor(
+        mut block: IsfReader,
+    ) -> Result<IsfStrokeDescriptor, anyhow::Error> {
+        let mut has_x = true;
+        let mut has_y = true;
+        let mut properties = vec![];
+
+        while !block.is_empty() {
+            match block.read_mbuint()? {
+                tag::NO_X => has_x = false,
+                tag::NO_Y => has_y = false,
+                tag::BUTTONS => {
+                    // Buttons don't have packet values
+                    let n_buttons = block.read_mbuint()?;
+                    for _ in 0..n_buttons {
+                        block.read_mbuint()?;
+                    }
+                }
+                // The rest are stroke properties, which are not packet values
+                tag::STROKE_PROPERTY_LIST => break,
+                property => properties.push(property),
+            }
+        }
+
+        let mut packet_properties = vec![];
+        if has_x {
+            packet_properties.push(tag::X);
+        }
+        if has_y {
+            packet_properties.push(tag::Y);
+        }
+        packet_properties.append(&mut properties);
+
+        Ok(IsfStrokeDescriptor { packet_properties })
+    }
+
+    fn decode_transform(
+        transform_tag: u64,
+        reader: &mut IsfReader,
+    ) -> Result<IsfTransform, anyhow::Error> {
+        let mut transform = IsfTransform::default();
+
+        match transform_tag {
+            tag::TRANSFORM => {
+                transform.m11 = reader.read_f32()?;
+                transform.m12 = reader.read_f32()?;
+                transform.m21 = reader.read_f32()?;
+                transform.m22 = reader.read_f32()?;
+                transform.dx = reader.read_f32()?;
+                transform.dy = reader.read_f32()?;
+            }
+            tag::TRANSFORM_ISOTROPIC_SCALE => {
+                let scale = reader.read_f32()?;
+                transform.m11 = scale;
+                transform.m22 = scale;
+            }
+            tag::TRANSFORM_ANISOTROPIC_SCALE => {
+                transform.m11 = reader.read_f32()?;
+                transform.m22 = reader.read_f32()?;
+            }
+            tag::TRANSFORM_ROTATE => {
+                // In hundredths of a degree
+                let angle = (reader.read_mbuint()? as f64 / 100.0).to_radians();
+                transform.m11 = angle.cos();
+                transform.m12 = angle.sin();
+                transform.m21 = -angle.sin();
+                transform.m22 = angle.cos();
+            }
+            tag::TRANSFORM_TRANSLATE => {
+                transform.dx = reader.read_f32()?;
+                transform.dy = reader.read_f32()?;
+            }
+            tag::TRANSFORM_SCALE_AND_TRANSLATE => {
+                transform.m11 = reader.read_f32()?;
+                transform.m22 = reader.read_f32()?;
+                transform.dx = reader.read_f32()?;
+                transform.dy = reader.read_f32()?;
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "unsupported ISF transform tag {}",
+                    transform_tag
+                ));
+            }
+        }
+
+        Ok(transform)
+    }
+
+    /// Returns the max of the pressure metric, if the block has one
+    fn decode_metric_block(mut block: IsfReader) -> Result<Option<f64>, anyhow::Error> {
+        let mut pressure_max = None;
+
+        while !block.is_empty() {
+            let property = block.read_mbuint()?;
+            let mut metric = block.read_block()?;
+
+            if property == tag::NORMAL_PRESSURE {
+                let min = metric.read_mbsint()?;
+                let max = metric.read_mbsint()?;
+                if max > min {
+                    pressure_max = Some(max as f64);
+                }
+            }
+        }
+
+        Ok(pressure_max)
+    }
+
+    fn decode_stroke(&self, mut block: IsfReader) -> Result<IsfStroke, anyhow::Error> {
+        let descriptor = self
+            .stroke_descriptors
+            .get(self.sidx)
+            .cloned()
+            .unwrap_or_default();
+        let n_packets = block.read_mbuint()? as usize;
+        // Every packet takes at least one bit, so larger counts can only come from malformed streams
+        if n_packets > block.remaining().len().saturating_mul(8) {
+            return Err(anyhow::anyhow!(
+                "ISF stroke with {} bytes can't hold {} packets",
+                block.remaining().len(),
+                n_packets
+            ));
+        }
+
+        let mut xs = None;
+        let mut ys = None;
+        let mut pressures = None;
+        for &property in descriptor.packet_properties.iter() {
+            let (values, len) = decompress_packet_values(block.remaining(), n_packets)?;
+            block.read_bytes(len)?;
+
+            match property {
+                tag::X => xs = Some(values),
+                tag::Y => ys = Some(values),
+                tag::NORMAL_PRESSURE => pressures = Some(values),
+                _ => {}
+            }
+        }
+
+        let transform = self.transforms.get(self.tidx).copied().unwrap_or_default();
+        let zeros = vec![0; n_packets];
+        let coords = xs
+            .as_ref()
+            .unwrap_or(&zeros)
+            .iter()
+            .zip(ys.as_ref().unwrap_or(&zeros).iter())
+            .map(|(&x, &y)| transform.apply(x as f64, y as f64))
+            .collect::<Vec<na::Vector2<f64>>>();
+
+        let pressure_max = self
+            .pressure_maxs
+            .get(self.midx)
+            .copied()
+            .flatten()
+            .unwrap_or(PRESSURE_MAX_DEFAULT);
+        let pressures = pressures.map(|pressures| {
+            pressures
+                .into_iter()
+                .map(|pressure| (pressure as f64 / pressure_max).clamp(0.0, 1.0))
+                .collect::<Vec<f64>>()
+        });
+
+        Ok(IsfStroke {
+            coords,
+            pressures,
+            drawing_attributes: self
+                .drawing_attributes
+                .get(self.didx)
+                .copied()
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Whether the bytes start with a EMF header record
+fn emf_is_emf(bytes: &[u8]) -> bool {
+    const EMR_HEADER: u32 = 1;
+    const EMF_SIGNATURE: u32 = 0x464d4520;
+
+    bytes.len() >= 44
+        && u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) == EMR_HEADER
+        && u32::from_le_bytes([bytes[40], bytes[41], bytes[42], bytes[43]]) == EMF_SIGNATURE
+}
+
+/// The data of all comment records of the EMF file
+fn emf_comments(bytes: &[u8]) -> Result<Vec<&[u8]>, anyhow::Error> {
+    const EMR_GDICOMMENT: u32 = 70;
+    const EMR_EOF: u32 = 14;
+
+    let read_u32 = |pos: usize| -> Result<u32, anyhow::Error> {
+        bytes
+            .get(pos..pos + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| anyhow::anyhow!("unexpected end of EMF file at {}", pos))
+    };
+
+    let mut comments = vec![];
+    let mut pos = 0;
+    while pos + 8 <= bytes.len() {
+        let record_type = read_u32(pos)?;
+        let record_size = read_u32(pos + 4)? as usize;
+        if record_size < 8 {
+            return Err(anyhow::anyhow!("invalid EMF record size at {}", pos));
+        }
+
+        if record_type == EMR_GDICOMMENT {
+            let data_size = read_u32(pos + 8)? as usize;
+            let data = (pos + 12)
+                .checked_add(data_size)
+                .and_then(|end| bytes.get(pos + 12..end))
+                .ok_or_else(|| anyhow::anyhow!("EMF comment at {} exceeds the file", pos))?;
+            comments.push(data);
+        } else if record_type == EMR_EOF {
+            break;
+        }
+
+        // A record exceeding the file ends it
+        pos = match pos
+            .checked_add(record_size)
+            .filter(|&next| next <= bytes.len())
+        {
+            Some(next) => next,
+            None => break,
+        };
+    }
+
+    Ok(comments)
+}
+
+/// Finds the ISF stream in the EMF comment data, which might be preceded by a comment identifier
+fn isf_find_stream(data: &[u8]) -> Option<&[u8]> {
+    [0, 4].into_iter().find_map(|offset| {
+        let stream = data.get(offset..)?;
+        let mut reader = IsfReader::new(stream);
+
+        // A version of 0, and a stream size that fits into the data
+        if reader.read_mbuint().ok()? != 0 {
+            return None;
+        }
+        let size = reader.read_mbuint().ok()? as usize;
+        if size == 0 || size > stream.len() - reader.pos {
+            return None;
+        }
+
+        Some(stream)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FileFormatLoader;
+
+    /// A ISF stream with a single stroke, which has the default stroke descriptor with the x and y packet properties
+    fn isf_with_stroke(stroke: &[u8]) -> Vec<u8> {
+        let mut block = vec![super::tag::STROKE as u8, stroke.len() as u8];
+        block.extend_from_slice(stroke);
+        let mut bytes = vec![0, block.len() as u8];
+        bytes.extend_from_slice(&block);
+        bytes
+    }
+
+    /// Packs a string of '0' and '1' into bytes, starting with the most significant bit
+    fn pack_bits(bits: &str) -> Vec<u8> {
+        let bits = bits.replace(' ', "");
+        bits.as_bytes()
+            .chunks(8)
+            .map(|chunk| {
+                chunk.iter().enumerate().fold(0_u8, |byte, (i, &bit)| {
+                    byte | (u8::from(bit == b'1') << (7 - i))
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn load_bitpacked_isf() -> Result<(), anyhow::Error> {
+        // A stroke with the packets (0, 0), (1, -1), (2, 2), bit packed with a width of 4
+        let stroke = [
+            super::tag::STROKE as u8,
+            7,
+            3,
+            0x04,
+            0x01,
+            0x20,
+            0x04,
+            0x0f,
+            0x20,
+        ];
+        let mut bytes = vec![0, stroke.len() as u8];
+        bytes.extend_from_slice(&stroke);
+
+        let isf_file = super::IsfFile::load_from_bytes(&bytes)?;
+
+        assert_eq!(isf_file.strokes.len(), 1);
+        assert_eq!(
+            isf_file.strokes[0].coords,
+            vec![
+                na::vector![0.0, 0.0],
+                na::vector![1.0, -1.0],
+                na::vector![2.0, 2.0]
+            ]
+        );
+        assert!(isf_file.strokes[0].pressures.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_huffman_isf() -> Result<(), anyhow::Error> {
+        // The packets (0, 0), (1, -1), (2, 2) with the delta-delta transform, which are (0, 0), (1, -1), (0, 4),
+        // huffman coded with the first codec
+        let mut stroke = vec![3, 0x80];
+        stroke.extend(pack_bits("0 100 0"));
+        stroke.push(0x80);
+        stroke.extend(pack_bits("0 101 1110 0000"));
+
+        let isf_file = super::IsfFile::load_from_bytes(&isf_with_stroke(&stroke))?;
+
+        assert_eq!(isf_file.strokes.len(), 1);
+        assert_eq!(
+            isf_file.strokes[0].coords,
+            vec![
+                na::vector![0.0, 0.0],
+                na::vector![1.0, -1.0],
+                na::vector![2.0, 2.0]
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_isf_embedded_in_emf() -> Result<(), anyhow::Error> {
+        let isf = isf_with_stroke(&[3, 0x04, 0x01, 0x20, 0x04, 0x0f, 0x20]);
+        // The stream is preceded by a comment identifier
+        let mut comment = b"ISF ".to_vec();
+        comment.extend_from_slice(&isf);
+        while comment.len() % 4 != 0 {
+            comment.push(0);
+        }
+
+        let record = |record_type: u32, data: &[u8]| -> Vec<u8> {
+            let mut record = record_type.to_le_bytes().to_vec();
+            record.extend_from_slice(&(8 + data.len() as u32).to_le_bytes());
+            record.extend_from_slice(data);
+            record
+        };
+        let mut header = vec![0; 80];
+        header[32..36].copy_from_slice(&0x464d4520_u32.to_le_bytes());
+        let mut comment_data = (isf.len() as u32 + 4).to_le_bytes().to_vec();
+        comment_data.extend_from_slice(&comment);
+
+        let mut bytes = record(1, &header);
+        bytes.extend(record(70, &comment_data));
+        bytes.extend(record(14, &[0; 12]));
+
+        let isf_file = super::IsfFile::load_from_bytes(&bytes)?;
+
+        assert_eq!(isf_file.strokes.len(), 1);
+        assert_eq!(
+            isf_file.strokes[0].coords,
+            vec![
+                na::vector![0.0, 0.0],
+                na::vector![1.0, -1.0],
+                na::vector![2.0, 2.0]
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_truncated_isf_fails() {
+        let bytes = isf_with_stroke(&[3, 0x04, 0x01, 0x20, 0x04, 0x0f, 0x20]);
+
+        for len in 0..bytes.len() {
+            assert!(super::IsfFile::load_from_bytes(&bytes[..len]).is_err());
+        }
+        // The sizes are consistent, but the x values are missing their last four bits
+        assert!(super::IsfFile::load_from_bytes(&isf_with_stroke(&[3, 0x04, 0x01])).is_err());
+    }
+
+    #[test]
+    fn load_isf_with_huge_packet_count_fails() {
+        // 2^63 - 1 packets, but the packet data only has a few bytes
+        let stroke = [
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f, 0x04, 0x01, 0x20, 0x04, 0x0f,
+            0x20,
+        ];
+
+        assert!(super::IsfFile::load_from_bytes(&isf_with_stroke(&stroke)).is_err());
+    }
+
+    #[test]
+    fn load_isf_with_overflowing_deltas_fails() {
+        // The escape code of the first huffman codec followed by i64::MAX, and a zero.
+        // Reverting the delta-delta transform of the second packet overflows
+        let mut stroke = vec![2, 0x80];
+        stroke.extend(pack_bits(&format!("1111111111 0 {:064b} 0", i64::MAX)));
+        stroke.extend([0x80, 0x00]);
+
+        assert!(super::IsfFile::load_from_bytes(&isf_with_stroke(&stroke)).is_err());
+    }
+}
@@ -13,7 +13,10 @@
 //! | --- | --- | --- | --- | --- |
 //! | Rnote | .rnote | - | native | see <https://github.com/flxzt/rnote> |
 //! | Xournal++ | .xopp | native | x | see <https://github.com/xournalpp/xournalpp> |
+//! | Ink Serialized Format | .isf, .emf | - | x | see <https://docs.microsoft.com/en-us/uwp/specifications/ink-serialized-format>, loading only |
 
+/// The Ink Serialized Format (ISF), as used by Windows Ink and OneNote
+pub mod isfformat;
 /// The Rnote `.rnote` file format
 pub mod rnoteformat;
 /// The Xournal++ `.xopp` file format
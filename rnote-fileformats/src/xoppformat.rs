@@ -239,6 +239,8 @@ pub enum XoppBackgroundSolidStyle {
     Ruled,
     /// The graph background style
     Graph,
+    /// The dotted background style
+    Dotted,
 }
 
 impl Default for XoppBackgroundSolidStyle {
@@ -254,6 +256,7 @@ impl AsXmlAttributeValue for XoppBackgroundSolidStyle {
             Self::Lined => String::from("lined"),
             Self::Ruled => String::from("ruled"),
             Self::Graph => String::from("graph"),
+            Self::Dotted => String::from("dotted"),
         }
     }
 }
@@ -313,6 +316,7 @@ impl XmlLoadable for XoppBackground {
                     "lined" => XoppBackgroundSolidStyle::Lined,
                     "ruled" => XoppBackgroundSolidStyle::Ruled,
                     "graph" => XoppBackgroundSolidStyle::Graph,
+                    "dotted" => XoppBackgroundSolidStyle::Dotted,
                     _ => {
                         return Err(anyhow::anyhow!("Err while parsing `style` attribute of XoppBackground with id {:?}, is not a valid value", node.id()));
                     }
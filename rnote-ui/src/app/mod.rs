@@ -9,6 +9,7 @@ mod imp {
     use adw::subclass::prelude::AdwApplicationImpl;
     use gtk4::{gio, glib, prelude::*, subclass::prelude::*};
     use once_cell::sync::Lazy;
+    use rnote_engine::compose::rough::roughoptions::FillStyle;
     use rnote_engine::compose::textured::TexturedDotsDistribution;
     use rnote_engine::{
         pens::PenStyle,
@@ -22,6 +23,7 @@ mod imp {
         config, mainheader::MainHeader, penssidebar::brushpage::BrushPage,
         penssidebar::eraserpage::EraserPage, penssidebar::selectorpage::SelectorPage,
         penssidebar::shaperpage::ShaperPage, penssidebar::toolspage::ToolsPage,
+        penssidebar::typewriterpage::TypewriterPage,
         penssidebar::PensSideBar, selectionmodifier::modifiernode::ModifierNode,
         selectionmodifier::SelectionModifier, settingspanel::penshortcutrow::PenShortcutRow,
         settingspanel::SettingsPanel, unitentry::UnitEntry, utils,
@@ -112,6 +114,7 @@ mod imp {
             EraserPage::static_type();
             SelectorPage::static_type();
             ToolsPage::static_type();
+            TypewriterPage::static_type();
             PenStyle::static_type();
             WorkspaceBrowser::static_type();
             FileRow::static_type();
@@ -120,6 +123,7 @@ mod imp {
             PatternStyle::static_type();
             UnitEntry::static_type();
             TexturedDotsDistribution::static_type();
+            FillStyle::static_type();
             PenShortcutRow::static_type();
 
             // Load the resources
@@ -4,6 +4,7 @@ use crate::app::RnoteApp;
 use crate::appwindow::RnoteAppWindow;
 use crate::canvas::ExpandMode;
 use rnote_engine::compose::color::Color;
+use rnote_engine::compose::palette::Palettes;
 use rnote_engine::pens::Pens;
 use rnote_engine::sheet::background::Background;
 use rnote_engine::sheet::format::Format;
@@ -85,6 +86,7 @@ impl RnoteAppWindow {
                     "fixed-size" => Some(ExpandMode::FixedSize.to_value()),
                     "endless-vertical" => Some(ExpandMode::EndlessVertical.to_value()),
                     "infinite" => Some(ExpandMode::Infinite.to_value()),
+                    "pages" => Some(ExpandMode::Pages.to_value()),
                     _ => {
                         log::error!(
                             "mapping expand-mode to setting failed, invalid str {}",
@@ -101,6 +103,7 @@ impl RnoteAppWindow {
                         Some(String::from("endless-vertical").to_variant())
                     }
                     ExpandMode::Infinite => Some(String::from("infinite").to_variant()),
+                    ExpandMode::Pages => Some(String::from("pages").to_variant()),
                 },
             )
             .build();
@@ -110,6 +113,11 @@ impl RnoteAppWindow {
             .bind("format-borders", &self.canvas(), "format-borders")
             .build();
 
+        // invert ink
+        self.app_settings()
+            .bind("invert-ink", &self.canvas(), "invert-ink")
+            .build();
+
         // pdf import width
         self.app_settings()
             .bind("pdf-import-width", &self.canvas(), "pdf-import-width")
@@ -124,6 +132,11 @@ impl RnoteAppWindow {
             )
             .build();
 
+        // undo memory budget
+        self.app_settings()
+            .bind("undo-memory-budget", &self.canvas(), "undo-memory-budget")
+            .build();
+
         // Brush page
         self.app_settings()
             .bind(
@@ -254,6 +267,34 @@ impl RnoteAppWindow {
             {
                 *self.canvas().pens().borrow_mut() = loaded_pens;
             }
+
+            let snapping = self.canvas().pens().borrow().snapping.clone();
+            adw::prelude::ActionGroupExt::change_action_state(
+                self,
+                "snap-to-grid",
+                &snapping.snap_to_grid.to_variant(),
+            );
+            adw::prelude::ActionGroupExt::change_action_state(
+                self,
+                "snap-to-strokes",
+                &snapping.snap_to_strokes.to_variant(),
+            );
+
+            let compass_show_radius = self.canvas().pens().borrow().brush.compass.show_radius;
+            adw::prelude::ActionGroupExt::change_action_state(
+                self,
+                "compass-show-radius",
+                &compass_show_radius.to_variant(),
+            );
+        }
+
+        {
+            // Load palettes
+            if let Ok(loaded_palettes) =
+                serde_json::from_str::<Palettes>(self.app_settings().string("palettes").as_str())
+            {
+                *self.canvas().palettes().borrow_mut() = loaded_palettes;
+            }
         }
 
         // refresh the UI
@@ -331,6 +372,11 @@ impl RnoteAppWindow {
                 .set_string("pens", pens_string.as_str())?;
 
             //println!("pens:\n{}", pens_string);
+
+            // Save palettes
+            let palettes_string = serde_json::to_string(&*self.canvas().palettes().borrow())?;
+            self.app_settings()
+                .set_string("palettes", palettes_string.as_str())?;
         }
 
         Ok(())
@@ -4,12 +4,23 @@ use crate::{
     utils,
     {canvas::Canvas, dialogs},
 };
+use rnote_engine::compose::palette::Palette;
+use rnote_engine::compose::smooth::FillRule;
 use rnote_engine::pens::brush::BrushStyle;
+use rnote_engine::pens::eraser::{EraserShape, EraserStyle};
+use rnote_engine::pens::guides::GuideKind;
 use rnote_engine::pens::selector::SelectorStyle;
 use rnote_engine::pens::shaper::{ShaperDrawStyle, ShaperStyle};
 use rnote_engine::pens::tools::ToolStyle;
 use rnote_engine::pens::{brush, selector, shaper, tools, PenEvent, PenStyle};
+use rnote_engine::recognition::handwriting;
 use rnote_engine::render::{self, RendererBackend};
+use rnote_engine::sheet::format::Orientation;
+use rnote_engine::strokes::strokestyle::StrokeOptionsModification;
+use rnote_engine::strokesstate::chrono_comp::StrokesZOrder;
+use rnote_engine::strokesstate::selection_comp::{
+    StrokesAlignment, StrokesClipboard, StrokesDistribution,
+};
 
 use gettextrs::gettext;
 use gtk4::PrintStatus;
@@ -17,7 +28,6 @@ use gtk4::{
     gdk, gio, glib, glib::clone, prelude::*, ArrowType, CornerType, PackType, PositionType,
     PrintOperation, PrintOperationAction, Unit,
 };
-use rnote_engine::compose;
 
 impl RnoteAppWindow {
     /// Appwindow actions only have state in the lifetime of the application. Actions that are saved to the settings should be app actions
@@ -55,6 +65,15 @@ impl RnoteAppWindow {
             &"librsvg".to_variant(),
         );
         self.add_action(&action_renderer_backend);
+        let action_gpu_marker_strokes =
+            gio::SimpleAction::new_stateful("gpu-marker-strokes", None, &false.to_variant());
+        self.add_action(&action_gpu_marker_strokes);
+        let action_gpu_solid_strokes =
+            gio::SimpleAction::new_stateful("gpu-solid-strokes", None, &false.to_variant());
+        self.add_action(&action_gpu_solid_strokes);
+        let action_gpu_textured_strokes =
+            gio::SimpleAction::new_stateful("gpu-textured-strokes", None, &true.to_variant());
+        self.add_action(&action_gpu_textured_strokes);
 
         let action_righthanded = gio::PropertyAction::new("righthanded", self, "righthanded");
         self.add_action(&action_righthanded);
@@ -63,12 +82,54 @@ impl RnoteAppWindow {
         let action_touch_drawing =
             gio::PropertyAction::new("touch-drawing", &self.canvas(), "touch-drawing");
         self.add_action(&action_touch_drawing);
+        let action_view_mode = gio::PropertyAction::new("view-mode", &self.canvas(), "view-mode");
+        self.add_action(&action_view_mode);
         let action_expand_mode =
             gio::PropertyAction::new("expand-mode", &self.canvas(), "expand-mode");
         self.add_action(&action_expand_mode);
         let action_format_borders =
             gio::PropertyAction::new("format-borders", &self.canvas(), "format-borders");
         self.add_action(&action_format_borders);
+        let action_invert_ink =
+            gio::PropertyAction::new("invert-ink", &self.canvas(), "invert-ink");
+        self.add_action(&action_invert_ink);
+        let action_snap_to_grid =
+            gio::SimpleAction::new_stateful("snap-to-grid", None, &false.to_variant());
+        self.add_action(&action_snap_to_grid);
+        let action_snap_to_strokes =
+            gio::SimpleAction::new_stateful("snap-to-strokes", None, &false.to_variant());
+        self.add_action(&action_snap_to_strokes);
+        let action_guide = gio::SimpleAction::new_stateful(
+            "guide",
+            Some(&glib::VariantType::new("s").unwrap()),
+            &"none".to_variant(),
+        );
+        self.add_action(&action_guide);
+        let action_compass =
+            gio::SimpleAction::new_stateful("compass", None, &false.to_variant());
+        self.add_action(&action_compass);
+        let action_compass_show_radius =
+            gio::SimpleAction::new_stateful("compass-show-radius", None, &true.to_variant());
+        self.add_action(&action_compass_show_radius);
+        let action_presentation =
+            gio::SimpleAction::new_stateful("presentation", None, &false.to_variant());
+        self.add_action(&action_presentation);
+        let action_record_audio_note =
+            gio::SimpleAction::new_stateful("record-audio-note", None, &false.to_variant());
+        self.add_action(&action_record_audio_note);
+        let action_stop_audio_note = gio::SimpleAction::new("stop-audio-note", None);
+        self.add_action(&action_stop_audio_note);
+        let action_add_bookmark = gio::SimpleAction::new("add-bookmark", None);
+        self.add_action(&action_add_bookmark);
+        let action_jump_to_bookmark = gio::SimpleAction::new(
+            "jump-to-bookmark",
+            Some(&glib::VariantType::new("s").unwrap()),
+        );
+        self.add_action(&action_jump_to_bookmark);
+        let action_next_bookmark = gio::SimpleAction::new("next-bookmark", None);
+        self.add_action(&action_next_bookmark);
+        let action_previous_bookmark = gio::SimpleAction::new("previous-bookmark", None);
+        self.add_action(&action_previous_bookmark);
 
         let action_undo_stroke = gio::SimpleAction::new("undo-stroke", None);
         self.add_action(&action_undo_stroke);
@@ -84,13 +145,72 @@ impl RnoteAppWindow {
         self.add_action(&action_zoomout);
         let action_return_origin_page = gio::SimpleAction::new("return-origin-page", None);
         self.add_action(&action_return_origin_page);
+        let action_page_insert = gio::SimpleAction::new("page-insert", None);
+        self.add_action(&action_page_insert);
+        let action_page_remove = gio::SimpleAction::new("page-remove", None);
+        self.add_action(&action_page_remove);
+        let action_page_move_up = gio::SimpleAction::new("page-move-up", None);
+        self.add_action(&action_page_move_up);
+        let action_page_move_down = gio::SimpleAction::new("page-move-down", None);
+        self.add_action(&action_page_move_down);
+        let action_page_rotate = gio::SimpleAction::new("page-rotate", None);
+        self.add_action(&action_page_rotate);
 
         let action_selection_trash = gio::SimpleAction::new("selection-trash", None);
         self.add_action(&action_selection_trash);
         let action_selection_duplicate = gio::SimpleAction::new("selection-duplicate", None);
         self.add_action(&action_selection_duplicate);
+        let action_selection_mirror_horizontal =
+            gio::SimpleAction::new("selection-mirror-horizontal", None);
+        self.add_action(&action_selection_mirror_horizontal);
+        let action_selection_mirror_vertical =
+            gio::SimpleAction::new("selection-mirror-vertical", None);
+        self.add_action(&action_selection_mirror_vertical);
+        let action_selection_group = gio::SimpleAction::new("selection-group", None);
+        self.add_action(&action_selection_group);
+        let action_selection_ungroup = gio::SimpleAction::new("selection-ungroup", None);
+        self.add_action(&action_selection_ungroup);
+        let action_selection_restyle = gio::SimpleAction::new("selection-restyle", None);
+        self.add_action(&action_selection_restyle);
+        let action_selection_align = gio::SimpleAction::new(
+            "selection-align",
+            Some(&glib::VariantType::new("s").unwrap()),
+        );
+        self.add_action(&action_selection_align);
+        let action_selection_distribute = gio::SimpleAction::new(
+            "selection-distribute",
+            Some(&glib::VariantType::new("s").unwrap()),
+        );
+        self.add_action(&action_selection_distribute);
+        let action_selection_z_order = gio::SimpleAction::new(
+            "selection-z-order",
+            Some(&glib::VariantType::new("s").unwrap()),
+        );
+        self.add_action(&action_selection_z_order);
         let action_selection_select_all = gio::SimpleAction::new("selection-select-all", None);
         self.add_action(&action_selection_select_all);
+        let action_selection_remove_fill = gio::SimpleAction::new("selection-remove-fill", None);
+        self.add_action(&action_selection_remove_fill);
+        let action_selection_link = gio::SimpleAction::new("selection-link", None);
+        self.add_action(&action_selection_link);
+        let action_selection_tags = gio::SimpleAction::new("selection-tags", None);
+        self.add_action(&action_selection_tags);
+        let action_tag_filter = gio::SimpleAction::new("tag-filter", None);
+        self.add_action(&action_tag_filter);
+        let action_clear_tag_filter = gio::SimpleAction::new("clear-tag-filter", None);
+        self.add_action(&action_clear_tag_filter);
+        let action_insert_math = gio::SimpleAction::new("insert-math", None);
+        self.add_action(&action_insert_math);
+        let action_insert_checkbox = gio::SimpleAction::new("insert-checkbox", None);
+        self.add_action(&action_insert_checkbox);
+        let action_open_todos = gio::SimpleAction::new("open-todos", None);
+        self.add_action(&action_open_todos);
+        let action_selection_crop_image =
+            gio::SimpleAction::new("selection-crop-image", None);
+        self.add_action(&action_selection_crop_image);
+        let action_selection_recognize_text =
+            gio::SimpleAction::new("selection-recognize-text", None);
+        self.add_action(&action_selection_recognize_text);
         let action_selection_deselect_all = gio::SimpleAction::new("selection-deselect-all", None);
         self.add_action(&action_selection_deselect_all);
         let action_clear_sheet = gio::SimpleAction::new("clear-sheet", None);
@@ -109,6 +229,10 @@ impl RnoteAppWindow {
         self.add_action(&action_print_sheet);
         let action_import_file = gio::SimpleAction::new("import-file", None);
         self.add_action(&action_import_file);
+        let action_attach_file = gio::SimpleAction::new("attach-file", None);
+        self.add_action(&action_attach_file);
+        let action_extract_attachments = gio::SimpleAction::new("extract-attachments", None);
+        self.add_action(&action_extract_attachments);
         let action_export_selection_as_svg =
             gio::SimpleAction::new("export-selection-as-svg", None);
         self.add_action(&action_export_selection_as_svg);
@@ -116,11 +240,19 @@ impl RnoteAppWindow {
         self.add_action(&action_export_sheet_as_svg);
         let action_export_sheet_as_pdf = gio::SimpleAction::new("export-sheet-as-pdf", None);
         self.add_action(&action_export_sheet_as_pdf);
+        let action_export_sheet_as_annotated_pdf =
+            gio::SimpleAction::new("export-sheet-as-annotated-pdf", None);
+        self.add_action(&action_export_sheet_as_annotated_pdf);
+        let action_export_sheet_as_bitmap = gio::SimpleAction::new("export-sheet-as-bitmap", None);
+        self.add_action(&action_export_sheet_as_bitmap);
         let action_export_sheet_as_xopp = gio::SimpleAction::new("export-sheet-as-xopp", None);
         self.add_action(&action_export_sheet_as_xopp);
         let action_clipboard_copy_selection =
             gio::SimpleAction::new("clipboard-copy-selection", None);
         self.add_action(&action_clipboard_copy_selection);
+        let action_clipboard_cut_selection =
+            gio::SimpleAction::new("clipboard-cut-selection", None);
+        self.add_action(&action_clipboard_cut_selection);
         let action_clipboard_paste_selection =
             gio::SimpleAction::new("clipboard-paste-selection", None);
         self.add_action(&action_clipboard_paste_selection);
@@ -240,6 +372,34 @@ impl RnoteAppWindow {
             action_renderer_backend.change_state(&target.to_variant());
         }));
 
+        // Strokes drawn by the GPU
+        action_gpu_marker_strokes.connect_activate(clone!(@weak self as appwindow => move |action_gpu_marker_strokes, _target| {
+            let state = !action_gpu_marker_strokes.state().unwrap().get::<bool>().unwrap();
+
+            appwindow.canvas().renderer().write().unwrap().tessellated_strokes.marker = state;
+            appwindow.canvas().regenerate_content(true, true);
+
+            action_gpu_marker_strokes.change_state(&state.to_variant());
+        }));
+
+        action_gpu_solid_strokes.connect_activate(clone!(@weak self as appwindow => move |action_gpu_solid_strokes, _target| {
+            let state = !action_gpu_solid_strokes.state().unwrap().get::<bool>().unwrap();
+
+            appwindow.canvas().renderer().write().unwrap().tessellated_strokes.solid = state;
+            appwindow.canvas().regenerate_content(true, true);
+
+            action_gpu_solid_strokes.change_state(&state.to_variant());
+        }));
+
+        action_gpu_textured_strokes.connect_activate(clone!(@weak self as appwindow => move |action_gpu_textured_strokes, _target| {
+            let state = !action_gpu_textured_strokes.state().unwrap().get::<bool>().unwrap();
+
+            appwindow.canvas().renderer().write().unwrap().tessellated_strokes.textured = state;
+            appwindow.canvas().regenerate_content(true, true);
+
+            action_gpu_textured_strokes.change_state(&state.to_variant());
+        }));
+
         // Righthanded
         action_righthanded.connect_state_notify(
             clone!(@weak self as appwindow => move |action_righthanded| {
@@ -434,6 +594,12 @@ impl RnoteAppWindow {
                     "tools_style" => {
                         Some(PenStyle::ToolsStyle)
                     }
+                    "typewriter_style" => {
+                        Some(PenStyle::TypewriterStyle)
+                    }
+                    "plugin_style" => {
+                        Some(PenStyle::PluginStyle)
+                    }
                     _ => {
                         log::error!("invalid target for action_current_pen, `{}`", pen_style);
                         None
@@ -486,6 +652,9 @@ impl RnoteAppWindow {
                     "tools_style" => {
                         Some(PenEvent::ChangeStyleOverride(Some(PenStyle::ToolsStyle)))
                     }
+                    "typewriter_style" => {
+                        Some(PenEvent::ChangeStyleOverride(Some(PenStyle::TypewriterStyle)))
+                    }
                     "none" => {
                         Some(PenEvent::ChangeStyleOverride(None))
                     }
@@ -529,6 +698,26 @@ impl RnoteAppWindow {
                     appwindow.canvas().pens().borrow_mut().brush.textured_options.width = appwindow.penssidebar().brush_page().width_spinbutton().value();
                     appwindow.canvas().pens().borrow_mut().brush.textured_options.stroke_color = Some(appwindow.penssidebar().brush_page().colorpicker().current_color());
                 },
+                "spray" => {
+                    appwindow.canvas().pens().borrow_mut().brush.style = brush::BrushStyle::Spray;
+                    appwindow.canvas().pens().borrow_mut().brush.spray_options.width = appwindow.penssidebar().brush_page().width_spinbutton().value();
+                    appwindow.canvas().pens().borrow_mut().brush.spray_options.stroke_color = Some(appwindow.penssidebar().brush_page().colorpicker().current_color());
+                },
+                "highlighter" => {
+                    appwindow.canvas().pens().borrow_mut().brush.style = brush::BrushStyle::Highlighter;
+                    appwindow.canvas().pens().borrow_mut().brush.highlighter_options.width = appwindow.penssidebar().brush_page().width_spinbutton().value();
+                    appwindow.canvas().pens().borrow_mut().brush.highlighter_options.stroke_color = Some(appwindow.penssidebar().brush_page().colorpicker().current_color());
+                },
+                "calligraphy" => {
+                    appwindow.canvas().pens().borrow_mut().brush.style = brush::BrushStyle::Calligraphy;
+                    appwindow.canvas().pens().borrow_mut().brush.calligraphy_options.width = appwindow.penssidebar().brush_page().width_spinbutton().value();
+                    appwindow.canvas().pens().borrow_mut().brush.calligraphy_options.stroke_color = Some(appwindow.penssidebar().brush_page().colorpicker().current_color());
+                },
+                "hatched" => {
+                    appwindow.canvas().pens().borrow_mut().brush.style = brush::BrushStyle::Hatched;
+                    appwindow.canvas().pens().borrow_mut().brush.hatched_options.width = appwindow.penssidebar().brush_page().width_spinbutton().value();
+                    appwindow.canvas().pens().borrow_mut().brush.hatched_options.stroke_color = Some(appwindow.penssidebar().brush_page().colorpicker().current_color());
+                },
                 _ => { log::error!("set invalid state of action `brush-style`")}
             }
 
@@ -552,6 +741,21 @@ impl RnoteAppWindow {
                 "ellipse" => {
                     appwindow.canvas().pens().borrow_mut().shaper.style = shaper::ShaperStyle::Ellipse;
                 },
+                "regular-polygon" => {
+                    appwindow.canvas().pens().borrow_mut().shaper.style = shaper::ShaperStyle::RegularPolygon;
+                },
+                "star" => {
+                    appwindow.canvas().pens().borrow_mut().shaper.style = shaper::ShaperStyle::Star;
+                },
+                "arc" => {
+                    appwindow.canvas().pens().borrow_mut().shaper.style = shaper::ShaperStyle::Arc;
+                },
+                "parallelogram" => {
+                    appwindow.canvas().pens().borrow_mut().shaper.style = shaper::ShaperStyle::Parallelogram;
+                },
+                "polyline" => {
+                    appwindow.canvas().pens().borrow_mut().shaper.style = shaper::ShaperStyle::Polyline;
+                },
                 _ => { log::error!("set invalid state of action `shaper-style`")}
             }
 
@@ -596,6 +800,9 @@ impl RnoteAppWindow {
                 "rectangle" => {
                     appwindow.canvas().pens().borrow_mut().selector.style = selector::SelectorStyle::Rectangle;
                 },
+                "single" => {
+                    appwindow.canvas().pens().borrow_mut().selector.style = selector::SelectorStyle::Single;
+                },
                 _ => { log::error!("set invalid state of action `selector-style`")}
             }
 
@@ -615,6 +822,15 @@ impl RnoteAppWindow {
                 "dragproximity" => {
                     appwindow.canvas().pens().borrow_mut().tools.style = tools::ToolStyle::DragProximity;
                 },
+                "eyedropper" => {
+                    appwindow.canvas().pens().borrow_mut().tools.style = tools::ToolStyle::Eyedropper;
+                },
+                "measure" => {
+                    appwindow.canvas().pens().borrow_mut().tools.style = tools::ToolStyle::Measure;
+                },
+                "laser" => {
+                    appwindow.canvas().pens().borrow_mut().tools.style = tools::ToolStyle::Laser;
+                },
                 _ => { log::error!("set invalid state of action `tool-style`")}
             }
 
@@ -655,6 +871,13 @@ impl RnoteAppWindow {
                         appwindow.narrow_tools_toggle().set_active(true);
                         appwindow.penssidebar().sidebar_stack().set_visible_child_name("tools_page");
                     }
+                    PenStyle::TypewriterStyle => {
+                        appwindow.mainheader().typewriter_toggle().set_active(true);
+                        appwindow.narrow_typewriter_toggle().set_active(true);
+                        appwindow.penssidebar().sidebar_stack().set_visible_child_name("typewriter_page");
+                    }
+                    // Plugin pens don't have a page in the sidebar
+                    PenStyle::PluginStyle => {}
                 }
 
                 // Brush
@@ -664,6 +887,8 @@ impl RnoteAppWindow {
                     .set_value(pens.brush.textured_options.radii[0]);
                 appwindow.penssidebar().brush_page().texturedstyle_radius_y_spinbutton()
                     .set_value(pens.brush.textured_options.radii[1]);
+                appwindow.penssidebar().brush_page().texturedstyle_pressure_density_switch()
+                    .set_active(pens.brush.textured_options.pressure_density);
                 appwindow.penssidebar().brush_page().set_texturedstyle_distribution_variant(pens.brush.textured_options.distribution);
                 match pens.brush.style {
                     BrushStyle::Marker => {
@@ -687,6 +912,34 @@ impl RnoteAppWindow {
                         appwindow.penssidebar().brush_page().styleconfig_menubutton().set_sensitive(true);
                         appwindow.penssidebar().brush_page().brushstyle_image().set_icon_name(Some("pen-brush-style-textured-symbolic"));
                     },
+                    BrushStyle::Spray => {
+                        appwindow.penssidebar().brush_page().brushstyle_listbox().select_row(Some(&appwindow.penssidebar().brush_page().brushstyle_spray_row()));
+                        appwindow.penssidebar().brush_page().width_spinbutton().set_value(pens.brush.spray_options.width);
+                        appwindow.penssidebar().brush_page().colorpicker().set_current_color(pens.brush.spray_options.stroke_color);
+                        appwindow.penssidebar().brush_page().styleconfig_menubutton().set_sensitive(false);
+                        appwindow.penssidebar().brush_page().brushstyle_image().set_icon_name(Some("pen-brush-style-spray-symbolic"));
+                    },
+                    BrushStyle::Highlighter => {
+                        appwindow.penssidebar().brush_page().brushstyle_listbox().select_row(Some(&appwindow.penssidebar().brush_page().brushstyle_highlighter_row()));
+                        appwindow.penssidebar().brush_page().width_spinbutton().set_value(pens.brush.highlighter_options.width);
+                        appwindow.penssidebar().brush_page().colorpicker().set_current_color(pens.brush.highlighter_options.stroke_color);
+                        appwindow.penssidebar().brush_page().styleconfig_menubutton().set_sensitive(false);
+                        appwindow.penssidebar().brush_page().brushstyle_image().set_icon_name(Some("pen-brush-style-highlighter-symbolic"));
+                    },
+                    BrushStyle::Calligraphy => {
+                        appwindow.penssidebar().brush_page().brushstyle_listbox().select_row(Some(&appwindow.penssidebar().brush_page().brushstyle_calligraphy_row()));
+                        appwindow.penssidebar().brush_page().width_spinbutton().set_value(pens.brush.calligraphy_options.width);
+                        appwindow.penssidebar().brush_page().colorpicker().set_current_color(pens.brush.calligraphy_options.stroke_color);
+                        appwindow.penssidebar().brush_page().styleconfig_menubutton().set_sensitive(false);
+                        appwindow.penssidebar().brush_page().brushstyle_image().set_icon_name(Some("pen-brush-style-calligraphy-symbolic"));
+                    },
+                    BrushStyle::Hatched => {
+                        appwindow.penssidebar().brush_page().brushstyle_listbox().select_row(Some(&appwindow.penssidebar().brush_page().brushstyle_hatched_row()));
+                        appwindow.penssidebar().brush_page().width_spinbutton().set_value(pens.brush.hatched_options.width);
+                        appwindow.penssidebar().brush_page().colorpicker().set_current_color(pens.brush.hatched_options.stroke_color);
+                        appwindow.penssidebar().brush_page().styleconfig_menubutton().set_sensitive(false);
+                        appwindow.penssidebar().brush_page().brushstyle_image().set_icon_name(Some("pen-brush-style-hatched-symbolic"));
+                    },
                 }
 
                 // Shaper
@@ -702,6 +955,20 @@ impl RnoteAppWindow {
                 appwindow.penssidebar().shaper_page()
                     .roughconfig_multistroke_switch()
                     .set_active(!pens.shaper.rough_options.disable_multistroke);
+                appwindow.penssidebar().shaper_page()
+                    .set_roughconfig_fillstyle_variant(pens.shaper.rough_options.fill_style);
+                appwindow.penssidebar().shaper_page()
+                    .roughconfig_hachureangle_spinbutton()
+                    .set_value(pens.shaper.rough_options.hachure_angle);
+                appwindow.penssidebar().shaper_page()
+                    .roughconfig_hachuregap_spinbutton()
+                    .set_value(pens.shaper.rough_options.hachure_gap);
+                appwindow.penssidebar().shaper_page()
+                    .roughconfig_fillweight_spinbutton()
+                    .set_value(pens.shaper.rough_options.fill_weight);
+                appwindow.penssidebar().shaper_page()
+                    .roughconfig_multistrokefill_switch()
+                    .set_active(!pens.shaper.rough_options.disable_multistroke_fill);
 
                 match pens.shaper.style {
                     ShaperStyle::Line => {
@@ -713,6 +980,21 @@ impl RnoteAppWindow {
                     ShaperStyle::Ellipse => {
                         appwindow.penssidebar().shaper_page().ellipse_toggle().set_active(true);
                     }
+                    ShaperStyle::RegularPolygon => {
+                        appwindow.penssidebar().shaper_page().regular_polygon_toggle().set_active(true);
+                    }
+                    ShaperStyle::Star => {
+                        appwindow.penssidebar().shaper_page().star_toggle().set_active(true);
+                    }
+                    ShaperStyle::Arc => {
+                        appwindow.penssidebar().shaper_page().arc_toggle().set_active(true);
+                    }
+                    ShaperStyle::Parallelogram => {
+                        appwindow.penssidebar().shaper_page().parallelogram_toggle().set_active(true);
+                    }
+                    ShaperStyle::Polyline => {
+                        appwindow.penssidebar().shaper_page().polyline_toggle().set_active(true);
+                    }
                 }
                 match pens.shaper.drawstyle {
                     ShaperDrawStyle::Smooth => {
@@ -728,19 +1010,63 @@ impl RnoteAppWindow {
                         appwindow.penssidebar().shaper_page().fill_colorpicker().set_current_color(pens.shaper.rough_options.fill_color);
                     },
                 }
+                appwindow.penssidebar().shaper_page().fill_evenodd_toggle().set_active(pens.shaper.smooth_options.fill_rule == FillRule::EvenOdd);
+                appwindow.penssidebar().shaper_page().fill_textured_toggle().set_active(pens.shaper.smooth_options.fill_textured);
                 // Eraser
                 appwindow.penssidebar().eraser_page().width_spinbutton().set_value(pens.eraser.width);
+                match pens.eraser.style {
+                    EraserStyle::TrashCollidingStrokes => appwindow.penssidebar().eraser_page().trash_strokes_toggle().set_active(true),
+                    EraserStyle::SplitCollidingStrokes => appwindow.penssidebar().eraser_page().split_strokes_toggle().set_active(true),
+                }
+                match pens.eraser.shape {
+                    EraserShape::Square => appwindow.penssidebar().eraser_page().shape_square_toggle().set_active(true),
+                    EraserShape::Circle => appwindow.penssidebar().eraser_page().shape_circle_toggle().set_active(true),
+                    EraserShape::MotionPath => appwindow.penssidebar().eraser_page().shape_motion_path_toggle().set_active(true),
+                }
 
                 // Selector
                 match pens.selector.style {
                     SelectorStyle::Polygon => appwindow.penssidebar().selector_page().selectorstyle_polygon_toggle().set_active(true),
                     SelectorStyle::Rectangle => appwindow.penssidebar().selector_page().selectorstyle_rect_toggle().set_active(true),
+                    SelectorStyle::Single => appwindow.penssidebar().selector_page().selectorstyle_single_toggle().set_active(true),
                 }
+                appwindow.penssidebar().selector_page().selection_stamp_toggle().set_active(pens.selector.stamp);
+                appwindow.penssidebar().selector_page().duplicationconfig_offset_x_spinbutton().set_value(pens.selector.duplication_offset[0]);
+                appwindow.penssidebar().selector_page().duplicationconfig_offset_y_spinbutton().set_value(pens.selector.duplication_offset[1]);
 
                 // Tools
                 match pens.tools.style {
                     ToolStyle::ExpandSheet => appwindow.penssidebar().tools_page().toolstyle_expandsheet_toggle().set_active(true),
                     ToolStyle::DragProximity => appwindow.penssidebar().tools_page().toolstyle_dragproximity_toggle().set_active(true),
+                    ToolStyle::Eyedropper => appwindow.penssidebar().tools_page().toolstyle_eyedropper_toggle().set_active(true),
+                    ToolStyle::Measure => appwindow.penssidebar().tools_page().toolstyle_measure_toggle().set_active(true),
+                    ToolStyle::Laser => appwindow.penssidebar().tools_page().toolstyle_laser_toggle().set_active(true),
+                }
+                appwindow.penssidebar().tools_page().set_measure_unit(pens.tools.measure_tool.unit);
+                appwindow.penssidebar().tools_page().laser_timeout_spinbutton().set_value(pens.tools.laser_tool.timeout);
+
+                // Typewriter
+                appwindow.penssidebar().typewriter_page().set_font_family(&pens.typewriter.text_style.font_family);
+                appwindow.penssidebar().typewriter_page().font_size_spinbutton().set_value(pens.typewriter.text_style.font_size);
+                appwindow.penssidebar().typewriter_page().set_heading_level(pens.typewriter.text_style.heading_level);
+                appwindow.penssidebar().typewriter_page().bold_toggle().set_active(pens.typewriter.text_style.bold);
+                appwindow.penssidebar().typewriter_page().italic_toggle().set_active(pens.typewriter.text_style.italic);
+                appwindow.penssidebar().typewriter_page().underline_toggle().set_active(pens.typewriter.text_style.underline);
+                appwindow.penssidebar().typewriter_page().set_alignment(pens.typewriter.text_style.alignment);
+                appwindow.penssidebar().typewriter_page().colorpicker().set_current_color(Some(pens.typewriter.text_style.color));
+
+                // Palettes, the one of the sheet first
+                {
+                    let sheet = appwindow.canvas().sheet();
+                    let sheet = sheet.borrow();
+                    let palettes = appwindow.canvas().palettes();
+                    let palettes = palettes.borrow();
+                    let shown_palettes = sheet.palette.iter().chain(palettes.palettes().iter()).collect::<Vec<&Palette>>();
+
+                    appwindow.penssidebar().brush_page().colorpicker().set_palettes(&shown_palettes);
+                    appwindow.penssidebar().shaper_page().stroke_colorpicker().set_palettes(&shown_palettes);
+                    appwindow.penssidebar().shaper_page().fill_colorpicker().set_palettes(&shown_palettes);
+                    appwindow.penssidebar().typewriter_page().colorpicker().set_palettes(&shown_palettes);
                 }
 
                 // Settings panel
@@ -761,15 +1087,238 @@ impl RnoteAppWindow {
         // Duplicate Selection
         action_selection_duplicate.connect_activate(
             clone!(@weak self as appwindow => move |_action_selection_duplicate, _| {
-                appwindow.canvas().sheet().borrow_mut().strokes_state.duplicate_selection(
-                                    appwindow.canvas().zoom()
-                );
+                let duplication_offset = appwindow.canvas().pens().borrow().selector.duplication_offset;
+                appwindow.canvas().sheet().borrow_mut().strokes_state.duplicate_selection(duplication_offset);
+
+                appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                appwindow.canvas().regenerate_content(false, true);
+            }),
+        );
+
+        // Mirror the selection horizontally
+        action_selection_mirror_horizontal.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_mirror_horizontal, _| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.mirror_selection_horizontal();
+
+                appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                appwindow.canvas().regenerate_content(false, true);
+            }),
+        );
+
+        // Mirror the selection vertically
+        action_selection_mirror_vertical.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_mirror_vertical, _| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.mirror_selection_vertical();
 
                 appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
                 appwindow.canvas().regenerate_content(false, true);
             }),
         );
 
+        // Group the selection
+        action_selection_group.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_group, _| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.group_selection();
+
+                appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                appwindow.canvas().queue_draw();
+            }),
+        );
+
+        // Ungroup the selection
+        action_selection_ungroup.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_ungroup, _| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.ungroup_selection();
+
+                appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                appwindow.canvas().queue_draw();
+            }),
+        );
+
+        // Restyle the selection with the current brush and shaper options
+        action_selection_restyle.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_restyle, _| {
+                let modification = StrokeOptionsModification {
+                    brush: Some(appwindow.canvas().pens().borrow().brush.clone()),
+                    shaper: Some(appwindow.canvas().pens().borrow().shaper.clone()),
+                    ..StrokeOptionsModification::default()
+                };
+
+                let selection_keys = appwindow.canvas().sheet().borrow().strokes_state.selection_keys_as_rendered();
+                appwindow.canvas().sheet().borrow_mut().strokes_state.modify_stroke_options(&selection_keys, &modification);
+
+                appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                appwindow.canvas().regenerate_content(false, true);
+            }),
+        );
+
+        // Align the selected strokes
+        action_selection_align.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_align, target| {
+                let alignment = match target.unwrap().str().unwrap() {
+                    "left" => StrokesAlignment::Left,
+                    "right" => StrokesAlignment::Right,
+                    "top" => StrokesAlignment::Top,
+                    "bottom" => StrokesAlignment::Bottom,
+                    "center-horizontal" => StrokesAlignment::CenterHorizontal,
+                    "center-vertical" => StrokesAlignment::CenterVertical,
+                    _ => {
+                        log::error!("invalid target of action `selection-align`");
+                        return;
+                    }
+                };
+                appwindow.canvas().sheet().borrow_mut().strokes_state.align_selection(alignment);
+
+                appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                appwindow.canvas().regenerate_content(false, true);
+            }),
+        );
+
+        // Distribute the selected strokes evenly
+        action_selection_distribute.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_distribute, target| {
+                let distribution = match target.unwrap().str().unwrap() {
+                    "horizontal" => StrokesDistribution::Horizontal,
+                    "vertical" => StrokesDistribution::Vertical,
+                    _ => {
+                        log::error!("invalid target of action `selection-distribute`");
+                        return;
+                    }
+                };
+                appwindow.canvas().sheet().borrow_mut().strokes_state.distribute_selection(distribution);
+
+                appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                appwindow.canvas().regenerate_content(false, true);
+            }),
+        );
+
+        // Change the z-order of the selected strokes
+        action_selection_z_order.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_z_order, target| {
+                let z_order = match target.unwrap().str().unwrap() {
+                    "raise" => StrokesZOrder::Raise,
+                    "lower" => StrokesZOrder::Lower,
+                    "bring-to-front" => StrokesZOrder::BringToFront,
+                    "send-to-back" => StrokesZOrder::SendToBack,
+                    _ => {
+                        log::error!("invalid target of action `selection-z-order`");
+                        return;
+                    }
+                };
+                appwindow.canvas().sheet().borrow_mut().strokes_state.change_selection_z_order(z_order);
+
+                appwindow.canvas().regenerate_content(false, true);
+            }),
+        );
+
+        // Remove the fill of the selected shapes
+        action_selection_remove_fill.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_remove_fill, _| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.set_fill_color_selection(None);
+
+                appwindow.canvas().regenerate_content(false, true);
+            }),
+        );
+
+        // Attach a link to the selected strokes
+        action_selection_link.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_link, _| {
+                dialogs::dialog_selection_link(&appwindow);
+            }),
+        );
+
+        // Tag the selected strokes
+        action_selection_tags.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_tags, _| {
+                dialogs::dialog_selection_tags(&appwindow);
+            }),
+        );
+
+        // Filter the drawn strokes by their tags
+        action_tag_filter.connect_activate(
+            clone!(@weak self as appwindow => move |_action_tag_filter, _| {
+                dialogs::dialog_tag_filter(&appwindow);
+            }),
+        );
+
+        // Draw all strokes again
+        action_clear_tag_filter.connect_activate(
+            clone!(@weak self as appwindow => move |_action_clear_tag_filter, _| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.set_tag_filter(None);
+                appwindow.canvas().queue_draw();
+            }),
+        );
+
+        // Insert a math expression, or edit the selected one
+        action_insert_math.connect_activate(
+            clone!(@weak self as appwindow => move |_action_insert_math, _| {
+                dialogs::dialog_math(&appwindow);
+            }),
+        );
+
+        // Insert a todo checkbox, or edit the label of the selected one
+        action_insert_checkbox.connect_activate(
+            clone!(@weak self as appwindow => move |_action_insert_checkbox, _| {
+                dialogs::dialog_checkbox(&appwindow);
+            }),
+        );
+
+        // List the open todos of the document
+        action_open_todos.connect_activate(
+            clone!(@weak self as appwindow => move |_action_open_todos, _| {
+                dialogs::dialog_open_todos(&appwindow);
+            }),
+        );
+
+        // Crop the selected image
+        action_selection_crop_image.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_crop_image, _| {
+                dialogs::dialog_crop_image(&appwindow);
+            }),
+        );
+
+        // Convert the selected handwriting into text. Recognizing runs on a separate thread, the selection is replaced when it finishes
+        action_selection_recognize_text.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_recognize_text, _| {
+                let keys = appwindow.canvas().sheet().borrow().strokes_state.selection_keys_as_rendered();
+                let ink = appwindow.canvas().sheet().borrow().strokes_state.ink_for_keys(&keys);
+                if ink.is_empty() {
+                    return;
+                }
+
+                let receiver = handwriting::recognize_async(appwindow.canvas().handwriting_backend(), ink);
+
+                let main_cx = glib::MainContext::default();
+                main_cx.spawn_local(clone!(@weak appwindow => async move {
+                    let recognized = match receiver.await {
+                        Ok(Ok(recognized)) => recognized,
+                        Ok(Err(e)) => {
+                            log::error!("recognizing the handwriting failed, {}", e);
+                            adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Recognizing the handwriting failed").to_variant()));
+                            return;
+                        }
+                        Err(e) => {
+                            log::error!("awaiting the recognized text failed, {}", e);
+                            return;
+                        }
+                    };
+
+                    let text_style = appwindow.canvas().pens().borrow().typewriter.text_style.clone();
+                    let text_key = appwindow.canvas().sheet().borrow_mut().strokes_state.replace_w_recognized_text(&keys, &recognized, text_style);
+
+                    if let Some(text_key) = text_key {
+                        appwindow.canvas().sheet().borrow_mut().strokes_state.set_selected(text_key, true);
+                        appwindow.canvas().set_unsaved_changes(true);
+                    } else {
+                        adw::prelude::ActionGroupExt::activate_action(&appwindow, "text-toast", Some(&gettext("No text recognized").to_variant()));
+                    }
+
+                    appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                    appwindow.canvas().regenerate_content(false, true);
+                }));
+            }),
+        );
+
         // select all strokes
         action_selection_select_all.connect_activate(
             clone!(@weak self as appwindow => move |_action_selection_select_all, _| {
@@ -792,23 +1341,157 @@ impl RnoteAppWindow {
             }),
         );
 
+        // Snapping
+        action_snap_to_grid.connect_activate(clone!(@weak self as appwindow => move |action_snap_to_grid, _target| {
+            let state = !action_snap_to_grid.state().unwrap().get::<bool>().unwrap();
+
+            appwindow.canvas().pens().borrow_mut().snapping.snap_to_grid = state;
+            action_snap_to_grid.change_state(&state.to_variant());
+        }));
+
+        action_snap_to_strokes.connect_activate(clone!(@weak self as appwindow => move |action_snap_to_strokes, _target| {
+            let state = !action_snap_to_strokes.state().unwrap().get::<bool>().unwrap();
+
+            appwindow.canvas().pens().borrow_mut().snapping.snap_to_strokes = state;
+            action_snap_to_strokes.change_state(&state.to_variant());
+        }));
+
+        // Drafting guides
+        action_guide.connect_activate(clone!(@weak self as appwindow => move |action_guide, target| {
+            let target = target.unwrap().get::<String>().unwrap();
+            let kind = match target.as_str() {
+                "none" => None,
+                "ruler" => Some(GuideKind::Ruler),
+                "set-square" => Some(GuideKind::SetSquare),
+                _ => {
+                    log::error!("invalid target for action `guide`, `{}`", target);
+                    return;
+                }
+            };
+
+            let viewport_center = appwindow.canvas().viewport_in_sheet_coords().center().coords;
+            appwindow.canvas().pens().borrow_mut().guides.show(kind, viewport_center);
+            action_guide.change_state(&target.to_variant());
+
+            appwindow.canvas().queue_draw();
+        }));
+
+        // Compass
+        action_compass.connect_activate(clone!(@weak self as appwindow => move |action_compass, _target| {
+            let state = !action_compass.state().unwrap().get::<bool>().unwrap();
+
+            appwindow.canvas().pens().borrow_mut().brush.compass.set_enabled(state);
+            action_compass.change_state(&state.to_variant());
+
+            appwindow.canvas().queue_draw();
+        }));
+
+        action_compass_show_radius.connect_activate(clone!(@weak self as appwindow => move |action_compass_show_radius, _target| {
+            let state = !action_compass_show_radius.state().unwrap().get::<bool>().unwrap();
+
+            appwindow.canvas().pens().borrow_mut().brush.compass.show_radius = state;
+            action_compass_show_radius.change_state(&state.to_variant());
+        }));
+
+        // Presentation
+        action_presentation.connect_activate(clone!(@weak self as appwindow => move |action_presentation, _target| {
+            let state = !action_presentation.state().unwrap().get::<bool>().unwrap();
+
+            if state {
+                appwindow.fullscreen();
+                appwindow.canvas().start_presentation();
+            } else {
+                appwindow.canvas().stop_presentation();
+                appwindow.unfullscreen();
+            }
+            action_presentation.change_state(&state.to_variant());
+        }));
+
+        // Record audio note
+        action_record_audio_note.connect_activate(clone!(@weak self as appwindow => move |action_record_audio_note, _target| {
+            let state = !action_record_audio_note.state().unwrap().get::<bool>().unwrap();
+
+            if state {
+                if let Err(e) = appwindow.audionotes().borrow_mut().start_recording() {
+                    log::error!("starting to record the audio note failed with Err {}", e);
+                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Recording the audio note failed").to_variant()));
+                    return;
+                }
+            } else {
+                let audio_note = appwindow.audionotes().borrow_mut().stop_recording();
+
+                match audio_note {
+                    Ok(Some(audio_note)) => {
+                        appwindow.canvas().sheet().borrow_mut().strokes_state.add_audio_note(audio_note);
+                        appwindow.canvas().set_unsaved_changes(true);
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        log::error!("stopping to record the audio note failed with Err {}", e);
+                        adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Recording the audio note failed").to_variant()));
+                    }
+                }
+            }
+            action_record_audio_note.change_state(&state.to_variant());
+        }));
+
+        // Stop the playback of the audio note
+        action_stop_audio_note.connect_activate(clone!(@weak self as appwindow => move |_, _| {
+            appwindow.audionotes().borrow().stop_playback();
+        }));
+
+        // Bookmarks
+        action_add_bookmark.connect_activate(clone!(@weak self as appwindow => move |_, _| {
+            appwindow.canvas().add_bookmark("");
+            adw::prelude::ActionGroupExt::activate_action(&appwindow, "text-toast", Some(&gettext("Added a bookmark").to_variant()));
+        }));
+
+        action_jump_to_bookmark.connect_activate(clone!(@weak self as appwindow => move |_, target| {
+            let name = target.unwrap().str().unwrap();
+            let index = appwindow.canvas().sheet().borrow().bookmark_index(name);
+
+            if !index.map_or(false, |index| appwindow.canvas().jump_to_bookmark(index)) {
+                log::error!("jumping to the bookmark `{}` failed, it does not exist", name);
+            }
+        }));
+
+        action_next_bookmark.connect_activate(clone!(@weak self as appwindow => move |_, _| {
+            if !appwindow.canvas().jump_to_next_bookmark() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "text-toast", Some(&gettext("No further bookmarks").to_variant()));
+            }
+        }));
+
+        action_previous_bookmark.connect_activate(clone!(@weak self as appwindow => move |_, _| {
+            if !appwindow.canvas().jump_to_previous_bookmark() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "text-toast", Some(&gettext("No previous bookmarks").to_variant()));
+            }
+        }));
+
         // Clear sheet
         action_clear_sheet.connect_activate(clone!(@weak self as appwindow => move |_, _| {
             dialogs::dialog_clear_sheet(&appwindow);
         }));
 
-        // Undo stroke
+        // Undo
         action_undo_stroke.connect_activate(clone!(@weak self as appwindow => move |_,_| {
-            appwindow.canvas().sheet().borrow_mut().strokes_state.undo_last_stroke();
-            appwindow.canvas().resize_sheet_autoexpand();
-            appwindow.canvas().update_background_rendernode(true);
+            let undone = appwindow.canvas().sheet().borrow_mut().strokes_state.undo();
+            if undone {
+                appwindow.canvas().set_unsaved_changes(true);
+                appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                appwindow.canvas().resize_sheet_autoexpand();
+                appwindow.canvas().regenerate_content(false, true);
+            }
         }));
 
-        // Redo stroke
+        // Redo
         action_redo_stroke.connect_activate(clone!(@weak self as appwindow => move |_,_| {
-            appwindow.canvas().sheet().borrow_mut().strokes_state.redo_last_stroke();
-            appwindow.canvas().resize_sheet_autoexpand();
-            appwindow.canvas().update_background_rendernode(true);
+            let redone = appwindow.canvas().sheet().borrow_mut().strokes_state.redo();
+            if redone {
+                appwindow.canvas().set_unsaved_changes(true);
+                appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                appwindow.canvas().resize_sheet_autoexpand();
+                appwindow.canvas().regenerate_content(false, true);
+            }
         }));
 
         // Zoom reset
@@ -844,6 +1527,68 @@ impl RnoteAppWindow {
             appwindow.canvas().resize_sheet_autoexpand();
         }));
 
+        // Insert a page like the current one after it
+        action_page_insert.connect_activate(clone!(@weak self as appwindow => move |_,_| {
+            if let Some(index) = appwindow.canvas().current_page_index() {
+                let page = appwindow.canvas().sheet().borrow().page_layout.as_ref().and_then(|page_layout| page_layout.get(index)).unwrap_or_default();
+                let zoom = appwindow.canvas().zoom();
+
+                appwindow.canvas().sheet().borrow_mut().insert_page(index + 1, page, zoom);
+                appwindow.canvas().update_for_changed_pages();
+            }
+        }));
+
+        // Remove the current page together with its strokes
+        action_page_remove.connect_activate(clone!(@weak self as appwindow => move |_,_| {
+            if let Some(index) = appwindow.canvas().current_page_index() {
+                let zoom = appwindow.canvas().zoom();
+
+                if appwindow.canvas().sheet().borrow_mut().remove_page(index, zoom).is_some() {
+                    appwindow.canvas().update_for_changed_pages();
+                }
+            }
+        }));
+
+        // Move the current page up
+        action_page_move_up.connect_activate(clone!(@weak self as appwindow => move |_,_| {
+            if let Some(index) = appwindow.canvas().current_page_index() {
+                let zoom = appwindow.canvas().zoom();
+
+                if index > 0 && appwindow.canvas().sheet().borrow_mut().move_page(index, index - 1, zoom) {
+                    appwindow.canvas().update_for_changed_pages();
+                }
+            }
+        }));
+
+        // Move the current page down
+        action_page_move_down.connect_activate(clone!(@weak self as appwindow => move |_,_| {
+            if let Some(index) = appwindow.canvas().current_page_index() {
+                let zoom = appwindow.canvas().zoom();
+
+                if appwindow.canvas().sheet().borrow_mut().move_page(index, index + 1, zoom) {
+                    appwindow.canvas().update_for_changed_pages();
+                }
+            }
+        }));
+
+        // Switch the orientation of the current page
+        action_page_rotate.connect_activate(clone!(@weak self as appwindow => move |_,_| {
+            if let Some(index) = appwindow.canvas().current_page_index() {
+                let page = appwindow.canvas().sheet().borrow().page_layout.as_ref().and_then(|page_layout| page_layout.get(index));
+
+                if let Some(page) = page {
+                    let orientation = match page.orientation() {
+                        Orientation::Portrait => Orientation::Landscape,
+                        Orientation::Landscape => Orientation::Portrait,
+                    };
+                    let zoom = appwindow.canvas().zoom();
+
+                    appwindow.canvas().sheet().borrow_mut().set_page(index, page.with_orientation(orientation), zoom);
+                    appwindow.canvas().update_for_changed_pages();
+                }
+            }
+        }));
+
         // New sheet
         action_new_sheet.connect_activate(clone!(@weak self as appwindow => move |_, _| {
             dialogs::dialog_new_sheet(&appwindow);
@@ -867,8 +1612,9 @@ impl RnoteAppWindow {
 
             if let Some(output_file) = appwindow.application().unwrap().downcast::<RnoteApp>().unwrap().output_file() {
                 match output_file.basename() {
-                    Some(basename) => {
-                        match appwindow.canvas().sheet().borrow().save_sheet_as_rnote_bytes(&basename.to_string_lossy()) {
+                    Some(_) => {
+                        let res = appwindow.canvas().sheet().borrow_mut().save_sheet_as_rnote_bytes();
+                        match res {
                             Ok(bytes) => {
                                 if let Err(e) = utils::replace_file_async(bytes, &output_file) {
                                     log::error!("saving sheet as .rnote failed, replace_file_async failed with Err {}", e);
@@ -910,14 +1656,15 @@ impl RnoteAppWindow {
                 let cx = print_cx.cairo_context();
 
                 if let Err(e) = || -> Result<(), anyhow::Error> {
+                    let page_bounds = pages_bounds[page_nr as usize];
+
+                    // Pages of a page layout can have different sizes
                     let print_zoom = {
-                        let width_scale = print_cx.width() / appwindow.canvas().sheet().borrow().format.width;
-                        let height_scale = print_cx.height() / appwindow.canvas().sheet().borrow().format.height;
+                        let width_scale = print_cx.width() / page_bounds.extents()[0];
+                        let height_scale = print_cx.height() / page_bounds.extents()[1];
                         width_scale.min(height_scale)
                     };
 
-                    let page_bounds = pages_bounds[page_nr as usize];
-
                     let page_svgs = appwindow.canvas().sheet().borrow().gen_svgs_for_viewport(page_bounds)?;
 
                     cx.scale(print_zoom, print_zoom);
@@ -966,6 +1713,15 @@ impl RnoteAppWindow {
             dialogs::dialog_import_file(&appwindow);
         }));
 
+        // Attachments
+        action_attach_file.connect_activate(clone!(@weak self as appwindow => move |_,_| {
+            dialogs::dialog_attach_file(&appwindow);
+        }));
+
+        action_extract_attachments.connect_activate(clone!(@weak self as appwindow => move |_,_| {
+            dialogs::dialog_extract_attachments(&appwindow);
+        }));
+
         // Export selection as SVG
         action_export_selection_as_svg.connect_activate(
             clone!(@weak self as appwindow => move |_,_| {
@@ -983,6 +1739,16 @@ impl RnoteAppWindow {
             dialogs::dialog_export_sheet_as_pdf(&appwindow);
         }));
 
+        // Export sheet onto the imported PDF
+        action_export_sheet_as_annotated_pdf.connect_activate(clone!(@weak self as appwindow => move |_,_| {
+            dialogs::dialog_export_sheet_as_annotated_pdf(&appwindow);
+        }));
+
+        // Export sheet as bitmap images
+        action_export_sheet_as_bitmap.connect_activate(clone!(@weak self as appwindow => move |_,_| {
+            dialogs::dialog_export_sheet_as_bitmap(&appwindow);
+        }));
+
         // Export sheet as Xopp
         action_export_sheet_as_xopp.connect_activate(
             clone!(@weak self as appwindow => move |_,_| {
@@ -992,37 +1758,87 @@ impl RnoteAppWindow {
 
         // Clipboard copy selection
         action_clipboard_copy_selection.connect_activate(clone!(@weak self as appwindow => move |_, _| {
-        let selection_svgs = appwindow.canvas().sheet().borrow().strokes_state.gen_svgs_selection();
-        match selection_svgs {
-            Ok(selection_svgs) => {
-                let mut svg_data = selection_svgs
-                    .iter()
-                    .map(|svg| svg.svg_data.as_str())
-                    .collect::<Vec<&str>>()
-                    .join("\n");
-
-                if let Some(selection_bounds) = appwindow.canvas().sheet().borrow().strokes_state.gen_selection_bounds() {
-                    svg_data = compose::wrap_svg_root(svg_data.as_str(), Some(selection_bounds), Some(selection_bounds), true);
-
-                    let svg_content_provider = gdk::ContentProvider::for_bytes("image/svg+xml", &glib::Bytes::from(svg_data.as_bytes()));
-                    match appwindow.clipboard().set_content(Some(&svg_content_provider)) {
-                        Ok(_) => {
-                        }
-                        Err(e) => {
-                            log::error!("copy selection into clipboard failed in clipboard().set_content(), {}", e);
-                        }
-                    }
-                }
+        let svg_data = match appwindow.canvas().sheet().borrow().strokes_state.export_selection_as_svg_string() {
+            Ok(Some(svg_data)) => svg_data,
+            Ok(None) => return,
+            Err(e) => {
+                log::error!("copy selection into clipboard failed in export_selection_as_svg_string(), {}", e);
+                return;
+            }
+        };
+
+        let mut content_providers = vec![gdk::ContentProvider::for_bytes("image/svg+xml", &glib::Bytes::from(svg_data.as_bytes()))];
+
+        // A bitmap for apps that can't paste svgs
+        match appwindow.canvas().sheet().borrow().strokes_state.export_selection_as_png_bytes(StrokesClipboard::PNG_ZOOM) {
+            Ok(Some(png_bytes)) => {
+                content_providers.push(gdk::ContentProvider::for_bytes("image/png", &glib::Bytes::from_owned(png_bytes)));
+            }
+            Ok(None) => {}
+            Err(e) => {
+                log::error!("exporting the selection as png for the clipboard failed in export_selection_as_png_bytes(), {}", e);
+            }
+        }
+
+        // The engine native format, to paste the strokes with full fidelity
+        match appwindow.canvas().sheet().borrow().strokes_state.copy_selection().map(|clipboard| clipboard.to_json_bytes()) {
+            Some(Ok(clipboard_bytes)) => {
+                content_providers.push(gdk::ContentProvider::for_bytes(StrokesClipboard::MIME_TYPE, &glib::Bytes::from_owned(clipboard_bytes)));
+            }
+            Some(Err(e)) => {
+                log::error!("serializing the selection for the clipboard failed in to_json_bytes(), {}", e);
+            }
+            None => {}
+        }
+
+        let content_provider = gdk::ContentProvider::new_union(&content_providers);
+        match appwindow.clipboard().set_content(Some(&content_provider)) {
+            Ok(_) => {
             }
             Err(e) => {
-                log::error!("copy selection into clipboard failed in gen_svg_selection(), {}", e);
+                log::error!("copy selection into clipboard failed in clipboard().set_content(), {}", e);
             }
         }
     }));
 
+        // Clipboard cut selection
+        action_clipboard_cut_selection.connect_activate(clone!(@weak self as appwindow => move |_, _| {
+            adw::prelude::ActionGroupExt::activate_action(&appwindow, "clipboard-copy-selection", None);
+            adw::prelude::ActionGroupExt::activate_action(&appwindow, "selection-trash", None);
+        }));
+
         // Clipboard paste as selection
         action_clipboard_paste_selection.connect_activate(clone!(@weak self as appwindow => move |_, _| {
         let clipboard = appwindow.clipboard();
+
+            // Prefer the engine native format
+            if clipboard.formats().contain_mime_type(StrokesClipboard::MIME_TYPE) {
+                clipboard.read_async(&[StrokesClipboard::MIME_TYPE], glib::PRIORITY_DEFAULT, None::<&gio::Cancellable>, clone!(@weak appwindow => move |read_res| {
+                    match read_res {
+                        Ok((input_stream, _mime_type)) => {
+                            let output_stream = gio::MemoryOutputStream::new_resizable();
+
+                            output_stream.splice_async(&input_stream, gio::OutputStreamSpliceFlags::CLOSE_SOURCE | gio::OutputStreamSpliceFlags::CLOSE_TARGET, glib::PRIORITY_DEFAULT, None::<&gio::Cancellable>, clone!(@weak appwindow, @strong output_stream => move |splice_res| {
+                                match splice_res {
+                                    Ok(_) => {
+                                        appwindow.load_in_strokes_clipboard_bytes(&output_stream.steal_as_bytes()).unwrap_or_else(|e| {
+                                            log::error!("failed to paste clipboard as strokes, load_in_strokes_clipboard_bytes() returned Err, {}", e);
+                                        });
+                                    }
+                                    Err(e) => {
+                                        log::error!("failed to paste clipboard as strokes, reading the clipboard content failed with Err, {}", e);
+                                    }
+                                }
+                            }));
+                        }
+                        Err(e) => {
+                            log::error!("failed to paste clipboard as strokes, read_async() returned Err, {}", e);
+                        }
+                    }
+                }));
+                return;
+            }
+
             for mime_type in clipboard.formats().mime_types() {
                     match mime_type.as_str() {
                         "image/svg+xml" => {
@@ -1085,6 +1901,10 @@ impl RnoteAppWindow {
 
         app.set_accels_for_action("win.close-active", &["<Ctrl>w"]);
         app.set_accels_for_action("win.fullscreen", &["F11"]);
+        app.set_accels_for_action("win.presentation", &["F5"]);
+        app.set_accels_for_action("win.add-bookmark", &["<Ctrl>b"]);
+        app.set_accels_for_action("win.next-bookmark", &["<Alt>Page_Down"]);
+        app.set_accels_for_action("win.previous-bookmark", &["<Alt>Page_Up"]);
         app.set_accels_for_action("win.keyboard-shortcuts", &["<Ctrl>question"]);
         app.set_accels_for_action("win.open-canvasmenu", &["F9"]);
         app.set_accels_for_action("win.open-appmenu", &["F10"]);
@@ -1103,8 +1923,19 @@ impl RnoteAppWindow {
         app.set_accels_for_action("win.selection-duplicate", &["<Ctrl>d"]);
         app.set_accels_for_action("win.selection-select-all", &["<Ctrl>a"]);
         app.set_accels_for_action("win.selection-deselect-all", &["Escape"]);
+        app.set_accels_for_action("win.selection-group", &["<Ctrl>g"]);
+        app.set_accels_for_action("win.selection-ungroup", &["<Ctrl><Shift>g"]);
+        app.set_accels_for_action("win.selection-link", &["<Ctrl>k"]);
+        app.set_accels_for_action("win.insert-math", &["<Ctrl>m"]);
+        app.set_accels_for_action("win.open-todos", &["<Ctrl><Shift>o"]);
+        app.set_accels_for_action("win.selection-recognize-text", &["<Ctrl><Shift>t"]);
+        app.set_accels_for_action("win.selection-z-order::raise", &["<Ctrl>Page_Up"]);
+        app.set_accels_for_action("win.selection-z-order::lower", &["<Ctrl>Page_Down"]);
+        app.set_accels_for_action("win.selection-z-order::bring-to-front", &["<Ctrl>Home"]);
+        app.set_accels_for_action("win.selection-z-order::send-to-back", &["<Ctrl>End"]);
         //app.set_accels_for_action("win.pen-style-override::eraser_style", &["d"]);
         app.set_accels_for_action("win.clipboard-copy-selection", &["<Ctrl>c"]);
+        app.set_accels_for_action("win.clipboard-cut-selection", &["<Ctrl>x"]);
         app.set_accels_for_action("win.clipboard-paste-selection", &["<Ctrl>v"]);
     }
 }
@@ -13,7 +13,10 @@ mod imp {
     use gtk4::{gio, GestureDrag, PropagationPhase, Revealer, Separator};
     use once_cell::sync::Lazy;
 
+    use crate::audionotes::RnoteAudioNotes;
     use crate::audioplayer::RnoteAudioPlayer;
+    use rnote_engine::sheet::recovery::Recovery;
+
     use crate::{
         app::RnoteApp, canvas::Canvas, config, dialogs, mainheader::MainHeader,
         penssidebar::PensSideBar, settingspanel::SettingsPanel, workspacebrowser::WorkspaceBrowser,
@@ -24,7 +27,9 @@ mod imp {
     pub struct RnoteAppWindow {
         pub app_settings: gio::Settings,
         pub audioplayer: Rc<RefCell<RnoteAudioPlayer>>,
+        pub audionotes: Rc<RefCell<RnoteAudioNotes>>,
         pub filechoosernative: Rc<RefCell<Option<FileChooserNative>>>,
+        pub recovery: Rc<RefCell<Recovery>>,
 
         pub righthanded: Cell<bool>,
         pub pen_sounds: Cell<bool>,
@@ -78,6 +83,8 @@ mod imp {
         #[template_child]
         pub narrow_tools_toggle: TemplateChild<ToggleButton>,
         #[template_child]
+        pub narrow_typewriter_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
         pub penssidebar: TemplateChild<PensSideBar>,
     }
 
@@ -86,7 +93,13 @@ mod imp {
             Self {
                 app_settings: gio::Settings::new(config::APP_ID),
                 audioplayer: Rc::new(RefCell::new(RnoteAudioPlayer::default())),
+                audionotes: Rc::new(RefCell::new(RnoteAudioNotes::default())),
                 filechoosernative: Rc::new(RefCell::new(None)),
+                recovery: Rc::new(RefCell::new(Recovery::new(
+                    glib::user_data_dir()
+                        .join(config::APP_NAME)
+                        .join("recovery"),
+                ))),
 
                 righthanded: Cell::new(true),
                 pen_sounds: Cell::new(true),
@@ -115,6 +128,7 @@ mod imp {
                 narrow_eraser_toggle: TemplateChild::<ToggleButton>::default(),
                 narrow_selector_toggle: TemplateChild::<ToggleButton>::default(),
                 narrow_tools_toggle: TemplateChild::<ToggleButton>::default(),
+                narrow_typewriter_toggle: TemplateChild::<ToggleButton>::default(),
                 penssidebar: TemplateChild::<PensSideBar>::default(),
             }
         }
@@ -188,6 +202,12 @@ mod imp {
                     adw::prelude::ActionGroupExt::activate_action(&appwindow, "pen-style", Some(&"tools_style".to_variant()));
                 }
             }));
+
+            self.narrow_typewriter_toggle.connect_toggled(clone!(@weak obj as appwindow => move |narrow_typewriter_toggle| {
+                if narrow_typewriter_toggle.is_active() {
+                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "pen-style", Some(&"typewriter_style".to_variant()));
+                }
+            }));
         }
 
         fn properties() -> &'static [glib::ParamSpec] {
@@ -434,9 +454,11 @@ use std::{
     cell::{Cell, RefCell},
     path::Path,
     rc::Rc,
+    time,
 };
 
 use adw::prelude::*;
+use gettextrs::gettext;
 use gtk4::{
     gdk, gio, glib, glib::clone, subclass::prelude::*, Application, Box, EventControllerScroll,
     EventControllerScrollFlags, FileChooserNative, GestureDrag, GestureZoom, Grid, IconTheme,
@@ -447,8 +469,9 @@ use rnote_engine::surfaceflags::SurfaceFlags;
 
 use crate::{
     app::RnoteApp,
+    audionotes::RnoteAudioNotes,
     audioplayer::RnoteAudioPlayer,
-    canvas::Canvas,
+    canvas::{Canvas, ExpandMode},
     config,
     penssidebar::PensSideBar,
     settingspanel::SettingsPanel,
@@ -456,9 +479,13 @@ use crate::{
     workspacebrowser::WorkspaceBrowser,
     {dialogs, mainheader::MainHeader},
 };
+use p2d::bounding_volume::BoundingVolume;
 use rnote_engine::{
+    sheet::bitmapexport::{BitmapExportPrefs, BitmapExportScope},
+    sheet::recovery::Recovery,
+    strokes::pdfimport::{self, PdfImportOptions},
     strokes::{bitmapimage::BitmapImage, vectorimage::VectorImage},
-    strokesstate::StateTask,
+    strokesstate::{selection_comp::StrokesClipboard, StateTask},
 };
 
 // The renderer as a global singleton
@@ -472,6 +499,8 @@ glib::wrapper! {
 impl RnoteAppWindow {
     pub const CANVAS_ZOOMGESTURE_THRESHOLD: f64 = 0.005; // Sets the delta threshold (eg. 0.01 = 1% ) when to update the canvas when doing a zoom gesture
     pub const CANVAS_ZOOM_SCROLL_STEP: f64 = 0.1; // Sets the canvas zoom scroll step in % for one unit of the event controller delta
+    pub const RECOVERY_JOURNAL_INTERVAL_SECS: u32 = 10; // How often the recovery journal is flushed while there are unsaved changes
+    pub const RECOVERY_SNAPSHOT_INTERVAL: time::Duration = time::Duration::from_secs(180); // How old the recovery snapshot gets before a new one is written
 
     pub fn new(app: &Application) -> Self {
         glib::Object::new(&[("application", app)]).expect("Failed to create `RnoteAppWindow`.")
@@ -486,6 +515,12 @@ impl RnoteAppWindow {
 
         // Setting all gstreamer pipelines state to Null
         self.audioplayer().borrow_mut().set_states_null();
+        self.audionotes().borrow_mut().set_states_null();
+
+        // The recovery is only for sessions that crashed
+        if let Err(e) = self.recovery().borrow_mut().discard() {
+            log::error!("discarding the recovery failed with Err {}", e);
+        }
 
         // Closing the state tasks channel receiver
         if let Err(e) = self
@@ -623,6 +658,12 @@ impl RnoteAppWindow {
             .get()
     }
 
+    pub fn narrow_typewriter_toggle(&self) -> ToggleButton {
+        imp::RnoteAppWindow::from_instance(self)
+            .narrow_typewriter_toggle
+            .get()
+    }
+
     pub fn penssidebar(&self) -> PensSideBar {
         imp::RnoteAppWindow::from_instance(self).penssidebar.get()
     }
@@ -631,6 +672,14 @@ impl RnoteAppWindow {
         imp::RnoteAppWindow::from_instance(self).audioplayer.clone()
     }
 
+    pub fn audionotes(&self) -> Rc<RefCell<RnoteAudioNotes>> {
+        imp::RnoteAppWindow::from_instance(self).audionotes.clone()
+    }
+
+    pub fn recovery(&self) -> Rc<RefCell<Recovery>> {
+        imp::RnoteAppWindow::from_instance(self).recovery.clone()
+    }
+
     pub fn save_window_size(&self) -> Result<(), anyhow::Error> {
         let mut width = self.width();
         let mut height = self.height();
@@ -694,6 +743,9 @@ impl RnoteAppWindow {
                 .update_state(&self.canvas());
             self.queue_resize();
         }
+        if surface_flags.animate {
+            self.canvas().start_pen_animation();
+        }
 
         false
     }
@@ -714,6 +766,7 @@ impl RnoteAppWindow {
         self.imp().penssidebar.get().eraser_page().init(self);
         self.imp().penssidebar.get().selector_page().init(self);
         self.imp().penssidebar.get().tools_page().init(self);
+        self.imp().penssidebar.get().typewriter_page().init(self);
         self.imp().canvas.get().init(self);
         //StrokesState::init(self);
         self.imp().canvas.get().selection_modifier().init(self);
@@ -733,6 +786,13 @@ impl RnoteAppWindow {
             log::debug!("failed to load appwindow settings with Err `{}`", e);
         }
 
+        // Offering to recover a sheet left over from a session that crashed. The recovery is only updated after the dialog is answered
+        if let Some(metadata) = self.recovery().borrow().detect() {
+            dialogs::dialog_recover_sheet(self, &metadata);
+        } else {
+            self.start_recovery_timer();
+        }
+
         // Loading in input file, if Some
         if let Some(input_file) = self
             .application()
@@ -755,6 +815,99 @@ impl RnoteAppWindow {
         }
     }
 
+    /// Starts periodically updating the recovery snapshot and journal
+    pub fn start_recovery_timer(&self) {
+        glib::source::timeout_add_seconds_local(
+            Self::RECOVERY_JOURNAL_INTERVAL_SECS,
+            clone!(@weak self as appwindow => @default-return glib::Continue(false), move || {
+                appwindow.update_recovery();
+
+                glib::Continue(true)
+            }),
+        );
+    }
+
+    /// Writes a new recovery snapshot when the last one is too old, else flushes the journal. Removes the recovery when there are no unsaved changes
+    pub fn update_recovery(&self) {
+        let recovery = self.recovery();
+        let mut recovery = recovery.borrow_mut();
+
+        if !self.canvas().unsaved_changes() {
+            if recovery.snapshot().is_some() {
+                if let Err(e) = recovery.discard() {
+                    log::error!("discarding the recovery failed with Err {}", e);
+                }
+            }
+            return;
+        }
+
+        let file_path = self
+            .application()
+            .unwrap()
+            .downcast::<RnoteApp>()
+            .unwrap()
+            .output_file()
+            .and_then(|output_file| output_file.path());
+        let sheet = self.canvas().sheet();
+        let mut sheet = sheet.borrow_mut();
+
+        let snapshot_due = recovery
+            .snapshot_age()
+            .map_or(true, |age| age >= Self::RECOVERY_SNAPSHOT_INTERVAL);
+        let res = if snapshot_due {
+            recovery.write_snapshot(&mut sheet, file_path)
+        } else {
+            recovery.flush_journal(&mut sheet, file_path)
+        };
+        if let Err(e) = res {
+            log::error!("updating the recovery failed with Err {}", e);
+        }
+    }
+
+    /// Restores the sheet from the recovery snapshot and journal
+    pub fn restore_recovery(&self) {
+        let app = self.application().unwrap().downcast::<RnoteApp>().unwrap();
+        let res = self
+            .recovery()
+            .borrow()
+            .restore(&mut self.canvas().sheet().borrow_mut());
+
+        match res {
+            Ok(metadata) => {
+                self.settings_panel().refresh_for_sheet(self);
+
+                app.set_input_file(None);
+                let output_file = metadata.file_path.map(gio::File::for_path);
+                app.set_output_file(output_file.as_ref(), self);
+
+                self.canvas().set_unsaved_changes(true);
+                self.canvas().set_empty(false);
+                self.canvas().return_to_origin_page();
+                self.canvas().regenerate_background(false);
+                self.canvas().regenerate_content(true, true);
+
+                self.canvas()
+                    .selection_modifier()
+                    .update_state(&self.canvas());
+
+                adw::prelude::ActionGroupExt::activate_action(self, "refresh-ui-for-sheet", None);
+                adw::prelude::ActionGroupExt::activate_action(
+                    self,
+                    "text-toast",
+                    Some(&gettext("Recovered the unsaved changes").to_variant()),
+                );
+            }
+            Err(e) => {
+                log::error!("restoring the recovery failed with Err {}", e);
+                adw::prelude::ActionGroupExt::activate_action(
+                    self,
+                    "error-toast",
+                    Some(&gettext("Recovering the unsaved changes failed").to_variant()),
+                );
+            }
+        }
+    }
+
     pub fn setup_controllers(&self) {
         let canvas_zoom_scroll_controller = EventControllerScroll::builder()
             .name("canvas_zoom_scroll_controller")
@@ -967,9 +1120,11 @@ impl RnoteAppWindow {
             }
             utils::FileType::VectorImageFile
             | utils::FileType::BitmapImageFile
-            | utils::FileType::PdfFile => {
+            | utils::FileType::PdfFile
+            | utils::FileType::InkFile
+            | utils::FileType::PaletteFile => {
                 if let Err(e) = self.load_in_file(file, target_pos) {
-                    log::error!("failed to load in file with FileType::VectorImageFile / FileType::BitmapImageFile / FileType::Pdf, {}", e);
+                    log::error!("failed to load in file with FileType::VectorImageFile / FileType::BitmapImageFile / FileType::Pdf / FileType::InkFile / FileType::PaletteFile, {}", e);
                 }
             }
             utils::FileType::Folder => {
@@ -1059,6 +1214,34 @@ impl RnoteAppWindow {
                     }
                 }));
             }
+            utils::FileType::InkFile => {
+                main_cx.spawn_local(clone!(@weak self as appwindow => async move {
+                    let result = file.load_bytes_future().await;
+                    if let Ok((file_bytes, _)) = result {
+                        if let Err(e) = appwindow.load_in_ink_bytes(file_bytes, target_pos) {
+                            log::error!(
+                                "load_in_ink_bytes() failed in load_in_file() with Err {}",
+                                e
+                            );
+                            adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Importing ink failed").to_variant()));
+                        }
+                    }
+                }));
+            }
+            utils::FileType::PaletteFile => {
+                main_cx.spawn_local(clone!(@weak self as appwindow => async move {
+                    let result = file.load_bytes_future().await;
+                    if let Ok((file_bytes, _)) = result {
+                        if let Err(e) = appwindow.load_in_palette_bytes(file_bytes) {
+                            log::error!(
+                                "load_in_palette_bytes() failed in load_in_file() with Err {}",
+                                e
+                            );
+                            adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Importing the palette failed").to_variant()));
+                        }
+                    }
+                }));
+            }
             utils::FileType::Folder => {
                 log::warn!("tried to open folder as sheet.");
             }
@@ -1085,6 +1268,13 @@ impl RnoteAppWindow {
             .borrow_mut()
             .open_sheet_from_rnote_bytes(bytes)?;
 
+        // Sheets with a page layout are shown in the page layout mode, and sheets without one get one in it
+        if self.canvas().sheet().borrow().page_layout.is_some() {
+            self.canvas().set_expand_mode(ExpandMode::Pages);
+        } else if self.canvas().expand_mode() == ExpandMode::Pages {
+            self.canvas().resize_sheet_to_fit_strokes();
+        }
+
         // Loading the sheet properties into the format settings panel
         self.settings_panel().refresh_for_sheet(self);
 
@@ -1190,6 +1380,44 @@ impl RnoteAppWindow {
         Ok(())
     }
 
+    /// Pastes the engine native clipboard content as the new selection. If the strokes were copied from outside of the current viewport
+    /// ( e.g. from another document ), they are pasted into the upper left of the viewport
+    pub fn load_in_strokes_clipboard_bytes(&self, bytes: &[u8]) -> Result<(), anyhow::Error> {
+        let clipboard = StrokesClipboard::from_json_bytes(bytes)?;
+
+        let pos = if clipboard
+            .bounds
+            .intersects(&self.canvas().viewport_in_sheet_coords())
+        {
+            None
+        } else {
+            Some(
+                self.canvas()
+                    .transform_canvas_coords_to_sheet_coords(na::vector![
+                        VectorImage::OFFSET_X_DEFAULT,
+                        VectorImage::OFFSET_Y_DEFAULT
+                    ]),
+            )
+        };
+
+        self.canvas()
+            .sheet()
+            .borrow_mut()
+            .strokes_state
+            .paste_clipboard(clipboard, pos);
+
+        self.canvas().set_unsaved_changes(true);
+        self.canvas().set_empty(false);
+
+        self.canvas()
+            .selection_modifier()
+            .update_state(&self.canvas());
+
+        self.canvas().regenerate_content(false, true);
+
+        Ok(())
+    }
+
     /// Target position is in the coordinate space of the sheet
     pub fn load_in_bitmapimage_bytes(
         &self,
@@ -1231,12 +1459,97 @@ impl RnoteAppWindow {
         Ok(())
     }
 
+    /// Imports the ink of a ISF stream or EMF file. Target position is in the coordinate space of the sheet
+    pub fn load_in_ink_bytes(
+        &self,
+        bytes: glib::Bytes,
+        target_pos: Option<na::Vector2<f64>>,
+    ) -> Result<(), anyhow::Error> {
+        let app = self.application().unwrap().downcast::<RnoteApp>().unwrap();
+
+        let pos = target_pos.unwrap_or_else(|| {
+            self.canvas()
+                .transform_canvas_coords_to_sheet_coords(na::vector![
+                    VectorImage::OFFSET_X_DEFAULT,
+                    VectorImage::OFFSET_Y_DEFAULT
+                ])
+        });
+        let all_strokes = self
+            .canvas()
+            .sheet()
+            .borrow()
+            .strokes_state
+            .keys_sorted_chrono();
+        self.canvas()
+            .sheet()
+            .borrow_mut()
+            .strokes_state
+            .set_selected_keys(&all_strokes, false);
+
+        let dpi = self.canvas().sheet().borrow().format.dpi;
+        self.canvas()
+            .sheet()
+            .borrow_mut()
+            .strokes_state
+            .insert_ink_bytes(pos, &bytes, dpi)?;
+
+        app.set_input_file(None);
+        self.canvas().set_unsaved_changes(true);
+        self.canvas().set_empty(false);
+
+        adw::prelude::ActionGroupExt::activate_action(
+            self,
+            "pen-style",
+            Some(&"selector_style".to_variant()),
+        );
+        self.canvas().resize_sheet_to_fit_strokes();
+        self.canvas()
+            .selection_modifier()
+            .update_state(&self.canvas());
+        self.canvas().regenerate_content(false, true);
+
+        Ok(())
+    }
+
+    /// Imports a GIMP palette into the named palettes
+    pub fn load_in_palette_bytes(&self, bytes: glib::Bytes) -> Result<(), anyhow::Error> {
+        let gpl = std::str::from_utf8(&bytes)?;
+        let name = self.canvas().palettes().borrow_mut().import_gpl(gpl)?;
+
+        adw::prelude::ActionGroupExt::activate_action(self, "refresh-ui-for-sheet", None);
+        adw::prelude::ActionGroupExt::activate_action(
+            self,
+            "text-toast",
+            Some(&format!("{}: {}", gettext("Imported palette"), name).to_variant()),
+        );
+
+        Ok(())
+    }
+
+    /// Opens the dialog with the options for importing the PDF.
     /// Target position is in the coordinate space of the sheet
     pub fn load_in_pdf_bytes(
         &self,
         bytes: glib::Bytes,
         target_pos: Option<na::Vector2<f64>>,
     ) -> Result<(), anyhow::Error> {
+        let n_pages = pdfimport::pdf_n_pages(&bytes)?;
+        if n_pages == 0 {
+            return Err(anyhow::anyhow!("the PDF has no pages"));
+        }
+
+        dialogs::dialog_import_pdf(self, bytes, n_pages, target_pos);
+
+        Ok(())
+    }
+
+    /// Imports the PDF with the options. Target position is in the coordinate space of the sheet
+    pub fn import_pdf_bytes_w_options(
+        &self,
+        bytes: glib::Bytes,
+        target_pos: Option<na::Vector2<f64>>,
+        options: PdfImportOptions,
+    ) {
         let app = self.application().unwrap().downcast::<RnoteApp>().unwrap();
 
         let pos = target_pos.unwrap_or_else(|| {
@@ -1246,9 +1559,6 @@ impl RnoteAppWindow {
                     VectorImage::OFFSET_Y_DEFAULT
                 ])
         });
-        let page_width = (f64::from(self.canvas().sheet().borrow().format.width)
-            * (self.canvas().pdf_import_width() / 100.0))
-            .round() as i32;
 
         let all_strokes = self
             .canvas()
@@ -1262,31 +1572,16 @@ impl RnoteAppWindow {
             .strokes_state
             .set_selected_keys(&all_strokes, false);
 
-        if self.canvas().pdf_import_as_vector() {
-            self.canvas()
-                .sheet()
-                .borrow_mut()
-                .strokes_state
-                .insert_pdf_bytes_as_vector_threaded(
-                    pos,
-                    Some(page_width),
-                    bytes.to_vec(),
-                    self.canvas().renderer(),
-                );
-        } else {
-            self.canvas()
-                .sheet()
-                .borrow_mut()
-                .strokes_state
-                .insert_pdf_bytes_as_bitmap_threaded(pos, Some(page_width), bytes.to_vec());
-        }
+        self.canvas()
+            .sheet()
+            .borrow_mut()
+            .strokes_state
+            .insert_pdf_bytes_threaded(pos, bytes.to_vec(), options, self.canvas().renderer());
 
         app.set_input_file(None);
 
         self.canvas().set_unsaved_changes(true);
         self.canvas().set_empty(false);
-
-        Ok(())
     }
 
     pub fn export_sheet_as_svg(&self, file: &gio::File) -> Result<(), anyhow::Error> {
@@ -1339,7 +1634,7 @@ impl RnoteAppWindow {
                 .canvas()
                 .sheet()
                 .borrow()
-                .export_sheet_as_pdf_bytes(basename.to_string_lossy().to_string());
+                .export_sheet_as_pdf_bytes(basename.to_string_lossy().to_string(), None);
             let pdf_data = pdf_data_receiver.await?;
 
             let output_stream = file
@@ -1367,4 +1662,108 @@ impl RnoteAppWindow {
 
         Ok(())
     }
+
+    pub async fn export_sheet_as_annotated_pdf(
+        &self,
+        file: &gio::File,
+    ) -> Result<(), anyhow::Error> {
+        if let Some(basename) = file.basename() {
+            let pdf_data_receiver = self
+                .canvas()
+                .sheet()
+                .borrow()
+                .export_sheet_as_annotated_pdf_bytes(basename.to_string_lossy().to_string())?;
+            let pdf_data = pdf_data_receiver.await?;
+
+            let output_stream = file
+                .replace_future(
+                    None,
+                    false,
+                    gio::FileCreateFlags::REPLACE_DESTINATION,
+                    glib::PRIORITY_HIGH_IDLE,
+                )
+                .await?;
+
+            if let Err(e) = output_stream.write(&pdf_data, None::<&gio::Cancellable>) {
+                log::error!(
+                    "output_stream().write() failed in export_sheet_as_annotated_pdf() with Err {}",
+                    e
+                );
+            };
+            if let Err(e) = output_stream.close(None::<&gio::Cancellable>) {
+                log::error!(
+                    "output_stream().close() failed in export_sheet_as_annotated_pdf() with Err {}",
+                    e
+                );
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Exports the sheet as bitmap images. When more than one image is exported, the images are numbered by their page and written next to the file
+    pub async fn export_sheet_as_bitmap(
+        &self,
+        file: &gio::File,
+        prefs: BitmapExportPrefs,
+    ) -> Result<(), anyhow::Error> {
+        let first_page = match &prefs.scope {
+            BitmapExportScope::Pages(page_range) => page_range.start,
+            BitmapExportScope::Selection => 0,
+        };
+        let images_data_receiver = self
+            .canvas()
+            .sheet()
+            .borrow()
+            .export_sheet_as_bitmap_bytes(prefs)?;
+        let images_data = images_data_receiver.await?;
+        let n_images = images_data.len();
+
+        for (i, image_data) in images_data.into_iter().enumerate() {
+            let file = if n_images > 1 {
+                let basename = file
+                    .basename()
+                    .ok_or_else(|| anyhow::anyhow!("file has no basename"))?;
+                let stem = basename
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let page_nr = first_page as usize + i + 1;
+                let numbered_name = match basename.extension() {
+                    Some(ext) => format!("{}_{}.{}", stem, page_nr, ext.to_string_lossy()),
+                    None => format!("{}_{}", stem, page_nr),
+                };
+
+                file.parent()
+                    .ok_or_else(|| anyhow::anyhow!("file has no parent directory"))?
+                    .child(numbered_name)
+            } else {
+                file.clone()
+            };
+
+            let output_stream = file
+                .replace_future(
+                    None,
+                    false,
+                    gio::FileCreateFlags::REPLACE_DESTINATION,
+                    glib::PRIORITY_HIGH_IDLE,
+                )
+                .await?;
+
+            if let Err(e) = output_stream.write(&image_data, None::<&gio::Cancellable>) {
+                log::error!(
+                    "output_stream().write() failed in export_sheet_as_bitmap() with Err {}",
+                    e
+                );
+            };
+            if let Err(e) = output_stream.close(None::<&gio::Cancellable>) {
+                log::error!(
+                    "output_stream().close() failed in export_sheet_as_bitmap() with Err {}",
+                    e
+                );
+            };
+        }
+
+        Ok(())
+    }
 }
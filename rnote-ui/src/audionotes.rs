@@ -0,0 +1,193 @@
+use std::path::PathBuf;
+use std::time;
+
+use gst::prelude::*;
+use gtk4::glib;
+use rnote_engine::strokesstate::audionotes::{AudioNote, AudioPosition};
+
+/// A recording which is in progress
+#[derive(Debug)]
+struct ActiveRecording {
+    pipeline: gst::Pipeline,
+    file: PathBuf,
+    started: chrono::DateTime<chrono::Utc>,
+    started_instant: time::Instant,
+}
+
+/// Records the audio notes from the default audio source, and plays them back
+#[derive(Debug, Default)]
+pub struct RnoteAudioNotes {
+    recording: Option<ActiveRecording>,
+    playbin: Option<gst::Element>,
+    /// The note that is loaded into the playbin, and the file it was written to
+    playback: Option<(AudioNote, PathBuf)>,
+}
+
+impl RnoteAudioNotes {
+    /// The recordings are encoded as opus in an ogg container
+    pub const RECORDING_PIPELINE: &'static str =
+        "autoaudiosrc ! audioconvert ! audioresample ! opusenc ! oggmux ! filesink name=audionote_sink";
+    pub const MIME_TYPE: &'static str = "audio/ogg";
+    /// The time that is waited for the pipelines to finish writing or to get ready to play
+    pub const STATE_TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn start_recording(&mut self) -> Result<(), anyhow::Error> {
+        if self.recording.is_some() {
+            return Ok(());
+        }
+
+        let pipeline = gst::parse_launch(Self::RECORDING_PIPELINE)?
+            .downcast::<gst::Pipeline>()
+            .map_err(|_| anyhow::anyhow!("the recording pipeline is not a pipeline"))?;
+        let file = Self::tmp_file("recording");
+        let sink = pipeline
+            .by_name("audionote_sink")
+            .ok_or_else(|| anyhow::anyhow!("the sink of the recording pipeline is missing"))?;
+        sink.set_property("location", file.to_string_lossy().as_ref());
+
+        pipeline.set_state(gst::State::Playing)?;
+
+        self.recording = Some(ActiveRecording {
+            pipeline,
+            file,
+            started: chrono::Utc::now(),
+            started_instant: time::Instant::now(),
+        });
+        Ok(())
+    }
+
+    /// Stops the recording and returns the recorded note, which is then expected to be added to the sheet
+    pub fn stop_recording(&mut self) -> Result<Option<AudioNote>, anyhow::Error> {
+        let recording = match self.recording.take() {
+            Some(recording) => recording,
+            None => return Ok(None),
+        };
+        let duration = recording.started_instant.elapsed().as_secs_f64();
+
+        // The muxer only finishes the file on end of stream
+        recording.pipeline.send_event(gst::event::Eos::new());
+        if let Some(bus) = recording.pipeline.bus() {
+            if let Some(message) = bus.timed_pop_filtered(
+                gst::ClockTime::from_mseconds(Self::STATE_TIMEOUT.as_millis() as u64),
+                &[gst::MessageType::Eos, gst::MessageType::Error],
+            ) {
+                if let gst::MessageView::Error(err) = message.view() {
+                    log::error!("recording the audio note failed with Err {:?}", err);
+                }
+            }
+        }
+        recording.pipeline.set_state(gst::State::Null)?;
+
+        let bytes = std::fs::read(&recording.file)?;
+        if let Err(e) = std::fs::remove_file(&recording.file) {
+            log::warn!("removing the recorded audio file failed with Err {}", e);
+        }
+
+        Ok(Some(AudioNote::new(
+            &bytes,
+            String::from(Self::MIME_TYPE),
+            recording.started,
+            duration,
+        )))
+    }
+
+    /// Plays the note from the position on
+    pub fn play(
+        &mut self,
+        audio_note: &AudioNote,
+        position: AudioPosition,
+    ) -> Result<(), anyhow::Error> {
+        let playbin = match self.playbin.as_ref() {
+            Some(playbin) => playbin.clone(),
+            None => {
+                let playbin = gst::ElementFactory::make("playbin", Some("audionote_playbin"))?;
+                self.playbin = Some(playbin.clone());
+                playbin
+            }
+        };
+
+        // The note is only written to a file again when another note is played
+        let loaded = self.playback.as_ref().map_or(false, |(loaded, _)| {
+            loaded.started == audio_note.started && loaded.data_base64 == audio_note.data_base64
+        });
+        if !loaded {
+            playbin.set_state(gst::State::Null)?;
+            self.remove_playback_file();
+
+            let file = Self::tmp_file("playback");
+            std::fs::write(&file, audio_note.data()?)?;
+            let uri = glib::filename_to_uri(&file, None)?;
+            playbin.set_property("uri", uri.as_str());
+
+            self.playback = Some((audio_note.clone(), file));
+        }
+
+        // Seeking needs the pipeline at least paused
+        playbin.set_state(gst::State::Paused)?;
+        let _ = playbin.state(gst::ClockTime::from_mseconds(
+            Self::STATE_TIMEOUT.as_millis() as u64,
+        ));
+        playbin.seek_simple(
+            gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT,
+            gst::format::GenericFormattedValue::Time(Some(gst::ClockTime::from_mseconds(
+                (position.offset * 1000.0).round() as u64,
+            ))),
+        )?;
+        playbin.set_state(gst::State::Playing)?;
+
+        Ok(())
+    }
+
+    pub fn stop_playback(&self) {
+        if let Some(playbin) = &self.playbin {
+            if let Err(e) = playbin.set_state(gst::State::Paused) {
+                log::error!(
+                    "audionotes playbin set_state(Paused) failed in stop_playback() with Err {}",
+                    e
+                );
+            }
+        }
+    }
+
+    /// Stops the recording and playback by setting the pipelines state to Null. Must be called when closing the application
+    pub fn set_states_null(&mut self) {
+        if let Some(recording) = self.recording.take() {
+            if let Err(e) = recording.pipeline.set_state(gst::State::Null) {
+                log::error!(
+                    "audionotes recording pipeline set_state(Null) failed in set_states_null() with Err {}",
+                    e
+                );
+            }
+            let _ = std::fs::remove_file(&recording.file);
+        }
+        if let Some(playbin) = &self.playbin {
+            if let Err(e) = playbin.set_state(gst::State::Null) {
+                log::error!(
+                    "audionotes playbin set_state(Null) failed in set_states_null() with Err {}",
+                    e
+                );
+            }
+        }
+        self.remove_playback_file();
+    }
+
+    fn remove_playback_file(&mut self) {
+        if let Some((_, file)) = self.playback.take() {
+            if let Err(e) = std::fs::remove_file(&file) {
+                log::warn!("removing the audio playback file failed with Err {}", e);
+            }
+        }
+    }
+
+    fn tmp_file(purpose: &str) -> PathBuf {
+        glib::tmp_dir().join(format!(
+            "rnote-audionote-{}-{}.ogg",
+            purpose,
+            std::process::id()
+        ))
+    }
+}
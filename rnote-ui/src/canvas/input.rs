@@ -1,10 +1,12 @@
 use gtk4::{gdk, prelude::*, GestureDrag, GestureStylus};
 use rnote_engine::pens::shortcuts::ShortcutKey;
-use rnote_engine::pens::{PenEvent, PenStyle};
+use rnote_engine::pens::{KeyboardKey, PenEvent, PenStyle};
 use std::collections::VecDeque;
 
 use crate::appwindow::RnoteAppWindow;
 use rnote_engine::strokes::inputdata::InputData;
+use rnote_engine::strokesstate::link_comp::StrokeLink;
+use rnote_engine::strokesstate::StrokesState;
 
 /// transform pen input with zoom and offset
 pub fn transform_inputdata(
@@ -13,10 +15,7 @@ pub fn transform_inputdata(
     zoom: f64,
 ) {
     data_entries.iter_mut().for_each(|inputdata| {
-        *inputdata = InputData::new(
-            inputdata.pos().scale(1.0 / zoom) + offset,
-            inputdata.pressure(),
-        )
+        inputdata.set_pos(inputdata.pos().scale(1.0 / zoom) + offset);
     });
 }
 
@@ -152,8 +151,26 @@ pub fn retreive_stylus_inputdata(
     } else {
         InputData::PRESSURE_DEFAULT
     };
+    // gtk reports the tilt normalized between -1.0 and 1.0
+    let tilt = match (
+        stylus_drawing_gesture.axis(gdk::AxisUse::Xtilt),
+        stylus_drawing_gesture.axis(gdk::AxisUse::Ytilt),
+    ) {
+        (Some(xtilt), Some(ytilt)) => na::vector![xtilt, ytilt],
+        _ => InputData::TILT_DEFAULT,
+    };
+    // gtk reports the rotation normalized between 0.0 and 1.0
+    let rotation = if let Some(rotation) = stylus_drawing_gesture.axis(gdk::AxisUse::Rotation) {
+        rotation * std::f64::consts::TAU
+    } else {
+        InputData::ROTATION_DEFAULT
+    };
+
+    let mut inputdata = InputData::new(na::vector![x, y], pressure);
+    inputdata.set_tilt(tilt);
+    inputdata.set_rotation(rotation);
 
-    data_entries.push_back(InputData::new(na::vector![x, y], pressure));
+    data_entries.push_back(inputdata);
 
     data_entries
 }
@@ -164,6 +181,18 @@ pub fn process_pen_down(
     shortcut_key: Option<ShortcutKey>,
     appwindow: &RnoteAppWindow,
 ) {
+    // In view mode the pens are disabled, and tapping checks todos, opens links or plays the audio notes from the moment the stroke was written instead
+    if appwindow.canvas().view_mode() {
+        if let Some(inputdata) = data_entries.back() {
+            if !toggle_checkbox_at_pos(inputdata.pos(), appwindow)
+                && !open_link_at_pos(inputdata.pos(), appwindow)
+            {
+                play_audio_note_at_pos(inputdata.pos(), appwindow);
+            }
+        }
+        return;
+    }
+
     let pen_event = match shortcut_key {
         // Stylus button presses are emitting separate down / up events, so we handle them here differently to only change the pen, not start drawing
         Some(ShortcutKey::StylusPrimaryButton) | Some(ShortcutKey::StylusSecondaryButton) => {
@@ -211,6 +240,10 @@ pub fn process_pen_motion(
     shortcut_key: Option<ShortcutKey>,
     appwindow: &RnoteAppWindow,
 ) {
+    if appwindow.canvas().view_mode() {
+        return;
+    }
+
     let surface_flags = appwindow.canvas().pens().borrow_mut().handle_event(
         PenEvent::MotionEvent {
             data_entries,
@@ -241,6 +274,10 @@ pub fn process_pen_up(
     shortcut_key: Option<ShortcutKey>,
     appwindow: &RnoteAppWindow,
 ) {
+    if appwindow.canvas().view_mode() {
+        return;
+    }
+
     let pen_event = match shortcut_key {
         // Stylus button presses are emitting separate down / up events, so we handle them here differently to only change the pen, not start drawing
         Some(ShortcutKey::StylusPrimaryButton) | Some(ShortcutKey::StylusSecondaryButton) => {
@@ -267,3 +304,155 @@ pub fn process_pen_up(
 
     appwindow.handle_surface_flags(surface_flags);
 }
+
+/// Checks or unchecks the checkbox at the position. Returns whether there was a checkbox
+pub fn toggle_checkbox_at_pos(pos: na::Vector2<f64>, appwindow: &RnoteAppWindow) -> bool {
+    let key = appwindow
+        .canvas()
+        .sheet()
+        .borrow()
+        .strokes_state
+        .checkbox_at_pos(pos, StrokesState::LINK_TOLERANCE);
+
+    match key {
+        Some(key) => {
+            appwindow
+                .canvas()
+                .sheet()
+                .borrow_mut()
+                .strokes_state
+                .toggle_checkbox(key);
+
+            appwindow.canvas().set_unsaved_changes(true);
+            appwindow.canvas().regenerate_content(false, true);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Opens the link of the stroke at the position. URLs are opened externally, page links scroll to the top of the page.
+/// Returns whether there was a link
+pub fn open_link_at_pos(pos: na::Vector2<f64>, appwindow: &RnoteAppWindow) -> bool {
+    let link = appwindow
+        .canvas()
+        .sheet()
+        .borrow()
+        .strokes_state
+        .link_at_position(pos);
+
+    match link {
+        Some((_key, StrokeLink::Url(url))) => {
+            gtk4::show_uri(Some(appwindow), &url, gdk::CURRENT_TIME);
+        }
+        Some((_key, StrokeLink::Page(page))) => {
+            let pages = appwindow.canvas().sheet().borrow().pages();
+
+            if let Some(page_bounds) = pages.get(page as usize) {
+                let viewport = appwindow.canvas().viewport_in_sheet_coords();
+
+                appwindow
+                    .canvas()
+                    .center_around_coord_on_sheet(na::vector![
+                        page_bounds.center()[0],
+                        page_bounds.mins[1] + viewport.half_extents()[1]
+                    ]);
+            } else {
+                log::warn!(
+                    "opening link to page {} failed, the sheet has only {} pages",
+                    page + 1,
+                    pages.len()
+                );
+            }
+        }
+        None => return false,
+    }
+
+    true
+}
+
+/// Plays the audio note from the moment the stroke at the position was written
+pub fn play_audio_note_at_pos(pos: na::Vector2<f64>, appwindow: &RnoteAppWindow) {
+    let audio_note = {
+        let sheet = appwindow.canvas().sheet();
+        let sheet = sheet.borrow();
+
+        sheet
+            .strokes_state
+            .audio_position_at(pos)
+            .and_then(|(_key, position)| {
+                sheet
+                    .strokes_state
+                    .audio_notes()
+                    .get(position.note_index)
+                    .cloned()
+                    .map(|audio_note| (audio_note, position))
+            })
+    };
+
+    if let Some((audio_note, position)) = audio_note {
+        if let Err(e) = appwindow
+            .audionotes()
+            .borrow_mut()
+            .play(&audio_note, position)
+        {
+            log::error!("playing the audio note failed with Err {}", e);
+        }
+    }
+}
+
+/// Maps the pressed key to the keys the pens handle
+pub fn retreive_keyboard_key(key: gdk::Key, modifier: gdk::ModifierType) -> Option<KeyboardKey> {
+    match key {
+        gdk::Key::Return | gdk::Key::KP_Enter => Some(KeyboardKey::Return),
+        gdk::Key::Escape => Some(KeyboardKey::Escape),
+        gdk::Key::BackSpace => Some(KeyboardKey::BackSpace),
+        gdk::Key::Delete | gdk::Key::KP_Delete => Some(KeyboardKey::Delete),
+        gdk::Key::Left | gdk::Key::KP_Left => Some(KeyboardKey::Left),
+        gdk::Key::Right | gdk::Key::KP_Right => Some(KeyboardKey::Right),
+        gdk::Key::Home | gdk::Key::KP_Home => Some(KeyboardKey::Home),
+        gdk::Key::End | gdk::Key::KP_End => Some(KeyboardKey::End),
+        _ => {
+            // Keys pressed together with ctrl or alt are left to the shortcuts
+            if modifier.intersects(gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::ALT_MASK) {
+                return None;
+            }
+
+            key.to_unicode()
+                .filter(|c| !c.is_control())
+                .map(KeyboardKey::Unicode)
+        }
+    }
+}
+
+/// Returns true if the key press was handled by the pens
+pub fn process_key_pressed(keyboard_key: KeyboardKey, appwindow: &RnoteAppWindow) -> bool {
+    // While presenting, the keys navigate through the pages
+    if appwindow.canvas().presenting() {
+        match keyboard_key {
+            KeyboardKey::Right | KeyboardKey::Unicode(' ') => {
+                appwindow.canvas().presentation_next_page()
+            }
+            KeyboardKey::Left => appwindow.canvas().presentation_previous_page(),
+            KeyboardKey::Escape => {
+                adw::prelude::ActionGroupExt::activate_action(appwindow, "presentation", None)
+            }
+            _ => {}
+        }
+
+        return true;
+    }
+
+    let surface_flags = appwindow.canvas().pens().borrow_mut().handle_event(
+        PenEvent::KeyPressed(keyboard_key),
+        &mut *appwindow.canvas().sheet().borrow_mut(),
+        Some(appwindow.canvas().viewport_in_sheet_coords()),
+        appwindow.canvas().zoom(),
+        appwindow.canvas().renderer(),
+    );
+
+    let handled = surface_flags.redraw;
+    appwindow.handle_surface_flags(surface_flags);
+
+    handled
+}
@@ -11,14 +11,17 @@ mod imp {
     use crate::config;
     use crate::selectionmodifier::SelectionModifier;
     use rnote_engine::compose::geometry::AABBHelpers;
+    use rnote_engine::compose::palette::Palettes;
     use rnote_engine::pens::{PenStyle, Pens};
-    use rnote_engine::render::Renderer;
+    use rnote_engine::recognition::handwriting::{HandwritingBackend, PointCloudBackend};
+    use rnote_engine::render::{self, Renderer};
+    use rnote_engine::sheet::presentation::Presentation;
     use rnote_engine::sheet::Sheet;
     use rnote_engine::strokesstate::render_comp::visual_debug;
 
     use gtk4::{
-        gdk, glib, graphene, gsk, prelude::*, subclass::prelude::*, GestureDrag, GestureStylus,
-        PropagationPhase, Snapshot, Widget,
+        gdk, glib, graphene, gsk, prelude::*, subclass::prelude::*, EventControllerKey,
+        GestureDrag, GestureStylus, PropagationPhase, Snapshot, Widget,
     };
     use gtk4::{AccessibleRole, Adjustment, Scrollable, ScrollablePolicy};
 
@@ -36,19 +39,28 @@ mod imp {
         pub hscroll_policy: Cell<ScrollablePolicy>,
         pub vscroll_policy: Cell<ScrollablePolicy>,
         pub zoom_timeout_id: RefCell<Option<glib::SourceId>>,
+        pub pen_animation_running: Cell<bool>,
+        pub presentation: RefCell<Option<Presentation>>,
+        pub presentation_transition_running: Cell<bool>,
+        /// The zoom and the center of the view before the presentation started, to return to when it stops
+        pub presentation_return_view: Cell<Option<(f64, na::Vector2<f64>)>>,
         pub cursor: gdk::Cursor,
         pub motion_cursor: gdk::Cursor,
         pub stylus_drawing_gesture: GestureStylus,
         pub mouse_drawing_gesture: GestureDrag,
         pub touch_drawing_gesture: GestureDrag,
+        pub key_controller: EventControllerKey,
         pub selection_modifier: SelectionModifier,
         pub return_to_center_toast: RefCell<Option<adw::Toast>>,
 
         pub pens: Rc<RefCell<Pens>>,
+        pub palettes: Rc<RefCell<Palettes>>,
         pub sheet: Rc<RefCell<Sheet>>,
+        pub handwriting_backend: RefCell<Arc<dyn HandwritingBackend>>,
         pub zoom: Cell<f64>,
         pub temporary_zoom: Cell<f64>,
         pub visual_debug: Cell<bool>,
+        pub view_mode: Cell<bool>,
         pub unsaved_changes: Cell<bool>,
         pub empty: Cell<bool>,
 
@@ -57,8 +69,10 @@ mod imp {
         pub expand_mode: Cell<ExpandMode>,
         pub endless_sheet: Cell<bool>,
         pub format_borders: Cell<bool>,
+        pub invert_ink: Cell<bool>,
         pub pdf_import_width: Cell<f64>,
         pub pdf_import_as_vector: Cell<bool>,
+        pub undo_memory_budget: Cell<u32>,
     }
 
     impl Default for Canvas {
@@ -88,6 +102,11 @@ mod imp {
             mouse_drawing_gesture.group_with(&stylus_drawing_gesture);
             touch_drawing_gesture.group_with(&stylus_drawing_gesture);
 
+            let key_controller = EventControllerKey::builder()
+                .name("key_controller")
+                .propagation_phase(PropagationPhase::Bubble)
+                .build();
+
             let cursor = gdk::Cursor::from_texture(
                 &gdk::Texture::from_resource(
                     (String::from(config::APP_IDPATH) + "icons/scalable/actions/canvas-cursor.svg")
@@ -121,17 +140,25 @@ mod imp {
                 stylus_drawing_gesture,
                 mouse_drawing_gesture,
                 touch_drawing_gesture,
+                key_controller,
                 zoom_timeout_id: RefCell::new(None),
+                pen_animation_running: Cell::new(false),
+                presentation: RefCell::new(None),
+                presentation_transition_running: Cell::new(false),
+                presentation_return_view: Cell::new(None),
                 return_to_center_toast: RefCell::new(None),
 
                 selection_modifier: SelectionModifier::default(),
 
                 pens: Rc::new(RefCell::new(Pens::default())),
+                palettes: Rc::new(RefCell::new(Palettes::default())),
                 sheet: Rc::new(RefCell::new(Sheet::default())),
+                handwriting_backend: RefCell::new(Arc::new(PointCloudBackend::default())),
 
                 zoom: Cell::new(super::Canvas::ZOOM_DEFAULT),
                 temporary_zoom: Cell::new(1.0),
                 visual_debug: Cell::new(false),
+                view_mode: Cell::new(false),
                 unsaved_changes: Cell::new(false),
                 empty: Cell::new(true),
 
@@ -139,8 +166,10 @@ mod imp {
                 expand_mode: Cell::new(ExpandMode::default()),
                 endless_sheet: Cell::new(true),
                 format_borders: Cell::new(true),
+                invert_ink: Cell::new(false),
                 pdf_import_width: Cell::new(super::Canvas::PDF_IMPORT_WIDTH_DEFAULT),
                 pdf_import_as_vector: Cell::new(true),
+                undo_memory_budget: Cell::new(super::Canvas::UNDO_MEMORY_BUDGET_DEFAULT),
             }
         }
     }
@@ -177,6 +206,7 @@ mod imp {
             obj.add_controller(&self.stylus_drawing_gesture);
             obj.add_controller(&self.mouse_drawing_gesture);
             obj.add_controller(&self.touch_drawing_gesture);
+            obj.add_controller(&self.key_controller);
         }
 
         fn dispose(&self, obj: &Self::Type) {
@@ -216,6 +246,14 @@ mod imp {
                         false,
                         glib::ParamFlags::READWRITE,
                     ),
+                    // View mode, in which the pens are disabled and tapping a stroke opens its link
+                    glib::ParamSpecBoolean::new(
+                        "view-mode",
+                        "view-mode",
+                        "view-mode",
+                        false,
+                        glib::ParamFlags::READWRITE,
+                    ),
                     // Flag for any unsaved changes on the canvas. Propagates to the application 'unsaved-changes' property
                     glib::ParamSpecBoolean::new(
                         "unsaved-changes",
@@ -249,6 +287,14 @@ mod imp {
                         true,
                         glib::ParamFlags::READWRITE,
                     ),
+                    // Wether to display the sheet with inverted ink, e.g. for dark themes. The strokes keep their colors
+                    glib::ParamSpecBoolean::new(
+                        "invert-ink",
+                        "invert-ink",
+                        "invert-ink",
+                        false,
+                        glib::ParamFlags::READWRITE,
+                    ),
                     // Wether to enable touch drawing
                     glib::ParamSpecBoolean::new(
                         "touch-drawing",
@@ -275,6 +321,16 @@ mod imp {
                         true,
                         glib::ParamFlags::READWRITE,
                     ),
+                    // the memory the undo history may take up in MiB
+                    glib::ParamSpecUInt::new(
+                        "undo-memory-budget",
+                        "undo-memory-budget",
+                        "undo-memory-budget",
+                        super::Canvas::UNDO_MEMORY_BUDGET_MIN,
+                        super::Canvas::UNDO_MEMORY_BUDGET_MAX,
+                        super::Canvas::UNDO_MEMORY_BUDGET_DEFAULT,
+                        glib::ParamFlags::READWRITE,
+                    ),
                     // Scrollable properties
                     glib::ParamSpecOverride::for_interface::<Scrollable>("hscroll-policy"),
                     glib::ParamSpecOverride::for_interface::<Scrollable>("vscroll-policy"),
@@ -290,6 +346,7 @@ mod imp {
                 "zoom" => self.zoom.get().to_value(),
                 "temporary-zoom" => self.temporary_zoom.get().to_value(),
                 "visual-debug" => self.visual_debug.get().to_value(),
+                "view-mode" => self.view_mode.get().to_value(),
                 "unsaved-changes" => self.unsaved_changes.get().to_value(),
                 "empty" => self.empty.get().to_value(),
                 "hadjustment" => self.hadjustment.borrow().to_value(),
@@ -298,9 +355,11 @@ mod imp {
                 "vscroll-policy" => self.vscroll_policy.get().to_value(),
                 "expand-mode" => self.expand_mode.get().to_value(),
                 "format-borders" => self.format_borders.get().to_value(),
+                "invert-ink" => self.invert_ink.get().to_value(),
                 "touch-drawing" => self.touch_drawing.get().to_value(),
                 "pdf-import-width" => self.pdf_import_width.get().to_value(),
                 "pdf-import-as-vector" => self.pdf_import_as_vector.get().to_value(),
+                "undo-memory-budget" => self.undo_memory_budget.get().to_value(),
                 _ => unimplemented!(),
             }
         }
@@ -342,6 +401,11 @@ mod imp {
 
                     obj.queue_draw();
                 }
+                "view-mode" => {
+                    let view_mode: bool =
+                        value.get().expect("The value needs to be of type `bool`.");
+                    self.view_mode.replace(view_mode);
+                }
                 "unsaved-changes" => {
                     let unsaved_changes: bool =
                         value.get().expect("The value needs to be of type `bool`.");
@@ -377,6 +441,12 @@ mod imp {
 
                     self.expand_mode.replace(expand_mode);
 
+                    if expand_mode == ExpandMode::Pages {
+                        obj.sheet().borrow_mut().enable_page_layout();
+                    } else {
+                        obj.sheet().borrow_mut().disable_page_layout();
+                    }
+
                     obj.return_to_origin_page();
                     obj.resize_sheet_to_fit_strokes();
                     obj.update_background_rendernode(false);
@@ -390,6 +460,14 @@ mod imp {
                     self.format_borders.replace(format_borders);
                     obj.queue_draw();
                 }
+                "invert-ink" => {
+                    let invert_ink = value
+                        .get::<bool>()
+                        .expect("The value needs to be of type `bool`.");
+
+                    self.invert_ink.replace(invert_ink);
+                    obj.queue_draw();
+                }
                 "touch-drawing" => {
                     let touch_drawing: bool =
                         value.get().expect("The value needs to be of type `bool`.");
@@ -417,6 +495,21 @@ mod imp {
 
                     self.pdf_import_as_vector.replace(pdf_import_as_vector);
                 }
+                "undo-memory-budget" => {
+                    let undo_memory_budget = value
+                        .get::<u32>()
+                        .expect("The value needs to be of type `u32`.")
+                        .clamp(
+                            super::Canvas::UNDO_MEMORY_BUDGET_MIN,
+                            super::Canvas::UNDO_MEMORY_BUDGET_MAX,
+                        );
+
+                    self.undo_memory_budget.replace(undo_memory_budget);
+                    self.sheet
+                        .borrow_mut()
+                        .strokes_state
+                        .set_history_memory_budget(undo_memory_budget as usize * 1024 * 1024);
+                }
                 _ => unimplemented!(),
             }
         }
@@ -460,15 +553,24 @@ mod imp {
             // From here in scaled sheet coordinate space
             snapshot.scale(temporary_zoom as f32, temporary_zoom as f32);
 
-            self.draw_shadow(
-                widget
-                    .sheet()
-                    .borrow()
-                    .bounds()
-                    .scale(na::Vector2::from_element(zoom)),
-                f64::from(super::Canvas::SHADOW_WIDTH) * zoom,
-                snapshot,
-            );
+            // Every page of a page layout has its own shadow
+            let shadowed_bounds = match &widget.sheet().borrow().page_layout {
+                Some(page_layout) => page_layout.pages_bounds(),
+                None => vec![widget.sheet().borrow().bounds()],
+            };
+            for bounds in shadowed_bounds {
+                self.draw_shadow(
+                    bounds.scale(na::Vector2::from_element(zoom)),
+                    f64::from(super::Canvas::SHADOW_WIDTH) * zoom,
+                    snapshot,
+                );
+            }
+
+            // Inverting the ink only changes what is displayed, so the whole sheet is drawn through the color matrix
+            if self.invert_ink.get() {
+                let (color_matrix, color_offset) = render::invert_ink_color_matrix();
+                snapshot.push_color_matrix(&color_matrix, &color_offset);
+            }
 
             // Clip sheet and stroke drawing to sheet bounds
             snapshot.push_clip(
@@ -484,10 +586,12 @@ mod imp {
                 .borrow()
                 .draw(zoom, snapshot, widget.format_borders());
 
-            self.sheet
-                .borrow()
-                .strokes_state
-                .draw_strokes(snapshot, Some(widget.viewport_in_sheet_coords()));
+            self.sheet.borrow().strokes_state.draw_strokes_tiled(
+                snapshot,
+                widget.viewport_in_sheet_coords(),
+                zoom,
+                Some(widget.upcast_ref::<Widget>()),
+            );
 
             snapshot.pop();
 
@@ -506,6 +610,10 @@ mod imp {
                 log::debug!("pens draw() failed in canvas snapshot() with Err {}", e);
             };
 
+            if self.invert_ink.get() {
+                snapshot.pop();
+            }
+
             if self.visual_debug.get() {
                 self.draw_debug(widget, snapshot);
             }
@@ -514,6 +622,10 @@ mod imp {
             snapshot.restore();
             widget.snapshot_child(&self.selection_modifier, snapshot);
 
+            if self.visual_debug.get() {
+                self.draw_perf_stats(widget, snapshot);
+            }
+
             snapshot.pop();
         }
     }
@@ -542,6 +654,17 @@ mod imp {
             );
         }
 
+        // Draw the performance counters of the engine in the top left corner, for visual debugging purposes
+        fn draw_perf_stats(&self, widget: &super::Canvas, snapshot: &Snapshot) {
+            let stats = self.sheet.borrow().strokes_state.perf_counters().stats();
+            let layout = widget.create_pango_layout(Some(&stats.to_string()));
+
+            snapshot.save();
+            snapshot.translate(&graphene::Point::new(8.0, 8.0));
+            snapshot.append_layout(&layout, &visual_debug::COLOR_STROKE_BOUNDS.to_gdk());
+            snapshot.restore();
+        }
+
         // Draw bounds, positions, .. for visual debugging purposes
         fn draw_debug(&self, widget: &super::Canvas, snapshot: &Snapshot) {
             let zoom = widget.zoom();
@@ -571,7 +694,11 @@ mod imp {
                             );
                         }
                     }
-                    PenStyle::BrushStyle | PenStyle::ShaperStyle | PenStyle::ToolsStyle => {}
+                    PenStyle::BrushStyle
+                    | PenStyle::ShaperStyle
+                    | PenStyle::ToolsStyle
+                    | PenStyle::TypewriterStyle
+                    | PenStyle::PluginStyle => {}
                 }
             }
 
@@ -595,8 +722,11 @@ use crate::{app::RnoteApp, appwindow::RnoteAppWindow};
 use futures::StreamExt;
 use rnote_engine::compose::color::Color;
 use rnote_engine::compose::geometry::AABBHelpers;
+use rnote_engine::compose::palette::Palettes;
 use rnote_engine::pens::Pens;
+use rnote_engine::recognition::handwriting::HandwritingBackend;
 use rnote_engine::render::{self, Renderer};
+use rnote_engine::sheet::presentation::Presentation;
 use rnote_engine::sheet::Sheet;
 use rnote_engine::strokes::inputdata::InputData;
 
@@ -610,7 +740,9 @@ use std::sync::{Arc, RwLock};
 use std::time;
 
 use gtk4::{gdk, glib, glib::clone, prelude::*, subclass::prelude::*};
-use gtk4::{gio, Adjustment, DropTarget, EventSequenceState, PropagationPhase, Snapshot, Widget};
+use gtk4::{
+    gio, Adjustment, DropTarget, EventSequenceState, Inhibit, PropagationPhase, Snapshot, Widget,
+};
 use p2d::bounding_volume::{BoundingVolume, AABB};
 
 #[derive(
@@ -625,6 +757,8 @@ pub enum ExpandMode {
     EndlessVertical = 1,
     #[enum_value(name = "Infinite", nick = "infinite")]
     Infinite = 2,
+    #[enum_value(name = "Pages", nick = "pages")]
+    Pages = 3,
 }
 
 impl Default for ExpandMode {
@@ -662,6 +796,12 @@ impl Canvas {
     pub const ZOOM_TIMEOUT_TIME: time::Duration = time::Duration::from_millis(300);
     // The default width of imported PDF's in percentage to the sheet width
     pub const PDF_IMPORT_WIDTH_DEFAULT: f64 = 50.0;
+    // The memory the undo history may take up in MiB
+    pub const UNDO_MEMORY_BUDGET_DEFAULT: u32 = 256;
+    pub const UNDO_MEMORY_BUDGET_MIN: u32 = 16;
+    pub const UNDO_MEMORY_BUDGET_MAX: u32 = 4096;
+    /// The interval in which pen animations, like the fading laser trails, are updated
+    pub const PEN_ANIMATION_INTERVAL: time::Duration = time::Duration::from_millis(16);
 
     pub fn new() -> Self {
         let canvas: Canvas = glib::Object::new(&[]).expect("Failed to create Canvas");
@@ -686,6 +826,20 @@ impl Canvas {
         self.imp().pens.clone()
     }
 
+    /// The named palettes of the app
+    pub fn palettes(&self) -> Rc<RefCell<Palettes>> {
+        self.imp().palettes.clone()
+    }
+
+    /// The backend the handwriting is recognized with
+    pub fn handwriting_backend(&self) -> Arc<dyn HandwritingBackend> {
+        Arc::clone(&*self.imp().handwriting_backend.borrow())
+    }
+
+    pub fn set_handwriting_backend(&self, handwriting_backend: Arc<dyn HandwritingBackend>) {
+        *self.imp().handwriting_backend.borrow_mut() = handwriting_backend;
+    }
+
     /// Only change the sheet state in actions to avoid nested mutable borrows!
     pub fn sheet(&self) -> Rc<RefCell<Sheet>> {
         Rc::clone(&imp::Canvas::from_instance(self).sheet)
@@ -707,6 +861,14 @@ impl Canvas {
         self.set_property("format-borders", format_borders.to_value());
     }
 
+    pub fn invert_ink(&self) -> bool {
+        self.property::<bool>("invert-ink")
+    }
+
+    pub fn set_invert_ink(&self, invert_ink: bool) {
+        self.set_property("invert-ink", invert_ink.to_value());
+    }
+
     pub fn zoom(&self) -> f64 {
         self.property::<f64>("zoom")
     }
@@ -739,6 +901,15 @@ impl Canvas {
         self.set_property("pdf-import-as-vector", as_vector.to_value());
     }
 
+    /// The memory the undo history may take up in MiB
+    pub fn undo_memory_budget(&self) -> u32 {
+        self.property::<u32>("undo-memory-budget")
+    }
+
+    pub fn set_undo_memory_budget(&self, undo_memory_budget: u32) {
+        self.set_property("undo-memory-budget", undo_memory_budget.to_value());
+    }
+
     pub fn unsaved_changes(&self) -> bool {
         self.property::<bool>("unsaved-changes")
     }
@@ -763,6 +934,14 @@ impl Canvas {
         self.set_property("touch-drawing", touch_drawing.to_value());
     }
 
+    pub fn view_mode(&self) -> bool {
+        self.property::<bool>("view-mode")
+    }
+
+    pub fn set_view_mode(&self, view_mode: bool) {
+        self.set_property("view-mode", view_mode.to_value());
+    }
+
     pub fn visual_debug(&self) -> bool {
         self.property::<bool>("visual-debug")
     }
@@ -913,7 +1092,7 @@ impl Canvas {
             "reveal-child",
         )
         .transform_to(move |_, value| match value.get::<ExpandMode>().unwrap() {
-            ExpandMode::FixedSize => Some(true.to_value()),
+            ExpandMode::FixedSize | ExpandMode::Pages => Some(true.to_value()),
             ExpandMode::EndlessVertical | ExpandMode::Infinite => Some(false.to_value()),
         })
         .flags(glib::BindingFlags::DEFAULT | glib::BindingFlags::SYNC_CREATE)
@@ -1061,6 +1240,23 @@ impl Canvas {
             }),
         );
 
+        // Keyboard
+        // Shift toggles strokes in and out of the selection when selecting single strokes, control snaps the angle of shapes
+        self.imp().key_controller.connect_modifiers(clone!(@weak appwindow => @default-return Inhibit(false), move |_key_controller, modifier| {
+            appwindow.canvas().pens().borrow_mut().selector.toggle_selection = modifier.contains(gdk::ModifierType::SHIFT_MASK);
+            appwindow.canvas().pens().borrow_mut().snapping.angle_snap = modifier.contains(gdk::ModifierType::CONTROL_MASK);
+
+            Inhibit(false)
+        }));
+
+        self.imp().key_controller.connect_key_pressed(clone!(@weak appwindow => @default-return Inhibit(false), move |_key_controller, key, _keycode, modifier| {
+            if let Some(keyboard_key) = input::retreive_keyboard_key(key, modifier) {
+                Inhibit(input::process_key_pressed(keyboard_key, &appwindow))
+            } else {
+                Inhibit(false)
+            }
+        }));
+
         // Drop Target
         let drop_target = DropTarget::builder()
             .name("canvas_drop_target")
@@ -1173,14 +1369,17 @@ impl Canvas {
                     .viewport_in_sheet_coords()
                     .translate(new_values - self.adj_values());
 
-                self.sheet()
+                // The sheet only grows when the viewport gets close to a new chunk
+                if self
+                    .sheet()
                     .borrow_mut()
-                    .expand_sheet_mode_infinite_for_viewport(new_viewport);
-
-                self.update_adj_config(na::vector![
-                    f64::from(self.allocation().width()),
-                    f64::from(self.allocation().height())
-                ]);
+                    .expand_sheet_mode_infinite_for_viewport(new_viewport)
+                {
+                    self.update_adj_config(na::vector![
+                        f64::from(self.allocation().width()),
+                        f64::from(self.allocation().height())
+                    ]);
+                }
             }
             _ => {}
         }
@@ -1197,7 +1396,7 @@ impl Canvas {
         let hadj = self.hadjustment().unwrap();
 
         let (h_lower, h_upper) = match self.expand_mode() {
-            ExpandMode::FixedSize | ExpandMode::EndlessVertical => (
+            ExpandMode::FixedSize | ExpandMode::EndlessVertical | ExpandMode::Pages => (
                 (self.sheet().borrow().x - Canvas::SHADOW_WIDTH) * total_zoom,
                 (self.sheet().borrow().x + self.sheet().borrow().width + Canvas::SHADOW_WIDTH)
                     * total_zoom,
@@ -1211,7 +1410,7 @@ impl Canvas {
         let vadj = self.vadjustment().unwrap();
 
         let (v_lower, v_upper) = match self.expand_mode() {
-            ExpandMode::FixedSize | ExpandMode::EndlessVertical => (
+            ExpandMode::FixedSize | ExpandMode::EndlessVertical | ExpandMode::Pages => (
                 (self.sheet().borrow().y - Canvas::SHADOW_WIDTH) * total_zoom,
                 (self.sheet().borrow().y + self.sheet().borrow().height + Canvas::SHADOW_WIDTH)
                     * total_zoom,
@@ -1260,6 +1459,9 @@ impl Canvas {
                     .borrow_mut()
                     .expand_sheet_mode_infinite_for_viewport(self.viewport_in_sheet_coords());
             }
+            ExpandMode::Pages => {
+                self.sheet().borrow_mut().resize_sheet_mode_pages();
+            }
         }
 
         self.queue_resize();
@@ -1284,6 +1486,9 @@ impl Canvas {
                     .borrow_mut()
                     .expand_sheet_mode_infinite_for_viewport(self.viewport_in_sheet_coords());
             }
+            ExpandMode::Pages => {
+                self.sheet().borrow_mut().resize_sheet_mode_pages();
+            }
         }
         self.queue_resize();
     }
@@ -1317,6 +1522,23 @@ impl Canvas {
         self.update_adj_values(new_adj_values);
     }
 
+    /// The index of the page of the page layout in the center of the viewport. None if the sheet has no page layout
+    pub fn current_page_index(&self) -> Option<usize> {
+        let viewport_center = self.viewport_in_sheet_coords().center().coords;
+        let sheet = self.sheet();
+        let sheet = sheet.borrow();
+
+        sheet.page_layout.as_ref()?.page_index_at(viewport_center)
+    }
+
+    /// Updates the canvas after the pages of the page layout have changed
+    pub fn update_for_changed_pages(&self) {
+        self.selection_modifier().update_state(self);
+        self.set_unsaved_changes(true);
+        self.update_background_rendernode(false);
+        self.regenerate_content(false, true);
+    }
+
     /// Zoom temporarily to a new zoom, not regenerating the contents while doing it.
     /// To zoom and regenerate the content and reset the temporary zoom, use zoom_to().
     pub fn zoom_temporarily_to(&self, temp_zoom: f64) {
@@ -1348,6 +1570,182 @@ impl Canvas {
         self.regenerate_content(false, true);
     }
 
+    /// Periodically updates and redraws the pen animations until they are finished. Does nothing if they are already running
+    pub fn start_pen_animation(&self) {
+        if self.imp().pen_animation_running.replace(true) {
+            return;
+        }
+
+        glib::source::timeout_add_local(
+            Self::PEN_ANIMATION_INTERVAL,
+            clone!(@weak self as canvas => @default-return glib::Continue(false), move || {
+                let running = canvas.pens().borrow_mut().update_animations(time::Instant::now());
+                canvas.imp().pen_animation_running.set(running);
+                canvas.queue_draw();
+
+                glib::Continue(running)
+            }),
+        );
+    }
+
+    pub fn presenting(&self) -> bool {
+        self.imp().presentation.borrow().is_some()
+    }
+
+    /// Presents the sheet one page at a time, beginning with the page in the center of the viewport. The pens don't edit the sheet while presenting
+    pub fn start_presentation(&self) {
+        if self.presenting() {
+            return;
+        }
+        let viewport_center = self.viewport_in_sheet_coords().center().coords;
+
+        self.imp()
+            .presentation_return_view
+            .set(Some((self.total_zoom(), viewport_center)));
+        self.imp()
+            .presentation
+            .replace(Some(Presentation::new_at_pos(
+                &self.sheet().borrow(),
+                viewport_center,
+            )));
+        self.pens().borrow_mut().set_presenting(true);
+
+        self.update_presentation_view(time::Instant::now());
+    }
+
+    /// Stops presenting and returns to the view from before
+    pub fn stop_presentation(&self) {
+        if self.imp().presentation.take().is_none() {
+            return;
+        }
+        self.pens().borrow_mut().set_presenting(false);
+
+        if let Some((zoom, center)) = self.imp().presentation_return_view.take() {
+            self.zoom_to(zoom);
+            self.center_around_coord_on_sheet(center);
+        }
+        self.queue_draw();
+    }
+
+    pub fn presentation_next_page(&self) {
+        self.change_presentation_page(Presentation::next_page);
+    }
+
+    pub fn presentation_previous_page(&self) {
+        self.change_presentation_page(Presentation::previous_page);
+    }
+
+    fn change_presentation_page(
+        &self,
+        change_page: fn(&mut Presentation, &Sheet, na::Vector2<f64>, time::Instant) -> bool,
+    ) {
+        let now = time::Instant::now();
+        let viewport_size = self.presentation_viewport_size();
+
+        let changed = match &mut *self.imp().presentation.borrow_mut() {
+            Some(presentation) => {
+                change_page(presentation, &self.sheet().borrow(), viewport_size, now)
+            }
+            None => return,
+        };
+
+        if changed {
+            self.start_presentation_transition();
+        }
+    }
+
+    fn presentation_viewport_size(&self) -> na::Vector2<f64> {
+        na::vector![
+            f64::from(self.parent().unwrap().width()),
+            f64::from(self.parent().unwrap().height())
+        ]
+    }
+
+    /// Updates the view to the one of the presentation at the time. Returns whether it is still transitioning
+    fn update_presentation_view(&self, now: time::Instant) -> bool {
+        let (view, transitioning) = match &*self.imp().presentation.borrow() {
+            Some(presentation) => (
+                presentation.view(
+                    &self.sheet().borrow(),
+                    self.presentation_viewport_size(),
+                    now,
+                ),
+                presentation.transitioning(now),
+            ),
+            None => return false,
+        };
+
+        if let Some(view) = view {
+            // Only regenerates the content once the transition has finished
+            if transitioning {
+                self.zoom_temporarily_to(view.zoom);
+            } else {
+                self.zoom_to(view.zoom);
+            }
+            self.center_around_coord_on_sheet(view.center);
+        }
+        self.queue_draw();
+
+        transitioning
+    }
+
+    fn start_presentation_transition(&self) {
+        if self.imp().presentation_transition_running.replace(true) {
+            return;
+        }
+
+        glib::source::timeout_add_local(
+            Self::PEN_ANIMATION_INTERVAL,
+            clone!(@weak self as canvas => @default-return glib::Continue(false), move || {
+                let running = canvas.update_presentation_view(time::Instant::now());
+                canvas.imp().presentation_transition_running.set(running);
+
+                glib::Continue(running)
+            }),
+        );
+    }
+
+    /// Adds a bookmark at the center of the current view and returns its index. Empty names get a default name
+    pub fn add_bookmark(&self, name: &str) -> usize {
+        let viewport_center = self.viewport_in_sheet_coords().center().coords;
+        let index =
+            self.sheet()
+                .borrow_mut()
+                .add_bookmark(name, viewport_center, self.total_zoom());
+
+        self.set_unsaved_changes(true);
+        index
+    }
+
+    /// Jumps to the view of the bookmark. Returns false if there is no bookmark with the index
+    pub fn jump_to_bookmark(&self, index: usize) -> bool {
+        let view = match self.sheet().borrow().bookmark_view(index) {
+            Some(view) => view,
+            None => return false,
+        };
+
+        self.zoom_to(view.zoom.clamp(Self::ZOOM_MIN, Self::ZOOM_MAX));
+        self.center_around_coord_on_sheet(view.center);
+        self.queue_draw();
+        true
+    }
+
+    /// Jumps to the next bookmark after the center of the current view. Returns false if there is none
+    pub fn jump_to_next_bookmark(&self) -> bool {
+        let viewport_center = self.viewport_in_sheet_coords().center().coords;
+        let index = self.sheet().borrow().next_bookmark_index(viewport_center);
+
+        index.map_or(false, |index| self.jump_to_bookmark(index))
+    }
+
+    /// Jumps to the previous bookmark before the center of the current view. Returns false if there is none
+    pub fn jump_to_previous_bookmark(&self) -> bool {
+        let viewport_center = self.viewport_in_sheet_coords().center().coords;
+        let index = self.sheet().borrow().previous_bookmark_index(viewport_center);
+
+        index.map_or(false, |index| self.jump_to_bookmark(index))
+    }
+
     /// Zooms temporarily and then scale the canvas and its contents to a new zoom after a given time.
     /// Repeated calls to this function reset the timeout.
     pub fn zoom_temporarily_then_scale_to_after_timeout(
@@ -1381,12 +1779,9 @@ impl Canvas {
 
     /// Update rendernodes of the background. Used when the background itself did not change, but for example the format
     pub fn update_background_rendernode(&self, redraw: bool) {
-        let sheet_bounds = self.sheet().borrow().bounds();
-
         self.sheet()
         .borrow_mut()
-        .background
-        .update_rendernode(self.zoom(), sheet_bounds, Some(self.viewport_in_sheet_coords()) ).unwrap_or_else(|e| {
+        .update_background_rendernodes(self.zoom(), Some(self.viewport_in_sheet_coords())).unwrap_or_else(|e| {
             log::error!("failed to update rendernode for background in update_background_rendernode() with Err {}", e);
         });
 
@@ -1410,12 +1805,10 @@ impl Canvas {
     /// regenerating the background image and rendernode.
     /// use for example when changing the background pattern or zoom
     pub fn regenerate_background(&self, redraw: bool) {
-        let background_bounds = self.sheet().borrow().bounds();
         let total_zoom = self.total_zoom();
 
-        if let Err(e) = self.sheet().borrow_mut().background.regenerate_background(
+        if let Err(e) = self.sheet().borrow_mut().regenerate_backgrounds(
             total_zoom,
-            background_bounds,
             Some(self.viewport_in_sheet_coords()),
             self.renderer(),
         ) {
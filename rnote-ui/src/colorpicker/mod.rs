@@ -38,6 +38,7 @@ mod imp {
         pub amount_colorbuttons: Cell<u32>,
         pub currentcolor_setters: Rc<RefCell<Vec<ColorSetter>>>,
         pub current_color: Cell<gdk::RGBA>,
+        pub palettes_shown: Cell<bool>,
     }
 
     impl Default for ColorPicker {
@@ -58,6 +59,7 @@ mod imp {
                 currentcolor_setters: Rc::new(RefCell::new(Vec::with_capacity(
                     super::ColorPicker::AMOUNT_COLORBUTTONS_DEFAULT as usize,
                 ))),
+                palettes_shown: Cell::new(false),
             }
         }
     }
@@ -365,9 +367,10 @@ mod imp {
     }
 }
 
-use gtk4::{gdk, glib, prelude::*, subclass::prelude::*, PositionType, Widget};
+use gtk4::{gdk, glib, prelude::*, subclass::prelude::*, Orientation, PositionType, Widget};
 
 use rnote_engine::compose::color::Color;
+use rnote_engine::compose::palette::Palette;
 
 glib::wrapper! {
     pub struct ColorPicker(ObjectSubclass<imp::ColorPicker>)
@@ -385,6 +388,8 @@ impl ColorPicker {
     pub const AMOUNT_COLORBUTTONS_MIN: u32 = 1;
     pub const AMOUNT_COLORBUTTONS_MAX: u32 = 1000;
     pub const AMOUNT_COLORBUTTONS_DEFAULT: u32 = 8;
+    /// The colors in a row of palettes which don't specify it
+    pub const PALETTE_COLUMNS_DEFAULT: u32 = 9;
 
     pub fn new(current_color: gdk::RGBA) -> Self {
         let color_picker: ColorPicker =
@@ -450,4 +455,31 @@ impl ColorPicker {
             colorsetter.set_color(color.to_gdk());
         }
     }
+
+    /// Shows the palettes in the color chooser in place of the ones shown before.
+    /// The default palette of the chooser is kept until palettes are shown for the first time
+    pub fn set_palettes(&self, palettes: &[&Palette]) {
+        if palettes.is_empty() && !self.imp().palettes_shown.get() {
+            return;
+        }
+        let colorchooser = self.imp().colorchooser.get();
+
+        // Without colors all palettes are removed
+        colorchooser.add_palette(Orientation::Horizontal, 0, &[]);
+        for palette in palettes {
+            let colors = palette
+                .colors
+                .iter()
+                .map(|palette_color| palette_color.color.to_gdk())
+                .collect::<Vec<gdk::RGBA>>();
+            let columns = if palette.columns > 0 {
+                palette.columns
+            } else {
+                Self::PALETTE_COLUMNS_DEFAULT
+            };
+
+            colorchooser.add_palette(Orientation::Horizontal, columns as i32, &colors);
+        }
+        self.imp().palettes_shown.set(true);
+    }
 }
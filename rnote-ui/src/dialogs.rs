@@ -1,13 +1,23 @@
 use gettextrs::gettext;
 use gtk4::{
-    gio, AboutDialog, FileChooserAction, FileChooserNative, FileFilter, MessageDialog,
-    ResponseType, ShortcutsWindow,
+    gio, AboutDialog, CheckButton, DropDown, Entry, FileChooserAction, FileChooserNative,
+    FileFilter, Label, ListBox, MessageDialog, ResponseType, ShortcutsWindow, SpinButton,
 };
 use gtk4::{glib, glib::clone, prelude::*, Builder};
 
 use crate::appwindow::RnoteAppWindow;
 use crate::utils;
 use crate::{app::RnoteApp, config};
+use rnote_engine::sheet::bitmapexport::{BitmapExportFormat, BitmapExportPrefs, BitmapExportScope};
+use rnote_engine::sheet::recovery::RecoveryMetadata;
+use rnote_engine::strokes::checkboxstroke::CheckboxStroke;
+use rnote_engine::strokes::mathstroke::MathStroke;
+use rnote_engine::strokes::pdfimport::{PdfImportOptions, PdfImportPagesType};
+use rnote_engine::strokes::strokestyle::StrokeStyle;
+use rnote_engine::strokesstate::link_comp::StrokeLink;
+use rnote_engine::strokesstate::tag_comp::{TagFilter, TagFilterMode};
+
+use p2d::bounding_volume::AABB;
 
 // About Dialog
 pub fn dialog_about(appwindow: &RnoteAppWindow) {
@@ -84,6 +94,527 @@ pub fn dialog_clear_sheet(appwindow: &RnoteAppWindow) {
     dialog_clear_sheet.show();
 }
 
+pub fn dialog_selection_link(appwindow: &RnoteAppWindow) {
+    let builder =
+        Builder::from_resource((String::from(config::APP_IDPATH) + "ui/dialogs.ui").as_str());
+    let dialog_selection_link: MessageDialog = builder.object("dialog_selection_link").unwrap();
+    let selection_link_entry: Entry = builder.object("selection_link_entry").unwrap();
+
+    // Start with the link of the first selected stroke
+    let current_link = {
+        let sheet = appwindow.canvas().sheet();
+        let sheet = sheet.borrow();
+        sheet
+            .strokes_state
+            .selection_keys_as_rendered()
+            .into_iter()
+            .find_map(|key| sheet.strokes_state.link(key).cloned())
+    };
+    if let Some(current_link) = current_link {
+        selection_link_entry.set_text(&current_link.to_string());
+    }
+
+    dialog_selection_link.set_transient_for(Some(appwindow));
+
+    dialog_selection_link.connect_response(
+        clone!(@weak selection_link_entry, @weak appwindow => move |dialog_selection_link, responsetype| {
+            match responsetype {
+                ResponseType::Ok => {
+                    let text = selection_link_entry.text();
+
+                    let link = if text.trim().is_empty() {
+                        None
+                    } else {
+                        match text.parse::<StrokeLink>() {
+                            Ok(link) => Some(link),
+                            Err(e) => {
+                                log::error!("parsing the link failed, {}", e);
+                                selection_link_entry.add_css_class("error");
+                                return;
+                            }
+                        }
+                    };
+
+                    appwindow.canvas().sheet().borrow_mut().strokes_state.set_selection_link(link);
+                    appwindow.canvas().set_unsaved_changes(true);
+
+                    dialog_selection_link.close();
+                },
+                _ => {
+                    dialog_selection_link.close();
+                }
+            }
+        }),
+    );
+
+    dialog_selection_link.show();
+}
+
+/// Edits the tags of the selected strokes, starting with the tags they have in common
+pub fn dialog_selection_tags(appwindow: &RnoteAppWindow) {
+    let builder =
+        Builder::from_resource((String::from(config::APP_IDPATH) + "ui/dialogs.ui").as_str());
+    let dialog_selection_tags: MessageDialog = builder.object("dialog_selection_tags").unwrap();
+    let selection_tags_entry: Entry = builder.object("selection_tags_entry").unwrap();
+
+    let current_tags = appwindow
+        .canvas()
+        .sheet()
+        .borrow()
+        .strokes_state
+        .selection_tags();
+    selection_tags_entry.set_text(&current_tags.join(", "));
+
+    dialog_selection_tags.set_transient_for(Some(appwindow));
+
+    dialog_selection_tags.connect_response(
+        clone!(@weak selection_tags_entry, @weak appwindow => move |dialog_selection_tags, responsetype| {
+            match responsetype {
+                ResponseType::Ok => {
+                    let text = selection_tags_entry.text();
+
+                    appwindow.canvas().sheet().borrow_mut().strokes_state.set_selection_tags(text.split(','));
+                    appwindow.canvas().set_unsaved_changes(true);
+                    appwindow.canvas().queue_draw();
+
+                    dialog_selection_tags.close();
+                },
+                _ => {
+                    dialog_selection_tags.close();
+                }
+            }
+        }),
+    );
+
+    dialog_selection_tags.show();
+}
+
+/// Filters the drawn strokes by their tags, starting with the current filter
+pub fn dialog_tag_filter(appwindow: &RnoteAppWindow) {
+    let builder =
+        Builder::from_resource((String::from(config::APP_IDPATH) + "ui/dialogs.ui").as_str());
+    let dialog_tag_filter: MessageDialog = builder.object("dialog_tag_filter").unwrap();
+    let tag_filter_entry: Entry = builder.object("tag_filter_entry").unwrap();
+    let tag_filter_tags_label: Label = builder.object("tag_filter_tags_label").unwrap();
+    let tag_filter_hide_checkbutton: CheckButton =
+        builder.object("tag_filter_hide_checkbutton").unwrap();
+
+    let (current_filter, all_tags) = {
+        let sheet = appwindow.canvas().sheet();
+        let sheet = sheet.borrow();
+        (
+            sheet.strokes_state.tag_filter().cloned(),
+            sheet.strokes_state.all_tags(),
+        )
+    };
+    if let Some(current_filter) = current_filter {
+        tag_filter_entry.set_text(&current_filter.tags.join(", "));
+        tag_filter_hide_checkbutton.set_active(current_filter.mode == TagFilterMode::Hide);
+    }
+    // The tags in the document, to choose from
+    if all_tags.is_empty() {
+        tag_filter_tags_label.set_text(&gettext("No strokes are tagged yet"));
+    } else {
+        tag_filter_tags_label.set_text(&all_tags.join(", "));
+    }
+
+    dialog_tag_filter.set_transient_for(Some(appwindow));
+
+    dialog_tag_filter.connect_response(
+        clone!(@weak tag_filter_entry, @weak tag_filter_hide_checkbutton, @weak appwindow => move |dialog_tag_filter, responsetype| {
+            match responsetype {
+                ResponseType::Ok => {
+                    let mode = if tag_filter_hide_checkbutton.is_active() {
+                        TagFilterMode::Hide
+                    } else {
+                        TagFilterMode::Dim
+                    };
+                    let text = tag_filter_entry.text();
+
+                    appwindow.canvas().sheet().borrow_mut().strokes_state.set_tag_filter(TagFilter::new(text.split(','), mode));
+                    appwindow.canvas().queue_draw();
+
+                    dialog_tag_filter.close();
+                },
+                _ => {
+                    dialog_tag_filter.close();
+                }
+            }
+        }),
+    );
+
+    dialog_tag_filter.show();
+}
+
+/// Edits the source of the selected math expression, or inserts a new one in the center of the viewport if none is selected
+pub fn dialog_math(appwindow: &RnoteAppWindow) {
+    let builder =
+        Builder::from_resource((String::from(config::APP_IDPATH) + "ui/dialogs.ui").as_str());
+    let dialog_math: MessageDialog = builder.object("dialog_math").unwrap();
+    let math_source_entry: Entry = builder.object("math_source_entry").unwrap();
+    let math_error_label: Label = builder.object("math_error_label").unwrap();
+
+    let edited_key = {
+        let sheet = appwindow.canvas().sheet();
+        let sheet = sheet.borrow();
+        let selection_keys = sheet.strokes_state.selection_keys_as_rendered();
+
+        match selection_keys.as_slice() {
+            [key] => sheet.strokes_state.get_mathstroke(*key).map(|mathstroke| {
+                math_source_entry.set_text(&mathstroke.source);
+                *key
+            }),
+            _ => None,
+        }
+    };
+
+    dialog_math.set_transient_for(Some(appwindow));
+
+    math_source_entry.connect_changed(
+        clone!(@weak math_error_label => move |math_source_entry| {
+            match MathStroke::parse_source(&math_source_entry.text()) {
+                Ok(_) => {
+                    math_source_entry.remove_css_class("error");
+                    math_error_label.set_text("");
+                }
+                Err(e) => {
+                    math_source_entry.add_css_class("error");
+                    math_error_label.set_text(&e.to_string());
+                }
+            }
+        }),
+    );
+
+    dialog_math.connect_response(
+        clone!(@weak math_source_entry, @weak appwindow => move |dialog_math, responsetype| {
+            match responsetype {
+                ResponseType::Ok => {
+                    let source = math_source_entry.text().to_string();
+                    if source.trim().is_empty() || MathStroke::parse_source(&source).is_err() {
+                        math_source_entry.add_css_class("error");
+                        return;
+                    }
+
+                    if let Some(key) = edited_key {
+                        if let Some(mathstroke) = appwindow.canvas().sheet().borrow_mut().strokes_state.get_mathstroke_mut(key) {
+                            mathstroke.set_source(source);
+                        }
+                        appwindow.canvas().sheet().borrow_mut().strokes_state.update_geometry_for_stroke(key);
+                    } else {
+                        // New expressions take on the font size and color of the typewriter
+                        let text_style = appwindow.canvas().pens().borrow().typewriter.text_style.clone();
+                        let pos = appwindow.canvas().viewport_in_sheet_coords().center().coords;
+                        let mathstroke = MathStroke::new(source, pos, text_style.font_size, text_style.color);
+
+                        let all_strokes = appwindow.canvas().sheet().borrow().strokes_state.keys_sorted_chrono();
+                        appwindow.canvas().sheet().borrow_mut().strokes_state.set_selected_keys(&all_strokes, false);
+                        let key = appwindow.canvas().sheet().borrow_mut().strokes_state.insert_stroke(StrokeStyle::MathStroke(mathstroke));
+                        appwindow.canvas().sheet().borrow_mut().strokes_state.set_selected(key, true);
+                        appwindow.canvas().set_empty(false);
+                    }
+
+                    appwindow.canvas().set_unsaved_changes(true);
+                    appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                    appwindow.canvas().regenerate_content(false, true);
+
+                    dialog_math.close();
+                },
+                _ => {
+                    dialog_math.close();
+                }
+            }
+        }),
+    );
+
+    dialog_math.show();
+}
+
+/// Edits the label of the selected checkbox, or inserts a new one in the center of the viewport if none is selected
+pub fn dialog_checkbox(appwindow: &RnoteAppWindow) {
+    let builder =
+        Builder::from_resource((String::from(config::APP_IDPATH) + "ui/dialogs.ui").as_str());
+    let dialog_checkbox: MessageDialog = builder.object("dialog_checkbox").unwrap();
+    let checkbox_text_entry: Entry = builder.object("checkbox_text_entry").unwrap();
+
+    let edited_key = {
+        let sheet = appwindow.canvas().sheet();
+        let sheet = sheet.borrow();
+        let selection_keys = sheet.strokes_state.selection_keys_as_rendered();
+
+        match selection_keys.as_slice() {
+            [key] => sheet.strokes_state.get_checkboxstroke(*key).map(|checkboxstroke| {
+                checkbox_text_entry.set_text(&checkboxstroke.text);
+                *key
+            }),
+            _ => None,
+        }
+    };
+
+    dialog_checkbox.set_transient_for(Some(appwindow));
+
+    dialog_checkbox.connect_response(
+        clone!(@weak checkbox_text_entry, @weak appwindow => move |dialog_checkbox, responsetype| {
+            match responsetype {
+                ResponseType::Ok => {
+                    let text = checkbox_text_entry.text().trim().to_string();
+                    if text.is_empty() {
+                        checkbox_text_entry.add_css_class("error");
+                        return;
+                    }
+
+                    if let Some(key) = edited_key {
+                        if let Some(checkboxstroke) = appwindow.canvas().sheet().borrow_mut().strokes_state.get_checkboxstroke_mut(key) {
+                            checkboxstroke.set_text(text);
+                        }
+                        appwindow.canvas().sheet().borrow_mut().strokes_state.update_geometry_for_stroke(key);
+                    } else {
+                        // New checkboxes take on the font size and color of the typewriter
+                        let text_style = appwindow.canvas().pens().borrow().typewriter.text_style.clone();
+                        let pos = appwindow.canvas().viewport_in_sheet_coords().center().coords;
+                        let checkboxstroke = CheckboxStroke::new(text, pos, text_style.font_size, text_style.color);
+
+                        let all_strokes = appwindow.canvas().sheet().borrow().strokes_state.keys_sorted_chrono();
+                        appwindow.canvas().sheet().borrow_mut().strokes_state.set_selected_keys(&all_strokes, false);
+                        let key = appwindow.canvas().sheet().borrow_mut().strokes_state.insert_stroke(StrokeStyle::CheckboxStroke(checkboxstroke));
+                        appwindow.canvas().sheet().borrow_mut().strokes_state.set_selected(key, true);
+                        appwindow.canvas().set_empty(false);
+                    }
+
+                    appwindow.canvas().set_unsaved_changes(true);
+                    appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                    appwindow.canvas().regenerate_content(false, true);
+
+                    dialog_checkbox.close();
+                },
+                _ => {
+                    dialog_checkbox.close();
+                }
+            }
+        }),
+    );
+
+    dialog_checkbox.show();
+}
+
+/// Lists the open todos of the document. Activating one jumps to it
+pub fn dialog_open_todos(appwindow: &RnoteAppWindow) {
+    let open_todos = appwindow.canvas().sheet().borrow().open_todos();
+    if open_todos.is_empty() {
+        adw::prelude::ActionGroupExt::activate_action(
+            appwindow,
+            "text-toast",
+            Some(&gettext("No open todos").to_variant()),
+        );
+        return;
+    }
+
+    let builder =
+        Builder::from_resource((String::from(config::APP_IDPATH) + "ui/dialogs.ui").as_str());
+    let dialog_open_todos: MessageDialog = builder.object("dialog_open_todos").unwrap();
+    let open_todos_listbox: ListBox = builder.object("open_todos_listbox").unwrap();
+
+    for todo in open_todos {
+        let subtitle = todo
+            .page_index
+            .map(|page_index| format!("{} {}", gettext("Page"), page_index + 1))
+            .unwrap_or_default();
+        let row = adw::ActionRow::builder()
+            .title(&todo.title)
+            .subtitle(&subtitle)
+            .activatable(true)
+            .build();
+
+        adw::prelude::ActionRowExt::connect_activated(
+            &row,
+            clone!(@weak dialog_open_todos, @weak appwindow => move |_row| {
+                appwindow.canvas().center_around_coord_on_sheet(todo.pos);
+                appwindow.canvas().queue_draw();
+
+                dialog_open_todos.close();
+            }),
+        );
+        open_todos_listbox.append(&row);
+    }
+
+    dialog_open_todos.set_transient_for(Some(appwindow));
+
+    dialog_open_todos.connect_response(move |dialog_open_todos, _responsetype| {
+        dialog_open_todos.close();
+    });
+
+    dialog_open_todos.show();
+}
+
+/// Crops the selected image. Does nothing if the selection is not a single image
+pub fn dialog_crop_image(appwindow: &RnoteAppWindow) {
+    let key = {
+        let sheet = appwindow.canvas().sheet();
+        let sheet = sheet.borrow();
+
+        match sheet.strokes_state.selection_keys_as_rendered().as_slice() {
+            [key] if sheet.strokes_state.get_bitmapimage(*key).is_some() => *key,
+            _ => {
+                adw::prelude::ActionGroupExt::activate_action(
+                    appwindow,
+                    "text-toast",
+                    Some(&gettext("Select a single image to crop it").to_variant()),
+                );
+                return;
+            }
+        }
+    };
+
+    let builder =
+        Builder::from_resource((String::from(config::APP_IDPATH) + "ui/dialogs.ui").as_str());
+    let dialog_crop_image: MessageDialog = builder.object("dialog_crop_image").unwrap();
+    let left_spinbutton: SpinButton = builder.object("crop_image_left_spinbutton").unwrap();
+    let top_spinbutton: SpinButton = builder.object("crop_image_top_spinbutton").unwrap();
+    let right_spinbutton: SpinButton = builder.object("crop_image_right_spinbutton").unwrap();
+    let bottom_spinbutton: SpinButton = builder.object("crop_image_bottom_spinbutton").unwrap();
+
+    // Start with the current crop
+    if let Some(bitmapimage) = appwindow
+        .canvas()
+        .sheet()
+        .borrow()
+        .strokes_state
+        .get_bitmapimage(key)
+    {
+        let crop_bounds = bitmapimage.crop_bounds();
+        let size = bitmapimage.intrinsic_size;
+
+        left_spinbutton.set_value(100.0 * crop_bounds.mins[0] / size[0]);
+        top_spinbutton.set_value(100.0 * crop_bounds.mins[1] / size[1]);
+        right_spinbutton.set_value(100.0 * (size[0] - crop_bounds.maxs[0]) / size[0]);
+        bottom_spinbutton.set_value(100.0 * (size[1] - crop_bounds.maxs[1]) / size[1]);
+    }
+
+    dialog_crop_image.set_transient_for(Some(appwindow));
+
+    dialog_crop_image.connect_response(
+        clone!(@weak left_spinbutton, @weak top_spinbutton, @weak right_spinbutton, @weak bottom_spinbutton, @weak appwindow => move |dialog_crop_image, responsetype| {
+            let crop = match responsetype {
+                ResponseType::Ok => {
+                    let size = match appwindow.canvas().sheet().borrow().strokes_state.get_bitmapimage(key) {
+                        Some(bitmapimage) => bitmapimage.intrinsic_size,
+                        None => {
+                            dialog_crop_image.close();
+                            return;
+                        }
+                    };
+
+                    Some(AABB::new(
+                        na::point![
+                            size[0] * left_spinbutton.value() / 100.0,
+                            size[1] * top_spinbutton.value() / 100.0
+                        ],
+                        na::point![
+                            size[0] * (1.0 - right_spinbutton.value() / 100.0),
+                            size[1] * (1.0 - bottom_spinbutton.value() / 100.0)
+                        ],
+                    ))
+                }
+                ResponseType::Apply => None,
+                _ => {
+                    dialog_crop_image.close();
+                    return;
+                }
+            };
+
+            if let Some(bitmapimage) = appwindow.canvas().sheet().borrow_mut().strokes_state.get_bitmapimage_mut(key) {
+                bitmapimage.set_crop(crop);
+            }
+            appwindow.canvas().sheet().borrow_mut().strokes_state.update_geometry_for_stroke(key);
+
+            appwindow.canvas().set_unsaved_changes(true);
+            appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+            appwindow.canvas().regenerate_content(false, true);
+
+            dialog_crop_image.close();
+        }),
+    );
+
+    dialog_crop_image.show();
+}
+
+/// Asks for the pages, the page width and the spacing before importing the PDF
+pub fn dialog_import_pdf(
+    appwindow: &RnoteAppWindow,
+    bytes: glib::Bytes,
+    n_pages: u32,
+    target_pos: Option<na::Vector2<f64>>,
+) {
+    let builder =
+        Builder::from_resource((String::from(config::APP_IDPATH) + "ui/dialogs.ui").as_str());
+    let dialog_import_pdf: MessageDialog = builder.object("dialog_import_pdf").unwrap();
+    let first_page_spinbutton: SpinButton =
+        builder.object("import_pdf_first_page_spinbutton").unwrap();
+    let last_page_spinbutton: SpinButton =
+        builder.object("import_pdf_last_page_spinbutton").unwrap();
+    let page_width_spinbutton: SpinButton =
+        builder.object("import_pdf_page_width_spinbutton").unwrap();
+    let page_spacing_spinbutton: SpinButton =
+        builder.object("import_pdf_page_spacing_spinbutton").unwrap();
+
+    first_page_spinbutton.set_range(1.0, f64::from(n_pages));
+    first_page_spinbutton.set_value(1.0);
+    last_page_spinbutton.set_range(1.0, f64::from(n_pages));
+    last_page_spinbutton.set_value(f64::from(n_pages));
+    page_width_spinbutton.set_value(appwindow.canvas().pdf_import_width());
+    page_spacing_spinbutton.set_value(PdfImportOptions::PAGE_SPACING_DEFAULT);
+
+    // Keep the page range valid
+    first_page_spinbutton.connect_value_changed(
+        clone!(@weak last_page_spinbutton => move |first_page_spinbutton| {
+            if last_page_spinbutton.value() < first_page_spinbutton.value() {
+                last_page_spinbutton.set_value(first_page_spinbutton.value());
+            }
+        }),
+    );
+    last_page_spinbutton.connect_value_changed(
+        clone!(@weak first_page_spinbutton => move |last_page_spinbutton| {
+            if first_page_spinbutton.value() > last_page_spinbutton.value() {
+                first_page_spinbutton.set_value(last_page_spinbutton.value());
+            }
+        }),
+    );
+
+    dialog_import_pdf.set_transient_for(Some(appwindow));
+
+    dialog_import_pdf.connect_response(
+        clone!(@weak first_page_spinbutton, @weak last_page_spinbutton, @weak page_width_spinbutton, @weak page_spacing_spinbutton, @weak appwindow => move |dialog_import_pdf, responsetype| {
+            match responsetype {
+                ResponseType::Ok => {
+                    let page_width = f64::from(appwindow.canvas().sheet().borrow().format.width)
+                        * (page_width_spinbutton.value() / 100.0);
+                    let pages_type = if appwindow.canvas().pdf_import_as_vector() {
+                        PdfImportPagesType::Vector
+                    } else {
+                        PdfImportPagesType::Bitmap
+                    };
+
+                    // The spinbuttons count the pages from 1
+                    let options = PdfImportOptions {
+                        page_range: (first_page_spinbutton.value_as_int() as u32 - 1)..(last_page_spinbutton.value_as_int() as u32),
+                        page_width: Some(page_width.round()),
+                        page_spacing: page_spacing_spinbutton.value(),
+                        pages_type,
+                    };
+
+                    appwindow.import_pdf_bytes_w_options(bytes.clone(), target_pos, options);
+
+                    dialog_import_pdf.close();
+                },
+                _ => {
+                    dialog_import_pdf.close();
+                }
+            }
+        }),
+    );
+
+    dialog_import_pdf.show();
+}
+
 pub fn dialog_new_sheet(appwindow: &RnoteAppWindow) {
     let builder =
         Builder::from_resource((String::from(config::APP_IDPATH) + "ui/dialogs.ui").as_str());
@@ -97,6 +628,9 @@ pub fn dialog_new_sheet(appwindow: &RnoteAppWindow) {
                 appwindow.application().unwrap().downcast::<RnoteApp>().unwrap().set_output_file(None, &appwindow);
 
                 appwindow.canvas().sheet().borrow_mut().strokes_state.clear();
+                appwindow.canvas().sheet().borrow_mut().palette = None;
+                appwindow.canvas().sheet().borrow_mut().page_layout = None;
+                appwindow.canvas().resize_sheet_to_fit_strokes();
                 appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
                 appwindow.canvas().set_unsaved_changes(false);
                 appwindow.canvas().set_empty(true);
@@ -179,6 +713,49 @@ pub fn dialog_open_overwrite(appwindow: &RnoteAppWindow) {
     dialog_open_input_file.show();
 }
 
+pub fn dialog_recover_sheet(appwindow: &RnoteAppWindow, metadata: &RecoveryMetadata) {
+    let builder =
+        Builder::from_resource((String::from(config::APP_IDPATH) + "ui/dialogs.ui").as_str());
+    let dialog_recover_sheet: MessageDialog = builder.object("dialog_recover_sheet").unwrap();
+
+    let snapshot_time = glib::DateTime::from_unix_local(metadata.snapshot_time.timestamp())
+        .and_then(|datetime| datetime.format("%F %H:%M"))
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+    let mut secondary_text = format!("{}: {}", gettext("Last snapshot"), snapshot_time);
+    if let Some(file_name) = metadata
+        .file_path
+        .as_ref()
+        .and_then(|file_path| file_path.file_name())
+    {
+        secondary_text += &format!("\n{}: {}", gettext("File"), file_name.to_string_lossy());
+    }
+    dialog_recover_sheet.set_secondary_text(Some(&secondary_text));
+
+    dialog_recover_sheet.set_transient_for(Some(appwindow));
+
+    dialog_recover_sheet.connect_response(
+        clone!(@weak appwindow => move |dialog_recover_sheet, responsetype| {
+            match responsetype {
+                ResponseType::Accept => {
+                    dialog_recover_sheet.close();
+                    appwindow.restore_recovery();
+                }
+                _ => {
+                    dialog_recover_sheet.close();
+                    if let Err(e) = appwindow.recovery().borrow_mut().discard() {
+                        log::error!("discarding the recovery snapshot failed with Err {}", e);
+                    }
+                }
+            }
+
+            appwindow.start_recovery_timer();
+        }),
+    );
+
+    dialog_recover_sheet.show();
+}
+
 // FileChooserNative Dialogs
 
 pub fn dialog_open_sheet(appwindow: &RnoteAppWindow) {
@@ -296,8 +873,9 @@ pub fn dialog_save_sheet_as(appwindow: &RnoteAppWindow) {
                     match dialog_export_sheet.file() {
                         Some(file) => {
                             match file.basename() {
-                                Some(basename) => {
-                                    match appwindow.canvas().sheet().borrow().save_sheet_as_rnote_bytes(&basename.to_string_lossy()) {
+                                Some(_) => {
+                                    let res = appwindow.canvas().sheet().borrow_mut().save_sheet_as_rnote_bytes();
+                                    match res {
                                         Ok(bytes) => {
                                             let main_cx = glib::MainContext::default();
 
@@ -353,12 +931,17 @@ pub fn dialog_import_file(appwindow: &RnoteAppWindow) {
     filter.add_mime_type("image/svg+xml");
     filter.add_mime_type("image/png");
     filter.add_mime_type("image/jpeg");
+    filter.add_mime_type("image/webp");
     filter.add_mime_type("application/pdf");
+    filter.add_mime_type("image/emf");
     filter.add_pattern("*.svg");
     filter.add_pattern("*.png");
     filter.add_pattern("*.jpg");
+    filter.add_pattern("*.webp");
     filter.add_pattern("*.pdf");
-    filter.set_name(Some(&gettext("PNG / SVG / JPG / PDF file")));
+    filter.add_pattern("*.isf");
+    filter.add_pattern("*.emf");
+    filter.set_name(Some(&gettext("PNG / SVG / JPG / WebP / PDF / ISF / EMF ink file")));
 
     let dialog_import_file: FileChooserNative = FileChooserNative::builder()
         .title(&gettext("Import file"))
@@ -396,6 +979,153 @@ pub fn dialog_import_file(appwindow: &RnoteAppWindow) {
     *appwindow.filechoosernative().borrow_mut() = Some(dialog_import_file);
 }
 
+/// Attaches a file to the sheet, stored in the .rnote file
+pub fn dialog_attach_file(appwindow: &RnoteAppWindow) {
+    let dialog_attach_file: FileChooserNative = FileChooserNative::builder()
+        .title(&gettext("Attach file"))
+        .modal(true)
+        .transient_for(appwindow)
+        .accept_label(&gettext("Attach"))
+        .cancel_label(&gettext("Cancel"))
+        .action(FileChooserAction::Open)
+        .select_multiple(false)
+        .build();
+
+    dialog_attach_file.connect_response(
+        clone!(@weak appwindow => move |dialog_attach_file, responsetype| {
+            match responsetype {
+                ResponseType::Accept => {
+                    match dialog_attach_file.file().and_then(|file| file.path()) {
+                        Some(path) => {
+                            let res = appwindow.canvas().sheet().borrow_mut().add_attachment_from_path(&path);
+
+                            match res {
+                                Ok(_) => {
+                                    appwindow.canvas().set_unsaved_changes(true);
+                                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "text-toast", Some(&gettext("Attached the file").to_variant()));
+                                }
+                                Err(e) => {
+                                    log::error!("attaching the file failed with Err {}", e);
+                                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Attaching the file failed").to_variant()));
+                                }
+                            }
+                        },
+                        None => { log::error!("unable to attach file. No file selected.")},
+                    }
+                }
+                _ => {
+                }
+            }
+        }),
+    );
+
+    dialog_attach_file.show();
+    // keeping the filechooser around because otherwise GTK won't keep it alive
+    *appwindow.filechoosernative().borrow_mut() = Some(dialog_attach_file);
+}
+
+/// Extracts all files attached to the sheet into a directory
+pub fn dialog_extract_attachments(appwindow: &RnoteAppWindow) {
+    if appwindow.canvas().sheet().borrow().attachments().is_empty() {
+        adw::prelude::ActionGroupExt::activate_action(appwindow, "text-toast", Some(&gettext("The sheet has no attached files").to_variant()));
+        return;
+    }
+
+    let dialog_extract_attachments: FileChooserNative = FileChooserNative::builder()
+        .title(&gettext("Extract attached files"))
+        .modal(true)
+        .transient_for(appwindow)
+        .accept_label(&gettext("Extract"))
+        .cancel_label(&gettext("Cancel"))
+        .action(FileChooserAction::SelectFolder)
+        .select_multiple(false)
+        .build();
+
+    dialog_extract_attachments.connect_response(
+        clone!(@weak appwindow => move |dialog_extract_attachments, responsetype| {
+            match responsetype {
+                ResponseType::Accept => {
+                    match dialog_extract_attachments.file().and_then(|file| file.path()) {
+                        Some(dir) => {
+                            if let Err(e) = appwindow.canvas().sheet().borrow().extract_attachments(&dir) {
+                                log::error!("extracting the attachments failed with Err {}", e);
+                                adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Extracting the attached files failed").to_variant()));
+                            } else {
+                                adw::prelude::ActionGroupExt::activate_action(&appwindow, "text-toast", Some(&gettext("Extracted the attached files").to_variant()));
+                            }
+                        },
+                        None => { log::error!("unable to extract the attachments. No directory selected.")},
+                    }
+                }
+                _ => {
+                }
+            }
+        }),
+    );
+
+    dialog_extract_attachments.show();
+    // keeping the filechooser around because otherwise GTK won't keep it alive
+    *appwindow.filechoosernative().borrow_mut() = Some(dialog_extract_attachments);
+}
+
+/// Chooses the svg tile of the custom tile background pattern
+pub fn dialog_import_background_tile(appwindow: &RnoteAppWindow) {
+    let filter = FileFilter::new();
+    filter.add_mime_type("image/svg+xml");
+    filter.add_pattern("*.svg");
+    filter.set_name(Some(&gettext("SVG file")));
+
+    let dialog_import_background_tile: FileChooserNative = FileChooserNative::builder()
+        .title(&gettext("Choose background tile"))
+        .modal(true)
+        .transient_for(appwindow)
+        .accept_label(&gettext("Choose"))
+        .cancel_label(&gettext("Cancel"))
+        .action(FileChooserAction::Open)
+        .select_multiple(false)
+        .build();
+
+    dialog_import_background_tile.add_filter(&filter);
+
+    dialog_import_background_tile.connect_response(
+        clone!(@weak appwindow => move |dialog_import_background_tile, responsetype| {
+            match responsetype {
+                ResponseType::Accept => {
+                    match dialog_import_background_tile.file() {
+                        Some(file) => {
+                            glib::MainContext::default().spawn_local(clone!(@weak appwindow => async move {
+                                let result = file.load_bytes_future().await.map_err(anyhow::Error::from).and_then(|(bytes, _)| {
+                                    let svg = String::from_utf8(bytes.to_vec())?;
+                                    appwindow.settings_panel().modify_background(&appwindow, |background| background.set_custom_tile(&svg))
+                                });
+
+                                match result {
+                                    Ok(()) => {
+                                        appwindow.settings_panel().refresh_for_sheet(&appwindow);
+                                        appwindow.canvas().set_unsaved_changes(true);
+                                        appwindow.canvas().regenerate_background(true);
+                                    }
+                                    Err(e) => {
+                                        log::error!("failed to import background tile, {}", e);
+                                        adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Importing background tile failed").to_variant()));
+                                    }
+                                }
+                            }));
+                        },
+                        None => { log::error!("unable to import background tile. No file selected.")},
+                    }
+                }
+                _ => {
+                }
+            }
+        }),
+    );
+
+    dialog_import_background_tile.show();
+    // keeping the filechooser around because otherwise GTK won't keep it alive
+    *appwindow.filechoosernative().borrow_mut() = Some(dialog_import_background_tile);
+}
+
 pub fn dialog_export_selection(appwindow: &RnoteAppWindow) {
     let filter = FileFilter::new();
     filter.add_mime_type("image/svg+xml");
@@ -545,6 +1275,264 @@ pub fn dialog_export_sheet_as_pdf(appwindow: &RnoteAppWindow) {
     *appwindow.filechoosernative().borrow_mut() = Some(dialog_export_sheet);
 }
 
+pub fn dialog_export_sheet_as_annotated_pdf(appwindow: &RnoteAppWindow) {
+    if appwindow
+        .canvas()
+        .sheet()
+        .borrow()
+        .strokes_state
+        .imported_pdfs()
+        .is_empty()
+    {
+        adw::prelude::ActionGroupExt::activate_action(
+            appwindow,
+            "error-toast",
+            Some(&gettext("The sheet has no imported PDF to annotate").to_variant()),
+        );
+        return;
+    }
+
+    let filter = FileFilter::new();
+    filter.add_mime_type("application/pdf");
+    filter.add_pattern("*.pdf");
+    filter.set_name(Some(&gettext("PDF file")));
+
+    let dialog_export_sheet: FileChooserNative = FileChooserNative::builder()
+        .title(&gettext("Export Annotated PDF"))
+        .modal(true)
+        .transient_for(appwindow)
+        .accept_label(&gettext("Export"))
+        .cancel_label(&gettext("Cancel"))
+        .action(FileChooserAction::Save)
+        .select_multiple(false)
+        .build();
+    dialog_export_sheet.add_filter(&filter);
+
+    dialog_export_sheet.set_current_name(
+        format!("{}_annotated.pdf", rnote_engine::utils::now_formatted_string()).as_str(),
+    );
+
+    dialog_export_sheet.connect_response(
+        clone!(@weak appwindow => move |dialog_export_sheet, responsetype| {
+            match responsetype {
+                ResponseType::Accept => {
+                    match dialog_export_sheet.file() {
+                        Some(file) => {
+                            let main_cx = glib::MainContext::default();
+                            main_cx.spawn_local(clone!(@weak appwindow, @strong file => async move {
+                                if let Err(e) = appwindow.export_sheet_as_annotated_pdf(&file).await {
+                                    log::error!("export_sheet_as_annotated_pdf() failed in dialog_export_sheet_as_annotated_pdf() with Err {}", e);
+                                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Export annotated PDF failed").to_variant()));
+                                } else {
+                                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "text-toast", Some(&gettext("Exported annotated PDF successfully").to_variant()));
+                                };
+                            }));
+                        },
+                        None => { log::error!("Can't export annotated pdf. No file selected.")},
+                    }
+                }
+                _ => {
+                }
+            }
+        }),
+    );
+
+    dialog_export_sheet.show();
+    // keeping the filechooser around because otherwise GTK won't keep it alive
+    *appwindow.filechoosernative().borrow_mut() = Some(dialog_export_sheet);
+}
+
+pub fn dialog_export_sheet_as_bitmap(appwindow: &RnoteAppWindow) {
+    let builder =
+        Builder::from_resource((String::from(config::APP_IDPATH) + "ui/dialogs.ui").as_str());
+    let dialog_export_bitmap: MessageDialog = builder.object("dialog_export_bitmap").unwrap();
+    let format_dropdown: DropDown = builder.object("export_bitmap_format_dropdown").unwrap();
+    let dpi_spinbutton: SpinButton = builder.object("export_bitmap_dpi_spinbutton").unwrap();
+    let jpeg_quality_spinbutton: SpinButton = builder
+        .object("export_bitmap_jpeg_quality_spinbutton")
+        .unwrap();
+    let selection_checkbutton: CheckButton = builder
+        .object("export_bitmap_selection_checkbutton")
+        .unwrap();
+    let first_page_spinbutton: SpinButton = builder
+        .object("export_bitmap_first_page_spinbutton")
+        .unwrap();
+    let last_page_spinbutton: SpinButton = builder
+        .object("export_bitmap_last_page_spinbutton")
+        .unwrap();
+    let transparent_checkbutton: CheckButton = builder
+        .object("export_bitmap_transparent_checkbutton")
+        .unwrap();
+
+    let n_pages = appwindow
+        .canvas()
+        .sheet()
+        .borrow()
+        .pages()
+        .len()
+        .max(1) as f64;
+    let has_selection = appwindow
+        .canvas()
+        .sheet()
+        .borrow()
+        .strokes_state
+        .selection_len()
+        > 0;
+
+    dpi_spinbutton.set_range(BitmapExportPrefs::DPI_MIN, BitmapExportPrefs::DPI_MAX);
+    dpi_spinbutton.set_value(BitmapExportPrefs::DPI_DEFAULT);
+    jpeg_quality_spinbutton.set_range(
+        f64::from(BitmapExportPrefs::JPEG_QUALITY_MIN),
+        f64::from(BitmapExportPrefs::JPEG_QUALITY_MAX),
+    );
+    jpeg_quality_spinbutton.set_value(f64::from(BitmapExportPrefs::JPEG_QUALITY_DEFAULT));
+    jpeg_quality_spinbutton.set_sensitive(false);
+    selection_checkbutton.set_sensitive(has_selection);
+    first_page_spinbutton.set_range(1.0, n_pages);
+    first_page_spinbutton.set_value(1.0);
+    last_page_spinbutton.set_range(1.0, n_pages);
+    last_page_spinbutton.set_value(n_pages);
+
+    // The dropdown lists PNG first, then JPEG
+    format_dropdown.connect_selected_notify(
+        clone!(@weak jpeg_quality_spinbutton => move |format_dropdown| {
+            jpeg_quality_spinbutton.set_sensitive(format_dropdown.selected() == 1);
+        }),
+    );
+    selection_checkbutton.connect_toggled(
+        clone!(@weak first_page_spinbutton, @weak last_page_spinbutton => move |selection_checkbutton| {
+            first_page_spinbutton.set_sensitive(!selection_checkbutton.is_active());
+            last_page_spinbutton.set_sensitive(!selection_checkbutton.is_active());
+        }),
+    );
+
+    // Keep the page range valid
+    first_page_spinbutton.connect_value_changed(
+        clone!(@weak last_page_spinbutton => move |first_page_spinbutton| {
+            if last_page_spinbutton.value() < first_page_spinbutton.value() {
+                last_page_spinbutton.set_value(first_page_spinbutton.value());
+            }
+        }),
+    );
+    last_page_spinbutton.connect_value_changed(
+        clone!(@weak first_page_spinbutton => move |last_page_spinbutton| {
+            if first_page_spinbutton.value() > last_page_spinbutton.value() {
+                first_page_spinbutton.set_value(last_page_spinbutton.value());
+            }
+        }),
+    );
+
+    dialog_export_bitmap.set_transient_for(Some(appwindow));
+
+    dialog_export_bitmap.connect_response(
+        clone!(@weak format_dropdown, @weak dpi_spinbutton, @weak jpeg_quality_spinbutton, @weak selection_checkbutton, @weak first_page_spinbutton, @weak last_page_spinbutton, @weak transparent_checkbutton, @weak appwindow => move |dialog_export_bitmap, responsetype| {
+            match responsetype {
+                ResponseType::Ok => {
+                    let format = if format_dropdown.selected() == 1 {
+                        BitmapExportFormat::Jpeg
+                    } else {
+                        BitmapExportFormat::Png
+                    };
+                    // The spinbuttons count the pages from 1
+                    let scope = if selection_checkbutton.is_active() {
+                        BitmapExportScope::Selection
+                    } else {
+                        BitmapExportScope::Pages((first_page_spinbutton.value_as_int() as u32 - 1)..(last_page_spinbutton.value_as_int() as u32))
+                    };
+
+                    let prefs = BitmapExportPrefs {
+                        format,
+                        dpi: dpi_spinbutton.value(),
+                        jpeg_quality: jpeg_quality_spinbutton.value_as_int() as u8,
+                        with_background: !transparent_checkbutton.is_active(),
+                        scope,
+                    };
+
+                    dialog_export_bitmap.close();
+                    dialog_export_sheet_as_bitmap_file(&appwindow, prefs);
+                },
+                _ => {
+                    dialog_export_bitmap.close();
+                }
+            }
+        }),
+    );
+
+    dialog_export_bitmap.show();
+}
+
+/// Asks for the file the bitmap images are exported to
+fn dialog_export_sheet_as_bitmap_file(appwindow: &RnoteAppWindow, prefs: BitmapExportPrefs) {
+    let filter = FileFilter::new();
+    match prefs.format {
+        BitmapExportFormat::Png => {
+            filter.add_mime_type("image/png");
+            filter.add_pattern("*.png");
+            filter.set_name(Some(&gettext("PNG image")));
+        }
+        BitmapExportFormat::Jpeg => {
+            filter.add_mime_type("image/jpeg");
+            filter.add_pattern("*.jpg");
+            filter.add_pattern("*.jpeg");
+            filter.set_name(Some(&gettext("JPEG image")));
+        }
+    }
+
+    let dialog_export_sheet: FileChooserNative = FileChooserNative::builder()
+        .title(&gettext("Export Image"))
+        .modal(true)
+        .transient_for(appwindow)
+        .accept_label(&gettext("Export"))
+        .cancel_label(&gettext("Cancel"))
+        .action(FileChooserAction::Save)
+        .select_multiple(false)
+        .build();
+    dialog_export_sheet.add_filter(&filter);
+
+    let current_name = match prefs.scope {
+        BitmapExportScope::Pages(_) => "sheet",
+        BitmapExportScope::Selection => "selection",
+    };
+    dialog_export_sheet.set_current_name(
+        format!(
+            "{}_{}.{}",
+            rnote_engine::utils::now_formatted_string(),
+            current_name,
+            prefs.format.file_ext()
+        )
+        .as_str(),
+    );
+
+    dialog_export_sheet.connect_response(
+        clone!(@weak appwindow => move |dialog_export_sheet, responsetype| {
+            match responsetype {
+                ResponseType::Accept => {
+                    match dialog_export_sheet.file() {
+                        Some(file) => {
+                            let main_cx = glib::MainContext::default();
+                            main_cx.spawn_local(clone!(@weak appwindow, @strong file, @strong prefs => async move {
+                                if let Err(e) = appwindow.export_sheet_as_bitmap(&file, prefs).await {
+                                    log::error!("export_sheet_as_bitmap() failed in dialog_export_sheet_as_bitmap_file() with Err {}", e);
+                                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("Export image failed").to_variant()));
+                                } else {
+                                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "text-toast", Some(&gettext("Exported image successfully").to_variant()));
+                                };
+                            }));
+                        },
+                        None => { log::error!("Can't export image. No file selected.")},
+                    }
+                }
+                _ => {
+                }
+            }
+        }),
+    );
+
+    dialog_export_sheet.show();
+    // keeping the filechooser around because otherwise GTK won't keep it alive
+    *appwindow.filechoosernative().borrow_mut() = Some(dialog_export_sheet);
+}
+
 pub fn dialog_export_sheet_as_xopp(appwindow: &RnoteAppWindow) {
     let filter = FileFilter::new();
     filter.add_mime_type("application/x-xopp");
@@ -3,6 +3,7 @@
 pub mod app;
 pub mod appmenu;
 pub mod appwindow;
+pub mod audionotes;
 pub mod audioplayer;
 pub mod canvas;
 pub mod canvasmenu;
@@ -45,6 +45,8 @@ mod imp {
         #[template_child]
         pub tools_toggle: TemplateChild<ToggleButton>,
         #[template_child]
+        pub typewriter_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
         pub canvasmenu: TemplateChild<CanvasMenu>,
         #[template_child]
         pub appmenu: TemplateChild<AppMenu>,
@@ -79,7 +81,9 @@ mod imp {
     impl WidgetImpl for MainHeader {}
 }
 
-use crate::{appmenu::AppMenu, appwindow::RnoteAppWindow, canvasmenu::CanvasMenu};
+use crate::{
+    appmenu::AppMenu, appwindow::RnoteAppWindow, canvas::ExpandMode, canvasmenu::CanvasMenu,
+};
 
 use gtk4::{
     gio, glib, glib::clone, prelude::*, subclass::prelude::*, Button, Label, Revealer,
@@ -179,6 +183,10 @@ impl MainHeader {
         imp::MainHeader::from_instance(self).tools_toggle.get()
     }
 
+    pub fn typewriter_toggle(&self) -> ToggleButton {
+        imp::MainHeader::from_instance(self).typewriter_toggle.get()
+    }
+
     pub fn canvasmenu(&self) -> CanvasMenu {
         imp::MainHeader::from_instance(self).canvasmenu.get()
     }
@@ -232,6 +240,17 @@ impl MainHeader {
 
         self.imp().add_page_button.get().connect_clicked(
             clone!(@weak appwindow => move |_add_page_button| {
+                // With a page layout, a page like the last one is appended
+                if appwindow.canvas().expand_mode() == ExpandMode::Pages {
+                    let page_layout = appwindow.canvas().sheet().borrow().page_layout.clone().unwrap_or_default();
+                    let page = page_layout.get(page_layout.len().saturating_sub(1)).unwrap_or_default();
+                    let zoom = appwindow.canvas().zoom();
+
+                    appwindow.canvas().sheet().borrow_mut().insert_page(page_layout.len(), page, zoom);
+                    appwindow.canvas().update_for_changed_pages();
+                    return;
+                }
+
                 let format_height = appwindow.canvas().sheet().borrow().format.height;
                 let new_sheet_height = appwindow.canvas().sheet().borrow().height + format_height;
                 appwindow.canvas().sheet().borrow_mut().height = new_sheet_height;
@@ -277,6 +296,12 @@ impl MainHeader {
             }
         }));
 
+        self.imp().typewriter_toggle.get().connect_toggled(clone!(@weak appwindow => move |typewriter_toggle| {
+            if typewriter_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "pen-style", Some(&"typewriter_style".to_variant()));
+            }
+        }));
+
         self.imp()
             .undo_button
             .get()
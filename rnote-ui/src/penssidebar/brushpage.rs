@@ -23,6 +23,14 @@ mod imp {
         #[template_child]
         pub brushstyle_textured_row: TemplateChild<adw::ActionRow>,
         #[template_child]
+        pub brushstyle_spray_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub brushstyle_highlighter_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub brushstyle_calligraphy_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub brushstyle_hatched_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
         pub styleconfig_menubutton: TemplateChild<MenuButton>,
         #[template_child]
         pub styleconfig_popover: TemplateChild<Popover>,
@@ -33,6 +41,8 @@ mod imp {
         #[template_child]
         pub texturedstyle_radius_y_spinbutton: TemplateChild<SpinButton>,
         #[template_child]
+        pub texturedstyle_pressure_density_switch: TemplateChild<Switch>,
+        #[template_child]
         pub texturedstyle_distribution_row: TemplateChild<adw::ComboRow>,
     }
 
@@ -68,7 +78,7 @@ mod imp {
 
 use crate::{appwindow::RnoteAppWindow, colorpicker::ColorPicker};
 use adw::prelude::*;
-use gtk4::{gdk, Image, ListBox, MenuButton, Popover};
+use gtk4::{gdk, Image, ListBox, MenuButton, Popover, Switch};
 use gtk4::{glib, glib::clone, subclass::prelude::*, SpinButton};
 use rnote_engine::compose::color::Color;
 use rnote_engine::compose::textured::{TexturedDotsDistribution, TexturedOptions};
@@ -137,6 +147,30 @@ impl BrushPage {
             .get()
     }
 
+    pub fn brushstyle_spray_row(&self) -> adw::ActionRow {
+        imp::BrushPage::from_instance(self)
+            .brushstyle_spray_row
+            .get()
+    }
+
+    pub fn brushstyle_highlighter_row(&self) -> adw::ActionRow {
+        imp::BrushPage::from_instance(self)
+            .brushstyle_highlighter_row
+            .get()
+    }
+
+    pub fn brushstyle_calligraphy_row(&self) -> adw::ActionRow {
+        imp::BrushPage::from_instance(self)
+            .brushstyle_calligraphy_row
+            .get()
+    }
+
+    pub fn brushstyle_hatched_row(&self) -> adw::ActionRow {
+        imp::BrushPage::from_instance(self)
+            .brushstyle_hatched_row
+            .get()
+    }
+
     pub fn styleconfig_menubutton(&self) -> MenuButton {
         imp::BrushPage::from_instance(self)
             .styleconfig_menubutton
@@ -173,6 +207,12 @@ impl BrushPage {
             .clone()
     }
 
+    pub fn texturedstyle_pressure_density_switch(&self) -> Switch {
+        imp::BrushPage::from_instance(self)
+            .texturedstyle_pressure_density_switch
+            .get()
+    }
+
     pub fn set_texturedstyle_distribution_variant(&self, distribution: TexturedDotsDistribution) {
         let texturedstyle_distribution_listmodel = self
             .imp()
@@ -205,6 +245,10 @@ impl BrushPage {
                     BrushStyle::Marker => appwindow.canvas().pens().borrow_mut().brush.smooth_options.stroke_color = Some(color),
                     BrushStyle::Solid => appwindow.canvas().pens().borrow_mut().brush.smooth_options.stroke_color = Some(color),
                     BrushStyle::Textured => appwindow.canvas().pens().borrow_mut().brush.textured_options.stroke_color = Some(color),
+                    BrushStyle::Spray => appwindow.canvas().pens().borrow_mut().brush.spray_options.stroke_color = Some(color),
+                    BrushStyle::Highlighter => appwindow.canvas().pens().borrow_mut().brush.highlighter_options.stroke_color = Some(color),
+                    BrushStyle::Calligraphy => appwindow.canvas().pens().borrow_mut().brush.calligraphy_options.stroke_color = Some(color),
+                    BrushStyle::Hatched => appwindow.canvas().pens().borrow_mut().brush.hatched_options.stroke_color = Some(color),
                 }
             }),
         );
@@ -217,6 +261,10 @@ impl BrushPage {
                     BrushStyle::Marker => appwindow.canvas().pens().borrow_mut().brush.smooth_options.width = brush_widthscale_spinbutton.value(),
                     BrushStyle::Solid => appwindow.canvas().pens().borrow_mut().brush.smooth_options.width = brush_widthscale_spinbutton.value(),
                     BrushStyle::Textured => appwindow.canvas().pens().borrow_mut().brush.textured_options.width = brush_widthscale_spinbutton.value(),
+                    BrushStyle::Spray => appwindow.canvas().pens().borrow_mut().brush.spray_options.width = brush_widthscale_spinbutton.value(),
+                    BrushStyle::Highlighter => appwindow.canvas().pens().borrow_mut().brush.highlighter_options.width = brush_widthscale_spinbutton.value(),
+                    BrushStyle::Calligraphy => appwindow.canvas().pens().borrow_mut().brush.calligraphy_options.width = brush_widthscale_spinbutton.value(),
+                    BrushStyle::Hatched => appwindow.canvas().pens().borrow_mut().brush.hatched_options.width = brush_widthscale_spinbutton.value(),
                 }
             }),
         );
@@ -237,6 +285,22 @@ impl BrushPage {
                         2 => {
                             adw::prelude::ActionGroupExt::activate_action(&appwindow, "brush-style", Some(&"textured".to_variant()));
                         }
+                        // Spray
+                        3 => {
+                            adw::prelude::ActionGroupExt::activate_action(&appwindow, "brush-style", Some(&"spray".to_variant()));
+                        }
+                        // Highlighter
+                        4 => {
+                            adw::prelude::ActionGroupExt::activate_action(&appwindow, "brush-style", Some(&"highlighter".to_variant()));
+                        }
+                        // Calligraphy
+                        5 => {
+                            adw::prelude::ActionGroupExt::activate_action(&appwindow, "brush-style", Some(&"calligraphy".to_variant()));
+                        }
+                        // Hatched
+                        6 => {
+                            adw::prelude::ActionGroupExt::activate_action(&appwindow, "brush-style", Some(&"hatched".to_variant()));
+                        }
                         _ => {}
                     }
                 }
@@ -314,6 +378,11 @@ impl BrushPage {
                 }),
             );
 
+        // Pressure density
+        self.imp().texturedstyle_pressure_density_switch.get().connect_state_notify(clone!(@weak appwindow => move |texturedstyle_pressure_density_switch| {
+            appwindow.canvas().pens().borrow_mut().brush.textured_options.pressure_density = texturedstyle_pressure_density_switch.state();
+        }));
+
         // Distribution
         self.set_texturedstyle_distribution_variant(
             appwindow
@@ -345,6 +414,12 @@ impl BrushPage {
                     "reverse-exponential" => {
                         appwindow.canvas().pens().borrow_mut().brush.textured_options.distribution = TexturedDotsDistribution::ReverseExponential;
                     },
+                    "clumped" => {
+                        appwindow.canvas().pens().borrow_mut().brush.textured_options.distribution = TexturedDotsDistribution::Clumped;
+                    },
+                    "blue-noise" => {
+                        appwindow.canvas().pens().borrow_mut().brush.textured_options.distribution = TexturedDotsDistribution::BlueNoise;
+                    },
                     _ => {
                         log::error!(
                             "invalid nick string when selecting a distribution in texturedstyle_distribution_row"
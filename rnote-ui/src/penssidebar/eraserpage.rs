@@ -1,9 +1,19 @@
 mod imp {
-    use gtk4::{glib, prelude::*, subclass::prelude::*, CompositeTemplate, SpinButton};
+    use gtk4::{glib, prelude::*, subclass::prelude::*, CompositeTemplate, SpinButton, ToggleButton};
 
     #[derive(Default, Debug, CompositeTemplate)]
     #[template(resource = "/com/github/flxzt/rnote/ui/penssidebar/eraserpage.ui")]
     pub struct EraserPage {
+        #[template_child]
+        pub trash_strokes_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub split_strokes_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub shape_square_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub shape_circle_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub shape_motion_path_toggle: TemplateChild<ToggleButton>,
         #[template_child]
         pub width_spinbutton: TemplateChild<SpinButton>,
     }
@@ -39,8 +49,8 @@ mod imp {
 }
 
 use crate::appwindow::RnoteAppWindow;
-use gtk4::{glib, glib::clone, subclass::prelude::*, SpinButton};
-use rnote_engine::pens::eraser::Eraser;
+use gtk4::{glib, glib::clone, prelude::*, subclass::prelude::*, SpinButton, ToggleButton};
+use rnote_engine::pens::eraser::{Eraser, EraserShape, EraserStyle};
 
 glib::wrapper! {
     pub struct EraserPage(ObjectSubclass<imp::EraserPage>)
@@ -58,11 +68,71 @@ impl EraserPage {
         glib::Object::new(&[]).expect("Failed to create EraserPage")
     }
 
+    pub fn trash_strokes_toggle(&self) -> ToggleButton {
+        imp::EraserPage::from_instance(self)
+            .trash_strokes_toggle
+            .get()
+    }
+
+    pub fn split_strokes_toggle(&self) -> ToggleButton {
+        imp::EraserPage::from_instance(self)
+            .split_strokes_toggle
+            .get()
+    }
+
+    pub fn shape_square_toggle(&self) -> ToggleButton {
+        imp::EraserPage::from_instance(self)
+            .shape_square_toggle
+            .get()
+    }
+
+    pub fn shape_circle_toggle(&self) -> ToggleButton {
+        imp::EraserPage::from_instance(self)
+            .shape_circle_toggle
+            .get()
+    }
+
+    pub fn shape_motion_path_toggle(&self) -> ToggleButton {
+        imp::EraserPage::from_instance(self)
+            .shape_motion_path_toggle
+            .get()
+    }
+
     pub fn width_spinbutton(&self) -> SpinButton {
         imp::EraserPage::from_instance(self).width_spinbutton.get()
     }
 
     pub fn init(&self, appwindow: &RnoteAppWindow) {
+        self.trash_strokes_toggle().connect_toggled(clone!(@weak appwindow => move |trash_strokes_toggle| {
+            if trash_strokes_toggle.is_active() {
+                appwindow.canvas().pens().borrow_mut().eraser.style = EraserStyle::TrashCollidingStrokes;
+            }
+        }));
+
+        self.split_strokes_toggle().connect_toggled(clone!(@weak appwindow => move |split_strokes_toggle| {
+            if split_strokes_toggle.is_active() {
+                appwindow.canvas().pens().borrow_mut().eraser.style = EraserStyle::SplitCollidingStrokes;
+            }
+        }));
+
+        self.shape_square_toggle().connect_toggled(clone!(@weak appwindow => move |shape_square_toggle| {
+            if shape_square_toggle.is_active() {
+                appwindow.canvas().pens().borrow_mut().eraser.shape = EraserShape::Square;
+            }
+        }));
+
+        self.shape_circle_toggle().connect_toggled(clone!(@weak appwindow => move |shape_circle_toggle| {
+            if shape_circle_toggle.is_active() {
+                appwindow.canvas().pens().borrow_mut().eraser.shape = EraserShape::Circle;
+            }
+        }));
+
+        self.shape_motion_path_toggle().connect_toggled(clone!(@weak appwindow => move |shape_motion_path_toggle| {
+            if shape_motion_path_toggle.is_active() {
+                appwindow.canvas().pens().borrow_mut().eraser.shape = EraserShape::MotionPath;
+            }
+        }));
+
         self.width_spinbutton().set_increments(1.0, 5.0);
         self.width_spinbutton()
             .set_range(Eraser::WIDTH_MIN, Eraser::WIDTH_MAX);
@@ -3,9 +3,11 @@ pub mod eraserpage;
 pub mod selectorpage;
 pub mod shaperpage;
 pub mod toolspage;
+pub mod typewriterpage;
 
 mod imp {
     use super::toolspage::ToolsPage;
+    use super::typewriterpage::TypewriterPage;
     use super::{
         brushpage::BrushPage, eraserpage::EraserPage, selectorpage::SelectorPage,
         shaperpage::ShaperPage,
@@ -40,6 +42,10 @@ mod imp {
         pub tools_stackpage: TemplateChild<StackPage>,
         #[template_child]
         pub tools_page: TemplateChild<ToolsPage>,
+        #[template_child]
+        pub typewriter_stackpage: TemplateChild<StackPage>,
+        #[template_child]
+        pub typewriter_page: TemplateChild<TypewriterPage>,
     }
 
     #[glib::object_subclass]
@@ -81,6 +87,7 @@ use shaperpage::ShaperPage;
 use gtk4::{glib, glib::clone, prelude::*, subclass::prelude::*, Stack, StackPage};
 
 use self::toolspage::ToolsPage;
+use self::typewriterpage::TypewriterPage;
 
 glib::wrapper! {
     pub struct PensSideBar(ObjectSubclass<imp::PensSideBar>)
@@ -142,6 +149,16 @@ impl PensSideBar {
         imp::PensSideBar::from_instance(self).tools_page.get()
     }
 
+    pub fn typewriter_stackpage(&self) -> StackPage {
+        imp::PensSideBar::from_instance(self)
+            .typewriter_stackpage
+            .get()
+    }
+
+    pub fn typewriter_page(&self) -> TypewriterPage {
+        imp::PensSideBar::from_instance(self).typewriter_page.get()
+    }
+
     pub fn init(&self, appwindow: &RnoteAppWindow) {
         self.imp().sidebar_stack.get().connect_visible_child_name_notify(
             clone!(@weak appwindow => move |sidebar_stack| {
@@ -162,6 +179,9 @@ impl PensSideBar {
                         "tools_page" => {
                             adw::prelude::ActionGroupExt::activate_action(&appwindow, "pen-style", Some(&"tools_style".to_variant()));
                         }
+                        "typewriter_page" => {
+                            adw::prelude::ActionGroupExt::activate_action(&appwindow, "pen-style", Some(&"typewriter_style".to_variant()));
+                        }
                         _ => {}
                     };
                 };
@@ -1,5 +1,5 @@
 mod imp {
-    use gtk4::ToggleButton;
+    use gtk4::{ColorButton, MenuButton, Popover, SpinButton, ToggleButton};
     use gtk4::{glib, prelude::*, subclass::prelude::*, CompositeTemplate};
 
     #[derive(Default, Debug, CompositeTemplate)]
@@ -10,7 +10,27 @@ mod imp {
         #[template_child]
         pub selectorstyle_rect_toggle: TemplateChild<ToggleButton>,
         #[template_child]
+        pub selectorstyle_single_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
         pub resize_lock_aspectratio_togglebutton: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub selection_stamp_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub duplicationconfig_menubutton: TemplateChild<MenuButton>,
+        #[template_child]
+        pub duplicationconfig_popover: TemplateChild<Popover>,
+        #[template_child]
+        pub duplicationconfig_offset_x_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub duplicationconfig_offset_y_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub selection_stroke_colorbutton: TemplateChild<ColorButton>,
+        #[template_child]
+        pub selection_width_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub selection_fill_colorbutton: TemplateChild<ColorButton>,
+        #[template_child]
+        pub selection_fill_evenodd_toggle: TemplateChild<ToggleButton>,
     }
 
     #[glib::object_subclass]
@@ -44,8 +64,13 @@ mod imp {
 }
 
 use crate::appwindow::RnoteAppWindow;
-use gtk4::ToggleButton;
+use crate::penssidebar::brushpage::BrushPage;
+use gtk4::{ColorButton, MenuButton, Popover, SpinButton, ToggleButton};
 use gtk4::{glib, glib::clone, prelude::*, subclass::prelude::*};
+use rnote_engine::compose::color::Color;
+use rnote_engine::compose::smooth::FillRule;
+use rnote_engine::pens::selector::Selector;
+use rnote_engine::strokes::strokestyle::StrokeOptionsModification;
 
 glib::wrapper! {
     pub struct SelectorPage(ObjectSubclass<imp::SelectorPage>)
@@ -75,12 +100,72 @@ impl SelectorPage {
             .get()
     }
 
+    pub fn selectorstyle_single_toggle(&self) -> ToggleButton {
+        imp::SelectorPage::from_instance(self)
+            .selectorstyle_single_toggle
+            .get()
+    }
+
     pub fn resize_lock_aspectratio_togglebutton(&self) -> ToggleButton {
         imp::SelectorPage::from_instance(self)
             .resize_lock_aspectratio_togglebutton
             .get()
     }
 
+    pub fn selection_stamp_toggle(&self) -> ToggleButton {
+        imp::SelectorPage::from_instance(self)
+            .selection_stamp_toggle
+            .get()
+    }
+
+    pub fn duplicationconfig_menubutton(&self) -> MenuButton {
+        imp::SelectorPage::from_instance(self)
+            .duplicationconfig_menubutton
+            .get()
+    }
+
+    pub fn duplicationconfig_popover(&self) -> Popover {
+        imp::SelectorPage::from_instance(self)
+            .duplicationconfig_popover
+            .get()
+    }
+
+    pub fn duplicationconfig_offset_x_spinbutton(&self) -> SpinButton {
+        imp::SelectorPage::from_instance(self)
+            .duplicationconfig_offset_x_spinbutton
+            .get()
+    }
+
+    pub fn duplicationconfig_offset_y_spinbutton(&self) -> SpinButton {
+        imp::SelectorPage::from_instance(self)
+            .duplicationconfig_offset_y_spinbutton
+            .get()
+    }
+
+    pub fn selection_stroke_colorbutton(&self) -> ColorButton {
+        imp::SelectorPage::from_instance(self)
+            .selection_stroke_colorbutton
+            .get()
+    }
+
+    pub fn selection_width_spinbutton(&self) -> SpinButton {
+        imp::SelectorPage::from_instance(self)
+            .selection_width_spinbutton
+            .get()
+    }
+
+    pub fn selection_fill_colorbutton(&self) -> ColorButton {
+        imp::SelectorPage::from_instance(self)
+            .selection_fill_colorbutton
+            .get()
+    }
+
+    pub fn selection_fill_evenodd_toggle(&self) -> ToggleButton {
+        imp::SelectorPage::from_instance(self)
+            .selection_fill_evenodd_toggle
+            .get()
+    }
+
     pub fn init(&self, appwindow: &RnoteAppWindow) {
         // selecting with Polygon / Rect toggles
         self.selectorstyle_polygon_toggle().connect_toggled(clone!(@weak appwindow => move |selectorstyle_polygon_toggle| {
@@ -95,6 +180,12 @@ impl SelectorPage {
             }
         }));
 
+        self.selectorstyle_single_toggle().connect_toggled(clone!(@weak appwindow => move |selectorstyle_single_toggle| {
+            if selectorstyle_single_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "selector-style", Some(&"single".to_variant()));
+            }
+        }));
+
         self.resize_lock_aspectratio_togglebutton()
             .bind_property(
                 "active",
@@ -103,5 +194,79 @@ impl SelectorPage {
             )
             .flags(glib::BindingFlags::SYNC_CREATE | glib::BindingFlags::BIDIRECTIONAL)
             .build();
+
+        // Stamping and duplicating
+        self.selection_stamp_toggle().connect_toggled(clone!(@weak appwindow => move |selection_stamp_toggle| {
+            appwindow.canvas().pens().borrow_mut().selector.stamp = selection_stamp_toggle.is_active();
+        }));
+
+        self.duplicationconfig_offset_x_spinbutton().set_increments(1.0, 10.0);
+        self.duplicationconfig_offset_x_spinbutton()
+            .set_range(-Selector::DUPLICATION_OFFSET_MAX, Selector::DUPLICATION_OFFSET_MAX);
+        // Must be after set_range() !
+        self.duplicationconfig_offset_x_spinbutton().set_value(Selector::default().duplication_offset[0]);
+
+        self.duplicationconfig_offset_x_spinbutton().connect_value_changed(clone!(@weak appwindow => move |duplicationconfig_offset_x_spinbutton| {
+            appwindow.canvas().pens().borrow_mut().selector.duplication_offset[0] = duplicationconfig_offset_x_spinbutton.value();
+        }));
+
+        self.duplicationconfig_offset_y_spinbutton().set_increments(1.0, 10.0);
+        self.duplicationconfig_offset_y_spinbutton()
+            .set_range(-Selector::DUPLICATION_OFFSET_MAX, Selector::DUPLICATION_OFFSET_MAX);
+        // Must be after set_range() !
+        self.duplicationconfig_offset_y_spinbutton().set_value(Selector::default().duplication_offset[1]);
+
+        self.duplicationconfig_offset_y_spinbutton().connect_value_changed(clone!(@weak appwindow => move |duplicationconfig_offset_y_spinbutton| {
+            appwindow.canvas().pens().borrow_mut().selector.duplication_offset[1] = duplicationconfig_offset_y_spinbutton.value();
+        }));
+
+        // Color and width of the selected strokes
+        self.selection_stroke_colorbutton().connect_color_set(clone!(@weak appwindow => move |selection_stroke_colorbutton| {
+            let modification = StrokeOptionsModification {
+                stroke_color: Some(Color::from(selection_stroke_colorbutton.rgba())),
+                ..StrokeOptionsModification::default()
+            };
+
+            let selection_keys = appwindow.canvas().sheet().borrow().strokes_state.selection_keys_as_rendered();
+            appwindow.canvas().sheet().borrow_mut().strokes_state.modify_stroke_options(&selection_keys, &modification);
+            appwindow.canvas().regenerate_content(false, true);
+        }));
+
+        self.selection_width_spinbutton().set_increments(0.1, 2.0);
+        self.selection_width_spinbutton()
+            .set_range(BrushPage::WIDTH_MIN, BrushPage::WIDTH_MAX);
+        // Must be after set_range() !
+        self.selection_width_spinbutton().set_value(BrushPage::WIDTH_DEFAULT);
+
+        self.selection_width_spinbutton().connect_value_changed(clone!(@weak appwindow => move |selection_width_spinbutton| {
+            let modification = StrokeOptionsModification {
+                width: Some(selection_width_spinbutton.value()),
+                ..StrokeOptionsModification::default()
+            };
+
+            let selection_keys = appwindow.canvas().sheet().borrow().strokes_state.selection_keys_as_rendered();
+            appwindow.canvas().sheet().borrow_mut().strokes_state.modify_stroke_options(&selection_keys, &modification);
+            appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+            appwindow.canvas().regenerate_content(false, true);
+        }));
+
+        // Fill of the selected shapes
+        self.selection_fill_colorbutton().connect_color_set(clone!(@weak appwindow => move |selection_fill_colorbutton| {
+            let color = Color::from(selection_fill_colorbutton.rgba());
+
+            appwindow.canvas().sheet().borrow_mut().strokes_state.set_fill_color_selection(Some(color));
+            appwindow.canvas().regenerate_content(false, true);
+        }));
+
+        self.selection_fill_evenodd_toggle().connect_toggled(clone!(@weak appwindow => move |selection_fill_evenodd_toggle| {
+            let fill_rule = if selection_fill_evenodd_toggle.is_active() {
+                FillRule::EvenOdd
+            } else {
+                FillRule::NonZero
+            };
+
+            appwindow.canvas().sheet().borrow_mut().strokes_state.set_fill_rule_selection(fill_rule);
+            appwindow.canvas().regenerate_content(false, true);
+        }));
     }
 }
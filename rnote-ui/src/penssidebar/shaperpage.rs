@@ -23,6 +23,16 @@ mod imp {
         #[template_child]
         pub roughconfig_multistroke_switch: TemplateChild<Switch>,
         #[template_child]
+        pub roughconfig_fillstyle_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub roughconfig_hachureangle_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub roughconfig_hachuregap_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub roughconfig_fillweight_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub roughconfig_multistrokefill_switch: TemplateChild<Switch>,
+        #[template_child]
         pub width_spinbutton: TemplateChild<SpinButton>,
         #[template_child]
         pub stroke_colorpicker: TemplateChild<ColorPicker>,
@@ -31,6 +41,10 @@ mod imp {
         #[template_child]
         pub fill_colorpicker: TemplateChild<ColorPicker>,
         #[template_child]
+        pub fill_evenodd_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub fill_textured_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
         pub shapes_togglebox: TemplateChild<gtk4::Box>,
         #[template_child]
         pub line_toggle: TemplateChild<ToggleButton>,
@@ -38,6 +52,16 @@ mod imp {
         pub rectangle_toggle: TemplateChild<ToggleButton>,
         #[template_child]
         pub ellipse_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub regular_polygon_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub star_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub arc_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub parallelogram_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub polyline_toggle: TemplateChild<ToggleButton>,
     }
 
     #[glib::object_subclass]
@@ -71,10 +95,12 @@ mod imp {
 }
 
 use crate::{appwindow::RnoteAppWindow, colorpicker::ColorPicker};
+use adw::prelude::ComboRowExt;
 use gtk4::{gdk, MenuButton, Popover, Revealer, SpinButton, Switch, ToggleButton};
 use gtk4::{glib, glib::clone, prelude::*, subclass::prelude::*};
 use rnote_engine::compose::color::Color;
-use rnote_engine::compose::rough::roughoptions::{self, RoughOptions};
+use rnote_engine::compose::rough::roughoptions::{self, FillStyle, RoughOptions};
+use rnote_engine::compose::smooth::FillRule;
 use rnote_engine::pens::shaper::ShaperDrawStyle;
 
 glib::wrapper! {
@@ -152,6 +178,51 @@ impl ShaperPage {
             .get()
     }
 
+    pub fn roughconfig_fillstyle_row(&self) -> adw::ComboRow {
+        imp::ShaperPage::from_instance(self)
+            .roughconfig_fillstyle_row
+            .get()
+    }
+
+    pub fn roughconfig_hachureangle_spinbutton(&self) -> SpinButton {
+        imp::ShaperPage::from_instance(self)
+            .roughconfig_hachureangle_spinbutton
+            .get()
+    }
+
+    pub fn roughconfig_hachuregap_spinbutton(&self) -> SpinButton {
+        imp::ShaperPage::from_instance(self)
+            .roughconfig_hachuregap_spinbutton
+            .get()
+    }
+
+    pub fn roughconfig_fillweight_spinbutton(&self) -> SpinButton {
+        imp::ShaperPage::from_instance(self)
+            .roughconfig_fillweight_spinbutton
+            .get()
+    }
+
+    pub fn roughconfig_multistrokefill_switch(&self) -> Switch {
+        imp::ShaperPage::from_instance(self)
+            .roughconfig_multistrokefill_switch
+            .get()
+    }
+
+    pub fn set_roughconfig_fillstyle_variant(&self, fill_style: FillStyle) {
+        let roughconfig_fillstyle_listmodel = self
+            .imp()
+            .roughconfig_fillstyle_row
+            .get()
+            .model()
+            .unwrap()
+            .downcast::<adw::EnumListModel>()
+            .unwrap();
+        self.imp()
+            .roughconfig_fillstyle_row
+            .get()
+            .set_selected(roughconfig_fillstyle_listmodel.find_position(fill_style as i32));
+    }
+
     pub fn stroke_colorpicker(&self) -> ColorPicker {
         imp::ShaperPage::from_instance(self)
             .stroke_colorpicker
@@ -166,6 +237,14 @@ impl ShaperPage {
         imp::ShaperPage::from_instance(self).fill_colorpicker.get()
     }
 
+    pub fn fill_evenodd_toggle(&self) -> ToggleButton {
+        imp::ShaperPage::from_instance(self).fill_evenodd_toggle.get()
+    }
+
+    pub fn fill_textured_toggle(&self) -> ToggleButton {
+        imp::ShaperPage::from_instance(self).fill_textured_toggle.get()
+    }
+
     pub fn shapes_togglebox(&self) -> gtk4::Box {
         imp::ShaperPage::from_instance(self).shapes_togglebox.get()
     }
@@ -182,6 +261,26 @@ impl ShaperPage {
         imp::ShaperPage::from_instance(self).ellipse_toggle.get()
     }
 
+    pub fn regular_polygon_toggle(&self) -> ToggleButton {
+        imp::ShaperPage::from_instance(self).regular_polygon_toggle.get()
+    }
+
+    pub fn star_toggle(&self) -> ToggleButton {
+        imp::ShaperPage::from_instance(self).star_toggle.get()
+    }
+
+    pub fn arc_toggle(&self) -> ToggleButton {
+        imp::ShaperPage::from_instance(self).arc_toggle.get()
+    }
+
+    pub fn parallelogram_toggle(&self) -> ToggleButton {
+        imp::ShaperPage::from_instance(self).parallelogram_toggle.get()
+    }
+
+    pub fn polyline_toggle(&self) -> ToggleButton {
+        imp::ShaperPage::from_instance(self).polyline_toggle.get()
+    }
+
     pub fn init(&self, appwindow: &RnoteAppWindow) {
         // Width
         self.width_spinbutton().set_increments(0.1, 2.0);
@@ -228,6 +327,23 @@ impl ShaperPage {
             }),
         );
 
+        // Fill rule
+        self.fill_evenodd_toggle().connect_toggled(clone!(@weak appwindow => move |fill_evenodd_toggle| {
+            let fill_rule = if fill_evenodd_toggle.is_active() {
+                FillRule::EvenOdd
+            } else {
+                FillRule::NonZero
+            };
+
+            appwindow.canvas().pens().borrow_mut().shaper.smooth_options.fill_rule = fill_rule;
+            appwindow.canvas().pens().borrow_mut().shaper.rough_options.fill_rule = fill_rule;
+        }));
+
+        // Textured fill
+        self.fill_textured_toggle().connect_toggled(clone!(@weak appwindow => move |fill_textured_toggle| {
+            appwindow.canvas().pens().borrow_mut().shaper.smooth_options.fill_textured = fill_textured_toggle.is_active();
+        }));
+
         // Roughness
         self.imp()
             .roughconfig_roughness_spinbutton
@@ -296,6 +412,115 @@ impl ShaperPage {
             appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_multistroke(roughconfig_multistroke_switch.state());
         }));
 
+        // Fill style
+        self.set_roughconfig_fillstyle_variant(FillStyle::default());
+
+        self.imp().roughconfig_fillstyle_row.get().connect_selected_item_notify(clone!(@weak appwindow => move |roughconfig_fillstyle_row| {
+            if let Some(selected_item) = roughconfig_fillstyle_row.selected_item() {
+                match selected_item
+                    .downcast::<adw::EnumListItem>()
+                    .unwrap()
+                    .nick()
+                    .as_str()
+                {
+                    "solid" => {
+                        appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_fill_style(FillStyle::Solid);
+                    },
+                    "hachure" => {
+                        appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_fill_style(FillStyle::Hachure);
+                    },
+                    "zigzag" => {
+                        appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_fill_style(FillStyle::Zigzag);
+                    },
+                    "zigzag-line" => {
+                        appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_fill_style(FillStyle::ZigzagLine);
+                    },
+                    "crosshatch" => {
+                        appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_fill_style(FillStyle::Crosshatch);
+                    },
+                    "dots" => {
+                        appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_fill_style(FillStyle::Dots);
+                    },
+                    "sunburst" => {
+                        appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_fill_style(FillStyle::Sunburst);
+                    },
+                    "dashed" => {
+                        appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_fill_style(FillStyle::Dashed);
+                    },
+                    _ => {
+                        log::error!(
+                            "invalid nick string when selecting a fill style in roughconfig_fillstyle_row"
+                        );
+                    }
+                };
+            }
+        }));
+
+        // Hachure angle
+        self.imp()
+            .roughconfig_hachureangle_spinbutton
+            .get()
+            .set_increments(1.0, 15.0);
+        self.imp()
+            .roughconfig_hachureangle_spinbutton
+            .get()
+            .set_range(RoughOptions::HACHURE_ANGLE_MIN, RoughOptions::HACHURE_ANGLE_MAX);
+        self.imp()
+            .roughconfig_hachureangle_spinbutton
+            .get()
+            .set_value(RoughOptions::HACHURE_ANGLE_DEFAULT);
+
+        self.imp().roughconfig_hachureangle_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |roughconfig_hachureangle_spinbutton| {
+                appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_hachure_angle(roughconfig_hachureangle_spinbutton.value());
+            }),
+        );
+
+        // Hachure gap
+        self.imp()
+            .roughconfig_hachuregap_spinbutton
+            .get()
+            .set_increments(0.5, 2.0);
+        self.imp()
+            .roughconfig_hachuregap_spinbutton
+            .get()
+            .set_range(RoughOptions::HACHURE_GAP_MIN, RoughOptions::HACHURE_GAP_MAX);
+        self.imp()
+            .roughconfig_hachuregap_spinbutton
+            .get()
+            .set_value(RoughOptions::HACHURE_GAP_DEFAULT);
+
+        self.imp().roughconfig_hachuregap_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |roughconfig_hachuregap_spinbutton| {
+                appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_hachure_gap(roughconfig_hachuregap_spinbutton.value());
+            }),
+        );
+
+        // Fill weight
+        self.imp()
+            .roughconfig_fillweight_spinbutton
+            .get()
+            .set_increments(0.1, 1.0);
+        self.imp()
+            .roughconfig_fillweight_spinbutton
+            .get()
+            .set_range(RoughOptions::FILL_WEIGHT_MIN, RoughOptions::FILL_WEIGHT_MAX);
+        self.imp()
+            .roughconfig_fillweight_spinbutton
+            .get()
+            .set_value(RoughOptions::FILL_WEIGHT_DEFAULT);
+
+        self.imp().roughconfig_fillweight_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |roughconfig_fillweight_spinbutton| {
+                appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_fill_weight(roughconfig_fillweight_spinbutton.value());
+            }),
+        );
+
+        // Multistroke fill
+        self.imp().roughconfig_multistrokefill_switch.get().connect_state_notify(clone!(@weak appwindow => move |roughconfig_multistrokefill_switch| {
+            appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_multistroke_fill(roughconfig_multistrokefill_switch.state());
+        }));
+
         // Smooth / Rough shape toggle
         self.drawstyle_smooth_toggle().connect_toggled(clone!(@weak appwindow => move |drawstyle_smooth_toggle| {
             if drawstyle_smooth_toggle.is_active() {
@@ -334,6 +559,31 @@ impl ShaperPage {
             .flags(glib::BindingFlags::DEFAULT)
             .build();
 
+        self.regular_polygon_toggle()
+            .bind_property("active", &self.fill_revealer(), "reveal-child")
+            .flags(glib::BindingFlags::DEFAULT)
+            .build();
+
+        self.star_toggle()
+            .bind_property("active", &self.fill_revealer(), "reveal-child")
+            .flags(glib::BindingFlags::DEFAULT)
+            .build();
+
+        self.arc_toggle()
+            .bind_property("active", &self.fill_revealer(), "reveal-child")
+            .flags(glib::BindingFlags::DEFAULT)
+            .build();
+
+        self.parallelogram_toggle()
+            .bind_property("active", &self.fill_revealer(), "reveal-child")
+            .flags(glib::BindingFlags::DEFAULT)
+            .build();
+
+        self.polyline_toggle()
+            .bind_property("active", &self.fill_revealer(), "reveal-child")
+            .flags(glib::BindingFlags::DEFAULT)
+            .build();
+
         // Shape toggles
         self.line_toggle().connect_toggled(clone!(@weak self as shaperpage, @weak appwindow => move |line_toggle| {
             if line_toggle.is_active() {
@@ -352,5 +602,35 @@ impl ShaperPage {
                 adw::prelude::ActionGroupExt::activate_action(&appwindow, "shaper-style", Some(&"ellipse".to_variant()));
             }
         }));
+
+        self.regular_polygon_toggle().connect_toggled(clone!(@weak appwindow => move |regular_polygon_toggle| {
+            if regular_polygon_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "shaper-style", Some(&"regular-polygon".to_variant()));
+            }
+        }));
+
+        self.star_toggle().connect_toggled(clone!(@weak appwindow => move |star_toggle| {
+            if star_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "shaper-style", Some(&"star".to_variant()));
+            }
+        }));
+
+        self.arc_toggle().connect_toggled(clone!(@weak appwindow => move |arc_toggle| {
+            if arc_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "shaper-style", Some(&"arc".to_variant()));
+            }
+        }));
+
+        self.parallelogram_toggle().connect_toggled(clone!(@weak appwindow => move |parallelogram_toggle| {
+            if parallelogram_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "shaper-style", Some(&"parallelogram".to_variant()));
+            }
+        }));
+
+        self.polyline_toggle().connect_toggled(clone!(@weak appwindow => move |polyline_toggle| {
+            if polyline_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "shaper-style", Some(&"polyline".to_variant()));
+            }
+        }));
     }
 }
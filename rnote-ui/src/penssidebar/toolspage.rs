@@ -1,5 +1,8 @@
 mod imp {
-    use gtk4::{glib, prelude::*, subclass::prelude::*, CompositeTemplate, ToggleButton};
+    use gtk4::{
+        glib, prelude::*, subclass::prelude::*, CompositeTemplate, DropDown, SpinButton,
+        ToggleButton,
+    };
 
     #[derive(Default, Debug, CompositeTemplate)]
     #[template(resource = "/com/github/flxzt/rnote/ui/penssidebar/toolspage.ui")]
@@ -8,6 +11,16 @@ mod imp {
         pub toolstyle_expandsheet_toggle: TemplateChild<ToggleButton>,
         #[template_child]
         pub toolstyle_dragproximity_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub toolstyle_eyedropper_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub toolstyle_measure_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub measure_unit_dropdown: TemplateChild<DropDown>,
+        #[template_child]
+        pub toolstyle_laser_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub laser_timeout_spinbutton: TemplateChild<SpinButton>,
     }
 
     #[glib::object_subclass]
@@ -41,7 +54,11 @@ mod imp {
 }
 
 use crate::appwindow::RnoteAppWindow;
-use gtk4::{glib, glib::clone, prelude::*, subclass::prelude::*, ToggleButton};
+use gtk4::{
+    glib, glib::clone, prelude::*, subclass::prelude::*, DropDown, SpinButton, ToggleButton,
+};
+use rnote_engine::pens::tools::LaserTool;
+use rnote_engine::sheet::format::MeasureUnit;
 
 glib::wrapper! {
     pub struct ToolsPage(ObjectSubclass<imp::ToolsPage>)
@@ -71,6 +88,48 @@ impl ToolsPage {
             .get()
     }
 
+    pub fn toolstyle_eyedropper_toggle(&self) -> ToggleButton {
+        imp::ToolsPage::from_instance(self)
+            .toolstyle_eyedropper_toggle
+            .get()
+    }
+
+    pub fn toolstyle_measure_toggle(&self) -> ToggleButton {
+        imp::ToolsPage::from_instance(self)
+            .toolstyle_measure_toggle
+            .get()
+    }
+
+    pub fn measure_unit_dropdown(&self) -> DropDown {
+        imp::ToolsPage::from_instance(self)
+            .measure_unit_dropdown
+            .get()
+    }
+
+    pub fn toolstyle_laser_toggle(&self) -> ToggleButton {
+        imp::ToolsPage::from_instance(self)
+            .toolstyle_laser_toggle
+            .get()
+    }
+
+    pub fn laser_timeout_spinbutton(&self) -> SpinButton {
+        imp::ToolsPage::from_instance(self)
+            .laser_timeout_spinbutton
+            .get()
+    }
+
+    pub fn set_measure_unit(&self, unit: MeasureUnit) {
+        let measure_unit_listmodel = self
+            .measure_unit_dropdown()
+            .model()
+            .unwrap()
+            .downcast::<adw::EnumListModel>()
+            .unwrap();
+
+        self.measure_unit_dropdown()
+            .set_selected(measure_unit_listmodel.find_position(unit as i32));
+    }
+
     pub fn init(&self, appwindow: &RnoteAppWindow) {
         self.toolstyle_expandsheet_toggle().connect_toggled(clone!(@weak appwindow => move |toolstyle_expandsheet_toggle| {
             if toolstyle_expandsheet_toggle.is_active() {
@@ -83,5 +142,49 @@ impl ToolsPage {
                 adw::prelude::ActionGroupExt::activate_action(&appwindow, "tool-style", Some(&"dragproximity".to_variant()));
             }
         }));
+
+        self.toolstyle_eyedropper_toggle().connect_toggled(clone!(@weak appwindow => move |toolstyle_eyedropper_toggle| {
+            if toolstyle_eyedropper_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "tool-style", Some(&"eyedropper".to_variant()));
+            }
+        }));
+
+        self.toolstyle_measure_toggle().connect_toggled(clone!(@weak appwindow => move |toolstyle_measure_toggle| {
+            if toolstyle_measure_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "tool-style", Some(&"measure".to_variant()));
+            }
+        }));
+
+        self.measure_unit_dropdown().connect_selected_notify(clone!(@weak appwindow => move |measure_unit_dropdown| {
+            let measure_unit_listmodel = measure_unit_dropdown
+                .model()
+                .unwrap()
+                .downcast::<adw::EnumListModel>()
+                .unwrap();
+
+            if let Some(item) = measure_unit_listmodel.item(measure_unit_dropdown.selected()) {
+                let unit = match item.downcast::<adw::EnumListItem>().unwrap().nick().as_str() {
+                    "px" => MeasureUnit::Px,
+                    "mm" => MeasureUnit::Mm,
+                    "cm" => MeasureUnit::Cm,
+                    "in" => MeasureUnit::In,
+                    _ => return,
+                };
+
+                appwindow.canvas().pens().borrow_mut().tools.measure_tool.unit = unit;
+                appwindow.canvas().queue_draw();
+            }
+        }));
+
+        self.toolstyle_laser_toggle().connect_toggled(clone!(@weak appwindow => move |toolstyle_laser_toggle| {
+            if toolstyle_laser_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "tool-style", Some(&"laser".to_variant()));
+            }
+        }));
+
+        self.laser_timeout_spinbutton().set_range(LaserTool::TIMEOUT_MIN, LaserTool::TIMEOUT_MAX);
+        self.laser_timeout_spinbutton().connect_value_changed(clone!(@weak appwindow => move |laser_timeout_spinbutton| {
+            appwindow.canvas().pens().borrow_mut().tools.laser_tool.timeout = laser_timeout_spinbutton.value();
+        }));
     }
 }
@@ -0,0 +1,270 @@
+mod imp {
+    use crate::colorpicker::ColorPicker;
+    use gtk4::{glib, prelude::*, subclass::prelude::*, CompositeTemplate};
+    use gtk4::{FontButton, SpinButton, ToggleButton};
+
+    #[derive(Default, Debug, CompositeTemplate)]
+    #[template(resource = "/com/github/flxzt/rnote/ui/penssidebar/typewriterpage.ui")]
+    pub struct TypewriterPage {
+        #[template_child]
+        pub font_button: TemplateChild<FontButton>,
+        #[template_child]
+        pub font_size_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub heading_level_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub bold_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub italic_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub underline_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub alignment_start_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub alignment_center_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub alignment_end_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub alignment_fill_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub colorpicker: TemplateChild<ColorPicker>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TypewriterPage {
+        const NAME: &'static str = "TypewriterPage";
+        type Type = super::TypewriterPage;
+        type ParentType = gtk4::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for TypewriterPage {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+        }
+
+        fn dispose(&self, obj: &Self::Type) {
+            while let Some(child) = obj.first_child() {
+                child.unparent();
+            }
+        }
+    }
+
+    impl WidgetImpl for TypewriterPage {}
+}
+
+use crate::{appwindow::RnoteAppWindow, colorpicker::ColorPicker};
+use gtk4::{gdk, glib, glib::clone, pango, prelude::*, subclass::prelude::*};
+use gtk4::{FontButton, SpinButton, ToggleButton};
+use rnote_engine::compose::color::Color;
+use rnote_engine::strokes::textstroke::{TextAlignment, TextStyle};
+
+glib::wrapper! {
+    pub struct TypewriterPage(ObjectSubclass<imp::TypewriterPage>)
+        @extends gtk4::Widget;
+}
+
+impl Default for TypewriterPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypewriterPage {
+    pub fn new() -> Self {
+        glib::Object::new(&[]).expect("Failed to create TypewriterPage")
+    }
+
+    pub fn font_button(&self) -> FontButton {
+        imp::TypewriterPage::from_instance(self).font_button.get()
+    }
+
+    pub fn font_size_spinbutton(&self) -> SpinButton {
+        imp::TypewriterPage::from_instance(self)
+            .font_size_spinbutton
+            .get()
+    }
+
+    pub fn heading_level_spinbutton(&self) -> SpinButton {
+        imp::TypewriterPage::from_instance(self)
+            .heading_level_spinbutton
+            .get()
+    }
+
+    pub fn set_heading_level(&self, heading_level: Option<u32>) {
+        self.heading_level_spinbutton()
+            .set_value(f64::from(heading_level.unwrap_or(0)));
+    }
+
+    pub fn bold_toggle(&self) -> ToggleButton {
+        imp::TypewriterPage::from_instance(self).bold_toggle.get()
+    }
+
+    pub fn italic_toggle(&self) -> ToggleButton {
+        imp::TypewriterPage::from_instance(self).italic_toggle.get()
+    }
+
+    pub fn underline_toggle(&self) -> ToggleButton {
+        imp::TypewriterPage::from_instance(self)
+            .underline_toggle
+            .get()
+    }
+
+    pub fn alignment_start_toggle(&self) -> ToggleButton {
+        imp::TypewriterPage::from_instance(self)
+            .alignment_start_toggle
+            .get()
+    }
+
+    pub fn alignment_center_toggle(&self) -> ToggleButton {
+        imp::TypewriterPage::from_instance(self)
+            .alignment_center_toggle
+            .get()
+    }
+
+    pub fn alignment_end_toggle(&self) -> ToggleButton {
+        imp::TypewriterPage::from_instance(self)
+            .alignment_end_toggle
+            .get()
+    }
+
+    pub fn alignment_fill_toggle(&self) -> ToggleButton {
+        imp::TypewriterPage::from_instance(self)
+            .alignment_fill_toggle
+            .get()
+    }
+
+    pub fn colorpicker(&self) -> ColorPicker {
+        imp::TypewriterPage::from_instance(self).colorpicker.get()
+    }
+
+    pub fn set_alignment(&self, alignment: TextAlignment) {
+        match alignment {
+            TextAlignment::Start => self.alignment_start_toggle().set_active(true),
+            TextAlignment::Center => self.alignment_center_toggle().set_active(true),
+            TextAlignment::End => self.alignment_end_toggle().set_active(true),
+            TextAlignment::Fill => self.alignment_fill_toggle().set_active(true),
+        }
+    }
+
+    pub fn set_font_family(&self, font_family: &str) {
+        let mut font_description = pango::FontDescription::new();
+        font_description.set_family(font_family);
+        self.font_button().set_font_desc(&font_description);
+    }
+
+    pub fn init(&self, appwindow: &RnoteAppWindow) {
+        self.set_font_family(TextStyle::FONT_FAMILY_DEFAULT);
+
+        self.font_button().connect_font_set(clone!(@weak appwindow => move |font_button| {
+            if let Some(font_family) = font_button.font_desc().and_then(|font_description| font_description.family()) {
+                update_text_style(&appwindow, |text_style| text_style.font_family = font_family.to_string());
+            }
+        }));
+
+        self.font_size_spinbutton().set_increments(1.0, 10.0);
+        self.font_size_spinbutton()
+            .set_range(TextStyle::FONT_SIZE_MIN, TextStyle::FONT_SIZE_MAX);
+        self.font_size_spinbutton()
+            .set_value(TextStyle::FONT_SIZE_DEFAULT);
+
+        self.font_size_spinbutton().connect_value_changed(
+            clone!(@weak appwindow => move |font_size_spinbutton| {
+                update_text_style(&appwindow, |text_style| text_style.font_size = font_size_spinbutton.value());
+            }),
+        );
+
+        // 0 stands for body text
+        self.heading_level_spinbutton().set_increments(1.0, 1.0);
+        self.heading_level_spinbutton()
+            .set_range(0.0, f64::from(TextStyle::HEADING_LEVEL_MAX));
+        self.heading_level_spinbutton().set_value(0.0);
+
+        self.heading_level_spinbutton().connect_value_changed(
+            clone!(@weak appwindow => move |heading_level_spinbutton| {
+                let heading_level = heading_level_spinbutton.value_as_int().max(0) as u32;
+
+                update_text_style(&appwindow, |text_style| text_style.heading_level = if heading_level >= TextStyle::HEADING_LEVEL_MIN {
+                    Some(heading_level)
+                } else {
+                    None
+                });
+            }),
+        );
+
+        self.bold_toggle().connect_toggled(clone!(@weak appwindow => move |bold_toggle| {
+            update_text_style(&appwindow, |text_style| text_style.bold = bold_toggle.is_active());
+        }));
+
+        self.italic_toggle().connect_toggled(clone!(@weak appwindow => move |italic_toggle| {
+            update_text_style(&appwindow, |text_style| text_style.italic = italic_toggle.is_active());
+        }));
+
+        self.underline_toggle().connect_toggled(clone!(@weak appwindow => move |underline_toggle| {
+            update_text_style(&appwindow, |text_style| text_style.underline = underline_toggle.is_active());
+        }));
+
+        self.alignment_start_toggle().connect_toggled(clone!(@weak appwindow => move |alignment_start_toggle| {
+            if alignment_start_toggle.is_active() {
+                update_text_style(&appwindow, |text_style| text_style.alignment = TextAlignment::Start);
+            }
+        }));
+
+        self.alignment_center_toggle().connect_toggled(clone!(@weak appwindow => move |alignment_center_toggle| {
+            if alignment_center_toggle.is_active() {
+                update_text_style(&appwindow, |text_style| text_style.alignment = TextAlignment::Center);
+            }
+        }));
+
+        self.alignment_end_toggle().connect_toggled(clone!(@weak appwindow => move |alignment_end_toggle| {
+            if alignment_end_toggle.is_active() {
+                update_text_style(&appwindow, |text_style| text_style.alignment = TextAlignment::End);
+            }
+        }));
+
+        self.alignment_fill_toggle().connect_toggled(clone!(@weak appwindow => move |alignment_fill_toggle| {
+            if alignment_fill_toggle.is_active() {
+                update_text_style(&appwindow, |text_style| text_style.alignment = TextAlignment::Fill);
+            }
+        }));
+
+        self.colorpicker().connect_notify_local(
+            Some("current-color"),
+            clone!(@weak appwindow => move |colorpicker, _paramspec| {
+                let color = Color::from(colorpicker.property::<gdk::RGBA>("current-color"));
+
+                update_text_style(&appwindow, |text_style| text_style.color = color);
+            }),
+        );
+    }
+}
+
+/// Updates the text style of the typewriter. The text currently being edited takes on the new style
+fn update_text_style<F>(appwindow: &RnoteAppWindow, update: F)
+where
+    F: FnOnce(&mut TextStyle),
+{
+    update(&mut appwindow.canvas().pens().borrow_mut().typewriter.text_style);
+
+    if appwindow
+        .canvas()
+        .pens()
+        .borrow_mut()
+        .typewriter
+        .apply_text_style_to_editing(
+            &mut *appwindow.canvas().sheet().borrow_mut(),
+            appwindow.canvas().zoom(),
+            appwindow.canvas().renderer(),
+        )
+    {
+        appwindow.canvas().queue_draw();
+        appwindow.canvas().queue_resize();
+    }
+}
@@ -550,6 +550,8 @@ impl SelectionModifier {
         resize_tl_drag_gesture.connect_drag_begin(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |drag_gesture, _x, _y| {
                 drag_gesture.set_state(EventSequenceState::Claimed);
+                // The whole drag is undone at once
+                appwindow.canvas().sheet().borrow_mut().strokes_state.begin_history_group();
                 start_bounds.set(selection_modifier.selection_bounds());
 
                 selection_modifier.update_state(&appwindow.canvas());
@@ -593,6 +595,7 @@ impl SelectionModifier {
         resize_tl_drag_gesture.connect_drag_end(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |_drag_gesture, _x, _y| {
                 start_bounds.set(None);
+                appwindow.canvas().sheet().borrow_mut().strokes_state.end_history_group();
 
                 appwindow.canvas().sheet().borrow_mut().strokes_state.update_geometry_selection_strokes();
                 appwindow.canvas().regenerate_content(false, true);
@@ -617,6 +620,8 @@ impl SelectionModifier {
         resize_tr_drag_gesture.connect_drag_begin(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |drag_gesture, _x, _y| {
                 drag_gesture.set_state(EventSequenceState::Claimed);
+                // The whole drag is undone at once
+                appwindow.canvas().sheet().borrow_mut().strokes_state.begin_history_group();
                 start_bounds.set(selection_modifier.selection_bounds());
 
                 selection_modifier.update_state(&appwindow.canvas());
@@ -660,6 +665,7 @@ impl SelectionModifier {
         resize_tr_drag_gesture.connect_drag_end(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |_drag_gesture, _x, _y| {
                 start_bounds.set(None);
+                appwindow.canvas().sheet().borrow_mut().strokes_state.end_history_group();
 
                 appwindow.canvas().sheet().borrow_mut().strokes_state.update_geometry_selection_strokes();
                 appwindow.canvas().regenerate_content(false, true);
@@ -684,6 +690,8 @@ impl SelectionModifier {
         resize_bl_drag_gesture.connect_drag_begin(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |drag_gesture, _x, _y| {
                 drag_gesture.set_state(EventSequenceState::Claimed);
+                // The whole drag is undone at once
+                appwindow.canvas().sheet().borrow_mut().strokes_state.begin_history_group();
                 start_bounds.set(selection_modifier.selection_bounds());
 
                 selection_modifier.update_state(&appwindow.canvas());
@@ -727,6 +735,7 @@ impl SelectionModifier {
         resize_bl_drag_gesture.connect_drag_end(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |_drag_gesture, _x, _y| {
                 start_bounds.set(None);
+                appwindow.canvas().sheet().borrow_mut().strokes_state.end_history_group();
 
                 appwindow.canvas().sheet().borrow_mut().strokes_state.update_geometry_selection_strokes();
                 appwindow.canvas().regenerate_content(false, true);
@@ -751,6 +760,8 @@ impl SelectionModifier {
         resize_br_drag_gesture.connect_drag_begin(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |drag_gesture, _x, _y| {
                 drag_gesture.set_state(EventSequenceState::Claimed);
+                // The whole drag is undone at once
+                appwindow.canvas().sheet().borrow_mut().strokes_state.begin_history_group();
                 start_bounds.set(selection_modifier.selection_bounds());
 
                 selection_modifier.update_state(&appwindow.canvas());
@@ -794,6 +805,7 @@ impl SelectionModifier {
         resize_br_drag_gesture.connect_drag_end(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |_drag_gesture, _x, _y| {
                 start_bounds.set(None);
+                appwindow.canvas().sheet().borrow_mut().strokes_state.end_history_group();
 
                 appwindow.canvas().sheet().borrow_mut().strokes_state.update_geometry_selection_strokes();
                 appwindow.canvas().regenerate_content(false, true);
@@ -813,19 +825,36 @@ impl SelectionModifier {
             .translate_node
             .add_controller(&translate_node_drag_gesture);
 
+        let start_bounds: Rc<Cell<Option<AABB>>> = Rc::new(Cell::new(None));
+
         translate_node_drag_gesture.connect_drag_begin(
-            clone!(@weak self as selection_modifier, @weak appwindow => move |translate_node_drag_gesture, _x, _y| {
+            clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |translate_node_drag_gesture, _x, _y| {
                 translate_node_drag_gesture.set_state(EventSequenceState::Claimed);
+                // The whole drag is undone at once
+                appwindow.canvas().sheet().borrow_mut().strokes_state.begin_history_group();
 
                 selection_modifier.update_state(&appwindow.canvas());
+                start_bounds.set(selection_modifier.selection_bounds());
             }),
         );
         translate_node_drag_gesture.connect_drag_update(
-            clone!(@weak self as selection_modifier, @weak appwindow => move |_translate_node_drag_gesture, x, y| {
+            clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |_translate_node_drag_gesture, x, y| {
                 let zoom = appwindow.canvas().zoom();
-                let offset = na::vector![x.round() / zoom, y.round() / zoom];
+                let mut offset = na::vector![x.round() / zoom, y.round() / zoom];
 
                 let selection_keys = appwindow.canvas().sheet().borrow().strokes_state.selection_keys_as_rendered();
+
+                let snapping_enabled = {
+                    let pens = appwindow.canvas().pens();
+                    let pens = pens.borrow();
+                    pens.snapping.snap_to_grid || pens.snapping.snap_to_strokes
+                };
+                if let (true, Some(start_bounds), Some(selection_bounds)) = (snapping_enabled, start_bounds.get(), selection_modifier.selection_bounds()) {
+                    // The offset is relative to the selection, which already moved by the snapped offsets
+                    let moved = selection_bounds.mins - start_bounds.mins;
+                    let snapped = appwindow.canvas().pens().borrow_mut().snapping.snap_offset(start_bounds, moved + offset, &*appwindow.canvas().sheet().borrow(), &selection_keys, zoom);
+                    offset = snapped - moved;
+                }
                 appwindow.canvas().sheet().borrow_mut().strokes_state.translate_strokes(&selection_keys, offset, zoom);
                 selection_modifier.set_selection_bounds(selection_modifier.imp().selection_bounds.get().map(|selection_bounds| selection_bounds.translate(offset)));
 
@@ -834,7 +863,10 @@ impl SelectionModifier {
             }),
         );
         translate_node_drag_gesture.connect_drag_end(
-            clone!(@weak self as selection_modifier, @weak appwindow => move |_translate_node_drag_gesture, _x, _y| {
+            clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |_translate_node_drag_gesture, _x, _y| {
+                start_bounds.set(None);
+                appwindow.canvas().pens().borrow_mut().snapping.clear_indicator();
+                appwindow.canvas().sheet().borrow_mut().strokes_state.end_history_group();
                 selection_modifier.update_state(&appwindow.canvas());
                 appwindow.canvas().queue_draw();
             }),
@@ -855,6 +887,8 @@ impl SelectionModifier {
         rotate_node_drag_gesture.connect_drag_begin(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |drag_gesture, _x, _y| {
                 drag_gesture.set_state(EventSequenceState::Claimed);
+                // The whole drag is undone at once
+                appwindow.canvas().sheet().borrow_mut().strokes_state.begin_history_group();
                 selection_modifier.update_state(&appwindow.canvas());
 
                 start_bounds.set(selection_modifier.selection_bounds());
@@ -914,6 +948,7 @@ impl SelectionModifier {
         );
         rotate_node_drag_gesture.connect_drag_end(
             clone!(@weak self as selection_modifier, @weak appwindow => move |_drag_gesture, _x, _y| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.end_history_group();
                 selection_modifier.imp().start_rotation_center.set(None);
                 selection_modifier.imp().start_rotation_angle.set(0.0);
                 selection_modifier.imp().current_rotation_angle.set(0.0);
@@ -7,7 +7,7 @@ mod imp {
 
     use adw::prelude::*;
     use gtk4::{glib, glib::clone, subclass::prelude::*, CompositeTemplate};
-    use gtk4::{Adjustment, Button, ColorButton, ScrolledWindow, ToggleButton};
+    use gtk4::{Adjustment, Button, ColorButton, ScrolledWindow, Switch, ToggleButton};
 
     use crate::unitentry::UnitEntry;
     use rnote_engine::sheet::format::{self, Format};
@@ -28,6 +28,8 @@ mod imp {
         #[template_child]
         pub general_pdf_import_as_bitmap_toggle: TemplateChild<ToggleButton>,
         #[template_child]
+        pub general_undo_memory_budget_adj: TemplateChild<Adjustment>,
+        #[template_child]
         pub format_predefined_formats_row: TemplateChild<adw::ComboRow>,
         #[template_child]
         pub format_orientation_row: TemplateChild<adw::ActionRow>,
@@ -62,6 +64,12 @@ mod imp {
         #[template_child]
         pub background_pattern_height_unitentry: TemplateChild<UnitEntry>,
         #[template_child]
+        pub background_custom_tile_button: TemplateChild<Button>,
+        #[template_child]
+        pub background_current_page_switch: TemplateChild<Switch>,
+        #[template_child]
+        pub background_reset_page_button: TemplateChild<Button>,
+        #[template_child]
         pub penshortcut_stylus_button_primary_row: TemplateChild<PenShortcutRow>,
         #[template_child]
         pub penshortcut_stylus_button_secondary_row: TemplateChild<PenShortcutRow>,
@@ -371,13 +379,14 @@ use std::rc::Rc;
 
 use adw::prelude::*;
 use gtk4::{glib, glib::clone, subclass::prelude::*, Widget};
-use gtk4::{Adjustment, ColorButton, ScrolledWindow, ToggleButton};
+use gtk4::{Adjustment, ColorButton, ScrolledWindow, Switch, ToggleButton};
 use rnote_engine::pens::shortcuts::ShortcutKey;
 
 use super::appwindow::RnoteAppWindow;
+use crate::dialogs;
 use crate::unitentry::UnitEntry;
 use rnote_engine::compose::color::Color;
-use rnote_engine::sheet::background::PatternStyle;
+use rnote_engine::sheet::background::{Background, PatternStyle};
 use rnote_engine::sheet::format::{self, Format};
 
 glib::wrapper! {
@@ -454,6 +463,12 @@ impl SettingsPanel {
             .clone()
     }
 
+    pub fn general_undo_memory_budget_adj(&self) -> Adjustment {
+        imp::SettingsPanel::from_instance(self)
+            .general_undo_memory_budget_adj
+            .clone()
+    }
+
     pub fn general_pdf_import_as_vector_toggle(&self) -> ToggleButton {
         imp::SettingsPanel::from_instance(self)
             .general_pdf_import_as_vector_toggle
@@ -514,6 +529,63 @@ impl SettingsPanel {
             .clone()
     }
 
+    pub fn background_current_page_switch(&self) -> Switch {
+        imp::SettingsPanel::from_instance(self)
+            .background_current_page_switch
+            .clone()
+    }
+
+    /// The index of the page in the center of the viewport
+    fn current_page_index(&self, appwindow: &RnoteAppWindow) -> Option<usize> {
+        let viewport_center = appwindow
+            .canvas()
+            .viewport_in_sheet_coords()
+            .center()
+            .coords;
+
+        appwindow.canvas().sheet().borrow().page_index_at(viewport_center)
+    }
+
+    /// The background the settings currently apply to, which is the sheet background or the background of the current page
+    pub fn current_background(&self, appwindow: &RnoteAppWindow) -> Background {
+        let sheet = appwindow.canvas().sheet();
+        let sheet = sheet.borrow();
+
+        if self.background_current_page_switch().is_active() {
+            if let Some(page_bounds) = self
+                .current_page_index(appwindow)
+                .and_then(|index| sheet.pages().get(index).copied())
+            {
+                return sheet.background_at_pos(page_bounds.center().coords).clone();
+            }
+        }
+
+        sheet.background.clone()
+    }
+
+    /// Modifies the background the settings currently apply to. Changing the background of the current page creates a background region for it
+    pub fn modify_background<R>(
+        &self,
+        appwindow: &RnoteAppWindow,
+        f: impl FnOnce(&mut Background) -> R,
+    ) -> R {
+        let current_page_index = if self.background_current_page_switch().is_active() {
+            self.current_page_index(appwindow)
+        } else {
+            None
+        };
+        let sheet = appwindow.canvas().sheet();
+        let mut sheet = sheet.borrow_mut();
+
+        if let Some(index) = current_page_index {
+            if let Some(background) = sheet.page_background_mut(index) {
+                return f(background);
+            }
+        }
+
+        f(&mut sheet.background)
+    }
+
     pub fn refresh_for_sheet(&self, appwindow: &RnoteAppWindow) {
         self.load_misc(appwindow);
         self.load_format(appwindow);
@@ -524,6 +596,8 @@ impl SettingsPanel {
     pub fn load_misc(&self, appwindow: &RnoteAppWindow) {
         self.general_pdf_import_width_adj()
             .set_value(appwindow.canvas().pdf_import_width());
+        self.general_undo_memory_budget_adj()
+            .set_value(f64::from(appwindow.canvas().undo_memory_budget()));
     }
 
     pub fn load_format(&self, appwindow: &RnoteAppWindow) {
@@ -545,7 +619,7 @@ impl SettingsPanel {
     }
 
     pub fn load_background(&self, appwindow: &RnoteAppWindow) {
-        let background = appwindow.canvas().sheet().borrow().background.clone();
+        let background = self.current_background(appwindow);
         let format = appwindow.canvas().sheet().borrow().format.clone();
 
         self.background_color_choosebutton()
@@ -638,6 +712,14 @@ impl SettingsPanel {
             )
             .build();
 
+        // Undo memory budget
+        self.imp()
+            .general_undo_memory_budget_adj
+            .get()
+            .bind_property("value", &appwindow.canvas(), "undo-memory-budget")
+            .flags(glib::BindingFlags::SYNC_CREATE | glib::BindingFlags::BIDIRECTIONAL)
+            .build();
+
         // revert format
         self.imp().format_revert_button.get().connect_clicked(
             clone!(@weak self as settings_panel, @weak appwindow => move |_format_revert_button| {
@@ -672,8 +754,8 @@ impl SettingsPanel {
         );
 
         // Background
-        self.imp().background_color_choosebutton.connect_color_set(clone!(@weak appwindow => move |background_color_choosebutton| {
-            appwindow.canvas().sheet().borrow_mut().background.color = Color::from(background_color_choosebutton.rgba());
+        self.imp().background_color_choosebutton.connect_color_set(clone!(@weak self as settings_panel, @weak appwindow => move |background_color_choosebutton| {
+            settings_panel.modify_background(&appwindow, |background| background.color = Color::from(background_color_choosebutton.rgba()));
             appwindow.canvas().regenerate_background(true);
         }));
 
@@ -686,26 +768,51 @@ impl SettingsPanel {
                     .as_str()
                 {
                     "none" => {
-                        appwindow.canvas().sheet().borrow_mut().background.pattern = PatternStyle::None;
+                        settings_panel.modify_background(&appwindow, |background| background.pattern = PatternStyle::None);
                         settings_panel.background_pattern_width_unitentry().set_sensitive(false);
                         settings_panel.background_pattern_height_unitentry().set_sensitive(false);
 
                     },
                     "lines" => {
-                        appwindow.canvas().sheet().borrow_mut().background.pattern = PatternStyle::Lines;
+                        settings_panel.modify_background(&appwindow, |background| background.pattern = PatternStyle::Lines);
                         settings_panel.background_pattern_width_unitentry().set_sensitive(false);
                         settings_panel.background_pattern_height_unitentry().set_sensitive(true);
                     },
                     "grid" => {
-                        appwindow.canvas().sheet().borrow_mut().background.pattern = PatternStyle::Grid;
+                        settings_panel.modify_background(&appwindow, |background| background.pattern = PatternStyle::Grid);
                         settings_panel.background_pattern_width_unitentry().set_sensitive(true);
                         settings_panel.background_pattern_height_unitentry().set_sensitive(true);
                     },
                     "dots" => {
-                        appwindow.canvas().sheet().borrow_mut().background.pattern = PatternStyle::Dots;
+                        settings_panel.modify_background(&appwindow, |background| background.pattern = PatternStyle::Dots);
+                        settings_panel.background_pattern_width_unitentry().set_sensitive(true);
+                        settings_panel.background_pattern_height_unitentry().set_sensitive(true);
+                    },
+                    "isometric" => {
+                        settings_panel.modify_background(&appwindow, |background| background.pattern = PatternStyle::Isometric);
+                        settings_panel.background_pattern_width_unitentry().set_sensitive(false);
+                        settings_panel.background_pattern_height_unitentry().set_sensitive(true);
+                    },
+                    "hexagons" => {
+                        settings_panel.modify_background(&appwindow, |background| background.pattern = PatternStyle::Hexagons);
                         settings_panel.background_pattern_width_unitentry().set_sensitive(true);
+                        settings_panel.background_pattern_height_unitentry().set_sensitive(false);
+                    },
+                    "music-staff" => {
+                        settings_panel.modify_background(&appwindow, |background| background.pattern = PatternStyle::MusicStaff);
+                        settings_panel.background_pattern_width_unitentry().set_sensitive(false);
                         settings_panel.background_pattern_height_unitentry().set_sensitive(true);
                     },
+                    "custom-tile" => {
+                        settings_panel.modify_background(&appwindow, |background| background.pattern = PatternStyle::CustomTile);
+                        settings_panel.background_pattern_width_unitentry().set_sensitive(true);
+                        settings_panel.background_pattern_height_unitentry().set_sensitive(true);
+
+                        // Without a tile there is nothing to repeat yet
+                        if settings_panel.modify_background(&appwindow, |background| background.custom_tile.is_none()) {
+                            dialogs::dialog_import_background_tile(&appwindow);
+                        }
+                    },
                     _ => {
                         log::error!(
                             "invalid nick string when selecting a pattern in background_patterns_row"
@@ -717,8 +824,25 @@ impl SettingsPanel {
             }
         }));
 
-        self.imp().background_pattern_color_choosebutton.connect_color_set(clone!(@weak appwindow => move |background_pattern_color_choosebutton| {
-            appwindow.canvas().sheet().borrow_mut().background.pattern_color = Color::from(background_pattern_color_choosebutton.rgba());
+        self.imp().background_custom_tile_button.connect_clicked(clone!(@weak appwindow => move |_background_custom_tile_button| {
+            dialogs::dialog_import_background_tile(&appwindow);
+        }));
+
+        self.imp().background_current_page_switch.connect_active_notify(clone!(@weak self as settings_panel, @weak appwindow => move |_background_current_page_switch| {
+            settings_panel.load_background(&appwindow);
+        }));
+
+        self.imp().background_reset_page_button.connect_clicked(clone!(@weak self as settings_panel, @weak appwindow => move |_background_reset_page_button| {
+            if let Some(index) = settings_panel.current_page_index(&appwindow) {
+                if appwindow.canvas().sheet().borrow_mut().reset_page_background(index) {
+                    appwindow.canvas().regenerate_background(true);
+                    settings_panel.load_background(&appwindow);
+                }
+            }
+        }));
+
+        self.imp().background_pattern_color_choosebutton.connect_color_set(clone!(@weak self as settings_panel, @weak appwindow => move |background_pattern_color_choosebutton| {
+            settings_panel.modify_background(&appwindow, |background| background.pattern_color = Color::from(background_pattern_color_choosebutton.rgba()));
             appwindow.canvas().regenerate_background(true);
         }));
 
@@ -726,10 +850,9 @@ impl SettingsPanel {
             "measurement-changed",
             false,
             clone!(@weak self as settings_panel, @weak appwindow => @default-return None, move |_args| {
-                    let mut pattern_size = appwindow.canvas().sheet().borrow().background.pattern_size;
-                    pattern_size[0] = f64::from(settings_panel.background_pattern_width_unitentry().value_in_px());
+                    let value = f64::from(settings_panel.background_pattern_width_unitentry().value_in_px());
 
-                    appwindow.canvas().sheet().borrow_mut().background.pattern_size = pattern_size;
+                    settings_panel.modify_background(&appwindow, |background| background.pattern_size[0] = value);
                     appwindow.canvas().regenerate_background(true);
 
                     None
@@ -740,10 +863,9 @@ impl SettingsPanel {
             "measurement-changed",
             false,
             clone!(@weak self as settings_panel, @weak appwindow => @default-return None, move |_args| {
-                    let mut pattern_size = appwindow.canvas().sheet().borrow().background.pattern_size;
-                    pattern_size[1] = f64::from(settings_panel.background_pattern_height_unitentry().value_in_px());
+                    let value = f64::from(settings_panel.background_pattern_height_unitentry().value_in_px());
 
-                    appwindow.canvas().sheet().borrow_mut().background.pattern_size = pattern_size;
+                    settings_panel.modify_background(&appwindow, |background| background.pattern_size[1] = value);
                     appwindow.canvas().regenerate_background(true);
 
                     None
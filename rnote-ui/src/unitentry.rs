@@ -97,6 +97,7 @@ mod imp {
                             "px" => Some(format::MeasureUnit::Px),
                             "mm" => Some(format::MeasureUnit::Mm),
                             "cm" => Some(format::MeasureUnit::Cm),
+                            "in" => Some(format::MeasureUnit::In),
                             _ => None,
                         };
 
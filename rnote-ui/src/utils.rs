@@ -29,6 +29,10 @@ pub enum FileType {
     VectorImageFile,
     BitmapImageFile,
     PdfFile,
+    /// Windows Ink, as raw ISF or embedded in EMF
+    InkFile,
+    /// A GIMP palette
+    PaletteFile,
     UnknownFile,
 }
 
@@ -53,12 +57,15 @@ impl FileType {
                             "image/svg+xml" => {
                                 return Self::VectorImageFile;
                             }
-                            "image/png" | "image/jpeg" => {
+                            "image/png" | "image/jpeg" | "image/webp" => {
                                 return Self::BitmapImageFile;
                             }
                             "application/pdf" => {
                                 return Self::PdfFile;
                             }
+                            "image/emf" | "image/x-emf" => {
+                                return Self::InkFile;
+                            }
                             _ => {}
                         }
                     }
@@ -85,6 +92,12 @@ impl FileType {
                     "xopp" => {
                         return Self::XoppFile;
                     }
+                    "isf" | "emf" => {
+                        return Self::InkFile;
+                    }
+                    "gpl" => {
+                        return Self::PaletteFile;
+                    }
                     _ => {}
                 }
             }
@@ -117,7 +130,10 @@ pub fn translate_aabb_to_widget(
     Some(AABB::new(mins, maxs))
 }
 
-pub fn replace_file_async(bytes: Vec<u8>, file: &gio::File) -> Result<(), anyhow::Error> {
+pub fn replace_file_async(
+    bytes: impl AsRef<[u8]> + 'static,
+    file: &gio::File,
+) -> Result<(), anyhow::Error> {
     file.replace_async(
         None,
         false,
@@ -136,7 +152,7 @@ pub fn replace_file_async(bytes: Vec<u8>, file: &gio::File) -> Result<(), anyhow
                 }
             };
 
-            if let Err(e) = output_stream.write(&bytes, None::<&gio::Cancellable>) {
+            if let Err(e) = output_stream.write(bytes.as_ref(), None::<&gio::Cancellable>) {
                 log::error!(
                     "output_stream().write() failed in save_sheet_to_file() with Err {}",
                     e
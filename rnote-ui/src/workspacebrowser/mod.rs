@@ -301,9 +301,12 @@ impl WorkspaceBrowser {
         filefilter.add_pattern("*.rnote");
         filefilter.add_pattern("*.xopp");
         filefilter.add_pattern("*.svg");
+        filefilter.add_pattern("*.isf");
+        filefilter.add_pattern("*.emf");
         filefilter.add_mime_type("image/svg+xml");
         filefilter.add_mime_type("image/png");
         filefilter.add_mime_type("image/jpeg");
+        filefilter.add_mime_type("image/webp");
         filefilter.add_mime_type("application/x-xopp");
         filefilter.add_mime_type("application/pdf");
         filefilter.add_mime_type("inode/directory");